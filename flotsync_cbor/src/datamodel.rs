@@ -0,0 +1,229 @@
+//! CBOR forms of the `flotsync.datamodel.v1` values and operations, including the row snapshots
+//! that operations carry.
+//!
+//! Message fields that protobuf tracks for presence are `Option`s here, so converting from and to
+//! protobuf never invents or drops a value.
+
+use crate::ByteString;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HistoryId {
+    pub version: u64,
+    pub node_index: u32,
+    pub chunk_index: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocRef {
+    pub document_id: ByteString,
+    pub anchor: Option<HistoryId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimitiveValue {
+    String(String),
+    Uint(u64),
+    Int(i64),
+    Byte(u32),
+    Float(f64),
+    Boolean(bool),
+    Binary(ByteString),
+    Date(Date),
+    Timestamp(i64),
+    DocRef(DocRef),
+}
+
+/// Homogeneous array of primitive values.
+///
+/// Byte arrays are one byte string rather than a list of integers, like in protobuf.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimitiveArrayValue {
+    String(Vec<String>),
+    Uint(Vec<u64>),
+    Int(Vec<i64>),
+    Byte(ByteString),
+    Float(Vec<f64>),
+    Boolean(Vec<bool>),
+    Binary(Vec<ByteString>),
+    Date(Vec<Date>),
+    Timestamp(Vec<i64>),
+    DocRef(Vec<DocRef>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NullableBasicValue {
+    Null,
+    Primitive(PrimitiveValue),
+    Array(PrimitiveArrayValue),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NullablePrimitiveValue {
+    Null,
+    Primitive(PrimitiveValue),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterValue {
+    Byte(u32),
+    Uint(u64),
+}
+
+/// Metadata of one node in a serialised causal history.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryNodeMeta {
+    pub version: u64,
+    pub node_index: u32,
+    pub chunk_index: u32,
+    pub origin_left_version: Option<u64>,
+    pub origin_left_node_index: Option<u32>,
+    pub origin_left_chunk_index: Option<u32>,
+    pub origin_right_version: Option<u64>,
+    pub origin_right_node_index: Option<u32>,
+    pub origin_right_chunk_index: Option<u32>,
+    pub deleted: bool,
+    pub value_len: u32,
+    pub value_is_null: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistorySnapshot {
+    pub nodes: Vec<HistoryNodeMeta>,
+    pub values: Option<HistoryValues>,
+}
+
+/// Concatenated node values of a [`HistorySnapshot`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryValues {
+    PrimitiveValues(PrimitiveArrayValue),
+    StringValues(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotField {
+    pub field_name: String,
+    pub value: SnapshotFieldValue,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotFieldValue {
+    LatestValueWins(HistorySnapshot),
+    LinearString(HistorySnapshot),
+    LinearList(HistorySnapshot),
+    MonotonicCounter(CounterValue),
+    TotalOrderRegister(PrimitiveValue),
+    TotalOrderFiniteStateRegister(NullablePrimitiveValue),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RowSnapshot {
+    pub fields: Vec<SnapshotField>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataSnapshot {
+    pub rows: Vec<RowSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LatestValueWinsOperation {
+    pub id: Option<HistoryId>,
+    pub pred: Option<HistoryId>,
+    pub succ: Option<HistoryId>,
+    pub value: Option<NullableBasicValue>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinearDeleteOperation {
+    pub start: Option<HistoryId>,
+    pub end_chunk_index: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinearStringInsertOperation {
+    pub id: Option<HistoryId>,
+    pub pred: Option<HistoryId>,
+    pub succ: Option<HistoryId>,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinearStringAction {
+    Insert(LinearStringInsertOperation),
+    Delete(LinearDeleteOperation),
+    DeleteRanges(Vec<LinearDeleteOperation>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinearListInsertOperation {
+    pub id: Option<HistoryId>,
+    pub pred: Option<HistoryId>,
+    pub succ: Option<HistoryId>,
+    pub value: Option<PrimitiveArrayValue>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinearListAction {
+    Insert(LinearListInsertOperation),
+    Delete(LinearDeleteOperation),
+    DeleteRanges(Vec<LinearDeleteOperation>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OperationField {
+    pub field_name: String,
+    pub operation: FieldOperation,
+}
+
+/// Change to one field, mirroring the `OperationField.value` oneof.
+///
+/// Operations that only wrap a single value carry that value directly.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldOperation {
+    LatestValueWins(LatestValueWinsOperation),
+    LinearString(Vec<LinearStringAction>),
+    LinearList(Vec<LinearListAction>),
+    MonotonicCounterIncrement(Option<CounterValue>),
+    TotalOrderRegisterSet(Option<PrimitiveValue>),
+    TotalOrderFiniteStateRegisterSet(Option<NullablePrimitiveValue>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaOperation {
+    pub change_id: Option<HistoryId>,
+    pub operation: RowOperation,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowOperation {
+    Insert {
+        row_id: ByteString,
+        snapshot: Option<RowSnapshot>,
+    },
+    Update {
+        row_id: ByteString,
+        fields: Vec<OperationField>,
+    },
+    Delete {
+        row_id: ByteString,
+    },
+}
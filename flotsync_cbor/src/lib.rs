@@ -0,0 +1,242 @@
+//! CBOR forms of the replication update messages.
+//!
+//! CBOR only covers the bodies of the `Update` and `UpdateBatch` runtime messages, i.e. the
+//! messages that carry operations. The delivery envelope around them, every control message, and
+//! snapshot transfers always use protobuf, so every peer still needs the protobuf codec; CBOR is an
+//! alternative encoding for operation payloads, not a replacement for protobuf.
+//!
+//! The types in this crate are plain serde types, so they build without the protobuf toolchain
+//! that `flotsync_messages` needs. They mirror the `.proto` definitions field by field: a message
+//! becomes a struct, a `oneof` becomes an enum, and wrapper messages with a single repeated field
+//! become that field. Every message therefore has exactly one CBOR form and one protobuf form, and
+//! `flotsync_messages` converts between the two with its `cbor` feature.
+//!
+//! Peers negotiate CBOR while exchanging summaries. A [`replication::RuntimeMessage`] encoded with
+//! [`encode`] travels in a protobuf `EncodedPayload` that records [`CBOR_ENCODING_VERSION`], so
+//! receivers can reject mappings newer than the one they know.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned};
+use snafu::prelude::*;
+use std::{fmt, ops::Deref};
+
+pub mod datamodel;
+pub mod replication;
+pub mod versions;
+
+/// Current version of the mapping from messages to CBOR.
+///
+/// Bump this whenever a type in this crate changes its serialised form.
+pub const CBOR_ENCODING_VERSION: u32 = 1;
+
+/// Serialise `message` as CBOR.
+///
+/// # Errors
+///
+/// Returns an error if `message` cannot be represented in CBOR.
+pub fn encode<M: Serialize>(message: &M) -> Result<Vec<u8>, CborError> {
+    let mut data = Vec::new();
+    ciborium::into_writer(message, &mut data).map_err(|source| CborError::Encode {
+        message: source.to_string(),
+    })?;
+    Ok(data)
+}
+
+/// Deserialise a message from CBOR `data`.
+///
+/// # Errors
+///
+/// Returns an error if `data` is not a CBOR encoding of `M`.
+pub fn decode<M: DeserializeOwned>(data: &[u8]) -> Result<M, CborError> {
+    ciborium::from_reader(data).map_err(|source| CborError::Decode {
+        message: source.to_string(),
+    })
+}
+
+/// Errors produced while encoding or decoding CBOR messages.
+#[derive(Debug, Snafu)]
+pub enum CborError {
+    #[snafu(display("Failed to encode CBOR message: {message}"))]
+    Encode { message: String },
+    #[snafu(display("Failed to decode CBOR message: {message}"))]
+    Decode { message: String },
+}
+
+/// Raw bytes, serialised as one CBOR byte string instead of an array of integers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ByteString(pub Vec<u8>);
+
+impl ByteString {
+    #[must_use]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for ByteString {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for ByteString {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ByteString> for Vec<u8> {
+    fn from(value: ByteString) -> Self {
+        value.0
+    }
+}
+
+impl Serialize for ByteString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(ByteStringVisitor)
+    }
+}
+
+struct ByteStringVisitor;
+
+impl<'de> serde::de::Visitor<'de> for ByteStringVisitor {
+    type Value = ByteString;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        Ok(ByteString(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(ByteString(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datamodel::{
+            FieldOperation,
+            HistoryId,
+            LinearStringAction,
+            LinearStringInsertOperation,
+            NullableBasicValue,
+            OperationField,
+            PrimitiveValue,
+            RowOperation,
+            RowSnapshot,
+            SchemaOperation,
+            SnapshotField,
+            SnapshotFieldValue,
+        },
+        replication::{DatasetUpdate, RuntimeMessage, Update},
+        versions::CompactVersionVector,
+    };
+
+    fn history_id(version: u64, chunk_index: u32) -> HistoryId {
+        HistoryId {
+            version,
+            node_index: 1,
+            chunk_index,
+        }
+    }
+
+    fn update() -> Update {
+        let insert = SchemaOperation {
+            change_id: Some(history_id(4, 0)),
+            operation: RowOperation::Insert {
+                row_id: ByteString(vec![7; 16]),
+                snapshot: Some(RowSnapshot {
+                    fields: vec![SnapshotField {
+                        field_name: "done".to_owned(),
+                        value: SnapshotFieldValue::TotalOrderRegister(PrimitiveValue::Boolean(
+                            false,
+                        )),
+                    }],
+                }),
+            },
+        };
+        let edit = SchemaOperation {
+            change_id: Some(history_id(4, 1)),
+            operation: RowOperation::Update {
+                row_id: ByteString(vec![7; 16]),
+                fields: vec![
+                    OperationField {
+                        field_name: "title".to_owned(),
+                        operation: FieldOperation::LinearString(vec![LinearStringAction::Insert(
+                            LinearStringInsertOperation {
+                                id: Some(history_id(4, 2)),
+                                pred: Some(history_id(0, 0)),
+                                succ: Some(history_id(0, 1)),
+                                value: "milk".to_owned(),
+                            },
+                        )]),
+                    },
+                    OperationField {
+                        field_name: "note".to_owned(),
+                        operation: FieldOperation::LatestValueWins(
+                            datamodel::LatestValueWinsOperation {
+                                id: Some(history_id(4, 3)),
+                                pred: None,
+                                succ: None,
+                                value: Some(NullableBasicValue::Null),
+                            },
+                        ),
+                    },
+                ],
+            },
+        };
+        Update {
+            group_id: ByteString(vec![1; 16]),
+            update_id: Some(history_id(4, 0)),
+            read_versions: Some(CompactVersionVector::Override {
+                group_version: 2,
+                override_position: 1,
+                override_version: 3,
+            }),
+            dataset_updates: vec![DatasetUpdate {
+                dataset_id: "groceries".to_owned(),
+                operations: vec![insert, edit],
+            }],
+        }
+    }
+
+    #[test]
+    fn runtime_message_roundtrips() {
+        let message = RuntimeMessage::Update(update());
+        let data = encode(&message).unwrap();
+        assert_eq!(decode::<RuntimeMessage>(&data).unwrap(), message);
+    }
+
+    #[test]
+    fn byte_strings_are_not_integer_arrays() {
+        let data = encode(&ByteString(vec![0xab; 16])).unwrap();
+        // Major type 2 (byte string) with a one-byte length.
+        assert_eq!(&data[..2], &[0x50, 0xab]);
+        assert_eq!(data.len(), 17);
+        assert_eq!(
+            decode::<ByteString>(&data).unwrap(),
+            ByteString(vec![0xab; 16])
+        );
+    }
+
+    #[test]
+    fn corrupt_data_is_rejected() {
+        let data = encode(&RuntimeMessage::Update(update())).unwrap();
+        assert!(matches!(
+            decode::<RuntimeMessage>(&data[..data.len() / 2]),
+            Err(CborError::Decode { .. })
+        ));
+    }
+}
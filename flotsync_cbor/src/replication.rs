@@ -0,0 +1,41 @@
+//! CBOR forms of the `flotsync.replication.v1` messages that carry operations.
+
+use crate::{
+    ByteString,
+    datamodel::{HistoryId, SchemaOperation},
+    versions::CompactVersionVector,
+};
+use serde::{Deserialize, Serialize};
+
+/// Runtime message that may be sent as CBOR.
+///
+/// Only the messages carrying operations have a CBOR form. All other runtime messages are small
+/// control messages and always use protobuf.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeMessage {
+    Update(Update),
+    UpdateBatch(UpdateBatch),
+}
+
+/// One causal replication update.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Update {
+    pub group_id: ByteString,
+    pub update_id: Option<HistoryId>,
+    pub read_versions: Option<CompactVersionVector>,
+    pub dataset_updates: Vec<DatasetUpdate>,
+}
+
+/// All schema operations for one dataset within a single update.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DatasetUpdate {
+    pub dataset_id: String,
+    pub operations: Vec<SchemaOperation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UpdateBatch {
+    pub group_id: ByteString,
+    pub updates: Vec<Update>,
+}
@@ -0,0 +1,30 @@
+//! CBOR forms of the `flotsync.versions.v1` messages.
+
+use serde::{Deserialize, Serialize};
+
+/// Compact version vector, mirroring the `CompactVersionVector.versions` oneof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactVersionVector {
+    Full {
+        entries: Vec<u64>,
+    },
+    Override {
+        group_version: u64,
+        override_position: u32,
+        override_version: u64,
+    },
+    Synced {
+        group_version: u64,
+    },
+    MultiOverride {
+        group_version: u64,
+        override_positions: Vec<u32>,
+        override_versions: Vec<u64>,
+    },
+    Sparse {
+        base_version: u64,
+        positions: Vec<u32>,
+        versions: Vec<u64>,
+    },
+}
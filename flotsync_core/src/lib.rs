@@ -1,8 +1,44 @@
+//! Core identity, membership, and version types shared by all flotsync crates.
+//!
+//! With the default `std` feature disabled the crate is `no_std` and only
+//! provides the flat version vector types in [`versions`], which need nothing
+//! beyond `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 pub mod errors;
+#[cfg(feature = "std")]
 mod ids;
+#[cfg(feature = "std")]
 pub mod member;
+#[cfg(feature = "std")]
 pub mod membership;
+/// Common imports for consumers of the `flotsync_core` identity, membership, and version types.
+pub mod prelude {
+    pub use crate::versions::{HappenedBeforeOrd, HappenedBeforeOrdering, UpdateId, VersionVector};
+    #[cfg(feature = "std")]
+    pub use crate::{
+        GroupId,
+        MemberIdentity,
+        MemberIndex,
+        member::{EpochMembership, GroupMembership, Identifier},
+        membership::{GroupContext, GroupMembers, SharedGroupMemberships},
+        versions::GroupVersionVector,
+    };
+}
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+#[cfg(feature = "std")]
 pub mod uuid_encodings;
 pub mod versions;
 
+#[cfg(feature = "std")]
 pub use ids::{GroupId, MemberIdentity, MemberIndex};
+
+/// Home of [`Sealed`](sealed::Sealed), which public traits that only this crate may implement
+/// require, so they can gain methods without breaking downstream code.
+mod sealed {
+    pub trait Sealed {}
+}
@@ -0,0 +1,233 @@
+//! Shortest-unique-prefix rendering of identifiers, to keep multi-peer logs readable.
+//!
+//! An abbreviation shortens every segment of an identifier to the shortest prefix that no sibling
+//! segment in the known identifiers shares, e.g. `alice.phone`, `alice.tablet`, and `bob` render
+//! as `a.p`, `a.t`, and `b`.
+//!
+//! Abbreviations are off by default. Once enabled with [`set_identifier_display_mode`], the
+//! [`Debug`](fmt::Debug) output of [`Identifier`] and log-facing displays such as
+//! [`GroupVersionVector`](crate::versions::GroupVersionVector) use the abbreviations for the
+//! members of all locally hosted groups, which [`SharedGroupMemberships`] keeps up to date.
+//! [`Display`](fmt::Display) stays canonical, since it is also the parseable and persisted form.
+//!
+//! [`SharedGroupMemberships`]: crate::membership::SharedGroupMemberships
+
+use super::{Identifier, IdentifierLike, SEGMENT_SEPARATOR, TrieMap};
+use arc_swap::ArcSwap;
+use itertools::Itertools;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        Arc,
+        LazyLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// How identifiers render in log output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IdentifierDisplayMode {
+    /// Render every identifier in full.
+    #[default]
+    Full,
+    /// Render known identifiers by their shortest unique prefixes.
+    ShortestUniquePrefix,
+}
+
+static ABBREVIATIONS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static ABBREVIATIONS: LazyLock<ArcSwap<IdentifierAbbreviations>> =
+    LazyLock::new(|| ArcSwap::from_pointee(IdentifierAbbreviations::default()));
+
+/// Select how identifiers render in log output for the whole process.
+pub fn set_identifier_display_mode(mode: IdentifierDisplayMode) {
+    ABBREVIATIONS_ENABLED.store(
+        mode == IdentifierDisplayMode::ShortestUniquePrefix,
+        Ordering::Relaxed,
+    );
+}
+
+/// The process-wide identifier display mode.
+#[must_use]
+pub fn identifier_display_mode() -> IdentifierDisplayMode {
+    if ABBREVIATIONS_ENABLED.load(Ordering::Relaxed) {
+        IdentifierDisplayMode::ShortestUniquePrefix
+    } else {
+        IdentifierDisplayMode::Full
+    }
+}
+
+/// Recompute the process-wide abbreviations for `identifiers`.
+///
+/// Does nothing unless abbreviations are enabled, so callers can report every membership change
+/// without paying for it by default.
+pub fn update_identifier_abbreviations<'a>(identifiers: impl IntoIterator<Item = &'a Identifier>) {
+    if identifier_display_mode() == IdentifierDisplayMode::ShortestUniquePrefix {
+        ABBREVIATIONS.store(Arc::new(IdentifierAbbreviations::new(identifiers)));
+    }
+}
+
+/// Shortest unique prefixes for a set of identifiers.
+#[derive(Clone, Debug, Default)]
+pub struct IdentifierAbbreviations {
+    abbreviations: TrieMap<String>,
+}
+
+impl IdentifierAbbreviations {
+    /// Compute the abbreviations that keep all of `identifiers` distinguishable.
+    pub fn new<'a>(identifiers: impl IntoIterator<Item = &'a Identifier>) -> Self {
+        let identifiers = identifiers.into_iter().unique().collect::<Vec<_>>();
+        let mut abbreviations = TrieMap::new();
+        abbreviate_level(&identifiers, 0, &mut Vec::new(), &mut abbreviations);
+        Self { abbreviations }
+    }
+
+    /// The abbreviation of `identifier`, if it is one of the known identifiers.
+    #[must_use]
+    pub fn get<I: IdentifierLike>(&self, identifier: &I) -> Option<&str> {
+        self.abbreviations.get(identifier).map(String::as_str)
+    }
+
+    /// Render `identifier` abbreviated, or in full if it is not known.
+    #[must_use]
+    pub fn display<'a>(&'a self, identifier: &'a Identifier) -> impl fmt::Display + 'a {
+        DisplayWith {
+            abbreviations: self,
+            identifier,
+        }
+    }
+}
+
+/// Renders an [`Identifier`] with the process-wide abbreviations, see [`Identifier::abbreviated`].
+pub struct AbbreviatedIdentifier<'a>(&'a Identifier);
+
+impl fmt::Display for AbbreviatedIdentifier<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match abbreviation_of(self.0) {
+            Some(abbreviation) => f.write_str(&abbreviation),
+            None => fmt::Display::fmt(self.0, f),
+        }
+    }
+}
+
+impl Identifier {
+    /// Render this identifier for log output, honouring [`identifier_display_mode`].
+    #[must_use]
+    pub fn abbreviated(&self) -> AbbreviatedIdentifier<'_> {
+        AbbreviatedIdentifier(self)
+    }
+}
+
+/// The process-wide abbreviation of `identifier`, if abbreviations are enabled and it is known.
+pub(super) fn abbreviation_of(identifier: &Identifier) -> Option<String> {
+    if identifier_display_mode() == IdentifierDisplayMode::Full {
+        return None;
+    }
+    ABBREVIATIONS.load().get(identifier).map(str::to_owned)
+}
+
+struct DisplayWith<'a> {
+    abbreviations: &'a IdentifierAbbreviations,
+    identifier: &'a Identifier,
+}
+
+impl fmt::Display for DisplayWith<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.abbreviations.get(self.identifier) {
+            Some(abbreviation) => f.write_str(abbreviation),
+            None => fmt::Display::fmt(self.identifier, f),
+        }
+    }
+}
+
+/// Abbreviate segment `depth` of `identifiers`, which share their first `depth` segments.
+fn abbreviate_level(
+    identifiers: &[&Identifier],
+    depth: usize,
+    prefix: &mut Vec<String>,
+    out: &mut TrieMap<String>,
+) {
+    let mut children: BTreeMap<&str, Vec<&Identifier>> = BTreeMap::new();
+    for identifier in identifiers {
+        match identifier.segments().nth(depth) {
+            Some(segment) => children
+                .entry(segment.as_ref())
+                .or_default()
+                .push(identifier),
+            None => {
+                out.insert(
+                    Identifier::clone(identifier),
+                    prefix.join(SEGMENT_SEPARATOR),
+                );
+            }
+        }
+    }
+    let segments = children.keys().copied().collect::<Vec<_>>();
+    for (segment, identifiers) in &children {
+        prefix.push(shortest_unique_prefix(segment, &segments).to_owned());
+        abbreviate_level(identifiers, depth + 1, prefix, out);
+        prefix.pop();
+    }
+}
+
+/// The shortest prefix of `segment` that none of the other `siblings` starts with.
+///
+/// If `segment` is itself a prefix of a sibling, it is returned in full.
+fn shortest_unique_prefix<'a>(segment: &'a str, siblings: &[&str]) -> &'a str {
+    let shared_chars = siblings
+        .iter()
+        .filter(|sibling| **sibling != segment)
+        .map(|sibling| {
+            segment
+                .chars()
+                .zip(sibling.chars())
+                .take_while(|(left, right)| left == right)
+                .count()
+        })
+        .max()
+        .unwrap_or(0);
+    match segment.char_indices().nth(shared_chars + 1) {
+        Some((end, _)) => &segment[..end],
+        None => segment,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(input: &str) -> Identifier {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn abbreviations_are_shortest_unique_prefixes_per_segment() {
+        let identifiers = [
+            id("alice.phone"),
+            id("alice.tablet"),
+            id("albert"),
+            id("bob"),
+            id("bob.phone"),
+            id("al"),
+        ];
+        let abbreviations = IdentifierAbbreviations::new(&identifiers);
+
+        let rendered = identifiers
+            .iter()
+            .map(|identifier| abbreviations.display(identifier).to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(rendered, ["ali.p", "ali.t", "alb", "b", "b.p", "al"]);
+        assert_eq!(
+            abbreviations.display(&id("carol.phone")).to_string(),
+            "carol.phone"
+        );
+    }
+
+    #[test]
+    fn single_identifiers_abbreviate_to_one_character_per_segment() {
+        let identifiers = [id("alice.laptop")];
+        let abbreviations = IdentifierAbbreviations::new(&identifiers);
+        assert_eq!(abbreviations.get(&identifiers[0]), Some("a.l"));
+    }
+}
@@ -0,0 +1,563 @@
+use super::{GroupMembership, Identifier, RetiredPosition};
+use crate::versions::{GroupVersionVector, UpdateId, VersionVector};
+use snafu::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Index,
+};
+
+/// Whether a [`MembershipOperation`] adds or removes a member.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MembershipChangeKind {
+    Join,
+    Leave,
+}
+
+/// A replicated join or leave that takes effect at the start of `epoch`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MembershipOperation {
+    pub epoch: u64,
+    pub member: Identifier,
+    pub kind: MembershipChangeKind,
+}
+
+/// Errors produced when combining [`EpochMembership`] with version vectors.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum EpochMembershipError {
+    #[snafu(display("Epoch {epoch} is after the latest known epoch {latest}."))]
+    UnknownEpoch { epoch: u64, latest: u64 },
+    #[snafu(display(
+        "Epoch {epoch} has {member_count} members, but the version vector has {version_count} entries."
+    ))]
+    MemberCountMismatch {
+        epoch: u64,
+        member_count: usize,
+        version_count: usize,
+    },
+    #[snafu(display("Member {member} has not left the group."))]
+    NotRemoved { member: Identifier },
+}
+
+/// The members of a group during one epoch of an [`EpochMembership`].
+///
+/// Members are sorted by identifier, so every replica assigns the same position to the same
+/// member within an epoch.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EpochMembers {
+    epoch: u64,
+    members: Vec<Identifier>,
+}
+
+impl EpochMembers {
+    #[must_use]
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// The position of `member` in this epoch, if it is a member.
+    #[must_use]
+    pub fn index_of(&self, member: &Identifier) -> Option<usize> {
+        self.members.binary_search(member).ok()
+    }
+
+    #[must_use]
+    pub fn contains(&self, member: &Identifier) -> bool {
+        self.index_of(member).is_some()
+    }
+
+    #[must_use]
+    pub fn as_slice(&self) -> &[Identifier] {
+        &self.members
+    }
+}
+
+impl Index<usize> for EpochMembers {
+    type Output = Identifier;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.members[index]
+    }
+}
+
+impl IntoIterator for EpochMembers {
+    type IntoIter = std::vec::IntoIter<Identifier>;
+    type Item = Identifier;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.into_iter()
+    }
+}
+
+impl GroupMembership for EpochMembers {
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Identifier> {
+        self.members.iter()
+    }
+}
+
+/// A group membership that changes over numbered epochs through replicated joins and leaves.
+///
+/// Epoch `0` holds the initial members. Every [`MembershipOperation`] names the epoch it takes
+/// effect in, and the members of an epoch are those of the previous epoch with all of its joins
+/// and leaves applied. As a [`GroupMembership`], this type exposes the members of the latest epoch.
+///
+/// ## Guarantees
+///
+/// - **Convergence:** operations commute and are idempotent, so all replicas that applied the same
+///   operations agree on the members of every epoch.
+/// - **Conflicts:** if the same member both joins and leaves in the same epoch, the leave wins.
+/// - **Stable positions:** within an epoch members are ordered by identifier, so a member's
+///   position, and therefore its entry in a [`VersionVector`] for that epoch, is the same on all
+///   replicas.
+///
+/// The members of an epoch only stop changing once every replica has seen all of its operations,
+/// so version vectors should only be bound to epochs that were agreed on, e.g. through the
+/// migration protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpochMembership {
+    initial: BTreeSet<Identifier>,
+    /// Changes grouped by the epoch they take effect in.
+    changes: BTreeMap<u64, BTreeMap<Identifier, MembershipChangeKind>>,
+    /// Members of the latest epoch.
+    current: EpochMembers,
+}
+
+impl EpochMembership {
+    /// Create a membership whose epoch `0` consists of `members`.
+    pub fn new(members: impl IntoIterator<Item = Identifier>) -> Self {
+        let initial: BTreeSet<Identifier> = members.into_iter().collect();
+        let current = EpochMembers {
+            epoch: 0,
+            members: initial.iter().cloned().collect(),
+        };
+        Self {
+            initial,
+            changes: BTreeMap::new(),
+            current,
+        }
+    }
+
+    /// The latest epoch any operation takes effect in.
+    #[must_use]
+    pub fn current_epoch(&self) -> u64 {
+        self.current.epoch
+    }
+
+    /// The members of the latest epoch.
+    #[must_use]
+    pub fn current(&self) -> &EpochMembers {
+        &self.current
+    }
+
+    /// The members during `epoch`, or `None` if `epoch` is after the latest epoch.
+    #[must_use]
+    pub fn members_at(&self, epoch: u64) -> Option<EpochMembers> {
+        (epoch <= self.current.epoch).then(|| self.fold_members(epoch))
+    }
+
+    /// Produce an operation that adds `member` in the epoch after the latest one.
+    ///
+    /// All operations produced before one of them is applied share the same epoch.
+    #[must_use]
+    pub fn join_operation(&self, member: Identifier) -> MembershipOperation {
+        self.next_operation(member, MembershipChangeKind::Join)
+    }
+
+    /// Produce an operation that removes `member` in the epoch after the latest one.
+    ///
+    /// All operations produced before one of them is applied share the same epoch.
+    #[must_use]
+    pub fn leave_operation(&self, member: Identifier) -> MembershipOperation {
+        self.next_operation(member, MembershipChangeKind::Leave)
+    }
+
+    /// Apply an operation received from some replica (including ourselves).
+    ///
+    /// # Errors
+    ///
+    /// Returns the operation unchanged if it targets epoch `0`, which only holds the initial
+    /// members.
+    pub fn apply_operation(
+        &mut self,
+        operation: MembershipOperation,
+    ) -> Result<(), MembershipOperation> {
+        if operation.epoch == 0 {
+            return Err(operation);
+        }
+        let kind = self
+            .changes
+            .entry(operation.epoch)
+            .or_default()
+            .entry(operation.member)
+            .or_insert(operation.kind);
+        // Leave sorts after join, so the maximum makes leaves win.
+        *kind = (*kind).max(operation.kind);
+        let latest = self.current.epoch.max(operation.epoch);
+        self.current = self.fold_members(latest);
+        Ok(())
+    }
+
+    /// Bind `versions` to the members of `epoch`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `epoch` is unknown, or if its member count differs from the number of
+    /// entries in `versions`.
+    pub fn version_vector_at(
+        &self,
+        epoch: u64,
+        versions: VersionVector,
+    ) -> Result<GroupVersionVector<EpochMembers>, EpochMembershipError> {
+        let members = self.members_at(epoch).context(UnknownEpochSnafu {
+            epoch,
+            latest: self.current.epoch,
+        })?;
+        GroupVersionVector::new_checked(members, versions).map_err(|(members, versions)| {
+            EpochMembershipError::MemberCountMismatch {
+                epoch,
+                member_count: members.len(),
+                version_count: versions.num_members().get(),
+            }
+        })
+    }
+
+    /// Retire the position `member` held before its latest leave took effect.
+    ///
+    /// `final_versions` are the versions of the epoch before the leave that all remaining members
+    /// agreed on, e.g. the final versions of the migration that removed the member. The member's
+    /// entry in them becomes the [grace frontier](RetiredPosition::grace_frontier).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `member` never left the group, or if `final_versions` does not match
+    /// the size of the epoch before the leave.
+    pub fn retire(
+        &self,
+        member: &Identifier,
+        final_versions: &VersionVector,
+    ) -> Result<RetiredPosition, EpochMembershipError> {
+        let not_removed = || NotRemovedSnafu {
+            member: member.clone(),
+        };
+        let leave_epoch = self
+            .changes
+            .iter()
+            .rev()
+            .find(|(_, changes)| changes.get(member) == Some(&MembershipChangeKind::Leave))
+            .map(|(epoch, _)| *epoch)
+            .with_context(not_removed)?;
+        let epoch = leave_epoch - 1;
+        let members = self.fold_members(epoch);
+        let position = members.index_of(member).with_context(not_removed)?;
+        ensure!(
+            members.len() == final_versions.num_members().get(),
+            MemberCountMismatchSnafu {
+                epoch,
+                member_count: members.len(),
+                version_count: final_versions.num_members().get(),
+            }
+        );
+        Ok(RetiredPosition {
+            member: member.clone(),
+            epoch,
+            position: u32::try_from(position).expect("group positions must fit the wire format"),
+            grace_frontier: final_versions.version_at(position),
+        })
+    }
+
+    /// The member that produced `update_id` in `epoch`.
+    ///
+    /// Positions are never reassigned within an epoch, so this keeps attributing updates to
+    /// members that have since left or been [retired](Self::retire).
+    #[must_use]
+    pub fn author_of(&self, epoch: u64, update_id: UpdateId) -> Option<Identifier> {
+        let members = self.members_at(epoch)?;
+        members
+            .as_slice()
+            .get(update_id.node_index as usize)
+            .cloned()
+    }
+
+    fn next_operation(
+        &self,
+        member: Identifier,
+        kind: MembershipChangeKind,
+    ) -> MembershipOperation {
+        MembershipOperation {
+            epoch: self.current.epoch + 1,
+            member,
+            kind,
+        }
+    }
+
+    fn fold_members(&self, epoch: u64) -> EpochMembers {
+        let mut members = self.initial.clone();
+        for changes in self.changes.range(..=epoch).map(|(_, changes)| changes) {
+            for (member, kind) in changes {
+                match kind {
+                    MembershipChangeKind::Join => members.insert(member.clone()),
+                    MembershipChangeKind::Leave => members.remove(member),
+                };
+            }
+        }
+        EpochMembers {
+            epoch,
+            members: members.into_iter().collect(),
+        }
+    }
+}
+
+impl Index<usize> for EpochMembership {
+    type Output = Identifier;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.current[index]
+    }
+}
+
+impl IntoIterator for EpochMembership {
+    type IntoIter = std::vec::IntoIter<Identifier>;
+    type Item = Identifier;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.current.into_iter()
+    }
+}
+
+impl GroupMembership for EpochMembership {
+    fn len(&self) -> usize {
+        self.current.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Identifier> {
+        self.current.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        member::{RetiredPositions, RetirementError},
+        test_support::{ChurnStep, churn_schedule_strategy},
+        versions::{HappenedBeforeOrd, HappenedBeforeOrdering},
+    };
+    use proptest::prelude::*;
+    use std::{assert_matches, num::NonZeroUsize};
+
+    fn member(name: &str) -> Identifier {
+        Identifier::from_array([name])
+    }
+
+    #[test]
+    fn concurrent_changes_converge_with_stable_positions() {
+        let initial = [member("carol"), member("alice")];
+        let mut left = EpochMembership::new(initial.clone());
+        let mut right = EpochMembership::new(initial);
+
+        let join = left.join_operation(member("bob"));
+        let leave = right.leave_operation(member("carol"));
+        let rejoin = right.join_operation(member("carol"));
+        assert_eq!(join.epoch, 1);
+        for operation in [join.clone(), leave.clone(), rejoin.clone()] {
+            left.apply_operation(operation).unwrap();
+        }
+        for operation in [rejoin, join, leave] {
+            right.apply_operation(operation).unwrap();
+        }
+
+        assert_eq!(left, right);
+        assert_eq!(left.current_epoch(), 1);
+        assert_eq!(
+            left.iter().cloned().collect::<Vec<_>>(),
+            vec![member("alice"), member("bob")]
+        );
+        assert_eq!(left.current().index_of(&member("bob")), Some(1));
+
+        let epoch_zero = left.members_at(0).unwrap();
+        assert_eq!(
+            epoch_zero.as_slice(),
+            [member("alice"), member("carol")].as_slice()
+        );
+        assert_eq!(left.members_at(2), None);
+        assert!(
+            left.apply_operation(left.join_operation(member("dave")))
+                .is_ok()
+        );
+        assert_eq!(left.current_epoch(), 2);
+        assert_eq!(left.len(), 3);
+        assert_eq!(left[2], member("dave"));
+    }
+
+    #[test]
+    fn version_vectors_must_match_the_epoch_size() {
+        let mut membership = EpochMembership::new([member("alice"), member("bob")]);
+        membership
+            .apply_operation(membership.leave_operation(member("bob")))
+            .unwrap();
+        let two = VersionVector::initial(NonZeroUsize::new(2).unwrap());
+        let one = VersionVector::initial(NonZeroUsize::MIN);
+
+        let group_versions = membership.version_vector_at(0, two.clone()).unwrap();
+        assert_eq!(group_versions.group_members().epoch(), 0);
+        assert!(membership.version_vector_at(1, one).is_ok());
+        assert_matches!(
+            membership.version_vector_at(1, two.clone()),
+            Err(EpochMembershipError::MemberCountMismatch {
+                member_count: 1,
+                version_count: 2,
+                ..
+            })
+        );
+        assert_matches!(
+            membership.version_vector_at(5, two),
+            Err(EpochMembershipError::UnknownEpoch { latest: 1, .. })
+        );
+    }
+
+    #[test]
+    fn removed_members_are_retired_at_their_grace_frontier() {
+        let mut membership =
+            EpochMembership::new([member("alice"), member("bob"), member("carol")]);
+        assert_matches!(
+            membership.retire(&member("bob"), &VersionVector::from_entries([1, 2, 3])),
+            Err(EpochMembershipError::NotRemoved { .. })
+        );
+        membership
+            .apply_operation(membership.leave_operation(member("bob")))
+            .unwrap();
+        assert_matches!(
+            membership.retire(&member("bob"), &VersionVector::from_entries([1, 2])),
+            Err(EpochMembershipError::MemberCountMismatch { epoch: 0, .. })
+        );
+
+        let retired = membership
+            .retire(&member("bob"), &VersionVector::from_entries([1, 2, 3]))
+            .unwrap();
+        assert_eq!(
+            retired,
+            RetiredPosition {
+                member: member("bob"),
+                epoch: 0,
+                position: 1,
+                grace_frontier: 2,
+            }
+        );
+        let mut retired_positions = RetiredPositions::new();
+        retired_positions.insert(retired);
+
+        let bob_update = |version| UpdateId {
+            version,
+            node_index: 1,
+        };
+        assert!(retired_positions.check_update(0, bob_update(2)).is_ok());
+        assert_matches!(
+            retired_positions.check_update(0, bob_update(3)),
+            Err(RetirementError::AfterGraceFrontier {
+                grace_frontier: 2,
+                ..
+            })
+        );
+        // Other positions and other epochs are unaffected.
+        let carol_update = UpdateId {
+            version: 9,
+            node_index: 2,
+        };
+        assert!(retired_positions.check_update(0, carol_update).is_ok());
+        assert!(retired_positions.check_update(1, bob_update(3)).is_ok());
+
+        // Past updates stay attributed to the removed member.
+        assert_eq!(membership.author_of(0, bob_update(2)), Some(member("bob")));
+        assert_eq!(
+            membership.author_of(1, bob_update(2)),
+            Some(member("carol"))
+        );
+        assert_eq!(membership.author_of(2, bob_update(2)), None);
+    }
+
+    /// Run `schedule` against a group of three, recording the version vector after every write.
+    ///
+    /// Returns the membership, the operations in the order they were applied, and all recorded
+    /// vectors bound to the epoch they were recorded in.
+    fn simulate_churn(
+        schedule: &[ChurnStep],
+    ) -> (
+        EpochMembership,
+        Vec<MembershipOperation>,
+        Vec<GroupVersionVector<EpochMembers>>,
+    ) {
+        let mut membership = EpochMembership::new((0..3).map(|i| member(&format!("m{i}"))));
+        let mut operations = Vec::new();
+        let mut recorded = Vec::new();
+        let mut versions = vec![0u64; membership.len()];
+        let mut joined = 0;
+        for step in schedule {
+            let operation = match *step {
+                ChurnStep::Write { writer } => {
+                    let position = writer % versions.len();
+                    versions[position] += 1;
+                    let vector = membership
+                        .version_vector_at(
+                            membership.current_epoch(),
+                            VersionVector::from_entries(versions.iter().copied()),
+                        )
+                        .expect("Vectors are sized by the current epoch.");
+                    recorded.push(vector);
+                    continue;
+                }
+                ChurnStep::Join => {
+                    joined += 1;
+                    membership.join_operation(member(&format!("j{joined}")))
+                }
+                ChurnStep::Leave { .. } if membership.len() == 1 => continue,
+                ChurnStep::Leave { member } => {
+                    let leaving = membership[member % membership.len()].clone();
+                    membership.leave_operation(leaving)
+                }
+            };
+            membership.apply_operation(operation.clone()).unwrap();
+            operations.push(operation);
+            // Every epoch starts with a fresh vector over its members.
+            versions = vec![0u64; membership.len()];
+        }
+        (membership, operations, recorded)
+    }
+
+    proptest! {
+        #[test]
+        fn vectors_across_membership_churn_are_only_comparable_within_a_member_list(
+            schedule in churn_schedule_strategy(40)
+        ) {
+            let (membership, mut operations, recorded) = simulate_churn(&schedule);
+
+            for (earlier_index, earlier) in recorded.iter().enumerate() {
+                for later in &recorded[earlier_index..] {
+                    let ordering = earlier.hb_cmp(later);
+                    if earlier.has_same_members(later) {
+                        prop_assert_ne!(ordering, HappenedBeforeOrdering::Incomparable);
+                    } else {
+                        prop_assert_eq!(ordering, HappenedBeforeOrdering::Incomparable);
+                    }
+                    if earlier.group_members().epoch() == later.group_members().epoch() {
+                        prop_assert!(matches!(
+                            ordering,
+                            HappenedBeforeOrdering::Before | HappenedBeforeOrdering::Equal
+                        ));
+                    }
+                }
+            }
+
+            // Replicas receiving the same operations in another order agree on every epoch.
+            operations.reverse();
+            let mut replica = EpochMembership::new((0..3).map(|i| member(&format!("m{i}"))));
+            for operation in operations {
+                replica.apply_operation(operation).unwrap();
+            }
+            prop_assert_eq!(&replica, &membership);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     errors::{Errors, ErrorsExt, ErrorsResultExt},
+    sealed::Sealed,
     uuid_encodings::{UuidEncoding, UuidEncodingError, UuidEncodingExt},
 };
 use flotsync_utils::IString;
@@ -24,7 +25,9 @@ pub const MAX_IDENTIFIER_SEGMENTS: usize = u8::MAX as usize;
 pub type IdentifierSegment = IString;
 
 /// Common trait for types that are kind of an [[Identifier]] and differ only in ownership.
-pub trait IdentifierLike: fmt::Debug + fmt::Display {
+///
+/// This trait is sealed. Only [[Identifier]] and [[IdentifierRef]] implement it.
+pub trait IdentifierLike: fmt::Debug + fmt::Display + Sealed {
     /// Go through all segments of the identifier in order.
     fn segments(&self) -> impl Iterator<Item = &IdentifierSegment>;
 
@@ -50,6 +53,7 @@ pub trait IdentifierLike: fmt::Debug + fmt::Display {
 }
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum IdentifierError {
     #[snafu(display("The segment '{segment}' contained an illegal character."))]
     IllegalCharactersError { segment: String },
@@ -60,6 +64,7 @@ pub enum IdentifierError {
 }
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum IdentifierUuidDecodeError {
     #[snafu(display("Could not infer UUID encoding from segment '{segment}'."))]
     UnknownEncodingShapeError { segment: String },
@@ -90,6 +95,7 @@ pub enum IdentifierUuidDecodeError {
 }
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum IdentifierParseError {
     #[snafu(display("The identifier text '{input}' contains an empty segment."))]
     ParseEmptySegmentError { input: String },
@@ -272,6 +278,7 @@ impl Identifier {
     }
 }
 
+impl Sealed for Identifier {}
 impl IdentifierLike for Identifier {
     fn len(&self) -> usize {
         self.segments.len()
@@ -294,6 +301,9 @@ impl From<IdentifierBuf> for Identifier {
 
 impl fmt::Debug for Identifier {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(abbreviation) = super::abbreviation_of(self) {
+            return write!(f, "Identifier({abbreviation})");
+        }
         write!(
             f,
             "Identifier({})",
@@ -341,6 +351,7 @@ impl<'a> IdentifierRef<'a> {
     }
 }
 
+impl Sealed for IdentifierRef<'_> {}
 impl IdentifierLike for IdentifierRef<'_> {
     fn len(&self) -> usize {
         self.segments.len()
@@ -778,7 +789,7 @@ fn split_identifier_text(input: &str) -> Result<Vec<&str>, IdentifierUuidDecodeE
     Ok(segments)
 }
 
-const SEGMENT_SEPARATOR: &str = ".";
+pub(super) const SEGMENT_SEPARATOR: &str = ".";
 
 static LEGAL_CHARS_PATTERN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^[a-zA-Z0-9:_-]+$").unwrap());
@@ -275,6 +275,10 @@ impl TrieSet {
         self.0.insert(key, ()).is_none()
     }
 
+    pub fn remove(&mut self, key: &Identifier) -> bool {
+        self.0.remove(key).is_some()
+    }
+
     #[must_use]
     pub fn contains(&self, key: &Identifier) -> bool {
         self.0.get(key).is_some()
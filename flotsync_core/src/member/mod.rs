@@ -1,9 +1,15 @@
 use std::ops::Index;
 
+mod abbreviation;
+pub use abbreviation::*;
+mod epoch;
+pub use epoch::*;
 mod identifier;
 pub use identifier::*;
 mod identifier_trie;
 pub use identifier_trie::{TrieMap, TrieSet};
+mod retirement;
+pub use retirement::*;
 
 /// Some representation of flotsync group's members.
 pub trait GroupMembership:
@@ -0,0 +1,110 @@
+use super::Identifier;
+use crate::versions::UpdateId;
+use snafu::prelude::*;
+use std::collections::BTreeMap;
+
+/// The position a removed member held in the epoch before its removal.
+///
+/// Removing a member retires its position instead of reassigning it: updates the member produced
+/// while it was part of the group stay tagged with `(epoch, position)`, so
+/// [`EpochMembership::author_of`](super::EpochMembership::author_of) still attributes them to the
+/// member. Only updates up to the `grace_frontier` are accepted from the position, which covers
+/// everything the member produced before the removal was agreed on, even if it reaches some
+/// replicas late. Anything beyond it was produced after the removal and is rejected.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RetiredPosition {
+    pub member: Identifier,
+    /// The last epoch the member was part of.
+    pub epoch: u64,
+    /// The member's position in `epoch`.
+    pub position: u32,
+    /// The last version at `position` that is still accepted.
+    pub grace_frontier: u64,
+}
+
+impl RetiredPosition {
+    /// Whether `update_id`, produced in [`epoch`](Self::epoch), is still accepted.
+    ///
+    /// Updates of other positions are always accepted.
+    #[must_use]
+    pub fn accepts(&self, update_id: UpdateId) -> bool {
+        update_id.node_index != self.position || update_id.version <= self.grace_frontier
+    }
+}
+
+/// Errors produced when checking updates against [`RetiredPositions`].
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum RetirementError {
+    #[snafu(display(
+        "Update {update_id} of epoch {epoch} was produced by removed member {member} after its grace frontier {grace_frontier}."
+    ))]
+    AfterGraceFrontier {
+        epoch: u64,
+        update_id: UpdateId,
+        member: Identifier,
+        grace_frontier: u64,
+    },
+}
+
+/// All [`RetiredPosition`]s of a group, by epoch and position.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RetiredPositions {
+    positions: BTreeMap<(u64, u32), RetiredPosition>,
+}
+
+impl RetiredPositions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `retired`, replacing and returning an earlier record for the same epoch and
+    /// position.
+    pub fn insert(&mut self, retired: RetiredPosition) -> Option<RetiredPosition> {
+        self.positions
+            .insert((retired.epoch, retired.position), retired)
+    }
+
+    /// The retired position `position` of `epoch`, if any.
+    #[must_use]
+    pub fn get(&self, epoch: u64, position: u32) -> Option<&RetiredPosition> {
+        self.positions.get(&(epoch, position))
+    }
+
+    /// Check that `update_id`, produced in `epoch`, may still be applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RetirementError::AfterGraceFrontier`] if the update was produced by a retired
+    /// position after its grace frontier.
+    pub fn check_update(&self, epoch: u64, update_id: UpdateId) -> Result<(), RetirementError> {
+        let Some(retired) = self.get(epoch, update_id.node_index) else {
+            return Ok(());
+        };
+        ensure!(
+            retired.accepts(update_id),
+            AfterGraceFrontierSnafu {
+                epoch,
+                update_id,
+                member: retired.member.clone(),
+                grace_frontier: retired.grace_frontier,
+            }
+        );
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RetiredPosition> {
+        self.positions.values()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
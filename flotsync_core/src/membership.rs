@@ -2,11 +2,19 @@ use crate::{
     GroupId,
     MemberIdentity,
     MemberIndex,
-    member::{IdentifierRef, TrieMap},
+    member::{
+        IdentifierDisplayMode,
+        IdentifierRef,
+        TrieMap,
+        TrieSet,
+        identifier_display_mode,
+        update_identifier_abbreviations,
+    },
+    versions::{GroupVersionVector, VersionVector},
 };
 use arc_swap::ArcSwap;
 use snafu::prelude::*;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, num::NonZeroUsize, sync::Arc};
 
 /// Immutable snapshot of the groups currently hosted locally and their
 /// currently known members.
@@ -48,6 +56,20 @@ impl GroupMemberships {
         self.groups.keys()
     }
 
+    /// Abbreviate the members of all groups in this snapshot in log output, if enabled.
+    ///
+    /// See [`update_identifier_abbreviations`](crate::member::update_identifier_abbreviations).
+    fn update_identifier_abbreviations(&self) {
+        if identifier_display_mode() == IdentifierDisplayMode::ShortestUniquePrefix {
+            let members = self
+                .groups
+                .values()
+                .flat_map(GroupMembers::iter)
+                .collect::<Vec<_>>();
+            update_identifier_abbreviations(&members);
+        }
+    }
+
     /// Replace the membership set for one group inside this snapshot.
     pub fn insert(&mut self, group_id: GroupId, members: GroupMembers) -> Option<GroupMembers> {
         self.groups.insert(group_id, members)
@@ -70,6 +92,7 @@ impl SharedGroupMemberships {
     /// Create one new shared snapshot handle around the provided initial view.
     #[must_use]
     pub fn new(initial: GroupMemberships) -> Self {
+        initial.update_identifier_abbreviations();
         Self {
             inner: Arc::new(ArcSwap::from_pointee(initial)),
         }
@@ -86,6 +109,7 @@ impl SharedGroupMemberships {
 
     /// Replace the full shared snapshot atomically.
     pub fn replace(&self, memberships: GroupMemberships) {
+        memberships.update_identifier_abbreviations();
         self.inner.store(Arc::new(memberships));
     }
 }
@@ -98,6 +122,7 @@ impl Default for SharedGroupMemberships {
 
 /// Construction failures for indexed group member sets.
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum GroupMembersError {
     #[snafu(display(
         "Group member set contains duplicate member {member} in its canonical order."
@@ -115,9 +140,14 @@ pub enum GroupMembersError {
 /// stores the fixed canonical member index from the group's bootstrap order so
 /// delivery and replication can both query membership and producer positions
 /// from one shared snapshot.
+///
+/// A group may also have observers, which receive and apply the group's updates but hold no
+/// member index. They are therefore not counted by [`len`](Self::len), take no entry in the
+/// group's version vectors, and can never be the producer of an update.
 #[derive(Clone, Debug)]
 pub struct GroupMembers {
     member_indices: TrieMap<MemberIndex>,
+    observers: TrieSet,
 }
 
 impl GroupMembers {
@@ -158,13 +188,41 @@ impl GroupMembers {
                 return DuplicateMemberSnafu { member }.fail();
             }
         }
-        Ok(Self { member_indices })
+        Ok(Self {
+            member_indices,
+            observers: TrieSet::new(),
+        })
     }
 
-    /// Return whether this group currently includes `member`.
+    /// Add `observers` to this group, none of which may already be a member or observer.
+    ///
+    /// # Errors
+    ///
+    /// See `GroupMembersError` for failure conditions.
+    pub fn with_observers(
+        mut self,
+        observers: impl IntoIterator<Item = MemberIdentity>,
+    ) -> Result<Self, GroupMembersError> {
+        for observer in observers {
+            ensure!(
+                !self.contains(&observer),
+                DuplicateMemberSnafu { member: observer }
+            );
+            self.observers.insert(observer);
+        }
+        Ok(self)
+    }
+
+    /// Return whether this group currently includes `member`, either as a member or an observer.
     #[must_use]
     pub fn contains(&self, member: &MemberIdentity) -> bool {
-        self.member_indices.get(member).is_some()
+        self.member_indices.get(member).is_some() || self.observers.contains(member)
+    }
+
+    /// Return whether `member` observes this group without a member index.
+    #[must_use]
+    pub fn is_observer(&self, member: &MemberIdentity) -> bool {
+        self.observers.contains(member)
     }
 
     /// Return the fixed producer index assigned to `member`, if present.
@@ -190,9 +248,16 @@ impl GroupMembers {
         None
     }
 
-    /// Iterate all members currently in this group.
+    /// Iterate all members and observers currently in this group.
     pub fn iter(&self) -> impl Iterator<Item = MemberIdentity> + '_ {
-        self.member_indices.owned_keys()
+        self.member_indices
+            .owned_keys()
+            .chain(self.observers.owned_keys())
+    }
+
+    /// Iterate the observers of this group.
+    pub fn observers(&self) -> impl Iterator<Item = MemberIdentity> + '_ {
+        self.observers.owned_keys()
     }
 
     /// Return the canonical bootstrap order for this group.
@@ -210,13 +275,13 @@ impl GroupMembers {
             .collect()
     }
 
-    /// Return whether this member set is empty.
+    /// Return whether this group has no members with a member index.
     #[must_use]
     pub fn is_empty(&self) -> bool {
         self.member_indices.is_empty()
     }
 
-    /// Return the number of members in this group.
+    /// Return the number of members with a member index in this group, excluding observers.
     #[must_use]
     pub fn len(&self) -> usize {
         self.member_indices.len()
@@ -225,7 +290,7 @@ impl GroupMembers {
 
 impl PartialEq for GroupMembers {
     fn eq(&self, other: &Self) -> bool {
-        if self.len() != other.len() {
+        if self.len() != other.len() || self.observers != other.observers {
             return false;
         }
         let mut entries = self.member_indices.entries();
@@ -240,6 +305,129 @@ impl PartialEq for GroupMembers {
 
 impl Eq for GroupMembers {}
 
+/// Failures constructing a [`GroupContext`] or version vectors for it.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum GroupContextError {
+    #[snafu(display("Group {group_id} has no members in epoch {epoch}."))]
+    EmptyGroup { group_id: GroupId, epoch: u64 },
+    #[snafu(display(
+        "A version vector with {actual} members does not fit group {group_id}, which has {expected} members in epoch {epoch}."
+    ))]
+    MemberCountMismatch {
+        group_id: GroupId,
+        epoch: u64,
+        expected: NonZeroUsize,
+        actual: NonZeroUsize,
+    },
+}
+
+/// The identity, members, and membership epoch of one replication group.
+///
+/// Version vectors and documents are constructed from a context rather than from a bare member
+/// count, so a vector that does not fit its group is rejected when it is built instead of only
+/// comparing as incomparable later.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupContext {
+    // Fields are read-only so the membership can never become empty.
+    id: GroupId,
+    membership: GroupMembers,
+    epoch: u64,
+}
+
+impl GroupContext {
+    /// Describe group `id` with `membership` in membership `epoch`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `membership` is empty.
+    pub fn new(
+        id: GroupId,
+        membership: GroupMembers,
+        epoch: u64,
+    ) -> Result<Self, GroupContextError> {
+        ensure!(
+            !membership.is_empty(),
+            EmptyGroupSnafu {
+                group_id: id,
+                epoch,
+            }
+        );
+        Ok(Self {
+            id,
+            membership,
+            epoch,
+        })
+    }
+
+    #[must_use]
+    pub fn id(&self) -> GroupId {
+        self.id
+    }
+
+    #[must_use]
+    pub fn membership(&self) -> &GroupMembers {
+        &self.membership
+    }
+
+    #[must_use]
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// The number of members, which is also the size of every version vector of this group.
+    ///
+    /// # Panics
+    ///
+    /// Never panics, since the membership was checked to be non-empty on construction.
+    #[must_use]
+    pub fn num_members(&self) -> NonZeroUsize {
+        NonZeroUsize::new(self.membership.len()).expect("checked on construction")
+    }
+
+    /// The version vector of this group before any member produced an update.
+    #[must_use]
+    pub fn initial_version_vector(&self) -> VersionVector {
+        VersionVector::initial(self.num_members())
+    }
+
+    /// Check that `versions` has one entry per member of this group.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `versions` has a different number of members.
+    pub fn check_version_vector(&self, versions: &VersionVector) -> Result<(), GroupContextError> {
+        let expected = self.num_members();
+        let actual = versions.num_members();
+        ensure!(
+            expected == actual,
+            MemberCountMismatchSnafu {
+                group_id: self.id,
+                epoch: self.epoch,
+                expected,
+                actual,
+            }
+        );
+        Ok(())
+    }
+
+    /// Pair `versions` with the members of this group in their canonical order.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `versions` has a different number of members.
+    pub fn version_vector(
+        &self,
+        versions: VersionVector,
+    ) -> Result<GroupVersionVector<Vec<MemberIdentity>>, GroupContextError> {
+        self.check_version_vector(&versions)?;
+        Ok(GroupVersionVector::new(
+            self.membership.ordered_members(),
+            versions,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::assert_matches;
@@ -296,6 +484,60 @@ mod tests {
         assert_eq!(members.member_index(&bob), Some(MemberIndex::new(2)));
     }
 
+    #[test]
+    fn group_members_keep_observers_without_indices() {
+        let alice = member(["alice"]);
+        let bob = member(["bob"]);
+        let dashboard = member(["dashboard"]);
+
+        let members = GroupMembers::from_ordered_members(vec![alice.clone(), bob.clone()])
+            .expect("members should build")
+            .with_observers([dashboard.clone()])
+            .expect("observers should be added");
+
+        assert!(members.contains(&dashboard));
+        assert!(members.is_observer(&dashboard));
+        assert!(!members.is_observer(&alice));
+        assert_eq!(members.member_index(&dashboard), None);
+        assert_eq!(members.len(), 2);
+        assert_eq!(members.ordered_members(), vec![alice.clone(), bob]);
+        assert_eq!(members.observers().collect::<Vec<_>>(), vec![dashboard]);
+
+        let error = GroupMembers::singleton(alice.clone())
+            .expect("members should build")
+            .with_observers([alice])
+            .expect_err("a member cannot also observe the group");
+        assert_matches!(error, GroupMembersError::DuplicateMember { .. });
+    }
+
+    #[test]
+    fn group_contexts_only_accept_vectors_of_their_size() {
+        let alice = member(["alice"]);
+        let bob = member(["bob"]);
+        let group_id = GroupId(Uuid::from_u128(7));
+        let members = GroupMembers::from_ordered_members(vec![bob.clone(), alice.clone()])
+            .expect("members should build");
+        let context = GroupContext::new(group_id, members, 3).expect("group is not empty");
+
+        let vector = context
+            .version_vector(VersionVector::from_entries([4, 2]))
+            .expect("vector fits the group");
+        assert_eq!(
+            vector.iter().collect::<Vec<_>>(),
+            vec![(&bob, 4), (&alice, 2)]
+        );
+        assert_matches!(
+            context.version_vector(VersionVector::from_entries([4, 2, 1])),
+            Err(GroupContextError::MemberCountMismatch { epoch: 3, .. })
+        );
+
+        let empty = GroupMembers::from_ordered_members(Vec::new()).expect("members should build");
+        assert_matches!(
+            GroupContext::new(group_id, empty, 0),
+            Err(GroupContextError::EmptyGroup { .. })
+        );
+    }
+
     #[test]
     fn shared_group_memberships_replace_snapshot_atomically() {
         let alice = member(["alice"]);
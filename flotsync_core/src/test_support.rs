@@ -0,0 +1,342 @@
+//! Proptest strategies for property-testing code built on top of this crate.
+//!
+//! The core version types also implement [`proptest::arbitrary::Arbitrary`] on top of these
+//! strategies, so `any::<VersionVector>()` and friends work in downstream tests.
+//!
+//! This module is available when running this crate's own tests and to third-party
+//! crates that enable the `test-support` feature in their dev-dependencies.
+
+use crate::versions::{
+    MultiOverrideVersion,
+    OverrideVersion,
+    PureVersionVector,
+    SparseVersionVector,
+    UpdateId,
+    VersionVector,
+};
+use proptest::{prelude::*, strategy::Union};
+use std::num::NonZeroUsize;
+
+/// The smallest version that does not fit into `u32`.
+///
+/// Strategies use it as a bound to also cover versions past the compact encoding range.
+pub const LARGE_VERSION: u64 = (u32::MAX as u64) + 1;
+
+/// [`VersionVector::Full`] vectors with 1 to 99 members.
+pub fn full_vector_strategy() -> impl Strategy<Value = VersionVector> {
+    // Don't make ridiculously large vectors, since they take a lot of memory.
+    prop::collection::vec(any::<u64>(), 1..100)
+        .prop_map(|entries| VersionVector::Full(PureVersionVector::from(entries)))
+}
+
+/// [`VersionVector::Override`] vectors with any number of members.
+pub fn override_vector_strategy() -> impl Strategy<Value = VersionVector> {
+    (any::<NonZeroUsize>(), 0..LARGE_VERSION).prop_flat_map(|(num_members, group_version)| {
+        (0..num_members.get(), (group_version + 1)..u64::MAX).prop_map(
+            move |(override_position, override_version)| VersionVector::Override {
+                num_members,
+                version: OverrideVersion::new(group_version, override_position, override_version),
+            },
+        )
+    })
+}
+
+/// [`VersionVector::MultiOverride`] vectors with 6 to 99 members.
+pub fn multi_override_vector_strategy() -> impl Strategy<Value = VersionVector> {
+    (6usize..100usize).prop_flat_map(|num_members| {
+        fixed_size_multi_override_vector_strategy(NonZeroUsize::new(num_members).unwrap())
+    })
+}
+
+/// [`VersionVector::Sparse`] vectors with 4 to 99 members.
+pub fn sparse_vector_strategy() -> impl Strategy<Value = VersionVector> {
+    (4usize..100usize).prop_flat_map(|num_members| {
+        fixed_size_sparse_vector_strategy(NonZeroUsize::new(num_members).unwrap())
+    })
+}
+
+/// Version vectors of any representation and size.
+pub fn version_vector_strategy() -> impl Strategy<Value = VersionVector> {
+    prop_oneof![
+        full_vector_strategy(),
+        override_vector_strategy(),
+        multi_override_vector_strategy(),
+        sparse_vector_strategy(),
+        (any::<NonZeroUsize>(), any::<u64>()).prop_map(|(num_members, version)| {
+            VersionVector::Synced {
+                num_members,
+                version,
+            }
+        }),
+    ]
+}
+
+/// [`VersionVector::Full`] vectors with exactly `num_members` members.
+pub fn fixed_size_full_vector_strategy(
+    num_members: NonZeroUsize,
+) -> impl Strategy<Value = VersionVector> {
+    prop::collection::vec(any::<u64>(), num_members.get())
+        .prop_map(|entries| VersionVector::Full(PureVersionVector(entries.into_boxed_slice())))
+}
+
+/// [`VersionVector::Override`] vectors with exactly `num_members` members.
+pub fn fixed_size_override_vector_strategy(
+    num_members: NonZeroUsize,
+) -> impl Strategy<Value = VersionVector> {
+    (0..LARGE_VERSION).prop_flat_map(move |group_version| {
+        (0..num_members.get(), (group_version + 1)..u64::MAX).prop_map(
+            move |(override_position, override_version)| VersionVector::Override {
+                num_members,
+                version: OverrideVersion::new(group_version, override_position, override_version),
+            },
+        )
+    })
+}
+
+/// [`VersionVector::MultiOverride`] vectors with exactly `num_members` members.
+///
+/// Only vectors with the most compact representation are generated, so `num_members`
+/// must be at least 6.
+pub fn fixed_size_multi_override_vector_strategy(
+    num_members: NonZeroUsize,
+) -> impl Strategy<Value = VersionVector> {
+    let max_overrides = MultiOverrideVersion::MAX_OVERRIDES.min((num_members.get() - 2) / 2);
+    assert!(
+        max_overrides >= 2,
+        "Multi-override vectors need at least 6 members"
+    );
+    (0..LARGE_VERSION, 2..=max_overrides).prop_flat_map(move |(group_version, num_overrides)| {
+        (
+            prop::sample::subsequence((0..num_members.get()).collect::<Vec<_>>(), num_overrides),
+            prop::collection::vec((group_version + 1)..u64::MAX, num_overrides),
+        )
+            .prop_map(move |(positions, versions)| VersionVector::MultiOverride {
+                num_members,
+                version: MultiOverrideVersion::new(
+                    group_version,
+                    positions.into_iter().zip(versions),
+                ),
+            })
+    })
+}
+
+/// [`VersionVector::Sparse`] vectors with exactly `num_members` members.
+///
+/// Only vectors in which a strict majority shares the base version are generated, so
+/// `num_members` must be at least 4. Some of them are more compactly represented in another form.
+pub fn fixed_size_sparse_vector_strategy(
+    num_members: NonZeroUsize,
+) -> impl Strategy<Value = VersionVector> {
+    let max_entries = (num_members.get() - 2) / 2;
+    assert!(max_entries >= 1, "Sparse vectors need at least 4 members");
+    (0..LARGE_VERSION, 1..=max_entries).prop_flat_map(move |(base_version, num_entries)| {
+        (
+            prop::sample::subsequence((0..num_members.get()).collect::<Vec<_>>(), num_entries),
+            prop::collection::vec(
+                any::<u64>().prop_filter("entries differ from the base", move |version| {
+                    *version != base_version
+                }),
+                num_entries,
+            ),
+        )
+            .prop_map(move |(positions, versions)| VersionVector::Sparse {
+                num_members,
+                version: SparseVersionVector::new(
+                    base_version,
+                    positions.into_iter().zip(versions),
+                ),
+            })
+    })
+}
+
+/// [`VersionVector::Synced`] vectors with exactly `num_members` members.
+pub fn fixed_size_synced_strategy(
+    num_members: NonZeroUsize,
+) -> impl Strategy<Value = VersionVector> {
+    any::<u64>().prop_map(move |version| VersionVector::Synced {
+        num_members,
+        version,
+    })
+}
+
+/// Version vectors of any representation with exactly `num_members` members.
+pub fn fixed_size_version_vector_strategy(
+    num_members: NonZeroUsize,
+) -> BoxedStrategy<VersionVector> {
+    let mut strategies = vec![
+        fixed_size_full_vector_strategy(num_members).boxed(),
+        fixed_size_synced_strategy(num_members).boxed(),
+    ];
+    if num_members.get() > 1 {
+        strategies.push(fixed_size_override_vector_strategy(num_members).boxed());
+    }
+    if num_members.get() >= 4 {
+        strategies.push(fixed_size_sparse_vector_strategy(num_members).boxed());
+    }
+    if num_members.get() >= 6 {
+        strategies.push(fixed_size_multi_override_vector_strategy(num_members).boxed());
+    }
+    Union::new(strategies).boxed()
+}
+
+/// Member counts from 1 to 99.
+pub fn version_vector_size_strategy() -> impl Strategy<Value = NonZeroUsize> {
+    (1usize..100usize).prop_map(|u| NonZeroUsize::new(u).unwrap())
+}
+
+prop_compose! {
+    /// Three version vectors with the same number of members.
+    pub fn equal_size_version_vector_strategy()(l in version_vector_size_strategy())(v1 in fixed_size_version_vector_strategy(l), v2 in fixed_size_version_vector_strategy(l), v3 in fixed_size_version_vector_strategy(l)) -> (VersionVector, VersionVector, VersionVector) {
+        (v1, v2, v3)
+    }
+}
+
+/// Sets of updates that `num_members` members produced concurrently from the same state.
+///
+/// Every member produces between 0 and `max_updates_per_member` updates with consecutive
+/// versions starting at 1, so the per-member order is valid causal order. The members' updates
+/// are interleaved arbitrarily, which makes the result suitable for checking that applying
+/// concurrent updates in any causally valid order converges.
+pub fn concurrent_update_ids_strategy(
+    num_members: NonZeroUsize,
+    max_updates_per_member: u64,
+) -> impl Strategy<Value = Vec<UpdateId>> {
+    prop::collection::vec(0..=max_updates_per_member, num_members.get())
+        .prop_flat_map(|update_counts| {
+            let update_ids: Vec<UpdateId> = update_counts
+                .iter()
+                .enumerate()
+                .flat_map(|(node_index, &count)| {
+                    let node_index = u32::try_from(node_index).expect("member count fits into u32");
+                    (1..=count).map(move |version| UpdateId {
+                        version,
+                        node_index,
+                    })
+                })
+                .collect();
+            Just(update_ids).prop_shuffle()
+        })
+        .prop_map(|mut update_ids| {
+            // Restore per-member version order while keeping the shuffled interleaving.
+            let mut positions_by_member: Vec<Vec<usize>> = Vec::new();
+            for (position, update_id) in update_ids.iter().enumerate() {
+                let member = update_id.node_index as usize;
+                if positions_by_member.len() <= member {
+                    positions_by_member.resize_with(member + 1, Vec::new);
+                }
+                positions_by_member[member].push(position);
+            }
+            for positions in positions_by_member {
+                let mut versions: Vec<u64> = positions
+                    .iter()
+                    .map(|&position| update_ids[position].version)
+                    .collect();
+                versions.sort_unstable();
+                for (position, version) in positions.into_iter().zip(versions) {
+                    update_ids[position].version = version;
+                }
+            }
+            update_ids
+        })
+}
+
+/// One step of a simulated group whose membership changes while its members write.
+///
+/// Member positions are taken modulo the number of members at the time the step runs, so every
+/// schedule is valid whatever the group looks like at that point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChurnStep {
+    /// The member at `writer` performs a local edit.
+    Write { writer: usize },
+    /// A new member joins the group.
+    Join,
+    /// The member at `member` leaves the group, unless it is the last one.
+    Leave { member: usize },
+}
+
+/// Schedules of up to `max_steps` [`ChurnStep`]s, mostly writes with joins and leaves in between.
+pub fn churn_schedule_strategy(max_steps: usize) -> impl Strategy<Value = Vec<ChurnStep>> {
+    let step = prop_oneof![
+        6 => any::<usize>().prop_map(|writer| ChurnStep::Write { writer }),
+        1 => Just(ChurnStep::Join),
+        1 => any::<usize>().prop_map(|member| ChurnStep::Leave { member }),
+    ];
+    prop::collection::vec(step, 0..=max_steps)
+}
+
+impl Arbitrary for UpdateId {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (any::<u64>(), any::<u32>())
+            .prop_map(|(version, node_index)| UpdateId {
+                version,
+                node_index,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for PureVersionVector {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        prop::collection::vec(any::<u64>(), 1..100)
+            .prop_map(PureVersionVector::from)
+            .boxed()
+    }
+}
+
+impl Arbitrary for OverrideVersion {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        override_vector_strategy()
+            .prop_map(|vector| match vector {
+                VersionVector::Override { version, .. } => version,
+                _ => unreachable!("override_vector_strategy only produces override vectors"),
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for VersionVector {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        version_vector_strategy().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn arbitrary_version_vectors_survive_normalization(vector in any::<VersionVector>()) {
+            let mut normalized = vector.clone();
+            normalized.normalize();
+            prop_assert_eq!(normalized.partial_cmp(&vector), Some(std::cmp::Ordering::Equal));
+        }
+
+        #[test]
+        fn concurrent_update_ids_are_in_causal_order(
+            (num_members, update_ids) in (1usize..8).prop_flat_map(|n| {
+                let num_members = NonZeroUsize::new(n).unwrap();
+                (Just(num_members), concurrent_update_ids_strategy(num_members, 5))
+            })
+        ) {
+            let mut next_versions = vec![1u64; num_members.get()];
+            for update_id in update_ids {
+                let next_version = &mut next_versions[update_id.node_index as usize];
+                prop_assert_eq!(update_id.version, *next_version);
+                *next_version += 1;
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::sealed::Sealed;
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use itertools::Itertools;
 use snafu::prelude::*;
@@ -15,6 +16,7 @@ pub enum UuidEncoding {
 }
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum UuidEncodingError {
     #[snafu(display("Failed to decode hyphenated UUID segment '{input}': {source}"))]
     DecodeHyphenatedError { input: String, source: uuid::Error },
@@ -36,7 +38,10 @@ pub enum UuidEncodingError {
     InvalidByteLengthError { input: String, actual: usize },
 }
 
-pub trait UuidEncodingExt {
+/// Text encodings for [`Uuid`].
+///
+/// This trait is sealed and only implemented for [`Uuid`].
+pub trait UuidEncodingExt: Sealed {
     /// # Errors
     ///
     /// See `UuidEncodingError` for failure conditions.
@@ -53,6 +58,7 @@ pub trait UuidEncodingExt {
     fn decode_base64(input: &str) -> Result<Uuid, UuidEncodingError>;
 }
 
+impl Sealed for Uuid {}
 impl UuidEncodingExt for Uuid {
     fn encode_words(&self) -> Result<String, UuidEncodingError> {
         let words = niceware::bytes_to_passphrase(self.as_bytes()).context(EncodeWordsSnafu)?;
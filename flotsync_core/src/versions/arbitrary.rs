@@ -0,0 +1,74 @@
+//! [`arbitrary::Arbitrary`] implementations for fuzzing code built on top of this crate.
+//!
+//! Generated values always uphold the invariants of the respective types, so fuzz targets
+//! exercise real logic instead of tripping over constructor assertions.
+
+use super::{OverrideVersion, PureVersionVector, UpdateId, VersionVector};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use core::num::NonZeroUsize;
+
+/// The largest number of members in a generated vector that stores explicit member versions.
+///
+/// Larger groups are only produced as [`VersionVector::Synced`], which does not pay for its size.
+const MAX_EXPLICIT_MEMBERS: usize = 64;
+
+impl<'a> Arbitrary<'a> for UpdateId {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            version: u.arbitrary()?,
+            node_index: u.arbitrary()?,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        <(u64, u32)>::size_hint(depth)
+    }
+}
+
+impl<'a> Arbitrary<'a> for PureVersionVector {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let num_members = u.int_in_range(1..=MAX_EXPLICIT_MEMBERS)?;
+        let entries = (0..num_members)
+            .map(|_| u.arbitrary())
+            .collect::<Result<Vec<u64>>>()?;
+        Ok(Self::from(entries))
+    }
+}
+
+impl<'a> Arbitrary<'a> for OverrideVersion {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let group_version = u.int_in_range(0..=u64::MAX - 1)?;
+        let override_position = u.arbitrary()?;
+        let override_version = u.int_in_range((group_version + 1)..=u64::MAX)?;
+        Ok(Self::new(
+            group_version,
+            override_position,
+            override_version,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for VersionVector {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.ratio(1, 4)? {
+            return Ok(Self::Synced {
+                num_members: u.arbitrary::<NonZeroUsize>()?,
+                version: u.arbitrary()?,
+            });
+        }
+        // Most members share a base version, so every compact representation shows up,
+        // while the canonical constructor decides which one actually applies.
+        let base_version: u64 = u.arbitrary()?;
+        let num_members = u.int_in_range(1..=MAX_EXPLICIT_MEMBERS)?;
+        let entries = (0..num_members)
+            .map(|_| {
+                if u.ratio(1, 4)? {
+                    u.arbitrary()
+                } else {
+                    Ok(base_version)
+                }
+            })
+            .collect::<Result<Vec<u64>>>()?;
+        Ok(Self::from_entries(entries))
+    }
+}
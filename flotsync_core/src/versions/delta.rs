@@ -0,0 +1,137 @@
+//! Deltas between successive [[`VersionVector`]]s of one member set.
+//!
+//! Peers that exchange their vectors every gossip round are usually almost synced, so only a
+//! few member versions change between rounds. A [[`VersionVectorDelta`]] carries just those
+//! members, and the receiver rebuilds the new vector from the previous one it already has.
+
+use super::VersionVector;
+use alloc::{boxed::Box, vec::Vec};
+use core::{fmt, num::NonZeroUsize};
+#[cfg(feature = "std")]
+use flotsync_utils::option_when;
+
+/// The member versions that changed from one [[`VersionVector`]] to the next.
+///
+/// Created by [`VersionVector::diff_encode`] and applied with [`VersionVector::apply_delta`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionVectorDelta {
+    num_members: NonZeroUsize,
+    /// `(position, version)` pairs of the changed members, in strictly increasing position order.
+    changes: Box<[(usize, u64)]>,
+}
+impl VersionVectorDelta {
+    /// # Panics
+    ///
+    /// Panics if the positions of `changes` are not strictly increasing or not below
+    /// `num_members`.
+    #[must_use]
+    pub fn new(num_members: NonZeroUsize, changes: impl IntoIterator<Item = (usize, u64)>) -> Self {
+        Self::new_opt(num_members, changes).expect("Invalid version vector delta")
+    }
+
+    /// Returns `None` if `changes` do not describe distinct members of a `num_members` group in
+    /// position order.
+    #[must_use]
+    pub fn new_opt(
+        num_members: NonZeroUsize,
+        changes: impl IntoIterator<Item = (usize, u64)>,
+    ) -> Option<Self> {
+        let delta = Self {
+            num_members,
+            changes: changes.into_iter().collect(),
+        };
+        option_when!(delta.is_valid(), delta)
+    }
+
+    /// The member count of the vectors this delta applies to.
+    #[must_use]
+    pub const fn num_members(&self) -> NonZeroUsize {
+        self.num_members
+    }
+
+    /// `(position, version)` pairs of the changed members, in position order.
+    #[must_use]
+    pub fn changes(&self) -> &[(usize, u64)] {
+        &self.changes
+    }
+
+    /// Returns `true` if no member version changed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn is_valid(&self) -> bool {
+        let in_order = self.changes.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        let in_range = self
+            .changes
+            .last()
+            .is_none_or(|(position, _)| *position < self.num_members.get());
+        in_order && in_range
+    }
+}
+
+impl fmt::Display for VersionVectorDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Δ〈")?;
+        for (index, (position, version)) in self.changes.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{position}:{version}")?;
+        }
+        write!(f, "〉")
+    }
+}
+
+impl VersionVector {
+    /// Return the member versions that changed from `previous` to `self`.
+    ///
+    /// Applying the result to `previous` with [`apply_delta`](Self::apply_delta) gives a vector
+    /// equal to `self`. Members whose version went down are included as well, so the delta does
+    /// not require `previous` to happen before `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vectors describe different member sets.
+    #[must_use]
+    pub fn diff_encode(&self, previous: &Self) -> VersionVectorDelta {
+        assert_eq!(
+            self.num_members(),
+            previous.num_members(),
+            "Version vectors with different member counts cannot be combined"
+        );
+        let changes = self
+            .iter()
+            .zip(previous.iter())
+            .enumerate()
+            .filter(|(_, (current, previous))| current != previous)
+            .map(|(position, (current, _))| (position, current))
+            .collect();
+        VersionVectorDelta {
+            num_members: self.num_members(),
+            changes,
+        }
+    }
+
+    /// Return this vector with the member versions of `delta` applied.
+    ///
+    /// The result uses the most compact representation for its member versions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delta` was computed for a different member count.
+    #[must_use]
+    pub fn apply_delta(&self, delta: &VersionVectorDelta) -> Self {
+        assert_eq!(
+            self.num_members(),
+            delta.num_members,
+            "Version vector deltas cannot be applied to vectors with different member counts"
+        );
+        let mut versions = self.iter().collect::<Vec<_>>();
+        for (position, version) in delta.changes() {
+            versions[*position] = *version;
+        }
+        Self::from_entries(versions)
+    }
+}
@@ -1,7 +1,10 @@
 use super::{HappenedBeforeOrd, HappenedBeforeOrdering, UpdateId};
+use crate::sealed::Sealed;
+use alloc::{borrow::Cow, boxed::Box, format, vec, vec::Vec};
+use core::{cmp, fmt, num::NonZeroUsize};
+#[cfg(feature = "std")]
 use flotsync_utils::option_when;
-use itertools::Itertools;
-use std::{cmp, fmt, num::NonZeroUsize};
+use itertools::{EitherOrBoth, Itertools};
 
 /// One inclusive member-version interval needed to catch one vector up to another.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -24,6 +27,18 @@ pub enum VersionVector {
         num_members: NonZeroUsize,
         version: OverrideVersion,
     },
+    /// The system is mostly synced up, but a few participants are posting new versions.
+    MultiOverride {
+        num_members: NonZeroUsize,
+        version: MultiOverrideVersion,
+    },
+    /// Most participants share one version, but any number of others differ from it.
+    ///
+    /// This is meant for very large groups in which most members never write.
+    Sparse {
+        num_members: NonZeroUsize,
+        version: SparseVersionVector,
+    },
     /// The system is fully synced up and all participants have exactly the same version.
     Synced {
         num_members: NonZeroUsize,
@@ -40,11 +55,68 @@ impl VersionVector {
         }
     }
 
+    /// Build a vector from explicit member versions in position order, using the most compact
+    /// representation that can encode them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entries` is empty.
+    #[must_use]
+    pub fn from_entries(entries: impl IntoIterator<Item = u64>) -> Self {
+        Self::from_versions(entries.into_iter().collect())
+    }
+
+    /// Switch to the most compact representation that can encode the current member versions.
+    ///
+    /// Most operations already keep vectors compact, but some, like
+    /// [`increment_at`](Self::increment_at) on a `Full` vector, never leave the `Full`
+    /// representation by themselves.
+    pub fn normalize(&mut self) {
+        match self {
+            VersionVector::Full(vector) => {
+                if let Some(compact) = Self::try_compact_versions(&vector.0) {
+                    *self = compact;
+                }
+            }
+            VersionVector::Override {
+                num_members,
+                version,
+            } if num_members.get() == 1 => {
+                *self = VersionVector::Synced {
+                    num_members: *num_members,
+                    version: version.override_version,
+                };
+            }
+            VersionVector::MultiOverride {
+                num_members,
+                version,
+            } if !MultiOverrideVersion::is_compact_for(*num_members, version.overrides.len()) => {
+                *self = compact_entries(
+                    *num_members,
+                    version.group_version,
+                    version.overrides.to_vec(),
+                );
+            }
+            VersionVector::Sparse {
+                num_members,
+                version,
+            } => {
+                *self =
+                    compact_entries(*num_members, version.base_version, version.entries.to_vec());
+            }
+            VersionVector::Override { .. }
+            | VersionVector::MultiOverride { .. }
+            | VersionVector::Synced { .. } => (),
+        }
+    }
+
     #[must_use]
     pub const fn num_members(&self) -> NonZeroUsize {
         match self {
             VersionVector::Full(v) => v.len(),
             VersionVector::Override { num_members, .. }
+            | VersionVector::MultiOverride { num_members, .. }
+            | VersionVector::Sparse { num_members, .. }
             | VersionVector::Synced { num_members, .. } => *num_members,
         }
     }
@@ -54,6 +126,8 @@ impl VersionVector {
         match self {
             VersionVector::Full(v) => v.max_version(),
             VersionVector::Override { version, .. } => version.override_version,
+            VersionVector::MultiOverride { version, .. } => version.max_version(),
+            VersionVector::Sparse { version, .. } => version.max_version(),
             VersionVector::Synced { version, .. } => *version,
         }
     }
@@ -86,6 +160,14 @@ impl VersionVector {
                 num_members,
                 version: override_version,
             } => override_version.with_version_at(*num_members, position, version),
+            VersionVector::MultiOverride {
+                num_members,
+                version: multi_override_version,
+            } => multi_override_version.with_version_at(*num_members, position, version),
+            VersionVector::Sparse {
+                num_members,
+                version: sparse_version,
+            } => sparse_version.with_version_at(*num_members, position, version),
             VersionVector::Synced {
                 num_members,
                 version: group_version,
@@ -183,11 +265,34 @@ impl VersionVector {
                         .checked_add(1)
                         .expect("Max version reached");
                 } else {
-                    let mut full = version.to_vector(*num_members);
-                    full.increment_at(position);
-                    *self = Self::Full(full);
+                    // Cannot overflow, since the group version is below the override version.
+                    *self =
+                        version.with_version_at(*num_members, position, version.group_version + 1);
                 }
             }
+            VersionVector::MultiOverride {
+                num_members,
+                version,
+            } => {
+                if let Ok(index) = version.override_index(position) {
+                    let entry = &mut version.overrides[index].1;
+                    *entry = entry.checked_add(1).expect("Max version reached");
+                } else {
+                    // Cannot overflow, since the group version is below every override version.
+                    *self =
+                        version.with_version_at(*num_members, position, version.group_version + 1);
+                }
+            }
+            VersionVector::Sparse {
+                num_members,
+                version,
+            } => {
+                let next_version = version
+                    .version_at(position)
+                    .checked_add(1)
+                    .expect("Max version reached");
+                *self = version.with_version_at(*num_members, position, next_version);
+            }
             VersionVector::Synced {
                 num_members,
                 version,
@@ -204,10 +309,74 @@ impl VersionVector {
         }
     }
 
+    /// Set `position` to `version` in place.
+    ///
+    /// Like [`with_version_at`](Self::with_version_at), this switches to the most compact
+    /// representation that can encode the resulting member versions, promoting compact vectors
+    /// to `Override` or `Full` only when necessary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is outside this vector's member range.
+    pub fn set_at(&mut self, position: usize, version: u64) {
+        if let VersionVector::Full(vector) = self {
+            assert!(
+                position < vector.0.len(),
+                "Position {position} is outside of group range (0-{})",
+                vector.0.len()
+            );
+            vector.0[position] = version;
+            if let Some(compact) = Self::try_compact_versions(&vector.0) {
+                *self = compact;
+            }
+        } else {
+            *self = self.with_version_at(position, version);
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = u64> {
         self.into_iter()
     }
 
+    /// Iterate over `(position, version)` pairs for all members, in position order.
+    pub fn entries(&self) -> impl Iterator<Item = (usize, u64)> {
+        self.iter().enumerate()
+    }
+
+    /// Return the version stored at `position`, or `None` if `position` is outside the fixed
+    /// member range.
+    ///
+    /// This reads compact representations directly, without expanding them.
+    #[must_use]
+    pub fn get(&self, position: usize) -> Option<u64> {
+        match self {
+            VersionVector::Full(vector) => vector.0.get(position).copied(),
+            VersionVector::Override {
+                num_members,
+                version,
+            } => option_when!(
+                position < num_members.get(),
+                if position == version.override_position {
+                    version.override_version
+                } else {
+                    version.group_version
+                }
+            ),
+            VersionVector::MultiOverride {
+                num_members,
+                version,
+            } => option_when!(position < num_members.get(), version.version_at(position)),
+            VersionVector::Sparse {
+                num_members,
+                version,
+            } => option_when!(position < num_members.get(), version.version_at(position)),
+            VersionVector::Synced {
+                num_members,
+                version,
+            } => option_when!(position < num_members.get(), *version),
+        }
+    }
+
     /// Return the version stored at `position`.
     ///
     /// # Panics
@@ -215,8 +384,7 @@ impl VersionVector {
     /// Panics when `position` is outside the fixed member range.
     #[must_use]
     pub fn version_at(&self, position: usize) -> u64 {
-        self.iter()
-            .nth(position)
+        self.get(position)
             .expect("version-vector position must be within range")
     }
 
@@ -226,24 +394,64 @@ impl VersionVector {
     ///
     /// Panics when `versions` is empty.
     fn from_versions(versions: Vec<u64>) -> Self {
+        Self::try_compact_versions(&versions)
+            .unwrap_or_else(|| Self::Full(PureVersionVector::from(versions)))
+    }
+
+    /// Return the `Synced`, `Override`, `MultiOverride`, or `Sparse` representation of explicit
+    /// member versions, if one exists.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `versions` is empty.
+    fn try_compact_versions(versions: &[u64]) -> Option<Self> {
         let num_members =
             NonZeroUsize::new(versions.len()).expect("version vectors must not be empty");
         let first_version = versions[0];
         if versions.iter().all(|version| *version == first_version) {
-            return Self::Synced {
+            return Some(Self::Synced {
                 num_members,
                 version: first_version,
-            };
+            });
         }
 
-        if let Some(version) = OverrideVersion::try_from_versions(&versions) {
-            return Self::Override {
+        if let Some(version) = OverrideVersion::try_from_versions(versions) {
+            return Some(Self::Override {
                 num_members,
                 version,
-            };
+            });
         }
 
-        Self::Full(PureVersionVector::from(versions))
+        if let Some(version) = MultiOverrideVersion::try_from_versions(versions) {
+            return Some(Self::MultiOverride {
+                num_members,
+                version,
+            });
+        }
+
+        SparseVersionVector::try_from_versions(versions).map(|version| Self::Sparse {
+            num_members,
+            version,
+        })
+    }
+
+    /// Return the common base version and the `(position, version)` entries that differ from it,
+    /// or `None` for `Full` vectors.
+    fn base_and_entries(&self) -> Option<BaseAndEntries<'_>> {
+        match self {
+            VersionVector::Full(_) => None,
+            VersionVector::Override { version, .. } => Some((
+                version.group_version,
+                Cow::Owned(vec![(version.override_position, version.override_version)]),
+            )),
+            VersionVector::MultiOverride { version, .. } => {
+                Some((version.group_version, Cow::Borrowed(&version.overrides)))
+            }
+            VersionVector::Sparse { version, .. } => {
+                Some((version.base_version, Cow::Borrowed(&version.entries)))
+            }
+            VersionVector::Synced { version, .. } => Some((*version, Cow::Borrowed(&[]))),
+        }
     }
 
     /// Apply one pointwise operation to compatible vectors.
@@ -301,7 +509,9 @@ impl VersionVector {
             ),
             (VersionVector::Override { .. }, VersionVector::Override { .. })
             | (VersionVector::Full(_), _)
-            | (_, VersionVector::Full(_)) => {
+            | (_, VersionVector::Full(_))
+            | (VersionVector::MultiOverride { .. } | VersionVector::Sparse { .. }, _)
+            | (_, VersionVector::MultiOverride { .. } | VersionVector::Sparse { .. }) => {
                 VersionVector::from_versions(pointwise_combine_to_vec(self, other, combine))
             }
         }
@@ -346,6 +556,14 @@ impl fmt::Display for VersionVector {
                     )
                 }
             }
+            VersionVector::MultiOverride {
+                num_members,
+                version,
+            } => fmt_base_and_entries(f, *num_members, version.group_version, &version.overrides),
+            VersionVector::Sparse {
+                num_members,
+                version,
+            } => fmt_base_and_entries(f, *num_members, version.base_version, &version.entries),
             VersionVector::Synced {
                 num_members,
                 version,
@@ -353,12 +571,22 @@ impl fmt::Display for VersionVector {
         }
     }
 }
+impl Sealed for VersionVector {}
 impl HappenedBeforeOrd for VersionVector {
     fn hb_cmp(&self, other: &Self) -> HappenedBeforeOrdering {
         if self.num_members() != other.num_members() {
             return HappenedBeforeOrdering::Incomparable;
         }
         match (self, other) {
+            (VersionVector::MultiOverride { .. } | VersionVector::Sparse { .. }, _)
+            | (_, VersionVector::MultiOverride { .. } | VersionVector::Sparse { .. }) => {
+                match (self.base_and_entries(), other.base_and_entries()) {
+                    (Some(left), Some(right)) => {
+                        hb_compare_base_and_entries(self.num_members(), left, right)
+                    }
+                    _ => hb_compare_entries(self.iter(), other.iter()),
+                }
+            }
             (VersionVector::Full(v1), VersionVector::Full(v2)) => v1.hb_cmp(v2),
             (
                 VersionVector::Full(v1),
@@ -455,6 +683,12 @@ impl PartialOrd for VersionVector {
         self.hb_cmp(other).into()
     }
 }
+impl FromIterator<u64> for VersionVector {
+    /// See [`VersionVector::from_entries`].
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        Self::from_entries(iter)
+    }
+}
 impl<'a> IntoIterator for &'a VersionVector {
     type Item = u64;
 
@@ -470,11 +704,27 @@ impl<'a> IntoIterator for &'a VersionVector {
                 *num_members,
                 version.clone(),
             )),
+            VersionVector::MultiOverride {
+                num_members,
+                version,
+            } => VersionVectorIterInternal::Entries(EntriesIter::new(
+                *num_members,
+                version.group_version,
+                &version.overrides,
+            )),
+            VersionVector::Sparse {
+                num_members,
+                version,
+            } => VersionVectorIterInternal::Entries(EntriesIter::new(
+                *num_members,
+                version.base_version,
+                &version.entries,
+            )),
             VersionVector::Synced {
                 num_members,
                 version,
             } => {
-                VersionVectorIterInternal::Synced(std::iter::repeat_n(*version, num_members.get()))
+                VersionVectorIterInternal::Synced(core::iter::repeat_n(*version, num_members.get()))
             }
         };
         VersionVectorIter(internal)
@@ -496,9 +746,10 @@ impl Iterator for VersionVectorIter<'_> {
 }
 
 enum VersionVectorIterInternal<'a> {
-    Full(std::iter::Copied<std::slice::Iter<'a, u64>>),
+    Full(core::iter::Copied<core::slice::Iter<'a, u64>>),
     Override(OverrideIter),
-    Synced(std::iter::RepeatN<u64>),
+    Entries(EntriesIter<'a>),
+    Synced(core::iter::RepeatN<u64>),
 }
 impl Iterator for VersionVectorIterInternal<'_> {
     type Item = u64;
@@ -507,6 +758,7 @@ impl Iterator for VersionVectorIterInternal<'_> {
         match self {
             Self::Full(iter) => iter.next(),
             Self::Override(iter) => iter.next(),
+            Self::Entries(iter) => iter.next(),
             Self::Synced(iter) => iter.next(),
         }
     }
@@ -606,6 +858,172 @@ fn hb_compare_override_synced(o: &OverrideVersion, synced_version: u64) -> Happe
     }
 }
 
+/// Compare two equally long sequences of member versions position by position.
+fn hb_compare_entries(
+    left: impl Iterator<Item = u64>,
+    right: impl Iterator<Item = u64>,
+) -> HappenedBeforeOrdering {
+    let mut orderings = EncounteredOrderings::none();
+    for (left, right) in left.zip(right) {
+        orderings.update(left.cmp(&right));
+        if orderings.has_less_and_greater() {
+            // We can stop checking early in this case.
+            return HappenedBeforeOrdering::Concurrent;
+        }
+    }
+    orderings.to_hb_assume_loop_check()
+}
+
+/// A base version plus the `(position, version)` entries that differ from it, in position order.
+type BaseAndEntries<'a> = (u64, Cow<'a, [(usize, u64)]>);
+
+/// Compare two compact vectors given as a base version plus differing entries.
+///
+/// Only positions with an entry on either side are visited, plus one comparison of the base
+/// versions if any position has no entry on both sides.
+fn hb_compare_base_and_entries(
+    num_members: NonZeroUsize,
+    (left_base, left_entries): BaseAndEntries<'_>,
+    (right_base, right_entries): BaseAndEntries<'_>,
+) -> HappenedBeforeOrdering {
+    let mut orderings = EncounteredOrderings::none();
+    let mut visited_positions = 0;
+    for entry in left_entries
+        .iter()
+        .merge_join_by(right_entries.iter(), |(left, _), (right, _)| {
+            left.cmp(right)
+        })
+    {
+        let ord = match entry {
+            EitherOrBoth::Both((_, left), (_, right)) => left.cmp(right),
+            EitherOrBoth::Left((_, left)) => left.cmp(&right_base),
+            EitherOrBoth::Right((_, right)) => left_base.cmp(right),
+        };
+        orderings.update(ord);
+        if orderings.has_less_and_greater() {
+            // We can stop checking early in this case.
+            return HappenedBeforeOrdering::Concurrent;
+        }
+        visited_positions += 1;
+    }
+    if visited_positions < num_members.get() {
+        orderings.update(left_base.cmp(&right_base));
+        if orderings.has_less_and_greater() {
+            return HappenedBeforeOrdering::Concurrent;
+        }
+    }
+    orderings.to_hb_assume_loop_check()
+}
+
+/// Write the run-length form of a base version plus differing entries, e.g. `〈0-1:4, 2:5, 3-7:4〉`.
+fn fmt_base_and_entries(
+    f: &mut fmt::Formatter<'_>,
+    num_members: NonZeroUsize,
+    base_version: u64,
+    entries: &[(usize, u64)],
+) -> fmt::Result {
+    let mut parts = Vec::with_capacity(2 * entries.len() + 1);
+    let mut next_position = 0;
+    for (position, version) in entries {
+        if next_position < *position {
+            parts.push(format!(
+                "{}-{}:{}",
+                next_position,
+                position - 1,
+                base_version
+            ));
+        }
+        parts.push(format!("{position}:{version}"));
+        next_position = position + 1;
+    }
+    if next_position < num_members.get() {
+        parts.push(format!(
+            "{}-{}:{}",
+            next_position,
+            num_members.get() - 1,
+            base_version
+        ));
+    }
+    write!(f, "〈{}〉", parts.join(", "))
+}
+
+/// Build the most compact representation for a base version plus differing entries.
+///
+/// `entries` must be in position order, and none may hold `base_version` itself. When the
+/// entries cover too many members for a compact form, the vector is expanded first.
+pub(super) fn compact_entries(
+    num_members: NonZeroUsize,
+    base_version: u64,
+    entries: Vec<(usize, u64)>,
+) -> VersionVector {
+    if entries.is_empty() {
+        return VersionVector::Synced {
+            num_members,
+            version: base_version,
+        };
+    }
+
+    if SparseVersionVector::is_compact_for(num_members, entries.len()) {
+        if entries.iter().all(|(_, version)| *version > base_version) {
+            if let [(override_position, override_version)] = *entries {
+                return VersionVector::Override {
+                    num_members,
+                    version: OverrideVersion::new(
+                        base_version,
+                        override_position,
+                        override_version,
+                    ),
+                };
+            }
+            if entries.len() <= MultiOverrideVersion::MAX_OVERRIDES {
+                return VersionVector::MultiOverride {
+                    num_members,
+                    version: MultiOverrideVersion {
+                        group_version: base_version,
+                        overrides: entries.into_boxed_slice(),
+                    },
+                };
+            }
+        }
+        return VersionVector::Sparse {
+            num_members,
+            version: SparseVersionVector {
+                base_version,
+                entries: entries.into_boxed_slice(),
+            },
+        };
+    }
+
+    let mut versions = vec![base_version; num_members.get()];
+    for (position, version) in entries {
+        versions[position] = version;
+    }
+    VersionVector::from_versions(versions)
+}
+
+/// Set `position` to `version` in a base version plus differing entries.
+fn compact_entries_with_version_at(
+    num_members: NonZeroUsize,
+    base_version: u64,
+    entries: &[(usize, u64)],
+    position: usize,
+    version: u64,
+) -> VersionVector {
+    let mut entries = entries.to_vec();
+    match (
+        entries.binary_search_by_key(&position, |(entry_position, _)| *entry_position),
+        version == base_version,
+    ) {
+        (Ok(index), true) => {
+            entries.remove(index);
+        }
+        (Ok(index), false) => entries[index].1 = version,
+        (Err(_), true) => (),
+        (Err(index), false) => entries.insert(index, (position, version)),
+    }
+    compact_entries(num_members, base_version, entries)
+}
+
 /// Panic when two version vectors cannot describe the same member set.
 fn assert_same_member_count(left: &VersionVector, right: &VersionVector) {
     assert_eq!(
@@ -617,8 +1035,9 @@ fn assert_same_member_count(left: &VersionVector, right: &VersionVector) {
 
 /// Apply a single-position write to a synced vector.
 ///
-/// This keeps the synced or override representation when possible and expands
-/// only when the selected member moves behind the common group version.
+/// This keeps the synced or override representation when possible and switches
+/// to another representation only when the selected member moves behind the
+/// common group version.
 fn synced_with_version_at(
     num_members: NonZeroUsize,
     group_version: u64,
@@ -639,15 +1058,13 @@ fn synced_with_version_at(
         };
     }
 
-    let mut versions = vec![group_version; num_members.get()];
-    versions[position] = version;
-    VersionVector::Full(PureVersionVector::from(versions))
+    compact_entries(num_members, group_version, vec![(position, version)])
 }
 
 /// Build the compact representation for a common group version plus one exception.
 ///
 /// The exception can be represented as `Override` only when it is ahead of the
-/// group version. If it falls behind, the vector must be expanded to `Full`.
+/// group version. If it falls behind, another representation must be used.
 fn compact_group_override(
     num_members: NonZeroUsize,
     override_position: usize,
@@ -668,9 +1085,11 @@ fn compact_group_override(
         };
     }
 
-    let mut versions = vec![group_version; num_members.get()];
-    versions[override_position] = override_version;
-    VersionVector::Full(PureVersionVector::from(versions))
+    compact_entries(
+        num_members,
+        group_version,
+        vec![(override_position, override_version)],
+    )
 }
 
 /// Expand two vectors just long enough to apply one pointwise operation.
@@ -763,6 +1182,7 @@ impl fmt::Display for PureVersionVector {
         write!(f, "〈{}〉", self.0.iter().join(", "))
     }
 }
+impl Sealed for PureVersionVector {}
 impl HappenedBeforeOrd for PureVersionVector {
     fn hb_cmp(&self, other: &Self) -> HappenedBeforeOrdering {
         self.assert_valid();
@@ -977,7 +1397,7 @@ impl OverrideVersion {
 
         let mut versions = self.to_vector(num_members);
         versions.0[position] = version;
-        VersionVector::Full(versions)
+        VersionVector::from_versions(versions.0.into_vec())
     }
 
     #[must_use]
@@ -991,6 +1411,7 @@ impl OverrideVersion {
         debug_assert!(self.is_valid(), "Invalid override version: {self:?}");
     }
 }
+impl Sealed for OverrideVersion {}
 impl HappenedBeforeOrd for OverrideVersion {
     fn hb_cmp(&self, other: &Self) -> HappenedBeforeOrdering {
         self.assert_valid();
@@ -1042,6 +1463,335 @@ impl fmt::Display for OverrideVersion {
     }
 }
 
+/// A representation of a [[`VersionVector`]] for when the system is mostly synced up,
+/// but a few members are posting new versions.
+///
+/// This keeps groups with several concurrently active writers compact, where a single
+/// [[`OverrideVersion`]] would force a fall back to the full vector.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MultiOverrideVersion {
+    /// Everyone has this version, except the members listed in [[`overrides`]].
+    group_version: u64,
+    /// `(position, version)` pairs of the members with newer versions, in position order.
+    ///
+    /// Every version must be > [[`group_version`]]!
+    overrides: Box<[(usize, u64)]>,
+}
+impl MultiOverrideVersion {
+    /// The most overrides a vector may carry before it is stored as `Full` instead.
+    pub const MAX_OVERRIDES: usize = 4;
+
+    /// # Panics
+    ///
+    /// Panics if there are fewer than 2 or more than [`Self::MAX_OVERRIDES`] overrides, if the
+    /// override positions are not strictly increasing, or if any override version is not greater
+    /// than `group_version`.
+    #[must_use]
+    pub fn new(group_version: u64, overrides: impl IntoIterator<Item = (usize, u64)>) -> Self {
+        Self::new_opt(group_version, overrides).expect("Invalid multi-override version")
+    }
+
+    /// Returns `None` if the combination of `group_version` and `overrides` is not legal.
+    #[must_use]
+    pub fn new_opt(
+        group_version: u64,
+        overrides: impl IntoIterator<Item = (usize, u64)>,
+    ) -> Option<Self> {
+        let version = Self {
+            group_version,
+            overrides: overrides.into_iter().collect(),
+        };
+        option_when!(version.is_valid(), version)
+    }
+
+    /// Recognise explicit member versions that can be represented as a few higher overrides.
+    ///
+    /// The group version is the lowest member version; every member above it becomes an
+    /// override, as long as the result stays smaller than the full vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `versions` is empty.
+    fn try_from_versions(versions: &[u64]) -> Option<Self> {
+        let group_version = versions.iter().copied().min().expect("non-empty versions");
+        let mut overrides = Vec::with_capacity(Self::MAX_OVERRIDES);
+        for (position, version) in versions.iter().copied().enumerate() {
+            if version != group_version {
+                if overrides.len() == Self::MAX_OVERRIDES {
+                    return None;
+                }
+                overrides.push((position, version));
+            }
+        }
+        let num_members = NonZeroUsize::new(versions.len()).expect("non-empty versions");
+        option_when!(
+            overrides.len() > 1 && Self::is_compact_for(num_members, overrides.len()),
+            Self {
+                group_version,
+                overrides: overrides.into_boxed_slice(),
+            }
+        )
+    }
+
+    /// Everyone has this version, except the members listed in [[`overrides`]].
+    #[must_use]
+    pub const fn group_version(&self) -> u64 {
+        self.group_version
+    }
+
+    /// `(position, version)` pairs of the members with newer versions, in position order.
+    #[must_use]
+    pub fn overrides(&self) -> &[(usize, u64)] {
+        &self.overrides
+    }
+
+    /// The version at `position`, without checking the member range.
+    #[must_use]
+    pub fn version_at(&self, position: usize) -> u64 {
+        self.override_index(position)
+            .map_or(self.group_version, |index| self.overrides[index].1)
+    }
+
+    #[must_use]
+    pub const fn max_version(&self) -> u64 {
+        // Same manual, but const, max as in PureVersionVector.
+        let mut max = self.group_version;
+        let mut i = 0;
+        while i < self.overrides.len() {
+            let v = self.overrides[i].1;
+            if max < v {
+                max = v;
+            }
+            i += 1;
+        }
+        max
+    }
+
+    #[must_use]
+    pub fn to_vector(&self, num_members: NonZeroUsize) -> PureVersionVector {
+        let mut entries = vec![self.group_version; num_members.get()];
+        for (position, version) in &self.overrides {
+            entries[*position] = *version;
+        }
+        PureVersionVector::from(entries)
+    }
+
+    /// Whether `override_count` overrides take less space than a full vector of `num_members`.
+    const fn is_compact_for(num_members: NonZeroUsize, override_count: usize) -> bool {
+        override_count <= Self::MAX_OVERRIDES && 2 * override_count + 1 < num_members.get()
+    }
+
+    fn override_index(&self, position: usize) -> Result<usize, usize> {
+        self.overrides
+            .binary_search_by_key(&position, |(override_position, _)| *override_position)
+    }
+
+    fn with_version_at(
+        &self,
+        num_members: NonZeroUsize,
+        position: usize,
+        version: u64,
+    ) -> VersionVector {
+        compact_entries_with_version_at(
+            num_members,
+            self.group_version,
+            &self.overrides,
+            position,
+            version,
+        )
+    }
+
+    #[must_use]
+    fn is_valid(&self) -> bool {
+        (2..=Self::MAX_OVERRIDES).contains(&self.overrides.len())
+            && self
+                .overrides
+                .iter()
+                .all(|(_, version)| *version > self.group_version)
+            && self
+                .overrides
+                .iter()
+                .tuple_windows()
+                .all(|((left, _), (right, _))| left < right)
+    }
+}
+impl fmt::Display for MultiOverrideVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let overrides = self
+            .overrides
+            .iter()
+            .map(|(position, version)| format!("{position}:{version}"))
+            .join(", ");
+        write!(
+            f,
+            "〈{}..., {}, {}...〉",
+            self.group_version, overrides, self.group_version,
+        )
+    }
+}
+
+/// A representation of a [[`VersionVector`]] for very large groups in which most members never
+/// write.
+///
+/// Only members whose version differs from a common base version are stored, so the size grows
+/// with the number of members that ever wrote instead of the group size. Unlike
+/// [[`MultiOverrideVersion`]], entries may also be behind the base version.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SparseVersionVector {
+    /// Every member not listed in [[`entries`]] has this version.
+    base_version: u64,
+    /// `(position, version)` pairs of the members whose version differs from
+    /// [[`base_version`]], in position order.
+    entries: Box<[(usize, u64)]>,
+}
+impl SparseVersionVector {
+    /// # Panics
+    ///
+    /// Panics if `entries` is empty, if the entry positions are not strictly increasing, or if
+    /// any entry version equals `base_version`.
+    #[must_use]
+    pub fn new(base_version: u64, entries: impl IntoIterator<Item = (usize, u64)>) -> Self {
+        Self::new_opt(base_version, entries).expect("Invalid sparse version vector")
+    }
+
+    /// Returns `None` if the combination of `base_version` and `entries` is not legal.
+    #[must_use]
+    pub fn new_opt(
+        base_version: u64,
+        entries: impl IntoIterator<Item = (usize, u64)>,
+    ) -> Option<Self> {
+        let version = Self {
+            base_version,
+            entries: entries.into_iter().collect(),
+        };
+        option_when!(version.is_valid(), version)
+    }
+
+    /// Recognise explicit member versions in which a strict majority shares one version.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `versions` is empty.
+    fn try_from_versions(versions: &[u64]) -> Option<Self> {
+        let num_members = NonZeroUsize::new(versions.len()).expect("non-empty versions");
+        let mut sorted = versions.to_vec();
+        sorted.sort_unstable();
+        let (base_version, base_count) = sorted
+            .chunk_by(|left, right| left == right)
+            .map(|run| (run[0], run.len()))
+            .max_by_key(|(_, count)| *count)
+            .expect("non-empty versions");
+        if !Self::is_compact_for(num_members, versions.len() - base_count) {
+            return None;
+        }
+        let entries = versions
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, version)| *version != base_version)
+            .collect();
+        Some(Self {
+            base_version,
+            entries,
+        })
+    }
+
+    /// Every member not listed in [[`entries`]] has this version.
+    #[must_use]
+    pub const fn base_version(&self) -> u64 {
+        self.base_version
+    }
+
+    /// `(position, version)` pairs of the members whose version differs from
+    /// [[`base_version`]], in position order.
+    #[must_use]
+    pub fn entries(&self) -> &[(usize, u64)] {
+        &self.entries
+    }
+
+    /// The version at `position`, without checking the member range.
+    #[must_use]
+    pub fn version_at(&self, position: usize) -> u64 {
+        self.entries
+            .binary_search_by_key(&position, |(entry_position, _)| *entry_position)
+            .map_or(self.base_version, |index| self.entries[index].1)
+    }
+
+    #[must_use]
+    pub const fn max_version(&self) -> u64 {
+        // Same manual, but const, max as in PureVersionVector.
+        let mut max = self.base_version;
+        let mut i = 0;
+        while i < self.entries.len() {
+            let v = self.entries[i].1;
+            if max < v {
+                max = v;
+            }
+            i += 1;
+        }
+        max
+    }
+
+    #[must_use]
+    pub fn to_vector(&self, num_members: NonZeroUsize) -> PureVersionVector {
+        let mut entries = vec![self.base_version; num_members.get()];
+        for (position, version) in &self.entries {
+            entries[*position] = *version;
+        }
+        PureVersionVector::from(entries)
+    }
+
+    /// Whether `entry_count` entries take less space than a full vector of `num_members`.
+    ///
+    /// This also guarantees that the base version is the one a strict majority shares.
+    const fn is_compact_for(num_members: NonZeroUsize, entry_count: usize) -> bool {
+        2 * entry_count + 1 < num_members.get()
+    }
+
+    fn with_version_at(
+        &self,
+        num_members: NonZeroUsize,
+        position: usize,
+        version: u64,
+    ) -> VersionVector {
+        compact_entries_with_version_at(
+            num_members,
+            self.base_version,
+            &self.entries,
+            position,
+            version,
+        )
+    }
+
+    #[must_use]
+    fn is_valid(&self) -> bool {
+        !self.entries.is_empty()
+            && self
+                .entries
+                .iter()
+                .all(|(_, version)| *version != self.base_version)
+            && self
+                .entries
+                .iter()
+                .tuple_windows()
+                .all(|((left, _), (right, _))| left < right)
+    }
+}
+impl fmt::Display for SparseVersionVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = self
+            .entries
+            .iter()
+            .map(|(position, version)| format!("{position}:{version}"))
+            .join(", ");
+        write!(
+            f,
+            "〈{}..., {}, {}...〉",
+            self.base_version, entries, self.base_version,
+        )
+    }
+}
+
 struct OverrideIter {
     num_members: NonZeroUsize,
     underlying: OverrideVersion,
@@ -1073,3 +1823,41 @@ impl Iterator for OverrideIter {
         }
     }
 }
+
+struct EntriesIter<'a> {
+    num_members: NonZeroUsize,
+    base_version: u64,
+    entries: &'a [(usize, u64)],
+    next_position: usize,
+    next_entry: usize,
+}
+impl<'a> EntriesIter<'a> {
+    fn new(num_members: NonZeroUsize, base_version: u64, entries: &'a [(usize, u64)]) -> Self {
+        Self {
+            num_members,
+            base_version,
+            entries,
+            next_position: 0usize,
+            next_entry: 0usize,
+        }
+    }
+}
+impl Iterator for EntriesIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_position < self.num_members.get() {
+            let res = match self.entries.get(self.next_entry) {
+                Some((position, version)) if *position == self.next_position => {
+                    self.next_entry += 1;
+                    *version
+                }
+                _ => self.base_version,
+            };
+            self.next_position += 1;
+            Some(res)
+        } else {
+            None
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use super::VersionVector;
 use crate::{
     member::{GroupMembership, Identifier},
+    sealed::Sealed,
     versions::{HappenedBeforeOrd, HappenedBeforeOrdering},
 };
 use core::fmt;
@@ -78,6 +79,15 @@ where
         GroupVersionVectorLineByLineDisplay(self)
     }
 
+    /// Whether `other` is over the same members, in the same order.
+    #[must_use]
+    pub fn has_same_members<O>(&self, other: &GroupVersionVector<O>) -> bool
+    where
+        O: GroupMembership,
+    {
+        self.group_members.iter().eq(other.group_members.iter())
+    }
+
     /// Iterate over group members and their associated versions.
     pub fn iter(&self) -> impl Iterator<Item = (&Identifier, u64)> {
         self.group_members.iter().zip(self.versions.iter())
@@ -121,7 +131,18 @@ where
 
         let mut result: BTreeMap<&Identifier, Vec<u64>> = BTreeMap::new();
         match (&self.versions, &other.versions) {
-            (VersionVector::Full(_), _) | (_, VersionVector::Full(_)) => {
+            (
+                VersionVector::Full(_)
+                | VersionVector::MultiOverride { .. }
+                | VersionVector::Sparse { .. },
+                _,
+            )
+            | (
+                _,
+                VersionVector::Full(_)
+                | VersionVector::MultiOverride { .. }
+                | VersionVector::Sparse { .. },
+            ) => {
                 for (self_version, (other_id, other_version)) in
                     self.versions.iter().zip(other.iter())
                 {
@@ -300,6 +321,33 @@ where
     }
 }
 
+impl<G, O> PartialEq<GroupVersionVector<O>> for GroupVersionVector<G>
+where
+    G: GroupMembership,
+    O: GroupMembership,
+{
+    fn eq(&self, other: &GroupVersionVector<O>) -> bool {
+        self.has_same_members(other) && self.versions == other.versions
+    }
+}
+
+impl<G> Sealed for GroupVersionVector<G> {}
+/// Vectors over different member lists are [`Incomparable`](HappenedBeforeOrdering::Incomparable),
+/// even if the groups have the same size, e.g. after one member left and another joined.
+impl<G, O> HappenedBeforeOrd<GroupVersionVector<O>> for GroupVersionVector<G>
+where
+    G: GroupMembership,
+    O: GroupMembership,
+{
+    fn hb_cmp(&self, other: &GroupVersionVector<O>) -> HappenedBeforeOrdering {
+        if self.has_same_members(other) {
+            self.versions.hb_cmp(&other.versions)
+        } else {
+            HappenedBeforeOrdering::Incomparable
+        }
+    }
+}
+
 impl<G> fmt::Display for GroupVersionVector<G>
 where
     G: GroupMembership,
@@ -309,7 +357,7 @@ where
             .group_members
             .iter()
             .zip(self.versions.iter())
-            .map(|(id, version)| format!("{id} -> {version}"))
+            .map(|(id, version)| format!("{} -> {version}", id.abbreviated()))
             .join(", ");
         write!(f, "〈{entries}〉")
     }
@@ -330,7 +378,7 @@ where
             .group_members
             .iter()
             .zip(self.0.versions.iter())
-            .map(|(id, version)| format!(" {id} -> {version}"))
+            .map(|(id, version)| format!(" {} -> {version}", id.abbreviated()))
             .join(",\n");
         write!(f, "〈\n{entries}\n〉")
     }
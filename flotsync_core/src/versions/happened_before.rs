@@ -1,4 +1,5 @@
-use std::cmp;
+use crate::sealed::Sealed;
+use core::cmp;
 
 /// Establishes the "happened-before" order.
 ///
@@ -6,6 +7,7 @@ use std::cmp;
 ///
 /// Otherwise it just has better naming to clarify the implication.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum HappenedBeforeOrdering {
     /// `a` happened strictly before `b`.
     Before,
@@ -58,7 +60,9 @@ impl From<HappenedBeforeOrdering> for Option<cmp::Ordering> {
 /// Trait for types that can establish a [happened-before order](HappenedBeforeOrdering).
 ///
 /// This is a form of partial order, so the same rules as [[`PartialOrd`]] apply, but an additional variants of incomparable is "concurrent".
-pub trait HappenedBeforeOrd<Rhs = Self>: PartialEq<Rhs>
+///
+/// This trait is sealed. Only the version types of this crate implement it.
+pub trait HappenedBeforeOrd<Rhs = Self>: PartialEq<Rhs> + Sealed
 where
     Rhs: ?Sized,
 {
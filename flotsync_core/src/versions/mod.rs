@@ -1,13 +1,31 @@
 //! The [[`VersionVector`]] is a variant of a [Version Vector](https://en.wikipedia.org/wiki/Version_vector).
 //! It has entries for the local version at each group member, which may however be collapsed to save space when they are all the same.
+//!
+//! Everything here except [[`GroupVersionVector`]] is available without `std`.
+
+// `flotsync_utils` needs `std`, so provide the same macro for `no_std` builds.
+#[cfg(not(feature = "std"))]
+macro_rules! option_when {
+    ($cond:expr, $then:expr) => {
+        if $cond { Some($then) } else { None }
+    };
+}
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
 mod happened_before;
 use core::fmt;
 
 pub use happened_before::*;
+mod delta;
+pub use delta::*;
 mod flat_vector;
 pub use flat_vector::*;
+mod parse;
+pub use parse::*;
+#[cfg(feature = "std")]
 mod group_vector;
+#[cfg(feature = "std")]
 pub use group_vector::*;
 
 /// The id of a concrete update from a single node at `node_index` in the group member object.
@@ -37,7 +55,7 @@ impl UpdateId {
 }
 
 impl fmt::Display for UpdateId {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "v{}@{}", self.version, self.node_index)
     }
 }
@@ -47,11 +65,10 @@ mod tests {
     use crate::member::Identifier;
 
     use super::*;
-    use proptest::{prelude::*, strategy::Union};
+    use crate::test_support::*;
+    use proptest::prelude::*;
     use std::{collections::BTreeMap, num::NonZeroUsize};
 
-    const LARGE_VERSION: u64 = (u32::MAX as u64) + 1;
-
     #[test]
     fn flat_string_representations() {
         const FOUR_MEMBERS: NonZeroUsize = NonZeroUsize::new(4).unwrap();
@@ -187,6 +204,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_string_representations() {
+        const FOUR_MEMBERS: NonZeroUsize = NonZeroUsize::new(4).unwrap();
+
+        assert_eq!(
+            "〈0-3:12〉".parse::<VersionVector>(),
+            Ok(VersionVector::Synced {
+                num_members: FOUR_MEMBERS,
+                version: 12,
+            })
+        );
+        assert_eq!(
+            "〈0-1:12, 2:13, 3-3:12〉".parse::<VersionVector>(),
+            Ok(VersionVector::Override {
+                num_members: FOUR_MEMBERS,
+                version: OverrideVersion::with_next_version(12, 2),
+            })
+        );
+        let full = VersionVector::Full(PureVersionVector::from([12, 13, 12, 11]));
+        assert_eq!(
+            "〈12, 13, 12, 11〉".parse::<VersionVector>(),
+            Ok(full.clone())
+        );
+        assert_eq!("<12,13, 2-2:12, 11>".parse::<VersionVector>(), Ok(full));
+
+        assert_eq!(
+            "0-3:12".parse::<VersionVector>(),
+            Err(VersionVectorParseError::MissingBrackets)
+        );
+        assert_eq!(
+            "〈 〉".parse::<VersionVector>(),
+            Err(VersionVectorParseError::NoMembers)
+        );
+        assert_eq!(
+            "〈0-1:12, 3:13〉".parse::<VersionVector>(),
+            Err(VersionVectorParseError::UnexpectedPosition {
+                expected: 2,
+                actual: 3,
+            })
+        );
+        assert_eq!(
+            "〈0-1:12, 2-1:13〉".parse::<VersionVector>(),
+            Err(VersionVectorParseError::EmptyRange { first: 2, last: 1 })
+        );
+        assert_eq!(
+            "〈12, x〉".parse::<VersionVector>(),
+            Err(VersionVectorParseError::InvalidEntry {
+                entry: "x".to_string(),
+            })
+        );
+    }
+
     #[test]
     fn version_vector_missing_ranges_omit_members_that_are_not_behind() {
         let local = VersionVector::Full(PureVersionVector::from([5, 3, 1]));
@@ -418,6 +487,202 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn set_at_matches_with_version_at() {
+        use helpers::*;
+
+        for vector in [sync(4), over(4, (1, 6)), pure([6, 4, 5]), pure([4, 6, 5])] {
+            for position in 0..3 {
+                for version in 3..8 {
+                    let mut updated = vector.clone();
+                    updated.set_at(position, version);
+                    let expected = vector.with_version_at(position, version);
+                    assert_eq!(
+                        format!("{updated:?}"),
+                        format!("{expected:?}"),
+                        "setting {position} to {version} in {vector}"
+                    );
+                    assert_eq!(updated.get(position), Some(version));
+                }
+            }
+        }
+
+        let entries: Vec<_> = over(4, (1, 6)).entries().collect();
+        assert_eq!(entries, vec![(0, 4), (1, 6), (2, 4)]);
+        assert_eq!(over(4, (1, 6)).get(3), None);
+        assert_eq!(sync(4).get(3), None);
+        assert_eq!(pure([6, 4, 5]).get(3), None);
+    }
+
+    #[test]
+    fn from_entries_selects_the_most_compact_representation() {
+        assert!(matches!(
+            VersionVector::from_entries([3, 3, 3]),
+            VersionVector::Synced { version: 3, .. }
+        ));
+        assert!(matches!(
+            VersionVector::from_entries([3, 5, 3]),
+            VersionVector::Override { version, .. }
+                if version.group_version() == 3
+                    && version.override_position == 1
+                    && version.override_version() == 5
+        ));
+        assert!(matches!(
+            [3, 5, 4].into_iter().collect::<VersionVector>(),
+            VersionVector::Full(PureVersionVector(values)) if values.as_ref() == [3, 5, 4]
+        ));
+
+        let mut full = VersionVector::Full(PureVersionVector::from([4, 5, 5]));
+        full.increment_at(0);
+        assert!(matches!(full, VersionVector::Full(_)));
+        full.normalize();
+        assert!(matches!(full, VersionVector::Synced { version: 5, .. }));
+
+        let mut still_full = VersionVector::Full(PureVersionVector::from([3, 5, 4]));
+        still_full.normalize();
+        assert!(matches!(still_full, VersionVector::Full(_)));
+    }
+
+    #[test]
+    fn multi_override_tracks_a_few_concurrent_writers() {
+        const TWENTY_MEMBERS: NonZeroUsize = NonZeroUsize::new(20).unwrap();
+
+        let synced = VersionVector::Synced {
+            num_members: TWENTY_MEMBERS,
+            version: 4,
+        };
+        let one_writer = synced.succ_at(7);
+        assert!(matches!(one_writer, VersionVector::Override { .. }));
+
+        let two_writers = one_writer.succ_at(2);
+        assert!(matches!(
+            &two_writers,
+            VersionVector::MultiOverride { version, .. }
+                if version.group_version() == 4 && version.overrides() == [(2, 5), (7, 5)]
+        ));
+        assert_eq!(
+            two_writers.to_string(),
+            "〈0-1:4, 2:5, 3-6:4, 7:5, 8-19:4〉".to_string()
+        );
+        assert_eq!(
+            synced
+                .succ_at(7)
+                .least_upper_bound(&synced.succ_at(2).succ_at(2)),
+            two_writers.with_version_at(2, 6)
+        );
+
+        let four_writers = two_writers.succ_at(7).succ_at(11).succ_at(19);
+        assert!(matches!(
+            &four_writers,
+            VersionVector::MultiOverride { version, .. }
+                if version.overrides() == [(2, 5), (7, 6), (11, 5), (19, 5)]
+        ));
+        assert_eq!(four_writers.max_version(), 6);
+        assert_eq!(four_writers.get(7), Some(6));
+        assert_eq!(four_writers.get(8), Some(4));
+        assert_eq!(four_writers.get(20), None);
+        assert!(matches!(
+            four_writers.succ_at(0),
+            VersionVector::Sparse { .. }
+        ));
+        assert!(synced < four_writers && two_writers < four_writers);
+        assert_eq!(
+            four_writers.hb_cmp(&four_writers.with_version_at(3, 9).with_version_at(7, 5)),
+            HappenedBeforeOrdering::Concurrent
+        );
+
+        let mut demoted = four_writers.clone();
+        for position in [19, 11, 7] {
+            demoted.set_at(position, 4);
+        }
+        assert!(matches!(
+            demoted,
+            VersionVector::Override { version, .. }
+                if version.override_position == 2 && version.override_version() == 5
+        ));
+        let mut behind = four_writers.with_version_at(0, 3);
+        assert!(matches!(behind, VersionVector::Sparse { .. }));
+        behind.set_at(0, 4);
+        assert!(matches!(behind, VersionVector::MultiOverride { .. }));
+        assert_eq!(behind, four_writers);
+        assert!(matches!(
+            VersionVector::from_entries([4, 4, 4, 4, 5, 6]),
+            VersionVector::MultiOverride { .. }
+        ));
+        assert!(matches!(
+            VersionVector::from_entries([4, 4, 4, 5, 6]),
+            VersionVector::Full(_)
+        ));
+    }
+
+    #[test]
+    fn sparse_vectors_store_only_members_that_wrote() {
+        use helpers::*;
+        const MANY_MEMBERS: NonZeroUsize = NonZeroUsize::new(300).unwrap();
+
+        let initial = VersionVector::initial(MANY_MEMBERS);
+        let mut writers = initial.clone();
+        for position in [3, 40, 41, 150, 299] {
+            writers.increment_at(position);
+            writers.increment_at(position);
+        }
+        assert!(matches!(
+            &writers,
+            VersionVector::Sparse { version, .. }
+                if version.base_version() == 0
+                    && version.entries() == [(3, 2), (40, 2), (41, 2), (150, 2), (299, 2)]
+        ));
+        assert_eq!(writers.get(41), Some(2));
+        assert_eq!(writers.get(42), Some(0));
+        assert_eq!(writers.max_version(), 2);
+
+        let full = VersionVector::Full(PureVersionVector::from(writers.iter().collect::<Vec<_>>()));
+        assert_eq!(writers.hb_cmp(&full), EQUAL);
+        assert_eq!(writers.hb_cmp(&full.succ_at(7)), BEFORE);
+        assert_eq!(initial.hb_cmp(&writers), BEFORE);
+        assert_eq!(writers.hb_cmp(&initial.succ_at(150)), AFTER);
+        assert_eq!(
+            writers.hb_cmp(&writers.with_version_at(41, 1).succ_at(100)),
+            CONCURRENT
+        );
+
+        let one_behind = VersionVector::Synced {
+            num_members: MANY_MEMBERS,
+            version: 10,
+        }
+        .with_version_at(5, 2);
+        assert!(matches!(
+            &one_behind,
+            VersionVector::Sparse { version, .. }
+                if version.base_version() == 10 && version.entries() == [(5, 2)]
+        ));
+        assert_eq!(one_behind.least_upper_bound(&writers), one_behind);
+        assert!(matches!(
+            one_behind.with_version_at(5, 10),
+            VersionVector::Synced { version: 10, .. }
+        ));
+
+        let twelve_members = NonZeroUsize::new(12).unwrap();
+        let small = VersionVector::Synced {
+            num_members: twelve_members,
+            version: 7,
+        }
+        .with_version_at(2, 3)
+        .with_version_at(9, 8);
+        assert_eq!(
+            small.to_string(),
+            "〈0-1:7, 2:3, 3-8:7, 9:8, 10-11:7〉".to_string()
+        );
+        assert!(matches!(
+            small
+                .with_version_at(4, 1)
+                .with_version_at(6, 1)
+                .with_version_at(8, 1)
+                .with_version_at(10, 1),
+            VersionVector::Full(_)
+        ));
+    }
+
     #[test]
     fn least_upper_bound_and_greatest_lower_bound_use_pointwise_versions() {
         use helpers::*;
@@ -447,6 +712,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn diff_encode_lists_only_changed_members() {
+        use helpers::*;
+
+        let delta = over(4, (2, 8)).diff_encode(&sync(4));
+        assert_eq!(delta.changes(), [(2, 8)]);
+        assert_eq!(delta.to_string(), "Δ〈2:8〉");
+        assert_eq!(sync(4).apply_delta(&delta), over(4, (2, 8)));
+
+        let delta = pure([5, 3, 7]).diff_encode(&pure([5, 4, 6]));
+        assert_eq!(delta.changes(), [(1, 3), (2, 7)]);
+        assert_eq!(pure([5, 4, 6]).apply_delta(&delta), pure([5, 3, 7]));
+
+        let unchanged = sync(3).diff_encode(&sync(3));
+        assert!(unchanged.is_empty());
+        assert_eq!(unchanged.to_string(), "Δ〈〉");
+    }
+
+    #[test]
+    fn version_vector_delta_rejects_invalid_positions() {
+        let three_members = NonZeroUsize::new(3).unwrap();
+
+        assert!(VersionVectorDelta::new_opt(three_members, [(0, 1), (2, 4)]).is_some());
+        assert!(VersionVectorDelta::new_opt(three_members, []).is_some());
+        assert!(VersionVectorDelta::new_opt(three_members, [(2, 1), (1, 4)]).is_none());
+        assert!(VersionVectorDelta::new_opt(three_members, [(1, 1), (1, 4)]).is_none());
+        assert!(VersionVectorDelta::new_opt(three_members, [(3, 1)]).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "different member counts")]
+    fn apply_delta_panics_for_incompatible_member_counts() {
+        use helpers::*;
+
+        let delta = VersionVectorDelta::new(NonZeroUsize::new(2).unwrap(), [(0, 2)]);
+        let _ = sync(1).apply_delta(&delta);
+    }
+
     #[test]
     fn with_update_applied_sets_the_producer_version() {
         use helpers::*;
@@ -600,96 +903,6 @@ mod tests {
         );
     }
 
-    fn full_vector_strategy() -> impl Strategy<Value = VersionVector> {
-        // Don't make ridiculously large vectors, since they take a lot of memory.
-        prop::collection::vec(any::<u64>(), 1..100)
-            .prop_map(|entries| VersionVector::Full(PureVersionVector::from(entries)))
-    }
-
-    fn override_vector_strategy() -> impl Strategy<Value = VersionVector> {
-        (any::<NonZeroUsize>(), 0..LARGE_VERSION).prop_flat_map(|(num_members, group_version)| {
-            (0..num_members.get(), (group_version + 1)..u64::MAX).prop_map(
-                move |(override_position, override_version)| VersionVector::Override {
-                    num_members,
-                    version: OverrideVersion::new(
-                        group_version,
-                        override_position,
-                        override_version,
-                    ),
-                },
-            )
-        })
-    }
-
-    fn version_vector_strategy() -> impl Strategy<Value = VersionVector> {
-        prop_oneof![
-            full_vector_strategy(),
-            override_vector_strategy(),
-            (any::<NonZeroUsize>(), any::<u64>()).prop_map(|(num_members, version)| {
-                VersionVector::Synced {
-                    num_members,
-                    version,
-                }
-            }),
-        ]
-    }
-
-    fn fixed_size_full_vector_strategy(
-        num_members: NonZeroUsize,
-    ) -> impl Strategy<Value = VersionVector> {
-        prop::collection::vec(any::<u64>(), num_members.get())
-            .prop_map(|entries| VersionVector::Full(PureVersionVector(entries.into_boxed_slice())))
-    }
-
-    fn fixed_size_override_vector_strategy(
-        num_members: NonZeroUsize,
-    ) -> impl Strategy<Value = VersionVector> {
-        (0..LARGE_VERSION).prop_flat_map(move |group_version| {
-            (0..num_members.get(), (group_version + 1)..u64::MAX).prop_map(
-                move |(override_position, override_version)| VersionVector::Override {
-                    num_members,
-                    version: OverrideVersion::new(
-                        group_version,
-                        override_position,
-                        override_version,
-                    ),
-                },
-            )
-        })
-    }
-
-    fn fixed_size_synced_strategy(
-        num_members: NonZeroUsize,
-    ) -> impl Strategy<Value = VersionVector> {
-        any::<u64>().prop_map(move |version| VersionVector::Synced {
-            num_members,
-            version,
-        })
-    }
-
-    fn fixed_size_version_vector_strategy(
-        num_members: NonZeroUsize,
-    ) -> BoxedStrategy<VersionVector> {
-        let mut strategies = vec![
-            fixed_size_full_vector_strategy(num_members).boxed(),
-            fixed_size_synced_strategy(num_members).boxed(),
-        ];
-        if num_members.get() > 1 {
-            strategies.push(fixed_size_override_vector_strategy(num_members).boxed());
-        }
-        Union::new(strategies).boxed()
-    }
-
-    fn version_vector_size_strategy() -> impl Strategy<Value = NonZeroUsize> {
-        (1usize..100usize).prop_map(|u| NonZeroUsize::new(u).unwrap())
-    }
-
-    prop_compose! {
-        fn equal_size_version_vector_strategy()(l in version_vector_size_strategy())(v1 in fixed_size_version_vector_strategy(l), v2 in fixed_size_version_vector_strategy(l), v3 in fixed_size_version_vector_strategy(l)) -> (VersionVector, VersionVector, VersionVector) {
-            (v1, v2, v3)
-        }
-    }
-
     proptest! {
         #[test]
         fn version_vector_invariants(v1 in version_vector_strategy(), v2 in version_vector_strategy(), v3 in version_vector_strategy()) {
@@ -700,6 +913,24 @@ mod tests {
         fn version_vector_equal_size_invariants((v1, v2, v3) in equal_size_version_vector_strategy()) {
             version_vector_invariants_impl(&v1, &v2, &v3);
         }
+
+        #[test]
+        fn version_vector_deltas_rebuild_the_current_vector((previous, current, _) in equal_size_version_vector_strategy()) {
+            let delta = current.diff_encode(&previous);
+            prop_assert!(delta.changes().len() <= current.num_members().get());
+            let rebuilt = previous.apply_delta(&delta);
+            prop_assert_eq!(
+                format!("{rebuilt:?}"),
+                format!("{:?}", VersionVector::from_entries(current.iter()))
+            );
+            prop_assert_eq!(rebuilt, current);
+        }
+
+        #[test]
+        fn version_vector_display_parses_back(v in version_vector_strategy()) {
+            let parsed: VersionVector = v.to_string().parse().expect("displayed vectors parse");
+            prop_assert_eq!(parsed, v);
+        }
     }
     fn version_vector_invariants_impl(v1: &VersionVector, v2: &VersionVector, v3: &VersionVector) {
         single_version_vector_invariants_impl(v1);
@@ -727,6 +958,21 @@ mod tests {
         }
         assert_eq!(v.hb_cmp(v), HappenedBeforeOrdering::Equal);
 
+        assert_eq!(v.get(v.num_members().get()), None);
+        if v.num_members().get() < 100 {
+            for (position, version) in v.entries() {
+                assert_eq!(v.get(position), Some(version));
+            }
+
+            let mut normalized = v.clone();
+            normalized.normalize();
+            assert_eq!(&normalized, v);
+            assert_eq!(
+                format!("{normalized:?}"),
+                format!("{:?}", VersionVector::from_entries(v.iter()))
+            );
+        }
+
         // Ensure that we don't overflow, and also we don't run out of memory if we need to expand to a full vector.
         if v.max_version() < u64::MAX - 2 && v.num_members().get() < 100 {
             // Increments
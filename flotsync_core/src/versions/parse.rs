@@ -0,0 +1,147 @@
+//! Parsing of the [[`VersionVector`]] string notation produced by its `Display` impl.
+
+use super::{VersionVector, flat_vector::compact_entries};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{error, fmt, num::NonZeroUsize, str::FromStr};
+
+/// Reasons why a string is not a valid [[`VersionVector`]].
+///
+/// `snafu` is only available with `std`, so this implements its traits by hand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VersionVectorParseError {
+    /// The input is not enclosed in `〈…〉` or `<…>`.
+    MissingBrackets,
+    /// The vector does not list any member.
+    NoMembers,
+    /// One comma-separated entry is neither `version`, `position:version`, nor
+    /// `first-last:version`.
+    InvalidEntry { entry: String },
+    /// An entry does not start at the position right after the previous entry.
+    UnexpectedPosition { expected: usize, actual: usize },
+    /// A `first-last:version` entry ends before it starts.
+    EmptyRange { first: usize, last: usize },
+}
+
+impl fmt::Display for VersionVectorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingBrackets => {
+                write!(f, "version vectors must be enclosed in '〈…〉' or '<…>'")
+            }
+            Self::NoMembers => write!(f, "version vectors must list at least one member"),
+            Self::InvalidEntry { entry } => write!(
+                f,
+                "invalid version vector entry '{entry}', expected 'version', 'position:version', \
+                 or 'first-last:version'"
+            ),
+            Self::UnexpectedPosition { expected, actual } => write!(
+                f,
+                "version vector entry starts at position {actual}, but the next member is at \
+                 position {expected}"
+            ),
+            Self::EmptyRange { first, last } => {
+                write!(f, "version vector range {first}-{last} is empty")
+            }
+        }
+    }
+}
+
+impl error::Error for VersionVectorParseError {}
+
+impl FromStr for VersionVector {
+    type Err = VersionVectorParseError;
+
+    /// Parse the notation written by `Display`, e.g. `〈0-1:12, 2:13, 3-3:12〉` or the full list
+    /// form `〈12, 13, 12, 11〉`.
+    ///
+    /// Entries list consecutive members from position `0` and may mix bare versions with
+    /// explicit positions and ranges. ASCII `<…>` brackets are accepted as well, for places
+    /// where `〈…〉` is awkward to type. The result uses the most compact representation for
+    /// the parsed member versions, without expanding ranges of the most common version.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = s
+            .trim()
+            .strip_prefix('〈')
+            .and_then(|rest| rest.strip_suffix('〉'))
+            .or_else(|| {
+                s.trim()
+                    .strip_prefix('<')
+                    .and_then(|rest| rest.strip_suffix('>'))
+            })
+            .ok_or(VersionVectorParseError::MissingBrackets)?;
+        if inner.trim().is_empty() {
+            return Err(VersionVectorParseError::NoMembers);
+        }
+        // Inclusive `(first, last, version)` runs of consecutive members.
+        let mut runs = Vec::new();
+        let mut next_position = 0usize;
+        for entry in inner.split(',').map(str::trim) {
+            let (first, last, version) = parse_entry(entry)?;
+            let first = first.unwrap_or(next_position);
+            let last = last.unwrap_or(first);
+            if first != next_position {
+                return Err(VersionVectorParseError::UnexpectedPosition {
+                    expected: next_position,
+                    actual: first,
+                });
+            }
+            if last < first {
+                return Err(VersionVectorParseError::EmptyRange { first, last });
+            }
+            next_position =
+                last.checked_add(1)
+                    .ok_or_else(|| VersionVectorParseError::InvalidEntry {
+                        entry: String::from(entry),
+                    })?;
+            runs.push((first, last, version));
+        }
+        let num_members = NonZeroUsize::new(next_position).expect("at least one entry was parsed");
+
+        let mut member_counts = BTreeMap::new();
+        for (first, last, version) in &runs {
+            *member_counts.entry(*version).or_insert(0usize) += last - first + 1;
+        }
+        let base_version = member_counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(version, _)| version)
+            .expect("at least one entry was parsed");
+        let entries = runs
+            .into_iter()
+            .filter(|(_, _, version)| *version != base_version)
+            .flat_map(|(first, last, version)| {
+                (first..=last).map(move |position| (position, version))
+            })
+            .collect();
+        Ok(compact_entries(num_members, base_version, entries))
+    }
+}
+
+/// The optional first and last position, and the version of one entry.
+type ParsedEntry = (Option<usize>, Option<usize>, u64);
+
+fn parse_entry(entry: &str) -> Result<ParsedEntry, VersionVectorParseError> {
+    let invalid = || VersionVectorParseError::InvalidEntry {
+        entry: String::from(entry),
+    };
+    let parsed = match entry.split_once(':') {
+        None => (None, None, entry.parse().map_err(|_| invalid())?),
+        Some((positions, version)) => {
+            let version = version.trim().parse().map_err(|_| invalid())?;
+            match positions.split_once('-') {
+                None => (
+                    Some(positions.trim().parse().map_err(|_| invalid())?),
+                    None,
+                    version,
+                ),
+                Some((first, last)) => (
+                    Some(first.trim().parse().map_err(|_| invalid())?),
+                    Some(last.trim().parse().map_err(|_| invalid())?),
+                    version,
+                ),
+            }
+        }
+    };
+    Ok(parsed)
+}
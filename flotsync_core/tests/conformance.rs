@@ -0,0 +1,115 @@
+//! Checks this crate against the version vector vectors in `conformance/version_vectors.toml`.
+//!
+//! The data file is shared with other implementations, see `docs/conformance_vectors.md`.
+
+use flotsync_core::versions::{
+    HappenedBeforeOrd,
+    HappenedBeforeOrdering,
+    VersionVector,
+    VersionVectorGap,
+};
+use toml::{Table, Value};
+
+const VERSION_VECTORS: &str = include_str!("../../conformance/version_vectors.toml");
+
+#[test]
+fn version_vector_comparisons_match_the_conformance_vectors() {
+    let vectors: Table = VERSION_VECTORS
+        .parse()
+        .expect("conformance vectors must be valid TOML");
+    let comparisons = vectors["comparison"]
+        .as_array()
+        .expect("comparison must be an array of tables");
+    assert!(!comparisons.is_empty());
+
+    for comparison in comparisons {
+        let name = str_field(comparison, "name");
+        let left = vector_field(comparison, "left");
+        let right = vector_field(comparison, "right");
+        let ordering = parse_ordering(str_field(comparison, "ordering"));
+        assert_eq!(left.hb_cmp(&right), ordering, "{name}: left vs right");
+        assert_eq!(
+            right.hb_cmp(&left),
+            ordering.reverse(),
+            "{name}: right vs left"
+        );
+        if ordering == HappenedBeforeOrdering::Incomparable {
+            continue;
+        }
+
+        let least_upper_bound = vector_field(comparison, "least-upper-bound");
+        assert_eq!(
+            left.least_upper_bound(&right),
+            least_upper_bound,
+            "{name}: least upper bound"
+        );
+        assert_eq!(
+            right.least_upper_bound(&left),
+            least_upper_bound,
+            "{name}: least upper bound is symmetric"
+        );
+        let greatest_lower_bound = vector_field(comparison, "greatest-lower-bound");
+        assert_eq!(
+            left.greatest_lower_bound(&right),
+            greatest_lower_bound,
+            "{name}: greatest lower bound"
+        );
+        assert_eq!(
+            right.greatest_lower_bound(&left),
+            greatest_lower_bound,
+            "{name}: greatest lower bound is symmetric"
+        );
+
+        let missing: Vec<_> = comparison["missing"]
+            .as_array()
+            .expect("missing must be an array")
+            .iter()
+            .map(parse_gap)
+            .collect();
+        assert_eq!(
+            left.missing_version_ranges_to(&right),
+            missing,
+            "{name}: missing version ranges"
+        );
+    }
+}
+
+fn str_field<'a>(table: &'a Value, key: &str) -> &'a str {
+    table[key]
+        .as_str()
+        .unwrap_or_else(|| panic!("{key} must be a string in {table}"))
+}
+
+fn u64_field(table: &Value, key: &str) -> u64 {
+    let value = table[key]
+        .as_integer()
+        .unwrap_or_else(|| panic!("{key} must be an integer in {table}"));
+    u64::try_from(value).expect("conformance integers are not negative")
+}
+
+fn vector_field(table: &Value, key: &str) -> VersionVector {
+    let notation = str_field(table, key);
+    notation
+        .parse()
+        .unwrap_or_else(|error| panic!("{key} = {notation:?} is not a version vector: {error}"))
+}
+
+fn parse_ordering(ordering: &str) -> HappenedBeforeOrdering {
+    match ordering {
+        "before" => HappenedBeforeOrdering::Before,
+        "equal" => HappenedBeforeOrdering::Equal,
+        "after" => HappenedBeforeOrdering::After,
+        "concurrent" => HappenedBeforeOrdering::Concurrent,
+        "incomparable" => HappenedBeforeOrdering::Incomparable,
+        other => panic!("unknown ordering {other:?}"),
+    }
+}
+
+fn parse_gap(gap: &Value) -> VersionVectorGap {
+    VersionVectorGap {
+        member_index: usize::try_from(u64_field(gap, "member"))
+            .expect("member positions fit into usize"),
+        start_version: u64_field(gap, "start"),
+        end_version: u64_field(gap, "end"),
+    }
+}
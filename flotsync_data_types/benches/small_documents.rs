@@ -0,0 +1,66 @@
+//! Creating, cloning and changing tiny documents, which keep their nodes inline.
+//!
+//! To compare against plain `Vec` node storage, save a baseline with the `heap-nodes` feature
+//! and then measure the default build against it:
+//!
+//! ```sh
+//! cargo bench -p flotsync_data_types --bench small_documents --features heap-nodes -- --save-baseline vec
+//! cargo bench -p flotsync_data_types --bench small_documents -- --baseline vec
+//! ```
+use criterion::{Criterion, criterion_group, criterion_main};
+use flotsync_data_types::{IdWithIndex, any_data::LinearLatestValueWins, text::LinearString};
+use std::{hint::black_box, time::Duration};
+
+const ONE_LINE: &str = "Buy milk and eggs";
+
+fn bench_registers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small_documents/register");
+    group.bench_function("create", |b| {
+        b.iter(|| LinearLatestValueWins::new(black_box(42u64), [0u32, 1, 2]));
+    });
+
+    let register = LinearLatestValueWins::new(42u64, [0u32, 1, 2]);
+    group.bench_function("clone", |b| b.iter(|| black_box(&register).clone()));
+    group.bench_function("update", |b| {
+        b.iter_batched(
+            || register.clone(),
+            |mut register| {
+                register.update(3, black_box(43));
+                register
+            },
+            criterion::BatchSize::SmallInput,
+        );
+    });
+    group.finish();
+}
+
+fn bench_one_line_strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small_documents/one_line_string");
+    group.bench_function("create", |b| {
+        b.iter(|| LinearString::with_value(black_box(ONE_LINE).to_owned(), 0u32));
+    });
+
+    let string = LinearString::with_value(ONE_LINE.to_owned(), 0u32);
+    group.bench_function("clone", |b| b.iter(|| black_box(&string).clone()));
+    group.bench_function("append", |b| {
+        b.iter_batched(
+            || string.clone(),
+            |mut string| {
+                string.append(IdWithIndex::zero(1), black_box(" and bread").to_owned());
+                string
+            },
+            criterion::BatchSize::SmallInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .sample_size(30)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2));
+    targets = bench_registers, bench_one_line_strings
+}
+criterion_main!(benches);
@@ -0,0 +1,80 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use flotsync_data_types::{
+    DataOperation,
+    IdWithIndex,
+    apply_workspace_batches,
+    linear_data::LinearData,
+    par_apply_workspace_batches,
+    text::LinearString,
+};
+use std::{collections::HashMap, hint::black_box, time::Duration};
+
+const NUM_DOCUMENTS: u32 = 256;
+const OPERATIONS_PER_DOCUMENT: u32 = 128;
+
+type Operation = DataOperation<IdWithIndex<u32>, String>;
+type Workspace = HashMap<u32, LinearString<u32>>;
+
+/// An empty workspace and one batch per document, as received during an initial sync.
+///
+/// Each batch is reversed, so applying it exercises the causal ordering as well.
+fn initial_sync() -> (Workspace, Vec<(u32, Vec<Operation>)>) {
+    let mut documents = HashMap::new();
+    let mut batches = Vec::new();
+    for key in 0..NUM_DOCUMENTS {
+        let base = LinearString::new(0u32);
+        let mut source = base.clone();
+        let mut operations = Vec::new();
+        for id in 1..=OPERATIONS_PER_DOCUMENT {
+            let ids = source.ids_before_end();
+            let operation = DataOperation::Insert {
+                id: IdWithIndex::zero(id),
+                pred: ids.predecessor,
+                succ: ids.successor,
+                value: format!("line {id}\n"),
+            };
+            source.apply_operation(operation.clone()).unwrap();
+            operations.push(operation);
+        }
+        operations.reverse();
+        documents.insert(key, base);
+        batches.push((key, operations));
+    }
+    (documents, batches)
+}
+
+fn bench_initial_sync(c: &mut Criterion) {
+    let mut group = c.benchmark_group("workspace_apply/initial_sync");
+    let workspace = initial_sync();
+    group.bench_function("sequential", |b| {
+        b.iter_batched(
+            || workspace.clone(),
+            |(mut documents, batches)| {
+                black_box(apply_workspace_batches(&mut documents, batches));
+                documents
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("parallel", |b| {
+        b.iter_batched(
+            || workspace.clone(),
+            |(mut documents, batches)| {
+                black_box(par_apply_workspace_batches(&mut documents, batches));
+                documents
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(3));
+    targets = bench_initial_sync
+}
+criterion_main!(benches);
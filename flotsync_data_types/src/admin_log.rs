@@ -0,0 +1,461 @@
+//! A signed, append-only log of the administrative actions taken in a group.
+//!
+//! An [`AdminLog`] records membership changes, key rotations and policy changes as they are
+//! made, so members can later tell who did what and in which order. It is an [`EventLog`] whose
+//! entries are signed by their author. The signature covers the group, the entry's id and
+//! sequence number, its author, and the action, so an entry can neither be altered nor replayed
+//! elsewhere in the log or in another group.
+//!
+//! Every replica verifies each entry before applying it, using an [`AdminLogVerifier`] that knows
+//! the signing keys of the group's members and which member produces updates under which node
+//! index. It rejects entries that do not verify, as well as entries whose author is not the member
+//! their id was produced by. Since the log
+//! is never compacted, [`AdminLog::verify_all`] can check the whole log again at any time, e.g.
+//! after a member's keys were learned later. How entries are signed is left to the
+//! [`AdminLogSigner`] and [`AdminLogVerifier`] implementations.
+use crate::{
+    any_data::event_log::{AppendOperation, EventLog},
+    retention::RetentionPolicy,
+    versioned::ReplicatedDocument,
+};
+use flotsync_core::{
+    GroupId,
+    member::{Identifier, MembershipOperation},
+    versions::UpdateId,
+};
+use snafu::prelude::*;
+use std::fmt;
+
+/// An administrative action recorded in an [`AdminLog`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdminAction {
+    /// A member joined or left the group.
+    Membership(MembershipOperation),
+    /// The group key was rotated to `generation`.
+    KeyRotated {
+        generation: u32,
+        /// The member whose removal caused the rotation, `None` for a scheduled rotation.
+        removed_member: Option<Identifier>,
+    },
+    /// The [`RetentionPolicy`] of `document_type` was changed to `policy`.
+    RetentionPolicyChanged {
+        document_type: String,
+        policy: RetentionPolicy,
+    },
+}
+
+/// An [`AdminAction`] together with its author and the author's signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdminEntry {
+    pub author: Identifier,
+    pub action: AdminAction,
+    pub signature: Vec<u8>,
+}
+
+/// Signs the entries a member records in an [`AdminLog`].
+pub trait AdminLogSigner {
+    type Error;
+
+    /// The member that signs.
+    fn author(&self) -> &Identifier;
+
+    /// Sign `message` with the member's signing key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature cannot be produced.
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Verifies the entries of an [`AdminLog`] against the signing keys of the group's members.
+pub trait AdminLogVerifier {
+    /// Whether `signature` is a valid signature of `message` by `author`.
+    ///
+    /// Must return `false` for authors whose signing key is unknown.
+    fn verify(&self, author: &Identifier, message: &[u8], signature: &[u8]) -> bool;
+
+    /// The member that produces updates with `node_index` in the group, if there is one.
+    fn member_at(&self, node_index: u32) -> Option<&Identifier>;
+}
+
+/// Returned when an [`AdminLog`] entry fails verification.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum AdminLogRejection {
+    #[snafu(display("Admin log entry {id} by {author} does not have a valid signature."))]
+    InvalidSignature { id: UpdateId, author: Identifier },
+    #[snafu(display(
+        "Admin log entry {id} claims {author} as its author, but was not produced by them."
+    ))]
+    AuthorMismatch { id: UpdateId, author: Identifier },
+}
+
+/// The signed administrative log of one group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdminLog<V> {
+    group_id: GroupId,
+    verifier: V,
+    log: EventLog<UpdateId, AdminEntry>,
+}
+
+impl<V> AdminLog<V>
+where
+    V: AdminLogVerifier,
+{
+    /// An empty log for `group_id`, whose entries are checked with `verifier`.
+    pub fn new(group_id: GroupId, verifier: V) -> Self {
+        Self {
+            group_id,
+            verifier,
+            log: EventLog::new(),
+        }
+    }
+
+    pub fn group_id(&self) -> GroupId {
+        self.group_id
+    }
+
+    /// Replace the verifier, e.g. after the group's members or their keys changed.
+    pub fn set_verifier(&mut self, verifier: V) {
+        self.verifier = verifier;
+    }
+
+    /// Produce the signed operation that records `action` under `id`.
+    ///
+    /// The operation still has to be applied, like any other operation of this document.
+    ///
+    /// # Errors
+    ///
+    /// Returns the signer's error if the entry cannot be signed.
+    pub fn record<S>(
+        &self,
+        signer: &S,
+        id: UpdateId,
+        action: AdminAction,
+    ) -> Result<AppendOperation<UpdateId, AdminEntry>, S::Error>
+    where
+        S: AdminLogSigner,
+    {
+        let sequence = self.log.last_sequence() + 1;
+        let author = signer.author().clone();
+        let message = signed_message(self.group_id, id, sequence, &author, &action);
+        let signature = signer.sign(&message)?;
+        Ok(AppendOperation {
+            id,
+            sequence,
+            value: AdminEntry {
+                author,
+                action,
+                signature,
+            },
+        })
+    }
+
+    /// Check the signature of every entry again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AdminLogRejection`] for the first entry that does not verify.
+    pub fn verify_all(&self) -> Result<(), AdminLogRejection> {
+        for (id, sequence, entry) in self.log.iter_events() {
+            self.verify_entry(*id, sequence, entry)?;
+        }
+        Ok(())
+    }
+
+    /// All entries in log order, with their ids and sequence numbers.
+    pub fn entries(&self) -> impl Iterator<Item = (&UpdateId, u64, &AdminEntry)> {
+        self.log.iter_events()
+    }
+
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    fn verify_entry(
+        &self,
+        id: UpdateId,
+        sequence: u64,
+        entry: &AdminEntry,
+    ) -> Result<(), AdminLogRejection> {
+        ensure!(
+            self.verifier.member_at(id.node_index) == Some(&entry.author),
+            AuthorMismatchSnafu {
+                id,
+                author: entry.author.clone(),
+            }
+        );
+        let message = signed_message(self.group_id, id, sequence, &entry.author, &entry.action);
+        ensure!(
+            self.verifier
+                .verify(&entry.author, &message, &entry.signature),
+            InvalidSignatureSnafu {
+                id,
+                author: entry.author.clone(),
+            }
+        );
+        Ok(())
+    }
+}
+
+impl<V> ReplicatedDocument for AdminLog<V>
+where
+    V: AdminLogVerifier + Clone + fmt::Debug,
+{
+    const DOCUMENT_TYPE: &'static str = "admin_log";
+
+    type Operation = AppendOperation<UpdateId, AdminEntry>;
+    type Rejection = AdminLogRejection;
+
+    fn apply_operation(&mut self, operation: Self::Operation) -> Result<(), Self::Rejection> {
+        self.verify_entry(operation.id, operation.sequence, &operation.value)?;
+        // The log is never compacted, so no append can fall into a compacted prefix.
+        let _ = self.log.apply_operation(operation);
+        Ok(())
+    }
+}
+
+/// The bytes an entry's signature covers.
+fn signed_message(
+    group_id: GroupId,
+    id: UpdateId,
+    sequence: u64,
+    author: &Identifier,
+    action: &AdminAction,
+) -> Vec<u8> {
+    let mut message = Vec::new();
+    write_str(&mut message, "flotsync admin log entry v1");
+    message.extend_from_slice(group_id.0.as_bytes());
+    message.extend_from_slice(&id.version.to_be_bytes());
+    message.extend_from_slice(&id.node_index.to_be_bytes());
+    message.extend_from_slice(&sequence.to_be_bytes());
+    write_str(&mut message, &author.to_string());
+    match action {
+        AdminAction::Membership(operation) => {
+            message.push(0);
+            message.extend_from_slice(&operation.epoch.to_be_bytes());
+            write_str(&mut message, &operation.member.to_string());
+            message.push(operation.kind as u8);
+        }
+        AdminAction::KeyRotated {
+            generation,
+            removed_member,
+        } => {
+            message.push(1);
+            message.extend_from_slice(&generation.to_be_bytes());
+            match removed_member {
+                Some(member) => {
+                    message.push(1);
+                    write_str(&mut message, &member.to_string());
+                }
+                None => message.push(0),
+            }
+        }
+        AdminAction::RetentionPolicyChanged {
+            document_type,
+            policy,
+        } => {
+            message.push(2);
+            write_str(&mut message, document_type);
+            match policy {
+                RetentionPolicy::Forever => message.push(0),
+                RetentionPolicy::UntilStable => message.push(1),
+                RetentionPolicy::KeepFor { duration } => {
+                    message.push(2);
+                    message.extend_from_slice(&duration.as_secs().to_be_bytes());
+                    message.extend_from_slice(&duration.subsec_nanos().to_be_bytes());
+                }
+            }
+        }
+    }
+    message
+}
+
+fn write_str(message: &mut Vec<u8>, value: &str) {
+    message.extend_from_slice(&(value.len() as u64).to_be_bytes());
+    message.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_core::member::MembershipChangeKind;
+    use std::{
+        collections::hash_map::DefaultHasher,
+        convert::Infallible,
+        hash::{Hash, Hasher},
+        time::Duration,
+    };
+    use uuid::Uuid;
+
+    /// Signs with a checksum keyed by the author's name, which is enough to tell authors apart.
+    struct TestSigner {
+        author: Identifier,
+    }
+
+    impl AdminLogSigner for TestSigner {
+        type Error = Infallible;
+
+        fn author(&self) -> &Identifier {
+            &self.author
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Self::Error> {
+            Ok(checksum(&self.author, message))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestVerifier {
+        members: Vec<Identifier>,
+    }
+
+    impl AdminLogVerifier for TestVerifier {
+        fn verify(&self, author: &Identifier, message: &[u8], signature: &[u8]) -> bool {
+            self.members.contains(author) && checksum(author, message) == signature
+        }
+
+        fn member_at(&self, node_index: u32) -> Option<&Identifier> {
+            self.members.get(usize::try_from(node_index).ok()?)
+        }
+    }
+
+    fn checksum(author: &Identifier, message: &[u8]) -> Vec<u8> {
+        let mut hasher = DefaultHasher::new();
+        author.hash(&mut hasher);
+        message.hash(&mut hasher);
+        hasher.finish().to_be_bytes().to_vec()
+    }
+
+    fn alice() -> Identifier {
+        Identifier::from_array(["alice"])
+    }
+
+    fn bob() -> Identifier {
+        Identifier::from_array(["bob"])
+    }
+
+    fn signer(author: Identifier) -> TestSigner {
+        TestSigner { author }
+    }
+
+    fn new_log() -> AdminLog<TestVerifier> {
+        AdminLog::new(
+            GroupId(Uuid::from_u128(1)),
+            TestVerifier {
+                members: vec![alice(), bob()],
+            },
+        )
+    }
+
+    fn update(version: u64, node_index: u32) -> UpdateId {
+        UpdateId {
+            version,
+            node_index,
+        }
+    }
+
+    fn removal() -> AdminAction {
+        AdminAction::Membership(MembershipOperation {
+            epoch: 2,
+            member: Identifier::from_array(["carol"]),
+            kind: MembershipChangeKind::Leave,
+        })
+    }
+
+    #[test]
+    fn signed_entries_converge_on_every_member() {
+        let mut alices_log = new_log();
+        let mut bobs_log = new_log();
+
+        let from_alice = alices_log
+            .record(&signer(alice()), update(1, 0), removal())
+            .unwrap();
+        let from_bob = bobs_log
+            .record(
+                &signer(bob()),
+                update(1, 1),
+                AdminAction::RetentionPolicyChanged {
+                    document_type: "list".to_owned(),
+                    policy: RetentionPolicy::KeepFor {
+                        duration: Duration::from_secs(60),
+                    },
+                },
+            )
+            .unwrap();
+        alices_log.apply_operation(from_alice.clone()).unwrap();
+        bobs_log.apply_operation(from_bob.clone()).unwrap();
+        alices_log.apply_operation(from_bob).unwrap();
+        bobs_log.apply_operation(from_alice).unwrap();
+
+        let rotation = alices_log
+            .record(
+                &signer(alice()),
+                update(2, 0),
+                AdminAction::KeyRotated {
+                    generation: 1,
+                    removed_member: Some(Identifier::from_array(["carol"])),
+                },
+            )
+            .unwrap();
+        assert_eq!(rotation.sequence, 2);
+        alices_log.apply_operation(rotation.clone()).unwrap();
+        bobs_log.apply_operation(rotation).unwrap();
+
+        assert!(alices_log.entries().eq(bobs_log.entries()));
+        assert_eq!(alices_log.len(), 3);
+        let authors: Vec<_> = alices_log
+            .entries()
+            .map(|(_, _, entry)| entry.author.clone())
+            .collect();
+        assert_eq!(authors, vec![alice(), bob(), alice()]);
+        alices_log.verify_all().unwrap();
+    }
+
+    #[test]
+    fn tampered_and_foreign_entries_are_rejected() {
+        let mut log = new_log();
+
+        let mut tampered = log
+            .record(&signer(alice()), update(1, 0), removal())
+            .unwrap();
+        tampered.value.action = AdminAction::KeyRotated {
+            generation: 7,
+            removed_member: None,
+        };
+        assert!(matches!(
+            log.apply_operation(tampered),
+            Err(AdminLogRejection::InvalidSignature { .. })
+        ));
+
+        let mut impersonated = log.record(&signer(bob()), update(1, 1), removal()).unwrap();
+        impersonated.value.author = alice();
+        assert!(log.apply_operation(impersonated).is_err());
+
+        // Correctly signed, but under an id that belongs to bob.
+        let misattributed = log
+            .record(&signer(alice()), update(1, 1), removal())
+            .unwrap();
+        assert!(matches!(
+            log.apply_operation(misattributed),
+            Err(AdminLogRejection::AuthorMismatch { .. })
+        ));
+
+        let outsider = Identifier::from_array(["mallory"]);
+        let foreign = log
+            .record(&signer(outsider), update(1, 2), removal())
+            .unwrap();
+        assert!(log.apply_operation(foreign).is_err());
+
+        let mut replayed = log
+            .record(&signer(alice()), update(1, 0), removal())
+            .unwrap();
+        replayed.sequence += 1;
+        assert!(log.apply_operation(replayed).is_err());
+        assert!(log.is_empty());
+    }
+}
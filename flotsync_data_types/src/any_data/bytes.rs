@@ -0,0 +1,526 @@
+//! A linear CRDT over raw bytes, for binary blobs that are edited collaboratively.
+use crate::{
+    IntegrityError,
+    InternalError,
+    LinearDataStats,
+    linear_data::{
+        ApplyBatch,
+        ApplyFailure,
+        BatchResult,
+        Composite,
+        DataOperation,
+        DataOperationRef,
+        IdWithIndex,
+        IdWithIndexRange,
+        LinearData,
+        LinkIds,
+        NodeIdRange,
+        NodeIds,
+        VecCoalescedLinearData,
+        VecCoalescedLinearDataIter,
+        VecLinearData,
+    },
+    snapshot::{SnapshotNode, SnapshotReadError, SnapshotSink},
+};
+use flotsync_utils::debugging::DebugFormatting;
+use std::{fmt, hash::Hash, ops::RangeBounds};
+
+/// A replicated [`LinearBytes`] operation.
+pub type LinearBytesOperation<Id> = DataOperation<IdWithIndex<Id>, Vec<u8>>;
+
+/// A [`LinearBytes`] operation that borrows its bytes, e.g. from a network buffer.
+pub type LinearBytesOperationRef<'a, Id> = DataOperationRef<'a, IdWithIndex<Id>, [u8]>;
+
+/// A contiguous run of bytes that is stored as a single coalesced node.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ByteChunk {
+    bytes: Vec<u8>,
+}
+impl ByteChunk {
+    fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+
+    fn unwrap(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+impl Composite for ByteChunk {
+    type Element = u8;
+    type Iter<'a> = std::slice::Iter<'a, u8>;
+
+    fn get(&self, index: usize) -> Option<&Self::Element> {
+        self.bytes.get(index)
+    }
+
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn split_at(mut self, index: usize) -> (Self, Self) {
+        assert!(index < self.bytes.len());
+        let rest = self.bytes.split_off(index);
+        (self, Self { bytes: rest })
+    }
+
+    fn concat(mut self, mut other: Self) -> Self {
+        self.bytes.append(&mut other.bytes);
+        self
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.bytes.iter()
+    }
+}
+impl fmt::Debug for ByteChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.bytes {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for ByteChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// A linear byte-sequence CRDT with the same convergence model as
+/// [[`LinearString`](crate::text::LinearString)].
+///
+/// The minimum item unit is a single byte, but consecutive bytes inserted by one operation are
+/// kept in one coalesced node, so appending large chunks stays cheap.
+/// This is intended for binary blobs (images, attachments, etc.) that are extended and truncated
+/// collaboratively. It makes no attempt at producing byte-level diffs of arbitrary edits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearBytes<Id> {
+    data: VecCoalescedLinearData<Id, ByteChunk>,
+}
+impl<Id> LinearBytes<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    pub fn new(initial_id: Id) -> Self {
+        let data = VecCoalescedLinearData::new(initial_id);
+        Self { data }
+    }
+
+    pub fn with_value(initial_value: Vec<u8>, initial_id: Id) -> Self {
+        if initial_value.is_empty() {
+            Self::new(initial_id)
+        } else {
+            let data =
+                VecCoalescedLinearData::with_value(initial_id, ByteChunk::new(initial_value));
+            Self { data }
+        }
+    }
+
+    /// Append `value` at the end.
+    ///
+    /// Empty chunks are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` cannot address every byte in `value`.
+    pub fn append(&mut self, id: IdWithIndex<Id>, value: Vec<u8>) {
+        if value.is_empty() {
+            return;
+        }
+        self.data.append(id, ByteChunk::new(value));
+    }
+
+    /// Prepend `value` at the front.
+    ///
+    /// Empty chunks are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` cannot address every byte in `value`.
+    pub fn prepend(&mut self, id: IdWithIndex<Id>, value: Vec<u8>) {
+        if value.is_empty() {
+            return;
+        }
+        self.data.prepend(id, ByteChunk::new(value));
+    }
+
+    /// Apply an operation that borrows its bytes, copying them only once they are stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the original borrowed operation if it could not be applied.
+    pub fn apply_operation_ref<'v>(
+        &mut self,
+        operation: LinearBytesOperationRef<'v, Id>,
+    ) -> Result<(), ApplyFailure<LinearBytesOperationRef<'v, Id>>> {
+        LinearData::apply_operation_ref(self, operation)
+    }
+
+    /// Apply a batch of operations in causal order, returning the ones that are still blocked.
+    ///
+    /// See [`VecCoalescedLinearData::apply_batch`] for how the batch is ordered.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first internal inconsistency. Operations applied before it stay applied.
+    pub fn apply_batch(
+        &mut self,
+        operations: Vec<LinearBytesOperation<Id>>,
+    ) -> Result<BatchResult<LinearBytesOperation<Id>>, InternalError> {
+        let operations = operations
+            .into_iter()
+            .map(|operation| operation.map_value(ByteChunk::new))
+            .collect();
+        let result = self.data.apply_batch(operations)?;
+        Ok(result.map_operation(|operation| operation.map_value(ByteChunk::unwrap)))
+    }
+
+    /// Build an append operation for replication.
+    ///
+    /// Returns `None` for empty chunks.
+    pub fn append_operation(
+        &self,
+        id: IdWithIndex<Id>,
+        value: Vec<u8>,
+    ) -> Option<DataOperation<IdWithIndex<Id>, Vec<u8>>> {
+        if value.is_empty() {
+            return None;
+        }
+        let ids = self.data.ids_before_end();
+        Some(ids.insert_operation(id, value))
+    }
+
+    /// Delete all bytes from position `len` onwards.
+    ///
+    /// Does nothing if `len >= self.len()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first id range that could not be deleted.
+    /// In this case the previous deletes will have been applied.
+    pub fn truncate(&mut self, len: usize) -> Result<(), IdWithIndexRange<Id>> {
+        let Some(range) = self.data.ids_in_range(len..) else {
+            return Ok(());
+        };
+        range.delete(&mut self.data).map_err(Clone::clone)
+    }
+
+    /// Build the delete operations that truncate the visible content to `len` bytes.
+    ///
+    /// The iterator is empty if `len >= self.len()`.
+    pub fn truncate_operations(
+        &self,
+        len: usize,
+    ) -> impl Iterator<Item = DataOperation<IdWithIndex<Id>, Vec<u8>>> {
+        self.data
+            .ids_in_range(len..)
+            .into_iter()
+            .flat_map(NodeIdRange::delete_operations)
+    }
+
+    /// This is the number of visible bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Iterate over the visible bytes in order.
+    #[must_use]
+    pub fn iter(&self) -> LinearBytesIter<'_, Id> {
+        LinearBytesIter {
+            underlying: self.data.iter_values(),
+        }
+    }
+
+    /// Copy the visible content into a contiguous buffer.
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(self.len());
+        buffer.extend(self.iter());
+        buffer
+    }
+
+    pub fn ids_in_range<R>(&self, range: R) -> Option<NodeIdRangeBytes<Id>>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.data.ids_in_range(range).map(NodeIdRangeBytes)
+    }
+
+    /// Returns an iterator over all ids that are associated with some node in the underlying
+    /// data structure.
+    ///
+    /// Note that there will always be duplicate ids.
+    /// The head and end nodes share the same id, and also when a coalesced node was split
+    /// later with another id being inserted within.
+    pub fn iter_ids(&self) -> impl Iterator<Item = &Id> {
+        self.data.iter_ids().map(|id| &id.id)
+    }
+
+    /// Encode a stable, ordered snapshot stream of the current in-memory state.
+    ///
+    /// # Errors
+    ///
+    /// See `S::Error` for failure conditions.
+    pub fn encode_snapshot<S>(&self, sink: &mut S) -> Result<(), S::Error>
+    where
+        S: SnapshotSink<IdWithIndex<Id>, [u8]>,
+    {
+        self.data
+            .encode_snapshot(sink, |value| value.bytes.as_slice())
+    }
+
+    /// # Errors
+    ///
+    /// See `SnapshotReadError<E>` for failure conditions.
+    pub fn from_snapshot_nodes<E, I>(nodes: I) -> Result<Self, SnapshotReadError<E>>
+    where
+        E: snafu::Error + Send + Sync + 'static,
+        I: IntoIterator<Item = Result<SnapshotNode<IdWithIndex<Id>, Vec<u8>>, E>>,
+    {
+        let mapped = nodes.into_iter().map(|entry| {
+            entry.map(|node| SnapshotNode {
+                id: node.id,
+                left: node.left,
+                right: node.right,
+                deleted: node.deleted,
+                value: node.value.map(ByteChunk::new),
+            })
+        });
+        let base = VecLinearData::from_snapshot_nodes(mapped)?;
+        let data = VecCoalescedLinearData::from_base_snapshot(base);
+        Ok(Self { data })
+    }
+
+    /// Validate the internal CRDT structure and chunk/id invariants.
+    ///
+    /// This is primarily useful after reconstructing a value from an external snapshot or other
+    /// untrusted input.
+    ///
+    /// # Errors
+    ///
+    /// See `IntegrityError` for failure conditions.
+    pub fn validate_integrity(&self) -> Result<(), IntegrityError> {
+        self.data.validate_integrity()
+    }
+
+    /// Conflict and storage statistics, e.g. to decide when compaction should run.
+    #[must_use]
+    pub fn stats(&self) -> LinearDataStats {
+        self.data.stats()
+    }
+
+    /// Store parts of the same insert that were split apart as single nodes again.
+    ///
+    /// Returns the number of nodes that were merged away. See
+    /// [`VecCoalescedLinearData::defragment`](crate::linear_data::VecCoalescedLinearData::defragment).
+    pub fn defragment(&mut self) -> usize {
+        self.data.defragment()
+    }
+}
+impl<Id> ApplyBatch for LinearBytes<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    type Operation = LinearBytesOperation<Id>;
+
+    fn apply_batch(
+        &mut self,
+        operations: Vec<Self::Operation>,
+    ) -> Result<BatchResult<Self::Operation>, InternalError> {
+        LinearBytes::apply_batch(self, operations)
+    }
+}
+impl<Id> LinearData<Vec<u8>, u8> for LinearBytes<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    type Id = IdWithIndex<Id>;
+
+    type Iter<'a> = LinearBytesIter<'a, Id>;
+
+    fn ids_after_head(&self) -> LinkIds<Self::Id> {
+        self.data.ids_after_head()
+    }
+
+    fn ids_before_end(&self) -> LinkIds<Self::Id> {
+        self.data.ids_before_end()
+    }
+
+    fn ids_at_pos(&self, position: usize) -> Option<NodeIds<Self::Id>> {
+        self.data.ids_at_pos(position)
+    }
+
+    fn insert(
+        &mut self,
+        id: Self::Id,
+        pred: Self::Id,
+        succ: Self::Id,
+        value: Vec<u8>,
+    ) -> Result<(), Vec<u8>> {
+        self.data
+            .insert(id, pred, succ, ByteChunk::new(value))
+            .map_err(ByteChunk::unwrap)
+    }
+
+    fn delete<'a>(&'a mut self, id: &Self::Id) -> Option<&'a u8> {
+        self.data.delete(id)
+    }
+
+    fn iter_values(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+
+    fn iter_ids(&self) -> impl Iterator<Item = &Self::Id> {
+        self.data.iter_ids()
+    }
+
+    fn apply_operation(
+        &mut self,
+        operation: DataOperation<Self::Id, Vec<u8>>,
+    ) -> Result<(), ApplyFailure<DataOperation<Self::Id, Vec<u8>>>> {
+        let op = operation.map_value(ByteChunk::new);
+        self.data
+            .apply_operation(op)
+            .map_err(|failure| failure.map_operation(|op| op.map_value(ByteChunk::unwrap)))
+    }
+}
+impl<Id> DebugFormatting for LinearBytes<Id>
+where
+    Id: fmt::Display + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        DebugFormatting::fmt(&self.data, f)
+    }
+}
+
+/// Convenience wrapper around [[`NodeIdRange`]] when using it with [[`LinearBytes`]].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeIdRangeBytes<Id>(NodeIdRange<Id>);
+impl<Id> NodeIdRangeBytes<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    /// Tries to delete all the nodes contained in the range.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failing range if unsuccessful.
+    /// In this case the previous deletes will have been applied.
+    pub fn delete<'a>(
+        &'a self,
+        data: &mut LinearBytes<Id>,
+    ) -> Result<(), &'a IdWithIndexRange<Id>> {
+        self.0.delete(&mut data.data)
+    }
+
+    pub fn delete_operations(
+        self,
+    ) -> impl Iterator<Item = DataOperation<IdWithIndex<Id>, Vec<u8>>> {
+        self.0.delete_operations()
+    }
+}
+
+pub struct LinearBytesIter<'a, Id> {
+    underlying: VecCoalescedLinearDataIter<'a, IdWithIndex<Id>, ByteChunk>,
+}
+impl<'a, Id> Iterator for LinearBytesIter<'a, Id> {
+    type Item = &'a u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.underlying.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ids::TestIdGenerator;
+    use std::assert_matches;
+
+    #[test]
+    fn append_prepend_and_truncate() {
+        let mut id_generator = TestIdGenerator::new();
+        let mut bytes = LinearBytes::new(id_generator.next().unwrap());
+        assert!(bytes.is_empty());
+
+        bytes.append(id_generator.next_with_zero_index().unwrap(), vec![3, 4, 5]);
+        bytes.prepend(id_generator.next_with_zero_index().unwrap(), vec![1, 2]);
+        assert_eq!(bytes.to_vec(), vec![1, 2, 3, 4, 5]);
+        bytes.validate_integrity().unwrap();
+
+        bytes.truncate(3).unwrap();
+        assert_eq!(bytes.to_vec(), vec![1, 2, 3]);
+        assert_eq!(bytes.len(), 3);
+
+        bytes.truncate(10).unwrap();
+        assert_eq!(bytes.to_vec(), vec![1, 2, 3]);
+        bytes.validate_integrity().unwrap();
+    }
+
+    #[test]
+    fn replicated_append_and_truncate_converge() {
+        let mut id_generator = TestIdGenerator::new();
+        let base = LinearBytes::with_value(vec![0xca, 0xfe], id_generator.next().unwrap());
+        let mut a = base.clone();
+        let mut b = base;
+
+        let append = a
+            .append_operation(
+                id_generator.next_with_zero_index().unwrap(),
+                vec![0xba, 0xbe],
+            )
+            .unwrap();
+        a.apply_operation(append.clone()).unwrap();
+        b.apply_operation(append).unwrap();
+
+        let truncates: Vec<_> = a.truncate_operations(1).collect();
+        assert_matches!(
+            truncates.as_slice(),
+            [DataOperation::DeleteRanges { ranges }] if ranges.len() == 2,
+            "a truncate across both updates is a single operation"
+        );
+        for op in truncates {
+            a.apply_operation(op.clone()).unwrap();
+            b.apply_operation(op).unwrap();
+        }
+
+        assert_eq!(a, b);
+        assert_eq!(a.to_vec(), vec![0xca]);
+    }
+
+    #[test]
+    fn borrowed_operations_match_owned_operations() {
+        let mut id_generator = TestIdGenerator::new();
+        let base = LinearBytes::with_value(vec![0xca, 0xfe], id_generator.next().unwrap());
+        let mut owned = base.clone();
+        let mut borrowed = base;
+
+        let append = owned
+            .append_operation(
+                id_generator.next_with_zero_index().unwrap(),
+                vec![0xba, 0xbe],
+            )
+            .unwrap();
+        let buffer = [0xba, 0xbe];
+        borrowed
+            .apply_operation_ref(append.clone().map_value(|_| &buffer[..]))
+            .unwrap();
+        owned.apply_operation(append).unwrap();
+        assert_eq!(owned, borrowed);
+
+        for op in owned.truncate_operations(1).collect::<Vec<_>>() {
+            borrowed
+                .apply_operation_ref(op.clone().map_value(|_| &[][..]))
+                .unwrap();
+            owned.apply_operation(op).unwrap();
+        }
+        assert_eq!(owned, borrowed);
+        assert_eq!(borrowed.to_vec(), vec![0xca]);
+    }
+}
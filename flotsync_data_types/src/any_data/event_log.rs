@@ -0,0 +1,264 @@
+//! An append-only log CRDT, for chat messages, audit trails and other write-once entries.
+use std::{collections::BTreeMap, fmt, hash::Hash};
+
+/// A replicated [`EventLog`] append.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppendOperation<Id, T> {
+    pub id: Id,
+    /// One more than the highest sequence number the author had seen when appending.
+    pub sequence: u64,
+    pub value: T,
+}
+
+/// An append-only log of events with a convergent causal order.
+///
+/// Unlike [`LinearList`](super::list::LinearList), events can neither be deleted nor inserted
+/// at arbitrary positions, so an append only needs the log's highest sequence number instead of
+/// a pair of neighbour ids, and no tombstones are ever kept.
+///
+/// ## Semantics
+///
+/// - **Append:** every event gets a sequence number one higher than any event its author had
+///   seen, Lamport-clock style.
+/// - **Order:** events are ordered by sequence number, then by id. If one event was appended
+///   after another was seen, it is ordered after it; concurrent events are ordered by id.
+/// - **Compaction:** a prefix of events that every replica has seen can be folded into plain
+///   values with [`compact_through`](Self::compact_through), dropping its ids.
+///
+/// ## Guarantees
+///
+/// - **Convergence:** given the same set of appends, all replicas iterate the same events in the
+///   same order, independent of delivery order. Appends are idempotent.
+/// - A replica that has not received all causal predecessors of an event may temporarily see
+///   the event without them. Once they arrive they are ordered before it.
+///
+/// ## Identifier requirements
+///
+/// `Id` must uniquely identify each event and provide a deterministic total order used to break
+/// ties between concurrent appends.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventLog<Id, T> {
+    /// Values of the compacted prefix, in log order.
+    compacted: Vec<T>,
+    /// The highest sequence number in the compacted prefix, `0` if nothing was compacted.
+    compacted_through: u64,
+    /// Events after the compacted prefix, keyed by their position in the log order.
+    events: BTreeMap<(u64, Id), T>,
+}
+
+impl<Id, T> Default for EventLog<Id, T> {
+    fn default() -> Self {
+        Self {
+            compacted: Vec::new(),
+            compacted_through: 0,
+            events: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Id, T> EventLog<Id, T>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    T: Clone + fmt::Debug,
+{
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of events in the log, including compacted ones.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.compacted.len() + self.events.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The highest sequence number of any event seen so far.
+    #[must_use]
+    pub fn last_sequence(&self) -> u64 {
+        self.events
+            .last_key_value()
+            .map_or(self.compacted_through, |((sequence, _), _)| *sequence)
+    }
+
+    /// Append `value` locally and return the operation to replicate.
+    pub fn append(&mut self, id: Id, value: T) -> AppendOperation<Id, T> {
+        let operation = self.append_operation(id, value);
+        self.events.insert(
+            (operation.sequence, operation.id.clone()),
+            operation.value.clone(),
+        );
+        operation
+    }
+
+    /// Produce an operation that appends `value` after every event seen so far.
+    #[must_use]
+    pub fn append_operation(&self, id: Id, value: T) -> AppendOperation<Id, T> {
+        AppendOperation {
+            id,
+            sequence: self.last_sequence() + 1,
+            value,
+        }
+    }
+
+    /// Apply an append received from some replica (including ourselves).
+    ///
+    /// Applying an append that was already applied has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Returns the operation unchanged if it belongs into the compacted prefix of the log, which
+    /// means the log was compacted before this event was seen everywhere.
+    pub fn apply_operation(
+        &mut self,
+        operation: AppendOperation<Id, T>,
+    ) -> Result<(), AppendOperation<Id, T>> {
+        if operation.sequence <= self.compacted_through {
+            return Err(operation);
+        }
+        self.events
+            .entry((operation.sequence, operation.id))
+            .or_insert(operation.value);
+        Ok(())
+    }
+
+    /// All values in log order, starting with the compacted ones.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.compacted.iter().chain(self.events.values())
+    }
+
+    /// The events after the compacted prefix in log order, with their ids and sequence numbers.
+    pub fn iter_events(&self) -> impl Iterator<Item = (&Id, u64, &T)> {
+        self.events
+            .iter()
+            .map(|((sequence, id), value)| (id, *sequence, value))
+    }
+
+    /// Fold all events with a sequence number up to `sequence` into the compacted prefix.
+    ///
+    /// Only compact through a sequence number that every replica has seen all events up to,
+    /// e.g. the minimum of all replicas' acknowledged [`last_sequence`](Self::last_sequence)
+    /// values under causal delivery. Events with a lower sequence number that arrive later are
+    /// rejected by [`apply_operation`](Self::apply_operation).
+    pub fn compact_through(&mut self, sequence: u64) {
+        while let Some(entry) = self.events.first_entry() {
+            if entry.key().0 > sequence {
+                break;
+            }
+            self.compacted.push(entry.remove());
+        }
+        self.compacted_through = self.compacted_through.max(sequence);
+    }
+
+    /// Capture the current state, e.g. to persist it or to bootstrap another replica.
+    #[must_use]
+    pub fn to_snapshot(&self) -> EventLogSnapshot<Id, T> {
+        EventLogSnapshot {
+            compacted: self.compacted.clone(),
+            compacted_through: self.compacted_through,
+            events: self
+                .events
+                .iter()
+                .map(|((sequence, id), value)| AppendOperation {
+                    id: id.clone(),
+                    sequence: *sequence,
+                    value: value.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Restore a log from a snapshot created by [`to_snapshot`](Self::to_snapshot).
+    ///
+    /// Events that belong into the compacted prefix of the snapshot are ignored.
+    #[must_use]
+    pub fn from_snapshot(snapshot: EventLogSnapshot<Id, T>) -> Self {
+        let compacted_through = snapshot.compacted_through;
+        let events = snapshot
+            .events
+            .into_iter()
+            .filter(|event| event.sequence > compacted_through)
+            .map(|event| ((event.sequence, event.id), event.value))
+            .collect();
+        Self {
+            compacted: snapshot.compacted,
+            compacted_through,
+            events,
+        }
+    }
+}
+
+/// The state of an [`EventLog`], see [`EventLog::to_snapshot`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventLogSnapshot<Id, T> {
+    /// Values of the compacted prefix, in log order.
+    pub compacted: Vec<T>,
+    /// The highest sequence number in the compacted prefix.
+    pub compacted_through: u64,
+    /// Events after the compacted prefix, in log order.
+    pub events: Vec<AppendOperation<Id, T>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(log: &EventLog<u32, &'static str>) -> Vec<&'static str> {
+        log.iter().copied().collect()
+    }
+
+    #[test]
+    fn concurrent_appends_converge_in_causal_order() {
+        let mut alice = EventLog::new();
+        let mut bob = EventLog::new();
+
+        let hello = alice.append(1, "hello");
+        bob.apply_operation(hello).unwrap();
+        // Both reply concurrently after having seen "hello".
+        let from_bob = bob.append(2, "hi alice");
+        let from_alice = alice.append(3, "anyone there?");
+        alice.apply_operation(from_bob.clone()).unwrap();
+        bob.apply_operation(from_alice).unwrap();
+        // Re-delivery is a no-op.
+        bob.apply_operation(from_bob).unwrap();
+
+        assert_eq!(alice, bob);
+        assert_eq!(values(&alice), vec!["hello", "hi alice", "anyone there?"]);
+
+        let reply = bob.append(4, "yes");
+        assert_eq!(reply.sequence, 3);
+        alice.apply_operation(reply).unwrap();
+        assert_eq!(alice, bob);
+        assert_eq!(alice.len(), 4);
+    }
+
+    #[test]
+    fn compaction_keeps_order_and_rejects_late_events() {
+        let mut log = EventLog::new();
+        log.append(1, "a");
+        log.append(2, "b");
+        let late = AppendOperation {
+            id: 7,
+            sequence: 2,
+            value: "late",
+        };
+        log.append(3, "c");
+
+        log.compact_through(2);
+        assert_eq!(values(&log), vec!["a", "b", "c"]);
+        assert_eq!(log.iter_events().count(), 1);
+        assert_eq!(log.apply_operation(late.clone()), Err(late));
+        assert_eq!(log.append_operation(4, "d").sequence, 4);
+
+        let restored = EventLog::from_snapshot(log.to_snapshot());
+        assert_eq!(restored, log);
+
+        log.compact_through(10);
+        assert_eq!(log.last_sequence(), 10);
+        assert_eq!(values(&log), vec!["a", "b", "c"]);
+    }
+}
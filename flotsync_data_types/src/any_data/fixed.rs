@@ -0,0 +1,493 @@
+//! Fixed-capacity variants of the list and register CRDTs for memory-constrained peers.
+//!
+//! [`FixedLinearList`] and [`FixedLatestValueWins`] take their capacity as a const generic
+//! and allocate all of their node storage once, when they are created. A write that would
+//! need more room is refused with [`CapacityExceeded`] or
+//! [`FixedApplyFailure::CapacityExceeded`], handing the value or operation back to the caller,
+//! so the storage never grows after construction.
+//!
+//! Capacity counts every element ever inserted, not only the visible ones: deleted list
+//! elements and superseded register values stay behind as nodes, because concurrent
+//! operations from other replicas may still be anchored on them.
+
+use super::{LinearLatestValueWins, UpdateOperation};
+use crate::{
+    IntegrityError,
+    linear_data::{ApplyFailure, DataOperation, LinearData, VecLinearData},
+};
+use snafu::prelude::*;
+use std::{fmt, hash::Hash};
+
+/// A local write refused because the fixed capacity is exhausted.
+#[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+#[snafu(display("The fixed capacity of {capacity} elements is exhausted."))]
+pub struct CapacityExceeded<V> {
+    /// The capacity of the refusing CRDT.
+    pub capacity: usize,
+    /// The refused value.
+    pub value: V,
+}
+
+/// The reason an operation could not be applied to a fixed-capacity CRDT.
+#[derive(Debug)]
+pub enum FixedApplyFailure<Op> {
+    /// The operation would insert an element beyond the fixed capacity.
+    ///
+    /// The state was left unchanged.
+    CapacityExceeded { capacity: usize, operation: Op },
+    /// The underlying CRDT could not apply the operation.
+    Failed { source: ApplyFailure<Op> },
+}
+impl<Op> FixedApplyFailure<Op> {
+    /// Returns the operation that failed to apply.
+    pub fn into_operation(self) -> Op {
+        match self {
+            Self::CapacityExceeded { operation, .. } => operation,
+            Self::Failed { source } => source.into_operation(),
+        }
+    }
+}
+
+/// A convergent linear list CRDT that holds at most `N` elements.
+///
+/// Unlike [[`LinearList`](super::list::LinearList)], every element is its own node, so the
+/// capacity is exact: the list accepts `N` inserts over its lifetime, whether or not the
+/// inserted elements are deleted later. Deletes never consume capacity.
+///
+/// # Example
+///
+/// ```rust
+/// use flotsync_data_types::any_data::FixedLinearList;
+///
+/// let mut list: FixedLinearList<u32, char, 2> = FixedLinearList::new(0, 1);
+/// list.append(2, 'a').unwrap();
+/// list.append(3, 'b').unwrap();
+///
+/// let overflow = list.append(4, 'c').unwrap_err();
+/// assert_eq!(overflow.value, 'c');
+/// assert_eq!(list.iter().copied().collect::<String>(), "ab");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedLinearList<Id, T, const N: usize> {
+    data: VecLinearData<Id, T>,
+}
+impl<Id, T, const N: usize> FixedLinearList<Id, T, N>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    T: fmt::Debug,
+{
+    /// The maximum number of elements this list can ever hold.
+    pub const CAPACITY: usize = N;
+
+    /// Create an empty list, allocating room for all `N` elements up front.
+    ///
+    /// Using a capacity of zero fails to compile.
+    pub fn new(begin_id: Id, end_id: Id) -> Self {
+        const {
+            assert!(
+                N > 0,
+                "A fixed-capacity list needs room for at least one element."
+            );
+        }
+        let mut data = VecLinearData::new(begin_id, end_id);
+        data.reserve_exact_nodes(N);
+        Self { data }
+    }
+
+    /// Number of visible elements in the list.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether the list contains no visible elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The number of inserts this list can still accept.
+    #[must_use]
+    pub fn remaining_capacity(&self) -> usize {
+        N - self.element_count()
+    }
+
+    /// Iterate over visible values in list order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter_values()
+    }
+
+    /// Append `value` at the end.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value if the list is full.
+    pub fn append(&mut self, id: Id, value: T) -> Result<(), CapacityExceeded<T>> {
+        ensure!(
+            self.remaining_capacity() > 0,
+            CapacityExceededSnafu { capacity: N, value }
+        );
+        self.data.append(id, value);
+        Ok(())
+    }
+
+    /// Delete the visible element at `position`.
+    ///
+    /// Returns `None` if the position is out of bounds.
+    pub fn delete_at(&mut self, position: usize) -> Option<&T> {
+        let node_ids = self.data.ids_at_pos(position)?;
+        self.data.delete(&node_ids.current)
+    }
+
+    /// Build an append operation for replication.
+    pub fn append_operation(&self, id: Id, value: T) -> DataOperation<Id, T> {
+        self.data.ids_before_end().insert_operation(id, value)
+    }
+
+    /// Build an insert operation at `position` for replication.
+    ///
+    /// Inserting at `self.len()` is append-like.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value for out-of-bounds positions.
+    pub fn insert_operation_at(
+        &self,
+        position: usize,
+        id: Id,
+        value: T,
+    ) -> Result<DataOperation<Id, T>, T> {
+        let ids = if position == self.len() {
+            self.data.ids_before_end()
+        } else if let Some(node_ids) = self.data.ids_at_pos(position) {
+            node_ids.before()
+        } else {
+            return Err(value);
+        };
+        Ok(ids.insert_operation(id, value))
+    }
+
+    /// Build a delete operation for the value at `position`.
+    ///
+    /// Returns `None` if the position is out of bounds.
+    #[must_use]
+    pub fn delete_operation_at(&self, position: usize) -> Option<DataOperation<Id, T>> {
+        let node_ids = self.data.ids_at_pos(position)?;
+        Some(DataOperation::Delete {
+            start: node_ids.current,
+            end: None,
+        })
+    }
+
+    /// Apply a replicated operation received from some replica (including ourselves).
+    ///
+    /// Inserts of ids that are already present are passed on unchecked, so that they are
+    /// reported as rejected rather than as a capacity overflow.
+    ///
+    /// # Errors
+    ///
+    /// The original operation is returned unchanged on failure.
+    pub fn apply_operation(
+        &mut self,
+        operation: DataOperation<Id, T>,
+    ) -> Result<(), FixedApplyFailure<DataOperation<Id, T>>> {
+        if let DataOperation::Insert { ref id, .. } = operation
+            && self.remaining_capacity() == 0
+            && !self.data.iter_ids().any(|existing| existing == id)
+        {
+            return Err(FixedApplyFailure::CapacityExceeded {
+                capacity: N,
+                operation,
+            });
+        }
+        self.data
+            .apply_operation(operation)
+            .map_err(|source| FixedApplyFailure::Failed { source })
+    }
+
+    /// Validate the internal CRDT structure and cached visible-length invariants.
+    ///
+    /// # Errors
+    ///
+    /// See `IntegrityError` for failure conditions.
+    pub fn validate_integrity(&self) -> Result<(), IntegrityError> {
+        self.data.validate_integrity()
+    }
+
+    /// The number of inserted elements, including deleted ones.
+    fn element_count(&self) -> usize {
+        self.data.node_count() - 2
+    }
+}
+
+/// A *latest value wins* register that holds at most `N` written values.
+///
+/// This behaves exactly like [[`LinearLatestValueWins`]], but allocates room for all `N`
+/// values when it is created. The initial value counts towards the capacity, so the register
+/// accepts `N - 1` updates over its lifetime.
+///
+/// # Example
+///
+/// ```rust
+/// use flotsync_data_types::any_data::FixedLatestValueWins;
+///
+/// let mut register: FixedLatestValueWins<u32, u8, 2> = FixedLatestValueWins::new(0, [0, 1, 2]);
+/// register.update(3, 1).unwrap();
+/// assert_eq!(*register.content(), 1);
+///
+/// assert!(register.update(4, 2).is_err());
+/// assert_eq!(*register.content(), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedLatestValueWins<Id, T, const N: usize> {
+    register: LinearLatestValueWins<Id, T>,
+}
+impl<Id, T, const N: usize> FixedLatestValueWins<Id, T, N>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    T: Clone + fmt::Debug,
+{
+    /// The maximum number of values this register can ever hold.
+    pub const CAPACITY: usize = N;
+
+    /// Create a register holding `initial_value`, allocating room for all `N` values up front.
+    ///
+    /// Using a capacity of zero fails to compile.
+    pub fn new(initial_value: T, ids: [Id; 3]) -> Self {
+        const {
+            assert!(
+                N > 0,
+                "A fixed-capacity register needs room for its initial value."
+            );
+        }
+        let mut register = LinearLatestValueWins::new(initial_value, ids);
+        register.reserve_exact_versions(N - 1);
+        Self { register }
+    }
+
+    /// Returns the current value of this CRDT.
+    #[must_use]
+    pub fn content(&self) -> &T {
+        self.register.content()
+    }
+
+    /// The number of updates this register can still accept.
+    #[must_use]
+    pub fn remaining_capacity(&self) -> usize {
+        N - self.register.version_count()
+    }
+
+    /// Update the current value of this CRDT to `new_value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value if the register is full.
+    pub fn update(&mut self, id: Id, new_value: T) -> Result<(), CapacityExceeded<T>> {
+        let operation = self.update_operation(id, new_value)?;
+        self.register
+            .apply_operation(operation)
+            .expect("Direct updates must succeed.");
+        Ok(())
+    }
+
+    /// Produce an operation that can be sent to other replicas and represents an attempt to
+    /// update the current value of this CRDT to `new_value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value if the register is full, since the operation could not be applied
+    /// locally.
+    pub fn update_operation(
+        &self,
+        id: Id,
+        new_value: T,
+    ) -> Result<UpdateOperation<Id, T>, CapacityExceeded<T>> {
+        ensure!(
+            self.remaining_capacity() > 0,
+            CapacityExceededSnafu {
+                capacity: N,
+                value: new_value,
+            }
+        );
+        Ok(self.register.update_operation(id, new_value))
+    }
+
+    /// Apply an update operation received from some replica (including ourselves).
+    ///
+    /// # Errors
+    ///
+    /// The original operation is returned unchanged on failure.
+    pub fn apply_operation(
+        &mut self,
+        operation: UpdateOperation<Id, T>,
+    ) -> Result<(), FixedApplyFailure<UpdateOperation<Id, T>>> {
+        if self.remaining_capacity() == 0 && !self.register.contains_version(&operation.id) {
+            return Err(FixedApplyFailure::CapacityExceeded {
+                capacity: N,
+                operation,
+            });
+        }
+        self.register
+            .apply_operation(operation)
+            .map_err(|operation| FixedApplyFailure::Failed {
+                source: ApplyFailure::Rejected { operation },
+            })
+    }
+
+    /// Returns all values that were at some point part of this CRDT.
+    ///
+    /// Conceptually they are returned newest to oldest, accounting for concurrency.
+    pub fn all_values(&self) -> impl Iterator<Item = &T> {
+        self.register.all_values()
+    }
+
+    /// Validate the internal CRDT structure and cached visible-value invariants.
+    ///
+    /// # Errors
+    ///
+    /// See `IntegrityError` for failure conditions.
+    pub fn validate_integrity(&self) -> Result<(), IntegrityError> {
+        self.register.validate_integrity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ids::TestIdGenerator;
+    use itertools::Itertools;
+
+    type Id = u32;
+
+    fn new_list<const N: usize>(id_generator: &mut TestIdGenerator) -> FixedLinearList<Id, u8, N> {
+        let [begin_id, end_id] = id_generator.next_array().unwrap();
+        FixedLinearList::new(begin_id, end_id)
+    }
+
+    #[test]
+    fn list_refuses_appends_beyond_capacity() {
+        let mut id_generator = TestIdGenerator::new();
+        let mut list = new_list::<2>(&mut id_generator);
+
+        list.append(id_generator.next().unwrap(), 1).unwrap();
+        list.append(id_generator.next().unwrap(), 2).unwrap();
+        assert_eq!(list.remaining_capacity(), 0);
+
+        let overflow = list.append(id_generator.next().unwrap(), 3).unwrap_err();
+        assert_eq!(
+            overflow,
+            CapacityExceeded {
+                capacity: 2,
+                value: 3
+            }
+        );
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        list.validate_integrity().unwrap();
+    }
+
+    #[test]
+    fn list_deletes_do_not_free_capacity() {
+        let mut id_generator = TestIdGenerator::new();
+        let mut list = new_list::<1>(&mut id_generator);
+
+        list.append(id_generator.next().unwrap(), 1).unwrap();
+        assert_eq!(list.delete_at(0), Some(&1));
+        assert!(list.is_empty());
+        assert_eq!(list.remaining_capacity(), 0);
+        assert!(list.append(id_generator.next().unwrap(), 2).is_err());
+    }
+
+    #[test]
+    fn list_remote_overflow_returns_operation_and_keeps_state() {
+        let mut id_generator = TestIdGenerator::new();
+        let mut list = new_list::<1>(&mut id_generator);
+        let mut remote = list.clone();
+
+        let local_op = list.append_operation(id_generator.next().unwrap(), 1);
+        let remote_op = remote.append_operation(id_generator.next().unwrap(), 2);
+        list.apply_operation(local_op.clone()).unwrap();
+        remote.apply_operation(remote_op.clone()).unwrap();
+
+        let before = list.clone();
+        let failure = list.apply_operation(remote_op.clone()).unwrap_err();
+        assert!(matches!(
+            failure,
+            FixedApplyFailure::CapacityExceeded { capacity: 1, .. }
+        ));
+        assert_eq!(failure.into_operation(), remote_op);
+        assert_eq!(list, before);
+
+        // Redelivering an already applied insert is still a plain rejection.
+        assert!(matches!(
+            list.apply_operation(local_op),
+            Err(FixedApplyFailure::Failed {
+                source: ApplyFailure::Rejected { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn list_replicas_converge_within_capacity() {
+        let mut id_generator = TestIdGenerator::new();
+        let base = new_list::<4>(&mut id_generator);
+        let mut a = base.clone();
+        let mut b = base;
+
+        let op_a = a
+            .insert_operation_at(0, id_generator.next().unwrap(), 1)
+            .unwrap();
+        let op_b = b.append_operation(id_generator.next().unwrap(), 2);
+        a.apply_operation(op_a.clone()).unwrap();
+        b.apply_operation(op_b.clone()).unwrap();
+        a.apply_operation(op_b).unwrap();
+        b.apply_operation(op_a).unwrap();
+
+        let delete = a.delete_operation_at(0).unwrap();
+        a.apply_operation(delete.clone()).unwrap();
+        b.apply_operation(delete).unwrap();
+
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 1);
+        assert_eq!(a.remaining_capacity(), 2);
+    }
+
+    #[test]
+    fn register_initial_value_counts_towards_capacity() {
+        let mut id_generator = TestIdGenerator::new();
+        let mut register: FixedLatestValueWins<Id, u64, 3> =
+            FixedLatestValueWins::new(0, id_generator.next_array().unwrap());
+        assert_eq!(register.remaining_capacity(), 2);
+
+        register.update(id_generator.next().unwrap(), 1).unwrap();
+        register.update(id_generator.next().unwrap(), 2).unwrap();
+        let overflow = register
+            .update(id_generator.next().unwrap(), 3)
+            .unwrap_err();
+        assert_eq!(overflow.value, 3);
+        assert_eq!(*register.content(), 2);
+        assert_eq!(
+            register.all_values().copied().collect::<Vec<_>>(),
+            vec![2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn register_remote_overflow_returns_operation() {
+        let mut id_generator = TestIdGenerator::new();
+        let base: FixedLatestValueWins<Id, u64, 2> =
+            FixedLatestValueWins::new(0, id_generator.next_array().unwrap());
+        let mut a = base.clone();
+        let b = base;
+
+        a.update(id_generator.next().unwrap(), 1).unwrap();
+        let remote_op = b.update_operation(id_generator.next().unwrap(), 2).unwrap();
+
+        let failure = a.apply_operation(remote_op.clone()).unwrap_err();
+        assert!(matches!(
+            failure,
+            FixedApplyFailure::CapacityExceeded { capacity: 2, .. }
+        ));
+        assert_eq!(failure.into_operation(), remote_op);
+        assert_eq!(*a.content(), 1);
+    }
+}
@@ -119,7 +119,15 @@ where
     ) -> Result<(), UpdateOperation<Id, T>> {
         self.data
             .apply_operation(operation.into())
-            .map_err(|op| UpdateOperation::try_from(op).expect("This must succeed"))
+            .map_err(|failure| {
+                UpdateOperation::try_from(failure.into_operation()).expect("This must succeed")
+            })
+    }
+
+    /// Number of stored nodes, including boundaries and every value ever written.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.data.node_count()
     }
 
     /// Returns all values that we at some point part of this CRDT.
@@ -167,6 +175,22 @@ where
     pub fn validate_integrity(&self) -> Result<(), IntegrityError> {
         self.data.validate_integrity()
     }
+
+    /// The number of values ever written to this register, including the initial one.
+    pub(crate) fn version_count(&self) -> usize {
+        // Versions are never deleted, so every non-boundary node is one version.
+        self.data.node_count() - 2
+    }
+
+    /// Reserve room for exactly `additional` more versions.
+    pub(crate) fn reserve_exact_versions(&mut self, additional: usize) {
+        self.data.reserve_exact_nodes(additional);
+    }
+
+    /// Whether a version with `id` has already been written.
+    pub(crate) fn contains_version(&self, id: &Id) -> bool {
+        self.data.iter_ids().any(|existing| existing == id)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -188,6 +212,7 @@ impl<Id, T> TryFrom<DataOperation<Id, T>> for UpdateOperation<Id, T> {
             value
         }),
             DataOperation::Delete { .. } => UnsupportedOperationVariantSnafu {explanation: Cow::Borrowed( "UpdateOperation can only be created from DataOperation::Insert variants, but got DataOperation::Delete")}.fail(),
+            DataOperation::DeleteRanges { .. } => UnsupportedOperationVariantSnafu {explanation: Cow::Borrowed( "UpdateOperation can only be created from DataOperation::Insert variants, but got DataOperation::DeleteRanges")}.fail(),
         }
     }
 }
@@ -205,7 +230,7 @@ impl<Id, T> From<UpdateOperation<Id, T>> for DataOperation<Id, T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::linear_data::tests::{TestIdGenerator, interleavings_with_local_order};
+    use crate::test_support::{ids::TestIdGenerator, schedules::interleavings_with_local_order};
     use itertools::Itertools;
 
     type Id = u32;
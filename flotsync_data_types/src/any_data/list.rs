@@ -2,7 +2,9 @@ use crate::{
     IntegrityError,
     InternalError,
     InternalSnafu,
+    LinearDataStats,
     linear_data::{
+        ApplyFailure,
         Composite,
         DataOperation,
         IdGeneratorWithIndex,
@@ -23,6 +25,7 @@ use snafu::prelude::*;
 use std::{fmt, hash::Hash, ops::RangeBounds};
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum DiffError {
     #[snafu(display(
         "A single row update cannot address the required list inserts with one operation id."
@@ -64,7 +67,12 @@ impl<Id, T> LinearListDiff<Id, T> {
     pub fn num_delete_operations(&self) -> usize {
         self.operations
             .iter()
-            .filter(|op| matches!(op, DataOperation::Delete { .. }))
+            .filter(|op| {
+                matches!(
+                    op,
+                    DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. }
+                )
+            })
             .count()
     }
 
@@ -72,7 +80,7 @@ impl<Id, T> LinearListDiff<Id, T> {
     pub fn values_inserted(&self) -> impl Iterator<Item = &[T]> {
         self.operations.iter().filter_map(|op| match op {
             DataOperation::Insert { value, .. } => Some(value.as_slice()),
-            DataOperation::Delete { .. } => None,
+            DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => None,
         })
     }
 
@@ -106,7 +114,6 @@ where
         Vec::with_capacity(basic_diff.len());
     let mut operation_ids = std::iter::once(operation_id.clone());
     let mut id_generator = IdGeneratorWithIndex::new(&mut operation_ids);
-    let mut pending_reserved_indices: Option<usize> = None;
 
     for change in basic_diff {
         match change {
@@ -128,7 +135,6 @@ where
                     old_index,
                     insert_values,
                     &mut id_generator,
-                    &mut pending_reserved_indices,
                     &mut operations,
                 )?;
             }
@@ -148,7 +154,6 @@ where
                     insert_at,
                     insert_values,
                     &mut id_generator,
-                    &mut pending_reserved_indices,
                     &mut operations,
                 )?;
             }
@@ -195,7 +200,6 @@ fn append_insert_operation<Id, T, IdIter>(
     position: usize,
     values: Vec<T>,
     id_generator: &mut IdGeneratorWithIndex<'_, IdIter>,
-    pending_reserved_indices: &mut Option<usize>,
     operations: &mut Vec<DataOperation<IdWithIndex<Id>, Vec<T>>>,
 ) -> Result<(), DiffError>
 where
@@ -207,7 +211,7 @@ where
         return Ok(());
     }
 
-    let insert_id = reserve_insert_id(id_generator, pending_reserved_indices, values.len())?;
+    let insert_id = reserve_insert_id(id_generator, values.len())?;
     let link_ids = resolve_insert_link_ids(base, position)?;
     operations.push(link_ids.insert_operation(insert_id, values));
     Ok(())
@@ -215,22 +219,15 @@ where
 
 fn reserve_insert_id<Id, IdIter>(
     id_generator: &mut IdGeneratorWithIndex<'_, IdIter>,
-    pending_reserved_indices: &mut Option<usize>,
     value_count: usize,
 ) -> Result<IdWithIndex<Id>, DiffError>
 where
     Id: Clone,
     IdIter: Iterator<Item = Id>,
 {
-    let insert_id = if let Some(skip) = pending_reserved_indices.take() {
-        id_generator.nth(skip).context(IndexExhaustedSnafu)?
-    } else {
-        id_generator.next().context(IndexExhaustedSnafu)?
-    };
-    ensure!(insert_id.can_address(value_count), IndexExhaustedSnafu);
-    *pending_reserved_indices = Some(value_count.saturating_sub(1));
-
-    Ok(insert_id)
+    id_generator
+        .reserve(value_count)
+        .context(IndexExhaustedSnafu)
 }
 
 fn resolve_insert_link_ids<Id, T>(
@@ -381,6 +378,14 @@ where
         self.data.is_empty()
     }
 
+    /// Number of stored nodes, including boundaries and deleted elements.
+    ///
+    /// Unlike [`len`](Self::len), this bounds the memory the list occupies.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.data.node_count()
+    }
+
     /// Iterate over visible values in list order.
     #[must_use]
     pub fn iter(&self) -> LinearListIter<'_, Id, T> {
@@ -389,6 +394,15 @@ where
         }
     }
 
+    /// Iterate over visible values in runs with consecutive ids.
+    ///
+    /// Each run comes with the id of its first value.
+    pub fn iter_with_ids(&self) -> impl Iterator<Item = (IdWithIndex<Id>, &[T])> {
+        self.data
+            .iter_with_ids()
+            .map(|(id, chunk)| (id.clone(), chunk.values.as_slice()))
+    }
+
     /// Encode a stable, ordered snapshot stream of the current in-memory state.
     ///
     /// # Errors
@@ -436,6 +450,20 @@ where
         self.data.validate_integrity()
     }
 
+    /// Conflict and storage statistics, e.g. to decide when compaction should run.
+    #[must_use]
+    pub fn stats(&self) -> LinearDataStats {
+        self.data.stats()
+    }
+
+    /// Store parts of the same insert that were split apart as single nodes again.
+    ///
+    /// Returns the number of nodes that were merged away. See
+    /// [`VecCoalescedLinearData::defragment`](crate::linear_data::VecCoalescedLinearData::defragment).
+    pub fn defragment(&mut self) -> usize {
+        self.data.defragment()
+    }
+
     /// Append one chunk of values at the end.
     ///
     /// Empty chunks are ignored.
@@ -721,9 +749,11 @@ where
         operation: ListOperation<Id, T>,
     ) -> Result<(), ListOperation<Id, T>> {
         let op = operation.op.map_value(ListChunk::new);
-        self.data.apply_operation(op).map_err(|op| ListOperation {
-            op: op.map_value(ListChunk::unwrap),
-        })
+        self.data
+            .apply_operation(op)
+            .map_err(|failure| ListOperation {
+                op: failure.into_operation().map_value(ListChunk::unwrap),
+            })
     }
 }
 
@@ -779,11 +809,11 @@ where
     fn apply_operation(
         &mut self,
         operation: DataOperation<Self::Id, Vec<T>>,
-    ) -> Result<(), DataOperation<Self::Id, Vec<T>>> {
+    ) -> Result<(), ApplyFailure<DataOperation<Self::Id, Vec<T>>>> {
         let op = operation.map_value(ListChunk::new);
         self.data
             .apply_operation(op)
-            .map_err(|op| op.map_value(ListChunk::unwrap))
+            .map_err(|failure| failure.map_operation(|op| op.map_value(ListChunk::unwrap)))
     }
 }
 
@@ -860,8 +890,10 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::linear_data::tests::{TestIdGenerator, interleavings_with_local_order};
+    use crate::test_support::{ids::TestIdGenerator, schedules::interleavings_with_local_order};
+    use flotsync_core::test_support::{ChurnStep, churn_schedule_strategy};
     use itertools::Itertools;
+    use proptest::prelude::*;
 
     type Id = u32;
     type Value = i32;
@@ -927,7 +959,7 @@ mod tests {
             .into_iter()
             .filter_map(|operation| match operation {
                 DataOperation::Insert { id, .. } => Some(id.index),
-                DataOperation::Delete { .. } => None,
+                DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => None,
             })
             .collect();
         insert_indices.sort_unstable();
@@ -956,7 +988,7 @@ mod tests {
             .into_iter()
             .filter_map(|operation| match operation {
                 DataOperation::Insert { id, .. } => Some(id.index),
-                DataOperation::Delete { .. } => None,
+                DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => None,
             })
             .collect::<Vec<_>>();
 
@@ -1056,6 +1088,27 @@ mod tests {
         assert_eq!(direct.iter().copied().collect::<Vec<_>>(), vec![1, 5]);
     }
 
+    #[test]
+    fn iter_with_ids_yields_visible_runs() {
+        let mut list = new_list([1, 2, 3, 4]);
+        let op = list
+            .insert_operation_at(2, IdWithIndex::zero(3), [9])
+            .unwrap()
+            .unwrap();
+        list.apply_operation(op).unwrap();
+        list.ids_in_range(0..=0).unwrap().delete(&mut list).unwrap();
+
+        let runs: Vec<_> = list.iter_with_ids().collect();
+        assert_eq!(
+            runs,
+            vec![
+                (IdWithIndex { id: 0, index: 2 }, &[2][..]),
+                (IdWithIndex::zero(3), &[9][..]),
+                (IdWithIndex { id: 0, index: 3 }, &[3, 4][..]),
+            ]
+        );
+    }
+
     #[test]
     fn concurrent_inserts_converge_independent_of_delivery_order() {
         let base = new_list([0]);
@@ -1278,4 +1331,82 @@ mod tests {
             r2.iter().copied().collect::<Vec<_>>()
         );
     }
+
+    /// A simulated group member: its list and the operations sent to it but not yet applied.
+    #[derive(Clone)]
+    struct Replica {
+        list: LinearList<Id, Value>,
+        inbox: Vec<ListOperation<Id, Value>>,
+    }
+
+    impl Replica {
+        /// Apply all operations in the inbox.
+        ///
+        /// Inboxes are in creation order, which is a causal order, as the list requires.
+        fn drain_inbox(&mut self) {
+            for operation in std::mem::take(&mut self.inbox) {
+                self.list.apply_operation(operation).unwrap();
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn documents_converge_among_current_members_under_churn(
+            schedule in churn_schedule_strategy(30)
+        ) {
+            let base = Replica {
+                list: new_list([0]),
+                inbox: Vec::new(),
+            };
+            let mut replicas = vec![base.clone(), base.clone(), base];
+            let mut ids = TestIdGenerator::without_ids(std::iter::once(0));
+            for step in schedule {
+                match step {
+                    ChurnStep::Write { writer } => {
+                        let position = writer % replicas.len();
+                        let id = ids.next().unwrap();
+                        let local = &mut replicas[position].list;
+                        let at = writer % (local.len() + 1);
+                        let value = Value::try_from(id).unwrap();
+                        let operation = local
+                            .insert_operation_at(at, IdWithIndex::zero(id), [value])
+                            .unwrap()
+                            .unwrap();
+                        local.apply_operation(operation.clone()).unwrap();
+                        for (index, replica) in replicas.iter_mut().enumerate() {
+                            if index != position {
+                                replica.inbox.push(operation.clone());
+                            }
+                        }
+                    }
+                    ChurnStep::Leave { .. } if replicas.len() == 1 => {}
+                    change => {
+                        // Like a view change, a membership change first delivers everything
+                        // that is in flight, so edits within an epoch are concurrent.
+                        for replica in &mut replicas {
+                            replica.drain_inbox();
+                        }
+                        match change {
+                            // Joiners bootstrap from the state of a current member.
+                            ChurnStep::Join => replicas.push(replicas[0].clone()),
+                            ChurnStep::Leave { member } => {
+                                let leaving = member % replicas.len();
+                                replicas.remove(leaving);
+                            }
+                            ChurnStep::Write { .. } => unreachable!(),
+                        }
+                    }
+                }
+            }
+
+            for replica in &mut replicas {
+                replica.drain_inbox();
+            }
+            let first = &replicas[0].list;
+            for replica in &replicas[1..] {
+                prop_assert_eq!(first, &replica.list);
+            }
+        }
+    }
 }
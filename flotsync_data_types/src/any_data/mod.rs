@@ -1,3 +1,7 @@
+pub mod bytes;
+pub mod event_log;
+mod fixed;
+pub use fixed::*;
 mod latest_value;
 pub mod list;
 pub use latest_value::*;
@@ -0,0 +1,339 @@
+//! Short-lived documents that are only kept in memory, such as a meeting scratchpad.
+//!
+//! An [`EphemeralDocument`] wraps a [`ReplicatedDocument`] together with the time it expires.
+//! The expiry is an absolute time that is part of the replicated state, so every member drops
+//! the document at the same moment, no matter when it received it. Members can push the expiry
+//! back with [`EphemeralOperation::ExtendUntil`]; concurrent extensions converge to the latest
+//! requested time.
+//!
+//! [`EphemeralDocuments`] holds the ephemeral documents of one replica. It never writes them to a
+//! store, so neither their snapshots nor their changes outlive the process, and
+//! [`drop_expired`](EphemeralDocuments::drop_expired) discards each document with all of its
+//! retained history once it expires. Changes that arrive for an expired document are ignored.
+use crate::versioned::{ReplicatedDocument, VersionedChange, VersionedDoc, VersionedDocError};
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    time::{Duration, SystemTime},
+};
+
+/// A document that is dropped by every member once `expires_at` has passed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EphemeralDocument<D> {
+    document: D,
+    expires_at: SystemTime,
+}
+
+impl<D> EphemeralDocument<D> {
+    /// Wrap `document`, which expires at `expires_at`.
+    ///
+    /// All members must create the document with the same `expires_at`.
+    pub fn new(document: D, expires_at: SystemTime) -> Self {
+        Self {
+            document,
+            expires_at,
+        }
+    }
+
+    /// Wrap `document`, which expires `ttl` after `now`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the expiry cannot be represented as a [`SystemTime`].
+    pub fn with_ttl(document: D, ttl: Duration, now: SystemTime) -> Self {
+        let expires_at = now
+            .checked_add(ttl)
+            .expect("ephemeral document expiry must be representable");
+        Self::new(document, expires_at)
+    }
+
+    pub fn document(&self) -> &D {
+        &self.document
+    }
+
+    pub fn expires_at(&self) -> SystemTime {
+        self.expires_at
+    }
+
+    /// Whether the document has expired at `now`.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// An operation on an [`EphemeralDocument`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum EphemeralOperation<Op> {
+    /// Change the wrapped document.
+    Change(Op),
+    /// Move the expiry to `expires_at`, unless the document already expires later.
+    ExtendUntil { expires_at: SystemTime },
+}
+
+impl<D> ReplicatedDocument for EphemeralDocument<D>
+where
+    D: ReplicatedDocument,
+{
+    const DOCUMENT_TYPE: &'static str = D::DOCUMENT_TYPE;
+
+    type Operation = EphemeralOperation<D::Operation>;
+    type Rejection = D::Rejection;
+
+    fn apply_operation(&mut self, operation: Self::Operation) -> Result<(), Self::Rejection> {
+        match operation {
+            EphemeralOperation::Change(operation) => self.document.apply_operation(operation),
+            EphemeralOperation::ExtendUntil { expires_at } => {
+                self.expires_at = self.expires_at.max(expires_at);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The in-memory [`EphemeralDocument`]s of one replica, by key.
+#[derive(Clone)]
+pub struct EphemeralDocuments<K, D>
+where
+    D: ReplicatedDocument,
+{
+    documents: HashMap<K, VersionedDoc<EphemeralDocument<D>>>,
+}
+
+impl<K, D> EphemeralDocuments<K, D>
+where
+    K: Clone + Eq + Hash,
+    D: ReplicatedDocument,
+{
+    pub fn new() -> Self {
+        Self {
+            documents: HashMap::new(),
+        }
+    }
+
+    /// Keep `document` under `key`, replacing and returning any document already kept there.
+    pub fn insert(
+        &mut self,
+        key: K,
+        document: VersionedDoc<EphemeralDocument<D>>,
+    ) -> Option<VersionedDoc<EphemeralDocument<D>>> {
+        self.documents.insert(key, document)
+    }
+
+    /// The document under `key`, unless it has expired at `now`.
+    pub fn get(&self, key: &K, now: SystemTime) -> Option<&VersionedDoc<EphemeralDocument<D>>> {
+        self.documents
+            .get(key)
+            .filter(|document| !document.document().is_expired(now))
+    }
+
+    /// The document under `key` for local changes, unless it has expired at `now`.
+    pub fn get_mut(
+        &mut self,
+        key: &K,
+        now: SystemTime,
+    ) -> Option<&mut VersionedDoc<EphemeralDocument<D>>> {
+        self.documents
+            .get_mut(key)
+            .filter(|document| !document.document().is_expired(now))
+    }
+
+    /// Apply remote changes to the document under `key`, see [`VersionedDoc::apply_remote`].
+    ///
+    /// Changes for a document that is unknown or has expired at `now` are ignored.
+    ///
+    /// Returns the number of newly applied changes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a change cannot be applied, see [`VersionedDoc::apply_remote`].
+    pub fn apply_remote<I>(
+        &mut self,
+        key: &K,
+        batch: I,
+        now: SystemTime,
+    ) -> Result<usize, VersionedDocError<D::Rejection>>
+    where
+        I: IntoIterator<Item = VersionedChange<EphemeralOperation<D::Operation>>>,
+    {
+        match self.get_mut(key, now) {
+            Some(document) => document.apply_remote(batch),
+            None => Ok(0),
+        }
+    }
+
+    /// Remove every document that has expired at `now`, including its retained history.
+    ///
+    /// Returns the keys of the removed documents.
+    pub fn drop_expired(&mut self, now: SystemTime) -> Vec<K> {
+        let expired: Vec<K> = self
+            .documents
+            .iter()
+            .filter(|(_, document)| document.document().is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.documents.remove(key);
+        }
+        expired
+    }
+
+    /// The earliest time any kept document expires, for scheduling the next
+    /// [`drop_expired`](Self::drop_expired).
+    pub fn next_expiry(&self) -> Option<SystemTime> {
+        self.documents
+            .values()
+            .map(|document| document.document().expires_at())
+            .min()
+    }
+
+    /// The number of kept documents, including expired ones that were not dropped yet.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+}
+
+impl<K, D> fmt::Debug for EphemeralDocuments<K, D>
+where
+    K: fmt::Debug,
+    D: ReplicatedDocument,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.documents
+                    .iter()
+                    .map(|(key, document)| (key, document.document().expires_at())),
+            )
+            .finish()
+    }
+}
+
+impl<K, D> Default for EphemeralDocuments<K, D>
+where
+    K: Clone + Eq + Hash,
+    D: ReplicatedDocument,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        IdWithIndex,
+        any_data::list::{LinearList, ListOperation},
+    };
+    use flotsync_core::{
+        GroupId,
+        MemberIndex,
+        member::Identifier,
+        membership::{GroupContext, GroupMembers},
+        versions::UpdateId,
+    };
+    use uuid::Uuid;
+
+    type Scratchpad = EphemeralDocument<LinearList<UpdateId, i32>>;
+    type ScratchpadChange = VersionedChange<EphemeralOperation<ListOperation<UpdateId, i32>>>;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn start() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000)
+    }
+
+    fn new_doc(local_member_index: u32) -> VersionedDoc<Scratchpad> {
+        let members = GroupMembers::from_ordered_members([
+            Identifier::from_array(["alice"]),
+            Identifier::from_array(["bob"]),
+        ])
+        .unwrap();
+        let group = GroupContext::new(GroupId(Uuid::from_u128(1)), members, 0).unwrap();
+        let scratchpad = EphemeralDocument::with_ttl(
+            LinearList::new(UpdateId::INITIAL_STATE_ORIGIN),
+            TTL,
+            start(),
+        );
+        VersionedDoc::new(scratchpad, group, MemberIndex::new(local_member_index))
+    }
+
+    fn append(doc: &mut VersionedDoc<Scratchpad>, value: i32) -> ScratchpadChange {
+        doc.apply_local(|scratchpad, update_id| {
+            scratchpad
+                .document()
+                .append_operation(IdWithIndex::zero(update_id), [value])
+                .into_iter()
+                .map(EphemeralOperation::Change)
+                .collect()
+        })
+        .unwrap()
+        .clone()
+    }
+
+    fn extend(doc: &mut VersionedDoc<Scratchpad>, expires_at: SystemTime) -> ScratchpadChange {
+        doc.apply_local(|_, _| vec![EphemeralOperation::ExtendUntil { expires_at }])
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn expired_documents_are_hidden_and_dropped() {
+        let mut documents = EphemeralDocuments::new();
+        documents.insert("scratch", new_doc(0));
+        let before_expiry = start() + TTL - Duration::from_secs(1);
+        let at_expiry = start() + TTL;
+
+        assert!(documents.get(&"scratch", before_expiry).is_some());
+        assert!(documents.get(&"scratch", at_expiry).is_none());
+        assert_eq!(documents.next_expiry(), Some(at_expiry));
+        assert!(documents.drop_expired(before_expiry).is_empty());
+        assert_eq!(documents.drop_expired(at_expiry), vec!["scratch"]);
+        assert!(documents.is_empty());
+        assert_eq!(documents.next_expiry(), None);
+    }
+
+    #[test]
+    fn concurrent_extensions_converge_to_the_latest_expiry() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        let later = start() + 2 * TTL;
+        let latest = start() + 3 * TTL;
+
+        let from_alice = extend(&mut alice, latest);
+        let from_bob = extend(&mut bob, later);
+        alice.apply_remote([from_bob]).unwrap();
+        bob.apply_remote([from_alice]).unwrap();
+
+        assert_eq!(alice.document().expires_at(), latest);
+        assert_eq!(bob.document().expires_at(), latest);
+    }
+
+    #[test]
+    fn changes_for_expired_documents_are_ignored() {
+        let mut alice = new_doc(0);
+        let mut bob_documents = EphemeralDocuments::new();
+        bob_documents.insert("scratch", new_doc(1));
+
+        let change = append(&mut alice, 7);
+        let applied = bob_documents
+            .apply_remote(&"scratch", [change.clone()], start())
+            .unwrap();
+        assert_eq!(applied, 1);
+
+        let late = append(&mut alice, 8);
+        let applied = bob_documents
+            .apply_remote(&"scratch", [late], start() + TTL)
+            .unwrap();
+        assert_eq!(applied, 0);
+        let applied = bob_documents
+            .apply_remote(&"unknown", [change], start())
+            .unwrap();
+        assert_eq!(applied, 0);
+    }
+}
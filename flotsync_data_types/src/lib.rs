@@ -5,19 +5,63 @@ pub use chrono;
 use snafu::{Location, prelude::*};
 use std::{borrow::Cow, collections::HashMap, fmt, hash::Hash};
 
+pub mod admin_log;
 pub mod any_data;
-#[allow(unused, reason = "Might re-use some already implemented things later.")]
-mod linear_data;
+pub mod ephemeral;
+pub mod linear_data;
+/// Common imports for applications working with flotsync documents.
+///
+/// Also includes the [`flotsync_core` prelude](flotsync_core::prelude), so one glob import covers
+/// the documents together with the identity and version types they are replicated with.
+pub mod prelude {
+    pub use crate::{
+        ApplyBatch,
+        DataOperation,
+        IdWithIndex,
+        admin_log::{AdminAction, AdminLog, AdminLogSigner, AdminLogVerifier},
+        any_data::{
+            LinearLatestValueWins,
+            UpdateOperation,
+            event_log::EventLog,
+            list::{LinearList, ListOperation},
+        },
+        ephemeral::{EphemeralDocument, EphemeralDocuments, EphemeralOperation},
+        linear_data::{Composite, LinearData},
+        retention::{RetentionPolicies, RetentionPolicy},
+        row_values::{RowOperations, RowValueRead},
+        text::{GraphemeString, LinearString},
+        versioned::{ReadSnapshot, ReplicatedDocument, VersionedChange, VersionedDoc},
+    };
+    pub use flotsync_core::prelude::*;
+}
+pub mod retention;
 pub mod row_values;
 pub mod schema;
 #[cfg(any(test, feature = "test-support"))]
 pub mod test_support;
 pub mod text;
-pub mod snapshot {
-    pub use crate::linear_data::snapshot::*;
-}
+pub mod versioned;
 
-pub use linear_data::{DataOperation, IdWithIndex, IdWithIndexRange, IntegrityError};
+#[cfg(feature = "parallel")]
+pub use linear_data::par_apply_workspace_batches;
+pub use linear_data::{
+    ApplyBatch,
+    ApplyFailure,
+    BatchResult,
+    DataOperation,
+    DataOperationRef,
+    IdGeneratorWithIndex,
+    IdRange,
+    IdWithIndex,
+    IdWithIndexRange,
+    IntegrityError,
+    LinearDataStats,
+    ReserveIds,
+    ReservedIds,
+    WorkspaceBatchResult,
+    apply_workspace_batches,
+    snapshot,
+};
 pub use row_values::{
     Decode,
     InMemoryValueData,
@@ -44,14 +88,112 @@ pub use schema::{
 };
 
 #[derive(Debug, Snafu)]
-#[snafu(display("The diff could not be applied due to a logic error at {location}: {context}"))]
+#[snafu(display(
+    "The diff could not be applied due to a logic error at {location}: {context}{scope}"
+))]
 pub struct InternalError {
     context: String,
     #[snafu(implicit)]
+    scope: Box<ErrorScope>,
+    #[snafu(implicit)]
     location: Location,
 }
+impl InternalError {
+    /// Where in the application the error occurred, as far as it is known.
+    #[must_use]
+    pub fn scope(&self) -> &ErrorScope {
+        &self.scope
+    }
+
+    /// Add the fields of `scope` that are not already known.
+    ///
+    /// Fields set closer to where the error occurred are more specific, so they are kept.
+    #[must_use]
+    pub fn in_scope(mut self, scope: &ErrorScope) -> Self {
+        self.scope.fill_from(scope);
+        self
+    }
+}
+
+/// Identifies the replicated object an [`InternalError`] occurred in.
+///
+/// Every field is optional, since each layer only knows some of them. The document id is added
+/// by [`apply_workspace_batches`], for example, while the group id is only known to the
+/// application.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorScope {
+    pub document_id: Option<String>,
+    pub group_id: Option<String>,
+    pub operation_id: Option<String>,
+}
+impl ErrorScope {
+    #[must_use]
+    pub fn with_document_id(mut self, document_id: impl fmt::Display) -> Self {
+        self.document_id = Some(document_id.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn with_group_id(mut self, group_id: impl fmt::Display) -> Self {
+        self.group_id = Some(group_id.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn with_operation_id(mut self, operation_id: impl fmt::Display) -> Self {
+        self.operation_id = Some(operation_id.to_string());
+        self
+    }
+
+    /// Returns `true` if none of the fields are known.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.document_id.is_none() && self.group_id.is_none() && self.operation_id.is_none()
+    }
+
+    fn fill_from(&mut self, other: &ErrorScope) {
+        for (field, other_field) in [
+            (&mut self.document_id, &other.document_id),
+            (&mut self.group_id, &other.group_id),
+            (&mut self.operation_id, &other.operation_id),
+        ] {
+            if field.is_none() {
+                field.clone_from(other_field);
+            }
+        }
+    }
+}
+impl fmt::Display for ErrorScope {
+    /// Formats as ` (document: d, group: g, operation: o)` with only the known fields, or
+    /// nothing at all, so it can be appended to an error message.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [
+            ("document", &self.document_id),
+            ("group", &self.group_id),
+            ("operation", &self.operation_id),
+        ];
+        let mut separator = " (";
+        for (name, value) in fields {
+            if let Some(value) = value {
+                write!(f, "{separator}{name}: {value}")?;
+                separator = ", ";
+            }
+        }
+        if !self.is_empty() {
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+impl snafu::GenerateImplicitData for Box<ErrorScope> {
+    /// Errors start out without a scope, which the layers above add as they pass it on.
+    fn generate() -> Self {
+        Box::default()
+    }
+}
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum OperationError {
     #[snafu(display(
         "An unusupported operation variant was encountered at {location}. {explanation}"
@@ -88,6 +230,7 @@ pub enum OperationError {
 }
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum DecodeValueError {
     #[snafu(display("Field '{field_name}' does not exist."))]
     FieldDoesNotExist { field_name: String },
@@ -158,7 +301,9 @@ where
 }
 
 /// State access helper for schema fields.
-pub trait FieldStateReadExt<OperationId> {
+///
+/// This trait is sealed and only implemented for [`Field`].
+pub trait FieldStateReadExt<OperationId>: sealed::Sealed {
     /// Get the current state of this field in `row`.
     ///
     /// Panics if `row` does not contain this field (i.e is from a different schema.)
@@ -168,7 +313,9 @@ pub trait FieldStateReadExt<OperationId> {
 }
 
 /// Typed value access helper for schema fields.
-pub trait FieldValueReadExt {
+///
+/// This trait is sealed and only implemented for [`Field`].
+pub trait FieldValueReadExt: sealed::Sealed {
     /// Get the current value of this field in `row` converted to `T` (owned or reference, as feasible).
     ///
     /// # Errors
@@ -275,3 +422,47 @@ macro_rules! update_values {
         [$(($field).set($value).unwrap(),)*]
     };
 }
+
+/// Home of [`Sealed`](sealed::Sealed), which public traits that only this crate may implement
+/// require, so they can gain methods without breaking downstream code.
+mod sealed {
+    pub trait Sealed {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_errors_keep_the_innermost_scope() {
+        let error = InternalSnafu {
+            context: "broken link",
+        }
+        .build();
+        assert!(error.scope().is_empty());
+        assert!(error.to_string().ends_with(": broken link"));
+
+        let error = error
+            .in_scope(&ErrorScope::default().with_operation_id("op-1"))
+            .in_scope(&ErrorScope::default().with_document_id("notes"))
+            .in_scope(
+                &ErrorScope::default()
+                    .with_document_id("other")
+                    .with_group_id("team")
+                    .with_operation_id("op-2"),
+            );
+        assert_eq!(
+            error.scope(),
+            &ErrorScope {
+                document_id: Some("notes".to_owned()),
+                group_id: Some("team".to_owned()),
+                operation_id: Some("op-1".to_owned()),
+            }
+        );
+        assert!(
+            error
+                .to_string()
+                .ends_with(": broken link (document: notes, group: team, operation: op-1)")
+        );
+    }
+}
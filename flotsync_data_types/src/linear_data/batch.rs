@@ -0,0 +1,271 @@
+//! Applying whole batches of [`DataOperation`]s without knowing their causal order upfront.
+//!
+//! Besides single documents, [`apply_workspace_batches`] applies the batches that arrived for
+//! many documents at once. With the `parallel` feature, [`par_apply_workspace_batches`] does
+//! the same with one task per document, which is what makes the initial sync of a large
+//! workspace scale beyond one core.
+use super::{Composite, DataOperation, IdWithIndex};
+use crate::{ErrorScope, InternalError};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+    fmt,
+    hash::Hash,
+};
+
+/// The outcome of applying a batch of operations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchResult<Op> {
+    /// The number of operations that were applied.
+    pub applied: usize,
+    /// The operations that did not fit the document, in the order they were last tried.
+    ///
+    /// These usually anchor on ids that neither the document nor the batch contained, so they
+    /// can be retried once the operations they depend on have arrived.
+    pub blocked: Vec<Op>,
+}
+impl<Op> BatchResult<Op> {
+    /// Returns `true` if every operation in the batch was applied.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.blocked.is_empty()
+    }
+
+    pub fn map_operation<Output, F>(self, mapper: F) -> BatchResult<Output>
+    where
+        F: FnMut(Op) -> Output,
+    {
+        BatchResult {
+            applied: self.applied,
+            blocked: self.blocked.into_iter().map(mapper).collect(),
+        }
+    }
+}
+
+/// A document that can apply a whole batch of its own operations.
+pub trait ApplyBatch {
+    type Operation;
+
+    /// Apply `operations` in causal order, returning the ones that are still blocked.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first internal inconsistency. Operations applied before it stay applied.
+    fn apply_batch(
+        &mut self,
+        operations: Vec<Self::Operation>,
+    ) -> Result<BatchResult<Self::Operation>, InternalError>;
+}
+
+/// The outcome of applying incoming batches to a set of documents.
+#[derive(Debug)]
+pub struct WorkspaceBatchResult<K, Op> {
+    /// The outcome for every document that received operations.
+    pub documents: BTreeMap<K, Result<BatchResult<Op>, InternalError>>,
+    /// The operations addressed to documents that are not in the workspace, in arrival order.
+    pub unknown: BTreeMap<K, Vec<Op>>,
+}
+impl<K, Op> WorkspaceBatchResult<K, Op>
+where
+    K: Ord,
+{
+    /// Record that the workspace belongs to the group `group_id` in every failed document's error.
+    #[must_use]
+    pub fn in_group(mut self, group_id: impl fmt::Display) -> Self {
+        let scope = ErrorScope::default().with_group_id(group_id);
+        self.documents = self
+            .documents
+            .into_iter()
+            .map(|(key, result)| (key, result.map_err(|error| error.in_scope(&scope))))
+            .collect();
+        self
+    }
+}
+
+/// Apply every incoming batch to the document it is addressed to, one document at a time.
+///
+/// Batches for the same document are concatenated in arrival order and applied as one
+/// [`ApplyBatch::apply_batch`] call, so each document ends up exactly as if its batches had been
+/// applied one after the other. Documents are independent of each other, which is why
+/// [`par_apply_workspace_batches`] produces the same result.
+pub fn apply_workspace_batches<K, D, I>(
+    documents: &mut HashMap<K, D>,
+    batches: I,
+) -> WorkspaceBatchResult<K, D::Operation>
+where
+    K: Clone + Eq + Hash + Ord + fmt::Display,
+    D: ApplyBatch,
+    I: IntoIterator<Item = (K, Vec<D::Operation>)>,
+{
+    let (work, unknown) = assign_batches(documents, batches);
+    let documents = work
+        .into_iter()
+        .map(|(key, document, operations)| apply_document_batch(key, document, operations))
+        .collect();
+    WorkspaceBatchResult { documents, unknown }
+}
+
+/// Apply every incoming batch to the document it is addressed to, applying different documents
+/// concurrently on the rayon thread pool.
+///
+/// Operations for a single document are never split across tasks, so the per-document order is
+/// the same as in [`apply_workspace_batches`] and so is the result.
+#[cfg(feature = "parallel")]
+pub fn par_apply_workspace_batches<K, D, I>(
+    documents: &mut HashMap<K, D>,
+    batches: I,
+) -> WorkspaceBatchResult<K, D::Operation>
+where
+    K: Clone + Eq + Hash + Ord + fmt::Display + Send + Sync,
+    D: ApplyBatch + Send,
+    D::Operation: Send,
+    I: IntoIterator<Item = (K, Vec<D::Operation>)>,
+{
+    let (work, unknown) = assign_batches(documents, batches);
+    let documents = work
+        .into_par_iter()
+        .map(|(key, document, operations)| apply_document_batch(key, document, operations))
+        .collect();
+    WorkspaceBatchResult { documents, unknown }
+}
+
+/// Sort `operations` so that every operation comes after the inserts in the batch that
+/// introduce the ids it anchors on.
+///
+/// Among operations whose anchors are satisfied, the original batch order is kept. Operations on
+/// a dependency cycle, which no valid history produces, are appended in batch order.
+pub(super) fn causal_order<BaseId, Value>(
+    operations: Vec<DataOperation<IdWithIndex<BaseId>, Value>>,
+) -> Vec<DataOperation<IdWithIndex<BaseId>, Value>>
+where
+    BaseId: Eq + Hash,
+    Value: Composite,
+{
+    let num_operations = operations.len();
+    let mut introduced: HashMap<&BaseId, Vec<(u64, u64, usize)>> = HashMap::new();
+    for (position, operation) in operations.iter().enumerate() {
+        if let DataOperation::Insert { id, value, .. } = operation {
+            let start = u64::from(id.index);
+            introduced.entry(&id.id).or_default().push((
+                start,
+                start + value.len() as u64,
+                position,
+            ));
+        }
+    }
+    let producer_of = |anchor: &IdWithIndex<BaseId>| {
+        let index = u64::from(anchor.index);
+        introduced.get(&anchor.id).and_then(|ranges| {
+            ranges
+                .iter()
+                .find(|(start, end, _)| *start <= index && index < *end)
+                .map(|(_, _, position)| *position)
+        })
+    };
+
+    let mut dependents = vec![Vec::new(); num_operations];
+    let mut unmet = vec![0usize; num_operations];
+    for (position, operation) in operations.iter().enumerate() {
+        let anchors: Vec<&IdWithIndex<BaseId>> = match operation {
+            DataOperation::Insert { pred, succ, .. } => vec![pred, succ],
+            DataOperation::Delete { start, end } => {
+                [Some(start), end.as_ref()].into_iter().flatten().collect()
+            }
+            DataOperation::DeleteRanges { ranges } => ranges
+                .iter()
+                .flat_map(|range| [&range.start, &range.end])
+                .collect(),
+        };
+        let mut producers: Vec<usize> = anchors
+            .into_iter()
+            .filter_map(&producer_of)
+            .filter(|producer| *producer != position)
+            .collect();
+        producers.sort_unstable();
+        producers.dedup();
+        for producer in producers {
+            dependents[producer].push(position);
+            unmet[position] += 1;
+        }
+    }
+
+    // Kahn's algorithm, always picking the earliest ready operation in batch order.
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..num_operations)
+        .filter(|position| unmet[*position] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(num_operations);
+    while let Some(Reverse(position)) = ready.pop() {
+        order.push(position);
+        for &dependent in &dependents[position] {
+            unmet[dependent] -= 1;
+            if unmet[dependent] == 0 {
+                ready.push(Reverse(dependent));
+            }
+        }
+    }
+    order.extend((0..num_operations).filter(|position| unmet[*position] > 0));
+
+    let mut slots: Vec<Option<_>> = operations.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|position| {
+            slots[position]
+                .take()
+                .expect("every position is ordered once")
+        })
+        .collect()
+}
+
+/// One document together with all operations addressed to it.
+type DocumentWork<'a, K, D> = (&'a K, &'a mut D, Vec<<D as ApplyBatch>::Operation>);
+
+/// The work per known document, and the batches addressed to unknown documents.
+type AssignedBatches<'a, K, D> = (
+    Vec<DocumentWork<'a, K, D>>,
+    BTreeMap<K, Vec<<D as ApplyBatch>::Operation>>,
+);
+
+/// Apply the batch for one document, recording its key in any error.
+fn apply_document_batch<K, D>(
+    key: &K,
+    document: &mut D,
+    operations: Vec<D::Operation>,
+) -> (K, Result<BatchResult<D::Operation>, InternalError>)
+where
+    K: Clone + fmt::Display,
+    D: ApplyBatch,
+{
+    let result = document.apply_batch(operations).map_err(|error| {
+        let scope = ErrorScope::default().with_document_id(key);
+        error.in_scope(&scope)
+    });
+    (key.clone(), result)
+}
+
+/// Pair each document with its concatenated batches, setting aside batches for unknown keys.
+fn assign_batches<'a, K, D, I>(
+    documents: &'a mut HashMap<K, D>,
+    batches: I,
+) -> AssignedBatches<'a, K, D>
+where
+    K: Eq + Hash + Ord,
+    D: ApplyBatch,
+    I: IntoIterator<Item = (K, Vec<D::Operation>)>,
+{
+    let mut pending: HashMap<K, Vec<D::Operation>> = HashMap::new();
+    for (key, operations) in batches {
+        pending.entry(key).or_default().extend(operations);
+    }
+    let work = documents
+        .iter_mut()
+        .filter_map(|(key, document)| {
+            pending
+                .remove(key)
+                .map(|operations| (key, document, operations))
+        })
+        .collect();
+    (work, pending.into_iter().collect())
+}
@@ -1,5 +1,9 @@
-use super::{vec_impl::RightTreeTraversalMemo, *};
-use crate::snapshot::SnapshotSink;
+use super::{
+    integration::{self, IntegrationError, IntegrationNodes},
+    vec_impl::NodeVec,
+    *,
+};
+use crate::{ErrorScope, InternalError, InternalSnafu, snapshot::SnapshotSink};
 use flotsync_utils::{debugging::DebugFormatting, require};
 use std::{hash::Hash, ops::RangeBounds};
 
@@ -37,12 +41,133 @@ pub trait Composite: Sized {
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns the first element, if there is one.
+    fn first(&self) -> Option<&Self::Element> {
+        self.get(0)
+    }
+
+    /// Returns the last element, if there is one.
+    fn last(&self) -> Option<&Self::Element> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+}
+
+/// Aggregate statistics over the visible values of a [`VecCoalescedLinearData`], kept up to date
+/// with every insert and delete instead of being recomputed from all values.
+///
+/// Statistics that depend on where a change happened, such as word counts or line indices, get
+/// its [`ChangeContext`]. Finding that requires scanning the nodes before and after the change,
+/// so it is only looked up when `context` is called.
+pub trait ValueSummary<Value>: Default + 'static
+where
+    Value: Composite,
+{
+    /// `value` became visible where `context` describes.
+    fn insert<'a, F>(&mut self, value: &Value, context: F)
+    where
+        Value::Element: 'a,
+        F: FnOnce() -> ChangeContext<'a, Value::Element>;
+
+    /// `value` was deleted from where `context` describes.
+    fn remove<'a, F>(&mut self, value: &Value, context: F)
+    where
+        Value::Element: 'a,
+        F: FnOnce() -> ChangeContext<'a, Value::Element>;
+}
+/// Tracks nothing.
+impl<Value> ValueSummary<Value> for ()
+where
+    Value: Composite,
+{
+    fn insert<'a, F>(&mut self, _value: &Value, _context: F)
+    where
+        Value::Element: 'a,
+        F: FnOnce() -> ChangeContext<'a, Value::Element>,
+    {
+    }
+
+    fn remove<'a, F>(&mut self, _value: &Value, _context: F)
+    where
+        Value::Element: 'a,
+        F: FnOnce() -> ChangeContext<'a, Value::Element>,
+    {
+    }
 }
 
+/// Where in the visible elements a value was inserted or deleted, see [`ValueSummary`].
 #[derive(Debug)]
+pub struct ChangeContext<'a, Element: ?Sized> {
+    /// The position of the first element of the value, among the visible elements that include
+    /// the value.
+    pub position: usize,
+    /// The visible element directly before the value, `None` at the start of the document.
+    pub before: Option<&'a Element>,
+    /// The visible element directly after the value, `None` at the end of the document.
+    pub after: Option<&'a Element>,
+}
+impl<Element: ?Sized> Clone for ChangeContext<'_, Element> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<Element: ?Sized> Copy for ChangeContext<'_, Element> {}
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum DeleteError {
+    #[snafu(display("The range does not cover a deletable part of a single update."))]
     InvalidRange,
+    #[snafu(display("The range refers to ids that do not exist."))]
     NotFound,
+    #[snafu(transparent)]
+    Internal { source: InternalError },
+}
+
+/// Conflict and storage statistics of a [`VecCoalescedLinearData`].
+///
+/// These indicate how much a document would benefit from [`defragment`] or from compacting its
+/// history.
+///
+/// [`defragment`]: VecCoalescedLinearData::defragment
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinearDataStats {
+    /// Inserts that had to be ordered among concurrent inserts between the same neighbours.
+    ///
+    /// This only counts inserts integrated by this instance. It is not part of snapshots.
+    pub conflicts_resolved: u64,
+    /// Deleted elements that are still kept to anchor concurrent inserts.
+    pub tombstones: usize,
+    /// All elements, deleted or not.
+    pub elements: usize,
+    /// Nodes holding elements, deleted or not.
+    pub nodes: usize,
+    /// Nodes that would remain after [`defragment`](VecCoalescedLinearData::defragment).
+    pub defragmented_nodes: usize,
+}
+impl LinearDataStats {
+    /// The fraction of nodes that [`defragment`](VecCoalescedLinearData::defragment) would
+    /// merge into their neighbours.
+    ///
+    /// This is `0.0` for a fully coalesced document.
+    #[must_use]
+    pub fn fragmentation_ratio(&self) -> f64 {
+        if self.nodes == 0 {
+            0.0
+        } else {
+            (self.nodes - self.defragmented_nodes) as f64 / self.nodes as f64
+        }
+    }
+
+    /// The average number of elements per node.
+    #[must_use]
+    pub fn average_chunk_len(&self) -> f64 {
+        if self.nodes == 0 {
+            0.0
+        } else {
+            self.elements as f64 / self.nodes as f64
+        }
+    }
 }
 
 /// An implementation of [[`LinearData`]] using a [[Vec]] to track the individual operation nodes.
@@ -55,16 +180,47 @@ pub enum DeleteError {
 /// This requires Values to implement the [[Composite]] trait, to facilitate coalescing and splitting.
 ///
 /// Otherwise the same properties as the [[`VecLinearData`]] apply.
-#[derive(Clone, Debug, PartialEq)]
-pub struct VecCoalescedLinearData<Id, Value> {
+///
+/// `Summary` optionally maintains a [`ValueSummary`] of the visible values, see
+/// [`summary`](Self::summary).
+///
+/// Equality only compares the nodes, not the [statistics](Self::stats) or the summary.
+#[derive(Clone, Debug)]
+pub struct VecCoalescedLinearData<Id, Value, Summary = ()> {
     /// The number of values in Insert nodes in `base`.
     len: usize,
     base: VecLinearData<IdWithIndex<Id>, Value>,
+    /// See [`LinearDataStats::conflicts_resolved`].
+    conflicts_resolved: u64,
+    summary: Summary,
+}
+impl<Id, Value, Summary> PartialEq for VecCoalescedLinearData<Id, Value, Summary>
+where
+    Id: PartialEq,
+    Value: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.base == other.base
+    }
 }
 impl<BaseId, Value> VecCoalescedLinearData<BaseId, Value>
 where
     BaseId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
     Value: Composite + fmt::Debug + 'static,
+{
+    pub fn new(initial_id: BaseId) -> Self {
+        Self::new_summarized(initial_id)
+    }
+
+    pub fn with_value(initial_id: BaseId, initial_value: Value) -> Self {
+        Self::with_value_summarized(initial_id, initial_value)
+    }
+}
+impl<BaseId, Value, Summary> VecCoalescedLinearData<BaseId, Value, Summary>
+where
+    BaseId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    Value: Composite + fmt::Debug + 'static,
+    Summary: ValueSummary<Value>,
 {
     pub(crate) fn encode_snapshot<S, ValueRef: ?Sized, F>(
         &self,
@@ -80,35 +236,56 @@ where
 
     pub(crate) fn from_base_snapshot(base: VecLinearData<IdWithIndex<BaseId>, Value>) -> Self {
         let len = base.iter_values().map(Composite::len).sum();
-        Self { len, base }
+        let mut summary = Summary::default();
+        let mut position = 0;
+        let mut before = None;
+        for value in base.iter_values() {
+            summary.insert(value, || ChangeContext {
+                position,
+                before,
+                after: None,
+            });
+            position += value.len();
+            before = value.last();
+        }
+        Self {
+            len,
+            base,
+            conflicts_resolved: 0,
+            summary,
+        }
     }
 
-    pub fn new(initial_id: BaseId) -> Self {
+    /// Like [`new`](VecCoalescedLinearData::new), but maintaining a `Summary` of the values.
+    pub fn new_summarized(initial_id: BaseId) -> Self {
         let begin_id = IdWithIndex::zero(initial_id);
         let end_id = begin_id.increment();
-        let begin_node = Node {
-            id: begin_id.clone(),
-            left_origin: None,
-            right_origin: Some(end_id.clone()),
-            operation: Operation::Beginning,
-        };
-        let end_node = Node {
-            id: end_id,
-            left_origin: Some(begin_id),
-            right_origin: None,
-            operation: Operation::End,
-        };
+        let begin_node = Node::beginning(begin_id);
+        let end_node = Node::end(end_id);
         let base = VecLinearData {
             len: 0,
-            nodes: vec![begin_node, end_node],
+            nodes: [begin_node, end_node].into_iter().collect(),
         };
-        Self { len: 0, base }
+        Self {
+            len: 0,
+            base,
+            conflicts_resolved: 0,
+            summary: Summary::default(),
+        }
     }
 
-    pub fn with_value(initial_id: BaseId, initial_value: Value) -> Self {
+    /// Like [`with_value`](VecCoalescedLinearData::with_value), but maintaining a `Summary` of the
+    /// values.
+    pub fn with_value_summarized(initial_id: BaseId, initial_value: Value) -> Self {
         if initial_value.is_empty() {
-            return Self::new(initial_id);
+            return Self::new_summarized(initial_id);
         }
+        let mut summary = Summary::default();
+        summary.insert(&initial_value, || ChangeContext {
+            position: 0,
+            before: None,
+            after: None,
+        });
 
         let value_len = initial_value.len();
 
@@ -122,32 +299,24 @@ where
             .checked_next_after(value_len)
             .expect("Initial value would require indices > u32::MAX");
 
-        let begin_node = Node {
-            id: begin_id.clone(),
-            left_origin: None,
-            right_origin: Some(value_id.clone()),
-            operation: Operation::Beginning,
-        };
         let value_node = Node {
-            id: value_id.clone(),
+            id: value_id,
             left_origin: Some(begin_id.clone()),
             right_origin: Some(end_id.clone()),
             operation: Operation::Insert {
                 value: initial_value,
             },
         };
-        let end_node = Node {
-            id: end_id,
-            left_origin: Some(value_node.last_id()),
-            right_origin: None,
-            operation: Operation::End,
-        };
-        let nodes = vec![begin_node, value_node, end_node];
+        let begin_node = Node::beginning(begin_id);
+        let end_node = Node::end(end_id);
+        let nodes = [begin_node, value_node, end_node].into_iter().collect();
 
         let base = VecLinearData { len: 1, nodes };
         Self {
             len: value_len,
             base,
+            conflicts_resolved: 0,
+            summary,
         }
     }
 
@@ -156,6 +325,11 @@ where
         self.len
     }
 
+    /// The [`ValueSummary`] of all visible values.
+    pub fn summary(&self) -> &Summary {
+        &self.summary
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
@@ -167,6 +341,7 @@ where
         );
         self.len += value.len();
         self.base.append(id, value);
+        self.summarize_insert(self.base.nodes.len() - 2);
     }
 
     pub fn prepend(&mut self, id: IdWithIndex<BaseId>, value: Value) {
@@ -176,11 +351,13 @@ where
         );
         self.len += value.len();
         self.base.prepend(id, value);
+        self.summarize_insert(1);
     }
 
-    /// Delete the (sub-range of the) node corresponding to [start, end].
+    /// Delete the (sub-range of the) nodes corresponding to [start, end].
     ///
-    /// Returns Err (and deletes nothing) if this range is not part of a single node.
+    /// Returns Err (and deletes nothing) if this range is not part of a single update, or touches
+    /// ids that don't exist or are boundaries.
     #[allow(
         clippy::too_many_lines,
         reason = "Range deletion has several structurally distinct split cases; keeping them together preserves the invariant checks."
@@ -191,8 +368,54 @@ where
         end: &IdWithIndex<BaseId>,
     ) -> Result<(), DeleteError> {
         if start == end {
-            return self.delete(start).map(|_| ()).ok_or(DeleteError::NotFound);
+            return self
+                .delete_element(start)?
+                .map(|_| ())
+                .ok_or(DeleteError::NotFound);
         }
+        let work_items = self.plan_delete_range(start, end)?;
+
+        // Splitting inserts new nodes, which shifts all later node indices.
+        // Working back to front keeps the indices of the remaining items valid.
+        for item in work_items.into_iter().rev() {
+            match item {
+                DeleteMode::Skip { .. } => (), // Just do nothing for these.
+                DeleteMode::Suffix { node_index } => {
+                    let new_node_index =
+                        self.split_node(node_index, start.index, SplitMode::Before)?;
+                    self.delete_node(new_node_index);
+                }
+                DeleteMode::Subrange { node_index } => {
+                    let node_index_after_start_split =
+                        self.split_node(node_index, start.index, SplitMode::Before)?;
+                    let node_index_after_end_split =
+                        self.split_node(node_index_after_start_split, end.index, SplitMode::After)?;
+                    self.delete_node(node_index_after_end_split);
+                }
+                DeleteMode::Full { node_index } => {
+                    self.delete_node(node_index);
+                }
+                DeleteMode::Prefix { node_index } => {
+                    let new_node_index =
+                        self.split_node(node_index, end.index, SplitMode::After)?;
+                    self.delete_node(new_node_index);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Work out how each node from `start` through `end` must change to delete that range,
+    /// without changing anything yet.
+    ///
+    /// Fails exactly when [`delete_range`](Self::delete_range) would reject the range, so all
+    /// ranges of a [`DataOperation::DeleteRanges`] can be checked before any is deleted.
+    fn plan_delete_range(
+        &self,
+        start: &IdWithIndex<BaseId>,
+        end: &IdWithIndex<BaseId>,
+    ) -> Result<Vec<DeleteMode>, DeleteError> {
         require!(start.id == end.id, DeleteError::InvalidRange);
         require!(start.index <= end.index, DeleteError::InvalidRange);
 
@@ -208,25 +431,33 @@ where
                               node: &Node<IdWithIndex<BaseId>, Value>,
                               contains_start: bool,
                               contains_end: bool| {
-            let start_at_node_start = contains_start && &node.id == start;
-            let end_at_node_end = contains_end && node.last_index() == end.index;
-
-            if matches!(node.operation, Operation::Delete { .. }) {
-                DeleteMode::Skip { node_index }
-            } else if (!contains_start && !contains_end) || (start_at_node_start && end_at_node_end)
-            {
-                DeleteMode::Full { node_index }
-            } else if start_at_node_start {
-                DeleteMode::Prefix { node_index }
-            } else if end_at_node_end {
-                DeleteMode::Suffix { node_index }
-            } else {
-                DeleteMode::Subrange { node_index }
+            let covers_node_start = !contains_start || &node.id == start;
+            let covers_node_end = !contains_end || node.last_index() == end.index;
+
+            match node.operation {
+                Operation::Delete { .. } => return Ok(DeleteMode::Skip { node_index }),
+                Operation::Insert { .. } => (),
+                // The boundaries share their base id with the initial value, but can never be deleted.
+                Operation::Beginning | Operation::End => return Err(DeleteError::InvalidRange),
+                Operation::Invalid => {
+                    return Err(InternalSnafu {
+                        context: format!("Node {node_index} is invalid."),
+                    }
+                    .build()
+                    .into());
+                }
             }
+            let item = match (covers_node_start, covers_node_end) {
+                (true, true) => DeleteMode::Full { node_index },
+                (true, false) => DeleteMode::Prefix { node_index },
+                (false, true) => DeleteMode::Suffix { node_index },
+                (false, false) => DeleteMode::Subrange { node_index },
+            };
+            Ok(item)
         };
         let mut work_items: Vec<DeleteMode> = Vec::new();
         let mut found_end = start_node.contains(end);
-        let start_item = item_from_node(start_node_index, start_node, true, found_end);
+        let start_item = item_from_node(start_node_index, start_node, true, found_end)?;
         work_items.push(start_item);
 
         let mut nodes = self
@@ -239,52 +470,14 @@ where
         while !found_end {
             if let Some((node_index, node)) = nodes.next() {
                 found_end = node.contains(end);
-                let item = item_from_node(node_index, node, false, found_end);
+                let item = item_from_node(node_index, node, false, found_end)?;
                 work_items.push(item);
             } else {
                 return Err(DeleteError::NotFound);
             }
         }
         debug_assert!(found_end);
-
-        for item in work_items {
-            match item {
-                DeleteMode::Skip { .. } => (), // Just do nothing for these.
-                DeleteMode::Suffix { node_index } => {
-                    let new_node_index =
-                        self.split_node(node_index, start.index, SplitMode::Before);
-                    let node = &mut self.base.nodes[new_node_index];
-                    node.operation.delete();
-                    self.len -= node.node_len();
-                    self.base.len -= 1;
-                }
-                DeleteMode::Subrange { node_index } => {
-                    let node_index_after_start_split =
-                        self.split_node(node_index, start.index, SplitMode::Before);
-                    let node_index_after_end_split =
-                        self.split_node(node_index_after_start_split, end.index, SplitMode::After);
-                    let node = &mut self.base.nodes[node_index_after_end_split];
-                    node.operation.delete();
-                    self.len -= node.node_len();
-                    self.base.len -= 1;
-                }
-                DeleteMode::Full { node_index } => {
-                    let node = &mut self.base.nodes[node_index];
-                    node.operation.delete();
-                    self.len -= node.node_len();
-                    self.base.len -= 1;
-                }
-                DeleteMode::Prefix { node_index } => {
-                    let new_node_index = self.split_node(node_index, end.index, SplitMode::After);
-                    let node = &mut self.base.nodes[new_node_index];
-                    node.operation.delete();
-                    self.len -= node.node_len();
-                    self.base.len -= 1;
-                }
-            }
-        }
-
-        Ok(())
+        Ok(work_items)
     }
 
     /// Returns the ids that make up insert nodes in the given `range` of element positions.
@@ -296,7 +489,7 @@ where
     where
         R: RangeBounds<usize>,
     {
-        let (start_node, start_id, mut next_position) = {
+        let (start_node, start_id) = {
             match range.start_bound() {
                 std::ops::Bound::Included(position) => {
                     self.node_at_position(*position).map(|pos| {
@@ -304,7 +497,7 @@ where
                         // This must fit if position is actually within the node.
                         let start_offset: u32 =
                             (position - pos.node_start_position).try_into().unwrap();
-                        (pos, &node.id + start_offset, *position)
+                        (pos, node.id_at_offset(start_offset))
                     })?
                 }
                 std::ops::Bound::Excluded(position) => {
@@ -314,22 +507,21 @@ where
                     let node = &self.base.nodes[node_at_position.node_index];
                     if node.node_len() > included_position_offset {
                         // The next position of the one we are excluding fits in here.
-                        let id = &node.id + included_position_offset.try_into().unwrap();
-                        (node_at_position, id, position + 1)
+                        let id = node.id_at_offset(included_position_offset.try_into().unwrap());
+                        (node_at_position, id)
                     } else {
                         // The next position is the beginning of the next node.
                         self.base
                             .iter_inserts_from(node_at_position.node_index + 1)
                             .next()
                             .map(|(node_index, node)| {
-                                let node_end_position =
-                                    node_at_position.node_start_position + node.node_len();
+                                // The excluded position was the last one of its node.
                                 let pos = NodePosition {
                                     node_index,
-                                    node_start_position: node_end_position + 1,
+                                    node_start_position: position + 1,
                                 };
                                 let id = node.id.clone();
-                                (pos, id, position + 1)
+                                (pos, id)
                             })?
                     }
                 }
@@ -341,7 +533,7 @@ where
                             node_start_position: 0,
                         };
                         let id = node.id.clone();
-                        (pos, id, 0)
+                        (pos, id)
                     })?
                 }
             }
@@ -358,46 +550,36 @@ where
 
         let mut node_ids_in_range: Vec<IdWithIndexRange<BaseId>> = Vec::with_capacity(1);
 
+        // Exclusive end position of the range, if it is bounded.
+        let end_position = match range.end_bound() {
+            std::ops::Bound::Included(position) => Some(position + 1),
+            std::ops::Bound::Excluded(position) => Some(*position),
+            std::ops::Bound::Unbounded => None,
+        };
+
+        // Skip whole nodes until we reach the one containing the end of the range.
         let mut reached_the_end = false;
-        while range.contains(&next_position) {
-            if next_position >= current_node_end_position {
-                // Move to the next node.
-                if let Some((new_node_index, new_node)) = insert_nodes.next() {
-                    let id = std::mem::replace(&mut current_node_start_id, new_node.id.clone());
-
-                    // Close out the current node.
-                    node_ids_in_range
-                        .push(IdWithIndexRange::with_end(id, current_node.last_index()));
-
-                    // Update the current node.
-                    current_node_index = new_node_index;
-                    current_node = new_node;
-                    current_node_start_position = next_position;
-                    current_node_end_position = next_position + new_node.node_len();
-                } else {
-                    reached_the_end = true;
-                    // No nodes left.
-                    match range.end_bound() {
-                        std::ops::Bound::Included(_) => {
-                            // The range's end does not occur in the collection.
-                            return None;
-                        }
-                        std::ops::Bound::Excluded(position) => {
-                            if *position == next_position {
-                                // This is fine.
-                                break;
-                            }
-                            // The range's end does not occur in the collection.
-                            return None;
-                        }
-                        std::ops::Bound::Unbounded => {
-                            // Very well, we reached the end.
-                            break;
-                        }
-                    }
+        while end_position.is_none_or(|end| end > current_node_end_position) {
+            if let Some((new_node_index, new_node)) = insert_nodes.next() {
+                let id = std::mem::replace(&mut current_node_start_id, new_node.id.clone());
+
+                // Close out the current node.
+                node_ids_in_range.push(IdWithIndexRange::with_end(id, current_node.last_index()));
+
+                // Update the current node.
+                current_node_index = new_node_index;
+                current_node = new_node;
+                current_node_start_position = current_node_end_position;
+                current_node_end_position += new_node.node_len();
+            } else {
+                if end_position.is_some() {
+                    // The range's end does not occur in the collection.
+                    return None;
                 }
+                // Very well, we reached the end.
+                reached_the_end = true;
+                break;
             }
-            next_position += 1;
         }
 
         // Close out the last node.
@@ -413,7 +595,7 @@ where
                     .unwrap(),
                 std::ops::Bound::Unbounded => unreachable!("We should have reached the end."),
             };
-            (&current_node.id + end_offset).index
+            current_node.id_at_offset(end_offset).index
         };
         let last_id_range = IdWithIndexRange::with_end(current_node_start_id, end_index);
         let end_id = last_id_range.last();
@@ -430,7 +612,7 @@ where
 
     fn predecessor_id(&self, id: &IdWithIndex<BaseId>, node_index: usize) -> IdWithIndex<BaseId> {
         let node = &self.base.nodes[node_index];
-        let id_offset = id.index_diff(&node.id);
+        let id_offset = node.offset_of(id);
         if id_offset > 0 {
             // It's the same node, but the previous index.
             id.decrement()
@@ -443,7 +625,7 @@ where
 
     fn successor_id(&self, id: &IdWithIndex<BaseId>, node_index: usize) -> IdWithIndex<BaseId> {
         let node = &self.base.nodes[node_index];
-        let id_offset = id.index_diff(&node.id);
+        let id_offset = node.offset_of(id);
         if id_offset as usize + 1 < node.node_len() {
             // It's the same node, but the next index.
             id.increment()
@@ -476,43 +658,283 @@ where
         })
     }
 
+    /// Returns `true` if any of the `len` ids starting at `id` already exist.
+    fn contains_any_id(&self, id: &IdWithIndex<BaseId>, len: usize) -> bool {
+        let last_offset = u32::try_from(len.saturating_sub(1)).unwrap_or(u32::MAX);
+        let last_index = id.index.saturating_add(last_offset);
+        self.base.nodes.iter().any(|node| {
+            node.id.id == id.id && node.id.index <= last_index && id.index <= node.last_index()
+        })
+    }
+
+    /// Find the node index at which an insert of `value` with `id` between `pred` and `succ`
+    /// must be placed, splitting the node that contains both origins if necessary.
+    ///
+    /// Returns `Ok(None)` without modifying anything if the insert does not fit the current
+    /// state.
+    fn make_insert_position(
+        &mut self,
+        id: &IdWithIndex<BaseId>,
+        pred: &IdWithIndex<BaseId>,
+        succ: &IdWithIndex<BaseId>,
+        value: &Value,
+    ) -> Result<Option<usize>, InternalError> {
+        if !id.can_address(value.len()) || self.contains_any_id(id, value.len()) {
+            return Ok(None);
+        }
+        let Some((pred_index, pred_node)) = self
+            .base
+            .nodes
+            .iter()
+            .enumerate()
+            .find(|(_, node)| node.contains(pred))
+        else {
+            return Ok(None);
+        };
+        let succ_opt = if pred_node.contains(succ) {
+            Some((pred_index, pred_node))
+        } else {
+            self.base
+                .nodes
+                .iter()
+                .enumerate()
+                .skip(pred_index)
+                .find(|(_, node)| node.contains(succ))
+        };
+        let Some((succ_index, succ_node)) = succ_opt else {
+            return Ok(None);
+        };
+
+        if pred_index == succ_index {
+            // Any insert picks 2 consecutive ids, and if the node holding them wasn't split,
+            // there can't have been a concurrent insert in between them,
+            // so they must be adjacent.
+            if !pred.is_followed_by(succ) {
+                return Ok(None);
+            }
+            // We need to split and then we can insert where we split.
+            return self
+                .split_node(pred_index, succ.index, SplitMode::Before)
+                .map(Some);
+        }
+
+        // Otherwise either some concurrent insert picked the same position, and then there is a
+        // split at that exact position already, or there hasn't been a concurrent insert and we
+        // would have been in the same-node case above.
+        if pred_node.last_id() != *pred || succ_node.id != *succ {
+            return Ok(None);
+        }
+        if pred_index + 1 == succ_index {
+            // We can just insert directly at the existing boundary.
+            return Ok(Some(succ_index));
+        }
+
+        // Must find a position between pred_index and succ_index.
+        // Sub-splits should not be necessary, since the position cannot be
+        // within another concurrently inserted node.
+        // (Concurrent conflict are resolved comparing the Id without the index
+        // and within a node only the index ever changes.)
+        match integration::find_insert_position(self, pred_index, pred, succ_index, succ, &id.id) {
+            Ok(position) => {
+                self.conflicts_resolved += 1;
+                Ok(Some(position))
+            }
+            // There is an existing node with the same base id.
+            // Nodes with the same base id should not conflict!
+            Err(IntegrationError::DuplicateInsert) => Ok(None),
+            Err(IntegrationError::Internal { source }) => Err(source),
+        }
+    }
+
+    fn insert_node(
+        &mut self,
+        position: usize,
+        id: IdWithIndex<BaseId>,
+        pred: IdWithIndex<BaseId>,
+        succ: IdWithIndex<BaseId>,
+        value: Value,
+    ) {
+        self.len += value.len();
+        self.base.len += 1;
+        self.base.nodes.insert(
+            position,
+            Node {
+                id,
+                left_origin: Some(pred),
+                right_origin: Some(succ),
+                operation: Operation::Insert { value },
+            },
+        );
+        self.summarize_insert(position);
+    }
+
+    /// Mark the single element with `id` as deleted.
+    ///
+    /// Returns the index of the node holding the deleted element, or `None` if there is no
+    /// element with `id` that can be deleted. Deleting an element twice is fine.
+    fn delete_element(&mut self, id: &IdWithIndex<BaseId>) -> Result<Option<usize>, InternalError> {
+        let Some((node_index, node)) = self
+            .base
+            .nodes
+            .iter()
+            .enumerate()
+            .find(|(_index, n)| n.contains(id))
+        else {
+            return Ok(None);
+        };
+
+        match node.operation {
+            Operation::Insert { ref value } => {
+                // We are only supposed to delete a single element here.
+                let node_index = if value.len() > 1 {
+                    self.split_node(node_index, id.index, SplitMode::BeforeAndAfter)?
+                } else {
+                    node_index
+                };
+                Ok(Some(self.delete_node(node_index)))
+            }
+            // Double delete is OK.
+            Operation::Delete { .. } => Ok(Some(node_index)),
+            // These cannot be deleted.
+            Operation::Beginning | Operation::End => Ok(None),
+            Operation::Invalid => InternalSnafu {
+                context: format!("Node {node_index} is invalid."),
+            }
+            .fail(),
+        }
+    }
+
+    /// Mark the insert node at `node_index` as deleted.
+    ///
+    /// The node is merged with deleted neighbours that were split off the same insert, so
+    /// deleting a fragmented range leaves a single node. Returns the index of the node that now
+    /// holds the deleted elements. Nodes before that index are never moved.
+    fn delete_node(&mut self, node_index: usize) -> usize {
+        let node = &mut self.base.nodes[node_index];
+        node.operation.delete();
+        self.len -= node.node_len();
+        self.base.len -= 1;
+        if let Operation::Delete { value } = &self.base.nodes[node_index].operation {
+            let nodes = &self.base.nodes;
+            self.summary
+                .remove(value, || change_context(nodes, node_index));
+        }
+
+        self.merge_into_previous(node_index + 1);
+        if self.merge_into_previous(node_index) {
+            node_index - 1
+        } else {
+            node_index
+        }
+    }
+
+    /// Add the insert node at `node_index` to the summary.
+    fn summarize_insert(&mut self, node_index: usize) {
+        let nodes = &self.base.nodes;
+        if let Operation::Insert { value } = &nodes[node_index].operation {
+            self.summary
+                .insert(value, || change_context(nodes, node_index));
+        }
+    }
+
+    /// Merge the node at `node_index` into the node before it, if both are parts of the same
+    /// insert in the same state.
+    ///
+    /// Returns whether the nodes were merged.
+    fn merge_into_previous(&mut self, node_index: usize) -> bool {
+        let nodes = &self.base.nodes;
+        if node_index == 0 || node_index >= nodes.len() {
+            return false;
+        }
+        if !can_merge(&nodes[node_index - 1], &nodes[node_index]) {
+            return false;
+        }
+        let node = self.base.nodes.remove(node_index);
+        if matches!(node.operation, Operation::Insert { .. }) {
+            self.base.len -= 1;
+        }
+        self.base.nodes[node_index - 1]
+            .operation
+            .merge(node.operation);
+        true
+    }
+
     /// Splits the node at `node_index` according to `mode` around `split_index` and returns
     /// the node index of the new node with the id that matches `split_index`.
+    ///
+    /// Fails without modifying anything if the requested split does not fit the node.
     #[allow(
         clippy::too_many_lines,
-        reason = "Node splitting owns the value during in-place replacement and keeps the index arithmetic in one invariant-preserving block."
+        reason = "Node splitting checks every precondition before mutating and keeps the index arithmetic in one invariant-preserving block."
     )]
-    fn split_node(&mut self, node_index: usize, split_index: u32, mut mode: SplitMode) -> usize {
-        let target_node = &mut self.base.nodes[node_index];
-        assert!(
+    fn split_node(
+        &mut self,
+        node_index: usize,
+        split_index: u32,
+        mut mode: SplitMode,
+    ) -> Result<usize, InternalError> {
+        let target_node = self
+            .base
+            .nodes
+            .get_mut(node_index)
+            .with_context(|| InternalSnafu {
+                context: format!("Cannot split missing node at index {node_index}"),
+            })?;
+        ensure!(
+            matches!(
+                target_node.operation,
+                Operation::Insert { .. } | Operation::Delete { .. }
+            ),
+            InternalSnafu {
+                context: format!("Cannot split a node without a value: {target_node:?}"),
+            }
+        );
+        ensure!(
             split_index <= target_node.last_index(),
-            "Cannot split a node beyond the end (at {split_index}): {:?}",
-            self.base.nodes[node_index]
+            InternalSnafu {
+                context: format!(
+                    "Cannot split a node beyond the end (at {split_index}): {target_node:?}"
+                ),
+            }
         );
-        let split_offset = split_index - target_node.id.index;
+        let split_offset = split_index
+            .checked_sub(target_node.id.index)
+            .with_context(|| InternalSnafu {
+                context: format!(
+                    "Cannot split a node before its beginning (at {split_index}): {target_node:?}"
+                ),
+            })?;
 
         let split_at_head = split_offset == 0;
         let split_at_end = split_index == target_node.last_index();
         match mode {
             SplitMode::Before => {
-                assert!(
+                ensure!(
                     !split_at_head,
-                    "Shouldn't have invoked split_node Before at the beginning of the node."
+                    InternalSnafu {
+                        context: "Shouldn't have invoked split_node Before at the beginning of the node.",
+                    }
                 );
             }
             SplitMode::After => {
-                assert!(
+                ensure!(
                     !split_at_end,
-                    "Shouldn't have invoked split_node After at the end of the node."
+                    InternalSnafu {
+                        context: "Shouldn't have invoked split_node After at the end of the node.",
+                    }
                 );
             }
             SplitMode::BeforeAndAfter => {
                 // We'll be a bit more lenient here and simply adjust the mode,
                 // as long as at least one split can happen here.
-                assert!(
+                ensure!(
                     !(split_at_head && split_at_end),
-                    "Shouldn't have invoked split_node BeforeAndAfter on a single element node (len={})",
-                    target_node.node_len()
+                    InternalSnafu {
+                        context: format!(
+                            "Shouldn't have invoked split_node BeforeAndAfter on a single element node (len={})",
+                            target_node.node_len()
+                        ),
+                    }
                 );
                 if split_at_head {
                     mode = SplitMode::After;
@@ -523,104 +945,52 @@ where
         }
         let node_is_deleted = target_node.is_deleted();
 
-        // Construct a placeholder we can swap into place while we are splitting.
-        let mut tmp_node = Node {
-            id: target_node.id.clone(),
-            left_origin: None,
-            right_origin: None,
-            operation: Operation::Invalid,
-        };
-        std::mem::swap(&mut tmp_node, target_node);
-
-        let (first_node, second_node, third_node_opt) = match mode {
+        // All preconditions hold, so none of the splits below can fail anymore.
+        // The first node stays in place and keeps the head of the value.
+        let (second_node_index, second_node_op, third_node_op_opt) = match mode {
             SplitMode::Before => {
-                let mut first_node = tmp_node;
-                let second_node_op = first_node
-                    .operation
-                    .split_off(split_offset)
-                    .expect("Cannot split node.");
-                let mut second_node_id = first_node.id.clone();
-                second_node_id.index = split_index;
-                let second_node = Node {
-                    id: second_node_id,
-                    // Keep origins in tact during splitting, so all split nodes are recognised as conflicting.
-                    left_origin: first_node.left_origin.clone(), //Some(first_node.last_id()),
-                    right_origin: first_node.right_origin.clone(),
-                    operation: second_node_op,
-                };
-                (first_node, second_node, None)
+                let second_node_op = target_node.operation.split_off(split_offset);
+                (split_index, second_node_op, None)
             }
             SplitMode::After => {
-                let mut first_node = tmp_node;
-                let second_node_op = first_node
-                    .operation
-                    .split_off(split_offset + 1)
-                    .expect("Cannot split node.");
-                let mut second_node_id = first_node.id.clone();
-                second_node_id.index = split_index + 1;
-                let second_node = Node {
-                    id: second_node_id,
-                    // Keep origins in tact during splitting, so all split nodes are recognised as conflicting.
-                    left_origin: first_node.left_origin.clone(), //Some(first_node.last_id()),
-                    right_origin: first_node.right_origin.clone(),
-                    operation: second_node_op,
-                };
-                (first_node, second_node, None)
+                let second_node_op = target_node.operation.split_off(split_offset + 1);
+                (split_index + 1, second_node_op, None)
             }
             SplitMode::BeforeAndAfter => {
-                let mut first_node = tmp_node;
-                let mut second_node_op = first_node
-                    .operation
-                    .split_off(split_offset)
-                    .expect("Cannot split node.");
-                let third_node_op = second_node_op.split_off(1).expect("Cannot split node");
-                let mut second_node_id = first_node.id.clone();
-                second_node_id.index = split_index;
-                let second_node = Node {
-                    id: second_node_id,
-                    // Keep origins in tact during splitting, so all split nodes are recognised as conflicting.
-                    left_origin: first_node.left_origin.clone(), //Some(first_node.last_id()),
-                    right_origin: first_node.right_origin.clone(),
-                    operation: second_node_op,
-                };
-                let third_node_id = second_node.id.increment();
-                let third_node = Node {
-                    id: third_node_id,
-                    // Keep origins in tact during splitting, so all split nodes are recognised as conflicting.
-                    left_origin: first_node.left_origin.clone(), //Some(second_node.last_id()),
-                    right_origin: first_node.right_origin.clone(),
-                    operation: third_node_op,
-                };
-
-                (first_node, second_node, Some(third_node))
+                let mut second_node_op = target_node.operation.split_off(split_offset);
+                let third_node_op = second_node_op
+                    .as_mut()
+                    .and_then(|second_node_op| second_node_op.split_off(1));
+                (split_index, second_node_op, third_node_op)
             }
         };
-        // Put the first node back into the original place.
-        *target_node = first_node;
+        let second_node_op = second_node_op.context(InternalSnafu {
+            context: "Cannot split node.",
+        })?;
+        let mut second_node_id = target_node.id.clone();
+        second_node_id.index = second_node_index;
+        let second_node = Node {
+            id: second_node_id,
+            // Keep origins in tact during splitting, so all split nodes are recognised as conflicting.
+            left_origin: target_node.left_origin.clone(),
+            right_origin: target_node.right_origin.clone(),
+            operation: second_node_op,
+        };
+        let third_node_opt = third_node_op_opt.map(|third_node_op| Node {
+            id: second_node.id.increment(),
+            // Keep origins in tact during splitting, so all split nodes are recognised as conflicting.
+            left_origin: target_node.left_origin.clone(),
+            right_origin: target_node.right_origin.clone(),
+            operation: third_node_op,
+        });
+
         // Insert second (and optionally third) node after.
         self.base.nodes.insert(node_index + 1, second_node);
-
-        // debug_assert_eq!(
-        //     Some(self.base.nodes[node_index].last_id()),
-        //     self.base.nodes[node_index + 1].left_origin,
-        //     "{:?} did not get the correct origin to fit behind {:?}",
-        //     self.base.nodes[node_index + 1],
-        //     self.base.nodes[node_index]
-        // );
-
         if let Some(third_node) = third_node_opt {
             self.base.nodes.insert(node_index + 2, third_node);
-
-            // debug_assert_eq!(
-            //     Some(self.base.nodes[node_index + 1].last_id()),
-            //     self.base.nodes[node_index + 2].left_origin,
-            //     "{:?} did not get the correct origin to fit behind {:?}",
-            //     self.base.nodes[node_index + 2],
-            //     self.base.nodes[node_index + 1]
-            // );
         }
 
-        match mode {
+        let new_node_index = match mode {
             SplitMode::Before => {
                 if !node_is_deleted {
                     self.base.len += 1;
@@ -639,7 +1009,8 @@ where
                 }
                 node_index + 1
             }
-        }
+        };
+        Ok(new_node_index)
     }
 
     /// Validate the internal node structure, cached visible length, and id-range uniqueness.
@@ -683,11 +1054,180 @@ where
 
         Ok(())
     }
+
+    /// Conflict and storage statistics of this document.
+    #[must_use]
+    pub fn stats(&self) -> LinearDataStats {
+        let mut stats = LinearDataStats {
+            conflicts_resolved: self.conflicts_resolved,
+            ..LinearDataStats::default()
+        };
+        let mut previous: Option<&Node<IdWithIndex<BaseId>, Value>> = None;
+        for node in &self.base.nodes {
+            if !matches!(
+                node.operation,
+                Operation::Insert { .. } | Operation::Delete { .. }
+            ) {
+                previous = None;
+                continue;
+            }
+            let node_len = node.node_len();
+            stats.elements += node_len;
+            if node.is_deleted() {
+                stats.tombstones += node_len;
+            }
+            stats.nodes += 1;
+            if !previous.is_some_and(|previous| can_merge(previous, node)) {
+                stats.defragmented_nodes += 1;
+            }
+            previous = Some(node);
+        }
+        stats
+    }
+
+    /// Merge adjacent nodes that were split off the same insert back into single nodes.
+    ///
+    /// Nodes are split when something is inserted into or deleted from their middle. Deletes
+    /// already merge the parts they leave contiguous, so this is mainly needed for documents
+    /// restored from snapshots that still contain such parts. This does not change the document
+    /// or how later operations are integrated.
+    ///
+    /// Returns the number of nodes that were merged away.
+    pub fn defragment(&mut self) -> usize {
+        let nodes = std::mem::take(&mut self.base.nodes);
+        let node_count = nodes.len();
+        let mut merged_nodes: NodeVec<IdWithIndex<BaseId>, Value> =
+            NodeVec::with_capacity(node_count);
+        for node in nodes {
+            match merged_nodes.last_mut() {
+                Some(previous) if can_merge(previous, &node) => {
+                    previous.operation.merge(node.operation);
+                }
+                _ => merged_nodes.push(node),
+            }
+        }
+        merged_nodes.shrink_to_fit();
+        self.base.len = merged_nodes
+            .iter()
+            .filter(|node| matches!(node.operation, Operation::Insert { .. }))
+            .count();
+        self.base.nodes = merged_nodes;
+        node_count - self.base.nodes.len()
+    }
+
+    /// Apply `operations` in an order that respects the ids they anchor on.
+    ///
+    /// Operations are sorted so that each one comes after the inserts in the batch it depends
+    /// on, and otherwise keep their batch order. Operations that still do not fit are retried
+    /// as long as others make progress, and are returned as blocked instead of failing the batch.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first internal inconsistency. Operations applied before it stay applied.
+    pub fn apply_batch(
+        &mut self,
+        operations: Vec<DataOperation<IdWithIndex<BaseId>, Value>>,
+    ) -> Result<BatchResult<DataOperation<IdWithIndex<BaseId>, Value>>, InternalError> {
+        let mut pending = batch::causal_order(operations);
+        let mut applied = 0;
+        let mut made_progress = true;
+        while made_progress && !pending.is_empty() {
+            let applied_before = applied;
+            let mut blocked = Vec::new();
+            for operation in pending {
+                match self.apply_operation(operation) {
+                    Ok(()) => applied += 1,
+                    Err(ApplyFailure::Rejected { operation }) => blocked.push(operation),
+                    Err(ApplyFailure::Internal { operation, source }) => {
+                        let mut scope = ErrorScope::default();
+                        if let Some(id) = operation.first_id() {
+                            scope = scope.with_operation_id(format_args!("{id:?}"));
+                        }
+                        return Err(source.in_scope(&scope));
+                    }
+                }
+            }
+            made_progress = applied > applied_before;
+            pending = blocked;
+        }
+        Ok(BatchResult {
+            applied,
+            blocked: pending,
+        })
+    }
+}
+impl<BaseId, Value, Summary> ApplyBatch for VecCoalescedLinearData<BaseId, Value, Summary>
+where
+    BaseId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    Value: Composite + fmt::Debug + 'static,
+    Summary: ValueSummary<Value>,
+{
+    type Operation = DataOperation<IdWithIndex<BaseId>, Value>;
+
+    fn apply_batch(
+        &mut self,
+        operations: Vec<Self::Operation>,
+    ) -> Result<BatchResult<Self::Operation>, InternalError> {
+        VecCoalescedLinearData::apply_batch(self, operations)
+    }
 }
-impl<BaseId, Value> LinearData<Value, Value::Element> for VecCoalescedLinearData<BaseId, Value>
+impl<BaseId, Value, Summary> VecCoalescedLinearData<BaseId, Value, Summary> {
+    /// The number of stored nodes, including both boundaries and deleted elements.
+    pub(crate) fn node_count(&self) -> usize {
+        self.base.node_count()
+    }
+
+    /// Iterate over the visible runs in document order, each with the id of its first element.
+    ///
+    /// The element at offset `n` within a run has the id's index advanced by `n`.
+    pub fn iter_with_ids(&self) -> impl Iterator<Item = (&IdWithIndex<BaseId>, &Value)> {
+        self.iter_content_nodes()
+            .filter_map(|(id, deleted, value)| option_when!(!deleted, (id, value)))
+    }
+
+    /// All nodes between the boundaries in document order, with whether they were deleted.
+    pub(crate) fn iter_content_nodes(
+        &self,
+    ) -> impl Iterator<Item = (&IdWithIndex<BaseId>, bool, &Value)> {
+        let nodes = &self.base.nodes;
+        nodes
+            .get(1..nodes.len().saturating_sub(1))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|node| match &node.operation {
+                Operation::Insert { value } => Some((&node.id, false, value)),
+                Operation::Delete { value } => Some((&node.id, true, value)),
+                Operation::Beginning | Operation::End | Operation::Invalid => None,
+            })
+    }
+}
+impl<BaseId, Value, Summary> IntegrationNodes for VecCoalescedLinearData<BaseId, Value, Summary>
+where
+    BaseId: fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord,
+{
+    type Id = IdWithIndex<BaseId>;
+    type Value = Value;
+    /// Chunks of the same insert never conflict, so only the base id orders concurrent inserts.
+    type ConflictKey = BaseId;
+
+    fn node_count(&self) -> usize {
+        self.base.nodes.len()
+    }
+
+    fn node(&self, index: usize) -> &Node<IdWithIndex<BaseId>, Value> {
+        &self.base.nodes[index]
+    }
+
+    fn conflict_key(id: &IdWithIndex<BaseId>) -> &BaseId {
+        &id.id
+    }
+}
+impl<BaseId, Value, Summary> LinearData<Value, Value::Element>
+    for VecCoalescedLinearData<BaseId, Value, Summary>
 where
     BaseId: Clone + fmt::Debug + PartialEq + Eq + PartialOrd + Ord + Hash + 'static,
     Value: Composite + fmt::Debug + 'static,
+    Summary: ValueSummary<Value>,
 {
     type Id = IdWithIndex<BaseId>;
 
@@ -721,7 +1261,7 @@ where
 
             // All of these must exist if the list is valid.
             let current_node = &self.base.nodes[node_index_at_position];
-            let current = &current_node.id + position_offset;
+            let current = current_node.id_at_offset(position_offset);
             let predecessor = self.predecessor_id(&current, node_index_at_position);
             let successor = self.successor_id(&current, node_index_at_position);
             Some(NodeIds {
@@ -741,331 +1281,102 @@ where
         succ: Self::Id,
         value: Value,
     ) -> Result<(), Value> {
-        self.apply_operation(DataOperation::Insert {
-            id,
-            pred,
-            succ,
-            value,
-        })
-        .map_err(|op| match op {
-            DataOperation::Insert { value, .. } => value,
-            DataOperation::Delete { .. } => {
-                // The apply_operation should not return a different operation type on error.
-                unreachable!();
+        match self.make_insert_position(&id, &pred, &succ, &value) {
+            Ok(Some(position)) => {
+                self.insert_node(position, id, pred, succ, value);
+                Ok(())
             }
-        })
+            Ok(None) | Err(_) => Err(value),
+        }
     }
 
     fn delete<'a>(&'a mut self, id: &Self::Id) -> Option<&'a Value::Element> {
-        //println!("Trying to delete id={id:?} from: {:#?}", self.nodes);
-        let (node_index, node) = self
-            .base
-            .nodes
-            .iter()
-            .enumerate()
-            .find(|(_index, n)| n.contains(id))?;
-
-        // We are only supposed to delete a single element here.
-        let must_split = matches!(
-            node.operation,
-            Operation::Insert { ref value } if value.len() > 1
-        );
-        let node_index = if must_split {
-            self.split_node(node_index, id.index, SplitMode::BeforeAndAfter)
-        } else {
-            node_index
-        };
-
-        let node = &mut self.base.nodes[node_index];
+        let node_index = self.delete_element(id).ok().flatten()?;
+        let node = &self.base.nodes[node_index];
         match node.operation {
-            Operation::Insert { ref value } => {
-                debug_assert_eq!(value.len(), 1);
-                node.operation.delete();
-                self.len -= 1;
-                self.base.len -= 1;
-                if let Operation::Delete { ref value } = node.operation {
-                    value.get(0)
-                } else {
-                    // We literally just put it there.
-                    unreachable!()
-                }
-            }
-            // Double delete is OK.
-            Operation::Delete { ref value } => value.get((id.index - node.id.index) as usize),
-            // These cannot be deleted.
-            Operation::Beginning | Operation::End => {
-                //println!("Tried to delete Beginning/End");
-                None
-            }
-            Operation::Invalid => panic!("Node is invalid."),
+            Operation::Delete { ref value } => value.get(node.offset_of(id) as usize),
+            _ => None,
         }
     }
 
-    #[allow(
-        clippy::too_many_lines,
-        reason = "Insert application validates predecessor, successor, duplicate, and sibling-order cases as one CRDT transition."
-    )]
     fn apply_operation(
         &mut self,
         operation: DataOperation<Self::Id, Value>,
-    ) -> Result<(), DataOperation<Self::Id, Value>> {
+    ) -> Result<(), ApplyFailure<DataOperation<Self::Id, Value>>> {
         match operation {
             DataOperation::Insert {
-                ref id,
-                ref pred,
-                ref succ,
-                ref value,
-                ..
-            } => {
-                if !id.can_address(value.len()) {
-                    return Err(operation);
+                id,
+                pred,
+                succ,
+                value,
+            } => match self.make_insert_position(&id, &pred, &succ, &value) {
+                Ok(Some(position)) => {
+                    self.insert_node(position, id, pred, succ, value);
+                    Ok(())
                 }
-                //println!("Inserting {:?}", operation);
-                let pred_opt = self
-                    .base
-                    .nodes
-                    .iter()
-                    .enumerate()
-                    .find(|(_, node)| node.contains(pred));
-                if let Some((pred_index, pred_node)) = pred_opt {
-                    let succ_opt = if pred_node.contains(succ) {
-                        Some((pred_index, pred_node))
-                    } else {
-                        self.base
-                            .nodes
-                            .iter()
-                            .enumerate()
-                            .skip(pred_index)
-                            .find(|(_, node)| node.contains(succ))
-                    };
-                    if let Some((succ_index, succ_node)) = succ_opt {
-                        if pred_index == succ_index {
-                            if pred.is_followed_by(succ) {
-                                // println!(
-                                //     "Succ and pred are the same node {pred_index} and we need to split at {}",
-                                //     succ.index
-                                // );
-                                // We need to split and then we can insert where we split.
-                                let new_succ_index =
-                                    self.split_node(pred_index, succ.index, SplitMode::Before);
-                                // Now we don't need the original operation anymore, so we can properly deconstruct it.
-                                if let DataOperation::Insert {
-                                    id,
-                                    pred,
-                                    succ,
-                                    value,
-                                } = operation
-                                {
-                                    self.len += value.len();
-                                    self.base.len += 1;
-                                    self.base.nodes.insert(
-                                        new_succ_index,
-                                        Node {
-                                            id,
-                                            left_origin: Some(pred),
-                                            right_origin: Some(succ),
-                                            operation: Operation::Insert { value },
-                                        },
-                                    );
-                                    Ok(())
-                                } else {
-                                    unreachable!("We *know* it's an Insert.");
-                                }
-                            } else {
-                                // I don't think this should happen until we try to merge nodes.
-                                // Any insert will pick 2 consecutive nodes, and if they aren't
-                                // split, there can't have been a concurrent insert in between them,
-                                // so they must be adjacent.
-                                unreachable!(
-                                    "The nodes are apart and we would need to resolve a position, but they aren't split so there cannot have been a concurrent insertion."
-                                );
-                            }
-                        } else if pred_index + 1 == succ_index {
-                            if pred_node.last_id() == *pred && succ_node.id == *succ {
-                                // println!(
-                                //     "Succ is the immediate successor of pred, and we just insert at the boundary."
-                                // );
-                                // We can just insert directly at the existing boundary.
-
-                                // Now we don't need the original operation anymore, so we can properly deconstruct it.
-                                if let DataOperation::Insert {
-                                    id,
-                                    pred,
-                                    succ,
-                                    value,
-                                    ..
-                                } = operation
-                                {
-                                    self.len += value.len();
-                                    self.base.len += 1;
-                                    self.base.nodes.insert(
-                                        succ_index,
-                                        Node {
-                                            id,
-                                            left_origin: Some(pred),
-                                            right_origin: Some(succ),
-                                            operation: Operation::Insert { value },
-                                        },
-                                    );
-                                    Ok(())
-                                } else {
-                                    unreachable!("We *know* it's an Insert.");
-                                }
-                            } else {
-                                // I don't think this can happen either, for the same reason as
-                                // above.
-                                // Either some concurrent insert picked the same position,
-                                // and then there should be a split at that exact position already
-                                // (and we are in the branch below),
-                                // or there hasn't been a concurrent insert and then we should be
-                                // in the is-followed-by case above.
-                                unreachable!(
-                                    "The nodes are apart and we would need to resolve a position, but our predecessor and successor ids aren't aligned with the boundaries of that split."
-                                );
-                            }
-                        } else {
-                            // println!(
-                            //     "There is a gap between between pred={pred_index} and succ={succ_index} and we need to find where to insert among:\n {}",
-                            //     DebugSlice(&self.base.nodes[pred_index..=succ_index]).debug_fmt()
-                            // );
-                            // Must find a position between pred_index and succ_index.
-                            // Sub-splits should not be necessary, since the position cannot be
-                            // within another concurrently inserted node.
-                            // (Concurrent conflict are resolved comparing the Id without the index
-                            // and within a node only the index ever changes.)
-
-                            // We are trying to find the left-most (lowest index) position where
-                            // everything before us has an origin to the right of us
-                            // or has the same origin but a lower Id.
-
-                            let left_right_range = (pred_index + 1)..succ_index;
-                            let mut conflicting_nodes: Vec<(&BaseId, usize)> =
-                                Vec::with_capacity(left_right_range.len());
-                            // The right subtree is all the nodes that have succ as their successor,
-                            // and all node that can reach those nodes by following right_origin.
-                            let mut right_subtree_start_index_opt = None;
-                            let mut right_tree_memo =
-                                RightTreeTraversalMemo::new(&self.base.nodes, succ);
-                            for node_index in left_right_range {
-                                let node = &self.base.nodes[node_index];
-                                if node.left_origin.as_ref() == Some(pred)
-                                    && node.right_origin.as_ref() == Some(succ)
-                                {
-                                    conflicting_nodes.push((&node.id.id, node_index));
-                                }
-                                // Don't overwrite this with a later node.
-                                if right_subtree_start_index_opt.is_none()
-                                    && right_tree_memo.reaches_boundary(node_index)
-                                {
-                                    right_subtree_start_index_opt = Some(node_index);
-                                }
-                            }
-                            //println!("Right subtree starts at {right_subtree_start_index_opt:?}");
-                            let right_subtree_start_index =
-                                right_subtree_start_index_opt.unwrap_or(succ_index);
-
-                            let position = if conflicting_nodes.is_empty() {
-                                // println!(
-                                //     "The conflict range is empty, no other node has left={pred:?}, right={succ:?}"
-                                // );
-                                right_subtree_start_index
-                            } else {
-                                // Double check `conflicting_nodes` is already ordered correctly.
-                                debug_assert!(
-                                    conflicting_nodes.is_sorted_by_key(|(one, _)| one),
-                                    "Conflict range should have been sorted by id already, but was: {conflicting_nodes:?}"
-                                );
-                                match conflicting_nodes
-                                    .binary_search_by(|&(probe, _)| probe.cmp(&id.id))
-                                {
-                                    Ok(_found_index) => {
-                                        // let (conflicting_id, conflicting_pos) =
-                                        //     conflicting_nodes[found_index];
-                                        // eprintln!(
-                                        //     "There is an existing node with the same base id={:?}. Nodes with the same base id should not conflict! Full id to be inserted = {:?}. Conflicting node: {:?}",
-                                        //     conflicting_id, id, &self.base.nodes[conflicting_pos]
-                                        // );
-                                        return Err(operation);
-                                    }
-                                    Err(insert_index) => {
-                                        // Still need to translate this into an index on base.nodes instead onf conflicting_nodes.
-                                        if insert_index == 0 {
-                                            // If we are supposed to insert before the first conflicting node,
-                                            // we must actually insert before *any* node in the range,
-                                            // because they might all have anchored off that first conflicting node.
-                                            pred_index + 1
-                                        } else if insert_index < conflicting_nodes.len() {
-                                            let target_conflict_pos =
-                                                conflicting_nodes[insert_index].1;
-                                            let target_conflict_id =
-                                                &self.base.nodes[target_conflict_pos].id;
-                                            // Insert before the target conflicting node's local
-                                            // subtree, not just before the node itself.
-                                            // Otherwise sibling subtree order can depend on
-                                            // delivery order.
-                                            let mut target_tree_memo = right_tree_memo
-                                                .with_new_boundary(target_conflict_id);
-                                            let mut subtree_start = target_conflict_pos;
-                                            for node_index in (pred_index + 1)..target_conflict_pos
-                                            {
-                                                if target_tree_memo.reaches_boundary(node_index) {
-                                                    subtree_start = node_index;
-                                                    break;
-                                                }
-                                            }
-                                            subtree_start
-                                        } else {
-                                            // It has to be right of all the conflicting nodes.
-                                            // Insert just before succ.
-                                            succ_index
-                                        }
-                                    }
-                                }
-                            };
-
-                            // println!(
-                            //     "Determined to insert at {position}, i.e. before {:?}",
-                            //     &self.base.nodes[position]
-                            // );
-
-                            // Now we don't need the original operation anymore, so we can properly deconstruct it.
-                            if let DataOperation::Insert {
-                                id,
-                                pred,
-                                succ,
-                                value,
-                                ..
-                            } = operation
-                            {
-                                self.len += value.len();
-                                self.base.len += 1;
-                                self.base.nodes.insert(
-                                    position,
-                                    Node {
-                                        id,
-                                        left_origin: Some(pred),
-                                        right_origin: Some(succ),
-                                        operation: Operation::Insert { value },
-                                    },
-                                );
-                                Ok(())
-                            } else {
-                                unreachable!("We *know* it's an Insert.");
-                            }
+                Ok(None) => Err(ApplyFailure::Rejected {
+                    operation: DataOperation::Insert {
+                        id,
+                        pred,
+                        succ,
+                        value,
+                    },
+                }),
+                Err(source) => Err(ApplyFailure::Internal {
+                    operation: DataOperation::Insert {
+                        id,
+                        pred,
+                        succ,
+                        value,
+                    },
+                    source,
+                }),
+            },
+            DataOperation::Delete { ref start, ref end } => {
+                let result = match end {
+                    Some(end) => self.delete_range(start, end),
+                    None => match self.delete_element(start) {
+                        Ok(Some(_)) => Ok(()),
+                        Ok(None) => Err(DeleteError::NotFound),
+                        Err(source) => Err(DeleteError::Internal { source }),
+                    },
+                };
+                match result {
+                    Ok(()) => Ok(()),
+                    Err(DeleteError::Internal { source }) => {
+                        Err(ApplyFailure::Internal { operation, source })
+                    }
+                    Err(DeleteError::InvalidRange | DeleteError::NotFound) => {
+                        Err(ApplyFailure::Rejected { operation })
+                    }
+                }
+            }
+            DataOperation::DeleteRanges { ref ranges } => {
+                // Check every range first, so a bad range leaves the document untouched.
+                for range in ranges {
+                    match self.plan_delete_range(&range.start, &range.end) {
+                        Ok(_) => (),
+                        Err(DeleteError::Internal { source }) => {
+                            return Err(ApplyFailure::Internal { operation, source });
+                        }
+                        Err(DeleteError::InvalidRange | DeleteError::NotFound) => {
+                            return Err(ApplyFailure::Rejected { operation });
                         }
-                    } else {
-                        // println!("Successor {succ:?} does not exist.");
-                        Err(operation)
                     }
-                } else {
-                    // println!("Pred {pred:?} does not exist.");
-                    Err(operation)
                 }
+                for range in ranges {
+                    match self.delete_range(&range.start, &range.end) {
+                        Ok(()) => (),
+                        Err(DeleteError::Internal { source }) => {
+                            return Err(ApplyFailure::Internal { operation, source });
+                        }
+                        Err(DeleteError::InvalidRange | DeleteError::NotFound) => {
+                            return Err(ApplyFailure::Rejected { operation });
+                        }
+                    }
+                }
+                Ok(())
             }
-            DataOperation::Delete { ref start, ref end } => match end {
-                Some(end) => self.delete_range(start, end).map_err(|_| operation),
-                None => self.delete(start).map(|_| ()).ok_or(operation),
-            },
         }
     }
 
@@ -1080,7 +1391,7 @@ where
         self.base.iter_ids()
     }
 }
-impl<BaseId, Value> DebugFormatting for VecCoalescedLinearData<BaseId, Value>
+impl<BaseId, Value, Summary> DebugFormatting for VecCoalescedLinearData<BaseId, Value, Summary>
 where
     BaseId: fmt::Display + 'static,
     Value: Composite + fmt::Display + 'static,
@@ -1095,6 +1406,40 @@ where
     }
 }
 
+/// Where the value of the node at `node_index` is, relative to the other visible values.
+fn change_context<Id, Value>(
+    nodes: &[Node<Id, Value>],
+    node_index: usize,
+) -> ChangeContext<'_, Value::Element>
+where
+    Value: Composite,
+{
+    fn visible_value<Id, Value>(node: &Node<Id, Value>) -> Option<&Value>
+    where
+        Value: Composite,
+    {
+        match &node.operation {
+            Operation::Insert { value } if !value.is_empty() => Some(value),
+            _ => None,
+        }
+    }
+    let mut position = 0;
+    let mut before = None;
+    for value in nodes[..node_index].iter().filter_map(visible_value) {
+        position += value.len();
+        before = Some(value);
+    }
+    let after = nodes[node_index + 1..]
+        .iter()
+        .find_map(visible_value)
+        .and_then(Composite::first);
+    ChangeContext {
+        position,
+        before: before.and_then(Composite::last),
+        after,
+    }
+}
+
 /// Just a newtype to wrap the slice for debug printing.
 struct DebugSlice<'a, BaseId, Value>(&'a [Node<IdWithIndex<BaseId>, Value>]);
 impl<BaseId, Value> DebugFormatting for DebugSlice<'_, BaseId, Value>
@@ -1240,6 +1585,47 @@ struct NodePosition {
 //     }
 // }
 
+/// Ids taken from an id generator ahead of time, see [[`ReserveIds::reserve`]].
+///
+/// This is itself an id generator that yields exactly the reserved ids.
+#[derive(Clone, Debug)]
+pub struct ReservedIds<Id> {
+    ids: std::vec::IntoIter<Id>,
+}
+impl<Id> Iterator for ReservedIds<Id> {
+    type Item = Id;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ids.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ids.size_hint()
+    }
+}
+impl<Id> ExactSizeIterator for ReservedIds<Id> {}
+
+/// Batch allocation for id generators.
+///
+/// Reserving ids up front allows producing them before an async boundary, or keeping a shared
+/// generator locked only briefly, and then computing diffs from the reserved ids later.
+pub trait ReserveIds: Iterator {
+    /// Take the next `count` ids from this generator.
+    ///
+    /// Returns `None` if fewer than `count` ids were available. The ids that were available are
+    /// consumed regardless.
+    fn reserve(&mut self, count: usize) -> Option<ReservedIds<Self::Item>>
+    where
+        Self: Sized,
+    {
+        let ids: Vec<Self::Item> = self.by_ref().take(count).collect();
+        (ids.len() == count).then(|| ReservedIds {
+            ids: ids.into_iter(),
+        })
+    }
+}
+impl<I> ReserveIds for I where I: Iterator {}
+
 /// An [[`IdWithIndex`]] generator that increments `index` first and only increments the underlying iterator when it has exhausted the `u32` index space.
 pub struct IdGeneratorWithIndex<'a, I>
 where
@@ -1263,6 +1649,34 @@ where
         }
     }
 
+    /// Reserve a contiguous block of `count` indices under a single major id.
+    ///
+    /// Returns the first id of the block; the block covers the next `count` indices from there.
+    /// If the current major id does not have enough indices left, the block starts at index 0 of
+    /// the next major id instead. At least one index is always reserved.
+    ///
+    /// Returns `None` without consuming anything if `count` exceeds the `u32` index space, and
+    /// `None` if the underlying iterator ran out of major ids.
+    pub fn reserve(&mut self, count: usize) -> Option<IdWithIndex<I::Item>>
+    where
+        I::Item: Clone,
+    {
+        let count = u64::try_from(count).ok()?.max(1);
+        let index_space = u64::from(u32::MAX) + 1;
+        if count > index_space {
+            return None;
+        }
+        self.advance_to_available_index()?;
+        if index_space - self.next_index < count {
+            self.advance_to_next_major_id()?;
+        }
+        let index = u32::try_from(self.next_index).expect(
+            "We already reset this if necessary and possible, so this should always work now.",
+        );
+        self.next_index += count;
+        self.current_id.clone().map(|id| IdWithIndex { id, index })
+    }
+
     fn load_next_major_id(&mut self) -> Option<()> {
         self.current_id = self.underlying.next();
         if self.current_id.is_none() {
@@ -1324,6 +1738,7 @@ where
 /// without having to do explictly different operations for each.
 #[allow(unused)]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct IdWithIndex<Id> {
     pub id: Id,
     pub index: u32,
@@ -1416,29 +1831,40 @@ where
                 .is_some_and(|next_index| next_index == other.index)
     }
 
-    fn index_diff(&self, other: &IdWithIndex<Id>) -> u32
+    /// The number of indices between `self` and `other`, or `None` if they belong to different
+    /// ids.
+    #[must_use]
+    pub fn distance(&self, other: &Self) -> Option<u32>
     where
         Id: PartialEq,
     {
-        assert!(self.id == other.id);
-        self.index
-            .checked_sub(other.index)
-            .expect("Index different overflowed")
+        option_when!(self.id == other.id, self.index.abs_diff(other.index))
     }
 
     #[must_use]
     pub fn checked_next_after(&self, num_elements: usize) -> Option<Self> {
         let offset = u32::try_from(num_elements).ok()?;
-        self.checked_add_offset(offset)
+        self.checked_add(offset)
     }
 
+    /// Gives the sub-id `rhs` indices after `self`, if there are enough indices left.
     #[must_use]
-    pub fn checked_add_offset(&self, rhs: u32) -> Option<Self> {
+    pub fn checked_add(&self, rhs: u32) -> Option<Self> {
         let mut next = self.clone();
         next.index = next.index.checked_add(rhs)?;
         Some(next)
     }
 
+    /// Gives the sub-id `rhs` indices after `self`, or the one with the largest index if there
+    /// are not enough indices left.
+    #[must_use]
+    pub fn saturating_add(&self, rhs: u32) -> Self {
+        Self {
+            id: self.id.clone(),
+            index: self.index.saturating_add(rhs),
+        }
+    }
+
     /// Returns a new id where `id` is the the same as `self.id` and `index` is the largest
     /// possible value.
     #[must_use]
@@ -1465,8 +1891,12 @@ where
 {
     type Output = Self;
 
+    /// # Panics
+    ///
+    /// Panics if the result would require indices > `u32::MAX`, use
+    /// [`checked_add`](IdWithIndex::checked_add) for untrusted offsets.
     fn add(self, rhs: u32) -> Self::Output {
-        self.checked_add_offset(rhs)
+        self.checked_add(rhs)
             .expect("Adding the offset would require indices > u32::MAX")
     }
 }
@@ -1476,8 +1906,12 @@ where
 {
     type Output = IdWithIndex<Id>;
 
+    /// # Panics
+    ///
+    /// Panics if the result would require indices > `u32::MAX`, use
+    /// [`checked_add`](IdWithIndex::checked_add) for untrusted offsets.
     fn add(self, rhs: u32) -> Self::Output {
-        self.checked_add_offset(rhs)
+        self.checked_add(rhs)
             .expect("Adding the offset would require indices > u32::MAX")
     }
 }
@@ -1554,12 +1988,13 @@ where
     ///
     /// Returns the first failing range if unsuccessful.
     /// In this case the previous deletes will have been applied.
-    pub fn delete<'a, Value>(
+    pub fn delete<'a, Value, Summary>(
         &'a self,
-        data: &mut VecCoalescedLinearData<Id, Value>,
+        data: &mut VecCoalescedLinearData<Id, Value, Summary>,
     ) -> Result<(), &'a IdWithIndexRange<Id>>
     where
         Value: Composite + fmt::Debug + 'static,
+        Summary: ValueSummary<Value>,
     {
         for id_range in &self.contained {
             let start = id_range.first();
@@ -1573,28 +2008,103 @@ where
         Ok(())
     }
 
+    /// The operations that delete all the nodes contained in the range.
+    ///
+    /// Ranges that span several updates are deleted by a single
+    /// [`DataOperation::DeleteRanges`].
     pub fn delete_operations<T>(self) -> impl Iterator<Item = DataOperation<IdWithIndex<Id>, T>> {
-        self.contained.into_iter().map(|range| {
-            let start = range.first();
-            let end = range.last();
-            DataOperation::Delete {
-                start,
-                end: Some(end),
-            }
-        })
+        let mut contained = self.contained;
+        let operation = match contained.len() {
+            0 => None,
+            1 => contained.pop().map(|range| DataOperation::Delete {
+                start: range.first(),
+                end: Some(range.last()),
+            }),
+            _ => Some(DataOperation::DeleteRanges {
+                ranges: contained
+                    .into_iter()
+                    .map(|range| IdRange {
+                        start: range.first(),
+                        end: range.last(),
+                    })
+                    .collect(),
+            }),
+        };
+        operation.into_iter()
     }
 }
 
+/// Whether `next` directly continues `node` as a part that was split off the same insert.
+///
+/// Splitting keeps the origins of the original node, so only parts that still share them and
+/// are in the same state are merged.
+fn can_merge<BaseId, Value>(
+    node: &Node<IdWithIndex<BaseId>, Value>,
+    next: &Node<IdWithIndex<BaseId>, Value>,
+) -> bool
+where
+    BaseId: Clone + PartialEq,
+    Value: Composite,
+{
+    let same_state = matches!(
+        (&node.operation, &next.operation),
+        (Operation::Insert { .. }, Operation::Insert { .. })
+            | (Operation::Delete { .. }, Operation::Delete { .. })
+    );
+    same_state
+        && node.last_id().is_followed_by(&next.id)
+        && node.left_origin == next.left_origin
+        && node.right_origin == next.right_origin
+}
+
 #[cfg(test)]
 mod tests {
     use itertools::Itertools;
-
-    use super::{IdGeneratorWithIndex, IdWithIndex};
+    use std::assert_matches;
+
+    use super::{
+        ApplyFailure,
+        DataOperation,
+        IdGeneratorWithIndex,
+        IdRange,
+        IdWithIndex,
+        IdWithIndexRange,
+        LinearData,
+        NodeIdRange,
+        ReserveIds,
+        SplitMode,
+        VecCoalescedLinearData,
+    };
+    use crate::text::GraphemeString;
+    use std::ops::Bound;
+
+    type TestData = VecCoalescedLinearData<u32, GraphemeString>;
 
     fn indexed(id: u32, index: u32) -> IdWithIndex<u32> {
         IdWithIndex { id, index }
     }
 
+    fn text(value: &str) -> GraphemeString {
+        GraphemeString::new(value.to_owned())
+    }
+
+    fn content(data: &TestData) -> String {
+        data.iter_values().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn id_with_index_arithmetic_reports_overflow() {
+        let near_max = indexed(7, u32::MAX - 1);
+        assert_eq!(near_max.checked_add(1), Some(indexed(7, u32::MAX)));
+        assert_eq!(near_max.checked_add(2), None);
+        assert_eq!(near_max.saturating_add(5), indexed(7, u32::MAX));
+        assert_eq!(near_max.checked_next_after(usize::MAX), None);
+
+        assert_eq!(indexed(7, 3).distance(&indexed(7, 10)), Some(7));
+        assert_eq!(indexed(7, 10).distance(&indexed(7, 3)), Some(7));
+        assert_eq!(indexed(7, 3).distance(&indexed(8, 3)), None);
+    }
+
     #[test]
     fn id_generator_with_index_reuses_major_id_with_increasing_indices() {
         let mut ids = [7].into_iter();
@@ -1672,7 +2182,7 @@ mod tests {
         let mut generator = IdGeneratorWithIndex::new(&mut ids);
         generator.next_index = u64::from(u32::MAX) - 1;
 
-        /// Should *not* wrap into 8, 3! Land on next major id when exhausted.
+        // Should *not* wrap into 8, 3! Land on next major id when exhausted.
         assert_eq!(generator.nth(5), Some(indexed(8, 0)));
         assert_eq!(generator.next(), Some(indexed(8, 1)));
     }
@@ -1689,6 +2199,46 @@ mod tests {
         assert_eq!(generator.next(), Some(indexed(9, 1)));
     }
 
+    #[test]
+    fn id_generator_with_index_reserve_returns_contiguous_blocks() {
+        let mut ids = [7, 8].into_iter();
+        let mut generator = IdGeneratorWithIndex::new(&mut ids);
+
+        assert_eq!(generator.reserve(3), Some(indexed(7, 0)));
+        assert_eq!(generator.reserve(0), Some(indexed(7, 3)));
+        assert_eq!(generator.next(), Some(indexed(7, 4)));
+
+        generator.next_index = u64::from(u32::MAX) - 1;
+        assert_eq!(generator.reserve(2), Some(indexed(7, u32::MAX - 1)));
+        generator.next_index = u64::from(u32::MAX) - 1;
+        // Does not fit into the rest of major id 7 anymore.
+        assert_eq!(generator.reserve(3), Some(indexed(8, 0)));
+        assert_eq!(generator.next(), Some(indexed(8, 3)));
+    }
+
+    #[test]
+    fn id_generator_with_index_reserve_rejects_blocks_beyond_index_space() {
+        let mut ids = [7].into_iter();
+        let mut generator = IdGeneratorWithIndex::new(&mut ids);
+
+        assert_eq!(generator.reserve(u32::MAX as usize + 2), None);
+        assert_eq!(
+            generator.reserve(u32::MAX as usize + 1),
+            Some(indexed(7, 0))
+        );
+        assert_eq!(generator.next(), None);
+    }
+
+    #[test]
+    fn reserve_ids_takes_requested_ids_up_front() {
+        let mut ids = 1..=5u32;
+
+        let reserved = ids.reserve(3).unwrap();
+        assert_eq!(ids.next(), Some(4));
+        assert_eq!(reserved.collect_vec(), vec![1, 2, 3]);
+        assert!(ids.reserve(2).is_none());
+    }
+
     #[test]
     fn id_generator_with_index_next_returns_none_without_major_ids() {
         let mut ids = std::iter::empty::<u32>();
@@ -1707,4 +2257,248 @@ mod tests {
         assert_eq!(generator.nth(1), None);
         assert_eq!(generator.next(), None);
     }
+
+    #[test]
+    fn range_deletes_cannot_remove_boundaries() {
+        // The boundaries share the base id of the initial value.
+        let mut data = TestData::with_value(0, text("abcd"));
+        for (start, end) in [(0, 2), (3, 5), (0, 5)] {
+            let res = data.apply_operation(DataOperation::Delete {
+                start: indexed(0, start),
+                end: Some(indexed(0, end)),
+            });
+            assert_matches!(res, Err(ApplyFailure::Rejected { .. }));
+        }
+        assert_eq!(content(&data), "abcd");
+        data.validate_integrity().unwrap();
+    }
+
+    #[test]
+    fn range_deletes_across_split_nodes_delete_the_whole_range() {
+        let mut data = TestData::with_value(0, text("abcdef"));
+        // Split the initial value between "c" and "d".
+        data.apply_operation(DataOperation::Insert {
+            id: indexed(5, 0),
+            pred: indexed(0, 3),
+            succ: indexed(0, 4),
+            value: text("x"),
+        })
+        .unwrap();
+        assert_eq!(content(&data), "abcxdef");
+
+        data.apply_operation(DataOperation::Delete {
+            start: indexed(0, 2),
+            end: Some(indexed(0, 5)),
+        })
+        .unwrap();
+        assert_eq!(content(&data), "axf");
+        data.validate_integrity().unwrap();
+    }
+
+    #[test]
+    fn malformed_inserts_are_rejected() {
+        let mut data = TestData::with_value(0, text("abcdef"));
+        let insert = |id: IdWithIndex<u32>, pred: u32, succ: u32| DataOperation::Insert {
+            id,
+            pred: indexed(0, pred),
+            succ: indexed(0, succ),
+            value: text("x"),
+        };
+        // Origins within the same node that are not adjacent.
+        assert_matches!(
+            data.apply_operation(insert(indexed(5, 0), 3, 5)),
+            Err(ApplyFailure::Rejected { .. })
+        );
+        // Origins that are not aligned with an existing split.
+        data.apply_operation(insert(indexed(5, 0), 3, 4)).unwrap();
+        assert_matches!(
+            data.apply_operation(insert(indexed(6, 0), 2, 4)),
+            Err(ApplyFailure::Rejected { .. })
+        );
+        // Ids that already exist.
+        assert_matches!(
+            data.apply_operation(insert(indexed(5, 0), 1, 2)),
+            Err(ApplyFailure::Rejected { .. })
+        );
+        assert_matches!(
+            data.apply_operation(insert(indexed(0, 4), 1, 2)),
+            Err(ApplyFailure::Rejected { .. })
+        );
+        assert_eq!(content(&data), "abcxdef");
+        data.validate_integrity().unwrap();
+    }
+
+    #[test]
+    fn deletes_merge_parts_of_the_same_insert() {
+        let mut data = TestData::with_value(0, text("abcdef"));
+        let content_nodes = |data: &TestData| data.stats().nodes;
+
+        // Delete single elements out of order, fragmenting the initial value each time.
+        for index in [4, 2, 3] {
+            let deleted = data.delete(&indexed(0, index)).map(ToString::to_string);
+            assert_eq!(
+                deleted.as_deref(),
+                Some(["b", "c", "d"][index as usize - 2])
+            );
+        }
+        assert_eq!(content(&data), "aef");
+        assert_eq!(content_nodes(&data), 3, "a, the deleted bcd and ef");
+
+        data.delete_range(&indexed(0, 1), &indexed(0, 6)).unwrap();
+        assert_eq!(content(&data), "");
+        assert_eq!(content_nodes(&data), 1);
+        data.validate_integrity().unwrap();
+
+        // Parts separated by another insert stay apart.
+        let mut data = TestData::with_value(0, text("abcd"));
+        data.apply_operation(DataOperation::Insert {
+            id: indexed(5, 0),
+            pred: indexed(0, 2),
+            succ: indexed(0, 3),
+            value: text("x"),
+        })
+        .unwrap();
+        data.delete_range(&indexed(0, 1), &indexed(0, 4)).unwrap();
+        assert_eq!(content(&data), "x");
+        assert_eq!(content_nodes(&data), 3);
+        data.validate_integrity().unwrap();
+    }
+
+    #[test]
+    fn delete_ranges_with_a_bad_range_deletes_nothing() {
+        let mut data = TestData::with_value(0, text("abcdef"));
+        data.apply_operation(DataOperation::Insert {
+            id: indexed(5, 0),
+            pred: indexed(0, 3),
+            succ: indexed(0, 4),
+            value: text("xy"),
+        })
+        .unwrap();
+        let before = data.clone();
+        let mut ranges = vec![
+            IdRange {
+                start: indexed(0, 1),
+                end: indexed(0, 2),
+            },
+            IdRange {
+                start: indexed(5, 0),
+                end: indexed(5, 1),
+            },
+        ];
+        let mut bad_ranges = ranges.clone();
+        bad_ranges.push(IdRange {
+            start: indexed(9, 0),
+            end: indexed(9, 1),
+        });
+
+        let result = data.apply_operation(DataOperation::DeleteRanges { ranges: bad_ranges });
+        assert_matches!(result, Err(ApplyFailure::Rejected { .. }));
+        assert_eq!(data, before);
+        assert_eq!(content(&data), "abcxydef");
+
+        ranges.reverse();
+        data.apply_operation(DataOperation::DeleteRanges { ranges })
+            .unwrap();
+        assert_eq!(content(&data), "cdef");
+        data.validate_integrity().unwrap();
+    }
+
+    #[test]
+    fn defragment_merges_split_parts_and_stats_track_conflicts() {
+        let mut data = TestData::with_value(0, text("abcdef"));
+        for id in [5, 6] {
+            data.apply_operation(DataOperation::Insert {
+                id: indexed(id, 0),
+                pred: indexed(0, 3),
+                succ: indexed(0, 4),
+                value: text("x"),
+            })
+            .unwrap();
+        }
+        data.delete_range(&indexed(0, 1), &indexed(0, 2)).unwrap();
+        // Deletes merge split parts right away, so split "def" directly, as a snapshot from an
+        // older version might have.
+        let def_index = data.base.nodes.len() - 2;
+        data.split_node(def_index, 5, SplitMode::Before).unwrap();
+        assert_eq!(content(&data), "cxxdef");
+
+        let stats = data.stats();
+        assert_eq!(stats.conflicts_resolved, 1);
+        assert_eq!(stats.tombstones, 2);
+        assert_eq!(stats.elements, 8);
+        assert_eq!((stats.nodes, stats.defragmented_nodes), (6, 5));
+        assert!((stats.fragmentation_ratio() - 1.0 / 6.0).abs() < f64::EPSILON);
+
+        let mut defragmented = data.clone();
+        assert_eq!(defragmented.defragment(), 1);
+        assert_eq!(defragmented.defragment(), 0);
+        defragmented.validate_integrity().unwrap();
+        assert_eq!(defragmented.stats().nodes, 5);
+        assert_eq!(defragmented.stats().fragmentation_ratio(), 0.0);
+
+        // Both keep integrating the same operations the same way.
+        let operations = [
+            DataOperation::Insert {
+                id: indexed(7, 0),
+                pred: indexed(0, 1),
+                succ: indexed(0, 2),
+                value: text("y"),
+            },
+            DataOperation::Delete {
+                start: indexed(0, 3),
+                end: Some(indexed(0, 3)),
+            },
+        ];
+        for operation in operations {
+            data.apply_operation(operation.clone()).unwrap();
+            defragmented.apply_operation(operation).unwrap();
+        }
+        assert_eq!(content(&data), "yxxdef");
+        assert_eq!(content(&defragmented), content(&data));
+        defragmented.validate_integrity().unwrap();
+        data.defragment();
+        assert_eq!(defragmented, data);
+    }
+
+    #[test]
+    fn ids_in_range_spans_large_nodes() {
+        const LEN: u32 = 100_000;
+        const MIDDLE: u32 = LEN / 2;
+        let mut data = TestData::with_value(0, text(&"a".repeat(LEN as usize)));
+        data.apply_operation(DataOperation::Insert {
+            id: indexed(5, 0),
+            pred: indexed(0, MIDDLE),
+            succ: indexed(0, MIDDLE + 1),
+            value: text("x"),
+        })
+        .unwrap();
+
+        let range = data.ids_in_range(1..LEN as usize).unwrap();
+        assert_eq!(
+            range,
+            NodeIdRange {
+                predecessor: indexed(0, 1),
+                contained: vec![
+                    IdWithIndexRange::with_end(indexed(0, 2), MIDDLE),
+                    IdWithIndexRange::with_end(indexed(5, 0), 0),
+                    IdWithIndexRange::with_end(indexed(0, MIDDLE + 1), LEN - 1),
+                ],
+                successor: indexed(0, LEN),
+            }
+        );
+        let everything = data.ids_in_range(..).unwrap();
+        assert_eq!(everything.contained.len(), 3);
+        assert_eq!(everything.contained[2].last(), indexed(0, LEN));
+        assert_eq!(data.ids_in_range(..=LEN as usize + 1), None);
+
+        // Excluding the last position of a node starts the range at the next node.
+        let middle = MIDDLE as usize;
+        let range = data
+            .ids_in_range((Bound::Excluded(middle - 1), Bound::Included(middle)))
+            .unwrap();
+        assert_eq!(
+            range.contained,
+            vec![IdWithIndexRange::with_end(indexed(5, 0), 0)]
+        );
+    }
 }
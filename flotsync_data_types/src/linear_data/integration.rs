@@ -0,0 +1,375 @@
+//! Yjs-style integration of concurrent inserts into a sequence of nodes.
+//!
+//! Every insert records the ids of its neighbours at creation time as its left and right origin.
+//! When the origins are no longer adjacent, other inserts were integrated between them in the
+//! meantime, and the new node must be placed among those so that all replicas end up with the
+//! same order, no matter in which order they received the inserts.
+//!
+//! Nodes in the gap that share both origins with the new node form its *conflict set*. These are
+//! ordered by their [conflict key](IntegrationNodes::conflict_key). Any node in the gap that
+//! transitively anchors its right origin on a conflicting node belongs to that node's subtree
+//! and stays in front of it, so the new node is always placed before a complete subtree.
+use super::{Node, fmt};
+use crate::{InternalError, InternalSnafu};
+use snafu::prelude::*;
+use std::{collections::HashMap, hash::Hash};
+
+/// Node storage that concurrent inserts can be integrated into.
+pub(super) trait IntegrationNodes {
+    type Id: PartialEq + Eq + Hash + fmt::Debug;
+    type Value;
+    /// The part of an id that orders concurrent inserts with the same origins.
+    type ConflictKey: Ord + fmt::Debug;
+
+    fn node_count(&self) -> usize;
+
+    /// The node at `index`, which must be less than [`node_count`](Self::node_count).
+    fn node(&self, index: usize) -> &Node<Self::Id, Self::Value>;
+
+    fn conflict_key(id: &Self::Id) -> &Self::ConflictKey;
+}
+
+/// Reasons why no insert position could be found for a new node.
+#[derive(Debug, Snafu)]
+pub(super) enum IntegrationError {
+    /// A node with the same origins and conflict key has already been integrated.
+    #[snafu(display("A node with the same origins and conflict key has already been integrated."))]
+    DuplicateInsert,
+    /// The origin chains of the existing nodes are inconsistent.
+    #[snafu(transparent)]
+    Internal { source: InternalError },
+}
+
+/// Find the index at which a new node with origins `pred` and `succ` must be inserted.
+///
+/// `pred_index` and `succ_index` are the positions of the origin nodes, with `pred_index` before
+/// `succ_index`. `new_key` is the conflict key of the new node.
+pub(super) fn find_insert_position<N>(
+    data: &N,
+    pred_index: usize,
+    pred: &N::Id,
+    succ_index: usize,
+    succ: &N::Id,
+    new_key: &N::ConflictKey,
+) -> Result<usize, IntegrationError>
+where
+    N: IntegrationNodes + ?Sized,
+{
+    debug_assert!(
+        pred_index < succ_index,
+        "Predecessor at {pred_index} must come before successor at {succ_index}"
+    );
+    let left_right_range = (pred_index + 1)..succ_index;
+    let mut conflicting_nodes: Vec<(&N::ConflictKey, usize)> =
+        Vec::with_capacity(left_right_range.len());
+    // The right subtree is all the nodes that have succ as their successor,
+    // and all nodes that can reach those nodes by following right_origin.
+    let mut right_subtree_start_index_opt = None;
+    let mut right_tree_memo = RightTreeTraversalMemo::new(data, succ);
+    for node_index in left_right_range {
+        let node = data.node(node_index);
+        if node.left_origin.as_ref() == Some(pred) && node.right_origin.as_ref() == Some(succ) {
+            conflicting_nodes.push((N::conflict_key(&node.id), node_index));
+        }
+        // Don't overwrite this with a later node.
+        if right_subtree_start_index_opt.is_none()
+            && right_tree_memo.reaches_boundary(node_index)?
+        {
+            right_subtree_start_index_opt = Some(node_index);
+        }
+    }
+
+    if conflicting_nodes.is_empty() {
+        return Ok(right_subtree_start_index_opt.unwrap_or(succ_index));
+    }
+
+    // Double check `conflicting_nodes` is already ordered correctly.
+    debug_assert!(
+        conflicting_nodes.is_sorted_by_key(|(one, _)| *one),
+        "Conflict range should have been sorted by key already, but was: {conflicting_nodes:?}"
+    );
+    let insert_index = match conflicting_nodes.binary_search_by(|&(probe, _)| probe.cmp(new_key)) {
+        Ok(_found_index) => return DuplicateInsertSnafu.fail(),
+        Err(insert_index) => insert_index,
+    };
+    // Still need to translate this into an index on `nodes` instead of `conflicting_nodes`.
+    let position = if insert_index == 0 {
+        // If we are supposed to insert before the first conflicting node,
+        // we must actually insert before *any* node in the range,
+        // because they might all have anchored off that first conflicting node.
+        pred_index + 1
+    } else if insert_index < conflicting_nodes.len() {
+        let target_conflict_pos = conflicting_nodes[insert_index].1;
+        // Insert before the target conflicting node's local subtree, not just before the node
+        // itself. Otherwise sibling subtree order can depend on delivery order.
+        let mut target_tree_memo =
+            right_tree_memo.with_new_boundary(&data.node(target_conflict_pos).id);
+        let mut subtree_start_index_opt = None;
+        for node_index in (pred_index + 1)..target_conflict_pos {
+            if target_tree_memo.reaches_boundary(node_index)? {
+                subtree_start_index_opt = Some(node_index);
+                break;
+            }
+        }
+        subtree_start_index_opt.unwrap_or(target_conflict_pos)
+    } else {
+        // It has to be right of all the conflicting nodes.
+        // Insert just before succ.
+        succ_index
+    };
+    Ok(position)
+}
+
+/// Per-operation memoized traversal helper for right-origin chains.
+///
+/// This is used during conflict-position calculation to avoid repeating the same transitive
+/// right-origin traversals for multiple candidate nodes.
+struct RightTreeTraversalMemo<'a, N>
+where
+    N: IntegrationNodes + ?Sized,
+{
+    nodes: &'a N,
+    boundary: &'a N::Id,
+    node_index_by_id: HashMap<&'a N::Id, usize>,
+    reaches_boundary_cache: Vec<Option<bool>>,
+}
+impl<'a, N> RightTreeTraversalMemo<'a, N>
+where
+    N: IntegrationNodes + ?Sized,
+{
+    fn new(nodes: &'a N, boundary: &'a N::Id) -> Self {
+        Self {
+            nodes,
+            boundary,
+            node_index_by_id: HashMap::new(),
+            reaches_boundary_cache: vec![None; nodes.node_count()],
+        }
+    }
+
+    fn with_new_boundary(mut self, boundary: &'a N::Id) -> Self {
+        if self.boundary != boundary {
+            self.boundary = boundary;
+            self.reaches_boundary_cache.fill(None);
+        }
+        self
+    }
+
+    /// Returns `true` iff the node at `start_index` is in the transitive right subtree that
+    /// includes `boundary`.
+    ///
+    /// In other words, if you follow `right_origin` anchors starting from this node, you hit the
+    /// node with `id = boundary` before you find a `None`.
+    ///
+    /// Fails if the chain refers to a missing node or does not strictly move right.
+    fn reaches_boundary(&mut self, start_index: usize) -> Result<bool, InternalError> {
+        if let Some(reaches) = self.reaches_boundary_cache[start_index] {
+            return Ok(reaches);
+        }
+
+        let mut path = Vec::new();
+        let mut current_index = start_index;
+        loop {
+            if let Some(reaches) = self.reaches_boundary_cache[current_index] {
+                self.cache_path(&path, reaches);
+                return Ok(reaches);
+            }
+
+            path.push(current_index);
+            let node = self.nodes.node(current_index);
+            self.node_index_by_id
+                .entry(&node.id)
+                .or_insert(current_index);
+
+            if &node.id == self.boundary {
+                self.cache_path(&path, true);
+                return Ok(true);
+            }
+
+            let Some(next_id) = node.right_origin.as_ref() else {
+                self.cache_path(&path, false);
+                return Ok(false);
+            };
+
+            current_index = self.resolve_index(current_index, next_id)?;
+        }
+    }
+
+    fn cache_path(&mut self, path: &[usize], reaches: bool) {
+        for node_index in path.iter().copied() {
+            self.reaches_boundary_cache[node_index] = Some(reaches);
+        }
+    }
+
+    fn resolve_index(&mut self, current_index: usize, id: &N::Id) -> Result<usize, InternalError> {
+        if let Some(index) = self.node_index_by_id.get(id).copied() {
+            ensure!(
+                index > current_index,
+                InternalSnafu {
+                    context: format!(
+                        "Invalid right_origin chain: id={id:?} resolves to index={index}, \
+                         which is not to the right of current_index={current_index}"
+                    ),
+                }
+            );
+            return Ok(index);
+        }
+
+        let search_start = current_index + 1;
+        let nodes = self.nodes;
+        if let Some(index) =
+            (search_start..nodes.node_count()).find(|&index| &nodes.node(index).id == id)
+        {
+            self.node_index_by_id.insert(&nodes.node(index).id, index);
+            return Ok(index);
+        }
+
+        if let Some(index) = (0..=current_index).find(|&index| &nodes.node(index).id == id) {
+            return InternalSnafu {
+                context: format!(
+                    "Invalid right_origin chain: id={id:?} resolves to index={index}, \
+                     which is not to the right of current_index={current_index}"
+                ),
+            }
+            .fail();
+        }
+
+        InternalSnafu {
+            context: format!("For every origin a node should exist (missing id={id:?})"),
+        }
+        .fail()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::Operation, *};
+    use std::assert_matches;
+
+    const BEGIN: u32 = 0;
+    const END: u32 = 99;
+
+    /// Plain nodes ordered by their full id.
+    struct TestNodes(Vec<Node<u32, char>>);
+    impl TestNodes {
+        /// Build a node sequence from `(id, left_origin, right_origin)` triples between the
+        /// boundaries.
+        fn new(inner: &[(u32, u32, u32)]) -> Self {
+            let mut nodes = vec![Node {
+                id: BEGIN,
+                left_origin: None,
+                right_origin: Some(END),
+                operation: Operation::Beginning,
+            }];
+            nodes.extend(inner.iter().map(|&(id, left, right)| Node {
+                id,
+                left_origin: Some(left),
+                right_origin: Some(right),
+                operation: Operation::Insert { value: 'x' },
+            }));
+            nodes.push(Node {
+                id: END,
+                left_origin: Some(BEGIN),
+                right_origin: None,
+                operation: Operation::End,
+            });
+            Self(nodes)
+        }
+
+        fn index_of(&self, id: u32) -> usize {
+            self.0.iter().position(|node| node.id == id).unwrap()
+        }
+
+        fn position_for(&self, pred: u32, succ: u32, id: u32) -> Result<usize, IntegrationError> {
+            find_insert_position(
+                self,
+                self.index_of(pred),
+                &pred,
+                self.index_of(succ),
+                &succ,
+                &id,
+            )
+        }
+    }
+    impl IntegrationNodes for TestNodes {
+        type Id = u32;
+        type Value = char;
+        type ConflictKey = u32;
+
+        fn node_count(&self) -> usize {
+            self.0.len()
+        }
+
+        fn node(&self, index: usize) -> &Node<u32, char> {
+            &self.0[index]
+        }
+
+        fn conflict_key(id: &u32) -> &u32 {
+            id
+        }
+    }
+
+    #[test]
+    fn inserts_without_conflicts_go_before_the_successor() {
+        let empty = TestNodes::new(&[]);
+        assert_eq!(empty.position_for(BEGIN, END, 1).unwrap(), 1);
+
+        // 2 was inserted after 1, so it neither conflicts with nor anchors on 3.
+        let nodes = TestNodes::new(&[(1, BEGIN, END), (2, 1, END), (3, 2, END)]);
+        assert_eq!(nodes.position_for(BEGIN, 3, 4).unwrap(), nodes.index_of(3));
+    }
+
+    #[test]
+    fn conflicting_inserts_are_ordered_by_key() {
+        let nodes = TestNodes::new(&[(2, BEGIN, END), (4, BEGIN, END), (6, BEGIN, END)]);
+        assert_eq!(nodes.position_for(BEGIN, END, 1).unwrap(), 1);
+        assert_eq!(
+            nodes.position_for(BEGIN, END, 3).unwrap(),
+            nodes.index_of(4)
+        );
+        assert_eq!(
+            nodes.position_for(BEGIN, END, 5).unwrap(),
+            nodes.index_of(6)
+        );
+        assert_eq!(
+            nodes.position_for(BEGIN, END, 7).unwrap(),
+            nodes.index_of(END)
+        );
+        assert_matches!(
+            nodes.position_for(BEGIN, END, 4),
+            Err(IntegrationError::DuplicateInsert)
+        );
+    }
+
+    #[test]
+    fn conflicting_inserts_go_before_the_whole_subtree() {
+        // 5 was inserted between 2 and 6 and 7 between 5 and 6, so both belong to the subtree
+        // of 6 and must stay in front of it.
+        let nodes = TestNodes::new(&[(2, BEGIN, END), (5, 2, 6), (7, 5, 6), (6, BEGIN, END)]);
+        assert_eq!(
+            nodes.position_for(BEGIN, END, 3).unwrap(),
+            nodes.index_of(5)
+        );
+        assert_eq!(nodes.position_for(BEGIN, END, 1).unwrap(), 1);
+        assert_eq!(
+            nodes.position_for(BEGIN, END, 8).unwrap(),
+            nodes.index_of(END)
+        );
+    }
+
+    #[test]
+    fn broken_right_origin_chains_are_reported() {
+        // 2 claims to anchor on 3, which does not exist.
+        let nodes = TestNodes::new(&[(2, BEGIN, 3), (1, BEGIN, END)]);
+        assert_matches!(
+            nodes.position_for(BEGIN, END, 4),
+            Err(IntegrationError::Internal { .. })
+        );
+
+        // 2 anchors on the beginning, which lies to its left.
+        let nodes = TestNodes::new(&[(2, BEGIN, BEGIN), (1, BEGIN, END)]);
+        assert_matches!(
+            nodes.position_for(BEGIN, END, 4),
+            Err(IntegrationError::Internal { .. })
+        );
+    }
+}
@@ -1,16 +1,41 @@
+//! Ordered sequence CRDTs shared by every linear data type.
+//!
+//! [`LinearData`] is the one trait hierarchy behind linear strings, lists and
+//! the other sequence-backed types in [`crate::text`] and [`crate::any_data`].
+//! The most commonly used items are also re-exported at the crate root, and
+//! [`snapshot`] is re-exported as `flotsync_data_types::snapshot`.
+//!
+//! With the `persistent` feature, `PersistentLinearData` offers an alternative to
+//! [`VecLinearData`] whose clones share their nodes, for documents that are cloned often.
+
+use crate::{ErrorScope, InternalError};
 use flotsync_utils::option_when;
 use snafu::prelude::*;
-use std::{assert_matches, fmt, vec};
+use std::{assert_matches, fmt};
 
+mod batch;
+#[cfg(feature = "parallel")]
+pub use batch::par_apply_workspace_batches;
+pub use batch::{ApplyBatch, BatchResult, WorkspaceBatchResult, apply_workspace_batches};
 mod coalesced;
-pub(crate) mod snapshot;
+mod integration;
+#[cfg(feature = "persistent")]
+mod persistent_impl;
+#[cfg(feature = "persistent")]
+pub use persistent_impl::{PersistentLinearData, PersistentLinearDataIter};
+pub mod snapshot;
 pub use coalesced::{
+    ChangeContext,
     Composite,
     IdGeneratorWithIndex,
     // IdGeneratorWithZeroIndex,
     IdWithIndex,
     IdWithIndexRange,
+    LinearDataStats,
     NodeIdRange,
+    ReserveIds,
+    ReservedIds,
+    ValueSummary,
     VecCoalescedLinearData,
     VecCoalescedLinearDataIter,
 };
@@ -20,6 +45,7 @@ mod vec_impl;
 pub use vec_impl::VecLinearData;
 
 #[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+#[non_exhaustive]
 pub enum IntegrityError {
     #[snafu(display("The first node is not a beginning boundary."))]
     MissingBeginningBoundary,
@@ -41,6 +67,8 @@ pub enum IntegrityError {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
 pub enum DataOperation<Id, Value> {
     /// Insert `value` as the content associated with `id` between `pred` and `succ`.
     Insert {
@@ -57,8 +85,38 @@ pub enum DataOperation<Id, Value> {
     /// update as `start` and may only differ in the trailing chunk/index component. In other
     /// words, a valid delete range must not cross update-id boundaries.
     Delete { start: Id, end: Option<Id> },
+    /// Delete the content of each of the `ranges`, in order.
+    ///
+    /// Each range follows the same rules as a [`Delete`](Self::Delete) with an `end`, but
+    /// different ranges may belong to different updates. This deletes a selection that spans
+    /// content from many updates in one operation.
+    ///
+    /// The operation is all or nothing: if any range cannot be deleted, the operation is rejected
+    /// and none of the ranges are deleted. Only [`VecCoalescedLinearData`] supports it; the
+    /// other backends do not support range deletes at all and always reject it.
+    DeleteRanges { ranges: Vec<IdRange<Id>> },
+}
+
+/// An inclusive range of ids from `start` through `end`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct IdRange<Id> {
+    pub start: Id,
+    pub end: Id,
 }
 impl<Id, Value> DataOperation<Id, Value> {
+    /// The id that locates this operation in the document's history.
+    ///
+    /// This is the id of the inserted content for inserts, and the first deleted id otherwise.
+    /// Only a [`DeleteRanges`](Self::DeleteRanges) without any ranges has none.
+    pub fn first_id(&self) -> Option<&Id> {
+        match self {
+            DataOperation::Insert { id, .. } => Some(id),
+            DataOperation::Delete { start, .. } => Some(start),
+            DataOperation::DeleteRanges { ranges } => ranges.first().map(|range| &range.start),
+        }
+    }
+
     pub fn map_value<Output, F>(self, mapper: F) -> DataOperation<Id, Output>
     where
         F: FnOnce(Value) -> Output,
@@ -76,6 +134,74 @@ impl<Id, Value> DataOperation<Id, Value> {
                 value: mapper(value),
             },
             DataOperation::Delete { start, end } => DataOperation::Delete { start, end },
+            DataOperation::DeleteRanges { ranges } => DataOperation::DeleteRanges { ranges },
+        }
+    }
+}
+
+/// A [`DataOperation`] whose inserted value is borrowed, e.g. from a network buffer.
+///
+/// Decoding into this form avoids an allocation per operation. The value is only copied into
+/// owned storage once the operation is applied via [`LinearData::apply_operation_ref`].
+pub type DataOperationRef<'a, Id, ValueRef> = DataOperation<Id, &'a ValueRef>;
+
+impl<Id, ValueRef> DataOperationRef<'_, Id, ValueRef>
+where
+    ValueRef: ToOwned + ?Sized,
+{
+    /// Copy the borrowed value into an owned operation.
+    #[must_use]
+    pub fn into_owned(self) -> DataOperation<Id, ValueRef::Owned> {
+        self.map_value(ToOwned::to_owned)
+    }
+}
+
+/// The reason an operation could not be applied, together with the original operation.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ApplyFailure<Op> {
+    /// The operation does not fit the current state, e.g. because it refers to unknown ids.
+    Rejected { operation: Op },
+    /// The operation exposed an inconsistency in the current state.
+    ///
+    /// The state was left unchanged.
+    Internal {
+        operation: Op,
+        source: InternalError,
+    },
+}
+impl<Op> ApplyFailure<Op> {
+    /// Returns the operation that failed to apply.
+    pub fn into_operation(self) -> Op {
+        match self {
+            Self::Rejected { operation } | Self::Internal { operation, .. } => operation,
+        }
+    }
+
+    /// Add the fields of `scope` that are not already known to an internal error.
+    #[must_use]
+    pub fn in_scope(self, scope: &ErrorScope) -> Self {
+        match self {
+            Self::Rejected { .. } => self,
+            Self::Internal { operation, source } => Self::Internal {
+                operation,
+                source: source.in_scope(scope),
+            },
+        }
+    }
+
+    pub fn map_operation<Output, F>(self, mapper: F) -> ApplyFailure<Output>
+    where
+        F: FnOnce(Op) -> Output,
+    {
+        match self {
+            Self::Rejected { operation } => ApplyFailure::Rejected {
+                operation: mapper(operation),
+            },
+            Self::Internal { operation, source } => ApplyFailure::Internal {
+                operation: mapper(operation),
+                source,
+            },
         }
     }
 }
@@ -127,11 +253,49 @@ where
     /// May try to resolve a new position if the requested operation cannot exactly be applied due
     /// to a change in the structure since the location's ids were retrieved originally.
     ///
-    /// Returns the original operation on failure.
+    /// Returns the original operation on failure, distinguishing operations that do not fit
+    /// the current state from internal inconsistencies. Malformed operations must never panic.
     fn apply_operation(
         &mut self,
         operation: DataOperation<Self::Id, Value>,
-    ) -> Result<(), DataOperation<Self::Id, Value>>;
+    ) -> Result<(), ApplyFailure<DataOperation<Self::Id, Value>>>;
+
+    /// Apply a modification operation whose value is still borrowed.
+    ///
+    /// The value is copied only here, right before it is stored. On failure the original
+    /// borrowed operation is returned, just like [`apply_operation`](Self::apply_operation) does.
+    fn apply_operation_ref<'v, Borrowed>(
+        &mut self,
+        operation: DataOperationRef<'v, Self::Id, Borrowed>,
+    ) -> Result<(), ApplyFailure<DataOperationRef<'v, Self::Id, Borrowed>>>
+    where
+        Borrowed: ToOwned<Owned = Value> + ?Sized,
+    {
+        match operation {
+            DataOperation::Insert {
+                id,
+                pred,
+                succ,
+                value,
+            } => {
+                let owned = DataOperation::Insert {
+                    id,
+                    pred,
+                    succ,
+                    value: value.to_owned(),
+                };
+                self.apply_operation(owned)
+                    .map_err(|failure| failure.map_operation(|op| op.map_value(|_| value)))
+            }
+            delete @ (DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. }) => self
+                .apply_operation(delete.map_value(|_| unreachable!("deletes have no value")))
+                .map_err(|failure| {
+                    failure.map_operation(|op| {
+                        op.map_value(|_| unreachable!("failed deletes are returned unchanged"))
+                    })
+                }),
+        }
+    }
 
     fn iter_values(&self) -> Self::Iter<'_>;
 
@@ -254,6 +418,29 @@ impl<Id, Value> Node<Id, Value> {
             Operation::Invalid => panic!("Node is invalid."),
         }
     }
+
+    /// The beginning boundary with `id`.
+    ///
+    /// Boundaries never take part in conflict resolution, so unlike inserts they don't record
+    /// the neighbour they were created next to, which saves cloning its id.
+    fn beginning(id: Id) -> Self {
+        Self {
+            id,
+            left_origin: None,
+            right_origin: None,
+            operation: Operation::Beginning,
+        }
+    }
+
+    /// The end boundary with `id`, see [`Node::beginning`].
+    fn end(id: Id) -> Self {
+        Self {
+            id,
+            left_origin: None,
+            right_origin: None,
+            operation: Operation::End,
+        }
+    }
 }
 impl<Id, Value> Node<IdWithIndex<Id>, Value>
 where
@@ -308,6 +495,33 @@ where
         id
     }
 
+    /// The id of the element at `offset` within this node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id cannot be addressed, which a valid node guarantees for all its elements.
+    pub fn id_at_offset(&self, offset: u32) -> IdWithIndex<Id> {
+        self.id
+            .checked_add(offset)
+            .expect("Nodes must not be longer than can be addressed")
+    }
+
+    /// The offset of `id` within this node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` belongs to a different base id or lies before this node.
+    pub fn offset_of(&self, id: &IdWithIndex<Id>) -> u32 {
+        assert!(
+            self.id.index <= id.index,
+            "Id {:?} lies before the node",
+            id.index
+        );
+        self.id
+            .distance(id)
+            .expect("Offsets can only be computed for ids of the same node")
+    }
+
     #[allow(unused, reason = "Used in testing atm.")]
     pub fn ids(&self) -> impl Iterator<Item = IdWithIndex<Id>> {
         (self.id.index..=self.last_index()).map(|index| IdWithIndex {
@@ -395,86 +609,26 @@ where
         };
         Some(new_operation)
     }
-}
 
-#[cfg(test)]
-pub(crate) mod tests {
-    use crate::linear_data::IdWithIndex;
-
-    /// Enumerates all schedules that interleave `num_writers` ordered local operation streams.
+    /// Append the value of `other` to this operation.
     ///
-    /// Each writer appears `per_writer_count` times, and the generated schedules preserve each
-    /// writer's local order while varying only the cross-writer interleaving.
-    pub(crate) fn interleavings_with_local_order(
-        per_writer_count: usize,
-        num_writers: usize,
-    ) -> Vec<Vec<usize>> {
-        fn dfs(
-            per_writer_count: usize,
-            total_steps: usize,
-            current: &mut Vec<usize>,
-            next_for_writer: &mut [usize],
-            out: &mut Vec<Vec<usize>>,
-        ) {
-            if current.len() == total_steps {
-                out.push(current.clone());
-                return;
-            }
-
-            for writer in 0..next_for_writer.len() {
-                if next_for_writer[writer] < per_writer_count {
-                    next_for_writer[writer] += 1;
-                    current.push(writer);
-                    dfs(per_writer_count, total_steps, current, next_for_writer, out);
-                    current.pop();
-                    next_for_writer[writer] -= 1;
-                }
-            }
-        }
-
-        let total_steps = per_writer_count * num_writers;
-        let mut out = Vec::new();
-        let mut current = Vec::with_capacity(total_steps);
-        let mut next_for_writer = vec![0usize; num_writers];
-
-        dfs(
-            per_writer_count,
-            total_steps,
-            &mut current,
-            &mut next_for_writer,
-            &mut out,
-        );
-        out
-    }
-
-    pub(crate) struct TestIdGenerator {
-        current: Option<u32>,
-    }
-    impl TestIdGenerator {
-        pub fn new() -> Self {
-            Self { current: Some(0) }
-        }
-
-        pub fn without_ids(existing_ids: impl Iterator<Item = u32>) -> Self {
-            let max_id = existing_ids.max().unwrap_or(0);
-            Self {
-                current: Some(max_id + 1),
+    /// Both must be inserts or both must be deletes.
+    fn merge(&mut self, mut other: Self) {
+        let is_insert = match (&*self, &other) {
+            (Operation::Insert { .. }, Operation::Insert { .. }) => true,
+            (Operation::Delete { .. }, Operation::Delete { .. }) => false,
+            _ => panic!("Only inserts with inserts and deletes with deletes can be merged."),
+        };
+        let value = self.take_value().unwrap();
+        let merged_value = value.concat(other.take_value().unwrap());
+        *self = if is_insert {
+            Operation::Insert {
+                value: merged_value,
             }
-        }
-
-        pub fn next_with_zero_index(&mut self) -> Option<IdWithIndex<u32>> {
-            self.next().map(IdWithIndex::zero)
-        }
-    }
-    impl Iterator for TestIdGenerator {
-        type Item = u32;
-
-        fn next(&mut self) -> Option<Self::Item> {
-            let current = self.current.take();
-            if let Some(current) = current {
-                self.current = current.checked_add(1);
+        } else {
+            Operation::Delete {
+                value: merged_value,
             }
-            current
-        }
+        };
     }
 }
@@ -0,0 +1,527 @@
+//! Structurally shared node storage for cheaply cloned documents.
+
+use super::{
+    ApplyFailure,
+    DataOperation,
+    IntegrityError,
+    InvalidNodeSnafu,
+    LinearData,
+    LinkIds,
+    MissingBeginningBoundarySnafu,
+    MissingEndBoundarySnafu,
+    Node,
+    NodeIds,
+    Operation,
+    VecLinearData,
+    VisibleLengthMismatchSnafu,
+    assert_matches,
+    ensure,
+    fmt,
+    integration::{self, IntegrationError, IntegrationNodes},
+    option_when,
+};
+use crate::{InternalError, InternalSnafu};
+use imbl::{Vector, shared_ptr::DefaultSharedPtr, vector};
+use std::hash::Hash;
+
+/// An implementation of [`LinearData`] that shares its nodes structurally between clones.
+///
+/// The nodes are kept in a persistent vector, so cloning is constant time no matter how large
+/// the document is, and an edit only copies the few chunks of the tree that it touches. This
+/// makes it a good fit for documents that are cloned for snapshots or read views much more
+/// often than they are edited, at the cost of slower indexing and iteration than
+/// [`VecLinearData`].
+///
+/// Range deletes are not supported, so [`DataOperation::Delete`] with an `end` and
+/// [`DataOperation::DeleteRanges`] are always rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PersistentLinearData<Id, Value>
+where
+    Id: Clone,
+    Value: Clone,
+{
+    /// The number of Insert nodes in the linear data.
+    len: usize,
+    nodes: Vector<Node<Id, Value>>,
+}
+
+impl<Id, Value> PersistentLinearData<Id, Value>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq,
+    Value: Clone,
+{
+    pub fn new(begin_id: Id, end_id: Id) -> Self {
+        let begin_node = Node {
+            id: begin_id.clone(),
+            left_origin: None,
+            right_origin: Some(end_id.clone()),
+            operation: Operation::Beginning,
+        };
+        let end_node = Node {
+            id: end_id,
+            left_origin: Some(begin_id),
+            right_origin: None,
+            operation: Operation::End,
+        };
+        let nodes = vector![begin_node, end_node];
+        Self { len: 0, nodes }
+    }
+
+    pub fn with_value(initial_value: Value, ids: [Id; 3]) -> Self {
+        let [begin_id, value_id, end_id] = ids;
+        let begin_node = Node {
+            id: begin_id.clone(),
+            left_origin: None,
+            right_origin: Some(value_id.clone()),
+            operation: Operation::Beginning,
+        };
+        let value_node = Node {
+            id: value_id.clone(),
+            left_origin: Some(begin_id.clone()),
+            right_origin: Some(end_id.clone()),
+            operation: Operation::Insert {
+                value: initial_value,
+            },
+        };
+        let end_node = Node {
+            id: end_id,
+            left_origin: Some(value_id.clone()),
+            right_origin: None,
+            operation: Operation::End,
+        };
+        let nodes = vector![begin_node, value_node, end_node];
+        Self { len: 1, nodes }
+    }
+
+    /// The number of stored nodes, including both boundaries and deleted elements.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Return whether `self` and `other` still share all of their nodes.
+    ///
+    /// This is `true` for a clone until either side is changed.
+    pub fn shares_nodes_with(&self, other: &Self) -> bool {
+        self.nodes.ptr_eq(&other.nodes)
+    }
+}
+impl<Id, Value> PersistentLinearData<Id, Value>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
+    Value: Clone + fmt::Debug,
+{
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn append(&mut self, id: Id, value: Value) {
+        let end_index = self.nodes.len() - 1;
+
+        let end_node = &self.nodes[end_index];
+        assert_matches!(end_node.operation, Operation::End);
+        let end_node_id = end_node.id.clone();
+
+        let last_node_id = self.nodes[end_index - 1].id.clone();
+
+        self.nodes.insert(
+            end_index,
+            Node {
+                id,
+                left_origin: Some(last_node_id),
+                right_origin: Some(end_node_id),
+                operation: Operation::Insert { value },
+            },
+        );
+        self.len += 1;
+    }
+
+    pub fn prepend(&mut self, id: Id, value: Value) {
+        let begin_node = &self.nodes[0];
+        assert_matches!(begin_node.operation, Operation::Beginning);
+        let begin_node_id = begin_node.id.clone();
+
+        let first_node_id = self.nodes[1].id.clone();
+
+        self.nodes.insert(
+            1,
+            Node {
+                id,
+                left_origin: Some(begin_node_id),
+                right_origin: Some(first_node_id),
+                operation: Operation::Insert { value },
+            },
+        );
+        self.len += 1;
+    }
+
+    /// Validate the internal node structure and cached visible length.
+    ///
+    /// This is primarily useful after converting from another representation.
+    pub fn validate_integrity(&self) -> Result<(), IntegrityError> {
+        let Some(first) = self.nodes.front() else {
+            return MissingBeginningBoundarySnafu.fail();
+        };
+        if !matches!(first.operation, Operation::Beginning) {
+            return MissingBeginningBoundarySnafu.fail();
+        }
+
+        let Some(last) = self.nodes.back() else {
+            return MissingEndBoundarySnafu.fail();
+        };
+        if !matches!(last.operation, Operation::End) {
+            return MissingEndBoundarySnafu.fail();
+        }
+
+        let mut actual_len = 0usize;
+        for (index, current) in self.nodes.iter().enumerate() {
+            ensure!(current.operation.is_valid(), InvalidNodeSnafu { index });
+            if matches!(current.operation, Operation::Insert { .. }) {
+                actual_len += 1;
+            }
+        }
+
+        ensure!(
+            self.len == actual_len,
+            VisibleLengthMismatchSnafu {
+                expected: self.len,
+                actual: actual_len,
+            }
+        );
+
+        Ok(())
+    }
+
+    fn iter_inserts(&self) -> impl Iterator<Item = (usize, &Node<Id, Value>)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| matches!(n.operation, Operation::Insert { .. }))
+    }
+}
+impl<Id, Value> PersistentLinearData<Id, Value>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord,
+    Value: Clone,
+{
+    /// Find the node index at which an insert with `id` between `pred` and `succ` must be
+    /// placed.
+    ///
+    /// Returns `Ok(None)` if the insert does not fit the current state.
+    fn find_insert_position(
+        &self,
+        id: &Id,
+        pred: &Id,
+        succ: &Id,
+    ) -> Result<Option<usize>, InternalError> {
+        if self.nodes.iter().any(|node| node.id == *id) {
+            return Ok(None);
+        }
+        let Some(pred_index) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .find_map(|(index, node)| option_when!(node.id == *pred, index))
+        else {
+            return Ok(None);
+        };
+        // Successor cannot appear before predecessor in a valid operation.
+        let Some(succ_index) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .skip(pred_index + 1)
+            .find_map(|(index, node)| option_when!(node.id == *succ, index))
+        else {
+            return Ok(None);
+        };
+        if pred_index + 1 == succ_index {
+            // We can insert directly at the existing boundary.
+            return Ok(Some(succ_index));
+        }
+        // There is a gap between pred and succ that may contain concurrent inserts.
+        match integration::find_insert_position(self, pred_index, pred, succ_index, succ, id) {
+            Ok(position) => Ok(Some(position)),
+            // Duplicate insert for the same conflict set.
+            Err(IntegrationError::DuplicateInsert) => Ok(None),
+            Err(IntegrationError::Internal { source }) => Err(source),
+        }
+    }
+
+    fn insert_node(&mut self, position: usize, id: Id, pred: Id, succ: Id, value: Value) {
+        self.nodes.insert(
+            position,
+            Node {
+                id,
+                left_origin: Some(pred),
+                right_origin: Some(succ),
+                operation: Operation::Insert { value },
+            },
+        );
+        self.len += 1;
+    }
+
+    /// Mark the element with `id` as deleted.
+    ///
+    /// Returns the index of its node, or `None` if there is no element with `id` that can be
+    /// deleted. Deleting an element twice is fine.
+    fn delete_element(&mut self, id: &Id) -> Result<Option<usize>, InternalError>
+    where
+        Value: fmt::Debug,
+    {
+        let Some(node_index) = self.nodes.iter().position(|n| &n.id == id) else {
+            return Ok(None);
+        };
+        match self.nodes[node_index].operation {
+            Operation::Insert { .. } => {
+                // Only copy the shared chunk if the node actually changes.
+                self.nodes[node_index].operation.delete();
+                self.len -= 1;
+                Ok(Some(node_index))
+            }
+            // Double delete is OK.
+            Operation::Delete { .. } => Ok(Some(node_index)),
+            // These cannot be deleted.
+            Operation::Beginning | Operation::End => Ok(None),
+            Operation::Invalid => InternalSnafu {
+                context: format!("Node {node_index} is invalid."),
+            }
+            .fail(),
+        }
+    }
+}
+impl<Id, Value> IntegrationNodes for PersistentLinearData<Id, Value>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord,
+    Value: Clone,
+{
+    type Id = Id;
+    type Value = Value;
+    type ConflictKey = Id;
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn node(&self, index: usize) -> &Node<Id, Value> {
+        &self.nodes[index]
+    }
+
+    fn conflict_key(id: &Id) -> &Id {
+        id
+    }
+}
+
+impl<Id, Value> LinearData<Value> for PersistentLinearData<Id, Value>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    Value: Clone + fmt::Debug,
+{
+    type Id = Id;
+    type Iter<'a>
+        = PersistentLinearDataIter<'a, Id, Value>
+    where
+        Self: 'a,
+        Value: 'a;
+
+    fn ids_after_head(&self) -> LinkIds<Self::Id> {
+        LinkIds {
+            predecessor: self.nodes[0].id.clone(),
+            successor: self.nodes[1].id.clone(),
+        }
+    }
+
+    fn ids_before_end(&self) -> LinkIds<Self::Id> {
+        let len = self.nodes.len();
+        LinkIds {
+            predecessor: self.nodes[len - 2].id.clone(),
+            successor: self.nodes[len - 1].id.clone(),
+        }
+    }
+
+    fn ids_at_pos(&self, position: usize) -> Option<NodeIds<Id>> {
+        if position < self.len() {
+            let index_at_position = self
+                .iter_inserts()
+                .nth(position)
+                .map(|(index, _node)| index)
+                .unwrap(); // This must exist in this branch.
+            // All of these must exist if the list is valid.
+            let predecessor = &self.nodes[index_at_position - 1];
+            let current = &self.nodes[index_at_position];
+            let successor = &self.nodes[index_at_position + 1];
+            Some(NodeIds {
+                predecessor: predecessor.id.clone(),
+                current: current.id.clone(),
+                successor: successor.id.clone(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, id: Id, pred: Id, succ: Id, value: Value) -> Result<(), Value> {
+        match self.find_insert_position(&id, &pred, &succ) {
+            Ok(Some(position)) => {
+                self.insert_node(position, id, pred, succ, value);
+                Ok(())
+            }
+            Ok(None) | Err(_) => Err(value),
+        }
+    }
+
+    fn delete(&mut self, id: &Self::Id) -> Option<&Value> {
+        let node_index = self.delete_element(id).ok().flatten()?;
+        match self.nodes[node_index].operation {
+            Operation::Delete { ref value } => Some(value),
+            _ => None,
+        }
+    }
+
+    fn apply_operation(
+        &mut self,
+        operation: DataOperation<Self::Id, Value>,
+    ) -> Result<(), ApplyFailure<DataOperation<Self::Id, Value>>> {
+        match operation {
+            DataOperation::Insert {
+                id,
+                pred,
+                succ,
+                value,
+            } => match self.find_insert_position(&id, &pred, &succ) {
+                Ok(Some(position)) => {
+                    self.insert_node(position, id, pred, succ, value);
+                    Ok(())
+                }
+                Ok(None) => Err(ApplyFailure::Rejected {
+                    operation: DataOperation::Insert {
+                        id,
+                        pred,
+                        succ,
+                        value,
+                    },
+                }),
+                Err(source) => Err(ApplyFailure::Internal {
+                    operation: DataOperation::Insert {
+                        id,
+                        pred,
+                        succ,
+                        value,
+                    },
+                    source,
+                }),
+            },
+            DataOperation::Delete { ref start, ref end } => {
+                // Ranges aren't supported in this impl.
+                if end.is_some() {
+                    return Err(ApplyFailure::Rejected { operation });
+                }
+                match self.delete_element(start) {
+                    Ok(Some(_)) => Ok(()),
+                    Ok(None) => Err(ApplyFailure::Rejected { operation }),
+                    Err(source) => Err(ApplyFailure::Internal { operation, source }),
+                }
+            }
+            // Ranges aren't supported in this impl.
+            DataOperation::DeleteRanges { .. } => Err(ApplyFailure::Rejected { operation }),
+        }
+    }
+
+    fn iter_values(&self) -> Self::Iter<'_> {
+        PersistentLinearDataIter {
+            underlying: self.nodes.iter(),
+        }
+    }
+
+    fn iter_ids(&self) -> impl Iterator<Item = &Self::Id> {
+        self.nodes.iter().map(|n| &n.id)
+    }
+}
+
+impl<Id, Value> From<VecLinearData<Id, Value>> for PersistentLinearData<Id, Value>
+where
+    Id: Clone,
+    Value: Clone,
+{
+    fn from(data: VecLinearData<Id, Value>) -> Self {
+        Self {
+            len: data.len,
+            nodes: data.nodes.into_iter().collect(),
+        }
+    }
+}
+
+pub struct PersistentLinearDataIter<'a, Id, Value>
+where
+    Id: Clone,
+    Value: Clone,
+{
+    underlying: imbl::vector::Iter<'a, Node<Id, Value>, DefaultSharedPtr>,
+}
+impl<'a, Id, Value> Iterator for PersistentLinearDataIter<'a, Id, Value>
+where
+    Id: Clone,
+    Value: Clone,
+{
+    type Item = &'a Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut next = self.underlying.next().map(Node::get_current_value);
+        while let Some(None) = next {
+            next = self.underlying.next().map(Node::get_current_value);
+        }
+        next.flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_nodes_until_changed_and_merge_like_vec_linear_data() {
+        let mut vec_data = VecLinearData::new(0u32, u32::MAX);
+        for id in 1..=100 {
+            vec_data.append(id, id);
+        }
+        let mut data = PersistentLinearData::from(vec_data.clone());
+        data.validate_integrity().unwrap();
+
+        let snapshot = data.clone();
+        assert!(snapshot.shares_nodes_with(&data));
+
+        let operations = [
+            DataOperation::Insert {
+                id: 200,
+                pred: 10,
+                succ: 11,
+                value: 200,
+            },
+            DataOperation::Insert {
+                id: 199,
+                pred: 10,
+                succ: 11,
+                value: 199,
+            },
+            DataOperation::Delete {
+                start: 50,
+                end: None,
+            },
+        ];
+        for operation in operations {
+            vec_data.apply_operation(operation.clone()).unwrap();
+            data.apply_operation(operation).unwrap();
+        }
+
+        assert!(!snapshot.shares_nodes_with(&data));
+        assert_eq!(snapshot.len(), 100);
+        assert!(snapshot.iter_values().copied().eq(1..=100));
+        assert_eq!(data.len(), vec_data.len());
+        assert!(data.iter_values().eq(vec_data.iter_values()));
+        assert!(data.iter_ids().eq(vec_data.iter_ids()));
+        data.validate_integrity().unwrap();
+    }
+}
@@ -17,7 +17,9 @@ pub struct SnapshotHeader {
 /// Invariants for emitted streams:
 /// - Nodes are emitted in canonical order.
 /// - `index == 0` and `index == node_count - 1` are boundary nodes.
-/// - Boundary nodes have `value == None` and `deleted == false`.
+/// - Boundary nodes have `left/right/value == None` and `deleted == false`. Older streams may
+///   still record the outward origin of a boundary, i.e. `right` of the first or `left` of the
+///   last node, which readers ignore.
 /// - Non-boundary nodes have `left/right/value == Some(_)`.
 #[derive(Clone, Copy, Debug)]
 pub struct SnapshotNodeRef<'a, Id, Value: ?Sized> {
@@ -42,6 +44,7 @@ pub struct SnapshotNode<Id, Value> {
 
 /// Errors while reading/reconstructing snapshots.
 #[derive(Clone, Debug, PartialEq, Snafu)]
+#[non_exhaustive]
 pub enum SnapshotReadError<E>
 where
     E: snafu::Error + Send + Sync + 'static,
@@ -256,13 +259,8 @@ pub(crate) mod bytes_testkit {
             assert_eq!(node.index, expected_index);
             let is_boundary = expected_index == 0 || expected_index + 1 == parsed.node_count;
             if is_boundary {
-                if expected_index == 0 {
-                    assert!(!node.has_left);
-                    assert!(node.has_right);
-                } else {
-                    assert!(node.has_left);
-                    assert!(!node.has_right);
-                }
+                assert!(!node.has_left);
+                assert!(!node.has_right);
                 assert!(!node.has_value);
                 assert!(!node.deleted);
             } else {
@@ -602,4 +600,21 @@ mod tests {
         .unwrap();
         assert_eq!(roundtrip, original);
     }
+
+    #[test]
+    fn outward_boundary_origins_of_older_snapshots_are_ignored() {
+        let original = LinearLatestValueWins::new(11u64, [0u32, 1, 2]);
+
+        let mut sink = ByteBufSink::new(encode_u32, encode_u64);
+        original.encode_snapshot(&mut sink).unwrap();
+        let mut nodes = parse_snapshot_nodes(sink.into_bytes(), decode_u32, decode_u64).unwrap();
+        nodes[0].right = Some(1);
+        nodes[2].left = Some(1);
+
+        let roundtrip = LinearLatestValueWins::from_snapshot_nodes(
+            nodes.into_iter().map(Ok::<_, std::convert::Infallible>),
+        )
+        .unwrap();
+        assert_eq!(roundtrip, original);
+    }
 }
@@ -1,5 +1,5 @@
 use super::{
-    Composite,
+    ApplyFailure,
     DataOperation,
     IntegrityError,
     InvalidNodeSnafu,
@@ -14,17 +14,34 @@ use super::{
     assert_matches,
     ensure,
     fmt,
+    integration::{self, IntegrationError, IntegrationNodes},
     option_when,
-    vec,
 };
-use crate::snapshot::{
-    SnapshotHeader,
-    SnapshotNode,
-    SnapshotNodeRef,
-    SnapshotReadError,
-    SnapshotSink,
+use crate::{
+    InternalError,
+    InternalSnafu,
+    snapshot::{SnapshotHeader, SnapshotNode, SnapshotNodeRef, SnapshotReadError, SnapshotSink},
 };
-use std::{collections::HashMap, hash::Hash};
+#[cfg(not(feature = "heap-nodes"))]
+use smallvec::SmallVec;
+use std::hash::Hash;
+
+/// The number of nodes [`VecLinearData`] stores without a separate allocation.
+///
+/// This covers the common tiny document, e.g. a register or a one-line string, which consists
+/// of the two boundary nodes and a single value node, plus room for one more node so that the
+/// first update or append does not immediately spill onto the heap.
+#[cfg(not(feature = "heap-nodes"))]
+const INLINE_NODES: usize = 4;
+
+/// Node storage for [`VecLinearData`], keeping tiny documents inline.
+#[cfg(not(feature = "heap-nodes"))]
+pub(super) type NodeVec<Id, Value> = SmallVec<[Node<Id, Value>; INLINE_NODES]>;
+/// Node storage for [`VecLinearData`], always on the heap.
+///
+/// Only used to measure the inline storage against in the `small_documents` benchmark.
+#[cfg(feature = "heap-nodes")]
+pub(super) type NodeVec<Id, Value> = Vec<Node<Id, Value>>;
 
 /// An implementation of [[`LinearData`]] using a [[Vec]] to track the individual operation nodes.
 ///
@@ -32,125 +49,28 @@ use std::{collections::HashMap, hash::Hash};
 /// While the natural representation of this data structure is linked nodes,
 /// storing them in a Vec is likely more efficient in practice for most usages
 /// (e.g. read-mostly strings).
+/// Tiny documents keep their nodes inline instead of in a separate allocation.
+///
+/// Range deletes are not supported, so [`DataOperation::Delete`] with an `end` and
+/// [`DataOperation::DeleteRanges`] are always rejected.
 #[derive(Clone, Debug, PartialEq)]
 pub struct VecLinearData<Id, Value> {
     /// The number of Insert nodes in the linear data.
     pub(super) len: usize,
-    pub(super) nodes: Vec<Node<Id, Value>>,
-}
-
-/// Per-operation memoized traversal helper for right-origin chains.
-///
-/// This is used during conflict-position calculation to avoid repeating the same transitive
-/// right-origin traversals for multiple candidate nodes.
-pub(super) struct RightTreeTraversalMemo<'a, Id, Value> {
-    nodes: &'a [Node<Id, Value>],
-    boundary: &'a Id,
-    node_index_by_id: HashMap<&'a Id, usize>,
-    reaches_boundary_cache: Vec<Option<bool>>,
+    pub(super) nodes: NodeVec<Id, Value>,
 }
-impl<'a, Id, Value> RightTreeTraversalMemo<'a, Id, Value>
-where
-    Id: PartialEq + Eq + Hash + fmt::Debug,
-{
-    pub fn new(nodes: &'a [Node<Id, Value>], boundary: &'a Id) -> Self {
-        Self {
-            nodes,
-            boundary,
-            node_index_by_id: HashMap::new(),
-            reaches_boundary_cache: vec![None; nodes.len()],
-        }
-    }
-
-    pub fn with_new_boundary(mut self, boundary: &'a Id) -> Self {
-        if self.boundary != boundary {
-            self.boundary = boundary;
-            self.reaches_boundary_cache.fill(None);
-        }
-        self
-    }
-
-    /// Returns `true` iff the node at `start_index` is in the transitive right subtree that
-    /// includes `boundary`.
-    ///
-    /// In other words, if you follow `right_origin` anchors starting from this node, you hit the
-    /// node with `id = boundary` before you find a `None`.
-    pub fn reaches_boundary(&mut self, start_index: usize) -> bool {
-        if let Some(reaches) = self.reaches_boundary_cache[start_index] {
-            return reaches;
-        }
-
-        let mut path = Vec::new();
-        let mut current_index = start_index;
-        loop {
-            if let Some(reaches) = self.reaches_boundary_cache[current_index] {
-                self.cache_path(&path, reaches);
-                return reaches;
-            }
-
-            path.push(current_index);
-            let node = &self.nodes[current_index];
-            self.node_index_by_id
-                .entry(&node.id)
-                .or_insert(current_index);
-
-            if &node.id == self.boundary {
-                self.cache_path(&path, true);
-                return true;
-            }
-
-            let Some(next_id) = node.right_origin.as_ref() else {
-                self.cache_path(&path, false);
-                return false;
-            };
-
-            current_index = self.resolve_index(current_index, next_id);
-        }
-    }
 
-    fn cache_path(&mut self, path: &[usize], reaches: bool) {
-        for node_index in path.iter().copied() {
-            self.reaches_boundary_cache[node_index] = Some(reaches);
-        }
+impl<Id, Value> VecLinearData<Id, Value> {
+    /// The number of stored nodes, including both boundaries and deleted elements.
+    pub(crate) fn node_count(&self) -> usize {
+        self.nodes.len()
     }
 
-    fn resolve_index(&mut self, current_index: usize, id: &Id) -> usize {
-        if let Some(index) = self.node_index_by_id.get(id).copied() {
-            if index > current_index {
-                return index;
-            }
-
-            panic!(
-                "Invalid right_origin chain: id={id:?} resolves to index={index}, \
-                 which is not to the right of current_index={current_index}"
-            );
-        }
-
-        let search_start = current_index + 1;
-        if let Some(offset) = self.nodes[search_start..]
-            .iter()
-            .position(|node| &node.id == id)
-        {
-            let index = search_start + offset;
-            self.node_index_by_id.insert(&self.nodes[index].id, index);
-            return index;
-        }
-
-        if let Some(index) = self.nodes[..=current_index]
-            .iter()
-            .position(|node| &node.id == id)
-        {
-            panic!(
-                "Invalid right_origin chain: id={id:?} resolves to index={index}, \
-                 which is not to the right of current_index={current_index}"
-            );
-        }
-
-        panic!("For every origin a node should exist (missing id={id:?})");
+    /// Reserve room for exactly `additional` more nodes.
+    pub(crate) fn reserve_exact_nodes(&mut self, additional: usize) {
+        self.nodes.reserve_exact(additional);
     }
-}
 
-impl<Id, Value> VecLinearData<Id, Value> {
     pub(crate) fn encode_snapshot<S, ValueRef: ?Sized, F>(
         &self,
         sink: &mut S,
@@ -220,13 +140,9 @@ impl<Id, Value> VecLinearData<Id, Value> {
         };
         let mut pending = second.map_err(SnapshotReadError::from_source)?;
 
-        let mut reconstructed_nodes = Vec::with_capacity(lower.max(2));
-        reconstructed_nodes.push(Node {
-            id: first.id,
-            left_origin: first.left,
-            right_origin: first.right,
-            operation: Operation::Beginning,
-        });
+        let mut reconstructed_nodes = NodeVec::with_capacity(lower.max(2));
+        // Older snapshots still record the outward origins of the boundaries, which are dropped.
+        reconstructed_nodes.push(Node::beginning(first.id));
 
         let mut len = 0usize;
         let mut pending_index = 1usize;
@@ -282,12 +198,7 @@ impl<Id, Value> VecLinearData<Id, Value> {
                 index: pending_index,
             });
         }
-        reconstructed_nodes.push(Node {
-            id: pending.id,
-            left_origin: pending.left,
-            right_origin: pending.right,
-            operation: Operation::End,
-        });
+        reconstructed_nodes.push(Node::end(pending.id));
 
         Ok(Self {
             len,
@@ -300,45 +211,25 @@ where
     Id: Clone + fmt::Debug + PartialEq + Eq,
 {
     pub fn new(begin_id: Id, end_id: Id) -> Self {
-        let begin_node = Node {
-            id: begin_id.clone(),
-            left_origin: None,
-            right_origin: Some(end_id.clone()),
-            operation: Operation::Beginning,
-        };
-        let end_node = Node {
-            id: end_id,
-            left_origin: Some(begin_id),
-            right_origin: None,
-            operation: Operation::End,
-        };
-        let nodes = vec![begin_node, end_node];
+        let begin_node = Node::beginning(begin_id);
+        let end_node = Node::end(end_id);
+        let nodes = [begin_node, end_node].into_iter().collect();
         Self { len: 0, nodes }
     }
 
     pub fn with_value(initial_value: Value, ids: [Id; 3]) -> Self {
         let [begin_id, value_id, end_id] = ids;
-        let begin_node = Node {
-            id: begin_id.clone(),
-            left_origin: None,
-            right_origin: Some(value_id.clone()),
-            operation: Operation::Beginning,
-        };
         let value_node = Node {
-            id: value_id.clone(),
+            id: value_id,
             left_origin: Some(begin_id.clone()),
             right_origin: Some(end_id.clone()),
             operation: Operation::Insert {
                 value: initial_value,
             },
         };
-        let end_node = Node {
-            id: end_id,
-            left_origin: Some(value_id.clone()),
-            right_origin: None,
-            operation: Operation::End,
-        };
-        let nodes = vec![begin_node, value_node, end_node];
+        let begin_node = Node::beginning(begin_id);
+        let end_node = Node::end(end_id);
+        let nodes = [begin_node, value_node, end_node].into_iter().collect();
         Self { len: 1, nodes }
     }
 }
@@ -452,6 +343,121 @@ where
             .filter(|(_, n)| matches!(n.operation, Operation::Insert { .. }))
     }
 }
+impl<Id, Value> VecLinearData<Id, Value>
+where
+    Id: fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord,
+{
+    /// Find the node index at which an insert with `id` between `pred` and `succ` must be
+    /// placed.
+    ///
+    /// Returns `Ok(None)` if the insert does not fit the current state.
+    fn find_insert_position(
+        &self,
+        id: &Id,
+        pred: &Id,
+        succ: &Id,
+    ) -> Result<Option<usize>, InternalError> {
+        if self.nodes.iter().any(|node| node.id == *id) {
+            return Ok(None);
+        }
+        let Some(pred_index) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .find_map(|(index, node)| option_when!(node.id == *pred, index))
+        else {
+            return Ok(None);
+        };
+        // Successor cannot appear before predecessor in a valid operation.
+        let Some(succ_index) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .skip(pred_index + 1)
+            .find_map(|(index, node)| option_when!(node.id == *succ, index))
+        else {
+            return Ok(None);
+        };
+        if pred_index + 1 == succ_index {
+            // We can insert directly at the existing boundary.
+            return Ok(Some(succ_index));
+        }
+        // There is a gap between pred and succ that may contain concurrent inserts.
+        match integration::find_insert_position(self, pred_index, pred, succ_index, succ, id) {
+            Ok(position) => Ok(Some(position)),
+            // Duplicate insert for the same conflict set.
+            Err(IntegrationError::DuplicateInsert) => Ok(None),
+            Err(IntegrationError::Internal { source }) => Err(source),
+        }
+    }
+
+    fn insert_node(&mut self, position: usize, id: Id, pred: Id, succ: Id, value: Value) {
+        self.nodes.insert(
+            position,
+            Node {
+                id,
+                left_origin: Some(pred),
+                right_origin: Some(succ),
+                operation: Operation::Insert { value },
+            },
+        );
+        self.len += 1;
+    }
+
+    /// Mark the element with `id` as deleted.
+    ///
+    /// Returns the index of its node, or `None` if there is no element with `id` that can be
+    /// deleted. Deleting an element twice is fine.
+    fn delete_element(&mut self, id: &Id) -> Result<Option<usize>, InternalError>
+    where
+        Value: fmt::Debug,
+    {
+        let Some((node_index, node)) = self
+            .nodes
+            .iter_mut()
+            .enumerate()
+            .find(|(_index, n)| &n.id == id)
+        else {
+            return Ok(None);
+        };
+        match node.operation {
+            Operation::Insert { .. } => {
+                node.operation.delete();
+                self.len -= 1;
+                Ok(Some(node_index))
+            }
+            // Double delete is OK.
+            Operation::Delete { .. } => Ok(Some(node_index)),
+            // These cannot be deleted.
+            Operation::Beginning | Operation::End => Ok(None),
+            Operation::Invalid => InternalSnafu {
+                context: format!("Node {node_index} is invalid."),
+            }
+            .fail(),
+        }
+    }
+}
+impl<Id, Value> IntegrationNodes for VecLinearData<Id, Value>
+where
+    Id: fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord,
+{
+    type Id = Id;
+    type Value = Value;
+    type ConflictKey = Id;
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn node(&self, index: usize) -> &Node<Id, Value> {
+        &self.nodes[index]
+    }
+
+    fn conflict_key(id: &Id) -> &Id {
+        id
+    }
+}
+
 impl<Id, Value> LinearData<Value> for VecLinearData<Id, Value>
 where
     Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
@@ -501,209 +507,69 @@ where
     }
 
     fn insert(&mut self, id: Id, pred: Id, succ: Id, value: Value) -> Result<(), Value> {
-        self.apply_operation(DataOperation::Insert {
-            id,
-            pred,
-            succ,
-            value,
-        })
-        .map_err(|op| match op {
-            DataOperation::Insert { value, .. } => value,
-            DataOperation::Delete { .. } => unreachable!(
-                "apply_operation should not return a different operation type on error."
-            ),
-        })
+        match self.find_insert_position(&id, &pred, &succ) {
+            Ok(Some(position)) => {
+                self.insert_node(position, id, pred, succ, value);
+                Ok(())
+            }
+            Ok(None) | Err(_) => Err(value),
+        }
     }
 
     fn delete(&mut self, id: &Self::Id) -> Option<&Value> {
-        //println!("Trying to delete id={id:?} from: {:#?}", self.nodes);
-        if let Some(node) = self.nodes.iter_mut().find(|n| &n.id == id) {
-            match node.operation {
-                Operation::Insert { .. } => {
-                    node.operation.delete();
-                    self.len -= 1;
-                    if let Operation::Delete { ref value } = node.operation {
-                        Some(value)
-                    } else {
-                        // We literally just put it there.
-                        unreachable!()
-                    }
-                }
-                // Double delete is OK.
-                Operation::Delete { ref value } => Some(value),
-                // These cannot be deleted.
-                Operation::Beginning | Operation::End => {
-                    //println!("Tried to delete Beginning/End");
-                    None
-                }
-                Operation::Invalid => panic!("Node is invalid."),
-            }
-        } else {
-            //println!("Could not find node.");
-            None
+        let node_index = self.delete_element(id).ok().flatten()?;
+        match self.nodes[node_index].operation {
+            Operation::Delete { ref value } => Some(value),
+            _ => None,
         }
     }
 
-    #[allow(
-        clippy::too_many_lines,
-        reason = "The insertion path is a single CRDT transition over predecessor, successor, and sibling-order cases."
-    )]
     fn apply_operation(
         &mut self,
         operation: DataOperation<Self::Id, Value>,
-    ) -> Result<(), DataOperation<Self::Id, Value>> {
+    ) -> Result<(), ApplyFailure<DataOperation<Self::Id, Value>>> {
         match operation {
             DataOperation::Insert {
-                ref id,
-                ref pred,
-                ref succ,
-                ..
-            } => {
-                let pred_index_opt = self
-                    .nodes
-                    .iter()
-                    .enumerate()
-                    .find_map(|(index, node)| option_when!(node.id == *pred, index));
-                if let Some(pred_index) = pred_index_opt {
-                    let succ_index_opt = self
-                        .nodes
-                        .iter()
-                        .enumerate()
-                        .skip(pred_index)
-                        .find_map(|(index, node)| option_when!(node.id == *succ, index));
-                    if let Some(succ_index) = succ_index_opt {
-                        if pred_index + 1 == succ_index {
-                            // We can insert directly at the existing boundary.
-                            if let DataOperation::Insert {
-                                id,
-                                pred,
-                                succ,
-                                value,
-                            } = operation
-                            {
-                                self.nodes.insert(
-                                    succ_index,
-                                    Node {
-                                        id,
-                                        left_origin: Some(pred),
-                                        right_origin: Some(succ),
-                                        operation: Operation::Insert { value },
-                                    },
-                                );
-                                self.len += 1;
-                                Ok(())
-                            } else {
-                                unreachable!("We *know* it's an Insert.");
-                            }
-                        } else if pred_index < succ_index {
-                            // There is a gap between pred and succ that may contain concurrent inserts.
-                            let left_right_range = (pred_index + 1)..succ_index;
-                            let mut conflicting_nodes: Vec<(&Id, usize)> =
-                                Vec::with_capacity(left_right_range.len());
-                            // The right subtree is all nodes that have succ as successor,
-                            // and all nodes that can reach those nodes by following right_origin.
-                            let mut right_subtree_start_index_opt = None;
-                            let mut right_tree_memo =
-                                RightTreeTraversalMemo::new(&self.nodes, succ);
-                            for node_index in left_right_range {
-                                let node = &self.nodes[node_index];
-                                if node.left_origin.as_ref() == Some(pred)
-                                    && node.right_origin.as_ref() == Some(succ)
-                                {
-                                    conflicting_nodes.push((&node.id, node_index));
-                                }
-                                if right_subtree_start_index_opt.is_none()
-                                    && right_tree_memo.reaches_boundary(node_index)
-                                {
-                                    right_subtree_start_index_opt = Some(node_index);
-                                }
-                            }
-                            let right_subtree_start_index =
-                                right_subtree_start_index_opt.unwrap_or(succ_index);
-
-                            let position = if conflicting_nodes.is_empty() {
-                                right_subtree_start_index
-                            } else {
-                                debug_assert!(
-                                    conflicting_nodes.is_sorted_by_key(|(one, _)| one),
-                                    "Conflict range should already be sorted by id, but was: {conflicting_nodes:?}"
-                                );
-                                match conflicting_nodes
-                                    .binary_search_by(|&(probe, _)| probe.cmp(id))
-                                {
-                                    Ok(_found_index) => {
-                                        // Duplicate insert for the same conflict set.
-                                        return Err(operation);
-                                    }
-                                    Err(insert_index) => {
-                                        if insert_index == 0 {
-                                            // Insert before the first conflicting node and its local subtree.
-                                            pred_index + 1
-                                        } else if insert_index < conflicting_nodes.len() {
-                                            let (target_conflict_id, target_conflict_pos) =
-                                                conflicting_nodes[insert_index];
-                                            // Insert before the target conflicting node's local
-                                            // subtree, not just before the node itself.
-                                            // Otherwise the relative order of sibling subtrees can
-                                            // depend on delivery order.
-                                            let mut target_tree_memo = right_tree_memo
-                                                .with_new_boundary(target_conflict_id);
-                                            let mut subtree_start = target_conflict_pos;
-                                            for node_index in (pred_index + 1)..target_conflict_pos
-                                            {
-                                                if target_tree_memo.reaches_boundary(node_index) {
-                                                    subtree_start = node_index;
-                                                    break;
-                                                }
-                                            }
-                                            subtree_start
-                                        } else {
-                                            // Insert before succ, to the right of all conflicting nodes.
-                                            succ_index
-                                        }
-                                    }
-                                }
-                            };
-
-                            if let DataOperation::Insert {
-                                id,
-                                pred,
-                                succ,
-                                value,
-                            } = operation
-                            {
-                                self.nodes.insert(
-                                    position,
-                                    Node {
-                                        id,
-                                        left_origin: Some(pred),
-                                        right_origin: Some(succ),
-                                        operation: Operation::Insert { value },
-                                    },
-                                );
-                                self.len += 1;
-                                Ok(())
-                            } else {
-                                unreachable!("We *know* it's an Insert.");
-                            }
-                        } else {
-                            // Successor cannot appear before predecessor in a valid operation.
-                            Err(operation)
-                        }
-                    } else {
-                        Err(operation)
-                    }
-                } else {
-                    Err(operation)
+                id,
+                pred,
+                succ,
+                value,
+            } => match self.find_insert_position(&id, &pred, &succ) {
+                Ok(Some(position)) => {
+                    self.insert_node(position, id, pred, succ, value);
+                    Ok(())
                 }
-            }
+                Ok(None) => Err(ApplyFailure::Rejected {
+                    operation: DataOperation::Insert {
+                        id,
+                        pred,
+                        succ,
+                        value,
+                    },
+                }),
+                Err(source) => Err(ApplyFailure::Internal {
+                    operation: DataOperation::Insert {
+                        id,
+                        pred,
+                        succ,
+                        value,
+                    },
+                    source,
+                }),
+            },
             DataOperation::Delete { ref start, ref end } => {
                 // Ranges aren't supported in this impl.
                 if end.is_some() {
-                    return Err(operation);
+                    return Err(ApplyFailure::Rejected { operation });
+                }
+                match self.delete_element(start) {
+                    Ok(Some(_)) => Ok(()),
+                    Ok(None) => Err(ApplyFailure::Rejected { operation }),
+                    Err(source) => Err(ApplyFailure::Internal { operation, source }),
                 }
-                self.delete(start).map(|_| ()).ok_or(operation)
             }
+            // Ranges aren't supported in this impl.
+            DataOperation::DeleteRanges { .. } => Err(ApplyFailure::Rejected { operation }),
         }
     }
 
@@ -0,0 +1,188 @@
+//! Retention policies that bound how long document history is kept.
+//!
+//! A [`VersionedDoc`](crate::versioned::VersionedDoc) retains every applied change, so it can send
+//! them to replicas that are behind. [`compact_history`] drops the oldest of these changes as
+//! allowed by the [`RetentionPolicy`] of the document type. Replicas that are further behind than
+//! the retained history have to catch up from a snapshot instead.
+//!
+//! The policies themselves live in [`RetentionPolicies`], a replicated document with one
+//! latest-value-wins register per document type, so every member enforces the same policy.
+//!
+//! [`compact_history`]: crate::versioned::VersionedDoc::compact_history
+use crate::{
+    IdWithIndex,
+    any_data::{LinearLatestValueWins, UpdateOperation},
+};
+use std::{collections::BTreeMap, fmt, hash::Hash, time::Duration};
+
+/// How long the history of a document is kept after it was applied.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RetentionPolicy {
+    /// Never drop any history.
+    #[default]
+    Forever,
+    /// Drop changes once every member of the group has applied them.
+    UntilStable,
+    /// Drop changes once they were applied longer than `duration` ago, whether or not every
+    /// member has applied them yet.
+    KeepFor { duration: Duration },
+}
+
+impl RetentionPolicy {
+    const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+    /// Keep history for `days` days.
+    #[must_use]
+    pub const fn keep_days(days: u32) -> Self {
+        Self::KeepFor {
+            duration: Duration::from_secs(days as u64 * Self::SECONDS_PER_DAY),
+        }
+    }
+}
+
+/// A change of the [`RetentionPolicy`] of one document type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetentionPolicyOperation<Id> {
+    pub document_type: String,
+    pub update: UpdateOperation<IdWithIndex<Id>, RetentionPolicy>,
+}
+
+/// The replicated [`RetentionPolicy`] of every document type.
+///
+/// Document types without a policy of their own use the default policy. Every type's policy is a
+/// [`LinearLatestValueWins`] register that starts out with the default policy, so concurrent
+/// changes to the same type converge like any other register.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetentionPolicies<Id> {
+    default_policy: RetentionPolicy,
+    /// The synthetic id the initial value of every register is derived from.
+    origin: Id,
+    policies: BTreeMap<String, LinearLatestValueWins<IdWithIndex<Id>, RetentionPolicy>>,
+}
+
+impl<Id> RetentionPolicies<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    /// Apply `default_policy` to all document types.
+    ///
+    /// `origin` must be the same on all replicas and must not be used for any operation, such as
+    /// `UpdateId::INITIAL_STATE_ORIGIN`.
+    pub fn new(default_policy: RetentionPolicy, origin: Id) -> Self {
+        Self {
+            default_policy,
+            origin,
+            policies: BTreeMap::new(),
+        }
+    }
+
+    pub fn default_policy(&self) -> RetentionPolicy {
+        self.default_policy
+    }
+
+    /// The policy that currently applies to `document_type`.
+    pub fn policy_for(&self, document_type: &str) -> RetentionPolicy {
+        self.policies
+            .get(document_type)
+            .map_or(self.default_policy, |register| *register.content())
+    }
+
+    /// Produce an operation that sets the policy of `document_type` to `policy`.
+    ///
+    /// The operation still has to be applied, locally as well as on the other replicas.
+    pub fn set_policy_operation(
+        &self,
+        id: Id,
+        document_type: &str,
+        policy: RetentionPolicy,
+    ) -> RetentionPolicyOperation<Id> {
+        let id = IdWithIndex::zero(id);
+        let update = match self.policies.get(document_type) {
+            Some(register) => register.update_operation(id, policy),
+            None => self.initial_register().update_operation(id, policy),
+        };
+        RetentionPolicyOperation {
+            document_type: document_type.to_owned(),
+            update,
+        }
+    }
+
+    /// Apply a policy change produced by this or another replica.
+    ///
+    /// # Errors
+    ///
+    /// Returns the operation unchanged if it does not fit the register of its document type.
+    pub fn apply_operation(
+        &mut self,
+        operation: RetentionPolicyOperation<Id>,
+    ) -> Result<(), RetentionPolicyOperation<Id>> {
+        let RetentionPolicyOperation {
+            document_type,
+            update,
+        } = operation;
+        let mut register = self
+            .policies
+            .get(&document_type)
+            .cloned()
+            .unwrap_or_else(|| self.initial_register());
+        match register.apply_operation(update) {
+            Ok(()) => {
+                self.policies.insert(document_type, register);
+                Ok(())
+            }
+            Err(update) => Err(RetentionPolicyOperation {
+                document_type,
+                update,
+            }),
+        }
+    }
+
+    /// The register every document type starts out with, identical on all replicas.
+    fn initial_register(&self) -> LinearLatestValueWins<IdWithIndex<Id>, RetentionPolicy> {
+        let begin_id = IdWithIndex::zero(self.origin.clone());
+        let value_id = begin_id.increment();
+        let end_id = value_id.increment();
+        LinearLatestValueWins::new(self.default_policy, [begin_id, value_id, end_id])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_core::versions::UpdateId;
+
+    fn update_id(version: u64, node_index: u32) -> UpdateId {
+        UpdateId {
+            version,
+            node_index,
+        }
+    }
+
+    #[test]
+    fn policy_changes_converge_per_document_type() {
+        let base = RetentionPolicies::new(RetentionPolicy::Forever, UpdateId::INITIAL_STATE_ORIGIN);
+        let mut alice = base.clone();
+        let mut bob = base;
+
+        let to_days =
+            alice.set_policy_operation(update_id(1, 0), "list", RetentionPolicy::keep_days(7));
+        let to_stable =
+            bob.set_policy_operation(update_id(1, 1), "list", RetentionPolicy::UntilStable);
+        alice.apply_operation(to_days.clone()).unwrap();
+        bob.apply_operation(to_stable.clone()).unwrap();
+        alice.apply_operation(to_stable).unwrap();
+        bob.apply_operation(to_days).unwrap();
+
+        assert_eq!(alice, bob);
+        assert_ne!(alice.policy_for("list"), RetentionPolicy::Forever);
+        assert_eq!(alice.policy_for("latest_value"), RetentionPolicy::Forever);
+
+        // A later change, made after seeing both, wins everywhere.
+        let to_forever =
+            alice.set_policy_operation(update_id(2, 0), "list", RetentionPolicy::Forever);
+        alice.apply_operation(to_forever.clone()).unwrap();
+        bob.apply_operation(to_forever).unwrap();
+        assert_eq!(alice, bob);
+        assert_eq!(bob.policy_for("list"), RetentionPolicy::Forever);
+    }
+}
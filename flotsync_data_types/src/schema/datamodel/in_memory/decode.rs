@@ -1,5 +1,6 @@
 use super::{
     BasicValueRef,
+    DocRef,
     NullableBasicValueRef,
     PrimitiveValueArrayRef,
     PrimitiveValueRef,
@@ -398,6 +399,17 @@ impl Decode for NaiveDate {
     }
 }
 
+impl Decode for DocRef {
+    fn decode(value: NullableBasicValueRef<'_>) -> Result<Cow<'_, Self>, DecodeValueError> {
+        match require_primitive::<Self>(value)? {
+            PrimitiveValueRef::DocRef(v) => Ok(Cow::Borrowed(v)),
+            actual => Err(basic_type_mismatch::<Self>(&BasicValueRef::Primitive(
+                actual,
+            ))),
+        }
+    }
+}
+
 impl_decode_integer_scalar!(u8);
 impl_decode_integer_scalar!(u16);
 impl_decode_integer_scalar!(u32);
@@ -427,6 +439,7 @@ impl_decode_exact_vec!(OrderedFloat<f64>, Float);
 impl_decode_exact_vec!(bool, Boolean);
 impl_decode_exact_vec!(Vec<u8>, Binary);
 impl_decode_exact_vec!(NaiveDate, Date);
+impl_decode_exact_vec!(DocRef, DocRef);
 
 impl Decode for Vec<f64> {
     fn decode(value: NullableBasicValueRef<'_>) -> Result<Cow<'_, Self>, DecodeValueError> {
@@ -494,6 +507,7 @@ impl_decode_box_slice_via_vec!(OrderedFloat<f64>);
 impl_decode_box_slice_via_vec!(bool);
 impl_decode_box_slice_via_vec!(Vec<u8>);
 impl_decode_box_slice_via_vec!(NaiveDate);
+impl_decode_box_slice_via_vec!(DocRef);
 
 impl_decode_integer_slice!(u8);
 impl_decode_integer_slice!(u16);
@@ -512,3 +526,4 @@ impl_decode_exact_slice!(OrderedFloat<f64>, Float);
 impl_decode_exact_slice!(bool, Boolean);
 impl_decode_exact_slice!(Vec<u8>, Binary);
 impl_decode_exact_slice!(NaiveDate, Date);
+impl_decode_exact_slice!(DocRef, DocRef);
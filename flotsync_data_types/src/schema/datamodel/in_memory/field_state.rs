@@ -103,6 +103,39 @@ impl<OperationId> InMemoryFieldState<OperationId>
 where
     OperationId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
 {
+    /// Validate the internal CRDT structure of this field.
+    ///
+    /// Fields without node-graph state, such as counters and registers, are always valid.
+    ///
+    /// # Errors
+    ///
+    /// See `IntegrityError` for failure conditions.
+    pub fn validate_integrity(&self) -> Result<(), IntegrityError> {
+        match self {
+            Self::LatestValueWins(value) => value.validate_integrity(),
+            Self::LinearString(value) => value.validate_integrity(),
+            Self::LinearList(value) => value.validate_integrity(),
+            Self::MonotonicCounter(_)
+            | Self::TotalOrderRegister(_)
+            | Self::TotalOrderFiniteStateRegister(_) => Ok(()),
+        }
+    }
+
+    /// Number of stored CRDT nodes in this field.
+    ///
+    /// Fields without node-graph state, such as counters and registers, count as a single node.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        match self {
+            Self::LatestValueWins(value) => value.node_count(),
+            Self::LinearString(value) => value.node_count(),
+            Self::LinearList(value) => value.node_count(),
+            Self::MonotonicCounter(_)
+            | Self::TotalOrderRegister(_)
+            | Self::TotalOrderFiniteStateRegister(_) => 1,
+        }
+    }
+
     /// Project this CRDT state to its current application-visible value.
     #[must_use]
     pub fn project_value(&self) -> ProjectedFieldValue<'_> {
@@ -160,6 +193,7 @@ pub enum LinearLatestValueWinsState<OperationId> {
     Binary(LinearLatestValueWins<IdWithIndex<OperationId>, Vec<u8>>),
     Date(LinearLatestValueWins<IdWithIndex<OperationId>, NaiveDate>),
     Timestamp(LinearLatestValueWins<IdWithIndex<OperationId>, UnixTimestamp>),
+    DocRef(LinearLatestValueWins<IdWithIndex<OperationId>, DocRef>),
     StringArray(LinearLatestValueWins<IdWithIndex<OperationId>, Vec<String>>),
     UIntArray(LinearLatestValueWins<IdWithIndex<OperationId>, Vec<u64>>),
     IntArray(LinearLatestValueWins<IdWithIndex<OperationId>, Vec<i64>>),
@@ -169,6 +203,7 @@ pub enum LinearLatestValueWinsState<OperationId> {
     BinaryArray(LinearLatestValueWins<IdWithIndex<OperationId>, Vec<Vec<u8>>>),
     DateArray(LinearLatestValueWins<IdWithIndex<OperationId>, Vec<NaiveDate>>),
     TimestampArray(LinearLatestValueWins<IdWithIndex<OperationId>, Vec<UnixTimestamp>>),
+    DocRefArray(LinearLatestValueWins<IdWithIndex<OperationId>, Vec<DocRef>>),
     NullableString(LinearLatestValueWins<IdWithIndex<OperationId>, Option<String>>),
     NullableUInt(LinearLatestValueWins<IdWithIndex<OperationId>, Option<u64>>),
     NullableInt(LinearLatestValueWins<IdWithIndex<OperationId>, Option<i64>>),
@@ -178,6 +213,7 @@ pub enum LinearLatestValueWinsState<OperationId> {
     NullableBinary(LinearLatestValueWins<IdWithIndex<OperationId>, Option<Vec<u8>>>),
     NullableDate(LinearLatestValueWins<IdWithIndex<OperationId>, Option<NaiveDate>>),
     NullableTimestamp(LinearLatestValueWins<IdWithIndex<OperationId>, Option<UnixTimestamp>>),
+    NullableDocRef(LinearLatestValueWins<IdWithIndex<OperationId>, Option<DocRef>>),
     NullableStringArray(LinearLatestValueWins<IdWithIndex<OperationId>, Option<Vec<String>>>),
     NullableUIntArray(LinearLatestValueWins<IdWithIndex<OperationId>, Option<Vec<u64>>>),
     NullableIntArray(LinearLatestValueWins<IdWithIndex<OperationId>, Option<Vec<i64>>>),
@@ -191,8 +227,112 @@ pub enum LinearLatestValueWinsState<OperationId> {
     NullableTimestampArray(
         LinearLatestValueWins<IdWithIndex<OperationId>, Option<Vec<UnixTimestamp>>>,
     ),
+    NullableDocRefArray(LinearLatestValueWins<IdWithIndex<OperationId>, Option<Vec<DocRef>>>),
 }
 impl<OperationId> LinearLatestValueWinsState<OperationId> {
+    /// Validate the internal CRDT structure of this register.
+    ///
+    /// # Errors
+    ///
+    /// See `IntegrityError` for failure conditions.
+    pub fn validate_integrity(&self) -> Result<(), IntegrityError>
+    where
+        OperationId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    {
+        match self {
+            Self::String(value) => value.validate_integrity(),
+            Self::UInt(value) => value.validate_integrity(),
+            Self::Int(value) => value.validate_integrity(),
+            Self::Byte(value) => value.validate_integrity(),
+            Self::Float(value) => value.validate_integrity(),
+            Self::Boolean(value) => value.validate_integrity(),
+            Self::Binary(value) => value.validate_integrity(),
+            Self::Date(value) => value.validate_integrity(),
+            Self::Timestamp(value) => value.validate_integrity(),
+            Self::DocRef(value) => value.validate_integrity(),
+            Self::StringArray(value) => value.validate_integrity(),
+            Self::UIntArray(value) => value.validate_integrity(),
+            Self::IntArray(value) => value.validate_integrity(),
+            Self::ByteArray(value) => value.validate_integrity(),
+            Self::FloatArray(value) => value.validate_integrity(),
+            Self::BooleanArray(value) => value.validate_integrity(),
+            Self::BinaryArray(value) => value.validate_integrity(),
+            Self::DateArray(value) => value.validate_integrity(),
+            Self::TimestampArray(value) => value.validate_integrity(),
+            Self::DocRefArray(value) => value.validate_integrity(),
+            Self::NullableString(value) => value.validate_integrity(),
+            Self::NullableUInt(value) => value.validate_integrity(),
+            Self::NullableInt(value) => value.validate_integrity(),
+            Self::NullableByte(value) => value.validate_integrity(),
+            Self::NullableFloat(value) => value.validate_integrity(),
+            Self::NullableBoolean(value) => value.validate_integrity(),
+            Self::NullableBinary(value) => value.validate_integrity(),
+            Self::NullableDate(value) => value.validate_integrity(),
+            Self::NullableTimestamp(value) => value.validate_integrity(),
+            Self::NullableDocRef(value) => value.validate_integrity(),
+            Self::NullableStringArray(value) => value.validate_integrity(),
+            Self::NullableUIntArray(value) => value.validate_integrity(),
+            Self::NullableIntArray(value) => value.validate_integrity(),
+            Self::NullableByteArray(value) => value.validate_integrity(),
+            Self::NullableFloatArray(value) => value.validate_integrity(),
+            Self::NullableBooleanArray(value) => value.validate_integrity(),
+            Self::NullableBinaryArray(value) => value.validate_integrity(),
+            Self::NullableDateArray(value) => value.validate_integrity(),
+            Self::NullableTimestampArray(value) => value.validate_integrity(),
+            Self::NullableDocRefArray(value) => value.validate_integrity(),
+        }
+    }
+
+    /// Number of stored nodes, including every value ever written to this register.
+    #[must_use]
+    pub fn node_count(&self) -> usize
+    where
+        OperationId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    {
+        match self {
+            Self::String(value) => value.node_count(),
+            Self::UInt(value) => value.node_count(),
+            Self::Int(value) => value.node_count(),
+            Self::Byte(value) => value.node_count(),
+            Self::Float(value) => value.node_count(),
+            Self::Boolean(value) => value.node_count(),
+            Self::Binary(value) => value.node_count(),
+            Self::Date(value) => value.node_count(),
+            Self::Timestamp(value) => value.node_count(),
+            Self::DocRef(value) => value.node_count(),
+            Self::StringArray(value) => value.node_count(),
+            Self::UIntArray(value) => value.node_count(),
+            Self::IntArray(value) => value.node_count(),
+            Self::ByteArray(value) => value.node_count(),
+            Self::FloatArray(value) => value.node_count(),
+            Self::BooleanArray(value) => value.node_count(),
+            Self::BinaryArray(value) => value.node_count(),
+            Self::DateArray(value) => value.node_count(),
+            Self::TimestampArray(value) => value.node_count(),
+            Self::DocRefArray(value) => value.node_count(),
+            Self::NullableString(value) => value.node_count(),
+            Self::NullableUInt(value) => value.node_count(),
+            Self::NullableInt(value) => value.node_count(),
+            Self::NullableByte(value) => value.node_count(),
+            Self::NullableFloat(value) => value.node_count(),
+            Self::NullableBoolean(value) => value.node_count(),
+            Self::NullableBinary(value) => value.node_count(),
+            Self::NullableDate(value) => value.node_count(),
+            Self::NullableTimestamp(value) => value.node_count(),
+            Self::NullableDocRef(value) => value.node_count(),
+            Self::NullableStringArray(value) => value.node_count(),
+            Self::NullableUIntArray(value) => value.node_count(),
+            Self::NullableIntArray(value) => value.node_count(),
+            Self::NullableByteArray(value) => value.node_count(),
+            Self::NullableFloatArray(value) => value.node_count(),
+            Self::NullableBooleanArray(value) => value.node_count(),
+            Self::NullableBinaryArray(value) => value.node_count(),
+            Self::NullableDateArray(value) => value.node_count(),
+            Self::NullableTimestampArray(value) => value.node_count(),
+            Self::NullableDocRefArray(value) => value.node_count(),
+        }
+    }
+
     /// Project this CRDT register to its current application-visible value.
     #[must_use]
     #[allow(
@@ -221,6 +361,7 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
             Self::Timestamp(value) => {
                 projected_primitive(PrimitiveValueRef::Timestamp(*value.content()))
             }
+            Self::DocRef(value) => projected_primitive(PrimitiveValueRef::DocRef(value.content())),
             Self::StringArray(value) => {
                 projected_array(PrimitiveValueArrayRef::String(value.content().as_slice()))
             }
@@ -248,6 +389,9 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
             Self::TimestampArray(value) => projected_array(PrimitiveValueArrayRef::Timestamp(
                 value.content().as_slice(),
             )),
+            Self::DocRefArray(value) => {
+                projected_array(PrimitiveValueArrayRef::DocRef(value.content().as_slice()))
+            }
             Self::NullableString(value) => projected_nullable_primitive(
                 value
                     .content()
@@ -302,6 +446,9 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                     .as_ref()
                     .map(|value| PrimitiveValueRef::Timestamp(*value)),
             ),
+            Self::NullableDocRef(value) => projected_nullable_primitive(
+                value.content().as_ref().map(PrimitiveValueRef::DocRef),
+            ),
             Self::NullableStringArray(value) => projected_nullable_array(
                 value
                     .content()
@@ -356,6 +503,12 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                     .as_ref()
                     .map(|value| PrimitiveValueArrayRef::Timestamp(value.as_slice())),
             ),
+            Self::NullableDocRefArray(value) => projected_nullable_array(
+                value
+                    .content()
+                    .as_ref()
+                    .map(|value| PrimitiveValueArrayRef::DocRef(value.as_slice())),
+            ),
         }
     }
 
@@ -403,6 +556,10 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                 Self::NullableTimestamp(_),
                 NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::Timestamp)),
             ) => true,
+            (
+                Self::NullableDocRef(_),
+                NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::DocRef)),
+            ) => true,
             (
                 Self::NullableStringArray(_),
                 NullableBasicDataType::Nullable(BasicDataType::Array(array_type)),
@@ -439,6 +596,10 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                 Self::NullableTimestampArray(_),
                 NullableBasicDataType::Nullable(BasicDataType::Array(array_type)),
             ) => array_type.element_type == PrimitiveType::Timestamp,
+            (
+                Self::NullableDocRefArray(_),
+                NullableBasicDataType::Nullable(BasicDataType::Array(array_type)),
+            ) => array_type.element_type == PrimitiveType::DocRef,
             (Self::NullableString(_), _) => false,
             (Self::NullableUInt(_), _) => false,
             (Self::NullableInt(_), _) => false,
@@ -448,6 +609,7 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
             (Self::NullableBinary(_), _) => false,
             (Self::NullableDate(_), _) => false,
             (Self::NullableTimestamp(_), _) => false,
+            (Self::NullableDocRef(_), _) => false,
             (Self::NullableStringArray(_), _) => false,
             (Self::NullableUIntArray(_), _) => false,
             (Self::NullableIntArray(_), _) => false,
@@ -457,6 +619,7 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
             (Self::NullableBinaryArray(_), _) => false,
             (Self::NullableDateArray(_), _) => false,
             (Self::NullableTimestampArray(_), _) => false,
+            (Self::NullableDocRefArray(_), _) => false,
             (_, NullableBasicDataType::Nullable(_)) => false,
             (
                 Self::String(_),
@@ -494,6 +657,10 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                 Self::Timestamp(_),
                 NullableBasicDataType::NonNull(BasicDataType::Primitive(PrimitiveType::Timestamp)),
             ) => true,
+            (
+                Self::DocRef(_),
+                NullableBasicDataType::NonNull(BasicDataType::Primitive(PrimitiveType::DocRef)),
+            ) => true,
             (
                 Self::StringArray(_),
                 NullableBasicDataType::NonNull(BasicDataType::Array(array_type)),
@@ -530,6 +697,10 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                 Self::TimestampArray(_),
                 NullableBasicDataType::NonNull(BasicDataType::Array(array_type)),
             ) => array_type.element_type == PrimitiveType::Timestamp,
+            (
+                Self::DocRefArray(_),
+                NullableBasicDataType::NonNull(BasicDataType::Array(array_type)),
+            ) => array_type.element_type == PrimitiveType::DocRef,
             _ => false,
         }
     }
@@ -625,6 +796,15 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                     });
                 value.encode_snapshot(&mut adapter)
             }
+            Self::DocRef(value) => {
+                let mut adapter =
+                    LatestValueWinsSnapshotSinkAdapter::new(sink, |value: &DocRef| {
+                        NullableBasicValueRef::Value(BasicValueRef::Primitive(
+                            PrimitiveValueRef::DocRef(value),
+                        ))
+                    });
+                value.encode_snapshot(&mut adapter)
+            }
             Self::StringArray(value) => {
                 let mut adapter =
                     LatestValueWinsSnapshotSinkAdapter::new(sink, |value: &Vec<String>| {
@@ -708,6 +888,15 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                     });
                 value.encode_snapshot(&mut adapter)
             }
+            Self::DocRefArray(value) => {
+                let mut adapter =
+                    LatestValueWinsSnapshotSinkAdapter::new(sink, |value: &Vec<DocRef>| {
+                        NullableBasicValueRef::Value(BasicValueRef::Array(
+                            PrimitiveValueArrayRef::DocRef(value.as_slice()),
+                        ))
+                    });
+                value.encode_snapshot(&mut adapter)
+            }
             Self::NullableString(value) => {
                 let mut adapter =
                     LatestValueWinsSnapshotSinkAdapter::new(sink, |value: &Option<String>| {
@@ -816,6 +1005,18 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                 );
                 value.encode_snapshot(&mut adapter)
             }
+            Self::NullableDocRef(value) => {
+                let mut adapter =
+                    LatestValueWinsSnapshotSinkAdapter::new(sink, |value: &Option<DocRef>| {
+                        match value {
+                            Some(value) => NullableBasicValueRef::Value(BasicValueRef::Primitive(
+                                PrimitiveValueRef::DocRef(value),
+                            )),
+                            None => NullableBasicValueRef::Null,
+                        }
+                    });
+                value.encode_snapshot(&mut adapter)
+            }
             Self::NullableStringArray(value) => {
                 let mut adapter =
                     LatestValueWinsSnapshotSinkAdapter::new(sink, |value: &Option<Vec<String>>| {
@@ -924,6 +1125,18 @@ impl<OperationId> LinearLatestValueWinsState<OperationId> {
                 );
                 value.encode_snapshot(&mut adapter)
             }
+            Self::NullableDocRefArray(value) => {
+                let mut adapter =
+                    LatestValueWinsSnapshotSinkAdapter::new(sink, |value: &Option<Vec<DocRef>>| {
+                        match value {
+                            Some(value) => NullableBasicValueRef::Value(BasicValueRef::Array(
+                                PrimitiveValueArrayRef::DocRef(value.as_slice()),
+                            )),
+                            None => NullableBasicValueRef::Null,
+                        }
+                    });
+                value.encode_snapshot(&mut adapter)
+            }
         }
     }
 }
@@ -940,8 +1153,52 @@ pub enum LinearListState<OperationId> {
     Binary(LinearList<OperationId, Vec<u8>>),
     Date(LinearList<OperationId, NaiveDate>),
     Timestamp(LinearList<OperationId, UnixTimestamp>),
+    DocRef(LinearList<OperationId, DocRef>),
 }
 impl<OperationId> LinearListState<OperationId> {
+    /// Validate the internal CRDT structure of this list.
+    ///
+    /// # Errors
+    ///
+    /// See `IntegrityError` for failure conditions.
+    pub fn validate_integrity(&self) -> Result<(), IntegrityError>
+    where
+        OperationId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    {
+        match self {
+            Self::String(value) => value.validate_integrity(),
+            Self::UInt(value) => value.validate_integrity(),
+            Self::Int(value) => value.validate_integrity(),
+            Self::Byte(value) => value.validate_integrity(),
+            Self::Float(value) => value.validate_integrity(),
+            Self::Boolean(value) => value.validate_integrity(),
+            Self::Binary(value) => value.validate_integrity(),
+            Self::Date(value) => value.validate_integrity(),
+            Self::Timestamp(value) => value.validate_integrity(),
+            Self::DocRef(value) => value.validate_integrity(),
+        }
+    }
+
+    /// Number of stored nodes, including deleted elements.
+    #[must_use]
+    pub fn node_count(&self) -> usize
+    where
+        OperationId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    {
+        match self {
+            Self::String(value) => value.node_count(),
+            Self::UInt(value) => value.node_count(),
+            Self::Int(value) => value.node_count(),
+            Self::Byte(value) => value.node_count(),
+            Self::Float(value) => value.node_count(),
+            Self::Boolean(value) => value.node_count(),
+            Self::Binary(value) => value.node_count(),
+            Self::Date(value) => value.node_count(),
+            Self::Timestamp(value) => value.node_count(),
+            Self::DocRef(value) => value.node_count(),
+        }
+    }
+
     /// Project this list CRDT to its current application-visible array value.
     #[must_use]
     pub fn project_value(&self) -> PrimitiveValueArray
@@ -960,6 +1217,7 @@ impl<OperationId> LinearListState<OperationId> {
             Self::Timestamp(value) => {
                 PrimitiveValueArray::Timestamp(value.iter().copied().collect())
             }
+            Self::DocRef(value) => PrimitiveValueArray::DocRef(value.iter().cloned().collect()),
         }
     }
 
@@ -975,6 +1233,7 @@ impl<OperationId> LinearListState<OperationId> {
             Self::Binary(_) => PrimitiveType::Binary,
             Self::Date(_) => PrimitiveType::Date,
             Self::Timestamp(_) => PrimitiveType::Timestamp,
+            Self::DocRef(_) => PrimitiveType::DocRef,
         }
     }
 
@@ -1046,6 +1305,12 @@ impl<OperationId> LinearListState<OperationId> {
                     });
                 value.encode_snapshot(&mut adapter)
             }
+            Self::DocRef(value) => {
+                let mut adapter = LinearListSnapshotSinkAdapter::new(sink, |value: &[DocRef]| {
+                    PrimitiveValueArrayRef::DocRef(value)
+                });
+                value.encode_snapshot(&mut adapter)
+            }
         }
     }
 }
@@ -6,6 +6,7 @@ use crate::{
     DataOperation,
     IdWithIndex,
     InMemoryValueDataError,
+    IntegrityError,
     OperationOutcome,
     ProjectedFieldValue,
     RowStateRead,
@@ -817,6 +818,21 @@ where
     pub fn is_tombstoned(&self) -> bool {
         self.data.rows[self.row_index].deleted
     }
+
+    /// Number of stored CRDT nodes across all fields of this row.
+    ///
+    /// See [`InMemoryFieldState::node_count`].
+    #[must_use]
+    pub fn node_count(&self) -> usize
+    where
+        OperationId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    {
+        self.data.rows[self.row_index]
+            .fields
+            .iter()
+            .map(InMemoryFieldState::node_count)
+            .sum()
+    }
 }
 impl<RowId, OperationId> RowStateRead<OperationId> for InMemoryStateDataRow<'_, RowId, OperationId>
 where
@@ -378,6 +378,17 @@ where
                 operation_id.clone(),
             ),
         ))),
+        (
+            ReplicatedDataType::LinearList {
+                value_type: PrimitiveType::DocRef,
+            },
+            super::super::super::public_api::FieldTargetValue::PrimitiveArray(value),
+        ) => Ok(InMemoryFieldState::LinearList(LinearListState::DocRef(
+            LinearList::with_values(
+                decode_list_doc_ref(value.clone()).map_err(operation_invalid_value)?,
+                operation_id.clone(),
+            ),
+        ))),
         (
             ReplicatedDataType::MonotonicCounter { .. },
             super::super::super::public_api::FieldTargetValue::Counter(value),
@@ -466,6 +477,10 @@ where
             NullableBasicDataType::NonNull(BasicDataType::Primitive(PrimitiveType::Timestamp)),
             NullableBasicValue::Value(BasicValue::Primitive(PrimitiveValue::Timestamp(value))),
         ) => build_lww_variant!(Timestamp, *value),
+        (
+            NullableBasicDataType::NonNull(BasicDataType::Primitive(PrimitiveType::DocRef)),
+            NullableBasicValue::Value(BasicValue::Primitive(PrimitiveValue::DocRef(value))),
+        ) => build_lww_variant!(DocRef, value.clone()),
         (
             NullableBasicDataType::NonNull(BasicDataType::Array(array_type)),
             NullableBasicValue::Value(BasicValue::Array(value)),
@@ -555,6 +570,15 @@ where
                 }
                 .fail(),
             },
+            PrimitiveType::DocRef => match value {
+                PrimitiveValueArray::DocRef(value) => build_lww_variant!(DocRefArray, value.clone()),
+                _ => crate::InternalOperationSnafu {
+                    context: format!(
+                        "Field '{field_name}' had an unexpected LatestValueWins array payload."
+                    ),
+                }
+                .fail(),
+            },
         },
         (
             NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::String)),
@@ -592,6 +616,10 @@ where
             NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::Timestamp)),
             NullableBasicValue::Null,
         ) => build_lww_variant!(NullableTimestamp, None::<UnixTimestamp>),
+        (
+            NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::DocRef)),
+            NullableBasicValue::Null,
+        ) => build_lww_variant!(NullableDocRef, None::<DocRef>),
         (
             NullableBasicDataType::Nullable(BasicDataType::Array(array_type)),
             NullableBasicValue::Null,
@@ -609,6 +637,7 @@ where
             PrimitiveType::Timestamp => {
                 build_lww_variant!(NullableTimestampArray, None::<Vec<UnixTimestamp>>)
             }
+            PrimitiveType::DocRef => build_lww_variant!(NullableDocRefArray, None::<Vec<DocRef>>),
         },
         (
             NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::String)),
@@ -646,6 +675,10 @@ where
             NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::Timestamp)),
             NullableBasicValue::Value(BasicValue::Primitive(PrimitiveValue::Timestamp(value))),
         ) => build_lww_variant!(NullableTimestamp, Some(*value)),
+        (
+            NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::DocRef)),
+            NullableBasicValue::Value(BasicValue::Primitive(PrimitiveValue::DocRef(value))),
+        ) => build_lww_variant!(NullableDocRef, Some(value.clone())),
         (
             NullableBasicDataType::Nullable(BasicDataType::Array(array_type)),
             NullableBasicValue::Value(BasicValue::Array(value)),
@@ -749,6 +782,17 @@ where
                 }
                 .fail(),
             },
+            PrimitiveType::DocRef => match value {
+                PrimitiveValueArray::DocRef(value) => {
+                    build_lww_variant!(NullableDocRefArray, Some(value.clone()))
+                }
+                _ => crate::InternalOperationSnafu {
+                    context: format!(
+                        "Field '{field_name}' had an unexpected LatestValueWins array payload."
+                    ),
+                }
+                .fail(),
+            },
         },
         _ => crate::InternalOperationSnafu {
             context: format!(
@@ -855,6 +899,15 @@ where
             operation_id,
         )
         .map(|value| value.map(OperationValue::LatestValueWins)),
+        (
+            InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::DocRef(current)),
+            super::super::super::public_api::FieldTargetValue::NullableBasic(target),
+        ) => build_lww_operation(
+            current,
+            decode_required_doc_ref(target).map_err(operation_invalid_value)?,
+            operation_id,
+        )
+        .map(|value| value.map(OperationValue::LatestValueWins)),
         (
             InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::StringArray(current)),
             super::super::super::public_api::FieldTargetValue::NullableBasic(target),
@@ -938,6 +991,15 @@ where
             operation_id,
         )
         .map(|value| value.map(OperationValue::LatestValueWins)),
+        (
+            InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::DocRefArray(current)),
+            super::super::super::public_api::FieldTargetValue::NullableBasic(target),
+        ) => build_lww_operation(
+            current,
+            decode_required_doc_ref_array(target).map_err(operation_invalid_value)?,
+            operation_id,
+        )
+        .map(|value| value.map(OperationValue::LatestValueWins)),
         (
             InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::NullableString(
                 current,
@@ -1027,6 +1089,17 @@ where
             operation_id,
         )
         .map(|value| value.map(OperationValue::LatestValueWins)),
+        (
+            InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::NullableDocRef(
+                current,
+            )),
+            super::super::super::public_api::FieldTargetValue::NullableBasic(target),
+        ) => build_lww_operation(
+            current,
+            decode_optional_doc_ref(target).map_err(operation_invalid_value)?,
+            operation_id,
+        )
+        .map(|value| value.map(OperationValue::LatestValueWins)),
         (
             InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::NullableStringArray(
                 current,
@@ -1126,6 +1199,17 @@ where
             operation_id,
         )
         .map(|value| value.map(OperationValue::LatestValueWins)),
+        (
+            InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::NullableDocRefArray(
+                current,
+            )),
+            super::super::super::public_api::FieldTargetValue::NullableBasic(target),
+        ) => build_lww_operation(
+            current,
+            decode_optional_doc_ref_array(target).map_err(operation_invalid_value)?,
+            operation_id,
+        )
+        .map(|value| value.map(OperationValue::LatestValueWins)),
         (
             InMemoryFieldState::LinearString(current),
             super::super::super::public_api::FieldTargetValue::String(target),
@@ -1307,6 +1391,9 @@ where
         LinearListState::Timestamp(current) => {
             build_linear_list_op!(current, target, decode_list_timestamp)
         }
+        LinearListState::DocRef(current) => {
+            build_linear_list_op!(current, target, decode_list_doc_ref)
+        }
     }
 }
 
@@ -1327,6 +1414,7 @@ fn map_data_operation_value<Id, InputValue, OutputValue, E>(
             value: map_value(value)?,
         }),
         DataOperation::Delete { start, end } => Ok(DataOperation::Delete { start, end }),
+        DataOperation::DeleteRanges { ranges } => Ok(DataOperation::DeleteRanges { ranges }),
     }
 }
 
@@ -1413,6 +1501,10 @@ where
             InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::Timestamp(current)),
             OperationValue::LatestValueWins(operation),
         ) => apply_lww_operation!(current, operation, decode_required_timestamp),
+        (
+            InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::DocRef(current)),
+            OperationValue::LatestValueWins(operation),
+        ) => apply_lww_operation!(current, operation, decode_required_doc_ref),
         (
             InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::StringArray(current)),
             OperationValue::LatestValueWins(operation),
@@ -1451,6 +1543,10 @@ where
             )),
             OperationValue::LatestValueWins(operation),
         ) => apply_lww_operation!(current, operation, decode_required_timestamp_array),
+        (
+            InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::DocRefArray(current)),
+            OperationValue::LatestValueWins(operation),
+        ) => apply_lww_operation!(current, operation, decode_required_doc_ref_array),
         (
             InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::NullableString(
                 current,
@@ -1495,6 +1591,12 @@ where
             )),
             OperationValue::LatestValueWins(operation),
         ) => apply_lww_operation!(current, operation, decode_optional_timestamp),
+        (
+            InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::NullableDocRef(
+                current,
+            )),
+            OperationValue::LatestValueWins(operation),
+        ) => apply_lww_operation!(current, operation, decode_optional_doc_ref),
         (
             InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::NullableStringArray(
                 current,
@@ -1549,6 +1651,12 @@ where
             ),
             OperationValue::LatestValueWins(operation),
         ) => apply_lww_operation!(current, operation, decode_optional_timestamp_array),
+        (
+            InMemoryFieldState::LatestValueWins(LinearLatestValueWinsState::NullableDocRefArray(
+                current,
+            )),
+            OperationValue::LatestValueWins(operation),
+        ) => apply_lww_operation!(current, operation, decode_optional_doc_ref_array),
         (InMemoryFieldState::LinearString(current), OperationValue::LinearString(operations)) => {
             for operation in operations {
                 if current.apply_operation(operation).is_err() {
@@ -1596,6 +1704,10 @@ where
             InMemoryFieldState::LinearList(LinearListState::Timestamp(current)),
             OperationValue::LinearList(operations),
         ) => apply_list_operations!(current, operations, decode_list_timestamp),
+        (
+            InMemoryFieldState::LinearList(LinearListState::DocRef(current)),
+            OperationValue::LinearList(operations),
+        ) => apply_list_operations!(current, operations, decode_list_doc_ref),
         (
             InMemoryFieldState::MonotonicCounter(current),
             OperationValue::MonotonicCounterIncrement(delta),
@@ -224,6 +224,13 @@ where
             ))
             .map(LinearLatestValueWinsState::Timestamp)
         }
+        NullableBasicDataType::NonNull(BasicDataType::Primitive(PrimitiveType::DocRef)) => {
+            LinearLatestValueWins::from_snapshot_nodes(map_snapshot_nodes_value(
+                nodes,
+                decode_required_doc_ref,
+            ))
+            .map(LinearLatestValueWinsState::DocRef)
+        }
         NullableBasicDataType::NonNull(BasicDataType::Array(array_type)) => {
             match array_type.element_type {
                 PrimitiveType::String => LinearLatestValueWins::from_snapshot_nodes(
@@ -262,6 +269,10 @@ where
                     map_snapshot_nodes_value(nodes, decode_required_timestamp_array),
                 )
                 .map(LinearLatestValueWinsState::TimestampArray),
+                PrimitiveType::DocRef => LinearLatestValueWins::from_snapshot_nodes(
+                    map_snapshot_nodes_value(nodes, decode_required_doc_ref_array),
+                )
+                .map(LinearLatestValueWinsState::DocRefArray),
             }
         }
         NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::String)) => {
@@ -327,6 +338,13 @@ where
             ))
             .map(LinearLatestValueWinsState::NullableTimestamp)
         }
+        NullableBasicDataType::Nullable(BasicDataType::Primitive(PrimitiveType::DocRef)) => {
+            LinearLatestValueWins::from_snapshot_nodes(map_snapshot_nodes_value(
+                nodes,
+                decode_optional_doc_ref,
+            ))
+            .map(LinearLatestValueWinsState::NullableDocRef)
+        }
         NullableBasicDataType::Nullable(BasicDataType::Array(array_type)) => {
             match array_type.element_type {
                 PrimitiveType::String => LinearLatestValueWins::from_snapshot_nodes(
@@ -365,6 +383,10 @@ where
                     map_snapshot_nodes_value(nodes, decode_optional_timestamp_array),
                 )
                 .map(LinearLatestValueWinsState::NullableTimestampArray),
+                PrimitiveType::DocRef => LinearLatestValueWins::from_snapshot_nodes(
+                    map_snapshot_nodes_value(nodes, decode_optional_doc_ref_array),
+                )
+                .map(LinearLatestValueWinsState::NullableDocRefArray),
             }
         }
     }
@@ -421,6 +443,10 @@ where
             LinearList::from_snapshot_nodes(map_snapshot_nodes_value(nodes, decode_list_timestamp))
                 .map(LinearListState::Timestamp)
         }
+        PrimitiveType::DocRef => {
+            LinearList::from_snapshot_nodes(map_snapshot_nodes_value(nodes, decode_list_doc_ref))
+                .map(LinearListState::DocRef)
+        }
     }
 }
 
@@ -534,6 +560,12 @@ define_nullable_basic_converters!(
     UnixTimestamp,
     BasicValue::Primitive(PrimitiveValue::Timestamp(value)) => value
 );
+define_nullable_basic_converters!(
+    decode_required_doc_ref,
+    decode_optional_doc_ref,
+    DocRef,
+    BasicValue::Primitive(PrimitiveValue::DocRef(value)) => value
+);
 define_nullable_basic_converters!(
     decode_required_string_array,
     decode_optional_string_array,
@@ -588,6 +620,12 @@ define_nullable_basic_converters!(
     Vec<UnixTimestamp>,
     BasicValue::Array(PrimitiveValueArray::Timestamp(value)) => value
 );
+define_nullable_basic_converters!(
+    decode_required_doc_ref_array,
+    decode_optional_doc_ref_array,
+    Vec<DocRef>,
+    BasicValue::Array(PrimitiveValueArray::DocRef(value)) => value
+);
 
 pub(super) fn decode_list_string(
     value: PrimitiveValueArray,
@@ -684,3 +722,14 @@ pub(super) fn decode_list_timestamp(
         }),
     }
 }
+pub(super) fn decode_list_doc_ref(
+    value: PrimitiveValueArray,
+) -> Result<Vec<DocRef>, DataModelValueError> {
+    match value {
+        PrimitiveValueArray::DocRef(value) => Ok(value),
+        actual => Err(DataModelValueError::PrimitiveTypeMismatch {
+            expected: PrimitiveType::DocRef,
+            actual: actual.primitive_type(),
+        }),
+    }
+}
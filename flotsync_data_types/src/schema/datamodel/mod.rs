@@ -17,6 +17,7 @@ use super::{
     Schema,
     ValueType,
     values::{
+        DocRef,
         NullablePrimitiveValue,
         NullablePrimitiveValueArray,
         NullablePrimitiveValueRef,
@@ -129,6 +130,7 @@ pub enum PrimitiveValueArrayRef<'a> {
     Binary(&'a [Vec<u8>]),
     Date(&'a [NaiveDate]),
     Timestamp(&'a [UnixTimestamp]),
+    DocRef(&'a [DocRef]),
 }
 impl PrimitiveValueArrayRef<'_> {
     #[must_use]
@@ -143,6 +145,7 @@ impl PrimitiveValueArrayRef<'_> {
             Self::Binary(_) => PrimitiveType::Binary,
             Self::Date(_) => PrimitiveType::Date,
             Self::Timestamp(_) => PrimitiveType::Timestamp,
+            Self::DocRef(_) => PrimitiveType::DocRef,
         }
     }
 
@@ -158,6 +161,7 @@ impl PrimitiveValueArrayRef<'_> {
             Self::Binary(values) => PrimitiveValueArray::Binary(values.to_vec()),
             Self::Date(values) => PrimitiveValueArray::Date(values.to_vec()),
             Self::Timestamp(values) => PrimitiveValueArray::Timestamp(values.to_vec()),
+            Self::DocRef(values) => PrimitiveValueArray::DocRef(values.to_vec()),
         }
     }
 }
@@ -175,6 +179,7 @@ impl PrimitiveValueArray {
             Self::Binary(values) => PrimitiveValueArrayRef::Binary(values.as_slice()),
             Self::Date(values) => PrimitiveValueArrayRef::Date(values.as_slice()),
             Self::Timestamp(values) => PrimitiveValueArrayRef::Timestamp(values.as_slice()),
+            Self::DocRef(values) => PrimitiveValueArrayRef::DocRef(values.as_slice()),
         }
     }
 
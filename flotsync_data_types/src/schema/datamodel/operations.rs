@@ -18,6 +18,7 @@ use super::{
 use crate::{
     DataOperation,
     IdWithIndex,
+    IntegrityError,
     ProjectedFieldValue,
     RowValueRead,
     any_data::UpdateOperation,
@@ -184,6 +185,34 @@ impl<'a, ChangeId> RowStateSnapshot<'a, ChangeId> {
         }
     }
 
+    /// Validate the internal CRDT structure of every field in this snapshot.
+    ///
+    /// This is primarily useful after loading a snapshot from storage or other
+    /// untrusted input.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first field whose state fails validation.
+    pub fn validate_integrity(&self) -> Result<(), RowIntegrityError>
+    where
+        ChangeId: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    {
+        let validate_field = |field_name: &str, field_value: &InMemoryFieldState<ChangeId>| {
+            field_value
+                .validate_integrity()
+                .context(RowIntegritySnafu { field_name })
+        };
+        match &self.repr {
+            RowStateSnapshotRepr::BorrowedInMemory { field_names, row } => field_names
+                .iter()
+                .zip(row.fields.iter())
+                .try_for_each(|(field_name, field_value)| validate_field(field_name, field_value)),
+            RowStateSnapshotRepr::Owned { fields } => fields
+                .iter()
+                .try_for_each(|(field_name, field_value)| validate_field(field_name, field_value)),
+        }
+    }
+
     /// # Errors
     ///
     /// See `RowStateSnapshotEncodeError<V::Error>` for failure conditions.
@@ -297,6 +326,14 @@ pub struct RowRecord<'a, RowId, ChangeId> {
     pub tombstoned: bool,
 }
 
+/// One field of a row snapshot failed CRDT integrity validation.
+#[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+#[snafu(display("Field '{field_name}' failed integrity validation."))]
+pub struct RowIntegrityError {
+    pub field_name: String,
+    pub source: IntegrityError,
+}
+
 #[derive(Debug, Snafu)]
 pub enum RowStateSnapshotEncodeError<E>
 where
@@ -475,7 +512,7 @@ fn validate_linear_operation_batch<Id, Value>(
     for value in values {
         match value {
             DataOperation::Insert { value, .. } => validate_insert(value)?,
-            DataOperation::Delete { .. } => {}
+            DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => {}
         }
     }
 
@@ -20,6 +20,7 @@ use crate::{
             LinearLatestValueWinsState,
             LinearListState,
         },
+        values::DocRef,
     },
     snapshot::{SnapshotHeader, SnapshotNodeRef, SnapshotSink},
     text::LinearString,
@@ -88,6 +89,7 @@ fn primitive_type_tag(value_type: PrimitiveType) -> u8 {
         PrimitiveType::Binary => 6,
         PrimitiveType::Date => 7,
         PrimitiveType::Timestamp => 8,
+        PrimitiveType::DocRef => 9,
     }
 }
 
@@ -102,6 +104,7 @@ fn primitive_type_from_tag(tag: u8) -> Result<PrimitiveType, String> {
         6 => Ok(PrimitiveType::Binary),
         7 => Ok(PrimitiveType::Date),
         8 => Ok(PrimitiveType::Timestamp),
+        9 => Ok(PrimitiveType::DocRef),
         _ => Err(format!("unknown primitive type tag {tag}")),
     }
 }
@@ -156,6 +159,51 @@ fn write_primitive_payload(
             target.put_i64_le(value);
             Ok(())
         }
+        PrimitiveValueRef::DocRef(value) => {
+            write_doc_ref(target, value);
+            Ok(())
+        }
+    }
+}
+
+fn write_doc_ref(target: &mut BytesMut, value: &DocRef) {
+    target.put_slice(value.document_id.as_bytes());
+    match &value.anchor {
+        Some(anchor) => {
+            target.put_u8(1);
+            target.put_u64_le(anchor.id.version);
+            target.put_u32_le(anchor.id.node_index);
+            target.put_u32_le(anchor.index);
+        }
+        None => target.put_u8(0),
+    }
+}
+
+fn read_doc_ref(input: &mut Bytes) -> Result<DocRef, String> {
+    if input.remaining() < 16 {
+        return Err("unexpected end of payload".to_owned());
+    }
+    let mut document_id = [0u8; 16];
+    input.copy_to_slice(&mut document_id);
+    let document_id = uuid::Uuid::from_bytes(document_id);
+    match snapshot_bytes::read_u8(input)? {
+        0 => Ok(DocRef::document(document_id)),
+        1 => {
+            let version = read_u64(input)?;
+            let node_index = snapshot_bytes::read_u32(input)?;
+            let index = snapshot_bytes::read_u32(input)?;
+            Ok(DocRef::anchored(
+                document_id,
+                IdWithIndex {
+                    id: flotsync_core::versions::UpdateId {
+                        version,
+                        node_index,
+                    },
+                    index,
+                },
+            ))
+        }
+        _ => Err("invalid doc ref anchor encoding".to_owned()),
     }
 }
 
@@ -228,6 +276,7 @@ fn read_primitive_payload(
                 .ok_or_else(|| "invalid date encoding".to_owned())
         }
         PrimitiveType::Timestamp => read_i64(input).map(PrimitiveValue::Timestamp),
+        PrimitiveType::DocRef => read_doc_ref(input).map(PrimitiveValue::DocRef),
     }
 }
 
@@ -304,6 +353,13 @@ fn write_primitive_array(
                 target.put_i64_le(*value);
             }
         }
+        PrimitiveValueArrayRef::DocRef(values) => {
+            target
+                .put_u32_le(u32::try_from(values.len()).map_err(|_| "array too large".to_owned())?);
+            for value in values {
+                write_doc_ref(target, value);
+            }
+        }
     }
     Ok(())
 }
@@ -388,6 +444,13 @@ fn read_primitive_array(input: &mut Bytes) -> Result<PrimitiveValueArray, String
             }
             Ok(PrimitiveValueArray::Timestamp(values))
         }
+        PrimitiveType::DocRef => {
+            let mut values = Vec::with_capacity(len);
+            for _ in 0..len {
+                values.push(read_doc_ref(input)?);
+            }
+            Ok(PrimitiveValueArray::DocRef(values))
+        }
     }
 }
 
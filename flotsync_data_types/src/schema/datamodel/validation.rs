@@ -258,6 +258,9 @@ fn finite_state_contains_non_null(
         (PrimitiveValueArray::Timestamp(values), PrimitiveValueRef::Timestamp(value)) => {
             values.contains(value)
         }
+        (PrimitiveValueArray::DocRef(values), PrimitiveValueRef::DocRef(value)) => {
+            values.contains(value)
+        }
         _ => false,
     }
 }
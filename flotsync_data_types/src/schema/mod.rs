@@ -3,12 +3,14 @@
 //! concurrent modification.
 use std::{borrow::Cow, collections::HashMap, fmt, ops::Index};
 
-use crate::{FieldStateReadExt, FieldValueReadExt};
+use crate::{FieldStateReadExt, FieldValueReadExt, sealed::Sealed};
 
 pub mod datamodel;
 mod public_api;
+mod semantics;
 pub mod values;
 pub use public_api::*;
+pub use semantics::*;
 pub use values::{NULL, OrderedValue, OrderedValueError};
 
 /// A schema a collection of named, and typed columns.
@@ -242,6 +244,8 @@ impl Field {
     }
 }
 
+impl Sealed for Field {}
+
 impl<OperationId> FieldStateReadExt<OperationId> for Field {
     fn get_from_row<'a, R>(&self, row: &'a R) -> &'a crate::InMemoryFieldState<OperationId>
     where
@@ -525,6 +529,8 @@ pub enum PrimitiveType {
     Date,
     /// Millisecond precision UNIX timestamp. UTC relative to the UNIX epoch.
     Timestamp,
+    /// A reference to another document, optionally anchored at one of its nodes.
+    DocRef,
 }
 
 impl fmt::Display for PrimitiveType {
@@ -539,6 +545,7 @@ impl fmt::Display for PrimitiveType {
             Self::Binary => f.write_str("BINARY"),
             Self::Date => f.write_str("DATE"),
             Self::Timestamp => f.write_str("TIMESTAMP"),
+            Self::DocRef => f.write_str("DOCREF"),
         }
     }
 }
@@ -382,6 +382,10 @@ fn validate_primitive_value(
             PrimitiveValueRef::Date(_) => Ok(()),
             actual => primitive_ref_mismatch(field_name, primitive_type_name(value_type), actual),
         },
+        PrimitiveType::DocRef => match value {
+            PrimitiveValueRef::DocRef(_) => Ok(()),
+            actual => primitive_ref_mismatch(field_name, primitive_type_name(value_type), actual),
+        },
     }
 }
 
@@ -416,6 +420,10 @@ fn validate_primitive_array_value(
             PrimitiveValueArrayRef::Date(_) => Ok(()),
             actual => array_ref_mismatch(field_name, value_type, actual),
         },
+        PrimitiveType::DocRef => match value {
+            PrimitiveValueArrayRef::DocRef(_) => Ok(()),
+            actual => array_ref_mismatch(field_name, value_type, actual),
+        },
     }
 }
 
@@ -581,6 +589,10 @@ fn convert_primitive_value(
             PrimitiveValue::Date(value) => Ok(PrimitiveValue::Date(value)),
             actual => mismatch(field_name, primitive_type_name(value_type), &actual),
         },
+        PrimitiveType::DocRef => match value {
+            PrimitiveValue::DocRef(value) => Ok(PrimitiveValue::DocRef(value)),
+            actual => mismatch(field_name, primitive_type_name(value_type), &actual),
+        },
         PrimitiveType::Timestamp => {
             Ok(PrimitiveValue::Timestamp(convert_primitive_integer::<i64>(
                 field_name,
@@ -629,6 +641,10 @@ fn convert_primitive_array_value(
             PrimitiveValueArray::Date(value) => Ok(PrimitiveValueArray::Date(value)),
             actual => array_mismatch(field_name, value_type, &actual),
         },
+        PrimitiveType::DocRef => match value {
+            PrimitiveValueArray::DocRef(value) => Ok(PrimitiveValueArray::DocRef(value)),
+            actual => array_mismatch(field_name, value_type, &actual),
+        },
         PrimitiveType::Timestamp => convert_integer_array::<i64, _>(
             field_name,
             value_type,
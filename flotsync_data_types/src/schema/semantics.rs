@@ -0,0 +1,116 @@
+//! Revisions of the conflict-resolution semantics that a [`Schema`] is replicated with.
+//!
+//! Every replica of a document must integrate concurrent operations in exactly the same way, or
+//! the replicas diverge without noticing. A fix to one of the integration algorithms therefore
+//! comes with a new [`CURRENT_SEMANTICS_REVISION`], which schemas opt into by recording it in
+//! their metadata with [`Schema::with_semantics_revision`]. Replicas refuse to merge operations
+//! into documents whose revision they do not implement, instead of merging them with different
+//! semantics.
+//!
+//! Schemas without a recorded revision were created before revisions existed and use revision
+//! `1`.
+
+use super::Schema;
+use snafu::prelude::*;
+use std::ops::RangeInclusive;
+
+/// Metadata key under which a [`Schema`] records its semantics revision.
+pub const SEMANTICS_REVISION_METADATA_KEY: &str = "flotsync.semantics_revision";
+
+/// Newest semantics revision implemented by this code.
+pub const CURRENT_SEMANTICS_REVISION: u32 = 1;
+
+/// Semantics revisions that this code can merge operations for.
+pub const SUPPORTED_SEMANTICS_REVISIONS: RangeInclusive<u32> = 1..=CURRENT_SEMANTICS_REVISION;
+
+/// Revision of schemas that do not record one.
+const IMPLICIT_SEMANTICS_REVISION: u32 = 1;
+
+/// The semantics revision of a [`Schema`] cannot be merged by this code.
+#[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+pub enum SemanticsRevisionError {
+    #[snafu(display(
+        "Schema semantics revision {revision} is not supported, only revisions {} to {} are.",
+        SUPPORTED_SEMANTICS_REVISIONS.start(),
+        SUPPORTED_SEMANTICS_REVISIONS.end()
+    ))]
+    UnsupportedRevision { revision: u32 },
+    #[snafu(display("Schema semantics revision '{value}' is not a number."))]
+    MalformedRevision { value: String },
+}
+
+impl Schema {
+    /// Record `revision` as the semantics revision of this schema.
+    #[must_use]
+    pub fn with_semantics_revision(mut self, revision: u32) -> Self {
+        self.metadata.insert(
+            SEMANTICS_REVISION_METADATA_KEY.to_owned(),
+            revision.to_string(),
+        );
+        self
+    }
+
+    /// The semantics revision recorded in this schema's metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SemanticsRevisionError::MalformedRevision`] if the recorded value is not a
+    /// number.
+    pub fn semantics_revision(&self) -> Result<u32, SemanticsRevisionError> {
+        let Some(value) = self.metadata.get(SEMANTICS_REVISION_METADATA_KEY) else {
+            return Ok(IMPLICIT_SEMANTICS_REVISION);
+        };
+        value.parse().ok().context(MalformedRevisionSnafu { value })
+    }
+
+    /// Check that operations on documents of this schema can be merged by this code.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SemanticsRevisionError`] if the recorded revision is malformed or not in
+    /// [`SUPPORTED_SEMANTICS_REVISIONS`].
+    pub fn check_semantics_revision(&self) -> Result<u32, SemanticsRevisionError> {
+        let revision = self.semantics_revision()?;
+        ensure!(
+            SUPPORTED_SEMANTICS_REVISIONS.contains(&revision),
+            UnsupportedRevisionSnafu { revision }
+        );
+        Ok(revision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Field;
+
+    #[test]
+    fn schemas_without_a_revision_use_the_first_one() {
+        let schema = Schema::from_fields([Field::linear_string("title")]);
+        assert_eq!(schema.semantics_revision(), Ok(1));
+        assert_eq!(schema.check_semantics_revision(), Ok(1));
+    }
+
+    #[test]
+    fn unsupported_and_malformed_revisions_are_refused() {
+        let schema = Schema::from_fields([Field::linear_string("title")])
+            .with_semantics_revision(CURRENT_SEMANTICS_REVISION + 1);
+        assert_eq!(
+            schema.check_semantics_revision(),
+            Err(SemanticsRevisionError::UnsupportedRevision {
+                revision: CURRENT_SEMANTICS_REVISION + 1,
+            })
+        );
+
+        let mut schema = Schema::empty();
+        schema
+            .metadata
+            .insert(SEMANTICS_REVISION_METADATA_KEY.to_owned(), "two".to_owned());
+        assert_eq!(
+            schema.check_semantics_revision(),
+            Err(SemanticsRevisionError::MalformedRevision {
+                value: "two".to_owned(),
+            })
+        );
+    }
+}
@@ -1,11 +1,60 @@
 use super::{NullablePrimitiveType, PrimitiveType};
+use crate::IdWithIndex;
 use chrono::NaiveDate;
+use flotsync_core::versions::UpdateId;
 use ordered_float::OrderedFloat;
 use snafu::prelude::*;
 use std::fmt;
+use uuid::Uuid;
 
 pub type UnixTimestamp = i64;
 
+/// A reference from one document to another, e.g. a wiki-style link between synced documents.
+///
+/// Documents are identified by their row key, which is unique across datasets.
+/// The optional `anchor` pins the reference to one node inside a linear field of the target
+/// document, such as a heading in a text. Since node ids are never reused, an anchor keeps pointing
+/// at the same content while the surrounding document is edited concurrently.
+///
+/// References are plain values: nothing stops the target from being deleted while references to
+/// it remain. Use the replication store's dangling-reference query to find those.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DocRef {
+    /// Row key of the referenced document.
+    pub document_id: Uuid,
+    /// Node inside the referenced document, if the reference points below document level.
+    pub anchor: Option<IdWithIndex<UpdateId>>,
+}
+impl DocRef {
+    /// Reference `document_id` as a whole.
+    #[must_use]
+    pub fn document(document_id: Uuid) -> Self {
+        Self {
+            document_id,
+            anchor: None,
+        }
+    }
+
+    /// Reference the node `anchor` inside `document_id`.
+    #[must_use]
+    pub fn anchored(document_id: Uuid, anchor: IdWithIndex<UpdateId>) -> Self {
+        Self {
+            document_id,
+            anchor: Some(anchor),
+        }
+    }
+}
+
+impl fmt::Display for DocRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "doc:{}", self.document_id)?;
+        if let Some(anchor) = &self.anchor {
+            write!(f, "#{}.{}", anchor.id, anchor.index)?;
+        }
+        Ok(())
+    }
+}
+
 /// Arrays over primitive values.
 ///
 /// Corresponds to the [[`super::PrimitiveType`]].
@@ -20,6 +69,7 @@ pub enum PrimitiveValueArray {
     Binary(Vec<Vec<u8>>),
     Date(Vec<chrono::NaiveDate>),
     Timestamp(Vec<UnixTimestamp>),
+    DocRef(Vec<DocRef>),
 }
 impl PrimitiveValueArray {
     #[must_use]
@@ -38,6 +88,7 @@ impl PrimitiveValueArray {
             Self::Binary(values) => values.len(),
             Self::Date(values) => values.len(),
             Self::Timestamp(values) => values.len(),
+            Self::DocRef(values) => values.len(),
         }
     }
 
@@ -81,6 +132,7 @@ impl_primitive_value_array_from_vec!(f32, Float, |values: Vec<f32>| values
 impl_primitive_value_array_from_vec!(bool, Boolean, |values: Vec<bool>| values);
 impl_primitive_value_array_from_vec!(Vec<u8>, Binary, |values: Vec<Vec<u8>>| values);
 impl_primitive_value_array_from_vec!(NaiveDate, Date, |values: Vec<NaiveDate>| values);
+impl_primitive_value_array_from_vec!(DocRef, DocRef, |values: Vec<DocRef>| values);
 
 /// Primitive values.
 ///
@@ -96,6 +148,7 @@ pub enum PrimitiveValue {
     Binary(Vec<u8>),
     Date(chrono::NaiveDate),
     Timestamp(UnixTimestamp),
+    DocRef(DocRef),
 }
 impl PrimitiveValue {
     #[must_use]
@@ -110,6 +163,7 @@ impl PrimitiveValue {
             Self::Binary(value) => PrimitiveValueRef::Binary(value.as_slice()),
             Self::Date(value) => PrimitiveValueRef::Date(*value),
             Self::Timestamp(value) => PrimitiveValueRef::Timestamp(*value),
+            Self::DocRef(value) => PrimitiveValueRef::DocRef(value),
         }
     }
 
@@ -227,6 +281,12 @@ impl From<NaiveDate> for PrimitiveValue {
     }
 }
 
+impl From<DocRef> for PrimitiveValue {
+    fn from(value: DocRef) -> Self {
+        Self::DocRef(value)
+    }
+}
+
 /// A borrowed primitive value.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum PrimitiveValueRef<'a> {
@@ -239,6 +299,7 @@ pub enum PrimitiveValueRef<'a> {
     Binary(&'a [u8]),
     Date(NaiveDate),
     Timestamp(UnixTimestamp),
+    DocRef(&'a DocRef),
 }
 impl PrimitiveValueRef<'_> {
     #[must_use]
@@ -257,6 +318,7 @@ impl PrimitiveValueRef<'_> {
             Self::Binary(_) => PrimitiveType::Binary,
             Self::Date(_) => PrimitiveType::Date,
             Self::Timestamp(_) => PrimitiveType::Timestamp,
+            Self::DocRef(_) => PrimitiveType::DocRef,
         }
     }
 
@@ -277,6 +339,7 @@ impl PrimitiveValueRef<'_> {
             Self::Binary(value) => PrimitiveValue::Binary((*value).to_vec()),
             Self::Date(value) => PrimitiveValue::Date(*value),
             Self::Timestamp(value) => PrimitiveValue::Timestamp(*value),
+            Self::DocRef(value) => PrimitiveValue::DocRef((*value).clone()),
         }
     }
 }
@@ -739,6 +802,15 @@ fn primitive_values_into_array(
                 })
                 .collect(),
         ),
+        PrimitiveType::DocRef => PrimitiveValueArray::DocRef(
+            values
+                .into_iter()
+                .map(|value| match value {
+                    PrimitiveValue::DocRef(value) => value,
+                    _ => unreachable!("primitive type already validated"),
+                })
+                .collect(),
+        ),
     }
 }
 
@@ -777,6 +849,9 @@ fn fmt_primitive_value_from_array(
             PrimitiveValueArray::Timestamp(values) => {
                 fmt_primitive_value(&PrimitiveValue::Timestamp(values[index]), f)
             }
+            PrimitiveValueArray::DocRef(values) => {
+                fmt_primitive_value(&PrimitiveValue::DocRef(values[index].clone()), f)
+            }
         },
     }
 }
@@ -796,6 +871,7 @@ fn fmt_primitive_value(value: &PrimitiveValue, f: &mut fmt::Formatter<'_>) -> fm
         PrimitiveValue::Binary(value) => write!(f, "{value:?}"),
         PrimitiveValue::Date(value) => write!(f, "{value}"),
         PrimitiveValue::Timestamp(value) => write!(f, "{value}"),
+        PrimitiveValue::DocRef(value) => write!(f, "{value}"),
     }
 }
 
@@ -0,0 +1,69 @@
+//! [`proptest::arbitrary::Arbitrary`] implementations for the operation types of this crate.
+
+use crate::linear_data::{DataOperation, IdWithIndex};
+use proptest::prelude::*;
+
+impl<Id> Arbitrary for IdWithIndex<Id>
+where
+    Id: Arbitrary + 'static,
+{
+    type Parameters = Id::Parameters;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        (any_with::<Id>(args), any::<u32>())
+            .prop_map(|(id, index)| IdWithIndex { id, index })
+            .boxed()
+    }
+}
+
+impl<Id, Value> Arbitrary for DataOperation<Id, Value>
+where
+    Id: Arbitrary + 'static,
+    Value: Arbitrary + 'static,
+{
+    type Parameters = (Id::Parameters, Value::Parameters);
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((id_args, value_args): Self::Parameters) -> Self::Strategy {
+        let id = any_with::<Id>(id_args).boxed();
+        prop_oneof![
+            (
+                id.clone(),
+                id.clone(),
+                id.clone(),
+                any_with::<Value>(value_args)
+            )
+                .prop_map(|(id, pred, succ, value)| DataOperation::Insert {
+                    id,
+                    pred,
+                    succ,
+                    value,
+                }),
+            (id.clone(), proptest::option::of(id))
+                .prop_map(|(start, end)| DataOperation::Delete { start, end }),
+        ]
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn arbitrary_operations_map_values(operation in any::<DataOperation<IdWithIndex<u32>, u8>>()) {
+            let is_insert = matches!(operation, DataOperation::Insert { .. });
+            let mapped = operation.clone().map_value(u16::from);
+            prop_assert_eq!(matches!(mapped, DataOperation::Insert { .. }), is_insert);
+            if let (
+                DataOperation::Insert { value, .. },
+                DataOperation::Insert { value: mapped_value, .. },
+            ) = (operation, mapped)
+            {
+                prop_assert_eq!(u16::from(value), mapped_value);
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Deterministic id generators for building operations in tests.
+
+use crate::linear_data::IdWithIndex;
+
+/// Hands out consecutive `u32` ids, starting at 0 unless created with
+/// [`without_ids`](Self::without_ids).
+#[derive(Clone, Debug)]
+pub struct TestIdGenerator {
+    current: Option<u32>,
+}
+impl TestIdGenerator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self { current: Some(0) }
+    }
+
+    /// Start after the largest of `existing_ids`, so no generated id collides with them.
+    #[must_use]
+    pub fn without_ids(existing_ids: impl Iterator<Item = u32>) -> Self {
+        let max_id = existing_ids.max().unwrap_or(0);
+        Self {
+            current: Some(max_id + 1),
+        }
+    }
+
+    pub fn next_with_zero_index(&mut self) -> Option<IdWithIndex<u32>> {
+        self.next().map(IdWithIndex::zero)
+    }
+}
+impl Default for TestIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Iterator for TestIdGenerator {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.current.take();
+        if let Some(current) = current {
+            self.current = current.checked_add(1);
+        }
+        current
+    }
+}
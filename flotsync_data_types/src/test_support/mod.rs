@@ -2,5 +2,14 @@
 //!
 //! This module is available when running this crate's own tests and to third-party
 //! crates that enable the `test-support` feature in their dev-dependencies.
+//!
+//! The feature also implements [`proptest::arbitrary::Arbitrary`] for [`IdWithIndex`] and
+//! [`DataOperation`], so downstream tests can write `any::<DataOperation<_, _>>()`.
+//!
+//! [`IdWithIndex`]: crate::linear_data::IdWithIndex
+//! [`DataOperation`]: crate::linear_data::DataOperation
 
+mod arbitrary;
+pub mod ids;
+pub mod schedules;
 pub mod schema_operations;
@@ -0,0 +1,65 @@
+//! Delivery schedules for checking convergence of concurrent operations.
+
+/// Enumerates all schedules that interleave `num_writers` ordered local operation streams.
+///
+/// Each writer appears `per_writer_count` times, and the generated schedules preserve each
+/// writer's local order while varying only the cross-writer interleaving. The number of
+/// schedules grows factorially, so keep both arguments small.
+#[must_use]
+pub fn interleavings_with_local_order(
+    per_writer_count: usize,
+    num_writers: usize,
+) -> Vec<Vec<usize>> {
+    fn dfs(
+        per_writer_count: usize,
+        total_steps: usize,
+        current: &mut Vec<usize>,
+        next_for_writer: &mut [usize],
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        if current.len() == total_steps {
+            out.push(current.clone());
+            return;
+        }
+
+        for writer in 0..next_for_writer.len() {
+            if next_for_writer[writer] < per_writer_count {
+                next_for_writer[writer] += 1;
+                current.push(writer);
+                dfs(per_writer_count, total_steps, current, next_for_writer, out);
+                current.pop();
+                next_for_writer[writer] -= 1;
+            }
+        }
+    }
+
+    let total_steps = per_writer_count * num_writers;
+    let mut out = Vec::new();
+    let mut current = Vec::with_capacity(total_steps);
+    let mut next_for_writer = vec![0usize; num_writers];
+
+    dfs(
+        per_writer_count,
+        total_steps,
+        &mut current,
+        &mut next_for_writer,
+        &mut out,
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleavings_keep_local_order() {
+        let schedules = interleavings_with_local_order(2, 2);
+        // 4! / (2! * 2!)
+        assert_eq!(schedules.len(), 6);
+        for schedule in schedules {
+            assert_eq!(schedule.iter().filter(|&&writer| writer == 0).count(), 2);
+            assert_eq!(schedule.iter().filter(|&&writer| writer == 1).count(), 2);
+        }
+    }
+}
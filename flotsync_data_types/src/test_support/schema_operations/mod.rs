@@ -21,6 +21,7 @@ use crate::{
             SchemaOperation,
         },
         values::{
+            DocRef,
             NullablePrimitiveValue,
             NullablePrimitiveValueArray,
             PrimitiveValue,
@@ -29,6 +30,7 @@ use crate::{
     },
 };
 use chrono::NaiveDate;
+use flotsync_core::versions::UpdateId;
 use ordered_float::OrderedFloat;
 use snafu::prelude::*;
 use std::{borrow::Cow, collections::HashMap};
@@ -40,16 +42,16 @@ pub use operations::{exhaustive_schema_operation, exhaustive_schema_operations};
 pub use schema::exhaustive_schema;
 
 /// The number of fields in [`exhaustive_schema`].
-pub const EXHAUSTIVE_SCHEMA_FIELD_COUNT: usize = 84;
+pub const EXHAUSTIVE_SCHEMA_FIELD_COUNT: usize = 93;
 
 /// The number of single-field operations returned by [`exhaustive_schema_operations`].
-pub const EXHAUSTIVE_SCHEMA_OPERATION_COUNT: usize = 88;
+pub const EXHAUSTIVE_SCHEMA_OPERATION_COUNT: usize = 97;
 
 /// The minimum number of IDs required by [`exhaustive_schema_operation`].
-pub const EXHAUSTIVE_SCHEMA_OPERATION_MIN_ID_COUNT: usize = 139;
+pub const EXHAUSTIVE_SCHEMA_OPERATION_MIN_ID_COUNT: usize = 154;
 
 /// The minimum number of IDs required by [`exhaustive_schema_operations`].
-pub const EXHAUSTIVE_SCHEMA_OPERATIONS_MIN_ID_COUNT: usize = 231;
+pub const EXHAUSTIVE_SCHEMA_OPERATIONS_MIN_ID_COUNT: usize = 255;
 
 /// Errors constructing exhaustive example operations.
 #[derive(Debug, Snafu)]
@@ -243,6 +245,7 @@ fn primitive_value(primitive_type: PrimitiveType) -> PrimitiveValue {
         PrimitiveType::Binary => PrimitiveValue::Binary(vec![0xAA, 0xBB]),
         PrimitiveType::Date => PrimitiveValue::Date(example_date(2025, 1, 15)),
         PrimitiveType::Timestamp => PrimitiveValue::Timestamp(1_736_944_200_000),
+        PrimitiveType::DocRef => PrimitiveValue::DocRef(example_doc_ref(1, Some(3))),
     }
 }
 
@@ -269,6 +272,9 @@ fn primitive_value_array(primitive_type: PrimitiveType) -> PrimitiveValueArray {
         PrimitiveType::Timestamp => {
             PrimitiveValueArray::Timestamp(vec![1_700_000_000_000, 1_710_000_000_000])
         }
+        PrimitiveType::DocRef => {
+            PrimitiveValueArray::DocRef(vec![example_doc_ref(1, None), example_doc_ref(2, Some(0))])
+        }
     }
 }
 
@@ -304,6 +310,11 @@ fn finite_state_values(
             1_710_000_000_000,
             1_720_000_000_000,
         ]),
+        PrimitiveType::DocRef => PrimitiveValueArray::DocRef(vec![
+            example_doc_ref(1, None),
+            example_doc_ref(2, None),
+            example_doc_ref(2, Some(5)),
+        ]),
     };
 
     if nullable {
@@ -327,6 +338,7 @@ fn finite_state_value(primitive_type: PrimitiveType) -> PrimitiveValue {
         PrimitiveType::Binary => PrimitiveValue::Binary(vec![0x20]),
         PrimitiveType::Date => PrimitiveValue::Date(example_date(2025, 1, 15)),
         PrimitiveType::Timestamp => PrimitiveValue::Timestamp(1_710_000_000_000),
+        PrimitiveType::DocRef => PrimitiveValue::DocRef(example_doc_ref(2, None)),
     }
 }
 
@@ -382,10 +394,11 @@ fn primitive_label(primitive_type: PrimitiveType) -> &'static str {
         PrimitiveType::Binary => "binary",
         PrimitiveType::Date => "date",
         PrimitiveType::Timestamp => "timestamp",
+        PrimitiveType::DocRef => "doc_ref",
     }
 }
 
-fn primitive_types() -> [PrimitiveType; 9] {
+fn primitive_types() -> [PrimitiveType; 10] {
     [
         PrimitiveType::String,
         PrimitiveType::UInt,
@@ -396,6 +409,7 @@ fn primitive_types() -> [PrimitiveType; 9] {
         PrimitiveType::Binary,
         PrimitiveType::Date,
         PrimitiveType::Timestamp,
+        PrimitiveType::DocRef,
     ]
 }
 
@@ -403,6 +417,23 @@ fn example_date(year: i32, month: u32, day: u32) -> NaiveDate {
     NaiveDate::from_ymd_opt(year, month, day).expect("example dates are valid")
 }
 
+fn example_doc_ref(document: u128, anchor_index: Option<u32>) -> DocRef {
+    let document_id = uuid::Uuid::from_u128(document);
+    match anchor_index {
+        Some(index) => DocRef::anchored(
+            document_id,
+            IdWithIndex {
+                id: UpdateId {
+                    version: 4,
+                    node_index: 1,
+                },
+                index,
+            },
+        ),
+        None => DocRef::document(document_id),
+    }
+}
+
 struct IdSource<Ids> {
     ids: Ids,
     builder: &'static str,
@@ -15,6 +15,7 @@ enum PrimitiveCoverageKey {
     Binary,
     Date,
     Timestamp,
+    DocRef,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -100,6 +101,7 @@ fn primitive_coverage_key(primitive_type: PrimitiveType) -> PrimitiveCoverageKey
         PrimitiveType::Binary => PrimitiveCoverageKey::Binary,
         PrimitiveType::Date => PrimitiveCoverageKey::Date,
         PrimitiveType::Timestamp => PrimitiveCoverageKey::Timestamp,
+        PrimitiveType::DocRef => PrimitiveCoverageKey::DocRef,
     }
 }
 
@@ -233,7 +235,9 @@ fn operation_coverage_key<Id>(
         (ReplicatedDataType::LinearString, OperationValue::LinearString(values)) => {
             match classify_linear_operation_batch(values) {
                 DataOperation::Insert { .. } => OperationCoverageKey::LinearStringInsert,
-                DataOperation::Delete { .. } => OperationCoverageKey::LinearStringDelete,
+                DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => {
+                    OperationCoverageKey::LinearStringDelete
+                }
             }
         }
         (ReplicatedDataType::LinearList { value_type }, OperationValue::LinearList(values)) => {
@@ -241,7 +245,9 @@ fn operation_coverage_key<Id>(
                 DataOperation::Insert { .. } => OperationCoverageKey::LinearListInsert {
                     value_type: primitive_coverage_key(*value_type),
                 },
-                DataOperation::Delete { .. } => OperationCoverageKey::LinearListDelete,
+                DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => {
+                    OperationCoverageKey::LinearListDelete
+                }
             }
         }
         (
@@ -0,0 +1,685 @@
+//! Annotations such as comments or suggestions, anchored to ranges of a [`LinearString`].
+//!
+//! An annotation refers to the ids of the first and last grapheme it covers, not to positions,
+//! so it follows its text through concurrent inserts and deletes. When annotated text is deleted
+//! the range shrinks to the graphemes that are still visible, and once all of it is gone the
+//! annotation is no longer resolved, see [`Annotations::resolve`]. For rendering, annotations
+//! such as formatting marks can be read as styled runs of text with
+//! [`Annotations::styled_segments`].
+//!
+//! Payloads are multi-value registers: an update supersedes every payload version its author
+//! had seen, and concurrent updates are resolved deterministically in favour of the highest id.
+//! Removing an annotation wins over concurrent updates.
+
+use super::{LinearString, fmt};
+use crate::{
+    IdWithIndex,
+    snapshot::{SnapshotHeader, SnapshotNodeRef, SnapshotSink},
+};
+use flotsync_utils::option_when;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    convert::Infallible,
+    hash::Hash,
+    ops::Range,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The graphemes an annotation is attached to, identified by their ids.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnnotationRange<Id> {
+    /// The id of the first annotated grapheme.
+    pub start: IdWithIndex<Id>,
+    /// The id of the last annotated grapheme.
+    pub end: IdWithIndex<Id>,
+}
+impl<Id> AnnotationRange<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    /// Anchor the visible graphemes at `range` in `text`.
+    ///
+    /// Returns `None` if `range` is empty or extends past the end of the visible text.
+    #[must_use]
+    pub fn from_visible_range(text: &LinearString<Id>, range: Range<usize>) -> Option<Self> {
+        if range.is_empty() {
+            return None;
+        }
+        let layout = TextLayout::of(text);
+        Some(Self {
+            start: layout.id_at(range.start)?,
+            end: layout.id_at(range.end - 1)?,
+        })
+    }
+}
+
+/// Replicated changes to [`Annotations`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnnotationOperation<Id, T> {
+    /// Attach a new annotation with id `id` and its initial payload.
+    Add {
+        id: Id,
+        range: AnnotationRange<Id>,
+        payload: T,
+    },
+    /// Replace the payload of `annotation` with a new payload version `id`.
+    Update {
+        id: Id,
+        annotation: Id,
+        /// The payload versions the author had seen as current.
+        supersedes: Vec<Id>,
+        payload: T,
+    },
+    /// Remove `annotation`.
+    Remove { annotation: Id },
+}
+
+/// A set of annotations on the ranges of one [`LinearString`].
+///
+/// ## Guarantees
+///
+/// - **Convergence:** given the same set of operations, all replicas agree on the annotations,
+///   their ranges and their payloads, independent of delivery order.
+/// - Operations must be applied in causal order. An update or remove is rejected until the
+///   annotation it refers to, and every payload version it supersedes, has been applied.
+///
+/// ## Identifier requirements
+///
+/// `Id` must uniquely identify each added annotation and each payload update, and its total
+/// order decides between concurrent payload updates. The same id space as the annotated
+/// [`LinearString`] can be used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotations<Id, T> {
+    entries: BTreeMap<Id, AnnotationEntry<Id, T>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct AnnotationEntry<Id, T> {
+    range: AnnotationRange<Id>,
+    removed: bool,
+    /// Payload versions that no other known version supersedes, ordered by id.
+    heads: Vec<(Id, T)>,
+    /// Ids of all payload versions ever applied, including superseded ones.
+    versions: BTreeSet<Id>,
+}
+impl<Id, T> AnnotationEntry<Id, T>
+where
+    Id: Ord,
+{
+    /// The winning payload among the concurrent heads.
+    fn payload(&self) -> Option<&T> {
+        if self.removed {
+            return None;
+        }
+        self.heads.last().map(|(_, payload)| payload)
+    }
+}
+
+impl<Id, T> Default for Annotations<Id, T> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Id, T> Annotations<Id, T>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    T: Clone + fmt::Debug,
+{
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of annotations that have not been removed.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.values().filter(|entry| !entry.removed).count()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The current payload of `annotation`, or `None` if it is unknown or was removed.
+    #[must_use]
+    pub fn get(&self, annotation: &Id) -> Option<&T> {
+        self.entries
+            .get(annotation)
+            .and_then(AnnotationEntry::payload)
+    }
+
+    /// The anchored range of `annotation`, or `None` if it is unknown or was removed.
+    #[must_use]
+    pub fn range(&self, annotation: &Id) -> Option<&AnnotationRange<Id>> {
+        self.entries
+            .get(annotation)
+            .filter(|entry| !entry.removed)
+            .map(|entry| &entry.range)
+    }
+
+    /// All concurrent payloads of `annotation`, from the losing to the winning one.
+    ///
+    /// This has more than one entry exactly when concurrent updates have not been superseded
+    /// by a later update yet.
+    pub fn concurrent_payloads(&self, annotation: &Id) -> impl Iterator<Item = &T> {
+        self.entries
+            .get(annotation)
+            .filter(|entry| !entry.removed)
+            .into_iter()
+            .flat_map(|entry| entry.heads.iter().map(|(_, payload)| payload))
+    }
+
+    /// All annotations that have not been removed, ordered by id.
+    pub fn iter(&self) -> impl Iterator<Item = (&Id, &AnnotationRange<Id>, &T)> {
+        self.entries
+            .iter()
+            .filter_map(|(id, entry)| entry.payload().map(|payload| (id, &entry.range, payload)))
+    }
+
+    /// Produce an operation that attaches a new annotation with `payload` to `range`.
+    #[must_use]
+    pub fn add_operation(
+        &self,
+        id: Id,
+        range: AnnotationRange<Id>,
+        payload: T,
+    ) -> AnnotationOperation<Id, T> {
+        AnnotationOperation::Add { id, range, payload }
+    }
+
+    /// Produce an operation that replaces the payload of `annotation` with `payload`.
+    ///
+    /// The update supersedes all payloads of `annotation` that are currently concurrent.
+    /// Returns `None` if `annotation` is unknown or was removed.
+    #[must_use]
+    pub fn update_operation(
+        &self,
+        id: Id,
+        annotation: Id,
+        payload: T,
+    ) -> Option<AnnotationOperation<Id, T>> {
+        let entry = self
+            .entries
+            .get(&annotation)
+            .filter(|entry| !entry.removed)?;
+        let supersedes = entry.heads.iter().map(|(id, _)| id.clone()).collect();
+        Some(AnnotationOperation::Update {
+            id,
+            annotation,
+            supersedes,
+            payload,
+        })
+    }
+
+    /// Produce an operation that removes `annotation`.
+    ///
+    /// Returns `None` if `annotation` is unknown or was already removed.
+    #[must_use]
+    pub fn remove_operation(&self, annotation: Id) -> Option<AnnotationOperation<Id, T>> {
+        self.entries
+            .get(&annotation)
+            .filter(|entry| !entry.removed)
+            .map(|_| AnnotationOperation::Remove { annotation })
+    }
+
+    /// Apply an operation received from some replica (including ourselves).
+    ///
+    /// Applying an operation that was already applied has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Returns the operation unchanged if it is not causally ready yet, i.e. it refers to an
+    /// annotation or a payload version that has not been applied.
+    pub fn apply_operation(
+        &mut self,
+        operation: AnnotationOperation<Id, T>,
+    ) -> Result<(), AnnotationOperation<Id, T>> {
+        match operation {
+            AnnotationOperation::Add { id, range, payload } => {
+                self.entries
+                    .entry(id.clone())
+                    .or_insert_with(|| AnnotationEntry {
+                        range,
+                        removed: false,
+                        versions: BTreeSet::from([id.clone()]),
+                        heads: vec![(id, payload)],
+                    });
+                Ok(())
+            }
+            AnnotationOperation::Update {
+                id,
+                annotation,
+                supersedes,
+                payload,
+            } => {
+                let Some(entry) = self.entries.get_mut(&annotation) else {
+                    return Err(AnnotationOperation::Update {
+                        id,
+                        annotation,
+                        supersedes,
+                        payload,
+                    });
+                };
+                if entry.versions.contains(&id) {
+                    return Ok(());
+                }
+                if !supersedes
+                    .iter()
+                    .all(|superseded| entry.versions.contains(superseded))
+                {
+                    return Err(AnnotationOperation::Update {
+                        id,
+                        annotation,
+                        supersedes,
+                        payload,
+                    });
+                }
+                entry.heads.retain(|(head, _)| !supersedes.contains(head));
+                let position = entry.heads.partition_point(|(head, _)| *head < id);
+                entry.versions.insert(id.clone());
+                entry.heads.insert(position, (id, payload));
+                Ok(())
+            }
+            AnnotationOperation::Remove { annotation } => {
+                let Some(entry) = self.entries.get_mut(&annotation) else {
+                    return Err(AnnotationOperation::Remove { annotation });
+                };
+                entry.removed = true;
+                Ok(())
+            }
+        }
+    }
+
+    /// Resolve all annotations against the current state of `text`, ordered by position.
+    ///
+    /// Annotations whose graphemes have all been deleted, or whose anchors `text` has not
+    /// integrated yet, are left out.
+    #[must_use]
+    pub fn resolve(&self, text: &LinearString<Id>) -> Vec<ResolvedAnnotation<'_, Id, T>> {
+        let layout = TextLayout::of(text);
+        let mut resolved = self
+            .iter()
+            .filter_map(|(id, range, payload)| {
+                let (start, _) = layout.position_of(&range.start)?;
+                let (end, end_visible) = layout.position_of(&range.end)?;
+                let end = if end_visible { end + 1 } else { end };
+                option_when!(
+                    start < end,
+                    ResolvedAnnotation {
+                        id,
+                        range: start..end,
+                        payload,
+                    }
+                )
+            })
+            .collect::<Vec<_>>();
+        resolved.sort_by(|left, right| {
+            (left.range.start, left.range.end, left.id).cmp(&(
+                right.range.start,
+                right.range.end,
+                right.id,
+            ))
+        });
+        resolved
+    }
+
+    /// Split the visible graphemes at `range` of `text` into runs covered by the same annotations,
+    /// for example to render payloads as styles.
+    ///
+    /// Every visible grapheme in `range` belongs to exactly one segment, and segments are
+    /// returned in document order. The part of `range` past the end of the visible text is
+    /// ignored.
+    pub fn styled_segments(
+        &self,
+        text: &LinearString<Id>,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = StyledSegment<'_, T>> {
+        let resolved = self.resolve(text);
+        let content = text.to_string();
+        let mut offsets = content
+            .grapheme_indices(true)
+            .map(|(offset, _)| offset)
+            .collect::<Vec<_>>();
+        let end = range.end.min(offsets.len());
+        let start = range.start.min(end);
+        offsets.push(content.len());
+
+        let mut boundaries = resolved
+            .iter()
+            .flat_map(|annotation| [annotation.range.start, annotation.range.end])
+            .filter(|boundary| (start..end).contains(boundary))
+            .chain([start, end])
+            .collect::<Vec<_>>();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        let segments = boundaries
+            .windows(2)
+            .map(|pair| {
+                let segment = pair[0]..pair[1];
+                let attributes = resolved
+                    .iter()
+                    .filter(|annotation| {
+                        annotation.range.start <= segment.start
+                            && segment.end <= annotation.range.end
+                    })
+                    .map(|annotation| annotation.payload)
+                    .collect();
+                StyledSegment {
+                    text: content[offsets[segment.start]..offsets[segment.end]].to_owned(),
+                    range: segment,
+                    attributes,
+                }
+            })
+            .collect::<Vec<_>>();
+        segments.into_iter()
+    }
+}
+
+/// A run of visible text that is covered by the same annotations, see
+/// [`Annotations::styled_segments`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StyledSegment<'a, T> {
+    /// Grapheme positions of the segment in the visible text.
+    pub range: Range<usize>,
+    pub text: String,
+    /// The payloads of all annotations covering the segment, ordered like
+    /// [`Annotations::resolve`].
+    pub attributes: Vec<&'a T>,
+}
+
+/// An annotation located in the visible text of a [`LinearString`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedAnnotation<'a, Id, T> {
+    pub id: &'a Id,
+    /// Grapheme positions of the annotated text that is still visible.
+    pub range: Range<usize>,
+    pub payload: &'a T,
+}
+
+/// Grapheme positions of all runs of a [`LinearString`], including deleted ones.
+struct TextLayout<Id> {
+    /// Runs by id, to find the position of a grapheme id.
+    runs: HashMap<Id, Vec<GraphemeRun>>,
+    /// Visible runs in document order, to find the grapheme id at a position.
+    visible: Vec<(IdWithIndex<Id>, usize, usize)>,
+    visible_len: usize,
+}
+
+/// Graphemes with consecutive id indices in one node.
+struct GraphemeRun {
+    first_index: u32,
+    graphemes: usize,
+    /// Number of visible graphemes before this run.
+    visible_before: usize,
+    deleted: bool,
+}
+
+impl<Id> TextLayout<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    fn of(text: &LinearString<Id>) -> Self {
+        let mut layout = Self {
+            runs: HashMap::new(),
+            visible: Vec::new(),
+            visible_len: 0,
+        };
+        let Ok(()) = text.encode_snapshot(&mut layout);
+        layout
+    }
+
+    /// The position of grapheme `id`, and whether it is still visible.
+    ///
+    /// The position of a deleted grapheme is the one the next visible grapheme has.
+    fn position_of(&self, id: &IdWithIndex<Id>) -> Option<(usize, bool)> {
+        self.runs.get(&id.id)?.iter().find_map(|run| {
+            let offset = id.index.checked_sub(run.first_index)? as usize;
+            if offset >= run.graphemes {
+                None
+            } else if run.deleted {
+                Some((run.visible_before, false))
+            } else {
+                Some((run.visible_before + offset, true))
+            }
+        })
+    }
+
+    /// The id of the visible grapheme at `position`.
+    fn id_at(&self, position: usize) -> Option<IdWithIndex<Id>> {
+        if position >= self.visible_len {
+            return None;
+        }
+        let run_index = self
+            .visible
+            .partition_point(|(_, visible_before, _)| *visible_before <= position)
+            .checked_sub(1)?;
+        let (first, visible_before, _) = &self.visible[run_index];
+        let offset = u32::try_from(position - visible_before).ok()?;
+        Some(IdWithIndex {
+            id: first.id.clone(),
+            index: first.index + offset,
+        })
+    }
+}
+impl<Id> SnapshotSink<IdWithIndex<Id>, str> for TextLayout<Id>
+where
+    Id: Clone + Eq + Hash,
+{
+    type Error = Infallible;
+
+    fn begin(&mut self, _header: SnapshotHeader) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn node(
+        &mut self,
+        _index: usize,
+        node: SnapshotNodeRef<'_, IdWithIndex<Id>, str>,
+    ) -> Result<(), Self::Error> {
+        let Some(value) = node.value else {
+            return Ok(());
+        };
+        let graphemes = value.graphemes(true).count();
+        if graphemes == 0 {
+            return Ok(());
+        }
+        self.runs
+            .entry(node.id.id.clone())
+            .or_default()
+            .push(GraphemeRun {
+                first_index: node.id.index,
+                graphemes,
+                visible_before: self.visible_len,
+                deleted: node.deleted,
+            });
+        if !node.deleted {
+            self.visible
+                .push((node.id.clone(), self.visible_len, graphemes));
+            self.visible_len += graphemes;
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::linear_diff;
+
+    fn edit(document: &mut LinearString<u32>, changed: &str, ids: &mut impl Iterator<Item = u32>) {
+        linear_diff(document, changed, ids)
+            .unwrap()
+            .apply_to(document)
+            .unwrap();
+    }
+
+    fn resolved_ranges<T>(resolved: &[ResolvedAnnotation<'_, u32, T>]) -> Vec<(u32, Range<usize>)> {
+        resolved
+            .iter()
+            .map(|annotation| (*annotation.id, annotation.range.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn ranges_follow_edits_of_the_annotated_text() {
+        let mut ids = 1u32..;
+        let mut document = LinearString::with_value("hello world".to_owned(), 0);
+        let mut annotations = Annotations::new();
+        let range = AnnotationRange::from_visible_range(&document, 6..11).unwrap();
+        annotations
+            .apply_operation(annotations.add_operation(100, range, "check spelling"))
+            .unwrap();
+        assert_eq!(
+            resolved_ranges(&annotations.resolve(&document)),
+            vec![(100, 6..11)]
+        );
+
+        edit(&mut document, "oh, hello, dear world", &mut ids);
+        assert_eq!(
+            resolved_ranges(&annotations.resolve(&document)),
+            vec![(100, 16..21)]
+        );
+
+        // Deleting the start of the range shrinks it to the remaining graphemes.
+        edit(&mut document, "oh, hello, dear rld", &mut ids);
+        assert_eq!(
+            resolved_ranges(&annotations.resolve(&document)),
+            vec![(100, 16..19)]
+        );
+
+        edit(&mut document, "oh, hello, dear ", &mut ids);
+        assert!(annotations.resolve(&document).is_empty());
+        assert_eq!(annotations.get(&100), Some(&"check spelling"));
+    }
+
+    #[test]
+    fn concurrent_payload_updates_converge() {
+        let document = LinearString::with_value("some text".to_owned(), 0);
+        let range = AnnotationRange::from_visible_range(&document, 0..4).unwrap();
+        let mut base = Annotations::new();
+        base.apply_operation(base.add_operation(100, range, "draft"))
+            .unwrap();
+
+        let left = base.update_operation(101, 100, "left").unwrap();
+        let right = base.update_operation(102, 100, "right").unwrap();
+        let mut left_first = base.clone();
+        left_first.apply_operation(left.clone()).unwrap();
+        left_first.apply_operation(right.clone()).unwrap();
+        let mut right_first = base.clone();
+        right_first.apply_operation(right).unwrap();
+        right_first.apply_operation(left).unwrap();
+
+        assert_eq!(left_first, right_first);
+        assert_eq!(left_first.get(&100), Some(&"right"));
+        assert_eq!(
+            left_first.concurrent_payloads(&100).collect::<Vec<_>>(),
+            vec![&"left", &"right"]
+        );
+
+        // A later update supersedes both concurrent payloads, even with a lower id.
+        let merged = AnnotationOperation::Update {
+            id: 99,
+            annotation: 100,
+            supersedes: vec![101, 102],
+            payload: "merged",
+        };
+        left_first.apply_operation(merged).unwrap();
+        assert_eq!(left_first.get(&100), Some(&"merged"));
+        assert_eq!(left_first.concurrent_payloads(&100).count(), 1);
+    }
+
+    #[test]
+    fn remove_wins_and_unready_operations_are_rejected() {
+        let document = LinearString::with_value("some text".to_owned(), 0);
+        let range = AnnotationRange::from_visible_range(&document, 5..9).unwrap();
+        let mut annotations = Annotations::new();
+
+        let orphan = AnnotationOperation::Update {
+            id: 101,
+            annotation: 100,
+            supersedes: vec![100],
+            payload: "too early",
+        };
+        assert_eq!(
+            annotations.apply_operation(orphan.clone()),
+            Err(orphan.clone())
+        );
+
+        annotations
+            .apply_operation(annotations.add_operation(100, range, "comment"))
+            .unwrap();
+        let remove = annotations.remove_operation(100).unwrap();
+        annotations.apply_operation(remove).unwrap();
+        annotations.apply_operation(orphan).unwrap();
+
+        assert_eq!(annotations.get(&100), None);
+        assert!(annotations.is_empty());
+        assert!(annotations.resolve(&document).is_empty());
+        assert_eq!(annotations.update_operation(102, 100, "again"), None);
+    }
+
+    #[test]
+    fn styled_segments_split_text_at_annotation_boundaries() {
+        let mut ids = 1u32..;
+        let mut document = LinearString::with_value("bold and italic".to_owned(), 0);
+        let mut annotations = Annotations::new();
+        let bold = AnnotationRange::from_visible_range(&document, 0..8).unwrap();
+        let italic = AnnotationRange::from_visible_range(&document, 5..15).unwrap();
+        annotations
+            .apply_operation(annotations.add_operation(100, bold, "bold"))
+            .unwrap();
+        annotations
+            .apply_operation(annotations.add_operation(101, italic, "italic"))
+            .unwrap();
+
+        let segments = |document: &LinearString<u32>, range: Range<usize>| {
+            annotations
+                .styled_segments(document, range)
+                .map(|segment| (segment.text, segment.attributes))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            segments(&document, 0..15),
+            vec![
+                ("bold ".to_owned(), vec![&"bold"]),
+                ("and".to_owned(), vec![&"bold", &"italic"]),
+                (" italic".to_owned(), vec![&"italic"]),
+            ]
+        );
+        assert_eq!(
+            segments(&document, 2..7),
+            vec![
+                ("ld ".to_owned(), vec![&"bold"]),
+                ("an".to_owned(), vec![&"bold", &"italic"]),
+            ]
+        );
+
+        // Segments follow edits and cover unannotated text as well.
+        edit(&mut document, "🎉 bold and italic!", &mut ids);
+        let all = annotations
+            .styled_segments(&document, 0..usize::MAX)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            all.iter()
+                .map(|segment| (segment.text.as_str(), segment.range.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("🎉 ", 0..2),
+                ("bold ", 2..7),
+                ("and", 7..10),
+                (" italic", 10..17),
+                ("!", 17..18),
+            ]
+        );
+        assert!(all[0].attributes.is_empty());
+        assert_eq!(segments(&document, 20..30), vec![]);
+    }
+}
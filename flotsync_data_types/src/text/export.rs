@@ -0,0 +1,699 @@
+//! Round-trips of [`LinearString`] content through external editors.
+//!
+//! [`export_text`] renders the visible text as it is, which also suits documents that hold
+//! Markdown sources. [`export_markdown`] renders plain text as a Markdown document instead, by
+//! escaping the characters Markdown would read as markup. Either way, the export comes with an
+//! [`AnchorMap`] that maps node ids to byte offsets in the exported text. The anchor map can be
+//! stored next to the exported file as a sidecar, see [`AnchorMap::to_sidecar`].
+//!
+//! Once the file was edited externally, [`TextExport::import`] reconciles it back into the
+//! CRDT. The edit is diffed against the exported state, not the current one, so changes that
+//! were integrated in the meantime are merged instead of overwritten. The exported state is
+//! rebuilt from the document itself, which still holds all exported text, if only as deleted
+//! nodes, and the anchors, which tell which of it was visible. So an export can also be
+//! imported after a restart, by [restoring](TextExport::restore) it from its sidecar.
+
+use super::{ApplyError, DiffError, LinearString, LinearStringDiff, fmt, linear_diff};
+use crate::{
+    IdWithIndex,
+    snapshot::{SnapshotHeader, SnapshotNode, SnapshotNodeRef, SnapshotReadError, SnapshotSink},
+};
+use snafu::prelude::*;
+use std::{collections::HashMap, convert::Infallible, hash::Hash, str::FromStr};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Errors while reconciling an externally edited text back into a [`LinearString`].
+#[derive(Debug, Snafu)]
+pub enum ImportError<Id>
+where
+    Id: fmt::Debug + fmt::Display + 'static,
+{
+    #[snafu(display("The document does not contain all the text the anchors refer to."))]
+    MissingAnchoredText,
+    #[snafu(display("Could not rebuild the exported state of the document."))]
+    Rebuild {
+        source: SnapshotReadError<Infallible>,
+    },
+    #[snafu(display("Could not diff the edited text against the export."))]
+    Diff { source: DiffError },
+    #[snafu(display("Could not apply the edited text."))]
+    Apply { source: ApplyError<Id> },
+}
+
+/// How the visible text of a [`LinearString`] is rendered into an exported file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextFormat {
+    /// The text as it is.
+    #[default]
+    PlainText,
+    /// The text as a Markdown document, with all characters that Markdown would read as markup
+    /// escaped by a backslash.
+    Markdown,
+}
+impl TextFormat {
+    /// Turn `edited`, a file in this format, back into the text it renders.
+    fn decode(self, edited: &str) -> String {
+        match self {
+            Self::PlainText => edited.to_owned(),
+            Self::Markdown => markdown_unescape(edited),
+        }
+    }
+}
+
+/// Errors while reading an [`AnchorMap`] sidecar.
+#[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+pub enum SidecarParseError {
+    #[snafu(display("Sidecar line {line_number} is not a valid anchor."))]
+    InvalidAnchorLine { line_number: usize },
+}
+
+/// The start of one visible run of text in an exported document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextAnchor<Id> {
+    /// The id of the first grapheme in the run.
+    pub id: IdWithIndex<Id>,
+    /// Byte offset of the run in the exported text.
+    pub offset: usize,
+    /// Number of UTF-8 graphemes in the run.
+    ///
+    /// Consecutive graphemes in a run have consecutive id indices.
+    pub graphemes: usize,
+}
+
+/// Maps between node ids and byte offsets in an exported text.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnchorMap<Id> {
+    anchors: Vec<TextAnchor<Id>>,
+}
+impl<Id> AnchorMap<Id>
+where
+    Id: Clone + PartialEq,
+{
+    /// All anchors, ordered by offset.
+    #[must_use]
+    pub fn anchors(&self) -> &[TextAnchor<Id>] {
+        &self.anchors
+    }
+
+    /// Whether the grapheme with id `index` of `id` is covered by one of the anchors.
+    fn covers(runs: &HashMap<&Id, Vec<(u32, usize)>>, id: &Id, index: u32) -> bool
+    where
+        Id: Hash + Eq,
+    {
+        runs.get(id).is_some_and(|runs| {
+            runs.iter()
+                .any(|&(first, graphemes)| index >= first && ((index - first) as usize) < graphemes)
+        })
+    }
+
+    /// The id of the grapheme starting at byte `offset` of `text`.
+    ///
+    /// `text` must be the exported text this map was created for.
+    /// Returns `None` if `offset` is past the end or not at a grapheme boundary.
+    #[must_use]
+    pub fn id_at(&self, text: &str, offset: usize) -> Option<IdWithIndex<Id>> {
+        let anchor_index = self
+            .anchors
+            .partition_point(|anchor| anchor.offset <= offset)
+            .checked_sub(1)?;
+        let anchor = &self.anchors[anchor_index];
+        let run = text.get(anchor.offset..)?;
+        (anchor.id.index..)
+            .zip(run.grapheme_indices(true).take(anchor.graphemes))
+            .find(|(_, (run_offset, _))| anchor.offset + run_offset == offset)
+            .map(|(index, _)| IdWithIndex {
+                id: anchor.id.id.clone(),
+                index,
+            })
+    }
+
+    /// The byte offset of the grapheme with `id` in `text`, if it is visible.
+    ///
+    /// `text` must be the exported text this map was created for.
+    #[must_use]
+    pub fn offset_of(&self, text: &str, id: &IdWithIndex<Id>) -> Option<usize> {
+        let anchor = self.anchors.iter().find(|anchor| {
+            anchor.id.id == id.id
+                && id.index >= anchor.id.index
+                && ((id.index - anchor.id.index) as usize) < anchor.graphemes
+        })?;
+        let skip = (id.index - anchor.id.index) as usize;
+        let run = text.get(anchor.offset..)?;
+        run.grapheme_indices(true)
+            .nth(skip)
+            .map(|(run_offset, _)| anchor.offset + run_offset)
+    }
+}
+impl<Id> AnchorMap<Id>
+where
+    Id: fmt::Display,
+{
+    /// Render the map as a sidecar text with one `offset<TAB>graphemes<TAB>id:index` line per
+    /// anchor.
+    #[must_use]
+    pub fn to_sidecar(&self) -> String {
+        let mut sidecar = String::new();
+        for anchor in &self.anchors {
+            sidecar.push_str(&format!(
+                "{}\t{}\t{}\n",
+                anchor.offset, anchor.graphemes, anchor.id
+            ));
+        }
+        sidecar
+    }
+}
+impl<Id> AnchorMap<Id>
+where
+    Id: FromStr,
+{
+    /// Read a map written by [`to_sidecar`](Self::to_sidecar).
+    ///
+    /// # Errors
+    ///
+    /// See `SidecarParseError` for failure conditions.
+    pub fn from_sidecar(sidecar: &str) -> Result<Self, SidecarParseError> {
+        let mut anchors = Vec::new();
+        for (line_index, line) in sidecar.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let anchor = parse_anchor_line(line).context(InvalidAnchorLineSnafu {
+                line_number: line_index + 1,
+            })?;
+            anchors.push(anchor);
+        }
+        Ok(Self { anchors })
+    }
+}
+
+/// The visible text of a [`LinearString`] at export time, with its anchors.
+///
+/// Only the anchors are needed to [`import`](Self::import) an edited file, so an export that was
+/// not kept around can be [restored](Self::restore) from the sidecar of its file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextExport<Id> {
+    text: String,
+    anchors: AnchorMap<Id>,
+    format: TextFormat,
+}
+impl<Id> TextExport<Id>
+where
+    Id: Clone + fmt::Debug + fmt::Display + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    /// Recreate the export of `document` that produced `anchors` in `format`, e.g. from the
+    /// sidecar of an exported file after a restart.
+    ///
+    /// `document` may have integrated further changes since the export.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ImportError::MissingAnchoredText`] if `document` does not contain the text
+    /// the anchors refer to, e.g. because they belong to another document.
+    pub fn restore(
+        document: &LinearString<Id>,
+        anchors: &AnchorMap<Id>,
+        format: TextFormat,
+    ) -> Result<Self, ImportError<Id>> {
+        let exported = exported_state(document, anchors)?;
+        Ok(render(&exported, format))
+    }
+
+    /// The exported text.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[must_use]
+    pub fn anchors(&self) -> &AnchorMap<Id> {
+        &self.anchors
+    }
+
+    #[must_use]
+    pub fn format(&self) -> TextFormat {
+        self.format
+    }
+
+    /// Reconcile an externally edited version of the exported text into `target`.
+    ///
+    /// `target` may have integrated further changes since the export. The returned diff has
+    /// already been applied to `target` and should be replicated like any local change.
+    ///
+    /// # Errors
+    ///
+    /// See `ImportError<Id>` for failure conditions.
+    pub fn import(
+        &self,
+        target: &mut LinearString<Id>,
+        edited: &str,
+        id_generator: &mut impl Iterator<Item = Id>,
+    ) -> Result<LinearStringDiff<Id>, ImportError<Id>> {
+        let exported = exported_state(target, &self.anchors)?;
+        let edited = self.format.decode(edited);
+        let diff = linear_diff(&exported, &edited, id_generator).context(DiffSnafu)?;
+        diff.clone().apply_to(target).context(ApplySnafu)?;
+        Ok(diff)
+    }
+}
+
+/// Export the visible text of `document` as it is, together with its [`AnchorMap`].
+#[must_use]
+pub fn export_text<Id>(document: &LinearString<Id>) -> TextExport<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    render(document, TextFormat::PlainText)
+}
+
+/// Export the visible text of `document` as a Markdown document, together with its
+/// [`AnchorMap`].
+///
+/// The anchors cover the characters of the text, but not the backslashes escaping them.
+#[must_use]
+pub fn export_markdown<Id>(document: &LinearString<Id>) -> TextExport<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    render(document, TextFormat::Markdown)
+}
+
+fn render<Id>(document: &LinearString<Id>, format: TextFormat) -> TextExport<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    let mut collector = AnchorCollector {
+        format,
+        text: String::new(),
+        anchors: Vec::new(),
+        line: MarkdownLine::default(),
+    };
+    let Ok(()) = document.encode_snapshot(&mut collector);
+    TextExport {
+        text: collector.text,
+        anchors: AnchorMap {
+            anchors: collector.anchors,
+        },
+        format,
+    }
+}
+
+/// Rebuild `document` as it was when it was exported with `anchors`.
+///
+/// Text is never removed from a [`LinearString`], only marked as deleted, so the result is
+/// `document` with exactly the anchored graphemes visible. Text inserted after the export is
+/// kept as deleted nodes, so operations diffed against the result still apply to `document`.
+fn exported_state<Id>(
+    document: &LinearString<Id>,
+    anchors: &AnchorMap<Id>,
+) -> Result<LinearString<Id>, ImportError<Id>>
+where
+    Id: Clone + fmt::Debug + fmt::Display + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    let mut runs: HashMap<&Id, Vec<(u32, usize)>> = HashMap::new();
+    for anchor in &anchors.anchors {
+        runs.entry(&anchor.id.id)
+            .or_default()
+            .push((anchor.id.index, anchor.graphemes));
+    }
+
+    let mut collector = NodeCollector { nodes: Vec::new() };
+    let Ok(()) = document.encode_snapshot(&mut collector);
+    let mut nodes = Vec::with_capacity(collector.nodes.len());
+    let mut visible_graphemes = 0usize;
+    for node in collector.nodes {
+        let Some(value) = node.value.as_deref().filter(|value| !value.is_empty()) else {
+            nodes.push(node);
+            continue;
+        };
+        // Split the node into runs that were either all visible or all deleted at export time.
+        let mut run: Option<(IdWithIndex<Id>, bool, String)> = None;
+        for (index, grapheme) in (node.id.index..).zip(value.graphemes(true)) {
+            let visible = AnchorMap::covers(&runs, &node.id.id, index);
+            visible_graphemes += usize::from(visible);
+            match &mut run {
+                Some((_, run_visible, text)) if *run_visible == visible => text.push_str(grapheme),
+                _ => {
+                    let id = IdWithIndex {
+                        id: node.id.id.clone(),
+                        index,
+                    };
+                    let next = (id, visible, grapheme.to_owned());
+                    if let Some(finished) = run.replace(next) {
+                        nodes.push(split_node(&node, finished));
+                    }
+                }
+            }
+        }
+        if let Some(finished) = run {
+            nodes.push(split_node(&node, finished));
+        }
+    }
+
+    let anchored_graphemes: usize = anchors.anchors.iter().map(|anchor| anchor.graphemes).sum();
+    ensure!(
+        visible_graphemes == anchored_graphemes,
+        MissingAnchoredTextSnafu
+    );
+    LinearString::from_snapshot_nodes(nodes.into_iter().map(Ok)).context(RebuildSnafu)
+}
+
+/// One run of a node split by [`exported_state`].
+///
+/// Split nodes keep the origins of the node they were split from, like any other split.
+fn split_node<Id>(
+    node: &SnapshotNode<IdWithIndex<Id>, String>,
+    (id, visible, text): (IdWithIndex<Id>, bool, String),
+) -> SnapshotNode<IdWithIndex<Id>, String>
+where
+    Id: Clone,
+{
+    SnapshotNode {
+        id,
+        left: node.left.clone(),
+        right: node.right.clone(),
+        deleted: !visible,
+        value: Some(text),
+    }
+}
+
+/// Snapshot sink that keeps an owned copy of every node.
+struct NodeCollector<Id> {
+    nodes: Vec<SnapshotNode<IdWithIndex<Id>, String>>,
+}
+impl<Id> SnapshotSink<IdWithIndex<Id>, str> for NodeCollector<Id>
+where
+    Id: Clone,
+{
+    type Error = Infallible;
+
+    fn begin(&mut self, _header: SnapshotHeader) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn node(
+        &mut self,
+        _index: usize,
+        node: SnapshotNodeRef<'_, IdWithIndex<Id>, str>,
+    ) -> Result<(), Self::Error> {
+        self.nodes.push(SnapshotNode {
+            id: node.id.clone(),
+            left: node.left.cloned(),
+            right: node.right.cloned(),
+            deleted: node.deleted,
+            value: node.value.map(str::to_owned),
+        });
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Snapshot sink that renders visible nodes and records where each one starts.
+struct AnchorCollector<Id> {
+    format: TextFormat,
+    text: String,
+    anchors: Vec<TextAnchor<Id>>,
+    line: MarkdownLine,
+}
+impl<Id> AnchorCollector<Id>
+where
+    Id: Clone + PartialEq,
+{
+    /// Append the grapheme with `id`, behind a backslash if it has to be escaped.
+    fn push_markdown_grapheme(&mut self, id: IdWithIndex<Id>, grapheme: &str) {
+        if self.line.needs_escape(grapheme) {
+            self.text.push('\\');
+            self.anchors.push(TextAnchor {
+                id,
+                offset: self.text.len(),
+                graphemes: 1,
+            });
+        } else {
+            let offset = self.text.len();
+            match self.anchors.last_mut() {
+                Some(anchor)
+                    if anchor.id.id == id.id
+                        && anchor.id.index as usize + anchor.graphemes == id.index as usize
+                        && self.line.run_end == offset =>
+                {
+                    anchor.graphemes += 1;
+                }
+                _ => self.anchors.push(TextAnchor {
+                    id,
+                    offset,
+                    graphemes: 1,
+                }),
+            }
+        }
+        self.text.push_str(grapheme);
+        self.line.run_end = self.text.len();
+    }
+}
+impl<Id> SnapshotSink<IdWithIndex<Id>, str> for AnchorCollector<Id>
+where
+    Id: Clone + PartialEq,
+{
+    type Error = Infallible;
+
+    fn begin(&mut self, _header: SnapshotHeader) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn node(
+        &mut self,
+        _index: usize,
+        node: SnapshotNodeRef<'_, IdWithIndex<Id>, str>,
+    ) -> Result<(), Self::Error> {
+        let Some(value) = node.value else {
+            return Ok(());
+        };
+        if node.deleted || value.is_empty() {
+            return Ok(());
+        }
+        if self.format == TextFormat::Markdown {
+            for (index, grapheme) in (node.id.index..).zip(value.graphemes(true)) {
+                let id = IdWithIndex {
+                    id: node.id.id.clone(),
+                    index,
+                };
+                self.push_markdown_grapheme(id, grapheme);
+            }
+            return Ok(());
+        }
+        self.anchors.push(TextAnchor {
+            id: node.id.clone(),
+            offset: self.text.len(),
+            graphemes: value.graphemes(true).count(),
+        });
+        self.text.push_str(value);
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Tracks where in a line the Markdown export is, to escape only what would be read as markup.
+#[derive(Debug)]
+struct MarkdownLine {
+    /// Only whitespace was written on this line so far.
+    at_start: bool,
+    /// Only whitespace followed by at least one digit was written on this line so far.
+    after_number: bool,
+    /// End of the text written for the last grapheme that was not escaped.
+    run_end: usize,
+}
+impl Default for MarkdownLine {
+    fn default() -> Self {
+        Self {
+            at_start: true,
+            after_number: false,
+            run_end: 0,
+        }
+    }
+}
+impl MarkdownLine {
+    /// Characters that mark up text anywhere in a line.
+    const INLINE_MARKUP: &[char] = &['\\', '`', '*', '_', '[', ']', '<', '&', '~'];
+    /// Characters that start a block, such as a heading or list item, at the start of a line.
+    const BLOCK_MARKUP: &[char] = &['#', '>', '+', '-', '='];
+
+    /// Whether `grapheme`, written next, has to be escaped.
+    fn needs_escape(&mut self, grapheme: &str) -> bool {
+        let mut chars = grapheme.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            // Multi-character graphemes are never markup, but may end the line, e.g. "\r\n".
+            self.at_start = grapheme.ends_with('\n');
+            self.after_number = false;
+            return false;
+        };
+        let escape = Self::INLINE_MARKUP.contains(&c)
+            || (self.at_start && Self::BLOCK_MARKUP.contains(&c))
+            || (self.after_number && matches!(c, '.' | ')'));
+        let digit_continues = (self.at_start || self.after_number) && c.is_ascii_digit();
+        self.at_start = c == '\n' || (self.at_start && matches!(c, ' ' | '\t'));
+        self.after_number = digit_continues;
+        escape
+    }
+}
+
+/// Remove the backslashes escaping ASCII punctuation, as Markdown renders them.
+fn markdown_unescape(markdown: &str) -> String {
+    let mut text = String::with_capacity(markdown.len());
+    let mut chars = markdown.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && next.is_ascii_punctuation() => {
+                text.push(next);
+                chars.next();
+            }
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+fn parse_anchor_line<Id>(line: &str) -> Option<TextAnchor<Id>>
+where
+    Id: FromStr,
+{
+    let mut fields = line.splitn(3, '\t');
+    let offset = fields.next()?.parse().ok()?;
+    let graphemes = fields.next()?.parse().ok()?;
+    let (id, index) = fields.next()?.rsplit_once(':')?;
+    Some(TextAnchor {
+        id: IdWithIndex {
+            id: id.parse().ok()?,
+            index: index.parse().ok()?,
+        },
+        offset,
+        graphemes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchors_map_ids_to_offsets_and_back() {
+        let mut ids = 1u32..;
+        let mut document = LinearString::with_value("hello world".to_owned(), 0);
+        linear_diff(&document, "hello brave world", &mut ids)
+            .unwrap()
+            .apply_to(&mut document)
+            .unwrap();
+
+        let export = export_text(&document);
+        assert_eq!(export.text(), "hello brave world");
+        let text = export.text();
+        for (offset, _) in text.grapheme_indices(true) {
+            let id = export.anchors().id_at(text, offset).unwrap();
+            assert_eq!(export.anchors().offset_of(text, &id), Some(offset));
+        }
+        assert_eq!(export.anchors().id_at(text, text.len()), None);
+
+        let sidecar = export.anchors().to_sidecar();
+        assert_eq!(
+            AnchorMap::<u32>::from_sidecar(&sidecar).unwrap(),
+            *export.anchors()
+        );
+        assert_eq!(
+            AnchorMap::<u32>::from_sidecar("0\tnot-a-number\t1:0\n"),
+            Err(SidecarParseError::InvalidAnchorLine { line_number: 1 })
+        );
+    }
+
+    #[test]
+    fn import_merges_external_edits_with_concurrent_changes() {
+        let mut ids = 1u32..;
+        let mut document = LinearString::with_value("# Title\n\nSome text.\n".to_owned(), 0);
+        let export = export_text(&document);
+
+        // Changed through the CRDT while the file was open in an editor.
+        linear_diff(&document, "# Title\n\nSome text.\nMore text.\n", &mut ids)
+            .unwrap()
+            .apply_to(&mut document)
+            .unwrap();
+
+        export
+            .import(&mut document, "# Better Title\n\nSome text.\n", &mut ids)
+            .unwrap();
+        assert_eq!(
+            document.to_string(),
+            "# Better Title\n\nSome text.\nMore text.\n"
+        );
+    }
+
+    #[test]
+    fn restored_exports_import_edits_after_concurrent_changes() {
+        let mut ids = 1u32..;
+        let mut document =
+            LinearString::with_value("# Title\n\nSome text.\nOld line.\n".to_owned(), 0);
+        let export = export_text(&document);
+        let sidecar = export.anchors().to_sidecar();
+        drop(export);
+
+        // Changed through the CRDT while the file was open in an editor, and the export is gone.
+        linear_diff(&document, "# Title\n\nSome new text.\n", &mut ids)
+            .unwrap()
+            .apply_to(&mut document)
+            .unwrap();
+
+        let anchors = AnchorMap::from_sidecar(&sidecar).unwrap();
+        let restored = TextExport::restore(&document, &anchors, TextFormat::PlainText).unwrap();
+        assert_eq!(restored.text(), "# Title\n\nSome text.\nOld line.\n");
+        restored
+            .import(&mut document, "# Title!\n\nSome text.\n", &mut ids)
+            .unwrap();
+        assert_eq!(document.to_string(), "# Title!\n\nSome new text.\n");
+
+        let unrelated = LinearString::with_value("# Title\n".to_owned(), 100);
+        assert!(matches!(
+            TextExport::restore(&unrelated, &anchors, TextFormat::PlainText),
+            Err(ImportError::MissingAnchoredText)
+        ));
+    }
+
+    #[test]
+    fn markdown_exports_escape_markup_and_import_back() {
+        let mut ids = 1u32..;
+        let mut document = LinearString::with_value(
+            "# not a heading, *not bold* 1. x\n2. not a list\n  - nor this\n".to_owned(),
+            0,
+        );
+        let export = export_markdown(&document);
+        assert_eq!(
+            export.text(),
+            "\\# not a heading, \\*not bold\\* 1. x\n2\\. not a list\n  \\- nor this\n"
+        );
+        let text = export.text();
+        for (offset, grapheme) in text.grapheme_indices(true) {
+            let id = export.anchors().id_at(text, offset);
+            let escape = grapheme == "\\";
+            assert_eq!(id.is_none(), escape, "at {offset}");
+            if let Some(id) = id {
+                assert_eq!(export.anchors().offset_of(text, &id), Some(offset));
+            }
+        }
+
+        export
+            .import(
+                &mut document,
+                "\\# not a heading, \\*still not bold\\* 1. x\n2\\. not a list\n  \\- nor this\n",
+                &mut ids,
+            )
+            .unwrap();
+        assert_eq!(
+            document.to_string(),
+            "# not a heading, *still not bold* 1. x\n2. not a list\n  - nor this\n"
+        );
+
+        let restored =
+            TextExport::restore(&document, export.anchors(), TextFormat::Markdown).unwrap();
+        assert_eq!(restored.text(), export.text());
+    }
+}
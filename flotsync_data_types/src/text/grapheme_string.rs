@@ -1,23 +1,36 @@
-use std::cmp;
+//! A `String` wrapper that keeps track of its length in extended grapheme clusters.
+use std::{cmp, ops::Bound};
 
-use super::{Composite, Graphemes, Hash, UnicodeSegmentation, fmt};
+use super::{Composite, Graphemes, Hash, RangeBounds, UnicodeSegmentation, fmt};
 
+/// An owned UTF-8 string that caches its length in extended grapheme clusters.
+///
+/// All positions and lengths on this type are measured in graphemes, not bytes or `char`s.
+/// This makes it suitable as the payload of composite values (like [[`LinearString`]]) where
+/// user-visible characters must never be split.
+///
+/// [[`LinearString`]]: super::LinearString
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct GraphemeString {
     len: usize,
     base: String,
 }
 impl GraphemeString {
+    pub const EMPTY: Self = Self {
+        len: 0,
+        base: String::new(),
+    };
+
     pub fn new(base: String) -> Self {
         let len = base.graphemes(true).count();
         Self { len, base }
     }
 
-    #[allow(dead_code)]
-    pub const EMPTY: Self = Self {
-        len: 0,
-        base: String::new(),
-    };
+    /// Create a [[`GraphemeStringBuilder`]] for assembling a value from many pieces.
+    #[must_use]
+    pub fn builder() -> GraphemeStringBuilder {
+        GraphemeStringBuilder::new()
+    }
 
     pub fn unwrap(self) -> String {
         self.base
@@ -27,11 +40,60 @@ impl GraphemeString {
         self.base.as_str()
     }
 
-    fn graphemes(&self) -> Graphemes<'_> {
-        self.base.graphemes(true)
+    /// The number of graphemes in this string.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append `value` to the end of this string.
+    ///
+    /// Only the last grapheme of the current content is re-segmented together with `value`,
+    /// since appending may extend it (e.g. with a combining mark).
+    pub fn push_str(&mut self, value: &str) {
+        if value.is_empty() {
+            return;
+        }
+        let (tail_start, tail_len) = match self.base.grapheme_indices(true).next_back() {
+            Some((index, _)) => (index, 1),
+            None => (0, 0),
+        };
+        self.base.push_str(value);
+        let tail_graphemes = self.base[tail_start..].graphemes(true).count();
+        self.len = self.len - tail_len + tail_graphemes;
+    }
+
+    /// Returns the substring covering the graphemes in `range`.
+    ///
+    /// Returns `None` if the range is out of bounds or its start lies after its end.
+    pub fn slice<R>(&self, range: R) -> Option<&str>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => end.checked_add(1)?,
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => self.len,
+        };
+        if start > end || end > self.len {
+            return None;
+        }
+        let start_byte = self.byte_offset(start);
+        let end_byte = self.byte_offset(end);
+        Some(&self.base[start_byte..end_byte])
     }
 
-    #[allow(dead_code)]
+    /// Remove and return the first `max_elements` graphemes of this string.
     pub fn take(&mut self, max_elements: usize) -> GraphemeString {
         if self.len <= max_elements {
             std::mem::replace(self, Self::EMPTY)
@@ -43,6 +105,21 @@ impl GraphemeString {
             res
         }
     }
+
+    fn graphemes(&self) -> Graphemes<'_> {
+        self.base.graphemes(true)
+    }
+
+    /// The byte offset at which the grapheme with index `position` starts.
+    ///
+    /// `position == self.len` maps to the end of the string.
+    fn byte_offset(&self, position: usize) -> usize {
+        debug_assert!(position <= self.len);
+        self.base
+            .grapheme_indices(true)
+            .nth(position)
+            .map_or(self.base.len(), |(index, _)| index)
+    }
 }
 impl Composite for GraphemeString {
     type Element = str;
@@ -56,6 +133,10 @@ impl Composite for GraphemeString {
         self.graphemes().nth(index)
     }
 
+    fn last(&self) -> Option<&Self::Element> {
+        self.graphemes().next_back()
+    }
+
     fn split_at(mut self, index: usize) -> (Self, Self) {
         assert!(index < self.len);
         let (split_index, _) = self.base.grapheme_indices(true).nth(index).unwrap();
@@ -78,6 +159,31 @@ impl Composite for GraphemeString {
         self.graphemes()
     }
 }
+impl Default for GraphemeString {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+impl From<String> for GraphemeString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+impl From<&str> for GraphemeString {
+    fn from(value: &str) -> Self {
+        Self::new(value.to_owned())
+    }
+}
+impl From<GraphemeString> for String {
+    fn from(value: GraphemeString) -> Self {
+        value.unwrap()
+    }
+}
+impl AsRef<str> for GraphemeString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
 impl fmt::Debug for GraphemeString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.base)
@@ -108,3 +214,113 @@ impl cmp::PartialOrd for GraphemeString {
         Some(self.cmp(other))
     }
 }
+
+/// A mutable buffer for assembling a [[`GraphemeString`]] from many pieces.
+///
+/// Unlike repeated [[`GraphemeString::push_str`]] calls, the builder defers grapheme
+/// segmentation until [[`GraphemeStringBuilder::build`]], so the whole content is only
+/// segmented once.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphemeStringBuilder {
+    buffer: String,
+}
+impl GraphemeStringBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder that can hold at least `capacity` bytes without reallocating.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: String::with_capacity(capacity),
+        }
+    }
+
+    pub fn push_str(&mut self, value: &str) -> &mut Self {
+        self.buffer.push_str(value);
+        self
+    }
+
+    pub fn push(&mut self, value: char) -> &mut Self {
+        self.buffer.push(value);
+        self
+    }
+
+    /// The current content of the buffer.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.buffer.as_str()
+    }
+
+    /// Segment the accumulated content and produce the final [[`GraphemeString`]].
+    #[must_use]
+    pub fn build(self) -> GraphemeString {
+        GraphemeString::new(self.buffer)
+    }
+}
+impl fmt::Write for GraphemeStringBuilder {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buffer.push_str(s);
+        Ok(())
+    }
+}
+impl From<GraphemeString> for GraphemeStringBuilder {
+    fn from(value: GraphemeString) -> Self {
+        Self {
+            buffer: value.unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_str_merges_combining_marks() {
+        let mut value = GraphemeString::from("cafe");
+        assert_eq!(value.len(), 4);
+
+        // U+0301 COMBINING ACUTE ACCENT extends the last grapheme instead of adding one.
+        value.push_str("\u{301}");
+        assert_eq!(value.len(), 4);
+        assert_eq!(value, GraphemeString::from("cafe\u{301}"));
+
+        value.push_str(" 👩‍👩‍👧");
+        assert_eq!(value.len(), 6);
+
+        let mut empty = GraphemeString::EMPTY;
+        empty.push_str("ab");
+        assert_eq!(empty.len(), 2);
+    }
+
+    #[test]
+    fn slice_by_grapheme_range() {
+        let value = GraphemeString::from("a👩‍👩‍👧be\u{301}c");
+        assert_eq!(value.len(), 5);
+
+        assert_eq!(value.slice(..), Some(value.as_str()));
+        assert_eq!(value.slice(1..2), Some("👩‍👩‍👧"));
+        assert_eq!(value.slice(2..=3), Some("be\u{301}"));
+        assert_eq!(value.slice(4..), Some("c"));
+        assert_eq!(value.slice(5..), Some(""));
+        assert_eq!(value.slice((Bound::Included(3), Bound::Excluded(2))), None);
+        assert_eq!(value.slice(..6), None);
+    }
+
+    #[test]
+    fn builder_matches_direct_construction() {
+        let mut builder = GraphemeString::builder();
+        builder.push_str("e").push('\u{301}').push_str("👍");
+        let value = builder.build();
+
+        assert_eq!(value, GraphemeString::new("e\u{301}👍".to_owned()));
+        assert_eq!(value.len(), 2);
+
+        let mut builder = GraphemeStringBuilder::from(value);
+        builder.push_str("!");
+        assert_eq!(String::from(builder.build()), "e\u{301}👍!");
+    }
+}
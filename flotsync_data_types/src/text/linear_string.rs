@@ -1,8 +1,14 @@
 use super::{DebugFormatting, LinearData, RangeBounds, VecCoalescedLinearDataIter, fmt};
 use crate::{
     IntegrityError,
+    InternalError,
+    LinearDataStats,
     linear_data::{
+        ApplyBatch,
+        ApplyFailure,
+        BatchResult,
         DataOperation,
+        DataOperationRef,
         IdWithIndex,
         IdWithIndexRange,
         LinkIds,
@@ -12,9 +18,9 @@ use crate::{
         VecLinearData,
     },
     snapshot::{SnapshotNode, SnapshotReadError, SnapshotSink},
-    text::grapheme_string::GraphemeString,
+    text::{TextStats, grapheme_string::GraphemeString, stats::TextSummary},
 };
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 
 pub type LinearWordString<Id> = VecLinearData<Id, String>;
 #[allow(unused, reason = "Testing")]
@@ -71,16 +77,22 @@ where
 ///
 /// `LinearString` is a *replicated data type*: it is intended to be updated by integrating
 /// operations produced locally and received from other replicas.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// ## Equality
+///
+/// `PartialEq` and `Hash` compare the replicated structure, see
+/// [`structural_eq`](Self::structural_eq), so two replicas compare equal exactly when they have
+/// converged. Use [`content_eq`](Self::content_eq) to only compare the visible text.
+#[derive(Clone, Debug)]
 pub struct LinearString<Id> {
-    data: VecCoalescedLinearData<Id, GraphemeString>,
+    data: VecCoalescedLinearData<Id, GraphemeString, TextSummary>,
 }
 impl<Id> LinearString<Id>
 where
     Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
 {
     pub fn new(initial_id: Id) -> Self {
-        let data = VecCoalescedLinearData::new(initial_id);
+        let data = VecCoalescedLinearData::new_summarized(initial_id);
         Self { data }
     }
 
@@ -89,7 +101,7 @@ where
             Self::new(initial_id)
         } else {
             let wrapped_value = GraphemeString::new(initial_value);
-            let data = VecCoalescedLinearData::with_value(initial_id, wrapped_value);
+            let data = VecCoalescedLinearData::with_value_summarized(initial_id, wrapped_value);
             Self { data }
         }
     }
@@ -102,6 +114,37 @@ where
         self.data.prepend(id, GraphemeString::new(value));
     }
 
+    /// Apply an operation that borrows its text, copying it only once it is stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the original borrowed operation if it could not be applied.
+    pub fn apply_operation_ref<'v>(
+        &mut self,
+        operation: DataOperationRef<'v, IdWithIndex<Id>, str>,
+    ) -> Result<(), ApplyFailure<DataOperationRef<'v, IdWithIndex<Id>, str>>> {
+        LinearData::apply_operation_ref(self, operation)
+    }
+
+    /// Apply a batch of operations in causal order, returning the ones that are still blocked.
+    ///
+    /// See [`VecCoalescedLinearData::apply_batch`] for how the batch is ordered.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first internal inconsistency. Operations applied before it stay applied.
+    pub fn apply_batch(
+        &mut self,
+        operations: Vec<DataOperation<IdWithIndex<Id>, String>>,
+    ) -> Result<BatchResult<DataOperation<IdWithIndex<Id>, String>>, InternalError> {
+        let operations = operations
+            .into_iter()
+            .map(|operation| operation.map_value(GraphemeString::new))
+            .collect();
+        let result = self.data.apply_batch(operations)?;
+        Ok(result.map_operation(|operation| operation.map_value(GraphemeString::unwrap)))
+    }
+
     /// This is the number of UTF-8 Graphemes in this string.
     #[must_use]
     pub fn len(&self) -> usize {
@@ -113,6 +156,41 @@ where
         self.data.is_empty()
     }
 
+    /// Number of stored nodes, including boundaries and deleted text.
+    ///
+    /// Unlike [`len`](Self::len), this bounds the memory the string occupies.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.data.node_count()
+    }
+
+    /// Size statistics of the visible text.
+    ///
+    /// These are kept up to date as operations are applied, so this does not look at the text.
+    #[must_use]
+    pub fn text_stats(&self) -> TextStats {
+        self.data.summary().stats()
+    }
+
+    /// The grapheme position at which the zero-based `line` starts.
+    ///
+    /// Returns `None` if the text has fewer lines. Unlike for [`TextStats::lines`], a line break
+    /// at the very end starts another, empty line, so the position after it has a line as well.
+    /// Lines are indexed as operations are applied, so this does not look at the text.
+    #[must_use]
+    pub fn pos_of_line(&self, line: usize) -> Option<usize> {
+        self.data.summary().pos_of_line(line)
+    }
+
+    /// The zero-based line that the grapheme at `position` is on.
+    ///
+    /// A line break is on the line it ends. `position` may be [`len`](Self::len), i.e. the end of
+    /// the text, and `None` is only returned for positions after that.
+    #[must_use]
+    pub fn line_of_pos(&self, position: usize) -> Option<usize> {
+        self.data.summary().line_of_pos(position)
+    }
+
     pub fn ids_in_range<R>(&self, range: R) -> Option<NodeIdRangeString<Id>>
     where
         R: RangeBounds<usize>,
@@ -130,6 +208,15 @@ where
         self.data.iter_ids().map(|id| &id.id)
     }
 
+    /// Iterate over the visible text in runs of graphemes with consecutive ids.
+    ///
+    /// Each run comes with the id of its first grapheme.
+    pub fn iter_with_ids(&self) -> impl Iterator<Item = (IdWithIndex<Id>, &str)> {
+        self.data
+            .iter_with_ids()
+            .map(|(id, value)| (id.clone(), value.as_str()))
+    }
+
     /// Encode a stable, ordered snapshot stream of the current in-memory state.
     ///
     /// # Errors
@@ -164,6 +251,17 @@ where
         Ok(Self { data })
     }
 
+    /// Whether `self` and `other` have the same visible text, independent of their history.
+    #[must_use]
+    pub fn content_eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .data
+                .iter_values()
+                .flat_map(str::chars)
+                .eq(other.data.iter_values().flat_map(str::chars))
+    }
+
     /// Validate the internal CRDT structure and chunk/id invariants.
     ///
     /// This is primarily useful after reconstructing a value from an external snapshot or other
@@ -175,6 +273,89 @@ where
     pub fn validate_integrity(&self) -> Result<(), IntegrityError> {
         self.data.validate_integrity()
     }
+
+    /// Conflict and storage statistics, e.g. to decide when compaction should run.
+    #[must_use]
+    pub fn stats(&self) -> LinearDataStats {
+        self.data.stats()
+    }
+
+    /// Store parts of the same insert that were split apart as single nodes again.
+    ///
+    /// Returns the number of nodes that were merged away. See
+    /// [`VecCoalescedLinearData::defragment`](crate::linear_data::VecCoalescedLinearData::defragment).
+    pub fn defragment(&mut self) -> usize {
+        self.data.defragment()
+    }
+}
+impl<Id> ApplyBatch for LinearString<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    type Operation = DataOperation<IdWithIndex<Id>, String>;
+
+    fn apply_batch(
+        &mut self,
+        operations: Vec<Self::Operation>,
+    ) -> Result<BatchResult<Self::Operation>, InternalError> {
+        LinearString::apply_batch(self, operations)
+    }
+}
+impl<Id> LinearString<Id>
+where
+    Id: PartialEq,
+{
+    /// Whether `self` and `other` contain the same graphemes, under the same ids, in the same
+    /// order, with the same ones deleted.
+    ///
+    /// This is what two replicas that integrated the same operations agree on, no matter in
+    /// which order they did so. How the graphemes happen to be split into internal nodes is not
+    /// compared, and neither are the ids of the boundary nodes.
+    #[must_use]
+    pub fn structural_eq(&self, other: &Self) -> bool {
+        self.structural_runs() == other.structural_runs()
+    }
+
+    /// All nodes in document order, with consecutive nodes of the same run coalesced.
+    fn structural_runs(&self) -> Vec<StructuralRun<'_, Id>> {
+        let mut runs: Vec<StructuralRun<'_, Id>> = Vec::new();
+        for (id, deleted, value) in self.data.iter_content_nodes() {
+            if let Some(last) = runs.last_mut()
+                && *last.id == id.id
+                && last.deleted == deleted
+                && u64::from(last.index) + last.graphemes as u64 == u64::from(id.index)
+            {
+                last.graphemes += value.len();
+                last.value.push_str(value.as_str());
+            } else {
+                runs.push(StructuralRun {
+                    id: &id.id,
+                    index: id.index,
+                    deleted,
+                    graphemes: value.len(),
+                    value: value.as_str().to_owned(),
+                });
+            }
+        }
+        runs
+    }
+}
+impl<Id> PartialEq for LinearString<Id>
+where
+    Id: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.structural_eq(other)
+    }
+}
+impl<Id> Eq for LinearString<Id> where Id: Eq {}
+impl<Id> Hash for LinearString<Id>
+where
+    Id: PartialEq + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.structural_runs().hash(state);
+    }
 }
 impl<Id> fmt::Display for LinearString<Id>
 where
@@ -237,11 +418,11 @@ where
     fn apply_operation(
         &mut self,
         operation: DataOperation<Self::Id, String>,
-    ) -> Result<(), DataOperation<Self::Id, String>> {
+    ) -> Result<(), ApplyFailure<DataOperation<Self::Id, String>>> {
         let op = operation.map_value(GraphemeString::new);
         self.data
             .apply_operation(op)
-            .map_err(|op| op.map_value(GraphemeString::unwrap))
+            .map_err(|failure| failure.map_operation(|op| op.map_value(GraphemeString::unwrap)))
     }
 }
 impl<Id> DebugFormatting for LinearString<Id>
@@ -298,10 +479,20 @@ where
     VecLinearData::with_value(s_owned, [(); 3])
 }
 
+/// A run of graphemes with consecutive ids, as compared by [`LinearString::structural_eq`].
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct StructuralRun<'a, Id> {
+    id: &'a Id,
+    index: u32,
+    deleted: bool,
+    graphemes: usize,
+    value: String,
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
-    use crate::linear_data::tests::TestIdGenerator;
+    use crate::test_support::ids::TestIdGenerator;
     use flotsync_utils::testing::CloneExt;
 
     const TEST_VALUES: [&str; 8] = ["A", " ", "simple", " ", "test", " ", "string", "."];
@@ -322,8 +513,15 @@ pub(crate) mod tests {
 
     mod linear_string {
         use super::*;
+        use crate::linear_data::apply_workspace_batches;
+        #[cfg(feature = "parallel")]
+        use crate::linear_data::par_apply_workspace_batches;
         use flotsync_utils::testing::BOOLEAN_DOMAIN;
-        use std::string::String;
+        use std::{
+            assert_matches,
+            collections::{BTreeMap, HashMap},
+            string::String,
+        };
         use unicode_segmentation::UnicodeSegmentation;
 
         fn empty_checks(l: &LinearString<u32>) {
@@ -342,6 +540,173 @@ pub(crate) mod tests {
             assert_eq!(linear.to_string(), input);
         }
 
+        #[test]
+        fn borrowed_operations_apply_and_come_back_on_failure() {
+            let mut id_generator = TestIdGenerator::new();
+            let mut linear = LinearString::new(id_generator.next().unwrap());
+            let buffer = String::from("borrowed text");
+
+            let link = linear.ids_after_head();
+            let insert = DataOperation::Insert {
+                id: id_generator.next_with_zero_index().unwrap(),
+                pred: link.predecessor,
+                succ: link.successor,
+                value: buffer.as_str(),
+            };
+            linear.apply_operation_ref(insert).unwrap();
+            linear.validate_integrity().unwrap();
+            assert_eq!(linear.to_string(), buffer);
+
+            let unknown = id_generator.next_with_zero_index().unwrap();
+            let orphan = DataOperation::Insert {
+                id: id_generator.next_with_zero_index().unwrap(),
+                pred: unknown.clone(),
+                succ: unknown,
+                value: &buffer[..8],
+            };
+            let failure = linear.apply_operation_ref(orphan.clone()).unwrap_err();
+            assert_matches!(failure, ApplyFailure::Rejected { operation } if operation == orphan);
+            assert_eq!(linear.to_string(), buffer);
+        }
+
+        #[test]
+        fn batches_apply_in_causal_order() {
+            let mut id_generator = TestIdGenerator::new();
+            let base = LinearString::new(id_generator.next().unwrap());
+            let mut source = base.clone();
+            let mut operations = Vec::new();
+            for s in TEST_VALUES {
+                let operation = source
+                    .ids_before_end()
+                    .insert_operation(id_generator.next_with_zero_index().unwrap(), s.to_owned());
+                source.apply_operation(operation.clone()).unwrap();
+                operations.push(operation);
+            }
+            let deletes: Vec<_> = source
+                .ids_in_range(2..8)
+                .unwrap()
+                .delete_operations()
+                .collect();
+            for operation in &deletes {
+                source.apply_operation(operation.clone()).unwrap();
+            }
+            operations.extend(deletes);
+            let num_operations = operations.len();
+
+            let mut target = base;
+            operations.reverse();
+            let result = target.apply_batch(operations).unwrap();
+            target.validate_integrity().unwrap();
+            assert!(result.is_complete());
+            assert_eq!(result.applied, num_operations);
+            assert_eq!(target.to_string(), source.to_string());
+        }
+
+        #[test]
+        fn batches_return_operations_with_missing_anchors() {
+            let mut id_generator = TestIdGenerator::new();
+            let mut linear = LinearString::new(id_generator.next().unwrap());
+            let unknown = id_generator.next_with_zero_index().unwrap();
+            let orphan_id = id_generator.next_with_zero_index().unwrap();
+            let orphan = DataOperation::Insert {
+                id: orphan_id.clone(),
+                pred: unknown.clone(),
+                succ: unknown,
+                value: "lost".to_owned(),
+            };
+            let orphan_child = DataOperation::Insert {
+                id: id_generator.next_with_zero_index().unwrap(),
+                pred: orphan_id.clone(),
+                succ: orphan_id,
+                value: "also lost".to_owned(),
+            };
+            let fitting = linear.ids_after_head().insert_operation(
+                id_generator.next_with_zero_index().unwrap(),
+                "kept".to_owned(),
+            );
+
+            let result = linear
+                .apply_batch(vec![orphan_child.clone(), orphan.clone(), fitting])
+                .unwrap();
+            assert_eq!(result.applied, 1);
+            assert_eq!(result.blocked, vec![orphan, orphan_child]);
+            assert_eq!(linear.to_string(), "kept");
+        }
+
+        type WorkspaceBatches = Vec<(&'static str, Vec<DataOperation<IdWithIndex<u32>, String>>)>;
+
+        /// Two documents with a few reversed appends each, plus their batches split in two.
+        fn workspace_sync() -> (
+            HashMap<&'static str, LinearString<u32>>,
+            WorkspaceBatches,
+            HashMap<&'static str, String>,
+        ) {
+            let mut id_generator = TestIdGenerator::new();
+            let mut documents = HashMap::new();
+            let mut batches = Vec::new();
+            let mut expected = HashMap::new();
+            for key in ["notes", "todo"] {
+                let base = LinearString::new(id_generator.next().unwrap());
+                let mut source = base.clone();
+                let mut operations = Vec::new();
+                for s in TEST_VALUES {
+                    let operation = source.ids_before_end().insert_operation(
+                        id_generator.next_with_zero_index().unwrap(),
+                        format!("{key}:{s}"),
+                    );
+                    source.apply_operation(operation.clone()).unwrap();
+                    operations.push(operation);
+                }
+                operations.reverse();
+                let later = operations.split_off(operations.len() / 2);
+                batches.push((key, operations));
+                batches.push((key, later));
+                documents.insert(key, base);
+                expected.insert(key, source.to_string());
+            }
+            (documents, batches, expected)
+        }
+
+        #[test]
+        fn workspace_batches_apply_to_their_documents() {
+            let (mut documents, mut batches, expected) = workspace_sync();
+            let stray = batches[0].1[0].clone();
+            batches.push(("missing", vec![stray.clone()]));
+
+            let result = apply_workspace_batches(&mut documents, batches);
+            assert_eq!(result.documents.len(), 2);
+            for outcome in result.documents.values() {
+                assert!(outcome.as_ref().unwrap().is_complete());
+            }
+            assert_eq!(result.unknown, BTreeMap::from([("missing", vec![stray])]));
+            for (key, document) in &documents {
+                document.validate_integrity().unwrap();
+                assert_eq!(&document.to_string(), &expected[key]);
+            }
+        }
+
+        #[cfg(feature = "parallel")]
+        #[test]
+        fn parallel_workspace_batches_match_sequential() {
+            let (mut sequential, batches, _) = workspace_sync();
+            let mut parallel = sequential.clone();
+
+            let sequential_result = apply_workspace_batches(&mut sequential, batches.clone());
+            let parallel_result = par_apply_workspace_batches(&mut parallel, batches);
+            assert_eq!(parallel, sequential);
+            assert_eq!(
+                parallel_result.documents.keys().collect::<Vec<_>>(),
+                sequential_result.documents.keys().collect::<Vec<_>>()
+            );
+            for (parallel, sequential) in parallel_result
+                .documents
+                .values()
+                .zip(sequential_result.documents.values())
+            {
+                assert_eq!(parallel.as_ref().unwrap(), sequential.as_ref().unwrap());
+            }
+        }
+
         #[test]
         fn ascii_appends() {
             let mut id_generator = TestIdGenerator::new();
@@ -349,6 +714,7 @@ pub(crate) mod tests {
             let mut reference = String::new();
             let mut linear = LinearString::new(id_generator.next().unwrap());
             assert_eq!(reference, linear.to_string());
+            assert_eq!(linear.node_count(), 2);
             for s in TEST_VALUES {
                 reference.push_str(s);
                 linear.append(id_generator.next_with_zero_index().unwrap(), s.to_string());
@@ -356,6 +722,7 @@ pub(crate) mod tests {
                 assert_eq!(linear.to_string(), reference);
                 assert_eq!(linear.len(), reference.len());
             }
+            assert_eq!(linear.node_count(), 2 + TEST_VALUES.len());
         }
 
         #[test]
@@ -623,6 +990,38 @@ pub(crate) mod tests {
             });
         }
 
+        #[test]
+        fn iter_with_ids_correlates_text_and_ids() {
+            let mut id_generator = TestIdGenerator::new();
+            let base_id = id_generator.next().unwrap();
+            let mut linear = LinearString::with_value("A test".to_string(), base_id);
+            let ids_for_space = linear.ids_in_range(1..=1).unwrap();
+            assert_eq!(ids_for_space.delete(&mut linear), Ok(()));
+
+            let runs: Vec<_> = linear.iter_with_ids().collect();
+            assert_eq!(
+                runs,
+                vec![
+                    (
+                        IdWithIndex {
+                            id: base_id,
+                            index: 1
+                        },
+                        "A"
+                    ),
+                    (
+                        IdWithIndex {
+                            id: base_id,
+                            index: 3
+                        },
+                        "test"
+                    ),
+                ]
+            );
+            let text: String = runs.iter().map(|(_, run)| *run).collect();
+            assert_eq!(text, linear.to_string());
+        }
+
         #[test]
         fn illegal_deletes() {
             let mut id_generator = TestIdGenerator::new();
@@ -725,6 +1124,33 @@ pub(crate) mod tests {
             assert_eq!(r1, r2);
             assert_eq!(r1.to_string(), r2.to_string());
         }
+
+        #[test]
+        fn equality_compares_replicated_structure() {
+            use crate::text::linear_diff;
+            use std::hash::{BuildHasher, RandomState};
+
+            let base = LinearString::with_value("hello world".to_owned(), 0u32);
+            let left = linear_diff(&base, "hello brave world", &mut (100u32..)).unwrap();
+            let right = linear_diff(&base, "hello world!", &mut (200u32..)).unwrap();
+
+            let mut left_first = base.clone();
+            left.clone().apply_to(&mut left_first).unwrap();
+            right.clone().apply_to(&mut left_first).unwrap();
+            let mut right_first = base.clone();
+            right.apply_to(&mut right_first).unwrap();
+            left.apply_to(&mut right_first).unwrap();
+
+            assert!(left_first.structural_eq(&right_first));
+            assert_eq!(left_first, right_first);
+            let hasher = RandomState::new();
+            assert_eq!(hasher.hash_one(&left_first), hasher.hash_one(&right_first));
+
+            let retyped = LinearString::with_value(left_first.to_string(), 0u32);
+            assert!(left_first.content_eq(&retyped));
+            assert_ne!(left_first, retyped);
+            assert!(!base.content_eq(&left_first));
+        }
     }
 
     mod linear_word_string {
@@ -0,0 +1,335 @@
+//! Guidance for editors that show local edits before the replica has acknowledged them.
+//!
+//! An editor renders its document with its own unacknowledged operations already applied, while
+//! remote operations for the same text may still sit in the causal buffer, waiting for their
+//! dependencies. A [`LocalEcho`] previews the document with those buffered remote operations
+//! applied as far as they currently can be. Editors use it to move carets and selections to where
+//! they will end up once the remote operations land, to find where their own pending text ends up,
+//! and to warn before a local edit touches text that a remote edit is about to change.
+//!
+//! All positions are in graphemes, like [`LinearString::len`].
+
+use super::{CausalFrontier, LinearString, fmt};
+use crate::{IdWithIndex, InternalError, linear_data::DataOperation};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    ops::Range,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// One way a proposed local edit collides with buffered remote operations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EchoConflict<Id> {
+    /// Remote operations delete graphemes that the edit replaces.
+    ///
+    /// `range` is in positions of the current document.
+    RemoteDelete { range: Range<usize> },
+    /// Remote operations insert `text` inside the edited range, or right at the insertion point.
+    ///
+    /// `position` is the position in the current document that the text is inserted at.
+    RemoteInsert { position: usize, text: String },
+    /// A remote insert that cannot be placed yet is anchored on the edited text.
+    ///
+    /// It waits for operations that have not arrived, so where exactly it lands is unknown.
+    Unplaced { id: IdWithIndex<Id> },
+}
+
+/// Preview of a document with the buffered remote operations applied.
+#[derive(Clone, Debug)]
+pub struct LocalEcho<Id> {
+    /// Ids of the visible graphemes of the current document, in order.
+    current: Vec<IdWithIndex<Id>>,
+    current_positions: HashMap<IdWithIndex<Id>, usize>,
+    preview: LinearString<Id>,
+    /// Ids of the visible graphemes of the preview, in order.
+    preview_ids: Vec<IdWithIndex<Id>>,
+    preview_graphemes: Vec<String>,
+    preview_positions: HashMap<IdWithIndex<Id>, usize>,
+    /// Remote operations that still wait for their dependencies.
+    blocked: Vec<DataOperation<IdWithIndex<Id>, String>>,
+}
+impl<Id> LocalEcho<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    /// Preview `document`, which already includes the local unacknowledged operations, with
+    /// `buffered_remote` applied.
+    ///
+    /// Remote operations that cannot be applied yet are kept and only taken into account by
+    /// [`check_edit`](Self::check_edit).
+    ///
+    /// # Errors
+    ///
+    /// Fails if applying the remote operations to a copy of `document` hits an internal
+    /// inconsistency.
+    pub fn preview(
+        document: &LinearString<Id>,
+        buffered_remote: Vec<DataOperation<IdWithIndex<Id>, String>>,
+    ) -> Result<Self, InternalError> {
+        let mut preview = document.clone();
+        let blocked = preview.apply_batch(buffered_remote)?.blocked;
+        let current: Vec<_> = visible_graphemes(document)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        let (preview_ids, preview_graphemes): (Vec<_>, Vec<_>) =
+            visible_graphemes(&preview).into_iter().unzip();
+        Ok(Self {
+            current_positions: positions(&current),
+            current,
+            preview_positions: positions(&preview_ids),
+            preview_ids,
+            preview_graphemes,
+            preview,
+            blocked,
+        })
+    }
+
+    /// The document as it will look once the buffered remote operations are applied.
+    #[must_use]
+    pub fn preview_document(&self) -> &LinearString<Id> {
+        &self.preview
+    }
+
+    /// Remote operations that could not be applied to the preview yet.
+    #[must_use]
+    pub fn blocked_operations(&self) -> &[DataOperation<IdWithIndex<Id>, String>] {
+        &self.blocked
+    }
+
+    /// Map a caret `position` in the current document to the preview.
+    ///
+    /// The caret stays right after the grapheme it followed, so text typed at the caret keeps
+    /// extending the same word. If remote operations delete that grapheme, it follows the closest
+    /// grapheme before it that survives.
+    #[must_use]
+    pub fn remap_position(&self, position: usize) -> usize {
+        let position = position.min(self.current.len());
+        self.current[..position]
+            .iter()
+            .rev()
+            .find_map(|id| self.preview_positions.get(id))
+            .map_or(0, |preview_position| preview_position + 1)
+    }
+
+    /// Map a selection in the current document to the preview.
+    #[must_use]
+    pub fn remap_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.remap_position(range.start);
+        let end = self.remap_position(range.end).max(start);
+        start..end
+    }
+
+    /// The ranges of the preview that hold text inserted by the local unacknowledged operations
+    /// in `local_pending`, in document order.
+    #[must_use]
+    pub fn pending_local_ranges(
+        &self,
+        local_pending: &impl CausalFrontier<Id>,
+    ) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (position, id) in self.preview_ids.iter().enumerate() {
+            if !local_pending.includes(&id.id) {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last) if last.end == position => last.end += 1,
+                _ => ranges.push(position..(position + 1)),
+            }
+        }
+        ranges
+    }
+
+    /// Check whether replacing `range` of the current document would collide with the buffered
+    /// remote operations.
+    ///
+    /// An empty `range` checks an insertion at `range.start`. No conflicts means the edit can be
+    /// shown optimistically without surprising the user once the remote operations land.
+    #[must_use]
+    pub fn check_edit(&self, range: Range<usize>) -> Vec<EchoConflict<Id>> {
+        let end = range.end.min(self.current.len());
+        let start = range.start.min(end);
+        let mut conflicts = Vec::new();
+
+        let mut deleted_run: Option<Range<usize>> = None;
+        for position in start..end {
+            if self.preview_positions.contains_key(&self.current[position]) {
+                if let Some(run) = deleted_run.take() {
+                    conflicts.push(EchoConflict::RemoteDelete { range: run });
+                }
+                continue;
+            }
+            match &mut deleted_run {
+                Some(run) => run.end = position + 1,
+                None => deleted_run = Some(position..(position + 1)),
+            }
+        }
+        if let Some(run) = deleted_run {
+            conflicts.push(EchoConflict::RemoteDelete { range: run });
+        }
+
+        self.collect_remote_inserts(start, end, &mut conflicts);
+
+        let anchors: HashSet<&IdWithIndex<Id>> = self.current
+            [start.saturating_sub(1)..(end + 1).min(self.current.len())]
+            .iter()
+            .collect();
+        for operation in &self.blocked {
+            if let DataOperation::Insert { id, pred, succ, .. } = operation
+                && (anchors.contains(pred) || anchors.contains(succ))
+            {
+                conflicts.push(EchoConflict::Unplaced { id: id.clone() });
+            }
+        }
+        conflicts
+    }
+
+    /// Collect remote inserts that the preview places between the graphemes surrounding
+    /// `start..end` of the current document.
+    fn collect_remote_inserts(
+        &self,
+        start: usize,
+        end: usize,
+        conflicts: &mut Vec<EchoConflict<Id>>,
+    ) {
+        let preview_start = self.remap_position(start);
+        let preview_end = self.current[end..]
+            .iter()
+            .find_map(|id| self.preview_positions.get(id).copied())
+            .unwrap_or(self.preview_ids.len());
+        let mut position = start;
+        let mut inserted: Option<(usize, String)> = None;
+        for preview_position in preview_start..preview_end {
+            let id = &self.preview_ids[preview_position];
+            if let Some(current_position) = self.current_positions.get(id) {
+                if let Some((position, text)) = inserted.take() {
+                    conflicts.push(EchoConflict::RemoteInsert { position, text });
+                }
+                position = current_position + 1;
+                continue;
+            }
+            let (_, text) = inserted.get_or_insert_with(|| (position, String::new()));
+            text.push_str(&self.preview_graphemes[preview_position]);
+        }
+        if let Some((position, text)) = inserted {
+            conflicts.push(EchoConflict::RemoteInsert { position, text });
+        }
+    }
+}
+
+/// Every visible grapheme of `document` with its id, in order.
+fn visible_graphemes<Id>(document: &LinearString<Id>) -> Vec<(IdWithIndex<Id>, String)>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    let mut graphemes = Vec::with_capacity(document.len());
+    for (first, text) in document.iter_with_ids() {
+        let mut next = Some(first);
+        for grapheme in text.graphemes(true) {
+            let id = next.expect("a run never addresses more graphemes than its id has indices");
+            next = id.checked_increment();
+            graphemes.push((id, grapheme.to_owned()));
+        }
+    }
+    graphemes
+}
+
+fn positions<Id>(ids: &[IdWithIndex<Id>]) -> HashMap<IdWithIndex<Id>, usize>
+where
+    Id: Clone + Eq + Hash,
+{
+    ids.iter()
+        .enumerate()
+        .map(|(position, id)| (id.clone(), position))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::linear_diff;
+
+    const LOCAL_IDS: Range<u32> = 100..200;
+    const REMOTE_IDS: Range<u32> = 200..300;
+    const LATER_REMOTE_IDS: Range<u32> = 300..400;
+
+    fn remote_operations(
+        base: &LinearString<u32>,
+        changed: &str,
+        ids: Range<u32>,
+    ) -> Vec<DataOperation<IdWithIndex<u32>, String>> {
+        linear_diff(base, changed, &mut ids.clone())
+            .unwrap()
+            .into_operations()
+    }
+
+    #[test]
+    fn remote_inserts_shift_positions_and_collide_at_the_caret() {
+        let base = LinearString::with_value("hello world".to_owned(), 0);
+        let remote = remote_operations(&base, "hello world!", REMOTE_IDS);
+        let mut document = base;
+        linear_diff(&document, "hello brave world", &mut LOCAL_IDS.clone())
+            .unwrap()
+            .apply_to(&mut document)
+            .unwrap();
+
+        let echo = LocalEcho::preview(&document, remote).unwrap();
+        assert_eq!(echo.preview_document().to_string(), "hello brave world!");
+        assert!(echo.blocked_operations().is_empty());
+
+        assert_eq!(echo.remap_position(document.len()), document.len());
+        assert_eq!(echo.remap_range(0..5), 0..5);
+        assert_eq!(
+            echo.check_edit(document.len()..document.len()),
+            vec![EchoConflict::RemoteInsert {
+                position: document.len(),
+                text: "!".to_owned(),
+            }]
+        );
+        assert!(echo.check_edit(0..5).is_empty());
+
+        let pending = echo.pending_local_ranges(&|id: &u32| LOCAL_IDS.contains(id));
+        let preview = echo.preview_document().to_string();
+        let pending_text: String = pending
+            .iter()
+            .map(|range| preview[range.clone()].to_owned())
+            .collect();
+        assert_eq!(pending_text.trim(), "brave");
+    }
+
+    #[test]
+    fn remote_deletes_move_carets_back_and_collide_with_replacements() {
+        let document = LinearString::with_value("hello world".to_owned(), 0);
+        let remote = remote_operations(&document, "hello", REMOTE_IDS);
+
+        let echo = LocalEcho::preview(&document, remote).unwrap();
+        assert_eq!(echo.preview_document().to_string(), "hello");
+        assert_eq!(echo.remap_position(8), 5);
+        assert_eq!(echo.remap_range(2..9), 2..5);
+        assert_eq!(
+            echo.check_edit(6..11),
+            vec![EchoConflict::RemoteDelete { range: 6..11 }]
+        );
+        assert!(echo.check_edit(0..3).is_empty());
+    }
+
+    #[test]
+    fn blocked_remote_inserts_are_reported_where_they_anchor() {
+        let document = LinearString::with_value("hello world".to_owned(), 0);
+        let first_remote = remote_operations(&document, "hello, world", REMOTE_IDS);
+        let mut remote_state = document.clone();
+        remote_state.apply_batch(first_remote).unwrap();
+        // Depends on the first remote edit, which has not arrived yet.
+        let second_remote = remote_operations(&remote_state, "hello,, world", LATER_REMOTE_IDS);
+
+        let echo = LocalEcho::preview(&document, second_remote).unwrap();
+        assert_eq!(echo.preview_document().to_string(), "hello world");
+        assert_eq!(echo.blocked_operations().len(), 1);
+        assert!(matches!(
+            echo.check_edit(5..5).as_slice(),
+            [EchoConflict::Unplaced { .. }]
+        ));
+        assert!(echo.check_edit(9..11).is_empty());
+    }
+}
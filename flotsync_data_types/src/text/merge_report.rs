@@ -0,0 +1,348 @@
+//! Reports on where concurrent edits met in a merged [`LinearString`].
+
+use super::{LinearString, fmt};
+use crate::{
+    IdWithIndex,
+    snapshot::{SnapshotHeader, SnapshotNodeRef, SnapshotSink},
+};
+use std::{convert::Infallible, hash::Hash, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Describes which operations one side of a merge had seen.
+///
+/// For ids made of a member position and a per-member version, such as `UpdateId`, this is
+/// typically a version vector check like
+/// `|id| frontier.version_at(id.node_index as usize) >= id.version`.
+pub trait CausalFrontier<Id> {
+    /// Whether the operation that introduced `id` is included in this frontier.
+    fn includes(&self, id: &Id) -> bool;
+}
+impl<Id, F> CausalFrontier<Id> for F
+where
+    F: Fn(&Id) -> bool,
+{
+    fn includes(&self, id: &Id) -> bool {
+        self(id)
+    }
+}
+
+/// Which side of a merge inserted a piece of text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MergeSide {
+    /// Included in both frontiers, or in neither.
+    Common,
+    /// Only included in the left frontier.
+    Left,
+    /// Only included in the right frontier.
+    Right,
+}
+
+/// A maximal run of visible text inserted by the same side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeSegment {
+    pub side: MergeSide,
+    pub text: String,
+}
+
+/// A region of the merged text where both sides inserted text with no common text in between.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictRegion {
+    /// Position of the region in the merged text, in UTF-8 graphemes.
+    pub range: Range<usize>,
+    /// The merged text of the region, as it appears in the document.
+    pub merged: String,
+    /// The text in the region that only the left side inserted, in document order.
+    pub left: String,
+    /// The text in the region that only the right side inserted, in document order.
+    pub right: String,
+}
+
+/// Where the edits of two concurrent frontiers ended up in a merged [`LinearString`].
+///
+/// Only insertions can be attributed to a side. Tombstones do not record which operation
+/// deleted them, so deletions never show up in a report, but they also do not separate
+/// insertions that end up next to each other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeReport {
+    segments: Vec<MergeSegment>,
+    conflicts: Vec<ConflictRegion>,
+}
+impl MergeReport {
+    /// The visible text of the merged document, split by the side that inserted it.
+    #[must_use]
+    pub fn segments(&self) -> &[MergeSegment] {
+        &self.segments
+    }
+
+    /// All regions in which both sides edited, in document order.
+    #[must_use]
+    pub fn conflicts(&self) -> &[ConflictRegion] {
+        &self.conflicts
+    }
+
+    #[must_use]
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+
+    /// Render the merged text with Git-style conflict markers around every conflict region.
+    ///
+    /// Markers are always placed on their own lines, so a newline is added before a marker
+    /// that would otherwise start in the middle of a line.
+    #[must_use]
+    pub fn render_conflict_markers(&self, left_label: &str, right_label: &str) -> String {
+        let mut output = String::new();
+        let mut region_texts: Option<(String, String)> = None;
+        for segment in &self.segments {
+            match segment.side {
+                MergeSide::Common => {
+                    if let Some((left, right)) = region_texts.take() {
+                        write_region(&mut output, &left, &right, left_label, right_label);
+                    }
+                    output.push_str(&segment.text);
+                }
+                MergeSide::Left => {
+                    region_texts
+                        .get_or_insert_default()
+                        .0
+                        .push_str(&segment.text);
+                }
+                MergeSide::Right => {
+                    region_texts
+                        .get_or_insert_default()
+                        .1
+                        .push_str(&segment.text);
+                }
+            }
+        }
+        if let Some((left, right)) = region_texts {
+            write_region(&mut output, &left, &right, left_label, right_label);
+        }
+        output
+    }
+}
+
+/// Compare the visible text of `merged` against two causal frontiers.
+///
+/// `merged` should contain the operations of both frontiers. Text inserted by operations
+/// outside of both frontiers is reported as [`MergeSide::Common`].
+pub fn merge_report<Id>(
+    merged: &LinearString<Id>,
+    left: &impl CausalFrontier<Id>,
+    right: &impl CausalFrontier<Id>,
+) -> MergeReport
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    let mut collector = SegmentCollector {
+        left,
+        right,
+        segments: Vec::new(),
+    };
+    let Ok(()) = merged.encode_snapshot(&mut collector);
+    let segments = collector.segments;
+
+    let mut conflicts = Vec::new();
+    let mut position = 0;
+    let mut run_start = 0;
+    while run_start < segments.len() {
+        if segments[run_start].side == MergeSide::Common {
+            position += grapheme_len(&segments[run_start].text);
+            run_start += 1;
+            continue;
+        }
+        let run_end = segments[run_start..]
+            .iter()
+            .position(|segment| segment.side == MergeSide::Common)
+            .map_or(segments.len(), |offset| run_start + offset);
+        let run = &segments[run_start..run_end];
+        let run_len: usize = run.iter().map(|segment| grapheme_len(&segment.text)).sum();
+        if run.iter().any(|segment| segment.side == MergeSide::Left)
+            && run.iter().any(|segment| segment.side == MergeSide::Right)
+        {
+            conflicts.push(ConflictRegion {
+                range: position..(position + run_len),
+                merged: run.iter().map(|segment| segment.text.as_str()).collect(),
+                left: side_text(run, MergeSide::Left),
+                right: side_text(run, MergeSide::Right),
+            });
+        }
+        position += run_len;
+        run_start = run_end;
+    }
+
+    MergeReport {
+        segments,
+        conflicts,
+    }
+}
+
+/// Snapshot sink that turns visible nodes into coalesced [`MergeSegment`]s.
+struct SegmentCollector<'a, L, R> {
+    left: &'a L,
+    right: &'a R,
+    segments: Vec<MergeSegment>,
+}
+impl<Id, L, R> SnapshotSink<IdWithIndex<Id>, str> for SegmentCollector<'_, L, R>
+where
+    L: CausalFrontier<Id>,
+    R: CausalFrontier<Id>,
+{
+    type Error = Infallible;
+
+    fn begin(&mut self, _header: SnapshotHeader) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn node(
+        &mut self,
+        _index: usize,
+        node: SnapshotNodeRef<'_, IdWithIndex<Id>, str>,
+    ) -> Result<(), Self::Error> {
+        let Some(value) = node.value else {
+            return Ok(());
+        };
+        if node.deleted || value.is_empty() {
+            return Ok(());
+        }
+        let side = match (
+            self.left.includes(&node.id.id),
+            self.right.includes(&node.id.id),
+        ) {
+            (true, false) => MergeSide::Left,
+            (false, true) => MergeSide::Right,
+            (true, true) | (false, false) => MergeSide::Common,
+        };
+        match self.segments.last_mut() {
+            Some(last) if last.side == side => last.text.push_str(value),
+            _ => self.segments.push(MergeSegment {
+                side,
+                text: value.to_owned(),
+            }),
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+fn grapheme_len(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+fn side_text(run: &[MergeSegment], side: MergeSide) -> String {
+    run.iter()
+        .filter(|segment| segment.side == side)
+        .map(|segment| segment.text.as_str())
+        .collect()
+}
+
+fn write_region(output: &mut String, left: &str, right: &str, left_label: &str, right_label: &str) {
+    if left.is_empty() || right.is_empty() {
+        // Only one side edited here, so there is nothing to mark.
+        output.push_str(left);
+        output.push_str(right);
+        return;
+    }
+    start_line(output);
+    output.push_str("<<<<<<< ");
+    output.push_str(left_label);
+    output.push('\n');
+    output.push_str(left);
+    start_line(output);
+    output.push_str("=======\n");
+    output.push_str(right);
+    start_line(output);
+    output.push_str(">>>>>>> ");
+    output.push_str(right_label);
+    output.push('\n');
+}
+
+fn start_line(output: &mut String) {
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::linear_diff;
+
+    const LEFT_IDS: Range<u32> = 100..200;
+    const RIGHT_IDS: Range<u32> = 200..300;
+
+    fn left_frontier(id: &u32) -> bool {
+        !RIGHT_IDS.contains(id)
+    }
+
+    fn right_frontier(id: &u32) -> bool {
+        !LEFT_IDS.contains(id)
+    }
+
+    fn merge(base: &str, left: &str, right: &str) -> LinearString<u32> {
+        let base = LinearString::with_value(base.to_owned(), 0);
+        let left_diff = linear_diff(&base, left, &mut LEFT_IDS.clone()).unwrap();
+        let right_diff = linear_diff(&base, right, &mut RIGHT_IDS.clone()).unwrap();
+        let mut merged = base;
+        left_diff.apply_to(&mut merged).unwrap();
+        right_diff.apply_to(&mut merged).unwrap();
+        merged
+    }
+
+    #[test]
+    fn separate_edits_do_not_conflict() {
+        let merged = merge("hello world", "hello brave world", "hello world!");
+        let report = merge_report(&merged, &left_frontier, &right_frontier);
+
+        assert!(!report.has_conflicts());
+        assert_eq!(
+            report
+                .segments()
+                .iter()
+                .map(|segment| segment.side)
+                .collect::<Vec<_>>(),
+            vec![
+                MergeSide::Common,
+                MergeSide::Left,
+                MergeSide::Common,
+                MergeSide::Right
+            ]
+        );
+        assert_eq!(
+            report.render_conflict_markers("left", "right"),
+            merged.to_string()
+        );
+    }
+
+    #[test]
+    fn concurrent_inserts_at_the_same_position_conflict() {
+        let merged = merge("hello world", "hello brave world", "hello cruel world");
+        let report = merge_report(&merged, &left_frontier, &right_frontier);
+
+        let [conflict] = report.conflicts() else {
+            panic!(
+                "Expected exactly one conflict, got {:?}",
+                report.conflicts()
+            );
+        };
+        assert_eq!(conflict.left.trim(), "brave");
+        assert_eq!(conflict.right.trim(), "cruel");
+        let merged_text = merged.to_string();
+        let region: String = merged_text
+            .graphemes(true)
+            .skip(conflict.range.start)
+            .take(conflict.range.len())
+            .collect();
+        assert_eq!(region, conflict.merged);
+
+        let rendered = report.render_conflict_markers("alice", "bob");
+        assert!(rendered.starts_with("hello"));
+        assert!(rendered.contains("<<<<<<< alice\n"));
+        assert!(rendered.contains("\n=======\n"));
+        assert!(rendered.contains(">>>>>>> bob\n"));
+        assert!(rendered.ends_with("world"));
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{
     InternalSnafu,
     linear_data::{
+        ApplyFailure,
         Composite,
         DataOperation,
         IdGeneratorWithIndex,
+        IdRange,
         IdWithIndex,
         LinearData,
         VecCoalescedLinearDataIter,
@@ -17,7 +19,48 @@ use unicode_segmentation::{Graphemes, UnicodeSegmentation};
 mod linear_string;
 pub use linear_string::{LinearString, LinearStringIter, NodeIdRangeString};
 mod grapheme_string;
-use grapheme_string::GraphemeString;
+pub use grapheme_string::{GraphemeString, GraphemeStringBuilder};
+mod annotations;
+pub use annotations::{
+    AnnotationOperation,
+    AnnotationRange,
+    Annotations,
+    ResolvedAnnotation,
+    StyledSegment,
+};
+mod export;
+pub use export::{
+    AnchorMap,
+    ImportError,
+    SidecarParseError,
+    TextAnchor,
+    TextExport,
+    TextFormat,
+    export_markdown,
+    export_text,
+};
+mod merge_report;
+pub use merge_report::{
+    CausalFrontier,
+    ConflictRegion,
+    MergeReport,
+    MergeSegment,
+    MergeSide,
+    merge_report,
+};
+mod stats;
+pub use stats::TextStats;
+mod local_echo;
+pub use local_echo::{EchoConflict, LocalEcho};
+mod suggestions;
+pub use suggestions::{
+    SuggestedInsert,
+    Suggestion,
+    SuggestionKind,
+    SuggestionOperation,
+    SuggestionStatus,
+    TrackedChanges,
+};
 
 use crate::InternalError;
 
@@ -25,6 +68,7 @@ use crate::InternalError;
 mod text_diff;
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum ApplyError<Id>
 where
     Id: fmt::Display,
@@ -55,7 +99,11 @@ where
         let mut iter = self.operations.into_iter();
 
         for op in iter.by_ref() {
-            if let Err(op) = target.apply_operation(op) {
+            if let Err(failure) = target.apply_operation(op) {
+                let op = match failure {
+                    ApplyFailure::Rejected { operation } => operation,
+                    ApplyFailure::Internal { source, .. } => return Err(source.into()),
+                };
                 let (lower, _) = iter.size_hint();
                 let mut remaining = Vec::with_capacity(lower + 1);
                 remaining.push(op);
@@ -82,7 +130,7 @@ where
                 DataOperation::Insert { id, .. } => {
                     ids.insert(id.id.clone());
                 }
-                DataOperation::Delete { .. } => (), // ignore
+                DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => (), // ignore
             }
         }
         ids
@@ -114,7 +162,12 @@ where
     pub fn num_delete_operations(&self) -> usize {
         self.operations
             .iter()
-            .filter(|op| matches!(op, DataOperation::Delete { .. }))
+            .filter(|op| {
+                matches!(
+                    op,
+                    DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. }
+                )
+            })
             .count()
     }
 
@@ -122,7 +175,7 @@ where
     pub fn values_inserted(&self) -> impl Iterator<Item = &str> {
         self.operations.iter().filter_map(|op| match op {
             DataOperation::Insert { value, .. } => Some(value.as_str()),
-            DataOperation::Delete { .. } => None,
+            DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => None,
         })
     }
 
@@ -149,6 +202,11 @@ where
                     let last = end.as_ref().unwrap_or(start);
                     writeln!(f, "@@ {start} @@ --- @@ {last} @@")?;
                 }
+                DataOperation::DeleteRanges { ranges } => {
+                    for IdRange { start, end } in ranges {
+                        writeln!(f, "@@ {start} @@ --- @@ {end} @@")?;
+                    }
+                }
             }
         }
         Ok(())
@@ -156,6 +214,7 @@ where
 }
 
 #[derive(Debug, Snafu)]
+#[non_exhaustive]
 pub enum DiffError {
     #[snafu(display("The id generator did not produce sufficient ids to complete the diff."))]
     IdsExhausted,
@@ -192,7 +251,6 @@ where
     // Convert the TextChange to DataOperations over `base`.
     let mut operations: Vec<DataOperation<IdWithIndex<Id>, String>> =
         Vec::with_capacity(basic_diff.len());
-    let mut pending_reserved_indices: Option<usize> = None;
     for change in basic_diff {
         match change {
             text_diff::TextChange::Insert { at, value } => {
@@ -226,18 +284,13 @@ where
                     }
                 };
                 let value_graphemes = GraphemeString::new(value);
-                let op_id = if let Some(skip) = pending_reserved_indices {
-                    id_with_index_generator
-                        .nth(skip)
-                        .context(IdsExhaustedSnafu)?
-                } else {
-                    id_with_index_generator.next().context(IdsExhaustedSnafu)?
-                };
                 require!(
-                    op_id.can_address(value_graphemes.len()),
+                    u32::try_from(value_graphemes.len().saturating_sub(1)).is_ok(),
                     IndexExhaustedSnafu.build()
                 );
-                pending_reserved_indices = Some(value_graphemes.len().saturating_sub(1));
+                let op_id = id_with_index_generator
+                    .reserve(value_graphemes.len())
+                    .context(IdsExhaustedSnafu)?;
                 operations.push(node_insert_ids.insert_operation(op_id, value_graphemes.unwrap()));
             }
             text_diff::TextChange::Delete { at, len } => {
@@ -258,7 +311,8 @@ where
 #[cfg(test)]
 mod tests {
     use crate::{
-        linear_data::{DataOperation, tests::TestIdGenerator},
+        linear_data::DataOperation,
+        test_support::ids::TestIdGenerator,
         text::{
             LinearString,
             LinearStringDiff,
@@ -365,7 +419,7 @@ mod tests {
             .into_iter()
             .filter_map(|operation| match operation {
                 DataOperation::Insert { id, .. } => Some(id.index),
-                DataOperation::Delete { .. } => None,
+                DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => None,
             })
             .collect::<Vec<_>>();
         assert_eq!(insert_indices, vec![0, 1]);
@@ -390,7 +444,7 @@ mod tests {
             .into_iter()
             .filter_map(|operation| match operation {
                 DataOperation::Insert { id, .. } => Some(id.index),
-                DataOperation::Delete { .. } => None,
+                DataOperation::Delete { .. } | DataOperation::DeleteRanges { .. } => None,
             })
             .collect::<Vec<_>>();
         assert_eq!(insert_indices, vec![0, 2]);
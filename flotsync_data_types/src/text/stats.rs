@@ -0,0 +1,326 @@
+//! Size statistics and the line index of a [`LinearString`], updated with every applied operation.
+//!
+//! Words and lines are counted per grapheme, so they never split what a user sees as a single
+//! character. Whether two graphemes belong to the same word depends on their neighbours, so the
+//! summary counts adjacent word graphemes instead of words, which only changes around an edit.
+//! Line breaks are kept as sorted grapheme positions, so an edit only shifts the line breaks
+//! after it, without looking at any text.
+
+#[cfg(doc)]
+use super::LinearString;
+use super::grapheme_string::GraphemeString;
+use crate::linear_data::{ChangeContext, Composite, ValueSummary};
+use flotsync_utils::option_when;
+
+/// Size statistics of the visible text of a [`LinearString`], see [`LinearString::text_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextStats {
+    /// The length of the text in UTF-8 bytes.
+    pub bytes: usize,
+    /// The number of graphemes, the same as [`LinearString::len`].
+    pub graphemes: usize,
+    /// The number of maximal runs of graphemes that are not whitespace.
+    pub words: usize,
+    /// The number of lines, like [`str::lines`] counts them.
+    ///
+    /// A line break at the very end does not start another line, and empty text has no lines.
+    pub lines: usize,
+}
+
+/// The counters [`TextStats`] are derived from, together with the positions of all line breaks.
+#[derive(Clone, Debug, Default)]
+pub(super) struct TextSummary {
+    bytes: usize,
+    graphemes: usize,
+    /// Grapheme positions of all line breaks, in increasing order.
+    line_breaks: Vec<usize>,
+    word_graphemes: usize,
+    /// Adjacent pairs of word graphemes, each of which continues a word instead of starting one.
+    word_continuations: usize,
+}
+impl TextSummary {
+    pub(super) fn stats(&self) -> TextStats {
+        let ends_with_line_break = self
+            .graphemes
+            .checked_sub(1)
+            .is_some_and(|last| self.line_breaks.last() == Some(&last));
+        let lines = if self.graphemes == 0 || ends_with_line_break {
+            self.line_breaks.len()
+        } else {
+            self.line_breaks.len() + 1
+        };
+        TextStats {
+            bytes: self.bytes,
+            graphemes: self.graphemes,
+            words: self.word_graphemes - self.word_continuations,
+            lines,
+        }
+    }
+
+    /// The grapheme position at which `line` starts, see [`LinearString::pos_of_line`].
+    pub(super) fn pos_of_line(&self, line: usize) -> Option<usize> {
+        match line.checked_sub(1) {
+            None => Some(0),
+            Some(previous_line) => self
+                .line_breaks
+                .get(previous_line)
+                .map(|line_break| line_break + 1),
+        }
+    }
+
+    /// The line the grapheme `position` is on, see [`LinearString::line_of_pos`].
+    pub(super) fn line_of_pos(&self, position: usize) -> Option<usize> {
+        option_when!(
+            position <= self.graphemes,
+            self.line_breaks
+                .partition_point(|line_break| *line_break < position)
+        )
+    }
+
+    fn add(&mut self, contribution: Contribution, position: usize, len: usize) {
+        self.bytes += contribution.bytes;
+        self.graphemes += len;
+        self.word_graphemes += contribution.word_graphemes;
+        self.word_continuations = self
+            .word_continuations
+            .checked_add_signed(contribution.word_continuations)
+            .expect("word continuations must not be negative");
+
+        let index = self
+            .line_breaks
+            .partition_point(|line_break| *line_break < position);
+        for line_break in &mut self.line_breaks[index..] {
+            *line_break += len;
+        }
+        let inserted = contribution
+            .line_break_offsets
+            .into_iter()
+            .map(|offset| position + offset);
+        self.line_breaks.splice(index..index, inserted);
+    }
+
+    fn subtract(&mut self, contribution: &Contribution, position: usize, len: usize) {
+        self.bytes -= contribution.bytes;
+        self.graphemes -= len;
+        self.word_graphemes -= contribution.word_graphemes;
+        self.word_continuations = self
+            .word_continuations
+            .checked_add_signed(-contribution.word_continuations)
+            .expect("word continuations must not be negative");
+
+        let start = self
+            .line_breaks
+            .partition_point(|line_break| *line_break < position);
+        let end = start + contribution.line_break_offsets.len();
+        self.line_breaks.drain(start..end);
+        for line_break in &mut self.line_breaks[start..] {
+            *line_break -= len;
+        }
+    }
+}
+impl ValueSummary<GraphemeString> for TextSummary {
+    fn insert<'a, F>(&mut self, value: &GraphemeString, context: F)
+    where
+        F: FnOnce() -> ChangeContext<'a, str>,
+    {
+        if value.is_empty() {
+            return;
+        }
+        let context = context();
+        let contribution = Contribution::of(value, context);
+        self.add(contribution, context.position, value.len());
+    }
+
+    fn remove<'a, F>(&mut self, value: &GraphemeString, context: F)
+    where
+        F: FnOnce() -> ChangeContext<'a, str>,
+    {
+        if value.is_empty() {
+            return;
+        }
+        let context = context();
+        let contribution = Contribution::of(value, context);
+        self.subtract(&contribution, context.position, value.len());
+    }
+}
+
+/// How much a value adds to the counters of a [`TextSummary`] where it is.
+struct Contribution {
+    bytes: usize,
+    /// Grapheme offsets of the line breaks within the value.
+    line_break_offsets: Vec<usize>,
+    word_graphemes: usize,
+    /// Negative if the value separates two words that were joined without it.
+    word_continuations: isize,
+}
+impl Contribution {
+    fn of(value: &GraphemeString, context: ChangeContext<'_, str>) -> Self {
+        let mut contribution = Self {
+            bytes: value.as_str().len(),
+            line_break_offsets: Vec::new(),
+            word_graphemes: 0,
+            word_continuations: 0,
+        };
+        let before_is_word = context.before.is_some_and(is_word);
+        let after_is_word = context.after.is_some_and(is_word);
+        let mut previous_is_word = before_is_word;
+        for (offset, grapheme) in value.iter().enumerate() {
+            let grapheme_is_word = is_word(grapheme);
+            if grapheme_is_word {
+                contribution.word_graphemes += 1;
+                if previous_is_word {
+                    contribution.word_continuations += 1;
+                }
+            }
+            if is_line_break(grapheme) {
+                contribution.line_break_offsets.push(offset);
+            }
+            previous_is_word = grapheme_is_word;
+        }
+        if previous_is_word && after_is_word {
+            contribution.word_continuations += 1;
+        }
+        // Without the value, the neighbours are adjacent to each other.
+        if before_is_word && after_is_word {
+            contribution.word_continuations -= 1;
+        }
+        contribution
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    !grapheme.chars().all(char::is_whitespace)
+}
+
+fn is_line_break(grapheme: &str) -> bool {
+    grapheme.ends_with('\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{LinearString, linear_diff};
+    use proptest::prelude::*;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    fn edit(document: &mut LinearString<u32>, changed: &str, ids: &mut impl Iterator<Item = u32>) {
+        linear_diff(document, changed, ids)
+            .unwrap()
+            .apply_to(document)
+            .unwrap();
+    }
+
+    fn recomputed(text: &str) -> TextStats {
+        let graphemes = text.graphemes(true).collect::<Vec<_>>();
+        TextStats {
+            bytes: text.len(),
+            graphemes: graphemes.len(),
+            words: graphemes
+                .chunk_by(|left, right| is_word(left) == is_word(right))
+                .filter(|run| is_word(run[0]))
+                .count(),
+            lines: text.lines().count(),
+        }
+    }
+
+    /// The start position of every line, including an empty last line after a final line break.
+    fn recomputed_line_starts(text: &str) -> Vec<usize> {
+        std::iter::once(0)
+            .chain(
+                text.graphemes(true)
+                    .enumerate()
+                    .filter(|(_, grapheme)| is_line_break(grapheme))
+                    .map(|(position, _)| position + 1),
+            )
+            .collect()
+    }
+
+    fn assert_line_index(document: &LinearString<u32>) {
+        let line_starts = recomputed_line_starts(&document.to_string());
+        for (line, start) in line_starts.iter().enumerate() {
+            assert_eq!(document.pos_of_line(line), Some(*start), "line {line}");
+        }
+        assert_eq!(document.pos_of_line(line_starts.len()), None);
+        for position in 0..=document.len() {
+            let line = line_starts.partition_point(|start| *start <= position) - 1;
+            assert_eq!(
+                document.line_of_pos(position),
+                Some(line),
+                "position {position}"
+            );
+        }
+        assert_eq!(document.line_of_pos(document.len() + 1), None);
+    }
+
+    #[test]
+    fn stats_follow_edits() {
+        let mut ids = 1u32..;
+        let mut document = LinearString::with_value("Hello wörld\n👋🏽 again".to_owned(), 0);
+        assert_eq!(
+            document.text_stats(),
+            TextStats {
+                bytes: 27,
+                graphemes: 19,
+                words: 4,
+                lines: 2,
+            }
+        );
+
+        // Joining two words and ending with a line break.
+        edit(&mut document, "Hello wörld\n👋🏽again\n", &mut ids);
+        assert_eq!(document.text_stats().words, 3);
+        assert_eq!(document.text_stats().lines, 2);
+
+        // Splitting a word.
+        edit(&mut document, "Hel lo wörld\n👋🏽again\n", &mut ids);
+        assert_eq!(document.text_stats().words, 4);
+
+        edit(&mut document, "", &mut ids);
+        assert_eq!(document.text_stats(), TextStats::default());
+    }
+
+    #[test]
+    fn line_index_follows_edits() {
+        let mut ids = 1u32..;
+        let mut document = LinearString::with_value("first\nsecond\nthird".to_owned(), 0);
+        assert_eq!(document.pos_of_line(1), Some(6));
+        assert_eq!(document.pos_of_line(2), Some(13));
+        assert_eq!(document.pos_of_line(3), None);
+        assert_eq!(document.line_of_pos(5), Some(0));
+        assert_eq!(document.line_of_pos(6), Some(1));
+        assert_eq!(document.line_of_pos(18), Some(2));
+
+        edit(&mut document, "first\nnew\nsecond\nthird\n", &mut ids);
+        assert_eq!(document.pos_of_line(2), Some(10));
+        assert_eq!(document.pos_of_line(4), Some(23));
+        assert_eq!(document.line_of_pos(23), Some(4));
+        assert_line_index(&document);
+
+        edit(&mut document, "first second\nthird\n", &mut ids);
+        assert_eq!(document.pos_of_line(1), Some(13));
+        assert_line_index(&document);
+
+        let empty = LinearString::new(0u32);
+        assert_eq!(empty.pos_of_line(0), Some(0));
+        assert_eq!(empty.line_of_pos(0), Some(0));
+        assert_eq!(empty.pos_of_line(1), None);
+    }
+
+    proptest! {
+        #[test]
+        fn incremental_stats_and_line_index_match_the_text(
+            versions in prop::collection::vec(
+                prop::collection::vec(prop::sample::select(vec!["a", "b", " ", "\n", "é", "🎉"]), 0..24),
+                1..8,
+            )
+        ) {
+            let mut ids = 1u32..;
+            let mut document = LinearString::new(0);
+            for version in versions {
+                edit(&mut document, &version.concat(), &mut ids);
+                prop_assert_eq!(document.text_stats(), recomputed(&document.to_string()));
+                assert_line_index(&document);
+            }
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Tracked changes on a [`LinearString`], which other members accept or reject.
+//!
+//! In tracked-changes mode a local insert still goes into the [`LinearString`], so that it has
+//! ids and replicates like any other text, but it is also recorded as a pending
+//! [`SuggestionKind::Insert`]. A local delete only records a pending [`SuggestionKind::Delete`]
+//! and leaves the text in place. Positions passed to [`TrackedChanges`] are therefore positions
+//! in the visible text of the [`LinearString`], which shows every suggestion.
+//!
+//! Accepting or rejecting a suggestion is itself a replicated payload update of the underlying
+//! [`Annotations`], so concurrent decisions converge like concurrent annotation edits. Because
+//! no decision deletes text, a rejected insert never has to be restored. The text with only
+//! accepted suggestions applied is rendered by [`TrackedChanges::accepted_text`].
+
+use super::{
+    AnnotationOperation,
+    AnnotationRange,
+    Annotations,
+    LinearString,
+    ResolvedAnnotation,
+    fmt,
+};
+use crate::{
+    IdWithIndex,
+    linear_data::{DataOperation, LinearData},
+};
+use std::{hash::Hash, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Whether a suggestion adds or removes text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SuggestionKind {
+    Insert,
+    Delete,
+}
+
+/// The decision on a suggestion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SuggestionStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+/// The payload of one tracked change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub status: SuggestionStatus,
+}
+impl Suggestion {
+    /// Whether the suggested text is part of [`TrackedChanges::accepted_text`].
+    #[must_use]
+    pub fn shows_text(&self) -> bool {
+        match self.kind {
+            SuggestionKind::Insert => self.status == SuggestionStatus::Accepted,
+            SuggestionKind::Delete => self.status != SuggestionStatus::Accepted,
+        }
+    }
+}
+
+/// Replicated changes to [`TrackedChanges`].
+pub type SuggestionOperation<Id> = AnnotationOperation<Id, Suggestion>;
+
+/// The operations produced by [`TrackedChanges::suggest_insert`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuggestedInsert<Id> {
+    /// The insert into the [`LinearString`].
+    pub text: DataOperation<IdWithIndex<Id>, String>,
+    /// The pending suggestion covering the inserted text.
+    pub suggestion: SuggestionOperation<Id>,
+}
+
+/// The suggested inserts and deletes on one [`LinearString`].
+///
+/// The same id requirements as for [`Annotations`] apply. A suggested insert uses its id both
+/// for the inserted text and for the suggestion, so it must also be unused in the text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedChanges<Id> {
+    suggestions: Annotations<Id, Suggestion>,
+}
+
+impl<Id> Default for TrackedChanges<Id> {
+    fn default() -> Self {
+        Self {
+            suggestions: Annotations::default(),
+        }
+    }
+}
+
+impl<Id> TrackedChanges<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current state of `suggestion`, or `None` if it is unknown.
+    #[must_use]
+    pub fn suggestion(&self, suggestion: &Id) -> Option<&Suggestion> {
+        self.suggestions.get(suggestion)
+    }
+
+    /// All suggestions that are still pending, ordered by id.
+    pub fn pending(&self) -> impl Iterator<Item = (&Id, &Suggestion)> {
+        self.suggestions
+            .iter()
+            .filter(|(_, _, suggestion)| suggestion.status == SuggestionStatus::Pending)
+            .map(|(id, _, suggestion)| (id, suggestion))
+    }
+
+    /// Insert `value` at `position` of `text` as a pending suggestion.
+    ///
+    /// The insert is applied to `text` and the suggestion to `self`. Both returned operations
+    /// must be replicated, the text operation like any other change to `text`.
+    /// Returns `None` if `value` is empty or `position` is past the end of `text`.
+    pub fn suggest_insert(
+        &mut self,
+        text: &mut LinearString<Id>,
+        position: usize,
+        value: String,
+        id: Id,
+    ) -> Option<SuggestedInsert<Id>> {
+        let graphemes = u32::try_from(value.graphemes(true).count()).ok()?;
+        let last_index = graphemes.checked_sub(1)?;
+        let link = if position == text.len() {
+            text.ids_before_end()
+        } else {
+            text.ids_at_pos(position)?.before()
+        };
+        let insert = link.insert_operation(IdWithIndex::zero(id.clone()), value);
+        text.apply_operation(insert.clone())
+            .expect("Inserts at a current position must succeed.");
+        let range = AnnotationRange {
+            start: IdWithIndex::zero(id.clone()),
+            end: IdWithIndex {
+                id: id.clone(),
+                index: last_index,
+            },
+        };
+        let suggestion = self.record(id, range, SuggestionKind::Insert);
+        Some(SuggestedInsert {
+            text: insert,
+            suggestion,
+        })
+    }
+
+    /// Record the deletion of the graphemes at `range` of `text` as a pending suggestion.
+    ///
+    /// `text` is left unchanged. Returns `None` if `range` is empty or extends past the end of
+    /// `text`.
+    pub fn suggest_delete(
+        &mut self,
+        text: &LinearString<Id>,
+        range: Range<usize>,
+        id: Id,
+    ) -> Option<SuggestionOperation<Id>> {
+        let range = AnnotationRange::from_visible_range(text, range)?;
+        Some(self.record(id, range, SuggestionKind::Delete))
+    }
+
+    /// Accept `suggestion` with a decision identified by `id`.
+    ///
+    /// Returns `None` if `suggestion` is unknown.
+    pub fn accept(&mut self, id: Id, suggestion: Id) -> Option<SuggestionOperation<Id>> {
+        self.decide(id, suggestion, SuggestionStatus::Accepted)
+    }
+
+    /// Reject `suggestion` with a decision identified by `id`.
+    ///
+    /// Returns `None` if `suggestion` is unknown.
+    pub fn reject(&mut self, id: Id, suggestion: Id) -> Option<SuggestionOperation<Id>> {
+        self.decide(id, suggestion, SuggestionStatus::Rejected)
+    }
+
+    /// Apply an operation received from some replica.
+    ///
+    /// # Errors
+    ///
+    /// Returns the operation unchanged if it is not causally ready yet, see
+    /// [`Annotations::apply_operation`].
+    pub fn apply_operation(
+        &mut self,
+        operation: SuggestionOperation<Id>,
+    ) -> Result<(), SuggestionOperation<Id>> {
+        self.suggestions.apply_operation(operation)
+    }
+
+    /// Resolve all suggestions against the current state of `text`, ordered by position.
+    #[must_use]
+    pub fn resolve(&self, text: &LinearString<Id>) -> Vec<ResolvedAnnotation<'_, Id, Suggestion>> {
+        self.suggestions.resolve(text)
+    }
+
+    /// The visible text of `text` with only the accepted suggestions applied.
+    ///
+    /// Pending and rejected inserts are left out, and only accepted deletes remove text.
+    #[must_use]
+    pub fn accepted_text(&self, text: &LinearString<Id>) -> String {
+        let hidden = self
+            .resolve(text)
+            .into_iter()
+            .filter(|resolved| !resolved.payload.shows_text())
+            .map(|resolved| resolved.range)
+            .collect::<Vec<_>>();
+        text.to_string()
+            .graphemes(true)
+            .enumerate()
+            .filter(|(position, _)| !hidden.iter().any(|range| range.contains(position)))
+            .map(|(_, grapheme)| grapheme)
+            .collect()
+    }
+
+    fn record(
+        &mut self,
+        id: Id,
+        range: AnnotationRange<Id>,
+        kind: SuggestionKind,
+    ) -> SuggestionOperation<Id> {
+        let operation = self.suggestions.add_operation(
+            id,
+            range,
+            Suggestion {
+                kind,
+                status: SuggestionStatus::Pending,
+            },
+        );
+        self.suggestions
+            .apply_operation(operation.clone())
+            .expect("Adding a suggestion must succeed.");
+        operation
+    }
+
+    fn decide(
+        &mut self,
+        id: Id,
+        suggestion: Id,
+        status: SuggestionStatus,
+    ) -> Option<SuggestionOperation<Id>> {
+        let kind = self.suggestions.get(&suggestion)?.kind;
+        let operation =
+            self.suggestions
+                .update_operation(id, suggestion, Suggestion { kind, status })?;
+        self.suggestions
+            .apply_operation(operation.clone())
+            .expect("Deciding on a known suggestion must succeed.");
+        Some(operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepted_text_applies_only_accepted_suggestions() {
+        let mut text = LinearString::with_value("the quick fox".to_owned(), 0);
+        let mut changes = TrackedChanges::new();
+
+        let insert = changes
+            .suggest_insert(&mut text, 10, "brown ".to_owned(), 1)
+            .unwrap();
+        changes.suggest_delete(&text, 4..10, 2).unwrap();
+        assert_eq!(text.to_string(), "the quick brown fox");
+        assert_eq!(changes.accepted_text(&text), "the quick fox");
+        assert_eq!(changes.pending().count(), 2);
+
+        changes.accept(3, 1).unwrap();
+        assert_eq!(changes.accepted_text(&text), "the quick brown fox");
+        changes.accept(4, 2).unwrap();
+        assert_eq!(changes.accepted_text(&text), "the brown fox");
+        changes.reject(5, 1).unwrap();
+        assert_eq!(changes.accepted_text(&text), "the fox");
+        assert_eq!(changes.pending().count(), 0);
+        assert!(matches!(insert.text, DataOperation::Insert { .. }));
+    }
+
+    #[test]
+    fn concurrent_decisions_converge() {
+        let mut author_text = LinearString::with_value("draft".to_owned(), 0);
+        let mut author = TrackedChanges::new();
+        let insert = author
+            .suggest_insert(&mut author_text, 5, " two".to_owned(), 1)
+            .unwrap();
+
+        let mut reviewer_text = LinearString::with_value("draft".to_owned(), 0);
+        reviewer_text.apply_operation(insert.text).unwrap();
+        let mut reviewer = TrackedChanges::new();
+        reviewer.apply_operation(insert.suggestion).unwrap();
+
+        let accept = author.accept(2, 1).unwrap();
+        let reject = reviewer.reject(3, 1).unwrap();
+        author.apply_operation(reject).unwrap();
+        reviewer.apply_operation(accept).unwrap();
+
+        assert_eq!(author, reviewer);
+        assert_eq!(
+            author.suggestion(&1).map(|suggestion| suggestion.status),
+            Some(SuggestionStatus::Rejected)
+        );
+        assert_eq!(author.accepted_text(&author_text), "draft");
+        assert_eq!(reviewer.accepted_text(&reviewer_text), "draft");
+    }
+}
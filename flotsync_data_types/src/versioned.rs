@@ -0,0 +1,1138 @@
+//! Document-level versioning that pairs a CRDT document with its [`VersionVector`].
+//!
+//! Every change to a [`VersionedDoc`] is tagged with the [`UpdateId`] of the member that
+//! produced it and the version vector it was produced against. That is enough to decide whether
+//! a remote change can be applied yet, and to select the changes another replica is missing.
+//!
+//! [`VersionedDoc::read_snapshot`] hands out a [`ReadSnapshot`] that shares the current state.
+//! Changes are applied in place while nothing else shares the document, and copy it once
+//! otherwise, so a snapshot stays unchanged while further changes are applied.
+use crate::{
+    any_data::{
+        LinearLatestValueWins,
+        UpdateOperation,
+        list::{LinearList, ListOperation},
+    },
+    retention::{RetentionPolicies, RetentionPolicy, RetentionPolicyOperation},
+};
+use flotsync_core::{
+    GroupId,
+    MemberIndex,
+    membership::GroupContext,
+    versions::{UpdateId, VersionVector, VersionVectorGap},
+};
+use flotsync_utils::option_when;
+use snafu::prelude::*;
+use std::{fmt, hash::Hash, num::NonZeroUsize, ops::Deref, sync::Arc, time::SystemTime};
+
+/// A CRDT document that only changes through replicated operations.
+pub trait ReplicatedDocument: Clone {
+    /// The name its [`RetentionPolicy`] is looked up by in [`RetentionPolicies`].
+    const DOCUMENT_TYPE: &'static str;
+
+    type Operation: Clone;
+    /// Returned when an operation does not fit the current state of the document.
+    type Rejection: fmt::Debug;
+
+    /// Apply a single operation produced by this or another replica.
+    ///
+    /// A rejected operation must leave the document unchanged, so a change with a single
+    /// operation can be applied in place without a copy to roll back to.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason if the operation cannot be applied.
+    fn apply_operation(&mut self, operation: Self::Operation) -> Result<(), Self::Rejection>;
+}
+
+impl<Id, T> ReplicatedDocument for LinearList<Id, T>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    T: Clone + fmt::Debug + 'static,
+{
+    const DOCUMENT_TYPE: &'static str = "list";
+
+    type Operation = ListOperation<Id, T>;
+    type Rejection = ListOperation<Id, T>;
+
+    fn apply_operation(&mut self, operation: Self::Operation) -> Result<(), Self::Rejection> {
+        LinearList::apply_operation(self, operation)
+    }
+}
+
+impl<Id, T> ReplicatedDocument for LinearLatestValueWins<Id, T>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    T: Clone + fmt::Debug,
+{
+    const DOCUMENT_TYPE: &'static str = "latest_value";
+
+    type Operation = UpdateOperation<Id, T>;
+    type Rejection = UpdateOperation<Id, T>;
+
+    fn apply_operation(&mut self, operation: Self::Operation) -> Result<(), Self::Rejection> {
+        LinearLatestValueWins::apply_operation(self, operation)
+    }
+}
+
+impl<Id> ReplicatedDocument for RetentionPolicies<Id>
+where
+    Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    const DOCUMENT_TYPE: &'static str = "retention_policies";
+
+    type Operation = RetentionPolicyOperation<Id>;
+    type Rejection = RetentionPolicyOperation<Id>;
+
+    fn apply_operation(&mut self, operation: Self::Operation) -> Result<(), Self::Rejection> {
+        RetentionPolicies::apply_operation(self, operation)
+    }
+}
+
+/// Errors applying or selecting changes of a [`VersionedDoc`].
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum VersionedDocError<Rejection>
+where
+    Rejection: fmt::Debug,
+{
+    #[snafu(display(
+        "The version vector has {actual} members, but the document has {expected} members."
+    ))]
+    MemberCountMismatch {
+        expected: NonZeroUsize,
+        actual: NonZeroUsize,
+    },
+    #[snafu(display("Update {update_id} was produced by a member outside of the group."))]
+    UnknownMember { update_id: UpdateId },
+    #[snafu(display(
+        "Update {update_id} claims to have read version {read_version} of its own producer."
+    ))]
+    SelfDependentReadVersions {
+        update_id: UpdateId,
+        read_version: u64,
+    },
+    #[snafu(display("Update {update_id} is not causally ready, missing: {missing:?}"))]
+    NotCausallyReady {
+        update_id: UpdateId,
+        missing: Vec<VersionVectorGap>,
+    },
+    #[snafu(display("An operation of update {update_id} was rejected: {rejection:?}"))]
+    OperationRejected {
+        update_id: UpdateId,
+        rejection: Rejection,
+    },
+    #[snafu(display(
+        "The history before {compacted_versions} was compacted, so the changes since {requested} are no longer available."
+    ))]
+    HistoryCompacted {
+        requested: VersionVector,
+        compacted_versions: VersionVector,
+    },
+    #[snafu(display(
+        "The document observes its group without a member index, so it cannot produce changes."
+    ))]
+    ObserverCannotProduce,
+    #[snafu(display("The document was not created by forking another document."))]
+    NotAFork,
+    #[snafu(display(
+        "The fork was created for group {group_id} at epoch {epoch}, which differs from the group of the document."
+    ))]
+    ForkGroupMismatch { group_id: GroupId, epoch: u64 },
+    #[snafu(display(
+        "Both the document and the fork advanced member {member_index} since the fork point {fork_point}."
+    ))]
+    ConcurrentForkEdits {
+        member_index: u32,
+        fork_point: VersionVector,
+    },
+}
+
+/// All operations a single member produced in one update.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionedChange<Op> {
+    pub update_id: UpdateId,
+    /// The version vector of the producer right before this update.
+    pub read_versions: VersionVector,
+    pub operations: Vec<Op>,
+}
+
+/// Where a [`VersionedDoc`] created by [`fork`](VersionedDoc::fork) branched off.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForkLineage {
+    /// The state vector of the source document when the fork was created.
+    pub fork_point: VersionVector,
+    /// The local member of the source document, or `None` if it observed the group.
+    pub source_member_index: Option<u32>,
+}
+
+/// A CRDT document together with the [`VersionVector`] of all changes applied to it.
+///
+/// Local changes are tagged with the next version of the local member and advance the vector
+/// automatically. Remote changes are only applied once everything they depend on has been applied.
+/// A document created with [`observe`](Self::observe) has no local member and only applies remote
+/// changes.
+///
+/// Applied changes are retained in causal order, so they can be sent to replicas that are
+/// behind via [`encode_changes_since`](Self::encode_changes_since), until a
+/// [`RetentionPolicy`] allows [`compact_history`](Self::compact_history) to drop them.
+#[derive(Clone, Debug)]
+pub struct VersionedDoc<D>
+where
+    D: ReplicatedDocument,
+{
+    /// Shared with [`ReadSnapshot`]s, and only copied on the next change while one is alive.
+    document: Arc<D>,
+    group: GroupContext,
+    /// `None` if this replica observes the group without a member index.
+    local_member_index: Option<u32>,
+    version_vector: VersionVector,
+    /// The versions of all changes that were dropped from `changes`.
+    compacted_versions: VersionVector,
+    changes: Vec<RetainedChange<D::Operation>>,
+    /// Set if this document is a fork of another one.
+    lineage: Option<ForkLineage>,
+}
+
+/// The state of a [`VersionedDoc`] at one point, unaffected by changes applied later.
+///
+/// Taking a snapshot only shares the current document, so it is cheap. Snapshots can be kept
+/// and sent to other threads for as long as needed, for example to render a consistent state
+/// while changes keep being applied. While a snapshot is alive, the next change copies the
+/// document once and the snapshot keeps the previous version.
+#[derive(Clone, Debug)]
+pub struct ReadSnapshot<D> {
+    document: Arc<D>,
+    version_vector: VersionVector,
+}
+impl<D> ReadSnapshot<D> {
+    pub fn document(&self) -> &D {
+        &self.document
+    }
+
+    /// The versions of all members whose changes are included in the snapshot.
+    pub fn state_vector(&self) -> &VersionVector {
+        &self.version_vector
+    }
+}
+impl<D> Deref for ReadSnapshot<D> {
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        &self.document
+    }
+}
+
+/// An applied change together with the time it was applied locally.
+#[derive(Clone, Debug)]
+struct RetainedChange<Op> {
+    change: VersionedChange<Op>,
+    applied_at: SystemTime,
+}
+impl<D> VersionedDoc<D>
+where
+    D: ReplicatedDocument,
+{
+    /// Wrap `document`, which must not contain any changes of the group yet, for the member at
+    /// `local_member_index` of `group`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `local_member_index` is outside of the group.
+    pub fn new(document: D, group: GroupContext, local_member_index: MemberIndex) -> Self {
+        let num_members = group.num_members();
+        assert!(
+            local_member_index.as_usize() < num_members.get(),
+            "Local member {local_member_index} is outside of group range (0-{num_members})"
+        );
+        Self::with_local_member(document, group, Some(local_member_index.as_u32()))
+    }
+
+    /// Wrap `document`, which must not contain any changes of the group yet, for a replica that
+    /// observes `group` without a member index.
+    ///
+    /// Such a document only applies remote changes, and [`apply_local`](Self::apply_local) always
+    /// fails.
+    pub fn observe(document: D, group: GroupContext) -> Self {
+        Self::with_local_member(document, group, None)
+    }
+
+    fn with_local_member(
+        document: D,
+        group: GroupContext,
+        local_member_index: Option<u32>,
+    ) -> Self {
+        Self {
+            document: Arc::new(document),
+            version_vector: group.initial_version_vector(),
+            compacted_versions: group.initial_version_vector(),
+            group,
+            local_member_index,
+            changes: Vec::new(),
+            lineage: None,
+        }
+    }
+
+    /// Create an independent copy of the current state for the member at `local_member_index`,
+    /// for example to edit a draft.
+    ///
+    /// The fork shares all element ids with this document, so its changes can later be merged back
+    /// with [`merge_fork`](Self::merge_fork). It retains no history from before the fork point.
+    ///
+    /// A fork may use the same local member as this document, but then only one of the two may
+    /// make local changes until the fork is merged back, since their update ids would collide.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `local_member_index` is outside of the group, or if this document observes the
+    /// group, since that would let an observer produce changes through the fork.
+    #[must_use]
+    pub fn fork(&self, local_member_index: MemberIndex) -> Self {
+        assert!(
+            self.local_member_index.is_some(),
+            "Observer documents cannot be forked for a local member"
+        );
+        let num_members = self.group.num_members();
+        assert!(
+            local_member_index.as_usize() < num_members.get(),
+            "Local member {local_member_index} is outside of group range (0-{num_members})"
+        );
+        Self {
+            document: Arc::clone(&self.document),
+            group: self.group.clone(),
+            local_member_index: Some(local_member_index.as_u32()),
+            version_vector: self.version_vector.clone(),
+            compacted_versions: self.version_vector.clone(),
+            changes: Vec::new(),
+            lineage: Some(ForkLineage {
+                fork_point: self.version_vector.clone(),
+                source_member_index: self.local_member_index,
+            }),
+        }
+    }
+
+    /// Where this document branched off, if it was created by [`fork`](Self::fork).
+    pub fn lineage(&self) -> Option<&ForkLineage> {
+        self.lineage.as_ref()
+    }
+
+    pub fn document(&self) -> &D {
+        &self.document
+    }
+
+    /// Capture the current state of the document, see [`ReadSnapshot`].
+    #[must_use]
+    pub fn read_snapshot(&self) -> ReadSnapshot<D> {
+        ReadSnapshot {
+            document: Arc::clone(&self.document),
+            version_vector: self.version_vector.clone(),
+        }
+    }
+
+    /// The group whose changes this document tracks.
+    pub fn group(&self) -> &GroupContext {
+        &self.group
+    }
+
+    pub fn into_document(self) -> D {
+        Arc::unwrap_or_clone(self.document)
+    }
+
+    /// The member index changes are produced at, or `None` if this document observes the group.
+    pub fn local_member_index(&self) -> Option<u32> {
+        self.local_member_index
+    }
+
+    /// The versions of all members whose changes have been applied to the document.
+    pub fn state_vector(&self) -> &VersionVector {
+        &self.version_vector
+    }
+
+    /// The id the next local change will be tagged with, or `None` if this document observes the
+    /// group and therefore never produces changes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the local version counter overflows.
+    #[must_use]
+    pub fn next_update_id(&self) -> Option<UpdateId> {
+        let node_index = self.local_member_index?;
+        let version = self
+            .version_vector
+            .version_at(node_index as usize)
+            .checked_add(1)
+            .expect("member version counter must not overflow");
+        Some(UpdateId {
+            version,
+            node_index,
+        })
+    }
+
+    /// Create a local change with `create_operations` and apply it.
+    ///
+    /// `create_operations` receives the current document and the id of the new update, which
+    /// should be used to derive the ids of any new elements. The state vector is only advanced if
+    /// all returned operations apply, otherwise the document is left unchanged.
+    ///
+    /// Returns the applied change, so it can be sent to the other members.
+    ///
+    /// # Errors
+    ///
+    /// Fails if this document observes the group, or if one of the created operations does not
+    /// apply to the document.
+    pub fn apply_local<F>(
+        &mut self,
+        create_operations: F,
+    ) -> Result<&VersionedChange<D::Operation>, VersionedDocError<D::Rejection>>
+    where
+        F: FnOnce(&D, UpdateId) -> Vec<D::Operation>,
+    {
+        let update_id = self.next_update_id().context(ObserverCannotProduceSnafu)?;
+        let operations = create_operations(&self.document, update_id);
+        let change = VersionedChange {
+            update_id,
+            read_versions: self.version_vector.clone(),
+            operations,
+        };
+        self.apply_in_place(std::slice::from_ref(&change))?;
+        self.version_vector
+            .increment_at(update_id.node_index as usize);
+        self.changes.push(RetainedChange {
+            change,
+            applied_at: SystemTime::now(),
+        });
+        Ok(&self.changes.last().expect("just pushed").change)
+    }
+
+    /// Apply a batch of remote changes in the given order.
+    ///
+    /// Changes that have already been applied are skipped. Every other change must be causally
+    /// ready once the changes before it in the batch have been applied.
+    /// The batch is applied atomically, so on failure neither the document nor the state vector
+    /// change.
+    ///
+    /// Returns the number of newly applied changes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a change is malformed, not causally ready, or contains an operation that does not
+    /// apply.
+    pub fn apply_remote<I>(&mut self, batch: I) -> Result<usize, VersionedDocError<D::Rejection>>
+    where
+        I: IntoIterator<Item = VersionedChange<D::Operation>>,
+    {
+        let mut version_vector = self.version_vector.clone();
+        let mut applied = Vec::new();
+        for mut change in batch {
+            change.read_versions.normalize();
+            if !Self::check_causally_ready(&version_vector, &change)? {
+                // Already applied.
+                continue;
+            }
+            version_vector.increment_at(change.update_id.node_index as usize);
+            applied.push(change);
+        }
+        // Readiness doesn't depend on the document, so the whole batch is applied in one go.
+        self.apply_in_place(&applied)?;
+
+        let num_applied = applied.len();
+        self.version_vector = version_vector;
+        let applied_at = SystemTime::now();
+        self.changes.extend(
+            applied
+                .into_iter()
+                .map(|change| RetainedChange { change, applied_at }),
+        );
+        Ok(num_applied)
+    }
+
+    /// Collect all applied changes that are not covered by `vector`, in causal order.
+    ///
+    /// Applying the result to a replica at `vector` brings it up to the state vector of `self`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `vector` belongs to a group of a different size, or if some of the changes it is
+    /// missing were already dropped by [`compact_history`](Self::compact_history).
+    pub fn encode_changes_since(
+        &self,
+        vector: &VersionVector,
+    ) -> Result<Vec<VersionedChange<D::Operation>>, VersionedDocError<D::Rejection>> {
+        self.ensure_same_member_count(vector)?;
+        ensure!(
+            vector
+                .missing_version_ranges_to(&self.compacted_versions)
+                .is_empty(),
+            HistoryCompactedSnafu {
+                requested: vector.clone(),
+                compacted_versions: self.compacted_versions.clone(),
+            }
+        );
+        let changes = self
+            .changes
+            .iter()
+            .map(|retained| &retained.change)
+            .filter(|change| {
+                vector.version_at(change.update_id.node_index as usize) < change.update_id.version
+            })
+            .cloned()
+            .collect();
+        Ok(changes)
+    }
+
+    /// Apply all changes `fork` has made or received since it was forked from this document.
+    ///
+    /// The merge compares both sides against the fork point: changes this document already has
+    /// are skipped, and the rest are applied like remote changes, so concurrent edits on both
+    /// sides converge just like edits of different replicas. Like
+    /// [`apply_remote`](Self::apply_remote), the merge is atomic.
+    ///
+    /// Afterwards the fork point of `fork` moves to its current state vector, so the fork can keep
+    /// being edited and merged again later.
+    ///
+    /// Returns the number of newly applied changes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `fork` is not a fork of a document of this group, if this document advanced the
+    /// fork's local member since the fork point while the fork made changes as that member, or if
+    /// the fork compacted changes this document is missing.
+    pub fn merge_fork(
+        &mut self,
+        fork: &mut Self,
+    ) -> Result<usize, VersionedDocError<D::Rejection>> {
+        let lineage = fork.lineage.as_ref().context(NotAForkSnafu)?;
+        ensure!(
+            self.group == fork.group,
+            ForkGroupMismatchSnafu {
+                group_id: fork.group.id(),
+                epoch: fork.group.epoch(),
+            }
+        );
+        let fork_point = &lineage.fork_point;
+        // The fork's own edits are only recognisable by its member index. If this document
+        // advanced that member too, by its own edits or remote ones, the two sets of updates share
+        // update ids and the fork's would be skipped as already applied.
+        if let Some(member_index) = fork.local_member_index {
+            let position = member_index as usize;
+            let base_version = fork_point.version_at(position);
+            ensure!(
+                self.version_vector.version_at(position) <= base_version
+                    || fork.version_vector.version_at(position) <= base_version,
+                ConcurrentForkEditsSnafu {
+                    member_index,
+                    fork_point: fork_point.clone(),
+                }
+            );
+        }
+        let changes = fork.encode_changes_since(fork_point)?;
+        let num_applied = self.apply_remote(changes)?;
+        let merged_point = fork.version_vector.clone();
+        if let Some(lineage) = fork.lineage.as_mut() {
+            lineage.fork_point = merged_point;
+        }
+        Ok(num_applied)
+    }
+
+    /// The versions of all changes that were dropped from the retained history.
+    pub fn compacted_versions(&self) -> &VersionVector {
+        &self.compacted_versions
+    }
+
+    /// The number of changes that are still retained.
+    pub fn retained_len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Drop the oldest retained changes that `policy` no longer requires to be kept at `now`.
+    ///
+    /// `stable_versions` are the versions every member of the group has applied. Changes are only
+    /// ever dropped from the front of the history, so what remains can still be applied on top of
+    /// [`compacted_versions`](Self::compacted_versions).
+    ///
+    /// Returns the number of dropped changes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `stable_versions` belongs to a group of a different size.
+    pub fn compact_history(
+        &mut self,
+        policy: RetentionPolicy,
+        stable_versions: &VersionVector,
+        now: SystemTime,
+    ) -> Result<usize, VersionedDocError<D::Rejection>> {
+        self.ensure_same_member_count(stable_versions)?;
+        let droppable = |retained: &RetainedChange<D::Operation>| match policy {
+            RetentionPolicy::Forever => false,
+            RetentionPolicy::UntilStable => {
+                let update_id = retained.change.update_id;
+                update_id.version <= stable_versions.version_at(update_id.node_index as usize)
+            }
+            RetentionPolicy::KeepFor { duration } => retained
+                .applied_at
+                .checked_add(duration)
+                .is_some_and(|expires_at| expires_at <= now),
+        };
+        let num_dropped = self
+            .changes
+            .iter()
+            .take_while(|retained| droppable(retained))
+            .count();
+        for retained in self.changes.drain(..num_dropped) {
+            self.compacted_versions
+                .increment_at(retained.change.update_id.node_index as usize);
+        }
+        Ok(num_dropped)
+    }
+
+    /// Like [`compact_history`](Self::compact_history) with the policy `policies` have for this
+    /// document type.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `stable_versions` belongs to a group of a different size.
+    pub fn compact_history_with<Id>(
+        &mut self,
+        policies: &RetentionPolicies<Id>,
+        stable_versions: &VersionVector,
+        now: SystemTime,
+    ) -> Result<usize, VersionedDocError<D::Rejection>>
+    where
+        Id: Clone + fmt::Debug + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+    {
+        self.compact_history(policies.policy_for(D::DOCUMENT_TYPE), stable_versions, now)
+    }
+
+    fn ensure_same_member_count(
+        &self,
+        vector: &VersionVector,
+    ) -> Result<(), VersionedDocError<D::Rejection>> {
+        let expected = self.version_vector.num_members();
+        let actual = vector.num_members();
+        ensure!(
+            expected == actual,
+            MemberCountMismatchSnafu { expected, actual }
+        );
+        Ok(())
+    }
+
+    /// Returns `Ok(true)` if `change` is the next change of its producer and everything it read
+    /// is covered by `version_vector`, and `Ok(false)` if it is already covered.
+    fn check_causally_ready(
+        version_vector: &VersionVector,
+        change: &VersionedChange<D::Operation>,
+    ) -> Result<bool, VersionedDocError<D::Rejection>> {
+        let update_id = change.update_id;
+        let expected = version_vector.num_members();
+        let actual = change.read_versions.num_members();
+        ensure!(
+            expected == actual,
+            MemberCountMismatchSnafu { expected, actual }
+        );
+        let producer_index = update_id.node_index as usize;
+        let applied_version = version_vector
+            .get(producer_index)
+            .context(UnknownMemberSnafu { update_id })?;
+        let read_version = change.read_versions.version_at(producer_index);
+        ensure!(
+            read_version < update_id.version,
+            SelfDependentReadVersionsSnafu {
+                update_id,
+                read_version,
+            }
+        );
+        if applied_version >= update_id.version {
+            return Ok(false);
+        }
+
+        // Everything the producer read, plus all of its own earlier updates.
+        let required = change
+            .read_versions
+            .with_version_at(producer_index, update_id.version - 1);
+        let missing = version_vector.missing_version_ranges_to(&required);
+        ensure!(
+            missing.is_empty(),
+            NotCausallyReadySnafu { update_id, missing }
+        );
+        Ok(true)
+    }
+
+    /// Apply all operations of `changes` to the document, or none of them if one is rejected.
+    ///
+    /// The operations are applied in place. The document is only copied if a [`ReadSnapshot`]
+    /// still shares it, or once if more than one operation has to be applied, since then the
+    /// operations before a rejected one have to be rolled back. A single operation never needs
+    /// that, because a rejected operation leaves the document unchanged.
+    fn apply_in_place(
+        &mut self,
+        changes: &[VersionedChange<D::Operation>],
+    ) -> Result<(), VersionedDocError<D::Rejection>> {
+        let num_operations = changes
+            .iter()
+            .map(|change| change.operations.len())
+            .sum::<usize>();
+        if num_operations == 0 {
+            return Ok(());
+        }
+        let rollback = option_when!(num_operations > 1, Arc::clone(&self.document));
+        let document = Arc::make_mut(&mut self.document);
+        for change in changes {
+            for operation in change.operations.iter().cloned() {
+                if let Err(rejection) = document.apply_operation(operation) {
+                    if let Some(rollback) = rollback {
+                        self.document = rollback;
+                    }
+                    return OperationRejectedSnafu {
+                        update_id: change.update_id,
+                        rejection,
+                    }
+                    .fail();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IdWithIndex, linear_data::DataOperation};
+    use flotsync_core::{
+        GroupId,
+        member::Identifier,
+        membership::{GroupContext, GroupMembers},
+    };
+    use std::{assert_matches, time::Duration};
+    use uuid::Uuid;
+
+    type Doc = VersionedDoc<LinearList<UpdateId, i32>>;
+
+    const TWO_MEMBERS: NonZeroUsize = NonZeroUsize::new(2).unwrap();
+
+    fn new_group() -> GroupContext {
+        let members = GroupMembers::from_ordered_members([
+            Identifier::from_array(["alice"]),
+            Identifier::from_array(["bob"]),
+        ])
+        .unwrap()
+        .with_observers([Identifier::from_array(["dashboard"])])
+        .unwrap();
+        GroupContext::new(GroupId(Uuid::from_u128(1)), members, 0).unwrap()
+    }
+
+    fn new_doc(local_member_index: u32) -> Doc {
+        let list = LinearList::new(UpdateId::INITIAL_STATE_ORIGIN);
+        VersionedDoc::new(list, new_group(), MemberIndex::new(local_member_index))
+    }
+
+    fn append(doc: &mut Doc, value: i32) -> VersionedChange<ListOperation<UpdateId, i32>> {
+        doc.apply_local(|list, update_id| {
+            list.append_operation(IdWithIndex::zero(update_id), [value])
+                .into_iter()
+                .collect()
+        })
+        .unwrap()
+        .clone()
+    }
+
+    /// A delete of an element that no document of the tests contains, so it is always rejected.
+    fn missing_delete() -> ListOperation<UpdateId, i32> {
+        ListOperation::from_operation(DataOperation::Delete {
+            start: IdWithIndex::zero(UpdateId {
+                version: 99,
+                node_index: 1,
+            }),
+            end: None,
+        })
+    }
+
+    fn values(doc: &Doc) -> Vec<i32> {
+        doc.document().iter().copied().collect()
+    }
+
+    #[test]
+    fn local_changes_advance_the_state_vector() {
+        let mut doc = new_doc(1);
+        assert_eq!(
+            doc.next_update_id().map(|update_id| update_id.version),
+            Some(1)
+        );
+
+        let first = append(&mut doc, 1);
+        let second = append(&mut doc, 2);
+        assert_eq!(first.update_id.version, 1);
+        assert_eq!(second.update_id.version, 2);
+        assert_eq!(second.read_versions, VersionVector::from_entries([0, 1]));
+        assert_eq!(doc.state_vector(), &VersionVector::from_entries([0, 2]));
+        assert_eq!(values(&doc), vec![1, 2]);
+    }
+
+    #[test]
+    fn observers_apply_remote_changes_but_never_produce_any() {
+        let mut alice = new_doc(0);
+        let mut observer: Doc =
+            VersionedDoc::observe(LinearList::new(UpdateId::INITIAL_STATE_ORIGIN), new_group());
+        assert_eq!(observer.local_member_index(), None);
+        assert_eq!(observer.next_update_id(), None);
+
+        let remote = append(&mut alice, 1);
+        assert_eq!(observer.apply_remote([remote]).unwrap(), 1);
+        assert_eq!(values(&observer), vec![1]);
+
+        assert_matches!(
+            observer.apply_local(|_, _| Vec::new()),
+            Err(VersionedDocError::ObserverCannotProduce)
+        );
+        assert_eq!(
+            observer.state_vector(),
+            &VersionVector::from_entries([1, 0])
+        );
+    }
+
+    #[test]
+    fn read_snapshots_are_unaffected_by_later_changes() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        append(&mut alice, 1);
+        let snapshot = alice.read_snapshot();
+
+        append(&mut alice, 2);
+        let remote = append(&mut bob, 3);
+        alice.apply_remote([remote.clone()]).unwrap();
+
+        // Batches that only contain already applied changes don't copy the document.
+        let unchanged = alice.read_snapshot();
+        assert_eq!(alice.apply_remote([remote]).unwrap(), 0);
+        assert!(std::ptr::eq(
+            unchanged.document(),
+            alice.read_snapshot().document()
+        ));
+
+        let rendered = std::thread::scope(|scope| {
+            scope
+                .spawn(|| snapshot.iter().copied().collect::<Vec<_>>())
+                .join()
+                .unwrap()
+        });
+        assert_eq!(rendered, vec![1]);
+        assert_eq!(
+            snapshot.state_vector(),
+            &VersionVector::from_entries([1, 0])
+        );
+        assert_eq!(alice.state_vector(), &VersionVector::from_entries([2, 1]));
+        let current = alice.read_snapshot();
+        assert_eq!(current.iter().copied().collect::<Vec<_>>(), values(&alice));
+    }
+
+    #[test]
+    fn local_changes_apply_in_place_unless_shared() {
+        let mut alice = new_doc(0);
+        append(&mut alice, 1);
+        let before: *const _ = alice.document();
+        append(&mut alice, 2);
+        assert!(std::ptr::eq(before, alice.document()));
+
+        let snapshot = alice.read_snapshot();
+        append(&mut alice, 3);
+        assert!(!std::ptr::eq(snapshot.document(), alice.document()));
+        assert_eq!(snapshot.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+
+        // The first operation applies, but the whole change is rolled back with the second.
+        let res = alice.apply_local(|list, update_id| {
+            let mut operations: Vec<_> = list
+                .append_operation(IdWithIndex::zero(update_id), [4])
+                .into_iter()
+                .collect();
+            operations.push(missing_delete());
+            operations
+        });
+        assert_matches!(res, Err(VersionedDocError::OperationRejected { .. }));
+        assert_eq!(values(&alice), vec![1, 2, 3]);
+        assert_eq!(alice.state_vector(), &VersionVector::from_entries([3, 0]));
+    }
+
+    #[test]
+    fn changes_since_bring_replicas_up_to_date() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        append(&mut alice, 1);
+        append(&mut alice, 2);
+        append(&mut bob, 3);
+
+        let to_bob = alice.encode_changes_since(bob.state_vector()).unwrap();
+        assert_eq!(to_bob.len(), 2);
+        assert_eq!(bob.apply_remote(to_bob).unwrap(), 2);
+        let to_alice = bob.encode_changes_since(alice.state_vector()).unwrap();
+        assert_eq!(to_alice.len(), 1);
+        assert_eq!(alice.apply_remote(to_alice).unwrap(), 1);
+
+        assert_eq!(alice.state_vector(), bob.state_vector());
+        assert_eq!(values(&alice), values(&bob));
+        assert!(
+            alice
+                .encode_changes_since(bob.state_vector())
+                .unwrap()
+                .is_empty()
+        );
+
+        // Replaying already applied changes is a no-op.
+        let everything = alice
+            .encode_changes_since(&VersionVector::initial(TWO_MEMBERS))
+            .unwrap();
+        assert_eq!(bob.apply_remote(everything).unwrap(), 0);
+    }
+
+    #[test]
+    fn remote_changes_wait_for_their_dependencies() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        let first = append(&mut alice, 1);
+        let second = append(&mut alice, 2);
+
+        let res = bob.apply_remote([second.clone(), first.clone()]);
+        assert_matches!(
+            res,
+            Err(VersionedDocError::NotCausallyReady { update_id, .. })
+                if update_id == second.update_id
+        );
+        // Nothing was applied from the failed batch.
+        assert_eq!(bob.state_vector(), &VersionVector::initial(TWO_MEMBERS));
+        assert!(values(&bob).is_empty());
+
+        assert_eq!(bob.apply_remote([first, second]).unwrap(), 2);
+        assert_eq!(values(&bob), vec![1, 2]);
+    }
+
+    #[test]
+    fn batches_with_a_rejected_last_change_apply_nothing() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        let first = append(&mut alice, 1);
+        let second = append(&mut alice, 2);
+        let mut rejected = append(&mut alice, 3);
+        rejected.operations.push(missing_delete());
+        let snapshot = bob.read_snapshot();
+
+        let res = bob.apply_remote([first.clone(), second.clone(), rejected.clone()]);
+        assert_matches!(
+            res,
+            Err(VersionedDocError::OperationRejected { update_id, .. })
+                if update_id == rejected.update_id
+        );
+        assert!(values(&bob).is_empty());
+        assert_eq!(bob.state_vector(), &VersionVector::initial(TWO_MEMBERS));
+        assert!(std::ptr::eq(snapshot.document(), bob.document()));
+
+        assert_eq!(bob.apply_remote([first, second]).unwrap(), 2);
+        assert_eq!(values(&bob), vec![1, 2]);
+    }
+
+    #[test]
+    fn malformed_remote_changes_are_rejected() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        let change = append(&mut alice, 1);
+
+        let mut self_dependent = change.clone();
+        self_dependent.read_versions = VersionVector::from_entries([1, 0]);
+        assert_matches!(
+            bob.apply_remote([self_dependent]),
+            Err(VersionedDocError::SelfDependentReadVersions { .. })
+        );
+
+        let mut unknown_member = change.clone();
+        unknown_member.update_id.node_index = 2;
+        assert_matches!(
+            bob.apply_remote([unknown_member]),
+            Err(VersionedDocError::UnknownMember { .. })
+        );
+
+        let mut wrong_group = change;
+        wrong_group.read_versions = VersionVector::initial(NonZeroUsize::new(3).unwrap());
+        assert_matches!(
+            bob.apply_remote([wrong_group]),
+            Err(VersionedDocError::MemberCountMismatch { .. })
+        );
+        assert_matches!(
+            bob.encode_changes_since(&VersionVector::initial(NonZeroUsize::MIN)),
+            Err(VersionedDocError::MemberCountMismatch { .. })
+        );
+        assert_eq!(bob.state_vector(), &VersionVector::initial(TWO_MEMBERS));
+    }
+
+    #[test]
+    fn forks_merge_back_with_concurrent_edits() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        append(&mut alice, 1);
+        let mut draft = alice.fork(MemberIndex::new(0));
+        assert_eq!(
+            draft.lineage(),
+            Some(&ForkLineage {
+                fork_point: VersionVector::from_entries([1, 0]),
+                source_member_index: Some(0),
+            })
+        );
+        assert_eq!(alice.lineage(), None);
+
+        append(&mut draft, 2);
+        append(&mut draft, 3);
+        let remote = append(&mut bob, 4);
+        alice.apply_remote([remote.clone()]).unwrap();
+        draft.apply_remote([remote]).unwrap();
+        assert_eq!(values(&alice), vec![1, 4]);
+
+        // Bob's change is already in both, only the draft's own changes are new.
+        assert_eq!(alice.merge_fork(&mut draft).unwrap(), 2);
+        assert_eq!(alice.state_vector(), &VersionVector::from_entries([3, 1]));
+        assert_eq!(values(&alice), values(&draft));
+        assert_eq!(alice.merge_fork(&mut draft).unwrap(), 0);
+        assert_eq!(
+            draft.lineage().map(|lineage| &lineage.fork_point),
+            Some(alice.state_vector())
+        );
+
+        // Merged changes are part of the history like any other.
+        let to_bob = alice.encode_changes_since(bob.state_vector()).unwrap();
+        assert_eq!(bob.apply_remote(to_bob).unwrap(), 3);
+        assert_eq!(values(&bob), values(&alice));
+    }
+
+    #[test]
+    fn conflicting_forks_are_rejected() {
+        let mut alice = new_doc(0);
+        let mut draft = alice.fork(MemberIndex::new(0));
+        append(&mut draft, 1);
+        append(&mut alice, 2);
+        assert_matches!(
+            alice.merge_fork(&mut draft),
+            Err(VersionedDocError::ConcurrentForkEdits {
+                member_index: 0,
+                ..
+            })
+        );
+        assert_eq!(values(&alice), vec![2]);
+
+        // A fork for another member can be edited alongside the source.
+        let mut bobs_draft = alice.fork(MemberIndex::new(1));
+        append(&mut bobs_draft, 3);
+        append(&mut alice, 4);
+        assert_eq!(alice.merge_fork(&mut bobs_draft).unwrap(), 1);
+        assert_eq!(alice.state_vector(), &VersionVector::from_entries([2, 1]));
+
+        let mut unrelated = new_doc(1);
+        assert_matches!(
+            alice.merge_fork(&mut unrelated),
+            Err(VersionedDocError::NotAFork)
+        );
+    }
+
+    #[test]
+    fn forks_for_a_member_whose_edits_arrived_meanwhile_are_rejected() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        let mut bobs_draft = alice.fork(MemberIndex::new(1));
+        append(&mut bobs_draft, 1);
+        let remote = append(&mut bob, 2);
+        alice.apply_remote([remote]).unwrap();
+
+        // Both sides now hold a different update 1 of bob, so merging would drop the draft's.
+        assert_matches!(
+            alice.merge_fork(&mut bobs_draft),
+            Err(VersionedDocError::ConcurrentForkEdits {
+                member_index: 1,
+                ..
+            })
+        );
+        assert_eq!(values(&alice), vec![2]);
+        assert_eq!(alice.state_vector(), &VersionVector::from_entries([0, 1]));
+    }
+
+    #[test]
+    fn history_is_compacted_by_retention_policy() {
+        let mut alice = new_doc(0);
+        let mut bob = new_doc(1);
+        append(&mut alice, 1);
+        append(&mut alice, 2);
+        let now = SystemTime::now();
+
+        let everything = alice.state_vector().clone();
+        assert_eq!(
+            alice
+                .compact_history(RetentionPolicy::Forever, &everything, now)
+                .unwrap(),
+            0
+        );
+        // Only the first change has been applied everywhere.
+        let stable_versions = VersionVector::from_entries([1, 0]);
+        assert_eq!(
+            alice
+                .compact_history(RetentionPolicy::UntilStable, &stable_versions, now)
+                .unwrap(),
+            1
+        );
+        assert_eq!(alice.retained_len(), 1);
+        assert_eq!(alice.compacted_versions(), &stable_versions);
+
+        // Bob never received the first change, so he has to catch up some other way.
+        assert_matches!(
+            alice.encode_changes_since(bob.state_vector()),
+            Err(VersionedDocError::HistoryCompacted { .. })
+        );
+        let to_caught_up = alice.encode_changes_since(&stable_versions).unwrap();
+        assert_eq!(to_caught_up.len(), 1);
+
+        // Time-bounded history is dropped once it expired, stable or not.
+        append(&mut bob, 3);
+        let keep_a_day = RetentionPolicy::keep_days(1);
+        let initial = VersionVector::initial(TWO_MEMBERS);
+        assert_eq!(bob.compact_history(keep_a_day, &initial, now).unwrap(), 0);
+        let in_two_days = now + Duration::from_secs(2 * 24 * 60 * 60);
+        assert_eq!(
+            bob.compact_history(keep_a_day, &initial, in_two_days)
+                .unwrap(),
+            1
+        );
+        assert_eq!(bob.retained_len(), 0);
+    }
+
+    #[test]
+    fn retention_policies_apply_per_document_type() {
+        let mut policies =
+            RetentionPolicies::new(RetentionPolicy::Forever, UpdateId::INITIAL_STATE_ORIGIN);
+        let mut doc = new_doc(0);
+        append(&mut doc, 1);
+        let stable_versions = doc.state_vector().clone();
+
+        let op = policies.set_policy_operation(
+            UpdateId {
+                version: 1,
+                node_index: 0,
+            },
+            "latest_value",
+            RetentionPolicy::UntilStable,
+        );
+        policies.apply_operation(op).unwrap();
+        assert_eq!(
+            doc.compact_history_with(&policies, &stable_versions, SystemTime::now())
+                .unwrap(),
+            0
+        );
+
+        let op = policies.set_policy_operation(
+            UpdateId {
+                version: 2,
+                node_index: 0,
+            },
+            <LinearList<UpdateId, i32> as ReplicatedDocument>::DOCUMENT_TYPE,
+            RetentionPolicy::UntilStable,
+        );
+        policies.apply_operation(op).unwrap();
+        assert_eq!(
+            doc.compact_history_with(&policies, &stable_versions, SystemTime::now())
+                .unwrap(),
+            1
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Checks this crate against the convergence vectors in `conformance/text_convergence.toml`.
+//!
+//! The data file is shared with other implementations, see `docs/conformance_vectors.md`.
+
+use flotsync_core::versions::UpdateId;
+use flotsync_data_types::{DataOperation, IdRange, IdWithIndex, text::LinearString};
+use itertools::Itertools;
+use toml::{Table, Value};
+use unicode_segmentation::UnicodeSegmentation;
+
+const TEXT_CONVERGENCE: &str = include_str!("../../conformance/text_convergence.toml");
+
+/// Every delivery order is checked, so scenarios must stay small.
+const MAX_SCENARIO_OPERATIONS: usize = 6;
+
+type TextId = IdWithIndex<UpdateId>;
+type TextOperation = DataOperation<TextId, String>;
+
+#[test]
+fn text_scenarios_converge_to_the_conformance_vectors() {
+    let vectors: Table = TEXT_CONVERGENCE
+        .parse()
+        .expect("conformance vectors must be valid TOML");
+    let scenarios = vectors["scenario"]
+        .as_array()
+        .expect("scenario must be an array of tables");
+    assert!(!scenarios.is_empty());
+
+    for scenario in scenarios {
+        let name = str_field(scenario, "name");
+        let initial = str_field(scenario, "initial");
+        let operations: Vec<_> = scenario["operations"]
+            .as_array()
+            .expect("operations must be an array")
+            .iter()
+            .map(parse_operation)
+            .collect();
+        assert!(
+            operations.len() <= MAX_SCENARIO_OPERATIONS,
+            "{name}: too many operations to check every delivery order"
+        );
+        let text = str_field(scenario, "text");
+        let ids: Vec<_> = scenario["ids"]
+            .as_array()
+            .expect("ids must be an array")
+            .iter()
+            .map(|id| parse_id(id.as_str().expect("ids must be strings")))
+            .collect();
+
+        for order in (0..operations.len()).permutations(operations.len()) {
+            let replica = deliver(initial, &operations, &order);
+            assert_eq!(
+                replica.to_string(),
+                text,
+                "{name}: text for order {order:?}"
+            );
+            assert_eq!(
+                grapheme_ids(&replica),
+                ids,
+                "{name}: grapheme ids for order {order:?}"
+            );
+        }
+    }
+}
+
+/// Deliver `operations` to a fresh replica in `order`, buffering those whose anchors are missing.
+fn deliver(initial: &str, operations: &[TextOperation], order: &[usize]) -> LinearString<UpdateId> {
+    let mut replica = LinearString::with_value(initial.to_owned(), UpdateId::INITIAL_STATE_ORIGIN);
+    let mut pending = Vec::new();
+    for &index in order {
+        pending.push(operations[index].clone());
+        let result = replica
+            .apply_batch(pending)
+            .expect("conformance operations must apply cleanly");
+        pending = result.blocked;
+    }
+    assert!(
+        pending.is_empty(),
+        "operations never became applicable: {pending:?}"
+    );
+    replica
+        .validate_integrity()
+        .expect("replica must stay consistent");
+    replica
+}
+
+/// The id of every visible grapheme, in order.
+fn grapheme_ids(replica: &LinearString<UpdateId>) -> Vec<TextId> {
+    replica
+        .iter_with_ids()
+        .flat_map(|(first, run)| {
+            (0..run.graphemes(true).count()).map(move |offset| IdWithIndex {
+                id: first.id,
+                index: first.index + u32::try_from(offset).expect("runs fit into u32 indices"),
+            })
+        })
+        .collect()
+}
+
+fn str_field<'a>(table: &'a Value, key: &str) -> &'a str {
+    table[key]
+        .as_str()
+        .unwrap_or_else(|| panic!("{key} must be a string in {table}"))
+}
+
+fn parse_operation(operation: &Value) -> TextOperation {
+    if let Some(value) = operation.get("insert") {
+        DataOperation::Insert {
+            id: parse_id(str_field(operation, "id")),
+            pred: parse_id(str_field(operation, "pred")),
+            succ: parse_id(str_field(operation, "succ")),
+            value: value
+                .as_str()
+                .expect("inserted text must be a string")
+                .to_owned(),
+        }
+    } else if let Some(start) = operation.get("delete") {
+        DataOperation::Delete {
+            start: parse_id(start.as_str().expect("delete must be an id")),
+            end: operation
+                .get("end")
+                .map(|end| parse_id(end.as_str().expect("end must be an id"))),
+        }
+    } else if let Some(ranges) = operation.get("delete-ranges") {
+        let ranges = ranges
+            .as_array()
+            .expect("delete-ranges must be an array")
+            .iter()
+            .map(|range| match range.as_array().map(Vec::as_slice) {
+                Some([start, end]) => IdRange {
+                    start: parse_id(start.as_str().expect("range start must be an id")),
+                    end: parse_id(end.as_str().expect("range end must be an id")),
+                },
+                _ => panic!("delete ranges must be [start, end] pairs, not {range}"),
+            })
+            .collect();
+        DataOperation::DeleteRanges { ranges }
+    } else {
+        panic!("unknown operation {operation}")
+    }
+}
+
+/// Parse an id written as `v<version>@<member>#<index>`.
+fn parse_id(id: &str) -> TextId {
+    let parsed = id
+        .strip_prefix('v')
+        .and_then(|rest| rest.split_once('@'))
+        .and_then(|(version, rest)| {
+            let (member, index) = rest.split_once('#')?;
+            Some(IdWithIndex {
+                id: UpdateId {
+                    version: version.parse().ok()?,
+                    node_index: member.parse().ok()?,
+                },
+                index: index.parse().ok()?,
+            })
+        });
+    parsed.unwrap_or_else(|| panic!("{id:?} is not a v<version>@<member>#<index> id"))
+}
@@ -263,7 +263,7 @@ fn modify_row_linear_list_emits_targeted_diff_operations() {
                 PrimitiveValueArray::Int(values) => Some(values.clone()),
                 _ => panic!("numbers field should encode int list insert payloads"),
             },
-            DataOperation::Delete { .. } => None,
+            _ => None,
         })
         .collect();
     assert_eq!(inserted_chunks, vec![vec![9]]);
@@ -811,3 +811,37 @@ fn apply_schema_operation_duplicate_updates_can_succeed_or_fail_by_field_type()
     assert_matches!(retry, Err(OperationError::InternalOperation { .. }));
     assert_eq!(string_target.num_active_rows(), 1);
 }
+
+mod prelude {
+    use flotsync_data_types::prelude::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn prelude_covers_replicating_a_document() {
+        let members = GroupMembers::from_ordered_members([
+            Identifier::from_array(["alice"]),
+            Identifier::from_array(["bob"]),
+        ])
+        .unwrap();
+        let group = GroupContext::new(GroupId(Uuid::from_u128(1)), members, 0).unwrap();
+        let list = LinearList::new(UpdateId::INITIAL_STATE_ORIGIN);
+        let mut doc = VersionedDoc::new(list, group, MemberIndex::new(0));
+        let before = doc.state_vector().clone();
+
+        doc.apply_local(|list, update_id| {
+            list.append_operation(IdWithIndex::zero(update_id), ["first"])
+                .into_iter()
+                .collect()
+        })
+        .unwrap();
+
+        assert_eq!(
+            before.hb_cmp(doc.state_vector()),
+            HappenedBeforeOrdering::Before
+        );
+        assert_eq!(
+            doc.document().iter().copied().collect::<Vec<_>>(),
+            vec!["first"]
+        );
+    }
+}
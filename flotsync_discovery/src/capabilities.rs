@@ -0,0 +1,206 @@
+//! Optional protocol features that peers advertise in announcements and introductions.
+//!
+//! Newer peers must keep working with older ones, so a feature is only used between two peers if
+//! both advertise it. Peers that predate capability advertisement send no capabilities at all and
+//! are treated as [`CapabilitySet::BASELINE`].
+
+use crate::protocol::{DiscoveryProtocolError, discovery_protocol_error};
+use bitflags::bitflags;
+use derive_more::Display;
+use flotsync_messages::{
+    discovery::{PeerCapabilities, PeerCapabilitiesView},
+    proto::{self, DecodeProtoView},
+};
+use snafu::prelude::*;
+
+bitflags! {
+    /// Optional features a peer supports.
+    ///
+    /// Bits unknown to this build are dropped when decoding, since they can never be negotiated.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct Capabilities: u64 {
+        /// The peer acts as a rendezvous relay for NAT traversal.
+        const RELAY = 1 << 0;
+        /// The peer serves version 2 replication snapshots.
+        const SNAPSHOTS_V2 = 1 << 1;
+    }
+}
+
+/// A version of the peer-to-peer protocol.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[display("v{_0}")]
+pub struct ProtocolVersion(pub u32);
+
+impl ProtocolVersion {
+    /// The first protocol version, spoken by every peer.
+    pub const V1: Self = Self(1);
+    /// The newest protocol version this build speaks.
+    pub const CURRENT: Self = Self::V1;
+}
+
+/// The optional features and highest protocol version of one peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CapabilitySet {
+    pub features: Capabilities,
+    pub max_protocol_version: ProtocolVersion,
+}
+
+impl CapabilitySet {
+    /// What peers that do not advertise capabilities support.
+    pub const BASELINE: Self = Self {
+        features: Capabilities::empty(),
+        max_protocol_version: ProtocolVersion::V1,
+    };
+    /// The protocol version of this build, without any optional features.
+    ///
+    /// Features that depend on local configuration, such as [`Capabilities::RELAY`], are added by
+    /// the caller.
+    pub const CURRENT: Self = Self {
+        features: Capabilities::empty(),
+        max_protocol_version: ProtocolVersion::CURRENT,
+    };
+
+    /// Return these capabilities with `features` added.
+    #[must_use]
+    pub const fn with_features(mut self, features: Capabilities) -> Self {
+        self.features = self.features.union(features);
+        self
+    }
+
+    /// Return whether every feature in `features` is supported.
+    #[must_use]
+    pub const fn supports(&self, features: Capabilities) -> bool {
+        self.features.contains(features)
+    }
+
+    /// Return what two peers with these and the `remote` capabilities can use with each other.
+    ///
+    /// That is the features both support and the lower of both maximum protocol versions.
+    #[must_use]
+    pub fn negotiate(&self, remote: &Self) -> Self {
+        Self {
+            features: self.features.intersection(remote.features),
+            max_protocol_version: self.max_protocol_version.min(remote.max_protocol_version),
+        }
+    }
+
+    /// Decode optional wire capabilities, treating absent ones as [`Self::BASELINE`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscoveryProtocolError`] when present capabilities are malformed.
+    pub fn from_optional_proto_view(
+        capabilities: Option<&PeerCapabilitiesView<'_>>,
+    ) -> Result<Self, DiscoveryProtocolError> {
+        capabilities.map_or(Ok(Self::BASELINE), Self::decode_proto_view)
+    }
+}
+
+impl Default for CapabilitySet {
+    fn default() -> Self {
+        Self::CURRENT
+    }
+}
+
+impl proto::ProtoCodec for CapabilitySet {
+    type DecodeError = DiscoveryProtocolError;
+    type Proto = PeerCapabilities;
+
+    fn to_proto(&self) -> Self::Proto {
+        PeerCapabilities {
+            max_protocol_version: self.max_protocol_version.0,
+            features: self.features.bits(),
+            ..PeerCapabilities::default()
+        }
+    }
+
+    fn from_proto(capabilities: Self::Proto) -> Result<Self, Self::DecodeError> {
+        capabilities_from_wire(capabilities.max_protocol_version, capabilities.features)
+    }
+}
+
+impl DecodeProtoView for CapabilitySet {
+    type Error = DiscoveryProtocolError;
+    type ProtoView<'a> = PeerCapabilitiesView<'a>;
+
+    fn decode_proto_view(capabilities: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        capabilities_from_wire(capabilities.max_protocol_version, capabilities.features)
+    }
+}
+
+fn capabilities_from_wire(
+    max_protocol_version: u32,
+    features: u64,
+) -> Result<CapabilitySet, DiscoveryProtocolError> {
+    ensure!(
+        max_protocol_version >= ProtocolVersion::V1.0,
+        discovery_protocol_error::InvalidProtocolVersionSnafu {
+            field: "PeerCapabilities.max_protocol_version",
+            version: max_protocol_version,
+        }
+    );
+    Ok(CapabilitySet {
+        features: Capabilities::from_bits_truncate(features),
+        max_protocol_version: ProtocolVersion(max_protocol_version),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_messages::proto::{DecodeProto, EncodeProto};
+    use std::assert_matches;
+
+    #[test]
+    fn negotiation_keeps_common_features_and_the_lower_version() {
+        let newer = CapabilitySet {
+            features: Capabilities::RELAY | Capabilities::SNAPSHOTS_V2,
+            max_protocol_version: ProtocolVersion(3),
+        };
+        let older = CapabilitySet {
+            features: Capabilities::SNAPSHOTS_V2,
+            max_protocol_version: ProtocolVersion(2),
+        };
+
+        let negotiated = newer.negotiate(&older);
+        assert_eq!(negotiated, older.negotiate(&newer));
+        assert!(negotiated.supports(Capabilities::SNAPSHOTS_V2));
+        assert!(!negotiated.supports(Capabilities::RELAY));
+        assert_eq!(negotiated.max_protocol_version, ProtocolVersion(2));
+        assert_eq!(
+            newer.negotiate(&CapabilitySet::BASELINE),
+            CapabilitySet::BASELINE
+        );
+    }
+
+    #[test]
+    fn wire_capabilities_drop_unknown_features_and_reject_version_zero() {
+        let capabilities = CapabilitySet::CURRENT.with_features(Capabilities::RELAY);
+        assert_eq!(
+            CapabilitySet::decode_proto(capabilities.encode_proto()).unwrap(),
+            capabilities
+        );
+
+        let from_future_peer = PeerCapabilities {
+            max_protocol_version: 7,
+            features: Capabilities::RELAY.bits() | 1 << 40,
+            ..PeerCapabilities::default()
+        };
+        assert_eq!(
+            CapabilitySet::decode_proto(from_future_peer).unwrap(),
+            CapabilitySet {
+                features: Capabilities::RELAY,
+                max_protocol_version: ProtocolVersion(7),
+            }
+        );
+
+        let invalid = PeerCapabilities {
+            max_protocol_version: 0,
+            ..PeerCapabilities::default()
+        };
+        assert_matches!(
+            CapabilitySet::decode_proto(invalid),
+            Err(DiscoveryProtocolError::InvalidProtocolVersion { version: 0, .. })
+        );
+    }
+}
@@ -17,6 +17,10 @@ pub enum ServiceError {
     ThreadJoin,
     #[snafu(display("Error during buffa decoding operations: {source}"))]
     Proto { source: buffa::DecodeError },
+    #[snafu(display("No local network interface matches '{selection}'"))]
+    InterfaceNotFound {
+        selection: crate::InterfaceSelection,
+    },
     #[cfg(feature = "zeroconf-support")]
     #[snafu(display("Error with a zeroconf service operation: {source}"))]
     Zeroconf {
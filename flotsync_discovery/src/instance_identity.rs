@@ -0,0 +1,123 @@
+//! Persistent identity of the local discovery instance.
+//!
+//! Peers key everything they learn from announcements by the announcing instance id. An instance
+//! that picks a fresh id on every launch looks like a new peer after each restart, while its old id
+//! lingers on other machines until it expires. Storing the id in a small file keeps it stable
+//! across restarts of the same installation.
+
+use snafu::prelude::*;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+const TEMP_FILE_SUFFIX: &str = ".tmp";
+
+/// Errors loading or storing the local instance id.
+#[derive(Debug, Snafu)]
+pub enum InstanceIdError {
+    #[snafu(display("Could not read instance id file {}: {source}", path.display()))]
+    ReadInstanceId { path: PathBuf, source: io::Error },
+    #[snafu(display("Instance id file {} does not contain a UUID: {source}", path.display()))]
+    ParseInstanceId { path: PathBuf, source: uuid::Error },
+    #[snafu(display("Instance id file {} contains the nil UUID", path.display()))]
+    NilInstanceId { path: PathBuf },
+    #[snafu(display("Could not write instance id file {}: {source}", path.display()))]
+    WriteInstanceId { path: PathBuf, source: io::Error },
+}
+
+/// Load the instance id stored at `path`, or generate and store a new one if there is none yet.
+///
+/// The file holds the hyphenated UUID on a single line.
+///
+/// # Errors
+///
+/// Fails if the file exists but cannot be read or does not hold a non-nil UUID, or if a new id
+/// cannot be written. An unreadable file is never replaced, since that would silently change the
+/// identity of this instance.
+pub fn load_or_create_instance_id(path: &Path) -> Result<Uuid, InstanceIdError> {
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            let instance_id =
+                Uuid::parse_str(content.trim()).context(ParseInstanceIdSnafu { path })?;
+            ensure!(!instance_id.is_nil(), NilInstanceIdSnafu { path });
+            Ok(instance_id)
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            let instance_id = Uuid::new_v4();
+            store_instance_id(path, instance_id)?;
+            Ok(instance_id)
+        }
+        Err(source) => Err(source).context(ReadInstanceIdSnafu { path }),
+    }
+}
+
+/// Write `instance_id` to `path` such that the file either holds the complete id or is unchanged.
+fn store_instance_id(path: &Path, instance_id: Uuid) -> Result<(), InstanceIdError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context(WriteInstanceIdSnafu { path: parent })?;
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(TEMP_FILE_SUFFIX);
+    let temp_path = path.with_file_name(temp_name);
+    let mut file =
+        fs::File::create(&temp_path).context(WriteInstanceIdSnafu { path: &temp_path })?;
+    writeln!(file, "{}", instance_id.as_hyphenated())
+        .and_then(|()| file.sync_all())
+        .context(WriteInstanceIdSnafu { path: &temp_path })?;
+    fs::rename(&temp_path, path).context(WriteInstanceIdSnafu { path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::assert_matches;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "flotsync-discovery-{name}-{}",
+            Uuid::new_v4().as_simple()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn instance_ids_survive_restarts() {
+        let dir = test_dir("instance-id");
+        let path = dir.join("state").join("instance-id");
+
+        let first = load_or_create_instance_id(&path).unwrap();
+        assert!(!first.is_nil());
+        assert_eq!(load_or_create_instance_id(&path).unwrap(), first);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{}\n", first.as_hyphenated())
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_instance_id_files_are_not_replaced() {
+        let dir = test_dir("invalid-instance-id");
+        let path = dir.join("instance-id");
+
+        fs::write(&path, "not a uuid\n").unwrap();
+        assert_matches!(
+            load_or_create_instance_id(&path),
+            Err(InstanceIdError::ParseInstanceId { .. })
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not a uuid\n");
+
+        fs::write(&path, format!("{}\n", Uuid::nil())).unwrap();
+        assert_matches!(
+            load_or_create_instance_id(&path),
+            Err(InstanceIdError::NilInstanceId { .. })
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,18 +1,17 @@
 //! Discovery services for finding peers and publishing local peer announcements.
 
-use derive_more::{Deref, Display, From};
-
 #[cfg(feature = "kompact-runtime")]
 pub use kompact;
 pub use uuid;
 /// Kompact configuration keys consumed by peer-announcement components.
 #[cfg(feature = "peer-announcement-via-kompact")]
 pub mod config_keys {
-    use crate::DEFAULT_DISCOVERY_PORT;
+    use crate::{DEFAULT_DISCOVERY_PORT, DEFAULT_IPV6_ANNOUNCEMENT_GROUP};
     use kompact::{
-        config::{BooleanValue, StringValue},
+        config::{BooleanValue, DurationValue, RealValue, StringValue},
         kompact_config,
     };
+    use std::time::Duration;
 
     fn default_peer_announcement_bind_addr() -> String {
         format!("0.0.0.0:{DEFAULT_DISCOVERY_PORT}")
@@ -27,6 +26,61 @@ pub mod config_keys {
         version = "0.1.0"
     }
 
+    kompact_config! {
+        PEER_ANNOUNCEMENT_BROADCAST_TARGET_PORT,
+        key = "flotsync.discovery.peer-announcement.broadcast-target-port",
+        type = StringValue,
+        default = String::new(),
+        doc = "UDP port that peer-announcement broadcasts are sent to. Empty means the port of the bind address. This does not affect local socket binding or advertised routes.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_ANNOUNCEMENT_INTERFACES,
+        key = "flotsync.discovery.peer-announcement.interfaces",
+        type = StringValue,
+        default = String::from("*"),
+        doc = "Local interfaces that peer announcements are sent and accepted on: `*` for all interfaces, an interface name such as `eth0`, or an address assigned to the interface.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_ANNOUNCEMENT_IPV6_MULTICAST_GROUP,
+        key = "flotsync.discovery.peer-announcement.ipv6-multicast-group",
+        type = StringValue,
+        default = DEFAULT_IPV6_ANNOUNCEMENT_GROUP.to_string(),
+        doc = "IPv6 multicast group that peer announcements are sent to when the bind address is IPv6. IPv4 binds use per-interface subnet broadcasts instead.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_ANNOUNCEMENT_INTERVAL,
+        key = "flotsync.discovery.peer-announcement.interval",
+        type = DurationValue,
+        default = Duration::from_secs(5),
+        doc = "Base time between peer announcements. Announcements return to this interval whenever another instance is heard.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_ANNOUNCEMENT_MAX_INTERVAL,
+        key = "flotsync.discovery.peer-announcement.max-interval",
+        type = DurationValue,
+        default = Duration::from_mins(1),
+        doc = "Upper bound for the time between peer announcements. While no other instance is heard, the interval doubles after every announcement up to this bound.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_ANNOUNCEMENT_JITTER,
+        key = "flotsync.discovery.peer-announcement.jitter",
+        type = RealValue,
+        default = 0.2,
+        validate = |value| (0.0..=1.0).contains(value),
+        doc = "Fraction of the current interval by which each announcement delay is randomised in either direction.",
+        version = "0.1.0"
+    }
+
     kompact_config! {
         PEER_ANNOUNCEMENT_BIND_REUSE_ADDRESS,
         key = "flotsync.discovery.peer-announcement.bind-reuse-address",
@@ -36,8 +90,12 @@ pub mod config_keys {
         version = "0.1.0"
     }
 }
+pub mod capabilities;
 pub mod endpoint_selection;
 pub mod errors;
+pub mod instance_identity;
+pub mod net_types;
+pub mod peer_registry;
 pub mod protocol;
 pub mod services;
 pub mod utils;
@@ -45,13 +103,14 @@ pub mod utils;
 #[cfg(feature = "zeroconf-support")]
 pub use zeroconf;
 
-/// A new-type wrapper for socket ports.
-#[derive(Clone, Copy, Debug, Deref, Display, PartialEq, Eq, Hash, From, PartialOrd, Ord)]
-pub struct SocketPort(u16);
+pub use net_types::{InterfaceSelection, MulticastGroupAddr, SocketPort};
 
 /// Default UDP port for Flotsync peer discovery.
 pub const DEFAULT_DISCOVERY_PORT: SocketPort = SocketPort(52156);
 
+/// Default IPv6 multicast group for Flotsync peer announcements.
+pub const DEFAULT_IPV6_ANNOUNCEMENT_GROUP: MulticastGroupAddr = MulticastGroupAddr::IPV6_ALL_NODES;
+
 #[cfg(test)]
 mod tests {
     #[allow(unused)]
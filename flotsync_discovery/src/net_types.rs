@@ -0,0 +1,308 @@
+//! Validated network value types for discovery and transport options.
+//!
+//! Every type here implements [`FromStr`] and [`Display`](fmt::Display) with the same textual
+//! form, so options can be read from config files and CLI flags and reported back unchanged.
+
+use crate::endpoint_selection::InterfaceSnapshotEntry;
+use derive_more::{Deref, Display, From};
+use pnet_datalink::NetworkInterface;
+use snafu::prelude::*;
+use std::{
+    fmt,
+    net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    num::ParseIntError,
+    str::FromStr,
+};
+
+/// Errors parsing or validating a network value.
+#[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+pub enum NetValueError {
+    #[snafu(display("'{input}' is not a port number: {source}"))]
+    InvalidPort {
+        input: String,
+        source: ParseIntError,
+    },
+    #[snafu(display("{value} is outside of the port range 0-{}", u16::MAX))]
+    PortOutOfRange { value: u32 },
+    #[snafu(display("'{input}' is not an IP address: {source}"))]
+    InvalidAddress {
+        input: String,
+        source: AddrParseError,
+    },
+    #[snafu(display("{address} is not a multicast address"))]
+    NotMulticast { address: IpAddr },
+    #[snafu(display("'{input}' is not a valid interface name"))]
+    InvalidInterfaceName { input: String },
+}
+
+/// A new-type wrapper for socket ports.
+#[derive(Clone, Copy, Debug, Deref, Display, PartialEq, Eq, Hash, From, PartialOrd, Ord)]
+pub struct SocketPort(pub(crate) u16);
+
+impl SocketPort {
+    #[must_use]
+    pub const fn new(port: u16) -> Self {
+        Self(port)
+    }
+
+    #[must_use]
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+}
+
+impl TryFrom<u32> for SocketPort {
+    type Error = NetValueError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        u16::try_from(value)
+            .map(Self)
+            .map_err(|_| NetValueError::PortOutOfRange { value })
+    }
+}
+
+impl FromStr for SocketPort {
+    type Err = NetValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u32 = s.trim().parse().context(InvalidPortSnafu { input: s })?;
+        Self::try_from(value)
+    }
+}
+
+/// An IPv4 or IPv6 multicast group address.
+#[derive(Clone, Copy, Debug, Deref, Display, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MulticastGroupAddr(pub(crate) IpAddr);
+
+impl MulticastGroupAddr {
+    /// The IPv4 mDNS group `224.0.0.251`.
+    pub const MDNS_IPV4: Self = Self(IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)));
+    /// The IPv6 mDNS group `ff02::fb`.
+    pub const MDNS_IPV6: Self = Self(IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb)));
+    /// The link-local IPv6 all-nodes group `ff02::1`.
+    ///
+    /// Every IPv6 host receives this group without joining it, which makes it the IPv6
+    /// counterpart of an IPv4 subnet broadcast.
+    pub const IPV6_ALL_NODES: Self = Self(IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1)));
+
+    /// Wrap `address` if it is a multicast address.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `address` is not in the IPv4 or IPv6 multicast range.
+    pub fn new(address: IpAddr) -> Result<Self, NetValueError> {
+        ensure!(address.is_multicast(), NotMulticastSnafu { address });
+        Ok(Self(address))
+    }
+
+    #[must_use]
+    pub const fn ip(self) -> IpAddr {
+        self.0
+    }
+
+    /// The address datagrams for this group on `port` are sent to.
+    #[must_use]
+    pub const fn socket_addr(self, port: SocketPort) -> SocketAddr {
+        SocketAddr::new(self.0, port.0)
+    }
+}
+
+impl TryFrom<IpAddr> for MulticastGroupAddr {
+    type Error = NetValueError;
+
+    fn try_from(address: IpAddr) -> Result<Self, Self::Error> {
+        Self::new(address)
+    }
+}
+
+impl TryFrom<Ipv4Addr> for MulticastGroupAddr {
+    type Error = NetValueError;
+
+    fn try_from(address: Ipv4Addr) -> Result<Self, Self::Error> {
+        Self::new(IpAddr::V4(address))
+    }
+}
+
+impl TryFrom<Ipv6Addr> for MulticastGroupAddr {
+    type Error = NetValueError;
+
+    fn try_from(address: Ipv6Addr) -> Result<Self, Self::Error> {
+        Self::new(IpAddr::V6(address))
+    }
+}
+
+impl FromStr for MulticastGroupAddr {
+    type Err = NetValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let address: IpAddr = s.trim().parse().context(InvalidAddressSnafu { input: s })?;
+        Self::new(address)
+    }
+}
+
+/// Which local network interfaces an option applies to.
+///
+/// The textual form is `*` for all interfaces, an IP address for the interface that has it
+/// assigned, or otherwise an interface name such as `eth0`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum InterfaceSelection {
+    /// Every interface.
+    #[default]
+    All,
+    /// The interface with this OS-level name.
+    Name(String),
+    /// The interface this address is assigned to.
+    Address(IpAddr),
+}
+
+impl InterfaceSelection {
+    const ALL: &'static str = "*";
+
+    /// Select the interface named `name`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `name` is empty, contains whitespace, or could be mistaken for another selection.
+    pub fn name(name: impl Into<String>) -> Result<Self, NetValueError> {
+        let name = name.into();
+        ensure!(
+            !name.is_empty()
+                && name != Self::ALL
+                && !name.chars().any(char::is_whitespace)
+                && name.parse::<IpAddr>().is_err(),
+            InvalidInterfaceNameSnafu { input: name }
+        );
+        Ok(Self::Name(name))
+    }
+
+    /// Whether `interface` is selected.
+    #[must_use]
+    pub fn matches(&self, interface: &InterfaceSnapshotEntry) -> bool {
+        self.matches_name_and_ips(
+            &interface.name,
+            interface.addresses.iter().map(|address| address.ip),
+        )
+    }
+
+    /// Whether the `pnet_datalink` `interface` is selected.
+    pub(crate) fn matches_network_interface(&self, interface: &NetworkInterface) -> bool {
+        self.matches_name_and_ips(
+            &interface.name,
+            interface.ips.iter().map(|network| network.ip()),
+        )
+    }
+
+    fn matches_name_and_ips(&self, name: &str, mut ips: impl Iterator<Item = IpAddr>) -> bool {
+        match self {
+            Self::All => true,
+            Self::Name(selected) => selected == name,
+            Self::Address(selected) => ips.any(|ip| ip == *selected),
+        }
+    }
+}
+
+impl fmt::Display for InterfaceSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => f.write_str(Self::ALL),
+            Self::Name(name) => f.write_str(name),
+            Self::Address(ip) => write!(f, "{ip}"),
+        }
+    }
+}
+
+impl FromStr for InterfaceSelection {
+    type Err = NetValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == Self::ALL {
+            Ok(Self::All)
+        } else if let Ok(ip) = s.parse::<IpAddr>() {
+            Ok(Self::Address(ip))
+        } else {
+            Self::name(s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint_selection::{InterfaceAddress, InterfaceFlags};
+    use std::assert_matches;
+
+    #[test]
+    fn socket_ports_are_range_checked() {
+        assert_eq!(SocketPort::try_from(52156u32), Ok(SocketPort::new(52156)));
+        assert_eq!("8080".parse(), Ok(SocketPort::new(8080)));
+        assert_matches!(
+            SocketPort::try_from(65536u32),
+            Err(NetValueError::PortOutOfRange { value: 65536 })
+        );
+        assert_matches!(
+            "70000".parse::<SocketPort>(),
+            Err(NetValueError::PortOutOfRange { .. })
+        );
+        assert_matches!(
+            "http".parse::<SocketPort>(),
+            Err(NetValueError::InvalidPort { .. })
+        );
+    }
+
+    #[test]
+    fn multicast_groups_reject_unicast_addresses() {
+        let group: MulticastGroupAddr = "224.0.0.251".parse().unwrap();
+        assert_eq!(group.to_string(), "224.0.0.251");
+        assert_eq!(
+            group.socket_addr(SocketPort::new(5353)),
+            "224.0.0.251:5353".parse().unwrap()
+        );
+        assert_eq!("ff02::fb".parse(), Ok(MulticastGroupAddr::MDNS_IPV6));
+        for group in [
+            MulticastGroupAddr::MDNS_IPV4,
+            MulticastGroupAddr::MDNS_IPV6,
+            MulticastGroupAddr::IPV6_ALL_NODES,
+        ] {
+            assert_eq!(MulticastGroupAddr::new(group.ip()), Ok(group));
+        }
+        assert_matches!(
+            "192.168.1.1".parse::<MulticastGroupAddr>(),
+            Err(NetValueError::NotMulticast { .. })
+        );
+        assert_matches!(
+            "not-an-ip".parse::<MulticastGroupAddr>(),
+            Err(NetValueError::InvalidAddress { .. })
+        );
+    }
+
+    #[test]
+    fn interface_selections_round_trip_and_match() {
+        let interface = InterfaceSnapshotEntry::new(
+            "eth0",
+            InterfaceFlags::UP,
+            [InterfaceAddress {
+                ip: "192.168.1.20".parse().unwrap(),
+                prefix: 24,
+            }],
+        );
+        for input in ["*", "eth0", "192.168.1.20"] {
+            let selection: InterfaceSelection = input.parse().unwrap();
+            assert_eq!(selection.to_string(), input);
+            assert!(selection.matches(&interface));
+        }
+        assert!(
+            !InterfaceSelection::name("wlan0")
+                .unwrap()
+                .matches(&interface)
+        );
+        assert_matches!(
+            "eth 0".parse::<InterfaceSelection>(),
+            Err(NetValueError::InvalidInterfaceName { .. })
+        );
+        assert_matches!(
+            InterfaceSelection::name("10.0.0.1"),
+            Err(NetValueError::InvalidInterfaceName { .. })
+        );
+    }
+}
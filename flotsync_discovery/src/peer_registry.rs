@@ -0,0 +1,317 @@
+//! One record per discovered peer instance, merged from all discovery backends.
+//!
+//! The same instance is usually seen several times per announcement cycle: once per interface
+//! that shares a network with it, and once per backend that it announces through. The
+//! [`PeerRegistry`] merges all of these sightings by instance id, so consumers see one peer with
+//! the union of its advertised routes. Each source expires on its own, so a peer that disappears
+//! from one interface keeps its record as long as it is still seen through another.
+
+use crate::protocol::DiscoveryRoute;
+use std::{
+    collections::{BTreeMap, HashMap, hash_map::Entry},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// A discovery mechanism that peers can be seen through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DiscoveryBackend {
+    /// Flotsync's own UDP peer announcements.
+    PeerAnnouncement,
+    /// Zeroconf mDNS service discovery.
+    Mdns,
+}
+
+/// Where a peer was seen: the backend and, where known, the local interface.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PeerSource {
+    pub backend: DiscoveryBackend,
+    pub interface: Option<String>,
+}
+
+/// One sighting of a peer instance through one source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerSighting {
+    pub instance_id: Uuid,
+    pub source: PeerSource,
+    /// Every route the peer currently advertises through this source.
+    pub routes: Vec<DiscoveryRoute>,
+}
+
+#[cfg(feature = "peer-announcement-via-kompact")]
+impl From<crate::services::PeerAnnouncementObserved> for PeerSighting {
+    fn from(observed: crate::services::PeerAnnouncementObserved) -> Self {
+        Self {
+            instance_id: observed.instance_id,
+            source: PeerSource {
+                backend: DiscoveryBackend::PeerAnnouncement,
+                interface: observed.interface,
+            },
+            routes: observed.routes,
+        }
+    }
+}
+
+/// How recording a sighting changed the registry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerChange {
+    /// The instance was not known before.
+    Discovered,
+    /// The instance was known, but the union of its routes changed.
+    RoutesChanged,
+    /// The instance was known with the same routes; only its liveness was refreshed.
+    Refreshed,
+}
+
+/// Everything known about one peer instance.
+#[derive(Clone, Debug)]
+pub struct PeerRecord {
+    instance_id: Uuid,
+    sources: BTreeMap<PeerSource, SourceState>,
+}
+
+impl PeerRecord {
+    #[must_use]
+    pub fn instance_id(&self) -> Uuid {
+        self.instance_id
+    }
+
+    /// The union of the routes advertised through all sources, without duplicates.
+    ///
+    /// Routes are ordered by the first source, in [`PeerSource`] order, that advertises them.
+    #[must_use]
+    pub fn routes(&self) -> Vec<DiscoveryRoute> {
+        let mut routes: Vec<DiscoveryRoute> = Vec::new();
+        for route in self.sources.values().flat_map(|source| &source.routes) {
+            if !routes.contains(route) {
+                routes.push(*route);
+            }
+        }
+        routes
+    }
+
+    /// The sources this instance is currently seen through.
+    pub fn sources(&self) -> impl Iterator<Item = &PeerSource> {
+        self.sources.keys()
+    }
+
+    /// When this instance was last seen through any source.
+    #[must_use]
+    pub fn last_seen(&self) -> Option<Instant> {
+        self.sources.values().map(|source| source.last_seen).max()
+    }
+}
+
+/// The merged view of all peer instances seen by local discovery.
+#[derive(Clone, Debug, Default)]
+pub struct PeerRegistry {
+    peers: HashMap<Uuid, PeerRecord>,
+}
+
+impl PeerRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge `sighting`, seen at `now`, into the record of its instance.
+    pub fn record(&mut self, sighting: PeerSighting, now: Instant) -> PeerChange {
+        let PeerSighting {
+            instance_id,
+            source,
+            routes,
+        } = sighting;
+        let state = SourceState {
+            routes,
+            last_seen: now,
+        };
+        match self.peers.entry(instance_id) {
+            Entry::Vacant(entry) => {
+                entry.insert(PeerRecord {
+                    instance_id,
+                    sources: BTreeMap::from([(source, state)]),
+                });
+                PeerChange::Discovered
+            }
+            Entry::Occupied(mut entry) => {
+                let record = entry.get_mut();
+                let routes_before = record.routes();
+                record.sources.insert(source, state);
+                let routes_after = record.routes();
+                if routes_before.len() == routes_after.len()
+                    && routes_after
+                        .iter()
+                        .all(|route| routes_before.contains(route))
+                {
+                    PeerChange::Refreshed
+                } else {
+                    PeerChange::RoutesChanged
+                }
+            }
+        }
+    }
+
+    /// Drop the sources of `instance_id` on `backend`, e.g. after an mDNS goodbye.
+    ///
+    /// Returns whether the instance lost its last source and was removed.
+    pub fn forget_backend(&mut self, instance_id: Uuid, backend: DiscoveryBackend) -> bool {
+        let Some(record) = self.peers.get_mut(&instance_id) else {
+            return false;
+        };
+        record.sources.retain(|source, _| source.backend != backend);
+        if record.sources.is_empty() {
+            self.peers.remove(&instance_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drop every source that was not seen within `ttl` before `now`.
+    ///
+    /// Returns the instances that lost their last source and were removed.
+    pub fn expire(&mut self, now: Instant, ttl: Duration) -> Vec<Uuid> {
+        let mut removed = Vec::new();
+        self.peers.retain(|instance_id, record| {
+            record
+                .sources
+                .retain(|_, source| now.saturating_duration_since(source.last_seen) <= ttl);
+            let keep = !record.sources.is_empty();
+            if !keep {
+                removed.push(*instance_id);
+            }
+            keep
+        });
+        removed
+    }
+
+    #[must_use]
+    pub fn get(&self, instance_id: &Uuid) -> Option<&PeerRecord> {
+        self.peers.get(instance_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PeerRecord> {
+        self.peers.values()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SourceState {
+    routes: Vec<DiscoveryRoute>,
+    last_seen: Instant,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const INSTANCE: Uuid = Uuid::from_u128(7);
+
+    fn sighting(backend: DiscoveryBackend, interface: &str, routes: &[&str]) -> PeerSighting {
+        PeerSighting {
+            instance_id: INSTANCE,
+            source: PeerSource {
+                backend,
+                interface: Some(interface.to_owned()),
+            },
+            routes: routes
+                .iter()
+                .map(|route| DiscoveryRoute::Udp(route.parse().unwrap()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn sightings_of_one_instance_merge_into_one_record() {
+        let start = Instant::now();
+        let mut registry = PeerRegistry::new();
+
+        let wired = sighting(
+            DiscoveryBackend::PeerAnnouncement,
+            "eth0",
+            &["192.168.1.5:52156"],
+        );
+        let vpn = sighting(
+            DiscoveryBackend::PeerAnnouncement,
+            "tun0",
+            &["10.8.0.5:52156", "192.168.1.5:52156"],
+        );
+        let mdns = sighting(DiscoveryBackend::Mdns, "eth0", &["192.168.1.5:52156"]);
+
+        assert_eq!(
+            registry.record(wired.clone(), start),
+            PeerChange::Discovered
+        );
+        assert_eq!(registry.record(vpn, start), PeerChange::RoutesChanged);
+        assert_eq!(registry.record(mdns, start), PeerChange::Refreshed);
+        assert_eq!(registry.len(), 1);
+
+        let record = registry.get(&INSTANCE).unwrap();
+        assert_eq!(record.sources().count(), 3);
+        assert_eq!(
+            record.routes().into_iter().collect::<HashSet<_>>(),
+            HashSet::from([
+                DiscoveryRoute::Udp("192.168.1.5:52156".parse().unwrap()),
+                DiscoveryRoute::Udp("10.8.0.5:52156".parse().unwrap()),
+            ])
+        );
+        assert_eq!(
+            record.routes().len(),
+            2,
+            "routes are deduplicated across sources"
+        );
+
+        // Only the wired sightings keep coming; the VPN source expires on its own.
+        let later = start + Duration::from_secs(20);
+        registry.record(wired.clone(), later);
+        assert!(registry.expire(later, Duration::from_secs(15)).is_empty());
+        let record = registry.get(&INSTANCE).unwrap();
+        assert_eq!(record.sources().count(), 1);
+        assert_eq!(
+            record.routes(),
+            [DiscoveryRoute::Udp("192.168.1.5:52156".parse().unwrap())]
+        );
+        assert_eq!(record.last_seen(), Some(later));
+
+        assert_eq!(
+            registry.expire(later + Duration::from_secs(30), Duration::from_secs(15)),
+            [INSTANCE]
+        );
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn forgetting_a_backend_keeps_instances_seen_through_others() {
+        let now = Instant::now();
+        let mut registry = PeerRegistry::new();
+        registry.record(
+            sighting(DiscoveryBackend::Mdns, "eth0", &["192.168.1.5:52156"]),
+            now,
+        );
+        registry.record(
+            sighting(
+                DiscoveryBackend::PeerAnnouncement,
+                "eth0",
+                &["192.168.1.5:52156"],
+            ),
+            now,
+        );
+
+        assert!(!registry.forget_backend(INSTANCE, DiscoveryBackend::Mdns));
+        assert_eq!(registry.get(&INSTANCE).unwrap().sources().count(), 1);
+        assert!(registry.forget_backend(INSTANCE, DiscoveryBackend::PeerAnnouncement));
+        assert!(registry.is_empty());
+        assert!(!registry.forget_backend(INSTANCE, DiscoveryBackend::Mdns));
+    }
+}
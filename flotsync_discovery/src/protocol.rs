@@ -1,5 +1,6 @@
 //! Shared peer-discovery protocol helpers.
 
+use crate::capabilities::CapabilitySet;
 use flotsync_core::GroupId;
 use flotsync_messages::{
     buffa::{DecodeError, EnumValue, MessageField},
@@ -125,22 +126,29 @@ pub struct DecodedPeer {
     pub instance_id: Uuid,
     /// Reachability endpoints advertised by this peer instance.
     pub listening_on: Vec<DiscoveryRoute>,
+    /// Advertised capabilities, or [`CapabilitySet::BASELINE`] for peers that advertise none.
+    pub capabilities: CapabilitySet,
 }
 
 impl DecodeProto for DecodedPeer {
     type Error = DiscoveryProtocolError;
     type Proto = Peer;
 
-    fn decode_proto(peer: Self::Proto) -> Result<Self, Self::Error> {
+    fn decode_proto(mut peer: Self::Proto) -> Result<Self, Self::Error> {
         ensure!(
             !peer.listening_on.is_empty(),
             discovery_protocol_error::EmptyPeerRoutesSnafu
         );
         let instance_id = uuid_from_wire(&peer.instance_uuid, "Peer.instance_uuid")?;
+        let capabilities = peer
+            .capabilities
+            .take()
+            .map_or(Ok(CapabilitySet::BASELINE), CapabilitySet::decode_proto)?;
         let listening_on = DiscoveryRoute::decode_proto_collection(peer.listening_on)?;
         Ok(Self {
             instance_id,
             listening_on,
+            capabilities,
         })
     }
 }
@@ -156,9 +164,11 @@ impl DecodeProtoView for DecodedPeer {
         );
         let instance_id = uuid_from_wire(peer.instance_uuid, "Peer.instance_uuid")?;
         let listening_on = DiscoveryRoute::decode_proto_view_collection(&peer.listening_on)?;
+        let capabilities = CapabilitySet::from_optional_proto_view(peer.capabilities.as_option())?;
         Ok(Self {
             instance_id,
             listening_on,
+            capabilities,
         })
     }
 }
@@ -214,6 +224,9 @@ pub enum DiscoveryProtocolError {
     /// A route used an invalid UDP/TCP port.
     #[snafu(display("Field '{field}' used invalid socket port {port}."))]
     InvalidRoutePort { field: &'static str, port: u32 },
+    /// Advertised capabilities named a protocol version that does not exist.
+    #[snafu(display("Field '{field}' used invalid protocol version {version}."))]
+    InvalidProtocolVersion { field: &'static str, version: u32 },
     /// A peer announcement did not advertise any route.
     #[snafu(display("Peer announcement did not contain any listening routes."))]
     EmptyPeerRoutes,
@@ -382,6 +395,11 @@ mod tests {
                 52156
             )))]
         );
+        assert_eq!(
+            decoded.capabilities,
+            CapabilitySet::BASELINE,
+            "peers without advertised capabilities get the baseline"
+        );
     }
 
     #[test]
@@ -0,0 +1,114 @@
+//! Pacing of periodic announcements.
+//!
+//! Every instance on a LAN announces itself on the same port, so fixed intervals make announcers
+//! that started together stay in lockstep, and an instance that is alone keeps broadcasting at full
+//! rate for nobody. [`AnnouncementSchedule`] randomises each delay around the current interval and
+//! doubles the interval, up to a maximum, for as long as no other instance is heard between two
+//! announcements. Hearing another instance returns to the base interval.
+
+use std::time::Duration;
+
+/// The delays between announcements of one announcer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnouncementSchedule {
+    base_interval: Duration,
+    max_interval: Duration,
+    jitter: f64,
+    current_interval: Duration,
+    peer_seen: bool,
+}
+
+impl AnnouncementSchedule {
+    /// Create a schedule that starts at `base_interval` and slows down to at most `max_interval`.
+    ///
+    /// `jitter` is the fraction of the current interval by which each delay may deviate in either
+    /// direction. It is clamped to `0.0..=1.0`, and `max_interval` is raised to `base_interval` if
+    /// it is smaller.
+    #[must_use]
+    pub fn new(base_interval: Duration, max_interval: Duration, jitter: f64) -> Self {
+        Self {
+            base_interval,
+            max_interval: max_interval.max(base_interval),
+            jitter: if jitter.is_nan() {
+                0.0
+            } else {
+                jitter.clamp(0.0, 1.0)
+            },
+            current_interval: base_interval,
+            peer_seen: false,
+        }
+    }
+
+    /// The interval the next delay is drawn around.
+    #[must_use]
+    pub fn current_interval(&self) -> Duration {
+        self.current_interval
+    }
+
+    /// Return the delay until the next announcement and advance the schedule.
+    ///
+    /// `jitter_sample` is a uniformly distributed value in `0.0..1.0` that places the delay within
+    /// the jitter range around the current interval.
+    pub fn next_delay(&mut self, jitter_sample: f64) -> Duration {
+        let interval = self.current_interval;
+        self.current_interval = if self.peer_seen {
+            self.base_interval
+        } else {
+            interval.saturating_mul(2).min(self.max_interval)
+        };
+        self.peer_seen = false;
+        let deviation = self.jitter * (2.0 * jitter_sample.clamp(0.0, 1.0) - 1.0);
+        interval.mul_f64(1.0 + deviation)
+    }
+
+    /// Record that another instance was heard, so announcements continue at the base interval.
+    ///
+    /// Returns whether the schedule had slowed down, in which case the pending announcement should
+    /// be rescheduled so the other instance hears from this one soon.
+    pub fn peer_observed(&mut self) -> bool {
+        self.peer_seen = true;
+        let slowed_down = self.current_interval > self.base_interval;
+        self.current_interval = self.base_interval;
+        slowed_down
+    }
+
+    /// Return to the base interval, e.g. after the announced content changed.
+    pub fn reset(&mut self) {
+        self.current_interval = self.base_interval;
+        self.peer_seen = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_slows_down_while_alone_and_recovers_on_peers() {
+        let mut schedule =
+            AnnouncementSchedule::new(Duration::from_secs(5), Duration::from_secs(30), 0.2);
+
+        let delays = (0..5).map(|_| schedule.next_delay(0.5)).collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [5, 10, 20, 30, 30].map(Duration::from_secs),
+            "the interval doubles up to the maximum while no peers are heard"
+        );
+
+        assert!(schedule.peer_observed());
+        assert!(!schedule.peer_observed());
+        assert_eq!(schedule.next_delay(0.5), Duration::from_secs(5));
+        schedule.peer_observed();
+        assert_eq!(schedule.next_delay(0.5), Duration::from_secs(5));
+        assert_eq!(
+            schedule.next_delay(0.5),
+            Duration::from_secs(5),
+            "the peer heard before the previous announcement keeps the base interval"
+        );
+        assert_eq!(schedule.next_delay(0.5), Duration::from_secs(10));
+
+        schedule.reset();
+        assert_eq!(schedule.next_delay(0.0), Duration::from_secs(4));
+        assert_eq!(schedule.next_delay(1.0), Duration::from_secs(12));
+    }
+}
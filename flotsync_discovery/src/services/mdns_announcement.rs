@@ -1,8 +1,11 @@
 use super::*;
 use crate::{
+    InterfaceSelection,
     SocketPort,
-    zeroconf::{ServiceType, TxtRecord, prelude::TTxtRecord},
+    errors::InterfaceNotFoundSnafu,
+    zeroconf::{NetworkInterface, ServiceType, TxtRecord, prelude::TTxtRecord},
 };
+use pnet_datalink as datalink;
 use std::{borrow::Cow, ffi::OsString};
 use uuid::Uuid;
 
@@ -11,12 +14,19 @@ pub struct Options {
     pub port: SocketPort,
     pub instance_id: Uuid,
     pub service_provider_name: Cow<'static, str>,
+    /// Interfaces the service is announced on.
+    ///
+    /// The mDNS daemon announces on both the IPv4 (`224.0.0.251`) and IPv6 (`ff02::fb`) groups of
+    /// every interface it registers the service on. Selections other than
+    /// [`InterfaceSelection::All`] register on the first matching interface only.
+    pub interfaces: InterfaceSelection,
 }
 impl Options {
     pub const DEFAULT: Self = Self {
         port: SocketPort(52156),
         instance_id: Uuid::nil(),
         service_provider_name: Cow::Borrowed("flotsync_discovery"),
+        interfaces: InterfaceSelection::All,
     };
 
     /// Replaces the current instance id with `instance_id`.
@@ -30,6 +40,13 @@ impl Options {
         self.instance_id = Uuid::new_v4();
     }
 
+    /// Replace the interfaces the service is announced on.
+    #[must_use]
+    pub fn with_interfaces(mut self, interfaces: InterfaceSelection) -> Self {
+        self.interfaces = interfaces;
+        self
+    }
+
     /// Replace the current service provider name with `name`.
     pub fn with_service_provider_name<I>(&mut self, name: I)
     where
@@ -44,30 +61,44 @@ impl Default for Options {
     }
 }
 
+/// TXT record key that carries the announcing instance id.
+pub(super) const INSTANCE_ID_TXT_KEY: &str = "id";
+
+const SERVICE_NAME: &str = "flotsync";
+const PROTOCOL: &str = "udp";
+
+/// The mDNS service type that Flotsync instances register and browse for.
+pub(super) fn flotsync_service_type() -> Result<ServiceType> {
+    ServiceType::new(SERVICE_NAME, PROTOCOL).context(ZeroconfSnafu)
+}
+
 #[derive(Clone, Debug)]
 struct ServiceConfig {
     options: Options,
     service_type: ServiceType,
     txt_record: TxtRecord,
+    network_interface: NetworkInterface,
 }
 impl ServiceConfig {
-    const SERVICE_NAME: &str = "flotsync";
-    const PROTOCOL: &str = "udp";
-
     fn try_from_options(options: Options) -> Result<Self> {
-        let service_type =
-            ServiceType::new(Self::SERVICE_NAME, Self::PROTOCOL).context(ZeroconfSnafu)?;
+        let service_type = flotsync_service_type()?;
 
         let mut txt_record = TxtRecord::new();
 
         txt_record
-            .insert("id", &options.instance_id.as_hyphenated().to_string())
+            .insert(
+                INSTANCE_ID_TXT_KEY,
+                &options.instance_id.as_hyphenated().to_string(),
+            )
             .context(ZeroconfSnafu)?;
 
+        let network_interface = mdns_network_interface(&options.interfaces)?;
+
         Ok(Self {
             options,
             service_type,
             txt_record,
+            network_interface,
         })
     }
 }
@@ -142,49 +173,60 @@ mod kompact_implementation {
             match ServiceConfig::try_from_options(options.clone()) {
                 Ok(config) => {
                     let start_config = config.clone();
-                    let (shutdown_handle, shutdown_watcher) = shutdown::watcher();
                     let actor_ref = self.actor_ref();
 
-                    let join_handle = std::thread::spawn(move || {
-                        let mut service = build_mdns_service(
-                            start_config.service_type,
-                            *start_config.options.port,
-                            start_config.options.service_provider_name.as_ref(),
-                            start_config.txt_record,
-                        );
+                    let shutdown_handle =
+                        shutdown::spawn_thread("mdns-announcement", move |shutdown_token| {
+                            let mut service = build_mdns_service(
+                                start_config.service_type,
+                                *start_config.options.port,
+                                start_config.options.service_provider_name.as_ref(),
+                                start_config.txt_record,
+                                start_config.network_interface,
+                            );
 
-                        let actor_ref_for_callback = actor_ref.clone();
-                        service.set_registered_callback(Box::new(move |result, _context| {
-                            match result {
-                                Ok(registration) => {
-                                    actor_ref_for_callback
-                                        .tell(MdnsAnnouncementMessages::registered(registration));
+                            let actor_ref_for_callback = actor_ref.clone();
+                            service.set_registered_callback(Box::new(move |result, _context| {
+                                match result {
+                                    Ok(registration) => {
+                                        actor_ref_for_callback.tell(
+                                            MdnsAnnouncementMessages::registered(registration),
+                                        );
+                                    }
+                                    Err(e) => {
+                                        actor_ref_for_callback
+                                            .tell(MdnsAnnouncementMessages::registration_failed(e));
+                                    }
+                                }
+                            }));
+                            match service.register() {
+                                Ok(event_loop) => {
+                                    while !shutdown_token.is_shutting_down() {
+                                        // A compromise between super hot polling and shutdown speed.
+                                        if let Err(error) = event_loop.poll(Duration::from_secs(1))
+                                        {
+                                            actor_ref.tell(
+                                                MdnsAnnouncementMessages::registration_failed(
+                                                    error,
+                                                ),
+                                            );
+                                            break;
+                                        }
+                                        std::thread::yield_now();
+                                    }
+                                    drop(event_loop);
                                 }
                                 Err(e) => {
-                                    actor_ref_for_callback
+                                    actor_ref
                                         .tell(MdnsAnnouncementMessages::registration_failed(e));
                                 }
                             }
-                        }));
-                        match service.register() {
-                            Ok(event_loop) => {
-                                while !shutdown_watcher.should_shutdown() {
-                                    // A compromise between super hot polling and shutdown speed.
-                                    if let Err(error) = event_loop.poll(Duration::from_secs(1)) {
-                                        actor_ref.tell(
-                                            MdnsAnnouncementMessages::registration_failed(error),
-                                        );
-                                        break;
-                                    }
-                                    std::thread::yield_now();
-                                }
-                            }
-                            Err(e) => {
-                                actor_ref.tell(MdnsAnnouncementMessages::registration_failed(e));
-                            }
-                        }
-                    });
-                    let shutdown_handle = shutdown_handle.with_thread(join_handle);
+                            // Releasing the registration makes the mDNS daemon withdraw the service
+                            // with a goodbye record (TTL 0), so browsers drop this instance right away
+                            // instead of waiting for their caches to expire.
+                            drop(service);
+                            log::debug!("Withdrew mDNS service registration");
+                        });
                     StateUpdate::transition(ComponentState::Starting {
                         config,
                         shutdown_handle,
@@ -339,10 +381,12 @@ fn build_mdns_service(
     port: u16,
     service_provider_name: &str,
     txt_record: TxtRecord,
+    network_interface: NetworkInterface,
 ) -> crate::zeroconf::MdnsService {
     use crate::zeroconf::prelude::*;
 
     let mut service = crate::zeroconf::MdnsService::new(service_type, port);
+    service.set_network_interface(network_interface);
     let host_name = hostname::get().map_or_else(
         |e| {
             log::warn!("Could not get hostname: {e}");
@@ -364,6 +408,20 @@ fn build_mdns_service(
     service
 }
 
+/// Resolve `selection` to the interface the mDNS daemon registers the service on.
+fn mdns_network_interface(selection: &InterfaceSelection) -> Result<NetworkInterface> {
+    if *selection == InterfaceSelection::All {
+        return Ok(NetworkInterface::Unspec);
+    }
+    datalink::interfaces()
+        .iter()
+        .find(|interface| selection.matches_network_interface(interface))
+        .map(|interface| NetworkInterface::AtIndex(interface.index))
+        .context(InterfaceNotFoundSnafu {
+            selection: selection.clone(),
+        })
+}
+
 #[allow(
     clippy::unnecessary_debug_formatting,
     reason = "Debug formatting preserves escaping for hostnames that failed UTF-8 conversion."
@@ -1 +1,313 @@
+//! mDNS browser for Flotsync services announced by other instances.
+//!
+//! The mDNS daemon reports services as they are resolved and again when they disappear, either
+//! because the announcer withdrew them with a goodbye record or because they expired from the
+//! daemon's cache. Removals only carry the service name, so [`MdnsPeerTable`] remembers which
+//! instance each name was announced by.
 
+use super::mdns_announcement::{INSTANCE_ID_TXT_KEY, flotsync_service_type};
+use crate::{
+    peer_registry::{DiscoveryBackend, PeerSighting, PeerSource},
+    protocol::DiscoveryRoute,
+    zeroconf::{BrowserEvent, ServiceDiscovery, ServiceRemoval, prelude::TTxtRecord},
+};
+use flotsync_utils::option_when;
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+};
+use uuid::Uuid;
+
+/// A change to the set of Flotsync instances visible through mDNS.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MdnsBrowseEvent {
+    /// A service was resolved, or resolved again with changed details.
+    Discovered(PeerSighting),
+    /// The last service of this instance was withdrawn or expired.
+    Removed { instance_id: Uuid },
+}
+
+/// The Flotsync services currently visible through mDNS, by service name.
+#[derive(Clone, Debug, Default)]
+pub struct MdnsPeerTable {
+    instances_by_service: HashMap<String, Uuid>,
+}
+
+impl MdnsPeerTable {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply one browser event and return the resulting change, if any.
+    ///
+    /// Services without a valid instance id in their TXT record are ignored, as are removals of
+    /// services that were never resolved.
+    pub fn apply(&mut self, event: BrowserEvent) -> Option<MdnsBrowseEvent> {
+        match event {
+            BrowserEvent::Add(discovery) => self.add(&discovery),
+            BrowserEvent::Remove(removal) => self.remove(&removal),
+        }
+    }
+
+    /// Forget all services and return every instance that was visible.
+    pub fn clear(&mut self) -> Vec<Uuid> {
+        let instances = self
+            .instances_by_service
+            .drain()
+            .map(|(_, instance_id)| instance_id)
+            .collect::<HashSet<_>>();
+        instances.into_iter().collect()
+    }
+
+    fn add(&mut self, discovery: &ServiceDiscovery) -> Option<MdnsBrowseEvent> {
+        let instance_id = discovery
+            .txt()
+            .as_ref()
+            .and_then(|txt| txt.get(INSTANCE_ID_TXT_KEY))
+            .and_then(|id| Uuid::parse_str(&id).ok())?;
+        self.instances_by_service
+            .insert(discovery.name().clone(), instance_id);
+        let routes = discovery
+            .address()
+            .parse()
+            .map(|ip| DiscoveryRoute::Udp(SocketAddr::new(ip, *discovery.port())))
+            .into_iter()
+            .collect();
+        Some(MdnsBrowseEvent::Discovered(PeerSighting {
+            instance_id,
+            source: PeerSource {
+                backend: DiscoveryBackend::Mdns,
+                interface: None,
+            },
+            routes,
+        }))
+    }
+
+    fn remove(&mut self, removal: &ServiceRemoval) -> Option<MdnsBrowseEvent> {
+        let instance_id = self.instances_by_service.remove(removal.name())?;
+        // An instance may be registered under several names, e.g. after a rename on conflict.
+        let still_visible = self
+            .instances_by_service
+            .values()
+            .any(|other| *other == instance_id);
+        option_when!(!still_visible, MdnsBrowseEvent::Removed { instance_id })
+    }
+}
+
+#[cfg(feature = "zeroconf-via-kompact")]
+mod kompact_implementation {
+    use super::{MdnsBrowseEvent, MdnsPeerTable, flotsync_service_type};
+    use crate::{
+        kompact::prelude::*,
+        utils::shutdown::{self, BlockingThreadShutdown},
+        zeroconf::{BrowserEvent, MdnsBrowser, prelude::*},
+    };
+    use std::time::Duration;
+
+    /// Port on which [`MdnsBrowserComponent`] publishes changes to the visible instances.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct MdnsBrowsePort;
+
+    impl Port for MdnsBrowsePort {
+        type Request = Never;
+        type Indication = MdnsBrowseEvent;
+    }
+
+    /// Browses for Flotsync services while started.
+    #[derive(ComponentDefinition)]
+    pub struct MdnsBrowserComponent {
+        ctx: ComponentContext<Self>,
+        browse_port: ProvidedPort<MdnsBrowsePort>,
+        peers: MdnsPeerTable,
+        shutdown_handle: Option<BlockingThreadShutdown<()>>,
+    }
+
+    impl MdnsBrowserComponent {
+        #[must_use]
+        pub fn new() -> Self {
+            Self {
+                ctx: ComponentContext::uninitialised(),
+                browse_port: ProvidedPort::uninitialised(),
+                peers: MdnsPeerTable::new(),
+                shutdown_handle: None,
+            }
+        }
+
+        fn start_browsing(&mut self) -> HandlerResult {
+            let service_type = match flotsync_service_type() {
+                Ok(service_type) => service_type,
+                Err(error) => {
+                    error!(self.log(), "Could not build the mDNS service type: {error}");
+                    return Handled::OK;
+                }
+            };
+            let actor_ref = self.actor_ref();
+            let shutdown_handle = shutdown::spawn_thread("mdns-browser", move |shutdown_token| {
+                let mut browser = MdnsBrowser::new(service_type);
+                let actor_ref_for_callback = actor_ref.clone();
+                browser.set_service_callback(Box::new(move |result, _context| {
+                    actor_ref_for_callback.tell(MdnsBrowserMessage::from(result));
+                }));
+                match browser.browse_services() {
+                    Ok(event_loop) => {
+                        while !shutdown_token.is_shutting_down() {
+                            // A compromise between super hot polling and shutdown speed.
+                            if let Err(error) = event_loop.poll(Duration::from_secs(1)) {
+                                actor_ref.tell(MdnsBrowserMessage::BrowseFailed(error));
+                                break;
+                            }
+                            std::thread::yield_now();
+                        }
+                    }
+                    Err(error) => actor_ref.tell(MdnsBrowserMessage::BrowseFailed(error)),
+                }
+            });
+            self.shutdown_handle = Some(shutdown_handle);
+            Handled::OK
+        }
+
+        fn stop_browsing(&mut self) -> HandlerResult {
+            let Some(shutdown_handle) = self.shutdown_handle.take() else {
+                return Handled::OK;
+            };
+            // Nothing is tracked while stopped, so report every visible instance as gone. A
+            // restart reports them as discovered again.
+            for instance_id in self.peers.clear() {
+                self.browse_port
+                    .trigger(MdnsBrowseEvent::Removed { instance_id });
+            }
+            Handled::block_on(self, async move |_async_self| {
+                shutdown_handle.shutdown().await.benign_err()?;
+                Handled::OK
+            })
+        }
+    }
+
+    impl Default for MdnsBrowserComponent {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl ComponentLifecycle for MdnsBrowserComponent {
+        fn on_start(&mut self) -> HandlerResult {
+            self.start_browsing()
+        }
+
+        fn on_stop(&mut self) -> HandlerResult {
+            self.stop_browsing()
+        }
+
+        fn on_kill(&mut self) -> HandlerResult {
+            self.stop_browsing()
+        }
+    }
+
+    ignore_requests!(MdnsBrowsePort, MdnsBrowserComponent);
+
+    impl Actor for MdnsBrowserComponent {
+        type Message = MdnsBrowserMessage;
+
+        fn receive_local(&mut self, msg: Self::Message) -> HandlerResult {
+            match msg {
+                MdnsBrowserMessage::Event(event) => {
+                    if let Some(change) = self.peers.apply(event) {
+                        debug!(self.log(), "mDNS peer change: {change:?}");
+                        self.browse_port.trigger(change);
+                    }
+                }
+                MdnsBrowserMessage::BrowseFailed(error) => {
+                    error!(self.log(), "mDNS browsing failed: {error}");
+                    if let Some(shutdown_handle) = self.shutdown_handle.take() {
+                        shutdown_handle.shutdown_and_forget();
+                    }
+                }
+            }
+            Handled::OK
+        }
+    }
+
+    /// Messages from the browsing thread.
+    #[derive(Debug)]
+    pub enum MdnsBrowserMessage {
+        Event(BrowserEvent),
+        BrowseFailed(crate::zeroconf::error::Error),
+    }
+
+    impl From<crate::zeroconf::Result<BrowserEvent>> for MdnsBrowserMessage {
+        fn from(result: crate::zeroconf::Result<BrowserEvent>) -> Self {
+            match result {
+                Ok(event) => Self::Event(event),
+                Err(error) => Self::BrowseFailed(error),
+            }
+        }
+    }
+}
+#[cfg(feature = "zeroconf-via-kompact")]
+pub use kompact_implementation::{MdnsBrowsePort, MdnsBrowserComponent, MdnsBrowserMessage};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zeroconf::{ServiceType, TxtRecord};
+
+    fn discovery(name: &str, instance_id: Uuid) -> BrowserEvent {
+        let mut txt = TxtRecord::new();
+        txt.insert(INSTANCE_ID_TXT_KEY, &instance_id.to_string())
+            .unwrap();
+        BrowserEvent::Add(
+            ServiceDiscovery::builder()
+                .name(name.to_owned())
+                .service_type(ServiceType::new("flotsync", "udp").unwrap())
+                .domain("local".to_owned())
+                .host_name("peer.local".to_owned())
+                .address("192.168.1.5".to_owned())
+                .port(52156)
+                .txt(Some(txt))
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn removal(name: &str) -> BrowserEvent {
+        BrowserEvent::Remove(
+            ServiceRemoval::builder()
+                .name(name.to_owned())
+                .kind("_flotsync._udp".to_owned())
+                .domain("local".to_owned())
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn goodbyes_remove_instances_once_all_their_services_are_gone() {
+        let instance_id = Uuid::from_u128(5);
+        let mut table = MdnsPeerTable::new();
+
+        let Some(MdnsBrowseEvent::Discovered(sighting)) =
+            table.apply(discovery("peer@host:CBBC", instance_id))
+        else {
+            panic!("resolved services must be reported as discovered");
+        };
+        assert_eq!(sighting.instance_id, instance_id);
+        assert_eq!(sighting.source.backend, DiscoveryBackend::Mdns);
+        assert_eq!(
+            sighting.routes,
+            [DiscoveryRoute::Udp("192.168.1.5:52156".parse().unwrap())]
+        );
+        table.apply(discovery("peer@host:CBBC (2)", instance_id));
+
+        assert_eq!(table.apply(removal("peer@host:CBBC")), None);
+        assert_eq!(
+            table.apply(removal("peer@host:CBBC (2)")),
+            Some(MdnsBrowseEvent::Removed { instance_id })
+        );
+        assert_eq!(
+            table.apply(removal("peer@host:CBBC")),
+            None,
+            "removals of unknown services are ignored"
+        );
+    }
+}
@@ -5,6 +5,9 @@ use crate::errors::{Result, ZeroconfSnafu};
 #[allow(unused)]
 use snafu::prelude::*;
 
+mod announcement_schedule;
+pub use announcement_schedule::AnnouncementSchedule;
+
 #[cfg(feature = "peer-announcement-via-kompact")]
 mod peer_announcement;
 #[cfg(feature = "peer-announcement-via-kompact")]
@@ -20,6 +23,7 @@ pub use peer_announcement::{
     PeerAnnouncementStartupError,
     PeerAnnouncementStartupResult,
     peer_announcement_bind_options_from_config,
+    peer_announcement_options_from_config,
     peer_announcement_startup_signal,
 };
 #[cfg(feature = "peer-announcement-via-kompact")]
@@ -41,3 +45,7 @@ pub use mdns_announcement::{
 
 #[cfg(feature = "zeroconf-support")]
 mod mdns_browser;
+#[cfg(feature = "zeroconf-support")]
+pub use mdns_browser::{MdnsBrowseEvent, MdnsPeerTable};
+#[cfg(feature = "zeroconf-via-kompact")]
+pub use mdns_browser::{MdnsBrowsePort, MdnsBrowserComponent, MdnsBrowserMessage};
@@ -1,11 +1,20 @@
 //! UDP peer-announcement sender component.
 
+use super::AnnouncementSchedule;
 use crate::{
     DEFAULT_DISCOVERY_PORT,
+    DEFAULT_IPV6_ANNOUNCEMENT_GROUP,
+    InterfaceSelection,
+    MulticastGroupAddr,
     SocketPort,
+    capabilities::CapabilitySet,
     config_keys,
     endpoint_selection::{EndpointSelection, EndpointSelectionPort},
-    kompact::{config::Config, prelude::*},
+    kompact::{
+        config::{Config, ConfigEntry, StringValue},
+        prelude::*,
+    },
+    protocol::DecodedPeer,
 };
 use flotsync_io::prelude::{
     ConfigureFailureReason,
@@ -23,16 +32,22 @@ use flotsync_io::prelude::{
     UdpSocketOption,
 };
 use flotsync_messages::{
-    buffa::Message,
+    buffa::{Message, MessageField},
     discovery::{Peer, SocketAddress},
-    proto::EncodeProto,
+    proto::{DecodeProto, EncodeProto},
+};
+use flotsync_utils::{
+    config::{FlotsyncConfig, FromFlotsyncConfig},
+    option_when,
 };
 use itertools::Itertools;
 use pnet_datalink::{self as datalink, MacAddr, NetworkInterface};
 use snafu::Snafu;
 use std::{
     collections::{HashMap, HashSet},
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    fmt,
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV6},
+    str::FromStr,
     time::Duration,
 };
 use uuid::Uuid;
@@ -54,6 +69,11 @@ pub struct Options {
     /// port identifies which shared UDP socket belongs to peer announcements when this component
     /// sees `UdpIndication::Bound`. It does not affect the `Peer` payload's advertised routes.
     ///
+    /// The address family also selects the announcement destinations: IPv4 binds send to the
+    /// subnet broadcast address of every selected interface, IPv6 binds send to
+    /// [`Self::ipv6_multicast_group`] scoped to every selected interface. Run one component per
+    /// family to announce on both.
+    ///
     /// Defaults to the unspecified IPv4 address on [`DEFAULT_DISCOVERY_PORT`].
     pub socket_bind_addr: SocketAddr,
     /// Optional UDP port used for broadcast announcement destinations.
@@ -67,8 +87,30 @@ pub struct Options {
     ///
     /// Defaults to `None`.
     pub broadcast_target_port: Option<SocketPort>,
+    /// Local interfaces that announcements are sent on.
+    ///
+    /// Defaults to [`InterfaceSelection::All`].
+    pub interfaces: InterfaceSelection,
+    /// Multicast group that announcements are sent to when [`Self::socket_bind_addr`] is IPv6.
+    ///
+    /// Destinations carry the index of the outgoing interface as their scope id, so link-local
+    /// groups such as the default [`DEFAULT_IPV6_ANNOUNCEMENT_GROUP`] leave through every selected
+    /// interface rather than only through the default route.
+    pub ipv6_multicast_group: MulticastGroupAddr,
     /// Time between periodic announcement attempts after startup or a route update.
+    ///
+    /// This is the base interval. While no other instance is heard on the announcement socket,
+    /// the interval doubles after every announcement up to [`Self::max_announcement_interval`].
     pub announcement_interval: Duration,
+    /// Upper bound for the announcement interval while no other instance is heard.
+    pub max_announcement_interval: Duration,
+    /// Fraction of the current interval by which each announcement delay is randomised in either
+    /// direction, so that announcers which started together do not stay in lockstep.
+    pub announcement_jitter: f64,
+    /// Capabilities encoded into outgoing `Peer` messages.
+    ///
+    /// Defaults to [`CapabilitySet::CURRENT`].
+    pub capabilities: CapabilitySet,
     /// Per-announcer instance identifier encoded into outgoing `Peer` messages.
     ///
     /// The default is nil; production callers should provide a real instance id.
@@ -84,7 +126,12 @@ impl Options {
             DEFAULT_DISCOVERY_PORT.0,
         ),
         broadcast_target_port: None,
+        interfaces: InterfaceSelection::All,
+        ipv6_multicast_group: DEFAULT_IPV6_ANNOUNCEMENT_GROUP,
         announcement_interval: Duration::from_secs(5),
+        max_announcement_interval: Duration::from_mins(1),
+        announcement_jitter: 0.2,
+        capabilities: CapabilitySet::CURRENT,
         instance_id: Uuid::nil(),
         socket_maintenance: PeerAnnouncementSocketMaintenance::Maintain,
     };
@@ -116,6 +163,20 @@ impl Options {
         self
     }
 
+    /// Replaces the interfaces that announcements are sent on.
+    #[must_use]
+    pub fn with_interfaces(mut self, interfaces: InterfaceSelection) -> Self {
+        self.interfaces = interfaces;
+        self
+    }
+
+    /// Replaces the IPv6 announcement multicast group.
+    #[must_use]
+    pub fn with_ipv6_multicast_group(mut self, ipv6_multicast_group: MulticastGroupAddr) -> Self {
+        self.ipv6_multicast_group = ipv6_multicast_group;
+        self
+    }
+
     /// Replaces the current instance id with `instance_id`.
     #[must_use]
     pub fn with_instance_id(mut self, instance_id: Uuid) -> Self {
@@ -123,6 +184,13 @@ impl Options {
         self
     }
 
+    /// Replaces the advertised capabilities with `capabilities`.
+    #[must_use]
+    pub fn with_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
     /// Replaces the current announcement interval with `announcement_interval`.
     #[must_use]
     pub fn with_announcement_interval(mut self, announcement_interval: Duration) -> Self {
@@ -130,6 +198,20 @@ impl Options {
         self
     }
 
+    /// Replaces the upper bound for the announcement interval with `max_announcement_interval`.
+    #[must_use]
+    pub fn with_max_announcement_interval(mut self, max_announcement_interval: Duration) -> Self {
+        self.max_announcement_interval = max_announcement_interval;
+        self
+    }
+
+    /// Replaces the announcement jitter fraction with `announcement_jitter`.
+    #[must_use]
+    pub fn with_announcement_jitter(mut self, announcement_jitter: f64) -> Self {
+        self.announcement_jitter = announcement_jitter;
+        self
+    }
+
     /// Replaces the peer-announcement socket lifecycle responsibility.
     #[must_use]
     pub fn with_socket_maintenance(
@@ -204,6 +286,75 @@ pub fn peer_announcement_bind_options_from_config(
     Ok(UdpBindOptions::default().with_socket_reuse(socket_reuse))
 }
 
+/// Apply the peer-announcement addresses, interfaces, and pacing from Kompact config to `options`.
+///
+/// Reads the bind address, the broadcast target port override, the interface selection, the
+/// IPv6 multicast group, and the announcement interval, maximum interval, and jitter. An empty
+/// broadcast target port keeps broadcasting to the bind address's port.
+///
+/// # Errors
+///
+/// Returns [`PeerAnnouncementStartupError`] when a configured value cannot be read or parsed.
+pub fn peer_announcement_options_from_config(
+    config: &Config,
+    mut options: Options,
+) -> std::result::Result<Options, PeerAnnouncementStartupError> {
+    if let Some(socket_bind_addr) =
+        read_parsed_config_value(config, &config_keys::PEER_ANNOUNCEMENT_BIND_ADDR)?
+    {
+        options = options.with_socket_bind_addr(socket_bind_addr);
+    }
+    let broadcast_target_port = read_parsed_config_value(
+        config,
+        &config_keys::PEER_ANNOUNCEMENT_BROADCAST_TARGET_PORT,
+    )?;
+    options = options.with_broadcast_target_port(broadcast_target_port);
+    if let Some(interfaces) =
+        read_parsed_config_value(config, &config_keys::PEER_ANNOUNCEMENT_INTERFACES)?
+    {
+        options = options.with_interfaces(interfaces);
+    }
+    if let Some(ipv6_multicast_group) =
+        read_parsed_config_value(config, &config_keys::PEER_ANNOUNCEMENT_IPV6_MULTICAST_GROUP)?
+    {
+        options = options.with_ipv6_multicast_group(ipv6_multicast_group);
+    }
+    let configuration_failed = |key: &'static str, error: &dyn fmt::Display| {
+        PeerAnnouncementStartupError::ConfigurationFailed {
+            key,
+            reason: error.to_string(),
+        }
+    };
+    let announcement_interval = config
+        .read_or_default(&config_keys::PEER_ANNOUNCEMENT_INTERVAL)
+        .map_err(|error| {
+            configuration_failed(config_keys::PEER_ANNOUNCEMENT_INTERVAL.key, &error)
+        })?;
+    let max_announcement_interval = config
+        .read_or_default(&config_keys::PEER_ANNOUNCEMENT_MAX_INTERVAL)
+        .map_err(|error| {
+            configuration_failed(config_keys::PEER_ANNOUNCEMENT_MAX_INTERVAL.key, &error)
+        })?;
+    let announcement_jitter = config
+        .read_or_default(&config_keys::PEER_ANNOUNCEMENT_JITTER)
+        .map_err(|error| configuration_failed(config_keys::PEER_ANNOUNCEMENT_JITTER.key, &error))?;
+    Ok(options
+        .with_announcement_interval(announcement_interval)
+        .with_max_announcement_interval(max_announcement_interval)
+        .with_announcement_jitter(announcement_jitter))
+}
+
+impl FromFlotsyncConfig for Options {
+    type Error = PeerAnnouncementStartupError;
+
+    /// Resolve the addresses, interfaces, and pacing on top of [`Options::DEFAULT`].
+    ///
+    /// See [`peer_announcement_options_from_config`].
+    fn from_flotsync_config(config: &FlotsyncConfig) -> std::result::Result<Self, Self::Error> {
+        peer_announcement_options_from_config(config.config(), Self::DEFAULT)
+    }
+}
+
 /// A route advertised in outgoing peer-announcement messages.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PeerAnnouncementRoute {
@@ -234,6 +385,7 @@ pub struct PeerAnnouncementComponent {
     broadcast_addresses: HashMap<MacAddr, SocketAddr>,
     advertised_routes: Vec<PeerAnnouncementRoute>,
     next_transmission_id: TransmissionId,
+    announcement_schedule: AnnouncementSchedule,
     announcement_timer: Option<ScheduledTimer>,
 }
 
@@ -307,6 +459,11 @@ impl PeerAnnouncementComponent {
         options: Options,
         startup_promise: Option<KPromise<PeerAnnouncementStartupResult>>,
     ) -> Self {
+        let announcement_schedule = AnnouncementSchedule::new(
+            options.announcement_interval,
+            options.max_announcement_interval,
+            options.announcement_jitter,
+        );
         Self {
             ctx: ComponentContext::uninitialised(),
             udp_port: RequiredPort::uninitialised(),
@@ -317,6 +474,7 @@ impl PeerAnnouncementComponent {
             broadcast_addresses: HashMap::new(),
             advertised_routes: Vec::new(),
             next_transmission_id: TransmissionId::ONE,
+            announcement_schedule,
             announcement_timer: None,
         }
     }
@@ -383,43 +541,69 @@ impl PeerAnnouncementComponent {
         peer_announcement_bind_options_from_config(self.ctx.config())
     }
 
-    fn get_active_broadcast_interfaces() -> Vec<NetworkInterface> {
+    /// Return the selected interfaces that can carry announcements for the bind address family.
+    fn get_active_broadcast_interfaces(&self) -> Vec<NetworkInterface> {
+        let ipv6 = self.socket_bind_addr().is_ipv6();
         datalink::interfaces()
             .into_iter()
             .filter(|interface| {
                 interface.mac.is_some()
                     && interface.is_up()
                     && !interface.ips.is_empty()
-                    && (interface.is_loopback() || interface.is_broadcast())
+                    && (interface.is_loopback()
+                        || if ipv6 {
+                            interface.is_multicast()
+                        } else {
+                            interface.is_broadcast()
+                        })
+                    && self.options.interfaces.matches_network_interface(interface)
             })
             .collect()
     }
 
+    /// Return the announcement destination on `interface` for the bind address family.
     fn get_broadcast_address_for_interface(
         &self,
         interface: &NetworkInterface,
     ) -> Option<SocketAddr> {
-        if interface.ips.len() > 1 {
-            trace!(
-                self.log(),
-                "Interface {} has {} IP ranges.\n{}\nArbitrarily picking the first IPv4.",
-                interface.name,
-                interface.ips.len(),
-                interface.ips.iter().join(", ")
-            );
+        let port = *self.options.broadcast_target_port();
+        match (
+            self.socket_bind_addr(),
+            self.options.ipv6_multicast_group.ip(),
+        ) {
+            (SocketAddr::V6(_), IpAddr::V6(group)) => option_when!(
+                interface.ips.iter().any(|network| network.is_ipv6()),
+                SocketAddr::V6(SocketAddrV6::new(group, port, 0, interface.index))
+            ),
+            (SocketAddr::V6(_), IpAddr::V4(group)) => {
+                warn!(
+                    self.log(),
+                    "Not announcing on interface {} because IPv4 group {group} cannot be reached from an IPv6 socket",
+                    interface.name
+                );
+                None
+            }
+            (SocketAddr::V4(_), _) => {
+                if interface.ips.len() > 1 {
+                    trace!(
+                        self.log(),
+                        "Interface {} has {} IP ranges.\n{}\nArbitrarily picking the first IPv4.",
+                        interface.name,
+                        interface.ips.len(),
+                        interface.ips.iter().join(", ")
+                    );
+                }
+                interface
+                    .ips
+                    .iter()
+                    .find(|network| network.is_ipv4())
+                    .map(|network| SocketAddr::new(network.broadcast(), port))
+            }
         }
-        interface
-            .ips
-            .iter()
-            .find(|network| network.is_ipv4())
-            .map(|network| {
-                let broadcast_addr = network.broadcast();
-                SocketAddr::new(broadcast_addr, *self.options.broadcast_target_port())
-            })
     }
 
     fn refresh_broadcast_addresses(&mut self) {
-        let active_interfaces = Self::get_active_broadcast_interfaces();
+        let active_interfaces = self.get_active_broadcast_interfaces();
         trace!(
             self.log(),
             "There are {} active interfaces: {}",
@@ -486,6 +670,7 @@ impl PeerAnnouncementComponent {
         Peer {
             instance_uuid: self.options.instance_id.as_bytes().to_vec(),
             listening_on: PeerAnnouncementRoute::encode_proto_collection(&self.advertised_routes),
+            capabilities: MessageField::some(self.options.capabilities.encode_proto()),
             ..Default::default()
         }
     }
@@ -549,6 +734,8 @@ impl PeerAnnouncementComponent {
 
     fn replace_advertised_routes(&mut self, routes: Vec<PeerAnnouncementRoute>) -> HandlerResult {
         self.advertised_routes = routes;
+        // Changed routes should reach peers at the base rate, regardless of how long we were alone.
+        self.announcement_schedule.reset();
         if self.advertised_routes.is_empty() {
             trace!(
                 self.log(),
@@ -570,10 +757,15 @@ impl PeerAnnouncementComponent {
             return;
         }
 
-        let timer = self.schedule_once(
-            self.options.announcement_interval,
-            move |component, timeout| component.handle_announcement_timeout(&timeout),
+        let delay = self.announcement_schedule.next_delay(rand::random());
+        trace!(
+            self.log(),
+            "Next peer announcement in {delay:?} (interval {:?})",
+            self.announcement_schedule.current_interval()
         );
+        let timer = self.schedule_once(delay, move |component, timeout| {
+            component.handle_announcement_timeout(&timeout)
+        });
         self.announcement_timer = Some(timer);
     }
 
@@ -597,6 +789,7 @@ impl PeerAnnouncementComponent {
 
     fn request_close(&mut self) {
         self.clear_announcement_timer();
+        self.announcement_schedule.reset();
         if self.options.socket_maintenance == PeerAnnouncementSocketMaintenance::Observe {
             // Observe mode does not own the socket, so stopping only discards the observed socket
             // id and returns to the initial state used when waiting for its maintainer.
@@ -757,6 +950,11 @@ impl PeerAnnouncementComponent {
             {
                 self.handle_broadcast_configure_failed(*socket_id, *reason)
             }
+            UdpIndication::Received {
+                socket_id, payload, ..
+            } if matches!(self.state, SocketState::Running { socket_id: current } if current == *socket_id) => {
+                self.handle_received_payload(payload)
+            }
             UdpIndication::Closed {
                 socket_id,
                 remote_addr: _,
@@ -766,6 +964,27 @@ impl PeerAnnouncementComponent {
         }
     }
 
+    /// Keep announcing at the base rate while other instances announce on our socket.
+    fn handle_received_payload(&mut self, payload: &IoPayload) -> HandlerResult {
+        let mut cursor = payload.cursor();
+        let Ok(peer) = DecodedPeer::decode_proto_from_buf(&mut cursor) else {
+            return Handled::OK;
+        };
+        // Broadcasts loop back, so our own announcements arrive here as well.
+        if peer.instance_id == self.options.instance_id {
+            return Handled::OK;
+        }
+        if self.announcement_schedule.peer_observed() && self.announcement_timer.is_some() {
+            debug!(
+                self.log(),
+                "Heard peer {} after slowing down; returning to the base announcement interval",
+                peer.instance_id
+            );
+            self.set_announcement_timer();
+        }
+        Handled::OK
+    }
+
     fn handle_send_result(&mut self, result: &UdpSendResult) -> HandlerResult {
         let Some(socket_id) = self.state.socket_id() else {
             return Handled::OK;
@@ -842,6 +1061,32 @@ impl Actor for PeerAnnouncementComponent {
     }
 }
 
+/// Read the string config value at `entry` and parse it, or return `None` if it is empty.
+fn read_parsed_config_value<T>(
+    config: &Config,
+    entry: &ConfigEntry<StringValue>,
+) -> std::result::Result<Option<T>, PeerAnnouncementStartupError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let configuration_failed = |reason: String| PeerAnnouncementStartupError::ConfigurationFailed {
+        key: entry.key,
+        reason,
+    };
+    let value = config
+        .read_or_default(entry)
+        .map_err(|error| configuration_failed(error.to_string()))?;
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    value
+        .trim()
+        .parse()
+        .map(Some)
+        .map_err(|error: T::Err| configuration_failed(error.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -859,7 +1104,8 @@ mod tests {
         PortTestingRefExt,
     };
     use std::{
-        net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+        assert_matches,
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4},
         sync::Arc,
     };
 
@@ -954,6 +1200,17 @@ mod tests {
         }
     }
 
+    fn ipv6_interface(index: u32, cidr: &str) -> NetworkInterface {
+        NetworkInterface {
+            name: format!("test{index}"),
+            description: "test interface".to_string(),
+            index,
+            mac: Some(MacAddr(0, 1, 2, 3, 4, 5)),
+            ips: vec![cidr.parse().expect("valid IPv6 network")],
+            flags: 0,
+        }
+    }
+
     #[test]
     fn peer_announcement_component_binds_and_enables_broadcast() {
         let system = build_test_kompact_system();
@@ -1059,6 +1316,129 @@ mod tests {
         );
     }
 
+    #[test]
+    fn peer_announcement_ipv6_targets_are_scoped_to_each_interface() {
+        let options = Options::DEFAULT
+            .with_socket_bind_addr(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 53_000));
+        let component = PeerAnnouncementComponent::with_options(options);
+
+        for index in [2, 7] {
+            assert_eq!(
+                component.get_broadcast_address_for_interface(&ipv6_interface(index, "fe80::1/64")),
+                Some(SocketAddr::V6(SocketAddrV6::new(
+                    Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1),
+                    53_000,
+                    0,
+                    index,
+                ))),
+                "IPv6 announcements must leave through the interface they were computed for"
+            );
+        }
+        assert_eq!(
+            component.get_broadcast_address_for_interface(&ipv4_interface("192.168.5.10/24")),
+            None,
+            "interfaces without IPv6 addresses cannot carry IPv6 announcements"
+        );
+    }
+
+    #[test]
+    fn peer_announcement_options_read_interfaces_and_ipv6_group_from_config() {
+        let system = build_test_kompact_system_with(|config| {
+            config.set_config_value(
+                &config_keys::PEER_ANNOUNCEMENT_BIND_ADDR,
+                "[::]:53002".to_string(),
+            );
+            config.set_config_value(
+                &config_keys::PEER_ANNOUNCEMENT_INTERFACES,
+                "eth1".to_string(),
+            );
+            config.set_config_value(
+                &config_keys::PEER_ANNOUNCEMENT_IPV6_MULTICAST_GROUP,
+                "ff02::1:f10".to_string(),
+            );
+        });
+
+        let options = peer_announcement_options_from_config(system.config(), Options::DEFAULT)
+            .expect("valid peer-announcement config");
+        assert_eq!(
+            options.socket_bind_addr(),
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 53_002)
+        );
+        assert_eq!(options.broadcast_target_port, None);
+        assert_eq!(
+            options.interfaces,
+            InterfaceSelection::name("eth1").expect("valid interface name")
+        );
+        assert_eq!(
+            options.ipv6_multicast_group,
+            "ff02::1:f10".parse().expect("valid multicast group")
+        );
+        system.shutdown().wait().expect("Kompact shutdown");
+
+        let system = build_test_kompact_system_with(|config| {
+            config.set_config_value(
+                &config_keys::PEER_ANNOUNCEMENT_IPV6_MULTICAST_GROUP,
+                "fe80::1".to_string(),
+            );
+        });
+        assert_matches!(
+            peer_announcement_options_from_config(system.config(), Options::DEFAULT),
+            Err(PeerAnnouncementStartupError::ConfigurationFailed { key, .. })
+                if key == config_keys::PEER_ANNOUNCEMENT_IPV6_MULTICAST_GROUP.key
+        );
+        system.shutdown().wait().expect("Kompact shutdown");
+    }
+
+    #[test]
+    fn peer_announcement_options_read_pacing_from_config() {
+        let system = build_test_kompact_system_with(|config| {
+            config.set_config_value(
+                &config_keys::PEER_ANNOUNCEMENT_INTERVAL,
+                Duration::from_secs(2),
+            );
+            config.set_config_value(
+                &config_keys::PEER_ANNOUNCEMENT_MAX_INTERVAL,
+                Duration::from_secs(20),
+            );
+            config.set_config_value(&config_keys::PEER_ANNOUNCEMENT_JITTER, 0.1);
+        });
+
+        let options = peer_announcement_options_from_config(system.config(), Options::DEFAULT)
+            .expect("valid peer-announcement config");
+        assert_eq!(options.announcement_interval, Duration::from_secs(2));
+        assert_eq!(options.max_announcement_interval, Duration::from_secs(20));
+        assert_eq!(options.announcement_jitter, 0.1);
+        system.shutdown().wait().expect("Kompact shutdown");
+    }
+
+    #[test]
+    fn peer_announcement_options_resolve_from_flotsync_config() {
+        let mut loader = flotsync_utils::config::FlotsyncConfigLoader::new();
+        loader
+            .load_str(
+                r#"
+                [flotsync.discovery.peer-announcement]
+                bind-addr = "0.0.0.0:53003"
+                interval = "3s"
+                "#,
+            )
+            .expect("valid TOML");
+        let config = loader.build().expect("config should build");
+
+        let options = config
+            .settings::<Options>()
+            .expect("valid peer-announcement config");
+        assert_eq!(
+            options.socket_bind_addr(),
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 53_003)
+        );
+        assert_eq!(options.announcement_interval, Duration::from_secs(3));
+        assert_eq!(
+            options.max_announcement_interval,
+            Options::DEFAULT.max_announcement_interval
+        );
+    }
+
     #[test]
     fn peer_announcement_component_applies_bind_reuse_config_override() {
         let system = build_test_kompact_system_with(|config| {
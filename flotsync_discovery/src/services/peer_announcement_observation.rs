@@ -5,7 +5,13 @@ use super::{
     PeerAnnouncementStartupError,
     peer_announcement_bind_options_from_config,
 };
-use crate::protocol::{DecodedPeer, DiscoveryRoute};
+use crate::{
+    DEFAULT_IPV6_ANNOUNCEMENT_GROUP,
+    InterfaceSelection,
+    MulticastGroupAddr,
+    capabilities::CapabilitySet,
+    protocol::{DecodedPeer, DiscoveryRoute},
+};
 use flotsync_io::prelude::{
     IoPayload,
     SocketId,
@@ -14,6 +20,7 @@ use flotsync_io::prelude::{
     UdpOpenRequestId,
     UdpPort,
     UdpRequest,
+    UdpSocketOption,
 };
 use flotsync_messages::proto::DecodeProto;
 use flotsync_utils::{
@@ -21,7 +28,8 @@ use flotsync_utils::{
     transform_state_match,
 };
 use kompact::prelude::*;
-use std::net::SocketAddr;
+use pnet_datalink::{self as datalink, NetworkInterface};
+use std::net::{IpAddr, SocketAddr};
 use uuid::Uuid;
 
 /// One decoded plaintext peer announcement.
@@ -31,6 +39,10 @@ pub struct PeerAnnouncementObserved {
     pub instance_id: Uuid,
     /// Reachability endpoints advertised by this peer instance.
     pub routes: Vec<DiscoveryRoute>,
+    /// Name of the local interface the announcement arrived on, if it could be determined.
+    pub interface: Option<String>,
+    /// Capabilities advertised by this peer instance.
+    pub capabilities: CapabilitySet,
 }
 
 /// Port used by announcement protocols to publish decoded peer announcements.
@@ -80,6 +92,12 @@ pub struct PeerAnnouncementObservationComponent {
     socket_bind_addr: SocketAddr,
     /// Whether this observation component maintains the peer-announcement socket.
     socket_maintenance: PeerAnnouncementSocketMaintenance,
+    /// Local interfaces that announcements are accepted on.
+    interfaces: InterfaceSelection,
+    /// IPv6 multicast group joined on the selected interfaces when the bind address is IPv6.
+    ipv6_multicast_group: MulticastGroupAddr,
+    /// Most recent snapshot of local interfaces, used to attribute announcements to interfaces.
+    local_interfaces: Vec<NetworkInterface>,
     /// Peer-announcement socket lifecycle state.
     state: State<SocketState>,
 }
@@ -110,10 +128,30 @@ impl PeerAnnouncementObservationComponent {
             udp_port: RequiredPort::uninitialised(),
             socket_bind_addr,
             socket_maintenance,
+            interfaces: InterfaceSelection::All,
+            ipv6_multicast_group: DEFAULT_IPV6_ANNOUNCEMENT_GROUP,
+            local_interfaces: Vec::new(),
             state: State::new(state),
         }
     }
 
+    /// Only accept announcements that arrive on `interfaces`.
+    #[must_use]
+    pub fn with_interfaces(mut self, interfaces: InterfaceSelection) -> Self {
+        self.interfaces = interfaces;
+        self
+    }
+
+    /// Replace the IPv6 multicast group joined on the selected interfaces.
+    ///
+    /// This must match the group the announcers send to, see
+    /// [`PeerAnnouncementOptions::ipv6_multicast_group`](super::PeerAnnouncementOptions::ipv6_multicast_group).
+    #[must_use]
+    pub fn with_ipv6_multicast_group(mut self, ipv6_multicast_group: MulticastGroupAddr) -> Self {
+        self.ipv6_multicast_group = ipv6_multicast_group;
+        self
+    }
+
     /// Send the UDP bind request for a maintained peer-announcement socket.
     ///
     /// # Errors
@@ -132,8 +170,46 @@ impl PeerAnnouncementObservationComponent {
         Ok(SocketState::Binding { request_id })
     }
 
+    /// Join the IPv6 announcement group on every selected interface.
+    ///
+    /// IPv4 sockets receive subnet broadcasts without joining anything, and every IPv6 host is
+    /// already a member of the all-nodes group.
+    fn join_ipv6_multicast_group(&mut self, socket_id: SocketId) {
+        let IpAddr::V6(group) = self.ipv6_multicast_group.ip() else {
+            return;
+        };
+        if !self.socket_bind_addr.is_ipv6()
+            || self.ipv6_multicast_group == MulticastGroupAddr::IPV6_ALL_NODES
+        {
+            return;
+        }
+        self.refresh_local_interfaces();
+        let interface_indices: Vec<u32> = self
+            .local_interfaces
+            .iter()
+            .filter(|interface| {
+                interface.is_up()
+                    && interface.is_multicast()
+                    && self.interfaces.matches_network_interface(interface)
+            })
+            .map(|interface| interface.index)
+            .collect();
+        for interface in interface_indices {
+            self.udp_port.trigger(UdpRequest::Configure {
+                socket_id,
+                option: UdpSocketOption::JoinMulticastV6 { group, interface },
+            });
+        }
+    }
+
+    fn refresh_local_interfaces(&mut self) {
+        self.local_interfaces = datalink::interfaces();
+    }
+
     /// Decode one peer-announcement payload and publish it to the observation port.
-    fn handle_peer_announcement_payload(&mut self, payload: &IoPayload) {
+    ///
+    /// Announcements that arrive on an interface outside of the selection are dropped.
+    fn handle_peer_announcement_payload(&mut self, source: SocketAddr, payload: &IoPayload) {
         let mut cursor = payload.cursor();
         let peer = match DecodedPeer::decode_proto_from_buf(&mut cursor) {
             Ok(peer) => peer,
@@ -145,9 +221,27 @@ impl PeerAnnouncementObservationComponent {
                 return;
             }
         };
+        if receiving_interface(source, &self.local_interfaces).is_none() {
+            self.refresh_local_interfaces();
+        }
+        let interface = receiving_interface(source, &self.local_interfaces);
+        if self.interfaces != InterfaceSelection::All
+            && !interface
+                .is_some_and(|interface| self.interfaces.matches_network_interface(interface))
+        {
+            trace!(
+                self.log(),
+                "ignored peer announcement from {source} outside of the selected interfaces {}",
+                self.interfaces
+            );
+            return;
+        }
+        let interface = interface.map(|interface| interface.name.clone());
         self.announcement_port.trigger(PeerAnnouncementObserved {
             instance_id: peer.instance_id,
             routes: peer.listening_on,
+            interface,
+            capabilities: peer.capabilities,
         });
     }
 
@@ -245,6 +339,7 @@ impl Require<UdpPort> for PeerAnnouncementObservationComponent {
                         local_addr,
                         socket_id
                     );
+                    self.join_ipv6_multicast_group(socket_id);
                     StateUpdate::transition(SocketState::Listening { socket_id })
                 }
                 UdpIndication::BindFailed {
@@ -276,6 +371,7 @@ impl Require<UdpPort> for PeerAnnouncementObservationComponent {
                         socket_id,
                         local_addr
                     );
+                    self.join_ipv6_multicast_group(socket_id);
                     StateUpdate::transition(SocketState::Listening { socket_id })
                 }
                 _ => StateUpdate::ok(SocketState::WaitingForSocket),
@@ -284,9 +380,26 @@ impl Require<UdpPort> for PeerAnnouncementObservationComponent {
                 socket_id: active_socket,
             } => match indication {
                 UdpIndication::Received {
-                    socket_id, payload, ..
+                    socket_id,
+                    source,
+                    payload,
+                } if active_socket == socket_id => {
+                    self.handle_peer_announcement_payload(source, &payload);
+                    StateUpdate::ok(SocketState::Listening {
+                        socket_id: active_socket,
+                    })
+                }
+                UdpIndication::ConfigureFailed {
+                    socket_id,
+                    option: option @ UdpSocketOption::JoinMulticastV6 { .. },
+                    reason,
                 } if active_socket == socket_id => {
-                    self.handle_peer_announcement_payload(&payload);
+                    warn!(
+                        self.log(),
+                        "peer-announcement observation could not apply {:?}: {:?}",
+                        option,
+                        reason
+                    );
                     StateUpdate::ok(SocketState::Listening {
                         socket_id: active_socket,
                     })
@@ -325,6 +438,30 @@ impl Actor for PeerAnnouncementObservationComponent {
     }
 }
 
+/// Find the local interface that a datagram from `source` arrived on.
+///
+/// IPv6 link-local sources carry the receiving interface as their scope id. Other sources are
+/// attributed to the interface with an address in the same subnet.
+fn receiving_interface(
+    source: SocketAddr,
+    interfaces: &[NetworkInterface],
+) -> Option<&NetworkInterface> {
+    if let SocketAddr::V6(source) = source
+        && source.scope_id() != 0
+    {
+        return interfaces
+            .iter()
+            .find(|interface| interface.index == source.scope_id());
+    }
+    let source_ip = source.ip().to_canonical();
+    interfaces.iter().find(|interface| {
+        interface
+            .ips
+            .iter()
+            .any(|network| network.contains(source_ip))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -341,10 +478,55 @@ mod tests {
     };
     use flotsync_utils::kompact_testing::{PortTestingExt, PortTestingRefExt};
     use std::{
-        net::{IpAddr, Ipv4Addr},
+        net::{Ipv4Addr, SocketAddrV6},
         time::Duration,
     };
 
+    fn network_interface(index: u32, cidrs: &[&str]) -> NetworkInterface {
+        NetworkInterface {
+            name: format!("test{index}"),
+            description: "test interface".to_string(),
+            index,
+            mac: None,
+            ips: cidrs
+                .iter()
+                .map(|cidr| cidr.parse().expect("valid network"))
+                .collect(),
+            flags: 0,
+        }
+    }
+
+    #[test]
+    fn announcements_are_attributed_to_the_receiving_interface() {
+        let interfaces = [
+            network_interface(1, &["127.0.0.1/8", "::1/128"]),
+            network_interface(2, &["192.168.1.20/24", "fe80::2/64"]),
+            network_interface(3, &["10.8.0.5/24", "fe80::3/64"]),
+        ];
+        let interface_of = |source: SocketAddr| {
+            receiving_interface(source, &interfaces).map(|interface| interface.index)
+        };
+
+        assert_eq!(interface_of("192.168.1.77:52156".parse().unwrap()), Some(2));
+        assert_eq!(interface_of("10.8.0.1:52156".parse().unwrap()), Some(3));
+        assert_eq!(
+            interface_of("[::ffff:192.168.1.77]:52156".parse().unwrap()),
+            Some(2),
+            "IPv4-mapped sources on dual-stack sockets belong to the IPv4 subnet"
+        );
+        assert_eq!(
+            interface_of(SocketAddr::V6(SocketAddrV6::new(
+                "fe80::77".parse().unwrap(),
+                52156,
+                0,
+                3,
+            ))),
+            Some(3),
+            "link-local sources are attributed by scope id, not by their shared fe80::/64 subnet"
+        );
+        assert_eq!(interface_of("172.16.0.1:52156".parse().unwrap()), None);
+    }
+
     #[test]
     fn observing_peer_announcement_component_infers_socket_id_by_configured_port() {
         let peer_port = 53156;
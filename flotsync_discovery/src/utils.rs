@@ -19,70 +19,60 @@ impl fmt::Display for Base64Display<'_> {
     }
 }
 
-/// A simple wrapper for handling async shutdowns.
+/// Stopping the blocking threads that poll the mDNS daemon.
+///
+/// Each thread registers as a participant of its own [`flotsync_utils::shutdown`]
+/// controller, polls [`ShutdownToken::is_shutting_down`] between event loop polls, and
+/// completes every phase by exiting.
+///
+/// [`ShutdownToken::is_shutting_down`]: flotsync_utils::shutdown::ShutdownToken::is_shutting_down
 #[cfg(feature = "zeroconf-via-kompact")]
 pub mod shutdown {
+    use flotsync_utils::shutdown::{ShutdownController, ShutdownToken, shutdown_channel};
     use snafu::prelude::*;
-    use std::sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
-    };
+    use std::time::Duration;
+
+    /// How long a polling thread may take to notice the shutdown and exit.
+    const THREAD_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
 
     #[derive(Debug, Snafu)]
     #[snafu(visibility(pub(crate)))]
     pub enum ShutdownError {
+        #[snafu(display("The target of the shutdown did not exit in time"))]
+        TimedOut {
+            source: flotsync_utils::shutdown::ShutdownError,
+        },
         #[snafu(display("The target of the shutdown panicked"))]
         Panic,
     }
 
+    /// Run `body` on a new thread until the returned handle shuts it down.
+    ///
+    /// `body` must return soon after its token reports
+    /// [`is_shutting_down`](ShutdownToken::is_shutting_down).
     #[must_use]
-    pub fn watcher() -> (ShutdownHandle, ShutdownWatch) {
-        let flag = Arc::new(AtomicBool::new(false));
-        (
-            ShutdownHandle {
-                flag: Arc::clone(&flag),
-            },
-            ShutdownWatch { flag },
-        )
-    }
-
-    #[derive(Debug)]
-    pub struct ShutdownHandle {
-        flag: Arc<AtomicBool>,
-    }
-
-    impl ShutdownHandle {
-        #[must_use]
-        pub fn with_thread<T>(
-            self,
-            join_handle: std::thread::JoinHandle<T>,
-        ) -> BlockingThreadShutdown<T> {
-            BlockingThreadShutdown {
-                shutdown_handle: self,
-                join_handle,
-            }
-        }
-
-        pub fn shutdown(self) {
-            self.flag.store(true, Ordering::Release);
-        }
-    }
-
-    #[derive(Debug)]
-    pub struct ShutdownWatch {
-        flag: Arc<AtomicBool>,
-    }
-
-    impl ShutdownWatch {
-        #[must_use]
-        pub fn should_shutdown(&self) -> bool {
-            self.flag.load(Ordering::Acquire)
+    pub fn spawn_thread<T, F>(name: &str, body: F) -> BlockingThreadShutdown<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ShutdownToken) -> T + Send + 'static,
+    {
+        let (controller, token) = shutdown_channel();
+        let participant = token.participant(name);
+        let join_handle = std::thread::spawn(move || {
+            let result = body(participant.token());
+            // Dropping the participant completes every phase.
+            drop(participant);
+            result
+        });
+        BlockingThreadShutdown {
+            controller,
+            join_handle,
         }
     }
 
     #[derive(Debug)]
     pub struct BlockingThreadShutdown<T> {
-        shutdown_handle: ShutdownHandle,
+        controller: ShutdownController,
         join_handle: std::thread::JoinHandle<T>,
     }
 
@@ -94,7 +84,9 @@ pub mod shutdown {
         ///
         /// Useful when the target is likely errored out already and we are mostly making sure that the memory gets cleaned up.
         pub fn shutdown_and_forget(self) {
-            self.shutdown_handle.shutdown();
+            std::thread::spawn(move || {
+                let _ = kompact::prelude::block_on(self.shutdown());
+            });
         }
 
         /// Shutdown and wait for the thread to complete.
@@ -103,8 +95,12 @@ pub mod shutdown {
         ///
         /// See `ShutdownError` for failure conditions.
         pub async fn shutdown(self) -> Result<T, ShutdownError> {
-            self.shutdown_handle.shutdown();
-            blocking::unblock(|| self.join_handle.join().map_err(|_| PanicSnafu.build())).await
+            self.controller
+                .shutdown(THREAD_EXIT_TIMEOUT)
+                .await
+                .context(TimedOutSnafu)?;
+            let join_handle = self.join_handle;
+            blocking::unblock(|| join_handle.join().map_err(|_| PanicSnafu.build())).await
         }
     }
 }
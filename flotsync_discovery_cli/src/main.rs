@@ -6,23 +6,27 @@ use flotsync_discovery::services::{
 };
 use flotsync_discovery::{
     endpoint_selection::EndpointSelection,
+    instance_identity::load_or_create_instance_id,
     kompact::prelude::*,
     services::{
-        PEER_ANNOUNCEMENT_DEFAULT_OPTIONS,
         PeerAnnouncementComponent,
+        PeerAnnouncementOptions,
         peer_announcement_startup_signal,
     },
     uuid::Uuid,
 };
 use flotsync_io::prelude::{DriverConfig, IoRuntime};
+use flotsync_utils::config::{FlotsyncConfig, FlotsyncConfigLoader};
 use std::{
     io::{self, BufRead, BufReader},
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 
 const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+const INSTANCE_ID_FILE_NAME: &str = "discovery-cli.instance-id";
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -31,6 +35,19 @@ struct Args {
     #[arg(short, long)]
     active: bool,
 
+    /// Load Flotsync configuration from this TOML file.
+    ///
+    /// `FLOTSYNC__*` environment variables override values from the file.
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Keep the announced instance id in this file, so it stays the same across launches.
+    ///
+    /// Defaults to `flotsync/discovery-cli.instance-id` in `$XDG_STATE_HOME`, or in
+    /// `~/.local/state` if that is not set.
+    #[arg(long, value_name = "FILE")]
+    instance_id_file: Option<PathBuf>,
+
     #[cfg(feature = "zeroconf")]
     /// Use zeroconf mDNS instead of a peer-announcement broadcast.
     #[arg(short, long)]
@@ -84,7 +101,16 @@ impl ActiveService {
 fn main() {
     let args = Args::parse();
 
-    let kompact_system = match KompactConfig::default().build().wait() {
+    let flotsync_config = match load_config(args.config.as_ref()) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Could not load configuration: {error}");
+            std::process::exit(1);
+        }
+    };
+    let mut kompact_config = KompactConfig::default();
+    flotsync_config.apply_to(&mut kompact_config);
+    let kompact_system = match kompact_config.build().wait() {
         Ok(system) => system,
         Err(error) => {
             eprintln!("Could not start Kompact system: {error}");
@@ -93,7 +119,7 @@ fn main() {
     };
 
     let active_service = if args.active {
-        let instance_id = Uuid::new_v4();
+        let instance_id = load_instance_id(args.instance_id_file.as_deref());
 
         #[cfg(feature = "zeroconf")]
         if cfg!(feature = "zeroconf") && args.mdns {
@@ -110,13 +136,13 @@ fn main() {
             Some(ActiveService::Mdns { component })
         } else {
             Some(
-                start_peer_announcement(&kompact_system, instance_id)
+                start_peer_announcement(&kompact_system, &flotsync_config, instance_id)
                     .unwrap_or_else(|error| shutdown_after_start_error(&kompact_system, &error)),
             )
         }
         #[cfg(not(feature = "zeroconf"))]
         Some(
-            start_peer_announcement(&kompact_system, instance_id)
+            start_peer_announcement(&kompact_system, &flotsync_config, instance_id)
                 .unwrap_or_else(|error| shutdown_after_start_error(&kompact_system, &error)),
         )
     } else {
@@ -136,14 +162,64 @@ fn main() {
     }
 }
 
+fn load_config(
+    path: Option<&PathBuf>,
+) -> std::result::Result<FlotsyncConfig, flotsync_utils::config::ConfigLoadError> {
+    let mut loader = FlotsyncConfigLoader::new();
+    if let Some(path) = path {
+        loader.load_file(path)?;
+    }
+    loader.load_process_env()?;
+    loader.build()
+}
+
+/// Load the persisted instance id, or use a fresh one if there is nowhere to keep it.
+fn load_instance_id(path: Option<&Path>) -> Uuid {
+    let Some(path) = path
+        .map(Path::to_path_buf)
+        .or_else(default_instance_id_file)
+    else {
+        log::warn!(
+            "No state directory found; announcing a new instance id that will change on the next launch"
+        );
+        return Uuid::new_v4();
+    };
+    match load_or_create_instance_id(&path) {
+        Ok(instance_id) => {
+            log::info!("Announcing instance id {instance_id}");
+            instance_id
+        }
+        Err(error) => {
+            eprintln!("Could not load instance id: {error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn default_instance_id_file() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_dir.join("flotsync").join(INSTANCE_ID_FILE_NAME))
+}
+
 fn start_peer_announcement(
     system: &KompactSystem,
+    flotsync_config: &FlotsyncConfig,
     instance_id: Uuid,
 ) -> std::result::Result<ActiveService, String> {
+    let options = flotsync_config
+        .settings::<PeerAnnouncementOptions>()
+        .map_err(|error| error.to_string())?
+        .with_instance_id(instance_id);
     let io_runtime = IoRuntime::build(system, DriverConfig::default());
 
     let (startup_promise, startup_future) = peer_announcement_startup_signal();
-    let options = PEER_ANNOUNCEMENT_DEFAULT_OPTIONS.with_instance_id(instance_id);
     let placeholder_endpoint = SocketAddr::new(
         IpAddr::V4(Ipv4Addr::LOCALHOST),
         options.socket_bind_addr().port(),
@@ -0,0 +1,721 @@
+//! Sync plain text files in a directory through [`LinearString`] documents.
+//!
+//! A [`SyncedDirectory`] keeps one [`LinearString`] per text file below its root. Local edits are
+//! picked up by [`SyncedDirectory::scan`], which diffs the new file content against the tracked
+//! document with [`linear_diff`] and reports the result as [`FileChange`]s for replication.
+//! Changes from other replicas go through [`SyncedDirectory::apply_remote`], which merges them
+//! into the tracked document and writes the merged text back to disk.
+//!
+//! Watching is done by polling: callers run [`SyncedDirectory::scan`] on whatever schedule suits
+//! them. Before a remote change is merged, any pending local edit of the same file is ingested
+//! first, so concurrent edits on both sides converge instead of overwriting each other.
+//!
+//! If two replicas start tracking the same path concurrently, e.g. because both scanned the same
+//! new file, the document with the lower initial id wins on every replica. The replica that
+//! created the other document re-expresses its content as a modification of the winning one, and
+//! changes to the losing document are dropped.
+//!
+//! Files that are not valid UTF-8 and symbolic links are ignored. Remote changes to paths that
+//! lead through a symbolic link are refused, so they cannot reach outside the synced root.
+
+use flotsync_data_types::text::{
+    ApplyError,
+    DiffError,
+    LinearString,
+    LinearStringDiff,
+    linear_diff,
+};
+use snafu::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs,
+    hash::Hash,
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Suffix of the temporary files used to replace tracked files atomically.
+///
+/// Files with this suffix are never tracked.
+pub const TEMP_FILE_SUFFIX: &str = ".flotsync-tmp";
+
+/// Errors while scanning a synced directory or applying remote changes to it.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum FsSyncError<Id>
+where
+    Id: fmt::Debug + fmt::Display + 'static,
+{
+    #[snafu(display("Could not access {}.", path.display()))]
+    Io { path: PathBuf, source: io::Error },
+    #[snafu(display("Path {} is not a plain relative path.", path.display()))]
+    InvalidPath { path: PathBuf },
+    #[snafu(display("Path {} leads through a symbolic link.", path.display()))]
+    SymlinkedPath { path: PathBuf },
+    #[snafu(display("File {} is not tracked.", path.display()))]
+    NotTracked { path: PathBuf },
+    #[snafu(display("Could not diff the local changes to {}.", path.display()))]
+    Diff { path: PathBuf, source: DiffError },
+    #[snafu(display("Could not apply changes to {}.", path.display()))]
+    Apply {
+        path: PathBuf,
+        source: ApplyError<Id>,
+    },
+}
+
+/// A change to a single file, relative to the root of its [`SyncedDirectory`].
+///
+/// Produced by [`SyncedDirectory::scan`] for local edits and consumed by
+/// [`SyncedDirectory::apply_remote`] on other replicas.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileChange<Id> {
+    /// A new file started being tracked.
+    ///
+    /// `initial_id` is the id of the empty document the `diff` applies to.
+    Created {
+        path: PathBuf,
+        initial_id: Id,
+        diff: LinearStringDiff<Id>,
+    },
+    /// The content of a tracked file changed.
+    ///
+    /// `document` is the initial id of the document the `diff` applies to.
+    Modified {
+        path: PathBuf,
+        document: Id,
+        diff: LinearStringDiff<Id>,
+    },
+    /// A tracked file was deleted.
+    Removed { path: PathBuf },
+}
+impl<Id> FileChange<Id> {
+    /// The path of the changed file, relative to the synced root.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Created { path, .. } | Self::Modified { path, .. } | Self::Removed { path } => {
+                path
+            }
+        }
+    }
+}
+
+/// A directory whose text files are kept in sync with [`LinearString`] documents.
+#[derive(Debug)]
+pub struct SyncedDirectory<Id> {
+    root: PathBuf,
+    files: BTreeMap<PathBuf, TrackedFile<Id>>,
+}
+impl<Id> SyncedDirectory<Id>
+where
+    Id: Clone + fmt::Debug + fmt::Display + PartialEq + Eq + Hash + PartialOrd + Ord + 'static,
+{
+    /// Create a synced directory at `root` that does not track any files yet.
+    ///
+    /// The first [`scan`](Self::scan) reports every existing text file as created.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// The root directory being synced.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Relative paths of all currently tracked files.
+    pub fn tracked_paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// The tracked document for the file at the relative `path`, if any.
+    #[must_use]
+    pub fn document(&self, path: &Path) -> Option<&LinearString<Id>> {
+        self.files.get(path).map(|file| &file.document)
+    }
+
+    /// Walk the directory and turn all local edits since the last scan into changes.
+    ///
+    /// New ids for created files and inserted text are taken from `id_generator`.
+    ///
+    /// # Errors
+    ///
+    /// See `FsSyncError<Id>` for failure conditions.
+    pub fn scan(
+        &mut self,
+        id_generator: &mut impl Iterator<Item = Id>,
+    ) -> Result<Vec<FileChange<Id>>, FsSyncError<Id>> {
+        let mut on_disk = Vec::new();
+        collect_files(&self.root, Path::new(""), &mut on_disk)?;
+
+        let mut changes = Vec::new();
+        for path in &on_disk {
+            changes.extend(self.ingest_local(path, id_generator)?);
+        }
+        let removed: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| on_disk.binary_search(path).is_err())
+            .cloned()
+            .collect();
+        for path in removed {
+            self.files.remove(&path);
+            changes.push(FileChange::Removed { path });
+        }
+        Ok(changes)
+    }
+
+    /// Merge a change from another replica and write the result back to disk.
+    ///
+    /// Any local edit of the same file that has not been scanned yet is merged as well and
+    /// returned, so the caller can replicate it like the result of a [`scan`](Self::scan).
+    /// This includes a file that was created locally under the same path as a remote
+    /// [`FileChange::Created`]: its content is kept as a modification of whichever document wins,
+    /// see the [module docs](self).
+    ///
+    /// Removals win over concurrent local edits.
+    ///
+    /// # Errors
+    ///
+    /// See `FsSyncError<Id>` for failure conditions.
+    /// If the diff depends on changes that have not been applied yet, this fails with
+    /// [`ApplyError::ApplicationFailed`] carrying the remaining operations.
+    pub fn apply_remote(
+        &mut self,
+        change: FileChange<Id>,
+        id_generator: &mut impl Iterator<Item = Id>,
+    ) -> Result<Option<FileChange<Id>>, FsSyncError<Id>> {
+        ensure!(
+            is_plain_relative(change.path()),
+            InvalidPathSnafu {
+                path: change.path()
+            }
+        );
+        self.ensure_no_symlinks(change.path())?;
+        match change {
+            FileChange::Created {
+                path,
+                initial_id,
+                diff,
+            } => {
+                let mut document = LinearString::new(initial_id.clone());
+                diff.apply_to(&mut document)
+                    .context(ApplySnafu { path: &path })?;
+                let rebase_local_content = match self.files.get(&path) {
+                    // Both sides created the file concurrently, and the tracked document wins.
+                    Some(file) if file.initial_id <= initial_id => return Ok(None),
+                    Some(file) => {
+                        let on_disk = read_text(&self.root.join(&path))?;
+                        file.created_locally || on_disk.is_some_and(|text| text != file.content)
+                    }
+                    None => fs::symlink_metadata(self.root.join(&path)).is_ok(),
+                };
+                if rebase_local_content {
+                    self.files.insert(
+                        path.clone(),
+                        TrackedFile {
+                            initial_id,
+                            content: document.to_string(),
+                            document,
+                            stamp: None,
+                            created_locally: false,
+                        },
+                    );
+                    self.ingest_local(&path, id_generator)
+                } else {
+                    let file = self.write_back(&path, initial_id, document)?;
+                    self.files.insert(path, file);
+                    Ok(None)
+                }
+            }
+            FileChange::Modified {
+                path,
+                document: initial_id,
+                diff,
+            } => {
+                let local_change = self.ingest_local(&path, id_generator)?;
+                let Some(file) = self.files.get(&path) else {
+                    return NotTrackedSnafu { path }.fail();
+                };
+                if file.initial_id < initial_id {
+                    // The change is to a document that lost against the tracked one, whose
+                    // creator keeps its content as a change to the winner instead.
+                    log::debug!(
+                        "Dropping change to superseded document {initial_id} of {}",
+                        path.display()
+                    );
+                    return Ok(local_change);
+                }
+                ensure!(file.initial_id == initial_id, NotTrackedSnafu { path });
+                let mut document = file.document.clone();
+                diff.apply_to(&mut document)
+                    .context(ApplySnafu { path: &path })?;
+                let initial_id = file.initial_id.clone();
+                let file = self.write_back(&path, initial_id, document)?;
+                self.files.insert(path, file);
+                Ok(local_change)
+            }
+            FileChange::Removed { path } => {
+                if self.files.remove(&path).is_some() {
+                    let full_path = self.root.join(&path);
+                    match fs::remove_file(&full_path) {
+                        Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                            return Err(error).context(IoSnafu { path: full_path });
+                        }
+                        _ => (),
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Bring the tracked document for `path` up to date with the file on disk.
+    ///
+    /// Returns the resulting change, if there was one.
+    fn ingest_local(
+        &mut self,
+        path: &Path,
+        id_generator: &mut impl Iterator<Item = Id>,
+    ) -> Result<Option<FileChange<Id>>, FsSyncError<Id>> {
+        let full_path = self.root.join(path);
+        let stamp = match FileStamp::read(&full_path) {
+            Ok(stamp) => stamp,
+            // Deletions are only picked up by full scans.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error).context(IoSnafu { path: full_path }),
+        };
+        if self
+            .files
+            .get(path)
+            .is_some_and(|file| file.stamp == Some(stamp))
+        {
+            return Ok(None);
+        }
+        let Some(content) = read_text(&full_path)? else {
+            return Ok(None);
+        };
+
+        match self.files.get_mut(path) {
+            Some(file) => {
+                file.stamp = Some(stamp);
+                if file.content == content {
+                    return Ok(None);
+                }
+                let diff = linear_diff(&file.document, &content, id_generator)
+                    .context(DiffSnafu { path })?;
+                diff.clone()
+                    .apply_to(&mut file.document)
+                    .context(ApplySnafu { path })?;
+                file.content = content;
+                Ok(Some(FileChange::Modified {
+                    path: path.to_path_buf(),
+                    document: file.initial_id.clone(),
+                    diff,
+                }))
+            }
+            None => {
+                let Some(initial_id) = id_generator.next() else {
+                    return Err(FsSyncError::Diff {
+                        path: path.to_path_buf(),
+                        source: DiffError::IdsExhausted,
+                    });
+                };
+                let mut document = LinearString::new(initial_id.clone());
+                let diff =
+                    linear_diff(&document, &content, id_generator).context(DiffSnafu { path })?;
+                diff.clone()
+                    .apply_to(&mut document)
+                    .context(ApplySnafu { path })?;
+                self.files.insert(
+                    path.to_path_buf(),
+                    TrackedFile {
+                        initial_id: initial_id.clone(),
+                        document,
+                        content,
+                        stamp: Some(stamp),
+                        created_locally: true,
+                    },
+                );
+                Ok(Some(FileChange::Created {
+                    path: path.to_path_buf(),
+                    initial_id,
+                    diff,
+                }))
+            }
+        }
+    }
+
+    /// Fail if the relative `path`, or any directory on the way to it, is a symbolic link.
+    ///
+    /// Checks the path as it currently is on disk, so parts that don't exist yet are fine.
+    fn ensure_no_symlinks(&self, path: &Path) -> Result<(), FsSyncError<Id>> {
+        let mut current = self.root.clone();
+        for component in path.components() {
+            current.push(component);
+            match fs::symlink_metadata(&current) {
+                Ok(metadata) => {
+                    ensure!(
+                        !metadata.file_type().is_symlink(),
+                        SymlinkedPathSnafu { path }
+                    );
+                }
+                Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(error) => return Err(error).context(IoSnafu { path: current }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Atomically replace the file at `path` with the content of `document`.
+    ///
+    /// `path` must have been checked with [`ensure_no_symlinks`](Self::ensure_no_symlinks).
+    fn write_back(
+        &self,
+        path: &Path,
+        initial_id: Id,
+        document: LinearString<Id>,
+    ) -> Result<TrackedFile<Id>, FsSyncError<Id>> {
+        let full_path = self.root.join(path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).context(IoSnafu { path: parent })?;
+        }
+        let mut temp_name = full_path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(TEMP_FILE_SUFFIX);
+        let temp_path = full_path.with_file_name(temp_name);
+        let content = document.to_string();
+        write_new_file(&temp_path, &content).context(IoSnafu { path: &temp_path })?;
+        fs::rename(&temp_path, &full_path).context(IoSnafu { path: &full_path })?;
+        let stamp = FileStamp::read(&full_path).context(IoSnafu { path: &full_path })?;
+        Ok(TrackedFile {
+            initial_id,
+            document,
+            content,
+            stamp: Some(stamp),
+            created_locally: false,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct TrackedFile<Id> {
+    /// The initial id of `document`, which tells concurrently created documents apart.
+    initial_id: Id,
+    /// Whether `document` was created by a local scan rather than a remote change.
+    created_locally: bool,
+    document: LinearString<Id>,
+    /// The text last seen on or written to disk.
+    content: String,
+    /// `None` if the file on disk still needs to be read.
+    stamp: Option<FileStamp>,
+}
+
+/// Cheap change detection, so unchanged files don't have to be read on every scan.
+///
+/// A matching stamp can hide an edit that kept the size within the timestamp resolution of the
+/// file system. Such edits are picked up with the next change to the file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+impl FileStamp {
+    fn read(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// Recursively collect the relative paths of all regular files below `root.join(relative)`.
+///
+/// The output is sorted.
+fn collect_files<Id>(
+    root: &Path,
+    relative: &Path,
+    out: &mut Vec<PathBuf>,
+) -> Result<(), FsSyncError<Id>>
+where
+    Id: fmt::Debug + fmt::Display + 'static,
+{
+    let directory = root.join(relative);
+    let entries = fs::read_dir(&directory).context(IoSnafu { path: &directory })?;
+    let mut children = Vec::new();
+    for entry in entries {
+        let entry = entry.context(IoSnafu { path: &directory })?;
+        let file_type = entry.file_type().context(IoSnafu { path: entry.path() })?;
+        let name = entry.file_name();
+        if name.to_string_lossy().ends_with(TEMP_FILE_SUFFIX) {
+            continue;
+        }
+        children.push((relative.join(name), file_type));
+    }
+    children.sort_by(|left, right| left.0.cmp(&right.0));
+    for (path, file_type) in children {
+        if file_type.is_dir() {
+            collect_files(root, &path, out)?;
+        } else if file_type.is_file() {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Write `content` to a new file at `path`, replacing whatever was left there before.
+///
+/// A leftover symbolic link is removed rather than followed.
+fn write_new_file(path: &Path, content: &str) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+        _ => (),
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    file.write_all(content.as_bytes())
+}
+
+/// Read `path` as text, or return `None` if it is not valid UTF-8.
+fn read_text<Id>(path: &Path) -> Result<Option<String>, FsSyncError<Id>>
+where
+    Id: fmt::Debug + fmt::Display + 'static,
+{
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+            log::debug!("Ignoring non-text file {}", path.display());
+            Ok(None)
+        }
+        Err(error) => Err(error).context(IoSnafu { path }),
+    }
+}
+
+/// Whether `path` is relative and only made of normal components.
+///
+/// Remote changes must not be able to address files outside the synced root.
+fn is_plain_relative(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    struct TestDir(PathBuf);
+    impl TestDir {
+        fn new() -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("flotsync-fs-{}", Uuid::new_v4().as_hyphenated()));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Ids that are unique per replica, by giving each replica its own range.
+    fn replica_ids(replica: u32) -> impl Iterator<Item = u32> {
+        (replica * 1_000_000)..
+    }
+
+    fn replicate(
+        changes: Vec<FileChange<u32>>,
+        target: &mut SyncedDirectory<u32>,
+        ids: &mut impl Iterator<Item = u32>,
+    ) -> Vec<FileChange<u32>> {
+        let mut local_changes = Vec::new();
+        for change in changes {
+            local_changes.extend(target.apply_remote(change, ids).unwrap());
+        }
+        local_changes
+    }
+
+    #[test]
+    fn scan_reports_created_modified_and_removed_files() {
+        let dir = TestDir::new();
+        let mut ids = replica_ids(1);
+        let mut synced = SyncedDirectory::new(&dir.0);
+        fs::create_dir_all(dir.0.join("notes")).unwrap();
+        fs::write(dir.0.join("notes/todo.txt"), "milk").unwrap();
+        fs::write(dir.0.join("binary.bin"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let changes = synced.scan(&mut ids).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(
+            matches!(&changes[0], FileChange::Created { path, .. } if path == Path::new("notes/todo.txt"))
+        );
+        assert!(synced.scan(&mut ids).unwrap().is_empty());
+
+        fs::write(dir.0.join("notes/todo.txt"), "milk and eggs").unwrap();
+        let changes = synced.scan(&mut ids).unwrap();
+        assert!(matches!(&changes[..], [FileChange::Modified { .. }]));
+        assert_eq!(
+            synced
+                .document(Path::new("notes/todo.txt"))
+                .unwrap()
+                .to_string(),
+            "milk and eggs"
+        );
+
+        fs::remove_file(dir.0.join("notes/todo.txt")).unwrap();
+        let changes = synced.scan(&mut ids).unwrap();
+        assert_eq!(
+            changes,
+            vec![FileChange::Removed {
+                path: PathBuf::from("notes/todo.txt")
+            }]
+        );
+        assert_eq!(synced.tracked_paths().count(), 0);
+    }
+
+    #[test]
+    fn concurrent_edits_converge_on_both_replicas() {
+        let alice_dir = TestDir::new();
+        let bob_dir = TestDir::new();
+        let mut alice_ids = replica_ids(1);
+        let mut bob_ids = replica_ids(2);
+        let mut alice = SyncedDirectory::new(&alice_dir.0);
+        let mut bob = SyncedDirectory::new(&bob_dir.0);
+
+        fs::write(alice_dir.0.join("shared.txt"), "hello world").unwrap();
+        let created = alice.scan(&mut alice_ids).unwrap();
+        assert!(replicate(created, &mut bob, &mut bob_ids).is_empty());
+        assert_eq!(
+            fs::read_to_string(bob_dir.0.join("shared.txt")).unwrap(),
+            "hello world"
+        );
+
+        fs::write(alice_dir.0.join("shared.txt"), "hello brave world").unwrap();
+        fs::write(bob_dir.0.join("shared.txt"), "hello world!").unwrap();
+        let alice_changes = alice.scan(&mut alice_ids).unwrap();
+        // Bob has not scanned yet, so his edit is picked up while merging Alice's.
+        let bob_changes = replicate(alice_changes, &mut bob, &mut bob_ids);
+        assert_eq!(bob_changes.len(), 1);
+        assert!(replicate(bob_changes, &mut alice, &mut alice_ids).is_empty());
+
+        let alice_text = fs::read_to_string(alice_dir.0.join("shared.txt")).unwrap();
+        let bob_text = fs::read_to_string(bob_dir.0.join("shared.txt")).unwrap();
+        assert_eq!(alice_text, "hello brave world!");
+        assert_eq!(alice_text, bob_text);
+        assert!(alice.scan(&mut alice_ids).unwrap().is_empty());
+        assert!(bob.scan(&mut bob_ids).unwrap().is_empty());
+    }
+
+    #[test]
+    fn concurrently_created_file_keeps_local_content() {
+        let alice_dir = TestDir::new();
+        let bob_dir = TestDir::new();
+        let mut alice_ids = replica_ids(1);
+        let mut bob_ids = replica_ids(2);
+        let mut alice = SyncedDirectory::new(&alice_dir.0);
+        let mut bob = SyncedDirectory::new(&bob_dir.0);
+
+        fs::write(alice_dir.0.join("shared.txt"), "from alice").unwrap();
+        fs::write(bob_dir.0.join("shared.txt"), "from bob").unwrap();
+        let created = alice.scan(&mut alice_ids).unwrap();
+        let bob_changes = replicate(created, &mut bob, &mut bob_ids);
+        assert!(matches!(&bob_changes[..], [FileChange::Modified { .. }]));
+        assert!(replicate(bob_changes, &mut alice, &mut alice_ids).is_empty());
+
+        assert_eq!(
+            fs::read_to_string(alice_dir.0.join("shared.txt")).unwrap(),
+            "from bob"
+        );
+        assert!(bob.scan(&mut bob_ids).unwrap().is_empty());
+    }
+
+    #[test]
+    fn file_scanned_on_both_replicas_converges() {
+        let alice_dir = TestDir::new();
+        let bob_dir = TestDir::new();
+        let carol_dir = TestDir::new();
+        let mut alice_ids = replica_ids(1);
+        let mut bob_ids = replica_ids(2);
+        let mut carol_ids = replica_ids(3);
+        let mut alice = SyncedDirectory::new(&alice_dir.0);
+        let mut bob = SyncedDirectory::new(&bob_dir.0);
+        let mut carol = SyncedDirectory::new(&carol_dir.0);
+
+        fs::write(alice_dir.0.join("shared.txt"), "from alice").unwrap();
+        fs::write(bob_dir.0.join("shared.txt"), "from bob").unwrap();
+        let from_alice = alice.scan(&mut alice_ids).unwrap();
+        let mut from_bob = bob.scan(&mut bob_ids).unwrap();
+        fs::write(bob_dir.0.join("shared.txt"), "from bob, edited").unwrap();
+        from_bob.extend(bob.scan(&mut bob_ids).unwrap());
+
+        // Alice's document has the lower initial id, so Bob's is dropped everywhere.
+        assert!(replicate(from_bob.clone(), &mut alice, &mut alice_ids).is_empty());
+        assert!(replicate(from_bob, &mut carol, &mut carol_ids).is_empty());
+        assert!(replicate(from_alice.clone(), &mut carol, &mut carol_ids).is_empty());
+        let rebased = replicate(from_alice, &mut bob, &mut bob_ids);
+        assert!(matches!(&rebased[..], [FileChange::Modified { .. }]));
+        assert!(replicate(rebased.clone(), &mut alice, &mut alice_ids).is_empty());
+        assert!(replicate(rebased, &mut carol, &mut carol_ids).is_empty());
+
+        for dir in [&alice_dir, &bob_dir, &carol_dir] {
+            assert_eq!(
+                fs::read_to_string(dir.0.join("shared.txt")).unwrap(),
+                "from bob, edited"
+            );
+        }
+        let path = Path::new("shared.txt");
+        assert_eq!(alice.document(path), bob.document(path));
+        assert_eq!(alice.document(path), carol.document(path));
+        assert!(alice.scan(&mut alice_ids).unwrap().is_empty());
+        assert!(bob.scan(&mut bob_ids).unwrap().is_empty());
+        assert!(carol.scan(&mut carol_ids).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_changes_cannot_follow_symlinks() {
+        let dir = TestDir::new();
+        let outside = TestDir::new();
+        let mut ids = replica_ids(1);
+        let mut synced = SyncedDirectory::new(&dir.0);
+        std::os::unix::fs::symlink(&outside.0, dir.0.join("link")).unwrap();
+
+        let mut document = LinearString::new(7);
+        let diff = linear_diff(&document, "escaped", &mut ids).unwrap();
+        diff.clone().apply_to(&mut document).unwrap();
+        let result = synced.apply_remote(
+            FileChange::Created {
+                path: PathBuf::from("link/escaped.txt"),
+                initial_id: 7,
+                diff,
+            },
+            &mut ids,
+        );
+        assert!(matches!(result, Err(FsSyncError::SymlinkedPath { .. })));
+        assert!(!outside.0.join("escaped.txt").exists());
+    }
+
+    #[test]
+    fn remote_changes_cannot_escape_the_root() {
+        let dir = TestDir::new();
+        let mut ids = replica_ids(1);
+        let mut synced = SyncedDirectory::new(&dir.0);
+        let result = synced.apply_remote(
+            FileChange::Removed {
+                path: PathBuf::from("../outside.txt"),
+            },
+            &mut ids,
+        );
+        assert!(matches!(result, Err(FsSyncError::InvalidPath { .. })));
+    }
+}
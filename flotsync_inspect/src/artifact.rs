@@ -0,0 +1,157 @@
+//! Artifact kinds understood by the inspector and their decoding.
+//!
+//! Every kind is decoded with the generated protobuf types and converted to the
+//! canonical protobuf JSON mapping, which is what both output formats work on.
+//! An operation log is a sequence of length-delimited `SchemaOperation`s, as written
+//! by [`Message::encode_length_delimited`].
+
+use crate::errors::{InspectError, inspect_error};
+use clap::ValueEnum;
+use flotsync_messages::{buffa::Message, datamodel, replication, versions};
+use serde_json::Value;
+use snafu::prelude::*;
+use std::fmt;
+
+/// The kinds of encoded artifacts the inspector can decode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ArtifactKind {
+    /// A replication `RuntimeMessage`, as exchanged between members.
+    RuntimeMessage,
+    /// A single datamodel `SchemaOperation`.
+    SchemaOperation,
+    /// Back-to-back length-delimited datamodel `SchemaOperation`s.
+    Oplog,
+    /// A datamodel `DataSnapshot` of a whole dataset.
+    DataSnapshot,
+    /// A datamodel `RowSnapshot` of one document.
+    RowSnapshot,
+    /// A datamodel `HistorySnapshot` of one field.
+    HistorySnapshot,
+    /// A replication `InitialSnapshot` handed to joining members.
+    InitialSnapshot,
+    /// A datamodel `SchemaDefinition`.
+    Schema,
+    /// A self-describing `VersionVector`.
+    VersionVector,
+    /// A `CompactVersionVector` without its member count.
+    CompactVersionVector,
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .to_possible_value()
+            .expect("no artifact kind is skipped");
+        f.write_str(name.get_name())
+    }
+}
+
+/// Decode `bytes` as an artifact of `kind` into the protobuf JSON mapping.
+///
+/// An operation log decodes into a JSON array with one entry per operation.
+///
+/// # Errors
+///
+/// Fails if `bytes` is not a valid encoding of `kind`.
+pub fn decode_artifact(kind: ArtifactKind, bytes: &[u8]) -> Result<Value, InspectError> {
+    match kind {
+        ArtifactKind::RuntimeMessage => decode_message::<replication::RuntimeMessage>(kind, bytes),
+        ArtifactKind::SchemaOperation => decode_message::<datamodel::SchemaOperation>(kind, bytes),
+        ArtifactKind::Oplog => decode_oplog(bytes),
+        ArtifactKind::DataSnapshot => decode_message::<datamodel::DataSnapshot>(kind, bytes),
+        ArtifactKind::RowSnapshot => decode_message::<datamodel::RowSnapshot>(kind, bytes),
+        ArtifactKind::HistorySnapshot => decode_message::<datamodel::HistorySnapshot>(kind, bytes),
+        ArtifactKind::InitialSnapshot => {
+            decode_message::<replication::InitialSnapshot>(kind, bytes)
+        }
+        ArtifactKind::Schema => decode_message::<datamodel::SchemaDefinition>(kind, bytes),
+        ArtifactKind::VersionVector => decode_message::<versions::VersionVector>(kind, bytes),
+        ArtifactKind::CompactVersionVector => {
+            decode_message::<versions::CompactVersionVector>(kind, bytes)
+        }
+    }
+}
+
+fn decode_message<M>(kind: ArtifactKind, bytes: &[u8]) -> Result<Value, InspectError>
+where
+    M: Message + serde::Serialize,
+{
+    let message = M::decode_from_slice(bytes).context(inspect_error::DecodeSnafu { kind })?;
+    serde_json::to_value(&message).context(inspect_error::ConvertJsonSnafu { kind })
+}
+
+fn decode_oplog(mut bytes: &[u8]) -> Result<Value, InspectError> {
+    let mut entries = Vec::new();
+    while !bytes.is_empty() {
+        let operation = datamodel::SchemaOperation::decode_length_delimited(&mut bytes).context(
+            inspect_error::DecodeLogEntrySnafu {
+                index: entries.len(),
+            },
+        )?;
+        let entry = serde_json::to_value(&operation).context(inspect_error::ConvertJsonSnafu {
+            kind: ArtifactKind::Oplog,
+        })?;
+        entries.push(entry);
+    }
+    Ok(Value::Array(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_messages::buffa::MessageField;
+    use serde_json::json;
+
+    fn delete_operation(version: u64) -> datamodel::SchemaOperation {
+        datamodel::SchemaOperation {
+            change_id: MessageField::some(datamodel::HistoryId {
+                version,
+                node_index: 1,
+                ..datamodel::HistoryId::default()
+            }),
+            operation: Some(datamodel::schema_operation::Operation::Delete(Box::new(
+                datamodel::DeleteRowOperation {
+                    row_id: vec![7; 16],
+                    ..datamodel::DeleteRowOperation::default()
+                },
+            ))),
+            ..datamodel::SchemaOperation::default()
+        }
+    }
+
+    #[test]
+    fn oplog_decodes_every_entry_in_order() {
+        let mut log = Vec::new();
+        delete_operation(3).encode_length_delimited(&mut log);
+        delete_operation(4).encode_length_delimited(&mut log);
+
+        let decoded = decode_artifact(ArtifactKind::Oplog, &log).expect("valid oplog");
+        assert_eq!(
+            decoded,
+            json!([
+                {
+                    "changeId": { "version": "3", "nodeIndex": 1 },
+                    "delete": { "rowId": "BwcHBwcHBwcHBwcHBwcHBw==" },
+                },
+                {
+                    "changeId": { "version": "4", "nodeIndex": 1 },
+                    "delete": { "rowId": "BwcHBwcHBwcHBwcHBwcHBw==" },
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn truncated_oplog_reports_the_broken_entry() {
+        let mut log = Vec::new();
+        delete_operation(3).encode_length_delimited(&mut log);
+        delete_operation(4).encode_length_delimited(&mut log);
+        log.pop();
+
+        let error = decode_artifact(ArtifactKind::Oplog, &log).expect_err("truncated oplog");
+        assert!(matches!(
+            error,
+            InspectError::DecodeLogEntry { index: 1, .. }
+        ));
+    }
+}
@@ -0,0 +1,28 @@
+//! Error types of the inspect binary.
+
+use crate::artifact::ArtifactKind;
+use flotsync_messages::buffa::DecodeError;
+use snafu::prelude::*;
+use std::{io, path::PathBuf};
+
+/// Failures while inspecting one artifact.
+#[derive(Debug, Snafu)]
+#[snafu(module(inspect_error), visibility(pub(crate)))]
+pub enum InspectError {
+    #[snafu(display("Could not read {}.", path.display()))]
+    ReadInput { path: PathBuf, source: io::Error },
+    #[snafu(display("The input is not a valid {kind}."))]
+    Decode {
+        kind: ArtifactKind,
+        source: DecodeError,
+    },
+    #[snafu(display("Operation log entry {index} is not a valid schema operation."))]
+    DecodeLogEntry { index: usize, source: DecodeError },
+    #[snafu(display("Could not convert the decoded {kind} to JSON."))]
+    ConvertJson {
+        kind: ArtifactKind,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Could not serialise the JSON output."))]
+    SerializeJson { source: serde_json::Error },
+}
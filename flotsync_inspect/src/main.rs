@@ -0,0 +1,77 @@
+//! `flotsync-inspect`: pretty-prints encoded Flotsync protobuf artifacts.
+//!
+//! Takes one encoded message, snapshot, or operation log (see [`artifact`] for the
+//! supported kinds) and prints it as an annotated tree: history ids and version
+//! vectors use their compact notations, UUID-valued ids are shown as UUIDs, and long
+//! strings and byte values are truncated. With `--json`, prints the canonical
+//! protobuf JSON mapping instead, for further processing with other tools.
+
+use crate::{
+    artifact::ArtifactKind,
+    errors::{InspectError, inspect_error},
+    render::RenderOptions,
+};
+use clap::Parser;
+use snafu::prelude::*;
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+mod artifact;
+mod errors;
+mod render;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Encoded artifact to inspect, or `-` to read standard input.
+    file: PathBuf,
+    /// What the file contains.
+    #[arg(short, long, value_enum, default_value_t = ArtifactKind::RuntimeMessage)]
+    kind: ArtifactKind,
+    /// Print the canonical protobuf JSON mapping instead of the annotated tree.
+    #[arg(long)]
+    json: bool,
+    /// Truncate strings and byte values longer than this many characters in the tree.
+    #[arg(long, value_name = "CHARS", default_value_t = 48)]
+    max_value_len: usize,
+}
+
+fn main() {
+    if let Err(error) = run(Args::parse()) {
+        eprintln!("{}", snafu::Report::from_error(error));
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), InspectError> {
+    let bytes = read_input(&args.file)?;
+    let artifact = artifact::decode_artifact(args.kind, &bytes)?;
+    if args.json {
+        let json =
+            serde_json::to_string_pretty(&artifact).context(inspect_error::SerializeJsonSnafu)?;
+        println!("{json}");
+    } else {
+        let options = RenderOptions {
+            max_value_len: args.max_value_len,
+        };
+        print!(
+            "{}",
+            render::render_artifact(args.kind, &artifact, &options)
+        );
+    }
+    Ok(())
+}
+
+fn read_input(file: &Path) -> Result<Vec<u8>, InspectError> {
+    let context = inspect_error::ReadInputSnafu { path: file };
+    if file.as_os_str() == "-" {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes).context(context)?;
+        Ok(bytes)
+    } else {
+        fs::read(file).context(context)
+    }
+}
@@ -0,0 +1,455 @@
+//! Annotated tree rendering of decoded artifacts.
+//!
+//! Works on the protobuf JSON mapping and recognises the recurring Flotsync shapes
+//! by field name: history ids, history nodes, and version vectors are printed in
+//! their compact notations, and UUID-sized id bytes as UUIDs. Anything that does not
+//! have the expected shape falls back to the plain tree. Fields are listed in name
+//! order.
+
+use crate::artifact::ArtifactKind;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use flotsync_core::versions::VersionVector;
+use flotsync_utils::option_when;
+use serde_json::{Map, Value};
+use std::fmt::Write as _;
+use uuid::Uuid;
+
+/// Presentation settings for the tree output.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Strings and byte values longer than this many characters are truncated.
+    pub max_value_len: usize,
+}
+
+/// Render a decoded artifact of `kind` as an indented tree.
+#[must_use]
+pub fn render_artifact(kind: ArtifactKind, artifact: &Value, options: &RenderOptions) -> String {
+    let mut out = String::new();
+    match (kind, artifact) {
+        (ArtifactKind::Oplog, Value::Array(entries)) => {
+            for (index, entry) in entries.iter().enumerate() {
+                write_field(&mut out, 0, &format!("[{index}]"), "", entry, options);
+            }
+        }
+        (ArtifactKind::VersionVector, _) => {
+            write_root(&mut out, Shape::VersionVector, artifact, options);
+        }
+        (ArtifactKind::CompactVersionVector, _) => {
+            write_root(&mut out, Shape::CompactVersionVector, artifact, options);
+        }
+        _ => write_root(&mut out, Shape::Plain, artifact, options),
+    }
+    out
+}
+
+/// Recognised value shapes, derived from the name of the field holding the value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Shape {
+    Plain,
+    HistoryId,
+    HistoryNode,
+    VersionVector,
+    CompactVersionVector,
+}
+
+impl Shape {
+    fn of_field(field: &str) -> Self {
+        match field {
+            "id" | "pred" | "succ" | "start" | "changeId" | "anchor" => Self::HistoryId,
+            "nodes" => Self::HistoryNode,
+            "versions" | "finalVersions" => Self::VersionVector,
+            "readVersions" | "hasVersions" | "appliedVersions" | "compact" => {
+                Self::CompactVersionVector
+            }
+            _ => Self::Plain,
+        }
+    }
+}
+
+fn write_root(out: &mut String, shape: Shape, value: &Value, options: &RenderOptions) {
+    if let Some(inline) = inline_value(shape, "", value, options) {
+        writeln!(out, "{inline}").expect("writing to a string cannot fail");
+    } else if let Value::Object(fields) = value {
+        write_object(out, 0, fields, options);
+    }
+}
+
+fn write_object(
+    out: &mut String,
+    indent: usize,
+    fields: &Map<String, Value>,
+    options: &RenderOptions,
+) {
+    for (field, value) in fields {
+        write_field(out, indent, field, field, value, options);
+    }
+}
+
+/// Write one value labelled `label`, held by the protobuf field `field`.
+///
+/// Array items keep the field of their array, so they are recognised like it.
+fn write_field(
+    out: &mut String,
+    indent: usize,
+    label: &str,
+    field: &str,
+    value: &Value,
+    options: &RenderOptions,
+) {
+    let pad = "  ".repeat(indent);
+    if let Some(inline) = inline_value(Shape::of_field(field), field, value, options) {
+        writeln!(out, "{pad}{label}: {inline}").expect("writing to a string cannot fail");
+        return;
+    }
+    writeln!(out, "{pad}{label}:").expect("writing to a string cannot fail");
+    match value {
+        Value::Object(fields) => write_object(out, indent + 1, fields, options),
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                write_field(out, indent + 1, &format!("[{index}]"), field, item, options);
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {
+            unreachable!("scalars are always rendered inline")
+        }
+    }
+}
+
+fn inline_value(
+    shape: Shape,
+    field: &str,
+    value: &Value,
+    options: &RenderOptions,
+) -> Option<String> {
+    let recognised = match (shape, value) {
+        (Shape::HistoryId, Value::Object(fields)) => history_id(fields),
+        (Shape::HistoryNode, Value::Object(fields)) => history_node(fields),
+        (Shape::VersionVector, Value::Object(fields)) => version_vector(fields),
+        (Shape::CompactVersionVector, Value::Object(fields)) => compact_version_vector(fields),
+        _ => None,
+    };
+    recognised.or_else(|| match value {
+        Value::Null => Some("null".to_owned()),
+        Value::Bool(value) => Some(value.to_string()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::String(value) => Some(
+            uuid_value(field, value).unwrap_or_else(|| truncated(value, options.max_value_len)),
+        ),
+        Value::Object(fields) if fields.is_empty() => Some("{}".to_owned()),
+        Value::Array(items) if items.is_empty() => Some("[]".to_owned()),
+        Value::Object(_) | Value::Array(_) => None,
+    })
+}
+
+/// Render a `HistoryId` in the `v{version}@{node}:{chunk}` notation of indexed update ids.
+fn history_id(fields: &Map<String, Value>) -> Option<String> {
+    if fields
+        .keys()
+        .any(|name| !matches!(name.as_str(), "version" | "nodeIndex" | "chunkIndex"))
+    {
+        return None;
+    }
+    history_id_fields(fields, "version", "nodeIndex", "chunkIndex")
+}
+
+fn history_id_fields(
+    fields: &Map<String, Value>,
+    version: &str,
+    node_index: &str,
+    chunk_index: &str,
+) -> Option<String> {
+    let version = field_u64(fields, version)?;
+    let node_index = field_u64(fields, node_index)?;
+    let chunk_index = field_u64(fields, chunk_index)?;
+    Some(format!("v{version}@{node_index}:{chunk_index}"))
+}
+
+/// Render a `HistoryNodeMeta` on one line, with its origins and value summary.
+///
+/// A missing origin marks a boundary node and is shown as `none`.
+fn history_node(fields: &Map<String, Value>) -> Option<String> {
+    let mut line = history_id_fields(fields, "version", "nodeIndex", "chunkIndex")?;
+    for (side, prefix) in [("left", "originLeft"), ("right", "originRight")] {
+        let version = format!("{prefix}Version");
+        let origin = if fields.contains_key(&version) {
+            history_id_fields(
+                fields,
+                &version,
+                &format!("{prefix}NodeIndex"),
+                &format!("{prefix}ChunkIndex"),
+            )?
+        } else {
+            "none".to_owned()
+        };
+        write!(line, " {side}={origin}").expect("writing to a string cannot fail");
+    }
+    write!(line, " len={}", field_u64(fields, "valueLen")?)
+        .expect("writing to a string cannot fail");
+    if fields.get("deleted") == Some(&Value::Bool(true)) {
+        line.push_str(" deleted");
+    }
+    if fields.get("valueIsNull") == Some(&Value::Bool(true)) {
+        line.push_str(" null");
+    }
+    Some(line)
+}
+
+/// Render a self-describing `VersionVector` with the notation of the core version vectors.
+fn version_vector(fields: &Map<String, Value>) -> Option<String> {
+    let num_members = usize::try_from(field_u64(fields, "numMembers")?).ok()?;
+    let Some(Value::Object(compact)) = fields.get("compact") else {
+        return None;
+    };
+    let entries = expand_compact(compact, num_members)?;
+    option_when!(
+        !entries.is_empty(),
+        VersionVector::from_entries(entries).to_string()
+    )
+}
+
+/// Render a `CompactVersionVector`, whose member count is not known.
+///
+/// Uses the `〈base..., position:version, base...〉` notation of the core override vectors.
+fn compact_version_vector(fields: &Map<String, Value>) -> Option<String> {
+    let (representation, Value::Object(body)) = single_field(fields)? else {
+        return None;
+    };
+    match representation.as_str() {
+        "full" => {
+            let entries = field_u64_list(body, "entries")?;
+            Some(format!("〈{}〉", join(entries.iter().map(u64::to_string))))
+        }
+        "synced" => Some(format!("〈{}...〉", field_u64(body, "groupVersion")?)),
+        "override" => {
+            let group_version = field_u64(body, "groupVersion")?;
+            Some(format!(
+                "〈{group_version}..., {}:{}, {group_version}...〉",
+                field_u64(body, "overridePosition")?,
+                field_u64(body, "overrideVersion")?,
+            ))
+        }
+        "multiOverride" => {
+            let group_version = field_u64(body, "groupVersion")?;
+            let entries = position_versions(body, "overridePositions", "overrideVersions")?;
+            Some(format!(
+                "〈{group_version}..., {}, {group_version}...〉",
+                join(
+                    entries
+                        .iter()
+                        .map(|(position, version)| format!("{position}:{version}"))
+                )
+            ))
+        }
+        "sparse" => {
+            let base_version = field_u64(body, "baseVersion")?;
+            let entries = position_versions(body, "positions", "versions")?;
+            Some(format!(
+                "〈{base_version}..., {}, {base_version}...〉",
+                join(
+                    entries
+                        .iter()
+                        .map(|(position, version)| format!("{position}:{version}"))
+                )
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Expand a `CompactVersionVector` to the versions of all `num_members` members.
+fn expand_compact(compact: &Map<String, Value>, num_members: usize) -> Option<Vec<u64>> {
+    let (representation, Value::Object(body)) = single_field(compact)? else {
+        return None;
+    };
+    let (base_version, entries) = match representation.as_str() {
+        "full" => {
+            let entries = field_u64_list(body, "entries")?;
+            return option_when!(entries.len() == num_members, entries);
+        }
+        "synced" => (field_u64(body, "groupVersion")?, Vec::new()),
+        "override" => (
+            field_u64(body, "groupVersion")?,
+            vec![(
+                field_u64(body, "overridePosition")?,
+                field_u64(body, "overrideVersion")?,
+            )],
+        ),
+        "multiOverride" => (
+            field_u64(body, "groupVersion")?,
+            position_versions(body, "overridePositions", "overrideVersions")?,
+        ),
+        "sparse" => (
+            field_u64(body, "baseVersion")?,
+            position_versions(body, "positions", "versions")?,
+        ),
+        _ => return None,
+    };
+    let mut versions = vec![base_version; num_members];
+    for (position, version) in entries {
+        *versions.get_mut(usize::try_from(position).ok()?)? = version;
+    }
+    Some(versions)
+}
+
+fn single_field(fields: &Map<String, Value>) -> Option<(&String, &Value)> {
+    let mut iter = fields.iter();
+    let field = iter.next()?;
+    option_when!(iter.next().is_none(), field)
+}
+
+fn position_versions(
+    body: &Map<String, Value>,
+    positions: &str,
+    versions: &str,
+) -> Option<Vec<(u64, u64)>> {
+    let positions = field_u64_list(body, positions)?;
+    let versions = field_u64_list(body, versions)?;
+    option_when!(
+        positions.len() == versions.len(),
+        positions.into_iter().zip(versions).collect()
+    )
+}
+
+/// Read an integer field, defaulting to zero like protobuf does for omitted fields.
+///
+/// 64-bit integers are strings in the protobuf JSON mapping, so both forms are accepted.
+fn field_u64(fields: &Map<String, Value>, name: &str) -> Option<u64> {
+    match fields.get(name) {
+        None => Some(0),
+        Some(value) => json_u64(value),
+    }
+}
+
+fn field_u64_list(fields: &Map<String, Value>, name: &str) -> Option<Vec<u64>> {
+    match fields.get(name) {
+        None => Some(Vec::new()),
+        Some(Value::Array(items)) => items.iter().map(json_u64).collect(),
+        Some(_) => None,
+    }
+}
+
+fn json_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Show id bytes of UUID size, such as row and group ids, as UUIDs.
+fn uuid_value(field: &str, value: &str) -> Option<String> {
+    if !(field.ends_with("Id") || field.ends_with("Ids") || field.ends_with("Uuid")) {
+        return None;
+    }
+    let bytes = BASE64.decode(value).ok()?;
+    Uuid::from_slice(&bytes).ok().map(|uuid| uuid.to_string())
+}
+
+fn truncated(value: &str, max_len: usize) -> String {
+    let len = value.chars().count();
+    if len <= max_len {
+        format!("{value:?}")
+    } else {
+        let prefix: String = value.chars().take(max_len).collect();
+        format!("{prefix:?}… ({len} chars)")
+    }
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const OPTIONS: RenderOptions = RenderOptions { max_value_len: 8 };
+
+    #[test]
+    fn ids_and_values_use_compact_notation() {
+        let operation = json!({
+            "changeId": { "version": "3", "nodeIndex": 1 },
+            "update": {
+                "rowId": "BwcHBwcHBwcHBwcHBwcHBw==",
+                "fields": [{
+                    "fieldName": "title",
+                    "linearString": {
+                        "actions": [{
+                            "insert": {
+                                "id": { "version": "3", "nodeIndex": 1, "chunkIndex": 2 },
+                                "pred": {},
+                                "value": "a rather long line",
+                            },
+                        }],
+                    },
+                }],
+            },
+        });
+
+        assert_eq!(
+            render_artifact(ArtifactKind::SchemaOperation, &operation, &OPTIONS),
+            concat!(
+                "changeId: v3@1:0\n",
+                "update:\n",
+                "  fields:\n",
+                "    [0]:\n",
+                "      fieldName: \"title\"\n",
+                "      linearString:\n",
+                "        actions:\n",
+                "          [0]:\n",
+                "            insert:\n",
+                "              id: v3@1:2\n",
+                "              pred: v0@0:0\n",
+                "              value: \"a rather\"… (18 chars)\n",
+                "  rowId: 07070707-0707-0707-0707-070707070707\n",
+            )
+        );
+    }
+
+    #[test]
+    fn history_nodes_render_on_one_line() {
+        let snapshot = json!({
+            "nodes": [
+                { "version": "2", "originRightVersion": "0", "valueLen": 1 },
+                { "version": "3", "nodeIndex": 1, "originLeftVersion": "2", "deleted": true },
+            ],
+        });
+
+        assert_eq!(
+            render_artifact(ArtifactKind::HistorySnapshot, &snapshot, &OPTIONS),
+            concat!(
+                "nodes:\n",
+                "  [0]: v2@0:0 left=none right=v0@0:0 len=1\n",
+                "  [1]: v3@1:0 left=v2@0:0 right=none len=0 deleted\n",
+            )
+        );
+    }
+
+    #[test]
+    fn version_vectors_use_angle_bracket_notation() {
+        let self_describing = json!({
+            "numMembers": 4,
+            "compact": { "override": { "groupVersion": "12", "overridePosition": 2, "overrideVersion": "13" } },
+        });
+        assert_eq!(
+            render_artifact(ArtifactKind::VersionVector, &self_describing, &OPTIONS),
+            "〈0-1:12, 2:13, 3-3:12〉\n"
+        );
+
+        let compact = json!({
+            "sparse": { "baseVersion": "1", "positions": [0, 5], "versions": ["4", "0"] },
+        });
+        assert_eq!(
+            render_artifact(ArtifactKind::CompactVersionVector, &compact, &OPTIONS),
+            "〈1..., 0:4, 5:0, 1...〉\n"
+        );
+
+        let message =
+            json!({ "summary": { "hasVersions": { "synced": { "groupVersion": "7" } } } });
+        assert_eq!(
+            render_artifact(ArtifactKind::RuntimeMessage, &message, &OPTIONS),
+            "summary:\n  hasVersions: 〈7...〉\n"
+        );
+    }
+}
@@ -415,7 +415,7 @@ mod tests {
                 .insert_replication_group(ReplicationGroupRecord {
                     group_id: config.group_id,
                     member_keys: test_group_member_keys(&config.ordered_members),
-                    local_member_index,
+                    local_member_index: Some(local_member_index),
                     version_vector: VersionVector::initial(member_count),
                     lifecycle: ReplicationGroupLifecycle::Open,
                     security_material,
@@ -87,7 +87,7 @@ pub(super) async fn initialise_configured_group_if_ready(
         .insert_replication_group(ReplicationGroupRecord {
             group_id: config.group_id,
             member_keys,
-            local_member_index: group_shape.local_member_index,
+            local_member_index: Some(group_shape.local_member_index),
             version_vector: VersionVector::initial(group_shape.member_count),
             lifecycle: ReplicationGroupLifecycle::Open,
             security_material,
@@ -258,12 +258,12 @@ pub enum StaticGroupError {
         actual: Vec<MemberIdentity>,
     },
     #[snafu(display(
-        "Stored group {group_id} has local member index {actual}, expected {expected}."
+        "Stored group {group_id} has local member index {actual:?}, expected {expected}."
     ))]
     LocalMemberIndexMismatch {
         group_id: GroupId,
         expected: MemberIndex,
-        actual: MemberIndex,
+        actual: Option<MemberIndex>,
     },
     #[snafu(display("Failed to prepare initial static group security material: {source}"))]
     InitialGroupSecurity { source: ProvisionSecurityError },
@@ -384,7 +384,7 @@ fn validate_existing_static_group(
         }
     );
     ensure!(
-        existing_group.local_member_index == local_member_index,
+        existing_group.local_member_index == Some(local_member_index),
         static_group_error::LocalMemberIndexMismatchSnafu {
             group_id: config.group_id,
             expected: local_member_index,
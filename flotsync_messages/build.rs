@@ -35,11 +35,15 @@ fn main() {
             ".flotsync.delivery.v1.SealedPSKPayload.ciphertext",
             ".flotsync.delivery.v1.SealedHPKEPayload.ciphertext",
             ".flotsync.delivery.v1.DetachedSignature.signature_bytes",
+            ".flotsync.delivery.v1.CompressedPayload.data",
+            ".flotsync.delivery.v1.EncodedPayload.data",
+            ".flotsync.replication.v1.BlobChunk.data",
+            ".flotsync.replication.v1.SnapshotChunk.data",
         ])
         .include_file("flotsync_messages.rs")
-        .generate_json(false)
+        .generate_json(env::var_os("CARGO_FEATURE_JSON").is_some())
         .generate_text(false)
-        .generate_arbitrary(false)
+        .generate_arbitrary(true)
         .compile()
         .expect("Failed to generate Rust code from .proto files");
 }
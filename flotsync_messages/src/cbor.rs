@@ -0,0 +1,1121 @@
+//! Conversions between the generated protobuf messages and their [`flotsync_cbor`] forms.
+//!
+//! Converting to CBOR fails only for messages whose required `oneof` has no selected value, which
+//! the protobuf decoders reject as well. Converting back to protobuf always succeeds.
+
+use crate::{
+    buffa::MessageField,
+    datamodel as datamodel_proto,
+    replication as replication_proto,
+    versions as versions_proto,
+};
+use flotsync_cbor::{ByteString, datamodel, replication, versions};
+use snafu::prelude::*;
+
+/// Errors produced while converting protobuf messages to their CBOR form.
+#[derive(Debug, Snafu)]
+pub enum CborConversionError {
+    #[snafu(display("Protobuf oneof '{name}' has no selected value."))]
+    MissingOneof { name: &'static str },
+    #[snafu(display("Runtime message '{message}' has no CBOR form."))]
+    NoCborForm { message: &'static str },
+}
+
+type ConversionResult<T> = Result<T, CborConversionError>;
+
+/// A generated protobuf message with a CBOR form.
+pub trait IntoCbor {
+    type Cbor;
+
+    /// Convert this message into its CBOR form.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required `oneof` has no selected value, or if the message has no
+    /// CBOR form.
+    fn into_cbor(self) -> ConversionResult<Self::Cbor>;
+}
+
+fn optional_into_cbor<P>(mut field: MessageField<P>) -> ConversionResult<Option<P::Cbor>>
+where
+    P: IntoCbor + Default,
+{
+    field.take().map(IntoCbor::into_cbor).transpose()
+}
+
+fn all_into_cbor<P: IntoCbor>(messages: Vec<P>) -> ConversionResult<Vec<P::Cbor>> {
+    messages.into_iter().map(IntoCbor::into_cbor).collect()
+}
+
+fn optional_from_cbor<C, P: From<C> + Default>(value: Option<C>) -> MessageField<P> {
+    value.map(P::from).into()
+}
+
+fn all_from_cbor<C, P: From<C>>(values: Vec<C>) -> Vec<P> {
+    values.into_iter().map(P::from).collect()
+}
+
+impl IntoCbor for versions_proto::CompactVersionVector {
+    type Cbor = versions::CompactVersionVector;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use versions_proto::compact_version_vector::Versions;
+
+        let versions = self.versions.context(MissingOneofSnafu {
+            name: "CompactVersionVector.versions",
+        })?;
+        Ok(match versions {
+            Versions::Full(full) => versions::CompactVersionVector::Full {
+                entries: full.entries,
+            },
+            Versions::Override(value) => versions::CompactVersionVector::Override {
+                group_version: value.group_version,
+                override_position: value.override_position,
+                override_version: value.override_version,
+            },
+            Versions::Synced(value) => versions::CompactVersionVector::Synced {
+                group_version: value.group_version,
+            },
+            Versions::MultiOverride(value) => versions::CompactVersionVector::MultiOverride {
+                group_version: value.group_version,
+                override_positions: value.override_positions,
+                override_versions: value.override_versions,
+            },
+            Versions::Sparse(value) => versions::CompactVersionVector::Sparse {
+                base_version: value.base_version,
+                positions: value.positions,
+                versions: value.versions,
+            },
+        })
+    }
+}
+
+impl From<versions::CompactVersionVector> for versions_proto::CompactVersionVector {
+    fn from(value: versions::CompactVersionVector) -> Self {
+        use versions_proto::compact_version_vector::Versions;
+
+        let versions = match value {
+            versions::CompactVersionVector::Full { entries } => {
+                Versions::Full(Box::new(versions_proto::FullVersionVector {
+                    entries,
+                    ..versions_proto::FullVersionVector::default()
+                }))
+            }
+            versions::CompactVersionVector::Override {
+                group_version,
+                override_position,
+                override_version,
+            } => Versions::Override(Box::new(versions_proto::OverrideVersionVector {
+                group_version,
+                override_position,
+                override_version,
+                ..versions_proto::OverrideVersionVector::default()
+            })),
+            versions::CompactVersionVector::Synced { group_version } => {
+                Versions::Synced(Box::new(versions_proto::SyncedVersionVector {
+                    group_version,
+                    ..versions_proto::SyncedVersionVector::default()
+                }))
+            }
+            versions::CompactVersionVector::MultiOverride {
+                group_version,
+                override_positions,
+                override_versions,
+            } => Versions::MultiOverride(Box::new(versions_proto::MultiOverrideVersionVector {
+                group_version,
+                override_positions,
+                override_versions,
+                ..versions_proto::MultiOverrideVersionVector::default()
+            })),
+            versions::CompactVersionVector::Sparse {
+                base_version,
+                positions,
+                versions,
+            } => Versions::Sparse(Box::new(versions_proto::SparseVersionVector {
+                base_version,
+                positions,
+                versions,
+                ..versions_proto::SparseVersionVector::default()
+            })),
+        };
+        Self {
+            versions: Some(versions),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::HistoryId {
+    type Cbor = datamodel::HistoryId;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::HistoryId {
+            version: self.version,
+            node_index: self.node_index,
+            chunk_index: self.chunk_index,
+        })
+    }
+}
+
+impl From<datamodel::HistoryId> for datamodel_proto::HistoryId {
+    fn from(value: datamodel::HistoryId) -> Self {
+        Self {
+            version: value.version,
+            node_index: value.node_index,
+            chunk_index: value.chunk_index,
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::Date {
+    type Cbor = datamodel::Date;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::Date {
+            year: self.year,
+            month: self.month,
+            day: self.day,
+        })
+    }
+}
+
+impl From<datamodel::Date> for datamodel_proto::Date {
+    fn from(value: datamodel::Date) -> Self {
+        Self {
+            year: value.year,
+            month: value.month,
+            day: value.day,
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::DocRef {
+    type Cbor = datamodel::DocRef;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::DocRef {
+            document_id: ByteString(self.document_id),
+            anchor: optional_into_cbor(self.anchor)?,
+        })
+    }
+}
+
+impl From<datamodel::DocRef> for datamodel_proto::DocRef {
+    fn from(value: datamodel::DocRef) -> Self {
+        Self {
+            document_id: value.document_id.into_vec(),
+            anchor: optional_from_cbor(value.anchor),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::PrimitiveValue {
+    type Cbor = datamodel::PrimitiveValue;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::primitive_value::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "PrimitiveValue.value",
+        })?;
+        Ok(match value {
+            Value::String(value) => datamodel::PrimitiveValue::String(value),
+            Value::Uint(value) => datamodel::PrimitiveValue::Uint(value),
+            Value::Int(value) => datamodel::PrimitiveValue::Int(value),
+            Value::Byte(value) => datamodel::PrimitiveValue::Byte(value),
+            Value::Float(value) => datamodel::PrimitiveValue::Float(value),
+            Value::Boolean(value) => datamodel::PrimitiveValue::Boolean(value),
+            Value::Binary(value) => datamodel::PrimitiveValue::Binary(ByteString(value)),
+            Value::Date(value) => datamodel::PrimitiveValue::Date((*value).into_cbor()?),
+            Value::Timestamp(value) => datamodel::PrimitiveValue::Timestamp(value),
+            Value::DocRef(value) => datamodel::PrimitiveValue::DocRef((*value).into_cbor()?),
+        })
+    }
+}
+
+impl From<datamodel::PrimitiveValue> for datamodel_proto::PrimitiveValue {
+    fn from(value: datamodel::PrimitiveValue) -> Self {
+        use datamodel_proto::primitive_value::Value;
+
+        let value = match value {
+            datamodel::PrimitiveValue::String(value) => Value::String(value),
+            datamodel::PrimitiveValue::Uint(value) => Value::Uint(value),
+            datamodel::PrimitiveValue::Int(value) => Value::Int(value),
+            datamodel::PrimitiveValue::Byte(value) => Value::Byte(value),
+            datamodel::PrimitiveValue::Float(value) => Value::Float(value),
+            datamodel::PrimitiveValue::Boolean(value) => Value::Boolean(value),
+            datamodel::PrimitiveValue::Binary(value) => Value::Binary(value.into_vec()),
+            datamodel::PrimitiveValue::Date(value) => Value::Date(Box::new(value.into())),
+            datamodel::PrimitiveValue::Timestamp(value) => Value::Timestamp(value),
+            datamodel::PrimitiveValue::DocRef(value) => Value::DocRef(Box::new(value.into())),
+        };
+        Self {
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::PrimitiveArrayValue {
+    type Cbor = datamodel::PrimitiveArrayValue;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::primitive_array_value::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "PrimitiveArrayValue.value",
+        })?;
+        Ok(match value {
+            Value::String(array) => datamodel::PrimitiveArrayValue::String(array.values),
+            Value::Uint(array) => datamodel::PrimitiveArrayValue::Uint(array.values),
+            Value::Int(array) => datamodel::PrimitiveArrayValue::Int(array.values),
+            Value::Byte(array) => datamodel::PrimitiveArrayValue::Byte(ByteString(array.values)),
+            Value::Float(array) => datamodel::PrimitiveArrayValue::Float(array.values),
+            Value::Boolean(array) => datamodel::PrimitiveArrayValue::Boolean(array.values),
+            Value::Binary(array) => datamodel::PrimitiveArrayValue::Binary(
+                array.values.into_iter().map(ByteString).collect(),
+            ),
+            Value::Date(array) => {
+                datamodel::PrimitiveArrayValue::Date(all_into_cbor(array.values)?)
+            }
+            Value::Timestamp(array) => datamodel::PrimitiveArrayValue::Timestamp(array.values),
+            Value::DocRef(array) => {
+                datamodel::PrimitiveArrayValue::DocRef(all_into_cbor(array.values)?)
+            }
+        })
+    }
+}
+
+impl From<datamodel::PrimitiveArrayValue> for datamodel_proto::PrimitiveArrayValue {
+    fn from(value: datamodel::PrimitiveArrayValue) -> Self {
+        use datamodel_proto::primitive_array_value::Value;
+
+        let value = match value {
+            datamodel::PrimitiveArrayValue::String(values) => {
+                Value::String(Box::new(datamodel_proto::StringArrayValue {
+                    values,
+                    ..datamodel_proto::StringArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::Uint(values) => {
+                Value::Uint(Box::new(datamodel_proto::UIntArrayValue {
+                    values,
+                    ..datamodel_proto::UIntArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::Int(values) => {
+                Value::Int(Box::new(datamodel_proto::IntArrayValue {
+                    values,
+                    ..datamodel_proto::IntArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::Byte(values) => {
+                Value::Byte(Box::new(datamodel_proto::ByteArrayValue {
+                    values: values.into_vec(),
+                    ..datamodel_proto::ByteArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::Float(values) => {
+                Value::Float(Box::new(datamodel_proto::FloatArrayValue {
+                    values,
+                    ..datamodel_proto::FloatArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::Boolean(values) => {
+                Value::Boolean(Box::new(datamodel_proto::BooleanArrayValue {
+                    values,
+                    ..datamodel_proto::BooleanArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::Binary(values) => {
+                Value::Binary(Box::new(datamodel_proto::BinaryArrayValue {
+                    values: all_from_cbor(values),
+                    ..datamodel_proto::BinaryArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::Date(values) => {
+                Value::Date(Box::new(datamodel_proto::DateArrayValue {
+                    values: all_from_cbor(values),
+                    ..datamodel_proto::DateArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::Timestamp(values) => {
+                Value::Timestamp(Box::new(datamodel_proto::TimestampArrayValue {
+                    values,
+                    ..datamodel_proto::TimestampArrayValue::default()
+                }))
+            }
+            datamodel::PrimitiveArrayValue::DocRef(values) => {
+                Value::DocRef(Box::new(datamodel_proto::DocRefArrayValue {
+                    values: all_from_cbor(values),
+                    ..datamodel_proto::DocRefArrayValue::default()
+                }))
+            }
+        };
+        Self {
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::NullableBasicValue {
+    type Cbor = datamodel::NullableBasicValue;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::nullable_basic_value::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "NullableBasicValue.value",
+        })?;
+        Ok(match value {
+            Value::Null(_) => datamodel::NullableBasicValue::Null,
+            Value::Primitive(value) => {
+                datamodel::NullableBasicValue::Primitive((*value).into_cbor()?)
+            }
+            Value::Array(value) => datamodel::NullableBasicValue::Array((*value).into_cbor()?),
+        })
+    }
+}
+
+impl From<datamodel::NullableBasicValue> for datamodel_proto::NullableBasicValue {
+    fn from(value: datamodel::NullableBasicValue) -> Self {
+        use datamodel_proto::nullable_basic_value::Value;
+
+        let value = match value {
+            datamodel::NullableBasicValue::Null => Value::Null(Box::default()),
+            datamodel::NullableBasicValue::Primitive(value) => {
+                Value::Primitive(Box::new(value.into()))
+            }
+            datamodel::NullableBasicValue::Array(value) => Value::Array(Box::new(value.into())),
+        };
+        Self {
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::NullablePrimitiveValue {
+    type Cbor = datamodel::NullablePrimitiveValue;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::nullable_primitive_value::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "NullablePrimitiveValue.value",
+        })?;
+        Ok(match value {
+            Value::Null(_) => datamodel::NullablePrimitiveValue::Null,
+            Value::Primitive(value) => {
+                datamodel::NullablePrimitiveValue::Primitive((*value).into_cbor()?)
+            }
+        })
+    }
+}
+
+impl From<datamodel::NullablePrimitiveValue> for datamodel_proto::NullablePrimitiveValue {
+    fn from(value: datamodel::NullablePrimitiveValue) -> Self {
+        use datamodel_proto::nullable_primitive_value::Value;
+
+        let value = match value {
+            datamodel::NullablePrimitiveValue::Null => Value::Null(Box::default()),
+            datamodel::NullablePrimitiveValue::Primitive(value) => {
+                Value::Primitive(Box::new(value.into()))
+            }
+        };
+        Self {
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::CounterValue {
+    type Cbor = datamodel::CounterValue;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::counter_value::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "CounterValue.value",
+        })?;
+        Ok(match value {
+            Value::Byte(value) => datamodel::CounterValue::Byte(value),
+            Value::Uint(value) => datamodel::CounterValue::Uint(value),
+        })
+    }
+}
+
+impl From<datamodel::CounterValue> for datamodel_proto::CounterValue {
+    fn from(value: datamodel::CounterValue) -> Self {
+        use datamodel_proto::counter_value::Value;
+
+        let value = match value {
+            datamodel::CounterValue::Byte(value) => Value::Byte(value),
+            datamodel::CounterValue::Uint(value) => Value::Uint(value),
+        };
+        Self {
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::HistoryNodeMeta {
+    type Cbor = datamodel::HistoryNodeMeta;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::HistoryNodeMeta {
+            version: self.version,
+            node_index: self.node_index,
+            chunk_index: self.chunk_index,
+            origin_left_version: self.origin_left_version,
+            origin_left_node_index: self.origin_left_node_index,
+            origin_left_chunk_index: self.origin_left_chunk_index,
+            origin_right_version: self.origin_right_version,
+            origin_right_node_index: self.origin_right_node_index,
+            origin_right_chunk_index: self.origin_right_chunk_index,
+            deleted: self.deleted,
+            value_len: self.value_len,
+            value_is_null: self.value_is_null,
+        })
+    }
+}
+
+impl From<datamodel::HistoryNodeMeta> for datamodel_proto::HistoryNodeMeta {
+    fn from(value: datamodel::HistoryNodeMeta) -> Self {
+        Self {
+            version: value.version,
+            node_index: value.node_index,
+            chunk_index: value.chunk_index,
+            origin_left_version: value.origin_left_version,
+            origin_left_node_index: value.origin_left_node_index,
+            origin_left_chunk_index: value.origin_left_chunk_index,
+            origin_right_version: value.origin_right_version,
+            origin_right_node_index: value.origin_right_node_index,
+            origin_right_chunk_index: value.origin_right_chunk_index,
+            deleted: value.deleted,
+            value_len: value.value_len,
+            value_is_null: value.value_is_null,
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::HistorySnapshot {
+    type Cbor = datamodel::HistorySnapshot;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::history_snapshot::Values;
+
+        let values = match self.values {
+            Some(Values::PrimitiveValues(values)) => Some(
+                datamodel::HistoryValues::PrimitiveValues((*values).into_cbor()?),
+            ),
+            Some(Values::StringValues(values)) => {
+                Some(datamodel::HistoryValues::StringValues(values))
+            }
+            None => None,
+        };
+        Ok(datamodel::HistorySnapshot {
+            nodes: all_into_cbor(self.nodes)?,
+            values,
+        })
+    }
+}
+
+impl From<datamodel::HistorySnapshot> for datamodel_proto::HistorySnapshot {
+    fn from(value: datamodel::HistorySnapshot) -> Self {
+        use datamodel_proto::history_snapshot::Values;
+
+        let values = value.values.map(|values| match values {
+            datamodel::HistoryValues::PrimitiveValues(values) => {
+                Values::PrimitiveValues(Box::new(values.into()))
+            }
+            datamodel::HistoryValues::StringValues(values) => Values::StringValues(values),
+        });
+        Self {
+            nodes: all_from_cbor(value.nodes),
+            values,
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::SnapshotField {
+    type Cbor = datamodel::SnapshotField;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::snapshot_field::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "SnapshotField.value",
+        })?;
+        let value = match value {
+            Value::LatestValueWins(history) => {
+                datamodel::SnapshotFieldValue::LatestValueWins((*history).into_cbor()?)
+            }
+            Value::LinearString(history) => {
+                datamodel::SnapshotFieldValue::LinearString((*history).into_cbor()?)
+            }
+            Value::LinearList(history) => {
+                datamodel::SnapshotFieldValue::LinearList((*history).into_cbor()?)
+            }
+            Value::MonotonicCounter(value) => {
+                datamodel::SnapshotFieldValue::MonotonicCounter((*value).into_cbor()?)
+            }
+            Value::TotalOrderRegister(value) => {
+                datamodel::SnapshotFieldValue::TotalOrderRegister((*value).into_cbor()?)
+            }
+            Value::TotalOrderFiniteStateRegister(value) => {
+                datamodel::SnapshotFieldValue::TotalOrderFiniteStateRegister((*value).into_cbor()?)
+            }
+        };
+        Ok(datamodel::SnapshotField {
+            field_name: self.field_name,
+            value,
+        })
+    }
+}
+
+impl From<datamodel::SnapshotField> for datamodel_proto::SnapshotField {
+    fn from(field: datamodel::SnapshotField) -> Self {
+        use datamodel_proto::snapshot_field::Value;
+
+        let value = match field.value {
+            datamodel::SnapshotFieldValue::LatestValueWins(history) => {
+                Value::LatestValueWins(Box::new(history.into()))
+            }
+            datamodel::SnapshotFieldValue::LinearString(history) => {
+                Value::LinearString(Box::new(history.into()))
+            }
+            datamodel::SnapshotFieldValue::LinearList(history) => {
+                Value::LinearList(Box::new(history.into()))
+            }
+            datamodel::SnapshotFieldValue::MonotonicCounter(value) => {
+                Value::MonotonicCounter(Box::new(value.into()))
+            }
+            datamodel::SnapshotFieldValue::TotalOrderRegister(value) => {
+                Value::TotalOrderRegister(Box::new(value.into()))
+            }
+            datamodel::SnapshotFieldValue::TotalOrderFiniteStateRegister(value) => {
+                Value::TotalOrderFiniteStateRegister(Box::new(value.into()))
+            }
+        };
+        Self {
+            field_name: field.field_name,
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::RowSnapshot {
+    type Cbor = datamodel::RowSnapshot;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::RowSnapshot {
+            fields: all_into_cbor(self.fields)?,
+        })
+    }
+}
+
+impl From<datamodel::RowSnapshot> for datamodel_proto::RowSnapshot {
+    fn from(value: datamodel::RowSnapshot) -> Self {
+        Self {
+            fields: all_from_cbor(value.fields),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::DataSnapshot {
+    type Cbor = datamodel::DataSnapshot;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::DataSnapshot {
+            rows: all_into_cbor(self.rows)?,
+        })
+    }
+}
+
+impl From<datamodel::DataSnapshot> for datamodel_proto::DataSnapshot {
+    fn from(value: datamodel::DataSnapshot) -> Self {
+        Self {
+            rows: all_from_cbor(value.rows),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::LatestValueWinsOperation {
+    type Cbor = datamodel::LatestValueWinsOperation;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::LatestValueWinsOperation {
+            id: optional_into_cbor(self.id)?,
+            pred: optional_into_cbor(self.pred)?,
+            succ: optional_into_cbor(self.succ)?,
+            value: optional_into_cbor(self.value)?,
+        })
+    }
+}
+
+impl From<datamodel::LatestValueWinsOperation> for datamodel_proto::LatestValueWinsOperation {
+    fn from(operation: datamodel::LatestValueWinsOperation) -> Self {
+        Self {
+            id: optional_from_cbor(operation.id),
+            pred: optional_from_cbor(operation.pred),
+            succ: optional_from_cbor(operation.succ),
+            value: optional_from_cbor(operation.value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::LinearDeleteOperation {
+    type Cbor = datamodel::LinearDeleteOperation;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::LinearDeleteOperation {
+            start: optional_into_cbor(self.start)?,
+            end_chunk_index: self.end_chunk_index,
+        })
+    }
+}
+
+impl From<datamodel::LinearDeleteOperation> for datamodel_proto::LinearDeleteOperation {
+    fn from(operation: datamodel::LinearDeleteOperation) -> Self {
+        Self {
+            start: optional_from_cbor(operation.start),
+            end_chunk_index: operation.end_chunk_index,
+            ..Self::default()
+        }
+    }
+}
+
+fn delete_ranges_from_cbor(
+    ranges: Vec<datamodel::LinearDeleteOperation>,
+) -> Box<datamodel_proto::LinearDeleteRangesOperation> {
+    Box::new(datamodel_proto::LinearDeleteRangesOperation {
+        ranges: all_from_cbor(ranges),
+        ..datamodel_proto::LinearDeleteRangesOperation::default()
+    })
+}
+
+impl IntoCbor for datamodel_proto::LinearStringInsertOperation {
+    type Cbor = datamodel::LinearStringInsertOperation;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::LinearStringInsertOperation {
+            id: optional_into_cbor(self.id)?,
+            pred: optional_into_cbor(self.pred)?,
+            succ: optional_into_cbor(self.succ)?,
+            value: self.value,
+        })
+    }
+}
+
+impl From<datamodel::LinearStringInsertOperation> for datamodel_proto::LinearStringInsertOperation {
+    fn from(operation: datamodel::LinearStringInsertOperation) -> Self {
+        Self {
+            id: optional_from_cbor(operation.id),
+            pred: optional_from_cbor(operation.pred),
+            succ: optional_from_cbor(operation.succ),
+            value: operation.value,
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::LinearStringAction {
+    type Cbor = datamodel::LinearStringAction;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::linear_string_action::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "LinearStringAction.value",
+        })?;
+        Ok(match value {
+            Value::Insert(insert) => datamodel::LinearStringAction::Insert((*insert).into_cbor()?),
+            Value::Delete(delete) => datamodel::LinearStringAction::Delete((*delete).into_cbor()?),
+            Value::DeleteRanges(delete) => {
+                datamodel::LinearStringAction::DeleteRanges(all_into_cbor(delete.ranges)?)
+            }
+        })
+    }
+}
+
+impl From<datamodel::LinearStringAction> for datamodel_proto::LinearStringAction {
+    fn from(action: datamodel::LinearStringAction) -> Self {
+        use datamodel_proto::linear_string_action::Value;
+
+        let value = match action {
+            datamodel::LinearStringAction::Insert(insert) => Value::Insert(Box::new(insert.into())),
+            datamodel::LinearStringAction::Delete(delete) => Value::Delete(Box::new(delete.into())),
+            datamodel::LinearStringAction::DeleteRanges(ranges) => {
+                Value::DeleteRanges(delete_ranges_from_cbor(ranges))
+            }
+        };
+        Self {
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::LinearListInsertOperation {
+    type Cbor = datamodel::LinearListInsertOperation;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(datamodel::LinearListInsertOperation {
+            id: optional_into_cbor(self.id)?,
+            pred: optional_into_cbor(self.pred)?,
+            succ: optional_into_cbor(self.succ)?,
+            value: optional_into_cbor(self.value)?,
+        })
+    }
+}
+
+impl From<datamodel::LinearListInsertOperation> for datamodel_proto::LinearListInsertOperation {
+    fn from(operation: datamodel::LinearListInsertOperation) -> Self {
+        Self {
+            id: optional_from_cbor(operation.id),
+            pred: optional_from_cbor(operation.pred),
+            succ: optional_from_cbor(operation.succ),
+            value: optional_from_cbor(operation.value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::LinearListAction {
+    type Cbor = datamodel::LinearListAction;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::linear_list_action::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "LinearListAction.value",
+        })?;
+        Ok(match value {
+            Value::Insert(insert) => datamodel::LinearListAction::Insert((*insert).into_cbor()?),
+            Value::Delete(delete) => datamodel::LinearListAction::Delete((*delete).into_cbor()?),
+            Value::DeleteRanges(delete) => {
+                datamodel::LinearListAction::DeleteRanges(all_into_cbor(delete.ranges)?)
+            }
+        })
+    }
+}
+
+impl From<datamodel::LinearListAction> for datamodel_proto::LinearListAction {
+    fn from(action: datamodel::LinearListAction) -> Self {
+        use datamodel_proto::linear_list_action::Value;
+
+        let value = match action {
+            datamodel::LinearListAction::Insert(insert) => Value::Insert(Box::new(insert.into())),
+            datamodel::LinearListAction::Delete(delete) => Value::Delete(Box::new(delete.into())),
+            datamodel::LinearListAction::DeleteRanges(ranges) => {
+                Value::DeleteRanges(delete_ranges_from_cbor(ranges))
+            }
+        };
+        Self {
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::OperationField {
+    type Cbor = datamodel::OperationField;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::operation_field::Value;
+
+        let value = self.value.context(MissingOneofSnafu {
+            name: "OperationField.value",
+        })?;
+        let operation = match value {
+            Value::LatestValueWins(operation) => {
+                datamodel::FieldOperation::LatestValueWins((*operation).into_cbor()?)
+            }
+            Value::LinearString(operation) => {
+                datamodel::FieldOperation::LinearString(all_into_cbor(operation.actions)?)
+            }
+            Value::LinearList(operation) => {
+                datamodel::FieldOperation::LinearList(all_into_cbor(operation.actions)?)
+            }
+            Value::MonotonicCounterIncrement(operation) => {
+                datamodel::FieldOperation::MonotonicCounterIncrement(optional_into_cbor(
+                    operation.value,
+                )?)
+            }
+            Value::TotalOrderRegisterSet(operation) => {
+                datamodel::FieldOperation::TotalOrderRegisterSet(optional_into_cbor(
+                    operation.value,
+                )?)
+            }
+            Value::TotalOrderFiniteStateRegisterSet(operation) => {
+                datamodel::FieldOperation::TotalOrderFiniteStateRegisterSet(optional_into_cbor(
+                    operation.value,
+                )?)
+            }
+        };
+        Ok(datamodel::OperationField {
+            field_name: self.field_name,
+            operation,
+        })
+    }
+}
+
+impl From<datamodel::OperationField> for datamodel_proto::OperationField {
+    fn from(field: datamodel::OperationField) -> Self {
+        use datamodel_proto::operation_field::Value;
+
+        let value = match field.operation {
+            datamodel::FieldOperation::LatestValueWins(operation) => {
+                Value::LatestValueWins(Box::new(operation.into()))
+            }
+            datamodel::FieldOperation::LinearString(actions) => {
+                Value::LinearString(Box::new(datamodel_proto::LinearStringOperation {
+                    actions: all_from_cbor(actions),
+                    ..datamodel_proto::LinearStringOperation::default()
+                }))
+            }
+            datamodel::FieldOperation::LinearList(actions) => {
+                Value::LinearList(Box::new(datamodel_proto::LinearListOperation {
+                    actions: all_from_cbor(actions),
+                    ..datamodel_proto::LinearListOperation::default()
+                }))
+            }
+            datamodel::FieldOperation::MonotonicCounterIncrement(value) => {
+                Value::MonotonicCounterIncrement(Box::new(
+                    datamodel_proto::MonotonicCounterIncrementOperation {
+                        value: optional_from_cbor(value),
+                        ..datamodel_proto::MonotonicCounterIncrementOperation::default()
+                    },
+                ))
+            }
+            datamodel::FieldOperation::TotalOrderRegisterSet(value) => {
+                Value::TotalOrderRegisterSet(Box::new(
+                    datamodel_proto::TotalOrderRegisterSetOperation {
+                        value: optional_from_cbor(value),
+                        ..datamodel_proto::TotalOrderRegisterSetOperation::default()
+                    },
+                ))
+            }
+            datamodel::FieldOperation::TotalOrderFiniteStateRegisterSet(value) => {
+                Value::TotalOrderFiniteStateRegisterSet(Box::new(
+                    datamodel_proto::TotalOrderFiniteStateRegisterSetOperation {
+                        value: optional_from_cbor(value),
+                        ..datamodel_proto::TotalOrderFiniteStateRegisterSetOperation::default()
+                    },
+                ))
+            }
+        };
+        Self {
+            field_name: field.field_name,
+            value: Some(value),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for datamodel_proto::SchemaOperation {
+    type Cbor = datamodel::SchemaOperation;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use datamodel_proto::schema_operation::Operation;
+
+        let operation = self.operation.context(MissingOneofSnafu {
+            name: "SchemaOperation.operation",
+        })?;
+        let operation = match operation {
+            Operation::Insert(insert) => datamodel::RowOperation::Insert {
+                row_id: ByteString(insert.row_id),
+                snapshot: optional_into_cbor(insert.snapshot)?,
+            },
+            Operation::Update(update) => datamodel::RowOperation::Update {
+                row_id: ByteString(update.row_id),
+                fields: all_into_cbor(update.fields)?,
+            },
+            Operation::Delete(delete) => datamodel::RowOperation::Delete {
+                row_id: ByteString(delete.row_id),
+            },
+        };
+        Ok(datamodel::SchemaOperation {
+            change_id: optional_into_cbor(self.change_id)?,
+            operation,
+        })
+    }
+}
+
+impl From<datamodel::SchemaOperation> for datamodel_proto::SchemaOperation {
+    fn from(operation: datamodel::SchemaOperation) -> Self {
+        use datamodel_proto::schema_operation::Operation;
+
+        let row_operation = match operation.operation {
+            datamodel::RowOperation::Insert { row_id, snapshot } => {
+                Operation::Insert(Box::new(datamodel_proto::InsertRowOperation {
+                    row_id: row_id.into_vec(),
+                    snapshot: optional_from_cbor(snapshot),
+                    ..datamodel_proto::InsertRowOperation::default()
+                }))
+            }
+            datamodel::RowOperation::Update { row_id, fields } => {
+                Operation::Update(Box::new(datamodel_proto::UpdateRowOperation {
+                    row_id: row_id.into_vec(),
+                    fields: all_from_cbor(fields),
+                    ..datamodel_proto::UpdateRowOperation::default()
+                }))
+            }
+            datamodel::RowOperation::Delete { row_id } => {
+                Operation::Delete(Box::new(datamodel_proto::DeleteRowOperation {
+                    row_id: row_id.into_vec(),
+                    ..datamodel_proto::DeleteRowOperation::default()
+                }))
+            }
+        };
+        Self {
+            change_id: optional_from_cbor(operation.change_id),
+            operation: Some(row_operation),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for replication_proto::DatasetUpdate {
+    type Cbor = replication::DatasetUpdate;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(replication::DatasetUpdate {
+            dataset_id: self.dataset_id,
+            operations: all_into_cbor(self.operations)?,
+        })
+    }
+}
+
+impl From<replication::DatasetUpdate> for replication_proto::DatasetUpdate {
+    fn from(update: replication::DatasetUpdate) -> Self {
+        Self {
+            dataset_id: update.dataset_id,
+            operations: all_from_cbor(update.operations),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for replication_proto::Update {
+    type Cbor = replication::Update;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(replication::Update {
+            group_id: ByteString(self.group_id),
+            update_id: optional_into_cbor(self.update_id)?,
+            read_versions: optional_into_cbor(self.read_versions)?,
+            dataset_updates: all_into_cbor(self.dataset_updates)?,
+        })
+    }
+}
+
+impl From<replication::Update> for replication_proto::Update {
+    fn from(update: replication::Update) -> Self {
+        Self {
+            group_id: update.group_id.into_vec(),
+            update_id: optional_from_cbor(update.update_id),
+            read_versions: optional_from_cbor(update.read_versions),
+            dataset_updates: all_from_cbor(update.dataset_updates),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for replication_proto::UpdateBatch {
+    type Cbor = replication::UpdateBatch;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        Ok(replication::UpdateBatch {
+            group_id: ByteString(self.group_id),
+            updates: all_into_cbor(self.updates)?,
+        })
+    }
+}
+
+impl From<replication::UpdateBatch> for replication_proto::UpdateBatch {
+    fn from(batch: replication::UpdateBatch) -> Self {
+        Self {
+            group_id: batch.group_id.into_vec(),
+            updates: all_from_cbor(batch.updates),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoCbor for replication_proto::RuntimeMessage {
+    type Cbor = replication::RuntimeMessage;
+
+    fn into_cbor(self) -> ConversionResult<Self::Cbor> {
+        use replication_proto::runtime_message::Body;
+
+        let body = self.body.context(MissingOneofSnafu {
+            name: "RuntimeMessage.body",
+        })?;
+        let message = runtime_body_name(&body);
+        match body {
+            Body::Update(update) => Ok(replication::RuntimeMessage::Update((*update).into_cbor()?)),
+            Body::UpdateBatch(batch) => Ok(replication::RuntimeMessage::UpdateBatch(
+                (*batch).into_cbor()?,
+            )),
+            _ => NoCborFormSnafu { message }.fail(),
+        }
+    }
+}
+
+impl From<replication::RuntimeMessage> for replication_proto::RuntimeMessage {
+    fn from(message: replication::RuntimeMessage) -> Self {
+        use replication_proto::runtime_message::Body;
+
+        let body = match message {
+            replication::RuntimeMessage::Update(update) => Body::Update(Box::new(update.into())),
+            replication::RuntimeMessage::UpdateBatch(batch) => {
+                Body::UpdateBatch(Box::new(batch.into()))
+            }
+        };
+        Self {
+            body: Some(body),
+            ..Self::default()
+        }
+    }
+}
+
+fn runtime_body_name(body: &replication_proto::runtime_message::Body) -> &'static str {
+    use replication_proto::runtime_message::Body;
+
+    match body {
+        Body::Update(_) => "update",
+        Body::SummaryRequest(_) => "summary_request",
+        Body::Summary(_) => "summary",
+        Body::NeedRange(_) => "need_range",
+        Body::UpdateBatch(_) => "update_batch",
+        Body::GroupInvitation(_) => "group_invitation",
+        Body::MigrationProposal(_) => "migration_proposal",
+        Body::UpdateAck(_) => "update_ack",
+        Body::FrontierAck(_) => "frontier_ack",
+        Body::Compressed(_) => "compressed",
+        Body::Encoded(_) => "encoded",
+        Body::Throttled(_) => "throttled",
+        Body::BlobChunkRequest(_) => "blob_chunk_request",
+        Body::BlobChunk(_) => "blob_chunk",
+        Body::BlobUnavailable(_) => "blob_unavailable",
+        Body::SnapshotManifestRequest(_) => "snapshot_manifest_request",
+        Body::SnapshotManifest(_) => "snapshot_manifest",
+        Body::SnapshotChunkRequest(_) => "snapshot_chunk_request",
+        Body::SnapshotChunk(_) => "snapshot_chunk",
+        Body::SnapshotUnavailable(_) => "snapshot_unavailable",
+    }
+}
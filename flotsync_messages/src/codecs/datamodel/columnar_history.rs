@@ -68,7 +68,7 @@ pub enum ColumnarHistoryCodecError {
     ValueBufferTooLarge { len: usize },
 }
 
-type ColumnarResult<T> = Result<T, ColumnarHistoryCodecError>;
+pub(super) type ColumnarResult<T> = Result<T, ColumnarHistoryCodecError>;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum ColumnarHistoryNodeValue {
@@ -117,6 +117,7 @@ impl ColumnarValueBuffer {
                 PrimitiveType::Binary => ModelPrimitiveValueArray::Binary(Vec::new()),
                 PrimitiveType::Date => ModelPrimitiveValueArray::Date(Vec::new()),
                 PrimitiveType::Timestamp => ModelPrimitiveValueArray::Timestamp(Vec::new()),
+                PrimitiveType::DocRef => ModelPrimitiveValueArray::DocRef(Vec::new()),
             };
             ColumnarValueBufferKind::Primitive(primitive_empty_value)
         };
@@ -179,6 +180,9 @@ impl ColumnarValueBuffer {
                 ModelPrimitiveValueArray::Timestamp(values),
                 ModelPrimitiveValueRef::Timestamp(value),
             ) => values.push(value),
+            (ModelPrimitiveValueArray::DocRef(values), ModelPrimitiveValueRef::DocRef(value)) => {
+                values.push(value.clone());
+            }
             (values, value) => {
                 return InvalidValueBufferTypeSnafu {
                     expected: values.primitive_type(),
@@ -237,6 +241,10 @@ impl ColumnarValueBuffer {
                 ModelPrimitiveValueArray::Timestamp(values),
                 ModelPrimitiveValueArrayRef::Timestamp(value),
             ) => values.extend_from_slice(value),
+            (
+                ModelPrimitiveValueArray::DocRef(values),
+                ModelPrimitiveValueArrayRef::DocRef(value),
+            ) => values.extend_from_slice(value),
             (values, value) => {
                 return InvalidValueBufferTypeSnafu {
                     expected: values.primitive_type(),
@@ -487,6 +495,66 @@ pub fn decode_columnar_linear_list_history_snapshot(
         .collect()
 }
 
+pub(super) fn set_self_id_fields(meta: &mut proto::HistoryNodeMeta, id: &proto::HistoryId) {
+    meta.version = id.version;
+    meta.node_index = id.node_index;
+    meta.chunk_index = id.chunk_index;
+}
+
+pub(super) fn set_origin_left_fields(
+    meta: &mut proto::HistoryNodeMeta,
+    id: Option<proto::HistoryId>,
+) {
+    if let Some(id) = id {
+        meta.origin_left_version = Some(id.version);
+        meta.origin_left_node_index = Some(id.node_index);
+        meta.origin_left_chunk_index = Some(id.chunk_index);
+    }
+}
+
+pub(super) fn set_origin_right_fields(
+    meta: &mut proto::HistoryNodeMeta,
+    id: Option<proto::HistoryId>,
+) {
+    if let Some(id) = id {
+        meta.origin_right_version = Some(id.version);
+        meta.origin_right_node_index = Some(id.node_index);
+        meta.origin_right_chunk_index = Some(id.chunk_index);
+    }
+}
+
+pub(super) fn decode_optional_origin_id<Id>(
+    side: &'static str,
+    version: Option<u64>,
+    node_index: Option<u32>,
+    chunk_index: Option<u32>,
+    decode_id: impl Fn(proto::HistoryId) -> Result<Id, CodecError>,
+) -> ColumnarResult<Option<Id>> {
+    match (version, node_index) {
+        (None, None) => {
+            ensure!(
+                chunk_index.is_none(),
+                InvalidNodeMetaSnafu {
+                    reason: format!("{side} chunk_index was set without a matching id"),
+                }
+            );
+            Ok(None)
+        }
+        (Some(version), Some(node_index)) => decode_id(proto::HistoryId {
+            version,
+            node_index,
+            chunk_index: chunk_index.unwrap_or(0),
+            ..proto::HistoryId::default()
+        })
+        .context(CodecSnafu)
+        .map(Some),
+        _ => InvalidNodeMetaSnafu {
+            reason: format!("{side} version/node_index presence did not match"),
+        }
+        .fail(),
+    }
+}
+
 fn encode_columnar_history_snapshot<'a>(
     data_type: &ReplicatedDataType,
     nodes: impl IntoIterator<Item = ColumnarHistoryNodeRecord<'a>>,
@@ -795,60 +863,6 @@ fn history_primitive_type(data_type: &ReplicatedDataType) -> ColumnarResult<Prim
     }
 }
 
-fn set_self_id_fields(meta: &mut proto::HistoryNodeMeta, id: &proto::HistoryId) {
-    meta.version = id.version;
-    meta.node_index = id.node_index;
-    meta.chunk_index = id.chunk_index;
-}
-
-fn set_origin_left_fields(meta: &mut proto::HistoryNodeMeta, id: Option<proto::HistoryId>) {
-    if let Some(id) = id {
-        meta.origin_left_version = Some(id.version);
-        meta.origin_left_node_index = Some(id.node_index);
-        meta.origin_left_chunk_index = Some(id.chunk_index);
-    }
-}
-
-fn set_origin_right_fields(meta: &mut proto::HistoryNodeMeta, id: Option<proto::HistoryId>) {
-    if let Some(id) = id {
-        meta.origin_right_version = Some(id.version);
-        meta.origin_right_node_index = Some(id.node_index);
-        meta.origin_right_chunk_index = Some(id.chunk_index);
-    }
-}
-
-fn decode_optional_origin_id<Id>(
-    side: &'static str,
-    version: Option<u64>,
-    node_index: Option<u32>,
-    chunk_index: Option<u32>,
-    decode_id: impl Fn(proto::HistoryId) -> Result<Id, CodecError>,
-) -> ColumnarResult<Option<Id>> {
-    match (version, node_index) {
-        (None, None) => {
-            ensure!(
-                chunk_index.is_none(),
-                InvalidNodeMetaSnafu {
-                    reason: format!("{side} chunk_index was set without a matching id"),
-                }
-            );
-            Ok(None)
-        }
-        (Some(version), Some(node_index)) => decode_id(proto::HistoryId {
-            version,
-            node_index,
-            chunk_index: chunk_index.unwrap_or(0),
-            ..proto::HistoryId::default()
-        })
-        .context(CodecSnafu)
-        .map(Some),
-        _ => InvalidNodeMetaSnafu {
-            reason: format!("{side} version/node_index presence did not match"),
-        }
-        .fail(),
-    }
-}
-
 fn slice_primitive_array(
     values: &ModelPrimitiveValueArray,
     value_offset: usize,
@@ -898,6 +912,9 @@ fn slice_primitive_array(
         ModelPrimitiveValueArray::Timestamp(values) => {
             ModelPrimitiveValueArray::Timestamp(values[start..end].to_vec())
         }
+        ModelPrimitiveValueArray::DocRef(values) => {
+            ModelPrimitiveValueArray::DocRef(values[start..end].to_vec())
+        }
     };
     Ok(slice)
 }
@@ -972,6 +989,9 @@ fn slice_primitive_value(
             .get(index)
             .copied()
             .map(ModelPrimitiveValue::Timestamp),
+        ModelPrimitiveValueArray::DocRef(values) => {
+            values.get(index).cloned().map(ModelPrimitiveValue::DocRef)
+        }
     };
     value.context(InvalidValueSpanSnafu {
         offset: value_offset,
@@ -995,6 +1015,7 @@ fn array_ref_len(values: &ModelPrimitiveValueArrayRef<'_>) -> usize {
         ModelPrimitiveValueArrayRef::Binary(values) => values.len(),
         ModelPrimitiveValueArrayRef::Date(values) => values.len(),
         ModelPrimitiveValueArrayRef::Timestamp(values) => values.len(),
+        ModelPrimitiveValueArrayRef::DocRef(values) => values.len(),
     }
 }
 
@@ -1132,12 +1153,13 @@ mod tests {
         let first = nodes.first().unwrap();
         let last = nodes.last().unwrap();
 
+        // Boundaries don't record origins, they always sit at the outer ends.
         assert!(first.left.is_none());
-        assert!(first.right.is_some());
+        assert!(first.right.is_none());
         assert!(!first.deleted);
         assert!(first.value.is_none());
 
-        assert!(last.left.is_some());
+        assert!(last.left.is_none());
         assert!(last.right.is_none());
         assert!(!last.deleted);
         assert!(last.value.is_none());
@@ -0,0 +1,297 @@
+//! History snapshots of [`LinearList`]s with application-encoded elements.
+//!
+//! The columnar history format only covers lists of schema primitives. Lists of any other
+//! element type are persisted as [`proto::EncodedListHistorySnapshot`] instead, with the caller
+//! providing the conversion between an element and its bytes.
+use super::{
+    CodecError,
+    UpdateIdWithIndex,
+    columnar_history::{
+        ColumnarHistoryCodecError,
+        ColumnarResult,
+        decode_optional_origin_id,
+        set_origin_left_fields,
+        set_origin_right_fields,
+        set_self_id_fields,
+    },
+    decode_indexed_update_id,
+    encode_indexed_update_id,
+};
+use crate::datamodel as proto;
+use flotsync_core::versions::UpdateId;
+use flotsync_data_types::{
+    any_data::list::LinearList,
+    snapshot::{SnapshotHeader, SnapshotNode, SnapshotNodeRef, SnapshotReadError, SnapshotSink},
+};
+use snafu::prelude::*;
+use std::fmt;
+
+/// Errors decoding an encoded list history snapshot.
+#[derive(Debug, Snafu)]
+pub enum EncodedListHistoryDecodeError<E>
+where
+    E: snafu::Error + Send + Sync + 'static,
+{
+    #[snafu(display("History snapshot node {index} has invalid ids."))]
+    NodeIds {
+        index: usize,
+        source: ColumnarHistoryCodecError,
+    },
+    #[snafu(display("History snapshot node {index} is invalid: {reason}"))]
+    InvalidNode { index: usize, reason: String },
+    #[snafu(display("List element {position} could not be decoded."))]
+    Element { position: usize, source: E },
+    #[snafu(display("History snapshot consumed {consumed} of its {available} elements."))]
+    UnusedElements { consumed: usize, available: usize },
+}
+
+/// Encode the history of `list` with `encode_element` providing the bytes of each element.
+///
+/// # Errors
+///
+/// See `ColumnarHistoryCodecError` for failure conditions.
+pub fn encode_linear_list_history_snapshot_with<T>(
+    list: &LinearList<UpdateId, T>,
+    encode_element: impl FnMut(&T) -> Vec<u8>,
+) -> ColumnarResult<proto::EncodedListHistorySnapshot>
+where
+    T: fmt::Debug + 'static,
+{
+    let mut sink = EncodedListSink {
+        encode_element,
+        snapshot: proto::EncodedListHistorySnapshot::default(),
+    };
+    list.encode_snapshot(&mut sink)?;
+    Ok(sink.snapshot)
+}
+
+/// Rebuild a [`LinearList`] from `snapshot`, with `decode_element` turning the bytes of each
+/// element back into a value.
+///
+/// # Errors
+///
+/// See `SnapshotReadError<EncodedListHistoryDecodeError<E>>` for failure conditions.
+pub fn decode_linear_list_history_snapshot_with<T, E>(
+    snapshot: proto::EncodedListHistorySnapshot,
+    mut decode_element: impl FnMut(&[u8]) -> Result<T, E>,
+) -> Result<LinearList<UpdateId, T>, SnapshotReadError<EncodedListHistoryDecodeError<E>>>
+where
+    T: fmt::Debug + 'static,
+    E: snafu::Error + Send + Sync + 'static,
+{
+    let available = snapshot.elements.len();
+    let mut elements = snapshot.elements.into_iter().enumerate();
+    let nodes =
+        snapshot.nodes.into_iter().enumerate().map(|(index, meta)| {
+            decode_list_node(index, &meta, &mut elements, &mut decode_element)
+        });
+    let list = LinearList::from_snapshot_nodes(nodes)?;
+
+    let unused = elements.len();
+    if unused != 0 {
+        return Err(SnapshotReadError::from_source(
+            EncodedListHistoryDecodeError::UnusedElements {
+                consumed: available - unused,
+                available,
+            },
+        ));
+    }
+    Ok(list)
+}
+
+/// Snapshot sink that writes list nodes into an [`proto::EncodedListHistorySnapshot`].
+struct EncodedListSink<F> {
+    encode_element: F,
+    snapshot: proto::EncodedListHistorySnapshot,
+}
+impl<T, F> SnapshotSink<UpdateIdWithIndex, [T]> for EncodedListSink<F>
+where
+    F: FnMut(&T) -> Vec<u8>,
+{
+    type Error = ColumnarHistoryCodecError;
+
+    fn begin(&mut self, header: SnapshotHeader) -> Result<(), Self::Error> {
+        self.snapshot.nodes.reserve(header.node_count);
+        Ok(())
+    }
+
+    fn node(
+        &mut self,
+        _index: usize,
+        node: SnapshotNodeRef<'_, UpdateIdWithIndex, [T]>,
+    ) -> Result<(), Self::Error> {
+        let values = node.value.unwrap_or_default();
+        let mut meta = proto::HistoryNodeMeta::default();
+        set_self_id_fields(&mut meta, &encode_indexed_update_id(node.id));
+        set_origin_left_fields(&mut meta, node.left.map(encode_indexed_update_id));
+        set_origin_right_fields(&mut meta, node.right.map(encode_indexed_update_id));
+        meta.deleted = node.deleted;
+        meta.value_len = u32::try_from(values.len())
+            .map_err(|_| ColumnarHistoryCodecError::ValueBufferTooLarge { len: values.len() })?;
+        self.snapshot.nodes.push(meta);
+        self.snapshot
+            .elements
+            .extend(values.iter().map(&mut self.encode_element));
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+fn decode_list_node<T, E>(
+    index: usize,
+    meta: &proto::HistoryNodeMeta,
+    elements: &mut impl Iterator<Item = (usize, Vec<u8>)>,
+    decode_element: &mut impl FnMut(&[u8]) -> Result<T, E>,
+) -> Result<SnapshotNode<UpdateIdWithIndex, Vec<T>>, EncodedListHistoryDecodeError<E>>
+where
+    E: snafu::Error + Send + Sync + 'static,
+{
+    let id = decode_indexed_update_id(proto::HistoryId {
+        version: meta.version,
+        node_index: meta.node_index,
+        chunk_index: meta.chunk_index,
+        ..proto::HistoryId::default()
+    })
+    .map_err(|source: CodecError| ColumnarHistoryCodecError::Codec { source })
+    .context(NodeIdsSnafu { index })?;
+    let left = decode_optional_origin_id(
+        "origin_left",
+        meta.origin_left_version,
+        meta.origin_left_node_index,
+        meta.origin_left_chunk_index,
+        decode_indexed_update_id,
+    )
+    .context(NodeIdsSnafu { index })?;
+    let right = decode_optional_origin_id(
+        "origin_right",
+        meta.origin_right_version,
+        meta.origin_right_node_index,
+        meta.origin_right_chunk_index,
+        decode_indexed_update_id,
+    )
+    .context(NodeIdsSnafu { index })?;
+    ensure!(
+        !meta.value_is_null,
+        InvalidNodeSnafu {
+            index,
+            reason: "list chunks cannot be null",
+        }
+    );
+
+    let is_boundary = left.is_none() || right.is_none();
+    let value = if is_boundary {
+        ensure!(
+            meta.value_len == 0,
+            InvalidNodeSnafu {
+                index,
+                reason: "boundary nodes must not have elements",
+            }
+        );
+        None
+    } else {
+        let values = elements
+            .take(meta.value_len as usize)
+            .map(|(position, bytes)| decode_element(&bytes).context(ElementSnafu { position }))
+            .collect::<Result<Vec<_>, _>>()?;
+        ensure!(
+            values.len() == meta.value_len as usize,
+            InvalidNodeSnafu {
+                index,
+                reason: format!(
+                    "value_len {} exceeds the remaining elements",
+                    meta.value_len
+                ),
+            }
+        );
+        Some(values)
+    };
+
+    Ok(SnapshotNode {
+        id,
+        left,
+        right,
+        deleted: meta.deleted,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_data_types::IdWithIndex;
+    use std::{assert_matches, string::FromUtf8Error};
+
+    fn tagged_list() -> LinearList<UpdateId, String> {
+        let mut list = LinearList::with_values(
+            ["alpha", "beta", "gamma", "delta"].map(str::to_owned),
+            UpdateId {
+                version: 1,
+                node_index: 0,
+            },
+        );
+        let range = list.ids_in_range(1..3).unwrap();
+        range.delete(&mut list).unwrap();
+        list.append(
+            IdWithIndex::zero(UpdateId {
+                version: 2,
+                node_index: 1,
+            }),
+            ["epsilon".to_owned()],
+        );
+        list
+    }
+
+    fn decode_utf8(bytes: &[u8]) -> Result<String, FromUtf8Error> {
+        String::from_utf8(bytes.to_vec())
+    }
+
+    #[test]
+    fn encoded_list_history_roundtrips() {
+        let list = tagged_list();
+
+        let encoded =
+            encode_linear_list_history_snapshot_with(&list, |value| value.clone().into_bytes())
+                .unwrap();
+        // Tombstones keep their elements, so replicas can still resolve them.
+        assert_eq!(encoded.elements.len(), 5);
+
+        let decoded = decode_linear_list_history_snapshot_with(encoded, decode_utf8).unwrap();
+        decoded.validate_integrity().unwrap();
+        assert_eq!(
+            decoded.iter().collect::<Vec<_>>(),
+            list.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn decode_rejects_mismatched_elements() {
+        let list = tagged_list();
+        let encoded =
+            encode_linear_list_history_snapshot_with(&list, |value| value.clone().into_bytes())
+                .unwrap();
+
+        let mut with_extra = encoded.clone();
+        with_extra.elements.push(b"zeta".to_vec());
+        assert_matches!(
+            decode_linear_list_history_snapshot_with(with_extra, decode_utf8),
+            Err(SnapshotReadError::Source {
+                source: EncodedListHistoryDecodeError::UnusedElements {
+                    consumed: 5,
+                    available: 6,
+                },
+            })
+        );
+
+        let mut with_invalid = encoded;
+        with_invalid.elements[0] = vec![0xff];
+        assert_matches!(
+            decode_linear_list_history_snapshot_with(with_invalid, decode_utf8),
+            Err(SnapshotReadError::Source {
+                source: EncodedListHistoryDecodeError::Element { position: 0, .. },
+            })
+        );
+    }
+}
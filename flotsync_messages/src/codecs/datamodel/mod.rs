@@ -4,11 +4,16 @@
 )]
 
 pub mod columnar_history;
+pub mod encoded_list_history;
 pub mod operations;
 pub use columnar_history::*;
+pub use encoded_list_history::*;
 pub use operations::*;
 
-use crate::datamodel as proto;
+use crate::{
+    datamodel as proto,
+    wire::{WireValueDecodeError, uuid_from_wire_bytes, uuid_to_wire_bytes},
+};
 use flotsync_core::versions::UpdateId;
 use flotsync_data_types::{
     IdWithIndex,
@@ -30,6 +35,7 @@ use flotsync_data_types::{
             validation::ensure_snapshot_state_value_type,
         },
         values::{
+            DocRef,
             NullablePrimitiveValue as ModelNullablePrimitiveValue,
             NullablePrimitiveValueRef as ModelNullablePrimitiveValueRef,
             PrimitiveValue as ModelPrimitiveValue,
@@ -56,6 +62,8 @@ pub enum CodecError {
     ByteOutOfRange { value: u32 },
     #[snafu(display("Date {year:04}-{month:02}-{day:02} is not a valid calendar date."))]
     InvalidDate { year: i32, month: u32, day: u32 },
+    #[snafu(display("DocRef carries an invalid document id."))]
+    InvalidDocRef { source: WireValueDecodeError },
     #[snafu(display("Snapshot payload does not match the schema data type."))]
     InvalidSnapshotValue { source: DataModelValueError },
     #[snafu(display("Reconstructed CRDT failed integrity validation."))]
@@ -112,6 +120,18 @@ pub fn decode_indexed_update_id(id: proto::HistoryId) -> Result<UpdateIdWithInde
     })
 }
 
+/// Decode an indexed update id from its borrowed protobuf view.
+#[must_use]
+pub fn decode_indexed_update_id_view(id: &proto::HistoryIdView<'_>) -> UpdateIdWithIndex {
+    IdWithIndex {
+        id: UpdateId {
+            version: id.version,
+            node_index: id.node_index,
+        },
+        index: id.chunk_index,
+    }
+}
+
 #[must_use]
 pub fn encode_primitive_value(value: ModelPrimitiveValueRef<'_>) -> proto::PrimitiveValue {
     let mut encoded = proto::PrimitiveValue::default();
@@ -133,6 +153,9 @@ pub fn encode_primitive_value(value: ModelPrimitiveValueRef<'_>) -> proto::Primi
             proto::primitive_value::Value::Date(Box::new(encode_date(value)))
         }
         ModelPrimitiveValueRef::Timestamp(value) => proto::primitive_value::Value::Timestamp(value),
+        ModelPrimitiveValueRef::DocRef(value) => {
+            proto::primitive_value::Value::DocRef(Box::new(encode_doc_ref(value)))
+        }
     };
     encoded.value = Some(value_enum);
     encoded
@@ -167,6 +190,10 @@ pub fn decode_primitive_value(
         proto::primitive_value::Value::Timestamp(value) => {
             Ok(ModelPrimitiveValue::Timestamp(value))
         }
+        proto::primitive_value::Value::DocRef(value) => {
+            let doc_ref = decode_doc_ref(&value)?;
+            Ok(ModelPrimitiveValue::DocRef(doc_ref))
+        }
     }
 }
 
@@ -237,6 +264,13 @@ pub fn encode_primitive_array(
             };
             proto::primitive_array_value::Value::Timestamp(Box::new(message))
         }
+        ModelPrimitiveValueArrayRef::DocRef(values) => {
+            let message = proto::DocRefArrayValue {
+                values: values.iter().map(encode_doc_ref).collect(),
+                ..proto::DocRefArrayValue::default()
+            };
+            proto::primitive_array_value::Value::DocRef(Box::new(message))
+        }
     };
     proto::PrimitiveArrayValue {
         value: Some(value_enum),
@@ -282,6 +316,10 @@ pub fn decode_primitive_array(
         proto::primitive_array_value::Value::Timestamp(values) => {
             Ok(ModelPrimitiveValueArray::Timestamp(values.values))
         }
+        proto::primitive_array_value::Value::DocRef(values) => {
+            let doc_refs: Vec<DocRef> = values.values.iter().map(decode_doc_ref).try_collect()?;
+            Ok(ModelPrimitiveValueArray::DocRef(doc_refs))
+        }
     }
 }
 
@@ -502,6 +540,30 @@ pub(crate) fn decode_date(value: &proto::Date) -> Result<NaiveDate, CodecError>
     })
 }
 
+pub(crate) fn encode_doc_ref(value: &DocRef) -> proto::DocRef {
+    proto::DocRef {
+        document_id: uuid_to_wire_bytes(value.document_id),
+        anchor: value.anchor.as_ref().map(encode_indexed_update_id).into(),
+        ..proto::DocRef::default()
+    }
+}
+
+pub(crate) fn decode_doc_ref(value: &proto::DocRef) -> Result<DocRef, CodecError> {
+    let document_id = uuid_from_wire_bytes(&value.document_id, "DocRef.document_id")
+        .context(InvalidDocRefSnafu)?;
+    let anchor = value.anchor.as_option().map(|anchor| IdWithIndex {
+        id: UpdateId {
+            version: anchor.version,
+            node_index: anchor.node_index,
+        },
+        index: anchor.chunk_index,
+    });
+    Ok(DocRef {
+        document_id,
+        anchor,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -551,6 +613,41 @@ mod tests {
         assert_eq!(counter_roundtrip, counter);
     }
 
+    #[test]
+    fn doc_ref_values_roundtrip() {
+        let anchor = IdWithIndex {
+            id: UpdateId {
+                version: 12,
+                node_index: 3,
+            },
+            index: 5,
+        };
+        let document = DocRef::document(uuid::Uuid::from_u128(0x11));
+        let anchored = DocRef::anchored(uuid::Uuid::from_u128(0x22), anchor);
+
+        let primitive = ModelPrimitiveValue::DocRef(anchored.clone());
+        let primitive_roundtrip =
+            decode_primitive_value(encode_primitive_value(primitive.as_ref())).unwrap();
+        assert_eq!(primitive_roundtrip, primitive);
+
+        let array = ModelPrimitiveValueArray::DocRef(vec![document, anchored]);
+        let array_roundtrip =
+            decode_primitive_array(encode_primitive_array(array.as_ref())).unwrap();
+        assert_eq!(array_roundtrip, array);
+    }
+
+    #[test]
+    fn doc_ref_with_invalid_document_id_is_rejected() {
+        let encoded = proto::DocRef {
+            document_id: vec![0; 4],
+            ..proto::DocRef::default()
+        };
+        assert_matches!(
+            decode_doc_ref(&encoded),
+            Err(CodecError::InvalidDocRef { .. })
+        );
+    }
+
     #[test]
     fn state_snapshot_values_roundtrip_with_schema_validation() {
         let counter_type = ReplicatedDataType::MonotonicCounter { small_range: false };
@@ -1,6 +1,6 @@
 use super::*;
 use crate::{
-    buffa::MessageField,
+    buffa::{MessageField, MessageFieldView},
     datamodel as proto,
     snapshots::datamodel::{
         ProtoSchemaSnapshotDecoder,
@@ -11,6 +11,8 @@ use crate::{
 use flotsync_core::versions::UpdateId;
 use flotsync_data_types::{
     DataOperation,
+    DataOperationRef,
+    IdRange,
     IdWithIndex,
     any_data::UpdateOperation,
     schema::{
@@ -50,6 +52,8 @@ pub enum OperationCodecError {
     DeleteRangeCrossesUpdateBoundary,
     #[snafu(display("Row id bytes must contain one UUID, but received {len} bytes."))]
     InvalidRowIdBytes { len: usize, source: uuid::Error },
+    #[snafu(display("The linear operation variant has no protobuf encoding yet."))]
+    UnsupportedLinearOperation,
 }
 
 trait RequiredMessageFieldExt<T> {
@@ -430,6 +434,26 @@ fn decode_linear_string_operation(
         .collect()
 }
 
+/// Decode a linear string batch from its borrowed protobuf view without copying inserted text.
+///
+/// Inserted values keep pointing into the buffer the view was decoded from, so ingesting a
+/// large batch does not allocate a string per operation. Apply the result with
+/// [`LinearString::apply_operation_ref`](flotsync_data_types::text::LinearString::apply_operation_ref).
+///
+/// # Errors
+///
+/// See `OperationCodecError` for failure conditions.
+pub fn decode_linear_string_operation_view<'a>(
+    operation: &proto::LinearStringOperationView<'a>,
+) -> OperationResult<Vec<DataOperationRef<'a, UpdateIdWithIndex, str>>> {
+    ensure_non_empty_batch(&operation.actions)?;
+    operation
+        .actions
+        .iter()
+        .map(decode_linear_string_action_view)
+        .collect()
+}
+
 fn encode_linear_string_action(
     action: &DataOperation<UpdateIdWithIndex, String>,
 ) -> OperationResult<proto::LinearStringAction> {
@@ -451,6 +475,13 @@ fn encode_linear_string_action(
                 encoded_op,
             )));
         }
+        DataOperation::DeleteRanges { ranges } => {
+            let encoded_op = encode_linear_delete_ranges_operation(ranges)?;
+            encoded.value = Some(proto::linear_string_action::Value::DeleteRanges(Box::new(
+                encoded_op,
+            )));
+        }
+        _ => return UnsupportedLinearOperationSnafu.fail(),
     }
     Ok(encoded)
 }
@@ -467,9 +498,75 @@ fn decode_linear_string_action(
             decode_linear_string_insert_operation(*value)
         }
         proto::linear_string_action::Value::Delete(value) => decode_linear_delete_operation(*value),
+        proto::linear_string_action::Value::DeleteRanges(value) => {
+            decode_linear_delete_ranges_operation(*value)
+        }
     }
 }
 
+fn decode_linear_string_action_view<'a>(
+    action: &proto::LinearStringActionView<'a>,
+) -> OperationResult<DataOperationRef<'a, UpdateIdWithIndex, str>> {
+    let value = action
+        .value
+        .as_ref()
+        .take_required_oneof("LinearStringAction.value")?;
+    match value {
+        proto::linear_string_action::ValueView::Insert(operation) => {
+            let id = required_history_id_view(&operation.id, "LinearStringInsertOperation", "id")?;
+            let pred =
+                required_history_id_view(&operation.pred, "LinearStringInsertOperation", "pred")?;
+            let succ =
+                required_history_id_view(&operation.succ, "LinearStringInsertOperation", "succ")?;
+            Ok(DataOperation::Insert {
+                id,
+                pred,
+                succ,
+                value: operation.value,
+            })
+        }
+        proto::linear_string_action::ValueView::Delete(operation) => {
+            let (start, end) = decode_linear_delete_range_view(operation)?;
+            Ok(DataOperation::Delete { start, end })
+        }
+        proto::linear_string_action::ValueView::DeleteRanges(operation) => {
+            let ranges = operation
+                .ranges
+                .iter()
+                .map(|range| {
+                    decode_linear_delete_range_view(range).map(|(start, end)| IdRange {
+                        end: end.unwrap_or_else(|| start.clone()),
+                        start,
+                    })
+                })
+                .collect::<OperationResult<_>>()?;
+            Ok(DataOperation::DeleteRanges { ranges })
+        }
+    }
+}
+
+fn decode_linear_delete_range_view(
+    operation: &proto::LinearDeleteOperationView<'_>,
+) -> OperationResult<(UpdateIdWithIndex, Option<UpdateIdWithIndex>)> {
+    let start = required_history_id_view(&operation.start, "LinearDeleteOperation", "start")?;
+    let end = operation.end_chunk_index.map(|index| IdWithIndex {
+        id: start.id,
+        index,
+    });
+    Ok((start, end))
+}
+
+fn required_history_id_view(
+    id: &MessageFieldView<proto::HistoryIdView<'_>>,
+    message: &'static str,
+    field: &'static str,
+) -> OperationResult<UpdateIdWithIndex> {
+    id.as_option()
+        .map(decode_indexed_update_id_view)
+        .context(MissingFieldSnafu { message, field })
+        .context(CodecSnafu)
+}
+
 fn encode_linear_string_insert_operation(
     id: &UpdateIdWithIndex,
     pred: &UpdateIdWithIndex,
@@ -554,6 +651,12 @@ fn encode_linear_list_action(
                 encode_linear_delete_operation(start, end.as_ref())?,
             )));
         }
+        DataOperation::DeleteRanges { ranges } => {
+            encoded.value = Some(proto::linear_list_action::Value::DeleteRanges(Box::new(
+                encode_linear_delete_ranges_operation(ranges)?,
+            )));
+        }
+        _ => return UnsupportedLinearOperationSnafu.fail(),
     }
     Ok(encoded)
 }
@@ -570,6 +673,9 @@ fn decode_linear_list_action(
             decode_linear_list_insert_operation(*value)
         }
         proto::linear_list_action::Value::Delete(value) => decode_linear_delete_operation(*value),
+        proto::linear_list_action::Value::DeleteRanges(value) => {
+            decode_linear_delete_ranges_operation(*value)
+        }
     }
 }
 
@@ -631,9 +737,45 @@ fn encode_linear_delete_operation(
     })
 }
 
+fn encode_linear_delete_ranges_operation(
+    ranges: &[IdRange<UpdateIdWithIndex>],
+) -> OperationResult<proto::LinearDeleteRangesOperation> {
+    let ranges = ranges
+        .iter()
+        .map(|range| encode_linear_delete_operation(&range.start, Some(&range.end)))
+        .collect::<OperationResult<_>>()?;
+    Ok(proto::LinearDeleteRangesOperation {
+        ranges,
+        ..proto::LinearDeleteRangesOperation::default()
+    })
+}
+
 fn decode_linear_delete_operation<Value>(
-    mut operation: proto::LinearDeleteOperation,
+    operation: proto::LinearDeleteOperation,
+) -> OperationResult<DataOperation<UpdateIdWithIndex, Value>> {
+    let (start, end) = decode_linear_delete_range(operation)?;
+    Ok(DataOperation::Delete { start, end })
+}
+
+fn decode_linear_delete_ranges_operation<Value>(
+    operation: proto::LinearDeleteRangesOperation,
 ) -> OperationResult<DataOperation<UpdateIdWithIndex, Value>> {
+    let ranges = operation
+        .ranges
+        .into_iter()
+        .map(|range| {
+            decode_linear_delete_range(range).map(|(start, end)| IdRange {
+                end: end.unwrap_or_else(|| start.clone()),
+                start,
+            })
+        })
+        .collect::<OperationResult<_>>()?;
+    Ok(DataOperation::DeleteRanges { ranges })
+}
+
+fn decode_linear_delete_range(
+    mut operation: proto::LinearDeleteOperation,
+) -> OperationResult<(UpdateIdWithIndex, Option<UpdateIdWithIndex>)> {
     let start = operation
         .start
         .take_required("LinearDeleteOperation", "start")
@@ -642,8 +784,7 @@ fn decode_linear_delete_operation<Value>(
         id: start.id,
         index,
     });
-
-    Ok(DataOperation::Delete { start, end })
+    Ok((start, end))
 }
 
 fn encode_monotonic_counter_increment_operation(
@@ -712,7 +853,10 @@ fn ensure_non_empty_batch<T>(values: &[T]) -> OperationResult<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Uuid;
+    use crate::{
+        Uuid,
+        buffa::{Message, MessageView},
+    };
     use flotsync_data_types::{
         schema::{
             Direction,
@@ -826,6 +970,18 @@ mod tests {
                                 start: indexed(4, 4, 0),
                                 end: Some(indexed(4, 4, 2)),
                             },
+                            DataOperation::DeleteRanges {
+                                ranges: vec![
+                                    IdRange {
+                                        start: indexed(4, 4, 1),
+                                        end: indexed(4, 4, 2),
+                                    },
+                                    IdRange {
+                                        start: indexed(5, 5, 0),
+                                        end: indexed(5, 5, 0),
+                                    },
+                                ],
+                            },
                         ]),
                     },
                 ],
@@ -838,6 +994,72 @@ mod tests {
         assert_eq!(decoded, operation);
     }
 
+    #[test]
+    fn linear_string_views_borrow_inserted_text() {
+        let actions = vec![
+            DataOperation::Insert {
+                id: indexed(1, 1, 0),
+                pred: indexed(2, 2, 0),
+                succ: indexed(3, 3, 0),
+                value: "alpha".to_owned(),
+            },
+            DataOperation::Delete {
+                start: indexed(1, 1, 0),
+                end: Some(indexed(1, 1, 1)),
+            },
+            DataOperation::DeleteRanges {
+                ranges: vec![
+                    IdRange {
+                        start: indexed(1, 1, 0),
+                        end: indexed(1, 1, 4),
+                    },
+                    IdRange {
+                        start: indexed(2, 2, 3),
+                        end: indexed(2, 2, 3),
+                    },
+                ],
+            },
+        ];
+        let bytes = encode_linear_string_operation(&actions)
+            .unwrap()
+            .encode_to_vec();
+
+        let view = proto::LinearStringOperationView::decode_view(&bytes).unwrap();
+        let decoded = decode_linear_string_operation_view(&view).unwrap();
+
+        let DataOperation::Insert { value, .. } = decoded[0] else {
+            panic!("expected the insert first, got {:?}", decoded[0]);
+        };
+        assert!(bytes.as_ptr_range().contains(&value.as_ptr()));
+        let owned: Vec<_> = decoded.into_iter().map(DataOperation::into_owned).collect();
+        assert_eq!(owned, actions);
+    }
+
+    #[test]
+    fn linear_string_views_reject_missing_ids() {
+        let action = proto::LinearStringAction {
+            value: Some(proto::linear_string_action::Value::Delete(Box::default())),
+            ..proto::LinearStringAction::default()
+        };
+        let bytes = proto::LinearStringOperation {
+            actions: vec![action],
+            ..proto::LinearStringOperation::default()
+        }
+        .encode_to_vec();
+
+        let view = proto::LinearStringOperationView::decode_view(&bytes).unwrap();
+        let err = decode_linear_string_operation_view(&view).unwrap_err();
+        assert_matches!(
+            err,
+            OperationCodecError::Codec {
+                source: CodecError::MissingField {
+                    message: "LinearDeleteOperation",
+                    field: "start",
+                }
+            }
+        );
+    }
+
     #[test]
     fn encode_rejects_delete_ranges_crossing_update_boundaries() {
         let operation = model::SchemaOperation {
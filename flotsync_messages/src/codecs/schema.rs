@@ -3,7 +3,9 @@ use crate::{
     codecs::datamodel::{
         CodecError,
         decode_date as decode_datamodel_date,
+        decode_doc_ref,
         decode_nullable_basic_value,
+        encode_doc_ref,
         encode_nullable_basic_value,
     },
     datamodel as proto,
@@ -673,6 +675,13 @@ fn encode_primitive_array(value: &PrimitiveValueArray) -> proto::PrimitiveArrayV
             };
             proto::primitive_array_value::Value::Timestamp(Box::new(message))
         }
+        PrimitiveValueArray::DocRef(values) => {
+            let message = proto::DocRefArrayValue {
+                values: values.iter().map(encode_doc_ref).collect(),
+                ..proto::DocRefArrayValue::default()
+            };
+            proto::primitive_array_value::Value::DocRef(Box::new(message))
+        }
     };
     proto::PrimitiveArrayValue {
         value: Some(encoded_value),
@@ -716,6 +725,15 @@ fn decode_primitive_array(
         proto::primitive_array_value::Value::Timestamp(values) => {
             Ok(PrimitiveValueArray::Timestamp(values.values))
         }
+        proto::primitive_array_value::Value::DocRef(values) => {
+            let values = values
+                .values
+                .iter()
+                .map(decode_doc_ref)
+                .try_collect()
+                .context(CodecSnafu)?;
+            Ok(PrimitiveValueArray::DocRef(values))
+        }
     }
 }
 
@@ -743,6 +761,7 @@ fn encode_primitive_type(value: PrimitiveType) -> EnumValue<proto::PrimitiveType
         PrimitiveType::Binary => proto::PrimitiveType::PRIMITIVE_TYPE_BINARY,
         PrimitiveType::Date => proto::PrimitiveType::PRIMITIVE_TYPE_DATE,
         PrimitiveType::Timestamp => proto::PrimitiveType::PRIMITIVE_TYPE_TIMESTAMP,
+        PrimitiveType::DocRef => proto::PrimitiveType::PRIMITIVE_TYPE_DOC_REF,
     };
     EnumValue::from(value)
 }
@@ -764,6 +783,7 @@ fn decode_primitive_type(value: EnumValue<proto::PrimitiveType>) -> SchemaResult
         proto::PrimitiveType::PRIMITIVE_TYPE_BINARY => Ok(PrimitiveType::Binary),
         proto::PrimitiveType::PRIMITIVE_TYPE_DATE => Ok(PrimitiveType::Date),
         proto::PrimitiveType::PRIMITIVE_TYPE_TIMESTAMP => Ok(PrimitiveType::Timestamp),
+        proto::PrimitiveType::PRIMITIVE_TYPE_DOC_REF => Ok(PrimitiveType::DocRef),
     }
 }
 
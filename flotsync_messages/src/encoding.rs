@@ -0,0 +1,503 @@
+//! Negotiated serialisation formats for logical messages.
+//!
+//! Protobuf is the canonical encoding that every peer understands. With the `cbor` feature, the
+//! bodies of update and update-batch messages can alternatively be sent as CBOR. The CBOR form of
+//! a message is its [`flotsync_cbor`] type, which mirrors the `.proto` definition field by field.
+//! The delivery envelope, all other runtime messages, and snapshot transfers are always protobuf,
+//! so CBOR does not remove the need for the protobuf codec.
+//!
+//! Each peer advertises the encodings it accepts besides protobuf in an [`EncodingOffer`] while
+//! exchanging summaries. The sender negotiates its preferred encoding against that offer and wraps
+//! every message in an [`EncodedPayload`], which names the encoding and its version, so receivers
+//! never have to guess.
+
+#[cfg(feature = "cbor")]
+use crate::cbor::IntoCbor;
+use crate::{
+    buffa::{self, EnumValue},
+    delivery as delivery_proto,
+    proto::{FromProtoDecodeError, ProtoCodec},
+};
+use bytes::Bytes;
+use enumset::{EnumSet, EnumSetType};
+use snafu::prelude::*;
+
+/// Current version of the protobuf encoding.
+pub const PROTOBUF_ENCODING_VERSION: u32 = 1;
+
+#[cfg(feature = "cbor")]
+pub use flotsync_cbor::CBOR_ENCODING_VERSION;
+
+/// Serialisation format of a logical message.
+#[derive(Debug, Default, EnumSetType, Hash)]
+pub enum PayloadEncoding {
+    #[default]
+    Protobuf,
+    Cbor,
+}
+
+impl PayloadEncoding {
+    /// Decode an encoding from its wire form, reading the unspecified value as protobuf.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PayloadEncodingError::UnsupportedEncoding`] for values this build does not know.
+    pub fn from_wire(
+        value: EnumValue<delivery_proto::PayloadEncoding>,
+    ) -> Result<Self, PayloadEncodingError> {
+        match value.as_known() {
+            Some(
+                delivery_proto::PayloadEncoding::PAYLOAD_ENCODING_UNSPECIFIED
+                | delivery_proto::PayloadEncoding::PAYLOAD_ENCODING_PROTOBUF,
+            ) => Ok(Self::Protobuf),
+            Some(delivery_proto::PayloadEncoding::PAYLOAD_ENCODING_CBOR) => Ok(Self::Cbor),
+            None => UnsupportedEncodingSnafu {
+                value: value.to_i32(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Encode this encoding into its wire form.
+    #[must_use]
+    pub fn to_wire(self) -> EnumValue<delivery_proto::PayloadEncoding> {
+        EnumValue::from(match self {
+            Self::Protobuf => delivery_proto::PayloadEncoding::PAYLOAD_ENCODING_PROTOBUF,
+            Self::Cbor => delivery_proto::PayloadEncoding::PAYLOAD_ENCODING_CBOR,
+        })
+    }
+
+    /// Whether this build can encode and decode messages with this encoding.
+    #[must_use]
+    pub const fn is_supported(self) -> bool {
+        match self {
+            Self::Protobuf => true,
+            Self::Cbor => cfg!(feature = "cbor"),
+        }
+    }
+
+    /// The newest version of this encoding this build understands, or `None` if this build does
+    /// not support it.
+    #[must_use]
+    pub const fn version(self) -> Option<u32> {
+        match self {
+            Self::Protobuf => Some(PROTOBUF_ENCODING_VERSION),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => Some(CBOR_ENCODING_VERSION),
+            #[cfg(not(feature = "cbor"))]
+            Self::Cbor => None,
+        }
+    }
+}
+
+/// Set of encodings one peer accepts on messages sent to it.
+///
+/// Protobuf is always accepted, whether or not it is listed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EncodingOffer {
+    encodings: EnumSet<PayloadEncoding>,
+}
+
+impl EncodingOffer {
+    /// The offer of a peer that only accepts protobuf.
+    pub const PROTOBUF: Self = Self {
+        encodings: enumset::enum_set!(PayloadEncoding::Protobuf),
+    };
+
+    /// Offer every encoding this build can decode.
+    #[must_use]
+    pub fn supported() -> Self {
+        Self::from_encodings(
+            EnumSet::<PayloadEncoding>::all()
+                .iter()
+                .filter(|encoding| encoding.is_supported())
+                .collect(),
+        )
+    }
+
+    #[must_use]
+    pub fn from_encodings(encodings: EnumSet<PayloadEncoding>) -> Self {
+        Self {
+            encodings: encodings | PayloadEncoding::Protobuf,
+        }
+    }
+
+    #[must_use]
+    pub fn encodings(self) -> EnumSet<PayloadEncoding> {
+        self.encodings
+    }
+
+    #[must_use]
+    pub fn accepts(self, encoding: PayloadEncoding) -> bool {
+        self.encodings.contains(encoding)
+    }
+
+    /// Encodings accepted by both offers, e.g. by every recipient of one group broadcast.
+    #[must_use]
+    pub fn intersection(self, other: Self) -> Self {
+        Self {
+            encodings: self.encodings & other.encodings,
+        }
+    }
+
+    /// Pick the encoding for messages sent to a peer offering `self`.
+    ///
+    /// The `preferred` encoding is used if this build supports it and the peer accepts it,
+    /// otherwise messages fall back to protobuf.
+    #[must_use]
+    pub fn negotiate(self, preferred: PayloadEncoding) -> PayloadEncoding {
+        if preferred.is_supported() && self.accepts(preferred) {
+            preferred
+        } else {
+            PayloadEncoding::Protobuf
+        }
+    }
+
+    /// Decode an offer, ignoring encodings this build does not know.
+    #[must_use]
+    pub fn from_wire_values<'a>(
+        values: impl IntoIterator<Item = &'a EnumValue<delivery_proto::PayloadEncoding>>,
+    ) -> Self {
+        let encodings = values
+            .into_iter()
+            .filter_map(|value| PayloadEncoding::from_wire(*value).ok())
+            .collect();
+        Self::from_encodings(encodings)
+    }
+}
+
+impl Default for EncodingOffer {
+    fn default() -> Self {
+        Self::PROTOBUF
+    }
+}
+
+impl ProtoCodec for EncodingOffer {
+    type DecodeError = PayloadEncodingError;
+    type Proto = delivery_proto::EncodingOffer;
+
+    fn to_proto(&self) -> Self::Proto {
+        delivery_proto::EncodingOffer {
+            encodings: self
+                .encodings
+                .iter()
+                .filter(|encoding| *encoding != PayloadEncoding::Protobuf)
+                .map(PayloadEncoding::to_wire)
+                .collect(),
+            ..delivery_proto::EncodingOffer::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        Ok(Self::from_wire_values(&message.encodings))
+    }
+}
+
+/// A generated message that can be sent with every encoding this build supports.
+#[cfg(feature = "cbor")]
+pub trait EncodableMessage:
+    buffa::Message + IntoCbor<Cbor: serde::Serialize + serde::de::DeserializeOwned + Into<Self>> + Sized
+{
+}
+
+#[cfg(feature = "cbor")]
+impl<M> EncodableMessage for M
+where
+    M: buffa::Message + IntoCbor,
+    M::Cbor: serde::Serialize + serde::de::DeserializeOwned + Into<M>,
+{
+}
+
+/// A generated message that can be sent with every encoding this build supports.
+#[cfg(not(feature = "cbor"))]
+pub trait EncodableMessage: buffa::Message + Sized {}
+
+#[cfg(not(feature = "cbor"))]
+impl<M> EncodableMessage for M where M: buffa::Message {}
+
+/// One logical message serialised with a negotiated encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodedPayload {
+    pub encoding: PayloadEncoding,
+    /// Version of the encoding the data was written with.
+    pub encoding_version: u32,
+    pub data: Bytes,
+}
+
+impl EncodedPayload {
+    /// Serialise `message` with the current version of `encoding`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this build does not support `encoding`, or if the message cannot be
+    /// represented in it.
+    pub fn encode<M>(encoding: PayloadEncoding, message: M) -> Result<Self, PayloadEncodingError>
+    where
+        M: EncodableMessage,
+    {
+        let encoding_version = encoding
+            .version()
+            .context(UnsupportedInBuildSnafu { encoding })?;
+        let data = match encoding {
+            PayloadEncoding::Protobuf => message.encode_to_vec(),
+            PayloadEncoding::Cbor => encode_cbor(message)?,
+        };
+        Ok(Self {
+            encoding,
+            encoding_version,
+            data: Bytes::from(data),
+        })
+    }
+
+    /// Deserialise the message, whichever supported encoding it was written with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this build does not support the payload's encoding or its version, or
+    /// if the data is corrupt.
+    pub fn decode<M>(&self) -> Result<M, PayloadEncodingError>
+    where
+        M: EncodableMessage,
+    {
+        let supported_version = self.encoding.version().context(UnsupportedInBuildSnafu {
+            encoding: self.encoding,
+        })?;
+        ensure!(
+            self.encoding_version <= supported_version,
+            UnsupportedVersionSnafu {
+                encoding: self.encoding,
+                version: self.encoding_version,
+            }
+        );
+        match self.encoding {
+            PayloadEncoding::Protobuf => {
+                M::decode_from_slice(&self.data).context(ProtobufDecodeSnafu)
+            }
+            PayloadEncoding::Cbor => decode_cbor(&self.data),
+        }
+    }
+}
+
+impl ProtoCodec for EncodedPayload {
+    type DecodeError = PayloadEncodingError;
+    type Proto = delivery_proto::EncodedPayload;
+
+    fn to_proto(&self) -> Self::Proto {
+        delivery_proto::EncodedPayload {
+            encoding: self.encoding.to_wire(),
+            encoding_version: self.encoding_version,
+            data: self.data.clone(),
+            ..delivery_proto::EncodedPayload::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        Ok(Self {
+            encoding: PayloadEncoding::from_wire(message.encoding)?,
+            encoding_version: message.encoding_version,
+            data: message.data,
+        })
+    }
+}
+
+#[cfg(feature = "cbor")]
+fn encode_cbor<M: EncodableMessage>(message: M) -> Result<Vec<u8>, PayloadEncodingError> {
+    let message = message
+        .into_cbor()
+        .map_err(|source| PayloadEncodingError::CborEncode {
+            message: source.to_string(),
+        })?;
+    flotsync_cbor::encode(&message).map_err(|source| PayloadEncodingError::CborEncode {
+        message: source.to_string(),
+    })
+}
+
+#[cfg(not(feature = "cbor"))]
+fn encode_cbor<M: EncodableMessage>(_message: M) -> Result<Vec<u8>, PayloadEncodingError> {
+    UnsupportedInBuildSnafu {
+        encoding: PayloadEncoding::Cbor,
+    }
+    .fail()
+}
+
+#[cfg(feature = "cbor")]
+fn decode_cbor<M: EncodableMessage>(data: &[u8]) -> Result<M, PayloadEncodingError> {
+    flotsync_cbor::decode::<M::Cbor>(data)
+        .map(Into::into)
+        .map_err(|source| PayloadEncodingError::CborDecode {
+            message: source.to_string(),
+        })
+}
+
+#[cfg(not(feature = "cbor"))]
+fn decode_cbor<M: EncodableMessage>(_data: &[u8]) -> Result<M, PayloadEncodingError> {
+    UnsupportedInBuildSnafu {
+        encoding: PayloadEncoding::Cbor,
+    }
+    .fail()
+}
+
+/// Errors produced while encoding or decoding messages with a negotiated encoding.
+#[derive(Debug, Snafu)]
+pub enum PayloadEncodingError {
+    #[snafu(display("Failed to decode encoded payload envelope."))]
+    Decode { source: buffa::DecodeError },
+    #[snafu(display("Payload encoding {value} is not supported."))]
+    UnsupportedEncoding { value: i32 },
+    #[snafu(display("Payload encoding {encoding:?} is not enabled in this build."))]
+    UnsupportedInBuild { encoding: PayloadEncoding },
+    #[snafu(display("Version {version} of payload encoding {encoding:?} is not supported."))]
+    UnsupportedVersion {
+        encoding: PayloadEncoding,
+        version: u32,
+    },
+    #[snafu(display("Failed to decode protobuf payload."))]
+    ProtobufDecode { source: buffa::DecodeError },
+    #[snafu(display("Failed to encode CBOR payload: {message}"))]
+    CborEncode { message: String },
+    #[snafu(display("Failed to decode CBOR payload: {message}"))]
+    CborDecode { message: String },
+}
+
+impl FromProtoDecodeError for PayloadEncodingError {
+    fn from_proto_decode_error(source: buffa::DecodeError) -> Self {
+        Self::Decode { source }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffa::MessageField,
+        datamodel as datamodel_proto,
+        proto::{DecodeProto, EncodeProto},
+        replication as replication_proto,
+        versions as versions_proto,
+    };
+
+    fn update(group_version: u64) -> replication_proto::Update {
+        let read_versions = versions_proto::CompactVersionVector {
+            versions: Some(versions_proto::compact_version_vector::Versions::Synced(
+                Box::new(versions_proto::SyncedVersionVector {
+                    group_version,
+                    ..versions_proto::SyncedVersionVector::default()
+                }),
+            )),
+            ..versions_proto::CompactVersionVector::default()
+        };
+        let delete = datamodel_proto::SchemaOperation {
+            change_id: MessageField::some(datamodel_proto::HistoryId {
+                version: group_version + 1,
+                node_index: 2,
+                ..datamodel_proto::HistoryId::default()
+            }),
+            operation: Some(datamodel_proto::schema_operation::Operation::Delete(
+                Box::new(datamodel_proto::DeleteRowOperation {
+                    row_id: vec![3; 16],
+                    ..datamodel_proto::DeleteRowOperation::default()
+                }),
+            )),
+            ..datamodel_proto::SchemaOperation::default()
+        };
+        replication_proto::Update {
+            group_id: vec![1; 16],
+            update_id: MessageField::some(datamodel_proto::HistoryId {
+                version: group_version + 1,
+                node_index: 2,
+                ..datamodel_proto::HistoryId::default()
+            }),
+            read_versions: MessageField::some(read_versions),
+            dataset_updates: vec![replication_proto::DatasetUpdate {
+                dataset_id: "groceries".to_owned(),
+                operations: vec![delete],
+                ..replication_proto::DatasetUpdate::default()
+            }],
+            ..replication_proto::Update::default()
+        }
+    }
+
+    #[test]
+    fn negotiation_falls_back_to_protobuf() {
+        let cbor_only = EncodingOffer::from_encodings(PayloadEncoding::Cbor.into());
+        assert!(cbor_only.accepts(PayloadEncoding::Protobuf));
+        assert_eq!(
+            EncodingOffer::PROTOBUF.negotiate(PayloadEncoding::Cbor),
+            PayloadEncoding::Protobuf
+        );
+        assert_eq!(
+            cbor_only.negotiate(PayloadEncoding::Protobuf),
+            PayloadEncoding::Protobuf
+        );
+        let expected = if cfg!(feature = "cbor") {
+            PayloadEncoding::Cbor
+        } else {
+            PayloadEncoding::Protobuf
+        };
+        assert_eq!(cbor_only.negotiate(PayloadEncoding::Cbor), expected);
+
+        let roundtrip = EncodingOffer::decode_proto(cbor_only.encode_proto()).unwrap();
+        assert_eq!(roundtrip, cbor_only);
+        assert_eq!(
+            EncodingOffer::decode_proto(delivery_proto::EncodingOffer::default()).unwrap(),
+            EncodingOffer::PROTOBUF
+        );
+    }
+
+    #[test]
+    fn protobuf_payload_roundtrips_and_rejects_newer_versions() {
+        let payload = EncodedPayload::encode(PayloadEncoding::Protobuf, update(7)).unwrap();
+        let payload = EncodedPayload::decode_proto(payload.encode_proto()).unwrap();
+        assert_eq!(
+            payload.decode::<replication_proto::Update>().unwrap(),
+            update(7)
+        );
+
+        let newer = EncodedPayload {
+            encoding_version: PROTOBUF_ENCODING_VERSION + 1,
+            ..payload
+        };
+        assert!(matches!(
+            newer.decode::<replication_proto::Update>(),
+            Err(PayloadEncodingError::UnsupportedVersion { .. })
+        ));
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_payload_roundtrips() {
+        use crate::buffa::Message as _;
+
+        let payload = EncodedPayload::encode(PayloadEncoding::Cbor, update(11)).unwrap();
+        assert_eq!(payload.encoding_version, CBOR_ENCODING_VERSION);
+        assert_ne!(payload.data, Bytes::from(update(11).encode_to_vec()));
+        assert_eq!(
+            payload.decode::<replication_proto::Update>().unwrap(),
+            update(11)
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_rejects_runtime_messages_without_a_cbor_form() {
+        let summary_request = replication_proto::RuntimeMessage {
+            body: Some(replication_proto::runtime_message::Body::SummaryRequest(
+                Box::default(),
+            )),
+            ..replication_proto::RuntimeMessage::default()
+        };
+        assert!(matches!(
+            EncodedPayload::encode(PayloadEncoding::Cbor, summary_request),
+            Err(PayloadEncodingError::CborEncode { .. })
+        ));
+    }
+
+    #[cfg(not(feature = "cbor"))]
+    #[test]
+    fn cbor_is_rejected_without_the_feature() {
+        assert!(matches!(
+            EncodedPayload::encode(PayloadEncoding::Cbor, update(11)),
+            Err(PayloadEncodingError::UnsupportedInBuild { .. })
+        ));
+    }
+}
@@ -4,7 +4,10 @@
 pub use buffa;
 pub use uuid::Uuid;
 
+#[cfg(feature = "cbor")]
+pub mod cbor;
 pub mod codecs;
+pub mod encoding;
 pub mod proto;
 pub mod serialisation;
 pub mod snapshots;
@@ -0,0 +1,136 @@
+//! Checks this crate against the wire vectors in `conformance/linear_string_operations.toml`.
+//!
+//! The data file is shared with other implementations, see `docs/conformance_vectors.md`.
+
+use flotsync_core::versions::UpdateId;
+use flotsync_data_types::{
+    DataOperation,
+    IdRange,
+    IdWithIndex,
+    schema::datamodel::{OperationFieldValue, OperationValue},
+};
+use flotsync_messages::{
+    buffa::{Message as _, MessageView as _},
+    codecs::datamodel::{decode_linear_string_operation_view, encode_operation_field},
+    datamodel as proto,
+};
+use std::borrow::Cow;
+use toml::{Table, Value};
+
+const LINEAR_STRING_OPERATIONS: &str =
+    include_str!("../../conformance/linear_string_operations.toml");
+
+type TextOperation = DataOperation<IdWithIndex<UpdateId>, String>;
+
+#[test]
+fn linear_string_operations_match_the_conformance_encodings() {
+    let vectors: Table = LINEAR_STRING_OPERATIONS
+        .parse()
+        .expect("conformance vectors must be valid TOML");
+    let batches = vectors["batch"]
+        .as_array()
+        .expect("batch must be an array of tables");
+    assert!(!batches.is_empty());
+
+    for batch in batches {
+        let name = str_field(batch, "name");
+        let operations: Vec<_> = batch["operations"]
+            .as_array()
+            .expect("operations must be an array")
+            .iter()
+            .map(parse_operation)
+            .collect();
+        let encoded = parse_hex(str_field(batch, "encoded"));
+
+        let field = encode_operation_field(&OperationFieldValue {
+            field_name: Cow::Borrowed("text"),
+            value: OperationValue::LinearString(operations.clone()),
+        })
+        .unwrap_or_else(|error| panic!("{name}: encoding failed: {error}"));
+        let Some(proto::operation_field::Value::LinearString(operation)) = field.value else {
+            panic!("{name}: not encoded as a linear string operation");
+        };
+        assert_eq!(operation.encode_to_vec(), encoded, "{name}: encoding");
+
+        let view = proto::LinearStringOperationView::decode_view(&encoded)
+            .unwrap_or_else(|error| panic!("{name}: protobuf decoding failed: {error}"));
+        let decoded: Vec<_> = decode_linear_string_operation_view(&view)
+            .unwrap_or_else(|error| panic!("{name}: decoding failed: {error}"))
+            .into_iter()
+            .map(DataOperation::into_owned)
+            .collect();
+        assert_eq!(decoded, operations, "{name}: decoding");
+    }
+}
+
+fn str_field<'a>(table: &'a Value, key: &str) -> &'a str {
+    table[key]
+        .as_str()
+        .unwrap_or_else(|| panic!("{key} must be a string in {table}"))
+}
+
+fn parse_hex(hex: &str) -> Vec<u8> {
+    assert!(hex.len().is_multiple_of(2), "{hex:?} has an odd number of digits");
+    (0..hex.len())
+        .step_by(2)
+        .map(|start| {
+            u8::from_str_radix(&hex[start..start + 2], 16)
+                .unwrap_or_else(|_| panic!("{hex:?} is not hex"))
+        })
+        .collect()
+}
+
+fn parse_operation(operation: &Value) -> TextOperation {
+    if let Some(value) = operation.get("insert") {
+        DataOperation::Insert {
+            id: parse_id(str_field(operation, "id")),
+            pred: parse_id(str_field(operation, "pred")),
+            succ: parse_id(str_field(operation, "succ")),
+            value: value
+                .as_str()
+                .expect("inserted text must be a string")
+                .to_owned(),
+        }
+    } else if let Some(start) = operation.get("delete") {
+        DataOperation::Delete {
+            start: parse_id(start.as_str().expect("delete must be an id")),
+            end: operation
+                .get("end")
+                .map(|end| parse_id(end.as_str().expect("end must be an id"))),
+        }
+    } else if let Some(ranges) = operation.get("delete-ranges") {
+        let ranges = ranges
+            .as_array()
+            .expect("delete-ranges must be an array")
+            .iter()
+            .map(|range| match range.as_array().map(Vec::as_slice) {
+                Some([start, end]) => IdRange {
+                    start: parse_id(start.as_str().expect("range start must be an id")),
+                    end: parse_id(end.as_str().expect("range end must be an id")),
+                },
+                _ => panic!("delete ranges must be [start, end] pairs, not {range}"),
+            })
+            .collect();
+        DataOperation::DeleteRanges { ranges }
+    } else {
+        panic!("unknown operation {operation}")
+    }
+}
+
+/// Parse an id written as `v<version>@<member>#<index>`.
+fn parse_id(id: &str) -> IdWithIndex<UpdateId> {
+    let parsed = id
+        .strip_prefix('v')
+        .and_then(|rest| rest.split_once('@'))
+        .and_then(|(version, rest)| {
+            let (member, index) = rest.split_once('#')?;
+            Some(IdWithIndex {
+                id: UpdateId {
+                    version: version.parse().ok()?,
+                    node_index: member.parse().ok()?,
+                },
+                index: index.parse().ok()?,
+            })
+        });
+    parsed.unwrap_or_else(|| panic!("{id:?} is not a v<version>@<member>#<index> id"))
+}
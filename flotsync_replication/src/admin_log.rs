@@ -0,0 +1,93 @@
+//! Signing and verifying [`AdminLog`](flotsync_data_types::admin_log::AdminLog) entries with the
+//! members' identity keys.
+//!
+//! Entries are signed like frames, with their own frame kind, so an entry signature can never be
+//! mistaken for the signature of a runtime message or vice versa.
+use flotsync_core::{MemberIdentity, MemberIndex};
+use flotsync_data_types::admin_log::{AdminLogSigner, AdminLogVerifier};
+use flotsync_security::{
+    FrameSignature,
+    LocalMemberKeys,
+    PublicMemberKeys,
+    SIGNATURE_LENGTH,
+    SecurityError,
+    SignedFrameParts,
+    sign_frame,
+    verify_frame_signature,
+};
+use std::collections::BTreeMap;
+
+/// Signs admin log entries with the local member's signing key.
+#[derive(Debug)]
+pub struct MemberKeysAdminSigner<'a> {
+    local_keys: &'a LocalMemberKeys,
+}
+
+impl<'a> MemberKeysAdminSigner<'a> {
+    pub fn new(local_keys: &'a LocalMemberKeys) -> Self {
+        Self { local_keys }
+    }
+}
+
+impl AdminLogSigner for MemberKeysAdminSigner<'_> {
+    type Error = SecurityError;
+
+    fn author(&self) -> &MemberIdentity {
+        self.local_keys.member_id()
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        let signature = sign_frame(self.local_keys, entry_parts(message))?;
+        Ok(signature.as_bytes().to_vec())
+    }
+}
+
+/// Verifies admin log entries against the public keys of the group's members.
+#[derive(Clone, Debug, Default)]
+pub struct MemberKeysAdminVerifier {
+    members: BTreeMap<MemberIdentity, PublicMemberKeys>,
+    producers: BTreeMap<MemberIndex, MemberIdentity>,
+}
+
+impl MemberKeysAdminVerifier {
+    /// Trust entries signed with any of `members`' keys, each under the member's group index.
+    pub fn new(members: impl IntoIterator<Item = (MemberIndex, PublicMemberKeys)>) -> Self {
+        let mut verifier = Self::default();
+        for (index, keys) in members {
+            verifier.producers.insert(index, keys.member_id().clone());
+            verifier.members.insert(keys.member_id().clone(), keys);
+        }
+        verifier
+    }
+}
+
+impl AdminLogVerifier for MemberKeysAdminVerifier {
+    fn verify(&self, author: &MemberIdentity, message: &[u8], signature: &[u8]) -> bool {
+        let Some(public_keys) = self.members.get(author) else {
+            return false;
+        };
+        let Ok(bytes) = <[u8; SIGNATURE_LENGTH]>::try_from(signature) else {
+            return false;
+        };
+        verify_frame_signature(
+            public_keys,
+            entry_parts(message),
+            &FrameSignature::from_bytes(bytes),
+        )
+        .is_ok()
+    }
+
+    fn member_at(&self, node_index: u32) -> Option<&MemberIdentity> {
+        self.producers.get(&MemberIndex::new(node_index))
+    }
+}
+
+const ADMIN_LOG_ENTRY_FRAME_KIND: &str = "admin-log-entry";
+
+fn entry_parts(message: &[u8]) -> SignedFrameParts<'_> {
+    SignedFrameParts {
+        frame_kind: ADMIN_LOG_ENTRY_FRAME_KIND,
+        public_header: &[],
+        ciphertext: message,
+    }
+}
@@ -0,0 +1,29 @@
+//! Binary attachments that documents reference by content hash.
+//!
+//! A blob's bytes never travel inside CRDT operations. Documents store the [`BlobHash`] of a
+//! blob instead, and the runtime keeps the blobs each hosted group references. Other members of
+//! the group fetch a blob from a peer with [`ReplicationApi::fetch_blob`] once they need its
+//! content, and only blobs their group references are served to them.
+
+use super::*;
+use crate::blobs::BlobHash;
+
+/// Request to store one blob and reference it from one group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoreBlobRequest {
+    /// Group whose members may fetch the blob from this runtime.
+    pub group_id: GroupId,
+    /// Content of the blob.
+    pub data: Bytes,
+}
+
+/// Request to read one blob referenced by a group, fetching it from a peer if needed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FetchBlobRequest {
+    /// Group that references the blob.
+    pub group_id: GroupId,
+    /// Content hash of the blob.
+    pub hash: BlobHash,
+    /// Group member to fetch the blob from when it is not held locally.
+    pub peer: MemberIdentity,
+}
@@ -0,0 +1,128 @@
+//! Named checkpoints in the causal history of a group.
+//!
+//! A checkpoint tags one causal frontier of a group with a human-readable
+//! label, such as "v1.0 sent to client". Checkpoints are ordinary
+//! [`Checkpoint`] documents stored in the [`checkpoints_dataset_id`] dataset
+//! of the group, so they replicate and converge like any other row. Groups
+//! that use checkpoints must therefore include that dataset in their
+//! [`GroupSchema`], for example by registering [`Checkpoint`] for it in the
+//! [`DocumentKindRegistry`] the schema is built from.
+//!
+//! The rows of a group as they were at a checkpoint are materialised with
+//! [`ReplicationApi::snapshot_rows_at`].
+
+use super::*;
+use flotsync_data_types::{
+    Field,
+    PrimitiveType,
+    schema::{BasicDataType, NullableBasicDataType},
+};
+
+/// Name of the dataset that holds the checkpoints of a group.
+pub const CHECKPOINTS_DATASET: &str = "flotsync_checkpoints";
+
+/// Field that holds the label of a [`Checkpoint`].
+const LABEL_FIELD: &str = "label";
+/// Field that holds the tagged versions of a [`Checkpoint`] in display notation.
+const VERSIONS_FIELD: &str = "versions";
+
+/// Return the id of the dataset that holds the checkpoints of a group.
+#[must_use]
+pub fn checkpoints_dataset_id() -> DatasetId {
+    DatasetId::try_new(CHECKPOINTS_DATASET).expect("checkpoint dataset id is valid")
+}
+
+/// A human-readable label for one causal frontier of a group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Label chosen by the member that created the checkpoint.
+    pub label: String,
+    /// Group versions the checkpoint refers to.
+    pub versions: VersionVector,
+}
+
+impl DocumentKind for Checkpoint {
+    const NAME: &'static str = "flotsync.checkpoint";
+
+    fn schema() -> SchemaSource {
+        let string_type =
+            NullableBasicDataType::NonNull(BasicDataType::Primitive(PrimitiveType::String));
+        SchemaSource::from(Schema::from_fields([
+            Field::latest_value_wins(LABEL_FIELD, string_type.clone()),
+            Field::latest_value_wins(VERSIONS_FIELD, string_type),
+        ]))
+    }
+
+    fn decode(row: &dyn RowValueRead) -> Result<Self, DecodeValueError> {
+        let label = row.get_field_value::<str>(LABEL_FIELD)?.into_owned();
+        let versions = row
+            .get_field_value::<str>(VERSIONS_FIELD)?
+            .parse()
+            .map_err(
+                |error: VersionVectorParseError| DecodeValueError::InvalidValue {
+                    requested_type: "VersionVector",
+                    explanation: error.to_string().into(),
+                },
+            )?;
+        Ok(Self { label, versions })
+    }
+
+    fn encode(&self) -> RowValuesPatch {
+        crate::row_values! {
+            LABEL_FIELD => self.label.as_str(),
+            VERSIONS_FIELD => self.versions.to_string(),
+        }
+    }
+}
+
+/// Request to tag the state an application has read with a label.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreateCheckpointRequest {
+    /// Read position whose versions of `group_id` the checkpoint refers to.
+    pub read_token: ReadToken,
+    /// Group to create the checkpoint in.
+    pub group_id: GroupId,
+    /// Human-readable label of the checkpoint.
+    pub label: String,
+}
+
+/// Request the rows of a group as they were at one set of group versions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoricalSnapshotRowsRequest {
+    /// Id of the replication group this request targets.
+    pub group_id: GroupId,
+    /// Application datasets to include in the snapshot.
+    pub datasets: HashSet<DatasetId>,
+    /// Group versions to materialise the rows at.
+    ///
+    /// Every version must already be applied locally.
+    pub versions: VersionVector,
+    /// Maximum number of rows in one batch.
+    pub max_rows_per_batch: NonZeroUsize,
+}
+
+impl HistoricalSnapshotRowsRequest {
+    /// Request the rows of `datasets` as they were at `checkpoint`.
+    #[must_use]
+    pub fn at_checkpoint(
+        checkpoint: &TypedDoc<Checkpoint>,
+        datasets: HashSet<DatasetId>,
+        max_rows_per_batch: NonZeroUsize,
+    ) -> Self {
+        Self {
+            group_id: checkpoint.row_id.group_id,
+            datasets,
+            versions: checkpoint.versions.clone(),
+            max_rows_per_batch,
+        }
+    }
+}
+
+/// Return a registry that declares [`Checkpoint`] for the checkpoint dataset.
+pub(crate) fn checkpoint_kinds() -> DocumentKindRegistry {
+    let mut kinds = DocumentKindRegistry::new();
+    kinds
+        .register::<Checkpoint>(checkpoints_dataset_id())
+        .expect("the checkpoint schema does not define the payload version field");
+    kinds
+}
@@ -1,4 +1,5 @@
-use super::StoreSecretKeyId;
+use super::{QuarantineId, StoreSecretKeyId};
+use crate::blobs::BlobHash;
 use flotsync_core::{GroupId, MemberIdentity, member::Identifier, membership::GroupMembersError};
 use flotsync_security::LocalStoreSecretError;
 pub use flotsync_utils::BoxError;
@@ -62,6 +63,22 @@ pub enum ApiError {
     },
     #[snafu(display("Replication runtime operation '{operation}' is not implemented yet."))]
     UnsupportedOperation { operation: &'static str },
+    #[snafu(display("Another sync step is still running."))]
+    SyncStepInProgress,
+    #[snafu(display("No delivery with id {id} is quarantined."))]
+    QuarantinedDeliveryNotFound { id: QuarantineId },
+    #[snafu(display("Member {peer} does not hold blob {hash} for group {group_id}."))]
+    BlobUnavailable {
+        group_id: GroupId,
+        hash: BlobHash,
+        peer: MemberIdentity,
+    },
+    #[snafu(display("Timed out fetching blob {hash} of group {group_id} from member {peer}."))]
+    BlobFetchTimedOut {
+        group_id: GroupId,
+        hash: BlobHash,
+        peer: MemberIdentity,
+    },
 }
 
 #[derive(Debug, Snafu)]
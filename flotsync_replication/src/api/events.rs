@@ -0,0 +1,117 @@
+//! Workspace event bus for applications that observe the runtime as a whole.
+//!
+//! [`ReplicationEventListener`] is the channel through which applications take part in
+//! replication: it receives row providers and invitation responders and its errors fail the
+//! delivery. [`WorkspaceEvents`] complements it for observers such as GUIs. It broadcasts
+//! self-contained, cloneable [`WorkspaceEvent`] notices from every runtime subsystem to any number
+//! of subscribers, and never waits for them.
+
+use super::*;
+use crate::store::StorageQuotaWarning;
+use flotsync_data_types::schema::SemanticsRevisionError;
+use tokio::sync::broadcast;
+
+/// Receiving end of a [`WorkspaceEvents`] subscription.
+///
+/// Subscribers that fall more than the bus capacity behind receive
+/// [`broadcast::error::RecvError::Lagged`] and skip ahead to the oldest retained event. Once the
+/// runtime has shut down, the receiver reports [`broadcast::error::RecvError::Closed`].
+pub type WorkspaceEventReceiver = broadcast::Receiver<WorkspaceEvent>;
+
+/// Something that happened in a workspace that applications may want to present.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WorkspaceEvent {
+    /// Documents were changed by a local publish or by applied remote updates.
+    ///
+    /// Emitted after the [`ReplicationEventListener`] has received the matching
+    /// [`ReplicationEvent::DataChanged`] event.
+    DocumentChanged {
+        /// Read position reached by the changes.
+        read_token: ReadToken,
+        /// Changed documents, in the order the listener received them.
+        documents: Vec<RowId>,
+    },
+    /// A peer became reachable for the first time, or again after being down.
+    PeerConnected { peer: MemberIdentity },
+    /// A sync step finished with every reachable peer answered and caught up.
+    SyncCompleted {
+        /// Groups that were synced, in ascending order.
+        groups: Vec<GroupId>,
+    },
+    /// A remote update was merged over many local updates its producer had not seen.
+    ///
+    /// Such updates were written against an outdated view of the group, so their changes are more
+    /// likely to have been merged with concurrent edits of the same documents in surprising ways.
+    ConflictHeavyMerge {
+        group_id: GroupId,
+        update_id: UpdateId,
+        /// Number of updates applied locally that the producer had not seen.
+        concurrent_updates: u64,
+    },
+    /// A member that was not part of a group became a member of its successor group.
+    MemberJoined {
+        /// Group the member is now part of.
+        group_id: GroupId,
+        /// Group the successor replaced.
+        previous_group_id: GroupId,
+        member: MemberIdentity,
+    },
+    /// A group or one of its documents uses more storage than a soft quota allows.
+    ///
+    /// Emitted by [`StorageQuota::publish_warnings`](crate::store::StorageQuota::publish_warnings).
+    /// Quotas never reject writes, so this is purely informational.
+    StorageQuotaExceeded { warning: StorageQuotaWarning },
+    /// A remote update was refused because one of its datasets uses conflict-resolution
+    /// semantics this code does not implement.
+    ///
+    /// Merging it anyway could make this replica silently diverge from its peers. The delivery
+    /// carrying the update is rejected like any other invalid delivery, see
+    /// [`QuarantinedDelivery`]. Later updates of the same dataset are refused as well, until this
+    /// replica is upgraded to code that implements the dataset's semantics revision.
+    IncompatibleSemantics {
+        group_id: GroupId,
+        dataset_id: DatasetId,
+        update_id: UpdateId,
+        error: SemanticsRevisionError,
+    },
+    /// A peer dropped a live update of ours because we exceeded its [`WriteRateLimit`].
+    ///
+    /// The dropped update still reaches the peer later through catch-up, but publishing should
+    /// slow down until `retry_after` has passed.
+    WriteThrottled {
+        group_id: GroupId,
+        peer: MemberIdentity,
+        reason: ThrottleReason,
+        retry_after: Duration,
+    },
+}
+
+/// Broadcast bus distributing [`WorkspaceEvent`]s to every subscriber.
+///
+/// Clones share the same bus.
+#[derive(Clone, Debug)]
+pub struct WorkspaceEvents {
+    sender: broadcast::Sender<WorkspaceEvent>,
+}
+
+impl WorkspaceEvents {
+    /// Create a bus that retains up to `capacity` events for slow subscribers.
+    #[must_use]
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.get());
+        Self { sender }
+    }
+
+    /// Receive every event emitted from now on.
+    #[must_use]
+    pub fn subscribe(&self) -> WorkspaceEventReceiver {
+        self.sender.subscribe()
+    }
+
+    /// Send `event` to every current subscriber.
+    ///
+    /// Events emitted while nobody is subscribed are dropped.
+    pub fn emit(&self, event: WorkspaceEvent) {
+        let _ = self.sender.send(event);
+    }
+}
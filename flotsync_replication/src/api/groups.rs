@@ -1,6 +1,8 @@
 //! Group, pending-group, invitation, and lifecycle API types.
 
 use super::*;
+use crate::blobs::BlobDescriptor;
+use flotsync_utils::shutdown::ShutdownToken;
 
 /// Policy decision for one invitation or migration classification.
 ///
@@ -99,6 +101,160 @@ pub enum GroupClosePolicy {
     CloseReadsAndWrites,
 }
 
+/// Whether this replica may produce its own updates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ReplicaRole {
+    /// Apply remote updates and publish local ones.
+    #[default]
+    Member,
+    /// Apply remote updates, but never produce any, e.g. for dashboards and backups.
+    ///
+    /// Observers only join groups that list them in
+    /// [`CreateGroupRequest::observers`] or [`ChangeGroupMembershipRequest::add_observers`], so
+    /// they never hold a member index. Invitations and migration proposals that would give them
+    /// one are rejected, and so are creating groups and proposing membership changes, since the
+    /// proposer always takes the first member index of the new group.
+    Observer,
+}
+
+/// Power state reported by the embedding application.
+///
+/// The runtime only uses this as a hint for how eagerly it starts background sync sessions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PowerHint {
+    /// No power constraints, e.g. while charging.
+    #[default]
+    Unconstrained,
+    /// Running on battery or in a power-saving mode.
+    ///
+    /// Debounce and periodic intervals are stretched by
+    /// [`SyncSchedulingPolicy::constrained_interval_factor`].
+    Constrained,
+    /// Battery critically low.
+    ///
+    /// No background sync sessions start until the hint changes. Triggers observed meanwhile are
+    /// kept and fire once sessions resume.
+    Critical,
+}
+
+/// Policy deciding when the runtime starts background sync sessions with group peers.
+///
+/// A sync session asks one peer for its group summary. Any versions the peer has that are
+/// missing locally are then fetched through the ordinary catch-up flow. Peers the failure
+/// detector reports as down are skipped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncSchedulingPolicy {
+    /// Quiet period after the last local or live remote change in a group before syncing it.
+    ///
+    /// `None` disables change-triggered sessions.
+    pub on_change_debounce: Option<Duration>,
+    /// Interval between sessions for every hosted group.
+    ///
+    /// `None` disables periodic sessions.
+    pub periodic_interval: Option<Duration>,
+    /// Sync every hosted group after the application reports a network change, and sync the
+    /// shared groups of a peer once it becomes reachable again.
+    pub sync_on_network_change: bool,
+    /// Multiplier applied to both intervals while the power hint is [`PowerHint::Constrained`].
+    pub constrained_interval_factor: u32,
+}
+
+impl SyncSchedulingPolicy {
+    /// Never start sessions automatically and leave orchestration to the application.
+    pub const MANUAL: Self = Self {
+        on_change_debounce: None,
+        periodic_interval: None,
+        sync_on_network_change: false,
+        constrained_interval_factor: 1,
+    };
+}
+
+impl Default for SyncSchedulingPolicy {
+    fn default() -> Self {
+        Self {
+            on_change_debounce: Some(Duration::from_secs(5)),
+            periodic_interval: Some(Duration::from_secs(5 * 60)),
+            sync_on_network_change: true,
+            constrained_interval_factor: 4,
+        }
+    }
+}
+
+/// Per-member limits on how fast remote members may push live updates into a group.
+///
+/// Each member gets two token buckets per group, one counting schema operations and one counting
+/// encoded payload bytes. Buckets refill continuously at their per-second rate up to their burst
+/// size. A live update that does not fit into both buckets is dropped and its producer receives a
+/// typed throttle notice telling it how long to back off. An update larger than a burst is still
+/// admitted once its bucket is full, so oversized updates are slowed down but never starved.
+///
+/// Dropped updates are not lost: they show up as gaps and are fetched later through catch-up,
+/// which the receiving replica paces itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteRateLimit {
+    /// Sustained schema operations per second accepted from one member.
+    pub operations_per_second: NonZeroU32,
+    /// Schema operations one member may send at once after being idle.
+    pub operation_burst: NonZeroU32,
+    /// Sustained encoded update bytes per second accepted from one member.
+    pub bytes_per_second: NonZeroU64,
+    /// Encoded update bytes one member may send at once after being idle.
+    pub byte_burst: NonZeroU64,
+}
+
+/// Which [`WriteRateLimit`] bucket an update exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ThrottleReason {
+    /// Too many schema operations.
+    OperationRate,
+    /// Too many encoded update bytes.
+    ByteRate,
+}
+
+impl fmt::Display for ThrottleReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OperationRate => f.write_str("operation rate"),
+            Self::ByteRate => f.write_str("byte rate"),
+        }
+    }
+}
+
+/// Resource limits that protect the runtime from oversized groups, rows, and messages.
+///
+/// Binaries usually resolve these from the `flotsync.replication.runtime.limits`
+/// config entries with `FlotsyncConfig::settings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuntimeLimits {
+    /// Maximum encoded size of one runtime message payload, in bytes.
+    ///
+    /// Larger inbound payloads are dropped before decoding, and local publishes that would
+    /// exceed it are rejected.
+    pub max_payload_bytes: usize,
+    /// Maximum number of members in one replication group.
+    pub max_group_members: usize,
+    /// Maximum number of CRDT nodes stored for one row.
+    ///
+    /// Inbound updates that would grow a row beyond it are dropped.
+    pub max_document_nodes: usize,
+    /// Maximum size of one blob fetched from a peer, in bytes.
+    ///
+    /// Fetches of larger blobs fail before any of their chunks are kept.
+    pub max_blob_bytes: usize,
+}
+
+impl Default for RuntimeLimits {
+    fn default() -> Self {
+        Self {
+            max_payload_bytes: crate::runtime::DEFAULT_MAX_RUNTIME_PAYLOAD_BYTES,
+            max_group_members: crate::runtime::DEFAULT_MAX_GROUP_MEMBERS,
+            max_document_nodes: crate::runtime::DEFAULT_MAX_DOCUMENT_NODES,
+            max_blob_bytes: crate::runtime::DEFAULT_MAX_BLOB_BYTES,
+        }
+    }
+}
+
 /// Runtime configuration passed during `load`.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ReplicationConfig {
@@ -110,6 +266,21 @@ pub struct ReplicationConfig {
     pub group_migration_policy: GroupMigrationPolicy,
     /// Local access policy reserved for the future standalone group-close flow.
     pub group_close_policy: GroupClosePolicy,
+    /// Whether this replica may produce its own updates.
+    pub replica_role: ReplicaRole,
+    /// When background sync sessions with group peers start.
+    pub sync_scheduling: SyncSchedulingPolicy,
+    /// Which payloads are compressed on which links, and what peers may send compressed.
+    pub compression: CompressionPolicy,
+    /// Encoding for outgoing updates.
+    ///
+    /// Updates only use it if every recipient advertised it in the summary exchange, and fall back
+    /// to protobuf otherwise. Encodings other than protobuf need the matching crate feature.
+    pub preferred_encoding: PayloadEncoding,
+    /// Limits on live updates from each remote member, `None` to accept updates at any rate.
+    pub write_rate_limit: Option<WriteRateLimit>,
+    /// Limits on group, row, and message sizes.
+    pub limits: RuntimeLimits,
 }
 
 /// Device-local security input required while loading one replication runtime.
@@ -219,6 +390,8 @@ impl ReplicationSecuritySecrets {
 #[derive(Clone, PartialEq, Eq)]
 pub struct CreateGroupRequest {
     pub members: Vec<MemberIdentity>,
+    /// Members that replicate the group without a member index, so they never produce updates.
+    pub observers: Vec<MemberIdentity>,
     /// Dataset schemas fixed for the lifetime of the new group.
     pub group_schema: GroupSchema,
 }
@@ -227,6 +400,7 @@ impl fmt::Debug for CreateGroupRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CreateGroupRequest")
             .field("members", &self.members)
+            .field("observers", &self.observers)
             .field("group_schema", &self.group_schema)
             .finish()
     }
@@ -237,6 +411,11 @@ impl fmt::Debug for CreateGroupRequest {
 pub struct ChangeGroupMembershipRequest {
     pub group_id: GroupId,
     pub add_members: HashSet<MemberIdentity>,
+    /// Members that join the new group as observers, without a member index.
+    ///
+    /// Existing observers carry over unless they are listed in `remove_members`.
+    pub add_observers: HashSet<MemberIdentity>,
+    /// Members or observers that leave the group.
     pub remove_members: HashSet<MemberIdentity>,
     pub group_name: Option<String>,
     pub message: Option<String>,
@@ -267,6 +446,8 @@ pub struct GroupInvitation {
     pub source: GroupInvitationSource,
     /// Proposed canonical member order for the invited group.
     pub proposed_members: Vec<MemberIdentity>,
+    /// Proposed observers of the invited group, which hold no member index.
+    pub proposed_observers: Vec<MemberIdentity>,
     /// Dataset schemas fixed for the lifetime of the invited group.
     pub group_schema: GroupSchema,
     /// Initial dataset state required before the invited group becomes active.
@@ -283,6 +464,7 @@ impl fmt::Debug for GroupInvitation {
             .field("group_id", &self.group_id)
             .field("source", &self.source)
             .field("proposed_member_count", &self.proposed_members.len())
+            .field("proposed_observer_count", &self.proposed_observers.len())
             .field("group_schema", &self.group_schema)
             .field("initial_snapshot", &self.initial_snapshot)
             .field("has_group_name", &self.group_name.is_some())
@@ -320,6 +502,7 @@ impl GroupInvitation {
             group_id,
             source: GroupInvitationSource::Creation,
             proposed_members,
+            proposed_observers: Vec::new(),
             group_schema,
             initial_snapshot,
             group_name,
@@ -344,6 +527,7 @@ impl GroupInvitation {
             group_id: migration_id.new_group_id,
             source: GroupInvitationSource::Migration { migration_id },
             proposed_members,
+            proposed_observers: Vec::new(),
             group_schema,
             initial_snapshot,
             group_name,
@@ -351,6 +535,13 @@ impl GroupInvitation {
         }
     }
 
+    /// Set the proposed observers of the invited group.
+    #[must_use]
+    pub fn with_proposed_observers(mut self, proposed_observers: Vec<MemberIdentity>) -> Self {
+        self.proposed_observers = proposed_observers;
+        self
+    }
+
     /// Build a group invitation from explicit API parts.
     ///
     /// This validates that migration-sourced invitations name the migration's
@@ -408,6 +599,8 @@ pub struct MigrationProposal {
     pub final_versions: VersionVector,
     /// Proposed canonical member order for the new group.
     pub proposed_members: Vec<MemberIdentity>,
+    /// Proposed observers of the new group, which hold no member index.
+    pub proposed_observers: Vec<MemberIdentity>,
     /// Dataset schemas fixed for the lifetime of the new group.
     pub group_schema: GroupSchema,
     /// Initial dataset state required before the new group becomes active.
@@ -424,6 +617,7 @@ impl fmt::Debug for MigrationProposal {
             .field("migration_id", &self.migration_id)
             .field("final_versions", &self.final_versions)
             .field("proposed_member_count", &self.proposed_members.len())
+            .field("proposed_observer_count", &self.proposed_observers.len())
             .field("group_schema", &self.group_schema)
             .field("initial_snapshot", &self.initial_snapshot)
             .field("has_group_name", &self.group_name.is_some())
@@ -512,6 +706,32 @@ impl PendingGroupDecisionRecord {
         }
     }
 
+    /// Return proposed target-group observers.
+    #[must_use]
+    pub fn proposed_observers(&self) -> &[MemberIdentity] {
+        match self {
+            Self::GroupInvitation(invitation) => &invitation.proposed_observers,
+            Self::MigrationProposal(proposal) => &proposal.proposed_observers,
+        }
+    }
+
+    /// Return the initial snapshot the target group starts from.
+    #[must_use]
+    pub const fn initial_snapshot(&self) -> &InitialSnapshot {
+        match self {
+            Self::GroupInvitation(invitation) => &invitation.initial_snapshot,
+            Self::MigrationProposal(proposal) => &proposal.initial_snapshot,
+        }
+    }
+
+    /// Replace the initial snapshot, e.g. with rows fetched for an announced snapshot.
+    pub fn set_initial_snapshot(&mut self, initial_snapshot: InitialSnapshot) {
+        match self {
+            Self::GroupInvitation(invitation) => invitation.initial_snapshot = initial_snapshot,
+            Self::MigrationProposal(proposal) => proposal.initial_snapshot = initial_snapshot,
+        }
+    }
+
     /// Return the target-group schema carried by this work.
     #[must_use]
     pub const fn group_schema(&self) -> &GroupSchema {
@@ -608,6 +828,7 @@ impl PendingGroupActivationRecord {
                 group_id: invitation.group_id,
                 migration_cutover: None,
                 proposed_members: invitation.proposed_members,
+                proposed_observers: invitation.proposed_observers,
                 group_schema: invitation.group_schema,
                 initial_snapshot: invitation.initial_snapshot,
             },
@@ -619,6 +840,7 @@ impl PendingGroupActivationRecord {
                     final_versions: proposal.final_versions,
                 }),
                 proposed_members: proposal.proposed_members,
+                proposed_observers: proposal.proposed_observers,
                 group_schema: proposal.group_schema,
                 initial_snapshot: proposal.initial_snapshot,
             },
@@ -637,6 +859,8 @@ pub struct AcceptedGroupActivationRecord {
     pub migration_cutover: Option<AcceptedMigrationCutover>,
     /// Proposed canonical member order for the activated group.
     pub proposed_members: Vec<MemberIdentity>,
+    /// Proposed observers of the activated group.
+    pub proposed_observers: Vec<MemberIdentity>,
     /// Dataset schemas fixed for the activated group.
     pub group_schema: GroupSchema,
     /// Initial dataset state required before the group becomes active.
@@ -747,6 +971,19 @@ pub trait ReplicationApi: Send + Sync {
     /// previous shutdown completed is a no-op.
     fn shutdown(&self) -> BoxFuture<'_, Result<(), ApiError>>;
 
+    /// Shut this runtime down as part of a coordinated application shutdown.
+    ///
+    /// Registers the runtime's discovery and its sync sessions as participants of
+    /// `token` right away, so call this before the shutdown starts. The returned
+    /// future waits until `token` stops accepting work, then refuses further API
+    /// calls like [`Self::shutdown`] and stops discovery. Sync sessions and
+    /// delivery stop in the drain phase, while transport and the runtime system
+    /// close in the close phase (see [`ShutdownPhase`]). Drive the future
+    /// concurrently with the shutdown controller.
+    ///
+    /// [`ShutdownPhase`]: flotsync_utils::shutdown::ShutdownPhase
+    fn shutdown_with(&self, token: &ShutdownToken) -> BoxFuture<'_, Result<(), ApiError>>;
+
     /// Return the local member's shareable identity-free public key bundle.
     ///
     /// Applications can encode this bundle for transfer to another user or
@@ -827,9 +1064,161 @@ pub trait ReplicationApi: Send + Sync {
         request: SnapshotRowsRequest,
     ) -> BoxFuture<'_, Result<SnapshotValueRows, ApiError>>;
 
+    /// Request the rows of a group as they were at [`HistoricalSnapshotRowsRequest::versions`].
+    ///
+    /// The rows are rebuilt by replaying the group's applied updates up to the
+    /// requested versions, so only rows that were visible at that point are
+    /// emitted. The returned read token refers to the requested versions: changes
+    /// published with it are rebased onto the current state like any other change
+    /// based on an older read.
+    ///
+    /// The method returns [`ApiError`] when the group is unknown or closed, the
+    /// versions do not fit the group or are not applied locally yet, the
+    /// runtime is unavailable, or the store cannot replay the group history.
+    fn snapshot_rows_at(
+        &self,
+        request: HistoricalSnapshotRowsRequest,
+    ) -> BoxFuture<'_, Result<SnapshotValueRows, ApiError>>;
+
+    /// Tag the versions of one group in a read token with a label.
+    ///
+    /// The checkpoint is published as a [`Checkpoint`] document in the group's
+    /// [`checkpoints_dataset_id`] dataset, which the group schema must include.
+    /// It replicates to the other members like any other change.
+    ///
+    /// The method returns [`ApiError`] when the read token does not contain the
+    /// group, or publishing the checkpoint fails for any reason
+    /// [`Self::publish_changes`] would fail.
+    fn create_checkpoint(
+        &self,
+        request: CreateCheckpointRequest,
+    ) -> BoxFuture<'_, Result<TypedDoc<Checkpoint>, ApiError>>;
+
+    /// List the checkpoints of a group, ordered by label.
+    ///
+    /// This only reads local state, so it includes the checkpoints of other
+    /// members that have been applied locally.
+    ///
+    /// The method returns [`ApiError`] when the group is unknown or has no
+    /// checkpoint dataset, a stored checkpoint cannot be decoded, the runtime
+    /// is unavailable, or the store cannot be read.
+    fn list_checkpoints(
+        &self,
+        group_id: GroupId,
+    ) -> BoxFuture<'_, Result<Vec<TypedDoc<Checkpoint>>, ApiError>>;
+
+    /// Store a blob and reference it from one group.
+    ///
+    /// The blob is kept in memory and served to the other members of the group when they
+    /// fetch it. Documents reference it through the hash of the returned descriptor.
+    ///
+    /// The method returns [`ApiError`] when the group is not hosted by this runtime, the blob
+    /// is too large to be chunked, or the runtime is unavailable.
+    fn store_blob(
+        &self,
+        request: StoreBlobRequest,
+    ) -> BoxFuture<'_, Result<BlobDescriptor, ApiError>>;
+
+    /// Read the content of a blob referenced by one group.
+    ///
+    /// A blob that is not held locally is requested from `request.peer` chunk by chunk. Its
+    /// content is checked against its hash and then kept for the group like a stored blob.
+    /// Concurrent reads of the same blob share one transfer.
+    ///
+    /// The method returns [`ApiError::BlobUnavailable`] when the peer does not hold the blob for
+    /// the group, [`ApiError::BlobFetchTimedOut`] when the peer does not send every chunk within
+    /// the configured fetch timeout, and [`ApiError`] when the group or peer is unknown, the
+    /// blob exceeds [`RuntimeLimits::max_blob_bytes`], the peer sends invalid content, or the
+    /// runtime is unavailable.
+    fn fetch_blob(&self, request: FetchBlobRequest) -> BoxFuture<'_, Result<Bytes, ApiError>>;
+
     /// Ask one group member for its current group version vector.
     fn request_summary(&self, request: SummaryRequest) -> BoxFuture<'_, Result<Summary, ApiError>>;
 
+    /// Report how far every member of a group has acknowledged applying its updates.
+    ///
+    /// This only reads local runtime state and does not contact any peer.
+    ///
+    /// The method returns [`ApiError`] when the group is unknown, the runtime is
+    /// unavailable, or the store cannot be read.
+    fn acknowledged_versions(
+        &self,
+        group_id: GroupId,
+    ) -> BoxFuture<'_, Result<AcknowledgedVersions, ApiError>>;
+
+    /// Report the sync health of every member of a group.
+    ///
+    /// Combines the local failure detector's view of each peer with the
+    /// versions it has acknowledged, so callers can show which members are
+    /// unreachable or lagging. This only reads local runtime state.
+    ///
+    /// The method returns [`ApiError`] when the group is unknown, the runtime is
+    /// unavailable, or the store cannot be read.
+    fn sync_health(&self, group_id: GroupId) -> BoxFuture<'_, Result<GroupSyncHealth, ApiError>>;
+
+    /// Report the device power state used to pace background sync sessions.
+    ///
+    /// See [`SyncSchedulingPolicy`] for how each hint affects scheduling.
+    ///
+    /// The method returns [`ApiError`] when the runtime is unavailable.
+    fn set_power_hint(&self, hint: PowerHint) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Report that the device's network connectivity changed.
+    ///
+    /// If [`SyncSchedulingPolicy::sync_on_network_change`] is set, every hosted group is synced
+    /// with its reachable peers on the next scheduling pass.
+    ///
+    /// The method returns [`ApiError`] when the runtime is unavailable.
+    fn notify_network_changed(&self) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Sync every hosted group with its reachable peers, spending at most `budget`.
+    ///
+    /// This is meant for platforms that only grant short background-task windows, such as
+    /// Android and iOS: the application calls it from a background-task callback instead of
+    /// keeping sync sessions running. Sessions start immediately with every remote member the
+    /// failure detector does not report as down, regardless of [`SyncSchedulingPolicy`] and the
+    /// current [`PowerHint`]. The returned future resolves as soon as every peer answered and
+    /// everything they reported was applied locally, or once `budget` is spent, whichever comes
+    /// first. See [`SyncStepProgress`] for what is reported.
+    ///
+    /// The method returns [`ApiError`] when another sync step is still running or the runtime is
+    /// unavailable.
+    fn run_sync_step(&self, budget: Duration) -> BoxFuture<'_, Result<SyncStepProgress, ApiError>>;
+
+    /// Report how much outbound traffic compression saved and how much inbound traffic was
+    /// decompressed since the runtime was loaded.
+    ///
+    /// See [`ReplicationConfig::compression`] for which payloads are compressed.
+    ///
+    /// The method returns [`ApiError`] when the runtime is unavailable.
+    fn compression_counters(&self) -> BoxFuture<'_, Result<CompressionCounters, ApiError>>;
+
+    /// List the inbound group broadcasts the runtime rejected and still keeps, oldest first.
+    ///
+    /// The quarantine only holds the most recent rejections. See [`QuarantinedDelivery`] for which
+    /// deliveries end up in it.
+    ///
+    /// The method returns [`ApiError`] when the runtime is unavailable.
+    fn quarantined_deliveries(&self) -> BoxFuture<'_, Result<Vec<QuarantinedDelivery>, ApiError>>;
+
+    /// Remove one delivery from the quarantine and hand it to the runtime again.
+    ///
+    /// The future completes once the delivery was handed over, not once it was applied. If the
+    /// runtime rejects it again, it is quarantined again under a new [`QuarantineId`].
+    ///
+    /// The method returns [`ApiError::QuarantinedDeliveryNotFound`] when no delivery with `id` is
+    /// quarantined, and [`ApiError`] when the runtime is unavailable.
+    fn retry_quarantined_delivery(&self, id: QuarantineId) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Subscribe to the [`WorkspaceEvent`]s of this runtime.
+    ///
+    /// The subscription only sees events emitted after this call, and is closed when the runtime
+    /// shuts down. Applications that need every data change must use the
+    /// [`ReplicationEventListener`] instead, since slow subscribers miss events.
+    ///
+    /// The method returns [`ApiError`] when the runtime is unavailable.
+    fn subscribe_workspace_events(&self) -> Result<WorkspaceEventReceiver, ApiError>;
+
     /// Create one new fixed-membership replication group rooted at this member.
     ///
     /// `req.members` defines the canonical member order for the new group and
@@ -862,6 +1251,13 @@ pub trait ReplicationApi: Send + Sync {
     /// remain available to the replication protocol for bounded catch-up, but
     /// no longer interact with the application.
     ///
+    /// Removing members rotates the group key: the new group gets a freshly
+    /// generated key that is only sealed for the remaining members. The
+    /// proposal's final versions act as the grace frontier of every removed
+    /// member, so their old-group updates up to it are still carried into the
+    /// new group's initial snapshot, and later ones are not. Those updates stay
+    /// attributed to the removed members through the old group's member order.
+    ///
     /// # Errors
     ///
     /// Returns [`ApiError`] when the old group is unknown or invalid, the
@@ -889,6 +1285,8 @@ pub struct GroupMemberKeys {
     ordered_member_keys: Vec<MemberKeyId>,
     /// Member identity to canonical index lookup derived from `ordered_member_keys`.
     member_indices: TrieMap<MemberIndex>,
+    /// Exact observer-key bindings, which hold no member index.
+    observer_keys: Vec<MemberKeyId>,
 }
 
 impl GroupMemberKeys {
@@ -922,9 +1320,25 @@ impl GroupMemberKeys {
         Ok(Self {
             ordered_member_keys,
             member_indices,
+            observer_keys: Vec::new(),
         })
     }
 
+    /// Add the observer-key bindings of this group.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GroupMembersError::DuplicateMember`] when an observer is
+    /// already a member or listed twice.
+    pub fn with_observer_keys(
+        mut self,
+        observer_keys: impl IntoIterator<Item = MemberKeyId>,
+    ) -> Result<Self, GroupMembersError> {
+        self.observer_keys = observer_keys.into_iter().collect();
+        self.to_group_members()?;
+        Ok(self)
+    }
+
     /// Return the exact member-key bindings in canonical group order.
     #[must_use]
     pub fn ordered_member_keys(&self) -> &[MemberKeyId] {
@@ -939,6 +1353,20 @@ impl GroupMemberKeys {
             .map(|member_key| &member_key.member_id)
     }
 
+    /// Return the exact observer-key bindings of this group.
+    #[must_use]
+    pub fn observer_keys(&self) -> &[MemberKeyId] {
+        &self.observer_keys
+    }
+
+    /// Return the observer identities of this group.
+    #[must_use]
+    pub fn observer_ids(&self) -> impl ExactSizeIterator<Item = &MemberIdentity> + '_ {
+        self.observer_keys
+            .iter()
+            .map(|observer_key| &observer_key.member_id)
+    }
+
     /// Convert this exact key set into an identity-only indexed group view.
     ///
     /// # Errors
@@ -946,13 +1374,21 @@ impl GroupMemberKeys {
     /// See [`GroupMembersError`] for failure conditions. Construction should
     /// only fail if this value was built from inconsistent internal state.
     pub fn to_group_members(&self) -> Result<GroupMembers, GroupMembersError> {
-        GroupMembers::from_ordered_members(self.member_ids().cloned())
+        GroupMembers::from_ordered_members(self.member_ids().cloned())?
+            .with_observers(self.observer_ids().cloned())
     }
 
-    /// Return whether this group contains `member_id`.
+    /// Return whether this group contains `member_id`, either as member or as observer.
     #[must_use]
     pub fn contains_member(&self, member_id: &MemberIdentity) -> bool {
-        self.member_indices.get(member_id).is_some()
+        self.member_indices.get(member_id).is_some() || self.is_observer(member_id)
+    }
+
+    /// Return whether `member_id` observes this group without a member index.
+    #[must_use]
+    pub fn is_observer(&self, member_id: &MemberIdentity) -> bool {
+        self.observer_ids()
+            .any(|observer_id| observer_id == member_id)
     }
 
     /// Return the canonical member index assigned to `member_id`, if present.
@@ -961,11 +1397,16 @@ impl GroupMemberKeys {
         self.member_indices.get(member_id).copied()
     }
 
-    /// Return the exact key binding for `member_id`, if present.
+    /// Return the exact key binding for `member_id`, if present as member or as observer.
     #[must_use]
     pub fn member_key(&self, member_id: &MemberIdentity) -> Option<&MemberKeyId> {
-        let member_index = self.member_index(member_id)?;
-        self.member_key_at_index(member_index)
+        match self.member_index(member_id) {
+            Some(member_index) => self.member_key_at_index(member_index),
+            None => self
+                .observer_keys
+                .iter()
+                .find(|observer_key| &observer_key.member_id == member_id),
+        }
     }
 
     /// Return the exact key binding assigned to one canonical group index.
@@ -974,13 +1415,13 @@ impl GroupMemberKeys {
         self.ordered_member_keys.get(index.as_u32() as usize)
     }
 
-    /// Return whether this member-key set is empty.
+    /// Return whether this member-key set has no indexed members.
     #[must_use]
     pub fn is_empty(&self) -> bool {
         self.ordered_member_keys.is_empty()
     }
 
-    /// Return the number of exact member-key bindings in this group.
+    /// Return the number of indexed member-key bindings in this group, excluding observers.
     #[must_use]
     pub fn len(&self) -> usize {
         self.ordered_member_keys.len()
@@ -991,6 +1432,7 @@ impl fmt::Debug for GroupMemberKeys {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("GroupMemberKeys")
             .field("ordered_member_keys", &self.ordered_member_keys)
+            .field("observer_keys", &self.observer_keys)
             .finish_non_exhaustive()
     }
 }
@@ -998,6 +1440,7 @@ impl fmt::Debug for GroupMemberKeys {
 impl PartialEq for GroupMemberKeys {
     fn eq(&self, other: &Self) -> bool {
         self.ordered_member_keys == other.ordered_member_keys
+            && self.observer_keys == other.observer_keys
     }
 }
 
@@ -1091,8 +1534,8 @@ pub struct ReplicationGroupRecord {
     pub group_id: GroupId,
     /// Canonical exact member-key order for the group.
     pub member_keys: GroupMemberKeys,
-    /// Position of the local member within `member_keys`.
-    pub local_member_index: MemberIndex,
+    /// Position of the local member within `member_keys`, or `None` when it only observes.
+    pub local_member_index: Option<MemberIndex>,
     /// Dataset schemas fixed for the lifetime of this group.
     pub group_schema: GroupSchema,
     /// Last applied version vector stored for this group.
@@ -1140,7 +1583,8 @@ impl ReplicationGroupRecord {
 
     /// Return the local member identity referenced by `local_member_index`.
     ///
-    /// Store implementations must preserve the invariant that `local_member_index
+    /// Returns `None` when the local replica only observes this group. Store
+    /// implementations must preserve the invariant that `local_member_index
     /// < member_keys.len()`.
     ///
     /// # Panics
@@ -1148,12 +1592,13 @@ impl ReplicationGroupRecord {
     /// Panics if the stored local member index is outside the stored member-key
     /// order.
     #[must_use]
-    pub fn local_member(&self) -> &MemberIdentity {
-        &self
+    pub fn local_member(&self) -> Option<&MemberIdentity> {
+        let local_member_index = self.local_member_index?;
+        let local_member_key = self
             .member_keys
-            .member_key_at_index(self.local_member_index)
-            .expect("replication group local member index must be in bounds")
-            .member_id
+            .member_key_at_index(local_member_index)
+            .expect("replication group local member index must be in bounds");
+        Some(&local_member_key.member_id)
     }
 
     /// Return whether another active record has the same group definition.
@@ -1205,8 +1650,8 @@ pub struct ReplicationGroupMaterialRecord {
     pub group_id: GroupId,
     /// Canonical exact member-key order for the group.
     pub member_keys: GroupMemberKeys,
-    /// Position of the local member within `member_keys`.
-    pub local_member_index: MemberIndex,
+    /// Position of the local member within `member_keys`, or `None` when it only observes.
+    pub local_member_index: Option<MemberIndex>,
     /// Dataset schemas fixed for the lifetime of this group.
     pub group_schema: GroupSchema,
     /// Already-encrypted group-security material needed by runtime operation.
@@ -1223,7 +1668,7 @@ impl ReplicationGroupMaterialRecord {
         &self,
         group_id: GroupId,
         member_keys: &GroupMemberKeys,
-        local_member_index: MemberIndex,
+        local_member_index: Option<MemberIndex>,
         group_schema: &GroupSchema,
     ) -> bool {
         self.group_id == group_id
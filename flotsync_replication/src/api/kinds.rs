@@ -0,0 +1,538 @@
+//! Typed document kinds declared by applications.
+//!
+//! A document is one row of a dataset. Applications that share a workspace
+//! declare which Rust payload type lives in which dataset through a
+//! [`DocumentKindRegistry`], so rows are only ever decoded and written with the
+//! payload codec of their declared kind.
+//!
+//! Every document also records the payload version of its kind it was written
+//! with. Opening an older document runs the registered migrations up to the
+//! current version, while documents written by newer application code are
+//! refused instead of being decoded with an outdated codec.
+
+use super::*;
+use flotsync_data_types::{
+    Field,
+    PrimitiveType,
+    schema::{BasicDataType, FieldValueBuildError, NullableBasicDataType},
+};
+use std::any::TypeId;
+
+/// Field that records the payload version a document was written with.
+///
+/// The registry adds this field to the schema of every declared kind, so kind
+/// schemas must not define it themselves.
+pub const PAYLOAD_VERSION_FIELD: &str = "payload_version";
+
+/// Rewrite the field values of one document from a payload version to the next.
+pub type PayloadMigration =
+    fn(&mut HashMap<String, NullableBasicValue>) -> Result<(), DecodeValueError>;
+
+/// A typed document payload stored as one row of a dataset.
+pub trait DocumentKind: Sized + 'static {
+    /// Name identifying this kind across every application sharing a workspace.
+    const NAME: &'static str;
+
+    /// Current payload version written by this code.
+    ///
+    /// Increase it whenever the payload format changes, and register a
+    /// [`PayloadMigration`] from the previous version.
+    const VERSION: u32 = 1;
+
+    /// Schema that every document of this kind is stored with, without
+    /// [`PAYLOAD_VERSION_FIELD`].
+    fn schema() -> SchemaSource;
+
+    /// Decode a payload of the current [`Self::VERSION`] from its projected row values.
+    ///
+    /// # Errors
+    ///
+    /// See `DecodeValueError` for failure conditions.
+    fn decode(row: &dyn RowValueRead) -> Result<Self, DecodeValueError>;
+
+    /// Encode this payload as field values for [`Self::schema`].
+    fn encode(&self) -> RowValuesPatch;
+}
+
+/// A decoded document together with the row it was read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedDoc<K> {
+    /// Row that holds the document.
+    pub row_id: RowId,
+    /// Decoded document payload.
+    pub document: K,
+}
+
+impl<K> TypedDoc<K> {
+    /// Return the decoded document payload.
+    #[must_use]
+    pub fn into_document(self) -> K {
+        self.document
+    }
+}
+
+impl<K> std::ops::Deref for TypedDoc<K> {
+    type Target = K;
+
+    fn deref(&self) -> &K {
+        &self.document
+    }
+}
+
+/// Document kinds declared for the datasets an application works with.
+///
+/// Each dataset holds documents of exactly one kind. The registry checks group
+/// schemas, outgoing mutations and incoming changes against the declared kind,
+/// and decodes rows into [`TypedDoc`] handles.
+#[derive(Clone, Default)]
+pub struct DocumentKindRegistry {
+    datasets: HashMap<DatasetId, RegisteredKind>,
+    migrations: HashMap<(TypeId, u32), PayloadMigration>,
+}
+
+impl DocumentKindRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare that `dataset_id` holds documents of kind `K`.
+    ///
+    /// Registering the same kind twice for one dataset is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocumentKindError::ConflictingKind`] if the dataset is already
+    /// declared with a different kind, and [`DocumentKindError::ReservedField`]
+    /// if the kind's schema defines [`PAYLOAD_VERSION_FIELD`].
+    pub fn register<K>(&mut self, dataset_id: DatasetId) -> Result<(), DocumentKindError>
+    where
+        K: DocumentKind,
+    {
+        match self.datasets.entry(dataset_id) {
+            Entry::Vacant(entry) => {
+                entry.insert(RegisteredKind::of::<K>()?);
+                Ok(())
+            }
+            Entry::Occupied(entry) if entry.get().type_id == TypeId::of::<K>() => Ok(()),
+            Entry::Occupied(entry) => document_kind::ConflictingKindSnafu {
+                dataset_id: entry.key().clone(),
+                registered: entry.get().name,
+                requested: K::NAME,
+            }
+            .fail(),
+        }
+    }
+
+    /// Register the migration of kind `K` documents from `from_version` to the next version.
+    ///
+    /// Registering another migration for the same version replaces the earlier one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocumentKindError::InvalidMigration`] unless `from_version`
+    /// is at least 1 and below `K::VERSION`.
+    pub fn register_migration<K>(
+        &mut self,
+        from_version: u32,
+        migration: PayloadMigration,
+    ) -> Result<(), DocumentKindError>
+    where
+        K: DocumentKind,
+    {
+        ensure!(
+            (1..K::VERSION).contains(&from_version),
+            document_kind::InvalidMigrationSnafu {
+                kind: K::NAME,
+                from_version,
+                current_version: K::VERSION,
+            }
+        );
+        self.migrations
+            .insert((TypeId::of::<K>(), from_version), migration);
+        Ok(())
+    }
+
+    /// Return the kind name declared for `dataset_id`, if any.
+    #[must_use]
+    pub fn kind_name(&self, dataset_id: &DatasetId) -> Option<&'static str> {
+        self.datasets.get(dataset_id).map(|kind| kind.name)
+    }
+
+    /// Build the group schema that stores every declared kind in its dataset.
+    #[must_use]
+    pub fn group_schema(&self) -> GroupSchema {
+        GroupSchema::new(
+            self.datasets
+                .iter()
+                .map(|(dataset_id, kind)| (dataset_id.clone(), kind.schema.clone()))
+                .collect(),
+        )
+    }
+
+    /// Check that `group_schema` stores every declared dataset with its kind's schema.
+    ///
+    /// Datasets the registry does not declare are ignored, so one group may
+    /// also carry datasets that are accessed untyped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocumentKindError::SchemaMismatch`] for the first declared
+    /// dataset whose schema differs from its kind's schema.
+    pub fn validate_group_schema(
+        &self,
+        group_schema: &GroupSchema,
+    ) -> Result<(), DocumentKindError> {
+        for dataset in group_schema.datasets() {
+            let Some(kind) = self.datasets.get(&dataset.dataset_id) else {
+                continue;
+            };
+            ensure!(
+                kind.schema.as_schema() == dataset.schema.as_schema(),
+                document_kind::SchemaMismatchSnafu {
+                    dataset_id: dataset.dataset_id,
+                    kind: kind.name,
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// Check that one mutation only writes fields of its dataset's declared kind.
+    ///
+    /// Mutations on undeclared datasets are accepted unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocumentKindError::UnknownField`] if an upsert names a field
+    /// the kind's schema does not have, and
+    /// [`DocumentKindError::InvalidFieldValue`] for a value that cannot be
+    /// stored in its field.
+    pub fn validate_mutation(&self, mutation: &RowMutation) -> Result<(), DocumentKindError> {
+        let RowMutation::Upsert { row_id, row } = mutation else {
+            return Ok(());
+        };
+        let Some(kind) = self.datasets.get(&row_id.dataset_id) else {
+            return Ok(());
+        };
+        let schema = kind.schema.as_schema();
+        for (field_name, value) in &row.fields {
+            let field = schema.columns.get(field_name.as_str()).with_context(|| {
+                document_kind::UnknownFieldSnafu {
+                    row_id: row_id.clone(),
+                    kind: kind.name,
+                    field_name: field_name.clone(),
+                }
+            })?;
+            field
+                .can_initial(value)
+                .map_err(Box::new)
+                .with_context(|_| document_kind::InvalidFieldValueSnafu {
+                    row_id: row_id.clone(),
+                    kind: kind.name,
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Build an upsert that stores `document` at `row_id`.
+    ///
+    /// The upsert records `K::VERSION` as the document's payload version, so
+    /// storing a migrated document also upgrades it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocumentKindError`] if `row_id` is not in a dataset declared
+    /// with kind `K`, or if the payload codec produced invalid field values.
+    pub fn upsert<K>(&self, row_id: RowId, document: &K) -> Result<RowMutation, DocumentKindError>
+    where
+        K: DocumentKind,
+    {
+        self.ensure_kind::<K>(&row_id.dataset_id)?;
+        let mut row = document.encode();
+        row.fields.insert(
+            PAYLOAD_VERSION_FIELD.to_owned(),
+            u64::from(K::VERSION).into(),
+        );
+        let mutation = RowMutation::Upsert { row_id, row };
+        self.validate_mutation(&mutation)?;
+        Ok(mutation)
+    }
+
+    /// Decode the document stored at `row_id` as kind `K`.
+    ///
+    /// Documents written with an older payload version are migrated to
+    /// `K::VERSION` before decoding; the stored row is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocumentKindError`] if `row_id` is not in a dataset declared
+    /// with kind `K`, or if the row cannot be decoded as `K`. Documents written
+    /// with a newer payload version than `K::VERSION` are refused with
+    /// [`DocumentKindError::NewerPayloadVersion`].
+    pub fn open<K>(
+        &self,
+        row_id: RowId,
+        row: &dyn RowValueRead,
+    ) -> Result<TypedDoc<K>, DocumentKindError>
+    where
+        K: DocumentKind,
+    {
+        let kind = self.ensure_kind::<K>(&row_id.dataset_id)?;
+        let version = row
+            .get_field_value::<u64>(PAYLOAD_VERSION_FIELD)
+            .map_err(Box::new)
+            .with_context(|_| document_kind::DecodeSnafu {
+                row_id: row_id.clone(),
+                kind: K::NAME,
+            })?
+            .into_owned();
+        ensure!(
+            version <= u64::from(K::VERSION),
+            document_kind::NewerPayloadVersionSnafu {
+                row_id: row_id.clone(),
+                kind: K::NAME,
+                version,
+                supported_version: K::VERSION,
+            }
+        );
+        let decoded = if version == u64::from(K::VERSION) {
+            K::decode(row)
+        } else {
+            let fields = self.migrate::<K>(&row_id, kind, version, row)?;
+            K::decode(&RowValues::from_fields_unchecked(fields))
+        };
+        let document = decoded
+            .map_err(Box::new)
+            .with_context(|_| document_kind::DecodeSnafu {
+                row_id: row_id.clone(),
+                kind: K::NAME,
+            })?;
+        Ok(TypedDoc { row_id, document })
+    }
+
+    /// Decode the document carried by one incoming row change as kind `K`.
+    ///
+    /// Returns `Ok(None)` for deletes.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::open`].
+    pub fn open_change<K>(
+        &self,
+        change: &RowChange,
+    ) -> Result<Option<TypedDoc<K>>, DocumentKindError>
+    where
+        K: DocumentKind,
+    {
+        match change {
+            RowChange::Upsert { row_id, row } => self.open(row_id.clone(), row.as_ref()).map(Some),
+            RowChange::Delete { row_id } => {
+                self.ensure_kind::<K>(&row_id.dataset_id)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn ensure_kind<K>(&self, dataset_id: &DatasetId) -> Result<&RegisteredKind, DocumentKindError>
+    where
+        K: DocumentKind,
+    {
+        let kind =
+            self.datasets
+                .get(dataset_id)
+                .context(document_kind::UndeclaredDatasetSnafu {
+                    dataset_id: dataset_id.clone(),
+                })?;
+        ensure!(
+            kind.type_id == TypeId::of::<K>(),
+            document_kind::KindMismatchSnafu {
+                dataset_id: dataset_id.clone(),
+                registered: kind.name,
+                requested: K::NAME,
+            }
+        );
+        Ok(kind)
+    }
+
+    fn migrate<K>(
+        &self,
+        row_id: &RowId,
+        kind: &RegisteredKind,
+        version: u64,
+        row: &dyn RowValueRead,
+    ) -> Result<HashMap<String, NullableBasicValue>, DocumentKindError>
+    where
+        K: DocumentKind,
+    {
+        let mut fields = kind
+            .schema
+            .as_schema()
+            .columns
+            .keys()
+            .filter_map(|field_name| {
+                let value = row.get_value(field_name)?;
+                Some((field_name.clone(), value.into_owned()))
+            })
+            .collect::<HashMap<_, _>>();
+        let version = u32::try_from(version).expect("version is below K::VERSION");
+        for from_version in version..K::VERSION {
+            let migration = self
+                .migrations
+                .get(&(TypeId::of::<K>(), from_version))
+                .context(document_kind::MissingMigrationSnafu {
+                    kind: K::NAME,
+                    from_version,
+                })?;
+            migration(&mut fields).map_err(Box::new).with_context(|_| {
+                document_kind::MigrationSnafu {
+                    row_id: row_id.clone(),
+                    kind: K::NAME,
+                    from_version,
+                }
+            })?;
+        }
+        Ok(fields)
+    }
+}
+
+impl fmt::Debug for DocumentKindRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut datasets = self
+            .datasets
+            .iter()
+            .map(|(dataset_id, kind)| (dataset_id, kind.name))
+            .collect::<Vec<_>>();
+        datasets.sort();
+        f.debug_struct("DocumentKindRegistry")
+            .field("datasets", &datasets)
+            .field("migration_count", &self.migrations.len())
+            .finish()
+    }
+}
+
+/// Failures raised when documents do not match their declared kind.
+#[derive(Debug, Snafu)]
+#[snafu(module(document_kind))]
+pub enum DocumentKindError {
+    /// The dataset was already declared with a different kind.
+    #[snafu(display(
+        "Dataset '{dataset_id}' is declared as '{registered}' and cannot also hold '{requested}'."
+    ))]
+    ConflictingKind {
+        dataset_id: DatasetId,
+        registered: &'static str,
+        requested: &'static str,
+    },
+    /// The kind's schema defines the field the registry reserves for payload versions.
+    #[snafu(display(
+        "Kind '{kind}' must not define the reserved field '{PAYLOAD_VERSION_FIELD}'."
+    ))]
+    ReservedField { kind: &'static str },
+    /// A migration was registered for a version it cannot migrate from.
+    #[snafu(display(
+        "Kind '{kind}' cannot migrate from version {from_version}; its current version is {current_version}."
+    ))]
+    InvalidMigration {
+        kind: &'static str,
+        from_version: u32,
+        current_version: u32,
+    },
+    /// The dataset has no declared kind.
+    #[snafu(display("Dataset '{dataset_id}' has no declared document kind."))]
+    UndeclaredDataset { dataset_id: DatasetId },
+    /// The dataset holds a different kind than the one requested.
+    #[snafu(display("Dataset '{dataset_id}' holds '{registered}' documents, not '{requested}'."))]
+    KindMismatch {
+        dataset_id: DatasetId,
+        registered: &'static str,
+        requested: &'static str,
+    },
+    /// A group stores the dataset with a schema other than its kind's schema.
+    #[snafu(display(
+        "Dataset '{dataset_id}' is stored with a schema that does not match kind '{kind}'."
+    ))]
+    SchemaMismatch {
+        dataset_id: DatasetId,
+        kind: &'static str,
+    },
+    /// A mutation writes a field the kind's schema does not have.
+    #[snafu(display("Row {row_id} sets field '{field_name}', which kind '{kind}' does not have."))]
+    UnknownField {
+        row_id: RowId,
+        kind: &'static str,
+        field_name: String,
+    },
+    /// A mutation writes a value its field does not accept.
+    #[snafu(display("Row {row_id} carried a value incompatible with kind '{kind}': {source}"))]
+    InvalidFieldValue {
+        row_id: RowId,
+        kind: &'static str,
+        source: Box<FieldValueBuildError>,
+    },
+    /// A document was written by newer application code than this one.
+    #[snafu(display(
+        "Row {row_id} holds '{kind}' version {version}, but this code only supports up to version {supported_version}."
+    ))]
+    NewerPayloadVersion {
+        row_id: RowId,
+        kind: &'static str,
+        version: u64,
+        supported_version: u32,
+    },
+    /// No migration is registered for one version an older document needs to pass.
+    #[snafu(display("Kind '{kind}' has no migration registered from version {from_version}."))]
+    MissingMigration {
+        kind: &'static str,
+        from_version: u32,
+    },
+    /// A registered migration failed on one document.
+    #[snafu(display("Row {row_id} could not be migrated from '{kind}' version {from_version}."))]
+    Migration {
+        row_id: RowId,
+        kind: &'static str,
+        from_version: u32,
+        source: Box<DecodeValueError>,
+    },
+    /// A row could not be decoded with its kind's payload codec.
+    #[snafu(display("Row {row_id} could not be decoded as kind '{kind}'."))]
+    Decode {
+        row_id: RowId,
+        kind: &'static str,
+        source: Box<DecodeValueError>,
+    },
+}
+
+#[derive(Clone)]
+struct RegisteredKind {
+    name: &'static str,
+    type_id: TypeId,
+    schema: SchemaSource,
+}
+
+impl RegisteredKind {
+    fn of<K>() -> Result<Self, DocumentKindError>
+    where
+        K: DocumentKind,
+    {
+        let mut schema = K::schema().as_schema().clone();
+        let version_field = Field::latest_value_wins(
+            PAYLOAD_VERSION_FIELD,
+            NullableBasicDataType::NonNull(BasicDataType::Primitive(PrimitiveType::UInt)),
+        )
+        .with_default(1_u64)
+        .expect("a UInt default is valid for a UInt field");
+        let previous = schema
+            .columns
+            .insert(PAYLOAD_VERSION_FIELD.to_owned(), version_field);
+        ensure!(
+            previous.is_none(),
+            document_kind::ReservedFieldSnafu { kind: K::NAME }
+        );
+        Ok(Self {
+            name: K::NAME,
+            type_id: TypeId::of::<K>(),
+            schema: SchemaSource::from(schema),
+        })
+    }
+}
@@ -1,4 +1,5 @@
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use bytes::Bytes;
 use enumset::{EnumSet, EnumSetType};
 use flotsync_core::{
     GroupId,
@@ -6,7 +7,7 @@ use flotsync_core::{
     MemberIndex,
     member::{Identifier, TrieMap},
     membership::{GroupMembers, GroupMembersError},
-    versions::{UpdateId, VersionVector},
+    versions::{UpdateId, VersionVector, VersionVectorParseError},
 };
 use flotsync_data_types::schema::{
     Schema,
@@ -26,9 +27,10 @@ use snafu::prelude::*;
 use std::{
     collections::{HashMap, HashSet, hash_map::Entry},
     fmt,
-    num::NonZeroUsize,
+    num::{NonZeroU32, NonZeroU64, NonZeroUsize},
     str::FromStr,
     sync::Arc,
+    time::Duration,
 };
 
 mod errors;
@@ -36,6 +38,13 @@ mod ids;
 pub mod providers;
 pub mod security;
 
+pub use crate::delivery::compression::{
+    CompressionCodec,
+    CompressionCounters,
+    CompressionOffer,
+    CompressionPolicy,
+    PayloadCounters,
+};
 pub use errors::*;
 pub use flotsync_data_types::{
     Decode,
@@ -48,6 +57,8 @@ pub use flotsync_data_types::{
     RowValues,
     schema::datamodel::SchemaSource,
 };
+pub use flotsync_messages::encoding::PayloadEncoding;
+pub use flotsync_routes::liveness::PeerLiveness;
 pub use flotsync_security::{LocalStoreSecretProfile, StoreSecretKeyId};
 pub use ids::*;
 
@@ -66,16 +77,26 @@ macro_rules! row_values {
     }};
 }
 
+mod blobs;
 mod changes;
+mod checkpoints;
+mod events;
 mod groups;
+mod kinds;
+mod quarantine;
 mod security_material;
 mod snapshots;
 mod store;
 #[cfg(test)]
 mod tests;
 
+pub use blobs::*;
 pub use changes::*;
+pub use checkpoints::*;
+pub use events::*;
 pub use groups::*;
+pub use kinds::*;
+pub use quarantine::*;
 pub use security_material::*;
 pub use snapshots::*;
 pub use store::*;
@@ -0,0 +1,43 @@
+//! Inbound group broadcasts that the runtime rejected as invalid.
+
+use super::*;
+use crate::delivery::shared::MessageId;
+use std::time::SystemTime;
+
+/// Identifier of one quarantined delivery, unique while the runtime is loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct QuarantineId(pub u64);
+
+impl fmt::Display for QuarantineId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "q#{}", self.0)
+    }
+}
+
+/// One inbound group broadcast that the runtime rejected instead of applying it.
+///
+/// The runtime rejects a delivery it cannot apply, for example an update that
+/// references operations that do not exist, or one for a group or dataset that
+/// is not known locally. Instead of losing the payload, the runtime keeps the
+/// most recent rejections in a bounded quarantine, listed by
+/// [`ReplicationApi::quarantined_deliveries`]. A delivery can be handed back
+/// with [`ReplicationApi::retry_quarantined_delivery`], for example once the
+/// state it was missing has been installed.
+///
+/// The quarantine is kept in memory and starts empty whenever the runtime is
+/// loaded. Only deliveries that reached the runtime as plaintext are quarantined.
+/// Envelopes that the delivery layer already rejected, such as those with an
+/// invalid signature or a payload that cannot be decrypted, never do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuarantinedDelivery {
+    pub id: QuarantineId,
+    pub group_id: GroupId,
+    pub sender: MemberIdentity,
+    pub message_id: MessageId,
+    /// Why the runtime rejected the delivery.
+    pub reason: String,
+    /// Size of the plaintext runtime message payload, in bytes.
+    pub payload_bytes: usize,
+    /// When the delivery was rejected.
+    pub quarantined_at: SystemTime,
+}
@@ -149,6 +149,70 @@ pub struct Summary {
     pub has_versions: VersionVector,
 }
 
+/// How far the members of a group have acknowledged applying its updates.
+///
+/// Peers acknowledge updates as they apply them. Acknowledgements are kept in
+/// memory only, so after a restart remote members start at the initial vector
+/// until they acknowledge again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AcknowledgedVersions {
+    /// Replication group described by these acknowledgements.
+    pub group_id: GroupId,
+    /// Versions acknowledged by each member, in canonical member order.
+    ///
+    /// The local member's entry is its own applied version vector. Updates
+    /// produced locally that a member's entry covers need not be retained for
+    /// re-sending to that member.
+    pub members: Vec<VersionVector>,
+    /// Versions every member has acknowledged, the greatest lower bound of `members`.
+    ///
+    /// No member will request updates covered by this vector again, so they are
+    /// safe to compact.
+    pub stable_versions: VersionVector,
+}
+
+/// Per-member sync health of one group, combining peer liveness with acknowledgements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupSyncHealth {
+    /// Replication group described by this report.
+    pub group_id: GroupId,
+    /// Health of each member, in canonical member order.
+    pub members: Vec<MemberSyncHealth>,
+}
+
+/// Sync health of one group member.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberSyncHealth {
+    /// Member described by this entry.
+    pub member: MemberIdentity,
+    /// Liveness reported by the local failure detector.
+    ///
+    /// `None` until the member has been heard from at least once. The local
+    /// member is always [`PeerLiveness::Alive`].
+    pub liveness: Option<PeerLiveness>,
+    /// Versions the member has acknowledged applying.
+    pub acknowledged_versions: VersionVector,
+    /// Whether the member has acknowledged every update applied locally.
+    pub up_to_date: bool,
+}
+
+/// Outcome of one bounded [`ReplicationApi::run_sync_step`] call.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncStepProgress {
+    /// Sync sessions started, one per reachable remote member of every hosted group.
+    pub sessions_started: usize,
+    /// Sessions whose peer answered with its group summary within the budget.
+    pub sessions_answered: usize,
+    /// Groups still missing versions that an answering peer reported, in ascending order.
+    pub groups_behind: Vec<GroupId>,
+    /// Whether every peer answered and everything they reported was applied locally.
+    ///
+    /// When this is `false` the budget ran out first. Catch-up continues in the background for
+    /// as long as the runtime keeps running, so calling the method again resumes where this
+    /// step stopped.
+    pub completed: bool,
+}
+
 /// One row entry in an initial dataset's value rows.
 #[derive(Clone, PartialEq, Eq)]
 pub struct InitialValueRow {
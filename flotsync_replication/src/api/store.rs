@@ -46,6 +46,28 @@ pub struct DatasetRowStateBatch {
     pub next_after: Option<RowKey>,
 }
 
+/// Stored byte sizes of one replication group's rows and update log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupStorageSizes {
+    /// Replication group these sizes belong to.
+    pub group_id: GroupId,
+    /// Size of every stored row, ordered by dataset and row key.
+    pub rows: Vec<RowStorageSize>,
+    /// Total size of every update retained in the group's update log.
+    pub update_log_bytes: u64,
+}
+
+/// Stored byte size of one dataset row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowStorageSize {
+    /// Dataset that owns the row.
+    pub dataset_id: DatasetId,
+    /// Stable row key in `dataset_id`.
+    pub row_key: RowKey,
+    /// Size of the stored row snapshot, tombstones included.
+    pub snapshot_bytes: u64,
+}
+
 /// Complete row state snapshot used by replication storage.
 pub type ReplicationRowStateSnapshot = RowStateSnapshot<'static, UpdateId>;
 
@@ -88,6 +110,11 @@ pub enum DatasetRowStateWrite {
         row_key: RowKey,
         snapshot: ReplicationRowStateSnapshot,
     },
+    /// Remove the retained tombstone for `row_key` from storage.
+    ///
+    /// Purging an active row is rejected, and purging a missing row does
+    /// nothing. The patch's `last_changed_versions` is not used by this action.
+    Purge { row_key: RowKey },
 }
 
 /// Iterator used to stream requested row keys into one store transaction.
@@ -215,6 +242,15 @@ pub trait ReplicationStoreReadTransaction: Send {
         limit: NonZeroUsize,
     ) -> BoxFuture<'a, Result<DatasetRowStateBatch, StoreError>>;
 
+    /// Load the stored byte sizes of every row and retained update of one group.
+    ///
+    /// Sizes count the encoded records as the store keeps them, without index or
+    /// page overhead. Unknown groups report no rows and an empty update log.
+    fn load_group_storage_sizes<'a>(
+        &'a mut self,
+        group_id: &'a GroupId,
+    ) -> BoxFuture<'a, Result<GroupStorageSizes, StoreError>>;
+
     /// Load all unresolved listener-mediated group decisions.
     fn load_pending_group_decisions(
         &mut self,
@@ -1,7 +1,8 @@
 //! Replication API tests.
 
 use super::*;
-use crate::test_support::docs_group_schema;
+use crate::test_support::{docs_dataset_id, docs_group_schema, docs_schema_source};
+use std::assert_matches;
 
 fn member_key_id<const N: usize>(segments: [&str; N], fingerprint_seed: u8) -> MemberKeyId {
     MemberKeyId {
@@ -120,7 +121,7 @@ fn group_material_definition_matching_excludes_security_material() {
     let material = ReplicationGroupMaterialRecord {
         group_id,
         member_keys: member_keys.clone(),
-        local_member_index: MemberIndex::new(0),
+        local_member_index: Some(MemberIndex::new(0)),
         group_schema: group_schema.clone(),
         security_material: current_slice_placeholder_group_security_material(group_id),
     };
@@ -138,7 +139,7 @@ fn group_material_definition_matching_excludes_security_material() {
     assert!(material.matches_definition(
         group_id,
         &member_keys,
-        MemberIndex::new(0),
+        Some(MemberIndex::new(0)),
         &group_schema,
     ));
     assert!(active.matches_definition(&different_security_active));
@@ -146,7 +147,7 @@ fn group_material_definition_matching_excludes_security_material() {
     assert!(!material.matches_definition(
         GroupId(uuid::Uuid::from_u128(91_099)),
         &member_keys,
-        MemberIndex::new(0),
+        Some(MemberIndex::new(0)),
         &group_schema,
     ));
 }
@@ -169,7 +170,7 @@ fn active_group_decomposition_preserves_progress_and_lifecycle() {
             1,
         )])
         .expect("test group member keys should build"),
-        local_member_index: MemberIndex::new(0),
+        local_member_index: Some(MemberIndex::new(0)),
         group_schema: GroupSchema::default(),
         version_vector: versions.clone(),
         lifecycle: lifecycle.clone(),
@@ -254,3 +255,313 @@ fn member_public_keys_alternate_debug_prints_base64url() {
 }"#,
     );
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Note {
+    title: String,
+}
+
+impl DocumentKind for Note {
+    const NAME: &'static str = "note";
+
+    fn schema() -> SchemaSource {
+        docs_schema_source()
+    }
+
+    fn decode(row: &dyn RowValueRead) -> Result<Self, DecodeValueError> {
+        let title = row.get_field_value::<str>("title")?.into_owned();
+        Ok(Self { title })
+    }
+
+    fn encode(&self) -> RowValuesPatch {
+        row_values! { "title" => self.title.as_str() }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Counter;
+
+impl DocumentKind for Counter {
+    const NAME: &'static str = "counter";
+
+    fn schema() -> SchemaSource {
+        SchemaSource::from(Schema::from_fields([
+            flotsync_data_types::Field::monotonic_counter("count"),
+        ]))
+    }
+
+    fn decode(_row: &dyn RowValueRead) -> Result<Self, DecodeValueError> {
+        Ok(Self)
+    }
+
+    fn encode(&self) -> RowValuesPatch {
+        RowValuesPatch::default()
+    }
+}
+
+/// Second payload version of [`Note`], which stores titles in upper case.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ShoutedNote {
+    title: String,
+}
+
+impl DocumentKind for ShoutedNote {
+    const NAME: &'static str = "note";
+    const VERSION: u32 = 2;
+
+    fn schema() -> SchemaSource {
+        docs_schema_source()
+    }
+
+    fn decode(row: &dyn RowValueRead) -> Result<Self, DecodeValueError> {
+        let title = row.get_field_value::<str>("title")?.into_owned();
+        Ok(Self { title })
+    }
+
+    fn encode(&self) -> RowValuesPatch {
+        row_values! { "title" => self.title.as_str() }
+    }
+}
+
+fn shout_title(fields: &mut HashMap<String, NullableBasicValue>) -> Result<(), DecodeValueError> {
+    let title = RowValues::from_fields_unchecked(fields.clone())
+        .get_field_value::<str>("title")?
+        .to_uppercase();
+    fields.insert("title".to_owned(), title.into());
+    Ok(())
+}
+
+fn note_row(title: &str, payload_version: u64) -> RowValues {
+    RowValues::from_fields_unchecked(HashMap::from([
+        ("title".to_owned(), title.into()),
+        (PAYLOAD_VERSION_FIELD.to_owned(), payload_version.into()),
+    ]))
+}
+
+fn note_row_id() -> RowId {
+    RowId {
+        group_id: GroupId(uuid::Uuid::from_u128(41)),
+        dataset_id: docs_dataset_id(),
+        row_key: RowKey(uuid::Uuid::from_u128(42)),
+    }
+}
+
+#[test]
+fn document_kind_registry_rejects_conflicting_kinds() {
+    let mut registry = DocumentKindRegistry::new();
+    let dataset_id = docs_dataset_id();
+    registry.register::<Note>(dataset_id.clone()).unwrap();
+    registry.register::<Note>(dataset_id.clone()).unwrap();
+
+    assert_matches!(
+        registry.register::<Counter>(dataset_id.clone()),
+        Err(DocumentKindError::ConflictingKind {
+            registered: "note",
+            requested: "counter",
+            ..
+        })
+    );
+    assert_eq!(registry.kind_name(&dataset_id), Some("note"));
+    let group_schema = registry.group_schema();
+    let stored_schema = group_schema.schema(&dataset_id).unwrap().as_schema();
+    assert!(stored_schema.columns.contains_key("title"));
+    assert!(stored_schema.columns.contains_key(PAYLOAD_VERSION_FIELD));
+    registry.validate_group_schema(&group_schema).unwrap();
+}
+
+#[test]
+fn document_kind_registry_rejects_mismatched_group_schema() {
+    let mut registry = DocumentKindRegistry::new();
+    let dataset_id = docs_dataset_id();
+    registry.register::<Counter>(dataset_id).unwrap();
+
+    assert_matches!(
+        registry.validate_group_schema(&docs_group_schema()),
+        Err(DocumentKindError::SchemaMismatch {
+            kind: "counter",
+            ..
+        })
+    );
+}
+
+#[test]
+fn document_kind_registry_roundtrips_typed_documents() {
+    let mut registry = DocumentKindRegistry::new();
+    registry.register::<Note>(docs_dataset_id()).unwrap();
+    let note = Note {
+        title: "Groceries".to_owned(),
+    };
+
+    let mutation = registry.upsert(note_row_id(), &note).unwrap();
+    let RowMutation::Upsert { row, .. } = &mutation else {
+        panic!("expected an upsert");
+    };
+    let change = RowChange::Upsert {
+        row_id: note_row_id(),
+        row: Arc::new(RowValues::from_fields_unchecked(row.fields.clone())),
+    };
+    let opened = registry.open_change::<Note>(&change).unwrap().unwrap();
+    assert_eq!(opened.row_id, note_row_id());
+    assert_eq!(opened.title, "Groceries");
+
+    assert_matches!(
+        registry.open_change::<Counter>(&change),
+        Err(DocumentKindError::KindMismatch {
+            registered: "note",
+            requested: "counter",
+            ..
+        })
+    );
+    let delete = RowChange::Delete {
+        row_id: note_row_id(),
+    };
+    assert_eq!(registry.open_change::<Note>(&delete).unwrap(), None);
+}
+
+#[test]
+fn checkpoint_documents_roundtrip_label_and_versions() {
+    let registry = checkpoint_kinds();
+    let row_id = RowId {
+        group_id: GroupId(uuid::Uuid::from_u128(41)),
+        dataset_id: checkpoints_dataset_id(),
+        row_key: RowKey(uuid::Uuid::from_u128(43)),
+    };
+    let checkpoint = Checkpoint {
+        label: "v1.0 sent to client".to_owned(),
+        versions: VersionVector::initial(NonZeroUsize::new(3).unwrap()).with_update_applied(
+            UpdateId {
+                version: 1,
+                node_index: 1,
+            },
+        ),
+    };
+
+    let mutation = registry.upsert(row_id.clone(), &checkpoint).unwrap();
+    let RowMutation::Upsert { row, .. } = &mutation else {
+        panic!("expected an upsert");
+    };
+    let change = RowChange::Upsert {
+        row_id: row_id.clone(),
+        row: Arc::new(RowValues::from_fields_unchecked(row.fields.clone())),
+    };
+    let opened = registry
+        .open_change::<Checkpoint>(&change)
+        .unwrap()
+        .unwrap();
+    assert_eq!(opened.row_id, row_id);
+    assert_eq!(opened.document, checkpoint);
+}
+
+#[test]
+fn document_kind_registry_validates_mutations() {
+    let mut registry = DocumentKindRegistry::new();
+    registry.register::<Note>(docs_dataset_id()).unwrap();
+
+    let unknown_field = RowMutation::Upsert {
+        row_id: note_row_id(),
+        row: row_values! { "body" => "text" },
+    };
+    assert_matches!(
+        registry.validate_mutation(&unknown_field),
+        Err(DocumentKindError::UnknownField { field_name, .. }) if field_name == "body"
+    );
+    let invalid_value = RowMutation::Upsert {
+        row_id: note_row_id(),
+        row: row_values! { "title" => 7_u64 },
+    };
+    assert_matches!(
+        registry.validate_mutation(&invalid_value),
+        Err(DocumentKindError::InvalidFieldValue { kind: "note", .. })
+    );
+    registry
+        .validate_mutation(&RowMutation::Delete {
+            row_id: note_row_id(),
+        })
+        .unwrap();
+}
+
+#[test]
+fn document_kind_registry_migrates_older_documents() {
+    let mut registry = DocumentKindRegistry::new();
+    registry.register::<ShoutedNote>(docs_dataset_id()).unwrap();
+
+    assert_matches!(
+        registry.open::<ShoutedNote>(note_row_id(), &note_row("draft", 1)),
+        Err(DocumentKindError::MissingMigration {
+            kind: "note",
+            from_version: 1,
+        })
+    );
+
+    registry
+        .register_migration::<ShoutedNote>(1, shout_title)
+        .unwrap();
+    let opened = registry
+        .open::<ShoutedNote>(note_row_id(), &note_row("draft", 1))
+        .unwrap();
+    assert_eq!(opened.title, "DRAFT");
+    let current = registry
+        .open::<ShoutedNote>(note_row_id(), &note_row("Draft", 2))
+        .unwrap();
+    assert_eq!(current.title, "Draft");
+
+    let RowMutation::Upsert { row, .. } = registry.upsert(note_row_id(), &*opened).unwrap() else {
+        panic!("expected an upsert");
+    };
+    assert_eq!(
+        row.fields.get(PAYLOAD_VERSION_FIELD),
+        Some(&NullableBasicValue::from(2_u64))
+    );
+}
+
+#[test]
+fn document_kind_registry_refuses_newer_documents() {
+    let mut registry = DocumentKindRegistry::new();
+    registry.register::<Note>(docs_dataset_id()).unwrap();
+
+    assert_matches!(
+        registry.open::<Note>(note_row_id(), &note_row("DRAFT", 2)),
+        Err(DocumentKindError::NewerPayloadVersion {
+            version: 2,
+            supported_version: 1,
+            ..
+        })
+    );
+    assert_matches!(
+        registry.register_migration::<Note>(1, shout_title),
+        Err(DocumentKindError::InvalidMigration {
+            from_version: 1,
+            current_version: 1,
+            ..
+        })
+    );
+}
+
+#[test]
+fn document_kind_registry_reserves_the_payload_version_field() {
+    struct Versioned;
+
+    impl DocumentKind for Versioned {
+        const NAME: &'static str = "versioned";
+
+        fn schema() -> SchemaSource {
+            SchemaSource::from(Schema::from_fields([
+                flotsync_data_types::Field::monotonic_counter(PAYLOAD_VERSION_FIELD),
+            ]))
+        }
+
+        fn decode(_row: &dyn RowValueRead) -> Result<Self, DecodeValueError> {
+            Ok(Self)
+        }
+
+        fn encode(&self) -> RowValuesPatch {
+            RowValuesPatch::default()
+        }
+    }
+
+    let mut registry = DocumentKindRegistry::new();
+    assert_matches!(
+        registry.register::<Versioned>(docs_dataset_id()),
+        Err(DocumentKindError::ReservedField { kind: "versioned" })
+    );
+}
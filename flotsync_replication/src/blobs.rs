@@ -0,0 +1,942 @@
+//! Content-addressed storage and lazy peer transfer for large binary attachments.
+//!
+//! Documents reference blobs by [`BlobHash`] instead of embedding their bytes in CRDT
+//! operations. A [`BlobStore`] keeps each blob split into fixed-size chunks and counts the
+//! documents that still reference it, so unreferenced blobs can be collected.
+//! Peers that see a reference to a blob they do not hold fetch it chunk-by-chunk with
+//! [`BlobChunkRequest`] and assemble the replies in a [`BlobFetch`], which verifies the content
+//! hash before the blob is admitted into the local store.
+//!
+//! Every stored blob has at least one chunk, so every [`BlobChunk`] reply tells the requester
+//! the blob's shape, even for empty blobs.
+
+use bytes::{Bytes, BytesMut};
+use flotsync_core::GroupId;
+use flotsync_messages::{
+    buffa,
+    buffa::MessageView,
+    proto::{DecodeProto, DecodeProtoView, FromProtoDecodeError, ProtoCodec},
+    replication as replication_proto,
+    wire::{
+        WireValueDecodeError,
+        fixed_bytes_field,
+        group_id_from_wire_bytes,
+        group_id_to_wire_bytes,
+    },
+};
+use sha2::{Digest, Sha256};
+use snafu::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    num::NonZeroUsize,
+};
+
+/// Byte length of one [`BlobHash`].
+pub const BLOB_HASH_LENGTH: usize = 32;
+
+/// Chunk size used by [`BlobStore::default`].
+pub const DEFAULT_BLOB_CHUNK_SIZE: NonZeroUsize = NonZeroUsize::new(64 * 1024).unwrap();
+
+/// SHA-256 digest identifying the content of one blob.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlobHash([u8; BLOB_HASH_LENGTH]);
+
+impl BlobHash {
+    /// Compute the content hash of `data`.
+    #[must_use]
+    pub fn of(data: &[u8]) -> Self {
+        Self(Sha256::digest(data).into())
+    }
+
+    /// Build a hash from its raw bytes.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; BLOB_HASH_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    /// Return this hash's raw bytes.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; BLOB_HASH_LENGTH] {
+        &self.0
+    }
+
+    fn from_wire(raw: &[u8], field: &'static str) -> Result<Self, BlobError> {
+        let bytes = fixed_bytes_field(field, raw).context(InvalidWireValueSnafu { field })?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for BlobHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BlobHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BlobHash({self})")
+    }
+}
+
+/// Size and chunking information for one stored blob.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlobDescriptor {
+    pub hash: BlobHash,
+    pub length: u64,
+    pub chunk_count: u32,
+}
+
+/// Errors produced by blob storage, transfer, and protocol decoding.
+#[derive(Debug, Snafu)]
+pub enum BlobError {
+    #[snafu(display("Failed to decode blob protocol payload."))]
+    Decode { source: buffa::DecodeError },
+    #[snafu(display("Blob protocol field '{field}' was invalid: {source}"))]
+    InvalidWireValue {
+        field: &'static str,
+        source: WireValueDecodeError,
+    },
+    #[snafu(display("Blob {hash} is not present in the store."))]
+    UnknownBlob { hash: BlobHash },
+    #[snafu(display(
+        "Blob {hash} requires {chunk_count} chunks, which exceeds the protocol limit."
+    ))]
+    TooManyChunks { hash: BlobHash, chunk_count: usize },
+    #[snafu(display("Received a chunk for blob {actual}, but the fetch is for blob {expected}."))]
+    ChunkHashMismatch {
+        expected: BlobHash,
+        actual: BlobHash,
+    },
+    #[snafu(display(
+        "Chunk {chunk_index} of blob {hash} disagrees with earlier chunks about the blob shape."
+    ))]
+    InconsistentChunkShape { hash: BlobHash, chunk_index: u32 },
+    #[snafu(display("Blob {hash} has {length} bytes, which exceeds the fetch limit of {limit}."))]
+    BlobTooLarge {
+        hash: BlobHash,
+        length: u64,
+        limit: u64,
+    },
+    #[snafu(display("Blob {hash} cannot have {length} bytes split into {chunk_count} chunks."))]
+    InvalidChunkShape {
+        hash: BlobHash,
+        length: u64,
+        chunk_count: u32,
+    },
+    #[snafu(display(
+        "Chunk {chunk_index} of blob {hash} would grow the received data to {received_bytes} bytes, beyond the blob length of {length}."
+    ))]
+    ChunkExceedsBlobLength {
+        hash: BlobHash,
+        chunk_index: u32,
+        received_bytes: u64,
+        length: u64,
+    },
+    #[snafu(display("Chunk index {chunk_index} of blob {hash} is out of range {chunk_count}."))]
+    ChunkIndexOutOfRange {
+        hash: BlobHash,
+        chunk_index: u32,
+        chunk_count: u32,
+    },
+    #[snafu(display("Blob {hash} is still missing {missing} chunks."))]
+    IncompleteBlob { hash: BlobHash, missing: usize },
+    #[snafu(display(
+        "Assembled blob has {actual_length} bytes, but {expected_length} were announced."
+    ))]
+    LengthMismatch {
+        expected_length: u64,
+        actual_length: u64,
+    },
+    #[snafu(display("Assembled blob content hashes to {actual}, expected {expected}."))]
+    ContentHashMismatch {
+        expected: BlobHash,
+        actual: BlobHash,
+    },
+}
+
+impl FromProtoDecodeError for BlobError {
+    fn from_proto_decode_error(source: buffa::DecodeError) -> Self {
+        Self::Decode { source }
+    }
+}
+
+/// In-memory content-addressed blob store with per-referrer reference counting.
+///
+/// `R` identifies whatever holds a reference to a blob, typically one document or row.
+/// Adding the same referrer twice is idempotent, so the reference count is the number of distinct
+/// referrers.
+#[derive(Clone, Debug)]
+pub struct BlobStore<R> {
+    chunk_size: NonZeroUsize,
+    blobs: HashMap<BlobHash, StoredBlob<R>>,
+}
+
+impl<R> BlobStore<R>
+where
+    R: Ord,
+{
+    /// Create an empty store that splits blobs into chunks of `chunk_size` bytes.
+    #[must_use]
+    pub fn new(chunk_size: NonZeroUsize) -> Self {
+        Self {
+            chunk_size,
+            blobs: HashMap::new(),
+        }
+    }
+
+    /// Chunk size used for newly inserted blobs.
+    #[must_use]
+    pub fn chunk_size(&self) -> NonZeroUsize {
+        self.chunk_size
+    }
+
+    /// Number of blobs currently held, referenced or not.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+
+    /// Store `data` and return its descriptor.
+    ///
+    /// Inserting content that is already present keeps the existing chunks.
+    /// Empty content is stored as one empty chunk.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::TooManyChunks`] if `data` cannot be addressed with `u32` chunk indices.
+    pub fn insert(&mut self, data: &[u8]) -> Result<BlobDescriptor, BlobError> {
+        let hash = BlobHash::of(data);
+        if let Some(stored) = self.blobs.get(&hash) {
+            return Ok(stored.descriptor(hash));
+        }
+        let mut chunks: Vec<Bytes> = data
+            .chunks(self.chunk_size.get())
+            .map(Bytes::copy_from_slice)
+            .collect();
+        if chunks.is_empty() {
+            chunks.push(Bytes::new());
+        }
+        ensure!(
+            u32::try_from(chunks.len()).is_ok(),
+            TooManyChunksSnafu {
+                hash,
+                chunk_count: chunks.len(),
+            }
+        );
+        let stored = StoredBlob {
+            length: data.len() as u64,
+            chunks,
+            referrers: BTreeSet::new(),
+        };
+        let descriptor = stored.descriptor(hash);
+        self.blobs.insert(hash, stored);
+        Ok(descriptor)
+    }
+
+    #[must_use]
+    pub fn contains(&self, hash: &BlobHash) -> bool {
+        self.blobs.contains_key(hash)
+    }
+
+    #[must_use]
+    pub fn descriptor(&self, hash: &BlobHash) -> Option<BlobDescriptor> {
+        self.blobs.get(hash).map(|stored| stored.descriptor(*hash))
+    }
+
+    /// Reassemble the complete content of one blob.
+    #[must_use]
+    pub fn read(&self, hash: &BlobHash) -> Option<Bytes> {
+        let stored = self.blobs.get(hash)?;
+        match stored.chunks.as_slice() {
+            [single] => Some(single.clone()),
+            chunks => {
+                let mut buffer = BytesMut::with_capacity(chunks.iter().map(Bytes::len).sum());
+                for chunk in chunks {
+                    buffer.extend_from_slice(chunk);
+                }
+                Some(buffer.freeze())
+            }
+        }
+    }
+
+    /// Return the chunk at `chunk_index` of one blob.
+    #[must_use]
+    pub fn chunk(&self, hash: &BlobHash, chunk_index: u32) -> Option<Bytes> {
+        let stored = self.blobs.get(hash)?;
+        stored.chunks.get(chunk_index as usize).cloned()
+    }
+
+    /// Record that `referrer` references the blob with `hash`.
+    ///
+    /// Returns the reference count after the update.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::UnknownBlob`] if the blob is not stored locally.
+    pub fn add_reference(&mut self, hash: &BlobHash, referrer: R) -> Result<usize, BlobError> {
+        let stored = self
+            .blobs
+            .get_mut(hash)
+            .context(UnknownBlobSnafu { hash: *hash })?;
+        stored.referrers.insert(referrer);
+        Ok(stored.referrers.len())
+    }
+
+    /// Drop the reference `referrer` holds on the blob with `hash`.
+    ///
+    /// The blob itself is kept until [`Self::collect_unreferenced`] runs.
+    /// Returns the reference count after the update.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobError::UnknownBlob`] if the blob is not stored locally.
+    pub fn remove_reference(&mut self, hash: &BlobHash, referrer: &R) -> Result<usize, BlobError> {
+        let stored = self
+            .blobs
+            .get_mut(hash)
+            .context(UnknownBlobSnafu { hash: *hash })?;
+        stored.referrers.remove(referrer);
+        Ok(stored.referrers.len())
+    }
+
+    /// Whether `referrer` holds a reference on the blob with `hash`.
+    #[must_use]
+    pub fn is_referenced_by(&self, hash: &BlobHash, referrer: &R) -> bool {
+        self.blobs
+            .get(hash)
+            .is_some_and(|stored| stored.referrers.contains(referrer))
+    }
+
+    #[must_use]
+    pub fn reference_count(&self, hash: &BlobHash) -> Option<usize> {
+        self.blobs.get(hash).map(|stored| stored.referrers.len())
+    }
+
+    /// Iterate every stored blob together with its referrers, in no particular order.
+    pub fn iter_with_referrers(&self) -> impl Iterator<Item = (BlobDescriptor, &BTreeSet<R>)> {
+        self.blobs
+            .iter()
+            .map(|(hash, stored)| (stored.descriptor(*hash), &stored.referrers))
+    }
+
+    /// Remove every blob without referrers and return their hashes.
+    pub fn collect_unreferenced(&mut self) -> Vec<BlobHash> {
+        let mut collected = Vec::new();
+        self.blobs.retain(|hash, stored| {
+            if stored.referrers.is_empty() {
+                collected.push(*hash);
+                false
+            } else {
+                true
+            }
+        });
+        collected.sort_unstable();
+        collected
+    }
+
+    /// Answer a peer's chunk request from the local store.
+    ///
+    /// Requested indices that do not exist are skipped.
+    #[must_use]
+    pub fn respond(&self, request: &BlobChunkRequest) -> BlobChunkResponse {
+        let Some(stored) = self.blobs.get(&request.hash) else {
+            return BlobChunkResponse::Unavailable(BlobUnavailable {
+                group_id: request.group_id,
+                hash: request.hash,
+            });
+        };
+        let descriptor = stored.descriptor(request.hash);
+        let make_chunk = |chunk_index: u32, data: &Bytes| BlobChunk {
+            group_id: request.group_id,
+            hash: request.hash,
+            blob_length: descriptor.length,
+            chunk_count: descriptor.chunk_count,
+            chunk_index,
+            data: data.clone(),
+        };
+        let chunks = if request.chunk_indices.is_empty() {
+            (0u32..)
+                .zip(stored.chunks.iter())
+                .map(|(index, data)| make_chunk(index, data))
+                .collect()
+        } else {
+            request
+                .chunk_indices
+                .iter()
+                .filter_map(|&index| {
+                    let data = stored.chunks.get(index as usize)?;
+                    Some(make_chunk(index, data))
+                })
+                .collect()
+        };
+        BlobChunkResponse::Chunks(chunks)
+    }
+
+    /// Verify and admit a completed fetch into the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fetch is incomplete or its content does not match its hash.
+    pub fn insert_fetched(&mut self, fetch: BlobFetch) -> Result<BlobDescriptor, BlobError> {
+        let data = fetch.finish()?;
+        self.insert(&data)
+    }
+}
+
+impl<R> Default for BlobStore<R>
+where
+    R: Ord,
+{
+    fn default() -> Self {
+        Self::new(DEFAULT_BLOB_CHUNK_SIZE)
+    }
+}
+
+/// Receiver-side assembly state for one blob fetched lazily from peers.
+///
+/// The blob shape is learned from the first accepted chunk, and later chunks must agree with it.
+/// The shape is checked against `max_length` before any chunk is kept, and the received data
+/// never grows beyond the announced length, so a peer cannot make the fetch buffer more than
+/// `max_length` bytes.
+#[derive(Clone, Debug)]
+pub struct BlobFetch {
+    group_id: GroupId,
+    hash: BlobHash,
+    max_length: u64,
+    shape: Option<BlobShape>,
+    chunks: BTreeMap<u32, Bytes>,
+    received_bytes: u64,
+}
+
+impl BlobFetch {
+    /// Start fetching the blob with `hash`, accepting at most `max_length` bytes of content.
+    #[must_use]
+    pub fn new(group_id: GroupId, hash: BlobHash, max_length: u64) -> Self {
+        Self {
+            group_id,
+            hash,
+            max_length,
+            shape: None,
+            chunks: BTreeMap::new(),
+            received_bytes: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn group_id(&self) -> GroupId {
+        self.group_id
+    }
+
+    #[must_use]
+    pub fn hash(&self) -> BlobHash {
+        self.hash
+    }
+
+    /// Build the request for every chunk that has not been received yet.
+    #[must_use]
+    pub fn next_request(&self) -> BlobChunkRequest {
+        let chunk_indices = match self.shape {
+            Some(shape) => (0..shape.chunk_count)
+                .filter(|index| !self.chunks.contains_key(index))
+                .collect(),
+            None => Vec::new(),
+        };
+        BlobChunkRequest {
+            group_id: self.group_id,
+            hash: self.hash,
+            chunk_indices,
+        }
+    }
+
+    /// Record one received chunk.
+    ///
+    /// Duplicate chunks replace earlier copies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chunk belongs to another blob, announces a shape that is too large
+    /// or impossible, disagrees with the blob shape, or carries more data than the blob can hold.
+    pub fn accept(&mut self, chunk: BlobChunk) -> Result<(), BlobError> {
+        ensure!(
+            chunk.hash == self.hash,
+            ChunkHashMismatchSnafu {
+                expected: self.hash,
+                actual: chunk.hash,
+            }
+        );
+        let shape = BlobShape {
+            length: chunk.blob_length,
+            chunk_count: chunk.chunk_count,
+        };
+        let expected_shape = match self.shape {
+            Some(expected_shape) => expected_shape,
+            None => {
+                self.validate_shape(shape)?;
+                *self.shape.insert(shape)
+            }
+        };
+        ensure!(
+            expected_shape == shape,
+            InconsistentChunkShapeSnafu {
+                hash: self.hash,
+                chunk_index: chunk.chunk_index,
+            }
+        );
+        ensure!(
+            chunk.chunk_index < shape.chunk_count,
+            ChunkIndexOutOfRangeSnafu {
+                hash: self.hash,
+                chunk_index: chunk.chunk_index,
+                chunk_count: shape.chunk_count,
+            }
+        );
+        let replaced_bytes = self
+            .chunks
+            .get(&chunk.chunk_index)
+            .map_or(0, |data| data.len() as u64);
+        let received_bytes = self.received_bytes - replaced_bytes + chunk.data.len() as u64;
+        ensure!(
+            received_bytes <= shape.length,
+            ChunkExceedsBlobLengthSnafu {
+                hash: self.hash,
+                chunk_index: chunk.chunk_index,
+                received_bytes,
+                length: shape.length,
+            }
+        );
+        self.received_bytes = received_bytes;
+        self.chunks.insert(chunk.chunk_index, chunk.data);
+        Ok(())
+    }
+
+    /// Check a shape announced by the first chunk before trusting it.
+    ///
+    /// Only empty blobs have empty chunks, so a blob has between one and `length` chunks.
+    fn validate_shape(&self, shape: BlobShape) -> Result<(), BlobError> {
+        ensure!(
+            shape.length <= self.max_length,
+            BlobTooLargeSnafu {
+                hash: self.hash,
+                length: shape.length,
+                limit: self.max_length,
+            }
+        );
+        ensure!(
+            shape.chunk_count >= 1 && u64::from(shape.chunk_count) <= shape.length.max(1),
+            InvalidChunkShapeSnafu {
+                hash: self.hash,
+                length: shape.length,
+                chunk_count: shape.chunk_count,
+            }
+        );
+        Ok(())
+    }
+
+    /// Whether every chunk of the blob has been received.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.missing_chunks() == 0
+    }
+
+    fn missing_chunks(&self) -> usize {
+        match self.shape {
+            Some(shape) => shape.chunk_count as usize - self.chunks.len(),
+            // The first chunk has not arrived yet, so at least one is missing.
+            None => 1,
+        }
+    }
+
+    fn finish(self) -> Result<Bytes, BlobError> {
+        let missing = self.missing_chunks();
+        ensure!(
+            missing == 0,
+            IncompleteBlobSnafu {
+                hash: self.hash,
+                missing,
+            }
+        );
+        let Some(shape) = self.shape else {
+            unreachable!("A complete fetch always knows its shape.");
+        };
+        let mut buffer = BytesMut::new();
+        for chunk in self.chunks.into_values() {
+            buffer.extend_from_slice(&chunk);
+        }
+        let actual_length = buffer.len() as u64;
+        ensure!(
+            actual_length == shape.length,
+            LengthMismatchSnafu {
+                expected_length: shape.length,
+                actual_length,
+            }
+        );
+        let actual = BlobHash::of(&buffer);
+        ensure!(
+            actual == self.hash,
+            ContentHashMismatchSnafu {
+                expected: self.hash,
+                actual,
+            }
+        );
+        Ok(buffer.freeze())
+    }
+}
+
+/// Request for chunks of one blob, sent to a peer that is expected to hold it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlobChunkRequest {
+    pub group_id: GroupId,
+    pub hash: BlobHash,
+    /// Requested chunk indices. Empty means every chunk.
+    pub chunk_indices: Vec<u32>,
+}
+
+impl ProtoCodec for BlobChunkRequest {
+    type DecodeError = BlobError;
+    type Proto = replication_proto::BlobChunkRequest;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::BlobChunkRequest {
+            group_id: group_id_to_wire_bytes(self.group_id),
+            blob_hash: self.hash.as_bytes().to_vec(),
+            chunk_indices: self.chunk_indices.clone(),
+            ..replication_proto::BlobChunkRequest::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = decode_group_id(&message.group_id, "blob_chunk_request.group_id")?;
+        let hash = BlobHash::from_wire(&message.blob_hash, "blob_chunk_request.blob_hash")?;
+        Ok(Self {
+            group_id,
+            hash,
+            chunk_indices: message.chunk_indices,
+        })
+    }
+}
+
+impl DecodeProtoView for BlobChunkRequest {
+    type Error = BlobError;
+    type ProtoView<'a> = replication_proto::BlobChunkRequestView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let message = message.to_owned_message().context(DecodeSnafu)?;
+        Self::decode_proto(message)
+    }
+}
+
+/// One chunk of a blob, sent in reply to a [`BlobChunkRequest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlobChunk {
+    pub group_id: GroupId,
+    pub hash: BlobHash,
+    pub blob_length: u64,
+    pub chunk_count: u32,
+    pub chunk_index: u32,
+    pub data: Bytes,
+}
+
+impl ProtoCodec for BlobChunk {
+    type DecodeError = BlobError;
+    type Proto = replication_proto::BlobChunk;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::BlobChunk {
+            group_id: group_id_to_wire_bytes(self.group_id),
+            blob_hash: self.hash.as_bytes().to_vec(),
+            blob_length: self.blob_length,
+            chunk_count: self.chunk_count,
+            chunk_index: self.chunk_index,
+            data: self.data.clone(),
+            ..replication_proto::BlobChunk::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = decode_group_id(&message.group_id, "blob_chunk.group_id")?;
+        let hash = BlobHash::from_wire(&message.blob_hash, "blob_chunk.blob_hash")?;
+        Ok(Self {
+            group_id,
+            hash,
+            blob_length: message.blob_length,
+            chunk_count: message.chunk_count,
+            chunk_index: message.chunk_index,
+            data: message.data,
+        })
+    }
+}
+
+impl DecodeProtoView for BlobChunk {
+    type Error = BlobError;
+    type ProtoView<'a> = replication_proto::BlobChunkView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let message = message.to_owned_message().context(DecodeSnafu)?;
+        Self::decode_proto(message)
+    }
+}
+
+/// Reply indicating that the responder does not hold a requested blob.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlobUnavailable {
+    pub group_id: GroupId,
+    pub hash: BlobHash,
+}
+
+impl ProtoCodec for BlobUnavailable {
+    type DecodeError = BlobError;
+    type Proto = replication_proto::BlobUnavailable;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::BlobUnavailable {
+            group_id: group_id_to_wire_bytes(self.group_id),
+            blob_hash: self.hash.as_bytes().to_vec(),
+            ..replication_proto::BlobUnavailable::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = decode_group_id(&message.group_id, "blob_unavailable.group_id")?;
+        let hash = BlobHash::from_wire(&message.blob_hash, "blob_unavailable.blob_hash")?;
+        Ok(Self { group_id, hash })
+    }
+}
+
+impl DecodeProtoView for BlobUnavailable {
+    type Error = BlobError;
+    type ProtoView<'a> = replication_proto::BlobUnavailableView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let group_id = decode_group_id(message.group_id, "blob_unavailable.group_id")?;
+        let hash = BlobHash::from_wire(message.blob_hash, "blob_unavailable.blob_hash")?;
+        Ok(Self { group_id, hash })
+    }
+}
+
+/// Local answer to one [`BlobChunkRequest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlobChunkResponse {
+    Chunks(Vec<BlobChunk>),
+    Unavailable(BlobUnavailable),
+}
+
+fn decode_group_id(raw: &[u8], field: &'static str) -> Result<GroupId, BlobError> {
+    group_id_from_wire_bytes(raw, field).context(InvalidWireValueSnafu { field })
+}
+
+#[derive(Clone, Debug)]
+struct StoredBlob<R> {
+    length: u64,
+    chunks: Vec<Bytes>,
+    referrers: BTreeSet<R>,
+}
+
+impl<R> StoredBlob<R> {
+    fn descriptor(&self, hash: BlobHash) -> BlobDescriptor {
+        BlobDescriptor {
+            hash,
+            length: self.length,
+            chunk_count: u32::try_from(self.chunks.len())
+                .expect("Chunk counts are validated on insert."),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BlobShape {
+    length: u64,
+    chunk_count: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_messages::proto::EncodeProto;
+    use uuid::Uuid;
+
+    fn small_store() -> BlobStore<&'static str> {
+        BlobStore::new(NonZeroUsize::new(4).unwrap())
+    }
+
+    fn group_id() -> GroupId {
+        GroupId(Uuid::from_u128(7))
+    }
+
+    fn fetch(hash: BlobHash) -> BlobFetch {
+        BlobFetch::new(group_id(), hash, 1024)
+    }
+
+    #[test]
+    fn insert_is_content_addressed_and_chunked() {
+        let mut store = small_store();
+        let first = store.insert(b"hello, blobs").unwrap();
+        let second = store.insert(b"hello, blobs").unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(store.len(), 1);
+        assert_eq!(first.length, 12);
+        assert_eq!(first.chunk_count, 3);
+        assert_eq!(store.chunk(&first.hash, 1).unwrap().as_ref(), b"o, b");
+        assert_eq!(store.read(&first.hash).unwrap().as_ref(), b"hello, blobs");
+    }
+
+    #[test]
+    fn unreferenced_blobs_are_collected() {
+        let mut store = small_store();
+        let kept = store.insert(b"kept").unwrap().hash;
+        let dropped = store.insert(b"dropped").unwrap().hash;
+
+        assert_eq!(store.add_reference(&kept, "doc-a").unwrap(), 1);
+        assert_eq!(store.add_reference(&kept, "doc-a").unwrap(), 1);
+        assert_eq!(store.add_reference(&dropped, "doc-b").unwrap(), 1);
+        assert_eq!(store.remove_reference(&dropped, &"doc-b").unwrap(), 0);
+
+        assert_eq!(store.collect_unreferenced(), vec![dropped]);
+        assert!(store.contains(&kept));
+        assert!(!store.contains(&dropped));
+        assert!(matches!(
+            store.add_reference(&dropped, "doc-b"),
+            Err(BlobError::UnknownBlob { .. })
+        ));
+    }
+
+    #[test]
+    fn fetch_from_peer_roundtrips_through_protobuf() {
+        let mut source = small_store();
+        let descriptor = source.insert(b"an attachment body").unwrap();
+        let mut target = small_store();
+
+        let mut fetch = fetch(descriptor.hash);
+        let request = BlobChunkRequest::decode_proto(fetch.next_request().encode_proto()).unwrap();
+        assert!(request.chunk_indices.is_empty());
+
+        let BlobChunkResponse::Chunks(chunks) = source.respond(&request) else {
+            panic!("Source must hold the blob.");
+        };
+        // Deliver everything except the last chunk first.
+        let (last, rest) = chunks.split_last().unwrap();
+        for chunk in rest {
+            fetch
+                .accept(BlobChunk::decode_proto(chunk.encode_proto()).unwrap())
+                .unwrap();
+        }
+        assert!(!fetch.is_complete());
+        assert_eq!(
+            fetch.next_request().chunk_indices,
+            vec![descriptor.chunk_count - 1]
+        );
+
+        fetch.accept(last.clone()).unwrap();
+        assert!(fetch.is_complete());
+        assert_eq!(target.insert_fetched(fetch).unwrap(), descriptor);
+        assert_eq!(
+            target.read(&descriptor.hash).unwrap().as_ref(),
+            b"an attachment body"
+        );
+    }
+
+    #[test]
+    fn fetch_rejects_tampered_content() {
+        let mut source = small_store();
+        let descriptor = source.insert(b"original").unwrap();
+        let mut fetch = fetch(descriptor.hash);
+
+        let BlobChunkResponse::Chunks(chunks) = source.respond(&fetch.next_request()) else {
+            panic!("Source must hold the blob.");
+        };
+        for mut chunk in chunks {
+            if chunk.chunk_index == 0 {
+                chunk.data = Bytes::from_static(b"forg");
+            }
+            fetch.accept(chunk).unwrap();
+        }
+        assert!(matches!(
+            small_store().insert_fetched(fetch),
+            Err(BlobError::ContentHashMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn empty_blob_fetch_completes() {
+        let mut source = small_store();
+        let descriptor = source.insert(b"").unwrap();
+        assert_eq!(descriptor.chunk_count, 1);
+
+        let mut fetch = fetch(descriptor.hash);
+        let BlobChunkResponse::Chunks(chunks) = source.respond(&fetch.next_request()) else {
+            panic!("Source must hold the blob.");
+        };
+        for chunk in chunks {
+            fetch.accept(chunk).unwrap();
+        }
+        assert!(fetch.is_complete());
+        let mut target = small_store();
+        assert_eq!(target.insert_fetched(fetch).unwrap(), descriptor);
+        assert!(target.read(&descriptor.hash).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fetch_rejects_oversized_and_impossible_shapes() {
+        let hash = BlobHash::of(b"whatever");
+        let chunk = |blob_length, chunk_count, data: &'static [u8]| BlobChunk {
+            group_id: group_id(),
+            hash,
+            blob_length,
+            chunk_count,
+            chunk_index: 0,
+            data: Bytes::from_static(data),
+        };
+
+        assert!(matches!(
+            fetch(hash).accept(chunk(4096, 1, b"x")),
+            Err(BlobError::BlobTooLarge { .. })
+        ));
+        assert!(matches!(
+            fetch(hash).accept(chunk(8, u32::MAX, b"x")),
+            Err(BlobError::InvalidChunkShape { .. })
+        ));
+        assert!(matches!(
+            fetch(hash).accept(chunk(0, 0, b"")),
+            Err(BlobError::InvalidChunkShape { .. })
+        ));
+
+        let mut overflowing = fetch(hash);
+        overflowing.accept(chunk(8, 2, b"12345")).unwrap();
+        let mut second = chunk(8, 2, b"6789");
+        second.chunk_index = 1;
+        assert!(matches!(
+            overflowing.accept(second),
+            Err(BlobError::ChunkExceedsBlobLength { .. })
+        ));
+        // A resent chunk replaces the earlier copy instead of adding to it.
+        overflowing.accept(chunk(8, 2, b"12345")).unwrap();
+    }
+
+    #[test]
+    fn missing_blob_reports_unavailable() {
+        let store = small_store();
+        let hash = BlobHash::of(b"absent");
+        let request = BlobChunkRequest {
+            group_id: group_id(),
+            hash,
+            chunk_indices: Vec::new(),
+        };
+        let response = store.respond(&request);
+        assert_eq!(
+            response,
+            BlobChunkResponse::Unavailable(BlobUnavailable {
+                group_id: group_id(),
+                hash,
+            })
+        );
+    }
+}
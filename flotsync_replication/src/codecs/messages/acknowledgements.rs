@@ -0,0 +1,171 @@
+//! Delivery receipt codecs.
+
+use super::*;
+
+/// Receipt for individual updates the sender has durably applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct UpdateAckMessage {
+    pub(crate) group_id: GroupId,
+    pub(crate) update_ids: Vec<UpdateId>,
+}
+
+impl proto::ProtoCodec for UpdateAckMessage {
+    type DecodeError = RuntimeMessageError;
+    type Proto = replication_proto::UpdateAck;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::UpdateAck {
+            group_id: self.group_id.0.as_bytes().to_vec(),
+            update_ids: self
+                .update_ids
+                .iter()
+                .copied()
+                .map(encode_update_id)
+                .collect(),
+            ..replication_proto::UpdateAck::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = group_id_from_wire(&message.group_id, "update_ack.group_id").context(
+            InvalidWireValueSnafu {
+                field: "update_ack.group_id",
+            },
+        )?;
+        if message.update_ids.is_empty() {
+            return EmptyUpdateAckSnafu.fail();
+        }
+        let update_ids = message
+            .update_ids
+            .into_iter()
+            .map(|update_id| {
+                let update_id = decode_update_id(update_id).context(InvalidUpdateIdSnafu {
+                    field: "update_ack.update_ids",
+                })?;
+                ensure_update_id_version_bound(update_id)?;
+                Ok(update_id)
+            })
+            .collect::<Result<_, RuntimeMessageError>>()?;
+        Ok(Self {
+            group_id,
+            update_ids,
+        })
+    }
+}
+
+impl DecodeProtoView for UpdateAckMessage {
+    type Error = RuntimeMessageError;
+    type ProtoView<'a> = replication_proto::UpdateAckView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let group_id = group_id_from_wire(message.group_id, "update_ack.group_id").context(
+            InvalidWireValueSnafu {
+                field: "update_ack.group_id",
+            },
+        )?;
+        if message.update_ids.is_empty() {
+            return EmptyUpdateAckSnafu.fail();
+        }
+        let update_ids = message
+            .update_ids
+            .iter()
+            .map(|update_id| {
+                let update_id = UpdateId {
+                    version: update_id.version,
+                    node_index: update_id.node_index,
+                };
+                ensure_update_id_version_bound(update_id)?;
+                Ok(update_id)
+            })
+            .collect::<Result<_, RuntimeMessageError>>()?;
+        Ok(Self {
+            group_id,
+            update_ids,
+        })
+    }
+}
+
+/// Receipt for every update covered by the sender's applied group version vector.
+#[derive(Clone, Debug, PartialEq, View)]
+pub(crate) struct FrontierAckMessage {
+    pub(crate) group_id: GroupId,
+    pub(crate) applied_versions: VersionVector,
+}
+
+impl EncodeProto for FrontierAckMessage {
+    type Proto = replication_proto::FrontierAck;
+
+    fn encode_proto(&self) -> Self::Proto {
+        self.view().encode_proto()
+    }
+}
+
+impl EncodeProto for FrontierAckMessageView<'_> {
+    type Proto = replication_proto::FrontierAck;
+
+    fn encode_proto(&self) -> Self::Proto {
+        replication_proto::FrontierAck {
+            group_id: self.group_id.0.as_bytes().to_vec(),
+            applied_versions: MessageField::some(
+                CompactVersionVectorProtoCodec::from(self.applied_versions).encode_proto(),
+            ),
+            ..replication_proto::FrontierAck::default()
+        }
+    }
+}
+
+impl proto::ProtoCodecWith<MemberCountContext> for FrontierAckMessage {
+    type DecodeError = RuntimeMessageError;
+
+    fn from_proto_with(
+        mut proto: <Self as EncodeProto>::Proto,
+        context: MemberCountContext,
+    ) -> Result<Self, Self::DecodeError> {
+        let group_id = group_id_from_wire(&proto.group_id, "frontier_ack.group_id").context(
+            InvalidWireValueSnafu {
+                field: "frontier_ack.group_id",
+            },
+        )?;
+        let Some(applied_versions) = proto.applied_versions.take() else {
+            return MissingAppliedVersionsSnafu.fail();
+        };
+        let applied_versions =
+            CompactVersionVectorProtoCodec::decode_proto_with(applied_versions, context).context(
+                InvalidReadVersionsSnafu {
+                    field: "frontier_ack.applied_versions",
+                },
+            )?;
+        Ok(Self {
+            group_id,
+            applied_versions: applied_versions.into_version_vector(),
+        })
+    }
+}
+
+impl DecodeProtoViewWith<MemberCountContext> for FrontierAckMessage {
+    type Error = RuntimeMessageError;
+    type ProtoView<'a> = replication_proto::FrontierAckView<'a>;
+
+    fn decode_proto_view_with(
+        proto: &Self::ProtoView<'_>,
+        context: MemberCountContext,
+    ) -> Result<Self, Self::Error> {
+        let group_id = group_id_from_wire(proto.group_id, "frontier_ack.group_id").context(
+            InvalidWireValueSnafu {
+                field: "frontier_ack.group_id",
+            },
+        )?;
+        let Some(applied_versions) = proto.applied_versions.as_option() else {
+            return MissingAppliedVersionsSnafu.fail();
+        };
+        let applied_versions =
+            CompactVersionVectorProtoCodec::decode_proto_view_with(applied_versions, context)
+                .context(InvalidReadVersionsSnafu {
+                    field: "frontier_ack.applied_versions",
+                })?;
+        Ok(Self {
+            group_id,
+            applied_versions: applied_versions.into_version_vector(),
+        })
+    }
+}
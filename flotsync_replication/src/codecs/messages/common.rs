@@ -15,6 +15,16 @@ pub(crate) enum RuntimeMessageError {
     },
     #[snafu(display("Runtime message did not contain a body."))]
     MissingBody,
+    /// A compressed payload arrived where the receiver does not accept one.
+    #[snafu(display("Runtime message carried an unexpected compressed payload."))]
+    UnexpectedCompressedPayload,
+    #[snafu(display("Compressed runtime message was invalid: {source}"))]
+    CompressedPayload { source: CompressionError },
+    /// Protobuf runtime messages are sent as-is, never wrapped in an encoded payload.
+    #[snafu(display("Runtime message carried an encoded payload with protobuf encoding."))]
+    UnexpectedProtobufEncodedPayload,
+    #[snafu(display("Encoded runtime message was invalid: {source}"))]
+    EncodedPayload { source: PayloadEncodingError },
     /// A compact vector referenced a group absent from the membership snapshot.
     #[snafu(display("Runtime message for group {group_id} requires hosted group-member context."))]
     MissingGroupMemberContext { group_id: GroupId },
@@ -23,7 +33,7 @@ pub(crate) enum RuntimeMessageError {
     #[snafu(display("Pending-group runtime message did not include private group setup."))]
     MissingGroupSetup,
     #[snafu(display(
-        "Pending-group members did not match the ordered identities in private group setup."
+        "Pending-group members or observers did not match the identities in private group setup."
     ))]
     GroupSetupMemberMismatch,
     #[snafu(display("Group setup must include key references for every member."))]
@@ -109,6 +119,12 @@ pub(crate) enum RuntimeMessageError {
     MissingReadVersions,
     #[snafu(display("Summary message did not include versions."))]
     MissingSummaryVersions,
+    #[snafu(display("UpdateAck message must include at least one update id."))]
+    EmptyUpdateAck,
+    #[snafu(display("FrontierAck message did not include applied versions."))]
+    MissingAppliedVersions,
+    #[snafu(display("Throttled message used unknown throttle reason value {value}."))]
+    UnknownThrottleReason { value: i32 },
     #[snafu(display("Runtime message field '{field}' was invalid: {source}"))]
     InvalidWireValue {
         field: &'static str,
@@ -116,6 +132,10 @@ pub(crate) enum RuntimeMessageError {
     },
     #[snafu(display("Runtime message pending-group payload was invalid: {source}"))]
     InvalidPendingGroupPayload { source: PendingGroupPayloadError },
+    #[snafu(display("Runtime message blob payload was invalid: {source}"))]
+    InvalidBlobPayload { source: Box<BlobError> },
+    #[snafu(display("Runtime message snapshot payload was invalid: {source}"))]
+    InvalidSnapshotPayload { source: Box<SnapshotTransferError> },
     #[snafu(display("Runtime message field '{field}' was not a valid UUID: {source}"))]
     InvalidCorrelationId {
         field: &'static str,
@@ -182,6 +202,30 @@ pub(crate) enum VersionVectorCodecError {
         override_position: u32,
         override_version: u64,
     },
+    /// Multi-override positions and versions did not pair up.
+    #[snafu(display(
+        "Version-vector multi-override had {positions} positions, but {versions} versions."
+    ))]
+    MultiOverrideLengthMismatch { positions: usize, versions: usize },
+    /// Multi-override versions violated the runtime vector invariants.
+    #[snafu(display(
+        "Version-vector multi-override was invalid: group version {group_version}, {override_count} overrides."
+    ))]
+    InvalidMultiOverride {
+        group_version: u64,
+        override_count: usize,
+    },
+    /// Sparse-vector positions and versions did not pair up.
+    #[snafu(display("Sparse version vector had {positions} positions, but {versions} versions."))]
+    SparseLengthMismatch { positions: usize, versions: usize },
+    /// Sparse-vector entries violated the runtime vector invariants.
+    #[snafu(display(
+        "Sparse version vector was invalid: base version {base_version}, {entry_count} entries."
+    ))]
+    InvalidSparse {
+        base_version: u64,
+        entry_count: usize,
+    },
     /// A version used the reserved upper bound unsupported by runtime arithmetic.
     #[snafu(display(
         "Version-vector field '{field}' used unsupported version bound {version}; maximum supported bound is {MAX_VERSION_VALUE}."
@@ -213,12 +257,62 @@ impl MemberCountContext {
 pub(crate) struct RuntimeMessageDecodeContext<'a> {
     /// Current immutable membership snapshot for locally hosted groups.
     memberships: &'a GroupMemberships,
+    /// Inbound compression settings; `None` rejects compressed payloads.
+    compression: Option<InboundCompression<'a>>,
 }
 
 impl<'a> RuntimeMessageDecodeContext<'a> {
     /// Create a runtime-message context from the current membership snapshot.
     pub(crate) const fn new(memberships: &'a GroupMemberships) -> Self {
-        Self { memberships }
+        Self {
+            memberships,
+            compression: None,
+        }
+    }
+
+    /// Accept payloads compressed with an algorithm in `accepts`, expanding to at most
+    /// `max_inflated_bytes`, and count them in `metrics`.
+    pub(crate) const fn with_compression(
+        mut self,
+        accepts: CompressionOffer,
+        max_inflated_bytes: usize,
+        metrics: &'a CompressionMetrics,
+    ) -> Self {
+        self.compression = Some(InboundCompression {
+            accepts,
+            max_inflated_bytes,
+            metrics,
+        });
+        self
+    }
+
+    /// Decompress one compressed runtime payload.
+    ///
+    /// Returns the inflated bytes together with a context that rejects further compression, since
+    /// compressed payloads never nest.
+    pub(crate) fn inflate(
+        self,
+        algorithm: EnumValue<delivery_proto::CompressionAlgorithm>,
+        uncompressed_length: u64,
+        data: &[u8],
+    ) -> Result<(Vec<u8>, Self), RuntimeMessageError> {
+        let inbound = self.compression.context(UnexpectedCompressedPayloadSnafu)?;
+        let inflated = inflate_offered(
+            inbound.accepts,
+            algorithm,
+            uncompressed_length,
+            data,
+            inbound.max_inflated_bytes,
+        )
+        .context(CompressedPayloadSnafu)?;
+        inbound
+            .metrics
+            .record_inflated(PayloadClass::OperationBatch, inflated.len());
+        let inner = Self {
+            memberships: self.memberships,
+            compression: None,
+        };
+        Ok((inflated, inner))
     }
 
     /// Return the member count for a compact vector scoped to `group_id`.
@@ -235,3 +329,14 @@ impl<'a> RuntimeMessageDecodeContext<'a> {
         Ok(MemberCountContext::new(member_count))
     }
 }
+
+/// Receiver-side compression settings carried by [`RuntimeMessageDecodeContext`].
+#[derive(Clone, Copy, Debug)]
+struct InboundCompression<'a> {
+    /// Algorithms the local replica offered to its peers.
+    accepts: CompressionOffer,
+    /// Upper bound for one decompressed payload.
+    max_inflated_bytes: usize,
+    /// Counters updated for every decompressed payload.
+    metrics: &'a CompressionMetrics,
+}
@@ -15,6 +15,17 @@ pub(crate) enum RuntimeMessage {
     UpdateBatch(UpdateBatchMessage),
     GroupInvitation(GroupInvitationMessage),
     MigrationProposal(MigrationProposalMessage),
+    UpdateAck(UpdateAckMessage),
+    FrontierAck(FrontierAckMessage),
+    Throttled(ThrottledMessage),
+    BlobChunkRequest(BlobChunkRequest),
+    BlobChunk(BlobChunk),
+    BlobUnavailable(BlobUnavailable),
+    SnapshotManifestRequest(SnapshotManifestRequest),
+    SnapshotManifest(SnapshotManifest),
+    SnapshotChunkRequest(SnapshotChunkRequest),
+    SnapshotChunk(SnapshotChunk),
+    SnapshotUnavailable(SnapshotUnavailable),
 }
 
 impl RuntimeMessage {
@@ -28,8 +39,95 @@ impl RuntimeMessage {
             Self::UpdateBatch(message) => message.group_id,
             Self::GroupInvitation(message) => message.invitation.group_id,
             Self::MigrationProposal(message) => message.proposal.migration_id.old_group_id,
+            Self::UpdateAck(message) => message.group_id,
+            Self::FrontierAck(message) => message.group_id,
+            Self::Throttled(message) => message.group_id,
+            Self::BlobChunkRequest(message) => message.group_id,
+            Self::BlobChunk(message) => message.group_id,
+            Self::BlobUnavailable(message) => message.group_id,
+            Self::SnapshotManifestRequest(message) => message.group_id,
+            Self::SnapshotManifest(message) => message.group_id,
+            Self::SnapshotChunkRequest(message) => message.group_id,
+            Self::SnapshotChunk(message) => message.group_id,
+            Self::SnapshotUnavailable(message) => message.group_id,
         }
     }
+
+    /// Encode this message for peers on a connection negotiated to use `encoding` and
+    /// `compression`.
+    ///
+    /// Updates and update batches are sent with the negotiated encoding and compressed when that
+    /// pays off, and every other message is always sent as uncompressed protobuf.
+    pub(crate) fn encode_payload(
+        &self,
+        encoding: PayloadEncoding,
+        compression: &ConnectionCompression,
+        metrics: &CompressionMetrics,
+    ) -> Bytes {
+        if !matches!(self, Self::Update(_) | Self::UpdateBatch(_)) {
+            return self.encode_proto_to_bytes();
+        }
+        let payload = wrap_encoded(encoding, EncodeProto::encode_proto(self))
+            .unwrap_or_else(|| self.encode_proto_to_bytes());
+        compress_operation_payload(payload, compression, metrics)
+    }
+}
+
+/// Re-encode one protobuf-encoded update or update-batch message with the negotiated `encoding`
+/// and wrap it in a compressed runtime message, if `compression` makes it smaller.
+pub(crate) fn encode_operation_payload(
+    payload: Bytes,
+    encoding: PayloadEncoding,
+    compression: &ConnectionCompression,
+    metrics: &CompressionMetrics,
+) -> Bytes {
+    let encoded = match encoding {
+        PayloadEncoding::Protobuf => None,
+        encoding => replication_proto::RuntimeMessage::decode_from_slice(&payload)
+            .ok()
+            .and_then(|message| wrap_encoded(encoding, message)),
+    };
+    compress_operation_payload(encoded.unwrap_or(payload), compression, metrics)
+}
+
+/// Wrap `message` in an encoded runtime message with the non-protobuf `encoding`.
+///
+/// Returns `None` when the message should be sent as plain protobuf instead, which every peer
+/// accepts: either `encoding` is protobuf, or the message has no form in `encoding`.
+fn wrap_encoded(
+    encoding: PayloadEncoding,
+    message: replication_proto::RuntimeMessage,
+) -> Option<Bytes> {
+    if encoding == PayloadEncoding::Protobuf {
+        return None;
+    }
+    let encoded = EncodedPayload::encode(encoding, message).ok()?;
+    let message = replication_proto::RuntimeMessage {
+        body: Some(replication_proto::runtime_message::Body::Encoded(Box::new(
+            encoded.encode_proto(),
+        ))),
+        ..replication_proto::RuntimeMessage::default()
+    };
+    Some(message.encode_to_bytes())
+}
+
+/// Wrap one encoded update or update-batch message in a compressed runtime message, if
+/// `compression` makes it smaller.
+fn compress_operation_payload(
+    payload: Bytes,
+    compression: &ConnectionCompression,
+    metrics: &CompressionMetrics,
+) -> Bytes {
+    match compression.compress(PayloadClass::OperationBatch, &payload, metrics) {
+        Some(compressed) => replication_proto::RuntimeMessage {
+            body: Some(replication_proto::runtime_message::Body::Compressed(
+                Box::new(compressed.encode_proto()),
+            )),
+            ..replication_proto::RuntimeMessage::default()
+        }
+        .encode_to_bytes(),
+        None => payload,
+    }
 }
 
 impl EncodeProto for RuntimeMessage {
@@ -65,6 +163,39 @@ impl EncodeProtoOneof for RuntimeMessage {
             RuntimeMessage::MigrationProposal(message) => {
                 Self::Proto::MigrationProposal(message.encode_proto_boxed())
             }
+            RuntimeMessage::UpdateAck(message) => {
+                Self::Proto::UpdateAck(message.encode_proto_boxed())
+            }
+            RuntimeMessage::FrontierAck(message) => {
+                Self::Proto::FrontierAck(message.encode_proto_boxed())
+            }
+            RuntimeMessage::Throttled(message) => {
+                Self::Proto::Throttled(message.encode_proto_boxed())
+            }
+            RuntimeMessage::BlobChunkRequest(message) => {
+                Self::Proto::BlobChunkRequest(message.encode_proto_boxed())
+            }
+            RuntimeMessage::BlobChunk(message) => {
+                Self::Proto::BlobChunk(message.encode_proto_boxed())
+            }
+            RuntimeMessage::BlobUnavailable(message) => {
+                Self::Proto::BlobUnavailable(message.encode_proto_boxed())
+            }
+            RuntimeMessage::SnapshotManifestRequest(message) => {
+                Self::Proto::SnapshotManifestRequest(message.encode_proto_boxed())
+            }
+            RuntimeMessage::SnapshotManifest(message) => {
+                Self::Proto::SnapshotManifest(message.encode_proto_boxed())
+            }
+            RuntimeMessage::SnapshotChunkRequest(message) => {
+                Self::Proto::SnapshotChunkRequest(message.encode_proto_boxed())
+            }
+            RuntimeMessage::SnapshotChunk(message) => {
+                Self::Proto::SnapshotChunk(message.encode_proto_boxed())
+            }
+            RuntimeMessage::SnapshotUnavailable(message) => {
+                Self::Proto::SnapshotUnavailable(message.encode_proto_boxed())
+            }
         }
     }
 }
@@ -115,6 +246,81 @@ impl DecodeProtoWith<RuntimeMessageDecodeContext<'_>> for RuntimeMessage {
                 let message = MigrationProposalMessage::decode_proto(*message)?;
                 Ok(Self::MigrationProposal(message))
             }
+            replication_proto::runtime_message::Body::UpdateAck(message) => {
+                let message = UpdateAckMessage::decode_proto(*message)?;
+                Ok(Self::UpdateAck(message))
+            }
+            replication_proto::runtime_message::Body::FrontierAck(message) => {
+                let member_count =
+                    member_count_context(&message.group_id, "frontier_ack.group_id", context)?;
+                let message = FrontierAckMessage::decode_proto_with(*message, member_count)?;
+                Ok(Self::FrontierAck(message))
+            }
+            replication_proto::runtime_message::Body::Throttled(message) => {
+                let message = ThrottledMessage::decode_proto(*message)?;
+                Ok(Self::Throttled(message))
+            }
+            replication_proto::runtime_message::Body::BlobChunkRequest(message) => {
+                let message = BlobChunkRequest::decode_proto(*message)
+                    .map_err(Box::new)
+                    .context(InvalidBlobPayloadSnafu)?;
+                Ok(Self::BlobChunkRequest(message))
+            }
+            replication_proto::runtime_message::Body::BlobChunk(message) => {
+                let message = BlobChunk::decode_proto(*message)
+                    .map_err(Box::new)
+                    .context(InvalidBlobPayloadSnafu)?;
+                Ok(Self::BlobChunk(message))
+            }
+            replication_proto::runtime_message::Body::BlobUnavailable(message) => {
+                let message = BlobUnavailable::decode_proto(*message)
+                    .map_err(Box::new)
+                    .context(InvalidBlobPayloadSnafu)?;
+                Ok(Self::BlobUnavailable(message))
+            }
+            replication_proto::runtime_message::Body::SnapshotManifestRequest(message) => {
+                let message = SnapshotManifestRequest::decode_proto(*message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotManifestRequest(message))
+            }
+            replication_proto::runtime_message::Body::SnapshotManifest(message) => {
+                let message = SnapshotManifest::decode_proto(*message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotManifest(message))
+            }
+            replication_proto::runtime_message::Body::SnapshotChunkRequest(message) => {
+                let message = SnapshotChunkRequest::decode_proto(*message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotChunkRequest(message))
+            }
+            replication_proto::runtime_message::Body::SnapshotChunk(message) => {
+                let message = SnapshotChunk::decode_proto(*message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotChunk(message))
+            }
+            replication_proto::runtime_message::Body::SnapshotUnavailable(message) => {
+                let message = SnapshotUnavailable::decode_proto(*message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotUnavailable(message))
+            }
+            replication_proto::runtime_message::Body::Compressed(message) => {
+                let (inflated, context) = context.inflate(
+                    message.algorithm,
+                    message.uncompressed_length,
+                    &message.data,
+                )?;
+                Self::decode_proto_from_slice_with(&inflated, context)
+            }
+            replication_proto::runtime_message::Body::Encoded(message) => {
+                let payload =
+                    EncodedPayload::decode_proto(*message).context(EncodedPayloadSnafu)?;
+                decode_encoded_payload(&payload, context)
+            }
         }
     }
 }
@@ -165,10 +371,105 @@ impl DecodeProtoViewWith<RuntimeMessageDecodeContext<'_>> for RuntimeMessage {
                 let message = MigrationProposalMessage::decode_proto_view(message)?;
                 Ok(Self::MigrationProposal(message))
             }
+            replication_proto::runtime_message::BodyView::UpdateAck(message) => {
+                let message = UpdateAckMessage::decode_proto_view(message)?;
+                Ok(Self::UpdateAck(message))
+            }
+            replication_proto::runtime_message::BodyView::FrontierAck(message) => {
+                let member_count =
+                    member_count_context(message.group_id, "frontier_ack.group_id", context)?;
+                let message = FrontierAckMessage::decode_proto_view_with(message, member_count)?;
+                Ok(Self::FrontierAck(message))
+            }
+            replication_proto::runtime_message::BodyView::Throttled(message) => {
+                let message = ThrottledMessage::decode_proto_view(message)?;
+                Ok(Self::Throttled(message))
+            }
+            replication_proto::runtime_message::BodyView::BlobChunkRequest(message) => {
+                let message = BlobChunkRequest::decode_proto_view(message)
+                    .map_err(Box::new)
+                    .context(InvalidBlobPayloadSnafu)?;
+                Ok(Self::BlobChunkRequest(message))
+            }
+            replication_proto::runtime_message::BodyView::BlobChunk(message) => {
+                let message = BlobChunk::decode_proto_view(message)
+                    .map_err(Box::new)
+                    .context(InvalidBlobPayloadSnafu)?;
+                Ok(Self::BlobChunk(message))
+            }
+            replication_proto::runtime_message::BodyView::BlobUnavailable(message) => {
+                let message = BlobUnavailable::decode_proto_view(message)
+                    .map_err(Box::new)
+                    .context(InvalidBlobPayloadSnafu)?;
+                Ok(Self::BlobUnavailable(message))
+            }
+            replication_proto::runtime_message::BodyView::SnapshotManifestRequest(message) => {
+                let message = SnapshotManifestRequest::decode_proto_view(message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotManifestRequest(message))
+            }
+            replication_proto::runtime_message::BodyView::SnapshotManifest(message) => {
+                let message = SnapshotManifest::decode_proto_view(message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotManifest(message))
+            }
+            replication_proto::runtime_message::BodyView::SnapshotChunkRequest(message) => {
+                let message = SnapshotChunkRequest::decode_proto_view(message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotChunkRequest(message))
+            }
+            replication_proto::runtime_message::BodyView::SnapshotChunk(message) => {
+                let message = SnapshotChunk::decode_proto_view(message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotChunk(message))
+            }
+            replication_proto::runtime_message::BodyView::SnapshotUnavailable(message) => {
+                let message = SnapshotUnavailable::decode_proto_view(message)
+                    .map_err(Box::new)
+                    .context(InvalidSnapshotPayloadSnafu)?;
+                Ok(Self::SnapshotUnavailable(message))
+            }
+            replication_proto::runtime_message::BodyView::Compressed(message) => {
+                let (inflated, context) = context.inflate(
+                    message.algorithm,
+                    message.uncompressed_length,
+                    message.data,
+                )?;
+                Self::decode_proto_view_from_slice_with(&inflated, context)
+            }
+            replication_proto::runtime_message::BodyView::Encoded(message) => {
+                let encoding =
+                    PayloadEncoding::from_wire(message.encoding).context(EncodedPayloadSnafu)?;
+                let payload = EncodedPayload {
+                    encoding,
+                    encoding_version: message.encoding_version,
+                    data: Bytes::copy_from_slice(message.data),
+                };
+                decode_encoded_payload(&payload, context)
+            }
         }
     }
 }
 
+/// Decode the update or update batch carried in a payload with a non-protobuf encoding.
+fn decode_encoded_payload(
+    payload: &EncodedPayload,
+    context: RuntimeMessageDecodeContext<'_>,
+) -> Result<RuntimeMessage, RuntimeMessageError> {
+    ensure!(
+        payload.encoding != PayloadEncoding::Protobuf,
+        UnexpectedProtobufEncodedPayloadSnafu
+    );
+    let message = payload
+        .decode::<replication_proto::RuntimeMessage>()
+        .context(EncodedPayloadSnafu)?;
+    RuntimeMessage::decode_proto_with(message, context)
+}
+
 /// Resolve the compact-vector member count from one message group id.
 fn member_count_context(
     group_id: &[u8],
@@ -88,6 +88,8 @@ impl EncodeProto for SummaryVersionsMessageView<'_, VersionVector> {
             has_versions: MessageField::some(
                 CompactVersionVectorProtoCodec::from(self.has_versions).encode_proto(),
             ),
+            accepts_compression: MessageField::some(self.accepts_compression.encode_proto()),
+            accepts_encodings: MessageField::some(self.accepts_encodings.encode_proto()),
             ..replication_proto::Summary::default()
         }
     }
@@ -16,7 +16,8 @@ impl GroupInvitationMessage {
         group_setup: Arc<GroupSetupMessage>,
     ) -> Result<Self, RuntimeMessageError> {
         ensure!(
-            invitation.proposed_members == group_setup.members(),
+            invitation.proposed_members == group_setup.members()
+                && invitation.proposed_observers == group_setup.observers(),
             GroupSetupMemberMismatchSnafu
         );
         Ok(Self {
@@ -85,7 +86,8 @@ impl MigrationProposalMessage {
         group_setup: Arc<GroupSetupMessage>,
     ) -> Result<Self, RuntimeMessageError> {
         ensure!(
-            proposal.proposed_members == group_setup.members(),
+            proposal.proposed_members == group_setup.members()
+                && proposal.proposed_observers == group_setup.observers(),
             GroupSetupMemberMismatchSnafu
         );
         Ok(Self {
@@ -144,29 +146,42 @@ impl DecodeProtoView for MigrationProposalMessage {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct GroupSetupMessage {
     members: Vec<MemberIdentity>,
+    /// Observers of the group, which hold no member index.
+    observers: Vec<MemberIdentity>,
+    /// Key references for both `members` and `observers`.
     member_keys: TrieMap<BootstrapMemberKeyMessage>,
     group_cipher_suite: GroupCipherSuite,
     group_key: GroupSetupKey,
 }
 
 impl GroupSetupMessage {
-    /// Build setup material whose member list and member-key map cover
-    /// exactly the same identities.
+    /// Build setup material whose member and observer lists together cover
+    /// exactly the identities of the member-key map.
     ///
     /// # Errors
     ///
-    /// Returns [`RuntimeMessageError`] if the group is empty, no member-key
+    /// Returns [`RuntimeMessageError`] if the group has no members, no member-key
     /// references are present, the member-key map does not match the member
-    /// list, or an inline public bundle is bound to a different member.
+    /// and observer lists, or an inline public bundle is bound to a different member.
     pub(crate) fn new(
         members: Vec<MemberIdentity>,
+        observers: Vec<MemberIdentity>,
         member_keys: TrieMap<BootstrapMemberKeyMessage>,
         group_cipher_suite: GroupCipherSuite,
         group_key: GroupSetupKey,
     ) -> Result<Self, RuntimeMessageError> {
-        validate_bootstrap_member_key_coverage(&members, &member_keys)?;
+        if members.is_empty() {
+            return EmptyGroupSetupSnafu.fail();
+        }
+        let covered_identities = members
+            .iter()
+            .chain(&observers)
+            .cloned()
+            .collect::<Vec<_>>();
+        validate_bootstrap_member_key_coverage(&covered_identities, &member_keys)?;
         Ok(Self {
             members,
+            observers,
             member_keys,
             group_cipher_suite,
             group_key,
@@ -177,13 +192,36 @@ impl GroupSetupMessage {
         &self.members
     }
 
+    pub(crate) fn observers(&self) -> &[MemberIdentity] {
+        &self.observers
+    }
+
     pub(crate) fn member_keys(&self) -> &TrieMap<BootstrapMemberKeyMessage> {
         &self.member_keys
     }
 
     /// Return ordered exact member-key references for persisted group metadata.
     pub(crate) fn ordered_member_key_ids(&self) -> Vec<MemberKeyId> {
-        self.members
+        self.member_key_ids(&self.members)
+    }
+
+    /// Return exact observer-key references for persisted group metadata.
+    pub(crate) fn observer_key_ids(&self) -> Vec<MemberKeyId> {
+        self.member_key_ids(&self.observers)
+    }
+
+    /// Build the persisted member-key set, including observer keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GroupMembersError`] when the members and observers do not form a valid group.
+    pub(crate) fn group_member_keys(&self) -> Result<GroupMemberKeys, GroupMembersError> {
+        GroupMemberKeys::from_ordered_member_keys(self.ordered_member_key_ids())?
+            .with_observer_keys(self.observer_key_ids())
+    }
+
+    fn member_key_ids(&self, member_ids: &[MemberIdentity]) -> Vec<MemberKeyId> {
+        member_ids
             .iter()
             .map(|member_id| {
                 let member_key = self
@@ -208,23 +246,25 @@ impl proto::ProtoCodec for GroupSetupMessage {
     type Proto = replication_proto::GroupSetup;
 
     fn to_proto(&self) -> Self::Proto {
-        let member_keys = self
-            .members
-            .iter()
-            .map(|member| {
-                let member_key = self
-                    .member_keys
-                    .get(member)
-                    .expect("bootstrap member key references must cover every member");
-                BootstrapMemberKeyProtoSource {
-                    member_id: member,
-                    member_key,
-                }
-                .encode_proto()
-            })
-            .collect();
+        let encode_member_keys = |member_ids: &[MemberIdentity]| {
+            member_ids
+                .iter()
+                .map(|member| {
+                    let member_key = self
+                        .member_keys
+                        .get(member)
+                        .expect("bootstrap member key references must cover every member");
+                    BootstrapMemberKeyProtoSource {
+                        member_id: member,
+                        member_key,
+                    }
+                    .encode_proto()
+                })
+                .collect()
+        };
         replication_proto::GroupSetup {
-            member_keys,
+            member_keys: encode_member_keys(&self.members),
+            observer_keys: encode_member_keys(&self.observers),
             group_cipher_suite: u32::from(self.group_cipher_suite.as_u16()),
             group_key: self.group_key.to_bytes().to_vec(),
             ..replication_proto::GroupSetup::default()
@@ -242,6 +282,12 @@ impl proto::ProtoCodec for GroupSetupMessage {
             members.push(entry.member_id.clone());
             member_keys.insert(entry.member_id, entry.member_key);
         }
+        let mut observers = Vec::with_capacity(message.observer_keys.len());
+        for observer_key in message.observer_keys {
+            let entry = BootstrapMemberKeyEntry::decode_proto(observer_key)?;
+            observers.push(entry.member_id.clone());
+            member_keys.insert(entry.member_id, entry.member_key);
+        }
         let expected = GROUP_CIPHER_SUITE_CHACHA20_POLY1305.as_u16();
         ensure!(
             message.group_cipher_suite == u32::from(expected),
@@ -254,6 +300,7 @@ impl proto::ProtoCodec for GroupSetupMessage {
             fixed_bytes_field::<GROUP_KEY_LENGTH>("group_setup.group_key", &message.group_key)?;
         Self::new(
             members,
+            observers,
             member_keys,
             GROUP_CIPHER_SUITE_CHACHA20_POLY1305,
             GroupSetupKey::from_bytes(group_key),
@@ -276,6 +323,12 @@ impl DecodeProtoView for GroupSetupMessage {
             members.push(entry.member_id.clone());
             member_keys.insert(entry.member_id, entry.member_key);
         }
+        let mut observers = Vec::with_capacity(message.observer_keys.len());
+        for observer_key in &message.observer_keys {
+            let entry = BootstrapMemberKeyEntry::decode_proto_view(observer_key)?;
+            observers.push(entry.member_id.clone());
+            member_keys.insert(entry.member_id, entry.member_key);
+        }
         let expected = GROUP_CIPHER_SUITE_CHACHA20_POLY1305.as_u16();
         ensure!(
             message.group_cipher_suite == u32::from(expected),
@@ -288,6 +341,7 @@ impl DecodeProtoView for GroupSetupMessage {
             fixed_bytes_field::<GROUP_KEY_LENGTH>("group_setup.group_key", message.group_key)?;
         Self::new(
             members,
+            observers,
             member_keys,
             GROUP_CIPHER_SUITE_CHACHA20_POLY1305,
             GroupSetupKey::from_bytes(group_key),
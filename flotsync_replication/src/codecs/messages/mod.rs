@@ -5,30 +5,61 @@ use crate::{
         DatasetIdError,
         DatasetUpdateRecord,
         GroupInvitation,
+        GroupMemberKeys,
         MemberKeyId,
         MigrationProposal,
         ReplicationUpdateRecord,
     },
+    blobs::{BlobChunk, BlobChunkRequest, BlobError, BlobUnavailable},
     codecs::pending_group::PendingGroupPayloadError,
-    delivery::wire::{
-        WireValueDecodeError,
-        group_id_from_wire,
-        member_identity_from_wire,
-        member_identity_to_wire_format,
+    delivery::{
+        compression::{
+            CompressionError,
+            CompressionMetrics,
+            CompressionOffer,
+            ConnectionCompression,
+            PayloadClass,
+            inflate_offered,
+        },
+        wire::{
+            WireValueDecodeError,
+            group_id_from_wire,
+            member_identity_from_wire,
+            member_identity_to_wire_format,
+        },
+    },
+    snapshot_transfer::{
+        SnapshotChunk,
+        SnapshotChunkRequest,
+        SnapshotManifest,
+        SnapshotManifestRequest,
+        SnapshotTransferError,
+        SnapshotUnavailable,
     },
 };
 use borrowize::View;
+use bytes::Bytes;
 use flotsync_core::{
     GroupId,
     MemberIdentity,
     member::TrieMap,
-    membership::GroupMemberships,
-    versions::{OverrideVersion, PureVersionVector, UpdateId, VersionVector, VersionVectorGap},
+    membership::{GroupMembersError, GroupMemberships},
+    versions::{
+        MultiOverrideVersion,
+        OverrideVersion,
+        PureVersionVector,
+        SparseVersionVector,
+        UpdateId,
+        VersionVector,
+        VersionVectorGap,
+    },
 };
 use flotsync_messages::{
-    buffa::MessageField,
+    buffa::{EnumValue, Message as _, MessageField},
     codecs::datamodel::{CodecError as DatamodelCodecError, decode_update_id, encode_update_id},
     datamodel as datamodel_proto,
+    delivery as delivery_proto,
+    encoding::{EncodedPayload, EncodingOffer, PayloadEncoding, PayloadEncodingError},
     proto::{
         self,
         DecodeProto,
@@ -58,17 +89,20 @@ use snafu::prelude::*;
 use std::{borrow::Cow, fmt, num::NonZeroUsize, sync::Arc};
 use uuid::Uuid;
 
+mod acknowledgements;
 mod common;
 mod control;
 mod encoding;
 mod group;
 #[cfg(test)]
 mod tests;
+mod throttle;
 mod updates;
 mod versions;
 
+pub(crate) use acknowledgements::*;
 pub(crate) use common::*;
-pub(crate) use control::RuntimeMessage;
+pub(crate) use control::{RuntimeMessage, encode_operation_payload};
 pub(crate) use encoding::*;
 pub(crate) use group::{
     BootstrapMemberKeyMessage,
@@ -78,5 +112,6 @@ pub(crate) use group::{
     MigrationProposalMessage,
     validate_bootstrap_public_key_bundle_matches_fingerprint,
 };
+pub(crate) use throttle::*;
 pub(crate) use updates::*;
 pub(crate) use versions::*;
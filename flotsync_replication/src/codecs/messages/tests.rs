@@ -5,6 +5,7 @@ use super::{
     CompactVersionVectorProtoCodec,
     DatasetUpdateMessage,
     DatasetUpdateMessageView,
+    FrontierAckMessage,
     GroupInvitationMessage,
     GroupSetupKey,
     GroupSetupMessage,
@@ -16,6 +17,8 @@ use super::{
     RuntimeMessageError,
     SummaryMessage,
     SummaryRequestMessage,
+    ThrottledMessage,
+    UpdateAckMessage,
     UpdateBatchMessage,
     UpdateMessage,
     UpdateMessageProtoSource,
@@ -39,6 +42,27 @@ use crate::{
         RowKey,
         RowValues,
         SnapshotRef,
+        ThrottleReason,
+    },
+    blobs::{BlobChunk, BlobChunkRequest, BlobError, BlobHash, BlobUnavailable},
+    delivery::compression::{
+        CompressedPayload,
+        CompressionAlgorithm,
+        CompressionCodec,
+        CompressionError,
+        CompressionMetrics,
+        CompressionOffer,
+        ConnectionCompression,
+    },
+    snapshot_transfer::{
+        SnapshotChunk,
+        SnapshotChunkId,
+        SnapshotChunkRequest,
+        SnapshotId,
+        SnapshotManifest,
+        SnapshotManifestRequest,
+        SnapshotTransferError,
+        SnapshotUnavailable,
     },
     test_support::{
         docs_dataset_id,
@@ -47,22 +71,31 @@ use crate::{
         test_public_member_keys,
     },
 };
+use bytes::Bytes;
 use flotsync_core::{
     GroupId,
     MemberIdentity,
     member::TrieMap,
     membership::{GroupMembers, GroupMemberships},
-    versions::{OverrideVersion, PureVersionVector, UpdateId, VersionVector},
+    versions::{
+        MultiOverrideVersion,
+        OverrideVersion,
+        PureVersionVector,
+        SparseVersionVector,
+        UpdateId,
+        VersionVector,
+    },
 };
 use flotsync_messages::{
-    buffa::{Message as _, MessageView as _},
+    buffa::{EnumValue, Message as _, MessageView as _},
     datamodel as datamodel_proto,
+    encoding::{EncodingOffer, PayloadEncoding},
     proto::{DecodeProto, DecodeProtoView, DecodeProtoViewWith, DecodeProtoWith, EncodeProto},
     replication as replication_proto,
     versions as versions_proto,
 };
 use flotsync_security::{GROUP_CIPHER_SUITE_CHACHA20_POLY1305, GROUP_KEY_LENGTH};
-use std::{num::NonZeroUsize, sync::Arc};
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
 use uuid::Uuid;
 
 fn test_update_message(
@@ -76,14 +109,29 @@ fn test_update_message(
         read_versions,
         dataset_updates: vec![DatasetUpdateMessage {
             dataset_id: DatasetId::try_new("docs").expect("dataset id should build"),
-            operations: vec![datamodel_proto::SchemaOperation::default()],
+            operations: vec![datamodel_proto::SchemaOperation {
+                operation: Some(datamodel_proto::schema_operation::Operation::Delete(
+                    Box::new(datamodel_proto::DeleteRowOperation {
+                        row_id: vec![7; 16],
+                        ..datamodel_proto::DeleteRowOperation::default()
+                    }),
+                )),
+                ..datamodel_proto::SchemaOperation::default()
+            }],
         }],
     }
 }
 
 fn test_group_setup(members: &[MemberIdentity]) -> Arc<GroupSetupMessage> {
+    test_observed_group_setup(members, &[])
+}
+
+fn test_observed_group_setup(
+    members: &[MemberIdentity],
+    observers: &[MemberIdentity],
+) -> Arc<GroupSetupMessage> {
     let mut member_keys = TrieMap::new();
-    for member in members {
+    for member in members.iter().chain(observers) {
         member_keys.insert(
             member.clone(),
             BootstrapMemberKeyMessage::from_public_keys(&test_public_member_keys(member)),
@@ -92,6 +140,7 @@ fn test_group_setup(members: &[MemberIdentity]) -> Arc<GroupSetupMessage> {
     Arc::new(
         GroupSetupMessage::new(
             members.to_vec(),
+            observers.to_vec(),
             member_keys,
             GROUP_CIPHER_SUITE_CHACHA20_POLY1305,
             GroupSetupKey::from_bytes([5; GROUP_KEY_LENGTH]),
@@ -188,8 +237,17 @@ fn compact_and_self_describing_version_vectors_round_trip_all_representations()
         num_members: NonZeroUsize::new(3).expect("three members"),
         version: 11,
     };
+    let multi_override = VersionVector::MultiOverride {
+        num_members: NonZeroUsize::new(8).expect("eight members"),
+        version: MultiOverrideVersion::new(5, [(1, 7), (6, 6)]),
+    };
+
+    let sparse = VersionVector::Sparse {
+        num_members: NonZeroUsize::new(8).expect("eight members"),
+        version: SparseVersionVector::new(5, [(0, 2), (3, 9)]),
+    };
 
-    for vector in [full, override_vector, synced] {
+    for vector in [full, override_vector, synced, multi_override, sparse] {
         let member_count = MemberCountContext::new(vector.num_members());
         let compact = CompactVersionVectorProtoCodec::from(&vector).encode_proto();
         let compact_payload = compact.encode_to_bytes();
@@ -217,6 +275,155 @@ fn compact_and_self_describing_version_vectors_round_trip_all_representations()
     }
 }
 
+/// Checks the shared vectors in `conformance/version_vector_encodings.toml`.
+#[test]
+fn self_describing_version_vectors_match_the_conformance_encodings() {
+    let vectors: toml::Table =
+        include_str!("../../../../conformance/version_vector_encodings.toml")
+            .parse()
+            .expect("conformance vectors must be valid TOML");
+    let encodings = vectors["encoding"]
+        .as_array()
+        .expect("encoding must be an array of tables");
+    assert!(!encodings.is_empty());
+
+    for encoding in encodings {
+        let notation = encoding["vector"].as_str().expect("vector notation");
+        let vector: VersionVector = notation.parse().expect("valid version vector notation");
+        let representation = match &vector {
+            VersionVector::Full(_) => "full",
+            VersionVector::Override { .. } => "override",
+            VersionVector::MultiOverride { .. } => "multi-override",
+            VersionVector::Sparse { .. } => "sparse",
+            VersionVector::Synced { .. } => "synced",
+        };
+        assert_eq!(
+            representation,
+            encoding["representation"].as_str().expect("representation"),
+            "{notation}: representation"
+        );
+        let hex = encoding["encoded"].as_str().expect("encoded hex");
+        let expected: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|start| u8::from_str_radix(&hex[start..start + 2], 16).expect("hex byte"))
+            .collect();
+
+        let encoded = VersionVectorProtoCodec::from(&vector)
+            .encode_proto()
+            .encode_to_vec();
+        assert_eq!(encoded, expected, "{notation}: encoding");
+
+        let view = versions_proto::VersionVectorView::decode_view(&expected)
+            .expect("conformance encoding should decode");
+        let decoded = VersionVectorProtoCodec::decode_proto_view(&view)
+            .expect("conformance encoding should convert");
+        assert_eq!(
+            decoded.into_version_vector(),
+            vector,
+            "{notation}: decoding"
+        );
+    }
+}
+
+#[test]
+fn compact_version_vector_rejects_invalid_multi_overrides() {
+    let member_count = MemberCountContext::new(NonZeroUsize::new(8).expect("eight members"));
+    let vector = VersionVector::MultiOverride {
+        num_members: member_count.member_count(),
+        version: MultiOverrideVersion::new(5, [(1, 7), (6, 6)]),
+    };
+    let encoded = CompactVersionVectorProtoCodec::from(&vector).encode_proto();
+    let with_multi_override = |update: fn(&mut versions_proto::MultiOverrideVersionVector)| {
+        let mut proto = encoded.clone();
+        let Some(versions_proto::compact_version_vector::Versions::MultiOverride(multi_override)) =
+            proto.versions.as_mut()
+        else {
+            panic!("multi-override vector should encode as multi-override");
+        };
+        update(multi_override);
+        proto
+    };
+
+    let mismatched = with_multi_override(|proto| {
+        proto.override_versions.pop();
+    });
+    assert!(matches!(
+        CompactVersionVectorProtoCodec::decode_proto_with(mismatched, member_count),
+        Err(VersionVectorCodecError::MultiOverrideLengthMismatch {
+            positions: 2,
+            versions: 1,
+        })
+    ));
+    let out_of_range = with_multi_override(|proto| proto.override_positions[1] = 8);
+    assert!(matches!(
+        CompactVersionVectorProtoCodec::decode_proto_with(out_of_range, member_count),
+        Err(VersionVectorCodecError::InvalidOverridePosition {
+            num_members: 8,
+            override_position: 8,
+        })
+    ));
+    let unordered = with_multi_override(|proto| proto.override_positions.reverse());
+    assert!(matches!(
+        CompactVersionVectorProtoCodec::decode_proto_with(unordered, member_count),
+        Err(VersionVectorCodecError::InvalidMultiOverride {
+            group_version: 5,
+            override_count: 2,
+        })
+    ));
+    let behind_group = with_multi_override(|proto| proto.override_versions[0] = 5);
+    assert!(matches!(
+        CompactVersionVectorProtoCodec::decode_proto_with(behind_group, member_count),
+        Err(VersionVectorCodecError::InvalidMultiOverride { .. })
+    ));
+}
+
+#[test]
+fn compact_version_vector_rejects_invalid_sparse_vectors() {
+    let member_count = MemberCountContext::new(NonZeroUsize::new(8).expect("eight members"));
+    let vector = VersionVector::Sparse {
+        num_members: member_count.member_count(),
+        version: SparseVersionVector::new(5, [(0, 2), (3, 9)]),
+    };
+    let encoded = CompactVersionVectorProtoCodec::from(&vector).encode_proto();
+    let with_sparse = |update: fn(&mut versions_proto::SparseVersionVector)| {
+        let mut proto = encoded.clone();
+        let Some(versions_proto::compact_version_vector::Versions::Sparse(sparse)) =
+            proto.versions.as_mut()
+        else {
+            panic!("sparse vector should encode as sparse");
+        };
+        update(sparse);
+        proto
+    };
+
+    let mismatched = with_sparse(|proto| {
+        proto.positions.pop();
+    });
+    assert!(matches!(
+        CompactVersionVectorProtoCodec::decode_proto_with(mismatched, member_count),
+        Err(VersionVectorCodecError::SparseLengthMismatch {
+            positions: 1,
+            versions: 2,
+        })
+    ));
+    let at_base = with_sparse(|proto| proto.versions[1] = 5);
+    assert!(matches!(
+        CompactVersionVectorProtoCodec::decode_proto_with(at_base, member_count),
+        Err(VersionVectorCodecError::InvalidSparse {
+            base_version: 5,
+            entry_count: 2,
+        })
+    ));
+    let empty = with_sparse(|proto| {
+        proto.positions.clear();
+        proto.versions.clear();
+    });
+    assert!(matches!(
+        CompactVersionVectorProtoCodec::decode_proto_with(empty, member_count),
+        Err(VersionVectorCodecError::InvalidSparse { .. })
+    ));
+}
+
 #[test]
 fn self_describing_version_vector_rejects_invalid_member_counts() {
     let vector = VersionVector::Full(PureVersionVector::from([2, 3]));
@@ -263,6 +470,8 @@ fn summary_messages_round_trip_through_runtime_envelope() {
     let summary_request = RuntimeMessage::SummaryRequest(SummaryRequestMessage {
         group_id,
         correlation_id,
+        accepts_compression: CompressionOffer::supported(),
+        accepts_encodings: EncodingOffer::supported(),
     });
     let request_payload = summary_request.encode_proto().encode_to_bytes();
     let memberships = test_memberships(&[(group_id, 2)]);
@@ -273,15 +482,19 @@ fn summary_messages_round_trip_through_runtime_envelope() {
         RuntimeMessage::SummaryRequest(SummaryRequestMessage {
             group_id,
             correlation_id,
+            accepts_compression: CompressionOffer::supported(),
+            accepts_encodings: EncodingOffer::supported(),
         })
     );
 
     let has_versions = VersionVector::Full(PureVersionVector::from([2, 4]));
-    let summary = RuntimeMessage::Summary(SummaryMessage::new(
-        group_id,
-        correlation_id,
-        has_versions.clone(),
-    ));
+    let lz4_only = CompressionOffer::from_algorithms(CompressionAlgorithm::Lz4.into());
+    let cbor = EncodingOffer::from_encodings(PayloadEncoding::Cbor.into());
+    let summary = RuntimeMessage::Summary(
+        SummaryMessage::new(group_id, correlation_id, has_versions.clone())
+            .with_accepts_compression(lz4_only)
+            .with_accepts_encodings(cbor),
+    );
     let summary_payload = summary.encode_proto().encode_to_bytes();
     let decoded_summary =
         decode_runtime_message(&summary_payload, &memberships).expect("summary should decode");
@@ -292,9 +505,336 @@ fn summary_messages_round_trip_through_runtime_envelope() {
     assert_eq!(
         decoded_summary,
         SummaryMessage::new(group_id, correlation_id, has_versions)
+            .with_accepts_compression(lz4_only)
+            .with_accepts_encodings(cbor)
+    );
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn cbor_update_batches_round_trip_through_runtime_envelope() {
+    let group_id = GroupId(Uuid::from_u128(213));
+    let memberships = test_memberships(&[(group_id, 2)]);
+    let updates = (1..=16)
+        .map(|version| {
+            test_update_message(
+                group_id,
+                UpdateId {
+                    version,
+                    node_index: 1,
+                },
+                VersionVector::Full(PureVersionVector::from([0, version - 1])),
+            )
+        })
+        .collect();
+    let batch = RuntimeMessage::UpdateBatch(UpdateBatchMessage { group_id, updates });
+    let metrics = CompressionMetrics::new();
+    let payload = batch.encode_payload(
+        PayloadEncoding::Cbor,
+        &ConnectionCompression::disabled(),
+        &metrics,
+    );
+    assert_ne!(payload, batch.encode_proto_to_bytes());
+    assert_eq!(
+        decode_runtime_message(&payload, &memberships).expect("CBOR batch view should decode"),
+        batch
+    );
+    assert_eq!(
+        RuntimeMessage::decode_proto_from_slice_with(
+            &payload,
+            RuntimeMessageDecodeContext::new(&memberships),
+        )
+        .expect("owned CBOR batch should decode"),
+        batch
+    );
+
+    let reencoded = super::encode_operation_payload(
+        batch.encode_proto_to_bytes(),
+        PayloadEncoding::Cbor,
+        &ConnectionCompression::disabled(),
+        &metrics,
+    );
+    assert_eq!(reencoded, payload);
+
+    // Control messages have no CBOR form and stay plain protobuf.
+    let summary_request = RuntimeMessage::SummaryRequest(SummaryRequestMessage {
+        group_id,
+        correlation_id: Uuid::from_u128(214),
+        accepts_compression: CompressionOffer::NONE,
+        accepts_encodings: EncodingOffer::supported(),
+    });
+    assert_eq!(
+        summary_request.encode_payload(
+            PayloadEncoding::Cbor,
+            &ConnectionCompression::disabled(),
+            &metrics,
+        ),
+        summary_request.encode_proto_to_bytes()
     );
 }
 
+#[test]
+fn compressed_update_batches_decode_only_where_compression_is_accepted() {
+    let group_id = GroupId(Uuid::from_u128(212));
+    let memberships = test_memberships(&[(group_id, 2)]);
+    let updates = (1..=64)
+        .map(|version| {
+            test_update_message(
+                group_id,
+                UpdateId {
+                    version,
+                    node_index: 1,
+                },
+                VersionVector::Full(PureVersionVector::from([0, version - 1])),
+            )
+        })
+        .collect();
+    let batch = RuntimeMessage::UpdateBatch(UpdateBatchMessage { group_id, updates });
+    let metrics = CompressionMetrics::new();
+    let compression = ConnectionCompression {
+        codec: CompressionCodec::Lz4,
+        min_payload_bytes: 0,
+    };
+    let payload = batch.encode_payload(PayloadEncoding::Protobuf, &compression, &metrics);
+    assert!(payload.len() < batch.encode_proto_to_bytes().len());
+    assert_eq!(metrics.counters().operation_batches.compressed_payloads, 1);
+
+    let accepting = RuntimeMessageDecodeContext::new(&memberships).with_compression(
+        CompressionOffer::supported(),
+        usize::MAX,
+        &metrics,
+    );
+    assert_eq!(
+        RuntimeMessage::decode_proto_view_from_slice_with(&payload, accepting)
+            .expect("compressed batch view should decode"),
+        batch
+    );
+    assert_eq!(
+        RuntimeMessage::decode_proto_from_slice_with(&payload, accepting)
+            .expect("compressed batch should decode"),
+        batch
+    );
+    assert_eq!(metrics.counters().operation_batches.inflated_payloads, 2);
+
+    assert!(matches!(
+        decode_runtime_message(&payload, &memberships),
+        Err(RuntimeMessageError::UnexpectedCompressedPayload)
+    ));
+    let zstd_only = RuntimeMessageDecodeContext::new(&memberships).with_compression(
+        CompressionOffer::from_algorithms(CompressionAlgorithm::Zstd.into()),
+        usize::MAX,
+        &metrics,
+    );
+    assert!(matches!(
+        RuntimeMessage::decode_proto_view_from_slice_with(&payload, zstd_only),
+        Err(RuntimeMessageError::CompressedPayload {
+            source: CompressionError::NotAccepted { .. }
+        })
+    ));
+    let too_small = RuntimeMessageDecodeContext::new(&memberships).with_compression(
+        CompressionOffer::supported(),
+        64,
+        &metrics,
+    );
+    assert!(matches!(
+        RuntimeMessage::decode_proto_view_from_slice_with(&payload, too_small),
+        Err(RuntimeMessageError::CompressedPayload {
+            source: CompressionError::PayloadTooLarge { .. }
+        })
+    ));
+
+    // Wrap by hand, since compressed data rarely shrinks enough to be compressed again.
+    let nested = replication_proto::RuntimeMessage {
+        body: Some(replication_proto::runtime_message::Body::Compressed(
+            Box::new(
+                CompressedPayload {
+                    algorithm: CompressionAlgorithm::Lz4,
+                    uncompressed_length: payload.len() as u64,
+                    data: lz4_flex::block::compress(&payload).into(),
+                }
+                .encode_proto(),
+            ),
+        )),
+        ..replication_proto::RuntimeMessage::default()
+    }
+    .encode_to_bytes();
+    assert!(matches!(
+        RuntimeMessage::decode_proto_view_from_slice_with(&nested, accepting),
+        Err(RuntimeMessageError::UnexpectedCompressedPayload)
+    ));
+}
+
+#[test]
+fn acknowledgements_round_trip_through_runtime_envelope() {
+    let group_id = GroupId(Uuid::from_u128(102));
+    let memberships = test_memberships(&[(group_id, 3)]);
+
+    let update_ack = RuntimeMessage::UpdateAck(UpdateAckMessage {
+        group_id,
+        update_ids: vec![
+            UpdateId {
+                version: 4,
+                node_index: 0,
+            },
+            UpdateId {
+                version: 1,
+                node_index: 2,
+            },
+        ],
+    });
+    let payload = update_ack.encode_proto().encode_to_bytes();
+    assert_runtime_decode_paths(&payload, &memberships, &update_ack);
+
+    let frontier_ack = RuntimeMessage::FrontierAck(FrontierAckMessage {
+        group_id,
+        applied_versions: VersionVector::Full(PureVersionVector::from([4, 0, 1])),
+    });
+    let payload = frontier_ack.encode_proto().encode_to_bytes();
+    assert_runtime_decode_paths(&payload, &memberships, &frontier_ack);
+
+    let empty_ack = RuntimeMessage::UpdateAck(UpdateAckMessage {
+        group_id,
+        update_ids: Vec::new(),
+    })
+    .encode_proto()
+    .encode_to_bytes();
+    assert!(matches!(
+        decode_runtime_message(&empty_ack, &memberships),
+        Err(RuntimeMessageError::EmptyUpdateAck)
+    ));
+}
+
+#[test]
+fn throttle_notices_round_trip_through_runtime_envelope() {
+    let group_id = GroupId(Uuid::from_u128(103));
+    let memberships = test_memberships(&[(group_id, 2)]);
+
+    let throttled = RuntimeMessage::Throttled(ThrottledMessage {
+        group_id,
+        update_id: UpdateId {
+            version: 9,
+            node_index: 1,
+        },
+        reason: ThrottleReason::ByteRate,
+        retry_after: Duration::from_millis(1_250),
+    });
+    let payload = throttled.encode_proto().encode_to_bytes();
+    assert_runtime_decode_paths(&payload, &memberships, &throttled);
+
+    let mut unspecified = throttled.encode_proto();
+    let Some(replication_proto::runtime_message::Body::Throttled(message)) = &mut unspecified.body
+    else {
+        panic!("throttle notice should encode as a throttled body");
+    };
+    message.reason = EnumValue::from(0);
+    assert!(matches!(
+        decode_runtime_message(&unspecified.encode_to_bytes(), &memberships),
+        Err(RuntimeMessageError::UnknownThrottleReason { value: 0 })
+    ));
+}
+
+#[test]
+fn blob_messages_round_trip_through_runtime_envelope() {
+    let group_id = GroupId(Uuid::from_u128(104));
+    let memberships = test_memberships(&[(group_id, 2)]);
+    let hash = BlobHash::of(b"attachment");
+
+    let messages = [
+        RuntimeMessage::BlobChunkRequest(BlobChunkRequest {
+            group_id,
+            hash,
+            chunk_indices: vec![0, 2],
+        }),
+        RuntimeMessage::BlobChunk(BlobChunk {
+            group_id,
+            hash,
+            blob_length: 10,
+            chunk_count: 3,
+            chunk_index: 2,
+            data: Bytes::from_static(b"nt"),
+        }),
+        RuntimeMessage::BlobUnavailable(BlobUnavailable { group_id, hash }),
+    ];
+    for message in &messages {
+        let payload = message.encode_proto().encode_to_bytes();
+        assert_runtime_decode_paths(&payload, &memberships, message);
+    }
+
+    let mut truncated_hash = messages[2].encode_proto();
+    let Some(replication_proto::runtime_message::Body::BlobUnavailable(message)) =
+        &mut truncated_hash.body
+    else {
+        panic!("blob notice should encode as a blob-unavailable body");
+    };
+    message.blob_hash.truncate(4);
+    assert!(matches!(
+        decode_runtime_message(&truncated_hash.encode_to_bytes(), &memberships),
+        Err(RuntimeMessageError::InvalidBlobPayload { source })
+            if matches!(*source, BlobError::InvalidWireValue { .. })
+    ));
+}
+
+#[test]
+fn snapshot_messages_round_trip_through_runtime_envelope() {
+    let group_id = GroupId(Uuid::from_u128(105));
+    let memberships = test_memberships(&[(group_id, 2)]);
+    let snapshot_id = SnapshotId::of(&SnapshotRef {
+        group_id: GroupId(Uuid::from_u128(106)),
+        versions: VersionVector::Full(PureVersionVector::from([3, 4])),
+    });
+    let chunk_id = SnapshotChunkId::derive(
+        snapshot_id,
+        &docs_dataset_id(),
+        RowKey(Uuid::from_u128(1)),
+        RowKey(Uuid::from_u128(2)),
+    );
+
+    let messages = [
+        RuntimeMessage::SnapshotManifestRequest(SnapshotManifestRequest {
+            group_id,
+            snapshot_id,
+        }),
+        RuntimeMessage::SnapshotManifest(SnapshotManifest {
+            group_id,
+            snapshot_id,
+            chunks: Vec::new(),
+        }),
+        RuntimeMessage::SnapshotChunkRequest(SnapshotChunkRequest {
+            group_id,
+            snapshot_id,
+            chunk_ids: vec![chunk_id],
+        }),
+        RuntimeMessage::SnapshotChunk(SnapshotChunk {
+            group_id,
+            snapshot_id,
+            chunk_id,
+            compression: None,
+            data: Bytes::from_static(b"rows"),
+        }),
+        RuntimeMessage::SnapshotUnavailable(SnapshotUnavailable {
+            group_id,
+            snapshot_id,
+        }),
+    ];
+    for message in &messages {
+        let payload = message.encode_proto().encode_to_bytes();
+        assert_runtime_decode_paths(&payload, &memberships, message);
+    }
+
+    let mut truncated_id = messages[4].encode_proto();
+    let Some(replication_proto::runtime_message::Body::SnapshotUnavailable(message)) =
+        &mut truncated_id.body
+    else {
+        panic!("snapshot notice should encode as a snapshot-unavailable body");
+    };
+    message.snapshot_id.truncate(4);
+    assert!(matches!(
+        decode_runtime_message(&truncated_id.encode_to_bytes(), &memberships),
+        Err(RuntimeMessageError::InvalidSnapshotPayload { source })
+            if matches!(*source, SnapshotTransferError::InvalidWireValue { .. })
+    ));
+}
+
 #[test]
 fn updates_decode_with_member_count_context_from_owned_and_view() {
     let group_id = GroupId(Uuid::from_u128(211));
@@ -561,6 +1101,7 @@ fn pending_group_messages_round_trip_through_runtime_envelope() {
                 migration_id,
                 final_versions: final_versions.clone(),
                 proposed_members: members.clone(),
+                proposed_observers: Vec::new(),
                 group_schema: group_schema.clone(),
                 initial_snapshot: snapshot,
                 group_name: Some("docs".to_owned()),
@@ -577,6 +1118,53 @@ fn pending_group_messages_round_trip_through_runtime_envelope() {
     }
 }
 
+#[test]
+fn pending_group_messages_round_trip_observers_through_runtime_envelope() {
+    let migration_id = MigrationId {
+        old_group_id: GroupId(Uuid::from_u128(91_101)),
+        new_group_id: GroupId(Uuid::from_u128(91_102)),
+    };
+    let members = vec![MemberIdentity::from_array(["runtime-message", "alice"])];
+    let observers = vec![MemberIdentity::from_array(["runtime-message", "carol"])];
+    let group_setup = test_observed_group_setup(&members, &observers);
+    let memberships = GroupMemberships::new();
+
+    let invitation = GroupInvitation::new_migration(
+        migration_id,
+        members.clone(),
+        docs_group_schema(),
+        InitialSnapshot::Empty,
+        None,
+        None,
+    );
+    GroupInvitationMessage::try_new(invitation.clone(), Arc::clone(&group_setup))
+        .expect_err("invitation without the setup's observers should be rejected");
+    let invitation_message = GroupInvitationMessage::try_new(
+        invitation.with_proposed_observers(observers.clone()),
+        Arc::clone(&group_setup),
+    )
+    .expect("invitation observers should match setup");
+    let runtime_message = RuntimeMessage::GroupInvitation(invitation_message);
+    let invitation_payload = runtime_message.encode_proto().encode_to_bytes();
+    assert_runtime_decode_paths(&invitation_payload, &memberships, &runtime_message);
+
+    let proposal = MigrationProposal {
+        migration_id,
+        final_versions: VersionVector::Full(PureVersionVector::from([3])),
+        proposed_members: members,
+        proposed_observers: observers,
+        group_schema: docs_group_schema(),
+        initial_snapshot: InitialSnapshot::Empty,
+        group_name: None,
+        message: None,
+    };
+    let proposal_message = MigrationProposalMessage::try_new(proposal, group_setup)
+        .expect("proposal observers should match setup");
+    let runtime_message = RuntimeMessage::MigrationProposal(proposal_message);
+    let proposal_payload = runtime_message.encode_proto().encode_to_bytes();
+    assert_runtime_decode_paths(&proposal_payload, &memberships, &runtime_message);
+}
+
 #[test]
 fn pending_group_message_view_preserves_group_setup_validation() {
     let group_id = GroupId(Uuid::from_u128(92_001));
@@ -0,0 +1,116 @@
+//! Write throttle notice codecs.
+
+use super::*;
+use crate::api::ThrottleReason;
+use std::time::Duration;
+
+/// Notice that the sender dropped one live update of the recipient because of its write rate
+/// limit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ThrottledMessage {
+    pub(crate) group_id: GroupId,
+    /// The dropped update.
+    pub(crate) update_id: UpdateId,
+    pub(crate) reason: ThrottleReason,
+    /// How long the recipient should hold back further updates.
+    pub(crate) retry_after: Duration,
+}
+
+impl proto::ProtoCodec for ThrottledMessage {
+    type DecodeError = RuntimeMessageError;
+    type Proto = replication_proto::Throttled;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::Throttled {
+            group_id: self.group_id.0.as_bytes().to_vec(),
+            update_id: MessageField::some(encode_update_id(self.update_id)),
+            reason: throttle_reason_to_wire(self.reason),
+            retry_after_millis: retry_after_to_wire(self.retry_after),
+            ..replication_proto::Throttled::default()
+        }
+    }
+
+    fn from_proto(mut message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = group_id_from_wire(&message.group_id, "throttled.group_id").context(
+            InvalidWireValueSnafu {
+                field: "throttled.group_id",
+            },
+        )?;
+        let Some(update_id) = message.update_id.take() else {
+            return MissingUpdateIdSnafu.fail();
+        };
+        let update_id = decode_update_id(update_id).context(InvalidUpdateIdSnafu {
+            field: "throttled.update_id",
+        })?;
+        ensure_update_id_version_bound(update_id)?;
+        let reason = throttle_reason_from_wire(message.reason)?;
+        Ok(Self {
+            group_id,
+            update_id,
+            reason,
+            retry_after: Duration::from_millis(message.retry_after_millis),
+        })
+    }
+}
+
+impl DecodeProtoView for ThrottledMessage {
+    type Error = RuntimeMessageError;
+    type ProtoView<'a> = replication_proto::ThrottledView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let group_id = group_id_from_wire(message.group_id, "throttled.group_id").context(
+            InvalidWireValueSnafu {
+                field: "throttled.group_id",
+            },
+        )?;
+        let Some(update_id) = message.update_id.as_option() else {
+            return MissingUpdateIdSnafu.fail();
+        };
+        let update_id = UpdateId {
+            version: update_id.version,
+            node_index: update_id.node_index,
+        };
+        ensure_update_id_version_bound(update_id)?;
+        let reason = throttle_reason_from_wire(message.reason)?;
+        Ok(Self {
+            group_id,
+            update_id,
+            reason,
+            retry_after: Duration::from_millis(message.retry_after_millis),
+        })
+    }
+}
+
+fn throttle_reason_to_wire(reason: ThrottleReason) -> EnumValue<replication_proto::ThrottleReason> {
+    EnumValue::from(match reason {
+        ThrottleReason::OperationRate => {
+            replication_proto::ThrottleReason::THROTTLE_REASON_OPERATION_RATE
+        }
+        ThrottleReason::ByteRate => replication_proto::ThrottleReason::THROTTLE_REASON_BYTE_RATE,
+    })
+}
+
+fn throttle_reason_from_wire(
+    value: EnumValue<replication_proto::ThrottleReason>,
+) -> Result<ThrottleReason, RuntimeMessageError> {
+    match value.as_known() {
+        Some(replication_proto::ThrottleReason::THROTTLE_REASON_OPERATION_RATE) => {
+            Ok(ThrottleReason::OperationRate)
+        }
+        Some(replication_proto::ThrottleReason::THROTTLE_REASON_BYTE_RATE) => {
+            Ok(ThrottleReason::ByteRate)
+        }
+        Some(replication_proto::ThrottleReason::THROTTLE_REASON_UNSPECIFIED) | None => {
+            UnknownThrottleReasonSnafu {
+                value: value.to_i32(),
+            }
+            .fail()
+        }
+    }
+}
+
+/// Round up, so a recipient never retries before the sender's bucket has refilled.
+fn retry_after_to_wire(retry_after: Duration) -> u64 {
+    let millis = retry_after.as_nanos().div_ceil(1_000_000);
+    u64::try_from(millis).unwrap_or(u64::MAX)
+}
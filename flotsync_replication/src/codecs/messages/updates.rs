@@ -128,6 +128,10 @@ impl From<ReplicationUpdateRecord> for UpdateMessage {
 pub(crate) struct SummaryRequestMessage {
     pub(crate) group_id: GroupId,
     pub(crate) correlation_id: Uuid,
+    /// Compression the requester accepts on runtime payloads sent to it.
+    pub(crate) accepts_compression: CompressionOffer,
+    /// Encodings the requester accepts on updates sent to it.
+    pub(crate) accepts_encodings: EncodingOffer,
 }
 
 impl proto::ProtoCodec for SummaryRequestMessage {
@@ -138,6 +142,8 @@ impl proto::ProtoCodec for SummaryRequestMessage {
         replication_proto::SummaryRequest {
             group_id: self.group_id.0.as_bytes().to_vec(),
             correlation_id: self.correlation_id.as_bytes().to_vec(),
+            accepts_compression: MessageField::some(self.accepts_compression.encode_proto()),
+            accepts_encodings: MessageField::some(self.accepts_encodings.encode_proto()),
             ..replication_proto::SummaryRequest::default()
         }
     }
@@ -150,9 +156,23 @@ impl proto::ProtoCodec for SummaryRequestMessage {
         )?;
         let correlation_id =
             correlation_id_from_wire(&message.correlation_id, "summary_request.correlation_id")?;
+        let accepts_compression = message
+            .accepts_compression
+            .as_option()
+            .map_or(CompressionOffer::NONE, |offer| {
+                CompressionOffer::from_wire_values(&offer.algorithms)
+            });
+        let accepts_encodings = message
+            .accepts_encodings
+            .as_option()
+            .map_or(EncodingOffer::PROTOBUF, |offer| {
+                EncodingOffer::from_wire_values(&offer.encodings)
+            });
         Ok(Self {
             group_id,
             correlation_id,
+            accepts_compression,
+            accepts_encodings,
         })
     }
 }
@@ -169,9 +189,23 @@ impl DecodeProtoView for SummaryRequestMessage {
         )?;
         let correlation_id =
             correlation_id_from_wire(message.correlation_id, "summary_request.correlation_id")?;
+        let accepts_compression = message
+            .accepts_compression
+            .as_option()
+            .map_or(CompressionOffer::NONE, |offer| {
+                CompressionOffer::from_wire_values(offer.algorithms.iter())
+            });
+        let accepts_encodings = message
+            .accepts_encodings
+            .as_option()
+            .map_or(EncodingOffer::PROTOBUF, |offer| {
+                EncodingOffer::from_wire_values(offer.encodings.iter())
+            });
         Ok(Self {
             group_id,
             correlation_id,
+            accepts_compression,
+            accepts_encodings,
         })
     }
 }
@@ -181,16 +215,38 @@ pub(crate) struct SummaryVersionsMessage<V> {
     pub(crate) group_id: GroupId,
     pub(crate) correlation_id: Uuid,
     pub(crate) has_versions: V,
+    /// Compression the responder accepts on runtime payloads sent to it.
+    pub(crate) accepts_compression: CompressionOffer,
+    /// Encodings the responder accepts on updates sent to it.
+    pub(crate) accepts_encodings: EncodingOffer,
 }
 
 impl<V> SummaryVersionsMessage<V> {
+    /// Create a summary from a responder that only accepts uncompressed protobuf payloads.
     pub(crate) fn new(group_id: GroupId, correlation_id: Uuid, has_versions: V) -> Self {
         Self {
             group_id,
             correlation_id,
             has_versions,
+            accepts_compression: CompressionOffer::NONE,
+            accepts_encodings: EncodingOffer::PROTOBUF,
         }
     }
+
+    /// Advertise the compression the responder accepts.
+    pub(crate) fn with_accepts_compression(
+        mut self,
+        accepts_compression: CompressionOffer,
+    ) -> Self {
+        self.accepts_compression = accepts_compression;
+        self
+    }
+
+    /// Advertise the encodings the responder accepts.
+    pub(crate) fn with_accepts_encodings(mut self, accepts_encodings: EncodingOffer) -> Self {
+        self.accepts_encodings = accepts_encodings;
+        self
+    }
 }
 
 pub(crate) type SummaryMessage = SummaryVersionsMessage<VersionVector>;
@@ -225,7 +281,21 @@ impl proto::ProtoCodecWith<MemberCountContext> for SummaryMessage {
                 field: "summary.has_versions",
             })?;
         let has_versions = has_versions.into_version_vector();
-        Ok(Self::new(group_id, correlation_id, has_versions))
+        let accepts_compression = proto
+            .accepts_compression
+            .as_option()
+            .map_or(CompressionOffer::NONE, |offer| {
+                CompressionOffer::from_wire_values(&offer.algorithms)
+            });
+        let accepts_encodings = proto
+            .accepts_encodings
+            .as_option()
+            .map_or(EncodingOffer::PROTOBUF, |offer| {
+                EncodingOffer::from_wire_values(&offer.encodings)
+            });
+        Ok(Self::new(group_id, correlation_id, has_versions)
+            .with_accepts_compression(accepts_compression)
+            .with_accepts_encodings(accepts_encodings))
     }
 }
 
@@ -254,7 +324,21 @@ impl DecodeProtoViewWith<MemberCountContext> for SummaryMessage {
                 },
             )?;
         let has_versions = has_versions.into_version_vector();
-        Ok(Self::new(group_id, correlation_id, has_versions))
+        let accepts_compression = proto
+            .accepts_compression
+            .as_option()
+            .map_or(CompressionOffer::NONE, |offer| {
+                CompressionOffer::from_wire_values(offer.algorithms.iter())
+            });
+        let accepts_encodings = proto
+            .accepts_encodings
+            .as_option()
+            .map_or(EncodingOffer::PROTOBUF, |offer| {
+                EncodingOffer::from_wire_values(offer.encodings.iter())
+            });
+        Ok(Self::new(group_id, correlation_id, has_versions)
+            .with_accepts_compression(accepts_compression)
+            .with_accepts_encodings(accepts_encodings))
     }
 }
 
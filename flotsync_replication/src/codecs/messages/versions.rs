@@ -156,6 +156,48 @@ fn encode_compact_version_vector(
                 },
             ))
         }
+        VersionVector::MultiOverride { version, .. } => {
+            versions_proto::compact_version_vector::Versions::MultiOverride(Box::new(
+                versions_proto::MultiOverrideVersionVector {
+                    group_version: version.group_version(),
+                    override_positions: version
+                        .overrides()
+                        .iter()
+                        .map(|(position, _)| {
+                            u32::try_from(*position)
+                                .expect("version-vector override position must fit into u32")
+                        })
+                        .collect(),
+                    override_versions: version
+                        .overrides()
+                        .iter()
+                        .map(|(_, version)| *version)
+                        .collect(),
+                    ..versions_proto::MultiOverrideVersionVector::default()
+                },
+            ))
+        }
+        VersionVector::Sparse { version, .. } => {
+            versions_proto::compact_version_vector::Versions::Sparse(Box::new(
+                versions_proto::SparseVersionVector {
+                    base_version: version.base_version(),
+                    positions: version
+                        .entries()
+                        .iter()
+                        .map(|(position, _)| {
+                            u32::try_from(*position)
+                                .expect("version-vector entry position must fit into u32")
+                        })
+                        .collect(),
+                    versions: version
+                        .entries()
+                        .iter()
+                        .map(|(_, version)| *version)
+                        .collect(),
+                    ..versions_proto::SparseVersionVector::default()
+                },
+            ))
+        }
         VersionVector::Synced { version, .. } => {
             versions_proto::compact_version_vector::Versions::Synced(Box::new(
                 versions_proto::SyncedVersionVector {
@@ -191,6 +233,22 @@ fn decode_version_vector_body(
         versions_proto::compact_version_vector::Versions::Synced(synced) => {
             decode_synced_version_vector(synced.group_version, num_members)
         }
+        versions_proto::compact_version_vector::Versions::Sparse(sparse) => {
+            decode_sparse_version_vector(
+                sparse.base_version,
+                &sparse.positions,
+                &sparse.versions,
+                num_members,
+            )
+        }
+        versions_proto::compact_version_vector::Versions::MultiOverride(multi_override) => {
+            decode_multi_override_version_vector(
+                multi_override.group_version,
+                &multi_override.override_positions,
+                &multi_override.override_versions,
+                num_members,
+            )
+        }
     }
 }
 
@@ -214,6 +272,22 @@ fn decode_version_vector_view_body(
         versions_proto::compact_version_vector::VersionsView::Synced(synced) => {
             decode_synced_version_vector(synced.group_version, num_members)
         }
+        versions_proto::compact_version_vector::VersionsView::Sparse(sparse) => {
+            decode_sparse_version_vector(
+                sparse.base_version,
+                &sparse.positions,
+                &sparse.versions,
+                num_members,
+            )
+        }
+        versions_proto::compact_version_vector::VersionsView::MultiOverride(multi_override) => {
+            decode_multi_override_version_vector(
+                multi_override.group_version,
+                &multi_override.override_positions,
+                &multi_override.override_versions,
+                num_members,
+            )
+        }
     }
 }
 
@@ -269,6 +343,93 @@ fn decode_override_version_vector(
     })
 }
 
+/// Decode and validate the multi-member override representation.
+fn decode_multi_override_version_vector(
+    group_version: u64,
+    override_positions: &[u32],
+    override_versions: &[u64],
+    num_members: NonZeroUsize,
+) -> Result<VersionVector, VersionVectorCodecError> {
+    ensure_version_vector_bound("multi_override.group_version", group_version)?;
+    ensure!(
+        override_positions.len() == override_versions.len(),
+        MultiOverrideLengthMismatchSnafu {
+            positions: override_positions.len(),
+            versions: override_versions.len(),
+        }
+    );
+    let mut overrides = Vec::with_capacity(override_positions.len());
+    for (override_position, override_version) in override_positions
+        .iter()
+        .copied()
+        .zip(override_versions.iter().copied())
+    {
+        ensure_version_vector_bound("multi_override.override_versions", override_version)?;
+        let override_position_index =
+            usize::try_from(override_position).expect("u32 override position must fit into usize");
+        ensure!(
+            override_position_index < num_members.get(),
+            InvalidOverridePositionSnafu {
+                num_members: num_members.get(),
+                override_position,
+            }
+        );
+        overrides.push((override_position_index, override_version));
+    }
+    let override_count = overrides.len();
+    let version = MultiOverrideVersion::new_opt(group_version, overrides).context(
+        InvalidMultiOverrideSnafu {
+            group_version,
+            override_count,
+        },
+    )?;
+    Ok(VersionVector::MultiOverride {
+        num_members,
+        version,
+    })
+}
+
+/// Decode and validate the sparse representation.
+fn decode_sparse_version_vector(
+    base_version: u64,
+    positions: &[u32],
+    versions: &[u64],
+    num_members: NonZeroUsize,
+) -> Result<VersionVector, VersionVectorCodecError> {
+    ensure_version_vector_bound("sparse.base_version", base_version)?;
+    ensure!(
+        positions.len() == versions.len(),
+        SparseLengthMismatchSnafu {
+            positions: positions.len(),
+            versions: versions.len(),
+        }
+    );
+    let mut entries = Vec::with_capacity(positions.len());
+    for (position, version) in positions.iter().copied().zip(versions.iter().copied()) {
+        ensure_version_vector_bound("sparse.versions", version)?;
+        let position_index =
+            usize::try_from(position).expect("u32 entry position must fit into usize");
+        ensure!(
+            position_index < num_members.get(),
+            InvalidOverridePositionSnafu {
+                num_members: num_members.get(),
+                override_position: position,
+            }
+        );
+        entries.push((position_index, version));
+    }
+    let entry_count = entries.len();
+    let version =
+        SparseVersionVector::new_opt(base_version, entries).context(InvalidSparseSnafu {
+            base_version,
+            entry_count,
+        })?;
+    Ok(VersionVector::Sparse {
+        num_members,
+        version,
+    })
+}
+
 /// Decode and validate the fully synchronised representation.
 fn decode_synced_version_vector(
     group_version: u64,
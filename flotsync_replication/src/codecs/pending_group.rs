@@ -255,6 +255,7 @@ impl EncodeProto for GroupInvitation {
             group_id: message_wire::group_id_to_wire_bytes(self.group_id),
             source: MessageField::some(EncodeProto::encode_proto(&self.source)),
             proposed_members: encode_member_identities(&self.proposed_members),
+            proposed_observers: encode_member_identities(&self.proposed_observers),
             dataset_schemas: DatasetSchema::encode_proto_collection(
                 self.group_schema.datasets().iter(),
             ),
@@ -280,6 +281,8 @@ impl DecodeProto for GroupInvitation {
         )?;
         let proposed_members =
             decode_member_identities(invitation.proposed_members, "group_invitation.members")?;
+        let proposed_observers =
+            decode_member_identities(invitation.proposed_observers, "group_invitation.observers")?;
         let group_schema = decode_group_schema(invitation.dataset_schemas)?;
         let snapshot_context = InitialSnapshotDecodeContext {
             group_schema: &group_schema,
@@ -297,6 +300,7 @@ impl DecodeProto for GroupInvitation {
             invitation.group_name,
             invitation.message,
         )
+        .map(|invitation| invitation.with_proposed_observers(proposed_observers))
         .map_err(PendingGroupPayloadError::from)
     }
 }
@@ -317,6 +321,10 @@ impl DecodeProtoView for GroupInvitation {
         let source = GroupInvitationSource::decode_proto_view(source)?;
         let proposed_members =
             decode_member_identity_views(&invitation.proposed_members, "group_invitation.members")?;
+        let proposed_observers = decode_member_identity_views(
+            &invitation.proposed_observers,
+            "group_invitation.observers",
+        )?;
         let group_schema = decode_group_schema_view(&invitation.dataset_schemas)?;
         let snapshot_context = InitialSnapshotDecodeContext {
             group_schema: &group_schema,
@@ -334,6 +342,7 @@ impl DecodeProtoView for GroupInvitation {
             invitation.group_name.map(str::to_owned),
             invitation.message.map(str::to_owned),
         )
+        .map(|invitation| invitation.with_proposed_observers(proposed_observers))
         .map_err(PendingGroupPayloadError::from)
     }
 }
@@ -455,6 +464,7 @@ impl EncodeProto for MigrationProposal {
                 VersionVectorProtoCodec::from(&self.final_versions).encode_proto(),
             ),
             proposed_members: encode_member_identities(&self.proposed_members),
+            proposed_observers: encode_member_identities(&self.proposed_observers),
             dataset_schemas: DatasetSchema::encode_proto_collection(
                 self.group_schema.datasets().iter(),
             ),
@@ -481,6 +491,8 @@ impl DecodeProto for MigrationProposal {
                 .context(InvalidWireValueSnafu)?;
         let proposed_members =
             decode_member_identities(proposal.proposed_members, "migration_proposal.members")?;
+        let proposed_observers =
+            decode_member_identities(proposal.proposed_observers, "migration_proposal.observers")?;
         let final_versions = proposal
             .final_versions
             .take_required_proto_field::<PendingGroupPayloadError>(
@@ -509,6 +521,7 @@ impl DecodeProto for MigrationProposal {
             },
             final_versions,
             proposed_members,
+            proposed_observers,
             group_schema,
             initial_snapshot,
             group_name: proposal.group_name,
@@ -532,6 +545,10 @@ impl DecodeProtoView for MigrationProposal {
                 .context(InvalidWireValueSnafu)?;
         let proposed_members =
             decode_member_identity_views(&proposal.proposed_members, "migration_proposal.members")?;
+        let proposed_observers = decode_member_identity_views(
+            &proposal.proposed_observers,
+            "migration_proposal.observers",
+        )?;
         let Some(final_versions) = proposal.final_versions.as_option() else {
             return Err(PendingGroupPayloadError::missing_required_field(
                 "migration_proposal.final_versions",
@@ -560,6 +577,7 @@ impl DecodeProtoView for MigrationProposal {
             },
             final_versions,
             proposed_members,
+            proposed_observers,
             group_schema,
             initial_snapshot,
             group_name: proposal.group_name.map(str::to_owned),
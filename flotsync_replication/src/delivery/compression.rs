@@ -0,0 +1,819 @@
+//! Per-connection compression of runtime and snapshot payloads.
+//!
+//! Compression is applied to plaintext payloads before they are sealed, since ciphertext does not
+//! compress. Each peer advertises the algorithms it can decompress in a [`CompressionOffer`] while
+//! exchanging summaries. The sender combines that offer with its local [`CompressionPolicy`] for
+//! the link the payload travels over, which yields one [`ConnectionCompression`] per connection.
+//!
+//! Group broadcasts are sealed once for every recipient, so they are compressed with a codec
+//! negotiated against the intersection of the recipients' offers.
+//!
+//! Every compression decision and every decompressed payload is counted in
+//! [`CompressionMetrics`], so applications can observe how much bandwidth compression saves.
+
+use bytes::Bytes;
+use enumset::{EnumSet, EnumSetType};
+use flotsync_messages::{
+    buffa::{self, EnumValue},
+    delivery as delivery_proto,
+    proto::{FromProtoDecodeError, ProtoCodec},
+};
+use snafu::prelude::*;
+use std::{
+    net::IpAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Zstd level used by [`CompressionPolicy::default`] and when falling back to zstd.
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// Payloads smaller than this are sent uncompressed by [`CompressionPolicy::default`].
+pub const DEFAULT_MIN_COMPRESSED_PAYLOAD_BYTES: usize = 1024;
+
+/// Compression algorithm understood on the wire.
+#[derive(Debug, EnumSetType, Hash)]
+pub enum CompressionAlgorithm {
+    Lz4,
+    Zstd,
+}
+
+impl CompressionAlgorithm {
+    /// Decode an algorithm from its wire form.
+    ///
+    /// Returns `Ok(None)` for the unspecified value, which marks uncompressed data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CompressionError::UnsupportedAlgorithm`] for values this build does not know.
+    pub fn from_wire(
+        value: EnumValue<delivery_proto::CompressionAlgorithm>,
+    ) -> Result<Option<Self>, CompressionError> {
+        match value.as_known() {
+            Some(delivery_proto::CompressionAlgorithm::COMPRESSION_ALGORITHM_UNSPECIFIED) => {
+                Ok(None)
+            }
+            Some(delivery_proto::CompressionAlgorithm::COMPRESSION_ALGORITHM_LZ4) => {
+                Ok(Some(Self::Lz4))
+            }
+            Some(delivery_proto::CompressionAlgorithm::COMPRESSION_ALGORITHM_ZSTD) => {
+                Ok(Some(Self::Zstd))
+            }
+            None => UnsupportedAlgorithmSnafu {
+                value: value.to_i32(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Encode `algorithm` into its wire form, using the unspecified value for `None`.
+    #[must_use]
+    pub fn to_wire(algorithm: Option<Self>) -> EnumValue<delivery_proto::CompressionAlgorithm> {
+        EnumValue::from(match algorithm {
+            None => delivery_proto::CompressionAlgorithm::COMPRESSION_ALGORITHM_UNSPECIFIED,
+            Some(Self::Lz4) => delivery_proto::CompressionAlgorithm::COMPRESSION_ALGORITHM_LZ4,
+            Some(Self::Zstd) => delivery_proto::CompressionAlgorithm::COMPRESSION_ALGORITHM_ZSTD,
+        })
+    }
+
+    /// Decompress `data` that must expand to exactly `uncompressed_length` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is corrupt or does not expand to the expected length.
+    pub fn decompress(
+        self,
+        data: &[u8],
+        uncompressed_length: usize,
+    ) -> Result<Vec<u8>, CompressionError> {
+        let inflated = match self {
+            Self::Lz4 => lz4_flex::block::decompress(data, uncompressed_length)
+                .context(Lz4DecompressSnafu)?,
+            Self::Zstd => {
+                zstd::bulk::decompress(data, uncompressed_length).context(ZstdDecompressSnafu)?
+            }
+        };
+        ensure!(
+            inflated.len() == uncompressed_length,
+            LengthMismatchSnafu {
+                expected: uncompressed_length,
+                actual: inflated.len(),
+            }
+        );
+        Ok(inflated)
+    }
+}
+
+/// Codec a sender applies to payloads on one kind of link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompressionCodec {
+    /// Send payloads as-is.
+    None,
+    /// Fast compression with a modest ratio, suited to direct links.
+    Lz4,
+    /// Slower compression with a better ratio, suited to constrained or metered links.
+    Zstd { level: i32 },
+}
+
+impl CompressionCodec {
+    /// The wire algorithm this codec produces, if it compresses at all.
+    #[must_use]
+    pub const fn algorithm(self) -> Option<CompressionAlgorithm> {
+        match self {
+            Self::None => None,
+            Self::Lz4 => Some(CompressionAlgorithm::Lz4),
+            Self::Zstd { .. } => Some(CompressionAlgorithm::Zstd),
+        }
+    }
+
+    fn compress(self, payload: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            Self::None => None,
+            Self::Lz4 => Some(lz4_flex::block::compress(payload)),
+            // A failing zstd context only costs the bandwidth saving, so fall back to plain data.
+            Self::Zstd { level } => zstd::bulk::compress(payload, level).ok(),
+        }
+    }
+}
+
+/// Set of algorithms one peer accepts on payloads sent to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CompressionOffer {
+    algorithms: EnumSet<CompressionAlgorithm>,
+}
+
+impl CompressionOffer {
+    /// The offer of a peer that only accepts uncompressed payloads.
+    pub const NONE: Self = Self {
+        algorithms: EnumSet::empty(),
+    };
+
+    /// Offer every algorithm this build can decompress.
+    #[must_use]
+    pub fn supported() -> Self {
+        Self {
+            algorithms: EnumSet::all(),
+        }
+    }
+
+    #[must_use]
+    pub fn from_algorithms(algorithms: EnumSet<CompressionAlgorithm>) -> Self {
+        Self { algorithms }
+    }
+
+    #[must_use]
+    pub fn algorithms(self) -> EnumSet<CompressionAlgorithm> {
+        self.algorithms
+    }
+
+    #[must_use]
+    pub fn accepts(self, algorithm: CompressionAlgorithm) -> bool {
+        self.algorithms.contains(algorithm)
+    }
+
+    /// Algorithms accepted by both offers, e.g. by every recipient of one group broadcast.
+    #[must_use]
+    pub fn intersection(self, other: Self) -> Self {
+        Self {
+            algorithms: self.algorithms & other.algorithms,
+        }
+    }
+
+    /// Decode an offer, ignoring algorithms this build does not know.
+    #[must_use]
+    pub fn from_wire_values<'a>(
+        values: impl IntoIterator<Item = &'a EnumValue<delivery_proto::CompressionAlgorithm>>,
+    ) -> Self {
+        let algorithms = values
+            .into_iter()
+            .filter_map(|value| CompressionAlgorithm::from_wire(*value).ok().flatten())
+            .collect();
+        Self { algorithms }
+    }
+}
+
+impl ProtoCodec for CompressionOffer {
+    type DecodeError = CompressionError;
+    type Proto = delivery_proto::CompressionOffer;
+
+    fn to_proto(&self) -> Self::Proto {
+        delivery_proto::CompressionOffer {
+            algorithms: self
+                .algorithms
+                .iter()
+                .map(|algorithm| CompressionAlgorithm::to_wire(Some(algorithm)))
+                .collect(),
+            ..delivery_proto::CompressionOffer::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        Ok(Self::from_wire_values(&message.algorithms))
+    }
+}
+
+/// Kind of network path a connection runs over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LinkClass {
+    /// Loopback or a private, link-local, or unique-local network.
+    Local,
+    /// A direct path across the public internet.
+    Direct,
+    /// A path through a relay peer.
+    Relay,
+}
+
+impl LinkClass {
+    /// Classify a direct route by its remote address.
+    ///
+    /// Relayed routes cannot be recognised from their address, so callers pick
+    /// [`LinkClass::Relay`] explicitly.
+    #[must_use]
+    pub fn for_direct_addr(addr: IpAddr) -> Self {
+        let is_local = match addr {
+            IpAddr::V4(addr) => addr.is_loopback() || addr.is_private() || addr.is_link_local(),
+            IpAddr::V6(addr) => {
+                addr.is_loopback() || addr.is_unique_local() || addr.is_unicast_link_local()
+            }
+        };
+        if is_local { Self::Local } else { Self::Direct }
+    }
+}
+
+/// Locally configured compression preferences.
+///
+/// Group broadcasts are not bound to a single link, so they use the [`Self::direct`] codec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressionPolicy {
+    /// Algorithms this replica offers to decompress.
+    pub accepts: CompressionOffer,
+    /// Codec for loopback and private-network links.
+    pub local: CompressionCodec,
+    /// Codec for direct links and group broadcasts.
+    pub direct: CompressionCodec,
+    /// Codec for links through a relay peer.
+    pub relay: CompressionCodec,
+    /// Payloads smaller than this many bytes are never compressed.
+    pub min_payload_bytes: usize,
+}
+
+impl CompressionPolicy {
+    /// Neither compress outbound payloads nor accept compressed inbound payloads.
+    pub const DISABLED: Self = Self {
+        accepts: CompressionOffer::NONE,
+        local: CompressionCodec::None,
+        direct: CompressionCodec::None,
+        relay: CompressionCodec::None,
+        min_payload_bytes: DEFAULT_MIN_COMPRESSED_PAYLOAD_BYTES,
+    };
+
+    /// The codec this policy prefers on `link`.
+    #[must_use]
+    pub const fn codec_for(&self, link: LinkClass) -> CompressionCodec {
+        match link {
+            LinkClass::Local => self.local,
+            LinkClass::Direct => self.direct,
+            LinkClass::Relay => self.relay,
+        }
+    }
+
+    /// Pick the compression for a connection over `link` to peers accepting `peer_offer`.
+    ///
+    /// The preferred codec is used when the peer accepts it. Otherwise any other accepted
+    /// algorithm is used, favouring lz4, unless the policy disables compression on `link`.
+    #[must_use]
+    pub fn negotiate(
+        &self,
+        link: LinkClass,
+        peer_offer: CompressionOffer,
+    ) -> ConnectionCompression {
+        let preferred = self.codec_for(link);
+        let codec = match preferred.algorithm() {
+            None => CompressionCodec::None,
+            Some(algorithm) if peer_offer.accepts(algorithm) => preferred,
+            Some(_) if peer_offer.accepts(CompressionAlgorithm::Lz4) => CompressionCodec::Lz4,
+            Some(_) if peer_offer.accepts(CompressionAlgorithm::Zstd) => CompressionCodec::Zstd {
+                level: DEFAULT_ZSTD_LEVEL,
+            },
+            Some(_) => CompressionCodec::None,
+        };
+        ConnectionCompression {
+            codec,
+            min_payload_bytes: self.min_payload_bytes,
+        }
+    }
+}
+
+impl Default for CompressionPolicy {
+    fn default() -> Self {
+        Self {
+            accepts: CompressionOffer::supported(),
+            local: CompressionCodec::None,
+            direct: CompressionCodec::Lz4,
+            relay: CompressionCodec::Zstd {
+                level: DEFAULT_ZSTD_LEVEL,
+            },
+            min_payload_bytes: DEFAULT_MIN_COMPRESSED_PAYLOAD_BYTES,
+        }
+    }
+}
+
+/// Compression negotiated for one connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectionCompression {
+    pub codec: CompressionCodec,
+    pub min_payload_bytes: usize,
+}
+
+impl ConnectionCompression {
+    /// A connection that sends every payload uncompressed.
+    #[must_use]
+    pub const fn disabled() -> Self {
+        Self {
+            codec: CompressionCodec::None,
+            min_payload_bytes: 0,
+        }
+    }
+
+    /// Compress one `class` payload, recording the outcome in `metrics`.
+    ///
+    /// Returns `None` when the payload should be sent as-is: compression is disabled, the payload
+    /// is below the size threshold, or compressing did not make it smaller.
+    #[must_use]
+    pub fn compress(
+        &self,
+        class: PayloadClass,
+        payload: &[u8],
+        metrics: &CompressionMetrics,
+    ) -> Option<CompressedPayload> {
+        let compressed = self
+            .codec
+            .algorithm()
+            .filter(|_| payload.len() >= self.min_payload_bytes)
+            .zip(self.codec.compress(payload))
+            .filter(|(_, data)| data.len() < payload.len());
+        let Some((algorithm, data)) = compressed else {
+            metrics.record_uncompressed(class);
+            return None;
+        };
+        metrics.record_compressed(class, payload.len(), data.len());
+        Some(CompressedPayload {
+            algorithm,
+            uncompressed_length: payload.len() as u64,
+            data: Bytes::from(data),
+        })
+    }
+}
+
+impl Default for ConnectionCompression {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// One payload compressed with a negotiated algorithm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompressedPayload {
+    pub algorithm: CompressionAlgorithm,
+    /// Byte length of the payload after decompression.
+    pub uncompressed_length: u64,
+    pub data: Bytes,
+}
+
+impl CompressedPayload {
+    /// Restore the original payload, refusing to expand beyond `max_bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the declared length exceeds `max_bytes`, or if the data is corrupt.
+    pub fn decompress(&self, max_bytes: usize) -> Result<Vec<u8>, CompressionError> {
+        decompress_bounded(
+            self.algorithm,
+            self.uncompressed_length,
+            &self.data,
+            max_bytes,
+        )
+    }
+}
+
+impl ProtoCodec for CompressedPayload {
+    type DecodeError = CompressionError;
+    type Proto = delivery_proto::CompressedPayload;
+
+    fn to_proto(&self) -> Self::Proto {
+        delivery_proto::CompressedPayload {
+            algorithm: CompressionAlgorithm::to_wire(Some(self.algorithm)),
+            uncompressed_length: self.uncompressed_length,
+            data: self.data.clone(),
+            ..delivery_proto::CompressedPayload::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let algorithm =
+            CompressionAlgorithm::from_wire(message.algorithm)?.context(MissingAlgorithmSnafu)?;
+        Ok(Self {
+            algorithm,
+            uncompressed_length: message.uncompressed_length,
+            data: message.data,
+        })
+    }
+}
+
+/// Errors produced while decoding or decompressing compressed payloads.
+#[derive(Debug, Snafu)]
+pub enum CompressionError {
+    #[snafu(display("Failed to decode compressed payload."))]
+    Decode { source: buffa::DecodeError },
+    #[snafu(display("Compression algorithm {value} is not supported."))]
+    UnsupportedAlgorithm { value: i32 },
+    #[snafu(display("Compressed payload did not name its compression algorithm."))]
+    MissingAlgorithm,
+    #[snafu(display("Compression algorithm {algorithm:?} was not offered to the sender."))]
+    NotAccepted { algorithm: CompressionAlgorithm },
+    #[snafu(display(
+        "Compressed payload would expand to {uncompressed_length} bytes, exceeding the limit of {limit} bytes."
+    ))]
+    PayloadTooLarge {
+        uncompressed_length: u64,
+        limit: usize,
+    },
+    #[snafu(display("Failed to decompress lz4 payload: {source}"))]
+    Lz4Decompress {
+        source: lz4_flex::block::DecompressError,
+    },
+    #[snafu(display("Failed to decompress zstd payload: {source}"))]
+    ZstdDecompress { source: std::io::Error },
+    #[snafu(display(
+        "Compressed payload expanded to {actual} bytes, but declared {expected} bytes."
+    ))]
+    LengthMismatch { expected: usize, actual: usize },
+}
+
+impl FromProtoDecodeError for CompressionError {
+    fn from_proto_decode_error(source: buffa::DecodeError) -> Self {
+        Self::Decode { source }
+    }
+}
+
+/// Kind of payload a compression counter refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PayloadClass {
+    /// Single updates and catch-up update batches.
+    OperationBatch,
+    /// Snapshot chunks.
+    Snapshot,
+}
+
+impl PayloadClass {
+    const COUNT: usize = 2;
+
+    const fn index(self) -> usize {
+        match self {
+            Self::OperationBatch => 0,
+            Self::Snapshot => 1,
+        }
+    }
+}
+
+/// Shared compression counters of one replication runtime.
+///
+/// Clones share the same counters.
+#[derive(Clone, Debug, Default)]
+pub struct CompressionMetrics {
+    classes: Arc<[PayloadMetrics; PayloadClass::COUNT]>,
+}
+
+impl CompressionMetrics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the current counter values.
+    #[must_use]
+    pub fn counters(&self) -> CompressionCounters {
+        CompressionCounters {
+            operation_batches: self.class(PayloadClass::OperationBatch).read(),
+            snapshots: self.class(PayloadClass::Snapshot).read(),
+        }
+    }
+
+    /// Count one inbound payload that was decompressed to `inflated_bytes`.
+    pub fn record_inflated(&self, class: PayloadClass, inflated_bytes: usize) {
+        let metrics = self.class(class);
+        metrics.inflated_payloads.fetch_add(1, Ordering::Relaxed);
+        metrics
+            .inflated_bytes
+            .fetch_add(inflated_bytes as u64, Ordering::Relaxed);
+    }
+
+    fn record_compressed(&self, class: PayloadClass, original_bytes: usize, wire_bytes: usize) {
+        let metrics = self.class(class);
+        metrics.compressed_payloads.fetch_add(1, Ordering::Relaxed);
+        metrics
+            .original_bytes
+            .fetch_add(original_bytes as u64, Ordering::Relaxed);
+        metrics
+            .compressed_bytes
+            .fetch_add(wire_bytes as u64, Ordering::Relaxed);
+    }
+
+    fn record_uncompressed(&self, class: PayloadClass) {
+        self.class(class)
+            .uncompressed_payloads
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn class(&self, class: PayloadClass) -> &PayloadMetrics {
+        &self.classes[class.index()]
+    }
+}
+
+/// Point-in-time copy of [`CompressionMetrics`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompressionCounters {
+    pub operation_batches: PayloadCounters,
+    pub snapshots: PayloadCounters,
+}
+
+impl CompressionCounters {
+    #[must_use]
+    pub const fn for_class(&self, class: PayloadClass) -> &PayloadCounters {
+        match class {
+            PayloadClass::OperationBatch => &self.operation_batches,
+            PayloadClass::Snapshot => &self.snapshots,
+        }
+    }
+}
+
+/// Compression counters for one [`PayloadClass`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PayloadCounters {
+    /// Outbound payloads sent compressed.
+    pub compressed_payloads: u64,
+    /// Outbound payloads sent as-is.
+    pub uncompressed_payloads: u64,
+    /// Size of the compressed outbound payloads before compression.
+    pub original_bytes: u64,
+    /// Size of the compressed outbound payloads after compression.
+    pub compressed_bytes: u64,
+    /// Inbound payloads that were decompressed.
+    pub inflated_payloads: u64,
+    /// Size of the decompressed inbound payloads.
+    pub inflated_bytes: u64,
+}
+
+impl PayloadCounters {
+    /// Outbound bytes saved by compression.
+    #[must_use]
+    pub const fn saved_bytes(&self) -> u64 {
+        self.original_bytes.saturating_sub(self.compressed_bytes)
+    }
+}
+
+/// Decompress the wire parts of one payload sent to a peer that offered `accepts`.
+pub(crate) fn inflate_offered(
+    accepts: CompressionOffer,
+    algorithm: EnumValue<delivery_proto::CompressionAlgorithm>,
+    uncompressed_length: u64,
+    data: &[u8],
+    max_bytes: usize,
+) -> Result<Vec<u8>, CompressionError> {
+    let algorithm = CompressionAlgorithm::from_wire(algorithm)?.context(MissingAlgorithmSnafu)?;
+    ensure!(accepts.accepts(algorithm), NotAcceptedSnafu { algorithm });
+    decompress_bounded(algorithm, uncompressed_length, data, max_bytes)
+}
+
+/// Decompress wire parts of one compressed payload, refusing to expand beyond `max_bytes`.
+pub(crate) fn decompress_bounded(
+    algorithm: CompressionAlgorithm,
+    uncompressed_length: u64,
+    data: &[u8],
+    max_bytes: usize,
+) -> Result<Vec<u8>, CompressionError> {
+    let length = usize::try_from(uncompressed_length)
+        .ok()
+        .filter(|length| *length <= max_bytes)
+        .context(PayloadTooLargeSnafu {
+            uncompressed_length,
+            limit: max_bytes,
+        })?;
+    algorithm.decompress(data, length)
+}
+
+#[derive(Debug, Default)]
+struct PayloadMetrics {
+    compressed_payloads: AtomicU64,
+    uncompressed_payloads: AtomicU64,
+    original_bytes: AtomicU64,
+    compressed_bytes: AtomicU64,
+    inflated_payloads: AtomicU64,
+    inflated_bytes: AtomicU64,
+}
+
+impl PayloadMetrics {
+    fn read(&self) -> PayloadCounters {
+        PayloadCounters {
+            compressed_payloads: self.compressed_payloads.load(Ordering::Relaxed),
+            uncompressed_payloads: self.uncompressed_payloads.load(Ordering::Relaxed),
+            original_bytes: self.original_bytes.load(Ordering::Relaxed),
+            compressed_bytes: self.compressed_bytes.load(Ordering::Relaxed),
+            inflated_payloads: self.inflated_payloads.load(Ordering::Relaxed),
+            inflated_bytes: self.inflated_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_messages::proto::{DecodeProto, EncodeProto};
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    fn compressible_payload() -> Vec<u8> {
+        b"flotsync replicates operations between peers. "
+            .iter()
+            .copied()
+            .cycle()
+            .take(8 * 1024)
+            .collect()
+    }
+
+    #[test]
+    fn every_codec_round_trips_through_the_wire_form() {
+        let payload = compressible_payload();
+        let metrics = CompressionMetrics::new();
+        for codec in [
+            CompressionCodec::Lz4,
+            CompressionCodec::Zstd {
+                level: DEFAULT_ZSTD_LEVEL,
+            },
+        ] {
+            let connection = ConnectionCompression {
+                codec,
+                min_payload_bytes: 0,
+            };
+            let compressed = connection
+                .compress(PayloadClass::OperationBatch, &payload, &metrics)
+                .expect("repetitive payload should compress");
+            assert!(compressed.data.len() < payload.len());
+            let decoded = CompressedPayload::decode_proto(compressed.encode_proto())
+                .expect("compressed payload should decode");
+            assert_eq!(decoded, compressed);
+            assert_eq!(
+                decoded
+                    .decompress(payload.len())
+                    .expect("payload should decompress"),
+                payload
+            );
+        }
+        let counters = metrics.counters();
+        assert_eq!(counters.operation_batches.compressed_payloads, 2);
+        assert_eq!(
+            counters.operation_batches.original_bytes,
+            2 * payload.len() as u64
+        );
+        assert!(counters.operation_batches.saved_bytes() > 0);
+        assert_eq!(counters.snapshots, PayloadCounters::default());
+    }
+
+    #[test]
+    fn small_or_incompressible_payloads_are_sent_as_is() {
+        let metrics = CompressionMetrics::new();
+        let connection =
+            CompressionPolicy::default().negotiate(LinkClass::Relay, CompressionOffer::supported());
+        assert!(
+            connection
+                .compress(PayloadClass::Snapshot, b"tiny", &metrics)
+                .is_none()
+        );
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let noise: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state.to_le_bytes()[0]
+            })
+            .collect();
+        assert!(
+            ConnectionCompression {
+                codec: CompressionCodec::Lz4,
+                min_payload_bytes: 0,
+            }
+            .compress(PayloadClass::Snapshot, &noise, &metrics)
+            .is_none()
+        );
+        assert_eq!(metrics.counters().snapshots.uncompressed_payloads, 2);
+    }
+
+    #[test]
+    fn negotiation_follows_link_policy_and_peer_offer() {
+        let policy = CompressionPolicy::default();
+        let all = CompressionOffer::supported();
+        let lz4_only = CompressionOffer::from_algorithms(CompressionAlgorithm::Lz4.into());
+        assert_eq!(
+            policy.negotiate(LinkClass::Local, all).codec,
+            CompressionCodec::None
+        );
+        assert_eq!(
+            policy.negotiate(LinkClass::Direct, all).codec,
+            CompressionCodec::Lz4
+        );
+        assert_eq!(
+            policy.negotiate(LinkClass::Relay, all).codec,
+            CompressionCodec::Zstd {
+                level: DEFAULT_ZSTD_LEVEL
+            }
+        );
+        assert_eq!(
+            policy.negotiate(LinkClass::Relay, lz4_only).codec,
+            CompressionCodec::Lz4
+        );
+        assert_eq!(
+            policy
+                .negotiate(LinkClass::Relay, CompressionOffer::NONE)
+                .codec,
+            CompressionCodec::None
+        );
+        assert_eq!(
+            all.intersection(lz4_only)
+                .intersection(CompressionOffer::NONE),
+            CompressionOffer::NONE
+        );
+        assert_eq!(
+            CompressionPolicy::DISABLED
+                .negotiate(LinkClass::Relay, all)
+                .codec,
+            CompressionCodec::None
+        );
+    }
+
+    #[test]
+    fn offers_ignore_unknown_algorithms() {
+        let offer = CompressionOffer::decode_proto(delivery_proto::CompressionOffer {
+            algorithms: vec![
+                EnumValue::from(delivery_proto::CompressionAlgorithm::COMPRESSION_ALGORITHM_ZSTD),
+                EnumValue::from(99),
+            ],
+            ..delivery_proto::CompressionOffer::default()
+        })
+        .expect("offer should decode");
+        assert_eq!(
+            offer,
+            CompressionOffer::from_algorithms(CompressionAlgorithm::Zstd.into())
+        );
+        assert_eq!(
+            CompressionOffer::decode_proto(CompressionOffer::supported().encode_proto())
+                .expect("offer should decode"),
+            CompressionOffer::supported()
+        );
+    }
+
+    #[test]
+    fn decompression_enforces_declared_length_and_limit() {
+        let payload = compressible_payload();
+        let compressed = ConnectionCompression {
+            codec: CompressionCodec::Lz4,
+            min_payload_bytes: 0,
+        }
+        .compress(
+            PayloadClass::OperationBatch,
+            &payload,
+            &CompressionMetrics::new(),
+        )
+        .expect("payload should compress");
+        assert!(matches!(
+            compressed.decompress(payload.len() - 1),
+            Err(CompressionError::PayloadTooLarge { .. })
+        ));
+        let understated = CompressedPayload {
+            uncompressed_length: payload.len() as u64 - 1,
+            ..compressed
+        };
+        assert!(understated.decompress(payload.len()).is_err());
+    }
+
+    #[test]
+    fn private_and_loopback_addresses_are_local_links() {
+        assert_eq!(
+            LinkClass::for_direct_addr(Ipv4Addr::LOCALHOST.into()),
+            LinkClass::Local
+        );
+        assert_eq!(
+            LinkClass::for_direct_addr(Ipv4Addr::new(192, 168, 1, 20).into()),
+            LinkClass::Local
+        );
+        assert_eq!(
+            LinkClass::for_direct_addr("fd00::1".parse::<Ipv6Addr>().unwrap().into()),
+            LinkClass::Local
+        );
+        assert_eq!(
+            LinkClass::for_direct_addr(Ipv4Addr::new(203, 0, 113, 7).into()),
+            LinkClass::Direct
+        );
+    }
+}
@@ -26,6 +26,7 @@ use flotsync_routes::{
     RouteTransportSend,
     RouteTransportSubmitResult,
     SendRouteCandidate,
+    TrafficClass,
     TransportRouteKey,
 };
 use flotsync_security::SealedPSKPayload;
@@ -68,6 +69,8 @@ impl GroupMessageEnvelope<SealedPSKPayload> {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct GroupBroadcastSubmit {
     pub delivery_class: DeliveryClass,
+    /// Transport scheduling lane for every recipient's copy.
+    pub traffic_class: TrafficClass,
     pub envelope: GroupMessageEnvelope<PlaintextPayload>,
     /// Skip the immediate local echo that would otherwise be emitted when the
     /// submitter is a member of the target group.
@@ -84,6 +87,7 @@ pub struct GroupBroadcastSubmitBuilder {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct GroupBroadcastSubmitMemberBuilder {
     delivery_class: DeliveryClass,
+    traffic_class: TrafficClass,
     group_id: GroupId,
     sender: MemberIdentity,
 }
@@ -106,6 +110,7 @@ impl GroupBroadcastSubmitBuilder {
     ) -> GroupBroadcastSubmitMemberBuilder {
         GroupBroadcastSubmitMemberBuilder {
             delivery_class: self.delivery_class,
+            traffic_class: TrafficClass::Interactive,
             group_id,
             sender,
         }
@@ -113,6 +118,13 @@ impl GroupBroadcastSubmitBuilder {
 }
 
 impl GroupBroadcastSubmitMemberBuilder {
+    /// Schedule the fan-out in `traffic_class` instead of the default interactive lane.
+    #[must_use]
+    pub fn with_traffic_class(mut self, traffic_class: TrafficClass) -> Self {
+        self.traffic_class = traffic_class;
+        self
+    }
+
     /// Finish the submit with plaintext runtime payload bytes.
     ///
     /// Group broadcast owns the generated envelope identity, endpoint-frame
@@ -121,6 +133,7 @@ impl GroupBroadcastSubmitMemberBuilder {
     pub fn with_payload(self, bytes: Bytes) -> GroupBroadcastPortRequest {
         GroupBroadcastPortRequest::Submit(GroupBroadcastSubmit {
             delivery_class: self.delivery_class,
+            traffic_class: self.traffic_class,
             envelope: GroupMessageEnvelope {
                 header: GroupMessageHeader {
                     group_id: self.group_id,
@@ -211,6 +224,7 @@ impl GroupBroadcastComponent {
     fn handle_submit_request(&mut self, submit: &GroupBroadcastSubmit) -> HandlerResult {
         let envelope = submit.envelope.clone();
         let message_id = envelope.header.message_id;
+        let traffic_class = submit.traffic_class;
         if self.accepted_submits.contains(&message_id) {
             warn!(
                 self.log(),
@@ -264,7 +278,13 @@ impl GroupBroadcastComponent {
                 .benign_err()?;
             let payload: Arc<dyn FlotsyncSerializable> = Arc::new(sealed.to_wire_format());
             for (recipient, route) in recipients {
-                async_self.dispatch_direct_send(message_id, recipient, route, Arc::clone(&payload));
+                async_self.dispatch_direct_send(
+                    message_id,
+                    recipient,
+                    route,
+                    Arc::clone(&payload),
+                    traffic_class,
+                );
             }
             Handled::OK
         })
@@ -325,6 +345,7 @@ impl GroupBroadcastComponent {
         recipient: MemberIdentity,
         route: SendRouteCandidate<TransportRouteKey>,
         payload: Arc<dyn FlotsyncSerializable>,
+        traffic_class: TrafficClass,
     ) {
         // Route-transport owns the actual IO lifecycle; broadcast only needs a
         // one-shot submission and observability for this direct-only slice.
@@ -333,6 +354,7 @@ impl GroupBroadcastComponent {
                 send_id: RouteSendId(Uuid::new_v4()),
                 route,
                 payload,
+                traffic_class,
             };
             let future = async_self
                 .route_transport
@@ -1513,7 +1535,7 @@ mod tests {
                 let group = ReplicationGroupRecord {
                     group_id: *group_id,
                     member_keys,
-                    local_member_index,
+                    local_member_index: Some(local_member_index),
                     group_schema: GroupSchema::default(),
                     version_vector: VersionVector::initial(
                         NonZeroUsize::new(members.len()).expect("test group must have members"),
@@ -1542,6 +1564,7 @@ mod tests {
     ) -> GroupBroadcastSubmit {
         GroupBroadcastSubmit {
             delivery_class: DeliveryClass::BestEffort,
+            traffic_class: TrafficClass::Interactive,
             envelope: GroupMessageEnvelope {
                 header: GroupMessageHeader {
                     group_id,
@@ -10,6 +10,7 @@
 //! direct group broadcast and reliable delivery. Relay, mailbox, and persisted
 //! queue integration remain follow-up work.
 
+pub mod compression;
 pub mod contracts;
 pub mod group_broadcast;
 pub mod ingress;
@@ -25,8 +25,12 @@ use super::{
         WorkScopeKey,
     },
 };
+use crate::fault_points;
 use bytes::Bytes;
-use flotsync_core::{MemberIdentity, member::TrieMap};
+use flotsync_core::{
+    MemberIdentity,
+    member::{TrieMap, TrieSet},
+};
 use flotsync_messages::{
     delivery as delivery_proto,
     endpoint as endpoint_proto,
@@ -42,7 +46,9 @@ use flotsync_routes::{
     RouteTransportSend,
     RouteTransportSubmitResult,
     SendRouteCandidate,
+    TrafficClass,
     TransportRouteKey,
+    liveness::{PeerLiveness, PeerLivenessPort, PeerLivenessUpdate},
 };
 use flotsync_security::SealedHPKEPayload;
 use flotsync_utils::{
@@ -50,6 +56,7 @@ use flotsync_utils::{
     NonOwningPhantomData,
     OptionExt as _,
     ResultExt as _,
+    fault_injection,
     kompact_config::ConfigReadExt as _,
 };
 use kompact::{kompact_config, prelude::*};
@@ -106,14 +113,18 @@ pub struct ReliableDeliveryComponent {
     delivery_port: ProvidedPort<ReliableDeliveryPort>,
     ingress_inbound_port: RequiredPort<TransportReliableDeliveryInboundPort>,
     discovery_port: RequiredPort<TransportRouteDiscoveryPort>,
+    liveness_port: RequiredPort<PeerLivenessPort>,
     route_transport: ActorRefStrong<RouteTransportActorMessage<TransportRouteKey>>,
     security: DeliverySecurity,
     direct_peer_routes: TrieMap<SendRouteCandidate<TransportRouteKey>>,
+    /// Peers the failure detector currently reports as down.
+    down_peers: TrieSet,
     sender_work_items: HashMap<MessageId, ReliableDeliveryWorkItem>,
     inbound_deliveries: HashMap<MessageId, PendingInboundDelivery>,
     retry_queue: RetryQueue,
     retry_timer: Option<ScheduledTimer>,
     retry_delay: Duration,
+    down_peer_retry_delay: Duration,
     recipient_ack_timeout: Duration,
 }
 
@@ -130,14 +141,17 @@ impl ReliableDeliveryComponent {
             delivery_port: ProvidedPort::uninitialised(),
             ingress_inbound_port: RequiredPort::uninitialised(),
             discovery_port: RequiredPort::uninitialised(),
+            liveness_port: RequiredPort::uninitialised(),
             route_transport,
             security,
             direct_peer_routes: TrieMap::new(),
+            down_peers: TrieSet::new(),
             sender_work_items: HashMap::new(),
             inbound_deliveries: HashMap::new(),
             retry_queue: RetryQueue::new(),
             retry_timer: None,
             retry_delay: DEFAULT_RETRY_DELAY,
+            down_peer_retry_delay: DEFAULT_DOWN_PEER_RETRY_DELAY,
             recipient_ack_timeout: DEFAULT_RECIPIENT_ACK_TIMEOUT,
         }
     }
@@ -191,6 +205,12 @@ impl ReliableDeliveryComponent {
             .read_or_default_warn(self.log(), &config_keys::RETRY_DELAY)
     }
 
+    fn load_down_peer_retry_delay(&self) -> Duration {
+        self.ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::DOWN_PEER_RETRY_DELAY)
+    }
+
     fn load_recipient_ack_timeout(&self) -> Duration {
         self.ctx
             .config()
@@ -234,6 +254,20 @@ impl ReliableDeliveryComponent {
         Handled::OK
     }
 
+    fn handle_liveness_update(&mut self, update: PeerLivenessUpdate) -> HandlerResult {
+        let PeerLivenessUpdate { peer, liveness } = update;
+        if liveness == PeerLiveness::Down {
+            self.down_peers.insert(peer);
+            return Handled::OK;
+        }
+        if self.down_peers.remove(&peer) {
+            // Work for this peer was parked on the slow down-peer retry delay.
+            self.retry_pending_sender_work_for_peer(&peer);
+            self.retry_pending_inbound_acks_for_peer(&peer);
+        }
+        Handled::OK
+    }
+
     fn handle_ingress_indication(
         &mut self,
         indication: ReliableDeliveryInboundDeliver<TransportRouteKey>,
@@ -538,6 +572,7 @@ impl ReliableDeliveryComponent {
             send_id: RouteSendId(Uuid::new_v4()),
             route,
             payload,
+            traffic_class: TrafficClass::Interactive,
         };
         let future = self
             .route_transport
@@ -603,7 +638,8 @@ impl ReliableDeliveryComponent {
             }
             Ok(RouteTransportSubmitResult::SendFailed { reason, .. }) => {
                 self.mark_sender_work_pending_retry(message_id, sender_retry_reason(&reason));
-                self.schedule_retry(RetryKey::Sender(message_id), self.retry_delay);
+                let delay = self.sender_retry_delay(message_id);
+                self.schedule_retry(RetryKey::Sender(message_id), delay);
                 warn!(
                     self.log(),
                     "Reliable delivery outbound envelope send failed for {message_id}: {reason:?}"
@@ -614,7 +650,8 @@ impl ReliableDeliveryComponent {
                     message_id,
                     PendingRouteReason::LocalResourcePressure,
                 );
-                self.schedule_retry(RetryKey::Sender(message_id), self.retry_delay);
+                let delay = self.sender_retry_delay(message_id);
+                self.schedule_retry(RetryKey::Sender(message_id), delay);
                 warn!(
                     self.log(),
                     "Reliable delivery outbound envelope promise dropped for {message_id}"
@@ -652,6 +689,7 @@ impl ReliableDeliveryComponent {
             return;
         };
         let envelope = work_item.submit.envelope.clone();
+        let traffic_class = work_item.submit.traffic_class;
 
         self.cancel_retry(RetryKey::Sender(message_id));
         let send_id = RouteSendId(Uuid::new_v4());
@@ -681,6 +719,7 @@ impl ReliableDeliveryComponent {
                 send_id,
                 route,
                 payload,
+                traffic_class,
             };
             let future = async_self
                 .route_transport
@@ -794,6 +833,25 @@ impl ReliableDeliveryComponent {
         }
     }
 
+    /// Return the delay before retrying one sender work item.
+    ///
+    /// Recipients reported as down are retried on the slower down-peer delay until the failure
+    /// detector hears from them again.
+    fn sender_retry_delay(&self, message_id: MessageId) -> Duration {
+        let recipient_is_down = self
+            .sender_work_items
+            .get(&message_id)
+            .is_some_and(|work_item| {
+                self.down_peers
+                    .contains(&work_item.submit.envelope.header.recipient)
+            });
+        if recipient_is_down {
+            self.down_peer_retry_delay
+        } else {
+            self.retry_delay
+        }
+    }
+
     fn schedule_retry(&mut self, key: RetryKey, delay: Duration) {
         let now = self.now();
         self.retry_queue.schedule(key, now + delay);
@@ -813,7 +871,10 @@ impl ReliableDeliveryComponent {
         let Some(next_due_at) = self.retry_queue.next_due_at() else {
             return;
         };
-        let delay = next_due_at.saturating_duration_since(now);
+        let delay = fault_injection::delayed(
+            fault_points::RELIABLE_DELIVERY_RETRY_TIMER,
+            next_due_at.saturating_duration_since(now),
+        );
         let timer = self.schedule_once(delay, move |component, expected_timer| {
             component.handle_retry_timeout(&expected_timer)
         });
@@ -860,6 +921,21 @@ impl ReliableDeliveryComponent {
             RouteActiveState::AwaitingRecipientAck => {
                 let sender = work_item.submit.envelope.header.sender.clone();
                 let recipient = work_item.submit.envelope.header.recipient.clone();
+                if self.down_peers.contains(&recipient) {
+                    debug!(
+                        self.log(),
+                        "Reliable delivery recipient ack timed out for message_id={} while recipient={} is down; deferring retry by {:?}",
+                        message_id,
+                        recipient,
+                        self.down_peer_retry_delay
+                    );
+                    self.mark_sender_work_pending_retry(
+                        message_id,
+                        PendingRouteReason::PeerCurrentlyUnreachable,
+                    );
+                    self.schedule_retry(RetryKey::Sender(message_id), self.down_peer_retry_delay);
+                    return;
+                }
                 warn!(
                     self.log(),
                     "Reliable delivery recipient ack timed out for message_id={} sender={} recipient={} after {:?}; retrying envelope delivery",
@@ -912,6 +988,7 @@ impl ReliableDeliveryComponent {
 impl ComponentLifecycle for ReliableDeliveryComponent {
     fn on_start(&mut self) -> HandlerResult {
         self.retry_delay = self.load_retry_delay();
+        self.down_peer_retry_delay = self.load_down_peer_retry_delay();
         self.recipient_ack_timeout = self.load_recipient_ack_timeout();
         Handled::OK
     }
@@ -951,6 +1028,12 @@ impl Require<TransportRouteDiscoveryPort> for ReliableDeliveryComponent {
     }
 }
 
+impl Require<PeerLivenessPort> for ReliableDeliveryComponent {
+    fn handle(&mut self, indication: PeerLivenessUpdate) -> HandlerResult {
+        self.handle_liveness_update(indication)
+    }
+}
+
 impl Actor for ReliableDeliveryComponent {
     type Message = Never;
 
@@ -964,7 +1047,12 @@ type TransportRouteDiscoveryPort = RouteDiscoveryPort<TransportRouteKey>;
 type TransportDiscoveryRouteUpdate = super::contracts::DiscoveryRouteUpdate<TransportRouteKey>;
 
 mod config_keys {
-    use super::{DEFAULT_RECIPIENT_ACK_TIMEOUT, Duration, kompact_config};
+    use super::{
+        DEFAULT_DOWN_PEER_RETRY_DELAY,
+        DEFAULT_RECIPIENT_ACK_TIMEOUT,
+        Duration,
+        kompact_config,
+    };
     use kompact::config::DurationValue;
 
     kompact_config! {
@@ -976,6 +1064,15 @@ mod config_keys {
         version = "0.1.0"
     }
 
+    kompact_config! {
+        DOWN_PEER_RETRY_DELAY,
+        key = "flotsync.reliable-delivery.down-peer-retry-delay",
+        type = DurationValue,
+        default = DEFAULT_DOWN_PEER_RETRY_DELAY,
+        doc = "Retry delay for direct reliable-delivery sends to recipients the failure detector reports as down.",
+        version = "0.1.0"
+    }
+
     kompact_config! {
         RECIPIENT_ACK_TIMEOUT,
         key = "flotsync.reliable-delivery.recipient-ack-timeout",
@@ -987,6 +1084,7 @@ mod config_keys {
 }
 
 const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(30);
+const DEFAULT_DOWN_PEER_RETRY_DELAY: Duration = Duration::from_mins(5);
 const DEFAULT_RECIPIENT_ACK_TIMEOUT: Duration = Duration::from_mins(5);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -294,6 +294,12 @@ impl FullStackHarness {
         });
     }
 
+    fn report_liveness(&self, peer: MemberIdentity, liveness: PeerLiveness) {
+        self.reliable.on_definition(|component| {
+            let _ = component.handle_liveness_update(PeerLivenessUpdate { peer, liveness });
+        });
+    }
+
     fn inject_recipient_ack_wire(&self, ack: delivery_proto::RecipientAckWire) {
         self.reliable.on_definition(|component| {
             let _ = component.handle_inbound_recipient_ack(ack);
@@ -511,6 +517,7 @@ fn reliable_submit(
                 bytes: Bytes::from_static(payload),
             },
         },
+        traffic_class: TrafficClass::Interactive,
     }
 }
 
@@ -662,6 +669,7 @@ fn reliable_delivery_round_trips_direct_envelope_and_processed_ack() {
                 bytes: Bytes::from_static(b"bootstrap payload"),
             },
         },
+        traffic_class: TrafficClass::Interactive,
     });
 
     let deliver = receiver.wait_for_delivery();
@@ -710,6 +718,44 @@ fn recipient_ack_timeout_redelivers_unprocessed_envelope() {
     );
 }
 
+#[test]
+fn recipient_ack_timeout_for_down_peer_waits_until_peer_is_alive() {
+    let alice = member_identity(&["alice"]);
+    let bob = member_identity(&["bob"]);
+    let sender =
+        FullStackHarness::with_recipient_ack_timeout(alice.clone(), TEST_RECIPIENT_ACK_TIMEOUT);
+    let receiver = FullStackHarness::new(bob.clone());
+
+    sender.publish_direct_route(bob.clone(), receiver.local_addr);
+
+    let message_id = MessageId(Uuid::from_u128(42));
+    sender.submit(reliable_submit(
+        alice,
+        bob.clone(),
+        message_id,
+        b"down peer payload",
+    ));
+
+    let deliver = receiver.wait_for_delivery();
+    sender.wait_for_sender_route_state(message_id, &RouteActiveState::AwaitingRecipientAck);
+    sender.report_liveness(bob.clone(), PeerLiveness::Down);
+    drop(deliver);
+    receiver.wait_for_inbound_clear(message_id);
+
+    sender.wait_for_sender_route_state(
+        message_id,
+        &RouteActiveState::PendingRoute {
+            retry_after: None,
+            reason: PendingRouteReason::PeerCurrentlyUnreachable,
+        },
+    );
+    receiver.expect_no_delivery(TEST_RECIPIENT_ACK_TIMEOUT * 4);
+
+    sender.report_liveness(bob, PeerLiveness::Alive);
+    let redelivered = receiver.wait_for_delivery();
+    assert_eq!(redelivered.envelope.header.message_id, message_id);
+}
+
 #[test]
 fn recipient_ack_cancels_timeout_redelivery() {
     let alice = member_identity(&["alice"]);
@@ -942,6 +988,7 @@ fn duplicate_submit_keeps_the_original_work_item() {
                 bytes: Bytes::from_static(b"first payload"),
             },
         },
+        traffic_class: TrafficClass::Interactive,
     });
     sender.submit(ReliableDeliverySubmit {
         envelope: ReliableMessageEnvelope::<PlaintextPayload> {
@@ -955,6 +1002,7 @@ fn duplicate_submit_keeps_the_original_work_item() {
                 bytes: Bytes::from_static(b"second payload"),
             },
         },
+        traffic_class: TrafficClass::Interactive,
     });
 
     sender.wait_for_sender_ciphertext(message_id, &Bytes::from_static(b"first payload"));
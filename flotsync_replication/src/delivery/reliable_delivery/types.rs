@@ -38,6 +38,8 @@ impl ReliableMessageEnvelope<EncryptedPayload> {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ReliableDeliverySubmit {
     pub envelope: ReliableMessageEnvelope<PlaintextPayload>,
+    /// Transport scheduling lane for the sealed envelope.
+    pub traffic_class: TrafficClass,
 }
 
 /// Inbound reliable-delivery message delivered by the network-facing service.
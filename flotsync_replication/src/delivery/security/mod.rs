@@ -513,6 +513,7 @@ mod tests {
         }
         GroupSetupMessage::new(
             members,
+            Vec::new(),
             member_keys,
             GROUP_CIPHER_SUITE_CHACHA20_POLY1305,
             GroupSetupKey::from_group_key(GroupKey::from_bytes([key_byte; 32])),
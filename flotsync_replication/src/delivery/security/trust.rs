@@ -97,13 +97,13 @@ impl DeliverySecurity {
             })
     }
 
-    /// Load public keys for the exact members that will be included in one bootstrap payload.
+    /// Load public keys for the exact members and observers included in one bootstrap payload.
     pub(crate) async fn public_keys_for_members(
         &self,
         members: &GroupMembers,
     ) -> Result<TrieMap<PublicMemberKeys>, DeliverySecurityError> {
         let mut public_keys = TrieMap::new();
-        for member in members.ordered_members() {
+        for member in members.iter() {
             let member_public_keys = if member == self.local_member {
                 self.local_keys.public_keys().clone()
             } else {
@@ -152,16 +152,18 @@ impl DeliverySecurity {
         payload: &GroupSetupMessage,
         setup_sender: &MemberIdentity,
     ) -> Result<(), DeliverySecurityError> {
-        for member_id in payload.members() {
+        for member_id in payload.members().iter().chain(payload.observers()) {
             if payload.member_keys().get(member_id).is_none() {
                 return Err(DeliverySecurityError::MissingBootstrapMemberKey {
                     member_id: member_id.clone(),
                 });
             }
         }
-        if payload.member_keys().len() != payload.members().len() {
+        if payload.member_keys().len() != payload.members().len() + payload.observers().len() {
             for (member_id, _) in payload.member_keys().owned_entries() {
-                if !payload.members().contains(&member_id) {
+                if !payload.members().contains(&member_id)
+                    && !payload.observers().contains(&member_id)
+                {
                     return Err(DeliverySecurityError::UnexpectedBootstrapMemberKey { member_id });
                 }
             }
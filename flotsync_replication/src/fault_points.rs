@@ -0,0 +1,12 @@
+//! Names of the replication fault points, see [`flotsync_utils::fault_injection`].
+//!
+//! Enable the `fault-injection` feature to configure them from tests.
+
+/// Reached before a SQLite replication store transaction commits.
+///
+/// Honours `Fail`, which rolls the transaction back and reports a full disk.
+pub const STORE_COMMIT: &str = "replication.store-commit";
+/// Reached whenever reliable delivery schedules its next retry check.
+///
+/// Honours `Delay`.
+pub const RELIABLE_DELIVERY_RETRY_TIMER: &str = "replication.reliable-delivery-retry-timer";
@@ -6,12 +6,34 @@
 /// reject it before runtime code can derive catch-up intervals from the value.
 pub const MAX_VERSION_VALUE: u64 = u64::MAX - 1;
 
+pub mod admin_log;
 pub mod api;
+pub mod blobs;
 pub(crate) mod codecs;
 pub mod delivery;
+pub mod fault_points;
+/// Common imports for applications embedding the replication runtime.
+///
+/// Also includes the [`flotsync_data_types` prelude](flotsync_data_types::prelude) and, through it,
+/// the [`flotsync_core` prelude](flotsync_core::prelude).
+pub mod prelude {
+    pub use crate::{
+        BatchProvider,
+        DocumentKind,
+        ProviderBatch,
+        ReplicationApi,
+        ReplicationEventListener,
+        ReplicationStore,
+        SqliteReplicationStore,
+        load_replication_runtime,
+    };
+    pub use flotsync_data_types::prelude::*;
+}
 pub mod runtime;
 pub mod security_provisioning;
 pub(crate) mod security_store;
+pub mod sharing;
+pub mod snapshot_transfer;
 pub mod store;
 #[cfg(any(test, feature = "test-support"))]
 pub mod test_support;
@@ -26,4 +48,21 @@ pub use security_provisioning::{
     provision_replication_security,
     validate_initial_group_security_material,
 };
-pub use store::SqliteReplicationStore;
+pub use store::{
+    DanglingDocRef,
+    DocumentStorageUsage,
+    GroupStorageUsage,
+    SqliteReplicationStore,
+    StorageQuota,
+    StorageQuotaWarning,
+    StorageReport,
+    StoreIssue,
+    StoreIssueKind,
+    StoreVerificationReport,
+    TombstonedDocument,
+    find_dangling_doc_refs,
+    list_tombstoned_documents,
+    purge_tombstoned_documents,
+    storage_report,
+    verify_replication_store,
+};
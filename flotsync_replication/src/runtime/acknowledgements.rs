@@ -0,0 +1,258 @@
+//! In-memory tracking of which updates every group member has acknowledged.
+//!
+//! Members send update and frontier acknowledgements after applying inbound
+//! updates. The tracker folds both into one acknowledged frontier per member,
+//! which lets senders advance their retention watermark per peer and lets
+//! compaction find the versions every member has applied.
+//!
+//! Acknowledgements are advisory and not persisted. After a restart every peer
+//! starts at the initial vector again, which only delays compaction and never
+//! drops updates a peer still needs.
+
+use crate::api::AcknowledgedVersions;
+use flotsync_core::{
+    GroupId,
+    versions::{UpdateId, VersionVector},
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    num::NonZeroUsize,
+};
+
+/// Acknowledged frontiers of every member in every hosted group.
+#[derive(Debug, Default)]
+pub(super) struct AcknowledgementTracker {
+    groups: HashMap<GroupId, Vec<MemberAcknowledgements>>,
+}
+
+impl AcknowledgementTracker {
+    /// Record receipts for individual updates from the member at `member_index`.
+    ///
+    /// Receipts only advance the member's frontier once they are contiguous
+    /// with it; later receipts are kept until the gap is acknowledged.
+    /// Receipts for producers outside the group are ignored.
+    ///
+    /// Returns `true` if the member's acknowledged frontier advanced.
+    pub(super) fn record_updates(
+        &mut self,
+        group_id: GroupId,
+        num_members: NonZeroUsize,
+        member_index: usize,
+        update_ids: &[UpdateId],
+    ) -> bool {
+        let Some(member) = self.member_mut(group_id, num_members, member_index) else {
+            return false;
+        };
+        for update_id in update_ids {
+            if (update_id.node_index as usize) < num_members.get()
+                && update_id.version > member.frontier.version_at(update_id.node_index as usize)
+            {
+                member.ahead.insert(*update_id);
+            }
+        }
+        member.absorb_contiguous()
+    }
+
+    /// Record that the member at `member_index` has applied everything covered
+    /// by `applied_versions`.
+    ///
+    /// Returns `true` if the member's acknowledged frontier advanced.
+    pub(super) fn record_frontier(
+        &mut self,
+        group_id: GroupId,
+        member_index: usize,
+        applied_versions: &VersionVector,
+    ) -> bool {
+        let num_members = applied_versions.num_members();
+        let Some(member) = self.member_mut(group_id, num_members, member_index) else {
+            return false;
+        };
+        let frontier = member.frontier.least_upper_bound(applied_versions);
+        if frontier == member.frontier {
+            return false;
+        }
+        member.frontier = frontier;
+        member.ahead.retain(|update_id| {
+            update_id.version > member.frontier.version_at(update_id.node_index as usize)
+        });
+        member.absorb_contiguous();
+        true
+    }
+
+    /// Return the acknowledged frontier of the member at `member_index`, if any was recorded.
+    pub(super) fn frontier(
+        &self,
+        group_id: GroupId,
+        member_index: usize,
+    ) -> Option<&VersionVector> {
+        self.groups
+            .get(&group_id)
+            .and_then(|members| members.get(member_index))
+            .map(|member| &member.frontier)
+    }
+
+    /// Summarise the acknowledged frontiers of one group.
+    ///
+    /// The local member's entry is `local_versions`, since it has trivially
+    /// acknowledged everything it applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `local_member_index` is outside the member range of `local_versions`.
+    pub(super) fn acknowledged_versions(
+        &self,
+        group_id: GroupId,
+        local_member_index: usize,
+        local_versions: VersionVector,
+    ) -> AcknowledgedVersions {
+        let num_members = local_versions.num_members();
+        assert!(
+            local_member_index < num_members.get(),
+            "Local member {local_member_index} is outside of group range (0-{num_members})"
+        );
+        let recorded = self
+            .groups
+            .get(&group_id)
+            .filter(|members| members.len() == num_members.get());
+        let members: Vec<VersionVector> = (0..num_members.get())
+            .map(|member_index| {
+                if member_index == local_member_index {
+                    local_versions.clone()
+                } else {
+                    recorded.map_or_else(
+                        || VersionVector::initial(num_members),
+                        |members| members[member_index].frontier.clone(),
+                    )
+                }
+            })
+            .collect();
+        let stable_versions = members
+            .iter()
+            .fold(local_versions.clone(), |stable, member| {
+                stable.greatest_lower_bound(member)
+            });
+        AcknowledgedVersions {
+            group_id,
+            members,
+            stable_versions,
+        }
+    }
+
+    /// Return the record for one member, resetting the group if its member count changed.
+    fn member_mut(
+        &mut self,
+        group_id: GroupId,
+        num_members: NonZeroUsize,
+        member_index: usize,
+    ) -> Option<&mut MemberAcknowledgements> {
+        let members = self.groups.entry(group_id).or_default();
+        if members.len() != num_members.get() {
+            *members = vec![MemberAcknowledgements::new(num_members); num_members.get()];
+        }
+        members.get_mut(member_index)
+    }
+}
+
+/// Acknowledgement state of one member.
+#[derive(Clone, Debug)]
+struct MemberAcknowledgements {
+    /// Every update covered by this vector has been acknowledged.
+    frontier: VersionVector,
+    /// Acknowledged updates that are not yet contiguous with `frontier`.
+    ahead: BTreeSet<UpdateId>,
+}
+
+impl MemberAcknowledgements {
+    fn new(num_members: NonZeroUsize) -> Self {
+        Self {
+            frontier: VersionVector::initial(num_members),
+            ahead: BTreeSet::new(),
+        }
+    }
+
+    /// Move receipts that extend the frontier into it.
+    ///
+    /// Returns `true` if the frontier advanced.
+    fn absorb_contiguous(&mut self) -> bool {
+        let mut advanced = false;
+        loop {
+            let next = self.ahead.iter().copied().find(|update_id| {
+                update_id.version == self.frontier.version_at(update_id.node_index as usize) + 1
+            });
+            let Some(update_id) = next else {
+                return advanced;
+            };
+            self.ahead.remove(&update_id);
+            self.frontier = self.frontier.with_update_applied(update_id);
+            advanced = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const THREE_MEMBERS: NonZeroUsize = NonZeroUsize::new(3).unwrap();
+
+    fn update(node_index: u32, version: u64) -> UpdateId {
+        UpdateId {
+            version,
+            node_index,
+        }
+    }
+
+    #[test]
+    fn update_receipts_advance_only_contiguous_frontiers() {
+        let group_id = GroupId(Uuid::from_u128(1));
+        let mut tracker = AcknowledgementTracker::default();
+
+        assert!(!tracker.record_updates(group_id, THREE_MEMBERS, 1, &[update(0, 2)]));
+        assert!(tracker.record_updates(group_id, THREE_MEMBERS, 1, &[update(0, 1), update(2, 1)]));
+        // Producers outside the group never move the frontier.
+        assert!(!tracker.record_updates(group_id, THREE_MEMBERS, 1, &[update(7, 1)]));
+
+        let acknowledged =
+            tracker.acknowledged_versions(group_id, 0, VersionVector::from_entries([3, 0, 1]));
+        assert_eq!(
+            acknowledged.members[1],
+            VersionVector::from_entries([2, 0, 1])
+        );
+        assert_eq!(
+            acknowledged.members[2],
+            VersionVector::initial(THREE_MEMBERS)
+        );
+        assert_eq!(
+            acknowledged.stable_versions,
+            VersionVector::initial(THREE_MEMBERS)
+        );
+    }
+
+    #[test]
+    fn frontier_receipts_merge_with_update_receipts() {
+        let group_id = GroupId(Uuid::from_u128(2));
+        let mut tracker = AcknowledgementTracker::default();
+
+        assert!(!tracker.record_updates(group_id, THREE_MEMBERS, 2, &[update(0, 3)]));
+        assert!(tracker.record_frontier(group_id, 2, &VersionVector::from_entries([2, 1, 0])));
+        assert!(tracker.record_frontier(group_id, 1, &VersionVector::from_entries([4, 1, 1])));
+        // Stale frontiers never move acknowledgements backwards.
+        assert!(!tracker.record_frontier(group_id, 1, &VersionVector::from_entries([1, 0, 0])));
+
+        let acknowledged =
+            tracker.acknowledged_versions(group_id, 0, VersionVector::from_entries([4, 2, 1]));
+        assert_eq!(
+            acknowledged.members,
+            vec![
+                VersionVector::from_entries([4, 2, 1]),
+                VersionVector::from_entries([4, 1, 1]),
+                VersionVector::from_entries([3, 1, 0]),
+            ]
+        );
+        assert_eq!(
+            acknowledged.stable_versions,
+            VersionVector::from_entries([3, 1, 0])
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use super::{compression::SharedRuntimeCompression, encodings::SharedRuntimeEncodings};
 use crate::{
     api::{ReplicationStore, ReplicationUpdateFilter, StoreError},
     codecs::messages::{
@@ -22,6 +23,7 @@ use flotsync_core::{
     versions::{UpdateId, VersionVector},
 };
 use flotsync_messages::proto::{DecodeProtoViewWith, EncodeProto};
+use flotsync_routes::TrafficClass;
 use flotsync_utils::{OptionExt as _, ResultExt as _, kompact_config::ConfigReadExt as _};
 use interval::prelude::{Bounded, Difference, IntervalSet, IsEmpty, Range, Union};
 use itertools::Itertools;
@@ -317,7 +319,13 @@ pub(super) struct CatchUpManagerComponent {
     group_broadcast: RequiredPort<GroupBroadcastPort>,
     local_member: MemberIdentity,
     group_memberships: SharedGroupMemberships,
+    /// Compression negotiated for catch-up responses, shared with the runtime component.
+    compression: SharedRuntimeCompression,
+    /// Update encodings negotiated for catch-up responses, shared with the runtime component.
+    encodings: SharedRuntimeEncodings,
     store: Arc<dyn ReplicationStore>,
+    /// Upper bound for one decompressed inbound payload.
+    max_runtime_payload_bytes: usize,
     /// Delay between rebroadcasts while any pending need remains unsatisfied.
     retry_delay: Duration,
     /// Per-response update limit; `None` means the configured zero/unlimited mode.
@@ -334,14 +342,20 @@ impl CatchUpManagerComponent {
     pub(super) fn new(
         local_member: MemberIdentity,
         group_memberships: SharedGroupMemberships,
+        compression: SharedRuntimeCompression,
+        encodings: SharedRuntimeEncodings,
         store: Arc<dyn ReplicationStore>,
+        max_runtime_payload_bytes: usize,
     ) -> Self {
         Self {
             ctx: ComponentContext::uninitialised(),
             group_broadcast: RequiredPort::uninitialised(),
             local_member,
             group_memberships,
+            compression,
+            encodings,
             store,
+            max_runtime_payload_bytes,
             retry_delay: DEFAULT_RETRY_DELAY,
             max_updates_per_batch: NonZeroUsize::new(DEFAULT_MAX_UPDATES_PER_BATCH),
             pending_needs: HashMap::new(),
@@ -563,7 +577,12 @@ impl CatchUpManagerComponent {
 
     fn handle_group_delivery(&mut self, deliver: &GroupBroadcastDeliver) -> HandlerResult {
         let memberships = self.group_memberships.snapshot();
-        let decode_context = RuntimeMessageDecodeContext::new(memberships.as_ref());
+        let decode_context = RuntimeMessageDecodeContext::new(memberships.as_ref())
+            .with_compression(
+                self.compression.local_offer(),
+                self.max_runtime_payload_bytes,
+                self.compression.metrics(),
+            );
         let message = RuntimeMessage::decode_proto_view_from_slice_with(
             &deliver.envelope.payload.bytes,
             decode_context,
@@ -583,7 +602,18 @@ impl CatchUpManagerComponent {
             | RuntimeMessage::Summary(_)
             | RuntimeMessage::UpdateBatch(_)
             | RuntimeMessage::GroupInvitation(_)
-            | RuntimeMessage::MigrationProposal(_) => Handled::OK,
+            | RuntimeMessage::MigrationProposal(_)
+            | RuntimeMessage::UpdateAck(_)
+            | RuntimeMessage::FrontierAck(_)
+            | RuntimeMessage::Throttled(_)
+            | RuntimeMessage::BlobChunkRequest(_)
+            | RuntimeMessage::BlobChunk(_)
+            | RuntimeMessage::BlobUnavailable(_)
+            | RuntimeMessage::SnapshotManifestRequest(_)
+            | RuntimeMessage::SnapshotManifest(_)
+            | RuntimeMessage::SnapshotChunkRequest(_)
+            | RuntimeMessage::SnapshotChunk(_)
+            | RuntimeMessage::SnapshotUnavailable(_) => Handled::OK,
         }
     }
 
@@ -700,10 +730,22 @@ impl CatchUpManagerComponent {
             }
             Ok(updates) => {
                 let message = RuntimeMessage::UpdateBatch(UpdateBatchMessage { group_id, updates });
+                let memberships = self.group_memberships.snapshot();
+                let compression = self.compression.for_group_broadcast(
+                    &memberships,
+                    &group_id,
+                    &self.local_member,
+                );
+                let encoding =
+                    self.encodings
+                        .for_group_broadcast(&memberships, &group_id, &self.local_member);
+                let payload =
+                    message.encode_payload(encoding, &compression, self.compression.metrics());
                 self.group_broadcast.trigger(
                     GroupBroadcastPortRequest::build_submit(DeliveryClass::BestEffort)
                         .for_member_in_group(self.local_member.clone(), group_id)
-                        .with_payload(message.encode_proto_to_bytes()),
+                        .with_traffic_class(TrafficClass::Snapshot)
+                        .with_payload(payload),
                 );
             }
             Err(error) => {
@@ -887,6 +929,7 @@ mod tests {
             ReplicationUpdateRecord,
             current_slice_placeholder_group_security_material,
         },
+        runtime::DEFAULT_MAX_RUNTIME_PAYLOAD_BYTES,
         test_support::test_public_member_keys,
     };
     use flotsync_core::{
@@ -948,7 +991,7 @@ mod tests {
         ReplicationGroupRecord {
             group_id,
             member_keys,
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: GroupSchema::default(),
             version_vector: VersionVector::initial(NonZeroUsize::new(2).unwrap()),
             lifecycle: ReplicationGroupLifecycle::Open,
@@ -1004,7 +1047,16 @@ mod tests {
             wait_for_store_future(SqliteReplicationStore::in_memory(local_member.clone()))
                 .expect("store should build"),
         );
-        system.create(move || CatchUpManagerComponent::new(local_member, memberships, store))
+        system.create(move || {
+            CatchUpManagerComponent::new(
+                local_member,
+                memberships,
+                SharedRuntimeCompression::default(),
+                SharedRuntimeEncodings::default(),
+                store,
+                DEFAULT_MAX_RUNTIME_PAYLOAD_BYTES,
+            )
+        })
     }
 
     #[test]
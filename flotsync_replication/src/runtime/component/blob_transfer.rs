@@ -0,0 +1,330 @@
+//! Blob storage and lazy peer-to-peer blob transfer for the runtime component.
+
+use super::*;
+use crate::{
+    blobs::{
+        BlobChunk,
+        BlobChunkRequest,
+        BlobChunkResponse,
+        BlobError,
+        BlobFetch,
+        BlobUnavailable,
+    },
+    runtime::errors::{BlobTransferError, blob_transfer},
+};
+use bytes::Bytes;
+use std::collections::hash_map::Entry;
+
+/// One blob being fetched from a peer on behalf of local callers.
+pub(super) struct PendingBlobFetch {
+    /// Member the chunks were requested from. Chunks from other members are ignored.
+    peer: MemberIdentity,
+    fetch: BlobFetch,
+    /// Callers waiting for the blob content.
+    promises: Vec<KPromise<Result<Bytes, ApiError>>>,
+    timeout_timer: ScheduledTimer,
+}
+
+impl ReplicationRuntimeComponent {
+    pub(super) fn handle_store_blob(
+        &mut self,
+        ask: Ask<StoreBlobRequest, Result<BlobDescriptor, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, request) = ask.take();
+        let reply = self.store_blob(&request).boxed().context(ApiExternalSnafu);
+        self.reply_api(promise, "store_blob", reply);
+        Handled::OK
+    }
+
+    fn store_blob(
+        &mut self,
+        request: &StoreBlobRequest,
+    ) -> Result<BlobDescriptor, BlobTransferError> {
+        let group_id = request.group_id;
+        ensure!(
+            self.group_memberships.snapshot().contains_group(&group_id),
+            blob_transfer::UnknownGroupSnafu { group_id }
+        );
+        let descriptor = self
+            .blobs
+            .insert(&request.data)
+            .context(blob_transfer::StoreSnafu)?;
+        self.blobs
+            .add_reference(&descriptor.hash, group_id)
+            .context(blob_transfer::StoreSnafu)?;
+        Ok(descriptor)
+    }
+
+    pub(super) fn handle_fetch_blob(
+        &mut self,
+        ask: Ask<FetchBlobRequest, Result<Bytes, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, request) = ask.take();
+        let FetchBlobRequest {
+            group_id,
+            hash,
+            peer,
+        } = request;
+        if let Err(error) = self.validate_blob_peer(group_id, &peer) {
+            let reply = Err(error).boxed().context(ApiExternalSnafu);
+            self.reply_api(promise, "fetch_blob", reply);
+            return Handled::OK;
+        }
+        if let Some(data) = self.blobs.read(&hash) {
+            // Blobs held for another group are shared instead of fetched again.
+            let reply = self
+                .blobs
+                .add_reference(&hash, group_id)
+                .map(|_| data)
+                .context(blob_transfer::StoreSnafu)
+                .boxed()
+                .context(ApiExternalSnafu);
+            self.reply_api(promise, "fetch_blob", reply);
+            return Handled::OK;
+        }
+        if peer == self.local_member {
+            let reply = Err(ApiError::BlobUnavailable {
+                group_id,
+                hash,
+                peer,
+            });
+            self.reply_api(promise, "fetch_blob", reply);
+            return Handled::OK;
+        }
+        if let Some(pending) = self.blob_fetches.get_mut(&(group_id, hash)) {
+            pending.promises.push(promise);
+            return Handled::OK;
+        }
+        let fetch = BlobFetch::new(group_id, hash, self.max_blob_bytes as u64);
+        let request = fetch.next_request();
+        let timeout_timer = self.schedule_once(self.blob_fetch_timeout, move |component, timer| {
+            component.handle_blob_fetch_timeout((group_id, hash), &timer)
+        });
+        self.blob_fetches.insert(
+            (group_id, hash),
+            PendingBlobFetch {
+                peer: peer.clone(),
+                fetch,
+                promises: vec![promise],
+                timeout_timer,
+            },
+        );
+        self.submit_reliable_runtime_message(peer, &RuntimeMessage::BlobChunkRequest(request));
+        Handled::OK
+    }
+
+    /// Check that `peer` can serve blobs of `group_id` to the local member.
+    fn validate_blob_peer(
+        &self,
+        group_id: GroupId,
+        peer: &MemberIdentity,
+    ) -> Result<(), BlobTransferError> {
+        let memberships = self.group_memberships.snapshot();
+        let members = memberships
+            .members(&group_id)
+            .context(blob_transfer::UnknownGroupSnafu { group_id })?;
+        ensure!(
+            members.contains(peer),
+            blob_transfer::PeerNotInGroupSnafu {
+                group_id,
+                peer: peer.clone(),
+            }
+        );
+        Ok(())
+    }
+
+    fn handle_blob_fetch_timeout(
+        &mut self,
+        key: (GroupId, BlobHash),
+        expected_timer: &ScheduledTimer,
+    ) -> HandlerResult {
+        let Entry::Occupied(entry) = self.blob_fetches.entry(key) else {
+            return Handled::OK;
+        };
+        if &entry.get().timeout_timer != expected_timer {
+            return Handled::OK;
+        }
+        let pending = entry.remove();
+        let (group_id, hash) = key;
+        self.settle_blob_fetch(pending, |peer| {
+            Err(ApiError::BlobFetchTimedOut {
+                group_id,
+                hash,
+                peer: peer.clone(),
+            })
+        });
+        Handled::OK
+    }
+
+    /// Fail every blob fetch because the component is shutting down.
+    pub(super) fn cancel_blob_fetches(&mut self) {
+        let pending_fetches = std::mem::take(&mut self.blob_fetches);
+        for pending in pending_fetches.into_values() {
+            self.cancel_timer(pending.timeout_timer.clone());
+            self.settle_blob_fetch(pending, |_| Err(ApiError::RuntimeUnavailable));
+        }
+    }
+
+    /// Answer a group member's request for chunks of a blob the group references.
+    pub(super) fn handle_blob_chunk_request(
+        &mut self,
+        peer: &MemberIdentity,
+        request: &BlobChunkRequest,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        self.ensure_blob_sender_in_group(request.group_id, peer)?;
+        let response = if self
+            .blobs
+            .is_referenced_by(&request.hash, &request.group_id)
+        {
+            self.blobs.respond(request)
+        } else {
+            // Blobs held only for other groups are not revealed to this group's members.
+            BlobChunkResponse::Unavailable(BlobUnavailable {
+                group_id: request.group_id,
+                hash: request.hash,
+            })
+        };
+        match response {
+            BlobChunkResponse::Chunks(chunks) => {
+                for chunk in chunks {
+                    let group_id = chunk.group_id;
+                    let payload = RuntimeMessage::BlobChunk(chunk).encode_proto_to_bytes();
+                    self.submit_reliable_runtime_payload(
+                        peer.clone(),
+                        group_id,
+                        payload,
+                        TrafficClass::Blob,
+                    );
+                }
+            }
+            BlobChunkResponse::Unavailable(unavailable) => {
+                self.submit_reliable_runtime_message(
+                    peer.clone(),
+                    &RuntimeMessage::BlobUnavailable(unavailable),
+                );
+            }
+        }
+        Ok(Handled::OK)
+    }
+
+    /// Add one received chunk to the matching fetch and finish the fetch once it is complete.
+    pub(super) fn handle_blob_chunk(
+        &mut self,
+        peer: &MemberIdentity,
+        chunk: BlobChunk,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        self.ensure_blob_sender_in_group(chunk.group_id, peer)?;
+        let key = (chunk.group_id, chunk.hash);
+        let Entry::Occupied(mut entry) = self.blob_fetches.entry(key) else {
+            debug!(
+                self.log(),
+                "ignoring chunk of blob {} from {peer} without a running fetch", chunk.hash
+            );
+            return Ok(Handled::OK);
+        };
+        if entry.get().peer != *peer {
+            debug!(
+                self.log(),
+                "ignoring chunk of blob {} from {peer}, which was not asked for it", chunk.hash
+            );
+            return Ok(Handled::OK);
+        }
+        let accepted = entry.get_mut().fetch.accept(chunk);
+        if accepted.is_ok() && !entry.get().fetch.is_complete() {
+            return Ok(Handled::OK);
+        }
+        let pending = entry.remove();
+        self.cancel_timer(pending.timeout_timer.clone());
+        let (group_id, hash) = key;
+        let admitted = accepted
+            .and_then(|()| self.admit_fetched_blob(group_id, &pending))
+            .with_context(|_| blob_transfer::InvalidTransferSnafu {
+                peer: peer.clone(),
+                hash,
+            });
+        match admitted {
+            Ok(data) => self.settle_blob_fetch(pending, |_| Ok(data.clone())),
+            Err(error) => {
+                warn!(self.log(), "fetching blob {hash} failed: {error}");
+                let error = Arc::new(error);
+                self.settle_blob_fetch(pending, |_| {
+                    Err(ApiError::ApiExternal {
+                        source: Box::new(Arc::clone(&error)),
+                    })
+                });
+            }
+        }
+        Ok(Handled::OK)
+    }
+
+    /// Verify a complete fetch and keep the blob for its group.
+    fn admit_fetched_blob(
+        &mut self,
+        group_id: GroupId,
+        pending: &PendingBlobFetch,
+    ) -> Result<Bytes, BlobError> {
+        let descriptor = self.blobs.insert_fetched(pending.fetch.clone())?;
+        self.blobs.add_reference(&descriptor.hash, group_id)?;
+        let data = self
+            .blobs
+            .read(&descriptor.hash)
+            .expect("Blobs are readable right after they are stored.");
+        Ok(data)
+    }
+
+    /// Fail the fetch of a blob the asked peer does not hold.
+    pub(super) fn handle_blob_unavailable(
+        &mut self,
+        peer: &MemberIdentity,
+        message: BlobUnavailable,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        self.ensure_blob_sender_in_group(message.group_id, peer)?;
+        let key = (message.group_id, message.hash);
+        let Entry::Occupied(entry) = self.blob_fetches.entry(key) else {
+            return Ok(Handled::OK);
+        };
+        if entry.get().peer != *peer {
+            return Ok(Handled::OK);
+        }
+        let pending = entry.remove();
+        self.cancel_timer(pending.timeout_timer.clone());
+        self.settle_blob_fetch(pending, |peer| {
+            Err(ApiError::BlobUnavailable {
+                group_id: message.group_id,
+                hash: message.hash,
+                peer: peer.clone(),
+            })
+        });
+        Ok(Handled::OK)
+    }
+
+    fn ensure_blob_sender_in_group(
+        &self,
+        group_id: GroupId,
+        sender: &MemberIdentity,
+    ) -> Result<(), InboundDeliveryError> {
+        let memberships = self.group_memberships.snapshot();
+        let members = memberships
+            .members(&group_id)
+            .context(inbound::UnknownHostedGroupSnafu { group_id })?;
+        ensure!(
+            members.contains(sender),
+            inbound::BlobSenderNotInGroupSnafu {
+                group_id,
+                sender: sender.clone(),
+            }
+        );
+        Ok(())
+    }
+
+    /// Reply to every caller waiting for one blob fetch.
+    fn settle_blob_fetch(
+        &self,
+        pending: PendingBlobFetch,
+        reply: impl Fn(&MemberIdentity) -> Result<Bytes, ApiError>,
+    ) {
+        for promise in pending.promises {
+            self.reply_api(promise, "fetch_blob", reply(&pending.peer));
+        }
+    }
+}
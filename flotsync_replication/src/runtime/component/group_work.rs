@@ -22,9 +22,13 @@ pub(super) struct PreparedMembershipMigration {
     pub(super) final_versions: VersionVector,
     pub(super) group_schema: GroupSchema,
     pub(super) initial_snapshot: InitialSnapshot,
+    /// Set when `initial_snapshot` is too large to send inline and is streamed to recipients.
+    pub(super) streamed_snapshot: Option<StreamedInitialSnapshot>,
     pub(super) prepared_setup: PreparedGroupSetup,
     /// Sparse proposed-member indices for newly added recipients.
     pub(super) added_member_indices: RoaringBitmap,
+    /// Proposed observers that do not observe the old group.
+    pub(super) added_observers: HashSet<MemberIdentity>,
     pub(super) group_name: Option<String>,
     pub(super) message: Option<String>,
 }
@@ -33,6 +37,7 @@ pub(super) struct PreparedMembershipMigration {
 pub(super) struct ProposedMembershipChange {
     pub(super) proposed_members: GroupMembers,
     pub(super) added_member_indices: RoaringBitmap,
+    pub(super) added_observers: HashSet<MemberIdentity>,
 }
 
 /// Encoded migration payloads and sparse recipient classes ready for fan-out.
@@ -42,6 +47,9 @@ pub(super) struct PreparedMembershipDispatch {
     pub(super) migration_payload: bytes::Bytes,
     pub(super) invitation_payload: bytes::Bytes,
     pub(super) added_member_indices: RoaringBitmap,
+    pub(super) added_observers: HashSet<MemberIdentity>,
+    /// Chunks of the initial snapshot, if the payloads only announce it.
+    pub(super) snapshot_source: Option<SnapshotSource>,
 }
 
 /// Result of activating accepted group work into externally readable row state.
@@ -18,6 +18,10 @@ pub(super) struct InboundUpdateOutcome {
     pub(super) needed_ranges: Vec<UpdateRangeMessage>,
     /// Producer versions now known to be present in the local update log.
     pub(super) observed_available: Vec<UpdateRangeMessage>,
+    /// Updates newly applied by this delivery, in apply order.
+    pub(super) applied_update_ids: Vec<UpdateId>,
+    /// Local group version vector after applying `applied_update_ids`, if any were applied.
+    pub(super) applied_versions: Option<VersionVector>,
 }
 
 /// Catch-up notifications derived from one observed summary message.
@@ -44,15 +48,18 @@ pub(super) enum InboundDeliveryContext {
         group_id: GroupId,
         sender: MemberIdentity,
         message_id: MessageId,
+        /// Kept so that a rejected broadcast can be quarantined and retried.
+        payload: PlaintextPayload,
     },
 }
 
 impl InboundDeliveryContext {
-    pub(super) fn group(header: &GroupMessageHeader) -> Self {
+    pub(super) fn group(envelope: &GroupMessageEnvelope<PlaintextPayload>) -> Self {
         Self::Group {
-            group_id: header.group_id,
-            sender: header.sender.clone(),
-            message_id: header.message_id,
+            group_id: envelope.header.group_id,
+            sender: envelope.header.sender.clone(),
+            message_id: envelope.header.message_id,
+            payload: envelope.payload.clone(),
         }
     }
 
@@ -80,6 +87,7 @@ impl std::fmt::Display for InboundDeliveryContext {
                 group_id,
                 sender,
                 message_id,
+                ..
             } => write!(
                 f,
                 "group broadcast message {message_id} for group {group_id} from {sender}"
@@ -112,6 +120,16 @@ pub(super) fn summary_from_message(sender: MemberIdentity, message: SummaryMessa
     }
 }
 
+/// Confirm one handled reliable delivery, so that its sender stops retrying it.
+pub(super) fn complete_processed(
+    processed: KClaimablePromise<()>,
+    group_id: GroupId,
+) -> Result<(), InboundDeliveryError> {
+    processed
+        .complete()
+        .context(inbound::CompleteProcessedPromiseSnafu { group_id })
+}
+
 /// Finish inbound handling after escalating fatal failures.
 pub(super) fn handled_after_inbound_failure(
     action: InboundFailureAction,
@@ -14,28 +14,41 @@ pub(super) struct ListenerDataChanges {
 /// Notify a listener about data changes while translating its error for inbound delivery.
 pub(super) async fn notify_listener_batches(
     listener: Arc<dyn ReplicationEventListener>,
+    workspace_events: WorkspaceEvents,
     event_batches: ListenerDataChangeBatches,
 ) -> Result<(), InboundDeliveryError> {
-    notify_listener_data_changes(listener, event_batches)
+    notify_listener_data_changes(listener, workspace_events, event_batches)
         .await
         .context(inbound::NotifyListenerSnafu)
 }
 
 /// Emit non-empty listener data-change batches in their prepared order.
+///
+/// Each batch the listener accepted is also announced on the workspace event bus.
 pub(super) async fn notify_listener_data_changes(
     listener: Arc<dyn ReplicationEventListener>,
+    workspace_events: WorkspaceEvents,
     event_batches: ListenerDataChangeBatches,
 ) -> Result<(), ListenerError> {
     for event_batch in event_batches {
         if event_batch.row_changes.is_empty() {
             continue;
         }
+        let documents = event_batch
+            .row_changes
+            .iter()
+            .map(|change| change.row_id().clone())
+            .collect();
         listener
             .on_event(ReplicationEvent::DataChanged {
-                read_token: event_batch.read_token,
+                read_token: event_batch.read_token.clone(),
                 rows: Box::new(VecRowProvider::new(event_batch.row_changes)),
             })
             .await?;
+        workspace_events.emit(WorkspaceEvent::DocumentChanged {
+            read_token: event_batch.read_token,
+            documents,
+        });
     }
     Ok(())
 }
@@ -43,10 +56,12 @@ pub(super) async fn notify_listener_data_changes(
 /// Notify listeners when accepted pending activation produced externally visible rows.
 pub(super) async fn notify_pending_activation_data_changes(
     listener: Arc<dyn ReplicationEventListener>,
+    workspace_events: WorkspaceEvents,
     outcome: PendingGroupActivationOutcome,
 ) -> Result<(), ListenerError> {
     notify_listener_data_changes(
         listener,
+        workspace_events,
         smallvec![ListenerDataChanges {
             read_token: outcome.read_token,
             row_changes: outcome.row_changes,
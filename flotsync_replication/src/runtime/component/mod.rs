@@ -1,15 +1,28 @@
 use super::{
+    DEFAULT_BLOB_FETCH_TIMEOUT,
+    DEFAULT_CONFLICT_HEAVY_MERGE_THRESHOLD,
     DEFAULT_MAX_INLINE_BOOTSTRAP_PUBLIC_KEY_BUNDLES,
+    DEFAULT_MAX_INLINE_SNAPSHOT_BYTES,
+    DEFAULT_QUARANTINE_CAPACITY,
+    DEFAULT_SNAPSHOT_FETCH_ATTEMPTS,
+    DEFAULT_SNAPSHOT_FETCH_TIMEOUT,
+    DEFAULT_SNAPSHOT_SERVE_RETENTION,
+    DEFAULT_SYNC_STEP_CHECK_INTERVAL,
+    acknowledgements::AcknowledgementTracker,
     catch_up_manager::{
         CatchUpManagerMessage,
         NeedVersions,
         ObservedAvailable,
         subtract_available_ranges,
     },
+    compression::SharedRuntimeCompression,
     config_keys,
+    encodings::SharedRuntimeEncodings,
     errors::{
         AcceptMigrationError,
+        AcknowledgedVersionsError,
         ChangeGroupMembershipError,
+        CheckpointError,
         ConflictingExistingGroupSnafu,
         CreateGroupError,
         CreatorNotInMembersSnafu,
@@ -22,6 +35,7 @@ use super::{
         InvalidGroupSnafu,
         InvalidMembersSnafu,
         LocalMemberMissingSnafu,
+        ObserverReplicaSnafu,
         PendingGroupActivationResumeSnafu,
         PublishChangesError,
         ReplayPendingDecisionSnafu,
@@ -31,12 +45,17 @@ use super::{
         StoreGroupSnafu,
         StoreStartupSnafu,
         SummaryError,
+        SyncStepError,
+        TooManyMembersSnafu,
         accept_migration,
+        acknowledged_versions,
         activation,
         change_membership,
+        checkpoint,
         group_lifecycle,
         inbound,
         publish,
+        run_sync_step,
         snapshot,
         summary,
     },
@@ -51,9 +70,14 @@ use super::{
         validate_inbound_update_read_versions,
         validate_update_mapping,
     },
+    outbox::Outbox,
     pending_group,
+    quarantine::DeliveryQuarantine,
+    rate_limit::WriteRateLimiter,
     replay,
     summary_request_manager::SummaryRequestManagerMessage,
+    sync_scheduler::{SyncScheduler, SyncSession},
+    sync_step::SyncStep,
 };
 #[cfg(any(test, feature = "test-support"))]
 use crate::api::MemberKeyId;
@@ -62,36 +86,50 @@ use crate::test_support::{test_group_key, test_public_member_keys};
 use crate::{
     MAX_VERSION_VALUE,
     api::{
+        AcknowledgedVersions,
         ApiError,
         ApiExternalSnafu,
         BatchProvider,
         ChangeGroupMembershipRequest,
+        Checkpoint,
+        CompressionCounters,
+        CompressionOffer,
+        CreateCheckpointRequest,
         CreateGroupRequest,
         DatasetId,
         DatasetRowStatePatch,
         DatasetRowStateWrite,
         DatasetUpdateRecord,
         EncryptedGroupSecurityMaterial,
+        FetchBlobRequest,
         GroupInvitation,
         GroupInvitationResponder,
         GroupMemberKeys,
         GroupMigrationPolicy,
         GroupSchema,
+        GroupSyncHealth,
+        HistoricalSnapshotRowsRequest,
         InitialSnapshot,
         ListenerError,
+        MemberSyncHealth,
         MigrationCandidateProposal,
         MigrationId,
         MigrationProposal,
         MigrationProposalResponder,
+        PeerLiveness,
         PendingGroupActivationRecord,
         PendingGroupDecisionRecord,
         PendingGroupWorkKey,
         PolicyDecision,
+        PowerHint,
         ProviderExternalSnafu,
         PublishChangesRequest,
         PublishReceipt,
+        QuarantineId,
+        QuarantinedDelivery,
         ReadToken,
         RejectionReason,
+        ReplicaRole,
         ReplicationConfig,
         ReplicationEvent,
         ReplicationEventListener,
@@ -109,12 +147,20 @@ use crate::{
         RowMutation,
         RowProviderError,
         SchemaSource,
+        SnapshotRef,
         SnapshotRowsRequest,
         SnapshotValueRowBatch,
         SnapshotValueRows,
+        StoreBlobRequest,
         StoreError,
         Summary,
         SummaryRequest,
+        SyncStepProgress,
+        TypedDoc,
+        WorkspaceEvent,
+        WorkspaceEvents,
+        checkpoint_kinds,
+        checkpoints_dataset_id,
         providers::VecRowProvider,
         security::{
             AssessPublicKeyBundleRequest,
@@ -122,8 +168,10 @@ use crate::{
             RecordPublicKeyBundleFeedbackRequest,
         },
     },
+    blobs::{BlobDescriptor, BlobHash, BlobStore},
     codecs::messages::{
         BootstrapMemberKeyMessage,
+        FrontierAckMessage,
         GroupInvitationMessage,
         GroupSetupKey,
         GroupSetupMessage,
@@ -132,11 +180,15 @@ use crate::{
         RuntimeMessageDecodeContext,
         SummaryMessage,
         SummaryRequestMessage,
+        ThrottledMessage,
+        UpdateAckMessage,
         UpdateBatchMessage,
         UpdateMessage,
         UpdateRangeMessage,
+        encode_operation_payload,
     },
     delivery::{
+        compression::ConnectionCompression,
         contracts::{
             GroupBroadcastPort,
             GroupBroadcastPortIndication,
@@ -145,7 +197,7 @@ use crate::{
             ReliableDeliveryPortIndication,
             ReliableDeliveryPortRequest,
         },
-        group_broadcast::{GroupBroadcastDeliver, GroupMessageHeader},
+        group_broadcast::{GroupBroadcastDeliver, GroupMessageEnvelope, GroupMessageHeader},
         reliable_delivery::{
             ReliableDeliveryDeliver,
             ReliableDeliverySubmit,
@@ -155,15 +207,24 @@ use crate::{
         security::DeliverySecurity,
         shared::{DeliveryClass, MessageId, PlaintextPayload, ReliableMessageScope},
     },
+    snapshot_transfer::{SnapshotId, SnapshotSource},
 };
 use flotsync_core::{
     GroupId,
     MemberIdentity,
     MemberIndex,
+    member::TrieMap,
     membership::{GroupMembers, GroupMemberships, SharedGroupMemberships},
     versions::{UpdateId, VersionVector},
 };
-use flotsync_messages::proto::{DecodeProtoViewWith, EncodeProto};
+use flotsync_messages::{
+    encoding::{EncodingOffer, PayloadEncoding},
+    proto::{DecodeProtoViewWith, EncodeProto},
+};
+use flotsync_routes::{
+    TrafficClass,
+    liveness::{PeerLivenessPort, PeerLivenessUpdate},
+};
 use flotsync_security::{GROUP_CIPHER_SUITE_CHACHA20_POLY1305, PublicKeyBundle};
 use flotsync_utils::{
     BoxFuture,
@@ -171,6 +232,7 @@ use flotsync_utils::{
     OptionExt as _,
     ResultExt as _,
     kompact_config::ConfigReadExt as _,
+    option_when,
 };
 use futures_util::FutureExt;
 use itertools::Itertools;
@@ -181,15 +243,21 @@ use snafu::prelude::*;
 use std::{
     collections::{HashMap, HashSet},
     num::NonZeroUsize,
+    path::PathBuf,
     sync::Arc,
+    time::{Duration, SystemTime},
 };
 use uuid::Uuid;
 
+mod blob_transfer;
 mod group_work;
 mod inbound_support;
 mod listeners;
+mod outboxes;
 mod snapshot_provider;
+mod snapshot_streaming;
 
+use blob_transfer::PendingBlobFetch;
 use group_work::{
     ComponentBackedPendingGroupResponder,
     MigrationProposalArrival,
@@ -208,6 +276,7 @@ use inbound_support::{
     InboundUpdateOrigin,
     InboundUpdateOutcome,
     SummaryCatchUpObservation,
+    complete_processed,
     handled_after_inbound_failure,
     panic_if_fatal_inbound_failure,
     summary_from_message,
@@ -219,7 +288,8 @@ use listeners::{
     notify_listener_data_changes,
     notify_pending_activation_data_changes,
 };
-use snapshot_provider::StoreSnapshotRowProvider;
+use snapshot_provider::{ReplayedSnapshotRowProvider, StoreSnapshotRowProvider};
+use snapshot_streaming::{PendingSnapshotDownload, ServedSnapshot, StreamedInitialSnapshot};
 
 /// One local publish batch after local apply, encoding, and delivery-envelope preparation.
 struct PreparedLocalPublish {
@@ -279,8 +349,34 @@ pub enum ReplicationRuntimeMessage {
     PublishChanges(Ask<PublishChangesRequest, Result<PublishReceipt, ApiError>>),
     /// Request a local snapshot stream through the component interface.
     SnapshotRows(Ask<SnapshotRowsRequest, Result<SnapshotValueRows, ApiError>>),
+    /// Request a snapshot stream of the rows at earlier group versions.
+    SnapshotRowsAt(Ask<HistoricalSnapshotRowsRequest, Result<SnapshotValueRows, ApiError>>),
+    /// Publish one named checkpoint of a group's versions.
+    CreateCheckpoint(Ask<CreateCheckpointRequest, Result<TypedDoc<Checkpoint>, ApiError>>),
+    /// List the checkpoints of one group.
+    ListCheckpoints(Ask<GroupId, Result<Vec<TypedDoc<Checkpoint>>, ApiError>>),
+    /// Store one blob and reference it from one group.
+    StoreBlob(Ask<StoreBlobRequest, Result<BlobDescriptor, ApiError>>),
+    /// Read one blob referenced by a group, fetching it from a peer if needed.
+    FetchBlob(Ask<FetchBlobRequest, Result<bytes::Bytes, ApiError>>),
     /// Ask one group member for its current group version vector.
     RequestSummary(Ask<SummaryRequest, Result<Summary, ApiError>>),
+    /// Report how far each group member has acknowledged applying updates.
+    AcknowledgedVersions(Ask<GroupId, Result<AcknowledgedVersions, ApiError>>),
+    /// Report liveness and acknowledgement progress of each group member.
+    SyncHealth(Ask<GroupId, Result<GroupSyncHealth, ApiError>>),
+    /// Update the power hint pacing background sync sessions.
+    SetPowerHint(Ask<PowerHint, Result<(), ApiError>>),
+    /// Record that the device's network connectivity changed.
+    NetworkChanged(Ask<(), Result<(), ApiError>>),
+    /// Sync every hosted group with its reachable peers within the given budget.
+    RunSyncStep(Ask<Duration, Result<SyncStepProgress, ApiError>>),
+    /// Read the runtime's compression counters.
+    CompressionCounters(Ask<(), Result<CompressionCounters, ApiError>>),
+    /// List the rejected inbound group broadcasts kept in the quarantine.
+    QuarantinedDeliveries(Ask<(), Result<Vec<QuarantinedDelivery>, ApiError>>),
+    /// Hand one quarantined group broadcast to the runtime again.
+    RetryQuarantinedDelivery(Ask<QuarantineId, Result<(), ApiError>>),
     /// Create one new fixed-membership group through the component interface.
     CreateGroup(Ask<CreateGroupRequest, Result<GroupId, ApiError>>),
     /// Request one group-membership change through the component interface.
@@ -292,6 +388,9 @@ pub enum ReplicationRuntimeMessage {
     Test(ReplicationRuntimeTestMessage),
 }
 
+/// Rows per batch when reading a group's checkpoint dataset.
+const CHECKPOINT_ROWS_PER_BATCH: NonZeroUsize = NonZeroUsize::new(256).unwrap();
+
 /// Delivery path that should carry one inbound summary response.
 enum SummaryReplyRoute {
     /// Reply to a recipient-addressed reliable request and acknowledge it after
@@ -304,6 +403,15 @@ enum SummaryReplyRoute {
     GroupBroadcast,
 }
 
+/// Caller-driven sync step waiting for its peers to answer and catch-up to finish.
+struct ActiveSyncStep {
+    step: SyncStep,
+    promise: KPromise<Result<SyncStepProgress, ApiError>>,
+    check_timer: ScheduledTimer,
+    /// Whether a store check of the step's targets is in flight.
+    checking: bool,
+}
+
 #[cfg(any(test, feature = "test-support"))]
 #[derive(Debug)]
 #[allow(
@@ -379,23 +487,78 @@ pub struct ReplicationRuntimeComponent {
     ctx: ComponentContext<Self>,
     group_broadcast: RequiredPort<GroupBroadcastPort>,
     reliable_delivery: RequiredPort<ReliableDeliveryPort>,
+    liveness: RequiredPort<PeerLivenessPort>,
     local_member: MemberIdentity,
     store: Arc<dyn ReplicationStore>,
     listener: Arc<dyn ReplicationEventListener>,
+    workspace_events: WorkspaceEvents,
     config: ReplicationConfig,
     security: DeliverySecurity,
     group_memberships: SharedGroupMemberships,
+    /// Compression policy, counters, and peer offers shared with the other runtime components.
+    compression: SharedRuntimeCompression,
+    /// Preferred update encoding and peer encoding offers shared with the other runtime components.
+    encodings: SharedRuntimeEncodings,
     summary_request_manager: ActorRefStrong<SummaryRequestManagerMessage>,
     catch_up_manager: ActorRefStrong<CatchUpManagerMessage>,
+    /// Versions each peer has acknowledged applying, per hosted group.
+    acknowledgements: AcknowledgementTracker,
+    /// Last liveness transition reported by the failure detector, per peer.
+    peer_liveness: TrieMap<PeerLiveness>,
+    /// Decides when to start background sync sessions with group peers.
+    sync_scheduler: SyncScheduler,
+    sync_scheduler_timer: Option<ScheduledTimer>,
+    /// Caller-driven sync step currently running, if any.
+    sync_step: Option<ActiveSyncStep>,
+    /// Resolved interval between checks whether the running sync step has finished.
+    sync_step_check_interval: Duration,
     /// Resolved group-size limit for including inline public key bundles in bootstrap messages.
     max_inline_bootstrap_public_key_bundles: usize,
+    /// Resolved size limit for encoded runtime message payloads.
+    max_runtime_payload_bytes: usize,
+    /// Resolved member-count limit for hosted groups.
+    max_group_members: usize,
+    /// Resolved CRDT node limit for rows changed by inbound updates.
+    max_document_nodes: usize,
+    /// Resolved number of unseen local updates that makes merging a remote update conflict-heavy.
+    conflict_heavy_merge_threshold: usize,
+    /// Enforces the configured write rate limit on live updates from each remote member.
+    write_rate_limiter: WriteRateLimiter,
+    /// Rejected inbound group broadcasts kept for inspection and retry.
+    quarantine: DeliveryQuarantine,
+    /// Blobs referenced by hosted groups. Each blob's referrers are the groups that may fetch it.
+    blobs: BlobStore<GroupId>,
+    /// Blobs being fetched from a peer, by group and content hash.
+    blob_fetches: HashMap<(GroupId, BlobHash), PendingBlobFetch>,
+    /// Resolved time to wait for a peer to send every chunk of a requested blob.
+    blob_fetch_timeout: Duration,
+    /// Resolved size limit for blobs fetched from peers.
+    max_blob_bytes: usize,
+    /// Streamed initial snapshots kept for the recipients of local migrations.
+    served_snapshots: HashMap<(GroupId, SnapshotId), ServedSnapshot>,
+    /// Inbound invitations and proposals waiting for their announced initial snapshot.
+    snapshot_downloads: HashMap<(GroupId, SnapshotId), PendingSnapshotDownload>,
+    /// Resolved size above which migration snapshots are streamed instead of sent inline.
+    max_inline_snapshot_bytes: usize,
+    /// Resolved time to wait for progress on a snapshot fetch before asking again.
+    snapshot_fetch_timeout: Duration,
+    /// Resolved number of unanswered snapshot requests after which a fetch fails.
+    snapshot_fetch_attempts: usize,
+    /// Resolved time a streamed snapshot stays available to its recipients.
+    snapshot_serve_retention: Duration,
+    /// Resolved directory of the per-group outboxes, or `None` if outboxes are disabled.
+    outbox_directory: Option<PathBuf>,
+    /// Outboxes of hosted groups that were used since startup.
+    outboxes: HashMap<GroupId, Outbox>,
 }
 
-/// Identity and membership views shared by runtime logic components.
+/// Identity, membership, and peer views shared by runtime logic components.
 #[derive(Clone)]
 pub(super) struct RuntimeIdentityContext {
     pub(super) local_member: MemberIdentity,
     pub(super) group_memberships: SharedGroupMemberships,
+    pub(super) compression: SharedRuntimeCompression,
+    pub(super) encodings: SharedRuntimeEncodings,
 }
 
 /// Application-facing services consumed by the replication runtime component.
@@ -403,6 +566,7 @@ pub(super) struct RuntimeIdentityContext {
 pub(super) struct RuntimeApplicationServices {
     pub(super) store: Arc<dyn ReplicationStore>,
     pub(super) listener: Arc<dyn ReplicationEventListener>,
+    pub(super) workspace_events: WorkspaceEvents,
     pub(super) config: ReplicationConfig,
 }
 
@@ -426,20 +590,51 @@ impl ReplicationRuntimeComponent {
         security: RuntimeSecurityContext,
         actors: RuntimeComponentActors,
     ) -> Self {
+        let sync_scheduler = SyncScheduler::new(services.config.sync_scheduling.clone());
+        let write_rate_limiter = WriteRateLimiter::new(services.config.write_rate_limit);
+        let limits = services.config.limits;
         Self {
             ctx: ComponentContext::uninitialised(),
             group_broadcast: RequiredPort::uninitialised(),
             reliable_delivery: RequiredPort::uninitialised(),
+            liveness: RequiredPort::uninitialised(),
             local_member: identity.local_member,
             store: services.store,
             listener: services.listener,
+            workspace_events: services.workspace_events,
             config: services.config,
             security: security.security,
             group_memberships: identity.group_memberships,
+            compression: identity.compression,
+            encodings: identity.encodings,
             summary_request_manager: actors.summary_request_manager,
             catch_up_manager: actors.catch_up_manager,
+            acknowledgements: AcknowledgementTracker::default(),
+            peer_liveness: TrieMap::new(),
+            sync_scheduler,
+            sync_scheduler_timer: None,
+            sync_step: None,
+            sync_step_check_interval: DEFAULT_SYNC_STEP_CHECK_INTERVAL,
             max_inline_bootstrap_public_key_bundles:
                 DEFAULT_MAX_INLINE_BOOTSTRAP_PUBLIC_KEY_BUNDLES,
+            max_runtime_payload_bytes: limits.max_payload_bytes,
+            max_group_members: limits.max_group_members,
+            max_document_nodes: limits.max_document_nodes,
+            conflict_heavy_merge_threshold: DEFAULT_CONFLICT_HEAVY_MERGE_THRESHOLD,
+            write_rate_limiter,
+            quarantine: DeliveryQuarantine::new(DEFAULT_QUARANTINE_CAPACITY),
+            blobs: BlobStore::default(),
+            blob_fetches: HashMap::new(),
+            blob_fetch_timeout: DEFAULT_BLOB_FETCH_TIMEOUT,
+            max_blob_bytes: limits.max_blob_bytes,
+            served_snapshots: HashMap::new(),
+            snapshot_downloads: HashMap::new(),
+            max_inline_snapshot_bytes: DEFAULT_MAX_INLINE_SNAPSHOT_BYTES,
+            snapshot_fetch_timeout: DEFAULT_SNAPSHOT_FETCH_TIMEOUT,
+            snapshot_fetch_attempts: DEFAULT_SNAPSHOT_FETCH_ATTEMPTS,
+            snapshot_serve_retention: DEFAULT_SNAPSHOT_SERVE_RETENTION,
+            outbox_directory: None,
+            outboxes: HashMap::new(),
         }
     }
 
@@ -456,17 +651,10 @@ impl ReplicationRuntimeComponent {
         }
     }
 
-    fn record_inbound_failure(&self, failure: &InboundDeliveryFailure) -> InboundFailureAction {
+    fn record_inbound_failure(&mut self, failure: &InboundDeliveryFailure) -> InboundFailureAction {
         let action = failure.error.failure_action();
         match action {
-            InboundFailureAction::Drop => {
-                warn!(
-                    self.log(),
-                    "dropping inbound {} after recoverable error: {}",
-                    failure.context,
-                    failure.error
-                );
-            }
+            InboundFailureAction::Drop => self.quarantine_inbound_failure(failure),
             InboundFailureAction::Fatal => {
                 error!(
                     self.log(),
@@ -477,6 +665,55 @@ impl ReplicationRuntimeComponent {
         action
     }
 
+    /// Log a dropped inbound delivery and keep it in the quarantine if it was a group broadcast.
+    ///
+    /// Reliable deliveries are only logged.
+    fn quarantine_inbound_failure(&mut self, failure: &InboundDeliveryFailure) {
+        let quarantined = match &failure.context {
+            InboundDeliveryContext::Group {
+                group_id,
+                sender,
+                message_id,
+                payload,
+            } => {
+                let header = GroupMessageHeader {
+                    group_id: *group_id,
+                    sender: sender.clone(),
+                    message_id: *message_id,
+                };
+                self.quarantine.insert(
+                    header,
+                    payload.clone(),
+                    failure.error.to_string(),
+                    SystemTime::now(),
+                )
+            }
+            InboundDeliveryContext::Reliable { .. } => None,
+        };
+        let Some(quarantined) = quarantined else {
+            warn!(
+                self.log(),
+                "dropping inbound {} after recoverable error: {}", failure.context, failure.error
+            );
+            return;
+        };
+        warn!(
+            self.log(),
+            "quarantined inbound {} as {} after recoverable error: {}",
+            failure.context,
+            quarantined.id,
+            failure.error;
+            "quarantine_id" => quarantined.id.0,
+            "reason" => %failure.error
+        );
+        if let Some(evicted) = quarantined.evicted {
+            warn!(
+                self.log(),
+                "dropped quarantined delivery {evicted} to make room for {}", quarantined.id
+            );
+        }
+    }
+
     async fn load_dataset_schemas<I>(
         store: Arc<dyn ReplicationStore>,
         dataset_ids: I,
@@ -506,10 +743,12 @@ impl ReplicationRuntimeComponent {
         &self,
         group_id: GroupId,
         proposed_members: Vec<MemberIdentity>,
+        proposed_observers: Vec<MemberIdentity>,
         group_schema: &GroupSchema,
         material: &ReplicationGroupMaterialRecord,
     ) -> Result<ReplicationGroupRecord, GroupActivationError> {
         let expected_members = GroupMembers::from_ordered_members(proposed_members)
+            .and_then(|members| members.with_observers(proposed_observers))
             .context(activation::InvalidMembersSnafu)?;
         ensure!(
             expected_members.contains(&self.local_member),
@@ -526,6 +765,10 @@ impl ReplicationRuntimeComponent {
         .context(activation::InvalidPersistedGroupSnafu { group_id })?;
         ensure!(
             local_group.members.ordered_members() == expected_members.ordered_members()
+                && local_group
+                    .members
+                    .observers()
+                    .eq(expected_members.observers())
                 && group_record.group_schema == *group_schema,
             activation::ConflictingGroupMaterialSnafu { group_id }
         );
@@ -572,19 +815,7 @@ impl ReplicationRuntimeComponent {
             }
         );
         let read_token = Self::read_token_from_groups(groups);
-
-        let mut schemas = HashMap::with_capacity(request.datasets.len());
-        for dataset_id in &request.datasets {
-            let schema = self
-                .store
-                .load_dataset_schema(dataset_id)
-                .await
-                .context(snapshot::StoreAccessSnafu)?;
-            let schema = schema.context(snapshot::MissingDatasetSchemaSnafu {
-                dataset_id: dataset_id.clone(),
-            })?;
-            schemas.insert(dataset_id.clone(), schema);
-        }
+        let schemas = Self::load_snapshot_schemas(self.store.as_ref(), &request.datasets).await?;
 
         let provider = StoreSnapshotRowProvider::new(
             transaction,
@@ -601,6 +832,101 @@ impl ReplicationRuntimeComponent {
         })
     }
 
+    /// Replay one group's applied updates and stream the rows at the requested versions.
+    async fn historical_snapshot_rows_from_store(
+        &mut self,
+        request: HistoricalSnapshotRowsRequest,
+    ) -> Result<SnapshotValueRows, SnapshotRowsError> {
+        ensure!(!request.datasets.is_empty(), snapshot::EmptyDatasetsSnafu);
+        let group_id = request.group_id;
+        ensure!(
+            self.group_memberships.snapshot().contains_group(&group_id),
+            snapshot::UnknownGroupSnafu { group_id }
+        );
+
+        let mut transaction = self
+            .store
+            .begin_read_transaction()
+            .await
+            .context(snapshot::StoreAccessSnafu)?;
+        let group = transaction
+            .load_replication_group(&group_id)
+            .await
+            .context(snapshot::StoreAccessSnafu)?
+            .context(snapshot::UnknownGroupSnafu { group_id })?;
+        ensure!(
+            group.lifecycle.is_readable(),
+            snapshot::GroupClosedSnafu { group_id }
+        );
+        let member_count = group.member_count();
+        ensure!(
+            request.versions.num_members() == member_count,
+            snapshot::VersionsMemberCountMismatchSnafu {
+                group_id,
+                versions_member_count: request.versions.num_members().get(),
+                persisted_member_count: member_count.get(),
+            }
+        );
+        ensure!(
+            request.versions <= group.version_vector,
+            snapshot::VersionsNotAppliedSnafu { group_id }
+        );
+        let applied_updates = transaction
+            .load_replication_updates(&group_id, ReplicationUpdateFilter::Applied, None)
+            .await
+            .context(snapshot::StoreAccessSnafu)?;
+        transaction
+            .release()
+            .await
+            .context(snapshot::StoreAccessSnafu)?;
+
+        let schemas = Self::load_snapshot_schemas(self.store.as_ref(), &request.datasets).await?;
+        let replayed = replay::replay_datasets_at_versions(
+            group_id,
+            member_count,
+            &schemas,
+            applied_updates,
+            &request.versions,
+            replay::ReplayScope::AllRows,
+        )
+        .context(snapshot::ReplaySnafu { group_id })?;
+        let read_token = if group.lifecycle.is_writable() {
+            ReadToken::from_group_versions(HashMap::from([(group_id, request.versions)]))
+        } else {
+            ReadToken::from_group_versions(HashMap::new())
+        };
+        let provider = ReplayedSnapshotRowProvider::new(
+            group_id,
+            &schemas,
+            replayed,
+            request.max_rows_per_batch,
+        );
+        Ok(SnapshotValueRows {
+            group_id,
+            read_token,
+            rows: Box::new(provider),
+        })
+    }
+
+    /// Load the schemas of all datasets requested for one snapshot.
+    async fn load_snapshot_schemas(
+        store: &dyn ReplicationStore,
+        datasets: &HashSet<DatasetId>,
+    ) -> Result<HashMap<DatasetId, SchemaSource>, SnapshotRowsError> {
+        let mut schemas = HashMap::with_capacity(datasets.len());
+        for dataset_id in datasets {
+            let schema = store
+                .load_dataset_schema(dataset_id)
+                .await
+                .context(snapshot::StoreAccessSnafu)?;
+            let schema = schema.context(snapshot::MissingDatasetSchemaSnafu {
+                dataset_id: dataset_id.clone(),
+            })?;
+            schemas.insert(dataset_id.clone(), schema);
+        }
+        Ok(schemas)
+    }
+
     /// Persist one set of explicit row patches back into the replication store.
     async fn apply_dataset_row_patches(
         transaction: &mut dyn ReplicationStoreTransaction,
@@ -641,9 +967,11 @@ impl ReplicationRuntimeComponent {
     ) -> ReplicationGroupMaterialRecord {
         NonZeroUsize::new(member_keys.len())
             .expect("group installation must keep members non-empty");
-        let local_member_index = member_keys
-            .member_index(&self.local_member)
-            .expect("group installation validates the local member before persistence");
+        assert!(
+            member_keys.contains_member(&self.local_member),
+            "group installation validates the local member before persistence"
+        );
+        let local_member_index = member_keys.member_index(&self.local_member);
         ReplicationGroupMaterialRecord {
             group_id,
             member_keys,
@@ -700,6 +1028,55 @@ impl ReplicationRuntimeComponent {
         Ok(ranges)
     }
 
+    /// Refuse `update` if one of its datasets uses semantics this code does not implement.
+    ///
+    /// The refusal is also emitted as a [`WorkspaceEvent::IncompatibleSemantics`], so that
+    /// applications can tell users to upgrade.
+    fn ensure_supported_semantics(
+        &self,
+        update: &ReplicationUpdateRecord,
+        schemas: &HashMap<DatasetId, SchemaSource>,
+    ) -> Result<(), InboundDeliveryError> {
+        for dataset_update in &update.dataset_updates {
+            let schema = schemas
+                .get(&dataset_update.dataset_id)
+                .expect("inbound update schemas must be pre-loaded before validation");
+            let Err(error) = schema.check_semantics_revision() else {
+                continue;
+            };
+            self.workspace_events
+                .emit(WorkspaceEvent::IncompatibleSemantics {
+                    group_id: update.group_id,
+                    dataset_id: dataset_update.dataset_id.clone(),
+                    update_id: update.update_id,
+                    error: error.clone(),
+                });
+            return Err(error).context(inbound::IncompatibleSemanticsRevisionSnafu {
+                group_id: update.group_id,
+                update_id: update.update_id,
+                dataset_id: dataset_update.dataset_id.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Count the updates of other producers in `local_versions` that `update` had not read.
+    ///
+    /// The producer's own earlier updates are skipped since updates do not read themselves.
+    fn count_unseen_updates(
+        local_versions: &VersionVector,
+        update: &ReplicationUpdateRecord,
+    ) -> u64 {
+        let producer_index = update.update_id.node_index as usize;
+        update
+            .read_versions
+            .missing_version_ranges_to(local_versions)
+            .into_iter()
+            .filter(|gap| gap.member_index != producer_index)
+            .map(|gap| gap.end_version - gap.start_version + 1)
+            .sum()
+    }
+
     /// Clamp producer ranges to the immutable replay cut of a non-open group.
     fn bound_update_ranges(
         lifecycle: &ReplicationGroupLifecycle,
@@ -871,9 +1248,19 @@ impl ReplicationRuntimeComponent {
         &self,
         group_id: GroupId,
         proposed_members: &[MemberIdentity],
+        proposed_observers: &[MemberIdentity],
     ) -> Result<GroupMembers, InboundDeliveryError> {
         let members = GroupMembers::from_ordered_members(proposed_members.iter().cloned())
+            .and_then(|members| members.with_observers(proposed_observers.iter().cloned()))
             .context(inbound::InvalidPendingGroupMembersSnafu)?;
+        ensure!(
+            members.len() <= self.max_group_members,
+            inbound::TooManyGroupMembersSnafu {
+                group_id,
+                member_count: members.len(),
+                limit: self.max_group_members,
+            }
+        );
         ensure!(
             members.contains(&self.local_member),
             inbound::PendingGroupMissingLocalMemberSnafu {
@@ -889,7 +1276,11 @@ impl ReplicationRuntimeComponent {
         &self,
         invitation: &GroupInvitation,
     ) -> Result<PolicyDecision, InboundDeliveryError> {
-        self.validate_pending_group_members(invitation.group_id, &invitation.proposed_members)?;
+        self.validate_pending_group_members(
+            invitation.group_id,
+            &invitation.proposed_members,
+            &invitation.proposed_observers,
+        )?;
         Ok(match invitation.source {
             crate::api::GroupInvitationSource::Creation => {
                 self.config.group_invitation_policy.creation
@@ -906,8 +1297,11 @@ impl ReplicationRuntimeComponent {
         proposal: &MigrationProposal,
     ) -> Result<PolicyDecision, InboundDeliveryError> {
         let group_id = proposal.migration_id.new_group_id;
-        let proposed_members =
-            self.validate_pending_group_members(group_id, &proposal.proposed_members)?;
+        let proposed_members = self.validate_pending_group_members(
+            group_id,
+            &proposal.proposed_members,
+            &proposal.proposed_observers,
+        )?;
         let mut transaction = self
             .store
             .begin_read_transaction()
@@ -967,6 +1361,12 @@ impl ReplicationRuntimeComponent {
         if record.requires_snapshot_fetch() {
             return Ok(PolicyDecision::AutoReject);
         }
+        // Observer replicas never take a member index, whatever the proposer intended.
+        if self.config.replica_role == ReplicaRole::Observer
+            && record.proposed_members().contains(&self.local_member)
+        {
+            return Ok(PolicyDecision::AutoReject);
+        }
         match record {
             PendingGroupDecisionRecord::GroupInvitation(invitation) => {
                 self.invitation_policy_decision(invitation)
@@ -1116,17 +1516,21 @@ impl ReplicationRuntimeComponent {
     fn summary_message_for_request(
         message: SummaryRequestMessage,
         has_versions: VersionVector,
+        accepts_compression: CompressionOffer,
+        accepts_encodings: EncodingOffer,
     ) -> RuntimeMessage {
-        RuntimeMessage::Summary(SummaryMessage::new(
-            message.group_id,
-            message.correlation_id,
-            has_versions,
-        ))
+        RuntimeMessage::Summary(
+            SummaryMessage::new(message.group_id, message.correlation_id, has_versions)
+                .with_accepts_compression(accepts_compression)
+                .with_accepts_encodings(accepts_encodings),
+        )
     }
 
     async fn load_summary_message_from_store(
         store: Arc<dyn ReplicationStore>,
         message: SummaryRequestMessage,
+        accepts_compression: CompressionOffer,
+        accepts_encodings: EncodingOffer,
     ) -> Result<RuntimeMessage, InboundDeliveryError> {
         let has_versions = Self::load_summary_versions_from_store(store, message.group_id)
             .await
@@ -1134,7 +1538,12 @@ impl ReplicationRuntimeComponent {
         let has_versions = has_versions.context(inbound::UnknownHostedGroupSnafu {
             group_id: message.group_id,
         })?;
-        Ok(Self::summary_message_for_request(message, has_versions))
+        Ok(Self::summary_message_for_request(
+            message,
+            has_versions,
+            accepts_compression,
+            accepts_encodings,
+        ))
     }
 
     fn summary_for_request(
@@ -1213,6 +1622,27 @@ impl ReplicationRuntimeComponent {
         Ok(())
     }
 
+    /// Emit a workspace event for every member of `group_id` that was not part of the group it
+    /// replaced.
+    fn emit_joined_members(&self, previous_group_id: GroupId, group_id: GroupId) {
+        let memberships = self.group_memberships.snapshot();
+        let (Some(previous_members), Some(members)) = (
+            memberships.members(&previous_group_id),
+            memberships.members(&group_id),
+        ) else {
+            return;
+        };
+        for member in members.iter() {
+            if !previous_members.contains(&member) {
+                self.workspace_events.emit(WorkspaceEvent::MemberJoined {
+                    group_id,
+                    previous_group_id,
+                    member,
+                });
+            }
+        }
+    }
+
     /// Load the persisted group registry into the shared membership snapshot during
     /// component startup.
     async fn load_hydrated_runtime_memberships(
@@ -1325,10 +1755,14 @@ impl ReplicationRuntimeComponent {
                 .activate_pending_group_record(activation)
                 .await
                 .context(PendingGroupActivationResumeSnafu)?;
-            notify_pending_activation_data_changes(self.listener.clone(), outcome)
-                .await
-                .context(activation::NotifyListenerSnafu)
-                .context(PendingGroupActivationResumeSnafu)?;
+            notify_pending_activation_data_changes(
+                self.listener.clone(),
+                self.workspace_events.clone(),
+                outcome,
+            )
+            .await
+            .context(activation::NotifyListenerSnafu)
+            .context(PendingGroupActivationResumeSnafu)?;
         }
         Ok(())
     }
@@ -1519,23 +1953,65 @@ impl ReplicationRuntimeComponent {
                 .await
                 .boxed()
                 .context(ApiExternalSnafu)?;
-            notify_pending_activation_data_changes(self.listener.clone(), outcome)
-                .await
-                .boxed()
-                .context(ApiExternalSnafu)?;
+            notify_pending_activation_data_changes(
+                self.listener.clone(),
+                self.workspace_events.clone(),
+                outcome,
+            )
+            .await
+            .boxed()
+            .context(ApiExternalSnafu)?;
         }
         Ok(())
     }
 
     /// Submit one encoded live update to the group-broadcast layer.
     fn submit_group_update(&mut self, prepared_publish: &PreparedLocalPublish) {
+        self.submit_group_update_payload(
+            prepared_publish.group_id,
+            prepared_publish.payload.clone(),
+        );
+    }
+
+    /// Submit one encoded update payload of `group_id` to the group-broadcast layer.
+    fn submit_group_update_payload(&mut self, group_id: GroupId, payload: bytes::Bytes) {
+        let compression = self.group_broadcast_compression(group_id);
+        let encoding = self.group_broadcast_encoding(group_id);
+        let payload =
+            encode_operation_payload(payload, encoding, &compression, self.compression.metrics());
         self.group_broadcast.trigger(
             GroupBroadcastPortRequest::build_submit(DeliveryClass::BestEffort)
-                .for_member_in_group(self.local_member.clone(), prepared_publish.group_id)
-                .with_payload(prepared_publish.payload.clone()),
+                .for_member_in_group(self.local_member.clone(), group_id)
+                .with_payload(payload),
         );
     }
 
+    /// Broadcast one runtime message to the rest of its group.
+    fn submit_group_runtime_message(&mut self, message: &RuntimeMessage) {
+        let compression = self.group_broadcast_compression(message.group_id());
+        let encoding = self.group_broadcast_encoding(message.group_id());
+        let payload = message.encode_payload(encoding, &compression, self.compression.metrics());
+        self.group_broadcast.trigger(
+            GroupBroadcastPortRequest::build_submit(DeliveryClass::BestEffort)
+                .for_member_in_group(self.local_member.clone(), message.group_id())
+                .with_payload(payload),
+        );
+    }
+
+    /// Negotiate compression for one broadcast to the remote members of `group_id`.
+    fn group_broadcast_compression(&self, group_id: GroupId) -> ConnectionCompression {
+        let memberships = self.group_memberships.snapshot();
+        self.compression
+            .for_group_broadcast(&memberships, &group_id, &self.local_member)
+    }
+
+    /// Negotiate the update encoding for one broadcast to the remote members of `group_id`.
+    fn group_broadcast_encoding(&self, group_id: GroupId) -> PayloadEncoding {
+        let memberships = self.group_memberships.snapshot();
+        self.encodings
+            .for_group_broadcast(&memberships, &group_id, &self.local_member)
+    }
+
     /// Submit one runtime envelope through reliable delivery using its authority scope.
     fn submit_reliable_runtime_message(
         &mut self,
@@ -1543,7 +2019,12 @@ impl ReplicationRuntimeComponent {
         message: &RuntimeMessage,
     ) {
         let payload = message.encode_proto_to_bytes();
-        self.submit_reliable_runtime_payload(recipient, message.group_id(), payload);
+        self.submit_reliable_runtime_payload(
+            recipient,
+            message.group_id(),
+            payload,
+            TrafficClass::Interactive,
+        );
     }
 
     /// Submit one already encoded runtime payload through reliable delivery.
@@ -1552,6 +2033,7 @@ impl ReplicationRuntimeComponent {
         recipient: MemberIdentity,
         scope_group_id: GroupId,
         payload: bytes::Bytes,
+        traffic_class: TrafficClass,
     ) {
         self.reliable_delivery
             .trigger(ReliableDeliveryPortRequest::Submit(
@@ -1567,6 +2049,7 @@ impl ReplicationRuntimeComponent {
                         },
                         payload: PlaintextPayload { bytes: payload },
                     },
+                    traffic_class,
                 },
             ));
     }
@@ -1582,16 +2065,33 @@ impl ReplicationRuntimeComponent {
         for recipient in group_setup
             .members()
             .iter()
+            .chain(group_setup.observers())
             .filter(|member| *member != &local_member)
             .cloned()
         {
-            self.submit_reliable_runtime_payload(recipient, group_id, payload.clone());
+            // Invitations may carry the group's initial rows inline.
+            self.submit_reliable_runtime_payload(
+                recipient,
+                group_id,
+                payload.clone(),
+                TrafficClass::Snapshot,
+            );
         }
     }
 
     /// Send old-group migration proposals and new-group invitations for one change.
-    fn submit_membership_migration_messages(&mut self, dispatch: &PreparedMembershipDispatch) {
+    fn submit_membership_migration_messages(&mut self, dispatch: PreparedMembershipDispatch) {
         let proposed_members = dispatch.group_setup.members();
+        let proposed_observers = dispatch.group_setup.observers();
+        if let Some(source) = dispatch.snapshot_source {
+            // Serve before sending, so recipients never ask for a snapshot that is not there yet.
+            let recipients = proposed_members[1..]
+                .iter()
+                .chain(proposed_observers)
+                .cloned()
+                .collect();
+            self.serve_initial_snapshot(source, recipients);
+        }
         // Index zero is the local member. Dispatch classifies only remote
         // recipients; every remote index absent from `added_member_indices`
         // necessarily names a continuing old-group member.
@@ -1602,23 +2102,51 @@ impl ReplicationRuntimeComponent {
                     recipient.clone(),
                     dispatch.migration_id.new_group_id,
                     dispatch.invitation_payload.clone(),
+                    TrafficClass::Snapshot,
                 );
             } else {
                 self.submit_reliable_runtime_payload(
                     recipient.clone(),
                     dispatch.migration_id.old_group_id,
                     dispatch.migration_payload.clone(),
+                    TrafficClass::Snapshot,
                 );
             }
         }
+        for recipient in proposed_observers {
+            let (group_id, payload) = if dispatch.added_observers.contains(recipient) {
+                (
+                    dispatch.migration_id.new_group_id,
+                    dispatch.invitation_payload.clone(),
+                )
+            } else {
+                (
+                    dispatch.migration_id.old_group_id,
+                    dispatch.migration_payload.clone(),
+                )
+            };
+            self.submit_reliable_runtime_payload(
+                recipient.clone(),
+                group_id,
+                payload,
+                TrafficClass::Snapshot,
+            );
+        }
     }
 
     /// Encode one proposal and one invitation before local activation consumes the snapshot.
     fn prepare_membership_dispatch(
         prepared: &PreparedMembershipMigration,
     ) -> PreparedMembershipDispatch {
-        let proposal = Self::prepared_migration_proposal(prepared);
+        let mut proposal = Self::prepared_migration_proposal(prepared);
+        // Recipients of a streamed snapshot only learn its metadata and fetch the rows.
+        let initial_snapshot = match &prepared.streamed_snapshot {
+            Some(streamed) => InitialSnapshot::Metadata(streamed.metadata.clone()),
+            None => prepared.initial_snapshot.clone(),
+        };
+        proposal.initial_snapshot = initial_snapshot.clone();
         let proposed_members = proposal.proposed_members.clone();
+        let proposed_observers = proposal.proposed_observers.clone();
         let proposal_message = MigrationProposalMessage::try_new(
             proposal,
             Arc::clone(&prepared.prepared_setup.group_setup),
@@ -1631,10 +2159,11 @@ impl ReplicationRuntimeComponent {
             prepared.migration_id,
             proposed_members,
             prepared.group_schema.clone(),
-            prepared.initial_snapshot.clone(),
+            initial_snapshot,
             prepared.group_name.clone(),
             prepared.message.clone(),
-        );
+        )
+        .with_proposed_observers(proposed_observers);
         let invitation_message = GroupInvitationMessage::try_new(
             invitation,
             Arc::clone(&prepared.prepared_setup.group_setup),
@@ -1649,6 +2178,11 @@ impl ReplicationRuntimeComponent {
             migration_payload,
             invitation_payload,
             added_member_indices: prepared.added_member_indices.clone(),
+            added_observers: prepared.added_observers.clone(),
+            snapshot_source: prepared
+                .streamed_snapshot
+                .as_ref()
+                .map(|streamed| streamed.source.clone()),
         }
     }
 
@@ -1658,6 +2192,7 @@ impl ReplicationRuntimeComponent {
             migration_id: prepared.migration_id,
             final_versions: prepared.final_versions.clone(),
             proposed_members: prepared.prepared_setup.group_setup.members().to_vec(),
+            proposed_observers: prepared.prepared_setup.group_setup.observers().to_vec(),
             group_schema: prepared.group_schema.clone(),
             initial_snapshot: prepared.initial_snapshot.clone(),
             group_name: prepared.group_name.clone(),
@@ -1671,15 +2206,27 @@ impl ReplicationRuntimeComponent {
         &self,
         req: CreateGroupRequest,
     ) -> Result<(GroupId, GroupMembers, GroupSchema), CreateGroupError> {
+        ensure!(
+            self.config.replica_role == ReplicaRole::Member,
+            ObserverReplicaSnafu
+        );
         let requested_members = creator_first_member_order(req.members, &self.local_member)?;
-        let members =
-            GroupMembers::from_ordered_members(requested_members).context(InvalidMembersSnafu)?;
+        let members = GroupMembers::from_ordered_members(requested_members)
+            .and_then(|members| members.with_observers(req.observers))
+            .context(InvalidMembersSnafu)?;
         ensure!(
-            members.contains(&self.local_member),
+            members.member_index(&self.local_member).is_some(),
             LocalMemberMissingSnafu {
                 local_member: self.local_member.clone(),
             }
         );
+        ensure!(
+            members.len() <= self.max_group_members,
+            TooManyMembersSnafu {
+                member_count: members.len(),
+                limit: self.max_group_members,
+            }
+        );
 
         let group_id = GroupId(Uuid::new_v4());
         Ok((group_id, members, req.group_schema))
@@ -1721,6 +2268,7 @@ impl ReplicationRuntimeComponent {
             .context(SecuritySnafu)?;
         let group_setup = GroupSetupMessage::new(
             members.ordered_members(),
+            members.observers().collect(),
             setup_member_keys,
             GROUP_CIPHER_SUITE_CHACHA20_POLY1305,
             GroupSetupKey::from_group_key(group_key),
@@ -1747,6 +2295,14 @@ impl ReplicationRuntimeComponent {
             CreateGroupError::InvalidMembers { source } => {
                 ChangeGroupMembershipError::InvalidMembers { source }
             }
+            CreateGroupError::TooManyMembers {
+                member_count,
+                limit,
+            } => ChangeGroupMembershipError::TooManyMembers {
+                member_count,
+                limit,
+            },
+            CreateGroupError::ObserverReplica => ChangeGroupMembershipError::ObserverReplica,
             CreateGroupError::Security { source } => {
                 ChangeGroupMembershipError::Security { source }
             }
@@ -1759,7 +2315,14 @@ impl ReplicationRuntimeComponent {
         current_members: &GroupMembers,
         req: &ChangeGroupMembershipRequest,
     ) -> Result<ProposedMembershipChange, ChangeGroupMembershipError> {
-        let current_member_set = current_members.iter().collect::<HashSet<_>>();
+        ensure!(
+            self.config.replica_role == ReplicaRole::Member,
+            change_membership::ObserverReplicaSnafu
+        );
+        let current_member_set = current_members
+            .ordered_members()
+            .into_iter()
+            .collect::<HashSet<_>>();
         ensure!(
             current_member_set.contains(&self.local_member),
             change_membership::LocalMemberMissingSnafu {
@@ -1767,10 +2330,20 @@ impl ReplicationRuntimeComponent {
             }
         );
         let mut final_members = current_member_set.clone();
+        let mut final_observers = current_members.observers().collect::<HashSet<_>>();
         for member in &req.remove_members {
             final_members.remove(member);
+            final_observers.remove(member);
+        }
+        // Members and observers may swap roles; listing one identity in both is rejected below.
+        for observer in &req.add_observers {
+            final_members.remove(observer);
+        }
+        for member in &req.add_members {
+            final_observers.remove(member);
         }
         final_members.extend(req.add_members.iter().cloned());
+        final_observers.extend(req.add_observers.iter().cloned());
         ensure!(
             final_members.remove(&self.local_member),
             change_membership::LocalMemberMissingSnafu {
@@ -1784,16 +2357,30 @@ impl ReplicationRuntimeComponent {
         for member in final_members {
             let member_index = u32::try_from(proposed_member_list.len())
                 .expect("group member indices fit into u32");
-            if !current_member_set.contains(&member) {
+            if !current_members.contains(&member) {
                 added_member_indices.insert(member_index);
             }
             proposed_member_list.push(member);
         }
+        let added_observers = final_observers
+            .iter()
+            .filter(|observer| !current_members.contains(observer))
+            .cloned()
+            .collect();
         let proposed_members = GroupMembers::from_ordered_members(proposed_member_list)
+            .and_then(|members| members.with_observers(final_observers))
             .context(change_membership::InvalidMembersSnafu)?;
+        ensure!(
+            proposed_members.len() <= self.max_group_members,
+            change_membership::TooManyMembersSnafu {
+                member_count: proposed_members.len(),
+                limit: self.max_group_members,
+            }
+        );
         Ok(ProposedMembershipChange {
             proposed_members,
             added_member_indices,
+            added_observers,
         })
     }
 
@@ -1853,13 +2440,23 @@ impl ReplicationRuntimeComponent {
             old_group_id,
             new_group_id,
         };
+        let streamed_snapshot = self.streamed_initial_snapshot(
+            new_group_id,
+            SnapshotRef {
+                group_id: old_group_id,
+                versions: final_versions.clone(),
+            },
+            &initial_snapshot,
+        );
         Ok(PreparedMembershipMigration {
             migration_id,
             final_versions,
             group_schema,
             initial_snapshot,
+            streamed_snapshot,
             prepared_setup,
             added_member_indices: proposed_change.added_member_indices,
+            added_observers: proposed_change.added_observers,
             group_name: req.group_name,
             message: req.message,
         })
@@ -1870,10 +2467,11 @@ impl ReplicationRuntimeComponent {
         &self,
         prepared: &PreparedMembershipMigration,
     ) -> Result<ReplicationGroupRecord, GroupActivationError> {
-        let member_keys = GroupMemberKeys::from_ordered_member_keys(
-            prepared.prepared_setup.group_setup.ordered_member_key_ids(),
-        )
-        .context(activation::InvalidMembersSnafu)?;
+        let member_keys = prepared
+            .prepared_setup
+            .group_setup
+            .group_member_keys()
+            .context(activation::InvalidMembersSnafu)?;
         Ok(self.build_replication_group_record(
             prepared.migration_id.new_group_id,
             member_keys,
@@ -1935,6 +2533,7 @@ impl ReplicationRuntimeComponent {
         let group_record = self.validate_activation_group_material(
             group_id,
             activation_record.proposed_members,
+            activation_record.proposed_observers,
             &activation_record.group_schema,
             &material,
         )?;
@@ -1991,17 +2590,27 @@ impl ReplicationRuntimeComponent {
             .context(activation::StoreAccessSnafu)?;
         self.install_group_membership_view(group_record)
             .context(activation::InstallGroupSnafu { group_id })?;
+        if let Some(cutover) = &migration_cutover {
+            self.emit_joined_members(cutover.old_group_id, group_id);
+        }
         Ok(PendingGroupActivationOutcome {
             read_token,
             row_changes,
         })
     }
 
+    /// Allocate the id of the next update this replica produces for `local_group`.
+    ///
+    /// This is the only place local update ids are created, so it is also where observers,
+    /// which hold no member index, are stopped from producing updates.
     fn next_local_update_id(
         local_group: &LoadedGroupMeta,
         group_id: GroupId,
     ) -> Result<UpdateId, PublishChangesError> {
-        let applied_version = local_group.applied_version(local_group.local_member_index);
+        let local_member_index = local_group
+            .local_member_index
+            .context(publish::ObserverReplicaSnafu { group_id })?;
+        let applied_version = local_group.applied_version(local_member_index);
         ensure!(
             applied_version < MAX_VERSION_VALUE,
             publish::ExhaustedUpdateIdsSnafu { group_id }
@@ -2009,7 +2618,7 @@ impl ReplicationRuntimeComponent {
         let next_local_version = applied_version + 1;
         Ok(UpdateId {
             version: next_local_version,
-            node_index: local_group.local_member_index.as_u32(),
+            node_index: local_member_index.as_u32(),
         })
     }
 
@@ -2140,6 +2749,13 @@ impl ReplicationRuntimeComponent {
                         PublishChangesError::MissingDatasetSchema { dataset_id }
                     }
                 })?;
+        for (dataset_id, schema) in &loaded_schemas {
+            schema.check_semantics_revision().context(
+                publish::IncompatibleSemanticsRevisionSnafu {
+                    dataset_id: dataset_id.clone(),
+                },
+            )?;
+        }
         let mut transaction = self
             .store
             .begin_transaction()
@@ -2193,12 +2809,34 @@ impl ReplicationRuntimeComponent {
         )?;
         local_group.mark_applied(update_id);
 
+        // Encode before persisting anything, so oversized updates never reach the store.
+        let message = RuntimeMessage::Update(Box::new(UpdateMessage {
+            group_id,
+            update_id,
+            read_versions: read_versions.clone(),
+            dataset_updates: prepared_local_changes
+                .dataset_updates
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .collect(),
+        }));
+        let payload = message.encode_proto_to_bytes();
+        ensure!(
+            payload.len() <= self.max_runtime_payload_bytes,
+            publish::PayloadTooLargeSnafu {
+                group_id,
+                payload_bytes: payload.len(),
+                limit: self.max_runtime_payload_bytes,
+            }
+        );
+
         let persisted_update = ReplicationUpdateRecord {
             group_id,
             update_id,
             sender: self.local_member.clone(),
-            read_versions: read_versions.clone(),
-            dataset_updates: prepared_local_changes.dataset_updates.clone(),
+            read_versions,
+            dataset_updates: prepared_local_changes.dataset_updates,
             applied_locally: true,
         };
         Self::apply_dataset_row_patches(transaction.as_mut(), prepared_local_changes.row_patches)
@@ -2218,17 +2856,6 @@ impl ReplicationRuntimeComponent {
             .await
             .context(publish::StoreAccessSnafu)?;
 
-        let message = RuntimeMessage::Update(Box::new(UpdateMessage {
-            group_id,
-            update_id,
-            read_versions,
-            dataset_updates: prepared_local_changes
-                .dataset_updates
-                .into_iter()
-                .map(Into::into)
-                .collect(),
-        }));
-        let payload = message.encode_proto_to_bytes();
         Ok(PreparedLocalPublish {
             group_id,
             update_id,
@@ -2274,9 +2901,17 @@ impl ReplicationRuntimeComponent {
         message: SummaryRequestMessage,
     ) -> HandlerResult {
         let store = self.store.clone();
+        let accepts_compression = self.compression.local_offer();
+        let accepts_encodings = self.encodings.local_offer();
         Handled::block_on(self, async move |mut async_self| {
             let reply = async {
-                let summary = Self::load_summary_message_from_store(store, message).await?;
+                let summary = Self::load_summary_message_from_store(
+                    store,
+                    message,
+                    accepts_compression,
+                    accepts_encodings,
+                )
+                .await?;
                 async_self.submit_summary_reply(route, message, &summary)
             }
             .await;
@@ -2296,21 +2931,43 @@ impl ReplicationRuntimeComponent {
         record: PendingGroupDecisionRecord,
         group_setup: Arc<GroupSetupMessage>,
     ) -> HandlerResult {
-        let group_id = record.group_id();
         let sender = deliver.envelope.header.sender.clone();
-        let processed = deliver.processed;
-        Handled::block_on(self, async move |mut async_self| {
-            let reply = async {
-                async_self
-                    .install_pending_group_delivery(record, group_setup, sender)
-                    .await?;
-                processed
-                    .complete()
-                    .context(inbound::CompleteProcessedPromiseSnafu { group_id })
-            }
-            .await;
-            if let Err(error) = reply {
-                let failure = InboundDeliveryFailure::new(context, error);
+        if let InitialSnapshot::Metadata(metadata) = record.initial_snapshot() {
+            let metadata = metadata.clone();
+            return self.handle_announced_pending_group(
+                context,
+                deliver.processed,
+                sender,
+                record,
+                group_setup,
+                &metadata,
+            );
+        }
+        self.install_inbound_pending_group(context, deliver.processed, sender, record, group_setup)
+    }
+
+    /// Persist inbound pending work whose initial snapshot is available locally.
+    fn install_inbound_pending_group(
+        &mut self,
+        context: InboundDeliveryContext,
+        processed: KClaimablePromise<()>,
+        sender: MemberIdentity,
+        record: PendingGroupDecisionRecord,
+        group_setup: Arc<GroupSetupMessage>,
+    ) -> HandlerResult {
+        let group_id = record.group_id();
+        Handled::block_on(self, async move |mut async_self| {
+            let reply = async {
+                async_self
+                    .install_pending_group_delivery(record, group_setup, sender)
+                    .await?;
+                processed
+                    .complete()
+                    .context(inbound::CompleteProcessedPromiseSnafu { group_id })
+            }
+            .await;
+            if let Err(error) = reply {
+                let failure = InboundDeliveryFailure::new(context, error);
                 let action = async_self.record_inbound_failure(&failure);
                 panic_if_fatal_inbound_failure(action, &failure);
             }
@@ -2384,14 +3041,21 @@ impl ReplicationRuntimeComponent {
     ) -> Result<ValidatedInboundGroupSetup, InboundDeliveryError> {
         let group_id = record.group_id();
         let members = GroupMembers::from_ordered_members(group_setup.members().to_vec())
+            .and_then(|members| members.with_observers(group_setup.observers().iter().cloned()))
             .context(inbound::InvalidGroupSetupMembersSnafu)?;
+        ensure!(
+            members.len() <= self.max_group_members,
+            inbound::TooManyGroupMembersSnafu {
+                group_id,
+                member_count: members.len(),
+                limit: self.max_group_members,
+            }
+        );
         Self::validate_group_setup_membership(group_id, &members, &self.local_member, sender)?;
-        let member_keys =
-            GroupMemberKeys::from_ordered_member_keys(group_setup.ordered_member_key_ids())
-                .context(inbound::InvalidGroupSetupMembersSnafu)?;
-        let local_member_index = member_keys
-            .member_index(&self.local_member)
-            .expect("group setup membership validation requires the local member");
+        let member_keys = group_setup
+            .group_member_keys()
+            .context(inbound::InvalidGroupSetupMembersSnafu)?;
+        let local_member_index = member_keys.member_index(&self.local_member);
         let group_schema = record.group_schema().clone();
         let mut read_transaction = self
             .store
@@ -2586,9 +3250,13 @@ impl ReplicationRuntimeComponent {
                     .activate_pending_group_record(activation)
                     .await
                     .context(inbound::PendingGroupActivationSnafu)?;
-                notify_pending_activation_data_changes(self.listener.clone(), outcome)
-                    .await
-                    .context(inbound::NotifyListenerSnafu)
+                notify_pending_activation_data_changes(
+                    self.listener.clone(),
+                    self.workspace_events.clone(),
+                    outcome,
+                )
+                .await
+                .context(inbound::NotifyListenerSnafu)
             }
         }
     }
@@ -2627,13 +3295,33 @@ impl ReplicationRuntimeComponent {
         }
     }
 
+    /// Reject oversized payloads before spending any effort on decoding them.
+    fn check_inbound_payload_size(&self, payload: &[u8]) -> Result<(), InboundDeliveryError> {
+        ensure!(
+            payload.len() <= self.max_runtime_payload_bytes,
+            inbound::PayloadTooLargeSnafu {
+                payload_bytes: payload.len(),
+                limit: self.max_runtime_payload_bytes,
+            }
+        );
+        Ok(())
+    }
+
     fn handle_reliable_delivery(
         &mut self,
         deliver: ReliableDeliveryDeliver,
     ) -> Result<HandlerResult, InboundDeliveryFailure> {
         let context = InboundDeliveryContext::reliable(&deliver.envelope.header);
+        if let Err(error) = self.check_inbound_payload_size(&deliver.envelope.payload.bytes) {
+            return Err(InboundDeliveryFailure::new(context, error));
+        }
         let memberships = self.group_memberships.snapshot();
-        let decode_context = RuntimeMessageDecodeContext::new(memberships.as_ref());
+        let decode_context = RuntimeMessageDecodeContext::new(memberships.as_ref())
+            .with_compression(
+                self.compression.local_offer(),
+                self.max_runtime_payload_bytes,
+                self.compression.metrics(),
+            );
         let message_res = RuntimeMessage::decode_proto_view_from_slice_with(
             &deliver.envelope.payload.bytes,
             decode_context,
@@ -2681,14 +3369,98 @@ impl ReplicationRuntimeComponent {
                 context,
                 InboundDeliveryError::UnexpectedReliableMessage,
             )),
-            RuntimeMessage::NeedRange(_) | RuntimeMessage::UpdateBatch(_) => {
-                Err(InboundDeliveryFailure::new(
-                    context,
-                    InboundDeliveryError::UnexpectedReliableMessage,
-                ))
+            RuntimeMessage::NeedRange(_)
+            | RuntimeMessage::UpdateBatch(_)
+            | RuntimeMessage::UpdateAck(_)
+            | RuntimeMessage::FrontierAck(_) => Err(InboundDeliveryFailure::new(
+                context,
+                InboundDeliveryError::UnexpectedReliableMessage,
+            )),
+            RuntimeMessage::Throttled(message) => {
+                let peer = deliver.envelope.header.sender.clone();
+                self.handle_throttled(&peer, &message)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, message.group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
+            }
+            RuntimeMessage::BlobChunkRequest(request) => {
+                let peer = deliver.envelope.header.sender.clone();
+                let group_id = request.group_id;
+                self.handle_blob_chunk_request(&peer, &request)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
+            }
+            RuntimeMessage::BlobChunk(chunk) => {
+                let peer = deliver.envelope.header.sender.clone();
+                let group_id = chunk.group_id;
+                self.handle_blob_chunk(&peer, chunk)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
+            }
+            RuntimeMessage::BlobUnavailable(message) => {
+                let peer = deliver.envelope.header.sender.clone();
+                let group_id = message.group_id;
+                self.handle_blob_unavailable(&peer, message)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
+            }
+            RuntimeMessage::SnapshotManifestRequest(request) => {
+                let peer = deliver.envelope.header.sender.clone();
+                self.handle_snapshot_manifest_request(&peer, &request)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, request.group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
+            }
+            RuntimeMessage::SnapshotManifest(manifest) => {
+                let peer = deliver.envelope.header.sender.clone();
+                let group_id = manifest.group_id;
+                self.handle_snapshot_manifest(&peer, manifest)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
+            }
+            RuntimeMessage::SnapshotChunkRequest(request) => {
+                let peer = deliver.envelope.header.sender.clone();
+                let group_id = request.group_id;
+                self.handle_snapshot_chunk_request(&peer, &request)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
+            }
+            RuntimeMessage::SnapshotChunk(chunk) => {
+                let peer = deliver.envelope.header.sender.clone();
+                let group_id = chunk.group_id;
+                self.handle_snapshot_chunk(&peer, chunk)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
+            }
+            RuntimeMessage::SnapshotUnavailable(message) => {
+                let peer = deliver.envelope.header.sender.clone();
+                let group_id = message.group_id;
+                self.handle_snapshot_unavailable(&peer, message)
+                    .and_then(|handled| {
+                        complete_processed(deliver.processed, group_id).map(|()| handled)
+                    })
+                    .map_err(|error| InboundDeliveryFailure::new(context, error))
             }
             RuntimeMessage::SummaryRequest(message) => {
                 let sender = deliver.envelope.header.sender.clone();
+                self.compression
+                    .record_peer_offer(&sender, message.accepts_compression);
+                self.encodings
+                    .record_peer_offer(&sender, message.accepts_encodings);
                 Ok(self.handle_inbound_summary_request(
                     context,
                     SummaryReplyRoute::Reliable {
@@ -2700,20 +3472,43 @@ impl ReplicationRuntimeComponent {
             }
             RuntimeMessage::Summary(message) => {
                 let sender = deliver.envelope.header.sender.clone();
+                self.compression
+                    .record_peer_offer(&sender, message.accepts_compression);
+                self.encodings
+                    .record_peer_offer(&sender, message.accepts_encodings);
                 let summary = summary_from_message(sender, message);
                 Ok(self.handle_observed_summary(summary))
             }
         }
     }
 
+    /// Handle one inbound group broadcast, quarantining or escalating its failure.
+    fn deliver_group_broadcast(&mut self, deliver: &GroupBroadcastDeliver) -> HandlerResult {
+        match self.handle_group_delivery(deliver) {
+            Ok(handled) => handled,
+            Err(failure) => {
+                let action = self.record_inbound_failure(&failure);
+                handled_after_inbound_failure(action, &failure)
+            }
+        }
+    }
+
     fn handle_group_delivery(
         &mut self,
         deliver: &GroupBroadcastDeliver,
     ) -> Result<HandlerResult, InboundDeliveryFailure> {
-        let context = InboundDeliveryContext::group(&deliver.envelope.header);
+        let context = InboundDeliveryContext::group(&deliver.envelope);
+        if let Err(error) = self.check_inbound_payload_size(&deliver.envelope.payload.bytes) {
+            return Err(InboundDeliveryFailure::new(context, error));
+        }
         let sender = deliver.envelope.header.sender.clone();
         let memberships = self.group_memberships.snapshot();
-        let decode_context = RuntimeMessageDecodeContext::new(memberships.as_ref());
+        let decode_context = RuntimeMessageDecodeContext::new(memberships.as_ref())
+            .with_compression(
+                self.compression.local_offer(),
+                self.max_runtime_payload_bytes,
+                self.compression.metrics(),
+            );
         let message = match RuntimeMessage::decode_proto_view_from_slice_with(
             &deliver.envelope.payload.bytes,
             decode_context,
@@ -2724,10 +3519,27 @@ impl ReplicationRuntimeComponent {
             Err(error) => return Err(InboundDeliveryFailure::new(context, error)),
         };
         match message {
-            RuntimeMessage::GroupInvitation(_) | RuntimeMessage::MigrationProposal(_) => Err(
-                InboundDeliveryFailure::new(context, InboundDeliveryError::UnexpectedGroupMessage),
-            ),
-            RuntimeMessage::Update(message) => Ok(self.handle_update(context, sender, *message)),
+            RuntimeMessage::GroupInvitation(_)
+            | RuntimeMessage::MigrationProposal(_)
+            | RuntimeMessage::Throttled(_)
+            | RuntimeMessage::BlobChunkRequest(_)
+            | RuntimeMessage::BlobChunk(_)
+            | RuntimeMessage::BlobUnavailable(_)
+            | RuntimeMessage::SnapshotManifestRequest(_)
+            | RuntimeMessage::SnapshotManifest(_)
+            | RuntimeMessage::SnapshotChunkRequest(_)
+            | RuntimeMessage::SnapshotChunk(_)
+            | RuntimeMessage::SnapshotUnavailable(_) => Err(InboundDeliveryFailure::new(
+                context,
+                InboundDeliveryError::UnexpectedGroupMessage,
+            )),
+            RuntimeMessage::Update(message) => {
+                let payload_bytes = deliver.envelope.payload.bytes.len();
+                if !self.admit_live_update(&sender, &message, payload_bytes) {
+                    return Ok(Handled::OK);
+                }
+                Ok(self.handle_update(context, sender, *message))
+            }
             RuntimeMessage::UpdateBatch(message) => {
                 Ok(self.handle_update_batch(context, sender, message))
             }
@@ -2736,14 +3548,30 @@ impl ReplicationRuntimeComponent {
                 Ok(Handled::OK)
             }
             RuntimeMessage::Summary(message) => {
+                self.compression
+                    .record_peer_offer(&sender, message.accepts_compression);
+                self.encodings
+                    .record_peer_offer(&sender, message.accepts_encodings);
                 let summary = summary_from_message(sender, message);
                 Ok(self.handle_observed_summary(summary))
             }
-            RuntimeMessage::SummaryRequest(message) => Ok(self.handle_inbound_summary_request(
-                context,
-                SummaryReplyRoute::GroupBroadcast,
-                message,
-            )),
+            RuntimeMessage::SummaryRequest(message) => {
+                self.compression
+                    .record_peer_offer(&sender, message.accepts_compression);
+                self.encodings
+                    .record_peer_offer(&sender, message.accepts_encodings);
+                Ok(self.handle_inbound_summary_request(
+                    context,
+                    SummaryReplyRoute::GroupBroadcast,
+                    message,
+                ))
+            }
+            RuntimeMessage::UpdateAck(message) => self
+                .handle_update_ack(&sender, &message)
+                .map_err(|error| InboundDeliveryFailure::new(context, error)),
+            RuntimeMessage::FrontierAck(message) => self
+                .handle_frontier_ack(&sender, &message)
+                .map_err(|error| InboundDeliveryFailure::new(context, error)),
         }
     }
 
@@ -2793,6 +3621,8 @@ impl ReplicationRuntimeComponent {
                 event_batches: ListenerDataChangeBatches::new(),
                 needed_ranges: Vec::new(),
                 observed_available: Vec::new(),
+                applied_update_ids: Vec::new(),
+                applied_versions: None,
             });
         }
         let incoming_available_range = UpdateRangeMessage::from(message.update_id);
@@ -2814,6 +3644,8 @@ impl ReplicationRuntimeComponent {
                 event_batches: ListenerDataChangeBatches::new(),
                 needed_ranges,
                 observed_available: vec![incoming_available_range],
+                applied_update_ids: Vec::new(),
+                applied_versions: None,
             });
         }
 
@@ -2846,6 +3678,7 @@ impl ReplicationRuntimeComponent {
                             InboundDeliveryError::MissingDatasetSchema { dataset_id }
                         }
                     })?;
+            self.ensure_supported_semantics(&inbound_update, &loaded_schemas)?;
             validate_update_mapping(&inbound_update, &loaded_schemas)?;
             transaction
                 .append_replication_update(inbound_update.clone())
@@ -2890,6 +3723,8 @@ impl ReplicationRuntimeComponent {
                 event_batches: ListenerDataChangeBatches::new(),
                 needed_ranges,
                 observed_available,
+                applied_update_ids: Vec::new(),
+                applied_versions: None,
             });
         }
 
@@ -2936,9 +3771,25 @@ impl ReplicationRuntimeComponent {
                 .with_group_version(group_id, local_group.version_vector.clone());
         }
         let mut event_batches = ListenerDataChangeBatches::new();
+        let mut conflict_heavy_merges = Vec::new();
         for ready_update in &apply_plan.ready_chain {
-            let applied_batch =
-                apply_one_update(&mut local_group, &mut working_datasets, ready_update)?;
+            let concurrent_updates =
+                Self::count_unseen_updates(&local_group.version_vector, ready_update);
+            if concurrent_updates > 0
+                && concurrent_updates >= self.conflict_heavy_merge_threshold as u64
+            {
+                conflict_heavy_merges.push(WorkspaceEvent::ConflictHeavyMerge {
+                    group_id,
+                    update_id: ready_update.update_id,
+                    concurrent_updates,
+                });
+            }
+            let applied_batch = apply_one_update(
+                &mut local_group,
+                &mut working_datasets,
+                ready_update,
+                self.max_document_nodes,
+            )?;
             if lifecycle.is_writable() {
                 listener_read_token = listener_read_token
                     .with_group_version(group_id, local_group.version_vector.clone());
@@ -2968,17 +3819,26 @@ impl ReplicationRuntimeComponent {
         }
         let applied_versions = local_group.version_vector.clone();
         transaction
-            .update_replication_group_version_vector(&group_id, applied_versions)
+            .update_replication_group_version_vector(&group_id, applied_versions.clone())
             .await
             .context(inbound::StoreAccessSnafu)?;
         transaction
             .commit()
             .await
             .context(inbound::StoreAccessSnafu)?;
+        for event in conflict_heavy_merges {
+            self.workspace_events.emit(event);
+        }
         Ok(InboundUpdateOutcome {
             event_batches,
             needed_ranges,
             observed_available,
+            applied_update_ids: apply_plan
+                .ready_chain
+                .iter()
+                .map(|update| update.update_id)
+                .collect(),
+            applied_versions: Some(applied_versions),
         })
     }
 
@@ -2996,6 +3856,7 @@ impl ReplicationRuntimeComponent {
         let group_id = message.group_id;
         let mut observed_available = Vec::new();
         let mut needed_ranges = Vec::new();
+        let mut applied_versions = None;
         for update in message.updates {
             let outcome = match self
                 .persist_and_apply_update(
@@ -3015,8 +3876,13 @@ impl ReplicationRuntimeComponent {
             };
             observed_available.extend(outcome.observed_available);
             needed_ranges.extend(outcome.needed_ranges);
-            if let Err(error) =
-                notify_listener_batches(self.listener.clone(), outcome.event_batches).await
+            applied_versions = outcome.applied_versions.or(applied_versions);
+            if let Err(error) = notify_listener_batches(
+                self.listener.clone(),
+                self.workspace_events.clone(),
+                outcome.event_batches,
+            )
+            .await
             {
                 self.notify_catch_up_available(group_id, observed_available);
                 self.notify_catch_up_needed(group_id, needed_ranges);
@@ -3025,6 +3891,15 @@ impl ReplicationRuntimeComponent {
         }
         self.notify_catch_up_available(group_id, observed_available);
         self.notify_catch_up_needed(group_id, needed_ranges);
+        if let Some(applied_versions) = applied_versions
+            && !self.local_member_observes(group_id)
+        {
+            // One frontier covers the whole batch, however many producers it spans.
+            self.submit_group_runtime_message(&RuntimeMessage::FrontierAck(FrontierAckMessage {
+                group_id,
+                applied_versions,
+            }));
+        }
         Ok(())
     }
 
@@ -3043,9 +3918,24 @@ impl ReplicationRuntimeComponent {
                 Ok(outcome) => {
                     async_self.notify_catch_up_available(group_id, outcome.observed_available);
                     async_self.notify_catch_up_needed(group_id, outcome.needed_ranges);
-                    notify_listener_batches(async_self.listener.clone(), outcome.event_batches)
-                        .await
-                        .err()
+                    if !outcome.applied_update_ids.is_empty() {
+                        async_self.record_sync_change(group_id);
+                        if !async_self.local_member_observes(group_id) {
+                            async_self.submit_group_runtime_message(&RuntimeMessage::UpdateAck(
+                                UpdateAckMessage {
+                                    group_id,
+                                    update_ids: outcome.applied_update_ids,
+                                },
+                            ));
+                        }
+                    }
+                    notify_listener_batches(
+                        async_self.listener.clone(),
+                        async_self.workspace_events.clone(),
+                        outcome.event_batches,
+                    )
+                    .await
+                    .err()
                 }
                 Err(error) => Some(error),
             };
@@ -3091,38 +3981,153 @@ impl ReplicationRuntimeComponent {
     ) -> HandlerResult {
         let (promise, request) = ask.take();
         Handled::block_on(self, async move |mut async_self| {
-            let reply = match async_self.publish_changes_transactionally(request).await {
-                Ok(prepared_publish) => {
-                    let update_id = prepared_publish.update_id;
-                    let read_token = prepared_publish.read_token.clone();
-                    async_self.submit_group_update(&prepared_publish);
-                    async_self.notify_catch_up_available(
-                        prepared_publish.group_id,
-                        vec![UpdateRangeMessage::from(update_id)],
-                    );
-                    match notify_listener_batches(
-                        async_self.listener.clone(),
-                        smallvec![ListenerDataChanges {
-                            read_token: read_token.clone(),
-                            row_changes: prepared_publish.row_changes,
-                        }],
-                    )
-                    .await
-                    {
-                        Ok(()) => Ok(PublishReceipt {
-                            update_id,
-                            read_token,
-                        }),
-                        Err(error) => Err(error).boxed().context(ApiExternalSnafu),
-                    }
-                }
-                Err(error) => Err(error).boxed().context(ApiExternalSnafu),
-            };
+            let reply = async_self.publish_and_notify(request).await;
             async_self.reply_api(promise, "publish_changes", reply);
             Handled::OK
         })
     }
 
+    /// Publish one local change set, then distribute it and notify the local listener.
+    async fn publish_and_notify(
+        &mut self,
+        request: PublishChangesRequest,
+    ) -> Result<PublishReceipt, ApiError> {
+        let prepared_publish = self
+            .publish_changes_transactionally(request)
+            .await
+            .boxed()
+            .context(ApiExternalSnafu)?;
+        let update_id = prepared_publish.update_id;
+        let read_token = prepared_publish.read_token.clone();
+        self.submit_group_update(&prepared_publish);
+        self.queue_local_update(&prepared_publish);
+        self.record_sync_change(prepared_publish.group_id);
+        self.notify_catch_up_available(
+            prepared_publish.group_id,
+            vec![UpdateRangeMessage::from(update_id)],
+        );
+        notify_listener_batches(
+            self.listener.clone(),
+            self.workspace_events.clone(),
+            smallvec![ListenerDataChanges {
+                read_token: read_token.clone(),
+                row_changes: prepared_publish.row_changes,
+            }],
+        )
+        .await
+        .boxed()
+        .context(ApiExternalSnafu)?;
+        Ok(PublishReceipt {
+            update_id,
+            read_token,
+        })
+    }
+
+    fn handle_create_checkpoint(
+        &mut self,
+        ask: Ask<CreateCheckpointRequest, Result<TypedDoc<Checkpoint>, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, request) = ask.take();
+        let (publish_request, checkpoint) = match Self::prepare_checkpoint(request) {
+            Ok(prepared) => prepared,
+            Err(error) => {
+                let reply = Err(error).boxed().context(ApiExternalSnafu);
+                self.reply_api(promise, "create_checkpoint", reply);
+                return Handled::OK;
+            }
+        };
+        Handled::block_on(self, async move |mut async_self| {
+            let reply = async_self
+                .publish_and_notify(publish_request)
+                .await
+                .map(|_| checkpoint);
+            async_self.reply_api(promise, "create_checkpoint", reply);
+            Handled::OK
+        })
+    }
+
+    /// Build the publish request that stores one new checkpoint document.
+    fn prepare_checkpoint(
+        request: CreateCheckpointRequest,
+    ) -> Result<(PublishChangesRequest, TypedDoc<Checkpoint>), CheckpointError> {
+        let group_id = request.group_id;
+        let versions = request
+            .read_token
+            .group_version(&group_id)
+            .context(checkpoint::ReadTokenMissingGroupSnafu { group_id })?
+            .clone();
+        let row_id = RowId {
+            group_id,
+            dataset_id: checkpoints_dataset_id(),
+            row_key: RowKey(Uuid::new_v4()),
+        };
+        let document = Checkpoint {
+            label: request.label,
+            versions,
+        };
+        let change = checkpoint_kinds()
+            .upsert(row_id.clone(), &document)
+            .context(checkpoint::DocumentSnafu { group_id })?;
+        let publish_request = PublishChangesRequest {
+            read_token: request.read_token,
+            changes: vec![change],
+        };
+        Ok((publish_request, TypedDoc { row_id, document }))
+    }
+
+    fn handle_list_checkpoints(
+        &mut self,
+        ask: Ask<GroupId, Result<Vec<TypedDoc<Checkpoint>>, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, group_id) = ask.take();
+        Handled::block_on(self, async move |mut async_self| {
+            let reply = async_self
+                .load_checkpoints(group_id)
+                .await
+                .boxed()
+                .context(ApiExternalSnafu);
+            async_self.reply_api(promise, "list_checkpoints", reply);
+            Handled::OK
+        })
+    }
+
+    /// Decode every checkpoint stored for one group, ordered by label.
+    async fn load_checkpoints(
+        &mut self,
+        group_id: GroupId,
+    ) -> Result<Vec<TypedDoc<Checkpoint>>, CheckpointError> {
+        let mut snapshot = self
+            .snapshot_rows_from_store(SnapshotRowsRequest {
+                group_id,
+                datasets: HashSet::from([checkpoints_dataset_id()]),
+                max_rows_per_batch: CHECKPOINT_ROWS_PER_BATCH,
+                include_tombstones: false,
+            })
+            .await
+            .context(checkpoint::SnapshotSnafu { group_id })?;
+        let kinds = checkpoint_kinds();
+        let mut checkpoints = Vec::new();
+        while let Some(batch) = snapshot
+            .rows
+            .next_batch()
+            .await
+            .context(checkpoint::ReadRowsSnafu { group_id })?
+        {
+            for row in batch.rows() {
+                let checkpoint = kinds
+                    .open::<Checkpoint>(row.row_id().clone(), &row)
+                    .context(checkpoint::DocumentSnafu { group_id })?;
+                checkpoints.push(checkpoint);
+            }
+        }
+        checkpoints.sort_by(|left, right| {
+            left.label
+                .cmp(&right.label)
+                .then_with(|| left.row_id.row_key.cmp(&right.row_id.row_key))
+        });
+        Ok(checkpoints)
+    }
+
     fn handle_assess_public_key_bundle(
         &mut self,
         ask: Ask<AssessPublicKeyBundleRequest, Result<PublicKeyBundleReport, ApiError>>,
@@ -3185,6 +4190,22 @@ impl ReplicationRuntimeComponent {
         })
     }
 
+    fn handle_snapshot_rows_at(
+        &mut self,
+        ask: Ask<HistoricalSnapshotRowsRequest, Result<SnapshotValueRows, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, request) = ask.take();
+        Handled::block_on(self, async move |mut async_self| {
+            let reply = async_self
+                .historical_snapshot_rows_from_store(request)
+                .await
+                .boxed()
+                .context(ApiExternalSnafu);
+            async_self.reply_api(promise, "snapshot_rows_at", reply);
+            Handled::OK
+        })
+    }
+
     fn handle_request_summary(
         &mut self,
         ask: Ask<SummaryRequest, Result<Summary, ApiError>>,
@@ -3295,6 +4316,11 @@ impl ReplicationRuntimeComponent {
     }
 
     fn handle_observed_summary(&mut self, summary: Summary) -> HandlerResult {
+        if let Some(active) = self.sync_step.as_mut() {
+            active
+                .step
+                .record_summary(summary.group_id, &summary.responder, &summary.has_versions);
+        }
         // Summary catch-up is advisory: it reads a store snapshot and sends
         // catch-up notifications, but it does not mutate runtime component
         // state. Running it asynchronously can at worst produce stale
@@ -3320,6 +4346,539 @@ impl ReplicationRuntimeComponent {
         Handled::OK
     }
 
+    /// Fold one peer's receipts for individual updates into its acknowledged frontier.
+    fn handle_update_ack(
+        &mut self,
+        sender: &MemberIdentity,
+        message: &UpdateAckMessage,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        let (num_members, sender_index) = self.ack_sender_index(message.group_id, sender)?;
+        let advanced = self.acknowledgements.record_updates(
+            message.group_id,
+            num_members,
+            sender_index,
+            &message.update_ids,
+        );
+        let frontier = self
+            .acknowledgements
+            .frontier(message.group_id, sender_index)
+            .cloned();
+        if advanced && let Some(frontier) = frontier {
+            self.acknowledge_outbox(message.group_id, sender_index, &frontier);
+        }
+        Ok(Handled::OK)
+    }
+
+    /// Fold one peer's applied frontier into its acknowledged frontier.
+    fn handle_frontier_ack(
+        &mut self,
+        sender: &MemberIdentity,
+        message: &FrontierAckMessage,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        let (_, sender_index) = self.ack_sender_index(message.group_id, sender)?;
+        let advanced = self.acknowledgements.record_frontier(
+            message.group_id,
+            sender_index,
+            &message.applied_versions,
+        );
+        if advanced {
+            self.acknowledge_outbox(message.group_id, sender_index, &message.applied_versions);
+        }
+        Ok(Handled::OK)
+    }
+
+    /// Charge one live update against its producer's write rate limit.
+    ///
+    /// Returns `false` if the update must be dropped. The producer is told once per back-off
+    /// period; the dropped update is fetched again through catch-up once it shows up as a gap.
+    fn admit_live_update(
+        &mut self,
+        sender: &MemberIdentity,
+        message: &UpdateMessage,
+        payload_bytes: usize,
+    ) -> bool {
+        let operations: usize = message
+            .dataset_updates
+            .iter()
+            .map(|dataset_update| dataset_update.operations.len())
+            .sum();
+        let now = self.ctx.system().now();
+        let Err(throttle) = self.write_rate_limiter.admit(
+            message.group_id,
+            sender,
+            operations as u64,
+            payload_bytes as u64,
+            now,
+        ) else {
+            return true;
+        };
+        debug!(
+            self.log(),
+            "dropping update {} of group {} from {sender}: {} limit exceeded, retry after {:?}",
+            message.update_id,
+            message.group_id,
+            throttle.reason,
+            throttle.retry_after,
+        );
+        if throttle.notify {
+            self.submit_reliable_runtime_message(
+                sender.clone(),
+                &RuntimeMessage::Throttled(ThrottledMessage {
+                    group_id: message.group_id,
+                    update_id: message.update_id,
+                    reason: throttle.reason,
+                    retry_after: throttle.retry_after,
+                }),
+            );
+        }
+        false
+    }
+
+    /// Surface a peer's notice that it dropped one of our live updates.
+    fn handle_throttled(
+        &mut self,
+        peer: &MemberIdentity,
+        message: &ThrottledMessage,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        let group_id = message.group_id;
+        let memberships = self.group_memberships.snapshot();
+        let members = memberships
+            .members(&group_id)
+            .context(inbound::UnknownHostedGroupSnafu { group_id })?;
+        // Observers apply updates too, so they may throttle ours.
+        ensure!(
+            members.contains(peer),
+            inbound::AckSenderNotInGroupSnafu {
+                group_id,
+                sender: peer.clone(),
+            }
+        );
+        warn!(
+            self.log(),
+            "{peer} throttled update {} of group {}: {} limit exceeded, retry after {:?}",
+            message.update_id,
+            message.group_id,
+            message.reason,
+            message.retry_after,
+        );
+        self.workspace_events.emit(WorkspaceEvent::WriteThrottled {
+            group_id: message.group_id,
+            peer: peer.clone(),
+            reason: message.reason,
+            retry_after: message.retry_after,
+        });
+        Ok(Handled::OK)
+    }
+
+    /// Return whether the local member only observes `group_id`.
+    ///
+    /// Observers send no acknowledgements, since members cannot index them in their
+    /// acknowledgement frontiers.
+    fn local_member_observes(&self, group_id: GroupId) -> bool {
+        self.group_memberships
+            .snapshot()
+            .members(&group_id)
+            .is_some_and(|members| members.is_observer(&self.local_member))
+    }
+
+    /// Resolve the group size and canonical member index of an acknowledgement sender.
+    fn ack_sender_index(
+        &self,
+        group_id: GroupId,
+        sender: &MemberIdentity,
+    ) -> Result<(NonZeroUsize, usize), InboundDeliveryError> {
+        let memberships = self.group_memberships.snapshot();
+        let members = memberships
+            .members(&group_id)
+            .context(inbound::UnknownHostedGroupSnafu { group_id })?;
+        let sender_index =
+            members
+                .member_index(sender)
+                .context(inbound::AckSenderNotInGroupSnafu {
+                    group_id,
+                    sender: sender.clone(),
+                })?;
+        let num_members = NonZeroUsize::new(members.len()).expect("group members are never empty");
+        Ok((num_members, usize::from(sender_index)))
+    }
+
+    fn handle_acknowledged_versions(
+        &mut self,
+        ask: Ask<GroupId, Result<AcknowledgedVersions, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, group_id) = ask.take();
+        self.reply_with_acknowledged_versions(
+            promise,
+            group_id,
+            "acknowledged_versions",
+            |_, acknowledged| acknowledged,
+        )
+    }
+
+    fn handle_sync_health(
+        &mut self,
+        ask: Ask<GroupId, Result<GroupSyncHealth, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, group_id) = ask.take();
+        self.reply_with_acknowledged_versions(
+            promise,
+            group_id,
+            "sync_health",
+            Self::sync_health_from_acknowledgements,
+        )
+    }
+
+    /// Summarise one group's acknowledgements against the locally applied
+    /// versions and reply with `project` applied to the summary.
+    fn reply_with_acknowledged_versions<T>(
+        &mut self,
+        promise: KPromise<Result<T, ApiError>>,
+        group_id: GroupId,
+        operation: &'static str,
+        project: fn(&Self, AcknowledgedVersions) -> T,
+    ) -> HandlerResult
+    where
+        T: Send + 'static,
+    {
+        let local_member_index = self
+            .group_memberships
+            .snapshot()
+            .members(&group_id)
+            .and_then(|members| members.member_index(&self.local_member));
+        let Some(local_member_index) = local_member_index else {
+            let reply = acknowledged_versions::UnknownGroupSnafu { group_id }
+                .fail::<T>()
+                .boxed()
+                .context(ApiExternalSnafu);
+            self.reply_api(promise, operation, reply);
+            return Handled::OK;
+        };
+        Handled::block_on(self, async move |async_self| {
+            let store = async_self.store.clone();
+            let local_versions = async move {
+                let mut transaction = store
+                    .begin_read_transaction()
+                    .await
+                    .context(acknowledged_versions::StoreAccessSnafu)?;
+                let group = transaction
+                    .load_replication_group(&group_id)
+                    .await
+                    .context(acknowledged_versions::StoreAccessSnafu)?
+                    .context(acknowledged_versions::UnknownGroupSnafu { group_id })?;
+                transaction
+                    .release()
+                    .await
+                    .context(acknowledged_versions::StoreAccessSnafu)?;
+                Ok::<_, AcknowledgedVersionsError>(group.version_vector)
+            }
+            .await;
+            let reply = local_versions
+                .map(|local_versions| {
+                    let acknowledged = async_self.acknowledgements.acknowledged_versions(
+                        group_id,
+                        usize::from(local_member_index),
+                        local_versions,
+                    );
+                    project(&async_self, acknowledged)
+                })
+                .boxed()
+                .context(ApiExternalSnafu);
+            async_self.reply_api(promise, operation, reply);
+            Handled::OK
+        })
+    }
+
+    /// Pair each member's acknowledged versions with its last reported liveness.
+    fn sync_health_from_acknowledgements(
+        &self,
+        acknowledged: AcknowledgedVersions,
+    ) -> GroupSyncHealth {
+        let AcknowledgedVersions {
+            group_id,
+            members: acknowledged_members,
+            stable_versions: _,
+        } = acknowledged;
+        let memberships = self.group_memberships.snapshot();
+        let member_identities = memberships
+            .members(&group_id)
+            .map(|members| members.ordered_members())
+            .unwrap_or_default();
+        let local_versions = member_identities
+            .iter()
+            .position(|member| member == &self.local_member)
+            .map(|local_index| acknowledged_members[local_index].clone());
+        let members = member_identities
+            .into_iter()
+            .zip(acknowledged_members)
+            .map(|(member, acknowledged_versions)| {
+                let liveness = if member == self.local_member {
+                    Some(PeerLiveness::Alive)
+                } else {
+                    self.peer_liveness.get(&member).copied()
+                };
+                let up_to_date = local_versions
+                    .as_ref()
+                    .is_none_or(|local_versions| acknowledged_versions >= *local_versions);
+                MemberSyncHealth {
+                    member,
+                    liveness,
+                    acknowledged_versions,
+                    up_to_date,
+                }
+            })
+            .collect();
+        GroupSyncHealth { group_id, members }
+    }
+
+    /// Record the latest liveness transition reported for one peer.
+    ///
+    /// A peer that is heard from for the first time or after being down counts as reconnected
+    /// for sync scheduling, outbox re-sends, and workspace events.
+    fn handle_peer_liveness(&mut self, update: PeerLivenessUpdate) -> HandlerResult {
+        let previous = self
+            .peer_liveness
+            .insert(update.peer.clone(), update.liveness);
+        if update.liveness == PeerLiveness::Alive && previous.is_none_or(PeerLiveness::is_down) {
+            self.sync_scheduler.record_peer_reachable(&update.peer);
+            self.resend_outbox_to(&update.peer);
+            self.workspace_events
+                .emit(WorkspaceEvent::PeerConnected { peer: update.peer });
+        }
+        Handled::OK
+    }
+
+    fn handle_set_power_hint(
+        &mut self,
+        ask: Ask<PowerHint, Result<(), ApiError>>,
+    ) -> HandlerResult {
+        let (promise, hint) = ask.take();
+        self.sync_scheduler.set_power_hint(hint);
+        self.reply_api(promise, "set_power_hint", Ok(()));
+        Handled::OK
+    }
+
+    fn handle_network_changed(&mut self, ask: Ask<(), Result<(), ApiError>>) -> HandlerResult {
+        let (promise, ()) = ask.take();
+        self.sync_scheduler.record_network_change();
+        self.reply_api(promise, "notify_network_changed", Ok(()));
+        Handled::OK
+    }
+
+    fn handle_compression_counters(
+        &mut self,
+        ask: Ask<(), Result<CompressionCounters, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, ()) = ask.take();
+        let counters = self.compression.metrics().counters();
+        self.reply_api(promise, "compression_counters", Ok(counters));
+        Handled::OK
+    }
+
+    fn handle_quarantined_deliveries(
+        &mut self,
+        ask: Ask<(), Result<Vec<QuarantinedDelivery>, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, ()) = ask.take();
+        let deliveries = self.quarantine.deliveries();
+        self.reply_api(promise, "quarantined_deliveries", Ok(deliveries));
+        Handled::OK
+    }
+
+    fn handle_retry_quarantined_delivery(
+        &mut self,
+        ask: Ask<QuarantineId, Result<(), ApiError>>,
+    ) -> HandlerResult {
+        let (promise, id) = ask.take();
+        let Some(deliver) = self.quarantine.take(id) else {
+            self.reply_api(
+                promise,
+                "retry_quarantined_delivery",
+                Err(ApiError::QuarantinedDeliveryNotFound { id }),
+            );
+            return Handled::OK;
+        };
+        info!(
+            self.log(),
+            "retrying quarantined delivery {id} of group broadcast message {} for group {}",
+            deliver.envelope.header.message_id,
+            deliver.envelope.header.group_id
+        );
+        self.reply_api(promise, "retry_quarantined_delivery", Ok(()));
+        self.deliver_group_broadcast(&deliver)
+    }
+
+    /// Record one local or live remote change for change-triggered sync sessions.
+    fn record_sync_change(&mut self, group_id: GroupId) {
+        let now = self.ctx.system().now();
+        self.sync_scheduler.record_change(group_id, now);
+    }
+
+    /// Start every sync session the scheduler reports as due.
+    fn run_sync_scheduler(&mut self) -> HandlerResult {
+        let now = self.ctx.system().now();
+        let memberships = self.group_memberships.snapshot();
+        let peer_liveness = &self.peer_liveness;
+        let sessions =
+            self.sync_scheduler
+                .poll(now, memberships.as_ref(), &self.local_member, |peer| {
+                    Self::is_peer_reachable(peer_liveness, peer)
+                });
+        for session in &sessions {
+            self.start_sync_session(session);
+        }
+        Handled::OK
+    }
+
+    /// Start one sync session as a summary request to its peer.
+    ///
+    /// The summary reply is observed like any other and feeds missing ranges into catch-up.
+    fn start_sync_session(&mut self, session: &SyncSession) {
+        debug!(
+            self.log(),
+            "starting sync session for group {} with {}", session.group_id, session.peer
+        );
+        let message = RuntimeMessage::SummaryRequest(SummaryRequestMessage {
+            group_id: session.group_id,
+            correlation_id: Uuid::new_v4(),
+            accepts_compression: self.compression.local_offer(),
+            accepts_encodings: self.encodings.local_offer(),
+        });
+        self.submit_reliable_runtime_message(session.peer.clone(), &message);
+    }
+
+    /// Whether sync sessions with `peer` are worth starting.
+    ///
+    /// Peers the failure detector has not reported on yet are tried optimistically.
+    fn is_peer_reachable(peer_liveness: &TrieMap<PeerLiveness>, peer: &MemberIdentity) -> bool {
+        !peer_liveness
+            .get(peer)
+            .is_some_and(|liveness| liveness.is_down())
+    }
+
+    /// Start sessions with every reachable peer of every hosted group and track them as one step.
+    ///
+    /// The step replies once all sessions are answered and caught up, or once its budget is spent.
+    fn handle_run_sync_step(
+        &mut self,
+        ask: Ask<Duration, Result<SyncStepProgress, ApiError>>,
+    ) -> HandlerResult {
+        let (promise, budget) = ask.take();
+        if self.sync_step.is_some() {
+            self.reply_api(promise, "run_sync_step", Err(ApiError::SyncStepInProgress));
+            return Handled::OK;
+        }
+        let now = self.ctx.system().now();
+        let memberships = self.group_memberships.snapshot();
+        let peer_liveness = &self.peer_liveness;
+        let sessions =
+            self.sync_scheduler
+                .poll_all(now, memberships.as_ref(), &self.local_member, |peer| {
+                    Self::is_peer_reachable(peer_liveness, peer)
+                });
+        for session in &sessions {
+            self.start_sync_session(session);
+        }
+        let step = SyncStep::start(now, budget, &sessions);
+        if step.all_answered() {
+            self.reply_api(promise, "run_sync_step", Ok(step.progress(Vec::new())));
+            return Handled::OK;
+        }
+        let check_timer = self.schedule_periodic(
+            self.sync_step_check_interval,
+            self.sync_step_check_interval,
+            |component, _timer| component.check_sync_step(),
+        );
+        self.sync_step = Some(ActiveSyncStep {
+            step,
+            promise,
+            check_timer,
+            checking: false,
+        });
+        Handled::OK
+    }
+
+    /// Check whether the running sync step has caught up or spent its budget.
+    ///
+    /// Both need the applied versions of the groups peers reported on, so the
+    /// decision is made in [`Self::finish_sync_step`] once the store has been read.
+    fn check_sync_step(&mut self) -> HandlerResult {
+        let now = self.ctx.system().now();
+        let Some(active) = self.sync_step.as_mut() else {
+            return Handled::OK;
+        };
+        let expired = active.step.is_expired(now);
+        if active.checking || !(expired || active.step.all_answered()) {
+            return Handled::OK;
+        }
+        active.checking = true;
+        let targets = active.step.targets().clone();
+        let store = self.store.clone();
+        self.spawn_local(move |mut async_self| async move {
+            let groups_behind = Self::load_groups_behind(store, targets).await;
+            async_self.finish_sync_step(expired, groups_behind);
+            Handled::OK
+        });
+        Handled::OK
+    }
+
+    /// Reply to the running sync step if it is done, or keep it running until the next check.
+    fn finish_sync_step(
+        &mut self,
+        expired: bool,
+        groups_behind: Result<Vec<GroupId>, SyncStepError>,
+    ) {
+        let Some(active) = self.sync_step.as_mut() else {
+            return;
+        };
+        active.checking = false;
+        let reply = match groups_behind {
+            Ok(groups_behind) if expired || groups_behind.is_empty() => {
+                Ok(active.step.progress(groups_behind))
+            }
+            Ok(_) => return,
+            Err(error) => Err(error).boxed().context(ApiExternalSnafu),
+        };
+        let active = self.sync_step.take().expect("sync step checked above");
+        self.cancel_timer(active.check_timer);
+        if reply.as_ref().is_ok_and(|progress| progress.completed) {
+            let groups = active.step.targets().keys().copied().sorted().collect();
+            self.workspace_events
+                .emit(WorkspaceEvent::SyncCompleted { groups });
+        }
+        self.reply_api(active.promise, "run_sync_step", reply);
+    }
+
+    /// Return the groups whose applied versions do not cover the versions in `targets` yet.
+    async fn load_groups_behind(
+        store: Arc<dyn ReplicationStore>,
+        targets: HashMap<GroupId, VersionVector>,
+    ) -> Result<Vec<GroupId>, SyncStepError> {
+        let mut transaction = store
+            .begin_read_transaction()
+            .await
+            .context(run_sync_step::StoreAccessSnafu)?;
+        let mut groups_behind = Vec::new();
+        for (group_id, target) in targets {
+            let Some(group) = transaction
+                .load_replication_group(&group_id)
+                .await
+                .context(run_sync_step::StoreAccessSnafu)?
+            else {
+                // The group was dropped meanwhile, so there is nothing left to catch up.
+                continue;
+            };
+            let target = group.lifecycle.bound_versions(&target);
+            if !group
+                .version_vector
+                .missing_version_ranges_to(&target)
+                .is_empty()
+            {
+                groups_behind.push(group_id);
+            }
+        }
+        Ok(groups_behind)
+    }
+
     fn handle_create_group(
         &mut self,
         ask: Ask<CreateGroupRequest, Result<GroupId, ApiError>>,
@@ -3350,9 +4909,7 @@ impl ReplicationRuntimeComponent {
                     return Handled::OK;
                 }
             };
-            let member_keys = match GroupMemberKeys::from_ordered_member_keys(
-                prepared_setup.group_setup.ordered_member_key_ids(),
-            ) {
+            let member_keys = match prepared_setup.group_setup.group_member_keys() {
                 Ok(member_keys) => member_keys,
                 Err(source) => {
                     let reply = Err(CreateGroupError::InvalidMembers { source })
@@ -3369,7 +4926,8 @@ impl ReplicationRuntimeComponent {
                 InitialSnapshot::Empty,
                 None,
                 None,
-            );
+            )
+            .with_proposed_observers(prepared_setup.group_setup.observers().to_vec());
             let invitation_message = GroupInvitationMessage::try_new(
                 invitation,
                 Arc::clone(&prepared_setup.group_setup),
@@ -3426,9 +4984,10 @@ impl ReplicationRuntimeComponent {
                                 async_self.activate_pending_group_record(activation).await;
                             match activation_result {
                                 Ok(outcome) => {
-                                    async_self.submit_membership_migration_messages(&dispatch);
+                                    async_self.submit_membership_migration_messages(dispatch);
                                     let notification_result = notify_listener_data_changes(
                                         async_self.listener.clone(),
+                                        async_self.workspace_events.clone(),
                                         smallvec![ListenerDataChanges {
                                             read_token: outcome.read_token,
                                             row_changes: outcome.row_changes,
@@ -3545,8 +5104,12 @@ impl ReplicationRuntimeComponent {
                 .await
             {
                 Ok(outcome) => {
-                    notify_listener_batches(async_self.listener.clone(), outcome.event_batches)
-                        .await
+                    notify_listener_batches(
+                        async_self.listener.clone(),
+                        async_self.workspace_events.clone(),
+                        outcome.event_batches,
+                    )
+                    .await
                 }
                 Err(error) => Err(error),
             };
@@ -3575,6 +5138,53 @@ impl ComponentLifecycle for ReplicationRuntimeComponent {
     fn on_start(&mut self) -> HandlerResult {
         self.max_inline_bootstrap_public_key_bundles =
             self.read_max_inline_bootstrap_public_key_bundles();
+        let sync_scheduler_tick_interval = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::SYNC_SCHEDULER_TICK_INTERVAL);
+        self.sync_scheduler_timer = Some(self.schedule_periodic(
+            sync_scheduler_tick_interval,
+            sync_scheduler_tick_interval,
+            |component, _timer| component.run_sync_scheduler(),
+        ));
+        self.sync_step_check_interval = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::SYNC_STEP_CHECK_INTERVAL);
+        self.conflict_heavy_merge_threshold = self.ctx.config().read_or_default_warn(
+            self.log(),
+            &config_keys::WORKSPACE_EVENTS_CONFLICT_HEAVY_MERGE_THRESHOLD,
+        );
+        let quarantine_capacity = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::QUARANTINE_CAPACITY);
+        self.quarantine = DeliveryQuarantine::new(quarantine_capacity);
+        self.blob_fetch_timeout = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::BLOBS_FETCH_TIMEOUT);
+        self.max_inline_snapshot_bytes = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::SNAPSHOTS_MAX_INLINE_BYTES);
+        self.snapshot_fetch_timeout = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::SNAPSHOTS_FETCH_TIMEOUT);
+        self.snapshot_fetch_attempts = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::SNAPSHOTS_FETCH_ATTEMPTS);
+        self.snapshot_serve_retention = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::SNAPSHOTS_SERVE_RETENTION);
+        let outbox_directory: String = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::OUTBOX_DIRECTORY);
+        self.outbox_directory = option_when!(!outbox_directory.is_empty(), outbox_directory.into());
         Handled::block_on(self, async move |mut async_self| {
             let hydrated_memberships = async_self
                 .load_hydrated_runtime_memberships()
@@ -3599,10 +5209,28 @@ impl ComponentLifecycle for ReplicationRuntimeComponent {
     }
 
     fn on_stop(&mut self) -> HandlerResult {
+        if let Some(timer) = self.sync_scheduler_timer.take() {
+            self.cancel_timer(timer);
+        }
+        if let Some(active) = self.sync_step.take() {
+            self.cancel_timer(active.check_timer);
+        }
+        self.cancel_blob_fetches();
+        self.cancel_snapshot_streams();
+        self.close_outboxes();
         Handled::OK
     }
 
     fn on_kill(&mut self) -> HandlerResult {
+        if let Some(timer) = self.sync_scheduler_timer.take() {
+            self.cancel_timer(timer);
+        }
+        if let Some(active) = self.sync_step.take() {
+            self.cancel_timer(active.check_timer);
+        }
+        self.cancel_blob_fetches();
+        self.cancel_snapshot_streams();
+        self.close_outboxes();
         Handled::OK
     }
 }
@@ -3620,16 +5248,16 @@ impl Require<ReliableDeliveryPort> for ReplicationRuntimeComponent {
     }
 }
 
+impl Require<PeerLivenessPort> for ReplicationRuntimeComponent {
+    fn handle(&mut self, indication: PeerLivenessUpdate) -> HandlerResult {
+        self.handle_peer_liveness(indication)
+    }
+}
+
 impl Require<GroupBroadcastPort> for ReplicationRuntimeComponent {
     fn handle(&mut self, indication: GroupBroadcastPortIndication) -> HandlerResult {
         let GroupBroadcastPortIndication::Deliver(deliver) = indication;
-        match self.handle_group_delivery(&deliver) {
-            Ok(handled) => handled,
-            Err(failure) => {
-                let action = self.record_inbound_failure(&failure);
-                handled_after_inbound_failure(action, &failure)
-            }
-        }
+        self.deliver_group_broadcast(&deliver)
     }
 }
 
@@ -3649,7 +5277,28 @@ impl Actor for ReplicationRuntimeComponent {
             }
             ReplicationRuntimeMessage::PublishChanges(ask) => self.handle_publish_changes(ask),
             ReplicationRuntimeMessage::SnapshotRows(ask) => self.handle_snapshot_rows(ask),
+            ReplicationRuntimeMessage::SnapshotRowsAt(ask) => self.handle_snapshot_rows_at(ask),
+            ReplicationRuntimeMessage::CreateCheckpoint(ask) => self.handle_create_checkpoint(ask),
+            ReplicationRuntimeMessage::ListCheckpoints(ask) => self.handle_list_checkpoints(ask),
+            ReplicationRuntimeMessage::StoreBlob(ask) => self.handle_store_blob(ask),
+            ReplicationRuntimeMessage::FetchBlob(ask) => self.handle_fetch_blob(ask),
             ReplicationRuntimeMessage::RequestSummary(ask) => self.handle_request_summary(ask),
+            ReplicationRuntimeMessage::AcknowledgedVersions(ask) => {
+                self.handle_acknowledged_versions(ask)
+            }
+            ReplicationRuntimeMessage::SyncHealth(ask) => self.handle_sync_health(ask),
+            ReplicationRuntimeMessage::SetPowerHint(ask) => self.handle_set_power_hint(ask),
+            ReplicationRuntimeMessage::NetworkChanged(ask) => self.handle_network_changed(ask),
+            ReplicationRuntimeMessage::RunSyncStep(ask) => self.handle_run_sync_step(ask),
+            ReplicationRuntimeMessage::CompressionCounters(ask) => {
+                self.handle_compression_counters(ask)
+            }
+            ReplicationRuntimeMessage::QuarantinedDeliveries(ask) => {
+                self.handle_quarantined_deliveries(ask)
+            }
+            ReplicationRuntimeMessage::RetryQuarantinedDelivery(ask) => {
+                self.handle_retry_quarantined_delivery(ask)
+            }
             ReplicationRuntimeMessage::CreateGroup(ask) => self.handle_create_group(ask),
             ReplicationRuntimeMessage::ChangeGroupMembership(ask) => {
                 self.handle_change_group_membership(ask)
@@ -3750,6 +5399,7 @@ mod tests {
             },
             final_versions: final_versions.clone(),
             proposed_members: Vec::new(),
+            proposed_observers: Vec::new(),
             group_schema: GroupSchema::default(),
             initial_snapshot: InitialSnapshot::Empty,
             group_name: None,
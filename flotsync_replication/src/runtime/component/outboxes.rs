@@ -0,0 +1,131 @@
+//! Per-group outboxes that keep local updates until every member has acknowledged them.
+//!
+//! Outboxes are only kept if an outbox directory is configured. Publishing queues each local
+//! update, update and frontier acknowledgements from peers drain the queue, and a member that
+//! reconnects gets every queued update it has not acknowledged broadcast again.
+
+use super::*;
+use crate::runtime::outbox::{Outbox, OutboxError};
+use std::collections::hash_map::Entry;
+
+impl ReplicationRuntimeComponent {
+    /// Return the outbox of `group_id`, opening it on first use.
+    ///
+    /// Returns `Ok(None)` if no outbox directory is configured or the group is not hosted.
+    fn outbox_mut(&mut self, group_id: GroupId) -> Result<Option<&mut Outbox>, OutboxError> {
+        let Some(directory) = &self.outbox_directory else {
+            return Ok(None);
+        };
+        let entry = match self.outboxes.entry(group_id) {
+            Entry::Occupied(entry) => return Ok(Some(entry.into_mut())),
+            Entry::Vacant(entry) => entry,
+        };
+        let memberships = self.group_memberships.snapshot();
+        let Some(members) = memberships.members(&group_id) else {
+            return Ok(None);
+        };
+        let Some(local_member_index) = members.member_index(&self.local_member) else {
+            return Ok(None);
+        };
+        let num_members = NonZeroUsize::new(members.len()).expect("group members are never empty");
+        let outbox = Outbox::open(
+            directory.join(group_id.0.as_hyphenated().to_string()),
+            num_members,
+            u32::from(local_member_index),
+        )?;
+        Ok(Some(entry.insert(outbox)))
+    }
+
+    /// Keep one published local update until every member has acknowledged it.
+    pub(super) fn queue_local_update(&mut self, prepared_publish: &PreparedLocalPublish) {
+        let group_id = prepared_publish.group_id;
+        let update_id = prepared_publish.update_id;
+        let queued = self.outbox_mut(group_id).and_then(|outbox| match outbox {
+            Some(outbox) => outbox.push(update_id, prepared_publish.payload.to_vec()),
+            None => Ok(()),
+        });
+        if let Err(error) = queued {
+            // Catch-up still serves the update from the store, just not proactively.
+            warn!(
+                self.log(),
+                "failed to queue update {update_id} of group {group_id} in its outbox: {error}"
+            );
+        }
+    }
+
+    /// Drop the queued local updates that the member at `member_index` has applied according to
+    /// its acknowledged `versions`.
+    pub(super) fn acknowledge_outbox(
+        &mut self,
+        group_id: GroupId,
+        member_index: usize,
+        versions: &VersionVector,
+    ) {
+        let member_index = u32::try_from(member_index).expect("group member indices fit u32");
+        let acknowledged = self.outbox_mut(group_id).and_then(|outbox| match outbox {
+            Some(outbox) => outbox.acknowledge(member_index, versions),
+            None => Ok(()),
+        });
+        if let Err(error) = acknowledged {
+            warn!(
+                self.log(),
+                "failed to record acknowledgement of member {member_index} in the outbox of group {group_id}: {error}"
+            );
+        }
+    }
+
+    /// Broadcast every queued local update again that the reconnected `peer` has not
+    /// acknowledged yet.
+    ///
+    /// Members that already applied an update drop the repeated copy.
+    pub(super) fn resend_outbox_to(&mut self, peer: &MemberIdentity) {
+        if self.outbox_directory.is_none() {
+            return;
+        }
+        let memberships = self.group_memberships.snapshot();
+        for group_id in memberships.group_ids().copied() {
+            let Some(peer_index) = memberships
+                .members(&group_id)
+                .and_then(|members| members.member_index(peer))
+            else {
+                continue;
+            };
+            let pending = self.outbox_mut(group_id).map(|outbox| {
+                outbox.filter(|outbox| !outbox.is_empty()).map(|outbox| {
+                    let payloads: Vec<bytes::Bytes> = outbox
+                        .pending_for(u32::from(peer_index))
+                        .map(|(_, batch)| bytes::Bytes::copy_from_slice(batch))
+                        .collect();
+                    (payloads, outbox.len())
+                })
+            });
+            let (payloads, queued) = match pending {
+                Ok(Some(pending)) => pending,
+                Ok(None) => continue,
+                Err(error) => {
+                    warn!(
+                        self.log(),
+                        "failed to open the outbox of group {group_id} for reconnected {peer}: {error}"
+                    );
+                    continue;
+                }
+            };
+            if payloads.is_empty() {
+                continue;
+            }
+            debug!(
+                self.log(),
+                "re-sending {} of {queued} queued updates of group {group_id} to reconnected {peer}",
+                payloads.len(),
+            );
+            for payload in payloads {
+                self.submit_group_update_payload(group_id, payload);
+            }
+        }
+    }
+
+    /// Forget every open outbox because the component is shutting down.
+    pub(super) fn close_outboxes(&mut self) {
+        self.outboxes.clear();
+    }
+}
@@ -1,6 +1,9 @@
-//! Store-backed snapshot row streaming for the runtime component.
+//! Store-backed and replayed snapshot row streaming for the runtime component.
 
 use super::*;
+use crate::runtime::in_memory::LocalDataset;
+use flotsync_utils::option_when;
+use std::collections::VecDeque;
 
 /// Snapshot provider backed by one store read transaction.
 ///
@@ -136,3 +139,102 @@ impl BatchProvider for StoreSnapshotRowProvider {
         .boxed()
     }
 }
+
+/// Snapshot provider over dataset rows rebuilt in memory by replaying group history.
+pub(super) struct ReplayedSnapshotRowProvider {
+    /// Group whose historical row state is being streamed.
+    group_id: GroupId,
+    /// Replayed datasets still waiting to be emitted, in dataset order.
+    datasets: VecDeque<ReplayedDatasetRows>,
+    max_rows_per_batch: NonZeroUsize,
+}
+
+/// One replayed dataset together with the keys of the rows not emitted yet.
+struct ReplayedDatasetRows {
+    dataset_id: DatasetId,
+    schema: SchemaSource,
+    dataset: LocalDataset,
+    row_keys: VecDeque<RowKey>,
+}
+
+impl ReplayedSnapshotRowProvider {
+    /// Create a provider for the visible rows of `datasets`.
+    ///
+    /// Requested datasets without any replayed row are omitted.
+    pub(super) fn new(
+        group_id: GroupId,
+        schemas: &HashMap<DatasetId, SchemaSource>,
+        datasets: HashMap<DatasetId, LocalDataset>,
+        max_rows_per_batch: NonZeroUsize,
+    ) -> Self {
+        let mut datasets = datasets
+            .into_iter()
+            .map(|(dataset_id, dataset)| {
+                let schema = schemas
+                    .get(&dataset_id)
+                    .expect("replayed datasets must have loaded schemas")
+                    .clone();
+                let row_keys = dataset.active_row_keys().into();
+                ReplayedDatasetRows {
+                    dataset_id,
+                    schema,
+                    dataset,
+                    row_keys,
+                }
+            })
+            .collect::<Vec<_>>();
+        datasets.sort_by(|left, right| left.dataset_id.cmp(&right.dataset_id));
+        Self {
+            group_id,
+            datasets: datasets.into(),
+            max_rows_per_batch,
+        }
+    }
+
+    /// Move the next rows of the current dataset into `batch`.
+    fn fill_rows(&mut self, batch: &mut SnapshotValueRowBatch) -> Result<(), RowProviderError> {
+        while let Some(replayed) = self.datasets.front_mut() {
+            if replayed.row_keys.is_empty() {
+                self.datasets.pop_front();
+                continue;
+            }
+            let rows = batch.prepare(replayed.schema.clone(), self.max_rows_per_batch.get());
+            let batch_len = replayed.row_keys.len().min(self.max_rows_per_batch.get());
+            for row_key in replayed.row_keys.drain(..batch_len) {
+                let snapshot = replayed
+                    .dataset
+                    .snapshot_row(row_key)
+                    .expect("replayed row keys must refer to replayed rows");
+                let row_id = RowId {
+                    group_id: self.group_id,
+                    dataset_id: replayed.dataset_id.clone(),
+                    row_key,
+                };
+                rows.push_row_read(row_id, false, &snapshot)
+                    .boxed()
+                    .context(ProviderExternalSnafu)?;
+            }
+            return Ok(());
+        }
+        Ok(())
+    }
+}
+
+impl BatchProvider for ReplayedSnapshotRowProvider {
+    type Batch = SnapshotValueRowBatch;
+
+    fn new_batch(&self) -> Self::Batch {
+        SnapshotValueRowBatch::empty()
+    }
+
+    fn fill_batch(
+        &mut self,
+        mut reuse: Self::Batch,
+    ) -> BoxFuture<'_, Result<Option<Self::Batch>, RowProviderError>> {
+        reuse.clear();
+        let filled = self
+            .fill_rows(&mut reuse)
+            .map(|()| option_when!(!reuse.is_empty(), reuse));
+        futures_util::future::ready(filled).boxed()
+    }
+}
@@ -0,0 +1,477 @@
+//! Chunked streaming of initial snapshots that are too large to carry inline.
+//!
+//! A proposer whose migration snapshot exceeds the inline limit announces it as
+//! [`InitialSnapshot::Metadata`] and keeps the chunks in a [`ServedSnapshot`] for the proposed
+//! members. A recipient parks the announcing invitation or proposal in a
+//! [`PendingSnapshotDownload`], fetches the manifest and the chunks from the proposer, and installs
+//! the work with the assembled rows inline. When a request goes unanswered, only the parts still
+//! missing are requested again.
+
+use super::*;
+use crate::{
+    api::InitialSnapshotMetadata,
+    snapshot_transfer::{
+        SnapshotChunk,
+        SnapshotChunkRequest,
+        SnapshotChunkResponse,
+        SnapshotDownload,
+        SnapshotManifest,
+        SnapshotManifestRequest,
+        SnapshotManifestResponse,
+        SnapshotUnavailable,
+    },
+};
+use std::collections::hash_map::Entry;
+
+/// Target group and snapshot id naming one streamed snapshot.
+type SnapshotKey = (GroupId, SnapshotId);
+
+/// Initial rows announced as metadata instead of being carried inline.
+pub(super) struct StreamedInitialSnapshot {
+    pub(super) metadata: InitialSnapshotMetadata,
+    pub(super) source: SnapshotSource,
+}
+
+/// Streamed snapshot the local member keeps available to the recipients of its migration.
+pub(super) struct ServedSnapshot {
+    source: SnapshotSource,
+    /// Members the snapshot was announced to. Requests from anyone else are dropped.
+    recipients: Vec<MemberIdentity>,
+    expiry_timer: ScheduledTimer,
+}
+
+/// Invitation or proposal waiting for its announced initial snapshot.
+pub(super) struct PendingSnapshotDownload {
+    /// Member that announced the snapshot and serves its chunks.
+    peer: MemberIdentity,
+    context: InboundDeliveryContext,
+    /// Confirms the announcing delivery once the work is installed.
+    processed: KClaimablePromise<()>,
+    record: PendingGroupDecisionRecord,
+    group_setup: Arc<GroupSetupMessage>,
+    /// `None` until the peer sent the manifest.
+    download: Option<SnapshotDownload>,
+    /// Requests sent since the peer last made progress.
+    attempts: usize,
+    timeout_timer: ScheduledTimer,
+}
+
+impl PendingSnapshotDownload {
+    /// Build the request for whatever the download is still missing.
+    fn next_request(&self, (group_id, snapshot_id): SnapshotKey) -> RuntimeMessage {
+        match &self.download {
+            None => RuntimeMessage::SnapshotManifestRequest(SnapshotManifestRequest {
+                group_id,
+                snapshot_id,
+            }),
+            Some(download) => RuntimeMessage::SnapshotChunkRequest(download.next_request()),
+        }
+    }
+}
+
+impl ReplicationRuntimeComponent {
+    /// Split inline initial rows into a streamed snapshot if they exceed the inline limit.
+    pub(super) fn streamed_initial_snapshot(
+        &self,
+        group_id: GroupId,
+        snapshot_ref: SnapshotRef,
+        initial_snapshot: &InitialSnapshot,
+    ) -> Option<StreamedInitialSnapshot> {
+        let InitialSnapshot::Inline(rows) = initial_snapshot else {
+            return None;
+        };
+        let source = SnapshotSource::split_default(group_id, &snapshot_ref, rows);
+        let manifest = source.manifest();
+        option_when!(
+            manifest.total_bytes() > self.max_inline_snapshot_bytes as u64,
+            StreamedInitialSnapshot {
+                metadata: InitialSnapshotMetadata {
+                    primary_ref: snapshot_ref,
+                    equivalent_refs: SmallVec::new(),
+                    record_count: Some(manifest.total_rows()),
+                },
+                source,
+            }
+        )
+    }
+
+    /// Keep `source` available to `recipients` for the configured retention.
+    pub(super) fn serve_initial_snapshot(
+        &mut self,
+        source: SnapshotSource,
+        recipients: Vec<MemberIdentity>,
+    ) {
+        let key = (source.manifest().group_id, source.snapshot_id());
+        let expiry_timer = self
+            .schedule_once(self.snapshot_serve_retention, move |component, timer| {
+                component.handle_served_snapshot_expiry(key, &timer)
+            });
+        let served = ServedSnapshot {
+            source,
+            recipients,
+            expiry_timer,
+        };
+        if let Some(replaced) = self.served_snapshots.insert(key, served) {
+            self.cancel_timer(replaced.expiry_timer);
+        }
+    }
+
+    fn handle_served_snapshot_expiry(
+        &mut self,
+        key: SnapshotKey,
+        expected_timer: &ScheduledTimer,
+    ) -> HandlerResult {
+        if let Entry::Occupied(entry) = self.served_snapshots.entry(key)
+            && &entry.get().expiry_timer == expected_timer
+        {
+            entry.remove();
+        }
+        Handled::OK
+    }
+
+    /// Look up a served snapshot on behalf of `requester`.
+    ///
+    /// Returns `Ok(None)` if the snapshot is not served (anymore).
+    fn served_snapshot_for(
+        &self,
+        key: SnapshotKey,
+        requester: &MemberIdentity,
+    ) -> Result<Option<&SnapshotSource>, InboundDeliveryError> {
+        let Some(served) = self.served_snapshots.get(&key) else {
+            return Ok(None);
+        };
+        let (group_id, snapshot_id) = key;
+        ensure!(
+            served.recipients.contains(requester),
+            inbound::SnapshotRequesterNotRecipientSnafu {
+                group_id,
+                snapshot_id,
+                sender: requester.clone(),
+            }
+        );
+        Ok(Some(&served.source))
+    }
+
+    /// Answer a recipient's request for the manifest of a served snapshot.
+    pub(super) fn handle_snapshot_manifest_request(
+        &mut self,
+        peer: &MemberIdentity,
+        request: &SnapshotManifestRequest,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        let key = (request.group_id, request.snapshot_id);
+        let response = match self.served_snapshot_for(key, peer)? {
+            Some(source) => source.respond_manifest(request),
+            None => SnapshotManifestResponse::Unavailable(SnapshotUnavailable {
+                group_id: request.group_id,
+                snapshot_id: request.snapshot_id,
+            }),
+        };
+        let message = match response {
+            SnapshotManifestResponse::Manifest(manifest) => {
+                RuntimeMessage::SnapshotManifest(manifest)
+            }
+            SnapshotManifestResponse::Unavailable(unavailable) => {
+                RuntimeMessage::SnapshotUnavailable(unavailable)
+            }
+        };
+        self.submit_reliable_runtime_message(peer.clone(), &message);
+        Ok(Handled::OK)
+    }
+
+    /// Answer a recipient's request for chunks of a served snapshot.
+    pub(super) fn handle_snapshot_chunk_request(
+        &mut self,
+        peer: &MemberIdentity,
+        request: &SnapshotChunkRequest,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        let key = (request.group_id, request.snapshot_id);
+        let compression = self.compression.for_peer(peer);
+        let response = match self.served_snapshot_for(key, peer)? {
+            Some(source) => {
+                source.respond_compressed(request, &compression, self.compression.metrics())
+            }
+            None => SnapshotChunkResponse::Unavailable(SnapshotUnavailable {
+                group_id: request.group_id,
+                snapshot_id: request.snapshot_id,
+            }),
+        };
+        match response {
+            SnapshotChunkResponse::Chunks(chunks) => {
+                for chunk in chunks {
+                    let group_id = chunk.group_id;
+                    let payload = RuntimeMessage::SnapshotChunk(chunk).encode_proto_to_bytes();
+                    self.submit_reliable_runtime_payload(
+                        peer.clone(),
+                        group_id,
+                        payload,
+                        TrafficClass::Snapshot,
+                    );
+                }
+            }
+            SnapshotChunkResponse::Unavailable(unavailable) => {
+                self.submit_reliable_runtime_message(
+                    peer.clone(),
+                    &RuntimeMessage::SnapshotUnavailable(unavailable),
+                );
+            }
+        }
+        Ok(Handled::OK)
+    }
+
+    /// Park an invitation or proposal that announces its initial snapshot and start fetching it.
+    ///
+    /// Work that cannot be installed anyway is passed on unchanged, so that it is rejected as
+    /// usual instead of being fetched first.
+    pub(super) fn handle_announced_pending_group(
+        &mut self,
+        context: InboundDeliveryContext,
+        processed: KClaimablePromise<()>,
+        sender: MemberIdentity,
+        record: PendingGroupDecisionRecord,
+        group_setup: Arc<GroupSetupMessage>,
+        metadata: &InitialSnapshotMetadata,
+    ) -> HandlerResult {
+        let proposed_members = record.proposed_members();
+        if proposed_members.first() != Some(&sender)
+            || !proposed_members.contains(&self.local_member)
+        {
+            return self.install_inbound_pending_group(
+                context,
+                processed,
+                sender,
+                record,
+                group_setup,
+            );
+        }
+        let key = (record.group_id(), SnapshotId::of(&metadata.primary_ref));
+        if self.snapshot_downloads.contains_key(&key) {
+            // The fetch already running installs the work. Dropping `processed` leaves this
+            // copy unconfirmed, so it is only delivered again if that fetch fails.
+            debug!(
+                self.log(),
+                "snapshot {} for group {} is already being fetched from {sender}", key.1, key.0
+            );
+            return Handled::OK;
+        }
+        let timeout_timer = self.schedule_snapshot_fetch_timeout(key);
+        let pending = PendingSnapshotDownload {
+            peer: sender.clone(),
+            context,
+            processed,
+            record,
+            group_setup,
+            download: None,
+            attempts: 1,
+            timeout_timer,
+        };
+        let request = pending.next_request(key);
+        self.snapshot_downloads.insert(key, pending);
+        self.submit_reliable_runtime_message(sender, &request);
+        Handled::OK
+    }
+
+    fn schedule_snapshot_fetch_timeout(&mut self, key: SnapshotKey) -> ScheduledTimer {
+        self.schedule_once(self.snapshot_fetch_timeout, move |component, timer| {
+            component.handle_snapshot_fetch_timeout(key, &timer)
+        })
+    }
+
+    /// Ask the serving peer again for the parts of a snapshot that are still missing.
+    fn request_missing_snapshot_parts(&mut self, key: SnapshotKey) {
+        let Some(pending) = self.snapshot_downloads.get(&key) else {
+            return;
+        };
+        let peer = pending.peer.clone();
+        let request = pending.next_request(key);
+        let previous_timer = pending.timeout_timer.clone();
+        self.cancel_timer(previous_timer);
+        let timeout_timer = self.schedule_snapshot_fetch_timeout(key);
+        if let Some(pending) = self.snapshot_downloads.get_mut(&key) {
+            pending.timeout_timer = timeout_timer;
+        }
+        self.submit_reliable_runtime_message(peer, &request);
+    }
+
+    fn handle_snapshot_fetch_timeout(
+        &mut self,
+        key: SnapshotKey,
+        expected_timer: &ScheduledTimer,
+    ) -> HandlerResult {
+        let Entry::Occupied(mut entry) = self.snapshot_downloads.entry(key) else {
+            return Handled::OK;
+        };
+        if &entry.get().timeout_timer != expected_timer {
+            return Handled::OK;
+        }
+        let attempts = entry.get().attempts;
+        if attempts >= self.snapshot_fetch_attempts {
+            let pending = entry.remove();
+            let (group_id, snapshot_id) = key;
+            let error = InboundDeliveryError::SnapshotFetchTimedOut {
+                group_id,
+                snapshot_id,
+                peer: pending.peer.clone(),
+                attempts,
+            };
+            self.fail_snapshot_download(pending, error);
+            return Handled::OK;
+        }
+        entry.get_mut().attempts += 1;
+        self.request_missing_snapshot_parts(key);
+        Handled::OK
+    }
+
+    /// Start fetching chunks once the serving peer sent the manifest.
+    pub(super) fn handle_snapshot_manifest(
+        &mut self,
+        peer: &MemberIdentity,
+        manifest: SnapshotManifest,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        let key = (manifest.group_id, manifest.snapshot_id);
+        let Some(pending) = self.snapshot_downloads.get_mut(&key) else {
+            debug!(
+                self.log(),
+                "ignoring manifest of snapshot {} from {peer} without a running fetch", key.1
+            );
+            return Ok(Handled::OK);
+        };
+        if pending.peer != *peer || pending.download.is_some() {
+            return Ok(Handled::OK);
+        }
+        let download =
+            SnapshotDownload::new(manifest).with_metrics(self.compression.metrics().clone());
+        let complete = download.is_complete();
+        pending.download = Some(download);
+        pending.attempts = 1;
+        if complete {
+            return Ok(self.finish_snapshot_download(key));
+        }
+        self.request_missing_snapshot_parts(key);
+        Ok(Handled::OK)
+    }
+
+    /// Add one received chunk to its download and install the parked work once it is complete.
+    pub(super) fn handle_snapshot_chunk(
+        &mut self,
+        peer: &MemberIdentity,
+        chunk: SnapshotChunk,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        let key = (chunk.group_id, chunk.snapshot_id);
+        let Entry::Occupied(mut entry) = self.snapshot_downloads.entry(key) else {
+            debug!(
+                self.log(),
+                "ignoring chunk of snapshot {} from {peer} without a running fetch", key.1
+            );
+            return Ok(Handled::OK);
+        };
+        let pending = entry.get_mut();
+        let Some(download) = pending.download.as_mut() else {
+            return Ok(Handled::OK);
+        };
+        if pending.peer != *peer {
+            return Ok(Handled::OK);
+        }
+        let accepted = download
+            .accept(chunk)
+            .with_context(|_| inbound::InvalidSnapshotTransferSnafu {
+                peer: peer.clone(),
+                snapshot_id: key.1,
+            });
+        if let Err(error) = accepted {
+            let pending = entry.remove();
+            self.cancel_timer(pending.timeout_timer.clone());
+            self.fail_snapshot_download(pending, error);
+            return Ok(Handled::OK);
+        }
+        pending.attempts = 1;
+        if !download.is_complete() {
+            return Ok(Handled::OK);
+        }
+        Ok(self.finish_snapshot_download(key))
+    }
+
+    /// Fail the fetch of a snapshot the serving peer no longer holds.
+    pub(super) fn handle_snapshot_unavailable(
+        &mut self,
+        peer: &MemberIdentity,
+        message: SnapshotUnavailable,
+    ) -> Result<HandlerResult, InboundDeliveryError> {
+        let key = (message.group_id, message.snapshot_id);
+        let Entry::Occupied(entry) = self.snapshot_downloads.entry(key) else {
+            return Ok(Handled::OK);
+        };
+        if entry.get().peer != *peer {
+            return Ok(Handled::OK);
+        }
+        let pending = entry.remove();
+        self.cancel_timer(pending.timeout_timer.clone());
+        let error = InboundDeliveryError::SnapshotUnavailable {
+            group_id: message.group_id,
+            snapshot_id: message.snapshot_id,
+            peer: peer.clone(),
+        };
+        self.fail_snapshot_download(pending, error);
+        Ok(Handled::OK)
+    }
+
+    /// Assemble a complete download and install its parked work with the rows inline.
+    fn finish_snapshot_download(&mut self, key: SnapshotKey) -> HandlerResult {
+        let Some(mut pending) = self.snapshot_downloads.remove(&key) else {
+            return Handled::OK;
+        };
+        self.cancel_timer(pending.timeout_timer.clone());
+        let download = pending
+            .download
+            .take()
+            .expect("Only downloads with a manifest can be complete.");
+        let finished = download
+            .finish(pending.record.group_schema())
+            .with_context(|_| inbound::InvalidSnapshotTransferSnafu {
+                peer: pending.peer.clone(),
+                snapshot_id: key.1,
+            });
+        match finished {
+            Ok(rows) => {
+                pending
+                    .record
+                    .set_initial_snapshot(InitialSnapshot::Inline(rows));
+                self.install_inbound_pending_group(
+                    pending.context,
+                    pending.processed,
+                    pending.peer,
+                    pending.record,
+                    pending.group_setup,
+                )
+            }
+            Err(error) => {
+                self.fail_snapshot_download(pending, error);
+                Handled::OK
+            }
+        }
+    }
+
+    /// Record why a parked invitation or proposal could not get its snapshot.
+    ///
+    /// The announcing delivery stays unconfirmed, so its sender delivers it again later.
+    fn fail_snapshot_download(
+        &mut self,
+        pending: PendingSnapshotDownload,
+        error: InboundDeliveryError,
+    ) {
+        let failure = InboundDeliveryFailure::new(pending.context, error);
+        let action = self.record_inbound_failure(&failure);
+        panic_if_fatal_inbound_failure(action, &failure);
+    }
+
+    /// Stop serving and fetching snapshots because the component is shutting down.
+    pub(super) fn cancel_snapshot_streams(&mut self) {
+        let served = std::mem::take(&mut self.served_snapshots);
+        for served in served.into_values() {
+            self.cancel_timer(served.expiry_timer);
+        }
+        let downloads = std::mem::take(&mut self.snapshot_downloads);
+        for pending in downloads.into_values() {
+            self.cancel_timer(pending.timeout_timer);
+        }
+    }
+}
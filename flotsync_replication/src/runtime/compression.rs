@@ -0,0 +1,101 @@
+//! Runtime-wide compression state shared by the components that send and receive payloads.
+
+use crate::delivery::compression::{
+    CompressionMetrics,
+    CompressionOffer,
+    CompressionPolicy,
+    ConnectionCompression,
+    LinkClass,
+};
+use arc_swap::ArcSwap;
+use flotsync_core::{GroupId, MemberIdentity, member::TrieMap, membership::GroupMemberships};
+use std::sync::Arc;
+
+/// Local compression policy, shared counters, and the offers peers advertised in summaries.
+///
+/// Clones share the same counters and peer offers.
+#[derive(Clone, Debug)]
+pub(super) struct SharedRuntimeCompression {
+    policy: CompressionPolicy,
+    metrics: CompressionMetrics,
+    /// Latest offer each peer advertised; peers not listed only accept uncompressed payloads.
+    peer_offers: Arc<ArcSwap<TrieMap<CompressionOffer>>>,
+}
+
+impl SharedRuntimeCompression {
+    pub(super) fn new(policy: CompressionPolicy) -> Self {
+        Self {
+            policy,
+            metrics: CompressionMetrics::new(),
+            peer_offers: Arc::new(ArcSwap::from_pointee(TrieMap::new())),
+        }
+    }
+
+    /// Algorithms the local replica accepts, as advertised to peers.
+    pub(super) fn local_offer(&self) -> CompressionOffer {
+        self.policy.accepts
+    }
+
+    pub(super) fn metrics(&self) -> &CompressionMetrics {
+        &self.metrics
+    }
+
+    /// Remember the offer `peer` advertised in its latest summary exchange.
+    pub(super) fn record_peer_offer(&self, peer: &MemberIdentity, offer: CompressionOffer) {
+        if self.peer_offers.load().get(peer) == Some(&offer) {
+            return;
+        }
+        self.peer_offers.rcu(|offers| {
+            let mut offers = TrieMap::clone(offers);
+            offers.insert(peer.clone(), offer);
+            offers
+        });
+    }
+
+    /// Negotiate the compression for one broadcast from `local_member` to the rest of `group_id`.
+    ///
+    /// Broadcasts are sealed once for every recipient, so only algorithms all remote members
+    /// offered are eligible, and the policy's [`LinkClass::Direct`] codec is preferred.
+    pub(super) fn for_group_broadcast(
+        &self,
+        memberships: &GroupMemberships,
+        group_id: &GroupId,
+        local_member: &MemberIdentity,
+    ) -> ConnectionCompression {
+        let Some(members) = memberships.members(group_id) else {
+            return ConnectionCompression::disabled();
+        };
+        let peer_offers = self.peer_offers.load();
+        let offer = members
+            .iter()
+            .filter(|member| member != local_member)
+            .map(|member| {
+                peer_offers
+                    .get(&member)
+                    .copied()
+                    .unwrap_or(CompressionOffer::NONE)
+            })
+            .fold(
+                CompressionOffer::supported(),
+                CompressionOffer::intersection,
+            );
+        self.policy.negotiate(LinkClass::Direct, offer)
+    }
+
+    /// Negotiate the compression for one reliable message from the local replica to `peer`.
+    pub(super) fn for_peer(&self, peer: &MemberIdentity) -> ConnectionCompression {
+        let offer = self
+            .peer_offers
+            .load()
+            .get(peer)
+            .copied()
+            .unwrap_or(CompressionOffer::NONE);
+        self.policy.negotiate(LinkClass::Direct, offer)
+    }
+}
+
+impl Default for SharedRuntimeCompression {
+    fn default() -> Self {
+        Self::new(CompressionPolicy::default())
+    }
+}
@@ -0,0 +1,124 @@
+//! Runtime-wide payload encoding state shared by the components that send updates.
+
+use arc_swap::ArcSwap;
+use flotsync_core::{GroupId, MemberIdentity, member::TrieMap, membership::GroupMemberships};
+use flotsync_messages::encoding::{EncodingOffer, PayloadEncoding};
+use std::sync::Arc;
+
+/// Locally preferred encoding and the offers peers advertised in summaries.
+///
+/// Clones share the same peer offers.
+#[derive(Clone, Debug)]
+pub(super) struct SharedRuntimeEncodings {
+    preferred: PayloadEncoding,
+    /// Latest offer each peer advertised; peers not listed only accept protobuf.
+    peer_offers: Arc<ArcSwap<TrieMap<EncodingOffer>>>,
+}
+
+impl SharedRuntimeEncodings {
+    pub(super) fn new(preferred: PayloadEncoding) -> Self {
+        Self {
+            preferred,
+            peer_offers: Arc::new(ArcSwap::from_pointee(TrieMap::new())),
+        }
+    }
+
+    /// Encodings the local replica accepts, as advertised to peers.
+    pub(super) fn local_offer(&self) -> EncodingOffer {
+        EncodingOffer::supported()
+    }
+
+    /// Remember the offer `peer` advertised in its latest summary exchange.
+    pub(super) fn record_peer_offer(&self, peer: &MemberIdentity, offer: EncodingOffer) {
+        if self.peer_offers.load().get(peer) == Some(&offer) {
+            return;
+        }
+        self.peer_offers.rcu(|offers| {
+            let mut offers = TrieMap::clone(offers);
+            offers.insert(peer.clone(), offer);
+            offers
+        });
+    }
+
+    /// Negotiate the encoding for one broadcast from `local_member` to the rest of `group_id`.
+    ///
+    /// Broadcasts are sealed once for every recipient, so the preferred encoding is only used if
+    /// all remote members offered it.
+    pub(super) fn for_group_broadcast(
+        &self,
+        memberships: &GroupMemberships,
+        group_id: &GroupId,
+        local_member: &MemberIdentity,
+    ) -> PayloadEncoding {
+        if self.preferred == PayloadEncoding::Protobuf {
+            return PayloadEncoding::Protobuf;
+        }
+        let Some(members) = memberships.members(group_id) else {
+            return PayloadEncoding::Protobuf;
+        };
+        let peer_offers = self.peer_offers.load();
+        members
+            .iter()
+            .filter(|member| member != local_member)
+            .map(|member| {
+                peer_offers
+                    .get(&member)
+                    .copied()
+                    .unwrap_or(EncodingOffer::PROTOBUF)
+            })
+            .fold(EncodingOffer::supported(), EncodingOffer::intersection)
+            .negotiate(self.preferred)
+    }
+}
+
+impl Default for SharedRuntimeEncodings {
+    fn default() -> Self {
+        Self::new(PayloadEncoding::Protobuf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_core::membership::GroupMembers;
+    use uuid::Uuid;
+
+    #[test]
+    fn broadcasts_use_the_preferred_encoding_only_if_every_peer_offered_it() {
+        let group_id = GroupId(Uuid::from_u128(1));
+        let local = MemberIdentity::from_array(["encodings", "local"]);
+        let first = MemberIdentity::from_array(["encodings", "first"]);
+        let second = MemberIdentity::from_array(["encodings", "second"]);
+        let members =
+            GroupMembers::from_ordered_members(vec![local.clone(), first.clone(), second.clone()])
+                .expect("test group members should be valid");
+        let memberships = GroupMemberships::from_groups([(group_id, members)]);
+        let cbor = EncodingOffer::from_encodings(PayloadEncoding::Cbor.into());
+        let encodings = SharedRuntimeEncodings::new(PayloadEncoding::Cbor);
+
+        encodings.record_peer_offer(&first, cbor);
+        assert_eq!(
+            encodings.for_group_broadcast(&memberships, &group_id, &local),
+            PayloadEncoding::Protobuf
+        );
+
+        encodings.record_peer_offer(&second, cbor);
+        let expected = if cfg!(feature = "cbor") {
+            PayloadEncoding::Cbor
+        } else {
+            PayloadEncoding::Protobuf
+        };
+        assert_eq!(
+            encodings.for_group_broadcast(&memberships, &group_id, &local),
+            expected
+        );
+
+        let protobuf_only = SharedRuntimeEncodings::new(PayloadEncoding::Protobuf);
+        protobuf_only.record_peer_offer(&first, cbor);
+        protobuf_only.record_peer_offer(&second, cbor);
+        assert_eq!(
+            protobuf_only.for_group_broadcast(&memberships, &group_id, &local),
+            PayloadEncoding::Protobuf
+        );
+    }
+}
@@ -1,8 +1,18 @@
 #[cfg(any(test, feature = "test-support"))]
 use crate::delivery::security::DeliverySecurityError;
 use crate::{
-    api::{DatasetId, ListenerError, ReplicationGroupLifecycle, RowId, StoreError},
+    api::{
+        DatasetId,
+        DocumentKindError,
+        ListenerError,
+        ReplicationGroupLifecycle,
+        RowId,
+        RowProviderError,
+        StoreError,
+    },
+    blobs::{BlobError, BlobHash},
     codecs::messages::RuntimeMessageError,
+    snapshot_transfer::{SnapshotId, SnapshotTransferError},
 };
 use flotsync_core::{
     GroupId,
@@ -14,7 +24,11 @@ use flotsync_core::{
 use flotsync_data_types::{
     InMemoryValueDataError,
     OperationError,
-    schema::{FieldValueBuildError, datamodel::InitialValueRowsEmbeddingError},
+    schema::{
+        FieldValueBuildError,
+        SemanticsRevisionError,
+        datamodel::InitialValueRowsEmbeddingError,
+    },
 };
 use flotsync_messages::codecs::datamodel::OperationCodecError;
 use kompact::prelude::PromiseErr;
@@ -75,6 +89,10 @@ pub(super) enum CreateGroupError {
     CreatorNotInMembers { creator: MemberIdentity },
     #[snafu(display("Group member list is invalid: {source}"))]
     InvalidMembers { source: GroupMembersError },
+    #[snafu(display("Group would have {member_count} members, but at most {limit} are allowed."))]
+    TooManyMembers { member_count: usize, limit: usize },
+    #[snafu(display("Observer replicas cannot create groups."))]
+    ObserverReplica,
     #[snafu(display("Failed to prepare secure group bootstrap material: {source}"))]
     Security { source: BoxedError },
 }
@@ -90,6 +108,68 @@ pub(super) enum SnapshotRowsError {
     GroupClosed { group_id: GroupId },
     #[snafu(display("Dataset {dataset_id} has no schema available for row snapshots."))]
     MissingDatasetSchema { dataset_id: DatasetId },
+    #[snafu(display(
+        "Snapshot versions for group {group_id} have {versions_member_count} members, but the persisted group has {persisted_member_count} members.",
+    ))]
+    VersionsMemberCountMismatch {
+        group_id: GroupId,
+        versions_member_count: usize,
+        persisted_member_count: usize,
+    },
+    #[snafu(display("Snapshot versions for group {group_id} are not applied locally yet."))]
+    VersionsNotApplied { group_id: GroupId },
+    #[snafu(display("Replaying group {group_id} to the snapshot versions failed: {source}"))]
+    Replay {
+        group_id: GroupId,
+        source: ReplayError,
+    },
+    #[snafu(display("Replication-store access failed at {location}: {source}"))]
+    StoreAccess {
+        source: StoreError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), module(checkpoint))]
+pub(super) enum CheckpointError {
+    #[snafu(display("Read token does not contain group {group_id}."))]
+    ReadTokenMissingGroup { group_id: GroupId },
+    #[snafu(display("Checkpoint document for group {group_id} is invalid: {source}"))]
+    Document {
+        group_id: GroupId,
+        source: DocumentKindError,
+    },
+    #[snafu(display("Loading the checkpoints of group {group_id} failed: {source}"))]
+    Snapshot {
+        group_id: GroupId,
+        #[snafu(source(from(SnapshotRowsError, Box::new)))]
+        source: Box<SnapshotRowsError>,
+    },
+    #[snafu(display("Reading the checkpoints of group {group_id} failed: {source}"))]
+    ReadRows {
+        group_id: GroupId,
+        source: RowProviderError,
+    },
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), module(acknowledged_versions))]
+pub(super) enum AcknowledgedVersionsError {
+    #[snafu(display("Group {group_id} is not hosted by this runtime."))]
+    UnknownGroup { group_id: GroupId },
+    #[snafu(display("Replication-store access failed at {location}: {source}"))]
+    StoreAccess {
+        source: StoreError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), module(run_sync_step))]
+pub(super) enum SyncStepError {
     #[snafu(display("Replication-store access failed at {location}: {source}"))]
     StoreAccess {
         source: StoreError,
@@ -133,13 +213,13 @@ pub(crate) enum GroupInstallError {
         source: GroupMembersError,
     },
     #[snafu(display(
-        "Persisted group {group_id} stored local member {local_member} at index {persisted_local_member_index}, but the canonical member order resolves it to {actual_local_member_index}.",
+        "Persisted group {group_id} stored local member {local_member} at index {persisted_local_member_index:?}, but the canonical member order resolves it to {actual_local_member_index:?}.",
     ))]
     PersistedLocalMemberIndexMismatch {
         group_id: GroupId,
         local_member: MemberIdentity,
-        persisted_local_member_index: MemberIndex,
-        actual_local_member_index: MemberIndex,
+        persisted_local_member_index: Option<MemberIndex>,
+        actual_local_member_index: Option<MemberIndex>,
     },
     #[snafu(display(
         "Persisted group {group_id} stored {persisted_member_count} version-vector members, but the canonical member set has {actual_member_count}.",
@@ -219,6 +299,12 @@ pub(crate) enum ChangeGroupMembershipError {
     InvalidMembers { source: GroupMembersError },
     #[snafu(display("New group members must include the local member {local_member}."))]
     LocalMemberMissing { local_member: MemberIdentity },
+    #[snafu(display(
+        "New group would have {member_count} members, but at most {limit} are allowed."
+    ))]
+    TooManyMembers { member_count: usize, limit: usize },
+    #[snafu(display("Observer replicas cannot change group membership."))]
+    ObserverReplica,
     #[snafu(display("Persisted group {group_id} was invalid at {location}: {source}"))]
     InvalidPersistedGroup {
         group_id: GroupId,
@@ -370,6 +456,11 @@ pub(crate) enum PublishChangesError {
     },
     #[snafu(display("No schema was available for dataset '{dataset_id}'."))]
     MissingDatasetSchema { dataset_id: DatasetId },
+    #[snafu(display("Changes cannot be merged into dataset '{dataset_id}': {source}"))]
+    IncompatibleSemanticsRevision {
+        dataset_id: DatasetId,
+        source: SemanticsRevisionError,
+    },
     #[snafu(display(
         "Row {row_id} referenced unknown schema field '{field_name}' in dataset '{dataset_id}'.",
     ))]
@@ -402,6 +493,18 @@ pub(crate) enum PublishChangesError {
     NoEffectiveChanges { group_id: GroupId },
     #[snafu(display("Group {group_id} exhausted its local update id range."))]
     ExhaustedUpdateIds { group_id: GroupId },
+    #[snafu(display(
+        "The local replica only observes group {group_id} and cannot publish updates."
+    ))]
+    ObserverReplica { group_id: GroupId },
+    #[snafu(display(
+        "Update for group {group_id} encodes to {payload_bytes} bytes, but at most {limit} are allowed."
+    ))]
+    PayloadTooLarge {
+        group_id: GroupId,
+        payload_bytes: usize,
+        limit: usize,
+    },
 }
 
 #[derive(Debug, Snafu)]
@@ -428,6 +531,18 @@ pub(crate) enum ReplayError {
 pub(crate) enum InboundDeliveryError {
     #[snafu(display("Failed to decode inbound runtime message: {source}"))]
     DecodeMessage { source: RuntimeMessageError },
+    #[snafu(display(
+        "Inbound runtime message payload has {payload_bytes} bytes, but at most {limit} are allowed."
+    ))]
+    PayloadTooLarge { payload_bytes: usize, limit: usize },
+    #[snafu(display(
+        "Inbound group setup for group {group_id} has {member_count} members, but at most {limit} are allowed."
+    ))]
+    TooManyGroupMembers {
+        group_id: GroupId,
+        member_count: usize,
+        limit: usize,
+    },
     #[snafu(display("Inbound group setup failed security checks: {source}"))]
     GroupSetupSecurity { source: BoxedError },
     #[snafu(display("Inbound migration acceptance failed: {source}"))]
@@ -519,6 +634,15 @@ pub(crate) enum InboundDeliveryError {
     },
     #[snafu(display("No schema was available for inbound dataset '{dataset_id}'."))]
     MissingDatasetSchema { dataset_id: DatasetId },
+    #[snafu(display(
+        "Inbound update {update_id} for group {group_id} cannot be merged into dataset '{dataset_id}': {source}"
+    ))]
+    IncompatibleSemanticsRevision {
+        group_id: GroupId,
+        update_id: UpdateId,
+        dataset_id: DatasetId,
+        source: SemanticsRevisionError,
+    },
     #[snafu(display(
         "Inbound update for group {group_id} came from sender {sender}, which is not a group member.",
     ))]
@@ -542,6 +666,52 @@ pub(crate) enum InboundDeliveryError {
         group_id: GroupId,
         producer_index: MemberIndex,
     },
+    #[snafu(display(
+        "Inbound acknowledgement for group {group_id} came from sender {sender}, which is not a group member.",
+    ))]
+    AckSenderNotInGroup {
+        group_id: GroupId,
+        sender: MemberIdentity,
+    },
+    #[snafu(display(
+        "Inbound blob transfer message for group {group_id} came from sender {sender}, which is not a group member.",
+    ))]
+    BlobSenderNotInGroup {
+        group_id: GroupId,
+        sender: MemberIdentity,
+    },
+    #[snafu(display(
+        "Inbound snapshot request for group {group_id} came from sender {sender}, which was not invited to fetch snapshot {snapshot_id}.",
+    ))]
+    SnapshotRequesterNotRecipient {
+        group_id: GroupId,
+        snapshot_id: SnapshotId,
+        sender: MemberIdentity,
+    },
+    #[snafu(display("Member {peer} sent invalid data for snapshot {snapshot_id}: {source}"))]
+    InvalidSnapshotTransfer {
+        peer: MemberIdentity,
+        snapshot_id: SnapshotId,
+        #[snafu(source(from(SnapshotTransferError, Box::new)))]
+        source: Box<SnapshotTransferError>,
+    },
+    #[snafu(display(
+        "Member {peer} no longer holds snapshot {snapshot_id} announced for group {group_id}."
+    ))]
+    SnapshotUnavailable {
+        group_id: GroupId,
+        snapshot_id: SnapshotId,
+        peer: MemberIdentity,
+    },
+    #[snafu(display(
+        "Member {peer} did not send snapshot {snapshot_id} for group {group_id} within {attempts} attempts."
+    ))]
+    SnapshotFetchTimedOut {
+        group_id: GroupId,
+        snapshot_id: SnapshotId,
+        peer: MemberIdentity,
+        attempts: usize,
+    },
     #[snafu(display(
         "Inbound update {update_id} for group {group_id} carried read versions that already include producer version {producer_read_version}.",
     ))]
@@ -575,6 +745,14 @@ pub(crate) enum InboundDeliveryError {
         row_id: RowId,
         source: OperationError,
     },
+    #[snafu(display(
+        "Inbound mutation would grow row {row_id} to {node_count} nodes, but at most {limit} are allowed."
+    ))]
+    DocumentTooLarge {
+        row_id: RowId,
+        node_count: usize,
+        limit: usize,
+    },
     #[snafu(display("Inbound pending group activation failed: {source}"))]
     PendingGroupActivation {
         #[snafu(source(from(GroupActivationError, Box::new)))]
@@ -599,6 +777,8 @@ impl InboundDeliveryError {
             | Self::NotifyPendingGroupDecision { .. }
             | Self::NotifyListener { .. } => InboundFailureAction::Fatal,
             Self::DecodeMessage { .. }
+            | Self::PayloadTooLarge { .. }
+            | Self::TooManyGroupMembers { .. }
             | Self::GroupSetupSecurity { .. }
             | Self::UnexpectedReliableMessage
             | Self::ReliableMessageGroupMismatch { .. }
@@ -612,18 +792,47 @@ impl InboundDeliveryError {
             | Self::InvalidPendingGroupMembers { .. }
             | Self::PendingGroupMissingLocalMember { .. }
             | Self::MissingDatasetSchema { .. }
+            | Self::IncompatibleSemanticsRevision { .. }
             | Self::UpdateSenderNotInGroup { .. }
             | Self::UpdateSenderIndexMismatch { .. }
             | Self::UpdateProducerIndexNotInGroup { .. }
+            | Self::AckSenderNotInGroup { .. }
+            | Self::BlobSenderNotInGroup { .. }
+            | Self::SnapshotRequesterNotRecipient { .. }
+            | Self::InvalidSnapshotTransfer { .. }
+            | Self::SnapshotUnavailable { .. }
+            | Self::SnapshotFetchTimedOut { .. }
             | Self::SelfDependentReadVersions { .. }
             | Self::ConflictingPersistedUpdate { .. }
             | Self::UpdateOperationIdMismatch { .. }
             | Self::DecodeSchemaOperation { .. }
-            | Self::ApplyInboundMutation { .. } => InboundFailureAction::Drop,
+            | Self::ApplyInboundMutation { .. }
+            | Self::DocumentTooLarge { .. } => InboundFailureAction::Drop,
         }
     }
 }
 
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)), module(blob_transfer))]
+pub(crate) enum BlobTransferError {
+    #[snafu(display("Group {group_id} is not hosted by this runtime."))]
+    UnknownGroup { group_id: GroupId },
+    #[snafu(display("Member {peer} is not a member of group {group_id}."))]
+    PeerNotInGroup {
+        group_id: GroupId,
+        peer: MemberIdentity,
+    },
+    #[snafu(display("Blob could not be stored: {source}"))]
+    Store { source: BlobError },
+    #[snafu(display("Member {peer} sent invalid content for blob {hash}: {source}"))]
+    InvalidTransfer {
+        peer: MemberIdentity,
+        hash: BlobHash,
+        #[snafu(source(from(BlobError, Box::new)))]
+        source: Box<BlobError>,
+    },
+}
+
 /// Runtime action selected after classifying an inbound delivery failure.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum InboundFailureAction {
@@ -12,15 +12,25 @@ use super::{
 };
 use crate::{
     api::{
+        AcknowledgedVersions,
         ApiError,
         ApiExternalSnafu,
         ApiResult,
         ChangeGroupMembershipRequest,
+        Checkpoint,
+        CompressionCounters,
+        CreateCheckpointRequest,
         CreateGroupRequest,
+        FetchBlobRequest,
+        GroupSyncHealth,
+        HistoricalSnapshotRowsRequest,
         LoadError,
         MigrationId,
+        PowerHint,
         PublishChangesRequest,
         PublishReceipt,
+        QuarantineId,
+        QuarantinedDelivery,
         ReplicationApi,
         ReplicationConfig,
         ReplicationEventListener,
@@ -29,28 +39,40 @@ use crate::{
         RuntimeSnafu,
         SnapshotRowsRequest,
         SnapshotValueRows,
+        StoreBlobRequest,
         Summary,
         SummaryRequest,
+        SyncStepProgress,
+        TypedDoc,
+        WorkspaceEventReceiver,
         security::{
             AssessPublicKeyBundleRequest,
             PublicKeyBundleReport,
             RecordPublicKeyBundleFeedbackRequest,
         },
     },
+    blobs::BlobDescriptor,
     delivery::security::DeliverySecurity,
     security_store::SecurityStore,
 };
+use bytes::Bytes;
 #[cfg(any(test, feature = "test-support"))]
 use flotsync_core::MemberIdentity;
 #[cfg(any(test, feature = "test-support"))]
 use flotsync_core::membership::{GroupMembers, GroupMemberships};
 use flotsync_core::{GroupId, member::Identifier};
 use flotsync_security::PublicKeyBundle;
-use flotsync_utils::BoxFuture;
+use flotsync_utils::{
+    BoxFuture,
+    shutdown::{ShutdownPhase, ShutdownToken},
+};
 use futures_util::{FutureExt, future};
 use kompact::{KompactLogger, prelude::*};
 use snafu::prelude::*;
-use std::sync::{Arc, RwLock};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 #[cfg(any(test, feature = "test-support"))]
 use super::errors::GroupInstallError;
@@ -58,8 +80,6 @@ use super::errors::GroupInstallError;
 use super::errors::InboundDeliveryError;
 #[cfg(test)]
 use crate::codecs::messages::{UpdateBatchMessage, UpdateMessage};
-#[cfg(any(test, feature = "test-support"))]
-use std::time::Duration;
 
 type ApiFuture<'a, T> = BoxFuture<'a, ApiResult<T>>;
 
@@ -254,6 +274,16 @@ struct RuntimeLifecycle {
 }
 
 impl ReplicationRuntime {
+    /// Detach the live resources so every later API call reports the runtime as unavailable.
+    fn take_lifecycle(&self) -> ApiResult<Option<RuntimeLifecycle>> {
+        let Ok(mut lifecycle) = self.lifecycle.write() else {
+            return Err(ApiError::RuntimeLifecyclePoisoned {
+                operation: "shutting runtime down",
+            });
+        };
+        Ok(lifecycle.take())
+    }
+
     fn runtime_ref(
         &self,
         operation: &'static str,
@@ -312,15 +342,7 @@ impl Drop for ReplicationRuntime {
 impl ReplicationApi for ReplicationRuntime {
     fn shutdown(&self) -> ApiFuture<'_, ()> {
         async move {
-            let lifecycle = {
-                let Ok(mut lifecycle) = self.lifecycle.write() else {
-                    return Err(ApiError::RuntimeLifecyclePoisoned {
-                        operation: "shutting runtime down",
-                    });
-                };
-                lifecycle.take()
-            };
-            let Some(RuntimeLifecycle { mut host, .. }) = lifecycle else {
+            let Some(RuntimeLifecycle { mut host, .. }) = self.take_lifecycle()? else {
                 return Ok(());
             };
             host.shutdown().await.boxed().context(ApiExternalSnafu)
@@ -328,6 +350,22 @@ impl ReplicationApi for ReplicationRuntime {
         .boxed()
     }
 
+    fn shutdown_with(&self, token: &ShutdownToken) -> ApiFuture<'_, ()> {
+        let discovery = token.participant("replication-discovery");
+        let sync = token.participant("replication-sync");
+        async move {
+            sync.token().reached(ShutdownPhase::StopAccepting).await;
+            let Some(RuntimeLifecycle { mut host, .. }) = self.take_lifecycle()? else {
+                return Ok(());
+            };
+            host.shutdown_in_phases(discovery, sync)
+                .await
+                .boxed()
+                .context(ApiExternalSnafu)
+        }
+        .boxed()
+    }
+
     fn local_public_key_bundle(&self) -> ApiFuture<'_, PublicKeyBundle> {
         self.ask(|promise| ReplicationRuntimeMessage::LocalPublicKeyBundle(Ask::new(promise, ())))
     }
@@ -360,12 +398,92 @@ impl ReplicationApi for ReplicationRuntime {
         self.ask(move |promise| ReplicationRuntimeMessage::SnapshotRows(Ask::new(promise, request)))
     }
 
+    fn snapshot_rows_at(
+        &self,
+        request: HistoricalSnapshotRowsRequest,
+    ) -> ApiFuture<'_, SnapshotValueRows> {
+        self.ask(move |promise| {
+            ReplicationRuntimeMessage::SnapshotRowsAt(Ask::new(promise, request))
+        })
+    }
+
+    fn create_checkpoint(
+        &self,
+        request: CreateCheckpointRequest,
+    ) -> ApiFuture<'_, TypedDoc<Checkpoint>> {
+        self.ask(move |promise| {
+            ReplicationRuntimeMessage::CreateCheckpoint(Ask::new(promise, request))
+        })
+    }
+
+    fn list_checkpoints(&self, group_id: GroupId) -> ApiFuture<'_, Vec<TypedDoc<Checkpoint>>> {
+        self.ask(move |promise| {
+            ReplicationRuntimeMessage::ListCheckpoints(Ask::new(promise, group_id))
+        })
+    }
+
+    fn store_blob(&self, request: StoreBlobRequest) -> ApiFuture<'_, BlobDescriptor> {
+        self.ask(move |promise| ReplicationRuntimeMessage::StoreBlob(Ask::new(promise, request)))
+    }
+
+    fn fetch_blob(&self, request: FetchBlobRequest) -> ApiFuture<'_, Bytes> {
+        self.ask(move |promise| ReplicationRuntimeMessage::FetchBlob(Ask::new(promise, request)))
+    }
+
     fn request_summary(&self, request: SummaryRequest) -> ApiFuture<'_, Summary> {
         self.ask(move |promise| {
             ReplicationRuntimeMessage::RequestSummary(Ask::new(promise, request))
         })
     }
 
+    fn acknowledged_versions(&self, group_id: GroupId) -> ApiFuture<'_, AcknowledgedVersions> {
+        self.ask(move |promise| {
+            ReplicationRuntimeMessage::AcknowledgedVersions(Ask::new(promise, group_id))
+        })
+    }
+
+    fn sync_health(&self, group_id: GroupId) -> ApiFuture<'_, GroupSyncHealth> {
+        self.ask(move |promise| ReplicationRuntimeMessage::SyncHealth(Ask::new(promise, group_id)))
+    }
+
+    fn set_power_hint(&self, hint: PowerHint) -> ApiFuture<'_, ()> {
+        self.ask(move |promise| ReplicationRuntimeMessage::SetPowerHint(Ask::new(promise, hint)))
+    }
+
+    fn notify_network_changed(&self) -> ApiFuture<'_, ()> {
+        self.ask(|promise| ReplicationRuntimeMessage::NetworkChanged(Ask::new(promise, ())))
+    }
+
+    fn run_sync_step(&self, budget: Duration) -> ApiFuture<'_, SyncStepProgress> {
+        self.ask(move |promise| ReplicationRuntimeMessage::RunSyncStep(Ask::new(promise, budget)))
+    }
+
+    fn compression_counters(&self) -> ApiFuture<'_, CompressionCounters> {
+        self.ask(|promise| ReplicationRuntimeMessage::CompressionCounters(Ask::new(promise, ())))
+    }
+
+    fn quarantined_deliveries(&self) -> ApiFuture<'_, Vec<QuarantinedDelivery>> {
+        self.ask(|promise| ReplicationRuntimeMessage::QuarantinedDeliveries(Ask::new(promise, ())))
+    }
+
+    fn retry_quarantined_delivery(&self, id: QuarantineId) -> ApiFuture<'_, ()> {
+        self.ask(move |promise| {
+            ReplicationRuntimeMessage::RetryQuarantinedDelivery(Ask::new(promise, id))
+        })
+    }
+
+    fn subscribe_workspace_events(&self) -> ApiResult<WorkspaceEventReceiver> {
+        let Ok(lifecycle) = self.lifecycle.read() else {
+            return Err(ApiError::RuntimeLifecyclePoisoned {
+                operation: "subscribing to workspace events",
+            });
+        };
+        lifecycle
+            .as_ref()
+            .map(|lifecycle| lifecycle.host.workspace_events().subscribe())
+            .ok_or(ApiError::RuntimeUnavailable)
+    }
+
     fn create_group(&self, req: CreateGroupRequest) -> ApiFuture<'_, GroupId> {
         self.ask(move |promise| ReplicationRuntimeMessage::CreateGroup(Ask::new(promise, req)))
     }
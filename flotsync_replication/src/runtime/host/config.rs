@@ -114,6 +114,7 @@ pub(in crate::runtime::host) struct DeliveryRuntimeHostConfig {
     pub(in crate::runtime::host) summary_request_timeout: Duration,
     pub(in crate::runtime::host) local_endpoint_bind_addr: SocketAddr,
     pub(in crate::runtime::host) peer_announcement_bind_addr: SocketAddr,
+    pub(in crate::runtime::host) workspace_events_capacity: NonZeroUsize,
 }
 
 impl DeliveryRuntimeHostConfig {
@@ -166,11 +167,21 @@ impl DeliveryRuntimeHostConfig {
                         message: error.to_string(),
                     },
                 )?;
+        let workspace_events_capacity = system
+            .config()
+            .read_or_default(&config_keys::WORKSPACE_EVENTS_CAPACITY)
+            .map_err(|error| RuntimeHostError::InvalidConfig {
+                key: config_keys::WORKSPACE_EVENTS_CAPACITY.key,
+                message: error.to_string(),
+            })?;
+        let workspace_events_capacity = NonZeroUsize::new(workspace_events_capacity)
+            .expect("workspace event capacity is validated to be positive");
         Ok(Self {
             control_timeout,
             summary_request_timeout,
             local_endpoint_bind_addr,
             peer_announcement_bind_addr,
+            workspace_events_capacity,
         })
     }
 }
@@ -9,12 +9,20 @@ use super::{
         RuntimeIdentityContext,
         RuntimeSecurityContext,
     },
+    compression::SharedRuntimeCompression,
+    encodings::SharedRuntimeEncodings,
     summary_request_manager::SummaryRequestManagerComponent,
 };
 #[cfg(test)]
 use super::{ReplicationRuntimeMessage, handle::wait_for_test_reply};
 use crate::{
-    api::{BoxError, ReplicationConfig, ReplicationEventListener, ReplicationStore},
+    api::{
+        BoxError,
+        ReplicationConfig,
+        ReplicationEventListener,
+        ReplicationStore,
+        WorkspaceEvents,
+    },
     delivery::{
         contracts::{GroupBroadcastPort, ReliableDeliveryPort},
         group_broadcast::{GroupBroadcastComponent, GroupBroadcastInboundPort},
@@ -28,6 +36,7 @@ use flotsync_core::{
     membership::{GroupMemberships, SharedGroupMemberships},
 };
 use flotsync_discovery::{
+    capabilities::Capabilities,
     config_keys as discovery_config_keys,
     endpoint_selection::EndpointSelectionPort,
     services::{
@@ -62,7 +71,9 @@ use flotsync_routes::{
     TransportRouteKey,
     UDPourConfig,
     key_material_discovery::{KeyMaterialDiscoveryComponent, KeyMaterialDiscoveryPort},
+    liveness::PeerLivenessPort,
     manager::{RouteTransportManager, configure_replication_runtime},
+    nat_traversal::{NatTraversalComponent, NatTraversalConfig, NatTraversalPort},
     route_establishment::{
         ManualRouteWatchError,
         RouteEstablishmentComponent,
@@ -77,7 +88,11 @@ use flotsync_utils::kompact_testing::{
     PortTestingExt as _,
     PortTestingRefExt as _,
 };
-use flotsync_utils::{FutureTimeoutExt as _, TimeoutError};
+use flotsync_utils::{
+    FutureTimeoutExt as _,
+    TimeoutError,
+    shutdown::{ShutdownParticipant, ShutdownPhase},
+};
 use futures_util::{FutureExt, future::BoxFuture};
 use kompact::{
     KompactLogger,
@@ -90,6 +105,7 @@ use std::{
     collections::HashSet,
     error::Error as StdError,
     net::SocketAddr,
+    num::NonZeroUsize,
     sync::Arc,
     time::Duration,
 };
@@ -132,7 +148,7 @@ const TEST_DIRECT_PEER_ROUTE_TIMEOUT: Duration = Duration::from_secs(5);
 
 mod config_keys {
     use kompact::{
-        config::{DurationValue, StringValue},
+        config::{DurationValue, StringValue, UsizeValue},
         kompact_config,
     };
     use std::time::Duration;
@@ -175,6 +191,16 @@ mod config_keys {
         version = "0.1.0"
     }
 
+    kompact_config! {
+        WORKSPACE_EVENTS_CAPACITY,
+        key = "flotsync.replication.runtime.workspace-events.capacity",
+        type = UsizeValue,
+        default = 256,
+        validate = |value| *value > 0,
+        doc = "Number of workspace events retained for subscribers that fall behind. Slower subscribers skip the oldest events.",
+        version = "0.1.0"
+    }
+
     kompact_config! {
         LOCAL_ENDPOINT_SELECTION_REFRESH_INTERVAL,
         key = "flotsync.replication.runtime.local-endpoint-selection-refresh-interval",
@@ -200,6 +226,7 @@ pub(crate) struct DeliveryRuntimeHost {
     system: Option<KompactSystem>,
     topology: Option<RuntimeTopology>,
     group_memberships: SharedGroupMemberships,
+    workspace_events: WorkspaceEvents,
     control_timeout: Duration,
     #[cfg_attr(not(any(test, feature = "test-support")), allow(dead_code))]
     external_udp_addr: SocketAddr,
@@ -212,6 +239,7 @@ impl DeliveryRuntimeHost {
         system: KompactSystem,
         topology: RuntimeTopology,
         group_memberships: SharedGroupMemberships,
+        workspace_events: WorkspaceEvents,
         control_timeout: Duration,
         external_udp_addr: SocketAddr,
         #[cfg(any(test, feature = "test-support"))] local_endpoint_lease: ReservedSocketLease,
@@ -220,6 +248,7 @@ impl DeliveryRuntimeHost {
             system: Some(system),
             topology: Some(topology),
             group_memberships,
+            workspace_events,
             control_timeout,
             external_udp_addr,
             #[cfg(any(test, feature = "test-support"))]
@@ -240,6 +269,11 @@ impl DeliveryRuntimeHost {
             .logger()
     }
 
+    /// Event bus the runtime component emits workspace events on.
+    pub(crate) fn workspace_events(&self) -> &WorkspaceEvents {
+        &self.workspace_events
+    }
+
     pub(crate) fn runtime_component(&self) -> &Arc<Component<ReplicationRuntimeComponent>> {
         &self.topology().runtime.runtime_component
     }
@@ -280,7 +314,15 @@ impl DeliveryRuntimeHost {
         let system = built_system.system.clone();
         let host_config = DeliveryRuntimeHostConfig::from_system_config(&system)?;
         let routes_config = PreconfiguredPeerRoutesConfig::from_config(system.config())?;
+        let nat_traversal_config =
+            NatTraversalConfig::from_config(system.config()).map_err(|error| {
+                RuntimeHostError::InvalidConfig {
+                    key: error.config_key(),
+                    message: error.to_string(),
+                }
+            })?;
         let group_memberships = SharedGroupMemberships::new(GroupMemberships::new());
+        let workspace_events = WorkspaceEvents::new(host_config.workspace_events_capacity);
         let topology = RuntimeTopology::build(
             &system,
             RuntimeTopologyBuildInput {
@@ -288,10 +330,14 @@ impl DeliveryRuntimeHost {
                 local_member: local_member.clone(),
                 store,
                 listener,
+                workspace_events: workspace_events.clone(),
                 config,
                 security,
                 host_config,
-                static_route_hints: routes_config,
+                discovery_settings: DiscoverySettings {
+                    static_route_hints: routes_config,
+                    nat_traversal: nat_traversal_config,
+                },
             },
         );
         topology.connect_all()?;
@@ -329,6 +375,7 @@ impl DeliveryRuntimeHost {
             system,
             topology,
             group_memberships,
+            workspace_events,
             host_config.control_timeout,
             local_endpoint.local_addr,
             #[cfg(any(test, feature = "test-support"))]
@@ -367,6 +414,52 @@ impl DeliveryRuntimeHost {
         };
         let stop_result = topology.stop_all(&system, self.control_timeout).await;
         drop(topology);
+        self.shutdown_system(system, stop_result).await
+    }
+
+    /// Shut down in step with the phases of a coordinated shutdown.
+    ///
+    /// Must only be called once the shutdown reached [`ShutdownPhase::StopAccepting`].
+    /// Discovery stops right away and then completes every phase. The sync
+    /// sessions stop during [`ShutdownPhase::Drain`], and transport, I/O, and the
+    /// Kompact system close during [`ShutdownPhase::Close`].
+    pub(crate) async fn shutdown_in_phases(
+        &mut self,
+        discovery: ShutdownParticipant,
+        sync: ShutdownParticipant,
+    ) -> Result<(), RuntimeHostError> {
+        let Some(topology) = self.topology.take() else {
+            return Ok(());
+        };
+        let Some(system) = self.system.take() else {
+            return Ok(());
+        };
+        // The caller already refuses API requests, so the sync sessions get no new work.
+        sync.complete(ShutdownPhase::StopAccepting);
+        let token = sync.token().clone();
+        let stop_result = async {
+            topology
+                .stop_discovery(&system, self.control_timeout)
+                .await?;
+            discovery.complete(ShutdownPhase::Close);
+            token.reached(ShutdownPhase::Drain).await;
+            topology.stop_sync(&system, self.control_timeout).await?;
+            // Sync sessions persist every update before acknowledging it, so stopping
+            // them leaves nothing to flush.
+            sync.complete(ShutdownPhase::Flush);
+            token.reached(ShutdownPhase::Close).await;
+            topology.stop_transport(&system, self.control_timeout).await
+        }
+        .await;
+        drop(topology);
+        self.shutdown_system(system, stop_result).await
+    }
+
+    async fn shutdown_system(
+        &mut self,
+        system: KompactSystem,
+        stop_result: Result<(), RuntimeHostError>,
+    ) -> Result<(), RuntimeHostError> {
         if let Err(error) = stop_result {
             system.shutdown_async();
             #[cfg(any(test, feature = "test-support"))]
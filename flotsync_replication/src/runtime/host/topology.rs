@@ -215,6 +215,7 @@ impl ComponentTopology for IoTopology {
 ///                                                                  +--RouteEndpointLifecyclePort--+
 ///                                                                                                 v
 ///                                                                                  RouteEstablishmentComponent
+///                                                                                  NatTraversalComponent
 /// ```
 pub(in crate::runtime::host) struct TransportTopology {
     manager: Arc<Component<RouteTransportManager>>,
@@ -323,6 +324,11 @@ impl DeliveryTopology {
             &self.reliable_delivery,
             "route establishment -> reliable delivery",
         )?;
+        connect_components::<PeerLivenessPort, _, _>(
+            discovery.route_discovery_provider(),
+            &self.reliable_delivery,
+            "peer liveness -> reliable delivery",
+        )?;
         #[cfg(any(test, feature = "test-support"))]
         {
             connect_components::<RouteDiscoveryPort<TransportRouteKey>, _, _>(
@@ -378,12 +384,17 @@ impl ComponentTopology for DeliveryTopology {
 ///                                                    |--RouteDiscoveryPort--> semantic delivery
 ///                                                    |
 ///                              KeyMaterialDiscoveryComponent <--KeyMaterialDiscoveryPort--+
+///                                                    |
+///                                                    |--RouteDiscoveryPort--> NatTraversalComponent
+///                                                    |                              |
+///                                                    +<----NatTraversalPort---------+
 /// ```
 pub(in crate::runtime::host) struct DiscoveryTopology {
     peer_announcement: Arc<Component<PeerAnnouncementComponent>>,
     peer_announcement_observation: Arc<Component<PeerAnnouncementObservationComponent>>,
     route_establishment: Arc<Component<RouteEstablishmentComponent>>,
     key_material_discovery: Arc<Component<KeyMaterialDiscoveryComponent>>,
+    nat_traversal: Arc<Component<NatTraversalComponent>>,
     #[cfg(any(test, feature = "test-support"))]
     manual_route_discovery: Arc<Component<PortTesterComponent<ManualRouteDiscoveryPort>>>,
     #[cfg(any(test, feature = "test-support"))]
@@ -392,6 +403,14 @@ pub(in crate::runtime::host) struct DiscoveryTopology {
     static_route_hints: PreconfiguredPeerRoutesConfig,
 }
 
+/// Config-derived discovery settings read once at host startup.
+pub(in crate::runtime::host) struct DiscoverySettings {
+    /// Preconfigured peer routes watched by route establishment.
+    pub(in crate::runtime::host) static_route_hints: PreconfiguredPeerRoutesConfig,
+    /// Rendezvous relays and relay role for NAT traversal.
+    pub(in crate::runtime::host) nat_traversal: NatTraversalConfig,
+}
+
 /// Shared transport handles needed by discovery-side runtime components.
 struct DiscoveryTransportHandles {
     /// Actor interface used by route establishment for UDPour-capable discovery frames.
@@ -408,16 +427,24 @@ impl DiscoveryTopology {
         local_member: MemberIdentity,
         security: DeliverySecurity,
         transport_handles: DiscoveryTransportHandles,
-        static_route_hints: PreconfiguredPeerRoutesConfig,
+        settings: DiscoverySettings,
     ) -> Self {
         let DiscoveryTransportHandles {
             route_transport,
             egress_pool,
         } = transport_handles;
-        let route_config = route_establishment_config(host_config.peer_announcement_bind_addr);
+        let DiscoverySettings {
+            static_route_hints,
+            nat_traversal,
+        } = settings;
+        let route_config = route_establishment_config(
+            host_config.peer_announcement_bind_addr,
+            nat_traversal.serve_rendezvous,
+        );
         let peer_options = PeerAnnouncementOptions::DEFAULT
             .with_socket_bind_addr(route_config.peer_announcement_bind_addr)
             .with_instance_id(route_config.instance_id)
+            .with_capabilities(route_config.capabilities)
             .with_socket_maintenance(PeerAnnouncementSocketMaintenance::Maintain);
         let peer_announcement =
             system.create(move || PeerAnnouncementComponent::with_options(peer_options));
@@ -437,6 +464,17 @@ impl DiscoveryTopology {
                 egress_pool,
             )
         });
+        let nat_traversal_transport = route_transport.clone();
+        let nat_traversal_member = local_member.clone();
+        let nat_traversal_memberships = group_memberships.clone();
+        let nat_traversal = system.create(move || {
+            NatTraversalComponent::new(
+                nat_traversal,
+                nat_traversal_transport,
+                nat_traversal_member,
+                nat_traversal_memberships,
+            )
+        });
         let route_establishment = system.create(move || {
             RouteEstablishmentComponent::new(
                 route_config,
@@ -459,6 +497,7 @@ impl DiscoveryTopology {
             peer_announcement_observation,
             route_establishment,
             key_material_discovery,
+            nat_traversal,
             #[cfg(any(test, feature = "test-support"))]
             manual_route_discovery,
             #[cfg(any(test, feature = "test-support"))]
@@ -500,6 +539,16 @@ impl DiscoveryTopology {
             &self.route_establishment,
             "route transport endpoint lifecycle -> route establishment",
         )?;
+        connect_components::<TransportRoutePort, _, _>(
+            transport.route_transport_manager(),
+            &self.nat_traversal,
+            "route transport -> NAT traversal",
+        )?;
+        connect_components::<RouteEndpointLifecyclePort, _, _>(
+            transport.route_transport_manager(),
+            &self.nat_traversal,
+            "route transport endpoint lifecycle -> NAT traversal",
+        )?;
         Ok(())
     }
 
@@ -528,6 +577,16 @@ impl DiscoveryTopology {
             &self.key_material_discovery,
             &self.route_establishment,
             "route establishment -> key material discovery",
+        )?;
+        connect_components::<RouteDiscoveryPort<TransportRouteKey>, _, _>(
+            &self.route_establishment,
+            &self.nat_traversal,
+            "route establishment -> NAT traversal",
+        )?;
+        connect_components::<NatTraversalPort, _, _>(
+            &self.nat_traversal,
+            &self.route_establishment,
+            "NAT traversal -> route establishment",
         )
     }
 
@@ -578,9 +637,15 @@ impl DiscoveryTopology {
     }
 }
 
-fn route_establishment_config(peer_announcement_bind_addr: SocketAddr) -> RouteEstablishmentConfig {
+fn route_establishment_config(
+    peer_announcement_bind_addr: SocketAddr,
+    serve_rendezvous: bool,
+) -> RouteEstablishmentConfig {
     let mut config = RouteEstablishmentConfig::new();
     config.peer_announcement_bind_addr = peer_announcement_bind_addr;
+    if serve_rendezvous {
+        config.capabilities = config.capabilities.with_features(Capabilities::RELAY);
+    }
     config
 }
 
@@ -601,6 +666,7 @@ impl ComponentTopology for DiscoveryTopology {
             &self.peer_announcement_observation as &dyn RuntimeLifecycleComponent,
             &self.route_establishment as &dyn RuntimeLifecycleComponent,
             &self.key_material_discovery as &dyn RuntimeLifecycleComponent,
+            &self.nat_traversal as &dyn RuntimeLifecycleComponent,
             &self.local_endpoint_manager as &dyn RuntimeLifecycleComponent,
         ]
         .into_iter()
@@ -613,6 +679,7 @@ impl ComponentTopology for DiscoveryTopology {
             &self.peer_announcement_observation as &dyn RuntimeLifecycleComponent,
             &self.route_establishment as &dyn RuntimeLifecycleComponent,
             &self.key_material_discovery as &dyn RuntimeLifecycleComponent,
+            &self.nat_traversal as &dyn RuntimeLifecycleComponent,
             &self.manual_route_discovery as &dyn RuntimeLifecycleComponent,
             &self.local_endpoint_manager as &dyn RuntimeLifecycleComponent,
         ]
@@ -655,7 +722,10 @@ impl RuntimeLogicTopology {
         let catch_up_manager = CatchUpManagerComponent::new(
             input.identity.local_member.clone(),
             input.identity.group_memberships.clone(),
+            input.identity.compression.clone(),
+            input.identity.encodings.clone(),
             input.services.store.clone(),
+            input.services.config.limits.max_payload_bytes,
         );
         let catch_up_manager = system.create(move || catch_up_manager);
         let catch_up_manager_ref = catch_up_manager
@@ -665,6 +735,8 @@ impl RuntimeLogicTopology {
         let summary_request_manager = SummaryRequestManagerComponent::new(
             input.identity.local_member.clone(),
             input.identity.group_memberships.clone(),
+            input.identity.compression.clone(),
+            input.identity.encodings.clone(),
             input.settings.summary_request_timeout,
         );
         let summary_request_manager = system.create(move || summary_request_manager);
@@ -711,6 +783,14 @@ impl RuntimeLogicTopology {
             "reliable delivery -> summary request manager",
         )
     }
+
+    fn connect_discovery(&self, discovery: &DiscoveryTopology) -> Result<(), RuntimeHostError> {
+        connect_components::<PeerLivenessPort, _, _>(
+            discovery.route_discovery_provider(),
+            &self.runtime_component,
+            "peer liveness -> replication runtime",
+        )
+    }
 }
 
 impl ComponentTopology for RuntimeLogicTopology {
@@ -749,10 +829,11 @@ pub(in crate::runtime::host) struct RuntimeTopologyBuildInput {
     pub(in crate::runtime::host) local_member: MemberIdentity,
     pub(in crate::runtime::host) store: Arc<dyn ReplicationStore>,
     pub(in crate::runtime::host) listener: Arc<dyn ReplicationEventListener>,
+    pub(in crate::runtime::host) workspace_events: WorkspaceEvents,
     pub(in crate::runtime::host) config: ReplicationConfig,
     pub(in crate::runtime::host) security: DeliverySecurity,
     pub(in crate::runtime::host) host_config: DeliveryRuntimeHostConfig,
-    pub(in crate::runtime::host) static_route_hints: PreconfiguredPeerRoutesConfig,
+    pub(in crate::runtime::host) discovery_settings: DiscoverySettings,
 }
 
 impl RuntimeTopology {
@@ -773,10 +854,13 @@ impl RuntimeTopology {
         let identity = RuntimeIdentityContext {
             local_member: input.local_member.clone(),
             group_memberships: input.group_memberships.clone(),
+            compression: SharedRuntimeCompression::new(input.config.compression),
+            encodings: SharedRuntimeEncodings::new(input.config.preferred_encoding),
         };
         let services = RuntimeApplicationServices {
             store: input.store,
             listener: input.listener,
+            workspace_events: input.workspace_events,
             config: input.config,
         };
         let security = RuntimeSecurityContext {
@@ -796,7 +880,7 @@ impl RuntimeTopology {
             input.local_member.clone(),
             input.security.clone(),
             discovery_transport_handles,
-            input.static_route_hints,
+            input.discovery_settings,
         );
         let runtime = RuntimeLogicTopology::build(
             system,
@@ -825,6 +909,7 @@ impl RuntimeTopology {
         self.delivery.connect_internal_routes()?;
         self.discovery.connect_internal_routes()?;
         self.delivery.connect_discovery(&self.discovery)?;
+        self.runtime.connect_discovery(&self.discovery)?;
         self.runtime.connect_delivery(&self.delivery)
     }
 
@@ -856,6 +941,35 @@ impl RuntimeTopology {
         self.io.stop_all(system, control_timeout).await?;
         Ok(())
     }
+
+    /// Stop the discovery components so no new peers, routes, or sessions are found.
+    pub(in crate::runtime::host) async fn stop_discovery(
+        &self,
+        system: &KompactSystem,
+        control_timeout: Duration,
+    ) -> Result<(), RuntimeHostError> {
+        self.discovery.stop_all(system, control_timeout).await
+    }
+
+    /// Stop the runtime logic, which runs the sync sessions, and then delivery.
+    pub(in crate::runtime::host) async fn stop_sync(
+        &self,
+        system: &KompactSystem,
+        control_timeout: Duration,
+    ) -> Result<(), RuntimeHostError> {
+        self.runtime.stop_all(system, control_timeout).await?;
+        self.delivery.stop_all(system, control_timeout).await
+    }
+
+    /// Stop transport and I/O, which releases the sockets.
+    pub(in crate::runtime::host) async fn stop_transport(
+        &self,
+        system: &KompactSystem,
+        control_timeout: Duration,
+    ) -> Result<(), RuntimeHostError> {
+        self.transport.stop_all(system, control_timeout).await?;
+        self.io.stop_all(system, control_timeout).await
+    }
 }
 
 impl ComponentTopology for RuntimeTopology {
@@ -65,7 +65,8 @@ struct LocalStoredStateRow {
 #[derive(Clone)]
 pub(super) struct LoadedGroupMeta {
     pub(super) members: GroupMembers,
-    pub(super) local_member_index: MemberIndex,
+    /// Position of the local member, or `None` when it only observes this group.
+    pub(super) local_member_index: Option<MemberIndex>,
     pub(super) version_vector: VersionVector,
 }
 
@@ -80,12 +81,13 @@ impl LoadedGroupMeta {
             .member_keys
             .to_group_members()
             .context(InvalidPersistedMembersSnafu { group_id })?;
-        let local_member_index =
-            members
-                .member_index(local_member)
-                .context(InstallMissingLocalMemberSnafu {
-                    local_member: local_member.clone(),
-                })?;
+        ensure!(
+            members.contains(local_member),
+            InstallMissingLocalMemberSnafu {
+                local_member: local_member.clone(),
+            }
+        );
+        let local_member_index = members.member_index(local_member);
         ensure!(
             local_member_index == group.local_member_index,
             PersistedLocalMemberIndexMismatchSnafu {
@@ -290,6 +292,10 @@ impl LocalDataset {
         self.data.row_is_tombstoned(&row_key.0)
     }
 
+    fn row_node_count(&self, row_key: RowKey) -> Option<usize> {
+        self.data.get_row(&row_key.0).map(|row| row.node_count())
+    }
+
     pub(super) fn clone_value_row(&self, row_key: RowKey) -> Option<RowValues> {
         let row = self.data.get_row(&row_key.0)?;
         Some(
@@ -298,6 +304,17 @@ impl LocalDataset {
         )
     }
 
+    /// Return the keys of all rows that are not tombstoned, in ascending order.
+    pub(super) fn active_row_keys(&self) -> Vec<RowKey> {
+        let mut row_keys = self
+            .data
+            .active_row_ids()
+            .map(|row_id| RowKey(*row_id))
+            .collect::<Vec<_>>();
+        row_keys.sort_unstable();
+        row_keys
+    }
+
     /// Snapshot the current row image for explicit durable row writes.
     pub(super) fn snapshot_row(&self, row_key: RowKey) -> Option<ReplicationRowStateSnapshot> {
         self.data.get_row(&row_key.0).map(|row| row.snapshot())
@@ -500,10 +517,13 @@ struct AppliedRemoteOperation {
 /// All touched datasets are first materialised into working copies so the batch
 /// either commits atomically into local state or returns an error without
 /// partially replacing dataset maps.
+///
+/// Fails if any touched row would end up with more than `max_document_nodes` CRDT nodes.
 pub(super) fn apply_one_update(
     group: &mut LoadedGroupMeta,
     working_datasets: &mut HashMap<DatasetId, LocalDataset>,
     update: &ReplicationUpdateRecord,
+    max_document_nodes: usize,
 ) -> Result<AppliedInboundBatch, InboundDeliveryError> {
     let mut row_changes = Vec::new();
     let mut row_patches = Vec::new();
@@ -521,6 +541,7 @@ pub(super) fn apply_one_update(
                 update.group_id,
                 &dataset_update.dataset_id,
                 operation,
+                max_document_nodes,
             )?;
             if let Some(row_change) = applied_operation.row_change {
                 row_changes.push(row_change);
@@ -772,6 +793,7 @@ fn apply_remote_operation(
     group_id: GroupId,
     dataset_id: &DatasetId,
     operation: flotsync_messages::SchemaOperation<'_>,
+    max_document_nodes: usize,
 ) -> Result<AppliedRemoteOperation, InboundDeliveryError> {
     let api_row_id = match &operation.operation {
         RowOperation::Insert { row_id, .. }
@@ -796,6 +818,19 @@ fn apply_remote_operation(
         .context(inbound::ApplyInboundMutationSnafu {
             row_id: api_row_id.clone(),
         })?;
+    let node_count = dataset
+        .row_node_count(api_row_id.row_key)
+        .unwrap_or_else(|| {
+            panic!("applied inbound operation must leave row {api_row_id} readable")
+        });
+    ensure!(
+        node_count <= max_document_nodes,
+        inbound::DocumentTooLargeSnafu {
+            row_id: api_row_id,
+            node_count,
+            limit: max_document_nodes,
+        }
+    );
 
     let stored_row = dataset.stored_row(api_row_id.row_key).unwrap_or_else(|| {
         panic!("applied inbound operation must leave row {api_row_id} snapshotable")
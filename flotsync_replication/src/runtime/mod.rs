@@ -1,13 +1,77 @@
 //! Replication runtime component, host, and runtime protocol support.
 
-use kompact::{config::UsizeValue, kompact_config};
+use crate::api::RuntimeLimits;
+use flotsync_utils::config::{ConfigValidationError, FlotsyncConfig, FromFlotsyncConfig};
+use kompact::{
+    config::{DurationValue, StringValue, UsizeValue},
+    kompact_config,
+};
+use std::time::Duration;
 
 /// Default maximum group size for inlining public key bundles in bootstrap messages.
 pub const DEFAULT_MAX_INLINE_BOOTSTRAP_PUBLIC_KEY_BUNDLES: usize = 10;
 
+/// Default maximum encoded size of one runtime message payload, in bytes.
+pub const DEFAULT_MAX_RUNTIME_PAYLOAD_BYTES: usize = 16 * 1024 * 1024;
+
+/// Default maximum number of members in one replication group.
+pub const DEFAULT_MAX_GROUP_MEMBERS: usize = 1024;
+
+/// Default maximum number of CRDT nodes stored for one row.
+pub const DEFAULT_MAX_DOCUMENT_NODES: usize = 1024 * 1024;
+
+/// Default maximum size of one blob fetched from a peer, in bytes.
+pub const DEFAULT_MAX_BLOB_BYTES: usize = 1024 * 1024 * 1024;
+
+/// Default time to wait for a peer to send every chunk of a requested blob.
+pub const DEFAULT_BLOB_FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default maximum encoded size of initial rows carried inline in a migration, in bytes.
+pub const DEFAULT_MAX_INLINE_SNAPSHOT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Default time to wait for a peer to answer one snapshot manifest or chunk request.
+pub const DEFAULT_SNAPSHOT_FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default number of snapshot requests sent to a peer before a snapshot fetch fails.
+pub const DEFAULT_SNAPSHOT_FETCH_ATTEMPTS: usize = 5;
+
+/// Default time a streamed initial snapshot stays available to its recipients.
+pub const DEFAULT_SNAPSHOT_SERVE_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Default interval between sync scheduling passes.
+pub const DEFAULT_SYNC_SCHEDULER_TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default interval between checks whether a running sync step has finished.
+pub const DEFAULT_SYNC_STEP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Default number of unseen local updates that makes merging a remote update conflict-heavy.
+pub const DEFAULT_CONFLICT_HEAVY_MERGE_THRESHOLD: usize = 16;
+
+/// Default number of rejected inbound group broadcasts kept for inspection and retry.
+pub const DEFAULT_QUARANTINE_CAPACITY: usize = 256;
+
 /// Kompact configuration keys consumed by the replication runtime.
 pub mod config_keys {
-    use super::{DEFAULT_MAX_INLINE_BOOTSTRAP_PUBLIC_KEY_BUNDLES, UsizeValue, kompact_config};
+    use super::{
+        DEFAULT_BLOB_FETCH_TIMEOUT,
+        DEFAULT_CONFLICT_HEAVY_MERGE_THRESHOLD,
+        DEFAULT_MAX_BLOB_BYTES,
+        DEFAULT_MAX_DOCUMENT_NODES,
+        DEFAULT_MAX_GROUP_MEMBERS,
+        DEFAULT_MAX_INLINE_BOOTSTRAP_PUBLIC_KEY_BUNDLES,
+        DEFAULT_MAX_INLINE_SNAPSHOT_BYTES,
+        DEFAULT_MAX_RUNTIME_PAYLOAD_BYTES,
+        DEFAULT_QUARANTINE_CAPACITY,
+        DEFAULT_SNAPSHOT_FETCH_ATTEMPTS,
+        DEFAULT_SNAPSHOT_FETCH_TIMEOUT,
+        DEFAULT_SNAPSHOT_SERVE_RETENTION,
+        DEFAULT_SYNC_SCHEDULER_TICK_INTERVAL,
+        DEFAULT_SYNC_STEP_CHECK_INTERVAL,
+        DurationValue,
+        StringValue,
+        UsizeValue,
+        kompact_config,
+    };
 
     kompact_config! {
         BOOTSTRAP_MAX_INLINE_PUBLIC_KEY_BUNDLES,
@@ -17,18 +81,169 @@ pub mod config_keys {
         doc = "Maximum group size for including inline public key bundles in bootstrap messages. Larger groups carry fingerprints only. Set to 0 to always elide inline bundles.",
         version = "0.1.0"
     }
+
+    kompact_config! {
+        LIMITS_MAX_RUNTIME_PAYLOAD_BYTES,
+        key = "flotsync.replication.runtime.limits.max-payload-bytes",
+        type = UsizeValue,
+        default = DEFAULT_MAX_RUNTIME_PAYLOAD_BYTES,
+        doc = "Maximum encoded size of one runtime message payload. Larger inbound payloads are dropped before decoding, and local publishes that would exceed it are rejected.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        LIMITS_MAX_GROUP_MEMBERS,
+        key = "flotsync.replication.runtime.limits.max-group-members",
+        type = UsizeValue,
+        default = DEFAULT_MAX_GROUP_MEMBERS,
+        doc = "Maximum number of members in one replication group. Applies to locally created groups, membership changes, and inbound group setups.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        LIMITS_MAX_DOCUMENT_NODES,
+        key = "flotsync.replication.runtime.limits.max-document-nodes",
+        type = UsizeValue,
+        default = DEFAULT_MAX_DOCUMENT_NODES,
+        doc = "Maximum number of CRDT nodes stored for one row, counting deleted elements and every value ever written. Inbound updates that would grow a row beyond it are dropped.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        LIMITS_MAX_BLOB_BYTES,
+        key = "flotsync.replication.runtime.limits.max-blob-bytes",
+        type = UsizeValue,
+        default = DEFAULT_MAX_BLOB_BYTES,
+        doc = "Maximum size of one blob fetched from a peer, in bytes. Fetches of larger blobs fail before any of their chunks are kept.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        BLOBS_FETCH_TIMEOUT,
+        key = "flotsync.replication.runtime.blobs.fetch-timeout",
+        type = DurationValue,
+        default = DEFAULT_BLOB_FETCH_TIMEOUT,
+        doc = "Time to wait for a peer to send every chunk of a requested blob before the fetch fails.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        SNAPSHOTS_MAX_INLINE_BYTES,
+        key = "flotsync.replication.runtime.snapshots.max-inline-bytes",
+        type = UsizeValue,
+        default = DEFAULT_MAX_INLINE_SNAPSHOT_BYTES,
+        doc = "Maximum encoded size of the initial rows carried inline in migration proposals and invitations. Larger snapshots are announced as metadata and streamed to recipients in chunks. Keep it well below flotsync.replication.runtime.limits.max-payload-bytes.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        SNAPSHOTS_FETCH_TIMEOUT,
+        key = "flotsync.replication.runtime.snapshots.fetch-timeout",
+        type = DurationValue,
+        default = DEFAULT_SNAPSHOT_FETCH_TIMEOUT,
+        doc = "Time to wait for a peer to answer one snapshot manifest or chunk request. On expiry only the chunks still missing are requested again.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        SNAPSHOTS_FETCH_ATTEMPTS,
+        key = "flotsync.replication.runtime.snapshots.fetch-attempts",
+        type = UsizeValue,
+        default = DEFAULT_SNAPSHOT_FETCH_ATTEMPTS,
+        doc = "Number of snapshot requests sent to a peer without an answer before the snapshot fetch fails and the invitation or proposal is dropped.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        SNAPSHOTS_SERVE_RETENTION,
+        key = "flotsync.replication.runtime.snapshots.serve-retention",
+        type = DurationValue,
+        default = DEFAULT_SNAPSHOT_SERVE_RETENTION,
+        doc = "Time a streamed initial snapshot stays available to the recipients of its migration. Streamed snapshots are only kept in memory, so they also become unavailable on restart.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        OUTBOX_DIRECTORY,
+        key = "flotsync.replication.runtime.outbox.directory",
+        type = StringValue,
+        default = String::new(),
+        doc = "Directory that keeps locally published updates until every group member has acknowledged them, one subdirectory per group. Pending updates are broadcast again when a member reconnects. Leave empty to rely on catch-up alone.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        SYNC_SCHEDULER_TICK_INTERVAL,
+        key = "flotsync.replication.runtime.sync-scheduler.tick-interval",
+        type = DurationValue,
+        default = DEFAULT_SYNC_SCHEDULER_TICK_INTERVAL,
+        doc = "Interval between passes that start due background sync sessions. Bounds how late debounced, periodic, and network-change sessions may start.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        SYNC_STEP_CHECK_INTERVAL,
+        key = "flotsync.replication.runtime.sync-step.check-interval",
+        type = DurationValue,
+        default = DEFAULT_SYNC_STEP_CHECK_INTERVAL,
+        doc = "Interval between checks whether a caller-driven sync step has caught up or spent its budget. Bounds how late a sync step returns.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        WORKSPACE_EVENTS_CONFLICT_HEAVY_MERGE_THRESHOLD,
+        key = "flotsync.replication.runtime.workspace-events.conflict-heavy-merge-threshold",
+        type = UsizeValue,
+        default = DEFAULT_CONFLICT_HEAVY_MERGE_THRESHOLD,
+        doc = "Minimum number of locally applied updates a merged remote update must not have seen to be reported as a conflict-heavy merge. Set to 0 to report every concurrent merge.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        QUARANTINE_CAPACITY,
+        key = "flotsync.replication.runtime.quarantine.capacity",
+        type = UsizeValue,
+        default = DEFAULT_QUARANTINE_CAPACITY,
+        doc = "Maximum number of rejected inbound group broadcasts kept in memory for inspection and retry. The oldest one is dropped when a new one is rejected. Set to 0 to drop rejected broadcasts immediately.",
+        version = "0.1.0"
+    }
+}
+
+impl FromFlotsyncConfig for RuntimeLimits {
+    type Error = ConfigValidationError;
+
+    fn from_flotsync_config(config: &FlotsyncConfig) -> Result<Self, Self::Error> {
+        let max_payload_bytes = config.read(&config_keys::LIMITS_MAX_RUNTIME_PAYLOAD_BYTES)?;
+        let max_group_members = config.read(&config_keys::LIMITS_MAX_GROUP_MEMBERS)?;
+        let max_document_nodes = config.read(&config_keys::LIMITS_MAX_DOCUMENT_NODES)?;
+        let max_blob_bytes = config.read(&config_keys::LIMITS_MAX_BLOB_BYTES)?;
+        Ok(Self {
+            max_payload_bytes,
+            max_group_members,
+            max_document_nodes,
+            max_blob_bytes,
+        })
+    }
 }
 
+mod acknowledgements;
 mod catch_up_manager;
 mod component;
+mod compression;
+mod encodings;
 mod errors;
 pub mod handle;
 pub(crate) mod host;
 mod in_memory;
+mod outbox;
 mod pending_group;
+mod quarantine;
+mod rate_limit;
 mod replay;
 mod store_security_validation;
 mod summary_request_manager;
+mod sync_scheduler;
+mod sync_step;
 
 pub use component::{ReplicationRuntimeComponent, ReplicationRuntimeMessage};
 pub(crate) use errors::BoxedError;
@@ -0,0 +1,458 @@
+//! On-disk queue of locally produced updates that not every member has acknowledged yet.
+//!
+//! Devices that are offline most of the time must keep their own updates around until every
+//! other member of the group has acknowledged them, so they can be re-sent whenever a peer is
+//! reachable again. The [`Outbox`] keeps each encoded batch in its own file and tracks, per
+//! member, the latest local version that member has acknowledged through its version vector.
+//! A batch is only dropped once all members have acknowledged it.
+
+use flotsync_core::versions::{UpdateId, VersionVector};
+use snafu::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+
+/// File storing the acknowledged local version of every member, one per line.
+const ACKNOWLEDGED_FILE_NAME: &str = "acknowledged";
+/// Suffix of the files holding one encoded batch each, named after the batch's version.
+const BATCH_FILE_SUFFIX: &str = ".batch";
+/// Suffix of files that are still being written and replace their target once complete.
+const TEMP_FILE_SUFFIX: &str = ".flotsync-tmp";
+
+/// Errors while reading or updating an [`Outbox`].
+#[derive(Debug, Snafu)]
+pub(super) enum OutboxError {
+    #[snafu(display("Could not access {}.", path.display()))]
+    OutboxIo { path: PathBuf, source: io::Error },
+    #[snafu(display("The outbox state in {} is corrupt: {explanation}", path.display()))]
+    CorruptOutbox { path: PathBuf, explanation: String },
+    #[snafu(display("The outbox is for {expected} members, but {actual} were given."))]
+    OutboxMemberCountMismatch {
+        expected: NonZeroUsize,
+        actual: NonZeroUsize,
+    },
+    #[snafu(display("Member {member_index} is outside of the group."))]
+    UnknownOutboxMember { member_index: u32 },
+    #[snafu(display(
+        "Update {update_id} was not produced by the local member {local_member_index}."
+    ))]
+    ForeignUpdate {
+        update_id: UpdateId,
+        local_member_index: u32,
+    },
+    #[snafu(display(
+        "Update {update_id} does not come after the last queued version {last_version}."
+    ))]
+    OutOfOrderUpdate {
+        update_id: UpdateId,
+        last_version: u64,
+    },
+}
+
+/// On-disk storage for locally produced batches until all members acknowledged them.
+///
+/// Batches are opaque bytes, e.g. encoded update payloads, and must be pushed in the order of
+/// their versions.
+#[derive(Debug)]
+pub(super) struct Outbox {
+    directory: PathBuf,
+    local_member_index: u32,
+    /// The latest local version each member has acknowledged, indexed by member.
+    acknowledged: Vec<u64>,
+    pending: BTreeMap<u64, Vec<u8>>,
+    last_version: u64,
+}
+impl Outbox {
+    /// Open the outbox stored in `directory`, creating it if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `local_member_index` is outside of the group, or if the directory cannot be read
+    /// or contains an outbox for a different group size.
+    pub(super) fn open(
+        directory: impl Into<PathBuf>,
+        num_members: NonZeroUsize,
+        local_member_index: u32,
+    ) -> Result<Self, OutboxError> {
+        ensure!(
+            (local_member_index as usize) < num_members.get(),
+            UnknownOutboxMemberSnafu {
+                member_index: local_member_index,
+            }
+        );
+        let directory = directory.into();
+        fs::create_dir_all(&directory).context(OutboxIoSnafu { path: &directory })?;
+        let acknowledged = read_acknowledged(&directory, num_members)?;
+        let pending = read_batches(&directory)?;
+        let mut outbox = Self {
+            directory,
+            local_member_index,
+            acknowledged,
+            pending,
+            last_version: 0,
+        };
+        outbox.last_version = outbox
+            .pending
+            .last_key_value()
+            .map_or(0, |(version, _)| *version)
+            .max(outbox.remote_acknowledged_versions().min().unwrap_or(0));
+        Ok(outbox)
+    }
+
+    /// Returns `true` if every queued batch has been acknowledged by all members.
+    pub(super) fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// The number of batches that some member has not acknowledged yet.
+    pub(super) fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Queue the encoded batch of the local update `update_id`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `update_id` was not produced locally, does not come after the previously queued
+    /// update, or the batch cannot be written.
+    pub(super) fn push(&mut self, update_id: UpdateId, batch: Vec<u8>) -> Result<(), OutboxError> {
+        ensure!(
+            update_id.node_index == self.local_member_index,
+            ForeignUpdateSnafu {
+                update_id,
+                local_member_index: self.local_member_index,
+            }
+        );
+        ensure!(
+            update_id.version > self.last_version,
+            OutOfOrderUpdateSnafu {
+                update_id,
+                last_version: self.last_version,
+            }
+        );
+        self.last_version = update_id.version;
+        if self.remote_acknowledged_versions().next().is_none() {
+            // Nobody else needs to see it.
+            return Ok(());
+        }
+        let path = batch_path(&self.directory, update_id.version);
+        write_atomically(&path, &batch)?;
+        self.pending.insert(update_id.version, batch);
+        Ok(())
+    }
+
+    /// Record that `member_index` has applied everything covered by `vector`, and drop all batches
+    /// that every member has acknowledged now.
+    ///
+    /// Acknowledgements never move backwards, so stale vectors are harmless.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `member_index` or `vector` don't belong to the group, or the outbox cannot be
+    /// updated.
+    pub(super) fn acknowledge(
+        &mut self,
+        member_index: u32,
+        vector: &VersionVector,
+    ) -> Result<(), OutboxError> {
+        let expected = self.num_members();
+        let actual = vector.num_members();
+        ensure!(
+            expected == actual,
+            OutboxMemberCountMismatchSnafu { expected, actual }
+        );
+        let acknowledged = self
+            .acknowledged
+            .get_mut(member_index as usize)
+            .context(UnknownOutboxMemberSnafu { member_index })?;
+        let version = vector.version_at(self.local_member_index as usize);
+        if version <= *acknowledged {
+            return Ok(());
+        }
+        *acknowledged = version;
+        let content: String = self
+            .acknowledged
+            .iter()
+            .map(|version| format!("{version}\n"))
+            .collect();
+        write_atomically(
+            &self.directory.join(ACKNOWLEDGED_FILE_NAME),
+            content.as_bytes(),
+        )?;
+
+        let fully_acknowledged = self
+            .remote_acknowledged_versions()
+            .min()
+            .unwrap_or(u64::MAX);
+        while let Some(entry) = self.pending.first_entry() {
+            if *entry.key() > fully_acknowledged {
+                break;
+            }
+            let path = batch_path(&self.directory, *entry.key());
+            fs::remove_file(&path).context(OutboxIoSnafu { path })?;
+            entry.remove();
+        }
+        Ok(())
+    }
+
+    /// All batches `member_index` has not acknowledged yet, in version order.
+    ///
+    /// These must be re-sent once `member_index` is reachable again.
+    pub(super) fn pending_for(&self, member_index: u32) -> impl Iterator<Item = (UpdateId, &[u8])> {
+        let acknowledged = if member_index == self.local_member_index {
+            u64::MAX
+        } else {
+            self.acknowledged
+                .get(member_index as usize)
+                .copied()
+                .unwrap_or(u64::MAX)
+        };
+        self.pending
+            .range(acknowledged.saturating_add(1)..)
+            .map(|(version, batch)| {
+                let update_id = UpdateId {
+                    version: *version,
+                    node_index: self.local_member_index,
+                };
+                (update_id, batch.as_slice())
+            })
+    }
+
+    /// All batches that some member has not acknowledged yet, in version order.
+    #[cfg(test)]
+    fn pending(&self) -> impl Iterator<Item = (UpdateId, &[u8])> {
+        self.pending.iter().map(|(version, batch)| {
+            let update_id = UpdateId {
+                version: *version,
+                node_index: self.local_member_index,
+            };
+            (update_id, batch.as_slice())
+        })
+    }
+
+    fn num_members(&self) -> NonZeroUsize {
+        NonZeroUsize::new(self.acknowledged.len()).expect("groups are never empty")
+    }
+
+    fn remote_acknowledged_versions(&self) -> impl Iterator<Item = u64> {
+        self.acknowledged
+            .iter()
+            .enumerate()
+            .filter(|(member_index, _)| *member_index != self.local_member_index as usize)
+            .map(|(_, version)| *version)
+    }
+}
+
+fn batch_path(directory: &Path, version: u64) -> PathBuf {
+    directory.join(format!("{version:020}{BATCH_FILE_SUFFIX}"))
+}
+
+fn read_acknowledged(directory: &Path, num_members: NonZeroUsize) -> Result<Vec<u64>, OutboxError> {
+    let path = directory.join(ACKNOWLEDGED_FILE_NAME);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Ok(vec![0; num_members.get()]);
+        }
+        Err(error) => return Err(error).context(OutboxIoSnafu { path }),
+    };
+    let acknowledged = content
+        .lines()
+        .map(str::parse::<u64>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| {
+            CorruptOutboxSnafu {
+                path: &path,
+                explanation: error.to_string(),
+            }
+            .build()
+        })?;
+    let actual = NonZeroUsize::new(acknowledged.len()).context(CorruptOutboxSnafu {
+        path: &path,
+        explanation: "no members",
+    })?;
+    ensure!(
+        actual == num_members,
+        OutboxMemberCountMismatchSnafu {
+            expected: actual,
+            actual: num_members,
+        }
+    );
+    Ok(acknowledged)
+}
+
+fn read_batches(directory: &Path) -> Result<BTreeMap<u64, Vec<u8>>, OutboxError> {
+    let mut batches = BTreeMap::new();
+    let entries = fs::read_dir(directory).context(OutboxIoSnafu { path: directory })?;
+    for entry in entries {
+        let entry = entry.context(OutboxIoSnafu { path: directory })?;
+        let name = entry.file_name();
+        // Leftover temp files were never completely written, so they were never queued.
+        let Some(version) = name
+            .to_str()
+            .and_then(|name| name.strip_suffix(BATCH_FILE_SUFFIX))
+        else {
+            continue;
+        };
+        let path = entry.path();
+        let version = version.parse().map_err(|_| {
+            CorruptOutboxSnafu {
+                path: &path,
+                explanation: "batch file name is not a version",
+            }
+            .build()
+        })?;
+        let batch = fs::read(&path).context(OutboxIoSnafu { path })?;
+        batches.insert(version, batch);
+    }
+    Ok(batches)
+}
+
+/// Write `content` to `path` such that it either fully exists afterwards or not at all, even if
+/// the process crashes.
+fn write_atomically(path: &Path, content: &[u8]) -> Result<(), OutboxError> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(TEMP_FILE_SUFFIX);
+    let temp_path = path.with_file_name(temp_name);
+    let mut file = fs::File::create(&temp_path).context(OutboxIoSnafu { path: &temp_path })?;
+    file.write_all(content)
+        .and_then(|()| file.sync_all())
+        .context(OutboxIoSnafu { path: &temp_path })?;
+    fs::rename(&temp_path, path).context(OutboxIoSnafu { path })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const THREE_MEMBERS: NonZeroUsize = NonZeroUsize::new(3).unwrap();
+
+    struct TestDir(PathBuf);
+    impl TestDir {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "flotsync-outbox-{}",
+                Uuid::new_v4().as_hyphenated()
+            ));
+            Self(path)
+        }
+    }
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn local_update(version: u64) -> UpdateId {
+        UpdateId {
+            version,
+            node_index: 1,
+        }
+    }
+
+    fn pending_versions<'a>(batches: impl Iterator<Item = (UpdateId, &'a [u8])>) -> Vec<u64> {
+        batches.map(|(update_id, _)| update_id.version).collect()
+    }
+
+    #[test]
+    fn batches_are_kept_until_every_member_acknowledged_them() {
+        let dir = TestDir::new();
+        let mut outbox = Outbox::open(&dir.0, THREE_MEMBERS, 1).unwrap();
+        for version in 1..=3 {
+            outbox
+                .push(local_update(version), vec![version as u8])
+                .unwrap();
+        }
+        assert_eq!(pending_versions(outbox.pending_for(0)), vec![1, 2, 3]);
+        assert!(outbox.pending_for(1).next().is_none());
+
+        outbox
+            .acknowledge(0, &VersionVector::from_entries([4, 2, 0]))
+            .unwrap();
+        assert_eq!(pending_versions(outbox.pending_for(0)), vec![3]);
+        assert_eq!(pending_versions(outbox.pending_for(2)), vec![1, 2, 3]);
+        assert_eq!(outbox.len(), 3);
+
+        outbox
+            .acknowledge(2, &VersionVector::from_entries([0, 3, 1]))
+            .unwrap();
+        assert_eq!(pending_versions(outbox.pending()), vec![3]);
+        // Stale acknowledgements don't resurrect anything.
+        outbox
+            .acknowledge(0, &VersionVector::from_entries([0, 0, 0]))
+            .unwrap();
+        assert_eq!(pending_versions(outbox.pending_for(0)), vec![3]);
+
+        outbox
+            .acknowledge(0, &VersionVector::from_entries([5, 3, 1]))
+            .unwrap();
+        assert!(outbox.is_empty());
+    }
+
+    #[test]
+    fn pending_batches_survive_reopening() {
+        let dir = TestDir::new();
+        {
+            let mut outbox = Outbox::open(&dir.0, THREE_MEMBERS, 1).unwrap();
+            outbox.push(local_update(1), b"first".to_vec()).unwrap();
+            outbox.push(local_update(2), b"second".to_vec()).unwrap();
+            outbox
+                .acknowledge(2, &VersionVector::from_entries([0, 1, 0]))
+                .unwrap();
+        }
+
+        let mut outbox = Outbox::open(&dir.0, THREE_MEMBERS, 1).unwrap();
+        assert_eq!(
+            outbox.pending_for(0).collect::<Vec<_>>(),
+            vec![
+                (local_update(1), b"first".as_slice()),
+                (local_update(2), b"second".as_slice())
+            ]
+        );
+        assert_eq!(pending_versions(outbox.pending_for(2)), vec![2]);
+        assert!(matches!(
+            outbox.push(local_update(2), Vec::new()),
+            Err(OutboxError::OutOfOrderUpdate { .. })
+        ));
+        outbox.push(local_update(3), Vec::new()).unwrap();
+        assert_eq!(outbox.len(), 3);
+
+        assert!(matches!(
+            Outbox::open(&dir.0, NonZeroUsize::new(2).unwrap(), 1),
+            Err(OutboxError::OutboxMemberCountMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_updates_and_members_are_rejected() {
+        let dir = TestDir::new();
+        let mut outbox = Outbox::open(&dir.0, THREE_MEMBERS, 1).unwrap();
+        let foreign = UpdateId {
+            version: 1,
+            node_index: 0,
+        };
+        assert!(matches!(
+            outbox.push(foreign, Vec::new()),
+            Err(OutboxError::ForeignUpdate { .. })
+        ));
+        assert!(matches!(
+            outbox.acknowledge(3, &VersionVector::initial(THREE_MEMBERS)),
+            Err(OutboxError::UnknownOutboxMember { member_index: 3 })
+        ));
+        assert!(matches!(
+            outbox.acknowledge(0, &VersionVector::initial(NonZeroUsize::MIN)),
+            Err(OutboxError::OutboxMemberCountMismatch { .. })
+        ));
+        assert!(outbox.is_empty());
+        assert!(matches!(
+            Outbox::open(&dir.0, THREE_MEMBERS, 3),
+            Err(OutboxError::UnknownOutboxMember { member_index: 3 })
+        ));
+    }
+}
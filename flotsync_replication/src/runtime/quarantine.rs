@@ -0,0 +1,217 @@
+//! Bounded in-memory quarantine of rejected inbound group broadcasts.
+//!
+//! See [`QuarantinedDelivery`] for which deliveries the runtime component keeps here. Once the
+//! quarantine is full, every new delivery evicts the oldest one, so a peer that keeps sending
+//! invalid messages cannot grow it without bound.
+
+use crate::{
+    api::{QuarantineId, QuarantinedDelivery},
+    delivery::{
+        group_broadcast::{GroupBroadcastDeliver, GroupMessageEnvelope, GroupMessageHeader},
+        shared::PlaintextPayload,
+    },
+};
+use std::{collections::VecDeque, time::SystemTime};
+
+/// Where one rejected delivery ended up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Quarantined {
+    pub(super) id: QuarantineId,
+    /// The oldest delivery, which was dropped to make room, if the quarantine was full.
+    pub(super) evicted: Option<QuarantineId>,
+}
+
+/// The most recently rejected group broadcasts, oldest first.
+#[derive(Debug)]
+pub(super) struct DeliveryQuarantine {
+    capacity: usize,
+    next_id: u64,
+    entries: VecDeque<QuarantineEntry>,
+}
+
+impl DeliveryQuarantine {
+    /// Keep at most `capacity` deliveries, or none for `0`.
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_id: 0,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Keep the delivery of `payload` under `header`, rejected at `now` because of `reason`.
+    ///
+    /// Returns `None` if the quarantine is disabled.
+    pub(super) fn insert(
+        &mut self,
+        header: GroupMessageHeader,
+        payload: PlaintextPayload,
+        reason: String,
+        now: SystemTime,
+    ) -> Option<Quarantined> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let evicted = if self.entries.len() >= self.capacity {
+            self.entries.pop_front().map(|entry| entry.delivery.id)
+        } else {
+            None
+        };
+        let id = QuarantineId(self.next_id);
+        self.next_id += 1;
+        let delivery = QuarantinedDelivery {
+            id,
+            group_id: header.group_id,
+            sender: header.sender,
+            message_id: header.message_id,
+            reason,
+            payload_bytes: payload.bytes.len(),
+            quarantined_at: now,
+        };
+        self.entries
+            .push_back(QuarantineEntry { delivery, payload });
+        Some(Quarantined { id, evicted })
+    }
+
+    /// Describe every quarantined delivery, oldest first.
+    pub(super) fn deliveries(&self) -> Vec<QuarantinedDelivery> {
+        self.entries
+            .iter()
+            .map(|entry| entry.delivery.clone())
+            .collect()
+    }
+
+    /// Remove the delivery with `id` and rebuild it as it was originally delivered.
+    pub(super) fn take(&mut self, id: QuarantineId) -> Option<GroupBroadcastDeliver> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.delivery.id == id)?;
+        let entry = self.entries.remove(index)?;
+        Some(entry.into_deliver())
+    }
+}
+
+/// One quarantined delivery together with the payload needed to hand it over again.
+#[derive(Debug)]
+struct QuarantineEntry {
+    delivery: QuarantinedDelivery,
+    payload: PlaintextPayload,
+}
+
+impl QuarantineEntry {
+    fn into_deliver(self) -> GroupBroadcastDeliver {
+        GroupBroadcastDeliver {
+            envelope: GroupMessageEnvelope {
+                header: GroupMessageHeader {
+                    group_id: self.delivery.group_id,
+                    sender: self.delivery.sender,
+                    message_id: self.delivery.message_id,
+                },
+                payload: self.payload,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::shared::MessageId;
+    use bytes::Bytes;
+    use flotsync_core::{GroupId, MemberIdentity};
+    use uuid::Uuid;
+
+    fn header(message: u128) -> GroupMessageHeader {
+        GroupMessageHeader {
+            group_id: GroupId(Uuid::from_u128(9)),
+            sender: MemberIdentity::from_array(["test", "bob"]),
+            message_id: MessageId(Uuid::from_u128(message)),
+        }
+    }
+
+    fn payload(bytes: &'static [u8]) -> PlaintextPayload {
+        PlaintextPayload {
+            bytes: Bytes::from_static(bytes),
+        }
+    }
+
+    #[test]
+    fn full_quarantine_evicts_the_oldest_delivery() {
+        let mut quarantine = DeliveryQuarantine::new(2);
+        let first = quarantine
+            .insert(
+                header(1),
+                payload(b"one"),
+                "bad".to_owned(),
+                SystemTime::UNIX_EPOCH,
+            )
+            .expect("quarantine is enabled");
+        let second = quarantine
+            .insert(
+                header(2),
+                payload(b"two"),
+                "bad".to_owned(),
+                SystemTime::UNIX_EPOCH,
+            )
+            .expect("quarantine is enabled");
+        assert_eq!(first.evicted, None);
+        assert_eq!(second.evicted, None);
+
+        let third = quarantine
+            .insert(
+                header(3),
+                payload(b"three"),
+                "worse".to_owned(),
+                SystemTime::UNIX_EPOCH,
+            )
+            .expect("quarantine is enabled");
+        assert_eq!(third.evicted, Some(first.id));
+        let deliveries = quarantine.deliveries();
+        assert_eq!(
+            deliveries
+                .iter()
+                .map(|delivery| delivery.id)
+                .collect::<Vec<_>>(),
+            vec![second.id, third.id]
+        );
+        assert_eq!(deliveries[1].reason, "worse");
+        assert_eq!(deliveries[1].payload_bytes, 5);
+    }
+
+    #[test]
+    fn taken_deliveries_match_the_original() {
+        let mut quarantine = DeliveryQuarantine::new(4);
+        let quarantined = quarantine
+            .insert(
+                header(1),
+                payload(b"one"),
+                "bad".to_owned(),
+                SystemTime::UNIX_EPOCH,
+            )
+            .expect("quarantine is enabled");
+
+        let deliver = quarantine
+            .take(quarantined.id)
+            .expect("delivery is quarantined");
+        assert_eq!(deliver.envelope.header, header(1));
+        assert_eq!(deliver.envelope.payload, payload(b"one"));
+        assert!(quarantine.take(quarantined.id).is_none());
+        assert!(quarantine.deliveries().is_empty());
+    }
+
+    #[test]
+    fn zero_capacity_disables_the_quarantine() {
+        let mut quarantine = DeliveryQuarantine::new(0);
+        assert_eq!(
+            quarantine.insert(
+                header(1),
+                payload(b"one"),
+                "bad".to_owned(),
+                SystemTime::UNIX_EPOCH
+            ),
+            None
+        );
+        assert!(quarantine.deliveries().is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! Per-member token buckets enforcing a [`WriteRateLimit`] on live updates.
+//!
+//! The runtime component asks the limiter before applying each live update from a producer.
+//! Catch-up batches bypass it: the local replica requested them, so it already controls their
+//! pace.
+
+use crate::api::{ThrottleReason, WriteRateLimit};
+use flotsync_core::{GroupId, MemberIdentity};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Why one live update was not admitted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Throttle {
+    pub(super) reason: ThrottleReason,
+    /// How long until an update of the same size would be admitted.
+    pub(super) retry_after: Duration,
+    /// Whether the producer should be told, i.e. whether its previous notice has run out.
+    ///
+    /// Limiting notices to one per back-off period keeps a flooding producer from turning every
+    /// dropped update into a reply.
+    pub(super) notify: bool,
+}
+
+/// Token buckets for every member that sent live updates, per group.
+#[derive(Debug)]
+pub(super) struct WriteRateLimiter {
+    limit: Option<WriteRateLimit>,
+    members: HashMap<(GroupId, MemberIdentity), MemberBuckets>,
+}
+
+impl WriteRateLimiter {
+    /// Enforce `limit`, or admit everything for `None`.
+    pub(super) fn new(limit: Option<WriteRateLimit>) -> Self {
+        Self {
+            limit,
+            members: HashMap::new(),
+        }
+    }
+
+    /// Charge one live update of `operations` schema operations and `bytes` encoded bytes to
+    /// `member` in `group_id`.
+    ///
+    /// Nothing is charged if the update is not admitted.
+    pub(super) fn admit(
+        &mut self,
+        group_id: GroupId,
+        member: &MemberIdentity,
+        operations: u64,
+        bytes: u64,
+        now: Instant,
+    ) -> Result<(), Throttle> {
+        let Some(limit) = self.limit else {
+            return Ok(());
+        };
+        let buckets = self
+            .members
+            .entry((group_id, member.clone()))
+            .or_insert_with(|| MemberBuckets::full(&limit, now));
+        buckets.operations.refill(now);
+        buckets.bytes.refill(now);
+        let operation_wait = buckets.operations.wait_for(operations);
+        let byte_wait = buckets.bytes.wait_for(bytes);
+        if operation_wait.is_zero() && byte_wait.is_zero() {
+            buckets.operations.take(operations);
+            buckets.bytes.take(bytes);
+            return Ok(());
+        }
+        let (reason, retry_after) = if operation_wait >= byte_wait {
+            (ThrottleReason::OperationRate, operation_wait)
+        } else {
+            (ThrottleReason::ByteRate, byte_wait)
+        };
+        let notify = buckets
+            .notified_until
+            .is_none_or(|notified_until| notified_until <= now);
+        if notify {
+            buckets.notified_until = Some(now + retry_after);
+        }
+        Err(Throttle {
+            reason,
+            retry_after,
+            notify,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct MemberBuckets {
+    operations: TokenBucket,
+    bytes: TokenBucket,
+    /// End of the back-off period of the last throttle notice sent to the member.
+    notified_until: Option<Instant>,
+}
+
+impl MemberBuckets {
+    fn full(limit: &WriteRateLimit, now: Instant) -> Self {
+        Self {
+            operations: TokenBucket::full(
+                u64::from(limit.operations_per_second.get()),
+                u64::from(limit.operation_burst.get()),
+                now,
+            ),
+            bytes: TokenBucket::full(limit.bytes_per_second.get(), limit.byte_burst.get(), now),
+            notified_until: None,
+        }
+    }
+}
+
+/// A continuously refilling bucket.
+///
+/// Tokens may go negative when a cost larger than the capacity is taken from a full bucket, so
+/// oversized updates are admitted, but only as often as the rate allows.
+#[derive(Debug)]
+struct TokenBucket {
+    per_second: f64,
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(per_second: u64, capacity: u64, now: Instant) -> Self {
+        Self {
+            per_second: per_second as f64,
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refilled_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.per_second).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// How long until `cost` can be taken, zero if it can be taken now.
+    fn wait_for(&self, cost: u64) -> Duration {
+        let missing = (cost as f64).min(self.capacity) - self.tokens;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.per_second)
+        }
+    }
+
+    fn take(&mut self, cost: u64) {
+        self.tokens -= cost as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::{NonZeroU32, NonZeroU64};
+    use uuid::Uuid;
+
+    const GROUP: GroupId = GroupId(Uuid::from_u128(9));
+
+    fn member(name: &str) -> MemberIdentity {
+        MemberIdentity::from_array(["test", name])
+    }
+
+    fn limiter() -> WriteRateLimiter {
+        WriteRateLimiter::new(Some(WriteRateLimit {
+            operations_per_second: NonZeroU32::new(10).unwrap(),
+            operation_burst: NonZeroU32::new(20).unwrap(),
+            bytes_per_second: NonZeroU64::new(1_000).unwrap(),
+            byte_burst: NonZeroU64::new(4_000).unwrap(),
+        }))
+    }
+
+    #[test]
+    fn bursts_are_admitted_and_then_throttled_until_refilled() {
+        let mut limiter = limiter();
+        let alice = member("alice");
+        let start = Instant::now();
+
+        limiter.admit(GROUP, &alice, 20, 100, start).unwrap();
+        let throttle = limiter.admit(GROUP, &alice, 5, 100, start).unwrap_err();
+        assert_eq!(throttle.reason, ThrottleReason::OperationRate);
+        assert_eq!(throttle.retry_after, Duration::from_millis(500));
+        assert!(throttle.notify);
+
+        let throttle = limiter
+            .admit(GROUP, &alice, 5, 100, start + Duration::from_millis(100))
+            .unwrap_err();
+        assert!(!throttle.notify, "one notice per back-off period");
+
+        limiter
+            .admit(GROUP, &alice, 5, 100, start + Duration::from_millis(500))
+            .unwrap();
+        limiter
+            .admit(GROUP, &member("bob"), 20, 100, start)
+            .unwrap();
+    }
+
+    #[test]
+    fn byte_rate_and_oversized_updates() {
+        let mut limiter = limiter();
+        let alice = member("alice");
+        let start = Instant::now();
+
+        // Larger than the burst, but admitted from a full bucket.
+        limiter.admit(GROUP, &alice, 1, 6_000, start).unwrap();
+        let throttle = limiter.admit(GROUP, &alice, 1, 10, start).unwrap_err();
+        assert_eq!(throttle.reason, ThrottleReason::ByteRate);
+        assert!(throttle.retry_after > Duration::from_secs(2));
+
+        limiter
+            .admit(GROUP, &alice, 1, 10, start + Duration::from_secs(3))
+            .unwrap();
+    }
+
+    #[test]
+    fn no_limit_admits_everything() {
+        let mut limiter = WriteRateLimiter::new(None);
+        let start = Instant::now();
+        for _ in 0..1_000 {
+            limiter
+                .admit(GROUP, &member("alice"), 1_000, 1_000_000, start)
+                .unwrap();
+        }
+    }
+}
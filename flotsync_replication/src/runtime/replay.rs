@@ -33,6 +33,15 @@ use std::{
     num::NonZeroUsize,
 };
 
+/// Rows reconstructed by a replay.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum ReplayScope<'a> {
+    /// Only the listed rows of each dataset.
+    Rows(&'a HashMap<DatasetId, HashSet<RowKey>>),
+    /// Every row of every dataset with a schema.
+    AllRows,
+}
+
 /// Row-state materialisations needed to publish from a possibly stale read token.
 pub(super) struct PublishDatasetState {
     /// Latest local dataset state used for direct writes and final persistence.
@@ -157,7 +166,7 @@ pub(super) async fn load_publish_dataset_state(
             schemas,
             applied_updates,
             read_versions,
-            &dataset_rows,
+            ReplayScope::Rows(&dataset_rows),
         )
         .context(publish::ReplaySnafu)?;
 
@@ -178,7 +187,7 @@ pub(super) async fn load_publish_dataset_state(
     })
 }
 
-/// Reconstruct selected dataset rows at one historical read token.
+/// Reconstruct the dataset rows in `row_scope` at one historical read token.
 ///
 /// The first implementation replays applied updates from the zero vector up to
 /// `target_versions`. It is intentionally simple; future slices can add durable
@@ -189,7 +198,7 @@ pub(super) fn replay_datasets_at_versions(
     schemas: &HashMap<DatasetId, SchemaSource>,
     updates: Vec<ReplicationUpdateRecord>,
     target_versions: &VersionVector,
-    row_scope: &HashMap<DatasetId, HashSet<RowKey>>,
+    row_scope: ReplayScope<'_>,
 ) -> Result<HashMap<DatasetId, LocalDataset>, ReplayError> {
     let mut datasets = HashMap::new();
     let mut simulated_versions = VersionVector::initial(member_count);
@@ -268,7 +277,7 @@ fn replay_ready(simulated_versions: &VersionVector, update: &ReplicationUpdateRe
 fn replay_one_update(
     group_id: GroupId,
     schemas: &HashMap<DatasetId, SchemaSource>,
-    row_scope: &HashMap<DatasetId, HashSet<RowKey>>,
+    row_scope: ReplayScope<'_>,
     datasets: &mut HashMap<DatasetId, LocalDataset>,
     update: &ReplicationUpdateRecord,
 ) -> Result<(), ReplayError> {
@@ -276,8 +285,14 @@ fn replay_one_update(
         let Some(schema) = schemas.get(&dataset_update.dataset_id) else {
             continue 'dataset_updates;
         };
-        let Some(scoped_rows) = row_scope.get(&dataset_update.dataset_id) else {
-            continue 'dataset_updates;
+        let scoped_rows = match row_scope {
+            ReplayScope::Rows(rows) => {
+                let Some(scoped_rows) = rows.get(&dataset_update.dataset_id) else {
+                    continue 'dataset_updates;
+                };
+                Some(scoped_rows)
+            }
+            ReplayScope::AllRows => None,
         };
 
         'operations: for operation in &dataset_update.operations {
@@ -286,7 +301,7 @@ fn replay_one_update(
                     dataset_id: dataset_update.dataset_id.clone(),
                 })?;
             let row_key = RowKey(*operation.operation.row_id());
-            if !scoped_rows.contains(&row_key) {
+            if scoped_rows.is_some_and(|rows| !rows.contains(&row_key)) {
                 continue 'operations;
             }
 
@@ -457,7 +472,7 @@ mod tests {
             &schemas,
             vec![update],
             &read_versions.with_update_applied(update_id),
-            &row_scope,
+            ReplayScope::Rows(&row_scope),
         )
         .expect("scoped replay should succeed");
 
@@ -516,7 +531,7 @@ mod tests {
             &schemas,
             vec![second_update, first_update],
             &after_first.with_update_applied(second_update_id),
-            &row_scope,
+            ReplayScope::Rows(&row_scope),
         )
         .expect("out-of-order replay should succeed");
 
@@ -1,4 +1,8 @@
-use super::errors::{InboundDeliveryError, InboundFailureAction, SummaryError, inbound, summary};
+use super::{
+    compression::SharedRuntimeCompression,
+    encodings::SharedRuntimeEncodings,
+    errors::{InboundDeliveryError, InboundFailureAction, SummaryError, inbound, summary},
+};
 use crate::{
     api::{ApiError, ApiExternalSnafu, Summary, SummaryRequest},
     codecs::messages::{
@@ -24,6 +28,7 @@ use crate::{
 };
 use flotsync_core::{GroupId, MemberIdentity, membership::SharedGroupMemberships};
 use flotsync_messages::proto::{DecodeProtoViewWith, EncodeProto};
+use flotsync_routes::TrafficClass;
 use flotsync_utils::{KClaimablePromise, OptionExt as _};
 use kompact::prelude::*;
 use snafu::prelude::*;
@@ -103,6 +108,10 @@ pub(super) struct SummaryRequestManagerComponent {
     reliable_delivery: RequiredPort<ReliableDeliveryPort>,
     local_member: MemberIdentity,
     group_memberships: SharedGroupMemberships,
+    /// Shared peer offers, updated from every summary reply.
+    compression: SharedRuntimeCompression,
+    /// Shared peer encoding offers, updated from every summary reply.
+    encodings: SharedRuntimeEncodings,
     request_timeout: Duration,
     pending_summaries: HashMap<Uuid, PendingSummaryRequest>,
 }
@@ -111,6 +120,8 @@ impl SummaryRequestManagerComponent {
     pub(super) fn new(
         local_member: MemberIdentity,
         group_memberships: SharedGroupMemberships,
+        compression: SharedRuntimeCompression,
+        encodings: SharedRuntimeEncodings,
         request_timeout: Duration,
     ) -> Self {
         Self {
@@ -118,6 +129,8 @@ impl SummaryRequestManagerComponent {
             reliable_delivery: RequiredPort::uninitialised(),
             local_member,
             group_memberships,
+            compression,
+            encodings,
             request_timeout,
             pending_summaries: HashMap::new(),
         }
@@ -174,6 +187,7 @@ impl SummaryRequestManagerComponent {
                         },
                         payload: PlaintextPayload { bytes: payload },
                     },
+                    traffic_class: TrafficClass::Interactive,
                 },
             ));
     }
@@ -269,6 +283,10 @@ impl SummaryRequestManagerComponent {
         processed: KClaimablePromise<()>,
         message: SummaryMessage,
     ) -> Result<(), InboundDeliveryError> {
+        self.compression
+            .record_peer_offer(&sender, message.accepts_compression);
+        self.encodings
+            .record_peer_offer(&sender, message.accepts_encodings);
         let summary = Summary {
             group_id: message.group_id,
             responder: sender,
@@ -310,7 +328,18 @@ impl SummaryRequestManagerComponent {
             | RuntimeMessage::NeedRange(_)
             | RuntimeMessage::UpdateBatch(_)
             | RuntimeMessage::GroupInvitation(_)
-            | RuntimeMessage::MigrationProposal(_) => Handled::OK,
+            | RuntimeMessage::MigrationProposal(_)
+            | RuntimeMessage::UpdateAck(_)
+            | RuntimeMessage::FrontierAck(_)
+            | RuntimeMessage::Throttled(_)
+            | RuntimeMessage::BlobChunkRequest(_)
+            | RuntimeMessage::BlobChunk(_)
+            | RuntimeMessage::BlobUnavailable(_)
+            | RuntimeMessage::SnapshotManifestRequest(_)
+            | RuntimeMessage::SnapshotManifest(_)
+            | RuntimeMessage::SnapshotChunkRequest(_)
+            | RuntimeMessage::SnapshotChunk(_)
+            | RuntimeMessage::SnapshotUnavailable(_) => Handled::OK,
         }
     }
 
@@ -344,6 +373,8 @@ impl SummaryRequestManagerComponent {
         let message = RuntimeMessage::SummaryRequest(SummaryRequestMessage {
             group_id: request.group_id,
             correlation_id,
+            accepts_compression: self.compression.local_offer(),
+            accepts_encodings: self.encodings.local_offer(),
         });
         self.submit_reliable_runtime_message(request.target, &message);
         Handled::OK
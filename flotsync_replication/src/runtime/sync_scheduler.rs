@@ -0,0 +1,359 @@
+//! Policy engine deciding when to start background sync sessions.
+//!
+//! A sync session asks one peer for its group summary; the runtime then
+//! fetches anything missing through the ordinary catch-up flow. The scheduler
+//! itself is pure bookkeeping: the runtime component feeds it triggers (local
+//! and live remote changes, network changes, reachable peers, power hints) and
+//! polls it on a fixed tick for the sessions that are due.
+
+use crate::api::{PowerHint, SyncSchedulingPolicy};
+use flotsync_core::{GroupId, MemberIdentity, member::TrieSet, membership::GroupMemberships};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// One sync session the runtime should start.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct SyncSession {
+    pub(super) group_id: GroupId,
+    pub(super) peer: MemberIdentity,
+}
+
+/// Trigger bookkeeping for every hosted group.
+#[derive(Debug)]
+pub(super) struct SyncScheduler {
+    policy: SyncSchedulingPolicy,
+    power_hint: PowerHint,
+    groups: HashMap<GroupId, GroupSyncState>,
+    network_changed: bool,
+    reachable_peers: TrieSet,
+}
+
+impl SyncScheduler {
+    pub(super) fn new(policy: SyncSchedulingPolicy) -> Self {
+        Self {
+            policy,
+            power_hint: PowerHint::default(),
+            groups: HashMap::new(),
+            network_changed: false,
+            reachable_peers: TrieSet::new(),
+        }
+    }
+
+    pub(super) fn set_power_hint(&mut self, hint: PowerHint) {
+        self.power_hint = hint;
+    }
+
+    /// Record a local or live remote change in `group_id`.
+    ///
+    /// Every further change restarts the debounce period.
+    pub(super) fn record_change(&mut self, group_id: GroupId, now: Instant) {
+        if self.policy.on_change_debounce.is_some() {
+            self.group_mut(group_id, now).last_change = Some(now);
+        }
+    }
+
+    /// Record that the device's network connectivity changed.
+    pub(super) fn record_network_change(&mut self) {
+        if self.policy.sync_on_network_change {
+            self.network_changed = true;
+        }
+    }
+
+    /// Record that `peer` became reachable after being unknown or unreachable.
+    pub(super) fn record_peer_reachable(&mut self, peer: &MemberIdentity) {
+        if self.policy.sync_on_network_change {
+            self.reachable_peers.insert(peer.clone());
+        }
+    }
+
+    /// Return the sessions due at `now` and reset the triggers that caused them.
+    ///
+    /// Groups due as a whole sync with every remote member for which `is_reachable` holds.
+    /// Otherwise only peers that recently became reachable are synced. Nothing is due while
+    /// the power hint is [`PowerHint::Critical`], but triggers are kept until sessions resume.
+    pub(super) fn poll(
+        &mut self,
+        now: Instant,
+        memberships: &GroupMemberships,
+        local_member: &MemberIdentity,
+        is_reachable: impl Fn(&MemberIdentity) -> bool,
+    ) -> Vec<SyncSession> {
+        if self.power_hint == PowerHint::Critical {
+            return Vec::new();
+        }
+        self.poll_sessions(now, memberships, local_member, is_reachable, false)
+    }
+
+    /// Return sessions for every hosted group at `now`, regardless of triggers and power hint.
+    ///
+    /// All groups count as due, so their pending triggers are reset as if [`Self::poll`] had
+    /// started them.
+    pub(super) fn poll_all(
+        &mut self,
+        now: Instant,
+        memberships: &GroupMemberships,
+        local_member: &MemberIdentity,
+        is_reachable: impl Fn(&MemberIdentity) -> bool,
+    ) -> Vec<SyncSession> {
+        self.poll_sessions(now, memberships, local_member, is_reachable, true)
+    }
+
+    /// Collect due sessions, treating every group as due when `force` is set.
+    fn poll_sessions(
+        &mut self,
+        now: Instant,
+        memberships: &GroupMemberships,
+        local_member: &MemberIdentity,
+        is_reachable: impl Fn(&MemberIdentity) -> bool,
+        force: bool,
+    ) -> Vec<SyncSession> {
+        self.groups
+            .retain(|group_id, _| memberships.contains_group(group_id));
+        let debounce = self.policy.on_change_debounce.map(|d| self.scaled(d));
+        let periodic_interval = self.policy.periodic_interval.map(|d| self.scaled(d));
+        let network_changed = std::mem::take(&mut self.network_changed);
+        let reachable_peers = std::mem::replace(&mut self.reachable_peers, TrieSet::new());
+
+        let mut sessions = Vec::new();
+        for group_id in memberships.group_ids() {
+            let Some(members) = memberships.members(group_id) else {
+                continue;
+            };
+            let state = self.group_mut(*group_id, now);
+            let change_due = state
+                .last_change
+                .zip(debounce)
+                .is_some_and(|(last_change, debounce)| now >= last_change + debounce);
+            let periodic_due =
+                periodic_interval.is_some_and(|interval| now >= state.last_sync + interval);
+            let group_due = force || network_changed || change_due || periodic_due;
+            if group_due {
+                state.last_change = None;
+                state.last_sync = now;
+            }
+            sessions.extend(
+                members
+                    .ordered_members()
+                    .into_iter()
+                    .chain(members.observers())
+                    .filter(|member| member != local_member)
+                    .filter(|member| group_due || reachable_peers.contains(member))
+                    .filter(|member| is_reachable(member))
+                    .map(|peer| SyncSession {
+                        group_id: *group_id,
+                        peer,
+                    }),
+            );
+        }
+        sessions
+    }
+
+    /// Return the schedule of one group, starting its periodic interval at `now`.
+    fn group_mut(&mut self, group_id: GroupId, now: Instant) -> &mut GroupSyncState {
+        self.groups.entry(group_id).or_insert(GroupSyncState {
+            last_change: None,
+            last_sync: now,
+        })
+    }
+
+    /// Stretch one interval according to the current power hint.
+    fn scaled(&self, interval: Duration) -> Duration {
+        match self.power_hint {
+            PowerHint::Constrained => {
+                interval.saturating_mul(self.policy.constrained_interval_factor.max(1))
+            }
+            PowerHint::Unconstrained | PowerHint::Critical => interval,
+        }
+    }
+}
+
+/// Trigger state of one hosted group.
+#[derive(Clone, Copy, Debug)]
+struct GroupSyncState {
+    /// Most recent change not yet covered by a session.
+    last_change: Option<Instant>,
+    /// Start of the current periodic interval.
+    last_sync: Instant,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flotsync_core::membership::GroupMembers;
+    use uuid::Uuid;
+
+    const GROUP: GroupId = GroupId(Uuid::from_u128(7));
+
+    fn member(name: &str) -> MemberIdentity {
+        MemberIdentity::from_array(["test", name])
+    }
+
+    fn memberships() -> GroupMemberships {
+        let members = GroupMembers::from_ordered_members(vec![
+            member("alice"),
+            member("bob"),
+            member("carol"),
+        ])
+        .expect("group members should build");
+        GroupMemberships::from_groups([(GROUP, members)])
+    }
+
+    fn policy() -> SyncSchedulingPolicy {
+        SyncSchedulingPolicy {
+            on_change_debounce: Some(Duration::from_secs(5)),
+            periodic_interval: Some(Duration::from_secs(60)),
+            sync_on_network_change: true,
+            constrained_interval_factor: 4,
+        }
+    }
+
+    fn peers(sessions: Vec<SyncSession>) -> Vec<MemberIdentity> {
+        sessions
+            .into_iter()
+            .map(|session| {
+                assert_eq!(session.group_id, GROUP);
+                session.peer
+            })
+            .collect()
+    }
+
+    #[test]
+    fn changes_are_debounced_and_power_hints_stretch_intervals() {
+        let start = Instant::now();
+        let memberships = memberships();
+        let local = member("alice");
+        let mut scheduler = SyncScheduler::new(policy());
+
+        assert!(
+            scheduler
+                .poll(start, &memberships, &local, |_| true)
+                .is_empty()
+        );
+        scheduler.record_change(GROUP, start);
+        scheduler.record_change(GROUP, start + Duration::from_secs(3));
+        assert!(
+            scheduler
+                .poll(start + Duration::from_secs(6), &memberships, &local, |_| {
+                    true
+                })
+                .is_empty()
+        );
+        assert_eq!(
+            peers(scheduler.poll(
+                start + Duration::from_secs(8),
+                &memberships,
+                &local,
+                |peer| peer != &member("carol"),
+            )),
+            vec![member("bob")]
+        );
+        assert!(
+            scheduler
+                .poll(start + Duration::from_secs(9), &memberships, &local, |_| {
+                    true
+                })
+                .is_empty()
+        );
+
+        scheduler.set_power_hint(PowerHint::Constrained);
+        let periodic_at = start + Duration::from_secs(8);
+        assert!(
+            scheduler
+                .poll(
+                    periodic_at + Duration::from_secs(60),
+                    &memberships,
+                    &local,
+                    |_| true
+                )
+                .is_empty()
+        );
+        assert_eq!(
+            peers(scheduler.poll(
+                periodic_at + Duration::from_secs(240),
+                &memberships,
+                &local,
+                |_| true,
+            )),
+            vec![member("bob"), member("carol")]
+        );
+    }
+
+    #[test]
+    fn network_triggers_wait_while_power_is_critical() {
+        let start = Instant::now();
+        let memberships = memberships();
+        let local = member("alice");
+        let mut scheduler = SyncScheduler::new(policy());
+
+        scheduler.record_peer_reachable(&member("carol"));
+        assert_eq!(
+            peers(scheduler.poll(start, &memberships, &local, |_| true)),
+            vec![member("carol")]
+        );
+
+        scheduler.set_power_hint(PowerHint::Critical);
+        scheduler.record_network_change();
+        assert!(
+            scheduler
+                .poll(start, &memberships, &local, |_| true)
+                .is_empty()
+        );
+
+        scheduler.set_power_hint(PowerHint::Unconstrained);
+        assert_eq!(
+            peers(scheduler.poll(start, &memberships, &local, |_| true)),
+            vec![member("bob"), member("carol")]
+        );
+        assert!(
+            scheduler
+                .poll(start, &memberships, &local, |_| true)
+                .is_empty()
+        );
+
+        let mut manual = SyncScheduler::new(SyncSchedulingPolicy::MANUAL);
+        manual.record_change(GROUP, start);
+        manual.record_network_change();
+        manual.record_peer_reachable(&member("bob"));
+        assert!(
+            manual
+                .poll(
+                    start + Duration::from_secs(3600),
+                    &memberships,
+                    &local,
+                    |_| true
+                )
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn forced_polls_ignore_power_hints_and_reset_triggers() {
+        let start = Instant::now();
+        let memberships = memberships();
+        let local = member("alice");
+        let mut scheduler = SyncScheduler::new(policy());
+
+        scheduler.set_power_hint(PowerHint::Critical);
+        scheduler.record_change(GROUP, start);
+        assert_eq!(
+            peers(scheduler.poll_all(start, &memberships, &local, |peer| {
+                peer != &member("bob")
+            })),
+            vec![member("carol")]
+        );
+
+        scheduler.set_power_hint(PowerHint::Unconstrained);
+        assert!(
+            scheduler
+                .poll(
+                    start + Duration::from_secs(10),
+                    &memberships,
+                    &local,
+                    |_| { true }
+                )
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Bookkeeping for one bounded, caller-driven sync step.
+//!
+//! A sync step starts a sync session with every reachable peer of every hosted
+//! group at once and then waits, up to a deadline, for their summaries. The
+//! summaries feed the ordinary catch-up flow; this module only tracks which
+//! peers answered and the union of the versions they reported, so the runtime
+//! component can tell when the step has caught up.
+
+use super::sync_scheduler::SyncSession;
+use crate::api::SyncStepProgress;
+use flotsync_core::{GroupId, MemberIdentity, member::TrieSet, versions::VersionVector};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Progress of one running sync step.
+#[derive(Debug)]
+pub(super) struct SyncStep {
+    /// End of the step's budget, or `None` when the budget does not fit an [`Instant`].
+    deadline: Option<Instant>,
+    sessions_started: usize,
+    sessions_answered: usize,
+    /// Peers whose summary is still outstanding, per group.
+    awaiting: HashMap<GroupId, TrieSet>,
+    /// Least upper bound of the versions answering peers reported, per group.
+    targets: HashMap<GroupId, VersionVector>,
+}
+
+impl SyncStep {
+    /// Start tracking `sessions` at `now`, which must all be answered within `budget`.
+    pub(super) fn start(now: Instant, budget: Duration, sessions: &[SyncSession]) -> Self {
+        let mut awaiting: HashMap<GroupId, TrieSet> = HashMap::new();
+        for session in sessions {
+            awaiting
+                .entry(session.group_id)
+                .or_default()
+                .insert(session.peer.clone());
+        }
+        Self {
+            deadline: now.checked_add(budget),
+            sessions_started: sessions.len(),
+            sessions_answered: 0,
+            awaiting,
+            targets: HashMap::new(),
+        }
+    }
+
+    /// Record one summary observed while the step is running.
+    ///
+    /// Summaries from peers the step did not ask, or that already answered, are ignored.
+    pub(super) fn record_summary(
+        &mut self,
+        group_id: GroupId,
+        responder: &MemberIdentity,
+        has_versions: &VersionVector,
+    ) {
+        let Some(peers) = self.awaiting.get_mut(&group_id) else {
+            return;
+        };
+        if !peers.remove(responder) {
+            return;
+        }
+        if peers.is_empty() {
+            self.awaiting.remove(&group_id);
+        }
+        self.sessions_answered += 1;
+        self.targets
+            .entry(group_id)
+            .and_modify(|target| *target = target.least_upper_bound(has_versions))
+            .or_insert_with(|| has_versions.clone());
+    }
+
+    /// Whether every peer the step asked has answered.
+    pub(super) fn all_answered(&self) -> bool {
+        self.awaiting.is_empty()
+    }
+
+    /// Whether the step's budget is spent at `now`.
+    pub(super) fn is_expired(&self, now: Instant) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Versions the local replica must reach for the step to complete, per group.
+    pub(super) fn targets(&self) -> &HashMap<GroupId, VersionVector> {
+        &self.targets
+    }
+
+    /// Summarise the step, given the groups still behind their targets.
+    pub(super) fn progress(&self, mut groups_behind: Vec<GroupId>) -> SyncStepProgress {
+        groups_behind.sort_unstable();
+        let completed = self.all_answered() && groups_behind.is_empty();
+        SyncStepProgress {
+            sessions_started: self.sessions_started,
+            sessions_answered: self.sessions_answered,
+            groups_behind,
+            completed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const GROUP: GroupId = GroupId(Uuid::from_u128(7));
+    const OTHER_GROUP: GroupId = GroupId(Uuid::from_u128(8));
+
+    fn member(name: &str) -> MemberIdentity {
+        MemberIdentity::from_array(["test", name])
+    }
+
+    fn session(group_id: GroupId, name: &str) -> SyncSession {
+        SyncSession {
+            group_id,
+            peer: member(name),
+        }
+    }
+
+    #[test]
+    fn summaries_complete_the_step_and_merge_targets() {
+        let start = Instant::now();
+        let mut step = SyncStep::start(
+            start,
+            Duration::from_secs(5),
+            &[
+                session(GROUP, "bob"),
+                session(GROUP, "carol"),
+                session(OTHER_GROUP, "bob"),
+            ],
+        );
+        assert!(!step.all_answered());
+        assert!(!step.is_expired(start));
+
+        step.record_summary(GROUP, &member("bob"), &VersionVector::from_entries([3, 1]));
+        // Repeated and unsolicited summaries do not count as answers.
+        step.record_summary(GROUP, &member("bob"), &VersionVector::from_entries([9, 9]));
+        step.record_summary(GROUP, &member("dave"), &VersionVector::from_entries([9, 9]));
+        step.record_summary(
+            GROUP,
+            &member("carol"),
+            &VersionVector::from_entries([1, 4]),
+        );
+        assert!(!step.all_answered());
+        step.record_summary(
+            OTHER_GROUP,
+            &member("bob"),
+            &VersionVector::from_entries([2]),
+        );
+        assert!(step.all_answered());
+        assert_eq!(
+            step.targets().get(&GROUP),
+            Some(&VersionVector::from_entries([3, 4]))
+        );
+
+        assert_eq!(
+            step.progress(Vec::new()),
+            SyncStepProgress {
+                sessions_started: 3,
+                sessions_answered: 3,
+                groups_behind: Vec::new(),
+                completed: true,
+            }
+        );
+        assert_eq!(
+            step.progress(vec![OTHER_GROUP, GROUP]),
+            SyncStepProgress {
+                sessions_started: 3,
+                sessions_answered: 3,
+                groups_behind: vec![GROUP, OTHER_GROUP],
+                completed: false,
+            }
+        );
+    }
+
+    #[test]
+    fn unanswered_sessions_leave_the_step_incomplete() {
+        let start = Instant::now();
+        let step = SyncStep::start(start, Duration::from_secs(1), &[session(GROUP, "bob")]);
+        assert!(step.is_expired(start + Duration::from_secs(1)));
+        assert!(
+            !SyncStep::start(start, Duration::MAX, &[]).is_expired(start + Duration::from_secs(1))
+        );
+        let progress = step.progress(Vec::new());
+        assert_eq!(progress.sessions_answered, 0);
+        assert!(!progress.completed);
+
+        let empty = SyncStep::start(start, Duration::ZERO, &[]);
+        assert!(empty.all_answered());
+        assert!(empty.progress(Vec::new()).completed);
+    }
+}
@@ -13,6 +13,7 @@ fn publish_changes_persists_applied_update_and_snapshot_state() {
     );
     let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
         members: vec![alice_member],
+        observers: Vec::new(),
         group_schema: docs_group_schema(),
     }))
     .expect("create_group should succeed");
@@ -64,6 +65,7 @@ fn publish_changes_linear_string_update_with_two_insert_hunks_reuses_operation_i
     );
     let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
         members: vec![alice_member],
+        observers: Vec::new(),
         group_schema: docs_group_schema(),
     }))
     .expect("create_group should succeed");
@@ -116,6 +118,7 @@ fn request_summary_reports_local_versions() {
     );
     let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
         members: vec![alice_member.clone()],
+        observers: Vec::new(),
         group_schema: docs_group_schema(),
     }))
     .expect("create_group should succeed");
@@ -156,6 +159,7 @@ fn snapshot_rows_streams_visible_rows_and_optional_tombstones() {
     );
     let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
         members: vec![alice_member],
+        observers: Vec::new(),
         group_schema: docs_group_schema(),
     }))
     .expect("create_group should succeed");
@@ -233,6 +237,107 @@ fn snapshot_rows_streams_visible_rows_and_optional_tombstones() {
     );
 }
 
+#[test]
+fn checkpoints_list_labels_and_materialise_rows_at_tagged_versions() {
+    let alice_member = alice_member();
+    let dataset_id = docs_dataset_id();
+    let checkpoint_schema = checkpoint_kinds()
+        .group_schema()
+        .schema(&checkpoints_dataset_id())
+        .expect("checkpoint kinds should declare the checkpoint dataset")
+        .clone();
+    let fixture = load_runtime_fixture(
+        app_alice_id(),
+        alice_member.clone(),
+        [
+            (
+                dataset_id.clone(),
+                SchemaSource::from(title_schema_shared()),
+            ),
+            (checkpoints_dataset_id(), checkpoint_schema.clone()),
+        ],
+    );
+    let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
+        members: vec![alice_member],
+        observers: Vec::new(),
+        group_schema: GroupSchema::new(HashMap::from([
+            (
+                dataset_id.clone(),
+                SchemaSource::from(title_schema_shared()),
+            ),
+            (checkpoints_dataset_id(), checkpoint_schema),
+        ])),
+    }))
+    .expect("create_group should succeed");
+    let edited_row_id = test_row_id(group_id, dataset_id.clone(), 37);
+    let later_row_id = test_row_id(group_id, dataset_id.clone(), 38);
+
+    let read_token = snapshot_read_token(fixture.runtime.as_ref(), group_id, dataset_id.clone());
+    let receipt = publish_changes(
+        fixture.runtime.as_ref(),
+        read_token,
+        vec![RowMutation::Upsert {
+            row_id: edited_row_id.clone(),
+            row: crate::row_values! {
+                "title" => "first draft",
+            },
+        }],
+    );
+    let tagged_versions = receipt
+        .read_token
+        .group_version(&group_id)
+        .expect("publish receipt should contain the group")
+        .clone();
+    let checkpoint =
+        wait_for_test_reply(fixture.runtime.create_checkpoint(CreateCheckpointRequest {
+            read_token: receipt.read_token,
+            group_id,
+            label: "v1.0 sent to client".to_owned(),
+        }))
+        .expect("create_checkpoint should succeed");
+    assert_eq!(checkpoint.versions, tagged_versions);
+
+    let read_token = snapshot_read_token(fixture.runtime.as_ref(), group_id, dataset_id.clone());
+    publish_changes(
+        fixture.runtime.as_ref(),
+        read_token,
+        vec![
+            RowMutation::Upsert {
+                row_id: edited_row_id.clone(),
+                row: crate::row_values! {
+                    "title" => "second draft",
+                },
+            },
+            RowMutation::Upsert {
+                row_id: later_row_id,
+                row: crate::row_values! {
+                    "title" => "added later",
+                },
+            },
+        ],
+    );
+
+    let checkpoints = wait_for_test_reply(fixture.runtime.list_checkpoints(group_id))
+        .expect("list_checkpoints should succeed");
+    assert_eq!(checkpoints, vec![checkpoint.clone()]);
+
+    let rows_at_checkpoint = drain_historical_snapshot_rows(
+        fixture.runtime.as_ref(),
+        HistoricalSnapshotRowsRequest::at_checkpoint(
+            &checkpoint,
+            HashSet::from([dataset_id]),
+            NonZeroUsize::new(1).expect("batch size should be non-zero"),
+        ),
+    );
+    assert_eq!(
+        rows_at_checkpoint,
+        vec![CapturedRowChange::Upsert {
+            row_id: edited_row_id,
+            title: "first draft".to_owned(),
+        }]
+    );
+}
+
 #[test]
 fn publish_changes_emits_local_data_changed_event_before_reply() {
     let alice_member = alice_member();
@@ -244,6 +349,7 @@ fn publish_changes_emits_local_data_changed_event_before_reply() {
     );
     let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
         members: vec![alice_member],
+        observers: Vec::new(),
         group_schema: docs_group_schema(),
     }))
     .expect("create_group should succeed");
@@ -272,6 +378,52 @@ fn publish_changes_emits_local_data_changed_event_before_reply() {
     );
 }
 
+#[test]
+fn publish_changes_announces_changed_documents_on_workspace_events() {
+    let alice_member = alice_member();
+    let dataset_id = docs_dataset_id();
+    let fixture = load_runtime_fixture(
+        app_alice_id(),
+        alice_member.clone(),
+        [(dataset_id.clone(), title_schema_shared())],
+    );
+    let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
+        members: vec![alice_member],
+        observers: Vec::new(),
+        group_schema: docs_group_schema(),
+    }))
+    .expect("create_group should succeed");
+    let row_id = test_row_id(group_id, dataset_id, 40);
+    let mut events = fixture
+        .runtime
+        .subscribe_workspace_events()
+        .expect("runtime should be live");
+
+    let read_token = snapshot_read_token(fixture.runtime.as_ref(), group_id, docs_dataset_id());
+    publish_changes(
+        fixture.runtime.as_ref(),
+        read_token,
+        vec![RowMutation::Upsert {
+            row_id: row_id.clone(),
+            row: crate::row_values! {
+                "title" => "announced",
+            },
+        }],
+    );
+
+    let WorkspaceEvent::DocumentChanged {
+        read_token,
+        documents,
+    } = events
+        .try_recv()
+        .expect("publish should emit a workspace event")
+    else {
+        panic!("publish should announce changed documents");
+    };
+    assert_eq!(documents, vec![row_id]);
+    assert!(read_token.group_version(&group_id).is_some());
+}
+
 #[test]
 fn change_group_membership_emits_inline_snapshot_upserts_for_new_group() {
     let alice_member = alice_member();
@@ -291,6 +443,7 @@ fn change_group_membership_emits_inline_snapshot_upserts_for_new_group() {
     let runtime = load_runtime_with_parts(app_alice_id(), store.clone(), listener.clone());
     let old_group_id = wait_for_test_reply(runtime.create_group(CreateGroupRequest {
         members: vec![alice_member.clone()],
+        observers: Vec::new(),
         group_schema: docs_group_schema(),
     }))
     .expect("create_group should succeed");
@@ -314,6 +467,7 @@ fn change_group_membership_emits_inline_snapshot_upserts_for_new_group() {
         ChangeGroupMembershipRequest {
             group_id: old_group_id,
             add_members: HashSet::from([bob_member]),
+            add_observers: HashSet::new(),
             remove_members: HashSet::new(),
             group_name: None,
             message: None,
@@ -391,7 +545,7 @@ fn read_only_group_allows_reads_but_rejects_application_writes() {
         ReplicationGroupRecord {
             group_id,
             member_keys: test_group_member_keys(vec![alice_member.clone()]),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: docs_group_schema(),
             version_vector: versions.clone(),
             lifecycle: ReplicationGroupLifecycle::ReadOnly {
@@ -433,6 +587,7 @@ fn read_only_group_allows_reads_but_rejects_application_writes() {
             runtime.change_group_membership(ChangeGroupMembershipRequest {
                 group_id,
                 add_members: HashSet::new(),
+                add_observers: HashSet::new(),
                 remove_members: HashSet::new(),
                 group_name: None,
                 message: None,
@@ -525,6 +680,7 @@ fn publish_changes_rebases_stale_field_patch_without_overwriting_newer_fields()
     );
     let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
         members: vec![alice_member],
+        observers: Vec::new(),
         group_schema: docs_group_schema_from_schema(title_note_schema_shared()),
     }))
     .expect("create_group should succeed");
@@ -605,6 +761,7 @@ fn publish_changes_error_display_includes_local_operation_source() {
     );
     let group_id = wait_for_test_reply(fixture.runtime.create_group(CreateGroupRequest {
         members: vec![alice_member],
+        observers: Vec::new(),
         group_schema: docs_group_schema_from_schema(schema),
     }))
     .expect("create_group should succeed");
@@ -661,7 +818,7 @@ fn publish_changes_rejects_reserved_local_update_version() {
         ReplicationGroupRecord {
             group_id,
             member_keys: test_group_member_keys(vec![alice_member.clone(), bob_member]),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: docs_group_schema(),
             version_vector: version_vector.clone(),
             lifecycle: ReplicationGroupLifecycle::Open,
@@ -713,3 +870,169 @@ fn publish_changes_rejects_reserved_local_update_version() {
     );
     assert!(listener.captured_data_changes().is_empty());
 }
+
+#[test]
+fn publish_changes_rejects_updates_above_configured_payload_limit() {
+    let alice_member = alice_member();
+    let dataset_id = docs_dataset_id();
+    let store = sqlite_store_with_schemas(
+        alice_member.clone(),
+        [(dataset_id.clone(), title_schema_shared())],
+    );
+    let listener = Arc::new(ListenerStub::default());
+    let runtime = load_runtime_with_parts_and_config(
+        app_alice_id(),
+        store.clone(),
+        listener.clone(),
+        ReplicationConfig {
+            limits: RuntimeLimits {
+                max_payload_bytes: 64,
+                ..RuntimeLimits::default()
+            },
+            ..ReplicationConfig::default()
+        },
+    );
+    let group_id = wait_for_test_reply(runtime.create_group(CreateGroupRequest {
+        members: vec![alice_member],
+        observers: Vec::new(),
+        group_schema: docs_group_schema(),
+    }))
+    .expect("create_group should succeed");
+    let row_id = test_row_id(group_id, dataset_id.clone(), 41);
+    let read_token = snapshot_read_token(runtime.as_ref(), group_id, dataset_id);
+
+    let error = wait_for_test_reply(runtime.publish_changes(PublishChangesRequest {
+        read_token,
+        changes: vec![RowMutation::Upsert {
+            row_id,
+            row: crate::row_values! {
+                "title" => "a title that is far too long to fit into the configured payload limit",
+            },
+        }],
+    }))
+    .expect_err("oversized publish should fail");
+
+    match error {
+        ApiError::ApiExternal { source } => match source.downcast_ref::<PublishChangesError>() {
+            Some(PublishChangesError::PayloadTooLarge {
+                group_id: rejected_group_id,
+                payload_bytes,
+                limit,
+            }) => {
+                assert_eq!(*rejected_group_id, group_id);
+                assert!(*payload_bytes > 64);
+                assert_eq!(*limit, 64);
+            }
+            other => panic!("unexpected publish error source: {other:?}"),
+        },
+        error => panic!("unexpected API error: {error:?}"),
+    }
+    assert_eq!(
+        load_persisted_group(store.as_ref(), group_id)
+            .version_vector
+            .version_at(0),
+        0
+    );
+    assert!(listener.captured_data_changes().is_empty());
+}
+
+#[test]
+fn observer_applies_remote_updates_without_member_index_but_cannot_publish() {
+    let _runtime_endpoint_leases =
+        reserve_sockets(&[ReservedSocketKind::UdpSocket, ReservedSocketKind::UdpSocket]);
+    let alice_member = alice_member();
+    let bob_member = bob_member();
+    let dataset_id = docs_dataset_id();
+    let alice_fixture = load_runtime_fixture(
+        app_alice_id(),
+        alice_member.clone(),
+        [(dataset_id.clone(), title_schema_shared())],
+    );
+    let bob_store = sqlite_store_with_schemas(
+        bob_member.clone(),
+        [(dataset_id.clone(), title_schema_static())],
+    );
+    provision_test_security(
+        alice_fixture.store.as_ref(),
+        &alice_member,
+        [bob_member.clone()],
+    );
+    provision_test_security(bob_store.as_ref(), &bob_member, [alice_member.clone()]);
+    let bob_listener = Arc::new(ListenerStub::default());
+    let bob_runtime = load_runtime_with_parts_and_config(
+        app_bob_id(),
+        bob_store.clone(),
+        bob_listener.clone(),
+        ReplicationConfig {
+            replica_role: ReplicaRole::Observer,
+            group_invitation_policy: GroupInvitationPolicy {
+                creation: PolicyDecision::AutoAccept,
+                ..GroupInvitationPolicy::default()
+            },
+            ..ReplicationConfig::default()
+        },
+    );
+    let alice_runtime = &alice_fixture.runtime;
+    publish_direct_peer_routes(alice_runtime, &alice_member, &bob_runtime, &bob_member);
+
+    let group_id = wait_for_test_reply(alice_runtime.create_group(CreateGroupRequest {
+        members: vec![alice_member],
+        observers: vec![bob_member.clone()],
+        group_schema: docs_group_schema(),
+    }))
+    .expect("create_group should succeed");
+    wait_for_group_install(&bob_runtime, group_id);
+    let observed_group = load_persisted_group(bob_store.as_ref(), group_id);
+    assert_eq!(observed_group.local_member_index, None);
+    assert_eq!(observed_group.member_count().get(), 1);
+    assert!(observed_group.member_keys.is_observer(&bob_member));
+
+    let row_id = test_row_id(group_id, dataset_id.clone(), 42);
+    let read_token = snapshot_read_token(alice_runtime.as_ref(), group_id, dataset_id.clone());
+    publish_changes(
+        alice_runtime.as_ref(),
+        read_token,
+        vec![RowMutation::Upsert {
+            row_id: row_id.clone(),
+            row: crate::row_values! {
+                "title" => "observed",
+            },
+        }],
+    );
+    bob_listener.wait_for_data_change_count(1);
+    assert_eq!(
+        bob_listener.captured_data_changes(),
+        vec![CapturedDataChange {
+            rows: vec![CapturedRowChange::Upsert {
+                row_id,
+                title: "observed".to_owned(),
+            }],
+        }]
+    );
+
+    let read_token = snapshot_read_token(bob_runtime.as_ref(), group_id, dataset_id.clone());
+    let error = wait_for_test_reply(bob_runtime.publish_changes(PublishChangesRequest {
+        read_token,
+        changes: vec![RowMutation::Upsert {
+            row_id: test_row_id(group_id, dataset_id, 43),
+            row: crate::row_values! {
+                "title" => "observers only read",
+            },
+        }],
+    }))
+    .expect_err("observers should not publish");
+
+    match error {
+        ApiError::ApiExternal { source } => match source.downcast_ref::<PublishChangesError>() {
+            Some(PublishChangesError::ObserverReplica {
+                group_id: rejected_group_id,
+            }) => assert_eq!(*rejected_group_id, group_id),
+            other => panic!("unexpected publish error source: {other:?}"),
+        },
+        error => panic!("unexpected API error: {error:?}"),
+    }
+    assert_eq!(
+        load_persisted_group(bob_store.as_ref(), group_id).version_vector,
+        VersionVector::from_entries([1])
+    );
+}
@@ -289,6 +289,7 @@ fn request_summary_returns_remote_current_version_vector() {
 
     let group_id = wait_for_test_reply(alice_runtime.create_group(CreateGroupRequest {
         members: vec![alice_member.clone(), bob_member.clone()],
+        observers: Vec::new(),
         group_schema: docs_group_schema(),
     }))
     .expect("create_group should succeed");
@@ -313,6 +314,132 @@ fn request_summary_returns_remote_current_version_vector() {
     );
 }
 
+#[test]
+fn applied_live_update_is_acknowledged_to_producer() {
+    let _runtime_endpoint_leases =
+        reserve_sockets(&[ReservedSocketKind::UdpSocket, ReservedSocketKind::UdpSocket]);
+    let dataset_id = docs_dataset_id();
+    let (alice_fixture, bob_fixture) = load_title_runtime_pair_with_trust(&dataset_id);
+    let alice_member = alice_fixture.local_member.clone();
+    let bob_member = bob_fixture.local_member.clone();
+    let alice_runtime = &alice_fixture.runtime;
+    let bob_runtime = &bob_fixture.runtime;
+    let group_id = GroupId(Uuid::from_u128(50_301));
+    let members =
+        GroupMembers::from_ordered_members(vec![alice_member.clone(), bob_member.clone()])
+            .expect("group members should build");
+    alice_runtime
+        .install_group_for_test(group_id, members.clone())
+        .expect("alice group should install");
+    bob_runtime
+        .install_group_for_test(group_id, members)
+        .expect("bob group should install");
+    alice_runtime.publish_direct_peer_route_for_test(
+        bob_member.clone(),
+        bob_runtime.advertised_loopback_udp_addr_for_test(),
+    );
+    bob_runtime.publish_direct_peer_route_for_test(
+        alice_member,
+        alice_runtime.advertised_loopback_udp_addr_for_test(),
+    );
+    alice_runtime.wait_for_direct_peer_route_for_test(&bob_member);
+    bob_runtime.wait_for_direct_peer_route_for_test(&alice_fixture.local_member);
+
+    let acknowledged = wait_for_test_reply(alice_runtime.acknowledged_versions(group_id))
+        .expect("acknowledged versions should load");
+    let two_members = NonZeroUsize::new(2).expect("two members");
+    assert_eq!(
+        acknowledged.stable_versions,
+        VersionVector::initial(two_members)
+    );
+
+    let read_token = snapshot_read_token(alice_runtime.as_ref(), group_id, dataset_id.clone());
+    publish_changes(
+        alice_runtime.as_ref(),
+        read_token,
+        vec![RowMutation::Upsert {
+            row_id: test_row_id(group_id, dataset_id, 50_311),
+            row: crate::row_values! {
+                "title" => "acknowledged",
+            },
+        }],
+    );
+    bob_fixture.listener.wait_for_data_change_count(1);
+
+    let applied = VersionVector::from_entries([1, 0]);
+    eventually(
+        TEST_WAIT_TIMEOUT,
+        || {
+            wait_for_test_reply(alice_runtime.acknowledged_versions(group_id))
+                .expect("acknowledged versions should load")
+                .stable_versions
+                == applied
+        },
+        "timed out waiting for bob to acknowledge the update",
+    );
+    let acknowledged = wait_for_test_reply(alice_runtime.acknowledged_versions(group_id))
+        .expect("acknowledged versions should load");
+    assert_eq!(acknowledged.members, vec![applied.clone(), applied.clone()]);
+
+    let health =
+        wait_for_test_reply(alice_runtime.sync_health(group_id)).expect("sync health should load");
+    assert_eq!(health.group_id, group_id);
+    assert_eq!(health.members.len(), 2);
+    assert_eq!(health.members[0].member, alice_fixture.local_member);
+    assert_eq!(health.members[0].liveness, Some(PeerLiveness::Alive));
+    assert_eq!(health.members[1].member, bob_member);
+    assert!(
+        health
+            .members
+            .iter()
+            .all(|member| member.up_to_date && member.acknowledged_versions == applied)
+    );
+}
+
+#[test]
+fn sync_step_returns_once_every_peer_answered() {
+    let _runtime_endpoint_leases =
+        reserve_sockets(&[ReservedSocketKind::UdpSocket, ReservedSocketKind::UdpSocket]);
+    let dataset_id = docs_dataset_id();
+    let (alice_fixture, bob_fixture) = load_title_runtime_pair_with_trust(&dataset_id);
+    let alice_member = alice_fixture.local_member.clone();
+    let bob_member = bob_fixture.local_member.clone();
+    let alice_runtime = &alice_fixture.runtime;
+    let bob_runtime = &bob_fixture.runtime;
+    let group_id = GroupId(Uuid::from_u128(50_351));
+    let members =
+        GroupMembers::from_ordered_members(vec![alice_member.clone(), bob_member.clone()])
+            .expect("group members should build");
+    alice_runtime
+        .install_group_for_test(group_id, members.clone())
+        .expect("alice group should install");
+    bob_runtime
+        .install_group_for_test(group_id, members)
+        .expect("bob group should install");
+    alice_runtime.publish_direct_peer_route_for_test(
+        bob_member.clone(),
+        bob_runtime.advertised_loopback_udp_addr_for_test(),
+    );
+    bob_runtime.publish_direct_peer_route_for_test(
+        alice_member.clone(),
+        alice_runtime.advertised_loopback_udp_addr_for_test(),
+    );
+    alice_runtime.wait_for_direct_peer_route_for_test(&bob_member);
+    bob_runtime.wait_for_direct_peer_route_for_test(&alice_member);
+
+    let progress = wait_for_test_reply(bob_runtime.run_sync_step(TEST_WAIT_TIMEOUT))
+        .expect("sync step should run");
+    assert_eq!(
+        progress,
+        SyncStepProgress {
+            sessions_started: 1,
+            sessions_answered: 1,
+            groups_behind: Vec::new(),
+            completed: true,
+        }
+    );
+}
+
 #[test]
 fn group_invitation_persists_group_schema() {
     let _runtime_endpoint_leases =
@@ -349,6 +476,7 @@ fn group_invitation_persists_group_schema() {
     let group_schema = docs_group_schema();
     let group_id = wait_for_test_reply(alice_fixture.runtime.create_group(CreateGroupRequest {
         members: vec![alice_member.clone(), bob_member.clone()],
+        observers: Vec::new(),
         group_schema: group_schema.clone(),
     }))
     .expect("create_group should succeed");
@@ -692,6 +820,74 @@ fn inbound_update_with_out_of_range_producer_index_is_rejected() {
     assert!(bob_fixture.listener.captured_data_changes().is_empty());
 }
 
+#[test]
+fn inbound_update_above_configured_document_node_limit_is_dropped() {
+    let alice_member = alice_member();
+    let bob_member = bob_member();
+    let dataset_id = docs_dataset_id();
+    let bob_listener = Arc::new(ListenerStub::default());
+    let bob_store = sqlite_store_with_schemas(
+        bob_member.clone(),
+        [(dataset_id.clone(), title_schema_shared())],
+    );
+    let bob_runtime = load_runtime_with_parts_and_config(
+        app_bob_id(),
+        bob_store.clone(),
+        bob_listener.clone(),
+        ReplicationConfig {
+            limits: RuntimeLimits {
+                max_document_nodes: 2,
+                ..RuntimeLimits::default()
+            },
+            ..ReplicationConfig::default()
+        },
+    );
+    let group_id = GroupId(Uuid::from_u128(22_111));
+    bob_runtime
+        .install_group_for_test(
+            group_id,
+            GroupMembers::from_ordered_members(vec![alice_member.clone(), bob_member])
+                .expect("group should build"),
+        )
+        .expect("group should install");
+    let member_count = NonZeroUsize::new(2).expect("group has two members");
+    let (row_id, update) = title_update_message(
+        group_id,
+        dataset_id,
+        22_112,
+        "too many nodes",
+        UpdateId {
+            version: 1,
+            node_index: 0,
+        },
+        VersionVector::initial(member_count),
+    );
+
+    let error = bob_runtime
+        .apply_update_for_test(alice_member, update)
+        .expect_err("update above the node limit should fail");
+    match &error {
+        InboundDeliveryError::DocumentTooLarge {
+            row_id: rejected_row_id,
+            node_count,
+            limit,
+        } => {
+            assert_eq!(*rejected_row_id, row_id);
+            assert!(*node_count > 2);
+            assert_eq!(*limit, 2);
+        }
+        error => panic!("unexpected inbound update error: {error:?}"),
+    }
+    assert_eq!(error.failure_action(), InboundFailureAction::Drop);
+    assert_eq!(
+        load_persisted_group(bob_store.as_ref(), group_id)
+            .version_vector
+            .version_at(0),
+        0
+    );
+    assert!(bob_listener.captured_data_changes().is_empty());
+}
+
 #[test]
 fn update_batch_failure_after_first_update_keeps_first_notifications() {
     let alice_member = alice_member();
@@ -42,6 +42,7 @@ pub(super) fn runtime_test_migration_proposal_decision() -> PendingGroupDecision
         migration_id,
         final_versions: VersionVector::Full(PureVersionVector::from([4, 0])),
         proposed_members: vec![alice_member(), bob_member(), carol_member()],
+        proposed_observers: Vec::new(),
         group_schema: GroupSchema::default(),
         initial_snapshot: InitialSnapshot::Empty,
         group_name: Some("runtime migration".to_owned()),
@@ -62,6 +63,7 @@ pub(super) fn migration_proposal_decision(
             NonZeroUsize::new(2).expect("two old-group members"),
         ),
         proposed_members: vec![alice_member(), bob_member()],
+        proposed_observers: Vec::new(),
         group_schema: GroupSchema::default(),
         initial_snapshot: InitialSnapshot::Empty,
         group_name: None,
@@ -718,7 +720,7 @@ pub(super) fn inactive_group_record(
     ReplicationGroupRecord {
         group_id,
         member_keys: test_group_member_keys(members),
-        local_member_index: MemberIndex::new(0),
+        local_member_index: Some(MemberIndex::new(0)),
         group_schema,
         version_vector: VersionVector::initial(
             NonZeroUsize::new(member_count).expect("group should not be empty"),
@@ -756,7 +758,7 @@ pub(super) fn persist_group_membership_for_member<S>(
         ReplicationGroupRecord {
             group_id,
             member_keys: test_group_member_keys(members),
-            local_member_index: MemberIndex::new(local_member_index),
+            local_member_index: Some(MemberIndex::new(local_member_index)),
             group_schema: GroupSchema::default(),
             version_vector: VersionVector::initial(
                 NonZeroUsize::new(member_count).expect("group should not be empty"),
@@ -777,7 +779,7 @@ pub(super) fn persist_alice_group_with_security_material(
         ReplicationGroupRecord {
             group_id,
             member_keys: test_group_member_keys(vec![alice_member()]),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: GroupSchema::default(),
             version_vector: VersionVector::initial(NonZeroUsize::new(1).unwrap()),
             lifecycle: ReplicationGroupLifecycle::Open,
@@ -870,8 +872,21 @@ pub(super) fn drain_snapshot_rows(
     runtime: &dyn ReplicationApi,
     request: SnapshotRowsRequest,
 ) -> Vec<CapturedRowChange> {
-    let mut snapshot =
+    let snapshot =
         wait_for_test_reply(runtime.snapshot_rows(request)).expect("snapshot should start");
+    collect_snapshot_rows(snapshot)
+}
+
+pub(super) fn drain_historical_snapshot_rows(
+    runtime: &dyn ReplicationApi,
+    request: HistoricalSnapshotRowsRequest,
+) -> Vec<CapturedRowChange> {
+    let snapshot = wait_for_test_reply(runtime.snapshot_rows_at(request))
+        .expect("historical snapshot should start");
+    collect_snapshot_rows(snapshot)
+}
+
+fn collect_snapshot_rows(mut snapshot: SnapshotValueRows) -> Vec<CapturedRowChange> {
     let mut rows = Vec::new();
     while let Some(batch) =
         wait_for_test_reply(snapshot.rows.next_batch()).expect("snapshot batch should load")
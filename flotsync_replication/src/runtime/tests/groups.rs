@@ -18,7 +18,7 @@ fn runtime_startup_hydrates_persisted_group_memberships_from_store() {
         ReplicationGroupRecord {
             group_id,
             member_keys: test_group_member_keys(members.ordered_members()),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: docs_group_schema(),
             version_vector: VersionVector::initial(
                 NonZeroUsize::new(2).expect("group should have two members"),
@@ -57,6 +57,7 @@ fn create_group_persists_membership_across_runtime_restart() {
     let runtime = load_runtime_with_parts(app_alice_id(), store.clone(), first_listener);
     let group_id = wait_for_test_reply(runtime.create_group(CreateGroupRequest {
         members: vec![alice_member.clone()],
+        observers: Vec::new(),
         group_schema: docs_group_schema(),
     }))
     .expect("create_group should succeed");
@@ -244,6 +245,7 @@ fn auto_accept_commit_failure_restarts_from_activation_instead_of_listener_decis
 
     let group_id = wait_for_test_reply(alice_runtime.create_group(CreateGroupRequest {
         members: vec![alice_member, bob_member],
+        observers: Vec::new(),
         group_schema: GroupSchema::default(),
     }))
     .expect("group creation should succeed locally");
@@ -304,7 +306,7 @@ fn runtime_resumes_pending_group_activation_with_global_read_token() {
         ReplicationGroupRecord {
             group_id: unrelated_group_id,
             member_keys: test_group_member_keys(members.clone()),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: docs_group_schema(),
             version_vector: unrelated_versions.clone(),
             lifecycle: ReplicationGroupLifecycle::Open,
@@ -318,7 +320,7 @@ fn runtime_resumes_pending_group_activation_with_global_read_token() {
         ReplicationGroupRecord {
             group_id,
             member_keys: test_group_member_keys(members.clone()),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: docs_group_schema(),
             version_vector: VersionVector::initial(member_count),
             lifecycle: ReplicationGroupLifecycle::Open,
@@ -403,7 +405,7 @@ fn runtime_resumes_pending_migration_proposal_activation() {
         ReplicationGroupRecord {
             group_id: migration_id.old_group_id,
             member_keys: test_group_member_keys(members.clone()),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: docs_group_schema(),
             version_vector: VersionVector::initial(NonZeroUsize::new(2).unwrap()),
             lifecycle: ReplicationGroupLifecycle::ReadOnly {
@@ -421,6 +423,7 @@ fn runtime_resumes_pending_migration_proposal_activation() {
             migration_id,
             final_versions,
             proposed_members: members,
+            proposed_observers: Vec::new(),
             group_schema: docs_group_schema(),
             initial_snapshot: InitialSnapshot::Inline(InitialGroupValueRows {
                 datasets: vec![InitialDatasetValueRows {
@@ -617,6 +620,7 @@ fn metadata_pending_group_work_accept_rejects_without_persisting_activation() {
             migration_id,
             final_versions: VersionVector::Full(PureVersionVector::from([4, 0])),
             proposed_members: vec![alice_member_id, bob_member_id],
+            proposed_observers: Vec::new(),
             group_schema: GroupSchema::default(),
             initial_snapshot: metadata_initial_snapshot(migration_id.new_group_id, member_count),
             group_name: None,
@@ -722,3 +726,184 @@ fn runtime_replay_listener_failure_keeps_pending_group_decision() {
     assert_eq!(decisions[0].key(), decision_key);
     assert!(load_pending_group_activations(store.as_ref()).is_empty());
 }
+
+#[test]
+fn runtime_limits_resolve_from_flotsync_config() {
+    let mut loader = flotsync_utils::config::FlotsyncConfigLoader::new();
+    loader
+        .load_str(
+            r"
+            [flotsync.replication.runtime.limits]
+            max-group-members = 3
+            ",
+        )
+        .expect("valid TOML")
+        .load_env_vars([(
+            "FLOTSYNC__REPLICATION__RUNTIME__LIMITS__MAX_DOCUMENT_NODES",
+            "100",
+        )])
+        .expect("valid env override");
+    let config = loader.build().expect("config should build");
+
+    assert_eq!(
+        config
+            .settings::<RuntimeLimits>()
+            .expect("limits should resolve"),
+        RuntimeLimits {
+            max_group_members: 3,
+            max_document_nodes: 100,
+            ..RuntimeLimits::default()
+        }
+    );
+}
+
+#[test]
+fn create_group_rejects_member_count_above_configured_limit() {
+    let alice_member = alice_member();
+    let store = sqlite_store_with_schemas(
+        alice_member.clone(),
+        [(docs_dataset_id(), title_schema_static())],
+    );
+    let listener = Arc::new(ListenerStub::default());
+    let runtime = load_runtime_with_parts_and_config(
+        app_alice_id(),
+        store,
+        listener,
+        ReplicationConfig {
+            limits: RuntimeLimits {
+                max_group_members: 1,
+                ..RuntimeLimits::default()
+            },
+            ..ReplicationConfig::default()
+        },
+    );
+
+    let error = wait_for_test_reply(runtime.create_group(CreateGroupRequest {
+        members: vec![alice_member, bob_member()],
+        observers: Vec::new(),
+        group_schema: docs_group_schema(),
+    }))
+    .expect_err("create_group above the member limit should fail");
+
+    match error {
+        ApiError::ApiExternal { source } => match source.downcast_ref::<CreateGroupError>() {
+            Some(CreateGroupError::TooManyMembers {
+                member_count,
+                limit,
+            }) => {
+                assert_eq!(*member_count, 2);
+                assert_eq!(*limit, 1);
+            }
+            other => panic!("unexpected create_group error source: {other:?}"),
+        },
+        error => panic!("unexpected API error: {error:?}"),
+    }
+}
+
+#[test]
+fn create_group_rejects_observer_replica() {
+    let alice_member = alice_member();
+    let store = sqlite_store_with_schemas(
+        alice_member.clone(),
+        [(docs_dataset_id(), title_schema_static())],
+    );
+    let listener = Arc::new(ListenerStub::default());
+    let runtime = load_runtime_with_parts_and_config(
+        app_alice_id(),
+        store.clone(),
+        listener,
+        ReplicationConfig {
+            replica_role: ReplicaRole::Observer,
+            ..ReplicationConfig::default()
+        },
+    );
+
+    let error = wait_for_test_reply(runtime.create_group(CreateGroupRequest {
+        members: vec![alice_member],
+        observers: Vec::new(),
+        group_schema: docs_group_schema(),
+    }))
+    .expect_err("observers should not create groups");
+
+    match error {
+        ApiError::ApiExternal { source } => assert!(
+            matches!(
+                source.downcast_ref::<CreateGroupError>(),
+                Some(CreateGroupError::ObserverReplica)
+            ),
+            "unexpected create_group error source: {source:?}"
+        ),
+        error => panic!("unexpected API error: {error:?}"),
+    }
+    assert!(load_persisted_groups(store.as_ref()).is_empty());
+}
+
+#[test]
+fn observer_replica_rejects_invitations_that_assign_it_a_member_index() {
+    let _runtime_endpoint_leases =
+        reserve_sockets(&[ReservedSocketKind::UdpSocket, ReservedSocketKind::UdpSocket]);
+    let alice_member = alice_member();
+    let bob_member = bob_member();
+    let alice_fixture = load_runtime_fixture(
+        app_alice_id(),
+        alice_member.clone(),
+        [(docs_dataset_id(), title_schema_shared())],
+    );
+    let bob_store = sqlite_store_with_schemas(
+        bob_member.clone(),
+        [(docs_dataset_id(), title_schema_static())],
+    );
+    provision_test_security(
+        alice_fixture.store.as_ref(),
+        &alice_member,
+        [bob_member.clone()],
+    );
+    provision_test_security(bob_store.as_ref(), &bob_member, [alice_member.clone()]);
+    let bob_listener = Arc::new(ListenerStub::default());
+    let bob_runtime = load_runtime_with_parts_and_config(
+        app_bob_id(),
+        bob_store.clone(),
+        bob_listener.clone(),
+        ReplicationConfig {
+            replica_role: ReplicaRole::Observer,
+            group_invitation_policy: GroupInvitationPolicy {
+                creation: PolicyDecision::AutoAccept,
+                ..GroupInvitationPolicy::default()
+            },
+            ..ReplicationConfig::default()
+        },
+    );
+    let alice_runtime = &alice_fixture.runtime;
+    publish_direct_peer_routes(alice_runtime, &alice_member, &bob_runtime, &bob_member);
+
+    let positioned_group_id = wait_for_test_reply(alice_runtime.create_group(CreateGroupRequest {
+        members: vec![alice_member.clone(), bob_member.clone()],
+        observers: Vec::new(),
+        group_schema: docs_group_schema(),
+    }))
+    .expect("create_group should succeed");
+    // Invitations from one sender arrive in order, so this one settles the first.
+    let observed_group_id = wait_for_test_reply(alice_runtime.create_group(CreateGroupRequest {
+        members: vec![alice_member],
+        observers: vec![bob_member],
+        group_schema: docs_group_schema(),
+    }))
+    .expect("create_group should succeed");
+    wait_for_group_install(&bob_runtime, observed_group_id);
+
+    assert!(
+        !bob_runtime
+            .membership_snapshot_for_test()
+            .contains_group(&positioned_group_id)
+    );
+    assert_eq!(
+        load_persisted_groups(bob_store.as_ref())
+            .into_iter()
+            .map(|group| group.group_id)
+            .collect::<Vec<_>>(),
+        vec![observed_group_id]
+    );
+    assert!(load_pending_group_decisions(bob_store.as_ref()).is_empty());
+    assert!(load_pending_group_activations(bob_store.as_ref()).is_empty());
+    assert!(bob_listener.take_pending_group_events().is_empty());
+}
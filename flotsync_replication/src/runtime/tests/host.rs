@@ -1,6 +1,8 @@
 //! Runtime-host and runtime-startup scenarios.
 
 use super::*;
+use flotsync_utils::shutdown::shutdown_channel;
+use futures_util::future;
 
 #[test]
 fn delivery_runtime_host_updates_shared_group_memberships() {
@@ -59,6 +61,27 @@ fn runtime_shutdown_is_graceful_idempotent_and_marks_runtime_unavailable() {
     assert!(matches!(error, ApiError::RuntimeUnavailable));
 }
 
+#[test]
+fn coordinated_runtime_shutdown_completes_every_phase() {
+    let store = sqlite_store(alice_member());
+    provision_test_security(store.as_ref(), &alice_member(), []);
+    let listener = Arc::new(ListenerStub::default());
+    let runtime = load_runtime_with_parts(app_alice_id(), store, listener);
+    let (controller, token) = shutdown_channel();
+
+    let runtime_shutdown = runtime.shutdown_with(&token);
+    let (runtime_result, controller_result) = wait_for_test_future(future::join(
+        runtime_shutdown,
+        controller.shutdown(TEST_WAIT_TIMEOUT),
+    ));
+
+    runtime_result.expect("runtime should shut down gracefully");
+    controller_result.expect("runtime should complete every shutdown phase in time");
+    let error = wait_for_test_reply(runtime.local_public_key_bundle())
+        .expect_err("runtime API should be unavailable after shutdown");
+    assert!(matches!(error, ApiError::RuntimeUnavailable));
+}
+
 #[test]
 fn dropping_runtime_inside_test_executor_does_not_reenter_local_pool() {
     let store = sqlite_store(alice_member());
@@ -264,7 +287,7 @@ fn load_replication_runtime_allows_unresolved_member_keys_for_stored_groups() {
         ReplicationGroupRecord {
             group_id,
             member_keys: member_keys.clone(),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: GroupSchema::default(),
             version_vector: VersionVector::initial(NonZeroUsize::new(2).unwrap()),
             lifecycle: ReplicationGroupLifecycle::Open,
@@ -317,7 +340,7 @@ fn load_replication_runtime_allows_ambiguous_member_keys_when_group_names_exact_
         ReplicationGroupRecord {
             group_id,
             member_keys: member_keys.clone(),
-            local_member_index: MemberIndex::new(0),
+            local_member_index: Some(MemberIndex::new(0)),
             group_schema: GroupSchema::default(),
             version_vector: VersionVector::initial(NonZeroUsize::new(2).unwrap()),
             lifecycle: ReplicationGroupLifecycle::Open,
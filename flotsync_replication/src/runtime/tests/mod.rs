@@ -1,6 +1,6 @@
 use super::{
     component::ReplicationRuntimeComponent,
-    errors::{CreateGroupError, InboundDeliveryError, PublishChangesError},
+    errors::{CreateGroupError, InboundDeliveryError, InboundFailureAction, PublishChangesError},
     handle::{
         ReplicationRuntime,
         load_replication_runtime_typed_with_security_for_test,
@@ -30,6 +30,7 @@ use crate::{
         ApiError,
         AuthorityScope,
         ChangeGroupMembershipRequest,
+        CreateCheckpointRequest,
         CreateGroupRequest,
         DatasetId,
         DatasetRowStateBatch,
@@ -43,6 +44,8 @@ use crate::{
         GroupInvitationSource,
         GroupMemberKeys,
         GroupSchema,
+        GroupStorageSizes,
+        HistoricalSnapshotRowsRequest,
         InitialDatasetValueRows,
         InitialGroupValueRows,
         InitialSnapshot,
@@ -63,6 +66,7 @@ use crate::{
         MigrationId,
         MigrationProposal,
         MigrationProposalResponder,
+        PeerLiveness,
         PendingGroupActivationRecord,
         PendingGroupDecisionRecord,
         PendingGroupWorkKey,
@@ -73,6 +77,7 @@ use crate::{
         PublishReceipt,
         ReadToken,
         RejectionReason,
+        ReplicaRole,
         ReplicationApi,
         ReplicationConfig,
         ReplicationEvent,
@@ -92,16 +97,22 @@ use crate::{
         RowKey,
         RowKeyIterator,
         RowMutation,
+        RuntimeLimits,
         SchemaSource,
         SnapshotRef,
         SnapshotRowsRequest,
         SnapshotValueRow,
+        SnapshotValueRows,
         StoreError,
         StoreExternalSnafu,
         StoreSecretCryptoVersion,
         StoreSecretKeyId,
         SummaryRequest,
+        SyncStepProgress,
         TrustPolicy,
+        WorkspaceEvent,
+        checkpoint_kinds,
+        checkpoints_dataset_id,
         current_slice_placeholder_group_security_material,
         current_slice_placeholder_group_security_material_with_key_id,
         process_batches,
@@ -420,6 +431,16 @@ impl ReplicationStoreReadTransaction for FailingStoreTransaction {
             .scan_dataset_row_batch(group_id, dataset_id, after, limit)
     }
 
+    fn load_group_storage_sizes<'a>(
+        &'a mut self,
+        group_id: &'a GroupId,
+    ) -> BoxFuture<'a, Result<GroupStorageSizes, StoreError>> {
+        self.inner
+            .as_mut()
+            .expect("failing store transaction must remain open during delegated reads")
+            .load_group_storage_sizes(group_id)
+    }
+
     fn load_pending_group_decisions(
         &mut self,
     ) -> BoxFuture<'_, Result<Vec<PendingGroupDecisionRecord>, StoreError>> {
@@ -900,6 +921,11 @@ impl ReplicationEventListener for ListenerStub {
                     let mut captured_rows = Vec::new();
                     process_batches::<RowChangeBatch>(rows.as_mut(), |batch| {
                         for change in batch.drain(..) {
+                            // Checkpoint documents are published like any other row, but
+                            // they don't carry the `title` field captured here.
+                            if change.row_id().dataset_id == checkpoints_dataset_id() {
+                                continue;
+                            }
                             let captured = CapturedRowChange::capture(change)
                                 .boxed()
                                 .context(ProviderExternalSnafu)?;
@@ -16,6 +16,7 @@ fn create_group_rejects_missing_permitted_keys_without_storing_group() {
 
     let error = wait_for_test_reply(runtime.create_group(CreateGroupRequest {
         members: vec![alice_member, bob_member.clone()],
+        observers: Vec::new(),
         group_schema: GroupSchema::default(),
     }))
     .expect_err("missing permitted keys should reject group creation");
@@ -58,6 +59,7 @@ fn bootstrap_payload_validation_rejects_unpermitted_sender_fingerprint() {
         BootstrapMemberKeyMessage::from_fingerprint(probe_keys.fingerprint());
     let payload = GroupSetupMessage::new(
         vec![alice_member.clone(), bob_member.clone()],
+        Vec::new(),
         bootstrap_member_keys([
             (alice_member.clone(), mismatched_alice_key),
             bootstrap_member_key(&bob_keys),
@@ -118,6 +120,7 @@ fn bootstrap_payload_validation_accepts_advertised_sender_fingerprint_when_multi
     let bob_keys = test_public_keys(&bob_member);
     let payload = GroupSetupMessage::new(
         vec![alice_member.clone(), bob_member.clone()],
+        Vec::new(),
         bootstrap_member_keys([
             bootstrap_member_key(&alternate_alice_keys),
             bootstrap_member_key(&bob_keys),
@@ -156,6 +159,7 @@ fn bootstrap_payload_validation_rejects_sender_without_bootstrap_activation_perm
     let bob_keys = test_public_keys(&bob_member);
     let payload = GroupSetupMessage::new(
         vec![alice_member.clone(), bob_member.clone()],
+        Vec::new(),
         bootstrap_member_keys([
             bootstrap_member_key(&alice_keys),
             bootstrap_member_key(&bob_keys),
@@ -203,6 +207,7 @@ fn bootstrap_prepare_stores_inline_unknown_keys_without_trust_evidence() {
             bob_member.clone(),
             charlie_member.clone(),
         ],
+        Vec::new(),
         bootstrap_member_keys([
             bootstrap_member_key(&alice_keys),
             bootstrap_member_key(&bob_keys),
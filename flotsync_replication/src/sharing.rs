@@ -0,0 +1,550 @@
+//! Sharing documents from one replication group into another.
+//!
+//! Groups have their own membership and version vectors, so a document cannot simply be listed
+//! in two groups. A [`DocumentShare`] instead names a source document and a target document in a
+//! different group, and a [`DocumentShareBridgeComponent`] running on a member of both groups
+//! keeps the target a copy of the source: whenever the source changes, the bridge publishes its
+//! current values, or its deletion, into the target group as an ordinary local change.
+//!
+//! Each mirrored publish is recorded in a [`StampTranslation`], which relates the versions of the
+//! source group that were copied to the [`UpdateId`] that carried them into the target group.
+//! This lets applications in either group tell which updates correspond to each other.
+//!
+//! Sharing is one-way. Members of the target group can still edit the copy, but their edits are
+//! overwritten by the next change to the source, so a share is effectively read-only for the
+//! wider audience. Doc references inside the copied values are not translated and keep pointing
+//! into the source group.
+
+use crate::api::{
+    ApiError,
+    PublishChangesRequest,
+    ReadToken,
+    ReplicationApi,
+    RowId,
+    RowMutation,
+    RowProviderError,
+    RowValuesPatch,
+    SnapshotRowsRequest,
+    WorkspaceEvent,
+    WorkspaceEventReceiver,
+};
+use flotsync_core::{
+    GroupId,
+    versions::{UpdateId, VersionVector},
+};
+use flotsync_data_types::{NullableBasicValue, RowValueRead};
+use kompact::prelude::*;
+use snafu::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    num::NonZeroUsize,
+    sync::Arc,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+/// Errors reported when defining a [`DocumentShare`].
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum DocumentShareError {
+    #[snafu(display(
+        "Cannot share document {source_row} into its own group {group_id}; the target must be in a different group."
+    ))]
+    SameGroup {
+        source_row: RowId,
+        group_id: GroupId,
+    },
+}
+
+/// Errors reported while copying a shared document into its target group.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum DocumentMirrorError {
+    #[snafu(display("Failed to read source document {source_row}: {source}"))]
+    ReadSource { source_row: RowId, source: ApiError },
+    #[snafu(display("Failed to scan rows of source document {source_row}: {source}"))]
+    ScanSource {
+        source_row: RowId,
+        source: RowProviderError,
+    },
+    #[snafu(display("Failed to read group {group_id} to publish into it: {source}"))]
+    ReadTarget { group_id: GroupId, source: ApiError },
+    #[snafu(display("Failed to publish document {source_row} into {target_row}: {source}"))]
+    PublishTarget {
+        source_row: RowId,
+        target_row: RowId,
+        source: ApiError,
+    },
+}
+
+/// A document published from one replication group into another.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DocumentShare {
+    source: RowId,
+    target: RowId,
+}
+
+impl DocumentShare {
+    /// Share the document `source` as the document `target`.
+    ///
+    /// The target dataset must accept every field of the source dataset.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DocumentShareError::SameGroup`] if both documents are in the same group.
+    pub fn new(source: RowId, target: RowId) -> Result<Self, DocumentShareError> {
+        ensure!(
+            source.group_id != target.group_id,
+            SameGroupSnafu {
+                source_row: source.clone(),
+                group_id: source.group_id,
+            }
+        );
+        Ok(Self { source, target })
+    }
+
+    /// The shared document.
+    #[must_use]
+    pub fn source(&self) -> &RowId {
+        &self.source
+    }
+
+    /// The copy of the shared document in the target group.
+    #[must_use]
+    pub fn target(&self) -> &RowId {
+        &self.target
+    }
+}
+
+/// Correspondence between updates of a share's source and target group.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StampTranslation {
+    /// Mirrored publishes, in the order they were made.
+    stamps: Vec<TranslatedStamp>,
+}
+
+impl StampTranslation {
+    /// Record that the source state at `source_versions` was published as `target_update`.
+    pub fn record(&mut self, source_versions: VersionVector, target_update: UpdateId) {
+        self.stamps.push(TranslatedStamp {
+            source_versions,
+            target_update,
+        });
+    }
+
+    /// Return the first target update that carried the effect of `source_update`.
+    ///
+    /// Returns `None` if `source_update` has not been mirrored yet.
+    #[must_use]
+    pub fn to_target(&self, source_update: UpdateId) -> Option<UpdateId> {
+        self.stamps
+            .iter()
+            .find(|stamp| {
+                stamp
+                    .source_versions
+                    .get(source_update.node_index as usize)
+                    .is_some_and(|version| version >= source_update.version)
+            })
+            .map(|stamp| stamp.target_update)
+    }
+
+    /// Return the source group versions that `target_update` mirrored.
+    ///
+    /// Returns `None` if `target_update` was not published by the bridge.
+    #[must_use]
+    pub fn to_source(&self, target_update: UpdateId) -> Option<&VersionVector> {
+        self.stamps
+            .iter()
+            .find(|stamp| stamp.target_update == target_update)
+            .map(|stamp| &stamp.source_versions)
+    }
+
+    /// Return whether nothing has been mirrored yet.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.stamps.is_empty()
+    }
+}
+
+/// Messages accepted by [`DocumentShareBridgeComponent`].
+pub enum DocumentShareMessage {
+    /// Start mirroring a share, copying the current source right away.
+    Share(DocumentShare),
+    /// Stop mirroring the share whose source is the given document.
+    ///
+    /// The copy already published into the target group is kept.
+    Unshare(RowId),
+    /// Return the stamp translation of the share whose source is the given document.
+    Translation(Ask<RowId, Option<StampTranslation>>),
+}
+
+impl fmt::Debug for DocumentShareMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Share(share) => f.debug_tuple("Share").field(share).finish(),
+            Self::Unshare(source) => f.debug_tuple("Unshare").field(source).finish(),
+            Self::Translation(ask) => f.debug_tuple("Translation").field(ask.request()).finish(),
+        }
+    }
+}
+
+/// Kompact component that keeps the targets of [`DocumentShare`]s in sync with their sources.
+///
+/// The bridge follows the runtime's [workspace events](crate::api::WorkspaceEvents) and mirrors a
+/// source whenever a [`WorkspaceEvent::DocumentChanged`] includes it. If it falls behind the
+/// event bus, it mirrors every source again. The local member must belong to both groups of every
+/// share. Failed mirrors are logged and retried on the next change to the source.
+#[derive(ComponentDefinition)]
+pub struct DocumentShareBridgeComponent {
+    ctx: ComponentContext<Self>,
+    api: Arc<dyn ReplicationApi>,
+    shares: HashMap<RowId, MirroredShare>,
+    /// Read position covering every group the bridge has read or published into.
+    read_token: Option<ReadToken>,
+}
+
+impl DocumentShareBridgeComponent {
+    /// Create a bridge publishing through `api`.
+    #[must_use]
+    pub fn new(api: Arc<dyn ReplicationApi>) -> Self {
+        Self {
+            ctx: ComponentContext::uninitialised(),
+            api,
+            shares: HashMap::new(),
+            read_token: None,
+        }
+    }
+
+    /// Mirror `share` once the component has started.
+    #[must_use]
+    pub fn with_share(mut self, share: DocumentShare) -> Self {
+        self.shares
+            .insert(share.source.clone(), MirroredShare::new(share));
+        self
+    }
+
+    fn follow_workspace_events(&mut self, mut events: WorkspaceEventReceiver) {
+        self.spawn_local(move |mut async_self| async move {
+            loop {
+                match events.recv().await {
+                    Ok(WorkspaceEvent::DocumentChanged {
+                        read_token,
+                        documents,
+                    }) => {
+                        async_self.merge_read_token(&read_token);
+                        let sources: Vec<RowId> = documents
+                            .into_iter()
+                            .filter(|document| async_self.shares.contains_key(document))
+                            .collect();
+                        async_self.mirror_sources(sources).await;
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(
+                            async_self.log(),
+                            "Document share bridge skipped {skipped} workspace events; mirroring every share again"
+                        );
+                        let sources: Vec<RowId> = async_self.shares.keys().cloned().collect();
+                        async_self.mirror_sources(sources).await;
+                    }
+                    Err(RecvError::Closed) => {
+                        debug!(
+                            async_self.log(),
+                            "Workspace events closed; document share bridge stops mirroring"
+                        );
+                        break;
+                    }
+                }
+            }
+            Handled::OK
+        });
+    }
+
+    fn mirror_later(&mut self, sources: Vec<RowId>) {
+        self.spawn_local(move |mut async_self| async move {
+            async_self.mirror_sources(sources).await;
+            Handled::OK
+        });
+    }
+
+    async fn mirror_sources(&mut self, sources: Vec<RowId>) {
+        for source in sources {
+            let Some(share) = self.shares.get(&source) else {
+                continue;
+            };
+            let share = share.share.clone();
+            if let Err(error) = self.mirror(&share).await {
+                warn!(
+                    self.log(),
+                    "Failed to mirror shared document {source}: {error}"
+                );
+            }
+        }
+    }
+
+    /// Publish the current state of `share`'s source into its target.
+    async fn mirror(&mut self, share: &DocumentShare) -> Result<(), DocumentMirrorError> {
+        let Some(source_state) = self.read_source(&share.source).await? else {
+            return Ok(());
+        };
+        let read_token = self.target_read_token(&share.target).await?;
+        let change = match source_state.fields {
+            Some(fields) => RowMutation::Upsert {
+                row_id: share.target.clone(),
+                row: RowValuesPatch::new(fields),
+            },
+            None => RowMutation::Delete {
+                row_id: share.target.clone(),
+            },
+        };
+        let receipt = self
+            .api
+            .publish_changes(PublishChangesRequest {
+                read_token,
+                changes: vec![change],
+            })
+            .await
+            .context(PublishTargetSnafu {
+                source_row: share.source.clone(),
+                target_row: share.target.clone(),
+            })?;
+        self.merge_read_token(&receipt.read_token);
+        if let Some(mirrored) = self.shares.get_mut(&share.source) {
+            mirrored
+                .translation
+                .record(source_state.versions, receipt.update_id);
+        }
+        Ok(())
+    }
+
+    /// Read the current values of `source`.
+    ///
+    /// Returns `None` if the store no longer holds the row, for example after its tombstone was
+    /// purged.
+    async fn read_source(
+        &mut self,
+        source: &RowId,
+    ) -> Result<Option<SourceState>, DocumentMirrorError> {
+        let mut snapshot = self
+            .api
+            .snapshot_rows(SnapshotRowsRequest {
+                group_id: source.group_id,
+                datasets: HashSet::from([source.dataset_id.clone()]),
+                max_rows_per_batch: SNAPSHOT_BATCH_SIZE,
+                include_tombstones: true,
+            })
+            .await
+            .context(ReadSourceSnafu {
+                source_row: source.clone(),
+            })?;
+        self.merge_read_token(&snapshot.read_token);
+        let Some(versions) = snapshot.read_token.group_version(&source.group_id).cloned() else {
+            return Ok(None);
+        };
+        let mut fields = None;
+        let mut found = false;
+        while !found {
+            let batch = snapshot.rows.next_batch().await.context(ScanSourceSnafu {
+                source_row: source.clone(),
+            })?;
+            let Some(batch) = batch else {
+                return Ok(None);
+            };
+            let Some(data) = batch.data() else {
+                continue;
+            };
+            let Some(row) = batch.rows().find(|row| row.row_id() == source) else {
+                continue;
+            };
+            found = true;
+            if !row.is_tombstoned() {
+                fields = Some(
+                    data.schema()
+                        .columns
+                        .keys()
+                        .filter_map(|field_name| {
+                            row.get_value(field_name)
+                                .map(|value| (field_name.clone(), value.into_owned()))
+                        })
+                        .collect(),
+                );
+            }
+        }
+        Ok(Some(SourceState { versions, fields }))
+    }
+
+    /// Return a read token that covers the group of `target`, reading the group if necessary.
+    async fn target_read_token(
+        &mut self,
+        target: &RowId,
+    ) -> Result<ReadToken, DocumentMirrorError> {
+        let group_id = target.group_id;
+        if let Some(read_token) = &self.read_token
+            && read_token.group_version(&group_id).is_some()
+        {
+            return Ok(read_token.clone());
+        }
+        let snapshot = self
+            .api
+            .snapshot_rows(SnapshotRowsRequest {
+                group_id,
+                datasets: HashSet::from([target.dataset_id.clone()]),
+                max_rows_per_batch: SNAPSHOT_BATCH_SIZE,
+                include_tombstones: false,
+            })
+            .await
+            .context(ReadTargetSnafu { group_id })?;
+        self.merge_read_token(&snapshot.read_token);
+        drop(snapshot.rows);
+        Ok(snapshot.read_token)
+    }
+
+    fn merge_read_token(&mut self, applied: &ReadToken) {
+        match &mut self.read_token {
+            Some(read_token) => read_token.merge_applied(applied),
+            None => self.read_token = Some(applied.clone()),
+        }
+    }
+}
+
+impl Actor for DocumentShareBridgeComponent {
+    type Message = DocumentShareMessage;
+
+    fn receive_local(&mut self, msg: Self::Message) -> HandlerResult {
+        match msg {
+            DocumentShareMessage::Share(share) => {
+                let source = share.source.clone();
+                debug!(self.log(), "Sharing document {source} as {}", share.target);
+                self.shares
+                    .insert(source.clone(), MirroredShare::new(share));
+                self.mirror_later(vec![source]);
+            }
+            DocumentShareMessage::Unshare(source) => {
+                if self.shares.remove(&source).is_some() {
+                    debug!(self.log(), "Stopped sharing document {source}");
+                }
+            }
+            DocumentShareMessage::Translation(ask) => {
+                let translation = self
+                    .shares
+                    .get(ask.request())
+                    .map(|mirrored| mirrored.translation.clone());
+                ask.reply(translation)
+                    .expect("stamp translation requester must still be waiting");
+            }
+        }
+        Handled::OK
+    }
+}
+
+impl ComponentLifecycle for DocumentShareBridgeComponent {
+    fn on_start(&mut self) -> HandlerResult {
+        match self.api.subscribe_workspace_events() {
+            Ok(events) => self.follow_workspace_events(events),
+            Err(error) => {
+                error!(
+                    self.log(),
+                    "Document share bridge cannot follow workspace events: {error}"
+                );
+                return Handled::SHUTDOWN;
+            }
+        }
+        let sources: Vec<RowId> = self.shares.keys().cloned().collect();
+        self.mirror_later(sources);
+        Handled::OK
+    }
+}
+
+/// Number of rows read per batch while looking for a source document.
+const SNAPSHOT_BATCH_SIZE: NonZeroUsize = NonZeroUsize::new(256).unwrap();
+
+/// One mirrored publish recorded in a [`StampTranslation`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TranslatedStamp {
+    source_versions: VersionVector,
+    target_update: UpdateId,
+}
+
+/// A share together with the stamps mirrored for it so far.
+#[derive(Debug)]
+struct MirroredShare {
+    share: DocumentShare,
+    translation: StampTranslation,
+}
+
+impl MirroredShare {
+    fn new(share: DocumentShare) -> Self {
+        Self {
+            share,
+            translation: StampTranslation::default(),
+        }
+    }
+}
+
+/// Values of a source document as read for mirroring.
+struct SourceState {
+    /// Source group versions the values were read at.
+    versions: VersionVector,
+    /// Field values, or `None` if the document is deleted.
+    fields: Option<HashMap<String, NullableBasicValue>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::RowKey, test_support::docs_dataset_id};
+    use uuid::Uuid;
+
+    fn row(group_id: GroupId) -> RowId {
+        RowId {
+            group_id,
+            dataset_id: docs_dataset_id(),
+            row_key: RowKey(Uuid::new_v4()),
+        }
+    }
+
+    #[test]
+    fn share_rejects_target_in_same_group() {
+        let group_id = GroupId(Uuid::new_v4());
+        let error = DocumentShare::new(row(group_id), row(group_id))
+            .expect_err("sharing within one group must be rejected");
+        assert!(matches!(error, DocumentShareError::SameGroup { .. }));
+        DocumentShare::new(row(group_id), row(GroupId(Uuid::new_v4())))
+            .expect("sharing into another group must be accepted");
+    }
+
+    #[test]
+    fn translation_maps_source_updates_to_first_covering_target_update() {
+        let mut translation = StampTranslation::default();
+        let first = UpdateId {
+            version: 1,
+            node_index: 0,
+        };
+        let second = UpdateId {
+            version: 2,
+            node_index: 0,
+        };
+        translation.record(VersionVector::from_entries([1, 0]), first);
+        translation.record(VersionVector::from_entries([3, 1]), second);
+
+        let source_update = |version, node_index| UpdateId {
+            version,
+            node_index,
+        };
+        assert_eq!(translation.to_target(source_update(1, 0)), Some(first));
+        assert_eq!(translation.to_target(source_update(2, 0)), Some(second));
+        assert_eq!(translation.to_target(source_update(1, 1)), Some(second));
+        assert_eq!(translation.to_target(source_update(4, 0)), None);
+        assert_eq!(
+            translation.to_source(second),
+            Some(&VersionVector::from_entries([3, 1]))
+        );
+        assert_eq!(
+            translation.to_source(UpdateId {
+                version: 3,
+                node_index: 0,
+            }),
+            None
+        );
+    }
+}
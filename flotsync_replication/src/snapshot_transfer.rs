@@ -0,0 +1,1234 @@
+//! Chunked, resumable network transfer of initial snapshots.
+//!
+//! Invitations and migration proposals whose initial rows are too large to carry inline announce
+//! them as [`InitialSnapshotMetadata`](crate::api::InitialSnapshotMetadata) instead. The peer that
+//! holds the rows splits them with a [`SnapshotSource`] into chunks, each covering a contiguous
+//! row-key range of one dataset, and describes the split in a [`SnapshotManifest`].
+//! Chunk ids are derived from the snapshot id and the range bounds, so splitting the same snapshot
+//! again yields the same ids.
+//!
+//! The receiver fetches the manifest, then tracks received chunks in a [`SnapshotDownload`].
+//! [`SnapshotDownload::next_request`] only ever asks for chunks that are still missing, and
+//! [`SnapshotDownload::resume`] rebuilds the download from chunks kept across an interruption, so
+//! a transfer that broke off late does not start over from the first chunk.
+//!
+//! [`SnapshotSource::respond_compressed`] compresses chunk data for the negotiated connection.
+//! Manifest lengths and hashes always describe the uncompressed data, so chunks stay verifiable
+//! and resumable regardless of how each copy travelled.
+
+use crate::{
+    api::{
+        DatasetId,
+        DatasetIdError,
+        GroupSchema,
+        InitialDatasetValueRows,
+        InitialGroupValueRows,
+        RowKey,
+        SnapshotRef,
+    },
+    blobs::BlobHash,
+    codecs::pending_group::PendingGroupPayloadError,
+    delivery::compression::{
+        CompressionAlgorithm,
+        CompressionError,
+        CompressionMetrics,
+        ConnectionCompression,
+        PayloadClass,
+    },
+};
+use bytes::Bytes;
+use flotsync_core::GroupId;
+use flotsync_messages::{
+    buffa::{self, Message as _, MessageView as _},
+    proto::{
+        DecodeProto,
+        DecodeProtoView,
+        DecodeProtoWith,
+        EncodeProto,
+        FromProtoDecodeError,
+        ProtoCodec,
+    },
+    replication as replication_proto,
+    wire::{
+        WireValueDecodeError,
+        fixed_bytes_field,
+        group_id_from_wire_bytes,
+        group_id_to_wire_bytes,
+        uuid_from_wire_bytes,
+        uuid_to_wire_bytes,
+    },
+};
+use sha2::{Digest, Sha256};
+use snafu::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    num::NonZeroUsize,
+};
+
+/// Byte length of one [`SnapshotId`] or [`SnapshotChunkId`].
+pub const SNAPSHOT_DIGEST_LENGTH: usize = 32;
+
+/// Chunk budget used by [`SnapshotSource::split_default`].
+pub const DEFAULT_SNAPSHOT_CHUNK_BYTES: NonZeroUsize = NonZeroUsize::new(256 * 1024).unwrap();
+
+/// Digest identifying one materialised snapshot on the wire.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotId([u8; SNAPSHOT_DIGEST_LENGTH]);
+
+impl SnapshotId {
+    /// Derive the id of the snapshot named by `snapshot_ref`.
+    ///
+    /// The digest covers the group id, the member count, and every member's version in position
+    /// order, so equivalent vectors produce the same id regardless of their in-memory
+    /// representation.
+    #[must_use]
+    pub fn of(snapshot_ref: &SnapshotRef) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(snapshot_ref.group_id.0.as_bytes());
+        let num_members = snapshot_ref.versions.num_members().get() as u64;
+        hasher.update(num_members.to_le_bytes());
+        for version in snapshot_ref.versions.iter() {
+            hasher.update(version.to_le_bytes());
+        }
+        Self(hasher.finalize().into())
+    }
+
+    /// Build an id from its raw bytes.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; SNAPSHOT_DIGEST_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    /// Return this id's raw bytes.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; SNAPSHOT_DIGEST_LENGTH] {
+        &self.0
+    }
+
+    fn from_wire(raw: &[u8], field: &'static str) -> Result<Self, SnapshotTransferError> {
+        decode_digest(raw, field).map(Self)
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(f, &self.0)
+    }
+}
+
+impl fmt::Debug for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SnapshotId({self})")
+    }
+}
+
+/// Stable id of one snapshot chunk, derived from the row-key range it covers.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotChunkId([u8; SNAPSHOT_DIGEST_LENGTH]);
+
+impl SnapshotChunkId {
+    /// Derive the id of the chunk covering `first_row_key..=last_row_key` of `dataset_id`.
+    #[must_use]
+    pub fn derive(
+        snapshot_id: SnapshotId,
+        dataset_id: &DatasetId,
+        first_row_key: RowKey,
+        last_row_key: RowKey,
+    ) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(snapshot_id.as_bytes());
+        hasher.update((dataset_id.as_str().len() as u64).to_be_bytes());
+        hasher.update(dataset_id.as_str().as_bytes());
+        hasher.update(first_row_key.0.as_bytes());
+        hasher.update(last_row_key.0.as_bytes());
+        Self(hasher.finalize().into())
+    }
+
+    /// Build an id from its raw bytes.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; SNAPSHOT_DIGEST_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    /// Return this id's raw bytes.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; SNAPSHOT_DIGEST_LENGTH] {
+        &self.0
+    }
+
+    fn from_wire(raw: &[u8], field: &'static str) -> Result<Self, SnapshotTransferError> {
+        decode_digest(raw, field).map(Self)
+    }
+}
+
+impl fmt::Display for SnapshotChunkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(f, &self.0)
+    }
+}
+
+impl fmt::Debug for SnapshotChunkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SnapshotChunkId({self})")
+    }
+}
+
+/// Errors produced by snapshot chunking, transfer, and protocol decoding.
+#[derive(Debug, Snafu)]
+pub enum SnapshotTransferError {
+    #[snafu(display("Failed to decode snapshot transfer payload."))]
+    Decode { source: buffa::DecodeError },
+    #[snafu(display("Snapshot transfer field '{field}' was invalid: {source}"))]
+    InvalidWireValue {
+        field: &'static str,
+        source: WireValueDecodeError,
+    },
+    #[snafu(display("Snapshot transfer dataset id '{value}' was invalid: {source}"))]
+    InvalidDatasetId {
+        value: String,
+        source: DatasetIdError,
+    },
+    #[snafu(display("Received a chunk of snapshot {actual}, but the download is for {expected}."))]
+    SnapshotMismatch {
+        expected: SnapshotId,
+        actual: SnapshotId,
+    },
+    #[snafu(display("Chunk {chunk_id} is not part of the snapshot manifest."))]
+    UnknownChunk { chunk_id: SnapshotChunkId },
+    #[snafu(display(
+        "Chunk {chunk_id} has {actual_length} bytes, but the manifest announced {expected_length}."
+    ))]
+    ChunkLengthMismatch {
+        chunk_id: SnapshotChunkId,
+        expected_length: u64,
+        actual_length: u64,
+    },
+    #[snafu(display("Chunk {chunk_id} could not be decompressed: {source}"))]
+    DecompressChunk {
+        chunk_id: SnapshotChunkId,
+        source: CompressionError,
+    },
+    #[snafu(display("Content of chunk {chunk_id} does not match its manifest hash."))]
+    ChunkContentMismatch { chunk_id: SnapshotChunkId },
+    #[snafu(display("Snapshot {snapshot_id} is still missing {missing} chunks."))]
+    IncompleteSnapshot {
+        snapshot_id: SnapshotId,
+        missing: usize,
+    },
+    #[snafu(display("Rows of chunk {chunk_id} could not be decoded: {source}"))]
+    DecodeChunkRows {
+        chunk_id: SnapshotChunkId,
+        source: PendingGroupPayloadError,
+    },
+    #[snafu(display("Rows of chunk {chunk_id} disagree with the chunk's manifest entry."))]
+    InconsistentChunkRows { chunk_id: SnapshotChunkId },
+}
+
+impl FromProtoDecodeError for SnapshotTransferError {
+    fn from_proto_decode_error(source: buffa::DecodeError) -> Self {
+        Self::Decode { source }
+    }
+}
+
+/// Manifest entry for one chunk of a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotChunkDescriptor {
+    pub id: SnapshotChunkId,
+    pub dataset_id: DatasetId,
+    /// First row key covered by the chunk, inclusive.
+    pub first_row_key: RowKey,
+    /// Last row key covered by the chunk, inclusive.
+    pub last_row_key: RowKey,
+    pub row_count: u64,
+    pub byte_length: u64,
+    /// SHA-256 digest of the chunk data.
+    pub content_hash: BlobHash,
+}
+
+impl ProtoCodec for SnapshotChunkDescriptor {
+    type DecodeError = SnapshotTransferError;
+    type Proto = replication_proto::SnapshotChunkDescriptor;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::SnapshotChunkDescriptor {
+            chunk_id: self.id.as_bytes().to_vec(),
+            dataset_id: self.dataset_id.to_string(),
+            first_row_key: uuid_to_wire_bytes(self.first_row_key.0),
+            last_row_key: uuid_to_wire_bytes(self.last_row_key.0),
+            row_count: self.row_count,
+            byte_length: self.byte_length,
+            content_hash: self.content_hash.as_bytes().to_vec(),
+            ..replication_proto::SnapshotChunkDescriptor::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let id =
+            SnapshotChunkId::from_wire(&message.chunk_id, "snapshot_chunk_descriptor.chunk_id")?;
+        let dataset_id =
+            DatasetId::try_new(message.dataset_id.clone()).context(InvalidDatasetIdSnafu {
+                value: message.dataset_id,
+            })?;
+        let first_row_key = decode_row_key(
+            &message.first_row_key,
+            "snapshot_chunk_descriptor.first_row_key",
+        )?;
+        let last_row_key = decode_row_key(
+            &message.last_row_key,
+            "snapshot_chunk_descriptor.last_row_key",
+        )?;
+        let content_hash = decode_digest(
+            &message.content_hash,
+            "snapshot_chunk_descriptor.content_hash",
+        )
+        .map(BlobHash::from_bytes)?;
+        Ok(Self {
+            id,
+            dataset_id,
+            first_row_key,
+            last_row_key,
+            row_count: message.row_count,
+            byte_length: message.byte_length,
+            content_hash,
+        })
+    }
+}
+
+/// Complete chunk layout of one snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotManifest {
+    pub group_id: GroupId,
+    pub snapshot_id: SnapshotId,
+    /// Chunks in assembly order.
+    pub chunks: Vec<SnapshotChunkDescriptor>,
+}
+
+impl SnapshotManifest {
+    /// Sum of the byte lengths of all chunks.
+    #[must_use]
+    pub fn total_bytes(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.byte_length).sum()
+    }
+
+    /// Sum of the row counts of all chunks.
+    #[must_use]
+    pub fn total_rows(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.row_count).sum()
+    }
+}
+
+impl ProtoCodec for SnapshotManifest {
+    type DecodeError = SnapshotTransferError;
+    type Proto = replication_proto::SnapshotManifest;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::SnapshotManifest {
+            group_id: group_id_to_wire_bytes(self.group_id),
+            snapshot_id: self.snapshot_id.as_bytes().to_vec(),
+            chunks: self
+                .chunks
+                .iter()
+                .map(SnapshotChunkDescriptor::to_proto)
+                .collect(),
+            ..replication_proto::SnapshotManifest::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = decode_group_id(&message.group_id, "snapshot_manifest.group_id")?;
+        let snapshot_id =
+            SnapshotId::from_wire(&message.snapshot_id, "snapshot_manifest.snapshot_id")?;
+        let chunks = message
+            .chunks
+            .into_iter()
+            .map(SnapshotChunkDescriptor::from_proto)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            group_id,
+            snapshot_id,
+            chunks,
+        })
+    }
+}
+
+impl DecodeProtoView for SnapshotManifest {
+    type Error = SnapshotTransferError;
+    type ProtoView<'a> = replication_proto::SnapshotManifestView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let message = message.to_owned_message().context(DecodeSnafu)?;
+        Self::decode_proto(message)
+    }
+}
+
+/// Sender-side chunked form of one snapshot's initial rows.
+#[derive(Clone, Debug)]
+pub struct SnapshotSource {
+    manifest: SnapshotManifest,
+    chunks: HashMap<SnapshotChunkId, Bytes>,
+}
+
+impl SnapshotSource {
+    /// Split `rows` into chunks of roughly `max_chunk_bytes` encoded bytes.
+    ///
+    /// Rows are ordered by row key within each dataset, so the same rows always produce the same
+    /// chunk ranges and ids. A single row larger than the budget gets a chunk of its own.
+    #[must_use]
+    pub fn split(
+        group_id: GroupId,
+        snapshot_ref: &SnapshotRef,
+        rows: &InitialGroupValueRows,
+        max_chunk_bytes: NonZeroUsize,
+    ) -> Self {
+        let snapshot_id = SnapshotId::of(snapshot_ref);
+        let mut source = Self {
+            manifest: SnapshotManifest {
+                group_id,
+                snapshot_id,
+                chunks: Vec::new(),
+            },
+            chunks: HashMap::new(),
+        };
+        for dataset in &rows.datasets {
+            let mut sorted_rows: Vec<_> = dataset.rows.iter().collect();
+            sorted_rows.sort_by_key(|row| row.row_key);
+            let mut pending = PendingChunk::default();
+            for row in sorted_rows {
+                let row_proto = row.encode_proto();
+                let row_length = row_proto.encoded_len() as usize;
+                if !pending.rows.is_empty() && pending.length + row_length > max_chunk_bytes.get() {
+                    source.push_chunk(&dataset.dataset_id, std::mem::take(&mut pending));
+                }
+                pending.first_row_key.get_or_insert(row.row_key);
+                pending.last_row_key = Some(row.row_key);
+                pending.length += row_length;
+                pending.rows.push(row_proto);
+            }
+            if !pending.rows.is_empty() {
+                source.push_chunk(&dataset.dataset_id, pending);
+            }
+        }
+        source
+    }
+
+    /// Split `rows` using [`DEFAULT_SNAPSHOT_CHUNK_BYTES`].
+    #[must_use]
+    pub fn split_default(
+        group_id: GroupId,
+        snapshot_ref: &SnapshotRef,
+        rows: &InitialGroupValueRows,
+    ) -> Self {
+        Self::split(group_id, snapshot_ref, rows, DEFAULT_SNAPSHOT_CHUNK_BYTES)
+    }
+
+    #[must_use]
+    pub fn manifest(&self) -> &SnapshotManifest {
+        &self.manifest
+    }
+
+    #[must_use]
+    pub fn snapshot_id(&self) -> SnapshotId {
+        self.manifest.snapshot_id
+    }
+
+    /// Answer a peer's manifest request.
+    #[must_use]
+    pub fn respond_manifest(&self, request: &SnapshotManifestRequest) -> SnapshotManifestResponse {
+        if request.snapshot_id == self.manifest.snapshot_id {
+            SnapshotManifestResponse::Manifest(self.manifest.clone())
+        } else {
+            SnapshotManifestResponse::Unavailable(SnapshotUnavailable {
+                group_id: request.group_id,
+                snapshot_id: request.snapshot_id,
+            })
+        }
+    }
+
+    /// Answer a peer's chunk request.
+    ///
+    /// Requested chunk ids that are not part of this snapshot are skipped.
+    #[must_use]
+    pub fn respond(&self, request: &SnapshotChunkRequest) -> SnapshotChunkResponse {
+        self.respond_compressed(
+            request,
+            &ConnectionCompression::disabled(),
+            &CompressionMetrics::new(),
+        )
+    }
+
+    /// Answer a peer's chunk request, compressing chunk data as negotiated for the connection.
+    ///
+    /// Chunks that do not shrink are sent uncompressed. Every chunk is counted in `metrics`.
+    #[must_use]
+    pub fn respond_compressed(
+        &self,
+        request: &SnapshotChunkRequest,
+        compression: &ConnectionCompression,
+        metrics: &CompressionMetrics,
+    ) -> SnapshotChunkResponse {
+        if request.snapshot_id != self.manifest.snapshot_id {
+            return SnapshotChunkResponse::Unavailable(SnapshotUnavailable {
+                group_id: request.group_id,
+                snapshot_id: request.snapshot_id,
+            });
+        }
+        let chunks = request
+            .chunk_ids
+            .iter()
+            .filter_map(|chunk_id| {
+                let data = self.chunks.get(chunk_id)?;
+                let (compression, data) =
+                    match compression.compress(PayloadClass::Snapshot, data, metrics) {
+                        Some(compressed) => (Some(compressed.algorithm), compressed.data),
+                        None => (None, data.clone()),
+                    };
+                Some(SnapshotChunk {
+                    group_id: request.group_id,
+                    snapshot_id: request.snapshot_id,
+                    chunk_id: *chunk_id,
+                    compression,
+                    data,
+                })
+            })
+            .collect();
+        SnapshotChunkResponse::Chunks(chunks)
+    }
+
+    fn push_chunk(&mut self, dataset_id: &DatasetId, pending: PendingChunk) {
+        let (Some(first_row_key), Some(last_row_key)) =
+            (pending.first_row_key, pending.last_row_key)
+        else {
+            unreachable!("Only chunks with at least one row are pushed.");
+        };
+        let row_count = pending.rows.len() as u64;
+        let data = replication_proto::InitialDatasetState {
+            dataset_id: dataset_id.to_string(),
+            rows: pending.rows,
+            ..replication_proto::InitialDatasetState::default()
+        }
+        .encode_to_bytes();
+        let id = SnapshotChunkId::derive(
+            self.manifest.snapshot_id,
+            dataset_id,
+            first_row_key,
+            last_row_key,
+        );
+        self.manifest.chunks.push(SnapshotChunkDescriptor {
+            id,
+            dataset_id: dataset_id.clone(),
+            first_row_key,
+            last_row_key,
+            row_count,
+            byte_length: data.len() as u64,
+            content_hash: BlobHash::of(&data),
+        });
+        self.chunks.insert(id, data);
+    }
+}
+
+/// Receiver-side assembly state for one snapshot streamed from a peer.
+#[derive(Clone, Debug)]
+pub struct SnapshotDownload {
+    manifest: SnapshotManifest,
+    positions: HashMap<SnapshotChunkId, usize>,
+    received: BTreeMap<usize, Bytes>,
+    metrics: Option<CompressionMetrics>,
+}
+
+impl SnapshotDownload {
+    /// Start a download with no chunks received.
+    #[must_use]
+    pub fn new(manifest: SnapshotManifest) -> Self {
+        let positions = manifest
+            .chunks
+            .iter()
+            .enumerate()
+            .map(|(position, chunk)| (chunk.id, position))
+            .collect();
+        Self {
+            manifest,
+            positions,
+            received: BTreeMap::new(),
+            metrics: None,
+        }
+    }
+
+    /// Count decompressed chunks in `metrics`.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: CompressionMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Restart a download from chunks kept across an interruption.
+    ///
+    /// `manifest` may come from a different peer than the one that sent `chunks`. Chunks that are
+    /// not part of `manifest`, or whose content does not match it, are dropped and fetched again.
+    #[must_use]
+    pub fn resume(
+        manifest: SnapshotManifest,
+        chunks: impl IntoIterator<Item = SnapshotChunk>,
+    ) -> Self {
+        let mut download = Self::new(manifest);
+        for chunk in chunks {
+            // Stale or damaged chunks are simply requested again.
+            let _ = download.accept(chunk);
+        }
+        download
+    }
+
+    #[must_use]
+    pub fn manifest(&self) -> &SnapshotManifest {
+        &self.manifest
+    }
+
+    /// Build the request for every chunk that has not been received yet, in manifest order.
+    #[must_use]
+    pub fn next_request(&self) -> SnapshotChunkRequest {
+        let chunk_ids = self
+            .manifest
+            .chunks
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| !self.received.contains_key(position))
+            .map(|(_, chunk)| chunk.id)
+            .collect();
+        SnapshotChunkRequest {
+            group_id: self.manifest.group_id,
+            snapshot_id: self.manifest.snapshot_id,
+            chunk_ids,
+        }
+    }
+
+    /// Verify and record one received chunk, decompressing its data first if necessary.
+    ///
+    /// Duplicate chunks replace earlier copies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chunk belongs to another snapshot, is not listed in the manifest,
+    /// cannot be decompressed, or does not match its manifest entry.
+    pub fn accept(&mut self, chunk: SnapshotChunk) -> Result<(), SnapshotTransferError> {
+        ensure!(
+            chunk.snapshot_id == self.manifest.snapshot_id,
+            SnapshotMismatchSnafu {
+                expected: self.manifest.snapshot_id,
+                actual: chunk.snapshot_id,
+            }
+        );
+        let position = *self
+            .positions
+            .get(&chunk.chunk_id)
+            .context(UnknownChunkSnafu {
+                chunk_id: chunk.chunk_id,
+            })?;
+        let descriptor = &self.manifest.chunks[position];
+        let data = match chunk.compression {
+            None => chunk.data,
+            Some(algorithm) => {
+                // The manifest length bounds decompression, so a chunk cannot inflate past it.
+                let length = usize::try_from(descriptor.byte_length).unwrap_or(usize::MAX);
+                let inflated =
+                    algorithm
+                        .decompress(&chunk.data, length)
+                        .context(DecompressChunkSnafu {
+                            chunk_id: chunk.chunk_id,
+                        })?;
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_inflated(PayloadClass::Snapshot, inflated.len());
+                }
+                Bytes::from(inflated)
+            }
+        };
+        let actual_length = data.len() as u64;
+        ensure!(
+            actual_length == descriptor.byte_length,
+            ChunkLengthMismatchSnafu {
+                chunk_id: chunk.chunk_id,
+                expected_length: descriptor.byte_length,
+                actual_length,
+            }
+        );
+        ensure!(
+            BlobHash::of(&data) == descriptor.content_hash,
+            ChunkContentMismatchSnafu {
+                chunk_id: chunk.chunk_id,
+            }
+        );
+        self.received.insert(position, data);
+        Ok(())
+    }
+
+    /// Whether every chunk of the snapshot has been received.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.missing_chunks() == 0
+    }
+
+    /// Total byte length of the chunks received so far.
+    #[must_use]
+    pub fn received_bytes(&self) -> u64 {
+        self.received.values().map(|data| data.len() as u64).sum()
+    }
+
+    /// Hand out the received chunks uncompressed, e.g. to persist progress before shutting down.
+    #[must_use]
+    pub fn into_received_chunks(self) -> Vec<SnapshotChunk> {
+        let Self {
+            manifest, received, ..
+        } = self;
+        received
+            .into_iter()
+            .map(|(position, data)| SnapshotChunk {
+                group_id: manifest.group_id,
+                snapshot_id: manifest.snapshot_id,
+                chunk_id: manifest.chunks[position].id,
+                compression: None,
+                data,
+            })
+            .collect()
+    }
+
+    /// Decode the assembled rows against `group_schema`.
+    ///
+    /// Chunks of the same dataset are merged in manifest order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if chunks are still missing or their rows do not decode into the rows the
+    /// manifest describes.
+    pub fn finish(
+        self,
+        group_schema: &GroupSchema,
+    ) -> Result<InitialGroupValueRows, SnapshotTransferError> {
+        let missing = self.missing_chunks();
+        ensure!(
+            missing == 0,
+            IncompleteSnapshotSnafu {
+                snapshot_id: self.manifest.snapshot_id,
+                missing,
+            }
+        );
+        let mut datasets: Vec<InitialDatasetValueRows> = Vec::new();
+        for (position, data) in self.received {
+            let descriptor = &self.manifest.chunks[position];
+            let chunk_rows =
+                InitialDatasetValueRows::decode_proto_from_slice_with(&data, group_schema)
+                    .context(DecodeChunkRowsSnafu {
+                        chunk_id: descriptor.id,
+                    })?;
+            ensure!(
+                chunk_rows.dataset_id == descriptor.dataset_id
+                    && chunk_rows.rows.len() as u64 == descriptor.row_count,
+                InconsistentChunkRowsSnafu {
+                    chunk_id: descriptor.id,
+                }
+            );
+            match datasets
+                .iter_mut()
+                .find(|dataset| dataset.dataset_id == chunk_rows.dataset_id)
+            {
+                Some(dataset) => dataset.rows.extend(chunk_rows.rows),
+                None => datasets.push(chunk_rows),
+            }
+        }
+        Ok(InitialGroupValueRows { datasets })
+    }
+
+    fn missing_chunks(&self) -> usize {
+        self.manifest.chunks.len() - self.received.len()
+    }
+}
+
+/// Request for the manifest of one snapshot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotManifestRequest {
+    pub group_id: GroupId,
+    pub snapshot_id: SnapshotId,
+}
+
+impl ProtoCodec for SnapshotManifestRequest {
+    type DecodeError = SnapshotTransferError;
+    type Proto = replication_proto::SnapshotManifestRequest;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::SnapshotManifestRequest {
+            group_id: group_id_to_wire_bytes(self.group_id),
+            snapshot_id: self.snapshot_id.as_bytes().to_vec(),
+            ..replication_proto::SnapshotManifestRequest::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = decode_group_id(&message.group_id, "snapshot_manifest_request.group_id")?;
+        let snapshot_id = SnapshotId::from_wire(
+            &message.snapshot_id,
+            "snapshot_manifest_request.snapshot_id",
+        )?;
+        Ok(Self {
+            group_id,
+            snapshot_id,
+        })
+    }
+}
+
+impl DecodeProtoView for SnapshotManifestRequest {
+    type Error = SnapshotTransferError;
+    type ProtoView<'a> = replication_proto::SnapshotManifestRequestView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let message = message.to_owned_message().context(DecodeSnafu)?;
+        Self::decode_proto(message)
+    }
+}
+
+/// Request for chunks of one snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotChunkRequest {
+    pub group_id: GroupId,
+    pub snapshot_id: SnapshotId,
+    pub chunk_ids: Vec<SnapshotChunkId>,
+}
+
+impl ProtoCodec for SnapshotChunkRequest {
+    type DecodeError = SnapshotTransferError;
+    type Proto = replication_proto::SnapshotChunkRequest;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::SnapshotChunkRequest {
+            group_id: group_id_to_wire_bytes(self.group_id),
+            snapshot_id: self.snapshot_id.as_bytes().to_vec(),
+            chunk_ids: self
+                .chunk_ids
+                .iter()
+                .map(|chunk_id| chunk_id.as_bytes().to_vec())
+                .collect(),
+            ..replication_proto::SnapshotChunkRequest::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = decode_group_id(&message.group_id, "snapshot_chunk_request.group_id")?;
+        let snapshot_id =
+            SnapshotId::from_wire(&message.snapshot_id, "snapshot_chunk_request.snapshot_id")?;
+        let chunk_ids = message
+            .chunk_ids
+            .iter()
+            .map(|raw| SnapshotChunkId::from_wire(raw, "snapshot_chunk_request.chunk_ids"))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            group_id,
+            snapshot_id,
+            chunk_ids,
+        })
+    }
+}
+
+impl DecodeProtoView for SnapshotChunkRequest {
+    type Error = SnapshotTransferError;
+    type ProtoView<'a> = replication_proto::SnapshotChunkRequestView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let message = message.to_owned_message().context(DecodeSnafu)?;
+        Self::decode_proto(message)
+    }
+}
+
+/// Data of one snapshot chunk, sent in reply to a [`SnapshotChunkRequest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotChunk {
+    pub group_id: GroupId,
+    pub snapshot_id: SnapshotId,
+    pub chunk_id: SnapshotChunkId,
+    /// Algorithm `data` is compressed with, if any.
+    pub compression: Option<CompressionAlgorithm>,
+    pub data: Bytes,
+}
+
+impl ProtoCodec for SnapshotChunk {
+    type DecodeError = SnapshotTransferError;
+    type Proto = replication_proto::SnapshotChunk;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::SnapshotChunk {
+            group_id: group_id_to_wire_bytes(self.group_id),
+            snapshot_id: self.snapshot_id.as_bytes().to_vec(),
+            chunk_id: self.chunk_id.as_bytes().to_vec(),
+            data: self.data.clone(),
+            compression: CompressionAlgorithm::to_wire(self.compression),
+            ..replication_proto::SnapshotChunk::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = decode_group_id(&message.group_id, "snapshot_chunk.group_id")?;
+        let snapshot_id =
+            SnapshotId::from_wire(&message.snapshot_id, "snapshot_chunk.snapshot_id")?;
+        let chunk_id = SnapshotChunkId::from_wire(&message.chunk_id, "snapshot_chunk.chunk_id")?;
+        let compression = CompressionAlgorithm::from_wire(message.compression)
+            .context(DecompressChunkSnafu { chunk_id })?;
+        Ok(Self {
+            group_id,
+            snapshot_id,
+            chunk_id,
+            compression,
+            data: message.data,
+        })
+    }
+}
+
+impl DecodeProtoView for SnapshotChunk {
+    type Error = SnapshotTransferError;
+    type ProtoView<'a> = replication_proto::SnapshotChunkView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let message = message.to_owned_message().context(DecodeSnafu)?;
+        Self::decode_proto(message)
+    }
+}
+
+/// Reply indicating that the responder does not hold a requested snapshot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotUnavailable {
+    pub group_id: GroupId,
+    pub snapshot_id: SnapshotId,
+}
+
+impl ProtoCodec for SnapshotUnavailable {
+    type DecodeError = SnapshotTransferError;
+    type Proto = replication_proto::SnapshotUnavailable;
+
+    fn to_proto(&self) -> Self::Proto {
+        replication_proto::SnapshotUnavailable {
+            group_id: group_id_to_wire_bytes(self.group_id),
+            snapshot_id: self.snapshot_id.as_bytes().to_vec(),
+            ..replication_proto::SnapshotUnavailable::default()
+        }
+    }
+
+    fn from_proto(message: Self::Proto) -> Result<Self, Self::DecodeError> {
+        let group_id = decode_group_id(&message.group_id, "snapshot_unavailable.group_id")?;
+        let snapshot_id =
+            SnapshotId::from_wire(&message.snapshot_id, "snapshot_unavailable.snapshot_id")?;
+        Ok(Self {
+            group_id,
+            snapshot_id,
+        })
+    }
+}
+
+impl DecodeProtoView for SnapshotUnavailable {
+    type Error = SnapshotTransferError;
+    type ProtoView<'a> = replication_proto::SnapshotUnavailableView<'a>;
+
+    fn decode_proto_view(message: &Self::ProtoView<'_>) -> Result<Self, Self::Error> {
+        let message = message.to_owned_message().context(DecodeSnafu)?;
+        Self::decode_proto(message)
+    }
+}
+
+/// Local answer to one [`SnapshotManifestRequest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotManifestResponse {
+    Manifest(SnapshotManifest),
+    Unavailable(SnapshotUnavailable),
+}
+
+/// Local answer to one [`SnapshotChunkRequest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotChunkResponse {
+    Chunks(Vec<SnapshotChunk>),
+    Unavailable(SnapshotUnavailable),
+}
+
+fn decode_group_id(raw: &[u8], field: &'static str) -> Result<GroupId, SnapshotTransferError> {
+    group_id_from_wire_bytes(raw, field).context(InvalidWireValueSnafu { field })
+}
+
+fn decode_row_key(raw: &[u8], field: &'static str) -> Result<RowKey, SnapshotTransferError> {
+    uuid_from_wire_bytes(raw, field)
+        .map(RowKey)
+        .context(InvalidWireValueSnafu { field })
+}
+
+fn decode_digest(
+    raw: &[u8],
+    field: &'static str,
+) -> Result<[u8; SNAPSHOT_DIGEST_LENGTH], SnapshotTransferError> {
+    fixed_bytes_field(field, raw).context(InvalidWireValueSnafu { field })
+}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for byte in bytes {
+        write!(f, "{byte:02x}")?;
+    }
+    Ok(())
+}
+
+/// Rows collected for the chunk currently being filled by [`SnapshotSource::split`].
+#[derive(Default)]
+struct PendingChunk {
+    first_row_key: Option<RowKey>,
+    last_row_key: Option<RowKey>,
+    length: usize,
+    rows: Vec<replication_proto::InitialRowState>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{InitialValueRow, RowValues},
+        delivery::compression::CompressionCodec,
+        test_support::{docs_dataset_id, docs_group_schema, docs_schema_source},
+    };
+    use flotsync_core::versions::{PureVersionVector, VersionVector};
+    use uuid::Uuid;
+
+    fn group_id() -> GroupId {
+        GroupId(Uuid::from_u128(11))
+    }
+
+    fn snapshot_ref() -> SnapshotRef {
+        SnapshotRef {
+            group_id: GroupId(Uuid::from_u128(12)),
+            versions: VersionVector::Full(PureVersionVector::from([3, 4])),
+        }
+    }
+
+    fn docs_rows(count: u128) -> InitialGroupValueRows {
+        let schema = docs_schema_source();
+        // Reverse key order checks that chunk ranges do not depend on input order.
+        let rows = (0..count)
+            .rev()
+            .map(|index| InitialValueRow {
+                row_key: RowKey(Uuid::from_u128(1_000 + index)),
+                row: RowValues::try_from_fields(
+                    schema.as_schema(),
+                    crate::row_values! {
+                        "title" => format!("row number {index}"),
+                    }
+                    .fields,
+                )
+                .expect("docs row should match docs schema"),
+            })
+            .collect();
+        InitialGroupValueRows {
+            datasets: vec![InitialDatasetValueRows {
+                dataset_id: docs_dataset_id(),
+                rows,
+            }],
+        }
+    }
+
+    fn small_source(rows: &InitialGroupValueRows) -> SnapshotSource {
+        SnapshotSource::split(
+            group_id(),
+            &snapshot_ref(),
+            rows,
+            NonZeroUsize::new(64).unwrap(),
+        )
+    }
+
+    fn sorted_row_keys(rows: &InitialGroupValueRows) -> Vec<RowKey> {
+        let mut keys: Vec<_> = rows
+            .datasets
+            .iter()
+            .flat_map(|dataset| dataset.rows.iter().map(|row| row.row_key))
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn chunk_ids_are_stable_across_splits() {
+        let rows = docs_rows(10);
+        let first = small_source(&rows);
+        let second = small_source(&rows);
+
+        assert!(first.manifest().chunks.len() > 1);
+        assert_eq!(first.manifest(), second.manifest());
+        assert_eq!(first.manifest().total_rows(), 10);
+        assert_eq!(first.snapshot_id(), SnapshotId::of(&snapshot_ref()));
+        let chunk = &first.manifest().chunks[0];
+        assert_eq!(chunk.first_row_key, RowKey(Uuid::from_u128(1_000)));
+        assert_eq!(
+            chunk.id,
+            SnapshotChunkId::derive(
+                first.snapshot_id(),
+                &docs_dataset_id(),
+                chunk.first_row_key,
+                chunk.last_row_key,
+            )
+        );
+    }
+
+    #[test]
+    fn snapshot_ids_ignore_version_vector_representation() {
+        let full = SnapshotRef {
+            group_id: group_id(),
+            versions: VersionVector::Full(PureVersionVector::from([5, 5, 5])),
+        };
+        let synced = SnapshotRef {
+            group_id: group_id(),
+            versions: VersionVector::from_entries([5, 5, 5]),
+        };
+        let other = SnapshotRef {
+            group_id: group_id(),
+            versions: VersionVector::from_entries([5, 5]),
+        };
+
+        assert!(matches!(synced.versions, VersionVector::Synced { .. }));
+        assert_eq!(SnapshotId::of(&full), SnapshotId::of(&synced));
+        assert_ne!(SnapshotId::of(&synced), SnapshotId::of(&other));
+    }
+
+    #[test]
+    fn download_roundtrips_through_protobuf() {
+        let rows = docs_rows(10);
+        let source = small_source(&rows);
+
+        let request = SnapshotManifestRequest {
+            group_id: group_id(),
+            snapshot_id: SnapshotId::of(&snapshot_ref()),
+        };
+        let request = SnapshotManifestRequest::decode_proto(request.encode_proto()).unwrap();
+        let SnapshotManifestResponse::Manifest(manifest) = source.respond_manifest(&request) else {
+            panic!("Source must hold the snapshot.");
+        };
+        let manifest = SnapshotManifest::decode_proto(manifest.encode_proto()).unwrap();
+        let mut download = SnapshotDownload::new(manifest);
+
+        let request =
+            SnapshotChunkRequest::decode_proto(download.next_request().encode_proto()).unwrap();
+        let SnapshotChunkResponse::Chunks(chunks) = source.respond(&request) else {
+            panic!("Source must hold the snapshot.");
+        };
+        for chunk in chunks {
+            download
+                .accept(SnapshotChunk::decode_proto(chunk.encode_proto()).unwrap())
+                .unwrap();
+        }
+        assert!(download.is_complete());
+        assert_eq!(download.received_bytes(), source.manifest().total_bytes());
+
+        let assembled = download.finish(&docs_group_schema()).unwrap();
+        assert_eq!(assembled.datasets.len(), 1);
+        assert_eq!(sorted_row_keys(&assembled), sorted_row_keys(&rows));
+    }
+
+    #[test]
+    fn interrupted_download_resumes_with_missing_chunks_only() {
+        let rows = docs_rows(10);
+        let source = small_source(&rows);
+        let mut download = SnapshotDownload::new(source.manifest().clone());
+        let SnapshotChunkResponse::Chunks(chunks) = source.respond(&download.next_request()) else {
+            panic!("Source must hold the snapshot.");
+        };
+        let (last, delivered) = chunks.split_last().unwrap();
+        for chunk in delivered {
+            download.accept(chunk.clone()).unwrap();
+        }
+        let kept = download.into_received_chunks();
+        assert_eq!(kept.len(), delivered.len());
+
+        // A fresh split, e.g. by a restarted or different peer, resumes the same chunks.
+        let restarted = small_source(&rows);
+        let mut resumed = SnapshotDownload::resume(restarted.manifest().clone(), kept);
+        assert_eq!(resumed.next_request().chunk_ids, vec![last.chunk_id]);
+
+        let SnapshotChunkResponse::Chunks(rest) = restarted.respond(&resumed.next_request()) else {
+            panic!("Source must hold the snapshot.");
+        };
+        assert_eq!(rest.len(), 1);
+        resumed.accept(rest[0].clone()).unwrap();
+        let assembled = resumed.finish(&docs_group_schema()).unwrap();
+        assert_eq!(sorted_row_keys(&assembled), sorted_row_keys(&rows));
+    }
+
+    #[test]
+    fn compressed_chunks_inflate_before_verification() {
+        let rows = docs_rows(40);
+        let source = SnapshotSource::split(
+            group_id(),
+            &snapshot_ref(),
+            &rows,
+            NonZeroUsize::new(4096).unwrap(),
+        );
+        let compression = ConnectionCompression {
+            codec: CompressionCodec::Zstd { level: 3 },
+            min_payload_bytes: 0,
+        };
+        let sent = CompressionMetrics::new();
+        let received = CompressionMetrics::new();
+        let mut download =
+            SnapshotDownload::new(source.manifest().clone()).with_metrics(received.clone());
+        let SnapshotChunkResponse::Chunks(chunks) =
+            source.respond_compressed(&download.next_request(), &compression, &sent)
+        else {
+            panic!("Source must hold the snapshot.");
+        };
+        assert!(
+            chunks
+                .iter()
+                .any(|chunk| chunk.compression == Some(CompressionAlgorithm::Zstd))
+        );
+        for chunk in chunks {
+            download
+                .accept(SnapshotChunk::decode_proto(chunk.encode_proto()).unwrap())
+                .unwrap();
+        }
+        assert_eq!(download.received_bytes(), source.manifest().total_bytes());
+
+        let sent = sent.counters().snapshots;
+        let received = received.counters().snapshots;
+        assert!(sent.saved_bytes() > 0);
+        assert_eq!(received.inflated_payloads, sent.compressed_payloads);
+        let assembled = download.finish(&docs_group_schema()).unwrap();
+        assert_eq!(sorted_row_keys(&assembled), sorted_row_keys(&rows));
+    }
+
+    #[test]
+    fn download_rejects_tampered_chunks() {
+        let source = small_source(&docs_rows(3));
+        let mut download = SnapshotDownload::new(source.manifest().clone());
+        let SnapshotChunkResponse::Chunks(mut chunks) = source.respond(&download.next_request())
+        else {
+            panic!("Source must hold the snapshot.");
+        };
+        let mut chunk = chunks.remove(0);
+        let mut data = chunk.data.to_vec();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        chunk.data = Bytes::from(data);
+
+        assert!(matches!(
+            download.accept(chunk.clone()),
+            Err(SnapshotTransferError::ChunkContentMismatch { .. })
+        ));
+        assert!(matches!(
+            SnapshotDownload::resume(source.manifest().clone(), [chunk])
+                .finish(&docs_group_schema()),
+            Err(SnapshotTransferError::IncompleteSnapshot { .. })
+        ));
+    }
+
+    #[test]
+    fn unknown_snapshot_reports_unavailable() {
+        let source = small_source(&docs_rows(1));
+        let snapshot_id = SnapshotId::from_bytes([9; SNAPSHOT_DIGEST_LENGTH]);
+        let response = source.respond(&SnapshotChunkRequest {
+            group_id: group_id(),
+            snapshot_id,
+            chunk_ids: Vec::new(),
+        });
+        assert_eq!(
+            response,
+            SnapshotChunkResponse::Unavailable(SnapshotUnavailable {
+                group_id: group_id(),
+                snapshot_id,
+            })
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Workspace-wide integrity query for cross-document references.
+//!
+//! Every row in a replication store is a document addressable by its
+//! [`RowKey`], and fields of type [`PrimitiveType::DocRef`] link to other
+//! documents by that key. Deleting a document does not touch the rows that
+//! link to it, so [`find_dangling_doc_refs`] scans every active group and
+//! reports each reference whose target is no longer a live row anywhere in the
+//! store.
+//!
+//! [`PrimitiveType::DocRef`]: flotsync_data_types::schema::PrimitiveType::DocRef
+
+use crate::api::{
+    ReplicationGroupRecord,
+    ReplicationStore,
+    ReplicationStoreReadTransaction,
+    RowId,
+    RowKey,
+    StoreError,
+};
+use flotsync_data_types::{
+    RowValueRead,
+    schema::{
+        datamodel::{BasicValueRef, NullableBasicValueRef, PrimitiveValueArrayRef},
+        values::{DocRef, PrimitiveValueRef},
+    },
+};
+use std::{collections::HashSet, fmt, num::NonZeroUsize};
+use uuid::Uuid;
+
+/// One document reference whose target document no longer exists.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DanglingDocRef {
+    /// Row that holds the reference.
+    pub source: RowId,
+    /// Field of `source` that holds the reference.
+    pub field_name: String,
+    /// The reference itself.
+    pub target: DocRef,
+}
+
+impl fmt::Display for DanglingDocRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "field '{}' of row {} references missing document {}",
+            self.field_name, self.source, self.target
+        )
+    }
+}
+
+/// Find every document reference in `store` whose target document is missing.
+///
+/// A reference is dangling when no active group holds a live row whose key
+/// equals the referenced document id; tombstoned rows count as missing.
+/// References held by tombstoned rows are not reported. Only the document part
+/// of a reference is checked, not whether its anchor still names a node.
+///
+/// Results are ordered by group, dataset, and row, matching the scan order.
+///
+/// # Errors
+///
+/// Returns [`StoreError`] if the store cannot be read.
+pub async fn find_dangling_doc_refs(
+    store: &dyn ReplicationStore,
+) -> Result<Vec<DanglingDocRef>, StoreError> {
+    let mut transaction = store.begin_read_transaction().await?;
+    let groups = transaction.load_replication_groups().await?;
+    let mut live_documents = HashSet::new();
+    let mut references = Vec::new();
+    for group in &groups {
+        collect_group_doc_refs(
+            transaction.as_mut(),
+            group,
+            &mut live_documents,
+            &mut references,
+        )
+        .await?;
+    }
+    transaction.release().await?;
+    references.retain(|reference| !live_documents.contains(&reference.target.document_id));
+    Ok(references)
+}
+
+/// Number of rows loaded per scan while collecting references from one dataset.
+const ROW_SCAN_BATCH_SIZE: NonZeroUsize = NonZeroUsize::new(256).unwrap();
+
+async fn collect_group_doc_refs(
+    transaction: &mut dyn ReplicationStoreReadTransaction,
+    group: &ReplicationGroupRecord,
+    live_documents: &mut HashSet<Uuid>,
+    references: &mut Vec<DanglingDocRef>,
+) -> Result<(), StoreError> {
+    for dataset in group.group_schema.datasets() {
+        let schema = dataset.schema.as_schema();
+        let mut after: Option<RowKey> = None;
+        let mut exhausted = false;
+        while !exhausted {
+            let batch = transaction
+                .scan_dataset_row_batch(
+                    &group.group_id,
+                    &dataset.dataset_id,
+                    after,
+                    ROW_SCAN_BATCH_SIZE,
+                )
+                .await?;
+            for row in batch.rows.into_iter().filter(|row| !row.tombstoned) {
+                live_documents.insert(row.row_id.0);
+                for field_name in schema.columns.keys() {
+                    let Some(value) = row.snapshot.get_value(field_name) else {
+                        continue;
+                    };
+                    for target in doc_refs_in(value.as_ref()) {
+                        references.push(DanglingDocRef {
+                            source: RowId {
+                                group_id: group.group_id,
+                                dataset_id: dataset.dataset_id.clone(),
+                                row_key: row.row_id,
+                            },
+                            field_name: field_name.clone(),
+                            target: target.clone(),
+                        });
+                    }
+                }
+            }
+            after = batch.next_after;
+            exhausted = after.is_none();
+        }
+    }
+    Ok(())
+}
+
+/// Return the document references held by one projected field value.
+fn doc_refs_in(value: NullableBasicValueRef<'_>) -> &[DocRef] {
+    match value {
+        NullableBasicValueRef::Value(BasicValueRef::Primitive(PrimitiveValueRef::DocRef(
+            doc_ref,
+        ))) => std::slice::from_ref(doc_ref),
+        NullableBasicValueRef::Value(BasicValueRef::Array(PrimitiveValueArrayRef::DocRef(
+            doc_refs,
+        ))) => doc_refs,
+        _ => &[],
+    }
+}
@@ -1,3 +1,18 @@
+mod doc_refs;
 mod sqlite;
+mod tombstones;
+mod usage;
+mod verify;
 
+pub use doc_refs::{DanglingDocRef, find_dangling_doc_refs};
 pub use sqlite::SqliteReplicationStore;
+pub use tombstones::{TombstonedDocument, list_tombstoned_documents, purge_tombstoned_documents};
+pub use usage::{
+    DocumentStorageUsage,
+    GroupStorageUsage,
+    StorageQuota,
+    StorageQuotaWarning,
+    StorageReport,
+    storage_report,
+};
+pub use verify::{StoreIssue, StoreIssueKind, StoreVerificationReport, verify_replication_store};
@@ -28,8 +28,10 @@ WHERE group_id = ?1
     };
 
     let member_count = decode_non_zero_member_count(row.get::<i64, _>("member_count"))?;
-    let local_member_index =
-        decode_member_index(row.get::<i64, _>("local_member_index"), member_count)?;
+    let local_member_index = row
+        .get::<Option<i64>, _>("local_member_index")
+        .map(|raw| decode_member_index(raw, member_count))
+        .transpose()?;
     let encrypted_group_secret = decode_encrypted_store_secret(
         row.get("group_secret_crypto_version"),
         row.get("group_secret_key_id"),
@@ -248,7 +250,9 @@ pub(super) async fn ensure_replication_group_material(
         return Ok(());
     }
     let member_count = material.member_count();
-    ensure_member_index_in_bounds(material.local_member_index, member_count)?;
+    if let Some(local_member_index) = material.local_member_index {
+        ensure_member_index_in_bounds(local_member_index, member_count)?;
+    }
 
     let stored_member_count =
         i64::try_from(member_count.get()).context(MemberCountOverflowSnafu)?;
@@ -268,7 +272,11 @@ VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
     )
     .bind(material.group_id.to_string())
     .bind(stored_member_count)
-    .bind(i64::from(material.local_member_index.as_u32()))
+    .bind(
+        material
+            .local_member_index
+            .map(|local_member_index| i64::from(local_member_index.as_u32())),
+    )
     .bind(i64::from(
         material
             .security_material
@@ -322,6 +330,23 @@ VALUES (?1, ?2, ?3, ?4)
         .await
         .context(SqlxSnafu)?;
     }
+    for (observer_ordinal, observer_key) in material.member_keys.observer_keys().iter().enumerate()
+    {
+        let observer_ordinal = i64::try_from(observer_ordinal).context(MemberCountOverflowSnafu)?;
+        sqlx::query(
+            "
+INSERT INTO group_observers (group_id, observer_ordinal, member_identity, key_fingerprint)
+VALUES (?1, ?2, ?3, ?4)
+",
+        )
+        .bind(material.group_id.to_string())
+        .bind(observer_ordinal)
+        .bind(observer_key.member_id.to_string())
+        .bind(observer_key.fingerprint.as_ref())
+        .execute(&mut *connection)
+        .await
+        .context(SqlxSnafu)?;
+    }
     insert_group_schema(connection, material.group_id, &material.group_schema).await?;
     Ok(())
 }
@@ -12,6 +12,7 @@ use crate::{
         EncryptedStoreSecret,
         GroupMemberKeys,
         GroupSchema,
+        GroupStorageSizes,
         LocalMemberPrivateKeysRecord,
         MemberKeyId,
         MemberKeyTrustEvidenceKind,
@@ -33,6 +34,7 @@ use crate::{
         ReplicationUpdateRecord,
         RowKey,
         RowKeyIterator,
+        RowStorageSize,
         SchemaSource,
         StoreError,
         StoreSecretCryptoVersion,
@@ -54,6 +56,7 @@ use crate::{
             encode_pending_group_decision_payload,
         },
     },
+    fault_points,
 };
 use flotsync_core::{
     GroupId,
@@ -69,7 +72,11 @@ use flotsync_messages::{
     proto::{DecodeProto, DecodeProtoWith, EncodeProto, ProtoInputDecodeError},
 };
 use flotsync_security::{KeyFingerprint, PublicMemberKeys};
-use flotsync_utils::BoxFuture;
+use flotsync_utils::{
+    BoxFuture,
+    fault_injection,
+    shutdown::{ShutdownPhase, ShutdownToken},
+};
 use futures_util::{FutureExt, future};
 use log::warn;
 use snafu::prelude::*;
@@ -85,7 +92,7 @@ use std::{
     collections::{HashMap, HashSet},
     error::Error as StdError,
     num::NonZeroUsize,
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
     time::Duration,
@@ -191,6 +198,85 @@ impl SqliteReplicationStore {
         Self::from_connect_options(local_member, schema_sources, connect_options).await
     }
 
+    /// Write a consistent copy of this disk-backed store into a new database file at `path`.
+    ///
+    /// Other connections may keep using the store while the copy is taken. The
+    /// copy can be opened with [`Self::file`], e.g. after moving it to another
+    /// device. `path` must not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Fails for in-memory stores and non-UTF-8 paths. See `StoreError` for
+    /// other failure conditions.
+    pub async fn export_snapshot(&self, path: impl AsRef<Path>) -> Result<(), StoreError> {
+        let path = path.as_ref();
+        let path = path.to_str().context(NonUtf8SnapshotPathSnafu { path })?;
+        let mut connection = self.pool.acquire().await.context(SqlxSnafu)?;
+        // In-memory connections would also open the copy in memory, so reject them upfront.
+        let main_file: String =
+            sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'")
+                .fetch_one(&mut *connection)
+                .await
+                .context(SqlxSnafu)?;
+        ensure!(!main_file.is_empty(), InMemorySnapshotSnafu);
+        sqlx::query("VACUUM INTO ?")
+            .bind(path)
+            .execute(&mut *connection)
+            .await
+            .context(SqlxSnafu)?;
+        Ok(())
+    }
+
+    /// Run `SQLite`'s own consistency checks over the database file.
+    ///
+    /// This covers page structure, index contents, and foreign keys, i.e. damage
+    /// below the level that [`verify_replication_store`](crate::verify_replication_store)
+    /// can see. Returns one message per problem; an empty result means the
+    /// database is intact.
+    ///
+    /// # Errors
+    ///
+    /// See `StoreError` for failure conditions.
+    pub async fn check_database_integrity(&self) -> Result<Vec<String>, StoreError> {
+        let mut connection = self.pool.acquire().await.context(SqlxSnafu)?;
+        let mut problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(&mut *connection)
+            .await
+            .context(SqlxSnafu)?;
+        problems.retain(|problem| problem != "ok");
+        let foreign_key_violations: Vec<(String, Option<i64>, String)> =
+            sqlx::query_as("SELECT \"table\", rowid, parent FROM pragma_foreign_key_check")
+                .fetch_all(&mut *connection)
+                .await
+                .context(SqlxSnafu)?;
+        problems.extend(foreign_key_violations.into_iter().map(
+            |(table, rowid, parent)| match rowid {
+                Some(rowid) => {
+                    format!("row {rowid} in {table} references a missing {parent} row")
+                }
+                None => format!("a row in {table} references a missing {parent} row"),
+            },
+        ));
+        Ok(problems)
+    }
+
+    /// Close this store as part of a coordinated application shutdown.
+    ///
+    /// Registers the store as a participant of `token` right away, so call this
+    /// before the shutdown starts. Every transaction writes through on commit, so
+    /// the store has nothing of its own to flush. Once `token` reaches the close
+    /// phase, the returned future waits for open transactions to finish and then
+    /// closes every connection. Later transactions fail.
+    pub fn shutdown_with(&self, token: &ShutdownToken) -> impl Future<Output = ()> + '_ {
+        let participant = token.participant("replication-store");
+        async move {
+            participant.complete(ShutdownPhase::Flush);
+            participant.token().reached(ShutdownPhase::Close).await;
+            self.pool.close().await;
+            participant.complete(ShutdownPhase::Close);
+        }
+    }
+
     async fn from_connect_options(
         local_member: MemberIdentity,
         schema_sources: HashMap<DatasetId, SchemaSource>,
@@ -486,6 +572,14 @@ impl ReplicationStoreReadTransaction for SqliteReplicationStoreTransaction {
         .boxed()
     }
 
+    fn load_group_storage_sizes<'a>(
+        &'a mut self,
+        group_id: &'a GroupId,
+    ) -> BoxFuture<'a, Result<GroupStorageSizes, StoreError>> {
+        async move { load_group_storage_sizes(self.assert_open_connection(), group_id).await }
+            .boxed()
+    }
+
     fn load_pending_group_decisions(
         &mut self,
     ) -> BoxFuture<'_, Result<Vec<PendingGroupDecisionRecord>, StoreError>> {
@@ -712,6 +806,14 @@ impl ReplicationStoreTransaction for SqliteReplicationStoreTransaction {
                 .connection
                 .take()
                 .expect("sqlite replication transaction must not commit twice");
+            if fault_injection::fails(fault_points::STORE_COMMIT) {
+                connection.rollback().await.context(SqlxSnafu)?;
+                return Err(InjectedDiskFullSnafu {
+                    point: fault_points::STORE_COMMIT,
+                }
+                .build()
+                .into());
+            }
             connection.commit().await.context(SqlxSnafu)?;
             Ok(())
         }
@@ -760,6 +862,7 @@ const UPDATE_VERSION_SORT_KEY_BYTES: usize = 8;
 // Group persistence is normalised around one material record per group:
 //
 // replication_group_material -> group_members
+//                            -> group_observers
 //                            -> group_dataset_schemas
 //                                      |
 // replication_groups (active marker + version vector)
@@ -778,7 +881,7 @@ const SCHEMA_STATEMENTS: &[&str] = &[
 CREATE TABLE IF NOT EXISTS replication_group_material (
     group_id TEXT PRIMARY KEY NOT NULL,
     member_count INTEGER NOT NULL,
-    local_member_index INTEGER NOT NULL,
+    local_member_index INTEGER,
     group_secret_crypto_version INTEGER NOT NULL,
     group_secret_key_id TEXT NOT NULL,
     group_secret_nonce BLOB NOT NULL,
@@ -795,6 +898,17 @@ CREATE TABLE IF NOT EXISTS group_members (
     UNIQUE (group_id, member_identity),
     FOREIGN KEY (group_id) REFERENCES replication_group_material(group_id) ON DELETE CASCADE
 );
+",
+    "
+CREATE TABLE IF NOT EXISTS group_observers (
+    group_id TEXT NOT NULL,
+    observer_ordinal INTEGER NOT NULL,
+    member_identity TEXT NOT NULL,
+    key_fingerprint BLOB NOT NULL,
+    PRIMARY KEY (group_id, observer_ordinal),
+    UNIQUE (group_id, member_identity),
+    FOREIGN KEY (group_id) REFERENCES replication_group_material(group_id) ON DELETE CASCADE
+);
 ",
     "
 CREATE TABLE IF NOT EXISTS group_dataset_schemas (
@@ -935,6 +1049,8 @@ use updates::*;
 enum SqliteStoreError {
     #[snafu(display("SQLite operation failed: {source}"))]
     Sqlx { source: sqlx::Error },
+    #[snafu(display("Injected fault at {point}: the disk is full."))]
+    InjectedDiskFull { point: &'static str },
     #[snafu(display("SQLite connection URL '{database_url}' was invalid: {source}"))]
     ParseSqliteUrl {
         database_url: String,
@@ -1049,6 +1165,10 @@ enum SqliteStoreError {
         group_id: GroupId,
         update_id: UpdateId,
     },
+    #[snafu(display("Snapshot path {} is not valid UTF-8.", path.display()))]
+    NonUtf8SnapshotPath { path: PathBuf },
+    #[snafu(display("In-memory stores cannot export snapshots."))]
+    InMemorySnapshot,
 }
 
 impl From<SqliteStoreError> for StoreError {
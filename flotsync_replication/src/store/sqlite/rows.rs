@@ -169,6 +169,49 @@ WHERE group_id = ",
     })
 }
 
+pub(super) async fn load_group_storage_sizes(
+    connection: &mut SqliteStoreConnection,
+    group_id: &GroupId,
+) -> Result<GroupStorageSizes, StoreError> {
+    let stored_rows = sqlx::query(
+        "
+SELECT dataset_id, row_key, length(row_snapshot) AS snapshot_bytes
+FROM dataset_rows
+WHERE group_id = ?1
+ORDER BY dataset_id, row_key
+",
+    )
+    .bind(group_id.to_string())
+    .fetch_all(&mut *connection)
+    .await
+    .context(SqlxSnafu)?;
+    let mut rows = Vec::with_capacity(stored_rows.len());
+    for row in stored_rows {
+        rows.push(RowStorageSize {
+            dataset_id: decode_dataset_id(&row.get::<String, _>("dataset_id"))?,
+            row_key: decode_row_key(&row.get::<String, _>("row_key"))?,
+            snapshot_bytes: row.get::<i64, _>("snapshot_bytes").unsigned_abs(),
+        });
+    }
+    let update_log = sqlx::query(
+        "
+SELECT COALESCE(SUM(length(update_message)), 0) AS update_log_bytes
+FROM dataset_updates
+WHERE group_id = ?1
+",
+    )
+    .bind(group_id.to_string())
+    .fetch_one(&mut *connection)
+    .await
+    .context(SqlxSnafu)?;
+    let update_log_bytes = update_log.get::<i64, _>("update_log_bytes").unsigned_abs();
+    Ok(GroupStorageSizes {
+        group_id: *group_id,
+        rows,
+        update_log_bytes,
+    })
+}
+
 pub(super) async fn apply_dataset_row_patch(
     connection: &mut SqliteStoreConnection,
     schema_sources: &HashMap<DatasetId, SchemaSource>,
@@ -202,6 +245,16 @@ pub(super) async fn apply_dataset_row_patch(
             DatasetRowStateWrite::UpsertTombstone { row_key, snapshot } => {
                 (row_key, snapshot, true)
             }
+            DatasetRowStateWrite::Purge { row_key } => {
+                purge_dataset_row_tombstone(
+                    connection,
+                    &patch.group_id,
+                    &patch.dataset_id,
+                    row_key,
+                )
+                .await?;
+                continue;
+            }
         };
         let row_snapshot = encode_dataset_row_snapshot(schema.as_schema(), snapshot)?;
         sqlx::query(
@@ -255,6 +308,39 @@ pub(super) async fn ensure_dataset_row_upsert_active_is_valid(
     Ok(())
 }
 
+pub(super) async fn purge_dataset_row_tombstone(
+    connection: &mut SqliteStoreConnection,
+    group_id: &GroupId,
+    dataset_id: &DatasetId,
+    row_key: &RowKey,
+) -> Result<(), StoreError> {
+    let existing_tombstoned =
+        load_dataset_row_tombstoned(connection, group_id, dataset_id, row_key).await?;
+    ensure!(
+        existing_tombstoned != Some(false),
+        InvalidDatasetRowStateTransitionSnafu {
+            group_id: *group_id,
+            dataset_id: dataset_id.clone(),
+            row_key: *row_key,
+            from: "active",
+            to: "purged",
+        }
+    );
+    sqlx::query(
+        "
+DELETE FROM dataset_rows
+WHERE group_id = ?1 AND dataset_id = ?2 AND row_key = ?3 AND row_tombstoned = 1
+",
+    )
+    .bind(group_id.to_string())
+    .bind(dataset_id.as_str())
+    .bind(row_key.to_string())
+    .execute(&mut *connection)
+    .await
+    .context(SqlxSnafu)?;
+    Ok(())
+}
+
 pub(super) async fn load_dataset_row_tombstoned(
     connection: &mut SqliteStoreConnection,
     group_id: &GroupId,
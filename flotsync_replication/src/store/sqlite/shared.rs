@@ -43,6 +43,30 @@ ORDER BY member_index
         }
     );
 
+    let member_keys = decode_member_key_rows(rows)?;
+
+    let observer_rows = sqlx::query(
+        "
+SELECT member_identity, key_fingerprint
+FROM group_observers
+WHERE group_id = ?1
+ORDER BY observer_ordinal
+",
+    )
+    .bind(group_id.to_string())
+    .fetch_all(&mut *connection)
+    .await
+    .context(SqlxSnafu)?;
+    let observer_keys = decode_member_key_rows(observer_rows)?;
+
+    GroupMemberKeys::from_ordered_member_keys(member_keys)
+        .and_then(|member_keys| member_keys.with_observer_keys(observer_keys))
+        .map_err(|source| invalid_stored_object("group member keys", source))
+}
+
+fn decode_member_key_rows(
+    rows: Vec<sqlx::sqlite::SqliteRow>,
+) -> Result<Vec<MemberKeyId>, StoreError> {
     let mut member_keys = Vec::with_capacity(rows.len());
     for row in rows {
         let raw_member = row.get::<String, _>("member_identity");
@@ -54,8 +78,7 @@ ORDER BY member_index
             fingerprint,
         });
     }
-    GroupMemberKeys::from_ordered_member_keys(member_keys)
-        .map_err(|source| invalid_stored_object("group member keys", source))
+    Ok(member_keys)
 }
 
 pub(super) async fn load_group_member_count(
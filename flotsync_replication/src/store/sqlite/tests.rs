@@ -2,6 +2,7 @@
 use super::*;
 use crate::{
     api::{
+        AcknowledgedVersions,
         DatasetRowStatePatch,
         DatasetRowStateWrite,
         GroupInvitation,
@@ -19,20 +20,36 @@ use crate::{
         PendingGroupDecisionRecord,
         ReplicationRowStateRecord,
         ReplicationUpdateFilter,
+        RowId,
         SnapshotRef,
+        WorkspaceEvent,
+        WorkspaceEvents,
         current_slice_placeholder_group_security_material,
     },
+    blobs::BlobStore,
+    store::{
+        DanglingDocRef,
+        DocumentStorageUsage,
+        GroupStorageUsage,
+        StorageQuota,
+        StorageQuotaWarning,
+        StorageReport,
+        StoreIssueKind,
+        TombstonedDocument,
+    },
     test_support::test_public_member_keys,
 };
 use flotsync_core::member::{Identifier, MAX_IDENTIFIER_SEGMENTS};
 use flotsync_data_types::{
     Field,
+    IdWithIndex,
     RowValues,
     Schema,
     TableOperations,
-    schema::datamodel::RowOperation,
+    schema::{PrimitiveType, datamodel::RowOperation, values::DocRef},
 };
 use flotsync_messages::codecs::datamodel::encode_schema_operation;
+use flotsync_utils::shutdown::shutdown_channel;
 use itertools::Itertools;
 use std::{
     assert_matches,
@@ -154,6 +171,7 @@ fn migration_proposal_decision(migration_id: MigrationId) -> PendingGroupDecisio
         migration_id,
         final_versions: initial_versions(2).with_version_at(0, 3),
         proposed_members: vec![local_member(), remote_member(), third_member()],
+        proposed_observers: Vec::new(),
         group_schema: GroupSchema::default(),
         initial_snapshot: metadata_snapshot(migration_id.old_group_id, migration_id.new_group_id),
         group_name: Some("new docs".to_owned()),
@@ -245,6 +263,83 @@ fn pending_group_decisions_round_trip_through_sqlite_payloads() {
     }
 }
 
+#[test]
+fn exported_snapshot_opens_as_file_store() {
+    let directory =
+        std::env::temp_dir().join(format!("flotsync-sqlite-snapshot-{}", Uuid::new_v4()));
+    std::fs::create_dir(&directory).expect("snapshot directory should create");
+    let store = wait_for_store_future(SqliteReplicationStore::file(
+        local_member(),
+        directory.join("store.sqlite"),
+    ))
+    .expect("store should build");
+    let group_id = GroupId(Uuid::from_u128(10_101));
+    wait_for_store_future(async {
+        let mut transaction = store
+            .begin_transaction()
+            .await
+            .expect("transaction should open");
+        transaction
+            .insert_replication_group(sample_group(group_id))
+            .await
+            .expect("group should store");
+        transaction
+            .commit()
+            .await
+            .expect("transaction should commit");
+    });
+
+    let snapshot_path = directory.join("snapshot.sqlite");
+    wait_for_store_future(store.export_snapshot(&snapshot_path)).expect("snapshot should export");
+    let restored =
+        wait_for_store_future(SqliteReplicationStore::file(local_member(), &snapshot_path))
+            .expect("snapshot should open");
+    let loaded = wait_for_store_future(async {
+        let mut transaction = restored
+            .begin_read_transaction()
+            .await
+            .expect("read transaction should open");
+        let loaded = transaction
+            .load_replication_group(&group_id)
+            .await
+            .expect("group should load");
+        transaction
+            .release()
+            .await
+            .expect("read transaction should release");
+        loaded
+    });
+    let in_memory_error = wait_for_store_future(
+        in_memory_store(local_member()).export_snapshot(directory.join("memory.sqlite")),
+    )
+    .expect_err("in-memory stores must not export");
+    drop(restored);
+    drop(store);
+    std::fs::remove_dir_all(&directory).expect("snapshot directory should be removable");
+
+    assert_eq!(loaded, Some(sample_group(group_id)));
+    assert!(matches!(in_memory_error, StoreError::StoreExternal { .. }));
+}
+
+#[test]
+fn coordinated_shutdown_closes_store_in_close_phase() {
+    let store = in_memory_store(local_member());
+    let (controller, token) = shutdown_channel();
+
+    let store_shutdown = store.shutdown_with(&token);
+    let ((), controller_result) = wait_for_store_future(future::join(
+        store_shutdown,
+        controller.shutdown(STORE_FUTURE_TIMEOUT),
+    ));
+    let transaction = wait_for_store_future(store.begin_read_transaction());
+
+    controller_result.expect("store should complete every shutdown phase in time");
+    assert!(
+        transaction.is_err(),
+        "closed store must refuse transactions"
+    );
+}
+
 #[test]
 fn pending_group_activation_remove_is_idempotent() {
     let store = in_memory_store(local_member());
@@ -526,6 +621,478 @@ fn inactive_group_material_is_not_active_and_cannot_own_data_state() {
     assert!(matches!(update_error, StoreError::StoreExternal { .. }));
 }
 
+#[test]
+fn verification_reports_inconsistent_state_and_repairs_stale_pending_updates() {
+    let dataset_id = docs_dataset_id();
+    let schema = title_schema();
+    let store =
+        in_memory_store_with_schema_sources(local_member(), [(dataset_id.clone(), schema.clone())]);
+    let group_id = GroupId(Uuid::from_u128(11_201));
+    let row_key = RowKey(Uuid::from_u128(11_202));
+    let stale_pending = UpdateId {
+        node_index: 0,
+        version: 1,
+    };
+    let premature_applied = UpdateId {
+        node_index: 1,
+        version: 1,
+    };
+    let update = |update_id: UpdateId, applied_locally| ReplicationUpdateRecord {
+        group_id,
+        update_id,
+        sender: local_member(),
+        read_versions: initial_versions(2),
+        dataset_updates: vec![DatasetUpdateRecord {
+            dataset_id: dataset_id.clone(),
+            operations: vec![encoded_insert_snapshot("update", &schema)],
+        }],
+        applied_locally,
+    };
+    wait_for_store_future(async {
+        let mut transaction = store
+            .begin_transaction()
+            .await
+            .expect("transaction should open");
+        transaction
+            .insert_replication_group(sample_group(group_id))
+            .await
+            .expect("group should store");
+        transaction
+            .apply_dataset_row_patch(DatasetRowStatePatch {
+                group_id,
+                dataset_id: dataset_id.clone(),
+                actions: vec![DatasetRowStateWrite::UpsertActive {
+                    row_key,
+                    snapshot: title_snapshot(&schema, row_key, "ahead"),
+                }],
+                last_changed_versions: VersionVector::from_entries([1, 1]),
+            })
+            .await
+            .expect("row should store");
+        transaction
+            .append_replication_update(update(stale_pending, false))
+            .await
+            .expect("pending update should store");
+        transaction
+            .append_replication_update(update(premature_applied, true))
+            .await
+            .expect("applied update should store");
+        transaction
+            .commit()
+            .await
+            .expect("transaction should commit");
+    });
+
+    assert_eq!(
+        wait_for_store_future(store.check_database_integrity())
+            .expect("integrity check should run"),
+        Vec::<String>::new()
+    );
+    let report = wait_for_store_future(crate::verify_replication_store(&store, true))
+        .expect("verification should run");
+    assert_eq!(report.groups_checked, 1);
+    assert_eq!(report.rows_checked, 1);
+    assert_eq!(report.updates_checked, 2);
+    assert!(!report.is_consistent());
+    assert_eq!(
+        report
+            .issues
+            .iter()
+            .map(|issue| (issue.kind.clone(), issue.repaired))
+            .collect_vec(),
+        vec![
+            (
+                StoreIssueKind::RowAheadOfGroup {
+                    dataset_id: dataset_id.clone(),
+                    row_key,
+                    last_changed_versions: VersionVector::from_entries([1, 1]),
+                },
+                false,
+            ),
+            (
+                StoreIssueKind::AppliedUpdateAhead {
+                    update_id: premature_applied,
+                },
+                false,
+            ),
+            (
+                StoreIssueKind::PendingUpdateAlreadyApplied {
+                    update_id: stale_pending,
+                },
+                true,
+            ),
+        ]
+    );
+
+    let report = wait_for_store_future(crate::verify_replication_store(&store, false))
+        .expect("verification should run");
+    assert_eq!(report.issues.len(), 2);
+    assert!(report.issues.iter().all(|issue| !matches!(
+        issue.kind,
+        StoreIssueKind::PendingUpdateAlreadyApplied { .. }
+    )));
+}
+
+#[test]
+fn dangling_doc_refs_report_links_to_deleted_documents() {
+    let dataset_id = docs_dataset_id();
+    let schema = links_schema();
+    let store =
+        in_memory_store_with_schema_sources(local_member(), [(dataset_id.clone(), schema.clone())]);
+    let group_id = GroupId(Uuid::from_u128(11_301));
+    let source = RowKey(Uuid::from_u128(11_302));
+    let live_target = RowKey(Uuid::from_u128(11_303));
+    let deleted_target = RowKey(Uuid::from_u128(11_304));
+    let deleted_link = DocRef::anchored(
+        deleted_target.0,
+        IdWithIndex {
+            id: UpdateId {
+                node_index: 0,
+                version: 1,
+            },
+            index: 2,
+        },
+    );
+    wait_for_store_future(async {
+        let mut transaction = store
+            .begin_transaction()
+            .await
+            .expect("transaction should open");
+        transaction
+            .insert_replication_group(ReplicationGroupRecord {
+                group_schema: GroupSchema::new(HashMap::from([(
+                    dataset_id.clone(),
+                    SchemaSource::from(schema.clone()),
+                )])),
+                ..sample_group(group_id)
+            })
+            .await
+            .expect("group should store");
+        transaction
+            .apply_dataset_row_patch(DatasetRowStatePatch {
+                group_id,
+                dataset_id: dataset_id.clone(),
+                actions: vec![
+                    DatasetRowStateWrite::UpsertActive {
+                        row_key: source,
+                        snapshot: links_snapshot(
+                            &schema,
+                            source,
+                            vec![DocRef::document(live_target.0), deleted_link.clone()],
+                        ),
+                    },
+                    DatasetRowStateWrite::UpsertActive {
+                        row_key: live_target,
+                        snapshot: links_snapshot(&schema, live_target, Vec::new()),
+                    },
+                    DatasetRowStateWrite::UpsertTombstone {
+                        row_key: deleted_target,
+                        snapshot: links_snapshot(
+                            &schema,
+                            deleted_target,
+                            vec![DocRef::document(Uuid::from_u128(11_305))],
+                        ),
+                    },
+                ],
+                last_changed_versions: VersionVector::from_entries([1, 0]),
+            })
+            .await
+            .expect("rows should store");
+        transaction
+            .commit()
+            .await
+            .expect("transaction should commit");
+    });
+
+    let dangling = wait_for_store_future(crate::find_dangling_doc_refs(&store))
+        .expect("reference scan should run");
+    assert_eq!(
+        dangling,
+        vec![DanglingDocRef {
+            source: RowId {
+                group_id,
+                dataset_id,
+                row_key: source,
+            },
+            field_name: "links".to_owned(),
+            target: deleted_link,
+        }]
+    );
+}
+
+#[test]
+fn purge_tombstoned_documents_waits_for_every_member() {
+    let dataset_id = docs_dataset_id();
+    let schema = links_schema();
+    let store =
+        in_memory_store_with_schema_sources(local_member(), [(dataset_id.clone(), schema.clone())]);
+    let group_id = GroupId(Uuid::from_u128(11_401));
+    let live_row = RowKey(Uuid::from_u128(11_402));
+    let deleted_row = RowKey(Uuid::from_u128(11_403));
+    wait_for_store_future(async {
+        let mut transaction = store
+            .begin_transaction()
+            .await
+            .expect("transaction should open");
+        transaction
+            .insert_replication_group(ReplicationGroupRecord {
+                group_schema: GroupSchema::new(HashMap::from([(
+                    dataset_id.clone(),
+                    SchemaSource::from(schema.clone()),
+                )])),
+                ..sample_group(group_id)
+            })
+            .await
+            .expect("group should store");
+        transaction
+            .apply_dataset_row_patch(DatasetRowStatePatch {
+                group_id,
+                dataset_id: dataset_id.clone(),
+                actions: vec![
+                    DatasetRowStateWrite::UpsertActive {
+                        row_key: live_row,
+                        snapshot: links_snapshot(&schema, live_row, Vec::new()),
+                    },
+                    DatasetRowStateWrite::UpsertTombstone {
+                        row_key: deleted_row,
+                        snapshot: links_snapshot(&schema, deleted_row, Vec::new()),
+                    },
+                ],
+                last_changed_versions: VersionVector::from_entries([1, 0]),
+            })
+            .await
+            .expect("rows should store");
+        transaction
+            .commit()
+            .await
+            .expect("transaction should commit");
+    });
+    let deleted_row_id = RowId {
+        group_id,
+        dataset_id: dataset_id.clone(),
+        row_key: deleted_row,
+    };
+
+    let tombstones = wait_for_store_future(crate::list_tombstoned_documents(&store, group_id))
+        .expect("tombstones should list");
+    assert_eq!(
+        tombstones,
+        vec![TombstonedDocument {
+            row_id: deleted_row_id.clone(),
+            last_changed_versions: VersionVector::from_entries([1, 0]),
+        }]
+    );
+
+    let remote_behind = AcknowledgedVersions {
+        group_id,
+        members: vec![
+            VersionVector::from_entries([1, 0]),
+            VersionVector::from_entries([0, 0]),
+        ],
+        stable_versions: VersionVector::from_entries([0, 0]),
+    };
+    let purged = wait_for_store_future(crate::purge_tombstoned_documents(&store, &remote_behind))
+        .expect("purge should run");
+    assert!(purged.is_empty());
+
+    let local_behind = AcknowledgedVersions {
+        group_id,
+        members: vec![
+            VersionVector::from_entries([1, 0]),
+            VersionVector::from_entries([1, 1]),
+        ],
+        stable_versions: VersionVector::from_entries([1, 0]),
+    };
+    let purged = wait_for_store_future(crate::purge_tombstoned_documents(&store, &local_behind))
+        .expect("purge should run");
+    assert!(purged.is_empty());
+
+    let all_acknowledged = AcknowledgedVersions {
+        group_id,
+        members: vec![
+            VersionVector::from_entries([1, 0]),
+            VersionVector::from_entries([1, 0]),
+        ],
+        stable_versions: VersionVector::from_entries([1, 0]),
+    };
+    let purged =
+        wait_for_store_future(crate::purge_tombstoned_documents(&store, &all_acknowledged))
+            .expect("purge should run");
+    assert_eq!(purged, vec![deleted_row_id]);
+
+    let tombstones = wait_for_store_future(crate::list_tombstoned_documents(&store, group_id))
+        .expect("tombstones should list");
+    assert!(tombstones.is_empty());
+    let remaining_rows = wait_for_store_future(async {
+        let mut transaction = store
+            .begin_read_transaction()
+            .await
+            .expect("transaction should open");
+        transaction
+            .scan_dataset_row_batch(&group_id, &dataset_id, None, NonZeroUsize::new(16).unwrap())
+            .await
+            .expect("rows should scan")
+    });
+    assert_eq!(
+        remaining_rows
+            .rows
+            .iter()
+            .map(|row| row.row_id)
+            .collect::<Vec<_>>(),
+        vec![live_row]
+    );
+}
+
+#[test]
+fn storage_report_accounts_snapshots_update_log_and_blobs() {
+    let dataset_id = docs_dataset_id();
+    let schema = title_schema();
+    let store =
+        in_memory_store_with_schema_sources(local_member(), [(dataset_id.clone(), schema.clone())]);
+    let group_id = GroupId(Uuid::from_u128(11_501));
+    let first_row = RowKey(Uuid::from_u128(11_502));
+    let second_row = RowKey(Uuid::from_u128(11_503));
+    let first_snapshot = title_snapshot(&schema, first_row, "a long first title");
+    let second_snapshot = title_snapshot(&schema, second_row, "second");
+    let update = ReplicationUpdateRecord {
+        group_id,
+        update_id: UpdateId {
+            node_index: 0,
+            version: 1,
+        },
+        sender: local_member(),
+        read_versions: initial_versions(2),
+        dataset_updates: vec![DatasetUpdateRecord {
+            dataset_id: dataset_id.clone(),
+            operations: vec![encoded_insert_snapshot("a long first title", &schema)],
+        }],
+        applied_locally: true,
+    };
+    wait_for_store_future(async {
+        let mut transaction = store
+            .begin_transaction()
+            .await
+            .expect("transaction should open");
+        transaction
+            .insert_replication_group(sample_group(group_id))
+            .await
+            .expect("group should store");
+        transaction
+            .apply_dataset_row_patch(DatasetRowStatePatch {
+                group_id,
+                dataset_id: dataset_id.clone(),
+                actions: vec![
+                    DatasetRowStateWrite::UpsertActive {
+                        row_key: first_row,
+                        snapshot: first_snapshot.clone(),
+                    },
+                    DatasetRowStateWrite::UpsertActive {
+                        row_key: second_row,
+                        snapshot: second_snapshot.clone(),
+                    },
+                ],
+                last_changed_versions: VersionVector::from_entries([1, 0]),
+            })
+            .await
+            .expect("rows should store");
+        transaction
+            .append_replication_update(update.clone())
+            .await
+            .expect("update should store");
+        transaction
+            .commit()
+            .await
+            .expect("transaction should commit");
+    });
+    let row_id = |row_key| RowId {
+        group_id,
+        dataset_id: dataset_id.clone(),
+        row_key,
+    };
+    let mut blobs = BlobStore::new(NonZeroUsize::new(4).unwrap());
+    let shared = blobs.insert(b"shared blob").expect("blob should store");
+    let own = blobs.insert(b"own").expect("blob should store");
+    let orphan = blobs.insert(b"orphan").expect("blob should store");
+    blobs
+        .add_reference(&shared.hash, row_id(first_row))
+        .expect("reference should add");
+    blobs
+        .add_reference(&shared.hash, row_id(second_row))
+        .expect("reference should add");
+    blobs
+        .add_reference(&own.hash, row_id(first_row))
+        .expect("reference should add");
+    blobs
+        .add_reference(&orphan.hash, row_id(RowKey(Uuid::from_u128(11_504))))
+        .expect("reference should add");
+
+    let report =
+        wait_for_store_future(crate::storage_report(&store, &blobs)).expect("report should build");
+
+    let snapshot_bytes = |snapshot| {
+        encode_dataset_row_snapshot(&schema, snapshot)
+            .expect("snapshot should encode")
+            .len() as u64
+    };
+    let first = DocumentStorageUsage {
+        row_id: row_id(first_row),
+        snapshot_bytes: snapshot_bytes(&first_snapshot),
+        blob_bytes: shared.length + own.length,
+    };
+    let second = DocumentStorageUsage {
+        row_id: row_id(second_row),
+        snapshot_bytes: snapshot_bytes(&second_snapshot),
+        blob_bytes: shared.length,
+    };
+    let group = GroupStorageUsage {
+        group_id,
+        snapshot_bytes: first.snapshot_bytes + second.snapshot_bytes,
+        oplog_bytes: UpdateMessageProtoSource::from(&update)
+            .encode_proto_to_vec()
+            .len() as u64,
+        blob_bytes: shared.length + own.length,
+        documents: vec![first.clone(), second],
+    };
+    assert_eq!(
+        report,
+        StorageReport {
+            groups: vec![group.clone()],
+        }
+    );
+
+    let events = WorkspaceEvents::new(NonZeroUsize::new(8).unwrap());
+    let mut receiver = events.subscribe();
+    let quota = StorageQuota {
+        group_bytes: Some(group.total_bytes() - 1),
+        document_bytes: Some(first.total_bytes() - 1),
+    };
+    let warnings = quota.publish_warnings(&report, &events);
+    assert_eq!(
+        warnings,
+        vec![
+            StorageQuotaWarning {
+                group_id,
+                document: None,
+                used_bytes: group.total_bytes(),
+                quota_bytes: group.total_bytes() - 1,
+            },
+            StorageQuotaWarning {
+                group_id,
+                document: Some(first.row_id.clone()),
+                used_bytes: first.total_bytes(),
+                quota_bytes: first.total_bytes() - 1,
+            },
+        ]
+    );
+    for warning in warnings {
+        assert_eq!(
+            receiver.try_recv().expect("warning should be published"),
+            WorkspaceEvent::StorageQuotaExceeded { warning }
+        );
+    }
+    assert!(StorageQuota::default().warnings(&report).is_empty());
+}
+
 #[test]
 fn stored_member_identity_rejects_overlong_identifier() {
     let raw = std::iter::repeat_n("s", MAX_IDENTIFIER_SEGMENTS + 1).join(".");
@@ -554,6 +1121,13 @@ fn title_schema() -> Arc<Schema> {
     Arc::new(Schema::from_fields([Field::linear_string("title")]))
 }
 
+fn links_schema() -> Arc<Schema> {
+    Arc::new(Schema::from_fields([Field::linear_list(
+        "links",
+        PrimitiveType::DocRef,
+    )]))
+}
+
 fn docs_group_schema() -> GroupSchema {
     GroupSchema::new(HashMap::from([(
         docs_dataset_id(),
@@ -585,7 +1159,7 @@ fn sample_group(group_id: GroupId) -> ReplicationGroupRecord {
     ReplicationGroupRecord {
         group_id,
         member_keys,
-        local_member_index: MemberIndex::new(0),
+        local_member_index: Some(MemberIndex::new(0)),
         group_schema: docs_group_schema(),
         version_vector,
         lifecycle: ReplicationGroupLifecycle::Open,
@@ -675,6 +1249,35 @@ fn title_snapshot(
     snapshot.into_owned()
 }
 
+fn links_snapshot(
+    schema: &Arc<Schema>,
+    row_key: RowKey,
+    links: Vec<DocRef>,
+) -> ReplicationRowStateSnapshot {
+    let mut source_data = flotsync_messages::InMemoryStateData::new(schema.clone());
+    let operation = source_data
+        .insert_row(
+            UpdateId {
+                node_index: 0,
+                version: 1,
+            },
+            row_key.0,
+            vec![
+                schema
+                    .columns
+                    .get("links")
+                    .expect("links field should exist")
+                    .initial(links)
+                    .expect("field value should build"),
+            ],
+        )
+        .expect("row insert should succeed");
+    let RowOperation::Insert { snapshot, .. } = operation.operation else {
+        panic!("expected insert operation");
+    };
+    snapshot.into_owned()
+}
+
 fn encoded_insert_snapshot(
     title: &str,
     schema: &Arc<Schema>,
@@ -828,7 +1431,9 @@ fn sqlite_store_roundtrips_group_dataset_and_update_records() {
             tombstoned: false,
             last_changed_versions: row_patch.last_changed_versions.clone(),
         },
-        DatasetRowStateWrite::UpsertTombstone { .. } => panic!("expected active row patch"),
+        DatasetRowStateWrite::UpsertTombstone { .. } | DatasetRowStateWrite::Purge { .. } => {
+            panic!("expected active row patch")
+        }
     };
     let update = ReplicationUpdateRecord {
         group_id,
@@ -1456,3 +2061,51 @@ fn sqlite_store_rejects_duplicate_update_insert_but_allows_applied_toggle() {
     assert!(loaded_update.applied_locally);
     assert_eq!(loaded_update.update_id.version, u64::MAX - 1);
 }
+
+#[cfg(feature = "fault-injection")]
+#[test]
+fn injected_commit_failure_rolls_back_the_transaction() {
+    use crate::fault_points;
+    use flotsync_utils::fault_injection::{FaultAction, FaultScenario};
+
+    let scenario = FaultScenario::setup();
+    scenario.configure_times(fault_points::STORE_COMMIT, FaultAction::Fail, 1);
+    let store = in_memory_store(local_member());
+    let group_id = GroupId(Uuid::from_u128(11_101));
+    let insert_group = || async {
+        let mut transaction = store
+            .begin_transaction()
+            .await
+            .expect("transaction should open");
+        transaction
+            .insert_replication_group(sample_group(group_id))
+            .await
+            .expect("group should store");
+        transaction.commit().await
+    };
+    let load_group_count = || async {
+        let mut transaction = store
+            .begin_read_transaction()
+            .await
+            .expect("read transaction should open");
+        let groups = transaction
+            .load_replication_groups()
+            .await
+            .expect("groups should load");
+        transaction
+            .release()
+            .await
+            .expect("read transaction should release");
+        groups.len()
+    };
+
+    let error = wait_for_store_future(insert_group()).expect_err("injected commit should fail");
+    assert!(
+        error.to_string().contains("disk is full"),
+        "unexpected error: {error}"
+    );
+    assert_eq!(wait_for_store_future(load_group_count()), 0);
+
+    wait_for_store_future(insert_group()).expect("commit should succeed once the fault is spent");
+    assert_eq!(wait_for_store_future(load_group_count()), 1);
+}
@@ -0,0 +1,174 @@
+//! Reclaiming the storage of deleted documents.
+//!
+//! Deleting a document is the first of two phases: the row stays in the store
+//! as a tombstone, so operations from members that have not seen the delete
+//! yet still find it deleted instead of bringing it back. Once every member
+//! has acknowledged the delete, and the local member has applied everything
+//! the others acknowledged, nothing can target the row anymore.
+//! [`purge_tombstoned_documents`] then removes it in the second phase.
+//!
+//! Acknowledgements are only kept in memory by the runtime, so the
+//! [`AcknowledgedVersions`] passed in here should come from
+//! [`ReplicationApi::acknowledged_versions`] just before purging.
+//!
+//! [`ReplicationApi::acknowledged_versions`]: crate::api::ReplicationApi::acknowledged_versions
+
+use crate::api::{
+    AcknowledgedVersions,
+    DatasetRowStatePatch,
+    DatasetRowStateWrite,
+    ReplicationGroupRecord,
+    ReplicationStore,
+    ReplicationStoreReadTransaction,
+    RowId,
+    RowKey,
+    StoreError,
+};
+use flotsync_core::{GroupId, versions::VersionVector};
+use std::num::NonZeroUsize;
+
+/// One deleted document that is still retained as a tombstone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TombstonedDocument {
+    /// The deleted row.
+    pub row_id: RowId,
+    /// Causal version of the last change to the row, usually its delete.
+    pub last_changed_versions: VersionVector,
+}
+
+impl TombstonedDocument {
+    /// Whether the tombstone can be purged without the document coming back.
+    ///
+    /// This requires that every member has acknowledged the last change to
+    /// the row, and that `local_versions` covers everything any member has
+    /// acknowledged. The latter ensures no update made concurrently with the
+    /// delete can still arrive for the row.
+    #[must_use]
+    pub fn is_reclaimable(
+        &self,
+        acknowledged: &AcknowledgedVersions,
+        local_versions: &VersionVector,
+    ) -> bool {
+        self.last_changed_versions <= acknowledged.stable_versions
+            && acknowledged
+                .members
+                .iter()
+                .all(|member_versions| member_versions <= local_versions)
+    }
+}
+
+/// List every tombstoned document of one group, ordered by dataset and row.
+///
+/// Returns an empty list if the store does not know the group.
+///
+/// # Errors
+///
+/// Returns [`StoreError`] if the store cannot be read.
+pub async fn list_tombstoned_documents(
+    store: &dyn ReplicationStore,
+    group_id: GroupId,
+) -> Result<Vec<TombstonedDocument>, StoreError> {
+    let mut transaction = store.begin_read_transaction().await?;
+    let group = transaction.load_replication_group(&group_id).await?;
+    let tombstones = match group {
+        Some(group) => collect_tombstones(transaction.as_mut(), &group).await?,
+        None => Vec::new(),
+    };
+    transaction.release().await?;
+    Ok(tombstones)
+}
+
+/// Remove every tombstone of the group in `acknowledged` that
+/// [is reclaimable](TombstonedDocument::is_reclaimable).
+///
+/// Returns the purged rows, ordered by dataset and row. Returns an empty list
+/// if the store does not know the group.
+///
+/// # Errors
+///
+/// Returns [`StoreError`] if the store cannot be read or written. Nothing is
+/// purged in that case.
+pub async fn purge_tombstoned_documents(
+    store: &dyn ReplicationStore,
+    acknowledged: &AcknowledgedVersions,
+) -> Result<Vec<RowId>, StoreError> {
+    let mut transaction = store.begin_transaction().await?;
+    let Some(group) = transaction
+        .load_replication_group(&acknowledged.group_id)
+        .await?
+    else {
+        transaction.rollback().await?;
+        return Ok(Vec::new());
+    };
+    let tombstones = collect_tombstones(transaction.as_mut(), &group).await?;
+    let mut purged = Vec::new();
+    for dataset in group.group_schema.datasets() {
+        let reclaimable: Vec<&TombstonedDocument> = tombstones
+            .iter()
+            .filter(|tombstone| tombstone.row_id.dataset_id == dataset.dataset_id)
+            .filter(|tombstone| tombstone.is_reclaimable(acknowledged, &group.version_vector))
+            .collect();
+        if reclaimable.is_empty() {
+            continue;
+        }
+        let actions = reclaimable
+            .iter()
+            .map(|tombstone| DatasetRowStateWrite::Purge {
+                row_key: tombstone.row_id.row_key,
+            })
+            .collect();
+        transaction
+            .apply_dataset_row_patch(DatasetRowStatePatch {
+                group_id: group.group_id,
+                dataset_id: dataset.dataset_id.clone(),
+                actions,
+                last_changed_versions: group.version_vector.clone(),
+            })
+            .await?;
+        purged.extend(
+            reclaimable
+                .into_iter()
+                .map(|tombstone| tombstone.row_id.clone()),
+        );
+    }
+    transaction.commit().await?;
+    Ok(purged)
+}
+
+/// Number of rows loaded per scan while collecting tombstones from one dataset.
+const ROW_SCAN_BATCH_SIZE: NonZeroUsize = NonZeroUsize::new(256).unwrap();
+
+/// Scan every dataset of `group` for tombstoned rows.
+async fn collect_tombstones(
+    transaction: &mut dyn ReplicationStoreReadTransaction,
+    group: &ReplicationGroupRecord,
+) -> Result<Vec<TombstonedDocument>, StoreError> {
+    let mut tombstones = Vec::new();
+    for dataset in group.group_schema.datasets() {
+        let mut after: Option<RowKey> = None;
+        let mut exhausted = false;
+        while !exhausted {
+            let batch = transaction
+                .scan_dataset_row_batch(
+                    &group.group_id,
+                    &dataset.dataset_id,
+                    after,
+                    ROW_SCAN_BATCH_SIZE,
+                )
+                .await?;
+            for row in batch.rows.into_iter().filter(|row| row.tombstoned) {
+                tombstones.push(TombstonedDocument {
+                    row_id: RowId {
+                        group_id: group.group_id,
+                        dataset_id: dataset.dataset_id.clone(),
+                        row_key: row.row_id,
+                    },
+                    last_changed_versions: row.last_changed_versions,
+                });
+            }
+            after = batch.next_after;
+            exhausted = after.is_none();
+        }
+    }
+    Ok(tombstones)
+}
@@ -0,0 +1,226 @@
+//! Storage accounting for workspaces and their documents.
+//!
+//! [`storage_report`] measures how many bytes each replication group and each of its documents
+//! occupy: row snapshots and the update log as kept by the [`ReplicationStore`], and attachment
+//! content held in a [`BlobStore`]. A [`StorageQuota`] compares a report against soft limits and
+//! announces every group or document above them on the [`WorkspaceEvents`] bus, so applications
+//! can show users what is consuming space. Quotas never reject writes.
+
+use crate::{
+    api::{ReplicationStore, RowId, StoreError, WorkspaceEvent, WorkspaceEvents},
+    blobs::BlobStore,
+};
+use flotsync_core::GroupId;
+use std::collections::{BTreeSet, HashMap};
+
+/// Storage used by every replication group in a store.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageReport {
+    /// Usage of each group, in the order the store lists them.
+    pub groups: Vec<GroupStorageUsage>,
+}
+
+impl StorageReport {
+    /// Total bytes used by all groups.
+    #[must_use]
+    pub fn total_bytes(&self) -> u64 {
+        self.groups.iter().map(GroupStorageUsage::total_bytes).sum()
+    }
+}
+
+/// Storage used by one replication group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupStorageUsage {
+    pub group_id: GroupId,
+    /// Bytes of every stored document snapshot, tombstones included.
+    pub snapshot_bytes: u64,
+    /// Bytes of the retained update log.
+    ///
+    /// A single update can change several documents, so the log is only accounted per group.
+    pub oplog_bytes: u64,
+    /// Bytes of the distinct blobs referenced by documents of this group.
+    ///
+    /// A blob referenced by several documents is counted once.
+    pub blob_bytes: u64,
+    /// Usage of each document, ordered by dataset and row.
+    pub documents: Vec<DocumentStorageUsage>,
+}
+
+impl GroupStorageUsage {
+    /// Total bytes used by this group.
+    #[must_use]
+    pub fn total_bytes(&self) -> u64 {
+        self.snapshot_bytes + self.oplog_bytes + self.blob_bytes
+    }
+}
+
+/// Storage used by one document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentStorageUsage {
+    pub row_id: RowId,
+    /// Bytes of the stored document snapshot.
+    pub snapshot_bytes: u64,
+    /// Bytes of every blob the document references, including blobs shared with other documents.
+    pub blob_bytes: u64,
+}
+
+impl DocumentStorageUsage {
+    /// Total bytes attributed to this document.
+    #[must_use]
+    pub fn total_bytes(&self) -> u64 {
+        self.snapshot_bytes + self.blob_bytes
+    }
+}
+
+/// Soft storage limits checked against a [`StorageReport`].
+///
+/// Limits that are `None` are not checked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StorageQuota {
+    /// Limit on [`GroupStorageUsage::total_bytes`].
+    pub group_bytes: Option<u64>,
+    /// Limit on [`DocumentStorageUsage::total_bytes`].
+    pub document_bytes: Option<u64>,
+}
+
+impl StorageQuota {
+    /// Return one warning for every group and document in `report` above this quota.
+    ///
+    /// Warnings follow the order of `report`, each group before its documents.
+    #[must_use]
+    pub fn warnings(&self, report: &StorageReport) -> Vec<StorageQuotaWarning> {
+        let mut warnings = Vec::new();
+        for group in &report.groups {
+            if let Some(quota_bytes) = self.group_bytes
+                && group.total_bytes() > quota_bytes
+            {
+                warnings.push(StorageQuotaWarning {
+                    group_id: group.group_id,
+                    document: None,
+                    used_bytes: group.total_bytes(),
+                    quota_bytes,
+                });
+            }
+            let Some(quota_bytes) = self.document_bytes else {
+                continue;
+            };
+            for document in &group.documents {
+                if document.total_bytes() > quota_bytes {
+                    warnings.push(StorageQuotaWarning {
+                        group_id: group.group_id,
+                        document: Some(document.row_id.clone()),
+                        used_bytes: document.total_bytes(),
+                        quota_bytes,
+                    });
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Emit a [`WorkspaceEvent::StorageQuotaExceeded`] for every [warning](Self::warnings).
+    ///
+    /// Returns the emitted warnings.
+    pub fn publish_warnings(
+        &self,
+        report: &StorageReport,
+        events: &WorkspaceEvents,
+    ) -> Vec<StorageQuotaWarning> {
+        let warnings = self.warnings(report);
+        for warning in &warnings {
+            events.emit(WorkspaceEvent::StorageQuotaExceeded {
+                warning: warning.clone(),
+            });
+        }
+        warnings
+    }
+}
+
+/// A group or document whose storage exceeds a [`StorageQuota`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageQuotaWarning {
+    pub group_id: GroupId,
+    /// Document above the document quota, or `None` if the group is above the group quota.
+    pub document: Option<RowId>,
+    pub used_bytes: u64,
+    pub quota_bytes: u64,
+}
+
+/// Measure the storage used by every group in `store`.
+///
+/// Blob usage is attributed through the referrers recorded in `blobs`. Blobs whose referrers are
+/// not stored rows of any group are not counted. Pass an empty [`BlobStore`] when attachments are
+/// not in use.
+///
+/// # Errors
+///
+/// Returns [`StoreError`] if the store cannot be read.
+pub async fn storage_report(
+    store: &dyn ReplicationStore,
+    blobs: &BlobStore<RowId>,
+) -> Result<StorageReport, StoreError> {
+    let mut transaction = store.begin_read_transaction().await?;
+    let group_records = transaction.load_replication_groups().await?;
+    let mut groups = Vec::with_capacity(group_records.len());
+    for group in &group_records {
+        let sizes = transaction
+            .load_group_storage_sizes(&group.group_id)
+            .await?;
+        let documents: Vec<DocumentStorageUsage> = sizes
+            .rows
+            .into_iter()
+            .map(|row| DocumentStorageUsage {
+                row_id: RowId {
+                    group_id: group.group_id,
+                    dataset_id: row.dataset_id,
+                    row_key: row.row_key,
+                },
+                snapshot_bytes: row.snapshot_bytes,
+                blob_bytes: 0,
+            })
+            .collect();
+        groups.push(GroupStorageUsage {
+            group_id: group.group_id,
+            snapshot_bytes: documents
+                .iter()
+                .map(|document| document.snapshot_bytes)
+                .sum(),
+            oplog_bytes: sizes.update_log_bytes,
+            blob_bytes: 0,
+            documents,
+        });
+    }
+    transaction.release().await?;
+    attribute_blobs(&mut groups, blobs);
+    Ok(StorageReport { groups })
+}
+
+/// Add the length of every blob in `blobs` to the documents and groups that reference it.
+fn attribute_blobs(groups: &mut [GroupStorageUsage], blobs: &BlobStore<RowId>) {
+    let document_positions: HashMap<RowId, (usize, usize)> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(group_index, group)| {
+            group
+                .documents
+                .iter()
+                .enumerate()
+                .map(move |(document_index, document)| {
+                    (document.row_id.clone(), (group_index, document_index))
+                })
+        })
+        .collect();
+    for (descriptor, referrers) in blobs.iter_with_referrers() {
+        let mut referencing_groups = BTreeSet::new();
+        for referrer in referrers {
+            let Some(&(group_index, document_index)) = document_positions.get(referrer) else {
+                continue;
+            };
+            groups[group_index].documents[document_index].blob_bytes += descriptor.length;
+            referencing_groups.insert(group_index);
+        }
+        for group_index in referencing_groups {
+            groups[group_index].blob_bytes += descriptor.length;
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Offline consistency check for stored replication state.
+//!
+//! [`verify_replication_store`] walks every active group in a
+//! [`ReplicationStore`] and checks what the runtime otherwise trusts on load:
+//! every stored row decodes and its CRDT fields pass their node-graph
+//! integrity checks, row change versions stay within the group's applied
+//! version vector, and every logged update decodes and agrees with that vector
+//! about whether it was applied. Only inconsistencies that the runtime would
+//! also resolve on its own are repaired; everything else is reported.
+
+use crate::api::{
+    DatasetId,
+    ReplicationGroupRecord,
+    ReplicationStore,
+    ReplicationStoreReadTransaction,
+    ReplicationUpdateFilter,
+    RowKey,
+    StoreError,
+};
+use flotsync_core::{
+    GroupId,
+    versions::{UpdateId, VersionVector},
+};
+use flotsync_data_types::schema::datamodel::RowIntegrityError;
+use itertools::Itertools;
+use snafu::ErrorCompat;
+use std::{cmp::Ordering, fmt, num::NonZeroUsize};
+
+/// Result of one [`verify_replication_store`] run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StoreVerificationReport {
+    /// Number of active groups that were checked.
+    pub groups_checked: usize,
+    /// Number of stored dataset rows that were checked, including tombstones.
+    pub rows_checked: usize,
+    /// Number of logged replication updates that were checked.
+    pub updates_checked: usize,
+    /// Every inconsistency found, in group order.
+    pub issues: Vec<StoreIssue>,
+}
+
+impl StoreVerificationReport {
+    /// Return whether the store is consistent after any repairs were applied.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.issues.iter().all(|issue| issue.repaired)
+    }
+
+    fn push(&mut self, group_id: GroupId, kind: StoreIssueKind) {
+        self.issues.push(StoreIssue {
+            group_id,
+            kind,
+            repaired: false,
+        });
+    }
+}
+
+/// One inconsistency found in stored group state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoreIssue {
+    /// Group whose state is inconsistent.
+    pub group_id: GroupId,
+    /// What is inconsistent.
+    pub kind: StoreIssueKind,
+    /// Whether the verification run already repaired the issue.
+    pub repaired: bool,
+}
+
+impl fmt::Display for StoreIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "group {}: {}", self.group_id, self.kind)?;
+        if self.repaired {
+            write!(f, " (repaired)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Kinds of inconsistencies reported by [`verify_replication_store`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoreIssueKind {
+    /// Rows of a dataset could not be loaded, so the rest of the dataset was skipped.
+    UnreadableDataset {
+        dataset_id: DatasetId,
+        message: String,
+    },
+    /// A stored row snapshot violates the structural invariants of one of its CRDT fields.
+    CorruptRow {
+        dataset_id: DatasetId,
+        row_key: RowKey,
+        source: RowIntegrityError,
+    },
+    /// A row was changed at versions the group has not applied.
+    RowAheadOfGroup {
+        dataset_id: DatasetId,
+        row_key: RowKey,
+        last_changed_versions: VersionVector,
+    },
+    /// A logged update could not be loaded.
+    UnreadableUpdate {
+        update_id: UpdateId,
+        message: String,
+    },
+    /// A logged update names a producer outside the group.
+    UpdateOutsideGroup { update_id: UpdateId },
+    /// An update is marked applied, but the group's version vector does not cover it.
+    AppliedUpdateAhead { update_id: UpdateId },
+    /// An update is still pending, but the group's version vector already covers it.
+    ///
+    /// Repairable: the update is marked applied, as the runtime would on the next inbound update.
+    PendingUpdateAlreadyApplied { update_id: UpdateId },
+}
+
+impl fmt::Display for StoreIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnreadableDataset {
+                dataset_id,
+                message,
+            } => write!(f, "dataset {dataset_id} could not be read: {message}"),
+            Self::CorruptRow {
+                dataset_id,
+                row_key,
+                source,
+            } => write!(
+                f,
+                "row {row_key} in dataset {dataset_id} is corrupt: {source} {}",
+                source.source
+            ),
+            Self::RowAheadOfGroup {
+                dataset_id,
+                row_key,
+                last_changed_versions,
+            } => write!(
+                f,
+                "row {row_key} in dataset {dataset_id} was changed at {last_changed_versions}, \
+                 which the group has not applied"
+            ),
+            Self::UnreadableUpdate { update_id, message } => {
+                write!(f, "update {update_id} could not be read: {message}")
+            }
+            Self::UpdateOutsideGroup { update_id } => {
+                write!(f, "update {update_id} names a producer outside the group")
+            }
+            Self::AppliedUpdateAhead { update_id } => write!(
+                f,
+                "update {update_id} is marked applied, but the group has not applied it"
+            ),
+            Self::PendingUpdateAlreadyApplied { update_id } => write!(
+                f,
+                "update {update_id} is pending, but the group has already applied it"
+            ),
+        }
+    }
+}
+
+/// Check all active groups in `store` for inconsistent state.
+///
+/// Reading happens in one read transaction. With `repair`, repairable issues are
+/// fixed afterwards in one write transaction and marked as repaired in the report.
+///
+/// # Errors
+///
+/// Returns [`StoreError`] if the store cannot be accessed at all. Unreadable
+/// rows and updates are reported as issues instead.
+pub async fn verify_replication_store(
+    store: &dyn ReplicationStore,
+    repair: bool,
+) -> Result<StoreVerificationReport, StoreError> {
+    let mut report = StoreVerificationReport::default();
+    let mut transaction = store.begin_read_transaction().await?;
+    let groups = transaction.load_replication_groups().await?;
+    for group in &groups {
+        report.groups_checked += 1;
+        verify_group_rows(transaction.as_mut(), group, &mut report).await;
+        verify_group_updates(transaction.as_mut(), group, &mut report).await?;
+    }
+    transaction.release().await?;
+    if repair {
+        repair_issues(store, &mut report).await?;
+    }
+    Ok(report)
+}
+
+/// Number of rows loaded per scan while verifying one dataset.
+const ROW_SCAN_BATCH_SIZE: NonZeroUsize = NonZeroUsize::new(256).unwrap();
+
+async fn verify_group_rows(
+    transaction: &mut dyn ReplicationStoreReadTransaction,
+    group: &ReplicationGroupRecord,
+    report: &mut StoreVerificationReport,
+) {
+    for dataset in group.group_schema.datasets() {
+        let dataset_id = dataset.dataset_id;
+        let mut after = None;
+        loop {
+            let batch = match transaction
+                .scan_dataset_row_batch(&group.group_id, &dataset_id, after, ROW_SCAN_BATCH_SIZE)
+                .await
+            {
+                Ok(batch) => batch,
+                Err(error) => {
+                    report.push(
+                        group.group_id,
+                        StoreIssueKind::UnreadableDataset {
+                            dataset_id: dataset_id.clone(),
+                            message: error_chain_message(&error),
+                        },
+                    );
+                    break;
+                }
+            };
+            for row in batch.rows {
+                report.rows_checked += 1;
+                if let Err(source) = row.snapshot.validate_integrity() {
+                    report.push(
+                        group.group_id,
+                        StoreIssueKind::CorruptRow {
+                            dataset_id: dataset_id.clone(),
+                            row_key: row.row_id,
+                            source,
+                        },
+                    );
+                }
+                if !matches!(
+                    row.last_changed_versions.partial_cmp(&group.version_vector),
+                    Some(Ordering::Less | Ordering::Equal)
+                ) {
+                    report.push(
+                        group.group_id,
+                        StoreIssueKind::RowAheadOfGroup {
+                            dataset_id: dataset_id.clone(),
+                            row_key: row.row_id,
+                            last_changed_versions: row.last_changed_versions,
+                        },
+                    );
+                }
+            }
+            match batch.next_after {
+                Some(next_after) => after = Some(next_after),
+                None => break,
+            }
+        }
+    }
+}
+
+async fn verify_group_updates(
+    transaction: &mut dyn ReplicationStoreReadTransaction,
+    group: &ReplicationGroupRecord,
+    report: &mut StoreVerificationReport,
+) -> Result<(), StoreError> {
+    let applied = transaction
+        .load_replication_update_ids(&group.group_id, ReplicationUpdateFilter::Applied, None)
+        .await?;
+    let pending = transaction
+        .load_replication_update_ids(&group.group_id, ReplicationUpdateFilter::PendingApply, None)
+        .await?;
+    let member_count = group.member_count().get();
+    let updates = applied
+        .into_iter()
+        .map(|update_id| (update_id, true))
+        .chain(pending.into_iter().map(|update_id| (update_id, false)));
+    for (update_id, applied_locally) in updates {
+        report.updates_checked += 1;
+        let producer_index = update_id.node_index as usize;
+        if producer_index >= member_count {
+            report.push(
+                group.group_id,
+                StoreIssueKind::UpdateOutsideGroup { update_id },
+            );
+            continue;
+        }
+        let covered = update_id.version <= group.version_vector.version_at(producer_index);
+        if applied_locally && !covered {
+            report.push(
+                group.group_id,
+                StoreIssueKind::AppliedUpdateAhead { update_id },
+            );
+        } else if !applied_locally && covered {
+            report.push(
+                group.group_id,
+                StoreIssueKind::PendingUpdateAlreadyApplied { update_id },
+            );
+        }
+        if let Err(error) = transaction
+            .load_replication_update(&group.group_id, update_id)
+            .await
+        {
+            report.push(
+                group.group_id,
+                StoreIssueKind::UnreadableUpdate {
+                    update_id,
+                    message: error_chain_message(&error),
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn repair_issues(
+    store: &dyn ReplicationStore,
+    report: &mut StoreVerificationReport,
+) -> Result<(), StoreError> {
+    if !report.issues.iter().any(|issue| {
+        matches!(
+            issue.kind,
+            StoreIssueKind::PendingUpdateAlreadyApplied { .. }
+        )
+    }) {
+        return Ok(());
+    }
+    let mut transaction = store.begin_transaction().await?;
+    for issue in &report.issues {
+        if let StoreIssueKind::PendingUpdateAlreadyApplied { update_id } = issue.kind {
+            transaction
+                .mark_replication_update_applied(&issue.group_id, update_id)
+                .await?;
+        }
+    }
+    transaction.commit().await?;
+    for issue in &mut report.issues {
+        if matches!(
+            issue.kind,
+            StoreIssueKind::PendingUpdateAlreadyApplied { .. }
+        ) {
+            issue.repaired = true;
+        }
+    }
+    Ok(())
+}
+
+/// Render an error and its sources on one line for an issue report.
+fn error_chain_message(error: &StoreError) -> String {
+    ErrorCompat::iter_chain(error).join(": ")
+}
@@ -14,6 +14,7 @@ use crate::{
     RouteTransportSend,
     RouteTransportSubmitResult,
     SendRouteCandidate,
+    TrafficClass,
     TransportRouteKey,
     UdpRouteKey,
 };
@@ -103,6 +104,7 @@ pub(crate) async fn submit_endpoint_discovery_frame(
         send_id: RouteSendId(Uuid::new_v4()),
         route,
         payload,
+        traffic_class: TrafficClass::Interactive,
     };
     let future = route_transport
         .ask_with(|promise| RouteTransportActorMessage::Submit(Ask::new(promise, send)));
@@ -268,7 +268,11 @@ impl KeyMaterialDiscoveryComponent {
             }
             Some(
                 discovery_proto::discovery_frame::Body::IntroductionRequest(_)
-                | discovery_proto::discovery_frame::Body::Introduction(_),
+                | discovery_proto::discovery_frame::Body::Introduction(_)
+                | discovery_proto::discovery_frame::Body::AddressObservationRequest(_)
+                | discovery_proto::discovery_frame::Body::AddressObservation(_)
+                | discovery_proto::discovery_frame::Body::RendezvousRequest(_)
+                | discovery_proto::discovery_frame::Body::RendezvousIntroduction(_),
             ) => Handled::OK,
             None => {
                 debug!(
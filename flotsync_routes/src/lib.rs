@@ -25,15 +25,22 @@ pub use kompact;
 mod endpoint_discovery;
 
 pub mod key_material_discovery;
+pub mod liveness;
 pub mod manager;
+pub mod nat_traversal;
 pub mod protocol;
 pub mod route_establishment;
 #[cfg(any(test, feature = "test-support"))]
 pub mod test_support;
+pub mod trace;
 
 /// Kompact configuration keys consumed by route-establishment support.
 pub mod config_keys {
-    use kompact::{config::DurationValue, kompact_config};
+    use crate::liveness::PhiAccrualConfig;
+    use kompact::{
+        config::{ArrayOfValues, BooleanValue, DurationValue, RealValue, StringValue},
+        kompact_config,
+    };
     use std::time::Duration;
 
     kompact_config! {
@@ -62,13 +69,96 @@ pub mod config_keys {
         doc = "Time for which one in-flight direct key-material lookup suppresses duplicate requests.",
         version = "0.1.0"
     }
+
+    kompact_config! {
+        PEER_LIVENESS_CHECK_INTERVAL,
+        key = "flotsync.discovery.peer-liveness.check-interval",
+        type = DurationValue,
+        default = Duration::from_secs(1),
+        doc = "Interval at which peer heartbeat suspicion levels are re-evaluated.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_LIVENESS_SUSPECT_THRESHOLD,
+        key = "flotsync.discovery.peer-liveness.suspect-threshold",
+        type = RealValue,
+        default = PhiAccrualConfig::DEFAULT.suspect_threshold,
+        validate = |value| *value > 0.0,
+        doc = "Phi-accrual suspicion level at which a peer is reported as suspected.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_LIVENESS_DOWN_THRESHOLD,
+        key = "flotsync.discovery.peer-liveness.down-threshold",
+        type = RealValue,
+        default = PhiAccrualConfig::DEFAULT.down_threshold,
+        validate = |value| *value > 0.0,
+        doc = "Phi-accrual suspicion level at which a peer is reported as down. Must not be below the suspect threshold.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_LIVENESS_MIN_STD_DEVIATION,
+        key = "flotsync.discovery.peer-liveness.min-std-deviation",
+        type = DurationValue,
+        default = PhiAccrualConfig::DEFAULT.min_std_deviation,
+        doc = "Lower bound for the heartbeat interval standard deviation used by the failure detector.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        PEER_LIVENESS_ACCEPTABLE_HEARTBEAT_PAUSE,
+        key = "flotsync.discovery.peer-liveness.acceptable-heartbeat-pause",
+        type = DurationValue,
+        default = PhiAccrualConfig::DEFAULT.acceptable_heartbeat_pause,
+        doc = "Heartbeat delay tolerated on top of the mean interval before peer suspicion rises.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        NAT_TRAVERSAL_RENDEZVOUS_RELAYS,
+        key = "flotsync.discovery.nat-traversal.rendezvous-relays",
+        type = ArrayOfValues<StringValue>,
+        default = Vec::new(),
+        doc = "UDP addresses (`ip:port`) of relay peers used for public address observation and hole-punching rendezvous. Empty disables hole punching.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        NAT_TRAVERSAL_SERVE_RENDEZVOUS,
+        key = "flotsync.discovery.nat-traversal.serve-rendezvous",
+        type = BooleanValue,
+        default = false,
+        doc = "Whether this endpoint acts as a rendezvous relay, answering address observations and introducing registered members to each other.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        NAT_TRAVERSAL_REFRESH_INTERVAL,
+        key = "flotsync.discovery.nat-traversal.refresh-interval",
+        type = DurationValue,
+        default = Duration::from_secs(20),
+        doc = "Interval at which relay registrations, observed public addresses, and rendezvous requests for unreachable members are refreshed. Must stay below typical NAT mapping timeouts.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        NAT_TRAVERSAL_REGISTRATION_LEASE,
+        key = "flotsync.discovery.nat-traversal.registration-lease",
+        type = DurationValue,
+        default = Duration::from_secs(60),
+        doc = "Time for which a rendezvous relay remembers the observed address of a registered member.",
+        version = "0.1.0"
+    }
 }
 
 use flotsync_core::MemberIdentity;
 use flotsync_io::prelude::{IoPayload, SocketId, UdpCloseReason};
 use flotsync_messages::serialisation::FlotsyncSerializable;
 /// Per-socket `UDPour` runtime configuration used by route transport.
-pub use flotsync_udpour::UDPourConfig;
+pub use flotsync_udpour::{TrafficClass, UDPourConfig};
 use flotsync_utils::{IString, NonOwningPhantomData};
 use kompact::{
     Never,
@@ -319,6 +409,8 @@ pub struct RouteTransportSend<R> {
     /// Logical payload to serialise once route transport has provisioned any
     /// transport-local resources it needs.
     pub payload: Arc<dyn FlotsyncSerializable>,
+    /// Scheduling lane for the payload on transports that share one socket between peers.
+    pub traffic_class: TrafficClass,
 }
 
 impl<R> std::fmt::Debug for RouteTransportSend<R>
@@ -330,6 +422,7 @@ where
             .field("send_id", &self.send_id)
             .field("route", &self.route)
             .field("payload", &"<serializable payload>")
+            .field("traffic_class", &self.traffic_class)
             .finish()
     }
 }
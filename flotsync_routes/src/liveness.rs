@@ -0,0 +1,358 @@
+//! Heartbeat-based peer liveness using a phi-accrual failure detector.
+//!
+//! Route establishment refreshes every verified route when its reachable lease
+//! expires, so each successful verification doubles as one heartbeat from the
+//! members published through that route. Instead of a fixed timeout, the
+//! detector learns the distribution of heartbeat inter-arrival times per peer
+//! and reports a suspicion level `phi`: the negative base-10 logarithm of the
+//! probability that a heartbeat this late would still arrive. Crossing the
+//! configured thresholds marks a peer as suspected and then as down, until the
+//! next heartbeat marks it alive again.
+
+use flotsync_core::{MemberIdentity, member::TrieMap};
+use kompact::{Never, prelude::Port};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Liveness of one peer as judged by the local failure detector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PeerLiveness {
+    /// Heartbeats from the peer arrive as expected.
+    Alive,
+    /// Heartbeats are overdue enough that the peer may have failed.
+    Suspected,
+    /// Heartbeats are overdue enough that the peer is treated as failed until it is heard from
+    /// again.
+    Down,
+}
+
+impl PeerLiveness {
+    /// Return whether the peer is currently treated as failed.
+    #[must_use]
+    pub fn is_down(self) -> bool {
+        matches!(self, Self::Down)
+    }
+}
+
+/// Liveness transition of one peer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerLivenessUpdate {
+    /// Peer whose liveness changed.
+    pub peer: MemberIdentity,
+    /// New liveness of the peer.
+    pub liveness: PeerLiveness,
+}
+
+/// Port used to publish peer liveness transitions into dependent components.
+///
+/// Only transitions are published. Peers that were never published have not sent a heartbeat
+/// yet.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PeerLivenessPort;
+
+impl Port for PeerLivenessPort {
+    type Request = Never;
+    type Indication = PeerLivenessUpdate;
+}
+
+/// Tuning for [`PhiAccrualDetector`] and the liveness thresholds applied to its output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhiAccrualConfig {
+    /// Suspicion level at or above which a peer is [`PeerLiveness::Suspected`].
+    pub suspect_threshold: f64,
+    /// Suspicion level at or above which a peer is [`PeerLiveness::Down`].
+    pub down_threshold: f64,
+    /// Number of recent heartbeat intervals kept per peer.
+    pub max_sample_size: usize,
+    /// Lower bound for the interval standard deviation.
+    ///
+    /// Very regular heartbeats would otherwise make the detector react to tiny delays.
+    pub min_std_deviation: Duration,
+    /// Additional delay tolerated on top of the mean interval before suspicion rises.
+    pub acceptable_heartbeat_pause: Duration,
+    /// Interval assumed until a peer has sent enough heartbeats to measure one.
+    pub first_heartbeat_estimate: Duration,
+}
+
+impl PhiAccrualConfig {
+    /// Defaults matching the route-establishment reachable lease cadence.
+    pub const DEFAULT: Self = Self {
+        suspect_threshold: 8.0,
+        down_threshold: 16.0,
+        max_sample_size: 100,
+        min_std_deviation: Duration::from_secs(1),
+        acceptable_heartbeat_pause: Duration::from_secs(5),
+        first_heartbeat_estimate: Duration::from_secs(30),
+    };
+
+    /// Classify one suspicion level against the configured thresholds.
+    #[must_use]
+    pub fn liveness(&self, phi: f64) -> PeerLiveness {
+        if phi >= self.down_threshold {
+            PeerLiveness::Down
+        } else if phi >= self.suspect_threshold {
+            PeerLiveness::Suspected
+        } else {
+            PeerLiveness::Alive
+        }
+    }
+}
+
+impl Default for PhiAccrualConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Phi-accrual failure detector for the heartbeats of one peer.
+///
+/// See Hayashibara et al., "The φ Accrual Failure Detector" (2004). The interval distribution is
+/// approximated as normal, using a logistic approximation of its cumulative distribution.
+#[derive(Clone, Debug)]
+pub struct PhiAccrualDetector {
+    config: PhiAccrualConfig,
+    /// Recent heartbeat intervals in milliseconds, oldest first.
+    intervals: VecDeque<f64>,
+    interval_sum: f64,
+    squared_interval_sum: f64,
+    last_heartbeat: Option<Instant>,
+}
+
+impl PhiAccrualDetector {
+    /// Build a detector that has not observed any heartbeat yet.
+    ///
+    /// The interval history is seeded from
+    /// [`first_heartbeat_estimate`](PhiAccrualConfig::first_heartbeat_estimate), so a peer that
+    /// stops after its first heartbeat is still detected.
+    #[must_use]
+    pub fn new(config: PhiAccrualConfig) -> Self {
+        let mut detector = Self {
+            config,
+            intervals: VecDeque::with_capacity(config.max_sample_size),
+            interval_sum: 0.0,
+            squared_interval_sum: 0.0,
+            last_heartbeat: None,
+        };
+        let estimate = millis(config.first_heartbeat_estimate);
+        let std_deviation = estimate / 4.0;
+        detector.push_interval(estimate - std_deviation);
+        detector.push_interval(estimate + std_deviation);
+        detector
+    }
+
+    /// Record one heartbeat observed at `now`.
+    pub fn heartbeat(&mut self, now: Instant) {
+        if let Some(last_heartbeat) = self.last_heartbeat.replace(now) {
+            self.push_interval(millis(now.saturating_duration_since(last_heartbeat)));
+        }
+    }
+
+    /// Return the suspicion level at `now`.
+    ///
+    /// Returns `0.0` before the first heartbeat. The value grows without bound while heartbeats
+    /// stay overdue and may be infinite.
+    #[must_use]
+    pub fn phi(&self, now: Instant) -> f64 {
+        let Some(last_heartbeat) = self.last_heartbeat else {
+            return 0.0;
+        };
+        let elapsed = millis(now.saturating_duration_since(last_heartbeat));
+        let samples = self.intervals.len() as f64;
+        let mean = self.interval_sum / samples;
+        let variance = (self.squared_interval_sum / samples - mean * mean).max(0.0);
+        let std_deviation = variance.sqrt().max(millis(self.config.min_std_deviation));
+        let expected = mean + millis(self.config.acceptable_heartbeat_pause);
+
+        let y = (elapsed - expected) / std_deviation;
+        let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+        if elapsed > expected {
+            -(e / (1.0 + e)).log10()
+        } else {
+            -(1.0 - 1.0 / (1.0 + e)).log10()
+        }
+    }
+
+    /// Classify the peer at `now` against the configured thresholds.
+    #[must_use]
+    pub fn liveness(&self, now: Instant) -> PeerLiveness {
+        self.config.liveness(self.phi(now))
+    }
+
+    fn push_interval(&mut self, interval: f64) {
+        if self.intervals.len() >= self.config.max_sample_size.max(1)
+            && let Some(evicted) = self.intervals.pop_front()
+        {
+            self.interval_sum -= evicted;
+            self.squared_interval_sum -= evicted * evicted;
+        }
+        self.intervals.push_back(interval);
+        self.interval_sum += interval;
+        self.squared_interval_sum += interval * interval;
+    }
+}
+
+/// Liveness of every peer that has sent at least one heartbeat.
+#[derive(Debug)]
+pub(crate) struct PeerLivenessTracker {
+    config: PhiAccrualConfig,
+    peers: TrieMap<TrackedPeer>,
+}
+
+impl PeerLivenessTracker {
+    pub(crate) fn new(config: PhiAccrualConfig) -> Self {
+        Self {
+            config,
+            peers: TrieMap::new(),
+        }
+    }
+
+    /// Record one heartbeat from `peer`.
+    ///
+    /// Returns the transition to publish when the peer was unknown or not alive before.
+    pub(crate) fn record_heartbeat(
+        &mut self,
+        peer: &MemberIdentity,
+        now: Instant,
+    ) -> Option<PeerLivenessUpdate> {
+        if let Some(tracked) = self.peers.get_mut(peer) {
+            tracked.detector.heartbeat(now);
+            if tracked.liveness == PeerLiveness::Alive {
+                return None;
+            }
+            tracked.liveness = PeerLiveness::Alive;
+        } else {
+            let mut detector = PhiAccrualDetector::new(self.config);
+            detector.heartbeat(now);
+            self.peers.insert(
+                peer.clone(),
+                TrackedPeer {
+                    detector,
+                    liveness: PeerLiveness::Alive,
+                },
+            );
+        }
+        Some(PeerLivenessUpdate {
+            peer: peer.clone(),
+            liveness: PeerLiveness::Alive,
+        })
+    }
+
+    /// Re-evaluate every tracked peer at `now` and return the transitions to publish.
+    pub(crate) fn evaluate(&mut self, now: Instant) -> Vec<PeerLivenessUpdate> {
+        let peers: Vec<MemberIdentity> = self.peers.owned_keys().collect();
+        let mut updates = Vec::new();
+        for peer in peers {
+            let tracked = self
+                .peers
+                .get_mut(&peer)
+                .expect("peer keys were collected from the tracker");
+            let liveness = tracked.detector.liveness(now);
+            if liveness != tracked.liveness {
+                tracked.liveness = liveness;
+                updates.push(PeerLivenessUpdate { peer, liveness });
+            }
+        }
+        updates
+    }
+}
+
+/// Detector state and last published liveness of one peer.
+#[derive(Clone, Debug)]
+struct TrackedPeer {
+    detector: PhiAccrualDetector,
+    liveness: PeerLiveness,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(10);
+
+    fn config() -> PhiAccrualConfig {
+        PhiAccrualConfig {
+            first_heartbeat_estimate: INTERVAL,
+            ..PhiAccrualConfig::DEFAULT
+        }
+    }
+
+    #[test]
+    fn phi_rises_as_heartbeats_become_overdue() {
+        let start = Instant::now();
+        let mut detector = PhiAccrualDetector::new(config());
+        assert_eq!(detector.phi(start), 0.0);
+
+        for beat in 0..10 {
+            detector.heartbeat(start + INTERVAL * beat);
+        }
+        let last_heartbeat = start + INTERVAL * 9;
+
+        let on_time = detector.phi(last_heartbeat + INTERVAL);
+        let late = detector.phi(last_heartbeat + INTERVAL * 2);
+        let very_late = detector.phi(last_heartbeat + INTERVAL * 4);
+        assert!(on_time < 1.0, "on-time phi was {on_time}");
+        assert!(on_time < late && late < very_late);
+        assert_eq!(
+            detector.liveness(last_heartbeat + INTERVAL),
+            PeerLiveness::Alive
+        );
+        assert_eq!(
+            detector.liveness(last_heartbeat + INTERVAL * 4),
+            PeerLiveness::Down
+        );
+    }
+
+    #[test]
+    fn tracker_publishes_only_transitions() {
+        let start = Instant::now();
+        let peer = MemberIdentity::from_array(["test", "peer"]);
+        let mut tracker = PeerLivenessTracker::new(config());
+
+        assert_eq!(
+            tracker.record_heartbeat(&peer, start),
+            Some(PeerLivenessUpdate {
+                peer: peer.clone(),
+                liveness: PeerLiveness::Alive,
+            })
+        );
+        assert_eq!(tracker.record_heartbeat(&peer, start + INTERVAL), None);
+        assert!(tracker.evaluate(start + INTERVAL * 2).is_empty());
+
+        let silent_since = start + INTERVAL;
+        let suspected_at = (1..)
+            .map(|seconds| silent_since + Duration::from_secs(seconds))
+            .find(|now| {
+                tracker.peers.get(&peer).unwrap().detector.liveness(*now) != PeerLiveness::Alive
+            })
+            .unwrap();
+        assert_eq!(
+            tracker.evaluate(suspected_at),
+            vec![PeerLivenessUpdate {
+                peer: peer.clone(),
+                liveness: PeerLiveness::Suspected,
+            }]
+        );
+        assert_eq!(
+            tracker.evaluate(silent_since + INTERVAL * 10),
+            vec![PeerLivenessUpdate {
+                peer: peer.clone(),
+                liveness: PeerLiveness::Down,
+            }]
+        );
+        assert!(tracker.evaluate(silent_since + INTERVAL * 20).is_empty());
+
+        assert_eq!(
+            tracker.record_heartbeat(&peer, silent_since + INTERVAL * 20),
+            Some(PeerLivenessUpdate {
+                peer,
+                liveness: PeerLiveness::Alive,
+            })
+        );
+    }
+}
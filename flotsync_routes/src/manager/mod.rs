@@ -37,6 +37,7 @@ use super::{
 };
 #[cfg(any(test, feature = "test-support"))]
 use crate::test_support::ManagerOwnedUdpBindBudget;
+use crate::trace::{TraceCapture, TraceDirection};
 use flotsync_io::prelude::{
     IoBridgeHandle,
     IoPayload,
@@ -63,7 +64,7 @@ use flotsync_udpour::{
     UDPourSendFailureReason,
     UDPourSubmitResult,
 };
-use flotsync_utils::{OptionExt as _, kompact_config::ConfigReadExt as _};
+use flotsync_utils::{OptionExt as _, kompact_config::ConfigReadExt as _, option_when};
 use kompact::{config::UsizeValue, kompact_config, prelude::*};
 #[cfg(any(test, feature = "test-support"))]
 use std::sync::Mutex;
@@ -124,6 +125,8 @@ pub struct RouteTransportManager {
     tcp_routes: HashMap<TcpRouteKey, LiveTcpRouteHandle>,
     /// Logical sends that are still waiting for one route-transport outcome.
     pending_sends: HashMap<RouteSendId, PendingRouteSend>,
+    /// Optional `.flotrace` capture of every sent and delivered payload.
+    trace_capture: Option<TraceCapture>,
     /// Test-only declared budget for manager-owned lazy UDP binds.
     #[cfg(any(test, feature = "test-support"))]
     test_manager_owned_udp_bind_budget: Option<Arc<Mutex<ManagerOwnedUdpBindBudget>>>,
@@ -154,11 +157,19 @@ impl RouteTransportManager {
             udp_sockets: HashMap::new(),
             tcp_routes: HashMap::new(),
             pending_sends: HashMap::new(),
+            trace_capture: None,
             #[cfg(any(test, feature = "test-support"))]
             test_manager_owned_udp_bind_budget: None,
         }
     }
 
+    /// Start or stop recording sent and delivered payloads into a `.flotrace` capture.
+    ///
+    /// Replaces any previously configured capture.
+    pub fn set_trace_capture(&mut self, capture: Option<TraceCapture>) {
+        self.trace_capture = capture;
+    }
+
     /// Return the route-endpoint lifecycle port reference used by tests to inject endpoint state.
     #[cfg(test)]
     fn route_endpoint_lifecycle_port(&mut self) -> RequiredRef<RouteEndpointLifecyclePort> {
@@ -784,11 +795,12 @@ impl RouteTransportManager {
         route: UdpRouteKey,
         socket_key: UdpSocketKey,
     ) {
-        let Some((payload_source, coverage_key)) =
+        let Some((payload_source, coverage_key, traffic_class)) =
             self.pending_sends.get(&send_id).map(|pending| {
                 (
                     Arc::clone(&pending.send.payload),
                     pending.send.route.coverage_key,
+                    pending.send.traffic_class,
                 )
             })
         else {
@@ -819,17 +831,26 @@ impl RouteTransportManager {
                 return;
             }
         };
+        let traced_payload = option_when!(self.trace_capture.is_some(), payload.clone());
         let submit = runtime_ref.ask_with(|promise| {
             UDPourComponentMessage::Submit(Ask::new(
                 promise,
                 UDPourSend {
                     target: route.remote_addr,
                     payload,
+                    traffic_class,
                 },
             ))
         });
         match submit.await {
             Ok(UDPourSubmitResult::Sent) => {
+                if let Some(payload) = traced_payload {
+                    self.record_trace(
+                        TraceDirection::Sent,
+                        TransportRouteKey::Udp(route),
+                        &payload,
+                    );
+                }
                 self.complete_pending_send_success(send_id, coverage_key);
             }
             Ok(UDPourSubmitResult::SendFailed { reason }) => {
@@ -881,6 +902,27 @@ impl RouteTransportManager {
         let _ = promise.fulfil(RouteTransportSubmitResult::Sent { coverage_key });
     }
 
+    /// Append one payload to the configured trace capture, if any.
+    ///
+    /// A capture that fails to write is dropped so the trace never silently skips records.
+    fn record_trace(
+        &mut self,
+        direction: TraceDirection,
+        route: TransportRouteKey,
+        payload: &IoPayload,
+    ) {
+        let Some(capture) = &self.trace_capture else {
+            return;
+        };
+        if let Err(error) = capture.record(direction, route, payload) {
+            warn!(
+                self.log(),
+                "Stopping route-transport trace capture after write failure: {}", error
+            );
+            self.trace_capture = None;
+        }
+    }
+
     fn report_route_failed(&mut self, route: TransportRouteKey, reason: ConnectionFailureReason) {
         self.connection_info_port
             .trigger(ConnectionInfoIndication::ReportRouteFailed { route, reason });
@@ -902,6 +944,7 @@ impl RouteTransportManager {
             scope: DatagramRouteScope::Unicast,
             local_bind: Some(socket_key.local_addr),
         });
+        self.record_trace(TraceDirection::Received, route, &deliver.payload);
         self.inbound_port.trigger(RouteTransportInboundDeliver {
             payload: deliver.payload,
             transport: InboundTransportMeta {
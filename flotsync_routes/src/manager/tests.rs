@@ -5,7 +5,8 @@ use crate::{
     RoutePreferenceRank,
     RouteSharingKind,
     SendRouteCandidate,
-    test_support::{BoundReservedUdpSocket, TransportHarnessCore},
+    TrafficClass,
+    test_support::{BoundReservedUdpSocket, SharedTraceBuffer, TransportHarnessCore},
 };
 use bytes::Bytes;
 use flotsync_io::{
@@ -576,6 +577,85 @@ fn udp_manager_delivers_oversized_payload_over_real_udpour_socket() {
     );
 }
 
+#[test]
+fn udp_manager_traces_sent_and_delivered_payloads() {
+    let receiver_harness = UdpManagerHarness::with_external_socket(
+        UdpActivationPolicy::OnBind,
+        TestSendRateControl::default(),
+        udpour_config(),
+    );
+    let receiver_trace = SharedTraceBuffer::default();
+    let receiver_capture = TraceCapture::new(receiver_trace.clone()).expect("start receiver trace");
+    receiver_harness
+        .manager
+        .on_definition(|manager| manager.set_trace_capture(Some(receiver_capture)));
+    let (receiver_socket_id, receiver_addr) =
+        receiver_harness.bind_external_socket(UdpLocalBind::Exact(localhost(0)));
+    let receiver_socket_key = UdpSocketKey {
+        local_addr: receiver_addr,
+    };
+    receiver_harness.publish_route_endpoint_available(receiver_socket_id, receiver_addr);
+    receiver_harness.wait_for_live_udp_socket(receiver_socket_key);
+
+    let inbound_probe = receiver_harness
+        .core
+        .system()
+        .create(TransportRouteTransportPort::tester_component_sidecar);
+    let inbound_probe_ref = inbound_probe.actor_ref();
+    biconnect_components::<TransportRouteTransportPort, _, _>(
+        &receiver_harness.manager,
+        &inbound_probe,
+    )
+    .expect("connect receiver route transport probe");
+    start_component(receiver_harness.core.system(), &inbound_probe);
+
+    let sender_harness = UdpManagerHarness::with_socket_budgets(
+        0,
+        1,
+        UdpActivationPolicy::OnBind,
+        TestSendRateControl::default(),
+        udpour_config(),
+    );
+    let sender_trace = SharedTraceBuffer::default();
+    let sender_capture = TraceCapture::new(sender_trace.clone()).expect("start sender trace");
+    sender_harness
+        .manager
+        .on_definition(|manager| manager.set_trace_capture(Some(sender_capture)));
+    let payload = b"traced payload".to_vec();
+    let route = UdpRouteKey {
+        remote_addr: receiver_addr,
+        scope: DatagramRouteScope::Unicast,
+        local_bind: None,
+    };
+    let send_id = RouteSendId(Uuid::new_v4());
+    let submit = sender_harness.send_async(route_send(send_id, route, payload.clone()));
+    let (_sender_socket_id, sender_addr) = sender_harness.wait_for_new_bound_socket();
+    let delivery_future = inbound_probe_ref
+        .observe_indication(move |deliver| deliver.transport.remote_addr == Some(sender_addr));
+
+    assert_eq!(
+        UdpManagerHarness::wait_for_send_ack_future(submit),
+        TransportRouteKey::Udp(route)
+    );
+    let observed = delivery_future
+        .wait_timeout(WAIT_TIMEOUT)
+        .expect("timed out waiting for traced route-transport delivery")
+        .expect("route transport delivery probe should stay live");
+
+    let sent = sender_trace.load();
+    assert_eq!(sent.records().len(), 1);
+    assert_eq!(sent.records()[0].direction, TraceDirection::Sent);
+    assert_eq!(sent.records()[0].route, TransportRouteKey::Udp(route));
+    assert_eq!(sent.records()[0].payload.as_ref(), payload.as_slice());
+
+    let received = receiver_trace.load();
+    let replayed: Vec<_> = received.inbound_deliveries().collect();
+    assert_eq!(replayed.len(), 1);
+    let delivery = observed.indication();
+    assert_eq!(replayed[0].payload.to_vec(), delivery.payload.to_vec());
+    assert_eq!(replayed[0].transport, delivery.transport);
+}
+
 #[test]
 fn udp_manager_reuses_one_socket_for_two_loopback_targets() {
     let harness = UdpManagerHarness::with_socket_budgets(
@@ -890,6 +970,7 @@ fn route_send(
             preference_rank: RoutePreferenceRank::UNRANKED,
         },
         payload: Arc::new(BytesPayload(bytes)),
+        traffic_class: TrafficClass::Interactive,
     }
 }
 
@@ -0,0 +1,815 @@
+//! UDP hole punching through rendezvous relays.
+//!
+//! Peers on different home networks usually sit behind NATs, so neither the addresses they bind
+//! nor the addresses they announce locally can be probed from outside. A peer configured with
+//! rendezvous relays periodically sends each relay an `AddressObservationRequest`. The relay
+//! answers with the public source address it observed, much like a STUN binding response, and
+//! remembers the member at that address for a lease. Observed addresses are published as
+//! reflexive routes, which route establishment signs in introduction claims alongside the
+//! locally selected endpoints.
+//!
+//! For every local-group member without a published route, the peer asks its relays for a
+//! rendezvous. A relay that knows the target answers both sides with a `RendezvousIntroduction`
+//! naming the other side's observed address. Both sides then probe each other at roughly the same
+//! time, which opens the NAT mappings in both directions, and the usual signed introduction
+//! exchange verifies the route.
+//!
+//! Relays are only trusted for reachability hints. A wrong or forged hint costs one failed probe,
+//! but routes are still published only for members that prove themselves with a signed claim.
+
+use crate::{
+    DiscoveryRouteUpdate,
+    RouteDiscoveryPort,
+    RouteEndpointBinding,
+    RouteEndpointLifecycle,
+    RouteEndpointLifecyclePort,
+    RouteTransportActorMessage,
+    RouteTransportInboundDeliver,
+    RouteTransportPort,
+    TransportRouteKey,
+    config_keys,
+    endpoint_discovery::{
+        LocalUdpEndpointBinding,
+        LocalUdpEndpointState,
+        route_transport_inbound_source,
+        submit_endpoint_discovery_frame,
+    },
+    protocol::{
+        DecodedAddressObservation,
+        DecodedAddressObservationRequest,
+        DecodedRendezvousIntroduction,
+        DecodedRendezvousRequest,
+        DiscoveryEndpointFrameView,
+        DiscoveryRoute,
+        decode_endpoint_discovery_frame_from_buf,
+    },
+    route_establishment::is_concrete_advertised_route,
+};
+use flotsync_core::{
+    MemberIdentity,
+    member::{TrieMap, TrieSet},
+    membership::SharedGroupMemberships,
+};
+use flotsync_io::prelude::IoPayload;
+use flotsync_messages::{
+    discovery as discovery_proto,
+    proto::{DecodeProto as _, EncodeProto as _},
+};
+use flotsync_utils::option_when;
+use kompact::{
+    Never,
+    config::{Config, ConfigEntry, ConfigValueType},
+    prelude::*,
+};
+use snafu::Snafu;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    net::{AddrParseError, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// Maximum number of members a rendezvous relay remembers at once.
+///
+/// Registrations are unauthenticated, so the bound keeps a flood of fake member ids from growing
+/// relay memory without limit. Existing members can always refresh their registration.
+pub const MAX_RENDEZVOUS_REGISTRATIONS: usize = 4096;
+
+/// Configuration failures for NAT traversal.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum NatTraversalConfigError {
+    /// A Kompact config value could not be read.
+    #[snafu(display("could not load NAT traversal config {key}: {reason}"))]
+    ConfigurationFailed {
+        /// Config key that failed to load.
+        key: &'static str,
+        /// Human-readable config lookup failure.
+        reason: String,
+    },
+    /// A configured relay address could not be parsed.
+    #[snafu(display("rendezvous relay {value:?} is not a valid UDP socket address: {source}"))]
+    InvalidRelayAddress {
+        /// Configured relay value.
+        value: String,
+        /// Socket address parse failure.
+        source: AddrParseError,
+    },
+    /// A configured relay address is a bind instruction rather than a reachable route.
+    #[snafu(display(
+        "rendezvous relay {route} is not concrete; wildcard addresses and port 0 cannot be reached."
+    ))]
+    NonConcreteRelay { route: SocketAddr },
+}
+
+/// Runtime configuration for [`NatTraversalComponent`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NatTraversalConfig {
+    /// Relays this endpoint registers with and asks for rendezvous.
+    pub rendezvous_relays: BTreeSet<SocketAddr>,
+    /// Whether this endpoint acts as a rendezvous relay for other members.
+    pub serve_rendezvous: bool,
+}
+
+impl NatTraversalConfigError {
+    /// Return the config key whose value caused this error.
+    #[must_use]
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            Self::ConfigurationFailed { key, .. } => key,
+            Self::InvalidRelayAddress { .. } | Self::NonConcreteRelay { .. } => {
+                config_keys::NAT_TRAVERSAL_RENDEZVOUS_RELAYS.key
+            }
+        }
+    }
+}
+
+impl NatTraversalConfig {
+    /// Read NAT traversal settings from Kompact config.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NatTraversalConfigError`] when a value cannot be read or a relay address is not a
+    /// concrete UDP socket address.
+    pub fn from_config(config: &Config) -> Result<Self, NatTraversalConfigError> {
+        let relays = read_config(config, &config_keys::NAT_TRAVERSAL_RENDEZVOUS_RELAYS)?;
+        let mut rendezvous_relays = BTreeSet::new();
+        for value in relays {
+            let route = match value.parse::<SocketAddr>() {
+                Ok(route) => route,
+                Err(source) => {
+                    return Err(NatTraversalConfigError::InvalidRelayAddress { value, source });
+                }
+            };
+            if !is_concrete_advertised_route(route) {
+                return Err(NatTraversalConfigError::NonConcreteRelay { route });
+            }
+            rendezvous_relays.insert(route);
+        }
+        Ok(Self {
+            rendezvous_relays,
+            serve_rendezvous: read_config(config, &config_keys::NAT_TRAVERSAL_SERVE_RENDEZVOUS)?,
+        })
+    }
+}
+
+/// NAT traversal results consumed by route establishment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NatTraversalUpdate {
+    /// Replace the public addresses at which relays currently observe the local endpoint.
+    ReflexiveRoutes(BTreeSet<SocketAddr>),
+    /// Probe `route` now, because a relay introduced `member` there and it is punching towards us.
+    PunchCandidate {
+        /// Member the relay claims is reachable at `route`.
+        member: MemberIdentity,
+        /// Observed public address of `member`.
+        route: DiscoveryRoute,
+    },
+}
+
+/// Port publishing NAT traversal results into route establishment.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NatTraversalPort;
+
+impl Port for NatTraversalPort {
+    type Request = Never;
+    type Indication = NatTraversalUpdate;
+}
+
+/// Component implementing both the peer and the relay side of rendezvous hole punching.
+#[derive(ComponentDefinition)]
+pub struct NatTraversalComponent {
+    /// Kompact component context.
+    ctx: ComponentContext<Self>,
+    /// Reflexive routes and punch candidates for route establishment.
+    nat_traversal_port: ProvidedPort<NatTraversalPort>,
+    /// Published peer routes, used to find members that still need a rendezvous.
+    route_discovery_port: RequiredPort<RouteDiscoveryPort<TransportRouteKey>>,
+    /// Reassembled route-transport payloads sharing the runtime endpoint.
+    route_transport_port: RequiredPort<RouteTransportPort<TransportRouteKey>>,
+    /// Accepted route endpoint lifecycle from route transport.
+    route_endpoint_lifecycle_port: RequiredPort<RouteEndpointLifecyclePort>,
+    /// Actor interface used for endpoint-discovery sends.
+    route_transport: ActorRefStrong<RouteTransportActorMessage<TransportRouteKey>>,
+    /// Configured relays and relay role.
+    config: NatTraversalConfig,
+    /// Local member identity registered at relays.
+    local_member: MemberIdentity,
+    /// Shared local group membership snapshot naming the members worth punching towards.
+    group_memberships: SharedGroupMemberships,
+    /// Configured refresh and lease durations.
+    timing: NatTraversalTiming,
+    /// Runtime endpoint socket used for all NAT traversal traffic.
+    local_endpoint: LocalUdpEndpointState,
+    /// Unanswered observation request nonce per relay.
+    pending_observations: HashMap<SocketAddr, Uuid>,
+    /// Latest public address each relay observed for the local endpoint.
+    observed_routes: BTreeMap<SocketAddr, SocketAddr>,
+    /// Members with at least one currently published route.
+    routed_members: TrieSet,
+    /// Relay-side member registrations.
+    registrations: RendezvousRegistry,
+    /// Periodic refresh timer while the component runs.
+    refresh_timer: Option<ScheduledTimer>,
+}
+
+impl NatTraversalComponent {
+    /// Build one NAT traversal component.
+    #[must_use]
+    pub fn new(
+        config: NatTraversalConfig,
+        route_transport: ActorRefStrong<RouteTransportActorMessage<TransportRouteKey>>,
+        local_member: MemberIdentity,
+        group_memberships: SharedGroupMemberships,
+    ) -> Self {
+        Self {
+            ctx: ComponentContext::uninitialised(),
+            nat_traversal_port: ProvidedPort::uninitialised(),
+            route_discovery_port: RequiredPort::uninitialised(),
+            route_transport_port: RequiredPort::uninitialised(),
+            route_endpoint_lifecycle_port: RequiredPort::uninitialised(),
+            route_transport,
+            config,
+            local_member,
+            group_memberships,
+            timing: NatTraversalTiming::default(),
+            local_endpoint: LocalUdpEndpointState::Unbound,
+            pending_observations: HashMap::new(),
+            observed_routes: BTreeMap::new(),
+            routed_members: TrieSet::new(),
+            registrations: RendezvousRegistry::default(),
+            refresh_timer: None,
+        }
+    }
+
+    /// Return the route-endpoint lifecycle port reference used by tests to inject endpoint state.
+    #[cfg(test)]
+    pub(crate) fn route_endpoint_lifecycle_port(
+        &mut self,
+    ) -> RequiredRef<RouteEndpointLifecyclePort> {
+        self.route_endpoint_lifecycle_port.share()
+    }
+
+    /// Return the observed local UDP endpoint state without exposing writable internals.
+    #[cfg(test)]
+    pub(crate) fn local_endpoint(&self) -> LocalUdpEndpointState {
+        self.local_endpoint
+    }
+
+    /// Re-register at every relay and ask for rendezvous with every unreachable member.
+    async fn refresh(&mut self) {
+        let now = self.ctx.system().now();
+        self.registrations.prune(now);
+        let Some(endpoint) = self.local_endpoint.binding() else {
+            return;
+        };
+        // Relays that did not answer since the previous refresh no longer vouch for an address.
+        let unanswered: Vec<_> = self
+            .pending_observations
+            .drain()
+            .map(|(relay, _)| relay)
+            .collect();
+        let mut observations_changed = false;
+        for relay in unanswered {
+            observations_changed |= self.observed_routes.remove(&relay).is_some();
+        }
+        if observations_changed {
+            self.publish_reflexive_routes();
+        }
+
+        let relays: Vec<_> = self.config.rendezvous_relays.iter().copied().collect();
+        let targets = self.unreachable_members();
+        for relay in relays {
+            let request_nonce = Uuid::new_v4();
+            let frame = DiscoveryEndpointFrameView::AddressObservationRequest {
+                member: &self.local_member,
+                request_nonce,
+            }
+            .encode_proto();
+            if self
+                .submit_endpoint_frame(
+                    endpoint,
+                    relay,
+                    Arc::new(frame),
+                    "address observation request",
+                )
+                .await
+            {
+                self.pending_observations.insert(relay, request_nonce);
+            }
+            for target in &targets {
+                let frame = DiscoveryEndpointFrameView::RendezvousRequest {
+                    member: &self.local_member,
+                    target_member: target,
+                }
+                .encode_proto();
+                self.submit_endpoint_frame(endpoint, relay, Arc::new(frame), "rendezvous request")
+                    .await;
+            }
+        }
+    }
+
+    /// Return remote local-group members that currently have no published route.
+    fn unreachable_members(&self) -> Vec<MemberIdentity> {
+        let memberships = self.group_memberships.snapshot();
+        let mut members = TrieSet::new();
+        for group_id in memberships.group_ids() {
+            let Some(group_members) = memberships.members(group_id) else {
+                continue;
+            };
+            if !group_members.contains(&self.local_member) {
+                continue;
+            }
+            for member in group_members.iter() {
+                if member != self.local_member && !self.routed_members.contains(&member) {
+                    members.insert(member);
+                }
+            }
+        }
+        members.owned_keys().collect()
+    }
+
+    /// Return whether `member` shares at least one local group with the local member.
+    fn shares_local_group(&self, member: &MemberIdentity) -> bool {
+        let memberships = self.group_memberships.snapshot();
+        memberships.group_ids().any(|group_id| {
+            memberships.members(group_id).is_some_and(|members| {
+                members.contains(&self.local_member) && members.contains(member)
+            })
+        })
+    }
+
+    /// Submit one NAT traversal endpoint frame through route transport.
+    async fn submit_endpoint_frame(
+        &mut self,
+        endpoint: LocalUdpEndpointBinding,
+        target: SocketAddr,
+        payload: Arc<dyn flotsync_messages::serialisation::FlotsyncSerializable>,
+        label: &'static str,
+    ) -> bool {
+        submit_endpoint_discovery_frame(
+            &self.route_transport,
+            self.log(),
+            "NAT traversal",
+            endpoint,
+            target,
+            payload,
+            label,
+        )
+        .await
+    }
+
+    /// Publish the current set of relay-observed public addresses.
+    fn publish_reflexive_routes(&mut self) {
+        let routes = self.observed_routes.values().copied().collect();
+        self.nat_traversal_port
+            .trigger(NatTraversalUpdate::ReflexiveRoutes(routes));
+    }
+
+    /// Dispatch one route-transport payload that may contain a NAT traversal frame.
+    fn handle_route_transport_inbound(
+        &mut self,
+        inbound: &RouteTransportInboundDeliver<TransportRouteKey>,
+    ) -> HandlerResult {
+        let Some(source) = route_transport_inbound_source(inbound) else {
+            return Handled::OK;
+        };
+        self.handle_possible_endpoint_discovery_frame(source, &inbound.payload)
+    }
+
+    /// Try to interpret one shared-endpoint payload as a NAT traversal frame.
+    fn handle_possible_endpoint_discovery_frame(
+        &mut self,
+        source: SocketAddr,
+        payload: &IoPayload,
+    ) -> HandlerResult {
+        let mut cursor = payload.cursor();
+        let Some(discovery_frame) =
+            decode_endpoint_discovery_frame_from_buf(&mut cursor).benign_err()?
+        else {
+            return Handled::OK;
+        };
+        match discovery_frame.body {
+            Some(discovery_proto::discovery_frame::Body::AddressObservationRequest(request)) => {
+                let request =
+                    DecodedAddressObservationRequest::decode_proto(*request).benign_err()?;
+                Handled::block_on(self, async move |mut async_self| {
+                    async_self
+                        .answer_address_observation_request(source, request)
+                        .await;
+                    Handled::OK
+                })
+            }
+            Some(discovery_proto::discovery_frame::Body::AddressObservation(observation)) => {
+                let observation =
+                    DecodedAddressObservation::decode_proto(*observation).benign_err()?;
+                self.handle_address_observation(source, observation);
+                Handled::OK
+            }
+            Some(discovery_proto::discovery_frame::Body::RendezvousRequest(request)) => {
+                let request = DecodedRendezvousRequest::decode_proto(*request).benign_err()?;
+                Handled::block_on(self, async move |mut async_self| {
+                    async_self.answer_rendezvous_request(source, request).await;
+                    Handled::OK
+                })
+            }
+            Some(discovery_proto::discovery_frame::Body::RendezvousIntroduction(introduction)) => {
+                let introduction =
+                    DecodedRendezvousIntroduction::decode_proto(*introduction).benign_err()?;
+                self.handle_rendezvous_introduction(source, introduction);
+                Handled::OK
+            }
+            Some(
+                discovery_proto::discovery_frame::Body::IntroductionRequest(_)
+                | discovery_proto::discovery_frame::Body::Introduction(_)
+                | discovery_proto::discovery_frame::Body::KeyBundleLookupRequest(_)
+                | discovery_proto::discovery_frame::Body::KeyBundleLookupResponse(_),
+            )
+            | None => Handled::OK,
+        }
+    }
+
+    /// Register the sender and report the source address its request arrived from.
+    async fn answer_address_observation_request(
+        &mut self,
+        source: SocketAddr,
+        request: DecodedAddressObservationRequest,
+    ) {
+        if !self.config.serve_rendezvous {
+            trace!(
+                self.log(),
+                "ignored address observation request from {} because this endpoint is not a relay",
+                source
+            );
+            return;
+        }
+        let Some(endpoint) = self.local_endpoint.binding() else {
+            return;
+        };
+        let expires_at = self.ctx.system().now() + self.timing.registration_lease;
+        if !self
+            .registrations
+            .register(request.member.clone(), source, expires_at)
+        {
+            debug!(
+                self.log(),
+                "rendezvous relay is full and did not register {} at {}", request.member, source
+            );
+        }
+        let frame = DiscoveryEndpointFrameView::AddressObservation {
+            request_nonce: request.request_nonce,
+            observed_route: DiscoveryRoute::Udp(source),
+        }
+        .encode_proto();
+        self.submit_endpoint_frame(endpoint, source, Arc::new(frame), "address observation")
+            .await;
+    }
+
+    /// Record the public address a relay observed for the local endpoint.
+    fn handle_address_observation(
+        &mut self,
+        source: SocketAddr,
+        observation: DecodedAddressObservation,
+    ) {
+        if self.pending_observations.get(&source) != Some(&observation.request_nonce) {
+            trace!(
+                self.log(),
+                "ignored unsolicited address observation from {}", source
+            );
+            return;
+        }
+        self.pending_observations.remove(&source);
+        let DiscoveryRoute::Udp(observed) = observation.observed_route;
+        if !is_concrete_advertised_route(observed) {
+            debug!(
+                self.log(),
+                "ignored non-concrete address observation {} from relay {}", observed, source
+            );
+            return;
+        }
+        if self.observed_routes.insert(source, observed) != Some(observed) {
+            debug!(
+                self.log(),
+                "relay {} observed the local endpoint at {}", source, observed
+            );
+            self.publish_reflexive_routes();
+        }
+    }
+
+    /// Introduce the sender and its target to each other if the target is registered.
+    async fn answer_rendezvous_request(
+        &mut self,
+        source: SocketAddr,
+        request: DecodedRendezvousRequest,
+    ) {
+        if !self.config.serve_rendezvous {
+            return;
+        }
+        let Some(endpoint) = self.local_endpoint.binding() else {
+            return;
+        };
+        let now = self.ctx.system().now();
+        self.registrations.register(
+            request.member.clone(),
+            source,
+            now + self.timing.registration_lease,
+        );
+        let Some(target_route) = self.registrations.lookup(&request.target_member, now) else {
+            trace!(
+                self.log(),
+                "rendezvous relay does not know {} requested by {}",
+                request.target_member,
+                request.member
+            );
+            return;
+        };
+        if target_route == source {
+            return;
+        }
+        let to_requester = DiscoveryEndpointFrameView::RendezvousIntroduction {
+            member: &request.target_member,
+            route: DiscoveryRoute::Udp(target_route),
+        }
+        .encode_proto();
+        let to_target = DiscoveryEndpointFrameView::RendezvousIntroduction {
+            member: &request.member,
+            route: DiscoveryRoute::Udp(source),
+        }
+        .encode_proto();
+        self.submit_endpoint_frame(
+            endpoint,
+            source,
+            Arc::new(to_requester),
+            "rendezvous introduction",
+        )
+        .await;
+        self.submit_endpoint_frame(
+            endpoint,
+            target_route,
+            Arc::new(to_target),
+            "rendezvous introduction",
+        )
+        .await;
+    }
+
+    /// Ask route establishment to punch towards a member a configured relay introduced.
+    fn handle_rendezvous_introduction(
+        &mut self,
+        source: SocketAddr,
+        introduction: DecodedRendezvousIntroduction,
+    ) {
+        if !self.config.rendezvous_relays.contains(&source) {
+            trace!(
+                self.log(),
+                "ignored rendezvous introduction from unconfigured relay {}", source
+            );
+            return;
+        }
+        if introduction.member == self.local_member
+            || !self.shares_local_group(&introduction.member)
+        {
+            trace!(
+                self.log(),
+                "ignored rendezvous introduction for {} outside local groups", introduction.member
+            );
+            return;
+        }
+        debug!(
+            self.log(),
+            "relay {} introduced {} at {:?}", source, introduction.member, introduction.route
+        );
+        self.nat_traversal_port
+            .trigger(NatTraversalUpdate::PunchCandidate {
+                member: introduction.member,
+                route: introduction.route,
+            });
+    }
+
+    /// Track which members route establishment currently publishes routes for.
+    fn handle_route_update(&mut self, update: DiscoveryRouteUpdate<TransportRouteKey>) {
+        match update {
+            DiscoveryRouteUpdate::PeerRoutes { peer, routes } => {
+                if routes.is_empty() {
+                    self.routed_members.remove(&peer);
+                } else {
+                    self.routed_members.insert(peer);
+                }
+            }
+            DiscoveryRouteUpdate::RelayRoutes { .. } => {}
+        }
+    }
+
+    /// Record the endpoint authorised for NAT traversal traffic and refresh immediately.
+    fn handle_route_endpoint_available(&mut self, binding: RouteEndpointBinding) -> HandlerResult {
+        let binding = LocalUdpEndpointBinding {
+            socket_id: binding.socket_id,
+            local_addr: binding.socket_bound_addr,
+        };
+        if self.local_endpoint.binding() == Some(binding) {
+            return Handled::OK;
+        }
+        self.local_endpoint = LocalUdpEndpointState::Bound(binding);
+        self.reset_observations();
+        Handled::block_on(self, async move |mut async_self| {
+            async_self.refresh().await;
+            Handled::OK
+        })
+    }
+
+    /// Clear endpoint state when the exact authorised endpoint is withdrawn.
+    fn handle_route_endpoint_unavailable(&mut self, binding: RouteEndpointBinding) {
+        let binding = LocalUdpEndpointBinding {
+            socket_id: binding.socket_id,
+            local_addr: binding.socket_bound_addr,
+        };
+        if self.local_endpoint.binding() != Some(binding) {
+            return;
+        }
+        self.local_endpoint = LocalUdpEndpointState::Unbound;
+        self.reset_observations();
+    }
+
+    /// Forget observations made for a previous endpoint, which maps to a different public address.
+    fn reset_observations(&mut self) {
+        self.pending_observations.clear();
+        if !self.observed_routes.is_empty() {
+            self.observed_routes.clear();
+            self.publish_reflexive_routes();
+        }
+    }
+
+    /// Load NAT traversal timing from Kompact config.
+    fn load_timing_from_config(&self) -> Result<NatTraversalTiming, NatTraversalConfigError> {
+        let config = self.ctx.config();
+        Ok(NatTraversalTiming {
+            refresh_interval: read_config(config, &config_keys::NAT_TRAVERSAL_REFRESH_INTERVAL)?,
+            registration_lease: read_config(
+                config,
+                &config_keys::NAT_TRAVERSAL_REGISTRATION_LEASE,
+            )?,
+        })
+    }
+}
+
+impl ComponentLifecycle for NatTraversalComponent {
+    fn on_start(&mut self) -> HandlerResult {
+        self.timing = self.load_timing_from_config().unrecoverable_err()?;
+        let interval = self.timing.refresh_interval;
+        self.refresh_timer =
+            Some(
+                self.schedule_periodic(interval, interval, |component, _timer| {
+                    Handled::block_on(component, async move |mut async_self| {
+                        async_self.refresh().await;
+                        Handled::OK
+                    })
+                }),
+            );
+        Handled::OK
+    }
+
+    fn on_stop(&mut self) -> HandlerResult {
+        if let Some(timer) = self.refresh_timer.take() {
+            self.cancel_timer(timer);
+        }
+        Handled::OK
+    }
+
+    fn on_kill(&mut self) -> HandlerResult {
+        self.on_stop()
+    }
+}
+
+ignore_requests!(NatTraversalPort, NatTraversalComponent);
+
+impl Require<RouteDiscoveryPort<TransportRouteKey>> for NatTraversalComponent {
+    fn handle(&mut self, indication: DiscoveryRouteUpdate<TransportRouteKey>) -> HandlerResult {
+        self.handle_route_update(indication);
+        Handled::OK
+    }
+}
+
+impl Require<RouteTransportPort<TransportRouteKey>> for NatTraversalComponent {
+    fn handle(
+        &mut self,
+        indication: RouteTransportInboundDeliver<TransportRouteKey>,
+    ) -> HandlerResult {
+        self.handle_route_transport_inbound(&indication)
+    }
+}
+
+impl Require<RouteEndpointLifecyclePort> for NatTraversalComponent {
+    fn handle(&mut self, indication: RouteEndpointLifecycle) -> HandlerResult {
+        match indication {
+            RouteEndpointLifecycle::Available(binding) => {
+                self.handle_route_endpoint_available(binding)
+            }
+            RouteEndpointLifecycle::Unavailable { binding, .. } => {
+                self.handle_route_endpoint_unavailable(binding);
+                Handled::OK
+            }
+        }
+    }
+}
+
+impl Actor for NatTraversalComponent {
+    type Message = Never;
+
+    fn receive_local(&mut self, msg: Self::Message) -> HandlerResult {
+        match msg {}
+    }
+}
+
+/// Relay-side record of where registered members were last observed.
+#[derive(Default)]
+struct RendezvousRegistry {
+    registrations: TrieMap<Registration>,
+}
+
+impl RendezvousRegistry {
+    /// Record `member` at `route` until `expires_at`.
+    ///
+    /// Returns `false` without registering when the registry is full and `member` is new.
+    fn register(&mut self, member: MemberIdentity, route: SocketAddr, expires_at: Instant) -> bool {
+        if let Some(registration) = self.registrations.get_mut(&member) {
+            *registration = Registration { route, expires_at };
+            return true;
+        }
+        if self.registrations.len() >= MAX_RENDEZVOUS_REGISTRATIONS {
+            return false;
+        }
+        self.registrations
+            .insert(member, Registration { route, expires_at });
+        true
+    }
+
+    /// Return the route `member` was last observed at, unless that registration expired.
+    fn lookup(&self, member: &MemberIdentity, now: Instant) -> Option<SocketAddr> {
+        self.registrations.get(member).and_then(|registration| {
+            option_when!(registration.expires_at > now, registration.route)
+        })
+    }
+
+    /// Drop every registration that expired at or before `now`.
+    fn prune(&mut self, now: Instant) {
+        let expired: Vec<_> = self
+            .registrations
+            .owned_entries()
+            .filter_map(|(member, registration)| {
+                option_when!(registration.expires_at <= now, member)
+            })
+            .collect();
+        for member in expired {
+            self.registrations.remove(&member);
+        }
+    }
+}
+
+/// One member registration at a rendezvous relay.
+#[derive(Clone, Copy, Debug)]
+struct Registration {
+    /// Public address the member's latest registration arrived from.
+    route: SocketAddr,
+    /// When the relay stops introducing the member at `route`.
+    expires_at: Instant,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct NatTraversalTiming {
+    /// Interval between relay registrations and rendezvous requests.
+    refresh_interval: Duration,
+    /// Relay-side lifetime of one member registration.
+    registration_lease: Duration,
+}
+
+impl Default for NatTraversalTiming {
+    fn default() -> Self {
+        Self {
+            refresh_interval: config_keys::NAT_TRAVERSAL_REFRESH_INTERVAL
+                .default()
+                .expect("NAT traversal refresh interval has a default"),
+            registration_lease: config_keys::NAT_TRAVERSAL_REGISTRATION_LEASE
+                .default()
+                .expect("NAT traversal registration lease has a default"),
+        }
+    }
+}
+
+/// Read one Kompact config value, falling back to its declared default when unset.
+fn read_config<T>(
+    config: &Config,
+    key: &ConfigEntry<T>,
+) -> Result<T::Value, NatTraversalConfigError>
+where
+    T: ConfigValueType,
+{
+    config
+        .read_or_default(key)
+        .map_err(|error| NatTraversalConfigError::ConfigurationFailed {
+            key: key.key,
+            reason: error.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests;
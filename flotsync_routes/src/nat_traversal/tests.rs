@@ -0,0 +1,585 @@
+//! NAT traversal component tests.
+
+use super::*;
+use crate::{
+    DatagramRouteScope,
+    InboundTransportMeta,
+    RoutePreferenceRank,
+    RouteSharingKind,
+    RouteTransportSend,
+    SendRouteCandidate,
+    UdpRouteKey,
+    test_support::{
+        RouteTransportRecorderComponent,
+        TestRouteTransportPort,
+        assert_udp_transport_route,
+        encode_transport_payload,
+        endpoint_payload,
+        member,
+    },
+};
+use flotsync_core::membership::{GroupMembers, GroupMemberships};
+use flotsync_io::{
+    prelude::SocketId,
+    test_support::{
+        build_test_kompact_system_with,
+        eventually_component_state,
+        kill_component,
+        start_component,
+    },
+};
+use flotsync_messages::discovery::discovery_frame;
+use flotsync_utils::kompact_testing::{PortTesterComponent, PortTestingExt, PortTestingRefExt};
+use std::{cell::Cell, sync::mpsc};
+
+/// Concrete route-discovery test port used to report published peer routes.
+type TestRouteDiscoveryPort = RouteDiscoveryPort<TransportRouteKey>;
+
+fn loopback(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+fn memberships(members: impl IntoIterator<Item = MemberIdentity>) -> SharedGroupMemberships {
+    let members = GroupMembers::from_ordered_members(members).expect("group members should build");
+    SharedGroupMemberships::new(GroupMemberships::from_groups([(
+        flotsync_core::GroupId(Uuid::from_u128(1)),
+        members,
+    )]))
+}
+
+fn relay_config() -> NatTraversalConfig {
+    NatTraversalConfig {
+        serve_rendezvous: true,
+        ..NatTraversalConfig::default()
+    }
+}
+
+fn peer_config(relay: SocketAddr) -> NatTraversalConfig {
+    NatTraversalConfig {
+        rendezvous_relays: BTreeSet::from([relay]),
+        ..NatTraversalConfig::default()
+    }
+}
+
+/// Decode the discovery frame body carried by one route-transport submit.
+fn decode_submitted_body(send: &RouteTransportSend<TransportRouteKey>) -> discovery_frame::Body {
+    let payload = encode_transport_payload(&send.payload);
+    let mut cursor = payload.cursor();
+    decode_endpoint_discovery_frame_from_buf(&mut cursor)
+        .expect("NAT traversal payload should decode")
+        .expect("NAT traversal payload should be a discovery frame")
+        .body
+        .expect("NAT traversal frame should carry a body")
+}
+
+/// Wait until NAT traversal tracks `expected` members with published routes.
+fn eventually_routed(harness: &NatTraversalHarness, expected: usize) {
+    eventually_component_state(
+        Duration::from_secs(1),
+        &harness.component,
+        |component| component.routed_members.len() == expected,
+        "published peer routes should reach NAT traversal",
+    );
+}
+
+/// Owns the NAT traversal test topology.
+struct NatTraversalHarness {
+    /// Kompact system owning the test topology.
+    system: KompactSystem,
+    /// Recorder acknowledging and capturing route-transport submits.
+    route_transport: Arc<Component<RouteTransportRecorderComponent>>,
+    /// Submits captured by the route-transport recorder.
+    route_transport_rx: mpsc::Receiver<RouteTransportSend<TransportRouteKey>>,
+    /// Port tester injecting inbound route-transport payloads.
+    inbound_transport: Arc<Component<PortTesterComponent<TestRouteTransportPort>>>,
+    /// Port tester injecting published peer routes.
+    route_discovery: Arc<Component<PortTesterComponent<TestRouteDiscoveryPort>>>,
+    /// Port tester observing NAT traversal updates.
+    update_probe: Arc<Component<PortTesterComponent<NatTraversalPort>>>,
+    /// Cursor into the NAT traversal update log.
+    update_cursor: Cell<usize>,
+    /// NAT traversal component under test.
+    component: Arc<Component<NatTraversalComponent>>,
+}
+
+impl NatTraversalHarness {
+    fn new(
+        config: NatTraversalConfig,
+        local_member: MemberIdentity,
+        group_memberships: SharedGroupMemberships,
+    ) -> Self {
+        let system = build_test_kompact_system_with(|_| {});
+        let (route_transport_tx, route_transport_rx) = mpsc::channel();
+        let route_transport =
+            system.create(move || RouteTransportRecorderComponent::new(route_transport_tx));
+        let route_transport_ref = route_transport
+            .actor_ref()
+            .hold()
+            .expect("route transport recorder must expose a strong actor ref");
+        let inbound_transport = system.create(TestRouteTransportPort::tester_component_sidecar);
+        let route_discovery = system.create(TestRouteDiscoveryPort::tester_component_sidecar);
+        let update_probe = system.create(NatTraversalPort::tester_component_sidecar);
+        let component = system.create(move || {
+            NatTraversalComponent::new(config, route_transport_ref, local_member, group_memberships)
+        });
+        biconnect_components::<TestRouteTransportPort, _, _>(&inbound_transport, &component)
+            .expect("connect route transport probe");
+        biconnect_components::<TestRouteDiscoveryPort, _, _>(&route_discovery, &component)
+            .expect("connect route discovery probe");
+        biconnect_components::<NatTraversalPort, _, _>(&component, &update_probe)
+            .expect("connect NAT traversal probe");
+
+        start_component(&system, &route_transport);
+        start_component(&system, &inbound_transport);
+        start_component(&system, &route_discovery);
+        start_component(&system, &update_probe);
+        start_component(&system, &component);
+
+        Self {
+            system,
+            route_transport,
+            route_transport_rx,
+            inbound_transport,
+            route_discovery,
+            update_probe,
+            update_cursor: Cell::new(0),
+            component,
+        }
+    }
+
+    fn bind_endpoint(&self, socket_id: SocketId, local_addr: SocketAddr) {
+        let route_endpoint_lifecycle_port = self
+            .component
+            .on_definition(NatTraversalComponent::route_endpoint_lifecycle_port);
+        self.system.trigger_i(
+            RouteEndpointLifecycle::Available(RouteEndpointBinding {
+                socket_id,
+                socket_bound_addr: local_addr,
+            }),
+            &route_endpoint_lifecycle_port,
+        );
+    }
+
+    fn publish_peer_route(&self, peer: MemberIdentity, route: SocketAddr) {
+        self.route_discovery
+            .actor_ref()
+            .inject_indication(DiscoveryRouteUpdate::PeerRoutes {
+                peer,
+                routes: vec![SendRouteCandidate {
+                    coverage_key: TransportRouteKey::Udp(UdpRouteKey {
+                        remote_addr: route,
+                        scope: DatagramRouteScope::Unicast,
+                        local_bind: None,
+                    }),
+                    sharing: RouteSharingKind::Exclusive,
+                    preference_rank: RoutePreferenceRank::new(1),
+                }],
+            });
+    }
+
+    fn receive_frame(&self, source: SocketAddr, frame: DiscoveryEndpointFrameView<'_>) {
+        let payload = endpoint_payload(&frame.encode_proto());
+        let local_bind = self
+            .component
+            .on_definition(|component| component.local_endpoint().binding())
+            .map(|endpoint| endpoint.local_addr);
+        self.inbound_transport
+            .actor_ref()
+            .inject_indication(RouteTransportInboundDeliver {
+                payload,
+                transport: InboundTransportMeta {
+                    route: TransportRouteKey::Udp(UdpRouteKey {
+                        remote_addr: source,
+                        scope: DatagramRouteScope::Unicast,
+                        local_bind,
+                    }),
+                    remote_addr: Some(source),
+                },
+            });
+    }
+
+    /// Receive the next submit, assert its route, and return its decoded frame body.
+    fn expect_submit(&self, local_bind: SocketAddr, target: SocketAddr) -> discovery_frame::Body {
+        let send = self
+            .route_transport_rx
+            .recv_timeout(Duration::from_secs(1))
+            .expect("route transport submit should be observed");
+        assert_udp_transport_route(&send, local_bind, target);
+        decode_submitted_body(&send)
+    }
+
+    fn expect_no_submit(&self, reason: &'static str) {
+        match self
+            .route_transport_rx
+            .recv_timeout(Duration::from_millis(100))
+        {
+            Ok(submit) => panic!("{reason}: unexpected submit {submit:?}"),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                panic!("{reason}: route transport recorder disconnected");
+            }
+        }
+    }
+
+    /// Expect an observation request to `relay` and return its nonce.
+    fn expect_observation_request(
+        &self,
+        local_bind: SocketAddr,
+        relay: SocketAddr,
+        expected_member: &MemberIdentity,
+    ) -> Uuid {
+        match self.expect_submit(local_bind, relay) {
+            discovery_frame::Body::AddressObservationRequest(request) => {
+                let request = DecodedAddressObservationRequest::decode_proto(*request)
+                    .expect("observation request should decode");
+                assert_eq!(&request.member, expected_member);
+                request.request_nonce
+            }
+            other => panic!("expected address observation request, got {other:?}"),
+        }
+    }
+
+    fn expect_update(&self, expected: &NatTraversalUpdate) {
+        let observed = self
+            .update_probe
+            .actor_ref()
+            .observe_indication_from(self.update_cursor.get(), |_| true)
+            .wait_timeout(Duration::from_secs(1))
+            .expect("NAT traversal update should be observed")
+            .expect("NAT traversal probe should stay live");
+        self.update_cursor.set(observed.index() + 1);
+        assert_eq!(observed.indication(), expected);
+    }
+
+    fn expect_no_update(&self, reason: &'static str) {
+        self.update_probe
+            .actor_ref()
+            .fail_if_indication_observed_from(
+                self.update_cursor.get(),
+                Duration::from_millis(100),
+                |_| true,
+            )
+            .wait_timeout(Duration::from_secs(1))
+            .expect("NAT traversal update absence check should complete")
+            .expect("NAT traversal probe should stay live")
+            .expect(reason);
+    }
+
+    fn shutdown(self) {
+        let Self {
+            system,
+            route_transport,
+            route_transport_rx: _,
+            inbound_transport,
+            route_discovery,
+            update_probe,
+            update_cursor: _,
+            component,
+        } = self;
+        kill_component(&system, component);
+        kill_component(&system, update_probe);
+        kill_component(&system, route_discovery);
+        kill_component(&system, inbound_transport);
+        kill_component(&system, route_transport);
+        system.shutdown().wait().expect("Kompact shutdown");
+    }
+}
+
+#[test]
+fn config_reads_rendezvous_relays() {
+    let relay = SocketAddr::from(([198, 51, 100, 4], 45_000));
+    let system = build_test_kompact_system_with(|config| {
+        config.set_config_value(
+            &config_keys::NAT_TRAVERSAL_RENDEZVOUS_RELAYS,
+            vec![relay.to_string()],
+        );
+        config.set_config_value(&config_keys::NAT_TRAVERSAL_SERVE_RENDEZVOUS, true);
+    });
+
+    let config = NatTraversalConfig::from_config(system.config()).expect("config should load");
+
+    assert_eq!(
+        config,
+        NatTraversalConfig {
+            rendezvous_relays: BTreeSet::from([relay]),
+            serve_rendezvous: true,
+        }
+    );
+    system.shutdown().wait().expect("Kompact shutdown");
+}
+
+#[test]
+fn config_rejects_unreachable_rendezvous_relays() {
+    for (value, expect_parse_error) in [("not-an-address", true), ("0.0.0.0:45000", false)] {
+        let system = build_test_kompact_system_with(|config| {
+            config.set_config_value(
+                &config_keys::NAT_TRAVERSAL_RENDEZVOUS_RELAYS,
+                vec![value.to_owned()],
+            );
+        });
+
+        let result = NatTraversalConfig::from_config(system.config());
+
+        match result {
+            Err(NatTraversalConfigError::InvalidRelayAddress { .. }) => {
+                assert!(expect_parse_error, "{value} should parse");
+            }
+            Err(NatTraversalConfigError::NonConcreteRelay { .. }) => {
+                assert!(!expect_parse_error, "{value} should fail to parse");
+            }
+            other => panic!("expected relay rejection for {value}, got {other:?}"),
+        }
+        system.shutdown().wait().expect("Kompact shutdown");
+    }
+}
+
+#[test]
+fn relay_answers_address_observation_with_observed_source() {
+    let bob = member(["bob"]);
+    let relay_endpoint = loopback(47_000);
+    let bob_public = SocketAddr::from(([203, 0, 113, 9], 51_000));
+    let request_nonce = Uuid::from_u128(1);
+    let harness = NatTraversalHarness::new(relay_config(), member(["relay"]), memberships([]));
+
+    harness.bind_endpoint(SocketId(1), relay_endpoint);
+    harness.receive_frame(
+        bob_public,
+        DiscoveryEndpointFrameView::AddressObservationRequest {
+            member: &bob,
+            request_nonce,
+        },
+    );
+
+    match harness.expect_submit(relay_endpoint, bob_public) {
+        discovery_frame::Body::AddressObservation(observation) => {
+            let observation = DecodedAddressObservation::decode_proto(*observation)
+                .expect("observation should decode");
+            assert_eq!(observation.request_nonce, request_nonce);
+            assert_eq!(observation.observed_route, DiscoveryRoute::Udp(bob_public));
+        }
+        other => panic!("expected address observation, got {other:?}"),
+    }
+    harness.shutdown();
+}
+
+#[test]
+fn non_relay_ignores_address_observation_requests() {
+    let harness = NatTraversalHarness::new(
+        NatTraversalConfig::default(),
+        member(["alice"]),
+        memberships([]),
+    );
+
+    harness.bind_endpoint(SocketId(2), loopback(47_001));
+    harness.receive_frame(
+        loopback(51_001),
+        DiscoveryEndpointFrameView::AddressObservationRequest {
+            member: &member(["bob"]),
+            request_nonce: Uuid::from_u128(2),
+        },
+    );
+
+    harness.expect_no_submit("only relays answer address observation requests");
+    harness.shutdown();
+}
+
+#[test]
+fn relay_introduces_registered_members_to_each_other() {
+    let alice = member(["alice"]);
+    let bob = member(["bob"]);
+    let relay_endpoint = loopback(47_002);
+    let alice_public = SocketAddr::from(([203, 0, 113, 1], 51_002));
+    let bob_public = SocketAddr::from(([198, 51, 100, 2], 51_003));
+    let harness = NatTraversalHarness::new(relay_config(), member(["relay"]), memberships([]));
+
+    harness.bind_endpoint(SocketId(3), relay_endpoint);
+    harness.receive_frame(
+        bob_public,
+        DiscoveryEndpointFrameView::AddressObservationRequest {
+            member: &bob,
+            request_nonce: Uuid::from_u128(3),
+        },
+    );
+    harness.expect_submit(relay_endpoint, bob_public);
+    harness.receive_frame(
+        alice_public,
+        DiscoveryEndpointFrameView::RendezvousRequest {
+            member: &alice,
+            target_member: &bob,
+        },
+    );
+
+    for (target, introduced_member, introduced_route) in [
+        (alice_public, &bob, bob_public),
+        (bob_public, &alice, alice_public),
+    ] {
+        match harness.expect_submit(relay_endpoint, target) {
+            discovery_frame::Body::RendezvousIntroduction(introduction) => {
+                let introduction = DecodedRendezvousIntroduction::decode_proto(*introduction)
+                    .expect("introduction should decode");
+                assert_eq!(&introduction.member, introduced_member);
+                assert_eq!(introduction.route, DiscoveryRoute::Udp(introduced_route));
+            }
+            other => panic!("expected rendezvous introduction, got {other:?}"),
+        }
+    }
+    harness.shutdown();
+}
+
+#[test]
+fn relay_ignores_rendezvous_for_unregistered_member() {
+    let harness = NatTraversalHarness::new(relay_config(), member(["relay"]), memberships([]));
+
+    harness.bind_endpoint(SocketId(4), loopback(47_003));
+    harness.receive_frame(
+        loopback(51_004),
+        DiscoveryEndpointFrameView::RendezvousRequest {
+            member: &member(["alice"]),
+            target_member: &member(["bob"]),
+        },
+    );
+
+    harness.expect_no_submit("unknown rendezvous targets must not produce introductions");
+    harness.shutdown();
+}
+
+#[test]
+fn peer_registers_and_requests_rendezvous_for_unrouted_members() {
+    let alice = member(["alice"]);
+    let bob = member(["bob"]);
+    let carol = member(["carol"]);
+    let local_endpoint = loopback(47_004);
+    let relay = loopback(47_100);
+    let harness = NatTraversalHarness::new(
+        peer_config(relay),
+        alice.clone(),
+        memberships([alice.clone(), bob.clone(), carol.clone()]),
+    );
+
+    harness.publish_peer_route(carol, loopback(51_005));
+    eventually_routed(&harness, 1);
+    harness.bind_endpoint(SocketId(5), local_endpoint);
+
+    harness.expect_observation_request(local_endpoint, relay, &alice);
+    match harness.expect_submit(local_endpoint, relay) {
+        discovery_frame::Body::RendezvousRequest(request) => {
+            let request =
+                DecodedRendezvousRequest::decode_proto(*request).expect("request should decode");
+            assert_eq!(request.member, alice);
+            assert_eq!(request.target_member, bob);
+        }
+        other => panic!("expected rendezvous request, got {other:?}"),
+    }
+    harness.expect_no_submit("routed members must not be requested");
+    harness.shutdown();
+}
+
+#[test]
+fn peer_publishes_reflexive_route_only_for_solicited_observation() {
+    let alice = member(["alice"]);
+    let local_endpoint = loopback(47_005);
+    let relay = loopback(47_101);
+    let public_route = SocketAddr::from(([203, 0, 113, 5], 51_006));
+    let harness = NatTraversalHarness::new(
+        peer_config(relay),
+        alice.clone(),
+        memberships([alice.clone()]),
+    );
+
+    harness.bind_endpoint(SocketId(6), local_endpoint);
+    let request_nonce = harness.expect_observation_request(local_endpoint, relay, &alice);
+    harness.receive_frame(
+        relay,
+        DiscoveryEndpointFrameView::AddressObservation {
+            request_nonce: Uuid::from_u128(99),
+            observed_route: DiscoveryRoute::Udp(public_route),
+        },
+    );
+    harness.receive_frame(
+        loopback(47_102),
+        DiscoveryEndpointFrameView::AddressObservation {
+            request_nonce,
+            observed_route: DiscoveryRoute::Udp(public_route),
+        },
+    );
+    harness.expect_no_update("observations need the request nonce and a configured relay");
+
+    harness.receive_frame(
+        relay,
+        DiscoveryEndpointFrameView::AddressObservation {
+            request_nonce,
+            observed_route: DiscoveryRoute::Udp(public_route),
+        },
+    );
+
+    harness.expect_update(&NatTraversalUpdate::ReflexiveRoutes(BTreeSet::from([
+        public_route,
+    ])));
+    harness.shutdown();
+}
+
+#[test]
+fn peer_publishes_punch_candidate_only_for_relay_introduced_group_member() {
+    let alice = member(["alice"]);
+    let bob = member(["bob"]);
+    let mallory = member(["mallory"]);
+    let relay = loopback(47_103);
+    let bob_public = SocketAddr::from(([198, 51, 100, 7], 51_007));
+    let harness = NatTraversalHarness::new(
+        peer_config(relay),
+        alice.clone(),
+        memberships([alice, bob.clone()]),
+    );
+
+    harness.receive_frame(
+        loopback(47_104),
+        DiscoveryEndpointFrameView::RendezvousIntroduction {
+            member: &bob,
+            route: DiscoveryRoute::Udp(bob_public),
+        },
+    );
+    harness.receive_frame(
+        relay,
+        DiscoveryEndpointFrameView::RendezvousIntroduction {
+            member: &mallory,
+            route: DiscoveryRoute::Udp(bob_public),
+        },
+    );
+    harness.expect_no_update("introductions need a configured relay and a local-group member");
+
+    harness.receive_frame(
+        relay,
+        DiscoveryEndpointFrameView::RendezvousIntroduction {
+            member: &bob,
+            route: DiscoveryRoute::Udp(bob_public),
+        },
+    );
+
+    harness.expect_update(&NatTraversalUpdate::PunchCandidate {
+        member: bob,
+        route: DiscoveryRoute::Udp(bob_public),
+    });
+    harness.shutdown();
+}
+
+#[test]
+fn rendezvous_registry_expires_and_caps_registrations() {
+    let now = Instant::now();
+    let lease = Duration::from_secs(60);
+    let mut registry = RendezvousRegistry::default();
+
+    assert!(registry.register(member(["alice"]), loopback(1), now + lease));
+    assert_eq!(registry.lookup(&member(["alice"]), now), Some(loopback(1)));
+    assert_eq!(registry.lookup(&member(["alice"]), now + lease), None);
+
+    for index in 1..MAX_RENDEZVOUS_REGISTRATIONS {
+        let name = format!("member-{index}");
+        assert!(registry.register(member([name.as_str()]), loopback(2), now + lease));
+    }
+    assert!(!registry.register(member(["overflow"]), loopback(3), now + lease));
+    assert!(registry.register(member(["alice"]), loopback(4), now + lease));
+
+    registry.prune(now + lease);
+    assert!(registry.registrations.get(&member(["alice"])).is_none());
+    assert!(registry.register(member(["overflow"]), loopback(3), now + lease * 2));
+}
@@ -6,13 +6,18 @@ use flotsync_discovery::protocol::{DiscoveryProtocolError, discovery_protocol_er
 use flotsync_messages::{
     buffa::{self, Message, MessageField, MessageView},
     discovery::{
+        AddressObservation,
+        AddressObservationRequest,
         DiscoveryFrame,
+        Identifier,
         Introduction,
         IntroductionClaimPayload,
         IntroductionClaimPayloadView,
         IntroductionRequest,
         KeyBundleLookupRequest,
         KeyBundleLookupResponsePayload as KeyBundleLookupResponsePayloadProto,
+        RendezvousIntroduction,
+        RendezvousRequest,
         SignedKeyBundleLookupResponse,
         discovery_frame,
     },
@@ -108,6 +113,138 @@ impl DecodeProto for DecodedKeyBundleLookupRequest {
     }
 }
 
+/// Decoded address observation request sent by a member to a rendezvous relay.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedAddressObservationRequest {
+    /// Member identity the sender registers under at the relay.
+    pub member: MemberIdentity,
+    /// Sender-generated challenge to echo in the observation.
+    pub request_nonce: Uuid,
+}
+
+impl DecodeProto for DecodedAddressObservationRequest {
+    type Error = DiscoveryProtocolError;
+    type Proto = AddressObservationRequest;
+
+    fn decode_proto(mut request: Self::Proto) -> Result<Self, Self::Error> {
+        let member = member_from_wire(
+            request.member_id.take(),
+            "AddressObservationRequest",
+            "member_id",
+            "AddressObservationRequest.member_id",
+        )?;
+        let request_nonce = uuid_from_wire_bytes(
+            &request.request_nonce,
+            "AddressObservationRequest.request_nonce",
+        )
+        .context(discovery_protocol_error::InvalidWireValueSnafu)?;
+        Ok(Self {
+            member,
+            request_nonce,
+        })
+    }
+}
+
+/// Decoded relay report of the source address it observed for one request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedAddressObservation {
+    /// Challenge echoed from the request.
+    pub request_nonce: Uuid,
+    /// Source address of the request as seen by the relay.
+    pub observed_route: DiscoveryRoute,
+}
+
+impl DecodeProto for DecodedAddressObservation {
+    type Error = DiscoveryProtocolError;
+    type Proto = AddressObservation;
+
+    fn decode_proto(mut observation: Self::Proto) -> Result<Self, Self::Error> {
+        let request_nonce = uuid_from_wire_bytes(
+            &observation.request_nonce,
+            "AddressObservation.request_nonce",
+        )
+        .context(discovery_protocol_error::InvalidWireValueSnafu)?;
+        let observed_route = observation.observed_route.take().context(
+            discovery_protocol_error::MissingFieldSnafu {
+                message: "AddressObservation",
+                field: "observed_route",
+            },
+        )?;
+        Ok(Self {
+            request_nonce,
+            observed_route: DiscoveryRoute::decode_proto(observed_route)?,
+        })
+    }
+}
+
+/// Decoded request for a rendezvous relay to introduce two members.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedRendezvousRequest {
+    /// Member identity of the sender.
+    pub member: MemberIdentity,
+    /// Member identity the sender wants a direct route to.
+    pub target_member: MemberIdentity,
+}
+
+impl DecodeProto for DecodedRendezvousRequest {
+    type Error = DiscoveryProtocolError;
+    type Proto = RendezvousRequest;
+
+    fn decode_proto(mut request: Self::Proto) -> Result<Self, Self::Error> {
+        let member = member_from_wire(
+            request.member_id.take(),
+            "RendezvousRequest",
+            "member_id",
+            "RendezvousRequest.member_id",
+        )?;
+        let target_member = member_from_wire(
+            request.target_member_id.take(),
+            "RendezvousRequest",
+            "target_member_id",
+            "RendezvousRequest.target_member_id",
+        )?;
+        Ok(Self {
+            member,
+            target_member,
+        })
+    }
+}
+
+/// Decoded relay notice that a member was observed at a route and is punching towards us.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedRendezvousIntroduction {
+    /// Member identity the relay observed. Unverified until the member answers a probe.
+    pub member: MemberIdentity,
+    /// Observed public address of `member`.
+    pub route: DiscoveryRoute,
+}
+
+impl DecodeProto for DecodedRendezvousIntroduction {
+    type Error = DiscoveryProtocolError;
+    type Proto = RendezvousIntroduction;
+
+    fn decode_proto(mut introduction: Self::Proto) -> Result<Self, Self::Error> {
+        let member = member_from_wire(
+            introduction.member_id.take(),
+            "RendezvousIntroduction",
+            "member_id",
+            "RendezvousIntroduction.member_id",
+        )?;
+        let route =
+            introduction
+                .route
+                .take()
+                .context(discovery_protocol_error::MissingFieldSnafu {
+                    message: "RendezvousIntroduction",
+                    field: "route",
+                })?;
+        Ok(Self {
+            member,
+            route: DiscoveryRoute::decode_proto(route)?,
+        })
+    }
+}
+
 /// Direct key-bundle lookup response payload.
 #[derive(Clone, Debug, PartialEq, Eq, View)]
 pub struct KeyBundleLookupResponsePayload {
@@ -451,6 +588,41 @@ pub enum DiscoveryEndpointFrame {
         /// Signed response to wrap in the endpoint envelope.
         response: SignedKeyBundleLookupResponse,
     },
+    /// Address observation and registration request sent to a rendezvous relay.
+    AddressObservationRequest {
+        /// Member identity to register at the relay.
+        member: MemberIdentity,
+        /// Request freshness challenge that the observation must echo.
+        #[borrowize(borrowed_type = "Uuid", generation_expression = "*request_nonce")]
+        request_nonce: Uuid,
+    },
+    /// Relay report of the observed source address of one request.
+    AddressObservation {
+        /// Challenge echoed from the request.
+        #[borrowize(borrowed_type = "Uuid", generation_expression = "*request_nonce")]
+        request_nonce: Uuid,
+        /// Source address of the request as seen by the relay.
+        #[borrowize(
+            borrowed_type = "DiscoveryRoute",
+            generation_expression = "*observed_route"
+        )]
+        observed_route: DiscoveryRoute,
+    },
+    /// Request for a relay to introduce the sender to `target_member`.
+    RendezvousRequest {
+        /// Member identity of the sender.
+        member: MemberIdentity,
+        /// Member identity the sender wants a direct route to.
+        target_member: MemberIdentity,
+    },
+    /// Relay notice that `member` was observed at `route`.
+    RendezvousIntroduction {
+        /// Member identity observed at `route`.
+        member: MemberIdentity,
+        /// Observed public address of `member`.
+        #[borrowize(borrowed_type = "DiscoveryRoute", generation_expression = "*route")]
+        route: DiscoveryRoute,
+    },
 }
 
 impl EncodeProto for DiscoveryEndpointFrame {
@@ -492,6 +664,39 @@ impl EncodeProto for DiscoveryEndpointFrameView<'_> {
             Self::KeyBundleLookupResponse { response } => {
                 discovery_frame::Body::KeyBundleLookupResponse(Box::new((*response).clone()))
             }
+            Self::AddressObservationRequest {
+                member,
+                request_nonce,
+            } => discovery_frame::Body::AddressObservationRequest(Box::new(
+                AddressObservationRequest {
+                    member_id: MessageField::some(member_identity_to_wire_format(member)),
+                    request_nonce: uuid_to_wire_bytes(*request_nonce),
+                    ..AddressObservationRequest::default()
+                },
+            )),
+            Self::AddressObservation {
+                request_nonce,
+                observed_route,
+            } => discovery_frame::Body::AddressObservation(Box::new(AddressObservation {
+                request_nonce: uuid_to_wire_bytes(*request_nonce),
+                observed_route: MessageField::some(observed_route.encode_proto()),
+                ..AddressObservation::default()
+            })),
+            Self::RendezvousRequest {
+                member,
+                target_member,
+            } => discovery_frame::Body::RendezvousRequest(Box::new(RendezvousRequest {
+                member_id: MessageField::some(member_identity_to_wire_format(member)),
+                target_member_id: MessageField::some(member_identity_to_wire_format(target_member)),
+                ..RendezvousRequest::default()
+            })),
+            Self::RendezvousIntroduction { member, route } => {
+                discovery_frame::Body::RendezvousIntroduction(Box::new(RendezvousIntroduction {
+                    member_id: MessageField::some(member_identity_to_wire_format(member)),
+                    route: MessageField::some(route.encode_proto()),
+                    ..RendezvousIntroduction::default()
+                }))
+            }
         };
         let discovery = DiscoveryFrame {
             body: Some(body),
@@ -601,6 +806,19 @@ fn discovery_endpoint_frame(discovery: DiscoveryFrame) -> EndpointFrame {
     }
 }
 
+/// Decode one required member identity field from a discovery message.
+fn member_from_wire(
+    member_id: Option<Identifier>,
+    message: &'static str,
+    field: &'static str,
+    path: &'static str,
+) -> Result<MemberIdentity, DiscoveryProtocolError> {
+    let member_id =
+        member_id.context(discovery_protocol_error::MissingFieldSnafu { message, field })?;
+    member_identity_from_wire_format(member_id, path)
+        .context(discovery_protocol_error::InvalidMemberIdentitySnafu { field: path })
+}
+
 fn group_id_from_wire(
     bytes: &[u8],
     field: &'static str,
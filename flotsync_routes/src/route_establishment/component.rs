@@ -24,6 +24,8 @@ use crate::{
         submit_endpoint_discovery_frame,
     },
     key_material_discovery::{FetchKeyMaterial, KeyMaterialDiscoveryPort},
+    liveness::{PeerLivenessPort, PeerLivenessTracker, PhiAccrualConfig},
+    nat_traversal::{NatTraversalPort, NatTraversalUpdate},
     protocol::{
         DecodedIntroductionClaimPayload,
         DiscoveryEndpointFrameView,
@@ -39,6 +41,7 @@ use flotsync_core::{
     membership::{GroupMemberships, SharedGroupMemberships},
 };
 use flotsync_discovery::{
+    capabilities::CapabilitySet,
     endpoint_selection::{EndpointSelection, EndpointSelectionPort},
     protocol::DiscoveryRoute,
     services::{PeerAnnouncementObservationPort, PeerAnnouncementObserved},
@@ -47,12 +50,15 @@ use flotsync_io::prelude::IoPayload;
 use flotsync_messages::{
     buffa::{Message as _, MessageField},
     discovery as discovery_proto,
-    proto::{DecodeProtoView, EncodeProto},
+    proto::{DecodeProto, DecodeProtoView, EncodeProto},
     serialisation::FlotsyncSerializable,
     wire::{group_id_to_wire_bytes, uuid_from_wire_bytes, uuid_to_wire_bytes},
 };
 use flotsync_utils::{BoxError, option_when};
-use kompact::prelude::*;
+use kompact::{
+    config::{ConfigEntry, ConfigValueType},
+    prelude::*,
+};
 use snafu::{ResultExt, Snafu};
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
@@ -99,6 +105,16 @@ enum RouteEstablishmentStartupError {
         /// Human-readable config lookup failure.
         reason: String,
     },
+    /// The configured down threshold would report peers as down before suspecting them.
+    #[snafu(display(
+        "peer liveness down threshold {down_threshold} is below the suspect threshold {suspect_threshold}"
+    ))]
+    InvalidPeerLivenessThresholds {
+        /// Configured suspect threshold.
+        suspect_threshold: f64,
+        /// Configured down threshold.
+        down_threshold: f64,
+    },
 }
 
 /// Failures after a claim signature has already verified.
@@ -127,6 +143,8 @@ pub struct RouteEstablishmentComponent {
     ctx: ComponentContext<Self>,
     /// Route source port where verified peer routes are published.
     discovery_port: ProvidedPort<RouteDiscoveryPort<TransportRouteKey>>,
+    /// Liveness transitions of peers whose routes were verified at least once.
+    liveness_port: ProvidedPort<PeerLivenessPort>,
     /// Key-material fetch request path for direct key-material discovery.
     key_material_discovery_port: RequiredPort<KeyMaterialDiscoveryPort>,
     /// Peer-announcement input from one or more announcement protocols.
@@ -139,10 +157,14 @@ pub struct RouteEstablishmentComponent {
     route_transport: ActorRefStrong<RouteTransportActorMessage<TransportRouteKey>>,
     /// Receives selected local endpoints used for signed introduction claims.
     endpoint_selection_port: RequiredPort<EndpointSelectionPort>,
+    /// Receives relay-observed public routes and hole-punching candidates.
+    nat_traversal_port: RequiredPort<NatTraversalPort>,
     /// Static runtime settings for this discovery source.
     config: super::RouteEstablishmentConfig,
     /// Concrete local routes that this component may sign in introduction claims.
     claim_routes: ConcreteRoutes,
+    /// Public routes at which rendezvous relays observe the local endpoint, also signed in claims.
+    reflexive_routes: BTreeSet<SocketAddr>,
     /// Local member identity whose keys sign outgoing introduction claims.
     local_member: MemberIdentity,
     /// Discovery claim signing and verification provider.
@@ -159,6 +181,10 @@ pub struct RouteEstablishmentComponent {
     manual_route_watch_routes: HashSet<DiscoveryRoute>,
     /// Last published route snapshot, rebuilt from route verification state.
     member_route_snapshots: TrieMap<BTreeSet<SocketAddr>>,
+    /// Failure detectors fed by successful route verifications.
+    peer_liveness: PeerLivenessTracker,
+    /// Periodic timer re-evaluating peer liveness while the component runs.
+    liveness_check_timer: Option<ScheduledTimer>,
 }
 
 impl RouteEstablishmentComponent {
@@ -175,14 +201,17 @@ impl RouteEstablishmentComponent {
         Self {
             ctx: ComponentContext::uninitialised(),
             discovery_port: ProvidedPort::uninitialised(),
+            liveness_port: ProvidedPort::uninitialised(),
             key_material_discovery_port: RequiredPort::uninitialised(),
             announcement_port: RequiredPort::uninitialised(),
             route_transport_port: RequiredPort::uninitialised(),
             route_endpoint_lifecycle_port: RequiredPort::uninitialised(),
             route_transport,
             endpoint_selection_port: RequiredPort::uninitialised(),
+            nat_traversal_port: RequiredPort::uninitialised(),
             config,
             claim_routes,
+            reflexive_routes: BTreeSet::new(),
             local_member,
             credentials,
             group_memberships,
@@ -191,9 +220,21 @@ impl RouteEstablishmentComponent {
             route_state: HashMap::new(),
             manual_route_watch_routes: HashSet::new(),
             member_route_snapshots: TrieMap::new(),
+            peer_liveness: PeerLivenessTracker::new(PhiAccrualConfig::DEFAULT),
+            liveness_check_timer: None,
         }
     }
 
+    /// Return the capabilities negotiated with the peer verified at `route`.
+    ///
+    /// Returns `None` while `route` is not reachable.
+    #[must_use]
+    pub fn negotiated_capabilities(&self, route: SocketAddr) -> Option<CapabilitySet> {
+        self.route_state
+            .get(&DiscoveryRoute::Udp(route))
+            .and_then(|route_state| route_state.verification.negotiated_capabilities())
+    }
+
     /// Return the observed local UDP endpoint state without exposing writable internals.
     #[cfg(test)]
     pub(super) fn local_endpoint(&self) -> LocalUdpEndpointState {
@@ -206,6 +247,12 @@ impl RouteEstablishmentComponent {
         self.claim_routes.routes()
     }
 
+    /// Return the relay-observed public routes currently signed in introduction claims.
+    #[cfg(test)]
+    pub(super) fn reflexive_routes(&self) -> &BTreeSet<SocketAddr> {
+        &self.reflexive_routes
+    }
+
     /// Return the endpoint-selection port reference used by tests to inject selected endpoints.
     #[cfg(test)]
     pub(super) fn endpoint_selection_port(&mut self) -> RequiredRef<EndpointSelectionPort> {
@@ -220,6 +267,12 @@ impl RouteEstablishmentComponent {
         self.route_endpoint_lifecycle_port.share()
     }
 
+    /// Return the NAT traversal port reference used by tests to inject relay results.
+    #[cfg(test)]
+    pub(super) fn nat_traversal_port(&mut self) -> RequiredRef<NatTraversalPort> {
+        self.nat_traversal_port.share()
+    }
+
     /// Load route-establishment timing from Kompact config.
     ///
     /// # Errors
@@ -228,29 +281,52 @@ impl RouteEstablishmentComponent {
     fn load_timing_from_config(
         &self,
     ) -> Result<RouteEstablishmentTiming, RouteEstablishmentStartupError> {
-        let probe_timeout = self
-            .ctx
-            .config()
-            .read_or_default(&config_keys::ROUTE_ESTABLISHMENT_PROBE_TIMEOUT)
-            .map_err(
-                |error| RouteEstablishmentStartupError::ConfigurationFailed {
-                    key: config_keys::ROUTE_ESTABLISHMENT_PROBE_TIMEOUT.key,
-                    reason: error.to_string(),
-                },
-            )?;
-        let reachable_lease = self
-            .ctx
-            .config()
-            .read_or_default(&config_keys::ROUTE_ESTABLISHMENT_REACHABLE_LEASE)
-            .map_err(
-                |error| RouteEstablishmentStartupError::ConfigurationFailed {
-                    key: config_keys::ROUTE_ESTABLISHMENT_REACHABLE_LEASE.key,
-                    reason: error.to_string(),
+        let reachable_lease =
+            self.read_config(&config_keys::ROUTE_ESTABLISHMENT_REACHABLE_LEASE)?;
+        let liveness = PhiAccrualConfig {
+            suspect_threshold: self.read_config(&config_keys::PEER_LIVENESS_SUSPECT_THRESHOLD)?,
+            down_threshold: self.read_config(&config_keys::PEER_LIVENESS_DOWN_THRESHOLD)?,
+            min_std_deviation: self.read_config(&config_keys::PEER_LIVENESS_MIN_STD_DEVIATION)?,
+            acceptable_heartbeat_pause: self
+                .read_config(&config_keys::PEER_LIVENESS_ACCEPTABLE_HEARTBEAT_PAUSE)?,
+            // Heartbeats are route refreshes, which happen once per reachable lease.
+            first_heartbeat_estimate: reachable_lease,
+            ..PhiAccrualConfig::DEFAULT
+        };
+        if liveness.down_threshold < liveness.suspect_threshold {
+            return Err(
+                RouteEstablishmentStartupError::InvalidPeerLivenessThresholds {
+                    suspect_threshold: liveness.suspect_threshold,
+                    down_threshold: liveness.down_threshold,
                 },
-            )?;
+            );
+        }
         Ok(RouteEstablishmentTiming {
-            probe_timeout,
+            probe_timeout: self.read_config(&config_keys::ROUTE_ESTABLISHMENT_PROBE_TIMEOUT)?,
             reachable_lease,
+            liveness_check_interval: self
+                .read_config(&config_keys::PEER_LIVENESS_CHECK_INTERVAL)?,
+            liveness,
+        })
+    }
+
+    /// Read one Kompact config value, falling back to its declared default when unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RouteEstablishmentStartupError`] when the configured value cannot be read.
+    fn read_config<T>(
+        &self,
+        key: &ConfigEntry<T>,
+    ) -> Result<T::Value, RouteEstablishmentStartupError>
+    where
+        T: ConfigValueType,
+    {
+        self.ctx.config().read_or_default(key).map_err(|error| {
+            RouteEstablishmentStartupError::ConfigurationFailed {
+                key: key.key,
+                reason: error.to_string(),
+            }
         })
     }
 
@@ -343,6 +419,24 @@ impl RouteEstablishmentComponent {
         probes
     }
 
+    /// Record a relay-introduced member at `route` and return whether the route should be probed.
+    pub(super) fn record_punch_candidate(
+        &mut self,
+        member: MemberIdentity,
+        route: DiscoveryRoute,
+    ) -> bool {
+        let route_state = self
+            .route_state
+            .entry(route)
+            .or_insert(WatchedRouteState::NEW);
+        route_state
+            .interest
+            .rendezvous
+            .try_add_expected_member(route, Some(member))
+            .expect("rendezvous interest only ever names expected members");
+        !route_state.has_active_timeout()
+    }
+
     /// Replace the manual route watches and return routes that should be probed.
     fn replace_manual_route_watches(
         &mut self,
@@ -565,6 +659,18 @@ impl RouteEstablishmentComponent {
                 );
                 Handled::OK
             }
+            Some(
+                discovery_proto::discovery_frame::Body::AddressObservationRequest(_)
+                | discovery_proto::discovery_frame::Body::AddressObservation(_)
+                | discovery_proto::discovery_frame::Body::RendezvousRequest(_)
+                | discovery_proto::discovery_frame::Body::RendezvousIntroduction(_),
+            ) => {
+                trace!(
+                    self.log(),
+                    "route establishment ignored NAT traversal frame from {}", source
+                );
+                Handled::OK
+            }
             None => {
                 debug!(
                     self.log(),
@@ -625,7 +731,7 @@ impl RouteEstablishmentComponent {
             );
             return Handled::OK;
         }
-        if self.claim_routes.is_empty() {
+        if self.claim_routes.is_empty() && self.reflexive_routes.is_empty() {
             trace!(
                 self.log(),
                 "ignored introduction request from {} because there are no routes to advertise",
@@ -645,9 +751,15 @@ impl RouteEstablishmentComponent {
             &self.local_member,
             self.credentials.local_discovery_key_fingerprint(),
         );
-        let mut claims = Vec::with_capacity(self.claim_routes.len());
-        for route in self.claim_routes.iter() {
-            let route = DiscoveryRoute::Udp(*route).encode_proto();
+        let claim_routes = self
+            .claim_routes
+            .iter()
+            .chain(&self.reflexive_routes)
+            .copied()
+            .collect::<BTreeSet<_>>();
+        let mut claims = Vec::with_capacity(claim_routes.len());
+        for route in claim_routes {
+            let route = DiscoveryRoute::Udp(route).encode_proto();
             let claim_payload = discovery_proto::IntroductionClaimPayload {
                 instance_uuid: instance_uuid.clone(),
                 request_nonce: request_nonce.clone(),
@@ -678,6 +790,7 @@ impl RouteEstablishmentComponent {
             instance_uuid,
             request_nonce,
             claims,
+            capabilities: MessageField::some(self.config.capabilities.encode_proto()),
             ..discovery_proto::Introduction::default()
         };
         let frame = DiscoveryEndpointFrameView::Introduction {
@@ -732,7 +845,8 @@ impl RouteEstablishmentComponent {
             self.mark_route_stale(prepared.route);
             return;
         }
-        self.mark_route_reachable(prepared.route, accepted_members);
+        let capabilities = self.config.capabilities.negotiate(&prepared.capabilities);
+        self.mark_route_reachable(prepared.route, accepted_members, capabilities);
     }
 
     /// Return the claim member when one prepared claim verifies and is publishable.
@@ -843,7 +957,7 @@ impl RouteEstablishmentComponent {
     fn collect_verifiable_claims_for_active_probe(
         &self,
         source: SocketAddr,
-        introduction: discovery_proto::Introduction,
+        mut introduction: discovery_proto::Introduction,
     ) -> Option<super::state::PartiallyVerifiedIntroduction> {
         let route = DiscoveryRoute::Udp(source);
         let Some(route_state) = self.route_state.get(&route) else {
@@ -886,6 +1000,24 @@ impl RouteEstablishmentComponent {
             return None;
         }
 
+        let capabilities = match introduction
+            .capabilities
+            .take()
+            .map_or(Ok(CapabilitySet::BASELINE), CapabilitySet::decode_proto)
+        {
+            Ok(capabilities) => capabilities,
+            Err(error) => {
+                // Capabilities only unlock optional features, so a peer that garbles them is still
+                // reachable with the baseline.
+                debug!(
+                    self.log(),
+                    "assumed baseline capabilities for introduction from {} with malformed capabilities: {}",
+                    source,
+                    error
+                );
+                CapabilitySet::BASELINE
+            }
+        };
         let claims = introduction
             .claims
             .into_iter()
@@ -908,7 +1040,11 @@ impl RouteEstablishmentComponent {
                 }
             })
             .collect();
-        Some(super::state::PartiallyVerifiedIntroduction { route, claims })
+        Some(super::state::PartiallyVerifiedIntroduction {
+            route,
+            claims,
+            capabilities,
+        })
     }
 
     /// Request one direct key-material fetch.
@@ -930,6 +1066,7 @@ impl RouteEstablishmentComponent {
         &mut self,
         route: DiscoveryRoute,
         accepted_members: TrieSet,
+        capabilities: CapabilitySet,
     ) {
         if !self.route_state.contains_key(&route) {
             return;
@@ -937,16 +1074,37 @@ impl RouteEstablishmentComponent {
         let timer = self.schedule_once(self.timing.reachable_lease, move |component, timeout| {
             component.handle_reachable_lease_expired(route, &timeout)
         });
+        let now = self.ctx.system().now();
+        let liveness_updates: Vec<_> = accepted_members
+            .owned_keys()
+            .filter_map(|member| self.peer_liveness.record_heartbeat(&member, now))
+            .collect();
         let timer_to_cancel = self
             .route_state
             .get_mut(&route)
             .expect("reachable route was checked before scheduling")
             .verification
-            .mark_reachable(timer, accepted_members);
+            .mark_reachable(timer, accepted_members, capabilities);
         if let Some(timer) = timer_to_cancel {
             self.cancel_timer(timer);
         }
         self.rebuild_published_member_routes();
+        for update in liveness_updates {
+            self.liveness_port.trigger(update);
+        }
+    }
+
+    /// Re-evaluate peer failure detectors and publish any liveness transitions.
+    fn check_peer_liveness(&mut self) -> HandlerResult {
+        let now = self.ctx.system().now();
+        for update in self.peer_liveness.evaluate(now) {
+            debug!(
+                self.log(),
+                "peer {} liveness changed to {:?}", update.peer, update.liveness
+            );
+            self.liveness_port.trigger(update);
+        }
+        Handled::OK
     }
 
     /// Expire one reachable lease if it still owns the active timer, then schedule a refresh probe.
@@ -1081,14 +1239,33 @@ impl RouteEstablishmentComponent {
 impl ComponentLifecycle for RouteEstablishmentComponent {
     fn on_start(&mut self) -> HandlerResult {
         self.timing = self.load_timing_from_config().unrecoverable_err()?;
+        self.peer_liveness = PeerLivenessTracker::new(self.timing.liveness);
+        let interval = self.timing.liveness_check_interval;
+        self.liveness_check_timer = Some(self.schedule_periodic(
+            interval,
+            interval,
+            |component, _timer| component.check_peer_liveness(),
+        ));
         Handled::OK
     }
+
+    fn on_stop(&mut self) -> HandlerResult {
+        if let Some(timer) = self.liveness_check_timer.take() {
+            self.cancel_timer(timer);
+        }
+        Handled::OK
+    }
+
+    fn on_kill(&mut self) -> HandlerResult {
+        self.on_stop()
+    }
 }
 
 ignore_requests!(
     RouteDiscoveryPort<TransportRouteKey>,
     RouteEstablishmentComponent
 );
+ignore_requests!(PeerLivenessPort, RouteEstablishmentComponent);
 ignore_indications!(KeyMaterialDiscoveryPort, RouteEstablishmentComponent);
 
 impl Require<RouteTransportPort<TransportRouteKey>> for RouteEstablishmentComponent {
@@ -1136,6 +1313,26 @@ impl Require<EndpointSelectionPort> for RouteEstablishmentComponent {
     }
 }
 
+impl Require<NatTraversalPort> for RouteEstablishmentComponent {
+    fn handle(&mut self, indication: NatTraversalUpdate) -> HandlerResult {
+        match indication {
+            NatTraversalUpdate::ReflexiveRoutes(routes) => {
+                self.reflexive_routes = routes;
+                Handled::OK
+            }
+            NatTraversalUpdate::PunchCandidate { member, route } => {
+                if !self.record_punch_candidate(member, route) {
+                    return Handled::OK;
+                }
+                Handled::block_on(self, async move |mut async_self| {
+                    async_self.send_introduction_request(route).await;
+                    Handled::OK
+                })
+            }
+        }
+    }
+}
+
 impl Actor for RouteEstablishmentComponent {
     type Message = RouteEstablishmentMessage;
 
@@ -1261,6 +1458,10 @@ struct RouteEstablishmentTiming {
     probe_timeout: Duration,
     /// Time for which a verified route remains published before refresh.
     reachable_lease: Duration,
+    /// Interval between peer liveness evaluations.
+    liveness_check_interval: Duration,
+    /// Failure detector tuning for peers heard from through verified routes.
+    liveness: PhiAccrualConfig,
 }
 
 impl Default for RouteEstablishmentTiming {
@@ -1272,6 +1473,10 @@ impl Default for RouteEstablishmentTiming {
             reachable_lease: config_keys::ROUTE_ESTABLISHMENT_REACHABLE_LEASE
                 .default()
                 .expect("route establishment reachable lease has a default"),
+            liveness_check_interval: config_keys::PEER_LIVENESS_CHECK_INTERVAL
+                .default()
+                .expect("peer liveness check interval has a default"),
+            liveness: PhiAccrualConfig::DEFAULT,
         }
     }
 }
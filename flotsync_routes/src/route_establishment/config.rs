@@ -1,6 +1,6 @@
 //! Route-establishment configuration and advertised-route validation.
 
-use flotsync_discovery::DEFAULT_DISCOVERY_PORT;
+use flotsync_discovery::{DEFAULT_DISCOVERY_PORT, capabilities::CapabilitySet};
 use snafu::Snafu;
 use std::{
     collections::BTreeSet,
@@ -29,6 +29,8 @@ pub struct RouteEstablishmentConfig {
     pub peer_announcement_bind_addr: SocketAddr,
     /// Local process instance id used in outgoing peer announcements and introductions.
     pub instance_id: Uuid,
+    /// Capabilities advertised in outgoing introductions and negotiated with verified peers.
+    pub capabilities: CapabilitySet,
     /// Initial concrete routes this endpoint is allowed to claim in signed introductions.
     advertised_routes: ConcreteRoutes,
 }
@@ -43,6 +45,7 @@ impl RouteEstablishmentConfig {
                 *DEFAULT_DISCOVERY_PORT,
             ),
             instance_id: Uuid::new_v4(),
+            capabilities: CapabilitySet::CURRENT,
             advertised_routes: ConcreteRoutes::default(),
         }
     }
@@ -136,6 +139,6 @@ impl ConcreteRoutes {
 }
 
 /// Returns whether `route` can be advertised as a remotely reachable endpoint.
-fn is_concrete_advertised_route(route: SocketAddr) -> bool {
+pub(crate) fn is_concrete_advertised_route(route: SocketAddr) -> bool {
     route.port() != 0 && !route.ip().is_unspecified()
 }
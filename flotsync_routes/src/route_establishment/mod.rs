@@ -14,6 +14,8 @@ pub use config::{ConcreteRoutes, RouteEstablishmentConfig, RouteEstablishmentCon
 pub use state::{ManualRouteWatchError, WatchedRoute};
 pub use wire::RouteEstablishmentError;
 
+pub(crate) use config::is_concrete_advertised_route;
+
 /// Future returned by asynchronous discovery credential verification.
 pub type DiscoveryCredentialFuture<'a> = BoxFuture<'a, Result<(), BoxError>>;
 
@@ -1,7 +1,7 @@
 //! Mutable route-establishment state and manual watch reconciliation.
 
 use flotsync_core::{MemberIdentity, member::TrieSet};
-use flotsync_discovery::protocol::DiscoveryRoute;
+use flotsync_discovery::{capabilities::CapabilitySet, protocol::DiscoveryRoute};
 use flotsync_messages::discovery as discovery_proto;
 use flotsync_security::{FrameSignature, KeyFingerprint};
 use kompact::prelude::ScheduledTimer;
@@ -91,6 +91,8 @@ pub struct RouteInterest {
     pub peer_announced: bool,
     /// Manual member filter supplied through local route watch messages.
     pub manual: ManualMemberFilter,
+    /// Members a rendezvous relay introduced at this route for hole punching.
+    pub rendezvous: ManualMemberFilter,
 }
 
 impl RouteInterest {
@@ -98,19 +100,23 @@ impl RouteInterest {
     pub const NONE: Self = Self {
         peer_announced: false,
         manual: ManualMemberFilter::None,
+        rendezvous: ManualMemberFilter::None,
     };
 
     /// Return whether this route has any active interest source.
     pub fn should_watch(&self) -> bool {
-        self.peer_announced || self.manual.should_watch()
+        self.peer_announced || self.manual.should_watch() || self.rendezvous.should_watch()
     }
 
     /// Return whether this interest allows publishing a verified route for `member`.
+    ///
+    /// Manual watches are authoritative for their route. Otherwise any announced peer, or the
+    /// exact members a relay introduced, may be published.
     pub fn permits_member(&self, member: &MemberIdentity) -> bool {
         if self.manual.should_watch() {
             self.manual.permits_member(member)
         } else {
-            self.peer_announced
+            self.peer_announced || self.rendezvous.permits_member(member)
         }
     }
 }
@@ -251,6 +257,8 @@ pub enum RouteVerificationState {
     Reachable {
         reachable_lease: ScheduledTimer,
         published_members: TrieSet,
+        /// Capabilities both sides support, negotiated from the verified introduction.
+        capabilities: CapabilitySet,
     },
     /// Route previously failed or expired and is not currently published.
     Stale,
@@ -280,6 +288,14 @@ impl RouteVerificationState {
         }
     }
 
+    /// Return the negotiated capabilities of the peer reachable through this route.
+    pub fn negotiated_capabilities(&self) -> Option<CapabilitySet> {
+        match self {
+            Self::Reachable { capabilities, .. } => Some(*capabilities),
+            Self::Known | Self::Probing { .. } | Self::Stale => None,
+        }
+    }
+
     /// Move this route into probing state.
     ///
     /// Returns the previous probe or reachable-lease timer, if this transition replaced an
@@ -318,12 +334,14 @@ impl RouteVerificationState {
         &mut self,
         reachable_lease: ScheduledTimer,
         published_members: TrieSet,
+        capabilities: CapabilitySet,
     ) -> Option<ScheduledTimer> {
         match std::mem::replace(
             self,
             Self::Reachable {
                 reachable_lease,
                 published_members,
+                capabilities,
             },
         ) {
             Self::Known | Self::Stale => None,
@@ -393,6 +411,8 @@ pub struct PartiallyVerifiedIntroduction {
     pub route: DiscoveryRoute,
     /// Claims whose structure and probe fields matched, but whose signatures are not yet trusted.
     pub claims: Vec<PendingClaimVerification>,
+    /// Capabilities advertised by the introducing peer, which are not covered by the signatures.
+    pub capabilities: CapabilitySet,
 }
 
 /// Signed claim material awaiting trusted-key verification.
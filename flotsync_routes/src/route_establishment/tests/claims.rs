@@ -92,6 +92,36 @@ fn endpoint_selection_port_updates_introduction_claim_routes() {
     harness.shutdown();
 }
 
+#[test]
+fn reflexive_routes_are_signed_in_introduction_claims() {
+    let local_member = member(["alice"]);
+    let remote_member = member(["bob"]);
+    let memberships = shared_memberships(&local_member, &remote_member);
+    let local_endpoint = SocketAddr::from(([0, 0, 0, 0], 45_102));
+    let reflexive_route = SocketAddr::from(([203, 0, 113, 7], 38_211));
+    let remote_route = SocketAddr::from(([127, 0, 0, 1], 62_102));
+    let request_nonce = Uuid::from_u128(42_102);
+    let harness = RouteEstablishmentHarness::new(local_member.clone(), memberships);
+
+    harness.publish_reflexive_routes_and_wait_until_applied([reflexive_route]);
+    harness.bind_endpoint(SocketId(44), local_endpoint);
+    let frame = DiscoveryEndpointFrameView::IntroductionRequest { request_nonce }.encode_proto();
+    let payload = endpoint_payload(&frame);
+    harness.receive_transport(remote_route, payload);
+    let response = harness.recv_transport_submit();
+
+    assert_introduction_claims_route(
+        &response,
+        local_endpoint,
+        remote_route,
+        &local_member,
+        TEST_DISCOVERY_KEY_FINGERPRINT,
+        request_nonce,
+        reflexive_route,
+    );
+    harness.shutdown();
+}
+
 #[test]
 fn oversized_introduction_response_is_submitted_through_route_transport() {
     let local_member = member(["alice"]);
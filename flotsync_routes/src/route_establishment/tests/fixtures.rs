@@ -184,6 +184,8 @@ pub(super) fn observe_peer_route(
     component.record_peer_announcement(PeerAnnouncementObserved {
         instance_id,
         routes: vec![DiscoveryRoute::Udp(route)],
+        interface: None,
+        capabilities: CapabilitySet::BASELINE,
     });
 }
 
@@ -227,6 +229,7 @@ pub(super) struct IntroductionSpec<'a> {
     claim_nonce: Option<Uuid>,
     claimed_route: SocketAddr,
     group_ids: Vec<GroupId>,
+    capabilities: Option<CapabilitySet>,
 }
 
 impl<'a> IntroductionSpec<'a> {
@@ -245,6 +248,7 @@ impl<'a> IntroductionSpec<'a> {
             claim_nonce: None,
             claimed_route,
             group_ids: group_ids.into_iter().collect(),
+            capabilities: None,
         }
     }
 
@@ -268,6 +272,11 @@ impl<'a> IntroductionSpec<'a> {
         self
     }
 
+    pub(super) fn with_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
     pub(super) fn encode(self, request_nonce: Uuid) -> IoPayload {
         let Self {
             member,
@@ -278,6 +287,7 @@ impl<'a> IntroductionSpec<'a> {
             claim_nonce,
             claimed_route,
             group_ids,
+            capabilities,
         } = self;
         let top_level_nonce = top_level_nonce.unwrap_or(request_nonce);
         let claim_nonce = claim_nonce.unwrap_or(request_nonce);
@@ -302,6 +312,9 @@ impl<'a> IntroductionSpec<'a> {
                 signature: MessageField::some(signature.encode_proto()),
                 ..discovery_proto::SignedIntroductionClaim::default()
             }],
+            capabilities: capabilities
+                .map(|capabilities| capabilities.encode_proto())
+                .into(),
             ..discovery_proto::Introduction::default()
         };
         let frame = DiscoveryEndpointFrameView::Introduction {
@@ -362,7 +375,7 @@ pub(super) fn assert_introduction_claims_route(
     let discovery_frame = decode_endpoint_discovery_frame_from_buf(&mut cursor)
         .expect("introduction response should decode")
         .expect("introduction response should be a discovery frame");
-    let Some(discovery_proto::discovery_frame::Body::Introduction(introduction)) =
+    let Some(discovery_proto::discovery_frame::Body::Introduction(mut introduction)) =
         discovery_frame.body
     else {
         panic!("expected introduction response");
@@ -371,6 +384,16 @@ pub(super) fn assert_introduction_claims_route(
         introduction.request_nonce,
         uuid_to_wire_bytes(expected_nonce)
     );
+    assert_eq!(
+        CapabilitySet::decode_proto(
+            introduction
+                .capabilities
+                .take()
+                .expect("introductions advertise capabilities")
+        )
+        .expect("advertised capabilities should decode"),
+        CapabilitySet::CURRENT
+    );
     assert_eq!(introduction.claims.len(), 1);
     let mut claim_payload = discovery_proto::IntroductionClaimPayload::decode_from_slice(
         &introduction.claims[0].claim_payload,
@@ -407,9 +430,11 @@ pub(super) struct RouteEstablishmentHarness {
     route_transport_rx: mpsc::Receiver<RouteTransportSend<TransportRouteKey>>,
     inbound_transport: Arc<Component<PortTesterComponent<TestRouteTransportPort>>>,
     update_probe: Arc<Component<PortTesterComponent<TestRouteDiscoveryPort>>>,
+    liveness_probe: Arc<Component<PortTesterComponent<PeerLivenessPort>>>,
     key_material_probe: Arc<Component<PortTesterComponent<TestKeyMaterialDiscoveryPort>>>,
     component: Arc<Component<RouteEstablishmentComponent>>,
     update_cursor: Cell<usize>,
+    liveness_cursor: Cell<usize>,
     key_material_cursor: Cell<usize>,
 }
 
@@ -440,6 +465,7 @@ impl RouteEstablishmentHarness {
             .expect("route transport recorder must expose a strong actor ref");
         let inbound_transport = system.create(TestRouteTransportPort::tester_component_sidecar);
         let update_probe = system.create(TestRouteDiscoveryPort::tester_component_sidecar);
+        let liveness_probe = system.create(PeerLivenessPort::tester_component_sidecar);
         let key_material_probe =
             system.create(TestKeyMaterialDiscoveryPort::tester_component_sidecar);
         let component = system.create(move || {
@@ -454,12 +480,15 @@ impl RouteEstablishmentHarness {
             .expect("connect route transport probe");
         biconnect_components::<TestRouteDiscoveryPort, _, _>(&component, &update_probe)
             .expect("connect route update probe");
+        biconnect_components::<PeerLivenessPort, _, _>(&component, &liveness_probe)
+            .expect("connect peer liveness probe");
         biconnect_components::<TestKeyMaterialDiscoveryPort, _, _>(&key_material_probe, &component)
             .expect("connect key-material discovery probe");
 
         start_component(&system, &route_transport);
         start_component(&system, &inbound_transport);
         start_component(&system, &update_probe);
+        start_component(&system, &liveness_probe);
         start_component(&system, &key_material_probe);
         start_component(&system, &component);
 
@@ -469,9 +498,11 @@ impl RouteEstablishmentHarness {
             route_transport_rx,
             inbound_transport,
             update_probe,
+            liveness_probe,
             key_material_probe,
             component,
             update_cursor: Cell::new(0),
+            liveness_cursor: Cell::new(0),
             key_material_cursor: Cell::new(0),
         }
     }
@@ -488,10 +519,19 @@ impl RouteEstablishmentHarness {
     ) {
         let reachable_members = member_set(members);
         self.component.on_definition(move |component| {
-            component.mark_route_reachable(DiscoveryRoute::Udp(route), reachable_members);
+            component.mark_route_reachable(
+                DiscoveryRoute::Udp(route),
+                reachable_members,
+                CapabilitySet::CURRENT,
+            );
         });
     }
 
+    pub(super) fn negotiated_capabilities(&self, route: SocketAddr) -> Option<CapabilitySet> {
+        self.component
+            .on_definition(|component| component.negotiated_capabilities(route))
+    }
+
     pub(super) fn mark_route_stale(&self, route: SocketAddr) {
         self.component.on_definition(move |component| {
             component.mark_route_stale(DiscoveryRoute::Udp(route));
@@ -564,6 +604,42 @@ impl RouteEstablishmentHarness {
         );
     }
 
+    /// Publish relay-observed public routes and wait until they are signed in claims.
+    pub(super) fn publish_reflexive_routes_and_wait_until_applied(
+        &self,
+        routes: impl IntoIterator<Item = SocketAddr>,
+    ) {
+        let routes = routes.into_iter().collect::<BTreeSet<_>>();
+        let expected_routes = routes.clone();
+        let nat_traversal_port = self
+            .component
+            .on_definition(RouteEstablishmentComponent::nat_traversal_port);
+        self.system.trigger_i(
+            NatTraversalUpdate::ReflexiveRoutes(routes),
+            &nat_traversal_port,
+        );
+        eventually_component_state(
+            Duration::from_secs(1),
+            &self.component,
+            |component| component.reflexive_routes() == &expected_routes,
+            "reflexive routes should reach route establishment",
+        );
+    }
+
+    /// Report that a rendezvous relay introduced `member` at `route`.
+    pub(super) fn publish_punch_candidate(&self, member: MemberIdentity, route: SocketAddr) {
+        let nat_traversal_port = self
+            .component
+            .on_definition(RouteEstablishmentComponent::nat_traversal_port);
+        self.system.trigger_i(
+            NatTraversalUpdate::PunchCandidate {
+                member,
+                route: DiscoveryRoute::Udp(route),
+            },
+            &nat_traversal_port,
+        );
+    }
+
     pub(super) fn probe_manual_route(
         &self,
         socket_id: SocketId,
@@ -666,6 +742,42 @@ impl RouteEstablishmentHarness {
             .expect(reason);
     }
 
+    pub(super) fn expect_peer_liveness(
+        &self,
+        expected_peer: &MemberIdentity,
+        expected_liveness: PeerLiveness,
+    ) {
+        let observed = self
+            .liveness_probe
+            .actor_ref()
+            .observe_indication_from(self.liveness_cursor.get(), |_| true)
+            .wait_timeout(Duration::from_secs(1))
+            .expect("peer liveness update should be observed")
+            .expect("peer liveness probe should stay live");
+        self.liveness_cursor.set(observed.index() + 1);
+        assert_eq!(
+            observed.indication(),
+            &PeerLivenessUpdate {
+                peer: expected_peer.clone(),
+                liveness: expected_liveness,
+            }
+        );
+    }
+
+    pub(super) fn expect_no_peer_liveness_update(&self, reason: &'static str) {
+        self.liveness_probe
+            .actor_ref()
+            .fail_if_indication_observed_from(
+                self.liveness_cursor.get(),
+                Duration::from_millis(100),
+                |_| true,
+            )
+            .wait_timeout(Duration::from_secs(1))
+            .expect("peer liveness absence check should complete")
+            .expect("peer liveness probe should stay live")
+            .expect(reason);
+    }
+
     pub(super) fn expect_fetch_key_material_request(
         &self,
         expected_route: SocketAddr,
@@ -697,13 +809,16 @@ impl RouteEstablishmentHarness {
             route_transport_rx: _,
             inbound_transport,
             update_probe,
+            liveness_probe,
             key_material_probe,
             component,
             update_cursor: _,
+            liveness_cursor: _,
             key_material_cursor: _,
         } = self;
         kill_component(&system, component);
         kill_component(&system, key_material_probe);
+        kill_component(&system, liveness_probe);
         kill_component(&system, update_probe);
         kill_component(&system, inbound_transport);
         kill_component(&system, route_transport);
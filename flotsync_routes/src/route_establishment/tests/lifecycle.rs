@@ -83,3 +83,25 @@ fn published_routes_are_rebuilt_from_current_reachable_state() {
     harness.expect_peer_route_update(&remote_member, &[], None);
     harness.shutdown();
 }
+
+#[test]
+fn route_verification_marks_member_alive_once() {
+    let local_member = member(["alice"]);
+    let remote_member = member(["bob"]);
+    let memberships = shared_memberships(&local_member, &remote_member);
+    let local_endpoint = SocketAddr::from(([127, 0, 0, 1], 49116));
+    let remote_route = SocketAddr::from(([127, 0, 0, 1], 62177));
+    let instance_id = Uuid::from_u128(77);
+    let harness = RouteEstablishmentHarness::new(local_member, memberships);
+
+    harness.observe_peer_route(instance_id, remote_route);
+    harness.bind_endpoint(SocketId(97), local_endpoint);
+    harness.expect_transport_probe(local_endpoint, remote_route);
+    harness.mark_route_reachable(remote_route, [remote_member.clone()]);
+    harness.expect_peer_liveness(&remote_member, PeerLiveness::Alive);
+
+    // A refreshed route is another heartbeat from an already alive member.
+    harness.mark_route_reachable(remote_route, [remote_member.clone()]);
+    harness.expect_no_peer_liveness_update("refreshing a live member should not republish it");
+    harness.shutdown();
+}
@@ -20,6 +20,8 @@ use crate::{
     TransportRouteKey,
     UdpRouteKey,
     key_material_discovery::{FetchKeyMaterial, KeyMaterialDiscoveryPort},
+    liveness::{PeerLiveness, PeerLivenessPort, PeerLivenessUpdate},
+    nat_traversal::NatTraversalUpdate,
     protocol::{DiscoveryEndpointFrameView, decode_endpoint_discovery_frame_from_buf},
     test_support::{
         RouteTransportRecorderComponent,
@@ -36,6 +38,7 @@ use flotsync_core::{
     membership::{GroupMembers, GroupMemberships, SharedGroupMemberships},
 };
 use flotsync_discovery::{
+    capabilities::{Capabilities, CapabilitySet, ProtocolVersion},
     endpoint_selection::EndpointSelection,
     protocol::DiscoveryRoute,
     services::PeerAnnouncementObserved,
@@ -69,7 +72,7 @@ use futures_util::FutureExt as _;
 use kompact::prelude::*;
 use std::{
     cell::Cell,
-    collections::HashSet,
+    collections::{BTreeSet, HashSet},
     io,
     net::SocketAddr,
     sync::{Arc, mpsc},
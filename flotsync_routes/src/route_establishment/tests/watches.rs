@@ -60,6 +60,42 @@ fn manual_route_watch_verifies_and_publishes_expected_member() {
     harness.shutdown();
 }
 
+#[test]
+fn verified_routes_negotiate_capabilities_from_the_introduction() {
+    let local_member = member(["alice"]);
+    let remote_member = member(["bob"]);
+    let memberships = shared_memberships(&local_member, &remote_member);
+    let local_endpoint = SocketAddr::from(([127, 0, 0, 1], 49112));
+    let remote_route = SocketAddr::from(([127, 0, 0, 1], 62172));
+    let remote_instance = Uuid::from_u128(72);
+    let harness = RouteEstablishmentHarness::new(local_member, memberships);
+    let nonce = harness.probe_manual_route(
+        SocketId(92),
+        local_endpoint,
+        [watched_udp_route(remote_route, Some(remote_member.clone()))],
+        remote_route,
+    );
+    assert_eq!(harness.negotiated_capabilities(remote_route), None);
+    let newer_peer = CapabilitySet {
+        features: Capabilities::RELAY | Capabilities::SNAPSHOTS_V2,
+        max_protocol_version: ProtocolVersion(ProtocolVersion::CURRENT.0 + 1),
+    };
+    let payload =
+        IntroductionSpec::new(&remote_member, remote_instance, remote_route, [group_id(1)])
+            .with_capabilities(newer_peer)
+            .encode(nonce);
+
+    harness.receive_transport(remote_route, payload);
+
+    harness.expect_peer_route_update(&remote_member, &[remote_route], Some(local_endpoint));
+    assert_eq!(
+        harness.negotiated_capabilities(remote_route),
+        Some(CapabilitySet::CURRENT),
+        "only features and versions both sides support are negotiated"
+    );
+    harness.shutdown();
+}
+
 #[test]
 fn manual_route_watch_without_expected_member_publishes_verified_group_member() {
     let local_member = member(["alice"]);
@@ -226,6 +262,55 @@ fn manual_route_watch_rejects_unexpected_member() {
     harness.shutdown();
 }
 
+#[test]
+fn punch_candidate_probes_and_publishes_introduced_member() {
+    let local_member = member(["alice"]);
+    let remote_member = member(["bob"]);
+    let memberships = shared_memberships(&local_member, &remote_member);
+    let local_endpoint = SocketAddr::from(([127, 0, 0, 1], 49130));
+    let remote_route = SocketAddr::from(([127, 0, 0, 1], 62176));
+    let remote_instance = Uuid::from_u128(76);
+    let harness = RouteEstablishmentHarness::new(local_member, memberships);
+
+    harness.bind_endpoint(SocketId(120), local_endpoint);
+    harness.publish_punch_candidate(remote_member.clone(), remote_route);
+    let nonce = harness.expect_transport_probe_with_nonce(local_endpoint, remote_route);
+    let payload =
+        IntroductionSpec::new(&remote_member, remote_instance, remote_route, [group_id(1)])
+            .encode(nonce);
+    harness.receive_transport(remote_route, payload);
+
+    harness.expect_peer_route_update(&remote_member, &[remote_route], Some(local_endpoint));
+    harness.shutdown();
+}
+
+#[test]
+fn punch_candidate_rejects_member_other_than_introduced_one() {
+    let local_member = member(["alice"]);
+    let introduced_member = member(["bob"]);
+    let other_member = member(["charlie"]);
+    let memberships = single_group_memberships([
+        local_member.clone(),
+        introduced_member.clone(),
+        other_member.clone(),
+    ]);
+    let local_endpoint = SocketAddr::from(([127, 0, 0, 1], 49131));
+    let remote_route = SocketAddr::from(([127, 0, 0, 1], 62177));
+    let remote_instance = Uuid::from_u128(77);
+    let harness = RouteEstablishmentHarness::new(local_member, memberships);
+
+    harness.bind_endpoint(SocketId(121), local_endpoint);
+    harness.publish_punch_candidate(introduced_member, remote_route);
+    let nonce = harness.expect_transport_probe_with_nonce(local_endpoint, remote_route);
+    let payload =
+        IntroductionSpec::new(&other_member, remote_instance, remote_route, [group_id(1)])
+            .encode(nonce);
+    harness.receive_transport(remote_route, payload);
+
+    harness.expect_no_route_update("relays only vouch for the member they introduced");
+    harness.shutdown();
+}
+
 #[test]
 fn manual_route_watch_rejects_unverifiable_claim() {
     let local_member = member(["alice"]);
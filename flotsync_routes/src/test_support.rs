@@ -14,6 +14,7 @@ use crate::{
     RouteTransportSubmitResult,
     TransportRouteKey,
     manager::{RouteTransportManager, configure_replication_runtime},
+    trace::Trace,
 };
 use flotsync_core::{
     MemberIdentity,
@@ -64,6 +65,7 @@ use flotsync_udpour::UDPourConfig;
 use kompact::prelude::*;
 use std::{
     collections::HashMap,
+    io,
     net::SocketAddr,
     ops::{Deref, DerefMut},
     sync::{
@@ -207,6 +209,51 @@ impl Actor for RouteTransportRecorderComponent {
     }
 }
 
+/// In-memory `.flotrace` sink whose clones share one buffer.
+#[derive(Clone, Debug, Default)]
+pub struct SharedTraceBuffer {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl SharedTraceBuffer {
+    /// Snapshot the raw trace bytes written so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer lock is poisoned.
+    #[must_use]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes
+            .lock()
+            .expect("trace buffer lock poisoned")
+            .clone()
+    }
+
+    /// Load the trace written so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffered bytes are not a valid trace.
+    #[must_use]
+    pub fn load(&self) -> Trace {
+        Trace::load(self.bytes().as_slice()).expect("captured trace should load")
+    }
+}
+
+impl io::Write for SharedTraceBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes
+            .lock()
+            .expect("trace buffer lock poisoned")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 /// Longer timeout used by the semantic full-stack delivery tests.
 pub const FULL_STACK_WAIT_TIMEOUT: Duration = Duration::from_secs(20);
 
@@ -0,0 +1,584 @@
+//! Capture and replay of route-transport traffic.
+//!
+//! A `.flotrace` file records every logical payload that the route-transport
+//! manager handed to, or received from, the network, together with its
+//! concrete route and the time since the capture started. Attaching a
+//! [`TraceCapture`] with
+//! [`RouteTransportManager::set_trace_capture`](crate::manager::RouteTransportManager::set_trace_capture)
+//! records a running peer; [`Trace`] loads the file again so a test harness can
+//! feed the received payloads back into the components under test.
+//!
+//! # Format
+//!
+//! All integers are little endian.
+//!
+//! - Header: the magic bytes `FLOTRACE`, followed by the format version as `u16`.
+//! - Records, until the end of the stream:
+//!   - elapsed time since capture start in microseconds as `u64`
+//!   - direction: `0` sent, `1` received
+//!   - route: `0` UDP followed by the datagram scope (`0` unicast, `1`
+//!     broadcast, `2` multicast), or `1` TCP; then the remote address and the
+//!     optional local bind address
+//!   - payload length as `u32`, followed by the payload bytes
+//!
+//! Socket addresses are encoded as the address family (`4` or `6`), the IP
+//! address octets, and the port as `u16`. Optional addresses are prefixed with
+//! `0` when absent and `1` when present.
+
+use crate::{
+    DatagramRouteScope,
+    InboundTransportMeta,
+    RouteTransportInboundDeliver,
+    TcpRouteKey,
+    TransportRouteKey,
+    UdpRouteKey,
+};
+use bytes::Bytes;
+use flotsync_io::prelude::IoPayload;
+use snafu::prelude::*;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// File extension used for captured route-transport traces.
+pub const TRACE_FILE_EXTENSION: &str = "flotrace";
+
+/// Magic bytes at the start of every `.flotrace` stream.
+pub const TRACE_MAGIC: [u8; 8] = *b"FLOTRACE";
+
+/// The `.flotrace` format version written by this build.
+pub const TRACE_FORMAT_VERSION: u16 = 1;
+
+/// Whether a captured payload left or reached the local peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TraceDirection {
+    /// Route transport handed the payload to the network.
+    Sent,
+    /// Route transport delivered the payload to its inbound port.
+    Received,
+}
+
+/// One captured route-transport payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceRecord {
+    /// Time between the start of the capture and this payload.
+    pub elapsed: Duration,
+    /// Whether the payload was sent or received.
+    pub direction: TraceDirection,
+    /// Concrete route the payload used.
+    pub route: TransportRouteKey,
+    /// Full logical payload, after any reassembly.
+    pub payload: Bytes,
+}
+
+/// Reasons why a `.flotrace` stream could not be read.
+#[derive(Debug, Snafu)]
+pub enum TraceReadError {
+    #[snafu(display("failed to read the trace"))]
+    ReadTrace {
+        /// Underlying read failure.
+        source: io::Error,
+    },
+    #[snafu(display("the stream does not start with the .flotrace magic bytes"))]
+    NotATrace,
+    #[snafu(display("unsupported .flotrace format version {version}"))]
+    UnsupportedVersion {
+        /// Version found in the header.
+        version: u16,
+    },
+    #[snafu(display("the trace ends in the middle of a record"))]
+    TruncatedRecord,
+    #[snafu(display("invalid {field} tag {tag} in trace record"))]
+    InvalidTag {
+        /// Record field that holds the tag.
+        field: &'static str,
+        /// Unknown tag value.
+        tag: u8,
+    },
+}
+
+/// Shared handle that appends route-transport traffic to a `.flotrace` stream.
+///
+/// Clones write to the same stream, and elapsed times are measured from the
+/// moment the capture was created.
+#[derive(Clone)]
+pub struct TraceCapture {
+    inner: Arc<Mutex<TraceCaptureState>>,
+}
+
+impl TraceCapture {
+    /// Start a capture that writes to `writer`, beginning with the trace header.
+    ///
+    /// # Errors
+    ///
+    /// Returns the write error if the header cannot be written.
+    pub fn new<W>(mut writer: W) -> io::Result<Self>
+    where
+        W: Write + Send + 'static,
+    {
+        writer.write_all(&TRACE_MAGIC)?;
+        writer.write_all(&TRACE_FORMAT_VERSION.to_le_bytes())?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new(TraceCaptureState {
+                started: Instant::now(),
+                writer: Box::new(writer),
+            })),
+        })
+    }
+
+    /// Start a capture into a newly created file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file cannot be created or the header cannot be written.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Append one payload to the trace.
+    ///
+    /// # Errors
+    ///
+    /// Returns the write error if the record cannot be written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the payload is longer than `u32::MAX` bytes.
+    pub fn record(
+        &self,
+        direction: TraceDirection,
+        route: TransportRouteKey,
+        payload: &IoPayload,
+    ) -> io::Result<()> {
+        let payload = payload.to_vec();
+        let mut state = self.inner.lock().expect("trace capture lock poisoned");
+        let elapsed = u64::try_from(state.started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        let mut record = Vec::with_capacity(payload.len() + 64);
+        record.extend_from_slice(&elapsed.to_le_bytes());
+        record.push(match direction {
+            TraceDirection::Sent => 0,
+            TraceDirection::Received => 1,
+        });
+        encode_route(&mut record, route);
+        let payload_len = u32::try_from(payload.len()).expect("traced payloads fit into u32");
+        record.extend_from_slice(&payload_len.to_le_bytes());
+        record.extend_from_slice(&payload);
+        state.writer.write_all(&record)
+    }
+
+    /// Flush everything recorded so far to the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the write error if flushing fails.
+    pub fn flush(&self) -> io::Result<()> {
+        self.inner
+            .lock()
+            .expect("trace capture lock poisoned")
+            .writer
+            .flush()
+    }
+}
+
+impl std::fmt::Debug for TraceCapture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TraceCapture").finish_non_exhaustive()
+    }
+}
+
+/// Streaming reader over the records of one `.flotrace` stream.
+pub struct TraceReader<R> {
+    reader: R,
+}
+
+impl<R> TraceReader<R>
+where
+    R: Read,
+{
+    /// Start reading a trace, checking its header.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the stream is not a `.flotrace` stream of a supported version.
+    pub fn new(mut reader: R) -> Result<Self, TraceReadError> {
+        let mut magic = [0u8; TRACE_MAGIC.len()];
+        reader.read_exact(&mut magic).map_err(|source| {
+            if source.kind() == io::ErrorKind::UnexpectedEof {
+                TraceReadError::NotATrace
+            } else {
+                TraceReadError::ReadTrace { source }
+            }
+        })?;
+        ensure!(magic == TRACE_MAGIC, NotATraceSnafu);
+        let version = u16::from_le_bytes(read_array(&mut reader)?);
+        ensure!(
+            version == TRACE_FORMAT_VERSION,
+            UnsupportedVersionSnafu { version }
+        );
+        Ok(Self { reader })
+    }
+
+    /// Read the next record, or `None` at the end of the stream.
+    fn read_record(&mut self) -> Result<Option<TraceRecord>, TraceReadError> {
+        let mut elapsed = [0u8; 8];
+        let mut filled = 0;
+        while filled < elapsed.len() {
+            let read = self
+                .reader
+                .read(&mut elapsed[filled..])
+                .context(ReadTraceSnafu)?;
+            if read == 0 {
+                ensure!(filled == 0, TruncatedRecordSnafu);
+                return Ok(None);
+            }
+            filled += read;
+        }
+        let elapsed = Duration::from_micros(u64::from_le_bytes(elapsed));
+        let direction = match read_u8(&mut self.reader)? {
+            0 => TraceDirection::Sent,
+            1 => TraceDirection::Received,
+            tag => {
+                return InvalidTagSnafu {
+                    field: "direction",
+                    tag,
+                }
+                .fail();
+            }
+        };
+        let route = decode_route(&mut self.reader)?;
+        let payload_len = u32::from_le_bytes(read_array(&mut self.reader)?);
+        let mut payload = vec![0u8; payload_len as usize];
+        read_exact(&mut self.reader, &mut payload)?;
+        Ok(Some(TraceRecord {
+            elapsed,
+            direction,
+            route,
+            payload: Bytes::from(payload),
+        }))
+    }
+}
+
+impl<R> Iterator for TraceReader<R>
+where
+    R: Read,
+{
+    type Item = Result<TraceRecord, TraceReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// A fully loaded `.flotrace` capture.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    records: Vec<TraceRecord>,
+}
+
+impl Trace {
+    /// Load every record from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the stream is not a valid `.flotrace` stream.
+    pub fn load(reader: impl Read) -> Result<Self, TraceReadError> {
+        let records = TraceReader::new(reader)?.collect::<Result<_, _>>()?;
+        Ok(Self { records })
+    }
+
+    /// Load a capture from the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be opened or is not a valid `.flotrace` file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, TraceReadError> {
+        let file = File::open(path).context(ReadTraceSnafu)?;
+        Self::load(BufReader::new(file))
+    }
+
+    /// All records in capture order.
+    #[must_use]
+    pub fn records(&self) -> &[TraceRecord] {
+        &self.records
+    }
+
+    /// Rebuild the inbound deliveries of every received payload, in capture order.
+    ///
+    /// Replaying these into a component's route-transport port reproduces what
+    /// it observed during the capture. Replay only depends on the record order,
+    /// never on the recorded timestamps, so it is deterministic.
+    pub fn inbound_deliveries(
+        &self,
+    ) -> impl Iterator<Item = RouteTransportInboundDeliver<TransportRouteKey>> + '_ {
+        self.records
+            .iter()
+            .filter(|record| record.direction == TraceDirection::Received)
+            .map(|record| RouteTransportInboundDeliver {
+                payload: IoPayload::Bytes(record.payload.clone()),
+                transport: InboundTransportMeta {
+                    route: record.route,
+                    remote_addr: Some(route_remote_addr(record.route)),
+                },
+            })
+    }
+}
+
+struct TraceCaptureState {
+    started: Instant,
+    writer: Box<dyn Write + Send>,
+}
+
+fn route_remote_addr(route: TransportRouteKey) -> SocketAddr {
+    match route {
+        TransportRouteKey::Udp(route) => route.remote_addr,
+        TransportRouteKey::Tcp(route) => route.remote_addr,
+    }
+}
+
+fn encode_route(buffer: &mut Vec<u8>, route: TransportRouteKey) {
+    match route {
+        TransportRouteKey::Udp(route) => {
+            buffer.push(0);
+            buffer.push(match route.scope {
+                DatagramRouteScope::Unicast => 0,
+                DatagramRouteScope::Broadcast => 1,
+                DatagramRouteScope::Multicast => 2,
+            });
+            encode_addr(buffer, route.remote_addr);
+            encode_optional_addr(buffer, route.local_bind);
+        }
+        TransportRouteKey::Tcp(route) => {
+            buffer.push(1);
+            encode_addr(buffer, route.remote_addr);
+            encode_optional_addr(buffer, route.local_bind);
+        }
+    }
+}
+
+fn encode_optional_addr(buffer: &mut Vec<u8>, addr: Option<SocketAddr>) {
+    match addr {
+        Some(addr) => {
+            buffer.push(1);
+            encode_addr(buffer, addr);
+        }
+        None => buffer.push(0),
+    }
+}
+
+fn encode_addr(buffer: &mut Vec<u8>, addr: SocketAddr) {
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            buffer.push(4);
+            buffer.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            buffer.push(6);
+            buffer.extend_from_slice(&ip.octets());
+        }
+    }
+    buffer.extend_from_slice(&addr.port().to_le_bytes());
+}
+
+fn decode_route(reader: &mut impl Read) -> Result<TransportRouteKey, TraceReadError> {
+    match read_u8(reader)? {
+        0 => {
+            let scope = match read_u8(reader)? {
+                0 => DatagramRouteScope::Unicast,
+                1 => DatagramRouteScope::Broadcast,
+                2 => DatagramRouteScope::Multicast,
+                tag => {
+                    return InvalidTagSnafu {
+                        field: "datagram scope",
+                        tag,
+                    }
+                    .fail();
+                }
+            };
+            let remote_addr = decode_addr(reader)?;
+            let local_bind = decode_optional_addr(reader)?;
+            Ok(TransportRouteKey::Udp(UdpRouteKey {
+                remote_addr,
+                scope,
+                local_bind,
+            }))
+        }
+        1 => {
+            let remote_addr = decode_addr(reader)?;
+            let local_bind = decode_optional_addr(reader)?;
+            Ok(TransportRouteKey::Tcp(TcpRouteKey {
+                remote_addr,
+                local_bind,
+            }))
+        }
+        tag => InvalidTagSnafu {
+            field: "route kind",
+            tag,
+        }
+        .fail(),
+    }
+}
+
+fn decode_optional_addr(reader: &mut impl Read) -> Result<Option<SocketAddr>, TraceReadError> {
+    match read_u8(reader)? {
+        0 => Ok(None),
+        1 => decode_addr(reader).map(Some),
+        tag => InvalidTagSnafu {
+            field: "optional address",
+            tag,
+        }
+        .fail(),
+    }
+}
+
+fn decode_addr(reader: &mut impl Read) -> Result<SocketAddr, TraceReadError> {
+    let ip = match read_u8(reader)? {
+        4 => IpAddr::V4(Ipv4Addr::from(read_array::<4>(reader)?)),
+        6 => IpAddr::V6(Ipv6Addr::from(read_array::<16>(reader)?)),
+        tag => {
+            return InvalidTagSnafu {
+                field: "address family",
+                tag,
+            }
+            .fail();
+        }
+    };
+    let port = u16::from_le_bytes(read_array(reader)?);
+    Ok(SocketAddr::new(ip, port))
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8, TraceReadError> {
+    read_array::<1>(reader).map(|[value]| value)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], TraceReadError> {
+    let mut buffer = [0u8; N];
+    read_exact(reader, &mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_exact(reader: &mut impl Read, buffer: &mut [u8]) -> Result<(), TraceReadError> {
+    reader.read_exact(buffer).map_err(|source| {
+        if source.kind() == io::ErrorKind::UnexpectedEof {
+            TraceReadError::TruncatedRecord
+        } else {
+            TraceReadError::ReadTrace { source }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::SharedTraceBuffer;
+
+    fn udp_route() -> TransportRouteKey {
+        TransportRouteKey::Udp(UdpRouteKey {
+            remote_addr: "127.0.0.1:4000".parse().unwrap(),
+            scope: DatagramRouteScope::Unicast,
+            local_bind: Some("127.0.0.1:5000".parse().unwrap()),
+        })
+    }
+
+    fn tcp_route() -> TransportRouteKey {
+        TransportRouteKey::Tcp(TcpRouteKey {
+            remote_addr: "[::1]:4000".parse().unwrap(),
+            local_bind: None,
+        })
+    }
+
+    #[test]
+    fn captured_records_roundtrip_in_order() {
+        let buffer = SharedTraceBuffer::default();
+        let capture = TraceCapture::new(buffer.clone()).unwrap();
+        capture
+            .record(
+                TraceDirection::Sent,
+                tcp_route(),
+                &IoPayload::Bytes(Bytes::from_static(b"hello")),
+            )
+            .unwrap();
+        capture
+            .record(
+                TraceDirection::Received,
+                udp_route(),
+                &IoPayload::Bytes(Bytes::from_static(b"world")),
+            )
+            .unwrap();
+
+        let trace = buffer.load();
+        let records = trace.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, TraceDirection::Sent);
+        assert_eq!(records[0].route, tcp_route());
+        assert_eq!(records[0].payload, Bytes::from_static(b"hello"));
+        assert_eq!(records[1].direction, TraceDirection::Received);
+        assert_eq!(records[1].route, udp_route());
+        assert_eq!(records[1].payload, Bytes::from_static(b"world"));
+        assert!(records[0].elapsed <= records[1].elapsed);
+    }
+
+    #[test]
+    fn inbound_deliveries_replay_only_received_payloads() {
+        let buffer = SharedTraceBuffer::default();
+        let capture = TraceCapture::new(buffer.clone()).unwrap();
+        for (direction, payload) in [
+            (TraceDirection::Received, b"first"),
+            (TraceDirection::Sent, b"reply"),
+            (TraceDirection::Received, b"again"),
+        ] {
+            capture
+                .record(
+                    direction,
+                    udp_route(),
+                    &IoPayload::Bytes(Bytes::from_static(payload)),
+                )
+                .unwrap();
+        }
+
+        let deliveries: Vec<_> = buffer.load().inbound_deliveries().collect();
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].payload.to_vec(), b"first");
+        assert_eq!(deliveries[1].payload.to_vec(), b"again");
+        assert_eq!(deliveries[0].transport.route, udp_route());
+        assert_eq!(
+            deliveries[0].transport.remote_addr,
+            Some("127.0.0.1:4000".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn reading_rejects_foreign_and_truncated_streams() {
+        assert!(matches!(
+            Trace::load(&b"NOTATRACE!"[..]),
+            Err(TraceReadError::NotATrace)
+        ));
+
+        let mut future_version = TRACE_MAGIC.to_vec();
+        future_version.extend_from_slice(&(TRACE_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            Trace::load(future_version.as_slice()),
+            Err(TraceReadError::UnsupportedVersion { .. })
+        ));
+
+        let buffer = SharedTraceBuffer::default();
+        let capture = TraceCapture::new(buffer.clone()).unwrap();
+        capture
+            .record(
+                TraceDirection::Sent,
+                udp_route(),
+                &IoPayload::Bytes(Bytes::from_static(b"payload")),
+            )
+            .unwrap();
+        let mut bytes = buffer.bytes();
+        bytes.truncate(bytes.len() - 1);
+        assert!(matches!(
+            Trace::load(bytes.as_slice()),
+            Err(TraceReadError::TruncatedRecord)
+        ));
+    }
+}
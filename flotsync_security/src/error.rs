@@ -1,5 +1,5 @@
-use crate::{group::GroupCipherSuite, identity::KeyRole};
-use flotsync_core::MemberIdentity;
+use crate::{group::GroupCipherSuite, identity::KeyRole, key_rotation::KeyGeneration};
+use flotsync_core::{GroupId, MemberIdentity};
 use flotsync_messages::security as security_proto;
 use rand_core::OsError;
 use snafu::prelude::*;
@@ -78,6 +78,12 @@ pub enum SecurityError {
     StoreSecretSeal,
     #[snafu(display("Store secret authentication failed."))]
     StoreSecretOpen,
+    #[snafu(display("Failed to derive a key from the passphrase: {source}"))]
+    PassphraseKeyDerivation { source: argon2::Error },
+    #[snafu(display("Passphrase encryption failed."))]
+    PassphraseSeal,
+    #[snafu(display("Passphrase authentication failed. The passphrase may be wrong."))]
+    PassphraseOpen,
     #[snafu(display("HPKE key material could not be decoded: {source}"))]
     HpkeKeyDecode { source: hpke::HpkeError },
     #[snafu(display("Key bundle contains invalid HPKE {role} key material: {source}"))]
@@ -97,6 +103,30 @@ pub enum SecurityError {
         context_member: MemberIdentity,
         key_member: MemberIdentity,
     },
+    #[snafu(display("Group {group_id} does not hold the key of {generation}."))]
+    UnknownKeyGeneration {
+        group_id: GroupId,
+        generation: KeyGeneration,
+    },
+    #[snafu(display(
+        "The key of {generation} in group {group_id} was replaced longer than the grace window ago."
+    ))]
+    ExpiredKeyGeneration {
+        group_id: GroupId,
+        generation: KeyGeneration,
+    },
+    #[snafu(display("Group {group_id} already holds a different key for {generation}."))]
+    KeyGenerationConflict {
+        group_id: GroupId,
+        generation: KeyGeneration,
+    },
+    #[snafu(display("The rotation to {generation} did not seal the new key for member {member}."))]
+    MissingKeyRotationRecipient {
+        generation: KeyGeneration,
+        member: MemberIdentity,
+    },
+    #[snafu(display("Rotated group key is {actual} bytes, expected {expected} bytes."))]
+    RotatedGroupKeyLength { expected: usize, actual: usize },
 }
 
 /// Member role whose typed context identity must match the supplied key material.
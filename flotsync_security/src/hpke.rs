@@ -31,6 +31,7 @@ pub const HPKE_ENCAPSULATED_KEY_LENGTH: usize = 32;
 const DOMAIN_HPKE_INFO: &[u8] = b"flotsync/security/hpke-info/v1";
 const DOMAIN_HPKE_AAD: &[u8] = b"flotsync/security/hpke-aad/v1";
 const PURPOSE_RELIABLE_PAYLOAD: &[u8] = b"reliable-payload";
+const PURPOSE_GROUP_KEY_ROTATION: &[u8] = b"group-key-rotation";
 const SCOPE_DIRECT_MESSAGE: &[u8] = b"direct-message";
 const SCOPE_GROUP: &[u8] = b"group";
 
@@ -117,6 +118,8 @@ pub fn hpke_open(
 pub enum HpkeEnvelopePurpose {
     /// Recipient-specific reliable-delivery payload.
     ReliablePayload,
+    /// New group key announced by a [`KeyRotation`](crate::KeyRotation).
+    GroupKeyRotation,
 }
 
 impl HpkeEnvelopePurpose {
@@ -124,6 +127,7 @@ impl HpkeEnvelopePurpose {
     const fn as_bytes(self) -> &'static [u8] {
         match self {
             Self::ReliablePayload => PURPOSE_RELIABLE_PAYLOAD,
+            Self::GroupKeyRotation => PURPOSE_GROUP_KEY_ROTATION,
         }
     }
 }
@@ -0,0 +1,452 @@
+//! Building blocks for replacing the key of a group in place and re-encrypting under a new key.
+//!
+//! The replication runtime does not use these yet. It replaces group keys by migrating to a new
+//! group: removing a member sets up the successor group with a freshly generated key that is only
+//! sealed for the remaining members. Group frames carry no key generation and nothing is stored
+//! under a group key, so there is no scheduled rotation and no stored data to re-encrypt.
+//!
+//! Replacing the key within one group works as follows. Every key has a [`KeyGeneration`]. A
+//! [`KeyRotation`] announces the next generation and carries the new key sealed for each remaining
+//! member with HPKE. Like other recipient payloads it relies on the signed outer frame for sender
+//! authentication.
+//!
+//! Members keep the keys of earlier generations in a [`GroupKeyRing`] for a bounded window after
+//! they were replaced, so peers that lag behind can still open operations that were encrypted
+//! under a prior key while they catch up. Once the window has passed, [`GroupKeyRing::prune`]
+//! drops these keys, and data still encrypted under them has to be moved to the current key with
+//! [`reencrypt_group_message`] before that.
+
+use crate::{
+    error::{
+        ExpiredKeyGenerationSnafu,
+        KeyGenerationConflictSnafu,
+        MissingKeyRotationRecipientSnafu,
+        Result,
+        RotatedGroupKeyLengthSnafu,
+        UnknownKeyGenerationSnafu,
+    },
+    group::{
+        GROUP_KEY_LENGTH,
+        GroupKey,
+        GroupMessageContext,
+        open_group_message,
+        seal_group_message,
+    },
+    hpke::{
+        HpkeCiphertext,
+        HpkeContext,
+        HpkeEnvelopePurpose,
+        HpkeEnvelopeScope,
+        hpke_open,
+        hpke_seal,
+    },
+    identity::{LocalMemberKeys, MemberIdentity, PublicMemberKeys},
+    util::fixed_array,
+};
+use bytes::Bytes;
+use flotsync_core::GroupId;
+use snafu::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// Sequence number of one group key, starting at [`KeyGeneration::INITIAL`] for the key the group
+/// was set up with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyGeneration(u32);
+
+impl KeyGeneration {
+    /// Generation of the key a group is set up with.
+    pub const INITIAL: Self = Self(0);
+
+    /// Build a key generation from its wire value.
+    #[must_use]
+    pub const fn new(value: u32) -> Self {
+        Self(value)
+    }
+
+    /// Return the integer value carried in protocol payloads.
+    #[must_use]
+    pub const fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    /// Return the generation after this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the generation counter overflows.
+    #[must_use]
+    pub fn next(self) -> Self {
+        Self(
+            self.0
+                .checked_add(1)
+                .expect("group key generation must not overflow"),
+        )
+    }
+}
+
+impl fmt::Display for KeyGeneration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key generation {}", self.0)
+    }
+}
+
+/// Why a group key was replaced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyRotationReason {
+    /// A member was removed and must not read anything the group writes afterwards.
+    MemberRemoved,
+    /// The key had been in use for longer than the rotation interval.
+    Scheduled,
+}
+
+impl KeyRotationReason {
+    /// Return the stable value bound into the rotation envelopes.
+    const fn as_u8(self) -> u8 {
+        match self {
+            Self::MemberRemoved => 1,
+            Self::Scheduled => 2,
+        }
+    }
+}
+
+/// Announcement of the next key of a group, sealed for every remaining member.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyRotation {
+    pub group_id: GroupId,
+    /// Unique id of this announcement, bound into every sealed key.
+    pub rotation_id: Uuid,
+    pub generation: KeyGeneration,
+    pub reason: KeyRotationReason,
+    /// The new key sealed for each recipient, in the order the recipients were given.
+    pub sealed_keys: Vec<(MemberIdentity, HpkeCiphertext)>,
+}
+
+impl KeyRotation {
+    /// Whether the new key was sealed for `member`.
+    #[must_use]
+    pub fn includes(&self, member: &MemberIdentity) -> bool {
+        self.sealed_keys
+            .iter()
+            .any(|(recipient, _)| recipient == member)
+    }
+}
+
+/// Seal `key` as `generation` of `group_id`'s key for every member in `recipients`.
+///
+/// Members that should lose access, such as a removed member, are simply not included in
+/// `recipients`. The RNG must be a cryptographic RNG outside of tests, see [`hpke_seal`].
+///
+/// # Errors
+///
+/// Returns [`crate::SecurityError`] if sealing the key for one of the recipients fails.
+pub fn seal_key_rotation<'a, R>(
+    sender: &LocalMemberKeys,
+    group_id: GroupId,
+    generation: KeyGeneration,
+    reason: KeyRotationReason,
+    key: &GroupKey,
+    recipients: impl IntoIterator<Item = &'a PublicMemberKeys>,
+    rng: &mut R,
+) -> Result<KeyRotation>
+where
+    R: hpke::rand_core::CryptoRng + hpke::rand_core::RngCore,
+{
+    let mut rotation_id = [0u8; 16];
+    rng.fill_bytes(&mut rotation_id);
+    let rotation_id = Uuid::from_bytes(rotation_id);
+    let metadata = rotation_metadata(generation, reason);
+    let key_bytes = Zeroizing::new(key.to_bytes());
+    let mut sealed_keys = Vec::new();
+    for recipient in recipients {
+        let context = rotation_context(
+            sender.member_id(),
+            recipient.member_id(),
+            group_id,
+            rotation_id,
+            &metadata,
+        );
+        let sealed = hpke_seal(recipient, context, key_bytes.as_slice(), rng)?;
+        sealed_keys.push((recipient.member_id().clone(), sealed));
+    }
+    Ok(KeyRotation {
+        group_id,
+        rotation_id,
+        generation,
+        reason,
+        sealed_keys,
+    })
+}
+
+/// Open the key announced by `rotation` for the local member.
+///
+/// `sender` must be the member whose signed frame carried the rotation.
+///
+/// # Errors
+///
+/// Returns [`crate::SecurityError::MissingKeyRotationRecipient`] if the key was not sealed for
+/// the local member, or HPKE errors if the sealed key does not open.
+pub fn open_key_rotation(
+    local: &LocalMemberKeys,
+    sender: &MemberIdentity,
+    rotation: &KeyRotation,
+) -> Result<GroupKey> {
+    let (_, sealed) = rotation
+        .sealed_keys
+        .iter()
+        .find(|(recipient, _)| recipient == local.member_id())
+        .context(MissingKeyRotationRecipientSnafu {
+            generation: rotation.generation,
+            member: local.member_id().clone(),
+        })?;
+    let metadata = rotation_metadata(rotation.generation, rotation.reason);
+    let context = rotation_context(
+        sender,
+        local.member_id(),
+        rotation.group_id,
+        rotation.rotation_id,
+        &metadata,
+    );
+    let plaintext = Zeroizing::new(hpke_open(local, context, sealed)?);
+    ensure!(
+        plaintext.len() == GROUP_KEY_LENGTH,
+        RotatedGroupKeyLengthSnafu {
+            expected: GROUP_KEY_LENGTH,
+            actual: plaintext.len(),
+        }
+    );
+    Ok(GroupKey::from_bytes(fixed_array(plaintext.as_slice())))
+}
+
+/// Move one group message from `old_key` to `new_key`.
+///
+/// The message keeps its context and public header. Nonces are derived from the context, which
+/// is safe here because every nonce is only ever used once per key.
+///
+/// # Errors
+///
+/// Returns [`crate::SecurityError::GroupOpen`] if the message does not authenticate under
+/// `old_key`, or [`crate::SecurityError::GroupSeal`] if sealing it under `new_key` fails.
+pub fn reencrypt_group_message(
+    old_key: &GroupKey,
+    new_key: &GroupKey,
+    context: GroupMessageContext<'_>,
+    public_header: &[u8],
+    ciphertext: &[u8],
+) -> Result<Bytes> {
+    let plaintext = open_group_message(old_key, context, public_header, ciphertext)?;
+    let plaintext = Zeroizing::new(Vec::from(plaintext));
+    seal_group_message(new_key, context, public_header, plaintext.as_slice())
+}
+
+/// The keys of one group by generation, including recently replaced ones.
+///
+/// The newest generation is the current key and is used for everything the group writes.
+/// Replaced keys can still open messages for `grace_window` after they were replaced.
+pub struct GroupKeyRing {
+    group_id: GroupId,
+    grace_window: Duration,
+    keys: BTreeMap<KeyGeneration, RingKey>,
+}
+
+impl GroupKeyRing {
+    /// Create a ring whose current key is `initial` at [`KeyGeneration::INITIAL`].
+    #[must_use]
+    pub fn new(
+        group_id: GroupId,
+        initial: GroupKey,
+        grace_window: Duration,
+        now: SystemTime,
+    ) -> Self {
+        let mut keys = BTreeMap::new();
+        keys.insert(
+            KeyGeneration::INITIAL,
+            RingKey {
+                key: Arc::new(initial),
+                installed_at: now,
+                replaced_at: None,
+            },
+        );
+        Self {
+            group_id,
+            grace_window,
+            keys,
+        }
+    }
+
+    #[must_use]
+    pub fn group_id(&self) -> GroupId {
+        self.group_id
+    }
+
+    /// Return the current generation and key.
+    #[must_use]
+    pub fn current(&self) -> (KeyGeneration, &Arc<GroupKey>) {
+        let (generation, entry) = self
+            .keys
+            .last_key_value()
+            .expect("a group key ring always holds its current key");
+        (*generation, &entry.key)
+    }
+
+    /// Add `key` as `generation`.
+    ///
+    /// A generation newer than the current one becomes the current key, and the previous current
+    /// key is replaced at `now`. An older generation that arrives late is kept as already
+    /// replaced at `now`. Installing a known generation again with the same key does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::SecurityError::KeyGenerationConflict`] if `generation` is already known
+    /// with a different key.
+    pub fn install(
+        &mut self,
+        generation: KeyGeneration,
+        key: GroupKey,
+        now: SystemTime,
+    ) -> Result<()> {
+        if let Some(existing) = self.keys.get(&generation) {
+            ensure!(
+                *existing.key == key,
+                KeyGenerationConflictSnafu {
+                    group_id: self.group_id,
+                    generation,
+                }
+            );
+            return Ok(());
+        }
+        let (current, _) = self.current();
+        let replaced_at = if generation > current {
+            if let Some(previous) = self.keys.get_mut(&current) {
+                previous.replaced_at = Some(now);
+            }
+            None
+        } else {
+            Some(now)
+        };
+        self.keys.insert(
+            generation,
+            RingKey {
+                key: Arc::new(key),
+                installed_at: now,
+                replaced_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// Return the key of `generation` for opening messages at `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::SecurityError::UnknownKeyGeneration`] if the ring never held the
+    /// generation or already pruned it, and [`crate::SecurityError::ExpiredKeyGeneration`] if
+    /// the key was replaced longer than the grace window ago.
+    pub fn key_for(&self, generation: KeyGeneration, now: SystemTime) -> Result<&Arc<GroupKey>> {
+        let entry = self
+            .keys
+            .get(&generation)
+            .context(UnknownKeyGenerationSnafu {
+                group_id: self.group_id,
+                generation,
+            })?;
+        ensure!(
+            !entry.is_expired(self.grace_window, now),
+            ExpiredKeyGenerationSnafu {
+                group_id: self.group_id,
+                generation,
+            }
+        );
+        Ok(&entry.key)
+    }
+
+    /// Whether the current key has been in use for at least `interval` at `now`.
+    #[must_use]
+    pub fn rotation_due(&self, interval: Duration, now: SystemTime) -> bool {
+        let (_, entry) = self
+            .keys
+            .last_key_value()
+            .expect("a group key ring always holds its current key");
+        now.duration_since(entry.installed_at)
+            .is_ok_and(|in_use| in_use >= interval)
+    }
+
+    /// Drop every replaced key whose grace window has passed at `now`.
+    ///
+    /// Returns the dropped generations, oldest first.
+    pub fn prune(&mut self, now: SystemTime) -> Vec<KeyGeneration> {
+        let expired: Vec<KeyGeneration> = self
+            .keys
+            .iter()
+            .filter(|(_, entry)| entry.is_expired(self.grace_window, now))
+            .map(|(generation, _)| *generation)
+            .collect();
+        for generation in &expired {
+            self.keys.remove(generation);
+        }
+        expired
+    }
+
+    /// Return every generation the ring holds, oldest first.
+    pub fn generations(&self) -> impl Iterator<Item = KeyGeneration> + '_ {
+        self.keys.keys().copied()
+    }
+}
+
+impl fmt::Debug for GroupKeyRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GroupKeyRing")
+            .field("group_id", &self.group_id)
+            .field("grace_window", &self.grace_window)
+            .field("generations", &self.keys.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// One key held by a [`GroupKeyRing`].
+struct RingKey {
+    key: Arc<GroupKey>,
+    installed_at: SystemTime,
+    /// When a newer generation replaced this key, or `None` for the current key.
+    replaced_at: Option<SystemTime>,
+}
+
+impl RingKey {
+    fn is_expired(&self, grace_window: Duration, now: SystemTime) -> bool {
+        self.replaced_at
+            .and_then(|replaced_at| replaced_at.checked_add(grace_window))
+            .is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Build the public metadata bound into every sealed key of one rotation.
+fn rotation_metadata(generation: KeyGeneration, reason: KeyRotationReason) -> [u8; 5] {
+    let mut metadata = [0u8; 5];
+    metadata[..4].copy_from_slice(&generation.as_u32().to_be_bytes());
+    metadata[4] = reason.as_u8();
+    metadata
+}
+
+/// Build the HPKE context sealing the key of one rotation for `recipient`.
+fn rotation_context<'a>(
+    sender: &'a MemberIdentity,
+    recipient: &'a MemberIdentity,
+    group_id: GroupId,
+    rotation_id: Uuid,
+    metadata: &'a [u8],
+) -> HpkeContext<'a> {
+    HpkeContext {
+        purpose: HpkeEnvelopePurpose::GroupKeyRotation,
+        sender,
+        recipient,
+        scope: HpkeEnvelopeScope::Group { group_id },
+        delivery_message_id: rotation_id,
+        authenticated_public_metadata: metadata,
+    }
+}
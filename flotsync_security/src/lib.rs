@@ -43,6 +43,15 @@ pub use identity::{
     local_member_keys_from_private_bundle,
     public_member_keys_from_public_bundle,
 };
+pub use key_rotation::{
+    GroupKeyRing,
+    KeyGeneration,
+    KeyRotation,
+    KeyRotationReason,
+    open_key_rotation,
+    reencrypt_group_message,
+    seal_key_rotation,
+};
 #[cfg(all(any(test, feature = "test-support"), feature = "local-secret-manager"))]
 pub use local_store_secret::install_local_store_secret_test_store;
 pub use local_store_secret::{
@@ -50,9 +59,17 @@ pub use local_store_secret::{
     LocalStoreSecretError,
     LocalStoreSecretProfile,
     LocalStoreSecretResult,
+    install_local_store_secret,
     load_local_store_secret,
     load_or_create_local_store_secret,
 };
+pub use passphrase::{
+    PASSPHRASE_NONCE_LENGTH,
+    PASSPHRASE_SALT_LENGTH,
+    PassphraseCiphertext,
+    open_with_passphrase,
+    seal_with_passphrase,
+};
 pub use reliable_payload::{
     ReliablePayloadContext,
     SealedHPKEPayload,
@@ -92,7 +109,9 @@ mod fingerprint;
 mod group;
 mod hpke;
 mod identity;
+mod key_rotation;
 mod local_store_secret;
+mod passphrase;
 mod reliable_payload;
 mod sealed_psk_payload;
 mod signature;
@@ -163,6 +163,31 @@ pub fn load_or_create_local_store_secret(
     }
 }
 
+/// Store a known secret in one application/profile slot, replacing any existing record.
+///
+/// This is meant for moving a store to another device together with the
+/// secret that protects it. Replacing a slot that other stores still use makes
+/// their encrypted cells unreadable.
+///
+/// # Errors
+///
+/// Returns [`LocalStoreSecretError`] if local secret storage cannot be accessed
+/// or written.
+pub fn install_local_store_secret(
+    application_id: &Identifier,
+    profile: &LocalStoreSecretProfile,
+    secret: &LoadedLocalStoreSecret,
+) -> LocalStoreSecretResult<()> {
+    let entry = local_store_secret_entry(application_id, profile)?;
+    let record = secret.encode_record();
+    entry
+        .set_secret(record.as_slice())
+        .with_context(|_| WriteSnafu {
+            application_id: application_id.clone(),
+            profile: profile.clone(),
+        })
+}
+
 /// Build the keyring entry address for one application/profile slot.
 fn local_store_secret_entry(
     application_id: &Identifier,
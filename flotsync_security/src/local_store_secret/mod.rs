@@ -17,9 +17,17 @@ mod unmanaged;
 #[cfg(all(any(test, feature = "test-support"), feature = "local-secret-manager"))]
 pub use manager::install_local_store_secret_test_store;
 #[cfg(feature = "local-secret-manager")]
-pub use manager::{load_local_store_secret, load_or_create_local_store_secret};
+pub use manager::{
+    install_local_store_secret,
+    load_local_store_secret,
+    load_or_create_local_store_secret,
+};
 #[cfg(not(feature = "local-secret-manager"))]
-pub use unmanaged::{load_local_store_secret, load_or_create_local_store_secret};
+pub use unmanaged::{
+    install_local_store_secret,
+    load_local_store_secret,
+    load_or_create_local_store_secret,
+};
 
 /// Result type for loading or creating the device-local store secret.
 pub type LocalStoreSecretResult<T> = std::result::Result<T, LocalStoreSecretError>;
@@ -78,6 +86,15 @@ pub struct LoadedLocalStoreSecret {
 }
 
 impl LoadedLocalStoreSecret {
+    /// Pair an existing key id with its secret key, e.g. when restoring a backup.
+    #[must_use]
+    pub fn new(key_id: StoreSecretKeyId, store_secret_key: StoreSecretKey) -> Self {
+        Self {
+            key_id,
+            store_secret_key,
+        }
+    }
+
     /// Return the generated key id stored next to encrypted replication cells.
     #[must_use]
     pub fn key_id(&self) -> StoreSecretKeyId {
@@ -89,6 +106,12 @@ impl LoadedLocalStoreSecret {
     pub fn into_parts(self) -> (StoreSecretKeyId, StoreSecretKey) {
         (self.key_id, self.store_secret_key)
     }
+
+    /// Return the secret key without consuming this value.
+    #[must_use]
+    pub fn store_secret_key(&self) -> &StoreSecretKey {
+        &self.store_secret_key
+    }
 }
 
 /// Errors from application-local store-secret loading and first-run creation.
@@ -31,3 +31,16 @@ pub fn load_or_create_local_store_secret(
 ) -> LocalStoreSecretResult<LoadedLocalStoreSecret> {
     LocalSecretManagerUnavailableSnafu.fail()
 }
+
+/// Report unavailable local secret-manager support in feature-minimal builds.
+///
+/// # Errors
+///
+/// Always returns [`LocalStoreSecretError::LocalSecretManagerUnavailable`](super::LocalStoreSecretError::LocalSecretManagerUnavailable).
+pub fn install_local_store_secret(
+    _application_id: &Identifier,
+    _profile: &LocalStoreSecretProfile,
+    _secret: &LoadedLocalStoreSecret,
+) -> LocalStoreSecretResult<()> {
+    LocalSecretManagerUnavailableSnafu.fail()
+}
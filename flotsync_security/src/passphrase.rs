@@ -0,0 +1,114 @@
+//! Passphrase-based encryption for data that leaves the device, e.g. backups.
+
+use crate::error::{PassphraseKeyDerivationSnafu, RandomnessSnafu, Result, SecurityError};
+use argon2::Argon2;
+use chacha20poly1305::{
+    Key,
+    KeyInit,
+    XChaCha20Poly1305,
+    XNonce,
+    aead::{Aead, Payload},
+};
+use rand_core::{OsRng, TryRngCore};
+use snafu::prelude::*;
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// Byte length of the random Argon2id salt stored next to passphrase ciphertexts.
+pub const PASSPHRASE_SALT_LENGTH: usize = 16;
+/// Byte length of the random XChaCha20-Poly1305 nonce stored next to passphrase ciphertexts.
+pub const PASSPHRASE_NONCE_LENGTH: usize = 24;
+/// Byte length of the key derived from a passphrase.
+const PASSPHRASE_KEY_LENGTH: usize = 32;
+
+/// Ciphertext produced by [`seal_with_passphrase`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct PassphraseCiphertext {
+    /// Random salt used to derive the encryption key from the passphrase.
+    pub salt: [u8; PASSPHRASE_SALT_LENGTH],
+    /// Random nonce used for the encryption.
+    pub nonce: [u8; PASSPHRASE_NONCE_LENGTH],
+    /// Ciphertext including the AEAD authentication tag.
+    pub ciphertext: Vec<u8>,
+}
+
+impl fmt::Debug for PassphraseCiphertext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PassphraseCiphertext")
+            .field("ciphertext_len", &self.ciphertext.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Encrypt `plaintext` under a key derived from `passphrase` with Argon2id.
+///
+/// `aad` is authenticated but not encrypted, and must be passed unchanged to
+/// [`open_with_passphrase`].
+///
+/// # Errors
+///
+/// Returns [`SecurityError::Randomness`] if salt or nonce generation fails,
+/// [`SecurityError::PassphraseKeyDerivation`] if key derivation fails, or
+/// [`SecurityError::PassphraseSeal`] if the AEAD rejects the request.
+pub fn seal_with_passphrase(
+    passphrase: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<PassphraseCiphertext> {
+    let mut salt = [0u8; PASSPHRASE_SALT_LENGTH];
+    OsRng.try_fill_bytes(&mut salt).context(RandomnessSnafu)?;
+    let mut nonce = [0u8; PASSPHRASE_NONCE_LENGTH];
+    OsRng.try_fill_bytes(&mut nonce).context(RandomnessSnafu)?;
+    let key = derive_passphrase_key(passphrase, &salt)?;
+    let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| SecurityError::PassphraseSeal)?;
+    Ok(PassphraseCiphertext {
+        salt,
+        nonce,
+        ciphertext,
+    })
+}
+
+/// Decrypt and authenticate data sealed by [`seal_with_passphrase`].
+///
+/// # Errors
+///
+/// Returns [`SecurityError::PassphraseKeyDerivation`] if key derivation fails,
+/// or [`SecurityError::PassphraseOpen`] if the passphrase, associated data, or
+/// ciphertext do not authenticate together.
+pub fn open_with_passphrase(
+    passphrase: &[u8],
+    aad: &[u8],
+    sealed: &PassphraseCiphertext,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let key = derive_passphrase_key(passphrase, &sealed.salt)?;
+    XChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+        .decrypt(
+            XNonce::from_slice(&sealed.nonce),
+            Payload {
+                msg: &sealed.ciphertext,
+                aad,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| SecurityError::PassphraseOpen)
+}
+
+/// Stretch a passphrase into an AEAD key with the default Argon2id parameters.
+fn derive_passphrase_key(
+    passphrase: &[u8],
+    salt: &[u8; PASSPHRASE_SALT_LENGTH],
+) -> Result<Zeroizing<[u8; PASSPHRASE_KEY_LENGTH]>> {
+    let mut key = Zeroizing::new([0u8; PASSPHRASE_KEY_LENGTH]);
+    Argon2::default()
+        .hash_password_into(passphrase, salt, key.as_mut_slice())
+        .context(PassphraseKeyDerivationSnafu)?;
+    Ok(key)
+}
@@ -71,6 +71,14 @@ impl StoreSecretKey {
         Ok(key)
     }
 
+    /// Copy the secret key bytes out, e.g. to export them into an encrypted backup.
+    ///
+    /// Callers are responsible for never persisting the copy unprotected.
+    #[must_use]
+    pub fn to_bytes(&self) -> Zeroizing<[u8; STORE_SECRET_KEY_LENGTH]> {
+        Zeroizing::new(self.bytes)
+    }
+
     /// Return the secret key bytes for security-crate record encoding and AEAD setup.
     #[must_use]
     pub(crate) fn as_bytes(&self) -> &[u8] {
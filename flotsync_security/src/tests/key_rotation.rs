@@ -0,0 +1,156 @@
+//! Tests for group key rotation, key rings, and re-encryption.
+
+use super::{fixtures::*, *};
+use std::time::{Duration, SystemTime};
+
+const GRACE_WINDOW: Duration = Duration::from_secs(600);
+
+fn rotation_group() -> GroupId {
+    GroupId(Uuid::from_u128(0x700))
+}
+
+fn start() -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000)
+}
+
+#[test]
+fn rotated_key_only_opens_for_remaining_members() {
+    let alice = local_member("alice", ALICE_SEED);
+    let bob = local_member("bob", BOB_SEED);
+    let new_key = GroupKey::from_bytes([7; GROUP_KEY_LENGTH]);
+    let mut rng = rng_from_seed([9u8; 32]);
+
+    let rotation = seal_key_rotation(
+        &alice,
+        rotation_group(),
+        KeyGeneration::INITIAL.next(),
+        KeyRotationReason::MemberRemoved,
+        &new_key,
+        [alice.public_keys()],
+        &mut rng,
+    )
+    .unwrap();
+
+    assert!(rotation.includes(alice.member_id()));
+    assert!(!rotation.includes(bob.member_id()));
+    let opened = open_key_rotation(&alice, alice.member_id(), &rotation).unwrap();
+    assert_eq!(opened, new_key);
+    let err = open_key_rotation(&bob, alice.member_id(), &rotation).unwrap_err();
+    assert!(matches!(
+        err,
+        SecurityError::MissingKeyRotationRecipient { .. }
+    ));
+}
+
+#[test]
+fn rotated_key_does_not_open_with_changed_generation() {
+    let alice = local_member("alice", ALICE_SEED);
+    let new_key = GroupKey::from_bytes([7; GROUP_KEY_LENGTH]);
+    let mut rng = rng_from_seed([9u8; 32]);
+    let mut rotation = seal_key_rotation(
+        &alice,
+        rotation_group(),
+        KeyGeneration::new(1),
+        KeyRotationReason::Scheduled,
+        &new_key,
+        [alice.public_keys()],
+        &mut rng,
+    )
+    .unwrap();
+
+    rotation.generation = KeyGeneration::new(2);
+
+    let err = open_key_rotation(&alice, alice.member_id(), &rotation).unwrap_err();
+    assert!(matches!(err, SecurityError::HpkeOpen { .. }));
+}
+
+#[test]
+fn replaced_keys_open_messages_until_the_grace_window_passes() {
+    let mut ring = GroupKeyRing::new(
+        rotation_group(),
+        GroupKey::from_bytes([1; GROUP_KEY_LENGTH]),
+        GRACE_WINDOW,
+        start(),
+    );
+    let rotated_at = start() + Duration::from_secs(60);
+    ring.install(
+        KeyGeneration::new(1),
+        GroupKey::from_bytes([2; GROUP_KEY_LENGTH]),
+        rotated_at,
+    )
+    .unwrap();
+
+    assert_eq!(ring.current().0, KeyGeneration::new(1));
+    assert!(
+        ring.key_for(
+            KeyGeneration::INITIAL,
+            rotated_at + GRACE_WINDOW - Duration::from_secs(1)
+        )
+        .is_ok()
+    );
+    let expired_at = rotated_at + GRACE_WINDOW;
+    assert!(matches!(
+        ring.key_for(KeyGeneration::INITIAL, expired_at),
+        Err(SecurityError::ExpiredKeyGeneration { .. })
+    ));
+    assert!(ring.key_for(KeyGeneration::new(1), expired_at).is_ok());
+
+    assert_eq!(ring.prune(expired_at), vec![KeyGeneration::INITIAL]);
+    assert!(matches!(
+        ring.key_for(KeyGeneration::INITIAL, expired_at),
+        Err(SecurityError::UnknownKeyGeneration { .. })
+    ));
+    assert!(matches!(
+        ring.install(
+            KeyGeneration::new(1),
+            GroupKey::from_bytes([3; GROUP_KEY_LENGTH]),
+            expired_at,
+        ),
+        Err(SecurityError::KeyGenerationConflict { .. })
+    ));
+}
+
+#[test]
+fn rotation_is_due_once_the_current_key_is_old_enough() {
+    let interval = Duration::from_secs(3600);
+    let mut ring = GroupKeyRing::new(
+        rotation_group(),
+        GroupKey::from_bytes([1; GROUP_KEY_LENGTH]),
+        GRACE_WINDOW,
+        start(),
+    );
+    assert!(!ring.rotation_due(interval, start() + interval - Duration::from_secs(1)));
+    assert!(ring.rotation_due(interval, start() + interval));
+
+    ring.install(
+        KeyGeneration::new(1),
+        GroupKey::from_bytes([2; GROUP_KEY_LENGTH]),
+        start() + interval,
+    )
+    .unwrap();
+    assert!(!ring.rotation_due(interval, start() + interval));
+}
+
+#[test]
+fn reencrypted_messages_open_only_under_the_new_key() {
+    let alice = local_member("alice", ALICE_SEED);
+    let old_key = GroupKey::from_bytes([1; GROUP_KEY_LENGTH]);
+    let new_key = GroupKey::from_bytes([2; GROUP_KEY_LENGTH]);
+    let context = GroupMessageContext {
+        group_id: rotation_group().0,
+        frame_kind: "snapshot-chunk",
+        sender: alice.member_id(),
+        message_id: Uuid::from_u128(0x701),
+    };
+    let sealed = seal_group_message(&old_key, context, PUBLIC_HEADER, b"snapshot").unwrap();
+
+    let reencrypted =
+        reencrypt_group_message(&old_key, &new_key, context, PUBLIC_HEADER, &sealed).unwrap();
+
+    let opened = open_group_message(&new_key, context, PUBLIC_HEADER, &reencrypted).unwrap();
+    assert_eq!(opened.as_ref(), b"snapshot");
+    assert!(matches!(
+        open_group_message(&old_key, context, PUBLIC_HEADER, &reencrypted),
+        Err(SecurityError::GroupOpen)
+    ));
+}
@@ -4,6 +4,7 @@ use crate::{
     GROUP_CIPHER_SUITE_CHACHA20_POLY1305,
     GROUP_KEY_LENGTH,
     GroupKey,
+    GroupKeyRing,
     GroupMessageContext,
     HpkeCiphertext,
     HpkeContext,
@@ -12,6 +13,8 @@ use crate::{
     KEY_FINGERPRINT_LENGTH,
     KeyFingerprint,
     KeyFingerprintParseError,
+    KeyGeneration,
+    KeyRotationReason,
     LocalMemberKeys,
     LocalStoreSecretError,
     LocalStoreSecretProfile,
@@ -28,19 +31,25 @@ use crate::{
     hpke_open,
     hpke_seal,
     identity::{MEMBER_KEY_SEED_LENGTH, generate_member_key_bundles_from_seed},
+    install_local_store_secret,
     install_local_store_secret_test_store,
     load_local_store_secret,
     load_or_create_local_store_secret,
     local_member_keys_from_private_bundle,
     open_group_message,
     open_group_payload,
+    open_key_rotation,
     open_reliable_payload,
     open_store_secret,
+    open_with_passphrase,
     public_member_keys_from_public_bundle,
+    reencrypt_group_message,
     seal_group_message,
     seal_group_payload,
+    seal_key_rotation,
     seal_reliable_payload,
     seal_store_secret_for_test,
+    seal_with_passphrase,
     sign_frame,
     test_support::rng_from_seed,
     verify_frame_signature,
@@ -63,6 +72,7 @@ use uuid::Uuid;
 mod fixtures;
 mod group_frames;
 mod hpke;
+mod key_rotation;
 mod keys;
 mod reliable;
 mod store_secret;
@@ -1,4 +1,4 @@
-//! Tests for encrypted store secrets and local keyring profiles.
+//! Tests for encrypted store secrets, local keyring profiles, and passphrase sealing.
 
 use super::{fixtures::*, *};
 
@@ -105,6 +105,38 @@ fn local_store_secret_load_reports_missing_profile() {
     assert!(matches!(err, LocalStoreSecretError::Missing { .. }));
 }
 
+#[test]
+fn local_store_secret_install_replaces_profile_record() {
+    install_local_store_secret_test_store().unwrap();
+    let application_id = Identifier::from_array(["flotsync", "security", "tests"]);
+    let source_profile = unique_local_store_secret_profile("install-source");
+    let target_profile = unique_local_store_secret_profile("install-target");
+
+    let source = load_or_create_local_store_secret(&application_id, &source_profile).unwrap();
+    load_or_create_local_store_secret(&application_id, &target_profile).unwrap();
+    install_local_store_secret(&application_id, &target_profile, &source).unwrap();
+
+    let installed = load_local_store_secret(&application_id, &target_profile).unwrap();
+    assert_eq!(installed.key_id(), source.key_id());
+    assert_eq!(
+        *installed.store_secret_key().to_bytes(),
+        *source.store_secret_key().to_bytes()
+    );
+}
+
+#[test]
+fn passphrase_ciphertext_opens_only_with_matching_passphrase_and_aad() {
+    let sealed = seal_with_passphrase(b"correct horse", b"backup-v1", b"archive body").unwrap();
+
+    let opened = open_with_passphrase(b"correct horse", b"backup-v1", &sealed).unwrap();
+    assert_eq!(opened.as_slice(), b"archive body");
+
+    let err = open_with_passphrase(b"wrong horse", b"backup-v1", &sealed).unwrap_err();
+    assert!(matches!(err, SecurityError::PassphraseOpen));
+    let err = open_with_passphrase(b"correct horse", b"backup-v2", &sealed).unwrap_err();
+    assert!(matches!(err, SecurityError::PassphraseOpen));
+}
+
 #[test]
 fn local_store_secret_profile_rejects_empty_selector() {
     let err = LocalStoreSecretProfile::new("  ").unwrap_err();
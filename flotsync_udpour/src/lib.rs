@@ -157,7 +157,6 @@
 //! - it emits fully reassembled inbound deliveries
 //! - it surfaces route-transport send failures separately from higher-level
 //!   semantic-delivery acknowledgments
-#![feature(deque_extend_front)]
 
 mod codec;
 mod receiver;
@@ -172,6 +171,7 @@ mod wire;
 pub use crate::{
     receiver::ReceiverConfig,
     runtime::{
+        TrafficClass,
         UDPourComponent,
         UDPourComponentMessage,
         UDPourConfig,
@@ -184,6 +184,7 @@ pub use crate::{
         UDPourStateFailure,
         UDPourSubmitResult,
         config_keys,
+        fault_points,
     },
     sender::SenderConfig,
     types::{Checksum, MessageId, PartCount},
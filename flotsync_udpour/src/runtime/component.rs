@@ -194,10 +194,17 @@ impl UDPourComponent {
             .ctx
             .config()
             .read_or_default_warn(self.log(), &config_keys::MAX_IN_FLIGHT_DATAGRAMS);
+        let lane_weights = [
+            config_keys::INTERACTIVE_WEIGHT,
+            config_keys::SNAPSHOT_WEIGHT,
+            config_keys::BLOB_WEIGHT,
+        ]
+        .map(|key| self.ctx.config().read_or_default_warn(self.log(), &key));
         UDPourSendRateControl {
             send_delay,
             backpressure_retry_delay,
             max_in_flight_datagrams,
+            lane_weights,
         }
     }
 
@@ -246,10 +253,11 @@ impl UDPourComponent {
         }
 
         self.clear_dispatch_timer();
+        let lane_weights = self.dispatcher.send_rate.lane_weights;
         let datagram = self
             .dispatcher
             .queue
-            .pop_front()
+            .pop_next(&lane_weights)
             .expect("queue emptiness was checked before dispatch");
         self.dispatcher.dispatch_in_progress = true;
         let socket_id = self.socket_id;
@@ -308,6 +316,16 @@ impl UDPourComponent {
             async_self.dispatcher.dispatch_in_progress = false;
             async_self.dispatcher.next_send_allowed_at =
                 Some(async_self.now() + async_self.dispatcher.send_rate.send_delay);
+            if fault_injection::fails(fault_points::SEND_DATAGRAM) {
+                // Report the injected failure through the same path as a driver rejection.
+                reply_to.tell(UdpSendResult::Nack {
+                    socket_id,
+                    transmission_id,
+                    reason: SendFailureReason::IoError,
+                });
+                async_self.try_dispatch_outbound();
+                return Handled::OK;
+            }
             async_self.udp_port.trigger(UdpRequest::Send {
                 socket_id,
                 transmission_id,
@@ -322,6 +340,7 @@ impl UDPourComponent {
 
     fn set_dispatch_timer(&mut self, delay: Duration) {
         self.clear_dispatch_timer();
+        let delay = fault_injection::delayed(fault_points::DISPATCH_TIMER, delay);
         let timer = self.schedule_once(delay, Self::handle_dispatch_timeout);
         self.dispatcher.dispatch_timer = Some(timer);
     }
@@ -363,6 +382,7 @@ impl UDPourComponent {
                     message_id,
                     OutboundTransfer {
                         target: send.target,
+                        traffic_class: send.traffic_class,
                         submit_promise: Some(promise),
                         failure_reported: false,
                         sent_reported: false,
@@ -603,9 +623,10 @@ impl UDPourComponent {
             return;
         };
         let target = outbound.target;
-        let datagrams = frames
-            .into_iter()
-            .map(|frame| QueuedDatagram::payload(target, frame, counts_towards_sent));
+        let traffic_class = outbound.traffic_class;
+        let datagrams = frames.into_iter().map(|frame| {
+            QueuedDatagram::payload(target, frame, counts_towards_sent, traffic_class)
+        });
         if counts_towards_sent {
             self.enqueue_back_all(datagrams);
         } else {
@@ -692,11 +713,12 @@ impl UDPourComponent {
 
     fn set_poll_timer(&mut self) {
         self.clear_poll_timer();
+        let delay = fault_injection::delayed(fault_points::POLL_TIMER, self.config.poll_interval);
         #[cfg(test)]
         {
-            self.next_poll_at = Some(self.now() + self.config.poll_interval);
+            self.next_poll_at = Some(self.now() + delay);
         }
-        let timer = self.schedule_once(self.config.poll_interval, Self::handle_poll_timeout);
+        let timer = self.schedule_once(delay, Self::handle_poll_timeout);
         self.poll_timer = Some(timer);
     }
 
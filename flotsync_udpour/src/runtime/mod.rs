@@ -23,7 +23,7 @@ use crate::{
     wire::EncodeToBufMut,
 };
 use flotsync_io::prelude::*;
-use flotsync_utils::{ResultExt as _, kompact_config::ConfigReadExt as _};
+use flotsync_utils::{ResultExt as _, fault_injection, kompact_config::ConfigReadExt as _};
 use kompact::{
     config::{DurationValue, UsizeValue},
     kompact_config,
@@ -46,6 +46,7 @@ mod tests;
 
 pub use component::{UDPourComponent, UDPourComponentMessage};
 pub use public::{
+    TrafficClass,
     UDPourConfig,
     UDPourConfigError,
     UDPourDeliver,
@@ -56,6 +57,7 @@ pub use public::{
     UDPourStateFailure,
     UDPourSubmitResult,
     config_keys,
+    fault_points,
 };
 
 #[cfg(test)]
@@ -9,6 +9,39 @@ pub struct UDPourSend {
     pub target: SocketAddr,
     /// Full logical payload to split into multipart `Payload` frames.
     pub payload: IoPayload,
+    /// Scheduling lane for this transfer's datagrams on the shared socket.
+    pub traffic_class: TrafficClass,
+}
+
+/// Scheduling lane of one outbound transfer.
+///
+/// Each socket keeps one queue per class and serves them by weighted round-robin, so a large
+/// bulk transfer to one peer cannot hold back small interactive messages to another. Protocol
+/// control frames bypass the lanes and are always sent first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TrafficClass {
+    /// Small latency-sensitive messages, such as live edits and acknowledgements.
+    #[default]
+    Interactive,
+    /// Bulk synchronisation of existing state, such as catch-up batches and snapshot chunks.
+    Snapshot,
+    /// Large binary attachment transfers.
+    Blob,
+}
+
+impl TrafficClass {
+    /// Every class, in the order the dispatcher visits their lanes.
+    pub const ALL: [Self; 3] = [Self::Interactive, Self::Snapshot, Self::Blob];
+}
+
+impl fmt::Display for TrafficClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Interactive => write!(f, "interactive"),
+            Self::Snapshot => write!(f, "snapshot"),
+            Self::Blob => write!(f, "blob"),
+        }
+    }
 }
 
 /// One fully reassembled inbound logical message.
@@ -156,6 +189,52 @@ pub mod config_keys {
         doc = "Maximum number of UDPour datagrams that may be in flight on one socket at once.",
         version = "0.1.0"
     }
+
+    kompact_config! {
+        INTERACTIVE_WEIGHT,
+        key = "flotsync.udpour.interactive-weight",
+        type = UsizeValue,
+        default = 8,
+        validate = |value| *value > 0,
+        doc = "Datagrams sent from the interactive lane per weighted round-robin round.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        SNAPSHOT_WEIGHT,
+        key = "flotsync.udpour.snapshot-weight",
+        type = UsizeValue,
+        default = 2,
+        validate = |value| *value > 0,
+        doc = "Datagrams sent from the snapshot lane per weighted round-robin round.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        BLOB_WEIGHT,
+        key = "flotsync.udpour.blob-weight",
+        type = UsizeValue,
+        default = 1,
+        validate = |value| *value > 0,
+        doc = "Datagrams sent from the blob lane per weighted round-robin round.",
+        version = "0.1.0"
+    }
+}
+
+/// Names of the `UDPour` runtime fault points, see [`flotsync_utils::fault_injection`].
+pub mod fault_points {
+    /// Reached before each datagram is handed to the UDP socket.
+    ///
+    /// Honours `Fail`, which reports the send as rejected by the operating system.
+    pub const SEND_DATAGRAM: &str = "udpour.send-datagram";
+    /// Reached whenever the dispatch timer that paces outbound datagrams is scheduled.
+    ///
+    /// Honours `Delay`.
+    pub const DISPATCH_TIMER: &str = "udpour.dispatch-timer";
+    /// Reached whenever the timer that polls sender and receiver timeouts is scheduled.
+    ///
+    /// Honours `Delay`.
+    pub const POLL_TIMER: &str = "udpour.poll-timer";
 }
 
 /// Runtime configuration for the `UDPour` component.
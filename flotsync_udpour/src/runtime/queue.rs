@@ -20,6 +20,8 @@ pub(in crate::runtime) struct UDPourSendRateControl {
     pub(in crate::runtime) send_delay: Duration,
     pub(in crate::runtime) backpressure_retry_delay: Duration,
     pub(in crate::runtime) max_in_flight_datagrams: usize,
+    /// Datagrams served from each traffic lane per round, indexed like [`TrafficClass::ALL`].
+    pub(in crate::runtime) lane_weights: [usize; TRAFFIC_LANE_COUNT],
 }
 
 impl Default for UDPourSendRateControl {
@@ -34,6 +36,17 @@ impl Default for UDPourSendRateControl {
             max_in_flight_datagrams: config_keys::MAX_IN_FLIGHT_DATAGRAMS
                 .default()
                 .expect("UDPour max-in-flight-datagrams key must define a default"),
+            lane_weights: [
+                config_keys::INTERACTIVE_WEIGHT
+                    .default()
+                    .expect("UDPour interactive-weight key must define a default"),
+                config_keys::SNAPSHOT_WEIGHT
+                    .default()
+                    .expect("UDPour snapshot-weight key must define a default"),
+                config_keys::BLOB_WEIGHT
+                    .default()
+                    .expect("UDPour blob-weight key must define a default"),
+            ],
         }
     }
 }
@@ -43,7 +56,7 @@ impl Default for UDPourSendRateControl {
 pub(in crate::runtime) struct OutboundDispatcherState {
     pub(in crate::runtime) send_rate: UDPourSendRateControl,
     /// Datagrams still waiting for one physical UDP send attempt.
-    pub(in crate::runtime) queue: VecDeque<QueuedDatagram>,
+    pub(in crate::runtime) queue: OutboundLanes,
     /// True while one spawned async task is encoding and dispatching the next queue head.
     pub(in crate::runtime) dispatch_in_progress: bool,
     /// Earliest time at which the next UDP send attempt may start once the component is live.
@@ -59,7 +72,7 @@ impl OutboundDispatcherState {
     pub(in crate::runtime) fn new() -> Self {
         Self {
             send_rate: UDPourSendRateControl::default(),
-            queue: VecDeque::new(),
+            queue: OutboundLanes::default(),
             dispatch_in_progress: false,
             next_send_allowed_at: None,
             dispatch_timer: None,
@@ -67,10 +80,114 @@ impl OutboundDispatcherState {
     }
 }
 
+/// Number of payload lanes, one per [`TrafficClass`].
+pub(in crate::runtime) const TRAFFIC_LANE_COUNT: usize = TrafficClass::ALL.len();
+
+/// Outbound datagrams waiting for a send attempt, split into one FIFO lane per traffic class.
+///
+/// Control frames are served first, since they are small and unblock the peer's repair and
+/// retention state. Payload lanes are served by weighted round-robin: the current lane may send
+/// up to its weight in datagrams before the next non-empty lane takes over, so no lane starves
+/// and an interactive message waits behind at most a few bulk datagrams.
+#[derive(Debug, Default)]
+pub(in crate::runtime) struct OutboundLanes {
+    control: VecDeque<QueuedDatagram>,
+    lanes: [VecDeque<QueuedDatagram>; TRAFFIC_LANE_COUNT],
+    /// Lane whose turn it currently is.
+    current_lane: usize,
+    /// Datagrams already sent from `current_lane` during its turn.
+    served_in_turn: usize,
+}
+
+impl OutboundLanes {
+    pub(in crate::runtime) fn is_empty(&self) -> bool {
+        self.control.is_empty() && self.lanes.iter().all(VecDeque::is_empty)
+    }
+
+    pub(in crate::runtime) fn push_back(&mut self, datagram: QueuedDatagram) {
+        self.lane_mut(datagram.kind).push_back(datagram);
+    }
+
+    /// Put `datagram` at the head of its lane, e.g. to retry it after backpressure.
+    pub(in crate::runtime) fn push_front(&mut self, datagram: QueuedDatagram) {
+        self.lane_mut(datagram.kind).push_front(datagram);
+    }
+
+    /// Put `datagrams` at the head of their lanes, keeping their relative order.
+    pub(in crate::runtime) fn prepend<I>(&mut self, datagrams: I)
+    where
+        I: DoubleEndedIterator<Item = QueuedDatagram>,
+    {
+        for datagram in datagrams.rev() {
+            self.push_front(datagram);
+        }
+    }
+
+    /// Take the next datagram to send, using `weights` for the payload lanes' turns.
+    pub(in crate::runtime) fn pop_next(
+        &mut self,
+        weights: &[usize; TRAFFIC_LANE_COUNT],
+    ) -> Option<QueuedDatagram> {
+        if let Some(datagram) = self.control.pop_front() {
+            return Some(datagram);
+        }
+        // A lane that is empty or has used up its turn hands over to the next one, so at most
+        // one full rotation plus the starting lane has to be visited.
+        for _ in 0..=TRAFFIC_LANE_COUNT {
+            let lane = &mut self.lanes[self.current_lane];
+            if !lane.is_empty() && self.served_in_turn < weights[self.current_lane] {
+                self.served_in_turn += 1;
+                return lane.pop_front();
+            }
+            self.current_lane = (self.current_lane + 1) % TRAFFIC_LANE_COUNT;
+            self.served_in_turn = 0;
+        }
+        None
+    }
+
+    pub(in crate::runtime) fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&QueuedDatagram) -> bool,
+    {
+        self.control.retain(&mut keep);
+        for lane in &mut self.lanes {
+            lane.retain(&mut keep);
+        }
+    }
+
+    pub(in crate::runtime) fn clear(&mut self) {
+        self.control.clear();
+        for lane in &mut self.lanes {
+            lane.clear();
+        }
+    }
+
+    fn lane_mut(&mut self, kind: QueuedDatagramKind) -> &mut VecDeque<QueuedDatagram> {
+        match kind {
+            QueuedDatagramKind::Payload { traffic_class, .. } => {
+                &mut self.lanes[lane_index(traffic_class)]
+            }
+            QueuedDatagramKind::Control => &mut self.control,
+        }
+    }
+}
+
+impl Extend<QueuedDatagram> for OutboundLanes {
+    fn extend<I>(&mut self, datagrams: I)
+    where
+        I: IntoIterator<Item = QueuedDatagram>,
+    {
+        for datagram in datagrams {
+            self.push_back(datagram);
+        }
+    }
+}
+
 #[doc(hidden)]
 #[derive(Debug)]
 pub(in crate::runtime) struct OutboundTransfer {
     pub(in crate::runtime) target: SocketAddr,
+    pub(in crate::runtime) traffic_class: TrafficClass,
     pub(in crate::runtime) submit_promise: Option<KPromise<UDPourSubmitResult>>,
     pub(in crate::runtime) failure_reported: bool,
     pub(in crate::runtime) sent_reported: bool,
@@ -79,7 +196,10 @@ pub(in crate::runtime) struct OutboundTransfer {
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(in crate::runtime) enum QueuedDatagramKind {
-    Payload { counts_towards_sent: bool },
+    Payload {
+        counts_towards_sent: bool,
+        traffic_class: TrafficClass,
+    },
     Control,
 }
 
@@ -95,12 +215,14 @@ impl QueuedDatagram {
         target: SocketAddr,
         frame: PayloadFrame,
         counts_towards_sent: bool,
+        traffic_class: TrafficClass,
     ) -> Self {
         Self {
             target,
             frame: UDPourFrame::Payload(frame),
             kind: QueuedDatagramKind::Payload {
                 counts_towards_sent,
+                traffic_class,
             },
         }
     }
@@ -125,7 +247,8 @@ impl QueuedDatagram {
         matches!(
             self.kind,
             QueuedDatagramKind::Payload {
-                counts_towards_sent: true
+                counts_towards_sent: true,
+                ..
             }
         )
     }
@@ -227,3 +350,11 @@ pub(in crate::runtime) fn classify_encode_error(error: &RuntimeEncodeError) -> U
         RuntimeEncodeError::EmptyEncodedFrame => UDPourEncodeFailure::EmptyEncodedFrame,
     }
 }
+
+fn lane_index(traffic_class: TrafficClass) -> usize {
+    match traffic_class {
+        TrafficClass::Interactive => 0,
+        TrafficClass::Snapshot => 1,
+        TrafficClass::Blob => 2,
+    }
+}
@@ -1,6 +1,7 @@
 //! Tests for the `UDPour` runtime adapter.
 
-use super::*;
+use super::{queue::*, *};
+use crate::types::{Checksum, FrameType, PartCount, PartNumber};
 
 use std::num::NonZeroUsize;
 
@@ -26,3 +27,38 @@ fn rejects_need_parts_frame_budget_that_cannot_fit_a_header() {
         }
     ));
 }
+
+#[test]
+fn outbound_lanes_serve_control_first_and_payload_lanes_by_weight() {
+    let target: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let part_count = PartCount::new(1).unwrap();
+    let datagram = |message_id: u32, traffic_class: TrafficClass| {
+        let frame = PayloadFrame {
+            header: UDPourHeader::payload(
+                MessageId(message_id),
+                PartNumber(0),
+                part_count,
+                Checksum(0),
+            ),
+            payload: IoPayload::from_static(b"x"),
+        };
+        QueuedDatagram::payload(target, frame, true, traffic_class)
+    };
+    let ack = AckFrame {
+        header: UDPourHeader::control(FrameType::Ack, MessageId(1), part_count, Checksum(0))
+            .unwrap(),
+    };
+
+    let mut lanes = OutboundLanes::default();
+    lanes.extend((0..4).map(|id| datagram(100 + id, TrafficClass::Snapshot)));
+    lanes.extend((0..3).map(|id| datagram(200 + id, TrafficClass::Interactive)));
+    lanes.push_back(datagram(300, TrafficClass::Blob));
+    lanes.push_back(QueuedDatagram::control(target, UDPourFrame::Ack(ack)));
+
+    let mut order = Vec::new();
+    while let Some(datagram) = lanes.pop_next(&[2, 1, 1]) {
+        order.push(datagram.message_id().0);
+    }
+    assert_eq!(order, vec![1, 200, 201, 100, 300, 202, 101, 102, 103]);
+    assert!(lanes.is_empty());
+}
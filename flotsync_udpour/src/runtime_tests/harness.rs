@@ -313,7 +313,14 @@ impl RuntimeHarness {
     ) -> KFuture<UDPourSubmitResult> {
         let target = self.receiver_addr;
         self.sender_runtime_ref.ask_with(|promise| {
-            UDPourComponentMessage::Submit(Ask::new(promise, UDPourSend { target, payload }))
+            UDPourComponentMessage::Submit(Ask::new(
+                promise,
+                UDPourSend {
+                    target,
+                    payload,
+                    traffic_class: TrafficClass::Interactive,
+                },
+            ))
         })
     }
 
@@ -6,6 +6,7 @@ use crate::{
     runtime::{
         ActiveTimerSnapshot,
         RuntimeTimerSnapshot,
+        TrafficClass,
         UDPourComponent,
         UDPourComponentMessage,
         UDPourConfig,
@@ -201,3 +201,34 @@ fn socket_close_fails_submit_while_datagrams_are_still_queued() {
     );
     harness.shutdown();
 }
+
+#[cfg(feature = "fault-injection")]
+#[test]
+fn injected_send_failure_is_reported_like_a_driver_rejection() {
+    use crate::fault_points;
+    use flotsync_utils::fault_injection::{FaultAction, FaultScenario};
+
+    let scenario = FaultScenario::setup();
+    scenario.configure_times(fault_points::SEND_DATAGRAM, FaultAction::Fail, 1);
+    let harness = RuntimeHarness::new(
+        ProxyRequestBehavior::Pass,
+        ProxyIndicationBehavior::Pass,
+        ProxyRequestBehavior::Pass,
+        ProxyIndicationBehavior::Pass,
+        DEFAULT_SENDER_CONFIG,
+        DEFAULT_RECEIVER_CONFIG,
+    );
+
+    assert_eq!(
+        harness.send(IoPayload::from_static(b"x")),
+        UDPourSubmitResult::SendFailed {
+            reason: UDPourSendFailureReason::Transport(SendFailureReason::IoError),
+        }
+    );
+    assert_eq!(scenario.hits(fault_points::SEND_DATAGRAM), 1);
+    assert_eq!(
+        harness.send(IoPayload::from_static(b"y")),
+        UDPourSubmitResult::Sent
+    );
+    harness.shutdown();
+}
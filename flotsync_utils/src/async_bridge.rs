@@ -0,0 +1,347 @@
+//! Bridges between Kompact actors and executor-agnostic async code.
+//!
+//! Kompact components are driven by message handlers, while a lot of I/O-facing
+//! code is written as plain futures and streams. The helpers here connect the two
+//! without tying either side to a particular async executor:
+//!
+//! - [`forward_stream_to_actor`] drives any [`Stream`] and delivers its items to a
+//!   Kompact actor, so an async service can feed a component.
+//! - [`bridge_channel`] gives a component a non-blocking [`BridgeSender`] whose
+//!   items can be awaited from a [`BridgeReceiver`] on any executor, so a component
+//!   can feed an async service.
+//! - [`AsyncServiceComponent`] hosts an [`AsyncService`] inside a Kompact component,
+//!   answering each [`Ask`] by running the service's future on the system's executor pool.
+//! - [`ActorService`] goes the other way and exposes an actor that answers [`Ask`]s as an
+//!   [`AsyncService`], so async code can call into a component without knowing about Kompact.
+//!
+//! Futures that need to run next to a component can be handed to Kompact's own
+//! executor pool with [`spawn_stream_forwarder`] or `KompactSystem::spawn`.
+use async_std::channel;
+use futures_util::{FutureExt, Stream, StreamExt};
+use kompact::{JoinHandle, prelude::*};
+use snafu::prelude::*;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Create a channel that carries values out of Kompact handlers into async code.
+///
+/// The channel is unbounded, so sending never blocks a component's handler.
+#[must_use]
+pub fn bridge_channel<T>() -> (BridgeSender<T>, BridgeReceiver<T>) {
+    let (sender, receiver) = channel::unbounded();
+    (BridgeSender { sender }, BridgeReceiver { receiver })
+}
+
+/// Drive `stream` to completion and deliver every item to `actor`.
+///
+/// Returns the number of items that were forwarded. The returned future does not
+/// depend on any particular executor.
+pub async fn forward_stream_to_actor<S, M>(stream: S, actor: ActorRef<M>) -> usize
+where
+    S: Stream<Item = M>,
+    M: MessageBounds,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut forwarded = 0;
+    while let Some(message) = stream.next().await {
+        actor.tell(message);
+        forwarded += 1;
+    }
+    forwarded
+}
+
+/// Run [`forward_stream_to_actor`] on the executor pool of `system`.
+pub fn spawn_stream_forwarder<S, M>(
+    system: &KompactSystem,
+    stream: S,
+    actor: ActorRef<M>,
+) -> JoinHandle<usize>
+where
+    S: Stream<Item = M> + Send + 'static,
+    M: MessageBounds,
+{
+    system.spawn(forward_stream_to_actor(stream, actor))
+}
+
+/// An executor-agnostic request/response service.
+///
+/// Each call produces a self-contained future, so a host can run many calls
+/// concurrently on whatever executor it owns.
+pub trait AsyncService: Send + 'static {
+    /// The request accepted by [`call`](AsyncService::call).
+    type Request: MessageBounds;
+    /// The response produced for each request.
+    type Response: fmt::Debug + Send + 'static;
+
+    /// Start handling `request`.
+    fn call(&self, request: Self::Request)
+    -> impl Future<Output = Self::Response> + Send + 'static;
+}
+
+/// A Kompact component that serves an [`AsyncService`] to other actors.
+///
+/// Every [`Ask`] starts one call whose future runs on the system's executor pool,
+/// so slow calls do not block the component's handler.
+#[derive(ComponentDefinition)]
+pub struct AsyncServiceComponent<S>
+where
+    S: AsyncService,
+{
+    ctx: ComponentContext<Self>,
+    service: S,
+}
+impl<S> AsyncServiceComponent<S>
+where
+    S: AsyncService,
+{
+    /// Create a component that hosts `service`.
+    #[must_use]
+    pub fn new(service: S) -> Self {
+        Self {
+            ctx: ComponentContext::uninitialised(),
+            service,
+        }
+    }
+}
+impl<S> Actor for AsyncServiceComponent<S>
+where
+    S: AsyncService,
+{
+    type Message = Ask<S::Request, S::Response>;
+
+    fn receive_local(&mut self, msg: Self::Message) -> HandlerResult {
+        let (promise, request) = msg.take();
+        let response = self.service.call(request);
+        // Nobody is waiting for the result if the asker dropped its future.
+        drop(self.spawn_off(async move {
+            let _ = promise.fulfil(response.await);
+        }));
+        Handled::OK
+    }
+}
+
+// Cannot use the macro due to the type parameter.
+impl<S> ComponentLifecycle for AsyncServiceComponent<S> where S: AsyncService {}
+
+/// Error returned by an [`ActorService`] when the actor dropped the request without replying.
+#[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+#[snafu(display("The actor behind the service dropped the request without replying."))]
+pub struct ActorServiceUnavailableError;
+
+/// An [`AsyncService`] that forwards each call to an actor answering [`Ask`]s.
+#[derive(Debug)]
+pub struct ActorService<Request, Response>
+where
+    Request: MessageBounds,
+    Response: fmt::Debug + Send + 'static,
+{
+    actor: ActorRef<Ask<Request, Response>>,
+}
+impl<Request, Response> ActorService<Request, Response>
+where
+    Request: MessageBounds,
+    Response: fmt::Debug + Send + 'static,
+{
+    /// Call into `actor` for every request.
+    #[must_use]
+    pub fn new(actor: ActorRef<Ask<Request, Response>>) -> Self {
+        Self { actor }
+    }
+}
+impl<Request, Response> Clone for ActorService<Request, Response>
+where
+    Request: MessageBounds,
+    Response: fmt::Debug + Send + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            actor: self.actor.clone(),
+        }
+    }
+}
+impl<Request, Response> AsyncService for ActorService<Request, Response>
+where
+    Request: MessageBounds,
+    Response: fmt::Debug + Send + 'static,
+{
+    type Request = Request;
+    type Response = Result<Response, ActorServiceUnavailableError>;
+
+    fn call(
+        &self,
+        request: Self::Request,
+    ) -> impl Future<Output = Self::Response> + Send + 'static {
+        self.actor
+            .ask(request)
+            .map(|response| response.map_err(|_| ActorServiceUnavailableError))
+    }
+}
+
+/// Error returned when the [`BridgeReceiver`] of a channel has been dropped.
+///
+/// Carries the value that could not be delivered.
+#[derive(Debug, Snafu)]
+#[snafu(display("Bridge channel receiver was dropped."))]
+pub struct BridgeClosedError<T> {
+    value: T,
+}
+impl<T> BridgeClosedError<T> {
+    /// Recover the value that could not be delivered.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+/// Sending half of a [`bridge_channel`].
+///
+/// Cheap to clone and safe to use from within Kompact handlers.
+#[derive(Debug)]
+pub struct BridgeSender<T> {
+    sender: channel::Sender<T>,
+}
+impl<T> BridgeSender<T> {
+    /// Send `value` without blocking.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value in a [`BridgeClosedError`] if the receiver was dropped.
+    pub fn send(&self, value: T) -> Result<(), BridgeClosedError<T>> {
+        self.sender
+            .try_send(value)
+            .map_err(|error| BridgeClosedError {
+                value: error.into_inner(),
+            })
+    }
+
+    /// Whether the receiving half has been dropped.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+impl<T> Clone for BridgeSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+/// Receiving half of a [`bridge_channel`].
+///
+/// The stream ends once all senders have been dropped and all sent values were received.
+#[derive(Debug)]
+pub struct BridgeReceiver<T> {
+    receiver: channel::Receiver<T>,
+}
+impl<T> BridgeReceiver<T> {
+    /// Wait for the next value, or `None` once all senders are gone.
+    pub async fn recv(&self) -> Option<T> {
+        self.receiver.recv().await.ok()
+    }
+}
+impl<T> Stream for BridgeReceiver<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::stream;
+
+    #[derive(ComponentDefinition)]
+    struct EchoComponent {
+        ctx: ComponentContext<Self>,
+        output: BridgeSender<u32>,
+    }
+    impl EchoComponent {
+        fn new(output: BridgeSender<u32>) -> Self {
+            Self {
+                ctx: ComponentContext::uninitialised(),
+                output,
+            }
+        }
+    }
+    ignore_lifecycle!(EchoComponent);
+    impl Actor for EchoComponent {
+        type Message = u32;
+
+        fn receive_local(&mut self, msg: Self::Message) -> HandlerResult {
+            self.output
+                .send(msg * 2)
+                .expect("test receiver outlives the component");
+            Handled::OK
+        }
+    }
+
+    #[test]
+    fn stream_items_round_trip_through_actor() {
+        let system = KompactConfig::default().build().wait().expect("system");
+        let (sender, receiver) = bridge_channel();
+        let component = system.create(move || EchoComponent::new(sender));
+        system.start_notify(&component).wait();
+
+        let forwarded = block_on(spawn_stream_forwarder(
+            &system,
+            stream::iter([1, 2, 3]),
+            component.actor_ref(),
+        ));
+        assert_eq!(forwarded, Ok(3));
+
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            received.push(block_on(receiver.recv()).expect("value"));
+        }
+        assert_eq!(received, vec![2, 4, 6]);
+
+        system.kill_notify(component).wait();
+        system.shutdown().wait().expect("shutdown");
+    }
+
+    #[derive(Debug)]
+    struct ScalingService {
+        factor: u32,
+    }
+    impl AsyncService for ScalingService {
+        type Request = u32;
+        type Response = u32;
+
+        fn call(&self, request: u32) -> impl Future<Output = u32> + Send + 'static {
+            let factor = self.factor;
+            async move { request * factor }
+        }
+    }
+
+    #[test]
+    fn async_service_round_trips_through_component_and_back() {
+        let system = KompactConfig::default().build().wait().expect("system");
+        let component = system.create(|| AsyncServiceComponent::new(ScalingService { factor: 2 }));
+        system.start_notify(&component).wait();
+
+        // Calling the hosted service from async code goes through the component's asks.
+        let service = ActorService::new(component.actor_ref());
+        assert_eq!(block_on(service.call(21)), Ok(42));
+
+        system.kill_notify(component).wait();
+        assert_eq!(block_on(service.call(1)), Err(ActorServiceUnavailableError));
+        system.shutdown().wait().expect("shutdown");
+    }
+
+    #[test]
+    fn sender_reports_dropped_receiver() {
+        let (sender, receiver) = bridge_channel::<u32>();
+        drop(receiver);
+
+        assert!(sender.is_closed());
+        let error = sender.send(5).expect_err("receiver is gone");
+        assert_eq!(error.into_value(), 5);
+    }
+}
@@ -0,0 +1,475 @@
+//! Layered loading and validation of Flotsync TOML configuration.
+//!
+//! Every subsystem declares its settings as Kompact [`ConfigEntry`] keys (via
+//! `kompact_config!`) under the `flotsync.*` namespace. This module assembles one
+//! merged configuration document from TOML files, in-memory TOML fragments, and
+//! `FLOTSYNC__*` environment variables, so binaries do not each have to hand-roll
+//! their own option handling. The resulting [`FlotsyncConfig`] can be validated
+//! against the entries a binary cares about and then handed to service
+//! constructors, either as a Kompact config or as a TOML string.
+//!
+//! Subsystems whose constructors take typed settings implement
+//! [`FromFlotsyncConfig`] for them, so a binary resolves e.g. discovery intervals,
+//! transport ports, storage paths, or runtime limits with
+//! [`FlotsyncConfig::settings`] and reports invalid values before starting anything.
+use kompact::{
+    config::{
+        Config,
+        ConfigEntry,
+        ConfigError,
+        ConfigLoadingError,
+        ConfigValueType,
+        parse_config_str,
+    },
+    prelude::KompactConfig,
+};
+use snafu::prelude::*;
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Prefix of environment variables that override configuration values.
+pub const ENV_PREFIX: &str = "FLOTSYNC__";
+/// Separator between path segments in override environment variable names.
+pub const ENV_PATH_SEPARATOR: &str = "__";
+
+/// Errors produced while assembling a [`FlotsyncConfig`] from its sources.
+#[derive(Debug, Snafu)]
+pub enum ConfigLoadError {
+    #[snafu(display("Could not read config file {}.", path.display()))]
+    ReadFile { path: PathBuf, source: io::Error },
+    #[snafu(display("Could not parse TOML config from {origin}."))]
+    ParseToml {
+        origin: String,
+        source: toml::de::Error,
+    },
+    #[snafu(display("Environment variable {name} does not name a valid config path."))]
+    InvalidEnvKey { name: String },
+    #[snafu(display("Config value at {path} conflicts with an existing table."))]
+    ConflictingValue { path: String },
+    #[snafu(display("Merged config could not be loaded."))]
+    Merged { source: ConfigLoadingError },
+}
+
+/// Errors produced when a config value fails to read or validate.
+#[derive(Debug, Snafu)]
+#[snafu(display("Invalid value for config key {key}: {source:?}"))]
+pub struct ConfigValidationError {
+    key: &'static str,
+    source: ConfigError,
+}
+impl ConfigValidationError {
+    /// The config key that failed to validate.
+    #[must_use]
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+}
+
+/// Typed settings of one subsystem, resolved from a merged [`FlotsyncConfig`].
+///
+/// Implementations read the subsystem's own config entries, fall back to their
+/// declared defaults, and validate the values they combine.
+pub trait FromFlotsyncConfig: Sized {
+    /// Error returned when a configured value is invalid.
+    type Error;
+
+    /// Resolve the settings from `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a configured value has the wrong type or is invalid.
+    fn from_flotsync_config(config: &FlotsyncConfig) -> Result<Self, Self::Error>;
+}
+
+/// Accumulates configuration sources in priority order.
+///
+/// Later sources override earlier ones. Tables are merged recursively, so a later
+/// source only needs to mention the keys it changes.
+#[derive(Clone, Debug, Default)]
+pub struct FlotsyncConfigLoader {
+    merged: toml::Table,
+}
+impl FlotsyncConfigLoader {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge the TOML file at `path` into the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not valid TOML.
+    pub fn load_file<P>(&mut self, path: P) -> Result<&mut Self, ConfigLoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).context(ReadFileSnafu { path })?;
+        self.merge_toml(&content, || path.display().to_string())
+    }
+
+    /// Merge an in-memory TOML fragment into the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `toml` is not valid TOML.
+    pub fn load_str(&mut self, toml: &str) -> Result<&mut Self, ConfigLoadError> {
+        self.merge_toml(toml, || "an inline fragment".to_owned())
+    }
+
+    /// Merge overrides from the process environment.
+    ///
+    /// See [`FlotsyncConfigLoader::load_env_vars`] for the naming scheme.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a `FLOTSYNC__*` variable does not map to a valid config path.
+    pub fn load_process_env(&mut self) -> Result<&mut Self, ConfigLoadError> {
+        self.load_env_vars(std::env::vars())
+    }
+
+    /// Merge overrides from `vars`, ignoring all variables without the [`ENV_PREFIX`].
+    ///
+    /// The rest of the variable name is split on `__` into path segments, which are
+    /// lowercased with `_` replaced by `-`. So
+    /// `FLOTSYNC__REPLICATION__RUNTIME__LIMITS__MAX_GROUP_MEMBERS=16` sets
+    /// `flotsync.replication.runtime.limits.max-group-members`. Values are read as
+    /// TOML values where possible (numbers, booleans, arrays) and as plain strings
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable does not map to a valid config path.
+    pub fn load_env_vars<I, K, V>(&mut self, vars: I) -> Result<&mut Self, ConfigLoadError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (name, value) in vars {
+            let name = name.as_ref();
+            let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let segments: Vec<String> = rest
+                .split(ENV_PATH_SEPARATOR)
+                .map(|segment| segment.to_lowercase().replace('_', "-"))
+                .collect();
+            ensure!(
+                segments.iter().all(|segment| !segment.is_empty()),
+                InvalidEnvKeySnafu { name }
+            );
+            let mut path = vec!["flotsync".to_owned()];
+            path.extend(segments);
+            let value = parse_env_value(value.as_ref());
+            insert_value(&mut self.merged, &path, value)?;
+        }
+        Ok(self)
+    }
+
+    /// Produce the merged configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the merged document cannot be loaded as a Kompact config.
+    pub fn build(&self) -> Result<FlotsyncConfig, ConfigLoadError> {
+        let toml = self.merged.to_string();
+        let config = parse_config_str(&toml).context(MergedSnafu)?;
+        Ok(FlotsyncConfig { toml, config })
+    }
+
+    fn merge_toml<F>(&mut self, content: &str, origin: F) -> Result<&mut Self, ConfigLoadError>
+    where
+        F: FnOnce() -> String,
+    {
+        let table: toml::Table = content
+            .parse()
+            .with_context(|_| ParseTomlSnafu { origin: origin() })?;
+        merge_tables(&mut self.merged, table);
+        Ok(self)
+    }
+}
+
+/// A merged and parsed Flotsync configuration document.
+#[derive(Clone, Debug)]
+pub struct FlotsyncConfig {
+    toml: String,
+    config: Config,
+}
+impl FlotsyncConfig {
+    /// The merged configuration as a TOML document.
+    ///
+    /// This is the form accepted by service constructors that take an additional
+    /// runtime config fragment.
+    #[must_use]
+    pub fn as_toml_str(&self) -> &str {
+        &self.toml
+    }
+
+    /// The merged configuration as a parsed Kompact config.
+    #[must_use]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Read `key`, falling back to its declared default if it is not set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured value has the wrong type or fails the
+    /// entry's validator, or if the key is missing and has no default.
+    pub fn read<T>(&self, key: &ConfigEntry<T>) -> Result<T::Value, ConfigValidationError>
+    where
+        T: ConfigValueType,
+    {
+        self.config
+            .read_or_default(key)
+            .context(ConfigValidationSnafu { key: key.key })
+    }
+
+    /// Resolve the typed settings `S` of one subsystem.
+    ///
+    /// # Errors
+    ///
+    /// See [`FromFlotsyncConfig::from_flotsync_config`].
+    pub fn settings<S>(&self) -> Result<S, S::Error>
+    where
+        S: FromFlotsyncConfig,
+    {
+        S::from_flotsync_config(self)
+    }
+
+    /// Check that `key` is either unset or holds a valid value.
+    ///
+    /// Binaries should call this for every entry of the subsystems they host before
+    /// starting them, so misconfiguration is reported up front instead of silently
+    /// falling back to defaults at component start.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured value has the wrong type or fails the
+    /// entry's validator.
+    pub fn validate<T>(&self, key: &ConfigEntry<T>) -> Result<(), ConfigValidationError>
+    where
+        T: ConfigValueType,
+    {
+        match key.read(&self.config) {
+            Ok(_) => Ok(()),
+            Err(ConfigError::PathError(error)) if error.is_missing() => Ok(()),
+            Err(source) => Err(ConfigValidationError {
+                key: key.key,
+                source,
+            }),
+        }
+    }
+
+    /// Add the merged configuration to a Kompact system config.
+    pub fn apply_to(&self, kompact_config: &mut KompactConfig) {
+        kompact_config.load_config_str(self.toml.clone());
+    }
+}
+
+/// Interpret an environment variable value as a TOML value, or as a plain string
+/// if it does not parse as one.
+fn parse_env_value(raw: &str) -> toml::Value {
+    let document = format!("value = {raw}");
+    match document.parse::<toml::Table>() {
+        Ok(mut table) => table
+            .remove("value")
+            .unwrap_or_else(|| toml::Value::String(raw.to_owned())),
+        Err(_) => toml::Value::String(raw.to_owned()),
+    }
+}
+
+/// Recursively merge `next` into `current`, with values from `next` taking priority.
+fn merge_tables(current: &mut toml::Table, next: toml::Table) {
+    for (key, value) in next {
+        match (current.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(nested)) => {
+                merge_tables(existing, nested);
+            }
+            (_, value) => {
+                current.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Set `value` at `path` in `table`, creating intermediate tables as needed.
+fn insert_value(
+    table: &mut toml::Table,
+    path: &[String],
+    value: toml::Value,
+) -> Result<(), ConfigLoadError> {
+    let (last, parents) = path
+        .split_last()
+        .expect("config paths always contain the flotsync root segment");
+    let mut current = table;
+    for (depth, segment) in parents.iter().enumerate() {
+        let entry = current
+            .entry(segment.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        current = match entry {
+            toml::Value::Table(nested) => nested,
+            _ => {
+                return ConflictingValueSnafu {
+                    path: path[..=depth].join("."),
+                }
+                .fail();
+            }
+        };
+    }
+    current.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kompact::{
+        config::{StringValue, UsizeValue},
+        kompact_config,
+    };
+
+    kompact_config! {
+        TEST_LIMIT,
+        key = "flotsync.test.limits.max-items",
+        type = UsizeValue,
+        default = 7,
+        validate = |value| *value > 0,
+        doc = "Test config value with a non-zero limit.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        TEST_NAME,
+        key = "flotsync.test.name",
+        type = StringValue,
+        default = String::from("default"),
+        doc = "Test config string value.",
+        version = "0.1.0"
+    }
+
+    #[test]
+    fn later_sources_override_earlier_ones() {
+        let mut loader = FlotsyncConfigLoader::new();
+        loader
+            .load_str(
+                r#"
+                [flotsync.test]
+                name = "from-file"
+
+                [flotsync.test.limits]
+                max-items = 3
+                "#,
+            )
+            .expect("valid TOML")
+            .load_env_vars([
+                ("FLOTSYNC__TEST__LIMITS__MAX_ITEMS", "11"),
+                ("UNRELATED", "ignored"),
+            ])
+            .expect("valid env overrides");
+        let config = loader.build().expect("config should build");
+
+        assert_eq!(config.read(&TEST_LIMIT).expect("valid limit"), 11);
+        assert_eq!(config.read(&TEST_NAME).expect("valid name"), "from-file");
+
+        let reparsed = parse_config_str(config.as_toml_str()).expect("merged TOML reparses");
+        assert_eq!(&reparsed, config.config());
+    }
+
+    #[test]
+    fn env_values_fall_back_to_strings() {
+        let mut loader = FlotsyncConfigLoader::new();
+        loader
+            .load_env_vars([("FLOTSYNC__TEST__NAME", "not a toml value")])
+            .expect("valid env override");
+        let config = loader.build().expect("config should build");
+
+        assert_eq!(
+            config.read(&TEST_NAME).expect("valid name"),
+            "not a toml value"
+        );
+        assert_eq!(config.read(&TEST_LIMIT).expect("default limit"), 7);
+    }
+
+    #[test]
+    fn validation_reports_invalid_values() {
+        let mut loader = FlotsyncConfigLoader::new();
+        loader
+            .load_str("flotsync.test.limits.max-items = 0")
+            .expect("valid TOML");
+        let config = loader.build().expect("config should build");
+
+        let error = config.validate(&TEST_LIMIT).expect_err("zero is rejected");
+        assert_eq!(error.key(), TEST_LIMIT.key);
+        config.validate(&TEST_NAME).expect("missing keys are valid");
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TestSettings {
+        name: String,
+        max_items: usize,
+    }
+    impl FromFlotsyncConfig for TestSettings {
+        type Error = ConfigValidationError;
+
+        fn from_flotsync_config(config: &FlotsyncConfig) -> Result<Self, Self::Error> {
+            Ok(Self {
+                name: config.read(&TEST_NAME)?,
+                max_items: config.read(&TEST_LIMIT)?,
+            })
+        }
+    }
+
+    #[test]
+    fn typed_settings_resolve_from_merged_config() {
+        let mut loader = FlotsyncConfigLoader::new();
+        loader
+            .load_env_vars([("FLOTSYNC__TEST__LIMITS__MAX_ITEMS", "5")])
+            .expect("valid env override");
+        let config = loader.build().expect("config should build");
+
+        assert_eq!(
+            config.settings::<TestSettings>().expect("valid settings"),
+            TestSettings {
+                name: "default".to_owned(),
+                max_items: 5,
+            }
+        );
+
+        loader
+            .load_str("flotsync.test.limits.max-items = 0")
+            .expect("valid TOML");
+        let config = loader.build().expect("config should build");
+        let error = config
+            .settings::<TestSettings>()
+            .expect_err("zero is rejected");
+        assert_eq!(error.key(), TEST_LIMIT.key);
+    }
+
+    #[test]
+    fn malformed_sources_are_rejected() {
+        let mut loader = FlotsyncConfigLoader::new();
+        assert!(matches!(
+            loader.load_str("flotsync.test = ["),
+            Err(ConfigLoadError::ParseToml { .. })
+        ));
+        assert!(matches!(
+            loader.load_env_vars([("FLOTSYNC__TEST____NAME", "x")]),
+            Err(ConfigLoadError::InvalidEnvKey { .. })
+        ));
+
+        loader
+            .load_str("flotsync.test.name = \"plain\"")
+            .expect("valid TOML");
+        assert!(matches!(
+            loader.load_env_vars([("FLOTSYNC__TEST__NAME__INNER", "x")]),
+            Err(ConfigLoadError::ConflictingValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+//! Named fault points for deterministic robustness tests.
+//!
+//! Code that writes to storage, sends on the network, or schedules timers calls
+//! [`eval`], [`fails`], or [`delayed`] with a point name where the real system could
+//! misbehave. With the `fault-injection` feature, tests configure what each point
+//! does through a [`FaultScenario`]. Without the feature every point is inert and
+//! the checks compile down to nothing.
+//!
+//! Each point documents which [`FaultAction`]s it honours. It ignores the others.
+
+use std::time::Duration;
+
+#[cfg(feature = "fault-injection")]
+pub use registry::FaultScenario;
+
+/// What a configured fault point does when it is reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultAction {
+    /// Report a failure instead of performing the operation.
+    Fail,
+    /// Perform the operation, but this much later than requested.
+    Delay(Duration),
+}
+
+/// Return the action configured for `point`, if it fires this time.
+#[inline]
+#[must_use]
+pub fn eval(point: &str) -> Option<FaultAction> {
+    #[cfg(feature = "fault-injection")]
+    {
+        registry::eval(point)
+    }
+    #[cfg(not(feature = "fault-injection"))]
+    {
+        let _ = point;
+        None
+    }
+}
+
+/// Return whether `point` fires with [`FaultAction::Fail`] this time.
+#[inline]
+#[must_use]
+pub fn fails(point: &str) -> bool {
+    matches!(eval(point), Some(FaultAction::Fail))
+}
+
+/// Return `delay`, extended by any [`FaultAction::Delay`] that `point` fires with this time.
+#[inline]
+#[must_use]
+pub fn delayed(point: &str, delay: Duration) -> Duration {
+    match eval(point) {
+        Some(FaultAction::Delay(extra)) => delay.saturating_add(extra),
+        Some(FaultAction::Fail) | None => delay,
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+mod registry {
+    use super::FaultAction;
+    use std::{
+        collections::BTreeMap,
+        sync::{Mutex, MutexGuard, PoisonError},
+    };
+
+    /// Serialises scenarios, since fault points are shared by the whole process.
+    static SCENARIO: Mutex<()> = Mutex::new(());
+    /// Configured points by name.
+    static POINTS: Mutex<BTreeMap<String, PointState>> = Mutex::new(BTreeMap::new());
+
+    /// Exclusive configuration of all fault points in this process.
+    ///
+    /// Fault points are reached from component threads, so their configuration is
+    /// process-global. Creating a scenario waits for any other scenario to end, and
+    /// every point is cleared again when the scenario is dropped.
+    #[derive(Debug)]
+    pub struct FaultScenario {
+        _exclusive: MutexGuard<'static, ()>,
+    }
+
+    impl FaultScenario {
+        /// Start a scenario with no configured points.
+        #[must_use]
+        pub fn setup() -> Self {
+            let exclusive = SCENARIO.lock().unwrap_or_else(PoisonError::into_inner);
+            points().clear();
+            Self {
+                _exclusive: exclusive,
+            }
+        }
+
+        /// Fire `action` every time `point` is reached.
+        pub fn configure(&self, point: &str, action: FaultAction) {
+            self.insert(point, action, None);
+        }
+
+        /// Fire `action` the next `times` times `point` is reached, then behave normally.
+        pub fn configure_times(&self, point: &str, action: FaultAction, times: usize) {
+            self.insert(point, action, Some(times));
+        }
+
+        /// Stop firing `point`.
+        pub fn remove(&self, point: &str) {
+            points().remove(point);
+        }
+
+        /// How often `point` fired since it was last configured.
+        #[must_use]
+        pub fn hits(&self, point: &str) -> usize {
+            points().get(point).map_or(0, |state| state.hits)
+        }
+
+        #[allow(
+            clippy::unused_self,
+            reason = "Configuration requires holding the scenario."
+        )]
+        fn insert(&self, point: &str, action: FaultAction, remaining: Option<usize>) {
+            points().insert(
+                point.to_owned(),
+                PointState {
+                    action,
+                    remaining,
+                    hits: 0,
+                },
+            );
+        }
+    }
+
+    impl Drop for FaultScenario {
+        fn drop(&mut self) {
+            points().clear();
+        }
+    }
+
+    pub(super) fn eval(point: &str) -> Option<FaultAction> {
+        let mut points = points();
+        let state = points.get_mut(point)?;
+        match &mut state.remaining {
+            Some(0) => return None,
+            Some(remaining) => *remaining -= 1,
+            None => {}
+        }
+        state.hits += 1;
+        Some(state.action)
+    }
+
+    /// Configuration of one fault point.
+    #[derive(Debug)]
+    struct PointState {
+        action: FaultAction,
+        /// How many more times the point fires, or `None` for always.
+        remaining: Option<usize>,
+        hits: usize,
+    }
+
+    fn points() -> MutexGuard<'static, BTreeMap<String, PointState>> {
+        POINTS.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(all(test, feature = "fault-injection"))]
+mod tests {
+    use super::*;
+
+    const POINT: &str = "test.point";
+
+    #[test]
+    fn unconfigured_points_are_inert() {
+        let _scenario = FaultScenario::setup();
+        assert_eq!(eval(POINT), None);
+        assert!(!fails(POINT));
+        assert_eq!(
+            delayed(POINT, Duration::from_millis(5)),
+            Duration::from_millis(5)
+        );
+    }
+
+    #[test]
+    fn limited_points_fire_the_configured_number_of_times() {
+        let scenario = FaultScenario::setup();
+        scenario.configure_times(POINT, FaultAction::Fail, 2);
+        assert!(fails(POINT));
+        assert!(fails(POINT));
+        assert!(!fails(POINT));
+        assert_eq!(scenario.hits(POINT), 2);
+
+        scenario.configure(POINT, FaultAction::Delay(Duration::from_millis(10)));
+        assert_eq!(
+            delayed(POINT, Duration::from_millis(5)),
+            Duration::from_millis(15)
+        );
+        assert_eq!(scenario.hits(POINT), 1);
+        scenario.remove(POINT);
+        assert_eq!(eval(POINT), None);
+    }
+
+    #[test]
+    fn dropping_the_scenario_clears_all_points() {
+        {
+            let scenario = FaultScenario::setup();
+            scenario.configure(POINT, FaultAction::Fail);
+        }
+        let _scenario = FaultScenario::setup();
+        assert_eq!(eval(POINT), None);
+    }
+}
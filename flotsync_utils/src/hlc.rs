@@ -0,0 +1,352 @@
+//! Hybrid logical clock (HLC) timestamps.
+//!
+//! An HLC combines a wall-clock reading with a logical counter, so timestamps are
+//! strictly monotonic on one node, causally ordered across nodes that exchange them,
+//! and still stay close to physical time. Subsystems that need comparable timestamps
+//! should obtain them from one shared [`HybridLogicalClock`] instead of calling
+//! `SystemTime::now()` directly.
+use snafu::prelude::*;
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Number of low bits of a packed [`HlcTimestamp`] used for the logical counter.
+const LOGICAL_BITS: u32 = 16;
+/// Largest physical value (in milliseconds) that fits into a packed [`HlcTimestamp`].
+const MAX_PHYSICAL_MILLIS: u64 = (1 << (u64::BITS - LOGICAL_BITS)) - 1;
+
+/// Default bound on how far a remote timestamp may run ahead of the local wall clock.
+pub const DEFAULT_MAX_CLOCK_DRIFT: Duration = Duration::from_secs(60);
+
+/// A hybrid logical clock timestamp.
+///
+/// Timestamps order by physical time first and by the logical counter second.
+/// They can be packed into a single `u64` for storage and on-wire use, with the
+/// physical part in the upper 48 bits and the logical counter in the lower 16 bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct HlcTimestamp {
+    physical_millis: u64,
+    logical: u16,
+}
+impl HlcTimestamp {
+    /// The smallest possible timestamp.
+    pub const ZERO: Self = Self {
+        physical_millis: 0,
+        logical: 0,
+    };
+
+    /// Create a timestamp from its parts.
+    ///
+    /// Returns `None` if `physical_millis` does not fit into the packed representation.
+    #[must_use]
+    pub const fn new(physical_millis: u64, logical: u16) -> Option<Self> {
+        if physical_millis > MAX_PHYSICAL_MILLIS {
+            None
+        } else {
+            Some(Self {
+                physical_millis,
+                logical,
+            })
+        }
+    }
+
+    /// Milliseconds since the Unix epoch of the physical component.
+    #[must_use]
+    pub const fn physical_millis(&self) -> u64 {
+        self.physical_millis
+    }
+
+    /// The logical counter that orders timestamps sharing the same physical component.
+    #[must_use]
+    pub const fn logical(&self) -> u16 {
+        self.logical
+    }
+
+    /// The physical component as a [`SystemTime`].
+    #[must_use]
+    pub fn to_system_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.physical_millis)
+    }
+
+    /// Pack this timestamp into a single order-preserving `u64`.
+    #[must_use]
+    pub const fn to_u64(&self) -> u64 {
+        (self.physical_millis << LOGICAL_BITS) | self.logical as u64
+    }
+
+    /// Unpack a timestamp produced by [`HlcTimestamp::to_u64`].
+    #[must_use]
+    pub const fn from_u64(value: u64) -> Self {
+        Self {
+            physical_millis: value >> LOGICAL_BITS,
+            logical: (value & ((1 << LOGICAL_BITS) - 1)) as u16,
+        }
+    }
+
+    /// The next timestamp after this one, rolling the logical counter over into
+    /// the physical component when it is exhausted.
+    ///
+    /// Returns `None` if this is the largest timestamp that can be packed.
+    fn successor(self) -> Option<Self> {
+        match self.logical.checked_add(1) {
+            Some(logical) => Some(Self {
+                physical_millis: self.physical_millis,
+                logical,
+            }),
+            None => Self::new(self.physical_millis + 1, 0),
+        }
+    }
+}
+impl fmt::Display for HlcTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:05}", self.physical_millis, self.logical)
+    }
+}
+
+/// Source of wall-clock readings for a [`HybridLogicalClock`].
+pub trait PhysicalClock {
+    /// Current wall-clock time in milliseconds since the Unix epoch.
+    fn now_millis(&self) -> u64;
+}
+
+/// [`PhysicalClock`] backed by [`SystemTime::now`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+impl PhysicalClock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        // A wall clock set before the epoch is treated as the epoch itself;
+        // the HLC stays monotonic regardless.
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| {
+                u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+            })
+            .min(MAX_PHYSICAL_MILLIS)
+    }
+}
+
+/// Errors produced by [`HybridLogicalClock::now`] and [`HybridLogicalClock::observe`].
+#[derive(Debug, Snafu)]
+pub enum HlcError {
+    #[snafu(display(
+        "Remote timestamp {remote} is more than {max_drift:?} ahead of the local wall clock \
+         ({local_physical_millis} ms)."
+    ))]
+    ClockDriftExceeded {
+        remote: HlcTimestamp,
+        local_physical_millis: u64,
+        max_drift: Duration,
+    },
+    #[snafu(display("No timestamp after {last} can be packed into 64 bits."))]
+    ClockExhausted { last: HlcTimestamp },
+}
+
+/// A hybrid logical clock.
+///
+/// Every timestamp returned by [`HybridLogicalClock::now`] and
+/// [`HybridLogicalClock::observe`] is strictly greater than all timestamps this clock
+/// has returned or observed before. Remote timestamps that are further ahead of the
+/// local wall clock than the configured maximum drift are rejected, so a single peer
+/// with a broken clock cannot drag every node's timestamps into the future.
+///
+/// To keep timestamps monotonic across restarts, persist [`HybridLogicalClock::last`]
+/// and pass it to [`HybridLogicalClock::resume`].
+#[derive(Clone, Debug)]
+pub struct HybridLogicalClock<C = SystemClock> {
+    clock: C,
+    max_drift: Duration,
+    last: HlcTimestamp,
+}
+impl HybridLogicalClock<SystemClock> {
+    /// Create a clock reading from the system wall clock with the default maximum drift.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock, DEFAULT_MAX_CLOCK_DRIFT)
+    }
+}
+impl Default for HybridLogicalClock<SystemClock> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C> HybridLogicalClock<C>
+where
+    C: PhysicalClock,
+{
+    /// Create a clock reading from `clock` that rejects remote timestamps more than
+    /// `max_drift` ahead of it.
+    #[must_use]
+    pub fn with_clock(clock: C, max_drift: Duration) -> Self {
+        Self {
+            clock,
+            max_drift,
+            last: HlcTimestamp::ZERO,
+        }
+    }
+
+    /// Continue from a previously persisted timestamp.
+    ///
+    /// All future timestamps will be greater than `last`, even if the wall clock
+    /// moved backwards in the meantime.
+    #[must_use]
+    pub fn resume(mut self, last: HlcTimestamp) -> Self {
+        self.last = self.last.max(last);
+        self
+    }
+
+    /// The most recent timestamp this clock has returned or observed.
+    #[must_use]
+    pub fn last(&self) -> HlcTimestamp {
+        self.last
+    }
+
+    /// The configured maximum drift for remote timestamps.
+    #[must_use]
+    pub fn max_drift(&self) -> Duration {
+        self.max_drift
+    }
+
+    /// Produce a new timestamp for a local event.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HlcError::ClockExhausted`] if the wall clock has not moved past
+    /// [`HybridLogicalClock::last`] and no larger timestamp can be packed. The clock is left
+    /// unchanged in that case.
+    pub fn now(&mut self) -> Result<HlcTimestamp, HlcError> {
+        let physical_millis = self.clock.now_millis().min(MAX_PHYSICAL_MILLIS);
+        self.last = if physical_millis > self.last.physical_millis {
+            HlcTimestamp {
+                physical_millis,
+                logical: 0,
+            }
+        } else {
+            self.last
+                .successor()
+                .context(ClockExhaustedSnafu { last: self.last })?
+        };
+        Ok(self.last)
+    }
+
+    /// Merge a timestamp received from a peer and produce a new timestamp for the
+    /// receive event.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HlcError::ClockDriftExceeded`] if `remote` is further ahead of the
+    /// local wall clock than the configured maximum drift, and [`HlcError::ClockExhausted`] if
+    /// no timestamp after `remote` can be packed. The clock is left unchanged in both cases.
+    pub fn observe(&mut self, remote: HlcTimestamp) -> Result<HlcTimestamp, HlcError> {
+        let physical_millis = self.clock.now_millis().min(MAX_PHYSICAL_MILLIS);
+        let max_drift_millis = u64::try_from(self.max_drift.as_millis()).unwrap_or(u64::MAX);
+        ensure!(
+            remote.physical_millis <= physical_millis.saturating_add(max_drift_millis),
+            ClockDriftExceededSnafu {
+                remote,
+                local_physical_millis: physical_millis,
+                max_drift: self.max_drift,
+            }
+        );
+        let latest = self.last.max(remote);
+        self.last = if physical_millis > latest.physical_millis {
+            HlcTimestamp {
+                physical_millis,
+                logical: 0,
+            }
+        } else {
+            latest
+                .successor()
+                .context(ClockExhaustedSnafu { last: latest })?
+        };
+        Ok(self.last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, rc::Rc};
+
+    #[derive(Clone, Default)]
+    struct ManualClock(Rc<Cell<u64>>);
+    impl ManualClock {
+        fn set(&self, millis: u64) {
+            self.0.set(millis);
+        }
+    }
+    impl PhysicalClock for ManualClock {
+        fn now_millis(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn now_is_monotonic_when_wall_clock_stalls_or_moves_back() {
+        let wall = ManualClock::default();
+        let mut hlc = HybridLogicalClock::with_clock(wall.clone(), DEFAULT_MAX_CLOCK_DRIFT);
+
+        wall.set(1_000);
+        let first = hlc.now().unwrap();
+        assert_eq!(first, HlcTimestamp::new(1_000, 0).unwrap());
+        let second = hlc.now().unwrap();
+        assert_eq!(second, HlcTimestamp::new(1_000, 1).unwrap());
+
+        wall.set(900);
+        let third = hlc.now().unwrap();
+        assert!(third > second);
+        assert_eq!(third.physical_millis(), 1_000);
+
+        wall.set(1_001);
+        assert_eq!(hlc.now().unwrap(), HlcTimestamp::new(1_001, 0).unwrap());
+    }
+
+    #[test]
+    fn observe_merges_remote_and_bounds_drift() {
+        let wall = ManualClock::default();
+        wall.set(10_000);
+        let mut hlc = HybridLogicalClock::with_clock(wall.clone(), Duration::from_secs(1));
+
+        let remote = HlcTimestamp::new(10_500, 7).unwrap();
+        let merged = hlc.observe(remote).expect("remote within drift bound");
+        assert_eq!(merged, HlcTimestamp::new(10_500, 8).unwrap());
+
+        let too_far = HlcTimestamp::new(11_001, 0).unwrap();
+        let error = hlc.observe(too_far).expect_err("remote beyond drift bound");
+        assert!(matches!(error, HlcError::ClockDriftExceeded { .. }));
+        assert_eq!(hlc.last(), merged);
+    }
+
+    #[test]
+    fn packed_representation_round_trips_and_preserves_order() {
+        let earlier = HlcTimestamp::new(1_700_000_000_000, u16::MAX).unwrap();
+        let later = earlier.successor().unwrap();
+        assert_eq!(later, HlcTimestamp::new(1_700_000_000_001, 0).unwrap());
+        assert!(earlier.to_u64() < later.to_u64());
+        assert_eq!(HlcTimestamp::from_u64(earlier.to_u64()), earlier);
+        assert_eq!(HlcTimestamp::new(MAX_PHYSICAL_MILLIS + 1, 0), None);
+
+        let wall = ManualClock::default();
+        let hlc = HybridLogicalClock::with_clock(wall, DEFAULT_MAX_CLOCK_DRIFT).resume(later);
+        assert_eq!(hlc.last(), later);
+    }
+
+    #[test]
+    fn exhausted_clock_reports_an_error_instead_of_overflowing() {
+        let largest = HlcTimestamp::new(MAX_PHYSICAL_MILLIS, u16::MAX).unwrap();
+        assert_eq!(largest.successor(), None);
+
+        let wall = ManualClock::default();
+        wall.set(MAX_PHYSICAL_MILLIS);
+        let mut hlc = HybridLogicalClock::with_clock(wall, DEFAULT_MAX_CLOCK_DRIFT).resume(largest);
+        let error = hlc.now().expect_err("no timestamp after the largest one");
+        assert!(matches!(error, HlcError::ClockExhausted { last } if last == largest));
+        assert_eq!(hlc.last(), largest);
+
+        let error = hlc
+            .observe(largest)
+            .expect_err("no timestamp after the largest one");
+        assert!(matches!(error, HlcError::ClockExhausted { .. }));
+        assert_eq!(hlc.last(), largest);
+    }
+}
@@ -4,12 +4,19 @@ use kompact::prelude::{HandlerError, HandlerResultExt as _};
 use snafu::{FromString, OptionExt as SnafuOptionExt, ResultExt as SnafuResultExt};
 use std::{error::Error, fmt, future::Future, marker::PhantomData, pin::Pin, time::Duration};
 
+pub mod async_bridge;
 pub mod claimable_promise;
+pub mod config;
 pub mod debugging;
 pub mod err;
+pub mod fault_injection;
+pub mod hlc;
 pub mod kompact_config;
 pub mod kompact_fsm;
 pub mod kompact_testing;
+pub mod maintenance;
+pub mod shared_doc;
+pub mod shutdown;
 pub mod testing;
 
 pub use async_std::future::TimeoutError;
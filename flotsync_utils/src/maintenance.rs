@@ -0,0 +1,586 @@
+//! Incremental background maintenance that stays out of the way of foreground work.
+//!
+//! Compaction, defragmentation, and garbage collection are split into small
+//! [`MaintenanceTask`] steps. A [`MaintenanceDriver`] runs these steps round-robin, but only
+//! for a fixed time budget per tick and only while no foreground work is in flight, as reported
+//! through a shared [`ForegroundActivity`]. [`MaintenanceComponent`] drives the ticks from a
+//! Kompact timer and stops once a coordinated [shutdown](crate::shutdown) begins, so maintenance
+//! never holds up requests or shutdown for longer than a single step.
+
+use crate::{
+    kompact_config::ConfigReadExt,
+    shutdown::{ShutdownParticipant, ShutdownPhase},
+};
+use kompact::{config::DurationValue, kompact_config, prelude::*};
+use std::{
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Default interval between maintenance ticks.
+pub const DEFAULT_MAINTENANCE_TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default time one maintenance tick may spend running steps.
+pub const DEFAULT_MAINTENANCE_TIME_BUDGET: Duration = Duration::from_millis(5);
+
+/// Default time foreground work must have been finished before maintenance resumes.
+pub const DEFAULT_MAINTENANCE_QUIET_PERIOD: Duration = Duration::from_millis(250);
+
+/// Kompact configuration keys consumed by [`MaintenanceComponent`].
+pub mod config_keys {
+    use super::{
+        DEFAULT_MAINTENANCE_QUIET_PERIOD,
+        DEFAULT_MAINTENANCE_TICK_INTERVAL,
+        DEFAULT_MAINTENANCE_TIME_BUDGET,
+        DurationValue,
+        kompact_config,
+    };
+
+    kompact_config! {
+        MAINTENANCE_TICK_INTERVAL,
+        key = "flotsync.maintenance.tick-interval",
+        type = DurationValue,
+        default = DEFAULT_MAINTENANCE_TICK_INTERVAL,
+        doc = "Interval between background maintenance ticks. Bounds how often compaction, defragmentation, and garbage collection make progress.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        MAINTENANCE_TIME_BUDGET,
+        key = "flotsync.maintenance.time-budget",
+        type = DurationValue,
+        default = DEFAULT_MAINTENANCE_TIME_BUDGET,
+        doc = "Time one maintenance tick may spend running steps. A tick always finishes the step it started, so the budget can be exceeded by at most one step.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        MAINTENANCE_QUIET_PERIOD,
+        key = "flotsync.maintenance.quiet-period",
+        type = DurationValue,
+        default = DEFAULT_MAINTENANCE_QUIET_PERIOD,
+        doc = "Time foreground work must have been finished before maintenance steps run again.",
+        version = "0.1.0"
+    }
+}
+
+/// One kind of incremental maintenance work.
+///
+/// Each call to [`Self::step`] should do a small, bounded amount of work, so the driver can check
+/// its budget and foreground activity in between.
+pub trait MaintenanceTask: Send {
+    /// Name used when logging this task.
+    fn name(&self) -> &str;
+
+    /// Perform one bounded unit of work.
+    fn step(&mut self) -> MaintenanceStep;
+}
+
+/// What one [`MaintenanceTask::step`] call achieved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaintenanceStep {
+    /// Work was done and more may be pending.
+    Progress,
+    /// Nothing needs to be done right now.
+    Idle,
+}
+
+/// Limits applied to one [`MaintenanceDriver::run_tick`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaintenanceBudget {
+    /// Time the tick may spend running steps.
+    pub time_budget: Duration,
+    /// Time foreground work must have been finished before steps run.
+    pub quiet_period: Duration,
+}
+
+impl Default for MaintenanceBudget {
+    fn default() -> Self {
+        Self {
+            time_budget: DEFAULT_MAINTENANCE_TIME_BUDGET,
+            quiet_period: DEFAULT_MAINTENANCE_QUIET_PERIOD,
+        }
+    }
+}
+
+/// Summary of one [`MaintenanceDriver::run_tick`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaintenanceTick {
+    /// Number of steps that reported [`MaintenanceStep::Progress`].
+    pub steps: usize,
+    /// Why the tick stopped running steps.
+    pub end: MaintenanceTickEnd,
+}
+
+/// Reason a maintenance tick stopped running steps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaintenanceTickEnd {
+    /// Every task reported [`MaintenanceStep::Idle`] in turn.
+    Idle,
+    /// The time budget was spent.
+    BudgetSpent,
+    /// Foreground work was in flight or finished too recently.
+    Yielded,
+}
+
+/// Shared record of in-flight foreground work.
+///
+/// Foreground code holds a [`ForegroundGuard`] while it works. Clones share the same record.
+#[derive(Clone, Debug, Default)]
+pub struct ForegroundActivity {
+    shared: Arc<Mutex<ForegroundState>>,
+}
+
+impl ForegroundActivity {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark foreground work as in flight until the returned guard is dropped.
+    #[must_use]
+    pub fn begin(&self) -> ForegroundGuard {
+        self.lock().in_flight += 1;
+        ForegroundGuard {
+            activity: self.clone(),
+        }
+    }
+
+    /// Whether no foreground work is in flight or finished within `quiet_period`.
+    #[must_use]
+    pub fn is_quiet(&self, quiet_period: Duration) -> bool {
+        let state = self.lock();
+        state.in_flight == 0
+            && state
+                .last_finished
+                .is_none_or(|finished| finished.elapsed() >= quiet_period)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ForegroundState> {
+        // Every update leaves the state consistent, so a poisoned lock is still usable.
+        self.shared
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Marks one unit of foreground work as in flight while alive.
+#[derive(Debug)]
+pub struct ForegroundGuard {
+    activity: ForegroundActivity,
+}
+
+impl Drop for ForegroundGuard {
+    fn drop(&mut self) {
+        let mut state = self.activity.lock();
+        state.in_flight -= 1;
+        state.last_finished = Some(Instant::now());
+    }
+}
+
+/// Runs [`MaintenanceTask`]s round-robin within a per-tick budget.
+pub struct MaintenanceDriver {
+    tasks: Vec<Box<dyn MaintenanceTask>>,
+    next_task: usize,
+    foreground: ForegroundActivity,
+}
+
+impl MaintenanceDriver {
+    /// Create a driver without tasks that yields to `foreground`.
+    #[must_use]
+    pub fn new(foreground: ForegroundActivity) -> Self {
+        Self {
+            tasks: Vec::new(),
+            next_task: 0,
+            foreground,
+        }
+    }
+
+    /// Add `task` to the end of the rotation.
+    pub fn register(&mut self, task: Box<dyn MaintenanceTask>) {
+        self.tasks.push(task);
+    }
+
+    /// Number of registered tasks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Run steps until every task is idle, the budget is spent, or foreground work appears.
+    ///
+    /// Unless the tick yields right away, it runs at least one step, so maintenance keeps making
+    /// progress even with a zero budget. The next tick continues with the task after the last one
+    /// that ran.
+    pub fn run_tick(&mut self, budget: &MaintenanceBudget) -> MaintenanceTick {
+        let mut tick = MaintenanceTick {
+            steps: 0,
+            end: MaintenanceTickEnd::Idle,
+        };
+        if self.tasks.is_empty() {
+            return tick;
+        }
+        if !self.foreground.is_quiet(budget.quiet_period) {
+            tick.end = MaintenanceTickEnd::Yielded;
+            return tick;
+        }
+        let started = Instant::now();
+        let mut idle_in_a_row = 0;
+        while idle_in_a_row < self.tasks.len() {
+            let task_index = self.next_task;
+            self.next_task = (task_index + 1) % self.tasks.len();
+            match self.tasks[task_index].step() {
+                MaintenanceStep::Progress => {
+                    tick.steps += 1;
+                    idle_in_a_row = 0;
+                }
+                MaintenanceStep::Idle => idle_in_a_row += 1,
+            }
+            if started.elapsed() >= budget.time_budget {
+                tick.end = MaintenanceTickEnd::BudgetSpent;
+                break;
+            }
+            if !self.foreground.is_quiet(budget.quiet_period) {
+                tick.end = MaintenanceTickEnd::Yielded;
+                break;
+            }
+        }
+        tick
+    }
+}
+
+impl fmt::Debug for MaintenanceDriver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MaintenanceDriver")
+            .field(
+                "tasks",
+                &self
+                    .tasks
+                    .iter()
+                    .map(|task| task.name())
+                    .collect::<Vec<_>>(),
+            )
+            .field("next_task", &self.next_task)
+            .field("foreground", &self.foreground)
+            .finish()
+    }
+}
+
+/// Actor messages understood by [`MaintenanceComponent`].
+pub enum MaintenanceMessage {
+    /// Add a task to the rotation.
+    Register(Box<dyn MaintenanceTask>),
+    /// Run one tick right away and reply with its summary.
+    RunTick(Ask<(), MaintenanceTick>),
+}
+
+impl fmt::Debug for MaintenanceMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(task) => f.debug_tuple("Register").field(&task.name()).finish(),
+            Self::RunTick(ask) => f.debug_tuple("RunTick").field(ask).finish(),
+        }
+    }
+}
+
+/// Kompact component that runs a [`MaintenanceDriver`] on a periodic timer.
+///
+/// Tick interval and budget are read from [`config_keys`] on start. When built
+/// [with a shutdown participant](Self::with_shutdown), the component stops ticking as soon as
+/// shutdown begins and then completes every phase, since it holds no work that needs draining.
+#[derive(ComponentDefinition)]
+pub struct MaintenanceComponent {
+    ctx: ComponentContext<Self>,
+    driver: MaintenanceDriver,
+    budget: MaintenanceBudget,
+    shutdown: Option<ShutdownParticipant>,
+    tick_timer: Option<ScheduledTimer>,
+}
+
+impl MaintenanceComponent {
+    /// Create a component whose maintenance yields to `foreground`.
+    #[must_use]
+    pub fn new(foreground: ForegroundActivity) -> Self {
+        Self {
+            ctx: ComponentContext::uninitialised(),
+            driver: MaintenanceDriver::new(foreground),
+            budget: MaintenanceBudget::default(),
+            shutdown: None,
+            tick_timer: None,
+        }
+    }
+
+    /// Stop maintenance once the shutdown `participant` observes begins.
+    #[must_use]
+    pub fn with_shutdown(mut self, participant: ShutdownParticipant) -> Self {
+        self.shutdown = Some(participant);
+        self
+    }
+
+    /// Add `task` to the rotation before the component starts.
+    #[must_use]
+    pub fn with_task(mut self, task: Box<dyn MaintenanceTask>) -> Self {
+        self.driver.register(task);
+        self
+    }
+
+    fn run_tick(&mut self) -> MaintenanceTick {
+        let shutting_down = self
+            .shutdown
+            .as_ref()
+            .is_some_and(|participant| participant.token().is_shutting_down());
+        if shutting_down {
+            if let Some(timer) = self.tick_timer.take() {
+                self.cancel_timer(timer);
+                debug!(self.log(), "Stopped background maintenance for shutdown");
+            }
+            if let Some(participant) = &self.shutdown {
+                participant.complete(ShutdownPhase::Close);
+            }
+            return MaintenanceTick {
+                steps: 0,
+                end: MaintenanceTickEnd::Yielded,
+            };
+        }
+        let tick = self.driver.run_tick(&self.budget);
+        trace!(self.log(), "Maintenance tick finished: {tick:?}");
+        tick
+    }
+}
+
+impl Actor for MaintenanceComponent {
+    type Message = MaintenanceMessage;
+
+    fn receive_local(&mut self, msg: Self::Message) -> HandlerResult {
+        match msg {
+            MaintenanceMessage::Register(task) => {
+                debug!(self.log(), "Registered maintenance task {}", task.name());
+                self.driver.register(task);
+            }
+            MaintenanceMessage::RunTick(ask) => {
+                let tick = self.run_tick();
+                let _ = ask.reply(tick);
+            }
+        }
+        Handled::OK
+    }
+}
+
+impl ComponentLifecycle for MaintenanceComponent {
+    fn on_start(&mut self) -> HandlerResult {
+        let tick_interval = self
+            .ctx
+            .config()
+            .read_or_default_warn(self.log(), &config_keys::MAINTENANCE_TICK_INTERVAL);
+        self.budget = MaintenanceBudget {
+            time_budget: self
+                .ctx
+                .config()
+                .read_or_default_warn(self.log(), &config_keys::MAINTENANCE_TIME_BUDGET),
+            quiet_period: self
+                .ctx
+                .config()
+                .read_or_default_warn(self.log(), &config_keys::MAINTENANCE_QUIET_PERIOD),
+        };
+        self.tick_timer =
+            Some(
+                self.schedule_periodic(tick_interval, tick_interval, |component, _timer| {
+                    component.run_tick();
+                    Handled::OK
+                }),
+            );
+        Handled::OK
+    }
+
+    fn on_stop(&mut self) -> HandlerResult {
+        if let Some(timer) = self.tick_timer.take() {
+            self.cancel_timer(timer);
+        }
+        Handled::OK
+    }
+
+    fn on_kill(&mut self) -> HandlerResult {
+        self.on_stop()
+    }
+}
+
+/// Foreground work counters shared by the clones of one [`ForegroundActivity`].
+#[derive(Debug, Default)]
+struct ForegroundState {
+    in_flight: usize,
+    last_finished: Option<Instant>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shutdown::shutdown_channel;
+
+    /// Task that does `remaining` steps of work and records its name for each step.
+    struct CountingTask {
+        name: &'static str,
+        remaining: usize,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl MaintenanceTask for CountingTask {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn step(&mut self) -> MaintenanceStep {
+            if self.remaining == 0 {
+                return MaintenanceStep::Idle;
+            }
+            self.remaining -= 1;
+            self.log.lock().unwrap().push(self.name);
+            MaintenanceStep::Progress
+        }
+    }
+
+    fn counting_task(
+        name: &'static str,
+        remaining: usize,
+        log: &Arc<Mutex<Vec<&'static str>>>,
+    ) -> Box<dyn MaintenanceTask> {
+        Box::new(CountingTask {
+            name,
+            remaining,
+            log: Arc::clone(log),
+        })
+    }
+
+    const ONE_STEP: MaintenanceBudget = MaintenanceBudget {
+        time_budget: Duration::ZERO,
+        quiet_period: Duration::ZERO,
+    };
+
+    const UNLIMITED: MaintenanceBudget = MaintenanceBudget {
+        time_budget: Duration::from_secs(60),
+        quiet_period: Duration::ZERO,
+    };
+
+    #[test]
+    fn zero_budget_runs_one_step_per_tick_round_robin() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut driver = MaintenanceDriver::new(ForegroundActivity::new());
+        driver.register(counting_task("compact", 2, &log));
+        driver.register(counting_task("collect", 1, &log));
+
+        let ticks: Vec<MaintenanceTick> = (0..3).map(|_| driver.run_tick(&ONE_STEP)).collect();
+
+        assert!(
+            ticks
+                .iter()
+                .all(|tick| tick.steps == 1 && tick.end == MaintenanceTickEnd::BudgetSpent)
+        );
+        assert_eq!(*log.lock().unwrap(), ["compact", "collect", "compact"]);
+    }
+
+    #[test]
+    fn tick_ends_once_every_task_is_idle() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut driver = MaintenanceDriver::new(ForegroundActivity::new());
+        driver.register(counting_task("compact", 3, &log));
+        driver.register(counting_task("collect", 1, &log));
+
+        let tick = driver.run_tick(&UNLIMITED);
+
+        assert_eq!(
+            tick,
+            MaintenanceTick {
+                steps: 4,
+                end: MaintenanceTickEnd::Idle,
+            }
+        );
+        assert_eq!(
+            driver.run_tick(&UNLIMITED),
+            MaintenanceTick {
+                steps: 0,
+                end: MaintenanceTickEnd::Idle,
+            }
+        );
+    }
+
+    #[test]
+    fn tick_yields_while_foreground_work_is_in_flight() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let foreground = ForegroundActivity::new();
+        let mut driver = MaintenanceDriver::new(foreground.clone());
+        driver.register(counting_task("compact", 1, &log));
+
+        let guard = foreground.begin();
+        assert_eq!(
+            driver.run_tick(&UNLIMITED),
+            MaintenanceTick {
+                steps: 0,
+                end: MaintenanceTickEnd::Yielded,
+            }
+        );
+        drop(guard);
+        let quiet_period = MaintenanceBudget {
+            quiet_period: Duration::from_secs(60),
+            ..UNLIMITED
+        };
+        assert_eq!(
+            driver.run_tick(&quiet_period).end,
+            MaintenanceTickEnd::Yielded
+        );
+
+        assert_eq!(driver.run_tick(&UNLIMITED).steps, 1);
+        assert_eq!(log.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn component_stops_ticking_once_shutdown_begins() {
+        let system = KompactConfig::default().build().wait().expect("system");
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (controller, token) = shutdown_channel();
+        let component = system.create(|| {
+            MaintenanceComponent::new(ForegroundActivity::new())
+                .with_shutdown(token.participant("maintenance"))
+                .with_task(counting_task("compact", 10, &log))
+        });
+        system
+            .start_notify(&component)
+            .wait_timeout(Duration::from_secs(1))
+            .expect("component start");
+        let component_ref = component.actor_ref();
+
+        let tick = component_ref
+            .ask_with(|promise| MaintenanceMessage::RunTick(Ask::new(promise, ())))
+            .wait_timeout(Duration::from_secs(1))
+            .expect("tick reply");
+        assert!(tick.steps >= 1);
+
+        let shutdown =
+            std::thread::spawn(move || block_on(controller.shutdown(Duration::from_secs(5))));
+        block_on(token.reached(ShutdownPhase::StopAccepting));
+        let tick = component_ref
+            .ask_with(|promise| MaintenanceMessage::RunTick(Ask::new(promise, ())))
+            .wait_timeout(Duration::from_secs(1))
+            .expect("tick reply");
+        assert_eq!(tick.end, MaintenanceTickEnd::Yielded);
+        shutdown
+            .join()
+            .expect("shutdown thread")
+            .expect("shutdown should complete");
+        let steps_after_shutdown = log.lock().unwrap().len();
+        assert_eq!(
+            component_ref
+                .ask_with(|promise| MaintenanceMessage::RunTick(Ask::new(promise, ())))
+                .wait_timeout(Duration::from_secs(1))
+                .expect("tick reply")
+                .steps,
+            0
+        );
+        assert_eq!(log.lock().unwrap().len(), steps_after_shutdown);
+        system.shutdown().wait().expect("system shutdown");
+    }
+}
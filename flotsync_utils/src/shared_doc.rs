@@ -0,0 +1,304 @@
+//! A shared handle around a replicated document.
+//!
+//! The CRDT data structures are plain values without interior synchronisation. [`SharedDoc`]
+//! owns one behind an async-aware read-write lock, so local edits and remote applies are
+//! serialised, readers can run concurrently, and interested tasks can [`subscribe`] to changes
+//! instead of polling.
+//!
+//! Edits report through their [`EditOutcome`] whether they changed the document, so that calls
+//! which turn out to be no-ops, such as re-applying operations that were already integrated,
+//! neither advance the revision nor wake subscribers.
+//!
+//! [`subscribe`]: SharedDoc::subscribe
+use async_std::{channel, sync::RwLock};
+use futures_util::{Stream, StreamExt};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+};
+
+/// Where a change to a [`SharedDoc`] came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EditOrigin {
+    /// Made through [`SharedDoc::edit`].
+    Local,
+    /// Made through [`SharedDoc::apply_remote`].
+    Remote,
+}
+
+/// The result of an edit to a [`SharedDoc`], which knows whether the document was changed.
+pub trait EditOutcome {
+    /// Whether the edit changed the document.
+    fn changed(&self) -> bool;
+}
+impl EditOutcome for bool {
+    fn changed(&self) -> bool {
+        *self
+    }
+}
+/// A failed edit is expected to leave the document as it was.
+impl<T, E> EditOutcome for Result<T, E>
+where
+    T: EditOutcome,
+{
+    fn changed(&self) -> bool {
+        self.as_ref().is_ok_and(EditOutcome::changed)
+    }
+}
+
+/// Notification about one change to a [`SharedDoc`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DocChange {
+    /// The revision the document is at after the change.
+    ///
+    /// Revisions start at 0 and increase by one with every edit that changed the document.
+    pub revision: u64,
+    pub origin: EditOrigin,
+}
+
+/// A document shared between tasks, see the [module docs](self).
+///
+/// Cloning the handle shares the same document.
+#[derive(Debug)]
+pub struct SharedDoc<T> {
+    inner: Arc<SharedDocInner<T>>,
+}
+impl<T> SharedDoc<T> {
+    #[must_use]
+    pub fn new(doc: T) -> Self {
+        Self {
+            inner: Arc::new(SharedDocInner {
+                state: RwLock::new(DocState { doc, revision: 0 }),
+                subscribers: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Run `f` with shared access to the document.
+    pub async fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let state = self.inner.state.read().await;
+        f(&state.doc)
+    }
+
+    /// The current revision of the document.
+    pub async fn revision(&self) -> u64 {
+        self.inner.state.read().await.revision
+    }
+
+    /// Run a local edit `f` with exclusive access to the document.
+    ///
+    /// If the [`EditOutcome`] reports a change, it is announced to subscribers as
+    /// [`EditOrigin::Local`].
+    pub async fn edit<R>(&self, f: impl FnOnce(&mut T) -> R) -> R
+    where
+        R: EditOutcome,
+    {
+        self.change(EditOrigin::Local, f).await
+    }
+
+    /// Apply changes received from another replica with exclusive access to the document.
+    ///
+    /// If the [`EditOutcome`] reports a change, it is announced to subscribers as
+    /// [`EditOrigin::Remote`].
+    pub async fn apply_remote<R>(&self, f: impl FnOnce(&mut T) -> R) -> R
+    where
+        R: EditOutcome,
+    {
+        self.change(EditOrigin::Remote, f).await
+    }
+
+    /// Receive a [`DocChange`] for every change made after this call.
+    ///
+    /// Notifications are delivered in revision order. Subscriptions are unbounded, so a slow
+    /// subscriber never holds up edits.
+    #[must_use]
+    pub fn subscribe(&self) -> DocSubscription {
+        let (sender, receiver) = channel::unbounded();
+        self.inner
+            .subscribers
+            .lock()
+            // The list is always left consistent, so a poisoned lock is still usable.
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+        DocSubscription { receiver }
+    }
+
+    async fn change<R>(&self, origin: EditOrigin, f: impl FnOnce(&mut T) -> R) -> R
+    where
+        R: EditOutcome,
+    {
+        let mut state = self.inner.state.write().await;
+        let result = f(&mut state.doc);
+        if !result.changed() {
+            return result;
+        }
+        state.revision += 1;
+        let change = DocChange {
+            revision: state.revision,
+            origin,
+        };
+        // Notify while still holding the write lock, so notifications can't be reordered.
+        self.inner
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|subscriber| subscriber.try_send(change).is_ok());
+        result
+    }
+}
+impl<T> Clone for SharedDoc<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// Stream of changes to a [`SharedDoc`], created by [`SharedDoc::subscribe`].
+///
+/// The stream ends once all handles to the document have been dropped.
+#[derive(Debug)]
+pub struct DocSubscription {
+    receiver: channel::Receiver<DocChange>,
+}
+impl DocSubscription {
+    /// Wait for the next change, or `None` once the document is gone.
+    pub async fn recv(&self) -> Option<DocChange> {
+        self.receiver.recv().await.ok()
+    }
+}
+impl Stream for DocSubscription {
+    type Item = DocChange;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+#[derive(Debug)]
+struct SharedDocInner<T> {
+    state: RwLock<DocState<T>>,
+    subscribers: Mutex<Vec<channel::Sender<DocChange>>>,
+}
+
+#[derive(Debug)]
+struct DocState<T> {
+    doc: T,
+    revision: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::task::block_on;
+
+    #[test]
+    fn edits_are_serialised_and_announced_in_order() {
+        let doc = SharedDoc::new(String::new());
+        let subscription = doc.subscribe();
+
+        block_on(async {
+            let writers: Vec<_> = (0..4)
+                .map(|writer| {
+                    let doc = doc.clone();
+                    async_std::task::spawn(async move {
+                        for _ in 0..25 {
+                            doc.edit(|text| {
+                                text.push(char::from(b'a' + writer));
+                                true
+                            })
+                            .await;
+                        }
+                    })
+                })
+                .collect();
+            for writer in writers {
+                writer.await;
+            }
+            doc.apply_remote(|text| {
+                text.push('!');
+                true
+            })
+            .await;
+
+            assert_eq!(doc.read(String::len).await, 101);
+            assert_eq!(doc.revision().await, 101);
+            for revision in 1..=100 {
+                assert_eq!(
+                    subscription.recv().await,
+                    Some(DocChange {
+                        revision,
+                        origin: EditOrigin::Local,
+                    })
+                );
+            }
+            assert_eq!(
+                subscription.recv().await,
+                Some(DocChange {
+                    revision: 101,
+                    origin: EditOrigin::Remote,
+                })
+            );
+        });
+    }
+
+    #[test]
+    fn subscription_ends_when_document_is_dropped() {
+        let doc = SharedDoc::new(0u32);
+        let subscription = doc.subscribe();
+        let dropped_subscription = doc.subscribe();
+        drop(dropped_subscription);
+
+        block_on(async {
+            doc.edit(|value| {
+                *value += 1;
+                true
+            })
+            .await;
+            assert_eq!(doc.inner.subscribers.lock().unwrap().len(), 1);
+            drop(doc);
+            assert_eq!(
+                subscription.recv().await.map(|change| change.revision),
+                Some(1)
+            );
+            assert_eq!(subscription.recv().await, None);
+        });
+    }
+
+    #[test]
+    fn edits_without_changes_keep_the_revision() {
+        let doc = SharedDoc::new(vec![1u32]);
+        let subscription = doc.subscribe();
+
+        block_on(async {
+            let changed = doc
+                .apply_remote(|values| {
+                    let already_applied = values.contains(&1);
+                    if !already_applied {
+                        values.push(1);
+                    }
+                    !already_applied
+                })
+                .await;
+            assert!(!changed);
+            let failed: Result<bool, &str> = doc.edit(|_| Err("rejected")).await;
+            assert!(failed.is_err());
+            assert_eq!(doc.revision().await, 0);
+
+            doc.edit(|values| {
+                values.push(2);
+                true
+            })
+            .await;
+            assert_eq!(doc.revision().await, 1);
+            assert_eq!(
+                subscription.recv().await,
+                Some(DocChange {
+                    revision: 1,
+                    origin: EditOrigin::Local,
+                })
+            );
+        });
+    }
+}
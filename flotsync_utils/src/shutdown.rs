@@ -0,0 +1,362 @@
+//! Ordered, cooperative shutdown coordination.
+//!
+//! A [`ShutdownController`] walks all registered participants through the same
+//! sequence of [`ShutdownPhase`]s: first stop accepting new work, then drain queued
+//! work, then flush buffered state to storage, and finally close resources. Each
+//! phase only starts once every participant has completed the previous one (or the
+//! per-phase timeout expired), so for example no storage is closed while a sync
+//! session is still flushing operations into it.
+//!
+//! Participants observe the current phase through a cloneable [`ShutdownToken`],
+//! which works like a watch channel: it can be polled synchronously from Kompact
+//! handlers or awaited from async code.
+use crate::FutureTimeoutExt;
+use futures_util::future::poll_fn;
+use snafu::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+/// Create a controller and the first token observing it.
+#[must_use]
+pub fn shutdown_channel() -> (ShutdownController, ShutdownToken) {
+    let shared = Arc::new(Shared::default());
+    (
+        ShutdownController {
+            shared: Arc::clone(&shared),
+        },
+        ShutdownToken { shared },
+    )
+}
+
+/// The ordered phases of a coordinated shutdown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShutdownPhase {
+    /// Normal operation.
+    #[default]
+    Running,
+    /// Stop accepting new connections, sessions, and local requests.
+    StopAccepting,
+    /// Finish or hand off already accepted work and empty in-memory queues.
+    Drain,
+    /// Write all buffered state to storage.
+    Flush,
+    /// Release sockets, storage handles, and other resources.
+    Close,
+    /// Shutdown has completed.
+    Terminated,
+}
+impl ShutdownPhase {
+    /// All phases a participant has to acknowledge, in order.
+    pub const COORDINATED: [Self; 4] = [Self::StopAccepting, Self::Drain, Self::Flush, Self::Close];
+}
+impl fmt::Display for ShutdownPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Running => "running",
+            Self::StopAccepting => "stop-accepting",
+            Self::Drain => "drain",
+            Self::Flush => "flush",
+            Self::Close => "close",
+            Self::Terminated => "terminated",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Errors produced by [`ShutdownController::shutdown`].
+#[derive(Debug, Snafu)]
+pub enum ShutdownError {
+    #[snafu(display(
+        "Shutdown phase {phase} timed out waiting for participants: {}",
+        pending.join(", ")
+    ))]
+    PhaseTimedOut {
+        phase: ShutdownPhase,
+        pending: Vec<String>,
+    },
+}
+
+/// Drives a coordinated shutdown through all [`ShutdownPhase`]s.
+#[derive(Debug)]
+pub struct ShutdownController {
+    shared: Arc<Shared>,
+}
+impl ShutdownController {
+    /// Create another token observing this controller.
+    #[must_use]
+    pub fn token(&self) -> ShutdownToken {
+        ShutdownToken {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Run all coordinated phases in order and finish in [`ShutdownPhase::Terminated`].
+    ///
+    /// Each phase waits at most `phase_timeout` for all participants to complete it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ShutdownError::PhaseTimedOut`] naming the participants that did not
+    /// complete a phase in time. The remaining phases are skipped in that case and
+    /// the controller moves directly to [`ShutdownPhase::Terminated`], so no participant
+    /// waits forever.
+    pub async fn shutdown(self, phase_timeout: Duration) -> Result<(), ShutdownError> {
+        for phase in ShutdownPhase::COORDINATED {
+            self.shared.advance_to(phase);
+            let completed = poll_fn(|cx| {
+                let mut state = self.shared.lock();
+                if state.pending_for(phase).next().is_none() {
+                    Poll::Ready(())
+                } else {
+                    state.wakers.push(cx.waker().clone());
+                    Poll::Pending
+                }
+            })
+            .timeout(phase_timeout)
+            .await;
+            if completed.is_err() {
+                let pending: Vec<String> = self
+                    .shared
+                    .lock()
+                    .pending_for(phase)
+                    .map(ToOwned::to_owned)
+                    .collect();
+                self.shared.advance_to(ShutdownPhase::Terminated);
+                return PhaseTimedOutSnafu { phase, pending }.fail();
+            }
+        }
+        self.shared.advance_to(ShutdownPhase::Terminated);
+        Ok(())
+    }
+}
+
+/// Read-only view of the current shutdown phase.
+///
+/// Cheap to clone and safe to share across threads and components.
+#[derive(Clone, Debug)]
+pub struct ShutdownToken {
+    shared: Arc<Shared>,
+}
+impl ShutdownToken {
+    /// The phase the shutdown is currently in.
+    #[must_use]
+    pub fn phase(&self) -> ShutdownPhase {
+        self.shared.lock().phase
+    }
+
+    /// Whether shutdown has started, i.e. new work should be refused.
+    #[must_use]
+    pub fn is_shutting_down(&self) -> bool {
+        self.phase() > ShutdownPhase::Running
+    }
+
+    /// Wait until shutdown has reached at least `phase`, and return the current phase.
+    pub async fn reached(&self, phase: ShutdownPhase) -> ShutdownPhase {
+        poll_fn(|cx| {
+            let mut state = self.shared.lock();
+            if state.phase >= phase {
+                Poll::Ready(state.phase)
+            } else {
+                state.wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Register a participant that the controller waits for in every phase.
+    ///
+    /// `name` is only used to report participants that hold up a phase.
+    pub fn participant<S>(&self, name: S) -> ShutdownParticipant
+    where
+        S: Into<String>,
+    {
+        let mut state = self.shared.lock();
+        let id = state.next_participant_id;
+        state.next_participant_id += 1;
+        state.participants.insert(
+            id,
+            ParticipantState {
+                name: name.into(),
+                completed: ShutdownPhase::Running,
+            },
+        );
+        ShutdownParticipant {
+            token: self.clone(),
+            id,
+        }
+    }
+}
+
+/// A registered participant in a coordinated shutdown.
+///
+/// Dropping a participant counts as completing all phases.
+#[derive(Debug)]
+pub struct ShutdownParticipant {
+    token: ShutdownToken,
+    id: u64,
+}
+impl ShutdownParticipant {
+    /// The token this participant observes the shutdown through.
+    #[must_use]
+    pub fn token(&self) -> &ShutdownToken {
+        &self.token
+    }
+
+    /// Report that this participant has finished everything `phase` requires.
+    ///
+    /// Completing a phase implies completing all earlier phases.
+    pub fn complete(&self, phase: ShutdownPhase) {
+        let wakers = {
+            let mut state = self.token.shared.lock();
+            if let Some(participant) = state.participants.get_mut(&self.id) {
+                participant.completed = participant.completed.max(phase);
+            }
+            std::mem::take(&mut state.wakers)
+        };
+        wake_all(wakers);
+    }
+}
+impl Drop for ShutdownParticipant {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.token.shared.lock();
+            state.participants.remove(&self.id);
+            std::mem::take(&mut state.wakers)
+        };
+        wake_all(wakers);
+    }
+}
+
+/// State shared between the controller, its tokens, and participants.
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+}
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state is always left consistent, so a poisoned lock is still usable.
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn advance_to(&self, phase: ShutdownPhase) {
+        let wakers = {
+            let mut state = self.lock();
+            state.phase = phase;
+            std::mem::take(&mut state.wakers)
+        };
+        wake_all(wakers);
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    phase: ShutdownPhase,
+    next_participant_id: u64,
+    participants: BTreeMap<u64, ParticipantState>,
+    /// Tasks waiting for any change of `phase` or participant progress.
+    wakers: Vec<Waker>,
+}
+impl State {
+    /// Names of participants that have not completed `phase` yet.
+    fn pending_for(&self, phase: ShutdownPhase) -> impl Iterator<Item = &str> {
+        self.participants
+            .values()
+            .filter(move |participant| participant.completed < phase)
+            .map(|participant| participant.name.as_str())
+    }
+}
+
+#[derive(Debug)]
+struct ParticipantState {
+    name: String,
+    /// The latest phase this participant has reported as complete.
+    completed: ShutdownPhase,
+}
+
+fn wake_all(wakers: Vec<Waker>) {
+    for waker in wakers {
+        waker.wake();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kompact::prelude::block_on;
+    use std::{sync::mpsc, thread};
+
+    const TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn phases_advance_only_after_all_participants_complete() {
+        let (controller, token) = shutdown_channel();
+        let storage = token.participant("storage");
+        let (phase_sender, phase_receiver) = mpsc::channel();
+
+        let session_token = token.clone();
+        let session = thread::spawn(move || {
+            let participant = session_token.participant("session");
+            for phase in ShutdownPhase::COORDINATED {
+                let observed = block_on(participant.token().reached(phase));
+                phase_sender.send(observed).expect("test receiver alive");
+                participant.complete(phase);
+            }
+        });
+        // Make sure the session registered before shutdown starts.
+        while token.shared.lock().participants.len() < 2 {
+            thread::yield_now();
+        }
+        assert!(!token.is_shutting_down());
+
+        let storage_thread = thread::spawn(move || {
+            for phase in ShutdownPhase::COORDINATED {
+                block_on(storage.token().reached(phase));
+                storage.complete(phase);
+            }
+        });
+        block_on(controller.shutdown(TEST_TIMEOUT)).expect("shutdown completes");
+
+        let observed: Vec<_> = phase_receiver.iter().collect();
+        assert_eq!(observed, ShutdownPhase::COORDINATED.to_vec());
+        assert_eq!(token.phase(), ShutdownPhase::Terminated);
+        session.join().expect("session thread");
+        storage_thread.join().expect("storage thread");
+    }
+
+    #[test]
+    fn stuck_participant_times_out_and_terminates() {
+        let (controller, token) = shutdown_channel();
+        let flusher = token.participant("flusher");
+        flusher.complete(ShutdownPhase::Drain);
+
+        let error = block_on(controller.shutdown(Duration::from_millis(20)))
+            .expect_err("flush never completes");
+        match error {
+            ShutdownError::PhaseTimedOut { phase, pending } => {
+                assert_eq!(phase, ShutdownPhase::Flush);
+                assert_eq!(pending, vec!["flusher".to_owned()]);
+            }
+        }
+        assert_eq!(token.phase(), ShutdownPhase::Terminated);
+    }
+
+    #[test]
+    fn dropped_participants_do_not_block_shutdown() {
+        let (controller, token) = shutdown_channel();
+        drop(token.participant("gone"));
+
+        block_on(controller.shutdown(TEST_TIMEOUT)).expect("shutdown completes");
+        assert_eq!(
+            block_on(token.reached(ShutdownPhase::Close)),
+            ShutdownPhase::Terminated
+        );
+    }
+}
@@ -0,0 +1,393 @@
+//! Passphrase-protected backups of everything the daemon keeps on one device.
+//!
+//! An archive holds a consistent copy of the `SQLite` store, which contains all
+//! groups with their memberships, documents, and update logs, together with the
+//! local store secret that protects the key material inside the store.
+//! Restoring an archive on a new device therefore brings back the same member
+//! with the same keys, instead of joining the groups as a new member.
+//!
+//! Archive layout: the magic bytes [`ARCHIVE_MAGIC`] and one format version
+//! byte, followed by the Argon2id salt, the nonce, and the ciphertext of the
+//! contents. The header is authenticated together with the contents, and
+//! everything after it is encrypted under a key derived from the passphrase.
+
+use crate::{
+    config::DaemonConfig,
+    daemon::daemon_application_id,
+    errors::{BackupError, backup_error},
+};
+use flotsync_core::MemberIdentity;
+use flotsync_replication::SqliteReplicationStore;
+use flotsync_security::{
+    LoadedLocalStoreSecret,
+    LocalStoreSecretError,
+    PASSPHRASE_NONCE_LENGTH,
+    PASSPHRASE_SALT_LENGTH,
+    PassphraseCiphertext,
+    STORE_SECRET_KEY_LENGTH,
+    StoreSecretKey,
+    StoreSecretKeyId,
+    install_local_store_secret,
+    load_local_store_secret,
+    open_with_passphrase,
+    seal_with_passphrase,
+};
+use kompact::prelude::block_on;
+use snafu::prelude::*;
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use zeroize::Zeroizing;
+
+/// Magic bytes at the start of every backup archive.
+pub const ARCHIVE_MAGIC: &[u8; 8] = b"FLOTBKUP";
+/// Archive format version written by this daemon.
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// Write the store and store secret of this device into a new archive at `archive_path`.
+///
+/// The store may be in use by a running daemon while the archive is written.
+///
+/// # Errors
+///
+/// See `BackupError` for failure conditions.
+pub fn export_backup(
+    config: &DaemonConfig,
+    archive_path: &Path,
+    passphrase_file: &Path,
+) -> Result<(), BackupError> {
+    let passphrase = read_passphrase(passphrase_file)?;
+    let store_secret =
+        load_local_store_secret(&daemon_application_id(), &config.store_secret_profile)
+            .context(backup_error::LocalStoreSecretSnafu)?;
+    let store = block_on(SqliteReplicationStore::file(
+        config.local_member.clone(),
+        &config.store_path,
+    ))
+    .context(backup_error::StoreSnafu)?;
+
+    let snapshot_path = sibling_path(archive_path, ".store.tmp");
+    remove_if_exists(&snapshot_path)?;
+    let export_result = block_on(store.export_snapshot(&snapshot_path));
+    drop(store);
+    let store_snapshot = export_result
+        .context(backup_error::StoreSnafu)
+        .and_then(|()| {
+            fs::read(&snapshot_path).context(backup_error::ReadFileSnafu {
+                path: snapshot_path.clone(),
+            })
+        });
+    remove_if_exists(&snapshot_path)?;
+
+    let contents = BackupContents {
+        local_member: config.local_member.clone(),
+        store_secret,
+        store_snapshot: store_snapshot?,
+    };
+    let archive = encode_archive(&contents, &passphrase)?;
+    write_atomically(archive_path, &archive)
+}
+
+/// Recreate the store and store secret of this device from the archive at `archive_path`.
+///
+/// The archive must belong to the configured local member, and no store may exist at the
+/// configured store path yet. An already installed store secret is kept if it is the archived
+/// one, and is never replaced by a different one.
+///
+/// # Errors
+///
+/// See `BackupError` for failure conditions.
+pub fn restore_backup(
+    config: &DaemonConfig,
+    archive_path: &Path,
+    passphrase_file: &Path,
+) -> Result<(), BackupError> {
+    let passphrase = read_passphrase(passphrase_file)?;
+    let archive = fs::read(archive_path).context(backup_error::ReadFileSnafu {
+        path: archive_path.to_path_buf(),
+    })?;
+    let contents = decode_archive(&archive, &passphrase)?;
+    ensure!(
+        contents.local_member == config.local_member,
+        backup_error::MemberMismatchSnafu {
+            archived: contents.local_member.clone(),
+            configured: config.local_member.clone(),
+        }
+    );
+    ensure!(
+        !config.store_path.exists(),
+        backup_error::StoreExistsSnafu {
+            path: config.store_path.clone(),
+        }
+    );
+
+    let application_id = daemon_application_id();
+    match load_local_store_secret(&application_id, &config.store_secret_profile) {
+        Ok(installed) => ensure!(
+            installed.key_id() == contents.store_secret.key_id()
+                && *installed.store_secret_key().to_bytes()
+                    == *contents.store_secret.store_secret_key().to_bytes(),
+            backup_error::ConflictingStoreSecretSnafu {
+                profile: config.store_secret_profile.clone(),
+            }
+        ),
+        Err(LocalStoreSecretError::Missing { .. }) => install_local_store_secret(
+            &application_id,
+            &config.store_secret_profile,
+            &contents.store_secret,
+        )
+        .context(backup_error::LocalStoreSecretSnafu)?,
+        Err(source) => return Err(source).context(backup_error::LocalStoreSecretSnafu),
+    }
+
+    if let Some(parent) = config.store_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).context(backup_error::CreateDirectorySnafu {
+            path: parent.to_path_buf(),
+        })?;
+    }
+    write_atomically(&config.store_path, &contents.store_snapshot)
+}
+
+/// Everything one archive restores.
+struct BackupContents {
+    local_member: MemberIdentity,
+    store_secret: LoadedLocalStoreSecret,
+    /// Complete `SQLite` database file of the store.
+    store_snapshot: Vec<u8>,
+}
+
+/// Encrypt `contents` into the archive format described in the module docs.
+///
+/// Plaintext layout: member identity (`u32` length + UTF-8), store-secret key id, store-secret
+/// key, and the store snapshot (`u64` length + bytes).
+fn encode_archive(contents: &BackupContents, passphrase: &[u8]) -> Result<Vec<u8>, BackupError> {
+    let local_member = contents.local_member.to_string();
+    let member_length =
+        u32::try_from(local_member.len()).expect("member identities are far shorter than 4 GiB");
+    let snapshot_length =
+        u64::try_from(contents.store_snapshot.len()).expect("usize always fits into u64");
+    let mut plaintext = Zeroizing::new(Vec::with_capacity(
+        4 + local_member.len()
+            + StoreSecretKeyId::BYTE_LENGTH
+            + STORE_SECRET_KEY_LENGTH
+            + 8
+            + contents.store_snapshot.len(),
+    ));
+    plaintext.extend_from_slice(&member_length.to_be_bytes());
+    plaintext.extend_from_slice(local_member.as_bytes());
+    plaintext.extend_from_slice(contents.store_secret.key_id().as_bytes());
+    plaintext.extend_from_slice(
+        contents
+            .store_secret
+            .store_secret_key()
+            .to_bytes()
+            .as_slice(),
+    );
+    plaintext.extend_from_slice(&snapshot_length.to_be_bytes());
+    plaintext.extend_from_slice(&contents.store_snapshot);
+
+    let header = archive_header();
+    let sealed = seal_with_passphrase(passphrase, &header, &plaintext)
+        .context(backup_error::EncryptSnafu)?;
+    let mut archive = Vec::with_capacity(
+        header.len() + PASSPHRASE_SALT_LENGTH + PASSPHRASE_NONCE_LENGTH + sealed.ciphertext.len(),
+    );
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(&sealed.salt);
+    archive.extend_from_slice(&sealed.nonce);
+    archive.extend_from_slice(&sealed.ciphertext);
+    Ok(archive)
+}
+
+/// Decrypt and parse an archive produced by [`encode_archive`].
+fn decode_archive(archive: &[u8], passphrase: &[u8]) -> Result<BackupContents, BackupError> {
+    let mut reader = ArchiveReader(archive);
+    let magic = reader.take(ARCHIVE_MAGIC.len()).ok();
+    ensure!(
+        magic == Some(ARCHIVE_MAGIC.as_slice()),
+        backup_error::NotABackupSnafu
+    );
+    let version = reader.take_array::<1>()?[0];
+    ensure!(
+        version == ARCHIVE_VERSION,
+        backup_error::UnsupportedVersionSnafu { version }
+    );
+    let sealed = PassphraseCiphertext {
+        salt: reader.take_array()?,
+        nonce: reader.take_array()?,
+        ciphertext: reader.0.to_vec(),
+    };
+    let plaintext = open_with_passphrase(passphrase, &archive_header(), &sealed)
+        .context(backup_error::DecryptSnafu)?;
+
+    let mut reader = ArchiveReader(&plaintext);
+    let member_length = u32::from_be_bytes(reader.take_array()?);
+    let local_member = std::str::from_utf8(reader.take(member_length as usize)?)
+        .ok()
+        .and_then(|member| MemberIdentity::from_str(member).ok())
+        .context(backup_error::MalformedArchiveSnafu {
+            message: "invalid member identity",
+        })?;
+    let key_id = StoreSecretKeyId::from_bytes(reader.take_array()?);
+    let store_secret_key = StoreSecretKey::from_bytes(*Zeroizing::new(reader.take_array()?));
+    let snapshot_length = u64::from_be_bytes(reader.take_array()?);
+    let snapshot_length =
+        usize::try_from(snapshot_length)
+            .ok()
+            .context(backup_error::MalformedArchiveSnafu {
+                message: "store snapshot too large",
+            })?;
+    let store_snapshot = reader.take(snapshot_length)?.to_vec();
+    ensure!(
+        reader.0.is_empty(),
+        backup_error::MalformedArchiveSnafu {
+            message: "trailing bytes",
+        }
+    );
+    Ok(BackupContents {
+        local_member,
+        store_secret: LoadedLocalStoreSecret::new(key_id, store_secret_key),
+        store_snapshot,
+    })
+}
+
+fn archive_header() -> [u8; 9] {
+    let mut header = [0u8; 9];
+    header[..ARCHIVE_MAGIC.len()].copy_from_slice(ARCHIVE_MAGIC);
+    header[ARCHIVE_MAGIC.len()] = ARCHIVE_VERSION;
+    header
+}
+
+/// Cursor over archive bytes that reports truncation as a malformed archive.
+struct ArchiveReader<'a>(&'a [u8]);
+
+impl<'a> ArchiveReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], BackupError> {
+        ensure!(
+            self.0.len() >= length,
+            backup_error::MalformedArchiveSnafu {
+                message: "truncated archive",
+            }
+        );
+        let (taken, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], BackupError> {
+        Ok(self
+            .take(N)?
+            .try_into()
+            .expect("take returns exactly the requested length"))
+    }
+}
+
+/// Read the passphrase from the first line of `path`, without its line ending.
+fn read_passphrase(path: &Path) -> Result<Zeroizing<Vec<u8>>, BackupError> {
+    let mut passphrase =
+        Zeroizing::new(fs::read(path).context(backup_error::ReadPassphraseSnafu {
+            path: path.to_path_buf(),
+        })?);
+    if let Some(line_end) = passphrase.iter().position(|byte| *byte == b'\n') {
+        passphrase.truncate(line_end);
+    }
+    if passphrase.last() == Some(&b'\r') {
+        passphrase.pop();
+    }
+    ensure!(
+        !passphrase.is_empty(),
+        backup_error::EmptyPassphraseSnafu {
+            path: path.to_path_buf(),
+        }
+    );
+    Ok(passphrase)
+}
+
+/// Write `bytes` to a temporary sibling of `path` and move it into place.
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), BackupError> {
+    let temporary_path = sibling_path(path, ".tmp");
+    fs::write(&temporary_path, bytes).context(backup_error::WriteFileSnafu {
+        path: temporary_path.clone(),
+    })?;
+    fs::rename(&temporary_path, path).context(backup_error::WriteFileSnafu {
+        path: path.to_path_buf(),
+    })
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), BackupError> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+            Err(error).context(backup_error::WriteFileSnafu {
+                path: path.to_path_buf(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::assert_matches;
+
+    fn sample_contents() -> BackupContents {
+        BackupContents {
+            local_member: MemberIdentity::from_array(["alice", "laptop"]),
+            store_secret: LoadedLocalStoreSecret::new(
+                StoreSecretKeyId::from_bytes([7; StoreSecretKeyId::BYTE_LENGTH]),
+                StoreSecretKey::from_bytes([9; STORE_SECRET_KEY_LENGTH]),
+            ),
+            store_snapshot: b"SQLite format 3\0 and more".to_vec(),
+        }
+    }
+
+    #[test]
+    fn archive_round_trips_with_matching_passphrase() {
+        let contents = sample_contents();
+        let archive = encode_archive(&contents, b"correct horse").expect("archive should encode");
+        assert!(archive.starts_with(ARCHIVE_MAGIC));
+
+        let decoded = decode_archive(&archive, b"correct horse").expect("archive should decode");
+        assert_eq!(decoded.local_member, contents.local_member);
+        assert_eq!(
+            decoded.store_secret.key_id(),
+            contents.store_secret.key_id()
+        );
+        assert_eq!(
+            *decoded.store_secret.store_secret_key().to_bytes(),
+            *contents.store_secret.store_secret_key().to_bytes()
+        );
+        assert_eq!(decoded.store_snapshot, contents.store_snapshot);
+    }
+
+    #[test]
+    fn archive_rejects_wrong_passphrase_and_foreign_headers() {
+        let mut archive =
+            encode_archive(&sample_contents(), b"correct horse").expect("archive should encode");
+
+        assert_matches!(
+            decode_archive(&archive, b"battery staple").err(),
+            Some(BackupError::Decrypt { .. })
+        );
+        assert_matches!(
+            decode_archive(b"not a backup", b"correct horse").err(),
+            Some(BackupError::NotABackup)
+        );
+        archive[ARCHIVE_MAGIC.len()] = ARCHIVE_VERSION + 1;
+        assert_matches!(
+            decode_archive(&archive, b"correct horse").err(),
+            Some(BackupError::UnsupportedVersion { version }) if version == ARCHIVE_VERSION + 1
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Daemon settings read from the merged Flotsync configuration.
+
+use crate::errors::{DaemonError, daemon_error};
+use flotsync_core::{MemberIdentity, member::IdentifierDisplayMode};
+use flotsync_replication::{LocalStoreSecretProfile, RuntimeLimits};
+use flotsync_utils::config::{FlotsyncConfig, FlotsyncConfigLoader, FromFlotsyncConfig};
+use snafu::prelude::*;
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+/// Config entries read by the daemon itself.
+pub mod config_keys {
+    use kompact::{
+        config::{BooleanValue, DurationValue, StringValue},
+        kompact_config,
+    };
+
+    kompact_config! {
+        LOCAL_MEMBER,
+        key = "flotsync.daemon.local-member",
+        type = StringValue,
+        default = String::new(),
+        doc = "Member identity hosted by this daemon, e.g. `alice.laptop`.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        STORE_PATH,
+        key = "flotsync.daemon.store-path",
+        type = StringValue,
+        default = String::from("flotsync.sqlite"),
+        validate = |value| !value.is_empty(),
+        doc = "Path of the SQLite replication store.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        STORE_SECRET_PROFILE,
+        key = "flotsync.daemon.store-secret-profile",
+        type = StringValue,
+        default = String::from("default"),
+        doc = "Device-local profile used to load or create the store-secret key.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        CONTROL_SOCKET,
+        key = "flotsync.daemon.control-socket",
+        type = StringValue,
+        default = String::from("flotsyncd.sock"),
+        validate = |value| !value.is_empty(),
+        doc = "Unix socket path on which the JSON-RPC control API listens.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        SHUTDOWN_PHASE_TIMEOUT,
+        key = "flotsync.daemon.shutdown-phase-timeout",
+        type = DurationValue,
+        default = std::time::Duration::from_secs(10),
+        doc = "Maximum time each shutdown phase waits for daemon services.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        ABBREVIATE_IDENTIFIERS,
+        key = "flotsync.daemon.abbreviate-identifiers",
+        type = BooleanValue,
+        default = false,
+        doc = "Render member identifiers in logs by their shortest unique prefixes.",
+        version = "0.1.0"
+    }
+
+    kompact_config! {
+        ACCEPT_INVITATIONS,
+        key = "flotsync.daemon.accept-invitations",
+        type = BooleanValue,
+        default = false,
+        doc = "Accept validated group invitations automatically instead of leaving them pending for an application.",
+        version = "0.1.0"
+    }
+}
+
+/// Fully resolved daemon settings.
+#[derive(Clone, Debug)]
+pub struct DaemonConfig {
+    pub local_member: MemberIdentity,
+    pub store_path: PathBuf,
+    pub store_secret_profile: LocalStoreSecretProfile,
+    pub control_socket: PathBuf,
+    pub shutdown_phase_timeout: Duration,
+    pub identifier_display_mode: IdentifierDisplayMode,
+    pub accept_invitations: bool,
+    /// Limits passed to the replication runtime.
+    pub limits: RuntimeLimits,
+    /// The complete merged configuration, forwarded to the replication runtime.
+    pub flotsync: FlotsyncConfig,
+}
+
+impl DaemonConfig {
+    /// Load settings from an optional TOML file plus `FLOTSYNC__*` environment overrides.
+    ///
+    /// # Errors
+    ///
+    /// See `DaemonError` for failure conditions.
+    pub fn load(path: Option<&PathBuf>) -> Result<Self, DaemonError> {
+        let mut loader = FlotsyncConfigLoader::new();
+        if let Some(path) = path {
+            loader
+                .load_file(path)
+                .context(daemon_error::LoadConfigSnafu)?;
+        }
+        loader
+            .load_process_env()
+            .context(daemon_error::LoadConfigSnafu)?;
+        let flotsync = loader.build().context(daemon_error::LoadConfigSnafu)?;
+        flotsync.settings()
+    }
+}
+
+impl FromFlotsyncConfig for DaemonConfig {
+    type Error = DaemonError;
+
+    fn from_flotsync_config(flotsync: &FlotsyncConfig) -> Result<Self, DaemonError> {
+        let local_member = flotsync
+            .read(&config_keys::LOCAL_MEMBER)
+            .context(daemon_error::InvalidConfigSnafu)?;
+        ensure!(
+            !local_member.is_empty(),
+            daemon_error::MissingSettingSnafu {
+                key: config_keys::LOCAL_MEMBER.key,
+            }
+        );
+        let local_member = MemberIdentity::from_str(&local_member).map_err(|source| {
+            DaemonError::InvalidSetting {
+                key: config_keys::LOCAL_MEMBER.key,
+                message: source.to_string(),
+            }
+        })?;
+        let store_path = flotsync
+            .read(&config_keys::STORE_PATH)
+            .context(daemon_error::InvalidConfigSnafu)?;
+        let store_secret_profile = flotsync
+            .read(&config_keys::STORE_SECRET_PROFILE)
+            .context(daemon_error::InvalidConfigSnafu)?;
+        let store_secret_profile =
+            LocalStoreSecretProfile::new(store_secret_profile).map_err(|source| {
+                DaemonError::InvalidSetting {
+                    key: config_keys::STORE_SECRET_PROFILE.key,
+                    message: source.to_string(),
+                }
+            })?;
+        let control_socket = flotsync
+            .read(&config_keys::CONTROL_SOCKET)
+            .context(daemon_error::InvalidConfigSnafu)?;
+        let shutdown_phase_timeout = flotsync
+            .read(&config_keys::SHUTDOWN_PHASE_TIMEOUT)
+            .context(daemon_error::InvalidConfigSnafu)?;
+        let identifier_display_mode = if flotsync
+            .read(&config_keys::ABBREVIATE_IDENTIFIERS)
+            .context(daemon_error::InvalidConfigSnafu)?
+        {
+            IdentifierDisplayMode::ShortestUniquePrefix
+        } else {
+            IdentifierDisplayMode::Full
+        };
+        let accept_invitations = flotsync
+            .read(&config_keys::ACCEPT_INVITATIONS)
+            .context(daemon_error::InvalidConfigSnafu)?;
+        let limits = flotsync
+            .settings::<RuntimeLimits>()
+            .context(daemon_error::InvalidConfigSnafu)?;
+        Ok(Self {
+            local_member,
+            store_path: PathBuf::from(store_path),
+            store_secret_profile,
+            control_socket: PathBuf::from(control_socket),
+            shutdown_phase_timeout,
+            identifier_display_mode,
+            accept_invitations,
+            limits,
+            flotsync: flotsync.clone(),
+        })
+    }
+}
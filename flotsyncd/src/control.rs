@@ -0,0 +1,662 @@
+//! Local JSON-RPC 2.0 control API served over a Unix socket.
+//!
+//! Every request and response is a single line of JSON. Supported methods:
+//!
+//! - `status`: local member, uptime, and group count.
+//! - `groups.list`: all stored groups with their members (peers), datasets
+//!   (documents), lifecycle, and applied version vector (sync status).
+//! - `groups.summary`: ask one group member for its current version vector.
+//!   Params: `{"group_id": "<uuid>", "target": "<member>"}`.
+//! - `groups.health`: per-member liveness and acknowledged versions of one group.
+//!   Params: `{"group_id": "<uuid>"}`.
+//! - `groups.create`: create a group and invite its other members.
+//!   Params: `{"members": ["<member>", ...]}`. The new group has no datasets.
+//! - `keys.bundle`: the pasteable public key bundle of the local member.
+//! - `keys.trust`: trust a pasteable public key bundle for one member.
+//!   Params: `{"member": "<member>", "bundle": "<bundle>"}`.
+//! - `quarantine.list`: rejected inbound group broadcasts with the reason they
+//!   were rejected, oldest first.
+//! - `quarantine.retry`: hand one quarantined broadcast to the runtime again.
+//!   Params: `{"id": <number>}`.
+//! - `shutdown`: start a graceful daemon shutdown.
+
+use flotsync_core::{GroupId, MemberIdentity, MemberIndex};
+use flotsync_replication::{
+    CreateGroupRequest,
+    GroupSchema,
+    GroupSyncHealth,
+    PeerLiveness,
+    QuarantineId,
+    QuarantinedDelivery,
+    ReplicationApi,
+    ReplicationGroupLifecycle,
+    ReplicationGroupRecord,
+    ReplicationStore,
+    SummaryRequest,
+    security::{PublicKeyBundleFeedback, RecordPublicKeyBundleFeedbackRequest},
+};
+use flotsync_security::PublicKeyBundle;
+use flotsync_utils::shutdown::{ShutdownParticipant, ShutdownPhase};
+use kompact::prelude::block_on;
+use serde_json::{Value, json};
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, mpsc},
+    thread,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+/// JSON-RPC error code for malformed JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC error code for a request object with an invalid shape.
+pub const INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC error code for an unknown method.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for missing or malformed method parameters.
+pub const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC error code for failures while executing a valid request.
+pub const SERVER_ERROR: i64 = -32000;
+
+/// How long the accept loop sleeps between checks for new connections or shutdown.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// State shared by all control connections.
+pub(crate) struct ControlHandler {
+    pub(crate) local_member: MemberIdentity,
+    pub(crate) store: Arc<dyn ReplicationStore>,
+    pub(crate) replication: Arc<dyn ReplicationApi>,
+    pub(crate) started_at: Instant,
+    /// Signals the daemon main loop that a client asked for shutdown.
+    pub(crate) stop_requests: mpsc::Sender<()>,
+}
+
+impl ControlHandler {
+    /// Handle one request line and produce the response line.
+    pub(crate) fn handle_line(&self, line: &str) -> String {
+        let response = match parse_request(line) {
+            Ok(request) => {
+                let result = match ControlMethod::parse(&request.method, &request.params) {
+                    Ok(method) => block_on(self.execute(method)),
+                    Err(error) => Err(error),
+                };
+                response_json(request.id, result)
+            }
+            Err(error) => response_json(Value::Null, Err(error)),
+        };
+        response.to_string()
+    }
+
+    async fn execute(&self, method: ControlMethod) -> Result<Value, RpcError> {
+        match method {
+            ControlMethod::Status => {
+                let groups = self.load_groups().await?;
+                Ok(json!({
+                    "local_member": self.local_member.to_string(),
+                    "uptime_secs": self.started_at.elapsed().as_secs(),
+                    "group_count": groups.len(),
+                }))
+            }
+            ControlMethod::ListGroups => {
+                let groups = self.load_groups().await?;
+                Ok(Value::Array(groups.iter().map(group_json).collect()))
+            }
+            ControlMethod::GroupSummary { group_id, target } => {
+                let summary = self
+                    .replication
+                    .request_summary(SummaryRequest { group_id, target })
+                    .await
+                    .map_err(|error| RpcError::server(error.to_string()))?;
+                Ok(json!({
+                    "group_id": summary.group_id.to_string(),
+                    "responder": summary.responder.to_string(),
+                    "versions": summary.has_versions.iter().collect::<Vec<_>>(),
+                }))
+            }
+            ControlMethod::GroupHealth { group_id } => {
+                let health = self
+                    .replication
+                    .sync_health(group_id)
+                    .await
+                    .map_err(|error| RpcError::server(error.to_string()))?;
+                Ok(health_json(&health))
+            }
+            ControlMethod::CreateGroup { members } => {
+                let group_id = self
+                    .replication
+                    .create_group(CreateGroupRequest {
+                        members,
+                        observers: Vec::new(),
+                        group_schema: GroupSchema::default(),
+                    })
+                    .await
+                    .map_err(|error| RpcError::server(error.to_string()))?;
+                Ok(json!({ "group_id": group_id.to_string() }))
+            }
+            ControlMethod::KeyBundle => {
+                let bundle = self
+                    .replication
+                    .local_public_key_bundle()
+                    .await
+                    .map_err(|error| RpcError::server(error.to_string()))?;
+                Ok(json!({
+                    "bundle": bundle.to_pasteable_string(),
+                    "fingerprint": bundle.fingerprint().to_string(),
+                }))
+            }
+            ControlMethod::TrustKeyBundle { member, bundle } => {
+                self.replication
+                    .record_public_key_bundle_feedback(RecordPublicKeyBundleFeedbackRequest {
+                        bundle: *bundle,
+                        feedback: PublicKeyBundleFeedback::TrustMember { member_id: member },
+                    })
+                    .await
+                    .map_err(|error| RpcError::server(error.to_string()))?;
+                Ok(json!({ "trusted": true }))
+            }
+            ControlMethod::ListQuarantine => {
+                let deliveries = self
+                    .replication
+                    .quarantined_deliveries()
+                    .await
+                    .map_err(|error| RpcError::server(error.to_string()))?;
+                Ok(Value::Array(
+                    deliveries.iter().map(quarantined_delivery_json).collect(),
+                ))
+            }
+            ControlMethod::RetryQuarantined { id } => {
+                self.replication
+                    .retry_quarantined_delivery(id)
+                    .await
+                    .map_err(|error| RpcError::server(error.to_string()))?;
+                Ok(json!({ "retried": true }))
+            }
+            ControlMethod::Shutdown => {
+                // The receiver only disappears once shutdown already started.
+                let _ = self.stop_requests.send(());
+                Ok(json!({ "shutting_down": true }))
+            }
+        }
+    }
+
+    async fn load_groups(&self) -> Result<Vec<ReplicationGroupRecord>, RpcError> {
+        let mut transaction = self
+            .store
+            .begin_read_transaction()
+            .await
+            .map_err(|error| RpcError::server(error.to_string()))?;
+        let groups = transaction
+            .load_replication_groups()
+            .await
+            .map_err(|error| RpcError::server(error.to_string()))?;
+        transaction
+            .release()
+            .await
+            .map_err(|error| RpcError::server(error.to_string()))?;
+        Ok(groups)
+    }
+}
+
+/// Bound control socket that has not started serving yet.
+pub(crate) struct ControlServer {
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+impl ControlServer {
+    /// Bind the control socket at `path`, replacing a stale socket file.
+    ///
+    /// A socket file is only stale if nothing accepts connections on it anymore, e.g. after
+    /// the daemon was killed. If another daemon is still serving it, this fails with
+    /// [`ErrorKind::AddrInUse`] instead of taking the socket over.
+    pub(crate) fn bind(path: &Path) -> io::Result<Self> {
+        match UnixStream::connect(path) {
+            Ok(_) => {
+                return Err(io::Error::new(
+                    ErrorKind::AddrInUse,
+                    "another daemon is serving this control socket",
+                ));
+            }
+            Err(error) if error.kind() == ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(path)?;
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+        })
+    }
+
+    /// Serve connections on a background thread until shutdown reaches
+    /// [`ShutdownPhase::StopAccepting`].
+    ///
+    /// Connections that are already open keep being served until the client closes them
+    /// or the daemon exits.
+    pub(crate) fn spawn(
+        self,
+        handler: Arc<ControlHandler>,
+        participant: ShutdownParticipant,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            while !participant.token().is_shutting_down() {
+                match self.listener.accept() {
+                    Ok((stream, _)) => {
+                        let handler = Arc::clone(&handler);
+                        thread::spawn(move || {
+                            if let Err(error) = serve_connection(stream, &handler) {
+                                log::debug!("Control connection closed with error: {error}");
+                            }
+                        });
+                    }
+                    Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                    Err(error) => {
+                        log::warn!("Could not accept control connection: {error}");
+                        thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                }
+            }
+            drop(self.listener);
+            if let Err(error) = std::fs::remove_file(&self.path) {
+                log::warn!(
+                    "Could not remove control socket {}: {error}",
+                    self.path.display()
+                );
+            }
+            participant.complete(ShutdownPhase::Close);
+        })
+    }
+}
+
+/// One parsed control method with its validated parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ControlMethod {
+    Status,
+    ListGroups,
+    GroupSummary {
+        group_id: GroupId,
+        target: MemberIdentity,
+    },
+    GroupHealth {
+        group_id: GroupId,
+    },
+    CreateGroup {
+        members: Vec<MemberIdentity>,
+    },
+    KeyBundle,
+    TrustKeyBundle {
+        member: MemberIdentity,
+        bundle: Box<PublicKeyBundle>,
+    },
+    ListQuarantine,
+    RetryQuarantined {
+        id: QuarantineId,
+    },
+    Shutdown,
+}
+
+impl ControlMethod {
+    fn parse(method: &str, params: &Value) -> Result<Self, RpcError> {
+        match method {
+            "status" => Ok(Self::Status),
+            "groups.list" => Ok(Self::ListGroups),
+            "groups.summary" => Ok(Self::GroupSummary {
+                group_id: group_id_param(params)?,
+                target: member_param(params, "target")?,
+            }),
+            "groups.health" => Ok(Self::GroupHealth {
+                group_id: group_id_param(params)?,
+            }),
+            "groups.create" => {
+                let members = params
+                    .get("members")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| {
+                        RpcError::invalid_params("Missing array parameter members".to_owned())
+                    })?;
+                let members = members
+                    .iter()
+                    .map(|member| {
+                        let member = member.as_str().ok_or_else(|| {
+                            RpcError::invalid_params("members must be strings".to_owned())
+                        })?;
+                        MemberIdentity::from_str(member)
+                            .map_err(|error| RpcError::invalid_params(format!("members: {error}")))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Self::CreateGroup { members })
+            }
+            "keys.bundle" => Ok(Self::KeyBundle),
+            "keys.trust" => {
+                let member = member_param(params, "member")?;
+                let bundle = string_param(params, "bundle")?;
+                let bundle = PublicKeyBundle::from_pasteable_string(bundle)
+                    .map_err(|error| RpcError::invalid_params(format!("bundle: {error}")))?;
+                Ok(Self::TrustKeyBundle {
+                    member,
+                    bundle: Box::new(bundle),
+                })
+            }
+            "quarantine.list" => Ok(Self::ListQuarantine),
+            "quarantine.retry" => {
+                let id = params.get("id").and_then(Value::as_u64).ok_or_else(|| {
+                    RpcError::invalid_params("Missing integer parameter id".to_owned())
+                })?;
+                Ok(Self::RetryQuarantined {
+                    id: QuarantineId(id),
+                })
+            }
+            "shutdown" => Ok(Self::Shutdown),
+            other => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Unknown method {other}"),
+            }),
+        }
+    }
+}
+
+/// A request after JSON-RPC envelope validation.
+#[derive(Debug)]
+struct ControlRequest {
+    id: Value,
+    method: String,
+    params: Value,
+}
+
+/// JSON-RPC error object.
+#[derive(Debug, PartialEq, Eq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: String) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message,
+        }
+    }
+
+    fn server(message: String) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message,
+        }
+    }
+}
+
+fn serve_connection(stream: UnixStream, handler: &ControlHandler) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = handler.handle_line(&line);
+        writer.write_all(response.as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn parse_request(line: &str) -> Result<ControlRequest, RpcError> {
+    let value: Value = serde_json::from_str(line).map_err(|error| RpcError {
+        code: PARSE_ERROR,
+        message: error.to_string(),
+    })?;
+    let invalid = |message: &str| RpcError {
+        code: INVALID_REQUEST,
+        message: message.to_owned(),
+    };
+    let Value::Object(mut object) = value else {
+        return Err(invalid("Request must be a JSON object"));
+    };
+    if object.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err(invalid("Request must set jsonrpc to \"2.0\""));
+    }
+    let Some(Value::String(method)) = object.remove("method") else {
+        return Err(invalid("Request must name a method"));
+    };
+    let id = object.remove("id").unwrap_or(Value::Null);
+    let params = object
+        .remove("params")
+        .unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+    Ok(ControlRequest { id, method, params })
+}
+
+fn string_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params(format!("Missing string parameter {name}")))
+}
+
+fn group_id_param(params: &Value) -> Result<GroupId, RpcError> {
+    let group_id = string_param(params, "group_id")?;
+    let group_id = uuid::Uuid::parse_str(group_id)
+        .map_err(|error| RpcError::invalid_params(format!("group_id: {error}")))?;
+    Ok(GroupId(group_id))
+}
+
+fn member_param(params: &Value, name: &str) -> Result<MemberIdentity, RpcError> {
+    let member = string_param(params, name)?;
+    MemberIdentity::from_str(member)
+        .map_err(|error| RpcError::invalid_params(format!("{name}: {error}")))
+}
+
+fn response_json(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    }
+}
+
+fn group_json(record: &ReplicationGroupRecord) -> Value {
+    let lifecycle = match &record.lifecycle {
+        ReplicationGroupLifecycle::Open => "open",
+        ReplicationGroupLifecycle::ReadOnly { .. } => "read-only",
+        ReplicationGroupLifecycle::Closed { .. } => "closed",
+    };
+    let members: Vec<String> = record
+        .member_keys
+        .member_ids()
+        .map(ToString::to_string)
+        .collect();
+    let datasets: Vec<String> = record
+        .group_schema
+        .datasets()
+        .into_iter()
+        .map(|dataset| dataset.dataset_id.into_string())
+        .collect();
+    json!({
+        "group_id": record.group_id.to_string(),
+        "lifecycle": lifecycle,
+        "local_member_index": record.local_member_index.map(MemberIndex::as_u32),
+        "members": members,
+        "datasets": datasets,
+        "versions": record.version_vector.iter().collect::<Vec<_>>(),
+    })
+}
+
+fn health_json(health: &GroupSyncHealth) -> Value {
+    let members: Vec<Value> = health
+        .members
+        .iter()
+        .map(|member| {
+            let liveness = member.liveness.map(|liveness| match liveness {
+                PeerLiveness::Alive => "alive",
+                PeerLiveness::Suspected => "suspected",
+                PeerLiveness::Down => "down",
+            });
+            json!({
+                "member": member.member.to_string(),
+                "liveness": liveness,
+                "acknowledged_versions": member.acknowledged_versions.iter().collect::<Vec<_>>(),
+                "up_to_date": member.up_to_date,
+            })
+        })
+        .collect();
+    json!({
+        "group_id": health.group_id.to_string(),
+        "members": members,
+    })
+}
+
+fn quarantined_delivery_json(delivery: &QuarantinedDelivery) -> Value {
+    let quarantined_at_ms = delivery
+        .quarantined_at
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_millis());
+    json!({
+        "id": delivery.id.0,
+        "group_id": delivery.group_id.to_string(),
+        "sender": delivery.sender.to_string(),
+        "message_id": delivery.message_id.0.to_string(),
+        "reason": delivery.reason,
+        "payload_bytes": delivery.payload_bytes,
+        "quarantined_at_ms": quarantined_at_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_request_validates_envelope() {
+        let request =
+            parse_request(r#"{"jsonrpc":"2.0","id":7,"method":"status"}"#).expect("valid request");
+        assert_eq!(request.id, json!(7));
+        assert_eq!(request.method, "status");
+        assert_eq!(request.params, json!({}));
+
+        assert_eq!(
+            parse_request("{").expect_err("malformed JSON").code,
+            PARSE_ERROR
+        );
+        assert_eq!(
+            parse_request(r#"{"id":1,"method":"status"}"#)
+                .expect_err("missing version")
+                .code,
+            INVALID_REQUEST
+        );
+        assert_eq!(
+            parse_request(r#"{"jsonrpc":"2.0","id":1}"#)
+                .expect_err("missing method")
+                .code,
+            INVALID_REQUEST
+        );
+    }
+
+    #[test]
+    fn method_parsing_checks_params() {
+        assert_eq!(
+            ControlMethod::parse("groups.list", &json!({})),
+            Ok(ControlMethod::ListGroups)
+        );
+        let group_id = uuid::Uuid::from_u128(42);
+        assert_eq!(
+            ControlMethod::parse(
+                "groups.summary",
+                &json!({ "group_id": group_id.to_string(), "target": "bob" }),
+            ),
+            Ok(ControlMethod::GroupSummary {
+                group_id: GroupId(group_id),
+                target: MemberIdentity::from_str("bob").expect("valid member"),
+            })
+        );
+        assert_eq!(
+            ControlMethod::parse("groups.summary", &json!({ "target": "bob" }))
+                .expect_err("missing group id")
+                .code,
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            ControlMethod::parse("groups.create", &json!({ "members": ["alice", "bob"] })),
+            Ok(ControlMethod::CreateGroup {
+                members: vec![
+                    MemberIdentity::from_str("alice").expect("valid member"),
+                    MemberIdentity::from_str("bob").expect("valid member"),
+                ],
+            })
+        );
+        assert_eq!(
+            ControlMethod::parse("groups.create", &json!({ "members": "alice" }))
+                .expect_err("members must be an array")
+                .code,
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            ControlMethod::parse("keys.trust", &json!({ "member": "bob", "bundle": "!" }))
+                .expect_err("malformed bundle")
+                .code,
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            ControlMethod::parse("quarantine.retry", &json!({ "id": 3 })),
+            Ok(ControlMethod::RetryQuarantined {
+                id: QuarantineId(3),
+            })
+        );
+        assert_eq!(
+            ControlMethod::parse("quarantine.retry", &json!({ "id": "3" }))
+                .expect_err("id must be an integer")
+                .code,
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            ControlMethod::parse("documents.delete", &json!({}))
+                .expect_err("unknown method")
+                .code,
+            METHOD_NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn responses_follow_json_rpc_shape() {
+        assert_eq!(
+            response_json(json!(3), Ok(json!({ "ok": true }))),
+            json!({ "jsonrpc": "2.0", "id": 3, "result": { "ok": true } })
+        );
+        assert_eq!(
+            response_json(Value::Null, Err(RpcError::server("boom".to_owned()))),
+            json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": SERVER_ERROR, "message": "boom" },
+            })
+        );
+    }
+
+    #[test]
+    fn bind_replaces_stale_sockets_but_not_live_ones() {
+        let path =
+            std::env::temp_dir().join(format!("flotsyncd-control-{}.sock", uuid::Uuid::new_v4()));
+
+        // A socket file left behind by a daemon that did not shut down cleanly.
+        drop(UnixListener::bind(&path).expect("bind stale socket"));
+        assert!(path.exists());
+        let server = ControlServer::bind(&path).expect("stale socket is replaced");
+
+        let error = ControlServer::bind(&path)
+            .err()
+            .expect("live socket is not replaced");
+        assert_eq!(error.kind(), ErrorKind::AddrInUse);
+
+        drop(server);
+        std::fs::remove_file(&path).expect("remove test socket");
+    }
+}
@@ -0,0 +1,189 @@
+//! Daemon lifecycle: open the store, host the replication runtime, serve the
+//! control API, and shut everything down in order.
+//!
+//! Shutdown starts once a client asks for it through the control API, or the daemon
+//! receives `SIGTERM` or `SIGINT`.
+
+use crate::{
+    config::DaemonConfig,
+    control::{ControlHandler, ControlServer},
+    errors::{DaemonError, daemon_error},
+};
+use flotsync_core::member::{Identifier, set_identifier_display_mode};
+use flotsync_replication::{
+    ListenerError,
+    PolicyDecision,
+    ReplicationConfig,
+    ReplicationEvent,
+    ReplicationEventListener,
+    ReplicationSecuritySecrets,
+    SqliteReplicationStore,
+    load_replication_runtime_with_runtime_config_toml,
+};
+use flotsync_utils::{BoxFuture, shutdown::shutdown_channel};
+use futures_util::{FutureExt, future};
+use kompact::prelude::block_on;
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::{Handle, Signals},
+};
+use snafu::prelude::*;
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, mpsc},
+    thread,
+    time::Instant,
+};
+
+/// Application id under which the daemon stores its local secrets.
+#[must_use]
+pub fn daemon_application_id() -> Identifier {
+    Identifier::from_array(["flotsync", "daemon"])
+}
+
+/// Run the daemon until a client requests shutdown through the control API, or a
+/// termination signal arrives.
+///
+/// # Errors
+///
+/// See `DaemonError` for failure conditions.
+pub fn run(config: DaemonConfig) -> Result<(), DaemonError> {
+    set_identifier_display_mode(config.identifier_display_mode);
+    let replication_security = ReplicationSecuritySecrets::load_or_create_local(
+        &daemon_application_id(),
+        &config.store_secret_profile,
+    )
+    .context(daemon_error::LocalStoreSecretSnafu)?;
+    ensure_store_parent_exists(&config.store_path)?;
+    let mut replication_config = ReplicationConfig {
+        limits: config.limits,
+        ..ReplicationConfig::default()
+    };
+    if config.accept_invitations {
+        replication_config.group_invitation_policy.creation = PolicyDecision::AutoAccept;
+    }
+    let store = block_on(SqliteReplicationStore::file(
+        config.local_member.clone(),
+        &config.store_path,
+    ))
+    .context(daemon_error::StoreSnafu)?;
+    let store = Arc::new(store);
+    let replication = block_on(load_replication_runtime_with_runtime_config_toml(
+        daemon_application_id(),
+        store.clone(),
+        Arc::new(LoggingListener),
+        replication_config,
+        replication_security,
+        config.flotsync.as_toml_str(),
+    ))
+    .context(daemon_error::LoadRuntimeSnafu)?;
+
+    let (controller, token) = shutdown_channel();
+    let (stop_sender, stop_receiver) = mpsc::channel();
+    let (signals, signal_thread) = spawn_signal_listener(stop_sender.clone())?;
+    let server = ControlServer::bind(&config.control_socket).context(
+        daemon_error::BindControlSocketSnafu {
+            path: config.control_socket.clone(),
+        },
+    )?;
+    let handler = Arc::new(ControlHandler {
+        local_member: config.local_member.clone(),
+        store: store.clone(),
+        replication: replication.clone(),
+        started_at: Instant::now(),
+        stop_requests: stop_sender,
+    });
+    // Both register their shutdown participants right away, i.e. before shutdown starts.
+    let runtime_shutdown = replication.shutdown_with(&token);
+    let store_shutdown = store.shutdown_with(&token);
+    let server_thread = server.spawn(handler, token.participant("control-api"));
+    log::info!(
+        "flotsyncd running as {} with control socket {}",
+        config.local_member,
+        config.control_socket.display()
+    );
+
+    // The signal listener keeps a sender for as long as the daemon runs, so this only
+    // returns once somebody asked for shutdown.
+    let _ = stop_receiver.recv();
+    log::info!("Shutting down flotsyncd...");
+    signals.close();
+
+    let phase_timeout = config.shutdown_phase_timeout;
+    let coordinator = thread::spawn(move || block_on(controller.shutdown(phase_timeout)));
+    let (runtime_result, ()) = block_on(future::join(runtime_shutdown, store_shutdown));
+
+    let coordinator_result = coordinator
+        .join()
+        .expect("shutdown coordinator thread panicked");
+    if server_thread.join().is_err() {
+        log::warn!("Control server thread panicked");
+    }
+    if signal_thread.join().is_err() {
+        log::warn!("Signal listener thread panicked");
+    }
+    runtime_result.context(daemon_error::ShutdownRuntimeSnafu)?;
+    coordinator_result.context(daemon_error::ShutdownSnafu)
+}
+
+/// Ask for shutdown through `stop_requests` whenever `SIGTERM` or `SIGINT` arrives.
+///
+/// The listener runs until the returned handle is closed.
+fn spawn_signal_listener(
+    stop_requests: mpsc::Sender<()>,
+) -> Result<(Handle, thread::JoinHandle<()>), DaemonError> {
+    let mut signals =
+        Signals::new([SIGTERM, SIGINT]).context(daemon_error::InstallSignalHandlersSnafu)?;
+    let handle = signals.handle();
+    let thread = thread::spawn(move || {
+        for signal in signals.forever() {
+            log::info!("Received signal {signal}, shutting down");
+            // Only fails once the daemon stopped waiting, i.e. it is shutting down anyway.
+            let _ = stop_requests.send(());
+        }
+    });
+    Ok((handle, thread))
+}
+
+/// Event listener that only logs what happens.
+///
+/// The daemon does not own any application state. Invitations and migration
+/// proposals stay pending until an application handles them, unless
+/// `flotsync.daemon.accept-invitations` lets the runtime accept invitations itself.
+struct LoggingListener;
+
+impl ReplicationEventListener for LoggingListener {
+    fn on_event(&self, event: ReplicationEvent) -> BoxFuture<'_, Result<(), ListenerError>> {
+        match event {
+            ReplicationEvent::DataChanged { read_token, .. } => {
+                log::debug!("Data changed up to {read_token:?}");
+            }
+            ReplicationEvent::GroupInvitation { invitation, .. } => {
+                log::info!(
+                    "Received invitation to group {} (left pending)",
+                    invitation.group_id
+                );
+            }
+            ReplicationEvent::MigrationProposals { proposals } => {
+                log::info!(
+                    "Received {} migration proposal(s) (left pending)",
+                    proposals.len()
+                );
+            }
+        }
+        future::ready(Ok(())).boxed()
+    }
+}
+
+fn ensure_store_parent_exists(path: &Path) -> Result<(), DaemonError> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            fs::create_dir_all(parent).context(daemon_error::CreateStoreDirectorySnafu {
+                path: parent.to_path_buf(),
+            })
+        }
+        // A bare filename has `Some("")` as parent, meaning the store lives in the current directory.
+        Some(_) | None => Ok(()),
+    }
+}
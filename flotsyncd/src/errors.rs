@@ -0,0 +1,102 @@
+//! Error types of the daemon binary.
+
+use flotsync_core::MemberIdentity;
+use flotsync_replication::{ApiError, LoadError, LoadSecurityError, StoreError};
+use flotsync_security::{LocalStoreSecretError, LocalStoreSecretProfile, SecurityError};
+use flotsync_utils::{
+    config::{ConfigLoadError, ConfigValidationError},
+    shutdown::ShutdownError,
+};
+use snafu::prelude::*;
+use std::{io, path::PathBuf};
+
+/// Failures that stop the daemon.
+#[derive(Debug, Snafu)]
+#[snafu(module(daemon_error), visibility(pub(crate)))]
+pub enum DaemonError {
+    #[snafu(display("Could not load configuration."))]
+    LoadConfig { source: ConfigLoadError },
+    #[snafu(display("Invalid configuration."))]
+    InvalidConfig { source: ConfigValidationError },
+    #[snafu(display("Missing required setting {key}."))]
+    MissingSetting { key: &'static str },
+    #[snafu(display("Invalid setting {key}: {message}"))]
+    InvalidSetting { key: &'static str, message: String },
+    #[snafu(display("Could not load the local store secret."))]
+    LocalStoreSecret { source: LoadSecurityError },
+    #[snafu(display("Could not create store directory {}.", path.display()))]
+    CreateStoreDirectory { path: PathBuf, source: io::Error },
+    #[snafu(display("Could not open the replication store."))]
+    Store { source: StoreError },
+    #[snafu(display("Could not load the replication runtime."))]
+    LoadRuntime { source: LoadError },
+    #[snafu(display("Could not bind the control socket at {}.", path.display()))]
+    BindControlSocket { path: PathBuf, source: io::Error },
+    #[snafu(display("Could not install the termination signal handlers."))]
+    InstallSignalHandlers { source: io::Error },
+    #[snafu(display("Shutdown did not complete cleanly."))]
+    Shutdown { source: ShutdownError },
+    #[snafu(display("Could not shut down the replication runtime."))]
+    ShutdownRuntime { source: ApiError },
+}
+
+/// Failures while writing or restoring a backup archive.
+#[derive(Debug, Snafu)]
+#[snafu(module(backup_error), visibility(pub(crate)))]
+pub enum BackupError {
+    #[snafu(display("Could not load the daemon configuration."))]
+    LoadConfig { source: DaemonError },
+    #[snafu(display("Could not read the passphrase file {}.", path.display()))]
+    ReadPassphrase { path: PathBuf, source: io::Error },
+    #[snafu(display("The passphrase file {} is empty.", path.display()))]
+    EmptyPassphrase { path: PathBuf },
+    #[snafu(display("Could not access the local store secret."))]
+    LocalStoreSecret { source: LocalStoreSecretError },
+    #[snafu(display(
+        "A different store secret is already installed for profile {profile}; refusing to replace it."
+    ))]
+    ConflictingStoreSecret { profile: LocalStoreSecretProfile },
+    #[snafu(display("Could not open the replication store."))]
+    Store { source: StoreError },
+    #[snafu(display("Refusing to overwrite the existing store at {}.", path.display()))]
+    StoreExists { path: PathBuf },
+    #[snafu(display("Could not create directory {}.", path.display()))]
+    CreateDirectory { path: PathBuf, source: io::Error },
+    #[snafu(display("Could not read {}.", path.display()))]
+    ReadFile { path: PathBuf, source: io::Error },
+    #[snafu(display("Could not write {}.", path.display()))]
+    WriteFile { path: PathBuf, source: io::Error },
+    #[snafu(display("Could not encrypt the backup archive."))]
+    Encrypt { source: SecurityError },
+    #[snafu(display(
+        "Could not decrypt the backup archive; wrong passphrase or corrupted archive."
+    ))]
+    Decrypt { source: SecurityError },
+    #[snafu(display("Not a flotsync backup archive."))]
+    NotABackup,
+    #[snafu(display("Unsupported backup archive version {version}."))]
+    UnsupportedVersion { version: u8 },
+    #[snafu(display("Malformed backup archive: {message}"))]
+    MalformedArchive { message: &'static str },
+    #[snafu(display(
+        "The archive belongs to member {archived}, but this device is configured as {configured}."
+    ))]
+    MemberMismatch {
+        archived: MemberIdentity,
+        configured: MemberIdentity,
+    },
+}
+
+/// Failures while verifying the daemon store.
+#[derive(Debug, Snafu)]
+#[snafu(module(verify_error), visibility(pub(crate)))]
+pub enum VerifyError {
+    #[snafu(display("Could not load the daemon configuration."))]
+    LoadConfig { source: DaemonError },
+    #[snafu(display("No store exists at {}.", path.display()))]
+    StoreMissing { path: PathBuf },
+    #[snafu(display("Could not check the replication store."))]
+    Store { source: StoreError },
+    #[snafu(display("The store has {unrepaired} unrepaired problems."))]
+    Inconsistent { unrepaired: usize },
+}
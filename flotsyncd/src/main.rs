@@ -0,0 +1,104 @@
+//! `flotsyncd`: a long-lived Flotsync replication daemon.
+//!
+//! The daemon hosts the replication runtime (including its peer-announcement
+//! discovery and transport components) on top of a `SQLite` store, and exposes a
+//! local JSON-RPC control API over a Unix socket, so GUI apps and scripts can inspect
+//! groups, documents, peers, and sync status without linking the Rust crates. See
+//! [`control`] for the protocol. mDNS discovery is not part of the daemon.
+//!
+//! The daemon stops gracefully when a client calls the `shutdown` control method.
+//! The control API, discovery, sync sessions, and the store then stop in the order
+//! of the shared shutdown phases.
+//!
+//! The `backup` and `restore` subcommands move the store and keys of one device into
+//! a passphrase-protected archive and back, e.g. onto a new device. See [`backup`].
+//! The `verify` subcommand checks the store for corruption and inconsistent
+//! replication state. See [`verify`].
+
+use clap::{Parser, Subcommand};
+use snafu::prelude::*;
+use std::{error::Error, path::PathBuf};
+
+mod backup;
+mod config;
+mod control;
+mod daemon;
+mod errors;
+mod verify;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Load Flotsync configuration from this TOML file.
+    ///
+    /// `FLOTSYNC__*` environment variables override values from the file.
+    #[arg(short, long, value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
+    /// Run a maintenance command instead of the daemon.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write all documents, update logs, memberships, and keys into one encrypted archive.
+    Backup {
+        /// Path of the archive to create.
+        archive: PathBuf,
+        /// Read the archive passphrase from the first line of this file.
+        #[arg(long, value_name = "FILE")]
+        passphrase_file: PathBuf,
+    },
+    /// Recreate the store and keys of the configured member from an archive.
+    Restore {
+        /// Path of the archive to restore.
+        archive: PathBuf,
+        /// Read the archive passphrase from the first line of this file.
+        #[arg(long, value_name = "FILE")]
+        passphrase_file: PathBuf,
+    },
+    /// Check the store for corruption and inconsistent replication state.
+    ///
+    /// Exits with a non-zero status if problems remain.
+    Verify {
+        /// Fix problems that can be resolved without losing data.
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+fn main() {
+    let args = Args::parse();
+    let config = config::DaemonConfig::load(args.config.as_ref());
+    match args.command {
+        None => exit_on_error(config.and_then(daemon::run)),
+        Some(Command::Backup {
+            archive,
+            passphrase_file,
+        }) => exit_on_error(
+            config
+                .context(errors::backup_error::LoadConfigSnafu)
+                .and_then(|config| backup::export_backup(&config, &archive, &passphrase_file)),
+        ),
+        Some(Command::Restore {
+            archive,
+            passphrase_file,
+        }) => exit_on_error(
+            config
+                .context(errors::backup_error::LoadConfigSnafu)
+                .and_then(|config| backup::restore_backup(&config, &archive, &passphrase_file)),
+        ),
+        Some(Command::Verify { repair }) => exit_on_error(
+            config
+                .context(errors::verify_error::LoadConfigSnafu)
+                .and_then(|config| verify::verify_store(&config, repair)),
+        ),
+    }
+}
+
+fn exit_on_error<E: Error + 'static>(result: Result<(), E>) {
+    if let Err(error) = result {
+        eprintln!("{}", snafu::Report::from_error(error));
+        std::process::exit(1);
+    }
+}
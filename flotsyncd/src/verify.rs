@@ -0,0 +1,57 @@
+//! Offline integrity check of the daemon store.
+//!
+//! Checks the `SQLite` database itself first, then the replication state stored
+//! in it (see [`verify_replication_store`]), and prints every problem found.
+//! With `--repair`, problems the runtime would also resolve on its own are fixed
+//! in place; everything else needs a backup restore or manual intervention.
+
+use crate::{
+    config::DaemonConfig,
+    errors::{VerifyError, verify_error},
+};
+use flotsync_replication::{SqliteReplicationStore, verify_replication_store};
+use kompact::prelude::block_on;
+use snafu::prelude::*;
+
+/// Verify the configured store and print a report to stdout.
+///
+/// # Errors
+///
+/// Returns [`VerifyError::Inconsistent`] if unrepaired problems remain, or
+/// another `VerifyError` if the store could not be checked at all.
+pub fn verify_store(config: &DaemonConfig, repair: bool) -> Result<(), VerifyError> {
+    ensure!(
+        config.store_path.exists(),
+        verify_error::StoreMissingSnafu {
+            path: config.store_path.clone(),
+        }
+    );
+    let store = block_on(SqliteReplicationStore::file(
+        config.local_member.clone(),
+        &config.store_path,
+    ))
+    .context(verify_error::StoreSnafu)?;
+
+    let database_problems =
+        block_on(store.check_database_integrity()).context(verify_error::StoreSnafu)?;
+    for problem in &database_problems {
+        println!("database: {problem}");
+    }
+    let report =
+        block_on(verify_replication_store(&store, repair)).context(verify_error::StoreSnafu)?;
+    for issue in &report.issues {
+        println!("{issue}");
+    }
+    println!(
+        "Checked {} groups, {} rows, and {} updates.",
+        report.groups_checked, report.rows_checked, report.updates_checked
+    );
+
+    let unrepaired =
+        database_problems.len() + report.issues.iter().filter(|issue| !issue.repaired).count();
+    ensure!(
+        unrepaired == 0,
+        verify_error::InconsistentSnafu { unrepaired }
+    );
+    Ok(())
+}
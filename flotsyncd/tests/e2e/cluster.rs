@@ -0,0 +1,118 @@
+//! A set of `flotsyncd` nodes that know each other's loopback endpoints.
+
+use crate::node::{DaemonNode, NodeSpec};
+use flotsync_io::test_support::{ReservedSocketKind, reserve_sockets};
+use serde_json::json;
+use std::{
+    fs,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Running daemons sharing one scratch directory that is removed on drop.
+pub struct Cluster {
+    root: PathBuf,
+    nodes: Vec<DaemonNode>,
+}
+
+impl Cluster {
+    /// Start one daemon per member, each with its own store, secret profile, and
+    /// static peer routes to all other members.
+    pub fn start(members: &[&str]) -> Self {
+        static CLUSTER_COUNTER: AtomicUsize = AtomicUsize::new(1);
+        let run = format!(
+            "{}-{}",
+            process::id(),
+            CLUSTER_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let root = std::env::temp_dir().join(format!("flotsyncd-e2e-{run}"));
+        // Reserve every socket up front so each config can name all peer endpoints.
+        let leases: Vec<_> = members
+            .iter()
+            .map(|_| {
+                reserve_sockets(&[ReservedSocketKind::UdpSocket, ReservedSocketKind::UdpSocket])
+            })
+            .collect();
+        let endpoints: Vec<_> = leases.iter().map(|lease| lease.addr(0)).collect();
+        let mut cluster = Self {
+            root,
+            nodes: Vec::with_capacity(members.len()),
+        };
+        for (index, (member, lease)) in members.iter().zip(leases).enumerate() {
+            let peers = members
+                .iter()
+                .zip(&endpoints)
+                .enumerate()
+                .filter(|(peer_index, _)| *peer_index != index)
+                .map(|(_, (peer, addr))| (*peer, *addr))
+                .collect();
+            let spec = NodeSpec {
+                member,
+                dir: cluster.root.join(member),
+                store_secret_profile: format!("e2e-{run}-{member}"),
+                peers,
+            };
+            cluster.nodes.push(DaemonNode::spawn(&spec, lease));
+        }
+        cluster
+    }
+
+    /// The node hosting `member`.
+    pub fn node(&mut self, member: &str) -> &mut DaemonNode {
+        self.nodes
+            .iter_mut()
+            .find(|node| node.member() == member)
+            .unwrap_or_else(|| panic!("no node hosts {member}"))
+    }
+
+    /// All nodes in start order.
+    pub fn nodes(&mut self) -> &mut [DaemonNode] {
+        &mut self.nodes
+    }
+
+    /// Let every node trust the public key bundle of every other node's member.
+    pub fn exchange_trust(&mut self) {
+        let bundles: Vec<_> = self
+            .nodes
+            .iter_mut()
+            .map(|node| {
+                let bundle = node.call("keys.bundle", json!({}));
+                (node.member().to_owned(), bundle["bundle"].clone())
+            })
+            .collect();
+        for node in &mut self.nodes {
+            for (member, bundle) in &bundles {
+                if member == node.member() {
+                    continue;
+                }
+                let result = node.call("keys.trust", json!({ "member": member, "bundle": bundle }));
+                assert_eq!(result["trusted"], true, "trust result: {result}");
+            }
+        }
+    }
+
+    /// Shut every node down through the control API, in start order, and check
+    /// that each one exits cleanly and removes its control socket.
+    pub fn shutdown(mut self) {
+        for node in std::mem::take(&mut self.nodes) {
+            let member = node.member().to_owned();
+            let control_socket = node.control_socket().to_path_buf();
+            let status = node.shutdown();
+            assert!(status.success(), "{member} exited with {status}");
+            assert!(
+                !control_socket.exists(),
+                "{member} left its control socket {} behind",
+                control_socket.display()
+            );
+        }
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        // Kill any remaining daemons before their storage disappears.
+        self.nodes.clear();
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
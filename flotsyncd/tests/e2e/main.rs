@@ -0,0 +1,113 @@
+//! Multi-process end-to-end tests for `flotsyncd`.
+//!
+//! Each test starts real daemon processes on loopback, one per member, with
+//! isolated store directories and store-secret profiles, and drives them only
+//! through their JSON-RPC control sockets. This covers what in-process tests
+//! cannot: config loading, socket permissions, request serialisation, and the
+//! shutdown order of separate processes.
+//!
+//! The daemons load their store secrets from the platform secret service, so
+//! these tests are ignored by default. Run them with
+//! `cargo test -p flotsyncd --test e2e -- --ignored`.
+//! See `docs/flotsyncd_e2e_testing.md`.
+
+use cluster::Cluster;
+use flotsync_utils::option_when;
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+
+mod cluster;
+mod node;
+
+/// JSON-RPC error code for an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for missing or malformed method parameters.
+const INVALID_PARAMS: i64 = -32602;
+
+#[test]
+#[ignore = "needs a platform secret service; run with --ignored"]
+fn daemons_start_isolated_and_shut_down_cleanly() {
+    let mut cluster = Cluster::start(&["alice", "bob", "carol"]);
+
+    let mut store_paths = BTreeSet::new();
+    let mut fingerprints = BTreeSet::new();
+    for node in cluster.nodes() {
+        let status = node.call("status", json!({}));
+        assert_eq!(status["local_member"], node.member(), "status: {status}");
+        assert_eq!(status["group_count"], 0, "status: {status}");
+        assert!(node.store_path().exists(), "{} has no store", node.member());
+        store_paths.insert(node.store_path().to_path_buf());
+        let bundle = node.call("keys.bundle", json!({}));
+        fingerprints.insert(bundle["fingerprint"].to_string());
+
+        let unknown = node
+            .try_call("documents.delete", &json!({}))
+            .expect_err("unknown method must fail");
+        assert_eq!(unknown["code"], METHOD_NOT_FOUND, "error: {unknown}");
+        let malformed = node
+            .try_call("groups.health", &json!({ "group_id": "not-a-uuid" }))
+            .expect_err("malformed group id must fail");
+        assert_eq!(malformed["code"], INVALID_PARAMS, "error: {malformed}");
+    }
+    assert_eq!(store_paths.len(), 3, "stores must not be shared");
+    assert_eq!(fingerprints.len(), 3, "members must not share keys");
+
+    cluster.shutdown();
+}
+
+#[test]
+#[ignore = "needs a platform secret service; run with --ignored"]
+fn created_group_converges_across_daemons() {
+    let members = ["alice", "bob", "carol"];
+    let mut cluster = Cluster::start(&members);
+    cluster.exchange_trust();
+
+    let created = cluster
+        .node("alice")
+        .call("groups.create", json!({ "members": members }));
+    let group_id = created["group_id"]
+        .as_str()
+        .expect("group id string")
+        .to_owned();
+
+    for node in cluster.nodes() {
+        let group = node.wait_for("the group to be installed", |node| {
+            find_group(&node.call("groups.list", json!({})), &group_id)
+        });
+        assert_eq!(group["members"], json!(members), "group: {group}");
+        assert_eq!(group["lifecycle"], "open", "group: {group}");
+    }
+
+    for node in cluster.nodes() {
+        node.wait_for("all members to be alive and up to date", |node| {
+            let health = node.call("groups.health", json!({ "group_id": group_id }));
+            let members = health["members"].as_array()?;
+            let converged = members.len() == 3
+                && members
+                    .iter()
+                    .all(|member| member["liveness"] == "alive" && member["up_to_date"] == true);
+            option_when!(converged, ())
+        });
+    }
+
+    let versions: BTreeSet<String> = cluster
+        .nodes()
+        .iter_mut()
+        .map(|node| {
+            let group = find_group(&node.call("groups.list", json!({})), &group_id)
+                .expect("installed group");
+            group["versions"].to_string()
+        })
+        .collect();
+    assert_eq!(versions.len(), 1, "applied versions diverge: {versions:?}");
+
+    cluster.shutdown();
+}
+
+fn find_group(groups: &Value, group_id: &str) -> Option<Value> {
+    groups
+        .as_array()?
+        .iter()
+        .find(|group| group["group_id"] == group_id)
+        .cloned()
+}
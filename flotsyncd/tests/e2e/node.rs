@@ -0,0 +1,297 @@
+//! One `flotsyncd` process under test and its JSON-RPC control client.
+
+use flotsync_io::test_support::ReservedSocketLease;
+use serde_json::{Value, json};
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::SocketAddr,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How long a node may take to start serving its control socket or to exit.
+pub const NODE_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long one control request may take before the test gives up.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long polling helpers sleep between attempts.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Reserved lease slot of the replication delivery endpoint.
+const ENDPOINT_SLOT: usize = 0;
+/// Reserved lease slot of the peer-announcement socket.
+const ANNOUNCEMENT_SLOT: usize = 1;
+
+/// Static settings a node is launched with.
+pub struct NodeSpec<'a> {
+    /// Member identity hosted by the node.
+    pub member: &'a str,
+    /// Directory that holds the node's config, store, and control socket.
+    pub dir: PathBuf,
+    /// Store-secret profile that no other node or test run uses.
+    pub store_secret_profile: String,
+    /// Delivery endpoints of the other nodes, written as static peer routes.
+    pub peers: Vec<(&'a str, SocketAddr)>,
+}
+
+/// A running `flotsyncd` child process with isolated storage.
+///
+/// The process is killed when the node is dropped without a clean shutdown.
+pub struct DaemonNode {
+    member: String,
+    control_socket: PathBuf,
+    store_path: PathBuf,
+    endpoint_addr: SocketAddr,
+    socket_lease: ReservedSocketLease,
+    child: Option<Child>,
+    stderr: Arc<Mutex<Vec<u8>>>,
+    stderr_thread: Option<JoinHandle<()>>,
+}
+
+impl DaemonNode {
+    /// Write the node config, start the daemon, and wait until it answers `status`.
+    pub fn spawn(spec: &NodeSpec<'_>, socket_lease: ReservedSocketLease) -> Self {
+        fs::create_dir_all(&spec.dir).expect("create node directory");
+        let control_socket = spec.dir.join("control.sock");
+        let store_path = spec.dir.join("store.sqlite");
+        let endpoint_addr = socket_lease.addr(ENDPOINT_SLOT);
+        let config_path = spec.dir.join("flotsyncd.toml");
+        let config = node_config(
+            spec,
+            &control_socket,
+            &store_path,
+            endpoint_addr,
+            socket_lease.addr(ANNOUNCEMENT_SLOT),
+        );
+        fs::write(&config_path, config).expect("write node config");
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_flotsyncd"))
+            .arg("--config")
+            .arg(&config_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn flotsyncd");
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let stderr_thread = {
+            let mut pipe = child.stderr.take().expect("piped daemon stderr");
+            let stderr = Arc::clone(&stderr);
+            thread::spawn(move || {
+                let mut buffer = [0_u8; 4096];
+                while let Ok(read) = pipe.read(&mut buffer) {
+                    if read == 0 {
+                        break;
+                    }
+                    stderr
+                        .lock()
+                        .expect("stderr lock")
+                        .extend_from_slice(&buffer[..read]);
+                }
+            })
+        };
+
+        let mut node = Self {
+            member: spec.member.to_owned(),
+            control_socket,
+            store_path,
+            endpoint_addr,
+            socket_lease,
+            child: Some(child),
+            stderr,
+            stderr_thread: Some(stderr_thread),
+        };
+        node.wait_until_serving();
+        node
+    }
+
+    /// Member identity hosted by this node.
+    pub fn member(&self) -> &str {
+        &self.member
+    }
+
+    /// Path of the node's SQLite store.
+    pub fn store_path(&self) -> &Path {
+        &self.store_path
+    }
+
+    /// Path of the node's control socket.
+    pub fn control_socket(&self) -> &Path {
+        &self.control_socket
+    }
+
+    /// Call one control method and return its `result`, panicking on a JSON-RPC error.
+    pub fn call(&mut self, method: &str, params: Value) -> Value {
+        match self.try_call(method, &params) {
+            Ok(result) => result,
+            Err(error) => self.fail(&format!("{method} failed: {error}")),
+        }
+    }
+
+    /// Call one control method and return either its `result` or its `error` object.
+    pub fn try_call(&self, method: &str, params: &Value) -> Result<Value, Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let stream = match UnixStream::connect(&self.control_socket) {
+            Ok(stream) => stream,
+            Err(error) => return Err(json!({ "connect": error.to_string() })),
+        };
+        stream
+            .set_read_timeout(Some(REQUEST_TIMEOUT))
+            .expect("set control read timeout");
+        let mut writer = stream.try_clone().expect("clone control stream");
+        writeln!(writer, "{request}").expect("write control request");
+        let mut line = String::new();
+        if let Err(error) = BufReader::new(stream).read_line(&mut line) {
+            return Err(json!({ "read": error.to_string() }));
+        }
+        let mut response: Value = serde_json::from_str(&line).expect("parse control response");
+        assert_eq!(response["jsonrpc"], "2.0", "response: {response}");
+        assert_eq!(response["id"], 1, "response: {response}");
+        match response.get_mut("error") {
+            Some(error) => Err(error.take()),
+            None => Ok(response["result"].take()),
+        }
+    }
+
+    /// Poll `check` until it returns a value or [`NODE_TIMEOUT`] expires.
+    pub fn wait_for<T>(&mut self, what: &str, mut check: impl FnMut(&mut Self) -> Option<T>) -> T {
+        let deadline = Instant::now() + NODE_TIMEOUT;
+        loop {
+            if let Some(value) = check(self) {
+                return value;
+            }
+            if let Some(status) = self.poll_exit() {
+                self.fail(&format!(
+                    "daemon exited with {status} while waiting for {what}"
+                ));
+            }
+            if Instant::now() >= deadline {
+                self.fail(&format!("timed out waiting for {what}"));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Ask the daemon to stop through the control API and wait for it to exit.
+    pub fn shutdown(mut self) -> ExitStatus {
+        let result = self.call("shutdown", json!({}));
+        assert_eq!(result["shutting_down"], true, "shutdown result: {result}");
+        let deadline = Instant::now() + NODE_TIMEOUT;
+        loop {
+            if let Some(status) = self.poll_exit() {
+                self.kill();
+                return status;
+            }
+            if Instant::now() >= deadline {
+                self.fail("timed out waiting for the daemon to exit after shutdown");
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Everything the daemon wrote to stderr so far.
+    pub fn stderr_string(&self) -> String {
+        let stderr = self.stderr.lock().expect("stderr lock");
+        String::from_utf8_lossy(&stderr).into_owned()
+    }
+
+    fn wait_until_serving(&mut self) {
+        self.wait_for("the control socket", |node| {
+            node.try_call("status", &json!({})).ok()
+        });
+        // The daemon bound both reserved sockets before it started serving control
+        // requests, so the reservation sockets must stop competing for datagrams.
+        self.socket_lease.activate_live_binding(ENDPOINT_SLOT);
+        self.socket_lease.activate_live_binding(ANNOUNCEMENT_SLOT);
+    }
+
+    fn poll_exit(&mut self) -> Option<ExitStatus> {
+        let child = self.child.as_mut()?;
+        child.try_wait().expect("poll daemon process")
+    }
+
+    fn kill(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if let Some(thread) = self.stderr_thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn fail(&mut self, context: &str) -> ! {
+        self.kill();
+        panic!(
+            "{} ({}): {context}\nstderr:\n{}",
+            self.member,
+            self.endpoint_addr,
+            self.stderr_string()
+        );
+    }
+}
+
+impl Drop for DaemonNode {
+    fn drop(&mut self) {
+        // The daemon must be gone before the socket lease re-binds its reservations.
+        self.kill();
+    }
+}
+
+fn node_config(
+    spec: &NodeSpec<'_>,
+    control_socket: &Path,
+    store_path: &Path,
+    endpoint_addr: SocketAddr,
+    announcement_addr: SocketAddr,
+) -> String {
+    let mut config = format!(
+        r#"[flotsync.daemon]
+local-member = "{member}"
+store-path = "{store}"
+store-secret-profile = "{profile}"
+control-socket = "{socket}"
+shutdown-phase-timeout = "5s"
+accept-invitations = true
+
+[flotsync.io]
+bind-reuse-address = true
+
+[flotsync.discovery.peer-announcement]
+bind-addr = "{announcement_addr}"
+
+[flotsync.replication.runtime]
+local-endpoint-bind-addr = "{endpoint_addr}"
+"#,
+        member = spec.member,
+        store = store_path.display(),
+        profile = spec.store_secret_profile,
+        socket = control_socket.display(),
+    );
+    for (peer, addr) in &spec.peers {
+        write!(
+            config,
+            r#"
+[[flotsync.replication.runtime.static-peer-routes]]
+name = "{peer}"
+protocol = "udp"
+ip = "{ip}"
+port = {port}
+"#,
+            ip = addr.ip(),
+            port = addr.port(),
+        )
+        .expect("format static peer route");
+    }
+    config
+}
@@ -0,0 +1,33 @@
+//! Feeds arbitrary bytes into the dataset snapshot reader for a schema that covers every field
+//! type.
+#![no_main]
+
+use buffa::Message;
+use flotsync_data_types::{Schema, test_support::schema_operations::exhaustive_schema};
+use flotsync_messages::{
+    InMemoryStateData,
+    datamodel as proto,
+    snapshots::datamodel::{ProtoDataSnapshotDecoder, ProtoDataSnapshotEncoder},
+};
+use libfuzzer_sys::fuzz_target;
+use std::sync::LazyLock;
+
+static SCHEMA: LazyLock<Schema> = LazyLock::new(exhaustive_schema);
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(snapshot) = proto::DataSnapshot::decode_from_slice(data) else {
+        return;
+    };
+    let mut decoder = ProtoDataSnapshotDecoder::new(snapshot);
+    let Ok(decoded) = InMemoryStateData::decode_data_snapshots(&*SCHEMA, &mut decoder) else {
+        return;
+    };
+    // Whatever was accepted must also be written out again.
+    let mut encoder = ProtoDataSnapshotEncoder::new(&SCHEMA);
+    decoded
+        .encode_data_snapshots(&mut encoder)
+        .expect("A decoded dataset must be encodable");
+    encoder
+        .into_snapshot()
+        .expect("A decoded dataset must be encodable");
+});
@@ -0,0 +1,65 @@
+//! Feeds arbitrary bytes into the history snapshot readers and rebuilds the replicated values.
+//!
+//! The first byte selects the snapshot format, the remaining bytes are decoded as its protobuf
+//! message.
+#![no_main]
+
+use buffa::Message;
+use flotsync_core::versions::UpdateId;
+use flotsync_data_types::text::LinearString;
+use flotsync_messages::{
+    codecs::datamodel::{
+        decode_columnar_linear_string_history_snapshot,
+        decode_linear_list_history_snapshot_with,
+    },
+    datamodel as proto,
+};
+use libfuzzer_sys::fuzz_target;
+use std::convert::Infallible;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&format, bytes)) = data.split_first() else {
+        return;
+    };
+    if format % 2 == 0 {
+        if let Ok(snapshot) = proto::HistorySnapshot::decode_from_slice(bytes) {
+            linear_string(snapshot);
+        }
+    } else if let Ok(snapshot) = proto::EncodedListHistorySnapshot::decode_from_slice(bytes) {
+        encoded_linear_list(snapshot);
+    }
+});
+
+fn linear_string(snapshot: proto::HistorySnapshot) {
+    let Ok(nodes) = decode_columnar_linear_string_history_snapshot(snapshot) else {
+        return;
+    };
+    let Ok(mut text) =
+        LinearString::<UpdateId>::from_snapshot_nodes(nodes.into_iter().map(Ok::<_, Infallible>))
+    else {
+        return;
+    };
+    if text.validate_integrity().is_err() {
+        return;
+    }
+    // A snapshot that passed validation must behave like any other replica.
+    let _ = text.to_string();
+    if let Some(range) = text.ids_in_range(..1) {
+        range
+            .delete(&mut text)
+            .expect("Visible ids of a valid snapshot must be deletable");
+        text.validate_integrity()
+            .expect("Edits must keep a valid snapshot valid");
+    }
+}
+
+fn encoded_linear_list(snapshot: proto::EncodedListHistorySnapshot) {
+    let Ok(list) = decode_linear_list_history_snapshot_with(snapshot, |bytes| {
+        String::from_utf8(bytes.to_vec())
+    }) else {
+        return;
+    };
+    if list.validate_integrity().is_ok() {
+        assert_eq!(list.iter().count(), list.len());
+    }
+}
@@ -0,0 +1,21 @@
+//! Feeds arbitrary bytes into the schema definition decoder.
+#![no_main]
+
+use buffa::Message;
+use flotsync_messages::{
+    codecs::schema::{decode_schema_definition, encode_schema_definition},
+    datamodel as proto,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(definition) = proto::SchemaDefinition::decode_from_slice(data) else {
+        return;
+    };
+    let Ok(schema) = decode_schema_definition(definition) else {
+        return;
+    };
+    let encoded = encode_schema_definition(&schema).expect("A decoded schema must be encodable");
+    let decoded = decode_schema_definition(encoded).expect("An encoded schema must be decodable");
+    assert_eq!(decoded, schema);
+});
@@ -0,0 +1,62 @@
+//! Feeds arbitrary bytes as a stream of length-delimited schema operations into a dataset.
+//!
+//! The dataset is seeded with one row (row id `1`), so operations that refer to that row reach
+//! the per-field CRDT operation application.
+#![no_main]
+
+use buffa::Message;
+use flotsync_core::versions::UpdateId;
+use flotsync_data_types::{
+    RowValues,
+    Schema,
+    schema::{Direction, Field, PrimitiveType},
+};
+use flotsync_messages::{
+    InMemoryStateData,
+    Uuid,
+    codecs::datamodel::decode_schema_operation,
+    datamodel as proto,
+};
+use libfuzzer_sys::fuzz_target;
+use std::{collections::HashMap, sync::LazyLock};
+
+static SCHEMA: LazyLock<Schema> = LazyLock::new(|| {
+    Schema::from_fields([
+        Field::linear_string("title"),
+        Field::linear_list("numbers", PrimitiveType::Int),
+        Field::monotonic_counter("counter"),
+        Field::total_order_register("priority", PrimitiveType::UInt, Direction::Ascending),
+    ])
+});
+
+static SEEDED_DATA: LazyLock<InMemoryStateData> = LazyLock::new(|| {
+    let fields = HashMap::from([
+        ("title".to_owned(), "hello".into()),
+        ("numbers".to_owned(), vec![1i64, 2, 3].into()),
+        ("counter".to_owned(), 4u64.into()),
+        ("priority".to_owned(), 7u64.into()),
+    ]);
+    let row = RowValues::try_from_fields(&SCHEMA, fields).expect("seed row must match the schema");
+    InMemoryStateData::from_initial_value_rows(
+        &*SCHEMA,
+        [(Uuid::from_u128(1), row)],
+        &UpdateId {
+            version: 0,
+            node_index: 0,
+        },
+    )
+    .expect("seed row must embed")
+});
+
+fuzz_target!(|data: &[u8]| {
+    let mut state = SEEDED_DATA.clone();
+    let mut input = data;
+    while let Ok(operation) = proto::SchemaOperation::decode_length_delimited(&mut input) {
+        let Ok(operation) = decode_schema_operation(operation, &SCHEMA) else {
+            continue;
+        };
+        if let Ok(next_state) = state.clone().apply_schema_operation(operation) {
+            state = next_state;
+        }
+    }
+});